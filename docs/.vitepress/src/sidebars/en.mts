@@ -88,6 +88,9 @@ const items = [
                     { text: "Flapping Detect", link: "/en/RobustMQ-MQTT/FlappingDetect" },
                     { text: "System Alarm", link: "/en/RobustMQ-MQTT/SystemAlarm" },
                     { text: "System Topics", link: "/en/RobustMQ-MQTT/SystemTopic" },
+                    { text: "MQTT-SN Gateway", link: "/en/RobustMQ-MQTT/MQTTSN" },
+                    { text: "CoAP Gateway", link: "/en/RobustMQ-MQTT/CoAP" },
+                    { text: "Web Subscribe", link: "/en/RobustMQ-MQTT/WebSubscribe" },
                 ],
             },
             {
@@ -131,6 +134,7 @@ const items = [
           { text: "Go SDK", link: "/en/RobustMQ-MQTT/SDK/go-sdk" },
           { text: "Python SDK", link: "/en/RobustMQ-MQTT/SDK/python-sdk" },
           { text: "JavaScript SDK", link: "/en/RobustMQ-MQTT/SDK/javascript-sdk" },
+          { text: "Rust SDK", link: "/en/RobustMQ-MQTT/SDK/rust-sdk" },
         ],
       },
       {
@@ -214,6 +218,7 @@ const items = [
           { text: "Introduction", link: "/en/RuleEngine/Introduction" },
           { text: "Operator List", link: "/en/RuleEngine/overview" },
           { text: "Processing Demo", link: "/en/RuleEngine/Demo" },
+          { text: "MQTT Message Rules", link: "/en/RuleEngine/MessageRule" },
         ],
       },
     ],
@@ -314,6 +319,14 @@ const items = [
             text: "Pprof Usage",
             link: "/en/ContributionGuide/ContributingCode/Pprof-Usage",
           },
+          {
+            text: "Fuzz Testing",
+            link: "/en/ContributionGuide/ContributingCode/Fuzz-Testing",
+          },
+          {
+            text: "Benchmarks",
+            link: "/en/ContributionGuide/ContributingCode/Benchmark",
+          },
           {
             text: "Build and Package",
             link: "/en/ContributionGuide/ContributingCode/Build-and-Package",
@@ -88,6 +88,9 @@ const items = [
                     { text: "连接抖动", link: "/zh/RobustMQ-MQTT/FlappingDetect" },
                     { text: "系统告警", link: "/zh/RobustMQ-MQTT/SystemAlarm.md" },
                     { text: "系统主题", link: "/zh/RobustMQ-MQTT/SystemTopic" },
+                    { text: "MQTT-SN 网关", link: "/zh/RobustMQ-MQTT/MQTTSN" },
+                    { text: "CoAP 网关", link: "/zh/RobustMQ-MQTT/CoAP" },
+                    { text: "Web 订阅", link: "/zh/RobustMQ-MQTT/WebSubscribe" },
                 ],
             },
             {
@@ -130,6 +133,7 @@ const items = [
           { text: "使用 Go SDK 连接", link: "/zh/RobustMQ-MQTT/SDK/go-sdk" },
           { text: "使用 Python SDK 连接", link: "/zh/RobustMQ-MQTT/SDK/python-sdk" },
           { text: "使用 JavaScript SDK 连接", link: "/zh/RobustMQ-MQTT/SDK/javascript-sdk" },
+          { text: "使用 Rust SDK 连接", link: "/zh/RobustMQ-MQTT/SDK/rust-sdk" },
         ],
       },
       {
@@ -217,6 +221,7 @@ const items = [
           { text: "概述", link: "/zh/RuleEngine/Introduction" },
           { text: "算子列表", link: "/zh/RuleEngine/overview" },
           { text: "处理 Demo", link: "/zh/RuleEngine/Demo" },
+          { text: "MQTT 消息规则", link: "/zh/RuleEngine/MessageRule" },
         ],
       },
     ],
@@ -324,6 +329,14 @@ const items = [
             text: "Pprof 使用指南",
             link: "/zh/ContributionGuide/ContributingCode/Pprof-Usage",
           },
+          {
+            text: "模糊测试",
+            link: "/zh/ContributionGuide/ContributingCode/Fuzz-Testing",
+          },
+          {
+            text: "基准测试",
+            link: "/zh/ContributionGuide/ContributingCode/Benchmark",
+          },
           {
             text: "编译打包",
             link: "/zh/ContributionGuide/ContributingCode/Build-and-Package",
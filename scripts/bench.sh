@@ -0,0 +1,115 @@
+#!/bin/bash
+# Copyright 2023 RobustMQ Team
+#
+# Licensed under the Apache License, Version 2.0 (the "License");
+# you may not use this file except in compliance with the License.
+# You may obtain a copy of the License at
+#
+#     http://www.apache.org/licenses/LICENSE-2.0
+#
+# Unless required by applicable law or agreed to in writing, software
+# distributed under the License is distributed on an "AS IS" BASIS,
+# WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+# See the License for the specific language governing permissions and
+# limitations under the License.
+
+# Criterion benchmarks for the hot paths, with an optional regression gate.
+#
+#   ./scripts/bench.sh                                  run the suite
+#   ./scripts/bench.sh --save-baseline main             run and save the results as "main"
+#   ./scripts/bench.sh --baseline main [--threshold 10] compare with "main" and fail when a
+#                                                       benchmark got slower by more than
+#                                                       THRESHOLD percent
+#
+# A benchmark only counts as regressed when the lower bound of criterion's confidence
+# interval for the change in mean time is above the threshold, so noise alone does not
+# fail the run.
+
+set -e
+
+SUITE=(
+    "mqtt-broker:topic_trie"
+    "common-security:acl_eval"
+    "metadata-struct:record_serialization"
+    "node-call:dispatch"
+)
+
+SAVE_BASELINE=""
+BASELINE=""
+THRESHOLD=10
+
+while [ $# -gt 0 ]; do
+    case "$1" in
+        --save-baseline)
+            SAVE_BASELINE="$2"
+            shift 2
+            ;;
+        --baseline)
+            BASELINE="$2"
+            shift 2
+            ;;
+        --threshold)
+            THRESHOLD="$2"
+            shift 2
+            ;;
+        *)
+            echo "Unknown argument: $1"
+            exit 1
+            ;;
+    esac
+done
+
+CRITERION_DIR="${CARGO_TARGET_DIR:-target}/criterion"
+
+CRITERION_ARGS=()
+if [ -n "$SAVE_BASELINE" ]; then
+    CRITERION_ARGS+=(--save-baseline "$SAVE_BASELINE")
+fi
+if [ -n "$BASELINE" ]; then
+    CRITERION_ARGS+=(--baseline "$BASELINE")
+    # Drop the comparisons left by earlier runs so only this run is checked.
+    if [ -d "$CRITERION_DIR" ]; then
+        find "$CRITERION_DIR" -type d -name change -prune -exec rm -rf {} +
+    fi
+fi
+
+for entry in "${SUITE[@]}"; do
+    package="${entry%%:*}"
+    bench="${entry##*:}"
+    echo "Running benchmark ${bench} (${package})..."
+    cargo bench -p "$package" --bench "$bench" -- "${CRITERION_ARGS[@]}"
+done
+
+if [ -z "$BASELINE" ]; then
+    exit 0
+fi
+
+echo "Comparing with baseline '${BASELINE}' (threshold ${THRESHOLD}%)..."
+python3 - "$CRITERION_DIR" "$THRESHOLD" <<'PYEOF'
+import json
+import os
+import sys
+
+root, threshold = sys.argv[1], float(sys.argv[2]) / 100
+rows = []
+for dirpath, _, filenames in os.walk(root):
+    if os.path.basename(dirpath) != "change" or "estimates.json" not in filenames:
+        continue
+    with open(os.path.join(dirpath, "estimates.json")) as f:
+        mean = json.load(f)["mean"]
+    bench_id = os.path.relpath(os.path.dirname(dirpath), root)
+    rows.append((bench_id, mean["point_estimate"], mean["confidence_interval"]["lower_bound"]))
+
+regressed = [row for row in rows if row[2] > threshold]
+for bench_id, change, _ in sorted(rows, key=lambda row: -row[1]):
+    mark = "REGRESSED" if any(r[0] == bench_id for r in regressed) else ""
+    print(f"{change * 100:+8.2f}%  {bench_id}  {mark}")
+
+if not rows:
+    print("No comparison results found, was the baseline saved?")
+    sys.exit(1)
+if regressed:
+    print(f"{len(regressed)} benchmark(s) regressed by more than {threshold * 100:.0f}%")
+    sys.exit(1)
+print("No benchmark regressed beyond the threshold")
+PYEOF
@@ -286,6 +286,16 @@ pub async fn get_monitor_data<T, R>(&self, request: &T) -> Result<R, HttpClientE
             .await
     }
 
+    /// Get the stored `$SYS` metrics history for a time range
+    pub async fn get_monitor_history<T, R>(&self, request: &T) -> Result<R, HttpClientError>
+    where
+        T: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        self.get_with_params(&api_path(MQTT_MONITOR_HISTORY_PATH), request)
+            .await
+    }
+
     /// Get client list
     pub async fn get_client_list<T, R>(
         &self,
@@ -299,6 +309,34 @@ pub async fn get_client_list<T, R>(
             .await
     }
 
+    /// Get client attributes
+    pub async fn get_client_attribute<T, R>(&self, request: &T) -> Result<R, HttpClientError>
+    where
+        T: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        self.get_with_params(&api_path(MQTT_CLIENT_ATTRIBUTE_PATH), request)
+            .await
+    }
+
+    /// Set client attributes
+    pub async fn set_client_attribute<T>(&self, request: &T) -> Result<String, HttpClientError>
+    where
+        T: Serialize,
+    {
+        self.post_raw(&api_path(MQTT_CLIENT_ATTRIBUTE_SET_PATH), request)
+            .await
+    }
+
+    /// Delete client attributes
+    pub async fn delete_client_attribute<T>(&self, request: &T) -> Result<String, HttpClientError>
+    where
+        T: Serialize,
+    {
+        self.post_raw(&api_path(MQTT_CLIENT_ATTRIBUTE_DELETE_PATH), request)
+            .await
+    }
+
     /// Get session list
     pub async fn get_session_list<T, R>(
         &self,
@@ -312,6 +350,26 @@ pub async fn get_session_list<T, R>(
             .await
     }
 
+    /// Export the state of one session or of every session of the node
+    pub async fn export_session<T, R>(&self, request: &T) -> Result<R, HttpClientError>
+    where
+        T: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        self.post(&api_path(MQTT_SESSION_EXPORT_PATH), request)
+            .await
+    }
+
+    /// Import sessions from an export
+    pub async fn import_session<T, R>(&self, request: &T) -> Result<R, HttpClientError>
+    where
+        T: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        self.post(&api_path(MQTT_SESSION_IMPORT_PATH), request)
+            .await
+    }
+
     /// Get topic list
     pub async fn get_topic_list<T, R>(
         &self,
@@ -573,6 +631,101 @@ pub async fn get_system_alarm_list<T, R>(
             .await
     }
 
+    /// Get the system alarms currently active
+    pub async fn get_system_alarm_active_list<T, R>(
+        &self,
+        request: &T,
+    ) -> Result<PageReplyData<R>, HttpClientError>
+    where
+        T: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        self.get_with_params(&api_path(MQTT_SYSTEM_ALARM_ACTIVE_PATH), request)
+            .await
+    }
+
+    /// Get message replay list
+    pub async fn get_message_replay_list<T, R>(
+        &self,
+        request: &T,
+    ) -> Result<PageReplyData<R>, HttpClientError>
+    where
+        T: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        self.get_with_params(&api_path(MQTT_MESSAGE_REPLAY_LIST_PATH), request)
+            .await
+    }
+
+    /// Start a message replay
+    pub async fn create_message_replay<T>(&self, request: &T) -> Result<String, HttpClientError>
+    where
+        T: Serialize,
+    {
+        self.post_raw(&api_path(MQTT_MESSAGE_REPLAY_CREATE_PATH), request)
+            .await
+    }
+
+    /// Cancel a running message replay
+    pub async fn cancel_message_replay<T>(&self, request: &T) -> Result<String, HttpClientError>
+    where
+        T: Serialize,
+    {
+        self.post_raw(&api_path(MQTT_MESSAGE_REPLAY_CANCEL_PATH), request)
+            .await
+    }
+
+    /// Get message trace rules
+    pub async fn get_trace_list<T, R>(&self, request: &T) -> Result<R, HttpClientError>
+    where
+        T: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        self.get_with_params(&api_path(MQTT_TRACE_LIST_PATH), request)
+            .await
+    }
+
+    /// Start tracing a client id or topic
+    pub async fn create_trace<T>(&self, request: &T) -> Result<String, HttpClientError>
+    where
+        T: Serialize,
+    {
+        self.post_raw(&api_path(MQTT_TRACE_CREATE_PATH), request)
+            .await
+    }
+
+    /// Stop a message trace
+    pub async fn delete_trace<T>(&self, request: &T) -> Result<String, HttpClientError>
+    where
+        T: Serialize,
+    {
+        self.post_raw(&api_path(MQTT_TRACE_DELETE_PATH), request)
+            .await
+    }
+
+    /// Get the trace events recorded on a broker
+    pub async fn get_trace_events<T, R>(&self, request: &T) -> Result<R, HttpClientError>
+    where
+        T: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        self.get_with_params(&api_path(MQTT_TRACE_EVENTS_PATH), request)
+            .await
+    }
+
+    /// Get slow request list
+    pub async fn get_slow_request_list<T, R>(
+        &self,
+        request: &T,
+    ) -> Result<PageReplyData<R>, HttpClientError>
+    where
+        T: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        self.get_with_params(&api_path(MQTT_SLOW_REQUEST_LIST_PATH), request)
+            .await
+    }
+
     /// Get cluster health status
     pub async fn get_cluster_healthy(&self) -> Result<String, HttpClientError> {
         self.get_raw(&api_path(HEALTH_CLUSTER_PATH)).await
@@ -597,6 +750,14 @@ pub async fn get_cluster_config(&self) -> Result<String, HttpClientError> {
         self.get_raw(&api_path(CLUSTER_CONFIG_GET_PATH)).await
     }
 
+    /// Get the config sections the brokers disagree on
+    pub async fn get_cluster_config_drift<R>(&self) -> Result<R, HttpClientError>
+    where
+        R: for<'de> Deserialize<'de>,
+    {
+        self.get(&api_path(CLUSTER_CONFIG_DRIFT_PATH)).await
+    }
+
     // ========== Tenant APIs ==========
 
     /// Get tenant list
@@ -639,6 +800,110 @@ pub async fn node_leave<T>(&self, request: &T) -> Result<String, HttpClientError
             .await
     }
 
+    /// Exclude a node from connector placement and leadership transfer.
+    pub async fn node_cordon<T>(&self, request: &T) -> Result<String, HttpClientError>
+    where
+        T: Serialize,
+    {
+        self.post_raw(&api_path(CLUSTER_NODE_CORDON_PATH), request)
+            .await
+    }
+
+    /// Revert a node cordon.
+    pub async fn node_uncordon<T>(&self, request: &T) -> Result<String, HttpClientError>
+    where
+        T: Serialize,
+    {
+        self.post_raw(&api_path(CLUSTER_NODE_UNCORDON_PATH), request)
+            .await
+    }
+
+    /// Get the version and cordon state of every node.
+    pub async fn node_status(&self) -> Result<String, HttpClientError> {
+        self.get_raw(&api_path(CLUSTER_NODE_STATUS_PATH)).await
+    }
+
+    /// Hand a node's raft shard leadership over to another meta node.
+    pub async fn node_transfer_leader<T>(&self, request: &T) -> Result<String, HttpClientError>
+    where
+        T: Serialize,
+    {
+        self.post_raw(&api_path(CLUSTER_NODE_TRANSFER_LEADER_PATH), request)
+            .await
+    }
+
+    /// List the backups of the node's local RocksDB.
+    pub async fn rocksdb_backup_list<R>(&self) -> Result<R, HttpClientError>
+    where
+        R: for<'de> Deserialize<'de>,
+    {
+        self.get(&api_path(CLUSTER_ROCKSDB_BACKUP_LIST_PATH)).await
+    }
+
+    /// Take a backup of the node's local RocksDB.
+    pub async fn rocksdb_backup_create<R>(&self) -> Result<R, HttpClientError>
+    where
+        R: for<'de> Deserialize<'de>,
+    {
+        self.post(&api_path(CLUSTER_ROCKSDB_BACKUP_CREATE_PATH), &())
+            .await
+    }
+
+    /// Restore a backup of the node's local RocksDB on its next start.
+    pub async fn rocksdb_backup_restore<T>(&self, request: &T) -> Result<String, HttpClientError>
+    where
+        T: Serialize,
+    {
+        self.post_raw(&api_path(CLUSTER_ROCKSDB_BACKUP_RESTORE_PATH), request)
+            .await
+    }
+
+    /// Dump the cluster metadata.
+    pub async fn metadata_export<T, R>(&self, request: &T) -> Result<R, HttpClientError>
+    where
+        T: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        self.post(&api_path(CLUSTER_METADATA_EXPORT_PATH), request)
+            .await
+    }
+
+    /// Restore a metadata dump into the cluster.
+    pub async fn metadata_import<T, R>(&self, request: &T) -> Result<R, HttpClientError>
+    where
+        T: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        self.post(&api_path(CLUSTER_METADATA_IMPORT_PATH), request)
+            .await
+    }
+
+    /// List the faults injected on the node.
+    pub async fn fault_list<R>(&self) -> Result<R, HttpClientError>
+    where
+        R: for<'de> Deserialize<'de>,
+    {
+        self.get(&api_path(CLUSTER_FAULT_LIST_PATH)).await
+    }
+
+    /// Inject a fault on the node.
+    pub async fn fault_set<T>(&self, request: &T) -> Result<String, HttpClientError>
+    where
+        T: Serialize,
+    {
+        self.post_raw(&api_path(CLUSTER_FAULT_SET_PATH), request)
+            .await
+    }
+
+    /// Remove one fault, or every fault when the id is empty.
+    pub async fn fault_remove<T>(&self, request: &T) -> Result<String, HttpClientError>
+    where
+        T: Serialize,
+    {
+        self.post_raw(&api_path(CLUSTER_FAULT_REMOVE_PATH), request)
+            .await
+    }
+
     /// Get MQTT tenant list
     pub async fn get_mqtt_tenant_list<T, R>(
         &self,
@@ -743,6 +1008,37 @@ pub async fn delete_auto_subscribe<T>(&self, request: &T) -> Result<String, Http
             .await
     }
 
+    /// Get message rule list
+    pub async fn get_message_rule_list<T, R>(
+        &self,
+        request: &T,
+    ) -> Result<PageReplyData<R>, HttpClientError>
+    where
+        T: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        self.get_with_params(&api_path(MQTT_MESSAGE_RULE_LIST_PATH), request)
+            .await
+    }
+
+    /// Create message rule
+    pub async fn create_message_rule<T>(&self, request: &T) -> Result<String, HttpClientError>
+    where
+        T: Serialize,
+    {
+        self.post_raw(&api_path(MQTT_MESSAGE_RULE_CREATE_PATH), request)
+            .await
+    }
+
+    /// Delete message rule
+    pub async fn delete_message_rule<T>(&self, request: &T) -> Result<String, HttpClientError>
+    where
+        T: Serialize,
+    {
+        self.post_raw(&api_path(MQTT_MESSAGE_RULE_DELETE_PATH), request)
+            .await
+    }
+
     /// Get slow subscribe list
     pub async fn get_slow_subscribe_list<T, R>(
         &self,
@@ -766,6 +1062,16 @@ pub async fn get_subscribe_detail<T, R>(&self, request: &T) -> Result<R, HttpCli
             .await
     }
 
+    /// Describe a subscription: push statistics, lag and push thread data
+    pub async fn get_subscribe_describe<T, R>(&self, request: &T) -> Result<R, HttpClientError>
+    where
+        T: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        self.get_with_params(&api_path(MQTT_SUBSCRIBE_DESCRIBE_PATH), request)
+            .await
+    }
+
     // ========== Storage Engine APIs ==========
 
     /// Get shard list
@@ -862,6 +1168,16 @@ pub async fn get_offset_by_group<T, R>(&self, request: &T) -> Result<R, HttpClie
             .await
     }
 
+    /// Get offset watermarks of every shard of a topic
+    pub async fn get_shard_watermarks<T, R>(&self, request: &T) -> Result<R, HttpClientError>
+    where
+        T: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        self.post(&api_path(CLUSTER_OFFSET_WATERMARKS_PATH), request)
+            .await
+    }
+
     /// Commit offset
     pub async fn commit_offset<T>(&self, request: &T) -> Result<String, HttpClientError>
     where
@@ -871,6 +1187,48 @@ pub async fn commit_offset<T>(&self, request: &T) -> Result<String, HttpClientEr
             .await
     }
 
+    /// Get consumer group list
+    pub async fn get_consumer_group_list<T, R>(
+        &self,
+        request: &T,
+    ) -> Result<PageReplyData<R>, HttpClientError>
+    where
+        T: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        self.get_with_params(&api_path(CLUSTER_CONSUMER_GROUP_LIST_PATH), request)
+            .await
+    }
+
+    /// Get consumer group detail (members + per-topic lag)
+    pub async fn get_consumer_group_detail<T, R>(&self, request: &T) -> Result<R, HttpClientError>
+    where
+        T: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        self.get_with_params(&api_path(CLUSTER_CONSUMER_GROUP_DETAIL_PATH), request)
+            .await
+    }
+
+    /// Delete the committed offsets of an idle consumer group
+    pub async fn delete_consumer_group<T>(&self, request: &T) -> Result<String, HttpClientError>
+    where
+        T: Serialize,
+    {
+        self.post_raw(&api_path(CLUSTER_CONSUMER_GROUP_DELETE_PATH), request)
+            .await
+    }
+
+    /// Reset the offsets of an idle consumer group on a topic
+    pub async fn reset_consumer_group_offset<T, R>(&self, request: &T) -> Result<R, HttpClientError>
+    where
+        T: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        self.post(&api_path(CLUSTER_CONSUMER_GROUP_RESET_OFFSET_PATH), request)
+            .await
+    }
+
     // ========== Cluster Message APIs ==========
 
     /// 发送消息到 topic
@@ -943,6 +1301,53 @@ pub async fn get_share_group_detail<T, R>(&self, request: &T) -> Result<R, HttpC
         self.get_with_params(&api_path(CLUSTER_SHARE_GROUP_DETAIL_PATH), request)
             .await
     }
+
+    /// Get recurring delay task list
+    pub async fn get_recurring_delay_task_list<T, R>(
+        &self,
+        request: &T,
+    ) -> Result<PageReplyData<R>, HttpClientError>
+    where
+        T: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        self.get_with_params(&api_path(CLUSTER_DELAY_TASK_RECURRING_LIST_PATH), request)
+            .await
+    }
+
+    /// Cancel a recurring delay task
+    pub async fn cancel_recurring_delay_task<T>(
+        &self,
+        request: &T,
+    ) -> Result<String, HttpClientError>
+    where
+        T: Serialize,
+    {
+        self.post_raw(&api_path(CLUSTER_DELAY_TASK_RECURRING_CANCEL_PATH), request)
+            .await
+    }
+
+    /// Get pending delay task count per shard
+    pub async fn get_delay_task_shard_list<R>(&self) -> Result<R, HttpClientError>
+    where
+        R: for<'de> Deserialize<'de>,
+    {
+        self.get(&api_path(CLUSTER_DELAY_TASK_SHARD_LIST_PATH))
+            .await
+    }
+
+    /// Get pending delay tasks of one shard
+    pub async fn get_delay_task_shard_task_list<T, R>(
+        &self,
+        request: &T,
+    ) -> Result<PageReplyData<R>, HttpClientError>
+    where
+        T: Serialize,
+        R: for<'de> Deserialize<'de>,
+    {
+        self.get_with_params(&api_path(CLUSTER_DELAY_TASK_SHARD_TASK_LIST_PATH), request)
+            .await
+    }
 }
 
 #[cfg(test)]
@@ -74,6 +74,8 @@ pub struct CreateAclReq {
     #[validate(length(min = 1, max = 50, message = "Permission length must be between 1-50"))]
     #[validate(custom(function = "validate_acl_permission"))]
     pub permission: String,
+
+    pub priority: Option<u32>,
 }
 
 fn validate_acl_resource_type(resource_type: &str) -> Result<(), validator::ValidationError> {
@@ -133,6 +135,7 @@ pub struct AclListRow {
     pub ip: String,
     pub action: String,
     pub permission: String,
+    pub priority: u32,
 }
 
 use common_base::{
@@ -190,6 +193,7 @@ pub async fn acl_list(
             ip: acl.ip.to_string(),
             action: acl.action.to_string(),
             permission: acl.permission.to_string(),
+            priority: acl.priority,
         })
         .collect();
 
@@ -258,6 +262,7 @@ async fn acl_create_inner(state: &Arc<HttpState>, params: &CreateAclReq) -> Resu
         ip: params.ip.clone().unwrap_or_default(),
         action,
         permission,
+        priority: params.priority.unwrap_or_default(),
     };
 
     let acl_storage = AclStorage::new(state.client_pool.clone());
@@ -288,6 +293,7 @@ async fn acl_delete_inner(state: &Arc<HttpState>, params: &DeleteAclReq) -> Resu
         ip: String::new(),
         action: EnumAclAction::All,
         permission: EnumAclPermission::Allow,
+        priority: 0,
     };
 
     let acl_storage = AclStorage::new(state.client_pool.clone());
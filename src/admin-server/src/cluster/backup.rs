@@ -0,0 +1,72 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{state::HttpState, tool::extractor::ValidatedJson};
+use axum::extract::State;
+use common_base::http_response::{error_response, success_response};
+use common_config::broker::broker_config;
+use rocksdb_engine::backup::{
+    list_backups, request_restore, rocksdb_backup_dir, schedule::backup_rocksdb,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use validator::Validate;
+
+pub use rocksdb_engine::backup::BackupInfo;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RocksDBBackupListResp {
+    pub backup_dir: String,
+    pub backups: Vec<BackupInfo>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default, Validate)]
+pub struct RocksDBBackupRestoreReq {
+    /// Defaults to the latest backup.
+    #[serde(default)]
+    pub backup_id: Option<u32>,
+}
+
+/// Backups of this node's local RocksDB, oldest first.
+pub async fn rocksdb_backup_list(State(_state): State<Arc<HttpState>>) -> String {
+    let backup_dir = rocksdb_backup_dir(broker_config());
+    match list_backups(&backup_dir) {
+        Ok(backups) => success_response(RocksDBBackupListResp {
+            backup_dir,
+            backups,
+        }),
+        Err(e) => error_response(e.to_string()),
+    }
+}
+
+/// Take a backup of this node's local RocksDB now.
+pub async fn rocksdb_backup_create(State(state): State<Arc<HttpState>>) -> String {
+    match backup_rocksdb(&state.rocksdb_engine_handler, broker_config()).await {
+        Ok(backup) => success_response(backup),
+        Err(e) => error_response(e.to_string()),
+    }
+}
+
+/// Restore a backup on the next start of this node. The DB cannot be
+/// replaced while it is open, so the node has to be restarted to apply it.
+pub async fn rocksdb_backup_restore(
+    State(_state): State<Arc<HttpState>>,
+    ValidatedJson(params): ValidatedJson<RocksDBBackupRestoreReq>,
+) -> String {
+    let backup_dir = rocksdb_backup_dir(broker_config());
+    match request_restore(&backup_dir, params.backup_id) {
+        Ok(()) => success_response("Restore scheduled, restart the node to apply it"),
+        Err(e) => error_response(e.to_string()),
+    }
+}
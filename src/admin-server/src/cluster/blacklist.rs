@@ -67,11 +67,12 @@ pub struct CreateBlackListReq {
 
 fn validate_blacklist_type(blacklist_type: &str) -> Result<(), validator::ValidationError> {
     match blacklist_type {
-        "ClientId" | "User" | "Ip" | "ClientIdMatch" | "UserMatch" | "IPCIDR" => Ok(()),
+        "ClientId" | "User" | "Ip" | "ClientIdMatch" | "UserMatch" | "IPCIDR" | "ClientIdRegex"
+        | "UserRegex" => Ok(()),
         _ => {
             let mut err = validator::ValidationError::new("invalid_blacklist_type");
             err.message = Some(std::borrow::Cow::from(
-                "Blacklist type must be ClientId, User, Ip, ClientIdMatch, UserMatch or IPCIDR",
+                "Blacklist type must be ClientId, User, Ip, ClientIdMatch, UserMatch, IPCIDR, ClientIdRegex or UserRegex",
             ));
             Err(err)
         }
@@ -101,6 +102,7 @@ pub struct BlackListListRow {
     http_response::{error_response, success_response},
     utils::time_util::timestamp_to_local_datetime,
 };
+use common_security::auth::blacklist::validate_blacklist_resource;
 use common_security::storage::blacklist::BlackListStorage;
 use metadata_struct::auth::blacklist::{get_blacklist_type_by_str, SecurityBlackList};
 use std::sync::Arc;
@@ -187,6 +189,10 @@ pub async fn blacklist_create(
         }
     };
 
+    if let Err(e) = validate_blacklist_resource(&blacklist_type, &params.resource_name) {
+        return error_response(e.to_string());
+    }
+
     let mqtt_blacklist = SecurityBlackList {
         name: params.name.clone(),
         tenant: params.tenant.clone(),
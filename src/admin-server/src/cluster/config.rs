@@ -18,12 +18,15 @@
     extract::{Query, State},
     Json,
 };
+use broker_core::cluster::ClusterStorage;
 use broker_core::dynamic_config::{
-    save_cluster_dynamic_config, update_cluster_dynamic_config, ClusterDynamicConfig,
+    merge_dynamic_config, save_cluster_dynamic_config, update_cluster_dynamic_config,
+    ClusterDynamicConfig,
 };
 use bytes::Bytes;
 use common_base::http_response::{error_response, success_response};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -37,12 +40,22 @@ pub struct ClusterConfigSetReq {
     pub config: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ClusterConfigDriftRow {
+    pub section: String,
+    // (broker_id, digest)
+    pub digests: BTreeMap<u64, u32>,
+    pub since: u64,
+}
+
 pub async fn cluster_config_set(
     State(state): State<Arc<HttpState>>,
     Json(params): Json<ClusterConfigSetReq>,
 ) -> String {
     let resource_type = match params.config_type.as_str() {
         "MqttSlowSubscribeConfig" => ClusterDynamicConfig::MqttSlowSubscribeConfig,
+        "MqttSlowRequest" => ClusterDynamicConfig::MqttSlowRequest,
+        "MqttKeepAlive" => ClusterDynamicConfig::MqttKeepAlive,
         "MqttFlappingDetect" => ClusterDynamicConfig::MqttFlappingDetect,
         "MqttProtocol" => ClusterDynamicConfig::MqttProtocol,
         "MqttOfflineMessage" => ClusterDynamicConfig::MqttOfflineMessage,
@@ -51,25 +64,60 @@ pub async fn cluster_config_set(
         "MqttLimit" => ClusterDynamicConfig::MqttLimit,
         "ClusterLimit" => ClusterDynamicConfig::ClusterLimit,
         "MetaRuntime" => ClusterDynamicConfig::MetaRuntime,
+        "Log" => ClusterDynamicConfig::Log,
+        "StorageRouting" => ClusterDynamicConfig::StorageRouting,
+        "MqttTrace" => ClusterDynamicConfig::MqttTrace,
         other => {
             return error_response(format!("Unknown config_type: {other}"));
         }
     };
 
     let config_bytes = Bytes::from(params.config.into_bytes());
+    match apply_cluster_config(&state, resource_type, config_bytes).await {
+        Ok(()) => success_response("success"),
+        Err(e) => error_response(e),
+    }
+}
+
+/// Validate, persist and apply a dynamic cluster config. The meta service
+/// pushes the stored value to every other broker.
+pub(crate) async fn apply_cluster_config(
+    state: &HttpState,
+    resource_type: ClusterDynamicConfig,
+    config_bytes: Bytes,
+) -> Result<(), String> {
+    // Reject invalid values before they are stored and pushed to every broker.
+    if let Err(e) = merge_dynamic_config(
+        &state.broker_cache.get_cluster_config(),
+        resource_type,
+        &config_bytes,
+    ) {
+        return Err(format!("Invalid config: {e}"));
+    }
 
     if let Err(e) =
         save_cluster_dynamic_config(&state.client_pool, resource_type, config_bytes.to_vec()).await
     {
-        return error_response(format!("Failed to save config: {e}"));
+        return Err(format!("Failed to save config: {e}"));
     }
 
     if let Err(e) = update_cluster_dynamic_config(&state.broker_cache, resource_type, config_bytes)
     {
-        return error_response(format!("Failed to update in-memory config: {e}"));
+        return Err(format!("Failed to update in-memory config: {e}"));
     }
 
-    success_response("success")
+    if resource_type == ClusterDynamicConfig::ClusterLimit {
+        let limit = state.broker_cache.get_cluster_config().cluster_limit;
+        if let Err(e) = state
+            .rate_limiter
+            .set_network_connection_rate(limit.max_network_connection_rate)
+            .await
+        {
+            return Err(format!("Failed to update connection rate limit: {e}"));
+        }
+    }
+
+    Ok(())
 }
 
 pub async fn cluster_config_get(
@@ -107,3 +155,22 @@ pub async fn cluster_config_get(
         )),
     }
 }
+
+/// Config sections the brokers disagree on, from the digests they send to
+/// Meta Service with each heartbeat.
+pub async fn cluster_config_drift(State(state): State<Arc<HttpState>>) -> String {
+    let cluster_storage = ClusterStorage::new(state.client_pool.clone());
+    match cluster_storage.get_config_drift().await {
+        Ok(drifts) => success_response(
+            drifts
+                .into_iter()
+                .map(|drift| ClusterConfigDriftRow {
+                    section: drift.section,
+                    digests: drift.digests.into_iter().collect(),
+                    since: drift.since,
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Err(e) => error_response(format!("Failed to get config drift: {e}")),
+    }
+}
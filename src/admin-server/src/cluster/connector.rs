@@ -18,6 +18,7 @@
     tools::now_second,
     utils::time_util::timestamp_to_local_datetime,
 };
+use connector::resource::ConnectorResourceUsage;
 use metadata_struct::connector::{
     config_cassandra::CassandraConnectorConfig,
     config_clickhouse::ClickHouseConnectorConfig,
@@ -85,6 +86,12 @@ pub struct ConnectorDetailResp {
     pub send_success_total: u64,
     pub send_fail_total: u64,
     pub last_msg: Option<String>,
+    #[serde(default)]
+    pub max_records_per_sec: Option<u32>,
+    #[serde(default)]
+    pub avg_sink_latency_ms: u64,
+    #[serde(default)]
+    pub resource_usage: ConnectorResourceUsage,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Validate)]
@@ -118,6 +125,10 @@ pub struct CreateConnectorReq {
         message = "Topic name length must be between 1-256"
     ))]
     pub topic_name: String,
+
+    #[serde(default)]
+    #[validate(range(min = 1, message = "max_records_per_sec must be greater than 0"))]
+    pub max_records_per_sec: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Validate, Default)]
@@ -361,6 +372,7 @@ async fn connector_create_inner(
         broker_id: None,
         create_time: now_second(),
         update_time: now_second(),
+        max_records_per_sec: params.max_records_per_sec,
     };
 
     storage.create_connector(connector).await
@@ -496,17 +508,16 @@ pub async fn connector_detail(
     State(state): State<Arc<HttpState>>,
     Query(params): Query<ConnectorDetailReq>,
 ) -> String {
-    if state
+    let Some(connector) = state
         .mqtt_context
         .connector_manager
         .get_connector_by_tenant(&params.tenant, &params.connector_name)
-        .is_none()
-    {
+    else {
         return error_response(format!(
             "Connector {} does not exist.",
             params.connector_name
         ));
-    }
+    };
 
     match state
         .mqtt_context
@@ -519,6 +530,9 @@ pub async fn connector_detail(
                 last_send_time: data.last_send_time,
                 send_fail_total: data.send_fail_total,
                 send_success_total: data.send_success_total,
+                max_records_per_sec: connector.max_records_per_sec,
+                avg_sink_latency_ms: data.resource_usage.avg_sink_latency_ms(),
+                resource_usage: data.resource_usage,
             };
             success_response(req)
         }
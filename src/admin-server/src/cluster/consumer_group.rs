@@ -0,0 +1,342 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::{
+    state::HttpState,
+    tool::{
+        query::{apply_pagination, apply_sorting, build_query_params, Queryable},
+        PageReplyData,
+    },
+};
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use common_base::http_response::{error_response, success_response};
+use metadata_struct::adapter::adapter_offset::{AdapterConsumerGroupOffset, AdapterOffsetStrategy};
+use mqtt_broker::core::consumer_lag::{shard_lags, ShardLag};
+use protocol::meta::meta_service_common::OffsetGroupRaw;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ConsumerGroupListReq {
+    pub tenant: Option<String>,
+    pub group_name: Option<String>,
+    pub limit: Option<u32>,
+    pub page: Option<u32>,
+    pub sort_field: Option<String>,
+    pub sort_by: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ConsumerGroupListRaw {
+    pub tenant: String,
+    pub group_name: String,
+    pub shard_count: usize,
+    pub member_count: usize,
+    pub last_commit_time: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConsumerGroupDetailReq {
+    pub tenant: String,
+    pub group_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConsumerGroupMember {
+    pub member_id: String,
+    pub shards: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ConsumerGroupTopicLag {
+    pub topic_name: String,
+    pub lag: u64,
+    pub shards: Vec<ShardLag>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ConsumerGroupDetailResp {
+    pub tenant: String,
+    pub group_name: String,
+    pub last_commit_time: u64,
+    pub members: Vec<ConsumerGroupMember>,
+    /// Lag of every topic owning a shard the group has committed on.
+    pub topics: Vec<ConsumerGroupTopicLag>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConsumerGroupDeleteReq {
+    pub tenant: String,
+    pub group_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConsumerGroupResetOffsetReq {
+    pub tenant: String,
+    pub group_name: String,
+    pub topic_name: String,
+    /// One of "earliest", "latest" or "timestamp".
+    pub target: String,
+    /// Required when `target` is "timestamp", in seconds.
+    pub timestamp: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ConsumerGroupResetOffsetResp {
+    pub offsets: HashMap<String, u64>,
+}
+
+impl Queryable for ConsumerGroupListRaw {
+    fn get_field_str(&self, field: &str) -> Option<String> {
+        match field {
+            "tenant" => Some(self.tenant.clone()),
+            "group_name" => Some(self.group_name.clone()),
+            "last_commit_time" => Some(self.last_commit_time.to_string()),
+            _ => None,
+        }
+    }
+}
+
+pub async fn consumer_group_list(
+    State(state): State<Arc<HttpState>>,
+    Query(params): Query<ConsumerGroupListReq>,
+) -> String {
+    let options = build_query_params(
+        params.page,
+        params.limit,
+        params.sort_field,
+        params.sort_by,
+        None,
+        None,
+        None,
+    );
+
+    let tenant = params.tenant.unwrap_or_default();
+    let groups = match state
+        .storage_driver_manager
+        .offset_manager
+        .list_groups(&tenant, "")
+        .await
+    {
+        Ok(data) => data,
+        Err(e) => {
+            return error_response(e.to_string());
+        }
+    };
+
+    let groups: Vec<ConsumerGroupListRaw> = groups
+        .into_iter()
+        .filter(|g| {
+            params
+                .group_name
+                .as_ref()
+                .is_none_or(|name| g.group.contains(name.as_str()))
+        })
+        .map(|g| ConsumerGroupListRaw {
+            tenant: g.tenant,
+            group_name: g.group,
+            shard_count: g.offsets.len(),
+            member_count: g.members.len(),
+            last_commit_time: g.last_commit_time,
+        })
+        .collect();
+
+    let sorted = apply_sorting(groups, &options);
+    let pagination = apply_pagination(sorted, &options);
+
+    success_response(PageReplyData {
+        data: pagination.0,
+        total_count: pagination.1,
+    })
+}
+
+pub async fn consumer_group_detail(
+    State(state): State<Arc<HttpState>>,
+    Query(params): Query<ConsumerGroupDetailReq>,
+) -> String {
+    let group = match find_group(&state, &params.tenant, &params.group_name).await {
+        Ok(group) => group,
+        Err(e) => return error_response(e),
+    };
+
+    let offsets: Vec<AdapterConsumerGroupOffset> = group
+        .offsets
+        .iter()
+        .map(|raw| AdapterConsumerGroupOffset {
+            group: group.group.clone(),
+            shard_name: raw.shard_name.clone(),
+            offset: raw.offset,
+            ..Default::default()
+        })
+        .collect();
+    let committed: HashSet<&str> = offsets.iter().map(|o| o.shard_name.as_str()).collect();
+
+    let mut topics = Vec::new();
+    for topic in state.broker_cache.list_topics_by_tenant(&group.tenant) {
+        if !topic
+            .storage_name_list
+            .values()
+            .any(|shard| committed.contains(shard.as_str()))
+        {
+            continue;
+        }
+        let watermarks = match state
+            .storage_driver_manager
+            .shard_watermarks(&group.tenant, &topic.topic_name)
+            .await
+        {
+            Ok(data) => data,
+            Err(e) => {
+                return error_response(e.to_string());
+            }
+        };
+        let shards = shard_lags(&watermarks, &offsets);
+        topics.push(ConsumerGroupTopicLag {
+            topic_name: topic.topic_name,
+            lag: shards.iter().map(|s| s.lag).sum(),
+            shards,
+        });
+    }
+    topics.sort_by(|a, b| a.topic_name.cmp(&b.topic_name));
+
+    success_response(ConsumerGroupDetailResp {
+        tenant: group.tenant,
+        group_name: group.group,
+        last_commit_time: group.last_commit_time,
+        members: group
+            .members
+            .into_iter()
+            .map(|m| ConsumerGroupMember {
+                member_id: m.member_id,
+                shards: m.shards,
+            })
+            .collect(),
+        topics,
+    })
+}
+
+pub async fn consumer_group_delete(
+    State(state): State<Arc<HttpState>>,
+    Json(params): Json<ConsumerGroupDeleteReq>,
+) -> String {
+    if params.tenant.is_empty() || params.group_name.is_empty() {
+        return error_response("tenant and group_name cannot be empty".to_string());
+    }
+
+    if let Err(e) = state
+        .storage_driver_manager
+        .offset_manager
+        .delete_group(&params.tenant, &params.group_name)
+        .await
+    {
+        return error_response(e.to_string());
+    }
+
+    success_response("success")
+}
+
+pub async fn consumer_group_reset_offset(
+    State(state): State<Arc<HttpState>>,
+    Json(params): Json<ConsumerGroupResetOffsetReq>,
+) -> String {
+    if params.tenant.is_empty() || params.group_name.is_empty() {
+        return error_response("tenant and group_name cannot be empty".to_string());
+    }
+    if params.topic_name.is_empty() {
+        return error_response("topic_name cannot be empty".to_string());
+    }
+
+    let driver = &state.storage_driver_manager;
+    let offsets = match params.target.to_lowercase().as_str() {
+        "earliest" | "latest" => {
+            let latest = params.target.eq_ignore_ascii_case("latest");
+            driver
+                .shard_watermarks(&params.tenant, &params.topic_name)
+                .await
+                .map(|watermarks| {
+                    watermarks
+                        .into_iter()
+                        .map(|w| {
+                            let offset = if latest {
+                                w.latest_offset
+                            } else {
+                                w.earliest_offset
+                            };
+                            (w.shard_name, offset)
+                        })
+                        .collect::<HashMap<String, u64>>()
+                })
+        }
+        "timestamp" => {
+            let Some(timestamp) = params.timestamp else {
+                return error_response("timestamp is required when target is 'timestamp'".into());
+            };
+            // Shards with no record at or after the timestamp move to
+            // their latest offset.
+            driver
+                .shard_offsets_by_timestamp(
+                    &params.tenant,
+                    &params.topic_name,
+                    timestamp,
+                    AdapterOffsetStrategy::Latest,
+                )
+                .await
+        }
+        _ => {
+            return error_response(format!(
+                "Invalid target '{}', must be 'earliest', 'latest' or 'timestamp'",
+                params.target
+            ));
+        }
+    };
+    let offsets = match offsets {
+        Ok(data) => data,
+        Err(e) => {
+            return error_response(e.to_string());
+        }
+    };
+
+    if let Err(e) = driver
+        .offset_manager
+        .reset_offset(&params.tenant, &params.group_name, &offsets)
+        .await
+    {
+        return error_response(e.to_string());
+    }
+
+    success_response(ConsumerGroupResetOffsetResp { offsets })
+}
+
+async fn find_group(
+    state: &HttpState,
+    tenant: &str,
+    group_name: &str,
+) -> Result<OffsetGroupRaw, String> {
+    if tenant.is_empty() || group_name.is_empty() {
+        return Err("tenant and group_name cannot be empty".to_string());
+    }
+    let groups = state
+        .storage_driver_manager
+        .offset_manager
+        .list_groups(tenant, group_name)
+        .await
+        .map_err(|e| e.to_string())?;
+    groups
+        .into_iter()
+        .find(|g| g.tenant == tenant && g.group == group_name)
+        .ok_or_else(|| format!("Consumer group '{}/{}' not found", tenant, group_name))
+}
@@ -0,0 +1,206 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    state::HttpState,
+    tool::extractor::ValidatedJson,
+    tool::{
+        query::{apply_filters, apply_pagination, apply_sorting, build_query_params, Queryable},
+        PageReplyData,
+    },
+};
+use axum::extract::{Query, State};
+use common_base::http_response::{error_response, success_response};
+use delay_task::schedule::TaskSchedule;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use validator::Validate;
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct RecurringDelayTaskListReq {
+    pub task_type: Option<String>,
+    pub limit: Option<u32>,
+    pub page: Option<u32>,
+    pub sort_field: Option<String>,
+    pub sort_by: Option<String>,
+    pub filter_field: Option<String>,
+    pub filter_values: Option<Vec<String>>,
+    pub exact_match: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Validate)]
+pub struct CancelRecurringDelayTaskReq {
+    #[validate(length(min = 1, message = "task_id cannot be empty"))]
+    pub task_id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct DelayTaskShardTaskListReq {
+    pub shard_no: u32,
+    pub limit: Option<u32>,
+    pub page: Option<u32>,
+    pub sort_field: Option<String>,
+    pub sort_by: Option<String>,
+    pub filter_field: Option<String>,
+    pub filter_values: Option<Vec<String>>,
+    pub exact_match: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RecurringDelayTaskRow {
+    pub task_id: String,
+    pub task_type: String,
+    pub schedule: TaskSchedule,
+    pub next_run_time: u64,
+    pub persistent: bool,
+    pub create_time: u64,
+}
+
+impl Queryable for RecurringDelayTaskRow {
+    fn get_field_str(&self, field: &str) -> Option<String> {
+        match field {
+            "task_id" => Some(self.task_id.clone()),
+            "task_type" => Some(self.task_type.clone()),
+            "next_run_time" => Some(self.next_run_time.to_string()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PendingDelayTaskRow {
+    pub task_id: String,
+    pub task_type: String,
+    pub shard_no: u32,
+    pub delay_target_time_ms: u64,
+    pub persistent: bool,
+}
+
+impl Queryable for PendingDelayTaskRow {
+    fn get_field_str(&self, field: &str) -> Option<String> {
+        match field {
+            "task_id" => Some(self.task_id.clone()),
+            "task_type" => Some(self.task_type.clone()),
+            "delay_target_time_ms" => Some(self.delay_target_time_ms.to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Recurring delay tasks scheduled on this node.
+pub async fn recurring_delay_task_list(
+    State(state): State<Arc<HttpState>>,
+    Query(params): Query<RecurringDelayTaskListReq>,
+) -> String {
+    let options = build_query_params(
+        params.page,
+        params.limit,
+        params.sort_field,
+        params.sort_by,
+        params.filter_field,
+        params.filter_values,
+        params.exact_match,
+    );
+
+    let tasks: Vec<RecurringDelayTaskRow> = state
+        .delay_task_manager
+        .list_recurring_tasks()
+        .into_iter()
+        .filter(|task| {
+            params
+                .task_type
+                .as_deref()
+                .is_none_or(|t| task.task_type_name() == t)
+        })
+        .map(|task| RecurringDelayTaskRow {
+            task_type: task.task_type_name().to_string(),
+            task_id: task.task_id,
+            schedule: task.schedule,
+            next_run_time: task.delay_target_time_ms / 1000,
+            persistent: task.persistent,
+            create_time: task.create_time_ms / 1000,
+        })
+        .collect();
+
+    let filtered = apply_filters(tasks, &options);
+    let sorted = apply_sorting(filtered, &options);
+    let pagination = apply_pagination(sorted, &options);
+
+    success_response(PageReplyData {
+        data: pagination.0,
+        total_count: pagination.1,
+    })
+}
+
+/// Stop a recurring delay task. A run already in progress completes but is
+/// not rescheduled.
+pub async fn recurring_delay_task_cancel(
+    State(state): State<Arc<HttpState>>,
+    ValidatedJson(params): ValidatedJson<CancelRecurringDelayTaskReq>,
+) -> String {
+    match state
+        .delay_task_manager
+        .cancel_recurring_task(&params.task_id)
+        .await
+    {
+        Ok(true) => success_response("success"),
+        Ok(false) => error_response(format!("Recurring delay task {} not found", params.task_id)),
+        Err(e) => error_response(e.to_string()),
+    }
+}
+
+/// Number of pending delay tasks in each shard of this node.
+pub async fn delay_task_shard_list(State(state): State<Arc<HttpState>>) -> String {
+    success_response(state.delay_task_manager.describe_shards())
+}
+
+/// Pending delay tasks of one shard of this node, soonest first.
+pub async fn delay_task_shard_task_list(
+    State(state): State<Arc<HttpState>>,
+    Query(params): Query<DelayTaskShardTaskListReq>,
+) -> String {
+    let Some(tasks) = state.delay_task_manager.list_shard_tasks(params.shard_no) else {
+        return error_response(format!("Delay task shard {} not found", params.shard_no));
+    };
+
+    let options = build_query_params(
+        params.page,
+        params.limit,
+        params.sort_field,
+        params.sort_by,
+        params.filter_field,
+        params.filter_values,
+        params.exact_match,
+    );
+
+    let rows: Vec<PendingDelayTaskRow> = tasks
+        .into_iter()
+        .map(|task| PendingDelayTaskRow {
+            task_id: task.task_id,
+            task_type: task.task_type,
+            shard_no: task.shard_no,
+            delay_target_time_ms: task.delay_target_time_ms,
+            persistent: task.persistent,
+        })
+        .collect();
+
+    let filtered = apply_filters(rows, &options);
+    let sorted = apply_sorting(filtered, &options);
+    let pagination = apply_pagination(sorted, &options);
+
+    success_response(PageReplyData {
+        data: pagination.0,
+        total_count: pagination.1,
+    })
+}
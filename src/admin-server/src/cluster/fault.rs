@@ -0,0 +1,72 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{state::HttpState, tool::extractor::ValidatedJson};
+use axum::extract::State;
+use axum::Json;
+use common_base::fault::{fault_injector, FaultRule, FaultRuleState};
+use common_base::http_response::{error_response, success_response};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use validator::Validate;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FaultListResp {
+    pub enabled: bool,
+    pub faults: Vec<FaultRuleState>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default, Validate)]
+pub struct FaultRemoveReq {
+    /// Empty removes every fault.
+    #[serde(default)]
+    pub id: String,
+}
+
+/// Faults injected on this node. Faults are per node: add them on every node
+/// that should misbehave.
+pub async fn fault_list(State(_state): State<Arc<HttpState>>) -> String {
+    success_response(FaultListResp {
+        enabled: fault_injector().is_enabled(),
+        faults: fault_injector().list(),
+    })
+}
+
+/// Add or replace a fault on this node. Rejected unless the node enables
+/// `[fault_injection]`.
+pub async fn fault_set(
+    State(_state): State<Arc<HttpState>>,
+    Json(params): Json<FaultRule>,
+) -> String {
+    let id = params.id.clone();
+    match fault_injector().add_rule(params) {
+        Ok(()) => success_response(format!("Fault {} set.", id)),
+        Err(e) => error_response(e),
+    }
+}
+
+pub async fn fault_remove(
+    State(_state): State<Arc<HttpState>>,
+    ValidatedJson(params): ValidatedJson<FaultRemoveReq>,
+) -> String {
+    if params.id.is_empty() {
+        fault_injector().clear();
+        return success_response("All faults removed.");
+    }
+    if fault_injector().remove_rule(&params.id) {
+        success_response(format!("Fault {} removed.", params.id))
+    } else {
+        error_response(format!("Fault {} does not exist.", params.id))
+    }
+}
@@ -12,10 +12,24 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use axum::http::StatusCode;
-use common_base::http_response::success_response;
-use common_healthy::ready::healthy_ready_check;
+use crate::state::HttpState;
+use axum::{extract::State, http::StatusCode, Json};
+use broker_core::cluster::ClusterStorage;
+use common_base::{http_response::success_response, node_status::NodeStatus, role::is_broker_node};
+use common_config::broker::broker_config;
+use common_healthy::{
+    probe::{port_checks, CheckResult, ProbeReport},
+    ready::healthy_ready_check,
+};
 use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+use storage_adapter::storage::StorageAdapter;
+use tokio::time::timeout;
+
+// Upper bound for each remote dependency check so a hung peer cannot stall a probe
+// past the kubelet's own timeout.
+const PROBE_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+const PROBE_SHARD_NAME: &str = "__robustmq_health_probe__";
 
 #[derive(Serialize, Deserialize, Debug)]
 struct HealthCheckResp {
@@ -61,3 +75,110 @@ pub async fn health_node() -> String {
 pub async fn health_cluster() -> String {
     build_placeholder_resp("cluster")
 }
+
+pub async fn livez(State(state): State<Arc<HttpState>>) -> (StatusCode, Json<ProbeReport>) {
+    probe_response(ProbeReport::new(liveness_checks(&state).await))
+}
+
+pub async fn readyz(State(state): State<Arc<HttpState>>) -> (StatusCode, Json<ProbeReport>) {
+    probe_response(ProbeReport::new(readiness_checks(&state).await))
+}
+
+pub async fn healthz(State(state): State<Arc<HttpState>>) -> (StatusCode, Json<ProbeReport>) {
+    let mut checks = liveness_checks(&state).await;
+    checks.extend(readiness_checks(&state).await);
+    probe_response(ProbeReport::new(checks))
+}
+
+fn probe_response(report: ProbeReport) -> (StatusCode, Json<ProbeReport>) {
+    let code = if report.is_pass() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (code, Json(report))
+}
+
+// Liveness only asks whether the process can still serve requests; a node that is
+// starting or draining is alive and must not be restarted by the kubelet.
+async fn liveness_checks(state: &Arc<HttpState>) -> Vec<CheckResult> {
+    let status = state.broker_cache.get_status().await;
+    vec![CheckResult::pass(
+        "process",
+        format!("node status {status:?}"),
+    )]
+}
+
+async fn readiness_checks(state: &Arc<HttpState>) -> Vec<CheckResult> {
+    let config = broker_config();
+    let mut checks = port_checks(config);
+    checks.push(node_status_check(state).await);
+    checks.push(cache_bootstrap_check(state));
+    checks.push(meta_raft_check(state).await);
+    if is_broker_node(&config.roles) {
+        checks.push(storage_adapter_check(state).await);
+    }
+    checks
+}
+
+async fn node_status_check(state: &Arc<HttpState>) -> CheckResult {
+    match state.broker_cache.get_status().await {
+        NodeStatus::Running => CheckResult::pass("node_status", "node is running"),
+        status => CheckResult::fail("node_status", format!("node status {status:?}")),
+    }
+}
+
+fn cache_bootstrap_check(state: &Arc<HttpState>) -> CheckResult {
+    if state.broker_cache.is_cache_loaded() {
+        CheckResult::pass("cache_bootstrap", "metadata cache loaded from meta service")
+    } else {
+        CheckResult::fail("cache_bootstrap", "metadata cache bootstrap not complete")
+    }
+}
+
+async fn meta_raft_check(state: &Arc<HttpState>) -> CheckResult {
+    let storage = ClusterStorage::new(state.client_pool.clone());
+    match timeout(PROBE_CHECK_TIMEOUT, storage.raft_ping()).await {
+        Ok(Ok(())) => CheckResult::pass("meta_raft", "meta service raft accepted a write"),
+        Ok(Err(e)) => CheckResult::fail("meta_raft", e.to_string()),
+        Err(_) => CheckResult::fail(
+            "meta_raft",
+            format!("raft ping timed out after {PROBE_CHECK_TIMEOUT:?}"),
+        ),
+    }
+}
+
+async fn storage_adapter_check(state: &Arc<HttpState>) -> CheckResult {
+    let drivers: Vec<_> = state
+        .storage_driver_manager
+        .driver_list
+        .iter()
+        .map(|entry| (entry.key().clone(), entry.value().clone()))
+        .collect();
+    if drivers.is_empty() {
+        return CheckResult::pass("storage_adapter", "no storage adapter in use");
+    }
+
+    for (storage_type, driver) in drivers.iter() {
+        let probe = driver.list_shard(Some(PROBE_SHARD_NAME.to_string()));
+        match timeout(PROBE_CHECK_TIMEOUT, probe).await {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => {
+                return CheckResult::fail(
+                    "storage_adapter",
+                    format!("{storage_type} adapter unreachable: {e}"),
+                )
+            }
+            Err(_) => {
+                return CheckResult::fail(
+                    "storage_adapter",
+                    format!("{storage_type} adapter timed out after {PROBE_CHECK_TIMEOUT:?}"),
+                )
+            }
+        }
+    }
+    CheckResult::pass(
+        "storage_adapter",
+        format!("{} storage adapter(s) reachable", drivers.len()),
+    )
+}
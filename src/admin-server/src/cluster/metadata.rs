@@ -0,0 +1,138 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::state::HttpState;
+use axum::extract::State;
+use axum::Json;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use broker_core::cluster::ClusterStorage;
+use common_base::http_response::{error_response, success_response};
+use protocol::meta::meta_service_common::{ImportMetadataRequest, MetadataDumpResource};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// A metadata dump as written to disk. Records keep the meta-service storage
+/// encoding and are base64 encoded, so a dump can only be restored into a
+/// cluster whose meta version is at least `meta_version`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct MetadataDump {
+    pub format_version: u32,
+    pub meta_version: String,
+    pub export_time: u64,
+    pub resources: Vec<MetadataDumpResourceRaw>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct MetadataDumpResourceRaw {
+    pub resource_type: String,
+    pub records: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct MetadataExportReq {
+    /// Resource types to dump, e.g. `topic`, `user`. Empty dumps all of them.
+    #[serde(default)]
+    pub resource_types: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct MetadataImportReq {
+    /// Only validate the dump and count what would be imported.
+    #[serde(default)]
+    pub dry_run: bool,
+    pub dump: MetadataDump,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MetadataImportResultRaw {
+    pub resource_type: String,
+    pub imported: u64,
+    pub skipped: u64,
+}
+
+pub async fn metadata_export(
+    State(state): State<Arc<HttpState>>,
+    Json(params): Json<MetadataExportReq>,
+) -> String {
+    let storage = ClusterStorage::new(state.client_pool.clone());
+    match storage.export_metadata(params.resource_types).await {
+        Ok(reply) => success_response(MetadataDump {
+            format_version: reply.format_version,
+            meta_version: reply.meta_version,
+            export_time: reply.export_time,
+            resources: reply
+                .resources
+                .into_iter()
+                .map(|resource| MetadataDumpResourceRaw {
+                    resource_type: resource.resource_type,
+                    records: resource
+                        .records
+                        .iter()
+                        .map(|record| STANDARD.encode(record))
+                        .collect(),
+                })
+                .collect(),
+        }),
+        Err(e) => error_response(e.to_string()),
+    }
+}
+
+/// Restore a dump into this cluster. The meta service checks version
+/// compatibility and referential integrity before anything is written, and
+/// skips records that already exist.
+pub async fn metadata_import(
+    State(state): State<Arc<HttpState>>,
+    Json(params): Json<MetadataImportReq>,
+) -> String {
+    let mut resources = Vec::with_capacity(params.dump.resources.len());
+    for resource in params.dump.resources {
+        let mut records = Vec::with_capacity(resource.records.len());
+        for record in resource.records {
+            match STANDARD.decode(&record) {
+                Ok(data) => records.push(data),
+                Err(e) => {
+                    return error_response(format!(
+                        "Invalid {} record in dump: {}",
+                        resource.resource_type, e
+                    ))
+                }
+            }
+        }
+        resources.push(MetadataDumpResource {
+            resource_type: resource.resource_type,
+            records,
+        });
+    }
+
+    let request = ImportMetadataRequest {
+        format_version: params.dump.format_version,
+        meta_version: params.dump.meta_version,
+        resources,
+        dry_run: params.dry_run,
+    };
+    let storage = ClusterStorage::new(state.client_pool.clone());
+    match storage.import_metadata(request).await {
+        Ok(results) => success_response(
+            results
+                .into_iter()
+                .map(|result| MetadataImportResultRaw {
+                    resource_type: result.resource_type,
+                    imported: result.imported,
+                    skipped: result.skipped,
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Err(e) => error_response(e.to_string()),
+    }
+}
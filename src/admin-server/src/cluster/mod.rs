@@ -24,11 +24,16 @@
 use serde::{Deserialize, Serialize};
 
 pub mod acl;
+pub mod backup;
 pub mod blacklist;
 pub mod config;
 pub mod connector;
+pub mod consumer_group;
+pub mod delay_task;
+pub mod fault;
 pub mod health;
 pub mod message;
+pub mod metadata;
 pub mod node;
 pub mod offset;
 pub mod schema;
@@ -20,6 +20,50 @@
 use std::sync::Arc;
 use validator::Validate;
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Validate)]
+pub struct CordonNodeReq {
+    #[validate(range(min = 1, message = "node_id must be >= 1"))]
+    pub node_id: u64,
+    #[serde(default)]
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Validate)]
+pub struct UncordonNodeReq {
+    #[validate(range(min = 1, message = "node_id must be >= 1"))]
+    pub node_id: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Validate)]
+pub struct TransferLeaderReq {
+    #[validate(range(min = 1, message = "node_id must be >= 1"))]
+    pub node_id: u64,
+    #[validate(range(min = 1, message = "target_node_id must be >= 1"))]
+    pub target_node_id: u64,
+    /// Raft shard to hand over, e.g. `data_3`. Empty hands over every shard
+    /// led by `node_id`.
+    #[serde(default)]
+    pub shard: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TransferLeaderResp {
+    pub shard: String,
+    pub previous_leader: u64,
+    pub current_leader: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NodeStatusResp {
+    pub node_id: u64,
+    pub node_ip: String,
+    pub version: String,
+    pub heartbeat_time: u64,
+    pub cordoned: bool,
+    pub cordon_reason: String,
+    pub cordon_time: u64,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Validate)]
 pub struct LeaveClusterNodeReq {
     #[validate(range(min = 1, message = "node_id must be >= 1"))]
@@ -58,3 +102,78 @@ pub async fn node_leave(
         Err(e) => error_response(e.to_string()),
     }
 }
+
+/// Cordon a node before upgrading it: meta stops placing connectors on it and
+/// stops moving segment leadership to it until it is uncordoned.
+pub async fn node_cordon(
+    State(state): State<Arc<HttpState>>,
+    ValidatedJson(params): ValidatedJson<CordonNodeReq>,
+) -> String {
+    let storage = ClusterStorage::new(state.client_pool.clone());
+    match storage.cordon_node(params.node_id, params.reason).await {
+        Ok(_) => success_response(format!("Node {} cordoned.", params.node_id)),
+        Err(e) => error_response(e.to_string()),
+    }
+}
+
+pub async fn node_uncordon(
+    State(state): State<Arc<HttpState>>,
+    ValidatedJson(params): ValidatedJson<UncordonNodeReq>,
+) -> String {
+    let storage = ClusterStorage::new(state.client_pool.clone());
+    match storage.uncordon_node(params.node_id).await {
+        Ok(_) => success_response(format!("Node {} uncordoned.", params.node_id)),
+        Err(e) => error_response(e.to_string()),
+    }
+}
+
+/// Version and cordon state of every node, to follow a rolling upgrade.
+pub async fn node_status(State(state): State<Arc<HttpState>>) -> String {
+    let storage = ClusterStorage::new(state.client_pool.clone());
+    match storage.list_node_status().await {
+        Ok(nodes) => success_response(
+            nodes
+                .into_iter()
+                .map(|node| NodeStatusResp {
+                    node_id: node.node_id,
+                    node_ip: node.node_ip,
+                    version: node.version,
+                    heartbeat_time: node.heartbeat_time,
+                    cordoned: node.cordoned,
+                    cordon_reason: node.cordon_reason,
+                    cordon_time: node.cordon_time,
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Err(e) => error_response(e.to_string()),
+    }
+}
+
+/// Hand the raft shard leadership of a meta node over to another meta node, to
+/// drain it before maintenance. Shards the node does not lead are left alone.
+pub async fn node_transfer_leader(
+    State(state): State<Arc<HttpState>>,
+    ValidatedJson(params): ValidatedJson<TransferLeaderReq>,
+) -> String {
+    if params.node_id == params.target_node_id {
+        return error_response("node_id and target_node_id must differ".to_string());
+    }
+
+    let storage = ClusterStorage::new(state.client_pool.clone());
+    match storage
+        .transfer_leader(params.node_id, params.shard, params.target_node_id)
+        .await
+    {
+        Ok(results) => success_response(
+            results
+                .into_iter()
+                .map(|result| TransferLeaderResp {
+                    shard: result.machine,
+                    previous_leader: result.previous_leader,
+                    current_leader: result.current_leader,
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Err(e) => error_response(e.to_string()),
+    }
+}
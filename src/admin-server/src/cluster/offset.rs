@@ -16,6 +16,7 @@
 use axum::{extract::State, Json};
 use common_base::http_response::{error_response, success_response};
 use metadata_struct::adapter::adapter_offset::{AdapterConsumerGroupOffset, AdapterOffsetStrategy};
+use metadata_struct::adapter::adapter_shard::AdapterShardWatermarks;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -43,6 +44,17 @@ pub struct GetOffsetByGroupResp {
     pub offsets: Vec<AdapterConsumerGroupOffset>,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetShardWatermarksReq {
+    pub tenant: String,
+    pub topic_name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GetShardWatermarksResp {
+    pub watermarks: Vec<AdapterShardWatermarks>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CommitOffsetReq {
     pub tenant: String,
@@ -107,6 +119,28 @@ pub async fn get_offset_by_group(
     success_response(GetOffsetByGroupResp { offsets })
 }
 
+pub async fn get_shard_watermarks(
+    State(state): State<Arc<HttpState>>,
+    Json(params): Json<GetShardWatermarksReq>,
+) -> String {
+    if params.topic_name.is_empty() {
+        return error_response("topic_name cannot be empty".to_string());
+    }
+
+    let watermarks = match state
+        .storage_driver_manager
+        .shard_watermarks(&params.tenant, &params.topic_name)
+        .await
+    {
+        Ok(data) => data,
+        Err(e) => {
+            return error_response(e.to_string());
+        }
+    };
+
+    success_response(GetShardWatermarksResp { watermarks })
+}
+
 pub async fn commit_offset(
     State(state): State<Arc<HttpState>>,
     Json(params): Json<CommitOffsetReq>,
@@ -35,6 +35,8 @@ pub struct TenantConfigReq {
     pub max_topics: Option<u64>,
     pub max_sessions: Option<u64>,
     pub max_publish_rate: Option<u32>,
+    pub max_subscriptions: Option<u64>,
+    pub max_storage_bytes_per_node: Option<u64>,
 }
 
 impl TenantConfigReq {
@@ -50,6 +52,10 @@ pub fn into_tenant_config(self) -> TenantConfig {
             max_topics: self.max_topics.unwrap_or(defaults.max_topics),
             max_sessions: self.max_sessions.unwrap_or(defaults.max_sessions),
             max_publish_rate: self.max_publish_rate.unwrap_or(defaults.max_publish_rate),
+            max_subscriptions: self.max_subscriptions.unwrap_or(defaults.max_subscriptions),
+            max_storage_bytes_per_node: self
+                .max_storage_bytes_per_node
+                .unwrap_or(defaults.max_storage_bytes_per_node),
         }
     }
 }
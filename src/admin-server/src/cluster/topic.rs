@@ -25,15 +25,16 @@
 };
 use axum::extract::{Query, State};
 use broker_core::topic::TopicStorage;
-use common_base::error::common::CommonError;
 use common_base::http_response::{error_response, success_response};
 use common_config::broker::broker_config;
 use common_config::storage::StorageType;
 use metadata_struct::adapter::adapter_shard::AdapterShardDetail;
 use metadata_struct::mqtt::{retain_message::MQTTRetainMessage, topic::Topic};
 use metadata_struct::topic::{Topic as MetaTopic, TopicSource};
+use mqtt_broker::storage::topic_stats::TopicStatsStorage;
 use mqtt_broker::subscribe::manager::TopicSubscribeInfo;
 use mqtt_broker::{core::error::MqttBrokerError, storage::retain::RetainStorage};
+use protocol::meta::meta_service_mqtt::DescribeTopicReply;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet},
@@ -41,6 +42,7 @@
     sync::Arc,
 };
 use storage_adapter::topic::create_topic_full;
+use tracing::warn;
 use validator::Validate;
 
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -109,6 +111,56 @@ pub struct TopicDetailResp {
     pub retain_message: Option<MQTTRetainMessage>,
     pub sub_list: HashSet<TopicSubscribeInfo>,
     pub storage_list: HashMap<u32, AdapterShardDetail>,
+    /// Cluster-wide statistics, `None` when meta-service could not be asked.
+    pub stats: Option<TopicStatsResp>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct TopicStatsResp {
+    pub message_count: u64,
+    pub byte_count: u64,
+    pub last_publish_time: u64,
+    pub retained: bool,
+    pub subscriber_count: u64,
+    pub nodes: Vec<TopicNodeStatsResp>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Default, Debug, PartialEq)]
+pub struct TopicNodeStatsResp {
+    pub broker_id: u64,
+    pub report_time: u64,
+    pub message_count: u64,
+    pub byte_count: u64,
+    pub last_publish_time: u64,
+    pub subscriber_count: u64,
+}
+
+impl From<DescribeTopicReply> for TopicStatsResp {
+    fn from(reply: DescribeTopicReply) -> Self {
+        let total = reply.stats.unwrap_or_default();
+        TopicStatsResp {
+            message_count: total.message_count,
+            byte_count: total.byte_count,
+            last_publish_time: total.last_publish_time,
+            retained: total.retained,
+            subscriber_count: total.subscriber_count,
+            nodes: reply
+                .nodes
+                .into_iter()
+                .map(|node| {
+                    let stats = node.stats.unwrap_or_default();
+                    TopicNodeStatsResp {
+                        broker_id: node.broker_id,
+                        report_time: node.report_time,
+                        message_count: stats.message_count,
+                        byte_count: stats.byte_count,
+                        last_publish_time: stats.last_publish_time,
+                        subscriber_count: stats.subscriber_count,
+                    }
+                })
+                .collect(),
+        }
+    }
 }
 
 pub async fn topic_list(
@@ -235,11 +287,26 @@ async fn read_topic_detail(
         .get_retain_message(&topic.tenant, &topic.topic_name)
         .await?;
 
+    let stats = match TopicStatsStorage::new(state.client_pool.clone())
+        .describe_topic(&topic.tenant, &topic.topic_name)
+        .await
+    {
+        Ok(reply) => Some(TopicStatsResp::from(reply)),
+        Err(e) => {
+            warn!(
+                "Failed to describe topic statistics for '{}': {}",
+                topic.topic_name, e
+            );
+            None
+        }
+    };
+
     Ok(TopicDetailResp {
         topic_info: topic,
         retain_message,
         sub_list,
         storage_list,
+        stats,
     })
 }
 
@@ -287,19 +354,17 @@ pub async fn topic_create(
         .with_partition(partition)
         .with_replication(replication);
 
-    if let Err(e) = create_topic_full(
+    match create_topic_full(
         &state.broker_cache,
         &state.storage_driver_manager,
         &state.client_pool,
         &topic,
     )
     .await
-    .map_err(|e: CommonError| e.to_string())
     {
-        return error_response(e);
+        Ok(topic) => success_response(topic),
+        Err(e) => error_response(e.to_string()),
     }
-
-    success_response(topic)
 }
 
 pub async fn topic_delete(
@@ -315,3 +380,43 @@ pub async fn topic_delete(
     }
     success_response("success")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protocol::meta::meta_service_mqtt::{TopicNodeStats, TopicStatsRaw};
+
+    #[test]
+    fn test_topic_stats_resp_from_reply() {
+        let raw = TopicStatsRaw {
+            tenant: "default".to_string(),
+            topic_name: "a".to_string(),
+            message_count: 3,
+            byte_count: 30,
+            last_publish_time: 1000,
+            retained: true,
+            retained_update_time: 900,
+            subscriber_count: 2,
+        };
+        let reply = DescribeTopicReply {
+            stats: Some(raw.clone()),
+            nodes: vec![TopicNodeStats {
+                broker_id: 1,
+                report_time: 1100,
+                stats: Some(raw),
+            }],
+        };
+
+        let resp = TopicStatsResp::from(reply);
+        assert_eq!(resp.message_count, 3);
+        assert_eq!(resp.byte_count, 30);
+        assert!(resp.retained);
+        assert_eq!(resp.subscriber_count, 2);
+        assert_eq!(resp.nodes.len(), 1);
+        assert_eq!(resp.nodes[0].broker_id, 1);
+        assert_eq!(resp.nodes[0].report_time, 1100);
+
+        let empty = TopicStatsResp::from(DescribeTopicReply::default());
+        assert_eq!(empty, TopicStatsResp::default());
+    }
+}
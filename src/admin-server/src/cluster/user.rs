@@ -48,6 +48,16 @@ pub struct CreateUserReq {
     #[validate(length(min = 1, max = 128, message = "Password length must be between 1-128"))]
     pub password: String,
 
+    /// When set, `password` is the hex SHA-256 of `salt + password` rather than plaintext.
+    #[serde(default)]
+    pub salt: Option<String>,
+
+    /// When set, `password` is an existing hash of this algorithm and is stored
+    /// as given. Plaintext passwords are checked against the password policy
+    /// and hashed with its algorithm.
+    #[serde(default)]
+    pub hash_algorithm: Option<PasswordHashAlgorithm>,
+
     pub is_superuser: bool,
 }
 
@@ -65,6 +75,8 @@ pub struct UserListRow {
     pub tenant: String,
     pub username: String,
     pub is_superuser: bool,
+    #[serde(default)]
+    pub hash_algorithm: PasswordHashAlgorithm,
     pub create_time: u64,
 }
 
@@ -72,6 +84,8 @@ pub struct UserListRow {
     http_response::{error_response, success_response},
     tools::now_second,
 };
+use common_config::config::PasswordHashAlgorithm;
+use common_security::login::password::{check_password_policy, hash_password};
 use common_security::storage::user::UserStorage;
 use metadata_struct::auth::user::SecurityUser;
 use std::sync::Arc;
@@ -113,6 +127,7 @@ pub async fn user_list(
                 tenant: tenant_entry.key().clone(),
                 username: ele.value().username.clone(),
                 is_superuser: ele.value().is_superuser,
+                hash_algorithm: ele.value().hash_algorithm(),
                 create_time: ele.value().create_time,
             };
             users.push(user_raw);
@@ -143,13 +158,28 @@ pub async fn user_create(
     State(state): State<Arc<HttpState>>,
     ValidatedJson(params): ValidatedJson<CreateUserReq>,
 ) -> String {
+    let salt = params.salt.clone().filter(|salt| !salt.is_empty());
+    let (password, salt, hash_algorithm) = if salt.is_some() || params.hash_algorithm.is_some() {
+        (params.password.clone(), salt, params.hash_algorithm)
+    } else {
+        let policy = state.broker_cache.get_cluster_config().password_policy;
+        if let Err(e) = check_password_policy(&policy, &params.password) {
+            return error_response(e);
+        }
+        match hash_password(&policy, &params.password).await {
+            Ok((hash, salt)) => (hash, salt, Some(policy.hash_algorithm)),
+            Err(e) => return error_response(e.to_string()),
+        }
+    };
+
     let user_info = SecurityUser {
         tenant: params.tenant.clone(),
         username: params.username.clone(),
-        password: params.password.clone(),
-        salt: None,
+        password,
+        salt,
         is_superuser: params.is_superuser,
         create_time: now_second(),
+        hash_algorithm,
     };
 
     let user_storage = UserStorage::new(state.client_pool.clone());
@@ -0,0 +1,299 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    state::HttpState,
+    tool::{
+        extractor::ValidatedJson,
+        query::{apply_pagination, apply_sorting, build_query_params, Queryable},
+        PageReplyData,
+    },
+};
+use axum::extract::{Query, State};
+use common_base::{
+    http_response::{error_response, success_response},
+    utils::time_util::timestamp_to_local_datetime,
+};
+use mqtt_broker::core::error::MqttBrokerError;
+use mqtt_broker::core::message_replay::{
+    start_message_replay, MessageReplayContext, MessageReplayProgress, MessageReplayRange,
+    MessageReplayTarget, MessageReplayTask,
+};
+use protocol::mqtt::common::qos;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use validator::Validate;
+
+#[derive(Debug, Serialize, Deserialize, Default, Validate)]
+pub struct MessageReplayCreateReq {
+    #[validate(length(min = 1, max = 256, message = "Tenant length must be between 1-256"))]
+    pub tenant: String,
+
+    #[validate(length(min = 1, max = 256, message = "Topic length must be between 1-256"))]
+    pub topic_name: String,
+
+    /// Replay one shard of the topic, or all of them when unset.
+    #[serde(default)]
+    pub shard_name: Option<String>,
+
+    #[serde(default)]
+    pub start_offset: Option<u64>,
+    /// Exclusive.
+    #[serde(default)]
+    pub end_offset: Option<u64>,
+    /// Seconds, inclusive.
+    #[serde(default)]
+    pub start_time: Option<u64>,
+    /// Seconds, inclusive.
+    #[serde(default)]
+    pub end_time: Option<u64>,
+
+    /// Exactly one of `target_topic` and `target_client_id` must be set.
+    #[serde(default)]
+    pub target_topic: Option<String>,
+    #[serde(default)]
+    pub target_client_id: Option<String>,
+
+    /// QoS of messages pushed to `target_client_id`.
+    #[serde(default)]
+    pub qos: u8,
+
+    /// Messages per second, 0 for no limit.
+    #[serde(default)]
+    pub rate_limit: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageReplayCreateResp {
+    pub replay_id: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Validate)]
+pub struct MessageReplayCancelReq {
+    pub replay_id: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct MessageReplayListReq {
+    pub tenant: Option<String>,
+    pub status: Option<String>,
+    pub limit: Option<u32>,
+    pub page: Option<u32>,
+    pub sort_field: Option<String>,
+    pub sort_by: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MessageReplayListRow {
+    pub replay_id: u64,
+    pub tenant: String,
+    pub topic_name: String,
+    pub shard_name: Option<String>,
+    /// `topic:<name>` or `client:<client id>`.
+    pub target: String,
+    pub status: String,
+    pub replayed: u64,
+    pub skipped: u64,
+    pub current_shard: String,
+    pub current_offset: u64,
+    pub rate_limit: u32,
+    pub error: Option<String>,
+    pub create_time: String,
+    pub finish_time: Option<String>,
+}
+
+impl From<MessageReplayProgress> for MessageReplayListRow {
+    fn from(progress: MessageReplayProgress) -> Self {
+        MessageReplayListRow {
+            replay_id: progress.id,
+            tenant: progress.task.tenant,
+            topic_name: progress.task.topic_name,
+            shard_name: progress.task.shard_name,
+            target: match progress.task.target {
+                MessageReplayTarget::Topic(topic) => format!("topic:{}", topic),
+                MessageReplayTarget::Client(client_id) => format!("client:{}", client_id),
+            },
+            status: format!("{:?}", progress.status),
+            replayed: progress.replayed,
+            skipped: progress.skipped,
+            current_shard: progress.current_shard,
+            current_offset: progress.current_offset,
+            rate_limit: progress.task.rate_limit,
+            error: progress.error,
+            create_time: timestamp_to_local_datetime(progress.create_time as i64),
+            finish_time: (progress.finish_time > 0)
+                .then(|| timestamp_to_local_datetime(progress.finish_time as i64)),
+        }
+    }
+}
+
+/// Start replaying a range of a topic to another topic or to a connected
+/// client. The replay runs in the background on this node.
+pub async fn message_replay_create(
+    State(state): State<Arc<HttpState>>,
+    ValidatedJson(params): ValidatedJson<MessageReplayCreateReq>,
+) -> String {
+    let task = match build_replay_task(params) {
+        Ok(task) => task,
+        Err(e) => return error_response(e.to_string()),
+    };
+    let context = MessageReplayContext {
+        cache_manager: state.mqtt_context.cache_manager.clone(),
+        connection_manager: state.connection_manager.clone(),
+        storage_driver_manager: state.storage_driver_manager.clone(),
+        rocksdb_engine_handler: state.rocksdb_engine_handler.clone(),
+        client_pool: state.client_pool.clone(),
+    };
+    match start_message_replay(context, task).await {
+        Ok(replay_id) => success_response(MessageReplayCreateResp { replay_id }),
+        Err(e) => error_response(e.to_string()),
+    }
+}
+
+fn build_replay_task(params: MessageReplayCreateReq) -> Result<MessageReplayTask, MqttBrokerError> {
+    let target = match (params.target_topic, params.target_client_id) {
+        (Some(topic), None) if !topic.is_empty() => MessageReplayTarget::Topic(topic),
+        (None, Some(client_id)) if !client_id.is_empty() => MessageReplayTarget::Client(client_id),
+        _ => {
+            return Err(MqttBrokerError::CommonError(
+                "Exactly one of target_topic and target_client_id must be set".to_string(),
+            ))
+        }
+    };
+    let qos = qos(params.qos)
+        .ok_or_else(|| MqttBrokerError::CommonError(format!("Invalid qos {}", params.qos)))?;
+    Ok(MessageReplayTask {
+        tenant: params.tenant,
+        topic_name: params.topic_name,
+        shard_name: params.shard_name.filter(|shard| !shard.is_empty()),
+        range: MessageReplayRange {
+            start_offset: params.start_offset,
+            end_offset: params.end_offset,
+            start_time: params.start_time,
+            end_time: params.end_time,
+        },
+        target,
+        qos,
+        rate_limit: params.rate_limit,
+    })
+}
+
+/// Replays started on this node with their progress, oldest first.
+pub async fn message_replay_list(
+    State(state): State<Arc<HttpState>>,
+    Query(params): Query<MessageReplayListReq>,
+) -> String {
+    let options = build_query_params(
+        params.page,
+        params.limit,
+        params.sort_field,
+        params.sort_by,
+        None,
+        None,
+        None,
+    );
+
+    let results = state
+        .mqtt_context
+        .cache_manager
+        .message_replay
+        .list()
+        .into_iter()
+        .map(MessageReplayListRow::from)
+        .filter(|row| {
+            params
+                .tenant
+                .as_ref()
+                .map(|t| &row.tenant == t)
+                .unwrap_or(true)
+                && params
+                    .status
+                    .as_ref()
+                    .map(|s| row.status.eq_ignore_ascii_case(s))
+                    .unwrap_or(true)
+        })
+        .collect::<Vec<_>>();
+
+    let sorted = apply_sorting(results, &options);
+    let pagination = apply_pagination(sorted, &options);
+
+    success_response(PageReplyData {
+        data: pagination.0,
+        total_count: pagination.1,
+    })
+}
+
+/// Stop a running replay after the message in flight.
+pub async fn message_replay_cancel(
+    State(state): State<Arc<HttpState>>,
+    ValidatedJson(params): ValidatedJson<MessageReplayCancelReq>,
+) -> String {
+    match state
+        .mqtt_context
+        .cache_manager
+        .message_replay
+        .cancel(params.replay_id)
+    {
+        Ok(()) => success_response("success"),
+        Err(e) => error_response(e.to_string()),
+    }
+}
+
+impl Queryable for MessageReplayListRow {
+    fn get_field_str(&self, field: &str) -> Option<String> {
+        match field {
+            // zero padded so the string order matches the numeric order
+            "replay_id" => Some(format!("{:020}", self.replay_id)),
+            "replayed" => Some(format!("{:020}", self.replayed)),
+            "tenant" => Some(self.tenant.clone()),
+            "topic_name" => Some(self.topic_name.clone()),
+            "target" => Some(self.target.clone()),
+            "status" => Some(self.status.clone()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_replay_task() {
+        let req = MessageReplayCreateReq {
+            tenant: "default".to_string(),
+            topic_name: "t1".to_string(),
+            end_offset: Some(100),
+            target_client_id: Some("c1".to_string()),
+            qos: 1,
+            ..Default::default()
+        };
+        let task = build_replay_task(req).unwrap();
+        assert_eq!(task.target, MessageReplayTarget::Client("c1".to_string()));
+
+        let req = MessageReplayCreateReq {
+            target_topic: Some("t2".to_string()),
+            target_client_id: Some("c1".to_string()),
+            ..Default::default()
+        };
+        assert!(build_replay_task(req).is_err());
+
+        let req = MessageReplayCreateReq {
+            target_topic: Some("t2".to_string()),
+            qos: 3,
+            ..Default::default()
+        };
+        assert!(build_replay_task(req).is_err());
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    state::HttpState,
+    tool::extractor::ValidatedJson,
+    tool::{
+        query::{apply_pagination, apply_sorting, build_query_params, Queryable},
+        PageReplyData,
+    },
+};
+use axum::extract::{Query, State};
+use common_base::{
+    http_response::{error_response, success_response},
+    tools::now_second,
+};
+use common_metrics::mqtt::rule_engine::get_message_rule_matched;
+use metadata_struct::mqtt::message_rule::{MqttMessageRule, MqttMessageRuleAction};
+use mqtt_broker::core::message_rule::compile_message_rule;
+use mqtt_broker::storage::message_rule::MessageRuleStorage;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use validator::Validate;
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct MessageRuleListReq {
+    pub tenant: Option<String>,
+    pub name: Option<String>,
+    pub limit: Option<u32>,
+    pub page: Option<u32>,
+    pub sort_field: Option<String>,
+    pub sort_by: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Validate)]
+pub struct CreateMessageRuleReq {
+    #[validate(length(min = 1, max = 256, message = "Name length must be between 1-256"))]
+    pub name: String,
+
+    pub desc: Option<String>,
+
+    #[validate(length(min = 1, max = 128, message = "Tenant length must be between 1-128"))]
+    pub tenant: String,
+
+    #[validate(length(min = 1, max = 4096, message = "SQL length must be between 1-4096"))]
+    pub sql: String,
+
+    #[validate(length(min = 1, message = "At least one action is required"))]
+    pub actions: Vec<MqttMessageRuleAction>,
+
+    pub enable: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Validate)]
+pub struct DeleteMessageRuleReq {
+    #[validate(length(min = 1, max = 128, message = "Tenant length must be between 1-128"))]
+    pub tenant: String,
+
+    #[validate(length(min = 1, max = 256, message = "Name length must be between 1-256"))]
+    pub name: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MessageRuleListRow {
+    pub name: String,
+    pub desc: String,
+    pub tenant: String,
+    pub sql: String,
+    pub actions: Vec<MqttMessageRuleAction>,
+    pub enable: bool,
+    pub matched: u64,
+    pub create_time: u64,
+}
+
+impl Queryable for MessageRuleListRow {
+    fn get_field_str(&self, field: &str) -> Option<String> {
+        match field {
+            "name" => Some(self.name.clone()),
+            "tenant" => Some(self.tenant.clone()),
+            "matched" => Some(self.matched.to_string()),
+            "create_time" => Some(self.create_time.to_string()),
+            _ => None,
+        }
+    }
+}
+
+pub async fn message_rule_list(
+    State(state): State<Arc<HttpState>>,
+    Query(params): Query<MessageRuleListReq>,
+) -> String {
+    let filter_tenant = params.tenant;
+    let filter_name = params.name;
+    let options = build_query_params(
+        params.page,
+        params.limit,
+        params.sort_field,
+        params.sort_by,
+        None,
+        None,
+        None,
+    );
+
+    let mut rules = Vec::new();
+    for tenant_entry in state.mqtt_context.cache_manager.message_rule.iter() {
+        if filter_tenant
+            .as_deref()
+            .map(|t| !tenant_entry.key().contains(t))
+            .unwrap_or(false)
+        {
+            continue;
+        }
+        for rule_entry in tenant_entry.value().iter() {
+            let rule = &rule_entry.value().rule;
+            if filter_name
+                .as_deref()
+                .map(|n| !rule.name.contains(n))
+                .unwrap_or(false)
+            {
+                continue;
+            }
+            rules.push(MessageRuleListRow {
+                name: rule.name.clone(),
+                desc: rule.desc.clone(),
+                tenant: rule.tenant.clone(),
+                sql: rule.sql.clone(),
+                actions: rule.actions.clone(),
+                enable: rule.enable,
+                matched: get_message_rule_matched(&rule.tenant, &rule.name),
+                create_time: rule.create_time,
+            });
+        }
+    }
+
+    let sorted = apply_sorting(rules, &options);
+    let pagination = apply_pagination(sorted, &options);
+    success_response(PageReplyData {
+        data: pagination.0,
+        total_count: pagination.1,
+    })
+}
+
+pub async fn message_rule_create(
+    State(state): State<Arc<HttpState>>,
+    ValidatedJson(params): ValidatedJson<CreateMessageRuleReq>,
+) -> String {
+    let rule = MqttMessageRule {
+        tenant: params.tenant.clone(),
+        name: params.name.clone(),
+        desc: params.desc.clone().unwrap_or_default(),
+        sql: params.sql.clone(),
+        actions: params.actions.clone(),
+        enable: params.enable.unwrap_or(true),
+        create_time: now_second(),
+    };
+
+    // reject rules the broker would not be able to load
+    if let Err(e) = compile_message_rule(rule.clone()) {
+        return error_response(e.to_string());
+    }
+
+    let storage = MessageRuleStorage::new(state.client_pool.clone());
+    if let Err(e) = storage.create_message_rule(rule).await {
+        return error_response(e.to_string());
+    }
+
+    success_response("success")
+}
+
+pub async fn message_rule_delete(
+    State(state): State<Arc<HttpState>>,
+    ValidatedJson(params): ValidatedJson<DeleteMessageRuleReq>,
+) -> String {
+    let storage = MessageRuleStorage::new(state.client_pool.clone());
+    if let Err(e) = storage
+        .delete_message_rule(params.tenant.clone(), params.name.clone())
+        .await
+    {
+        return error_response(e.to_string());
+    }
+
+    success_response("success")
+}
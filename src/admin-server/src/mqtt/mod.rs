@@ -13,9 +13,12 @@
 // limitations under the License.
 
 pub mod client;
+pub mod message_replay;
+pub mod message_rule;
 pub mod monitor;
 pub mod overview;
 pub mod session;
 pub mod subscribe;
 pub mod system;
 pub mod topic_rewrite;
+pub mod trace;
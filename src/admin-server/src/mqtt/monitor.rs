@@ -24,8 +24,14 @@
 use serde::{Deserialize, Serialize};
 
 use crate::state::HttpState;
+use common_base::tools::now_second;
+use mqtt_broker::system_topic::metrics_history::{read_metrics_history, MetricsHistoryQuery};
 use rocksdb_engine::metrics::mqtt::MQTTMetricsCache;
 
+const DEFAULT_MONITOR_HISTORY_RANGE_SEC: u64 = 3600;
+const DEFAULT_MONITOR_HISTORY_LIMIT: usize = 1000;
+const MAX_MONITOR_HISTORY_LIMIT: usize = 10000;
+
 #[derive(Deserialize, Serialize, Default)]
 pub struct MonitorDataReq {
     pub data_type: String,
@@ -35,6 +41,15 @@ pub struct MonitorDataReq {
     pub connector_name: Option<String>,
 }
 
+/// Time range over the `$SYS` metrics history. Defaults to the last hour.
+#[derive(Deserialize, Serialize, Default)]
+pub struct MonitorHistoryReq {
+    pub start_time: Option<u64>,
+    pub end_time: Option<u64>,
+    pub node_id: Option<u64>,
+    pub limit: Option<usize>,
+}
+
 pub enum MonitorDataType {
     ConnectionNum,
     TopicNum,
@@ -111,6 +126,33 @@ pub async fn monitor_data(
     success_response(resp)
 }
 
+pub async fn monitor_history(
+    State(state): State<Arc<HttpState>>,
+    Query(params): Query<MonitorHistoryReq>,
+) -> String {
+    let query = build_history_query(params, now_second());
+    match read_metrics_history(&state.storage_driver_manager, &query).await {
+        Ok(points) => success_response(points),
+        Err(e) => error_response(e.to_string()),
+    }
+}
+
+fn build_history_query(params: MonitorHistoryReq, now: u64) -> MetricsHistoryQuery {
+    let end_time = params.end_time.unwrap_or(now);
+    let start_time = params
+        .start_time
+        .unwrap_or(end_time.saturating_sub(DEFAULT_MONITOR_HISTORY_RANGE_SEC));
+    MetricsHistoryQuery {
+        start_time,
+        end_time,
+        node_id: params.node_id,
+        limit: params
+            .limit
+            .unwrap_or(DEFAULT_MONITOR_HISTORY_LIMIT)
+            .min(MAX_MONITOR_HISTORY_LIMIT),
+    }
+}
+
 pub fn get_monitor_data(
     metrics_manager: &Arc<MQTTMetricsCache>,
     params: MonitorDataReq,
@@ -221,7 +263,6 @@ pub fn get_monitor_data(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use common_base::tools::now_second;
     use rocksdb_engine::test::test_rocksdb_instance;
 
     #[tokio::test]
@@ -247,4 +288,23 @@ async fn test_get_monitor_data() {
         assert_eq!(result.len(), 1);
         assert_eq!(*result.get(&time).unwrap(), 100);
     }
+
+    #[test]
+    fn test_build_history_query() {
+        let query = build_history_query(MonitorHistoryReq::default(), 10000);
+        assert_eq!(query.start_time, 10000 - DEFAULT_MONITOR_HISTORY_RANGE_SEC);
+        assert_eq!(query.end_time, 10000);
+        assert_eq!(query.limit, DEFAULT_MONITOR_HISTORY_LIMIT);
+
+        let params = MonitorHistoryReq {
+            start_time: Some(100),
+            end_time: Some(200),
+            node_id: Some(1),
+            limit: Some(MAX_MONITOR_HISTORY_LIMIT + 1),
+        };
+        let query = build_history_query(params, 10000);
+        assert_eq!((query.start_time, query.end_time), (100, 200));
+        assert_eq!(query.node_id, Some(1));
+        assert_eq!(query.limit, MAX_MONITOR_HISTORY_LIMIT);
+    }
 }
@@ -14,6 +14,7 @@
 
 use crate::{
     state::HttpState,
+    tool::extractor::ValidatedJson,
     tool::{
         query::{apply_pagination, apply_sorting, build_query_params, Queryable},
         PageReplyData,
@@ -22,6 +23,8 @@
 use axum::extract::State;
 use metadata_struct::mqtt::lastwill::MqttLastWillData;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use validator::Validate;
 
 const MAX_SAMPLE_SIZE: usize = 100;
 
@@ -48,12 +51,73 @@ pub struct SessionListRow {
     pub reconnect_time: Option<u64>,
     pub distinct_time: Option<u64>,
     pub last_will: Option<MqttLastWillData>,
+    pub attributes: BTreeMap<String, String>,
+    #[serde(default)]
+    pub assigned_client_id: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ClientAttributeReq {
+    pub tenant: String,
+    pub client_id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Validate)]
+pub struct SetClientAttributeReq {
+    #[validate(length(min = 1, max = 256, message = "Tenant length must be between 1-256"))]
+    pub tenant: String,
+
+    #[validate(length(min = 1, max = 256, message = "Client ID length must be between 1-256"))]
+    pub client_id: String,
+
+    #[validate(length(min = 1, message = "Attributes cannot be empty"))]
+    pub attributes: BTreeMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Validate)]
+pub struct DeleteClientAttributeReq {
+    #[validate(length(min = 1, max = 256, message = "Tenant length must be between 1-256"))]
+    pub tenant: String,
+
+    #[validate(length(min = 1, max = 256, message = "Client ID length must be between 1-256"))]
+    pub client_id: String,
+
+    #[validate(length(min = 1, message = "Keys cannot be empty"))]
+    pub keys: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct SessionExportReq {
+    /// Required with `client_id`; narrows a node export otherwise.
+    pub tenant: Option<String>,
+    /// Export a single client; every session of this node when omitted.
+    pub client_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SessionImportReq {
+    #[serde(default)]
+    pub overwrite: bool,
+    pub data: SessionExport,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ClientAttributeReply {
+    pub tenant: String,
+    pub client_id: String,
+    pub attributes: BTreeMap<String, String>,
 }
 
 use axum::extract::Query;
+use axum::Json;
 use common_base::http_response::{error_response, success_response};
 use metadata_struct::mqtt::session::MqttSession;
+use mqtt_broker::core::client_attribute::update_client_attributes;
+use mqtt_broker::core::session_migration::{
+    export_sessions, import_sessions, SessionExport, SessionMigrationContext,
+};
 use mqtt_broker::storage::last_will::LastWillStorage;
+use mqtt_broker::storage::session::SessionStorage;
 use std::sync::Arc;
 
 pub async fn session_list(
@@ -95,6 +159,8 @@ pub async fn session_list(
             reconnect_time: session.reconnect_time,
             distinct_time: session.distinct_time,
             last_will: None,
+            attributes: session.attributes.clone(),
+            assigned_client_id: session.assigned_client_id,
         })
         .collect();
 
@@ -118,6 +184,116 @@ pub async fn session_list(
     success_response(PageReplyData { data, total_count })
 }
 
+pub async fn client_attribute_get(
+    State(state): State<Arc<HttpState>>,
+    Query(params): Query<ClientAttributeReq>,
+) -> String {
+    let session_storage = SessionStorage::new(state.client_pool.clone());
+    match session_storage
+        .get_session(params.tenant.clone(), params.client_id.clone())
+        .await
+    {
+        Ok(Some(session)) => success_response(ClientAttributeReply {
+            tenant: params.tenant,
+            client_id: params.client_id,
+            attributes: session.attributes,
+        }),
+        Ok(None) => error_response(format!(
+            "Session for client {} does not exist",
+            params.client_id
+        )),
+        Err(e) => error_response(e.to_string()),
+    }
+}
+
+pub async fn client_attribute_set(
+    State(state): State<Arc<HttpState>>,
+    ValidatedJson(params): ValidatedJson<SetClientAttributeReq>,
+) -> String {
+    match update_client_attributes(
+        &state.client_pool,
+        &state.mqtt_context.cache_manager,
+        &params.tenant,
+        &params.client_id,
+        params.attributes,
+        &[],
+    )
+    .await
+    {
+        Ok(attributes) => success_response(ClientAttributeReply {
+            tenant: params.tenant,
+            client_id: params.client_id,
+            attributes,
+        }),
+        Err(e) => error_response(e.to_string()),
+    }
+}
+
+pub async fn client_attribute_delete(
+    State(state): State<Arc<HttpState>>,
+    ValidatedJson(params): ValidatedJson<DeleteClientAttributeReq>,
+) -> String {
+    match update_client_attributes(
+        &state.client_pool,
+        &state.mqtt_context.cache_manager,
+        &params.tenant,
+        &params.client_id,
+        BTreeMap::new(),
+        &params.keys,
+    )
+    .await
+    {
+        Ok(attributes) => success_response(ClientAttributeReply {
+            tenant: params.tenant,
+            client_id: params.client_id,
+            attributes,
+        }),
+        Err(e) => error_response(e.to_string()),
+    }
+}
+
+pub async fn session_export(
+    State(state): State<Arc<HttpState>>,
+    Json(params): Json<SessionExportReq>,
+) -> String {
+    match export_sessions(
+        &session_migration_context(&state),
+        params.tenant.as_deref(),
+        params.client_id.as_deref(),
+    )
+    .await
+    {
+        Ok(export) => success_response(export),
+        Err(e) => error_response(e.to_string()),
+    }
+}
+
+pub async fn session_import(
+    State(state): State<Arc<HttpState>>,
+    Json(params): Json<SessionImportReq>,
+) -> String {
+    match import_sessions(
+        &session_migration_context(&state),
+        params.data,
+        params.overwrite,
+    )
+    .await
+    {
+        Ok(results) => success_response(results),
+        Err(e) => error_response(e.to_string()),
+    }
+}
+
+fn session_migration_context(state: &HttpState) -> SessionMigrationContext {
+    SessionMigrationContext {
+        cache_manager: state.mqtt_context.cache_manager.clone(),
+        subscribe_manager: state.mqtt_context.subscribe_manager.clone(),
+        client_pool: state.client_pool.clone(),
+        storage_driver_manager: state.mqtt_context.storage_driver_manager.clone(),
+        rocksdb_engine_handler: state.rocksdb_engine_handler.clone(),
+    }
+}
+
 /// Collects up to MAX_SAMPLE_SIZE (100) sessions from the cache, optionally filtered by
 /// tenant and client_id prefix. When tenant is specified, uses the index for O(1) lookup.
 fn sample_sessions_up_to_100(
@@ -22,8 +22,14 @@
 };
 use axum::extract::{Query, State};
 use mqtt_broker::{
-    core::sub_share::{decode_share_info, get_share_sub_leader, is_mqtt_share_subscribe},
-    subscribe::common::Subscriber,
+    core::{
+        consumer_lag::{group_shard_lags, ShardLag},
+        sub_share::{decode_share_info, get_share_sub_leader, is_mqtt_share_subscribe},
+    },
+    subscribe::{
+        buckets::SubPushThreadSnapshot, common::Subscriber, directly_push::directly_group_name,
+        share_thread_key, stats::SubscriptionStatsSnapshot,
+    },
 };
 use serde::{Deserialize, Serialize};
 use validator::Validate;
@@ -85,6 +91,43 @@ pub struct SubPushThreadDataRaw {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SubPushThreadRaw {}
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SubscribeDescribeReq {
+    pub tenant: String,
+    pub client_id: String,
+    pub path: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SubscribeDescribeRep {
+    pub tenant: String,
+    pub client_id: String,
+    pub path: String,
+    pub share_sub: bool,
+    pub topics: Vec<SubscribeTopicDescribe>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SubscribeTopicDescribe {
+    pub topic: String,
+    pub subscriber: Subscriber,
+    pub stats: SubscriptionStatsSnapshot,
+    // Consumer group the committed offsets belong to; shared by all members of a share group
+    pub group: String,
+    pub offset: u64,
+    pub lag: u64,
+    pub shards: Vec<ShardLag>,
+    // None when the push thread runs on another broker
+    pub push_thread: Option<SubPushThreadDescribe>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SubPushThreadDescribe {
+    pub thread_key: String,
+    #[serde(flatten)]
+    pub data: SubPushThreadSnapshot,
+}
+
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct AutoSubscribeListReq {
     pub tenant: Option<String>,
@@ -292,7 +335,7 @@ pub async fn subscribe_detail(
             .directly_buckets_push_thread
             .get(bucket_id)
         {
-            let val = thread_data.value();
+            let val = thread_data.value().snapshot();
             push_thread.insert(
                 topic.to_string(),
                 SubPushThreadDataRaw {
@@ -323,6 +366,98 @@ pub async fn subscribe_detail(
     })
 }
 
+pub async fn subscribe_describe(
+    State(state): State<Arc<HttpState>>,
+    Query(params): Query<SubscribeDescribeReq>,
+) -> String {
+    let subscribe_manager = &state.mqtt_context.subscribe_manager;
+    let push_manager = &state.mqtt_context.push_manager;
+    let share_sub = is_mqtt_share_subscribe(&params.path);
+
+    // (topic, consumer group, Subscriber, push thread)
+    let mut subscribers = Vec::new();
+    if share_sub {
+        if let Some(tenant_share) = subscribe_manager.share_push.get(&params.tenant) {
+            for row in tenant_share.iter() {
+                let data = row
+                    .value()
+                    .get_subscribe_data_by_sub(&params.client_id, &params.path);
+                let thread_key = share_thread_key(&params.tenant, row.key());
+                for (topic, (_, sub)) in data {
+                    let push_thread =
+                        push_manager
+                            .share_buckets_push_thread
+                            .get(&thread_key)
+                            .map(|thread| SubPushThreadDescribe {
+                                thread_key: thread_key.clone(),
+                                data: thread.value().snapshot(),
+                            });
+                    subscribers.push((topic, sub.group_name.clone(), sub, push_thread));
+                }
+            }
+        }
+    } else {
+        let data = subscribe_manager
+            .directly_push
+            .get_subscribe_data_by_sub(&params.client_id, &params.path);
+        for (topic, (bucket_id, sub)) in data {
+            if sub.tenant != params.tenant {
+                continue;
+            }
+            let push_thread = push_manager
+                .directly_buckets_push_thread
+                .get(&bucket_id)
+                .map(|thread| SubPushThreadDescribe {
+                    thread_key: bucket_id.clone(),
+                    data: thread.value().snapshot(),
+                });
+            let group = directly_group_name(&params.client_id, &params.path, &topic);
+            subscribers.push((topic, group, sub, push_thread));
+        }
+    }
+
+    let mut topics = Vec::with_capacity(subscribers.len());
+    for (topic, group, subscriber, push_thread) in subscribers {
+        let shards = match group_shard_lags(
+            &state.mqtt_context.storage_driver_manager,
+            &params.tenant,
+            &group,
+            &topic,
+        )
+        .await
+        {
+            Ok(shards) => shards,
+            Err(e) => return error_response(e.to_string()),
+        };
+        let stats = subscribe_manager
+            .subscription_stats
+            .get(&params.tenant, &params.client_id, &params.path, &topic)
+            .unwrap_or_default();
+        topics.push(SubscribeTopicDescribe {
+            offset: shards
+                .iter()
+                .filter_map(|shard| shard.committed_offset)
+                .sum(),
+            lag: shards.iter().map(|shard| shard.lag).sum(),
+            topic,
+            subscriber,
+            stats,
+            group,
+            shards,
+            push_thread,
+        });
+    }
+    topics.sort_by(|a, b| a.topic.cmp(&b.topic));
+
+    success_response(SubscribeDescribeRep {
+        tenant: params.tenant,
+        client_id: params.client_id,
+        path: params.path,
+        share_sub,
+        topics,
+    })
+}
+
 pub async fn auto_subscribe_list(
     State(state): State<Arc<HttpState>>,
     Query(params): Query<AutoSubscribeListReq>,
@@ -37,7 +37,27 @@ pub struct SystemAlarmListReq {
 pub struct SystemAlarmListRow {
     pub name: String,
     pub message: String,
+    /// Activation time of the alarm.
     pub create_time: u64,
+    #[serde(default)]
+    pub deactivate_at: Option<u64>,
+    #[serde(default)]
+    pub activated: bool,
+    #[serde(default)]
+    pub details: Option<SystemAlarmDetails>,
+}
+
+impl From<SystemAlarmEventMessage> for SystemAlarmListRow {
+    fn from(event: SystemAlarmEventMessage) -> Self {
+        SystemAlarmListRow {
+            name: event.name,
+            message: event.message,
+            create_time: event.create_time,
+            deactivate_at: event.deactivate_at,
+            activated: event.activated,
+            details: event.details,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -58,6 +78,30 @@ pub struct FlappingDetectListRaw {
     pub first_request_time: u64,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SlowRequestListReq {
+    pub tenant: Option<String>,
+    pub client_id: Option<String>,
+    pub packet_type: Option<String>,
+    pub limit: Option<u32>,
+    pub page: Option<u32>,
+    pub sort_field: Option<String>,
+    pub sort_by: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SlowRequestListRow {
+    pub id: u64,
+    pub tenant: String,
+    pub client_id: String,
+    pub connect_id: u64,
+    pub packet_type: String,
+    pub topic: String,
+    pub duration_ms: u64,
+    pub phases: Vec<SlowRequestPhase>,
+    pub create_time: String,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BanLogListReq {
     pub tenant: Option<String>,
@@ -84,6 +128,8 @@ pub struct BanLogListRaw {
     http_response::{error_response, success_response},
     utils::time_util::timestamp_to_local_datetime,
 };
+use metadata_struct::mqtt::system_alarm::{SystemAlarmDetails, SystemAlarmEventMessage};
+pub use mqtt_broker::core::slow_request::SlowRequestPhase;
 use mqtt_broker::storage::local::LocalStorage;
 use std::sync::Arc;
 
@@ -110,12 +156,46 @@ pub async fn system_alarm_list(
     };
 
     let results = data_list
-        .iter()
-        .map(|entry| SystemAlarmListRow {
-            name: entry.name.clone(),
-            message: entry.message.clone(),
-            create_time: entry.create_time,
-        })
+        .into_iter()
+        .map(SystemAlarmListRow::from)
+        .collect();
+
+    let filtered = apply_filters(results, &options);
+    let sorted = apply_sorting(filtered, &options);
+    let pagination = apply_pagination(sorted, &options);
+
+    success_response(PageReplyData {
+        data: pagination.0,
+        total_count: pagination.1,
+    })
+}
+
+/// Alarms currently active on this node.
+pub async fn system_alarm_active_list(
+    State(state): State<Arc<HttpState>>,
+    Query(params): Query<SystemAlarmListReq>,
+) -> String {
+    let options = build_query_params(
+        params.page,
+        params.limit,
+        params.sort_field,
+        params.sort_by,
+        params.filter_field,
+        params.filter_values,
+        params.exact_match,
+    );
+
+    let log_storage = LocalStorage::new(state.rocksdb_engine_handler.clone());
+    let data_list = match log_storage.list_active_system_alarm().await {
+        Ok(data) => data,
+        Err(e) => {
+            return error_response(e.to_string());
+        }
+    };
+
+    let results = data_list
+        .into_iter()
+        .map(SystemAlarmListRow::from)
         .collect();
 
     let filtered = apply_filters(results, &options);
@@ -133,6 +213,83 @@ fn get_field_str(&self, field: &str) -> Option<String> {
         match field {
             "name" => Some(self.name.clone()),
             "message" => Some(self.message.clone()),
+            "create_time" => Some(self.create_time.to_string()),
+            "activated" => Some(self.activated.to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Slow requests kept in memory on this node, oldest first.
+pub async fn slow_request_list(
+    State(state): State<Arc<HttpState>>,
+    Query(params): Query<SlowRequestListReq>,
+) -> String {
+    let options = build_query_params(
+        params.page,
+        params.limit,
+        params.sort_field,
+        params.sort_by,
+        None,
+        None,
+        None,
+    );
+
+    let results = state
+        .mqtt_context
+        .cache_manager
+        .slow_request_log
+        .list()
+        .into_iter()
+        .filter(|data| {
+            params
+                .tenant
+                .as_ref()
+                .map(|t| &data.tenant == t)
+                .unwrap_or(true)
+                && params
+                    .client_id
+                    .as_deref()
+                    .map(|kw| data.client_id.contains(kw))
+                    .unwrap_or(true)
+                && params
+                    .packet_type
+                    .as_ref()
+                    .map(|p| data.packet_type.eq_ignore_ascii_case(p))
+                    .unwrap_or(true)
+        })
+        .map(|data| SlowRequestListRow {
+            id: data.id,
+            tenant: data.tenant,
+            client_id: data.client_id,
+            connect_id: data.connect_id,
+            packet_type: data.packet_type,
+            topic: data.topic,
+            duration_ms: data.duration_ms,
+            phases: data.phases,
+            create_time: timestamp_to_local_datetime(data.create_time as i64),
+        })
+        .collect::<Vec<_>>();
+
+    let sorted = apply_sorting(results, &options);
+    let pagination = apply_pagination(sorted, &options);
+
+    success_response(PageReplyData {
+        data: pagination.0,
+        total_count: pagination.1,
+    })
+}
+
+impl Queryable for SlowRequestListRow {
+    fn get_field_str(&self, field: &str) -> Option<String> {
+        match field {
+            // zero padded so the string order matches the numeric order
+            "id" => Some(format!("{:020}", self.id)),
+            "duration_ms" => Some(format!("{:020}", self.duration_ms)),
+            "tenant" => Some(self.tenant.clone()),
+            "client_id" => Some(self.client_id.clone()),
+            "packet_type" => Some(self.packet_type.clone()),
+            "topic" => Some(self.topic.clone()),
             _ => None,
         }
     }
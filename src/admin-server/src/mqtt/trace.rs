@@ -0,0 +1,188 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    client::AdminHttpClient, cluster::config::apply_cluster_config, state::HttpState,
+    tool::extractor::ValidatedJson,
+};
+use axum::extract::{Query, State};
+use broker_core::dynamic_config::ClusterDynamicConfig;
+use bytes::Bytes;
+use common_base::http_response::{error_response, success_response};
+use common_config::config::{MqttTrace, MqttTraceRule, MqttTraceType};
+use mqtt_broker::core::message_trace::{MessageTraceEvent, MessageTraceQuery, MessageTraceStage};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use validator::Validate;
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct TraceListReq {
+    pub tenant: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Validate)]
+pub struct TraceCreateReq {
+    #[validate(length(min = 1, max = 256, message = "Name length must be between 1-256"))]
+    pub name: String,
+
+    #[validate(length(min = 1, max = 128, message = "Tenant length must be between 1-128"))]
+    pub tenant: String,
+
+    /// `client_id` or `topic`.
+    pub trace_type: MqttTraceType,
+
+    /// Client id, or topic filter with `+`/`#` wildcards.
+    #[validate(length(min = 1, max = 1024, message = "Value length must be between 1-1024"))]
+    pub value: String,
+
+    /// Seconds; the trace stops recording after this time. Unset keeps it
+    /// recording until it is deleted.
+    pub end_time: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Validate)]
+pub struct TraceDeleteReq {
+    #[validate(length(min = 1, max = 256, message = "Name length must be between 1-256"))]
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct TraceEventsReq {
+    /// Read the events recorded on another broker.
+    pub broker_id: Option<u64>,
+    pub trace_name: Option<String>,
+    pub client_id: Option<String>,
+    pub stage: Option<MessageTraceStage>,
+    pub limit: Option<usize>,
+}
+
+pub async fn trace_list(
+    State(state): State<Arc<HttpState>>,
+    Query(params): Query<TraceListReq>,
+) -> String {
+    let rules: Vec<MqttTraceRule> = state
+        .broker_cache
+        .get_cluster_config()
+        .mqtt_trace
+        .rules
+        .into_iter()
+        .filter(|rule| params.tenant.as_ref().is_none_or(|t| &rule.tenant == t))
+        .collect();
+    success_response(rules)
+}
+
+pub async fn trace_create(
+    State(state): State<Arc<HttpState>>,
+    ValidatedJson(params): ValidatedJson<TraceCreateReq>,
+) -> String {
+    let mut trace = state.broker_cache.get_cluster_config().mqtt_trace;
+    if trace.rules.iter().any(|rule| rule.name == params.name) {
+        return error_response(format!("Trace {} already exists", params.name));
+    }
+    trace.rules.push(MqttTraceRule {
+        name: params.name,
+        tenant: params.tenant,
+        trace_type: params.trace_type,
+        value: params.value,
+        end_time: params.end_time.unwrap_or_default(),
+    });
+    save_trace(&state, &trace).await
+}
+
+/// Stop a trace and drop the events it recorded on this broker. Other brokers
+/// keep theirs until newer events push them out.
+pub async fn trace_delete(
+    State(state): State<Arc<HttpState>>,
+    ValidatedJson(params): ValidatedJson<TraceDeleteReq>,
+) -> String {
+    let mut trace = state.broker_cache.get_cluster_config().mqtt_trace;
+    let before = trace.rules.len();
+    trace.rules.retain(|rule| rule.name != params.name);
+    if trace.rules.len() == before {
+        return error_response(format!("Trace {} not found", params.name));
+    }
+    let result = save_trace(&state, &trace).await;
+    state
+        .mqtt_context
+        .cache_manager
+        .message_trace
+        .remove_trace(&params.name);
+    result
+}
+
+/// Trace events recorded on this broker, or on `broker_id`, oldest first.
+pub async fn trace_events(
+    State(state): State<Arc<HttpState>>,
+    Query(params): Query<TraceEventsReq>,
+) -> String {
+    if let Some(broker_id) = params.broker_id {
+        if broker_id != state.broker_cache.get_cluster_config().broker_id {
+            return remote_trace_events(&state, broker_id, params).await;
+        }
+    }
+
+    let query = MessageTraceQuery {
+        trace_name: params.trace_name,
+        client_id: params.client_id,
+        stage: params.stage,
+        limit: params.limit.unwrap_or_default(),
+    };
+    success_response(state.mqtt_context.cache_manager.message_trace.list(&query))
+}
+
+async fn remote_trace_events(state: &HttpState, broker_id: u64, params: TraceEventsReq) -> String {
+    let node = match state
+        .broker_cache
+        .node_lists
+        .get(&broker_id)
+        .map(|n| n.clone())
+    {
+        Some(n) => n,
+        None => return error_response(format!("Broker node {} not found", broker_id)),
+    };
+
+    if node.http_addr.is_empty() {
+        return error_response(format!(
+            "Broker node {} has no http_addr registered",
+            broker_id
+        ));
+    }
+
+    let request = TraceEventsReq {
+        broker_id: None,
+        ..params
+    };
+    let client = AdminHttpClient::new(format!("http://{}", node.http_addr));
+    match client
+        .get_trace_events::<_, Vec<MessageTraceEvent>>(&request)
+        .await
+    {
+        Ok(events) => success_response(events),
+        Err(e) => error_response(format!(
+            "Failed to fetch trace events from broker {}: {}",
+            broker_id, e
+        )),
+    }
+}
+
+async fn save_trace(state: &HttpState, trace: &MqttTrace) -> String {
+    let config = match serde_json::to_vec(trace) {
+        Ok(config) => config,
+        Err(e) => return error_response(e.to_string()),
+    };
+    match apply_cluster_config(state, ClusterDynamicConfig::MqttTrace, Bytes::from(config)).await {
+        Ok(()) => success_response("success"),
+        Err(e) => error_response(e),
+    }
+}
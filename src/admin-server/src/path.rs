@@ -22,6 +22,10 @@
 pub const HEALTH_READY_PATH: &str = "/health/ready";
 pub const HEALTH_NODE_PATH: &str = "/health/node";
 pub const HEALTH_CLUSTER_PATH: &str = "/health/cluster";
+// Unauthenticated Kubernetes-style probes, served outside `/api`.
+pub const LIVEZ_PATH: &str = "/livez";
+pub const READYZ_PATH: &str = "/readyz";
+pub const HEALTHZ_PATH: &str = "/healthz";
 pub const DEBUG_PPROF_FLAMEGRAPH_PATH: &str = "/debug/pprof/flamegraph";
 pub const METRICS_PATH: &str = "/metrics";
 pub const CLUSTER_INFO: &str = "/info";
@@ -31,9 +35,14 @@
 // Cluster base
 pub const CLUSTER_CONFIG_SET_PATH: &str = "/cluster/config/set";
 pub const CLUSTER_CONFIG_GET_PATH: &str = "/cluster/config/get";
+pub const CLUSTER_CONFIG_DRIFT_PATH: &str = "/cluster/config/drift";
 
 // Cluster Node management
 pub const CLUSTER_NODE_LEAVE_PATH: &str = "/cluster/node/leave";
+pub const CLUSTER_NODE_CORDON_PATH: &str = "/cluster/node/cordon";
+pub const CLUSTER_NODE_UNCORDON_PATH: &str = "/cluster/node/uncordon";
+pub const CLUSTER_NODE_STATUS_PATH: &str = "/cluster/node/status";
+pub const CLUSTER_NODE_TRANSFER_LEADER_PATH: &str = "/cluster/node/transfer-leader";
 
 // Cluster Topic API paths
 pub const CLUSTER_TOPIC_LIST_PATH: &str = "/cluster/topic/list";
@@ -80,22 +89,45 @@
 // MQTT Overview
 pub const MQTT_OVERVIEW_PATH: &str = "/mqtt/overview";
 pub const MQTT_MONITOR_PATH: &str = "/mqtt/monitor/data";
+pub const MQTT_MONITOR_HISTORY_PATH: &str = "/mqtt/monitor/history";
 
 // MQTT Client
 pub const MQTT_CLIENT_LIST_PATH: &str = "/mqtt/client/list";
+pub const MQTT_CLIENT_ATTRIBUTE_PATH: &str = "/mqtt/client/attribute";
+pub const MQTT_CLIENT_ATTRIBUTE_SET_PATH: &str = "/mqtt/client/attribute/set";
+pub const MQTT_CLIENT_ATTRIBUTE_DELETE_PATH: &str = "/mqtt/client/attribute/delete";
 
 // MQTT Session
 pub const MQTT_SESSION_LIST_PATH: &str = "/mqtt/session/list";
+pub const MQTT_SESSION_EXPORT_PATH: &str = "/mqtt/session/export";
+pub const MQTT_SESSION_IMPORT_PATH: &str = "/mqtt/session/import";
 
 // MQTT Subscribe
 pub const MQTT_SUBSCRIBE_LIST_PATH: &str = "/mqtt/subscribe/list";
 pub const MQTT_SUBSCRIBE_DETAIL_PATH: &str = "/mqtt/subscribe/detail";
+pub const MQTT_SUBSCRIBE_DESCRIBE_PATH: &str = "/mqtt/subscribe/describe";
 
 // MQTT Auto Subscribe
 pub const MQTT_AUTO_SUBSCRIBE_LIST_PATH: &str = "/mqtt/auto-subscribe/list";
 pub const MQTT_AUTO_SUBSCRIBE_CREATE_PATH: &str = "/mqtt/auto-subscribe/create";
 pub const MQTT_AUTO_SUBSCRIBE_DELETE_PATH: &str = "/mqtt/auto-subscribe/delete";
 
+// MQTT Message Rule
+pub const MQTT_MESSAGE_RULE_LIST_PATH: &str = "/mqtt/message-rule/list";
+pub const MQTT_MESSAGE_RULE_CREATE_PATH: &str = "/mqtt/message-rule/create";
+pub const MQTT_MESSAGE_RULE_DELETE_PATH: &str = "/mqtt/message-rule/delete";
+
+// MQTT Message Replay
+pub const MQTT_MESSAGE_REPLAY_LIST_PATH: &str = "/mqtt/message-replay/list";
+pub const MQTT_MESSAGE_REPLAY_CREATE_PATH: &str = "/mqtt/message-replay/create";
+pub const MQTT_MESSAGE_REPLAY_CANCEL_PATH: &str = "/mqtt/message-replay/cancel";
+
+// MQTT Message Trace
+pub const MQTT_TRACE_LIST_PATH: &str = "/mqtt/trace/list";
+pub const MQTT_TRACE_CREATE_PATH: &str = "/mqtt/trace/create";
+pub const MQTT_TRACE_DELETE_PATH: &str = "/mqtt/trace/delete";
+pub const MQTT_TRACE_EVENTS_PATH: &str = "/mqtt/trace/events";
+
 // MQTT Slow Subscribe
 pub const MQTT_SLOW_SUBSCRIBE_LIST_PATH: &str = "/mqtt/slow-subscribe/list";
 
@@ -104,7 +136,9 @@
 
 // MQTT System
 pub const MQTT_SYSTEM_ALARM_LIST_PATH: &str = "/mqtt/system-alarm/list";
+pub const MQTT_SYSTEM_ALARM_ACTIVE_PATH: &str = "/mqtt/system-alarm/active";
 pub const MQTT_BAN_LOG_LIST_PATH: &str = "/mqtt/ban-log/list";
+pub const MQTT_SLOW_REQUEST_LIST_PATH: &str = "/mqtt/slow-request/list";
 
 // Cluster Message
 pub const CLUSTER_MESSAGE_SEND_PATH: &str = "/cluster/message/send";
@@ -127,6 +161,13 @@
 pub const CLUSTER_OFFSET_BY_TIMESTAMP_PATH: &str = "/cluster/offset/timestamp";
 pub const CLUSTER_OFFSET_BY_GROUP_PATH: &str = "/cluster/offset/group";
 pub const CLUSTER_OFFSET_COMMIT_PATH: &str = "/cluster/offset/commit";
+pub const CLUSTER_OFFSET_WATERMARKS_PATH: &str = "/cluster/offset/watermarks";
+
+// Cluster Consumer Group API paths
+pub const CLUSTER_CONSUMER_GROUP_LIST_PATH: &str = "/cluster/consumer-group/list";
+pub const CLUSTER_CONSUMER_GROUP_DETAIL_PATH: &str = "/cluster/consumer-group/detail";
+pub const CLUSTER_CONSUMER_GROUP_DELETE_PATH: &str = "/cluster/consumer-group/delete";
+pub const CLUSTER_CONSUMER_GROUP_RESET_OFFSET_PATH: &str = "/cluster/consumer-group/reset-offset";
 
 // Cluster Tenant (full CRUD, lives in cluster/tenant.rs)
 pub const TENANT_LIST_PATH: &str = "/cluster/tenant/list";
@@ -138,6 +179,26 @@
 pub const CLUSTER_SHARE_GROUP_LIST_PATH: &str = "/cluster/share-group/list";
 pub const CLUSTER_SHARE_GROUP_DETAIL_PATH: &str = "/cluster/share-group/detail";
 
+// Cluster Delay Task API paths
+pub const CLUSTER_DELAY_TASK_RECURRING_LIST_PATH: &str = "/cluster/delay-task/recurring/list";
+pub const CLUSTER_DELAY_TASK_RECURRING_CANCEL_PATH: &str = "/cluster/delay-task/recurring/cancel";
+pub const CLUSTER_DELAY_TASK_SHARD_LIST_PATH: &str = "/cluster/delay-task/shard/list";
+pub const CLUSTER_DELAY_TASK_SHARD_TASK_LIST_PATH: &str = "/cluster/delay-task/shard/task/list";
+
+// Cluster RocksDB Backup API paths
+pub const CLUSTER_ROCKSDB_BACKUP_LIST_PATH: &str = "/cluster/rocksdb/backup/list";
+pub const CLUSTER_ROCKSDB_BACKUP_CREATE_PATH: &str = "/cluster/rocksdb/backup/create";
+pub const CLUSTER_ROCKSDB_BACKUP_RESTORE_PATH: &str = "/cluster/rocksdb/backup/restore";
+
+// Cluster Metadata Dump API paths
+pub const CLUSTER_METADATA_EXPORT_PATH: &str = "/cluster/metadata/export";
+pub const CLUSTER_METADATA_IMPORT_PATH: &str = "/cluster/metadata/import";
+
+// Cluster Fault Injection API paths
+pub const CLUSTER_FAULT_LIST_PATH: &str = "/cluster/fault/list";
+pub const CLUSTER_FAULT_SET_PATH: &str = "/cluster/fault/set";
+pub const CLUSTER_FAULT_REMOVE_PATH: &str = "/cluster/fault/remove";
+
 // ── /mq9 ─────────────────────────────────────────────────────────────────────
 
 pub const MQ9_MAIL_LIST_PATH: &str = "/mq9/mail/list";
@@ -14,7 +14,9 @@
 
 use crate::auth::{auth_middleware, auth_router};
 use crate::cluster::index;
-use crate::cluster::offset::{commit_offset, get_offset_by_group, get_offset_by_timestamp};
+use crate::cluster::offset::{
+    commit_offset, get_offset_by_group, get_offset_by_timestamp, get_shard_watermarks,
+};
 use crate::debug::pprof_flamegraph;
 use crate::engine::record::{record_delete_by_keys, record_delete_by_offsets};
 use crate::engine::segment::{segment_detail, segment_list, segment_replica_state};
@@ -23,12 +25,23 @@
 use crate::{
     cluster::{
         acl::{acl_create, acl_delete, acl_list},
+        backup::{rocksdb_backup_create, rocksdb_backup_list, rocksdb_backup_restore},
         blacklist::{blacklist_create, blacklist_delete, blacklist_list},
-        config::{cluster_config_get, cluster_config_set},
+        config::{cluster_config_drift, cluster_config_get, cluster_config_set},
         connector::{connector_create, connector_delete, connector_detail, connector_list},
-        health::{health_cluster, health_node, health_ready},
+        consumer_group::{
+            consumer_group_delete, consumer_group_detail, consumer_group_list,
+            consumer_group_reset_offset,
+        },
+        delay_task::{
+            delay_task_shard_list, delay_task_shard_task_list, recurring_delay_task_cancel,
+            recurring_delay_task_list,
+        },
+        fault::{fault_list, fault_remove, fault_set},
+        health::{health_cluster, health_node, health_ready, healthz, livez, readyz},
         message::{read_message, send_message},
-        node::node_leave,
+        metadata::{metadata_export, metadata_import},
+        node::{node_cordon, node_leave, node_status, node_transfer_leader, node_uncordon},
         schema::{
             schema_bind_create, schema_bind_delete, schema_bind_list, schema_create, schema_delete,
             schema_list,
@@ -41,15 +54,24 @@
     mq9::{agent::agent_list, mail::mail_list},
     mqtt::{
         client::client_list,
-        monitor::monitor_data,
+        message_replay::{message_replay_cancel, message_replay_create, message_replay_list},
+        message_rule::{message_rule_create, message_rule_delete, message_rule_list},
+        monitor::{monitor_data, monitor_history},
         overview::overview,
-        session::session_list,
+        session::{
+            client_attribute_delete, client_attribute_get, client_attribute_set, session_export,
+            session_import, session_list,
+        },
         subscribe::{
             auto_subscribe_create, auto_subscribe_delete, auto_subscribe_list, slow_subscribe_list,
-            subscribe_detail, subscribe_list,
+            subscribe_describe, subscribe_detail, subscribe_list,
+        },
+        system::{
+            ban_log_list, flapping_detect_list, slow_request_list, system_alarm_active_list,
+            system_alarm_list,
         },
-        system::{ban_log_list, flapping_detect_list, system_alarm_list},
         topic_rewrite::{topic_rewrite_create, topic_rewrite_delete, topic_rewrite_list},
+        trace::{trace_create, trace_delete, trace_events, trace_list},
     },
     path::*,
     state::HttpState,
@@ -100,6 +122,9 @@ pub async fn start(&self, port: u32, state: Arc<HttpState>) -> Result<(), std::i
             .merge(mcp_route())
             .route(DEBUG_PPROF_FLAMEGRAPH_PATH, get(pprof_flamegraph))
             .route(METRICS_PATH, get(|| async { dump_metrics() }))
+            .route(LIVEZ_PATH, get(livez))
+            .route(READYZ_PATH, get(readyz))
+            .route(HEALTHZ_PATH, get(healthz))
             .merge(auth_router())
             .nest("/api", protected_api)
             .merge(self.static_route())
@@ -147,8 +172,16 @@ fn common_route(&self) -> Router<Arc<HttpState>> {
             // config
             .route(CLUSTER_CONFIG_SET_PATH, post(cluster_config_set))
             .route(CLUSTER_CONFIG_GET_PATH, get(cluster_config_get))
+            .route(CLUSTER_CONFIG_DRIFT_PATH, get(cluster_config_drift))
             // node
             .route(CLUSTER_NODE_LEAVE_PATH, post(node_leave))
+            .route(CLUSTER_NODE_CORDON_PATH, post(node_cordon))
+            .route(CLUSTER_NODE_UNCORDON_PATH, post(node_uncordon))
+            .route(CLUSTER_NODE_STATUS_PATH, get(node_status))
+            .route(
+                CLUSTER_NODE_TRANSFER_LEADER_PATH,
+                post(node_transfer_leader),
+            )
             // tenant
             .route(TENANT_LIST_PATH, get(tenant_list))
             .route(TENANT_CREATE_PATH, post(tenant_create))
@@ -232,9 +265,58 @@ fn cluster_resource_route(&self) -> Router<Arc<HttpState>> {
             )
             .route(CLUSTER_OFFSET_BY_GROUP_PATH, post(get_offset_by_group))
             .route(CLUSTER_OFFSET_COMMIT_PATH, post(commit_offset))
+            .route(CLUSTER_OFFSET_WATERMARKS_PATH, post(get_shard_watermarks))
+            // consumer-group
+            .route(CLUSTER_CONSUMER_GROUP_LIST_PATH, get(consumer_group_list))
+            .route(
+                CLUSTER_CONSUMER_GROUP_DETAIL_PATH,
+                get(consumer_group_detail),
+            )
+            .route(
+                CLUSTER_CONSUMER_GROUP_DELETE_PATH,
+                post(consumer_group_delete),
+            )
+            .route(
+                CLUSTER_CONSUMER_GROUP_RESET_OFFSET_PATH,
+                post(consumer_group_reset_offset),
+            )
             // message
             .route(CLUSTER_MESSAGE_SEND_PATH, post(send_message))
             .route(CLUSTER_MESSAGE_READ_PATH, post(read_message))
+            // delay-task
+            .route(
+                CLUSTER_DELAY_TASK_RECURRING_LIST_PATH,
+                get(recurring_delay_task_list),
+            )
+            .route(
+                CLUSTER_DELAY_TASK_RECURRING_CANCEL_PATH,
+                post(recurring_delay_task_cancel),
+            )
+            .route(
+                CLUSTER_DELAY_TASK_SHARD_LIST_PATH,
+                get(delay_task_shard_list),
+            )
+            .route(
+                CLUSTER_DELAY_TASK_SHARD_TASK_LIST_PATH,
+                get(delay_task_shard_task_list),
+            )
+            // rocksdb backup
+            .route(CLUSTER_ROCKSDB_BACKUP_LIST_PATH, get(rocksdb_backup_list))
+            .route(
+                CLUSTER_ROCKSDB_BACKUP_CREATE_PATH,
+                post(rocksdb_backup_create),
+            )
+            .route(
+                CLUSTER_ROCKSDB_BACKUP_RESTORE_PATH,
+                post(rocksdb_backup_restore),
+            )
+            // metadata dump
+            .route(CLUSTER_METADATA_EXPORT_PATH, post(metadata_export))
+            .route(CLUSTER_METADATA_IMPORT_PATH, post(metadata_import))
+            // fault injection
+            .route(CLUSTER_FAULT_LIST_PATH, get(fault_list))
+            .route(CLUSTER_FAULT_SET_PATH, post(fault_set))
+            .route(CLUSTER_FAULT_REMOVE_PATH, post(fault_remove))
     }
 
     fn mqtt_route(&self) -> Router<Arc<HttpState>> {
@@ -243,24 +325,50 @@ fn mqtt_route(&self) -> Router<Arc<HttpState>> {
             .route(MQTT_OVERVIEW_PATH, get(overview))
             // monitor
             .route(MQTT_MONITOR_PATH, get(monitor_data))
+            .route(MQTT_MONITOR_HISTORY_PATH, get(monitor_history))
             // client
             .route(MQTT_CLIENT_LIST_PATH, get(client_list))
+            .route(MQTT_CLIENT_ATTRIBUTE_PATH, get(client_attribute_get))
+            .route(MQTT_CLIENT_ATTRIBUTE_SET_PATH, post(client_attribute_set))
+            .route(
+                MQTT_CLIENT_ATTRIBUTE_DELETE_PATH,
+                post(client_attribute_delete),
+            )
             // session
             .route(MQTT_SESSION_LIST_PATH, get(session_list))
+            .route(MQTT_SESSION_EXPORT_PATH, post(session_export))
+            .route(MQTT_SESSION_IMPORT_PATH, post(session_import))
             // subscribe
             .route(MQTT_SUBSCRIBE_LIST_PATH, get(subscribe_list))
             .route(MQTT_SUBSCRIBE_DETAIL_PATH, get(subscribe_detail))
+            .route(MQTT_SUBSCRIBE_DESCRIBE_PATH, get(subscribe_describe))
             // auto subscribe
             .route(MQTT_AUTO_SUBSCRIBE_LIST_PATH, get(auto_subscribe_list))
             .route(MQTT_AUTO_SUBSCRIBE_CREATE_PATH, post(auto_subscribe_create))
             .route(MQTT_AUTO_SUBSCRIBE_DELETE_PATH, post(auto_subscribe_delete))
+            // message rule
+            .route(MQTT_MESSAGE_RULE_LIST_PATH, get(message_rule_list))
+            .route(MQTT_MESSAGE_RULE_CREATE_PATH, post(message_rule_create))
+            .route(MQTT_MESSAGE_RULE_DELETE_PATH, post(message_rule_delete))
+            // message replay
+            .route(MQTT_MESSAGE_REPLAY_LIST_PATH, get(message_replay_list))
+            .route(MQTT_MESSAGE_REPLAY_CREATE_PATH, post(message_replay_create))
+            .route(MQTT_MESSAGE_REPLAY_CANCEL_PATH, post(message_replay_cancel))
+            // message trace
+            .route(MQTT_TRACE_LIST_PATH, get(trace_list))
+            .route(MQTT_TRACE_CREATE_PATH, post(trace_create))
+            .route(MQTT_TRACE_DELETE_PATH, post(trace_delete))
+            .route(MQTT_TRACE_EVENTS_PATH, get(trace_events))
             // slow subscribe
             .route(MQTT_SLOW_SUBSCRIBE_LIST_PATH, get(slow_subscribe_list))
             // flapping_detect
             .route(MQTT_FLAPPING_DETECT_LIST_PATH, get(flapping_detect_list))
             // system alarm
             .route(MQTT_SYSTEM_ALARM_LIST_PATH, get(system_alarm_list))
+            .route(MQTT_SYSTEM_ALARM_ACTIVE_PATH, get(system_alarm_active_list))
             .route(MQTT_BAN_LOG_LIST_PATH, get(ban_log_list))
+            // slow request
+            .route(MQTT_SLOW_REQUEST_LIST_PATH, get(slow_request_list))
     }
 
     fn mq9_route(&self) -> Router<Arc<HttpState>> {
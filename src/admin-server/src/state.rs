@@ -17,6 +17,7 @@
 use broker_core::cache::NodeCacheManager;
 use common_security::manager::SecurityManager;
 use connector::manager::ConnectorManager;
+use delay_task::manager::DelayTaskManager;
 use grpc_clients::pool::ClientPool;
 use mqtt_broker::{
     core::cache::MQTTCacheManager,
@@ -46,6 +47,7 @@ pub struct HttpState {
     pub engine_context: StorageEngineContext,
     pub storage_driver_manager: Arc<StorageDriverManager>,
     pub rate_limiter: Arc<GlobalRateLimiterManager>,
+    pub delay_task_manager: Arc<DelayTaskManager>,
     pub nats_context: Option<NatsContext>,
     #[cfg(not(windows))]
     pub pprof_guard: Option<Arc<ProfilerGuard<'static>>>,
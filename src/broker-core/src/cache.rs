@@ -25,7 +25,8 @@
     },
     tenant::Tenant,
 };
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -64,6 +65,12 @@ pub struct NodeCacheManager {
 
     // broker_epoch from meta at register time; 0 = not registered.
     pub broker_epoch: AtomicU64,
+
+    // (resource_type, version): highest UpdateCache version applied, reported on heartbeat.
+    pub applied_cache_versions: DashMap<String, u64>,
+
+    // Set once the startup metadata bootstrap from meta has completed.
+    pub cache_loaded: AtomicBool,
 }
 impl NodeCacheManager {
     pub fn new(cluster: BrokerConfig) -> Self {
@@ -81,6 +88,8 @@ pub fn new(cluster: BrokerConfig) -> Self {
             topic_list: DashMap::new(),
             topic_tenant_index: DashMap::with_capacity(8),
             broker_epoch: AtomicU64::new(0),
+            applied_cache_versions: DashMap::with_capacity(8),
+            cache_loaded: AtomicBool::new(false),
         }
     }
 
@@ -92,6 +101,33 @@ pub fn get_broker_epoch(&self) -> u64 {
         self.broker_epoch.load(Ordering::SeqCst)
     }
 
+    pub fn set_cache_loaded(&self) {
+        self.cache_loaded.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cache_loaded(&self) -> bool {
+        self.cache_loaded.load(Ordering::SeqCst)
+    }
+
+    // Cache version
+    pub fn record_applied_cache_version(&self, resource_type: &str, version: u64) {
+        self.applied_cache_versions
+            .entry(resource_type.to_string())
+            .and_modify(|v| *v = (*v).max(version))
+            .or_insert(version);
+    }
+
+    pub fn clear_applied_cache_versions(&self) {
+        self.applied_cache_versions.clear();
+    }
+
+    pub fn applied_cache_versions(&self) -> HashMap<String, u64> {
+        self.applied_cache_versions
+            .iter()
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect()
+    }
+
     // Tenant
     pub fn add_tenant(&self, tenant: Tenant) {
         self.tenant_list.insert(tenant.tenant_name.clone(), tenant);
@@ -318,4 +354,16 @@ async fn node_operations() {
         let nodes = cache_manager.node_list();
         assert!(nodes.is_empty());
     }
+
+    #[test]
+    fn applied_cache_version_only_grows() {
+        let cache_manager = NodeCacheManager::new(default_broker_config());
+        cache_manager.record_applied_cache_version("Topic", 5);
+        cache_manager.record_applied_cache_version("Topic", 3);
+        cache_manager.record_applied_cache_version("User", 1);
+
+        let versions = cache_manager.applied_cache_versions();
+        assert_eq!(versions.get("Topic"), Some(&5));
+        assert_eq!(versions.get("User"), Some(&1));
+    }
 }
@@ -15,20 +15,26 @@
 use crate::cache::NodeCacheManager;
 use common_base::error::common::CommonError;
 use common_base::tools::{get_local_ip, now_second};
+use common_base::version::version;
 use common_config::broker::broker_config;
 use common_config::config::BrokerConfig;
 use grpc_clients::meta::common::call::{
-    cluster_status, delete_resource_config, get_resource_config, heartbeat, kv_set, leave_cluster,
-    node_list, register_node, set_resource_config, unregister_node,
+    cluster_status, cordon_node, delete_resource_config, export_metadata, get_config_drift,
+    get_resource_config, heartbeat, import_metadata, kv_set, leave_cluster, list_node_status,
+    node_list, register_node, set_resource_config, transfer_leader, uncordon_node, unregister_node,
 };
 use grpc_clients::pool::ClientPool;
 use metadata_struct::meta::extend::{KafkaNodeExtend, MqttNodeExtend, NatsNodeExtend, NodeExtend};
 use metadata_struct::meta::node::BrokerNode;
 use protocol::meta::meta_service_common::{
-    ClusterStatusRequest, DeleteResourceConfigRequest, GetResourceConfigRequest, HeartbeatRequest,
-    LeaveClusterRequest, NodeListRequest, RegisterNodeRequest, SetRequest,
-    SetResourceConfigRequest, UnRegisterNodeRequest,
+    ClusterStatusRequest, ConfigSectionDrift, CordonNodeRequest, DeleteResourceConfigRequest,
+    ExportMetadataReply, ExportMetadataRequest, GetConfigDriftRequest, GetResourceConfigRequest,
+    HeartbeatRequest, ImportMetadataRequest, LeaveClusterRequest, ListNodeStatusRequest,
+    MetadataImportResult, NodeListRequest, NodeStatus, RegisterNodeRequest, SetRequest,
+    SetResourceConfigRequest, TransferLeaderRequest, TransferLeaderResult, UnRegisterNodeRequest,
+    UncordonNodeRequest,
 };
+use std::collections::HashMap;
 use std::sync::Arc;
 
 pub struct ClusterStorage {
@@ -53,6 +59,7 @@ pub async fn raft_ping(&self) -> Result<(), CommonError> {
         let request = SetRequest {
             key: "__robustmq_raft_ping__".to_string(),
             value: "1".to_string(),
+            ..Default::default()
         };
         kv_set(&self.client_pool, &conf.get_meta_service_addr(), request).await?;
         Ok(())
@@ -83,6 +90,78 @@ pub async fn leave_cluster(&self, node_id: u64) -> Result<(), CommonError> {
         Ok(())
     }
 
+    /// Hand raft leadership of `machine` (every shard when empty) led by
+    /// `node_id` over to `target_node_id`. The request goes straight to
+    /// `node_id`, since only the current leader can hand a shard over.
+    pub async fn transfer_leader(
+        &self,
+        node_id: u64,
+        machine: String,
+        target_node_id: u64,
+    ) -> Result<Vec<TransferLeaderResult>, CommonError> {
+        let conf = broker_config();
+        let addr = conf
+            .meta_addrs
+            .get(&node_id.to_string())
+            .and_then(|addr| addr.as_str())
+            .ok_or_else(|| {
+                CommonError::CommonError(format!("Node {} is not a meta node", node_id))
+            })?;
+        let request = TransferLeaderRequest {
+            machine,
+            target_node_id,
+        };
+        let reply = transfer_leader(&self.client_pool, &[addr], request).await?;
+        Ok(reply.results)
+    }
+
+    pub async fn cordon_node(&self, node_id: u64, reason: String) -> Result<(), CommonError> {
+        let conf = broker_config();
+        let request = CordonNodeRequest { node_id, reason };
+        cordon_node(&self.client_pool, &conf.get_meta_service_addr(), request).await?;
+        Ok(())
+    }
+
+    pub async fn uncordon_node(&self, node_id: u64) -> Result<(), CommonError> {
+        let conf = broker_config();
+        let request = UncordonNodeRequest { node_id };
+        uncordon_node(&self.client_pool, &conf.get_meta_service_addr(), request).await?;
+        Ok(())
+    }
+
+    /// Dump the cluster metadata, every restorable resource type when
+    /// `resource_types` is empty.
+    pub async fn export_metadata(
+        &self,
+        resource_types: Vec<String>,
+    ) -> Result<ExportMetadataReply, CommonError> {
+        let conf = broker_config();
+        let request = ExportMetadataRequest { resource_types };
+        Ok(export_metadata(&self.client_pool, &conf.get_meta_service_addr(), request).await?)
+    }
+
+    /// Restore a metadata dump. Records that already exist are skipped.
+    pub async fn import_metadata(
+        &self,
+        request: ImportMetadataRequest,
+    ) -> Result<Vec<MetadataImportResult>, CommonError> {
+        let conf = broker_config();
+        let reply =
+            import_metadata(&self.client_pool, &conf.get_meta_service_addr(), request).await?;
+        Ok(reply.results)
+    }
+
+    pub async fn list_node_status(&self) -> Result<Vec<NodeStatus>, CommonError> {
+        let conf = broker_config();
+        let reply = list_node_status(
+            &self.client_pool,
+            &conf.get_meta_service_addr(),
+            ListNodeStatusRequest {},
+        )
+        .await?;
+        Ok(reply.nodes)
+    }
+
     /// Returns the node plus the broker_epoch meta assigned.
     pub async fn register_node(
         &self,
@@ -121,6 +200,8 @@ pub async fn register_node(
             start_time: cache_manager.get_start_time(),
             register_time: now_second(),
             storage_fold: config.storage_runtime.data_path.clone(),
+            zone: config.placement.zone.clone(),
+            rack: config.placement.rack.clone(),
         };
 
         let req = RegisterNodeRequest {
@@ -144,10 +225,17 @@ pub async fn unregister_node(&self, config: &BrokerConfig) -> Result<(), CommonE
         Ok(())
     }
 
-    pub async fn heartbeat(&self) -> Result<(), CommonError> {
+    pub async fn heartbeat(
+        &self,
+        cache_versions: HashMap<String, u64>,
+        config_digests: HashMap<String, u32>,
+    ) -> Result<(), CommonError> {
         let config = broker_config();
         let req = HeartbeatRequest {
             node_id: config.broker_id,
+            version: version(),
+            cache_versions,
+            config_digests,
         };
 
         // Send the heartbeat to EVERY meta node, not just one. The heartbeat only
@@ -162,7 +250,7 @@ pub async fn heartbeat(&self) -> Result<(), CommonError> {
         let mut acked = false;
         let mut last_err: Option<CommonError> = None;
         for addr in &addrs {
-            match heartbeat(&self.client_pool, std::slice::from_ref(addr), req).await {
+            match heartbeat(&self.client_pool, std::slice::from_ref(addr), req.clone()).await {
                 Ok(_) => acked = true,
                 Err(e) => last_err = Some(e),
             }
@@ -175,6 +263,18 @@ pub async fn heartbeat(&self) -> Result<(), CommonError> {
         }
     }
 
+    /// Config sections the brokers disagree on, as seen by Meta Service.
+    pub async fn get_config_drift(&self) -> Result<Vec<ConfigSectionDrift>, CommonError> {
+        let conf = broker_config();
+        let reply = get_config_drift(
+            &self.client_pool,
+            &conf.get_meta_service_addr(),
+            GetConfigDriftRequest {},
+        )
+        .await?;
+        Ok(reply.drifts)
+    }
+
     pub async fn set_dynamic_config(
         &self,
         resource: &str,
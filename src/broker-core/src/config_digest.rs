@@ -0,0 +1,72 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Digests of the config sections every broker in a cluster is expected to
+//! share. They are sent with each heartbeat so that Meta Service can report
+//! nodes running with different settings.
+
+use common_base::error::common::CommonError;
+use common_base::utils::crc::calc_digest;
+use common_config::config::BrokerConfig;
+use std::collections::HashMap;
+
+/// Top-level `BrokerConfig` fields compared across nodes. Node-local settings
+/// such as ports, paths and runtime sizes are left out.
+pub const CLUSTER_CONFIG_SECTIONS: [&str; 10] = [
+    "cluster_name",
+    "cluster_limit",
+    "storage_routing",
+    "mqtt_keep_alive",
+    "mqtt_offline_message",
+    "mqtt_protocol",
+    "mqtt_schema",
+    "mqtt_limit",
+    "mqtt_flapping_detect",
+    "password_policy",
+];
+
+pub fn config_section_digests(config: &BrokerConfig) -> Result<HashMap<String, u32>, CommonError> {
+    let value = serde_json::to_value(config)?;
+    let mut digests = HashMap::with_capacity(CLUSTER_CONFIG_SECTIONS.len());
+    for section in CLUSTER_CONFIG_SECTIONS {
+        if let Some(section_value) = value.get(section) {
+            digests.insert(section.to_string(), calc_digest(section_value)?);
+        }
+    }
+    Ok(digests)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_section_digests() {
+        let config = BrokerConfig::default();
+        let digests = config_section_digests(&config).unwrap();
+        assert_eq!(digests.len(), CLUSTER_CONFIG_SECTIONS.len());
+
+        // Node-local settings don't change any digest.
+        let mut other = config.clone();
+        other.broker_id = config.broker_id + 1;
+        other.grpc_port = config.grpc_port + 1;
+        assert_eq!(config_section_digests(&other).unwrap(), digests);
+
+        other.mqtt_protocol.max_packet_size += 1;
+        let changed = config_section_digests(&other).unwrap();
+        for (section, digest) in digests.iter() {
+            assert_eq!(changed[section] != *digest, section == "mqtt_protocol");
+        }
+    }
+}
@@ -0,0 +1,382 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Periodic check that this broker's metadata cache agrees with the meta
+//! service. Every round samples a window of cached keys per resource, sends
+//! their digests with `CheckResourceDigest` and repairs the records the meta
+//! service reports as different. A key is only repaired after it diverged in
+//! two consecutive rounds, so a cache notification still in flight is not
+//! mistaken for drift.
+
+use crate::cache::NodeCacheManager;
+use common_base::error::common::CommonError;
+use common_base::error::ResultCommonError;
+use common_base::tools::loop_select_ticket;
+use common_base::utils::crc::calc_digest;
+use common_config::broker::broker_config;
+use common_metrics::broker::{
+    record_broker_cache_divergence_detected, record_broker_cache_divergence_repaired,
+};
+use dashmap::DashMap;
+use grpc_clients::meta::common::call::check_resource_digest;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::meta::bootstrap::BootstrapResource;
+use protocol::broker::broker::{
+    BrokerUpdateCacheActionType, BrokerUpdateCacheResourceType, UpdateCacheRecord,
+};
+use protocol::meta::meta_service_common::{
+    CheckResourceDigestRequest, ResourceDigest, ResourceDivergence,
+};
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+pub const CONSISTENCY_CHECK_INTERVAL_MS: u64 = 60_000;
+pub const CONSISTENCY_SAMPLE_SIZE: usize = 100;
+
+/// A record as currently held in the local cache.
+pub struct CachedRecord {
+    pub data: Vec<u8>,
+    pub digest: u32,
+}
+
+/// A resource cache that can be compared against the meta service.
+pub trait ConsistencySource: Send + Sync {
+    fn resource(&self) -> BootstrapResource;
+
+    /// Cache update type used to repair records of this resource.
+    fn update_type(&self) -> BrokerUpdateCacheResourceType;
+
+    /// Keys of the cached records, formatted as `{tenant}/{name}`.
+    fn keys(&self) -> Vec<String>;
+
+    /// The cached record for `key`, or `None` if it should not be checked.
+    fn get(&self, key: &str) -> Result<Option<CachedRecord>, CommonError>;
+
+    /// Whether records missing from the meta service are dropped from the
+    /// cache. Sources that also load records from elsewhere return `false`.
+    fn delete_missing(&self) -> bool {
+        true
+    }
+}
+
+pub struct TopicConsistencySource {
+    cache_manager: Arc<NodeCacheManager>,
+}
+
+impl TopicConsistencySource {
+    pub fn new(cache_manager: Arc<NodeCacheManager>) -> Self {
+        TopicConsistencySource { cache_manager }
+    }
+}
+
+impl ConsistencySource for TopicConsistencySource {
+    fn resource(&self) -> BootstrapResource {
+        BootstrapResource::Topic
+    }
+
+    fn update_type(&self) -> BrokerUpdateCacheResourceType {
+        BrokerUpdateCacheResourceType::Topic
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.cache_manager
+            .topic_list
+            .iter()
+            .map(|entry| entry.key().clone())
+            .collect()
+    }
+
+    fn get(&self, key: &str) -> Result<Option<CachedRecord>, CommonError> {
+        let Some(topic) = self.cache_manager.topic_list.get(key).map(|t| t.clone()) else {
+            return Ok(None);
+        };
+        Ok(Some(CachedRecord {
+            data: topic.encode()?,
+            digest: calc_digest(&topic)?,
+        }))
+    }
+}
+
+pub struct ConsistencyChecker {
+    client_pool: Arc<ClientPool>,
+    sources: Vec<Arc<dyn ConsistencySource>>,
+    // (resource, position of the next sample window in the sorted key list)
+    cursors: DashMap<BootstrapResource, usize>,
+    // (resource, keys that diverged in the previous round)
+    suspects: DashMap<BootstrapResource, HashSet<String>>,
+}
+
+impl ConsistencyChecker {
+    pub fn new(client_pool: Arc<ClientPool>, sources: Vec<Arc<dyn ConsistencySource>>) -> Self {
+        ConsistencyChecker {
+            client_pool,
+            sources,
+            cursors: DashMap::with_capacity(4),
+            suspects: DashMap::with_capacity(4),
+        }
+    }
+
+    /// Runs one round over every source and returns the cache updates that
+    /// bring the confirmed divergent records back in line.
+    pub async fn check(&self) -> Vec<(BootstrapResource, UpdateCacheRecord)> {
+        let mut repairs = Vec::new();
+        for source in self.sources.iter() {
+            match self.check_source(source.as_ref()).await {
+                Ok(records) => repairs.extend(records.into_iter().map(|r| (source.resource(), r))),
+                Err(e) => warn!(
+                    "Cache consistency check for {} failed: {}",
+                    source.resource(),
+                    e
+                ),
+            }
+        }
+        repairs
+    }
+
+    async fn check_source(
+        &self,
+        source: &dyn ConsistencySource,
+    ) -> Result<Vec<UpdateCacheRecord>, CommonError> {
+        let resource = source.resource();
+        let mut keys = source.keys();
+        keys.sort();
+        let cursor = self.cursors.get(&resource).map(|c| *c).unwrap_or(0);
+        let (mut sample, next_cursor) = sample_keys(&keys, cursor, CONSISTENCY_SAMPLE_SIZE);
+        self.cursors.insert(resource, next_cursor);
+
+        let previous = self
+            .suspects
+            .get(&resource)
+            .map(|s| s.clone())
+            .unwrap_or_default();
+        for key in previous.iter() {
+            if !sample.contains(key) {
+                sample.push(key.clone());
+            }
+        }
+
+        let mut digests = Vec::with_capacity(sample.len());
+        for key in sample {
+            if let Some(record) = source.get(&key)? {
+                digests.push(ResourceDigest {
+                    key,
+                    digest: record.digest,
+                });
+            }
+        }
+        if digests.is_empty() {
+            self.suspects.remove(&resource);
+            return Ok(Vec::new());
+        }
+
+        let request = CheckResourceDigestRequest {
+            resource_type: resource.as_str().to_string(),
+            digests,
+        };
+        let reply = check_resource_digest(
+            &self.client_pool,
+            &broker_config().get_meta_service_addr(),
+            request,
+        )
+        .await?;
+
+        let mut repairs = Vec::new();
+        for divergence in self.confirm(resource, &previous, reply.divergences) {
+            if let Some(record) = repair_record(source, divergence)? {
+                repairs.push(record);
+            }
+        }
+        Ok(repairs)
+    }
+
+    // Keeps first-time divergences as suspects and returns those that were
+    // already suspected in the previous round.
+    fn confirm(
+        &self,
+        resource: BootstrapResource,
+        previous: &HashSet<String>,
+        divergences: Vec<ResourceDivergence>,
+    ) -> Vec<ResourceDivergence> {
+        let mut confirmed = Vec::new();
+        let mut suspects = HashSet::new();
+        for divergence in divergences {
+            if previous.contains(&divergence.key) {
+                confirmed.push(divergence);
+                continue;
+            }
+            warn!(
+                "Cached {} {} differs from the meta service, exists in meta service: {}",
+                resource, divergence.key, divergence.exists
+            );
+            record_broker_cache_divergence_detected(resource.as_str());
+            suspects.insert(divergence.key);
+        }
+
+        if suspects.is_empty() {
+            self.suspects.remove(&resource);
+        } else {
+            self.suspects.insert(resource, suspects);
+        }
+        confirmed
+    }
+}
+
+// A record that still exists in the meta service is re-created from its
+// data; one that is gone is deleted using the locally cached copy, unless
+// the source keeps such records.
+fn repair_record(
+    source: &dyn ConsistencySource,
+    divergence: ResourceDivergence,
+) -> Result<Option<UpdateCacheRecord>, CommonError> {
+    let (action_type, data) = if divergence.exists {
+        (BrokerUpdateCacheActionType::Create, divergence.data)
+    } else if source.delete_missing() {
+        match source.get(&divergence.key)? {
+            Some(record) => (BrokerUpdateCacheActionType::Delete, record.data),
+            None => return Ok(None),
+        }
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(UpdateCacheRecord {
+        action_type: action_type.into(),
+        resource_type: source.update_type().into(),
+        data,
+        version: 0,
+    }))
+}
+
+fn sample_keys(keys: &[String], cursor: usize, size: usize) -> (Vec<String>, usize) {
+    if keys.len() <= size {
+        return (keys.to_vec(), 0);
+    }
+    let start = if cursor >= keys.len() { 0 } else { cursor };
+    let end = (start + size).min(keys.len());
+    let next = if end == keys.len() { 0 } else { end };
+    (keys[start..end].to_vec(), next)
+}
+
+/// Checks the cache every `CONSISTENCY_CHECK_INTERVAL_MS` and hands each
+/// repair to `apply`, which applies it like a cache notification.
+pub async fn start_consistency_check_thread<F, Fut>(
+    checker: Arc<ConsistencyChecker>,
+    apply: F,
+    stop_send: broadcast::Sender<bool>,
+) where
+    F: Fn(UpdateCacheRecord) -> Fut + Copy,
+    Fut: Future<Output = ResultCommonError>,
+{
+    let ac_fn = async || -> ResultCommonError {
+        for (resource, record) in checker.check().await {
+            match apply(record).await {
+                Ok(()) => {
+                    info!("Repaired divergent {} cache record", resource);
+                    record_broker_cache_divergence_repaired(resource.as_str());
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to repair divergent {} cache record: {}",
+                        resource, e
+                    );
+                }
+            }
+        }
+        Ok(())
+    };
+
+    loop_select_ticket(ac_fn, CONSISTENCY_CHECK_INTERVAL_MS, &stop_send).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common_config::broker::default_broker_config;
+    use common_config::storage::StorageType;
+    use metadata_struct::topic::Topic;
+
+    fn divergence(key: &str, exists: bool) -> ResourceDivergence {
+        ResourceDivergence {
+            key: key.to_string(),
+            exists,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sample_keys() {
+        let keys: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        assert_eq!(sample_keys(&keys, 0, 10), (keys.clone(), 0));
+        assert_eq!(sample_keys(&keys, 0, 2), (keys[0..2].to_vec(), 2));
+        assert_eq!(sample_keys(&keys, 4, 2), (keys[4..5].to_vec(), 0));
+        assert_eq!(sample_keys(&keys, 9, 2), (keys[0..2].to_vec(), 2));
+    }
+
+    #[test]
+    fn test_confirm_requires_two_rounds() {
+        let checker = ConsistencyChecker::new(Arc::new(ClientPool::new(1)), Vec::new());
+        let resource = BootstrapResource::Topic;
+
+        let first = checker.confirm(
+            resource,
+            &HashSet::new(),
+            vec![divergence("t1/a", true), divergence("t1/b", false)],
+        );
+        assert!(first.is_empty());
+        let previous = checker.suspects.get(&resource).unwrap().clone();
+        assert_eq!(previous.len(), 2);
+
+        // "t1/b" caught up in the meantime, "t1/c" is new.
+        let second = checker.confirm(
+            resource,
+            &previous,
+            vec![divergence("t1/a", true), divergence("t1/c", true)],
+        );
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].key, "t1/a");
+        let suspects = checker.suspects.get(&resource).unwrap().clone();
+        assert_eq!(suspects, HashSet::from(["t1/c".to_string()]));
+
+        checker.confirm(resource, &suspects, Vec::new());
+        assert!(checker.suspects.get(&resource).is_none());
+    }
+
+    #[test]
+    fn test_topic_repair_record() {
+        let cache_manager = Arc::new(NodeCacheManager::new(default_broker_config()));
+        let topic = Topic::new("t1", "topic-1", StorageType::EngineMemory);
+        cache_manager.add_topic(&topic);
+        let source = TopicConsistencySource::new(cache_manager);
+        assert_eq!(source.keys(), vec!["t1/topic-1".to_string()]);
+        let cached = source.get("t1/topic-1").unwrap().unwrap();
+        assert_eq!(cached.digest, calc_digest(&topic).unwrap());
+
+        let record = repair_record(&source, divergence("t1/topic-1", false))
+            .unwrap()
+            .unwrap();
+        assert_eq!(record.action_type(), BrokerUpdateCacheActionType::Delete);
+        assert_eq!(record.resource_type(), BrokerUpdateCacheResourceType::Topic);
+        assert_eq!(Topic::decode(&record.data).unwrap(), topic);
+
+        let mut fresh = divergence("t1/topic-1", true);
+        fresh.data = topic.encode().unwrap();
+        let record = repair_record(&source, fresh).unwrap().unwrap();
+        assert_eq!(record.action_type(), BrokerUpdateCacheActionType::Create);
+
+        assert!(repair_record(&source, divergence("t1/gone", false))
+            .unwrap()
+            .is_none());
+    }
+}
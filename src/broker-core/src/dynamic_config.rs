@@ -16,19 +16,24 @@
 use crate::cluster::ClusterStorage;
 use bytes::Bytes;
 use common_base::error::common::CommonError;
+use common_base::logging::level::{parse_level, set_log_level_override};
 use common_config::broker::broker_config;
-use common_config::config::{
-    BrokerConfig, MetaRuntime, MqttFlappingDetect, MqttOfflineMessage, MqttProtocolConfig,
-    MqttSchema, MqttSlowSubscribeConfig, MqttSystemMonitor,
-};
+use common_config::common::Log;
+use common_config::config::BrokerConfig;
+use common_config::storage::StorageType;
 use grpc_clients::pool::ClientPool;
+use std::collections::HashSet;
+use std::str::FromStr;
 use std::sync::Arc;
 use strum_macros::{Display, EnumString};
+use tracing::{info, warn};
 
-#[derive(Default, Display, EnumString, Clone, Copy)]
+#[derive(Default, Display, EnumString, Clone, Copy, Debug, PartialEq)]
 pub enum ClusterDynamicConfig {
     #[default]
     MqttSlowSubscribeConfig,
+    MqttSlowRequest,
+    MqttKeepAlive,
     MqttFlappingDetect,
     MqttProtocol,
     MqttOfflineMessage,
@@ -37,75 +42,294 @@ pub enum ClusterDynamicConfig {
     MqttLimit,
     ClusterLimit,
     MetaRuntime,
+    Log,
+    StorageRouting,
+    MqttTrace,
 }
 
+impl ClusterDynamicConfig {
+    pub const ALL: [ClusterDynamicConfig; 14] = [
+        ClusterDynamicConfig::MqttSlowSubscribeConfig,
+        ClusterDynamicConfig::MqttSlowRequest,
+        ClusterDynamicConfig::MqttKeepAlive,
+        ClusterDynamicConfig::MqttFlappingDetect,
+        ClusterDynamicConfig::MqttProtocol,
+        ClusterDynamicConfig::MqttOfflineMessage,
+        ClusterDynamicConfig::MqttSystemMonitor,
+        ClusterDynamicConfig::MqttSchema,
+        ClusterDynamicConfig::MqttLimit,
+        ClusterDynamicConfig::ClusterLimit,
+        ClusterDynamicConfig::MetaRuntime,
+        ClusterDynamicConfig::Log,
+        ClusterDynamicConfig::StorageRouting,
+        ClusterDynamicConfig::MqttTrace,
+    ];
+
+    /// Resolve the resource name carried by a `ClusterResourceConfig` cache
+    /// update. Meta-service joins the resource path, so it arrives as
+    /// `cluster/<type>`.
+    pub fn from_resource(resource: &str) -> Option<Self> {
+        let name = resource.strip_prefix("cluster/").unwrap_or(resource);
+        ClusterDynamicConfig::from_str(name).ok()
+    }
+}
+
+/// Overlay every dynamic config stored in meta-service on the local config.
 pub async fn build_cluster_config(
     client_pool: &Arc<ClientPool>,
 ) -> Result<BrokerConfig, CommonError> {
+    let cluster_storage = ClusterStorage::new(client_pool.clone());
     let mut conf = broker_config().clone();
-    if let Some(data) = get_mqtt_protocol(client_pool).await? {
-        conf.mqtt_protocol = data;
-    }
-
-    if let Some(data) = get_slow_subscribe_config(client_pool).await? {
-        conf.mqtt_slow_subscribe = data;
-    }
-
-    if let Some(data) = get_flapping_detect(client_pool).await? {
-        conf.mqtt_flapping_detect = data;
-    }
-
-    if let Some(data) = get_offline_message(client_pool).await? {
-        conf.mqtt_offline_message = data;
-    }
-
-    if let Some(data) = get_schema(client_pool).await? {
-        conf.mqtt_schema = data;
-    }
+    for resource_type in ClusterDynamicConfig::ALL {
+        let data = cluster_storage
+            .get_dynamic_config(&resource_type.to_string())
+            .await?;
+        if data.is_empty() {
+            continue;
+        }
 
-    if let Some(data) = get_system_monitor(client_pool).await? {
-        conf.mqtt_system_monitor = data;
+        match merge_dynamic_config(&conf, resource_type, &data) {
+            Ok(merged) => conf = merged,
+            Err(e) => warn!(
+                "Ignoring invalid stored dynamic config {}: {}",
+                resource_type, e
+            ),
+        }
     }
-
     Ok(conf)
 }
 
-pub fn update_cluster_dynamic_config(
-    node_cache: &Arc<NodeCacheManager>,
+/// Parse `config` on top of `current` and validate the result. Nothing is
+/// applied, so this is also used to reject a change before it is saved.
+pub fn merge_dynamic_config(
+    current: &BrokerConfig,
     resource_type: ClusterDynamicConfig,
-    config: Bytes,
-) -> Result<(), CommonError> {
-    let mut new_config = node_cache.get_cluster_config();
+    config: &[u8],
+) -> Result<BrokerConfig, CommonError> {
+    let mut new_config = current.clone();
     match resource_type {
         ClusterDynamicConfig::ClusterLimit => {
-            new_config.cluster_limit = serde_json::from_slice(&config)?;
+            new_config.cluster_limit = serde_json::from_slice(config)?;
         }
         ClusterDynamicConfig::MqttSlowSubscribeConfig => {
-            new_config.mqtt_slow_subscribe = serde_json::from_slice(&config)?;
+            new_config.mqtt_slow_subscribe = serde_json::from_slice(config)?;
+        }
+        ClusterDynamicConfig::MqttSlowRequest => {
+            new_config.mqtt_slow_request = serde_json::from_slice(config)?;
+        }
+        ClusterDynamicConfig::MqttKeepAlive => {
+            new_config.mqtt_keep_alive = serde_json::from_slice(config)?;
         }
         ClusterDynamicConfig::MqttFlappingDetect => {
-            new_config.mqtt_flapping_detect = serde_json::from_slice(&config)?;
+            new_config.mqtt_flapping_detect = serde_json::from_slice(config)?;
         }
         ClusterDynamicConfig::MqttProtocol => {
-            new_config.mqtt_protocol = serde_json::from_slice(&config)?;
+            new_config.mqtt_protocol = serde_json::from_slice(config)?;
         }
         ClusterDynamicConfig::MqttOfflineMessage => {
-            new_config.mqtt_offline_message = serde_json::from_slice(&config)?;
+            new_config.mqtt_offline_message = serde_json::from_slice(config)?;
         }
         ClusterDynamicConfig::MqttSystemMonitor => {
-            new_config.mqtt_system_monitor = serde_json::from_slice(&config)?;
+            new_config.mqtt_system_monitor = serde_json::from_slice(config)?;
         }
         ClusterDynamicConfig::MqttSchema => {
-            new_config.mqtt_schema = serde_json::from_slice(&config)?;
+            new_config.mqtt_schema = serde_json::from_slice(config)?;
         }
         ClusterDynamicConfig::MqttLimit => {
-            new_config.mqtt_limit = serde_json::from_slice(&config)?;
+            new_config.mqtt_limit = serde_json::from_slice(config)?;
         }
         ClusterDynamicConfig::MetaRuntime => {
-            new_config.meta_runtime = serde_json::from_slice::<MetaRuntime>(&config)?;
+            new_config.meta_runtime = serde_json::from_slice(config)?;
         }
+        ClusterDynamicConfig::Log => {
+            new_config.log.level = serde_json::from_slice::<Log>(config)?.level;
+        }
+        ClusterDynamicConfig::StorageRouting => {
+            new_config.storage_routing = serde_json::from_slice(config)?;
+        }
+        ClusterDynamicConfig::MqttTrace => {
+            new_config.mqtt_trace = serde_json::from_slice(config)?;
+        }
+    }
+    validate_dynamic_config(resource_type, &new_config)?;
+    Ok(new_config)
+}
+
+/// Validate, store and apply a dynamic config change. If applying the side
+/// effects fails, the previous config is restored.
+pub fn update_cluster_dynamic_config(
+    node_cache: &Arc<NodeCacheManager>,
+    resource_type: ClusterDynamicConfig,
+    config: Bytes,
+) -> Result<(), CommonError> {
+    let previous = node_cache.get_cluster_config();
+    let new_config = merge_dynamic_config(&previous, resource_type, &config)?;
+    if let Err(e) = apply_dynamic_config(resource_type, &new_config) {
+        warn!(
+            "Failed to apply dynamic config {}, keeping the previous value: {}",
+            resource_type, e
+        );
+        let _ = apply_dynamic_config(resource_type, &previous);
+        return Err(e);
     }
     node_cache.set_cluster_config(new_config);
+    info!("Dynamic config {} updated", resource_type);
+    Ok(())
+}
+
+/// Apply the parts of a dynamic config that live outside the cluster config
+/// cache. Most settings are read from the cache on use and need nothing here.
+pub fn apply_dynamic_config(
+    resource_type: ClusterDynamicConfig,
+    config: &BrokerConfig,
+) -> Result<(), CommonError> {
+    if resource_type == ClusterDynamicConfig::Log {
+        set_log_level_override(&config.log.level)
+            .map_err(|e| CommonError::CommonError(e.to_string()))?;
+    }
+    Ok(())
+}
+
+pub fn validate_dynamic_config(
+    resource_type: ClusterDynamicConfig,
+    config: &BrokerConfig,
+) -> Result<(), CommonError> {
+    match resource_type {
+        ClusterDynamicConfig::ClusterLimit => {
+            let limit = &config.cluster_limit;
+            check_positive("max_network_connection", limit.max_network_connection)?;
+            check_positive(
+                "max_network_connection_rate",
+                limit.max_network_connection_rate as u64,
+            )?;
+            check_positive("max_connection_per_ip", limit.max_connection_per_ip)?;
+            check_positive(
+                "max_admin_http_uri_rate",
+                limit.max_admin_http_uri_rate as u64,
+            )?;
+        }
+        ClusterDynamicConfig::MqttLimit => {
+            for quota in [&config.mqtt_limit.cluster, &config.mqtt_limit.tenant] {
+                check_positive("max_connections_per_node", quota.max_connections_per_node)?;
+                check_positive("max_connection_rate", quota.max_connection_rate as u64)?;
+                check_positive("max_publish_rate", quota.max_publish_rate as u64)?;
+            }
+        }
+        ClusterDynamicConfig::MqttSystemMonitor => {
+            let monitor = &config.mqtt_system_monitor;
+            check_percent("os_cpu_high_watermark", monitor.os_cpu_high_watermark)?;
+            check_percent("os_memory_high_watermark", monitor.os_memory_high_watermark)?;
+            if !(0.0..=monitor.os_cpu_high_watermark).contains(&monitor.os_cpu_low_watermark) {
+                return Err(invalid(
+                    "os_cpu_low_watermark",
+                    monitor.os_cpu_low_watermark,
+                ));
+            }
+            if !(0.0..=monitor.os_memory_high_watermark).contains(&monitor.os_memory_low_watermark)
+            {
+                return Err(invalid(
+                    "os_memory_low_watermark",
+                    monitor.os_memory_low_watermark,
+                ));
+            }
+            check_positive("system_topic_interval_ms", monitor.system_topic_interval_ms)?;
+            check_positive(
+                "overload_check_interval_ms",
+                monitor.overload_check_interval_ms,
+            )?;
+            check_positive(
+                "metrics_history_interval_ms",
+                monitor.metrics_history_interval_ms,
+            )?;
+            check_positive(
+                "metrics_history_retention_sec",
+                monitor.metrics_history_retention_sec,
+            )?;
+        }
+        ClusterDynamicConfig::MqttSlowSubscribeConfig => {
+            check_positive("record_time", config.mqtt_slow_subscribe.record_time)?;
+        }
+        ClusterDynamicConfig::MqttSlowRequest => {
+            let slow_request = &config.mqtt_slow_request;
+            check_positive("threshold_ms", slow_request.threshold_ms)?;
+            check_positive("max_records", slow_request.max_records as u64)?;
+        }
+        ClusterDynamicConfig::MqttKeepAlive => {
+            let keep_alive = &config.mqtt_keep_alive;
+            check_positive("default_time", keep_alive.default_time as u64)?;
+            check_positive("max_time", keep_alive.max_time as u64)?;
+            if !(keep_alive.timeout_multiplier >= 1.0 && keep_alive.timeout_multiplier.is_finite())
+            {
+                return Err(invalid("timeout_multiplier", keep_alive.timeout_multiplier));
+            }
+        }
+        ClusterDynamicConfig::MqttFlappingDetect => {
+            let flapping = &config.mqtt_flapping_detect;
+            check_positive("window_time", flapping.window_time as u64)?;
+            check_positive("max_client_connections", flapping.max_client_connections)?;
+        }
+        ClusterDynamicConfig::MqttProtocol => {
+            let protocol = &config.mqtt_protocol;
+            check_positive("max_packet_size", protocol.max_packet_size as u64)?;
+            check_positive("receive_max", protocol.receive_max as u64)?;
+            check_positive("max_topic_levels", protocol.max_topic_levels as u64)?;
+            check_positive("max_topic_length", protocol.max_topic_length as u64)?;
+            if protocol.max_topic_length > u16::MAX as u32 {
+                return Err(invalid("max_topic_length", protocol.max_topic_length));
+            }
+            if protocol.default_session_expiry_interval > protocol.max_session_expiry_interval {
+                return Err(invalid(
+                    "default_session_expiry_interval",
+                    protocol.default_session_expiry_interval,
+                ));
+            }
+        }
+        ClusterDynamicConfig::MetaRuntime => {
+            let meta = &config.meta_runtime;
+            check_positive("heartbeat_check_time_ms", meta.heartbeat_check_time_ms)?;
+            if meta.heartbeat_timeout_ms <= meta.heartbeat_check_time_ms {
+                return Err(invalid("heartbeat_timeout_ms", meta.heartbeat_timeout_ms));
+            }
+        }
+        ClusterDynamicConfig::Log => {
+            if !config.log.level.trim().is_empty() {
+                parse_level(&config.log.level).map_err(|_| invalid("level", &config.log.level))?;
+            }
+        }
+        ClusterDynamicConfig::StorageRouting => {
+            for rule in &config.storage_routing.rules {
+                if rule.topic_filter.is_empty() {
+                    return Err(invalid("topic_filter", &rule.topic_filter));
+                }
+                if !matches!(
+                    rule.storage_type,
+                    StorageType::EngineMemory
+                        | StorageType::EngineSegment
+                        | StorageType::EngineRocksDB
+                        | StorageType::Memory
+                ) {
+                    return Err(invalid("storage_type", format!("{:?}", rule.storage_type)));
+                }
+            }
+        }
+        ClusterDynamicConfig::MqttTrace => {
+            let trace = &config.mqtt_trace;
+            check_positive("max_events", trace.max_events as u64)?;
+            let mut names = HashSet::new();
+            for rule in &trace.rules {
+                if rule.name.trim().is_empty() || !names.insert(rule.name.as_str()) {
+                    return Err(invalid("name", &rule.name));
+                }
+                if rule.tenant.is_empty() {
+                    return Err(invalid("tenant", &rule.tenant));
+                }
+                if rule.value.is_empty() {
+                    return Err(invalid("value", &rule.value));
+                }
+            }
+        }
+        ClusterDynamicConfig::MqttOfflineMessage | ClusterDynamicConfig::MqttSchema => {}
+    }
     Ok(())
 }
 
@@ -121,88 +345,120 @@ pub async fn save_cluster_dynamic_config(
     Ok(())
 }
 
-async fn get_mqtt_protocol(
-    client_pool: &Arc<ClientPool>,
-) -> Result<Option<MqttProtocolConfig>, CommonError> {
-    let cluster_storage = ClusterStorage::new(client_pool.clone());
-    let data = cluster_storage
-        .get_dynamic_config(&ClusterDynamicConfig::MqttProtocol.to_string())
-        .await?;
-
-    if !data.is_empty() {
-        return Ok(Some(serde_json::from_slice::<MqttProtocolConfig>(&data)?));
+fn check_positive(name: &str, value: u64) -> Result<(), CommonError> {
+    if value == 0 {
+        return Err(invalid(name, value));
     }
-
-    Ok(None)
+    Ok(())
 }
 
-async fn get_slow_subscribe_config(
-    client_pool: &Arc<ClientPool>,
-) -> Result<Option<MqttSlowSubscribeConfig>, CommonError> {
-    let cluster_storage = ClusterStorage::new(client_pool.clone());
-    let data = cluster_storage
-        .get_dynamic_config(&ClusterDynamicConfig::MqttSlowSubscribeConfig.to_string())
-        .await?;
-    if !data.is_empty() {
-        return Ok(Some(serde_json::from_slice::<MqttSlowSubscribeConfig>(
-            &data,
-        )?));
+fn check_percent(name: &str, value: f32) -> Result<(), CommonError> {
+    if !(value > 0.0 && value <= 100.0) {
+        return Err(invalid(name, value));
     }
-    Ok(None)
+    Ok(())
 }
 
-async fn get_flapping_detect(
-    client_pool: &Arc<ClientPool>,
-) -> Result<Option<MqttFlappingDetect>, CommonError> {
-    let cluster_storage = ClusterStorage::new(client_pool.clone());
-    let data = cluster_storage
-        .get_dynamic_config(&ClusterDynamicConfig::MqttFlappingDetect.to_string())
-        .await?;
-    if !data.is_empty() {
-        return Ok(Some(serde_json::from_slice::<MqttFlappingDetect>(&data)?));
-    }
-    Ok(None)
+fn invalid(name: &str, value: impl ToString) -> CommonError {
+    CommonError::InvalidParameterFormat(name.to_string(), value.to_string())
 }
 
-async fn get_offline_message(
-    client_pool: &Arc<ClientPool>,
-) -> Result<Option<MqttOfflineMessage>, CommonError> {
-    let cluster_storage = ClusterStorage::new(client_pool.clone());
-    let data = cluster_storage
-        .get_dynamic_config(&ClusterDynamicConfig::MqttOfflineMessage.to_string())
-        .await?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common_config::broker::default_broker_config;
 
-    if !data.is_empty() {
-        return Ok(Some(serde_json::from_slice::<MqttOfflineMessage>(&data)?));
+    #[test]
+    fn test_from_resource() {
+        assert_eq!(
+            ClusterDynamicConfig::from_resource("cluster/MqttLimit"),
+            Some(ClusterDynamicConfig::MqttLimit)
+        );
+        assert_eq!(
+            ClusterDynamicConfig::from_resource("Log"),
+            Some(ClusterDynamicConfig::Log)
+        );
+        assert_eq!(ClusterDynamicConfig::from_resource("cluster/Unknown"), None);
     }
 
-    Ok(None)
-}
+    #[test]
+    fn test_merge_dynamic_config() {
+        let current = default_broker_config();
 
-async fn get_schema(client_pool: &Arc<ClientPool>) -> Result<Option<MqttSchema>, CommonError> {
-    let cluster_storage = ClusterStorage::new(client_pool.clone());
-    let data = cluster_storage
-        .get_dynamic_config(&ClusterDynamicConfig::MqttSchema.to_string())
-        .await?;
+        let merged = merge_dynamic_config(
+            &current,
+            ClusterDynamicConfig::MqttSystemMonitor,
+            br#"{"enable": true, "os_cpu_high_watermark": 85.0}"#,
+        )
+        .unwrap();
+        assert!(merged.mqtt_system_monitor.enable);
+        assert_eq!(merged.mqtt_system_monitor.os_cpu_high_watermark, 85.0);
 
-    if !data.is_empty() {
-        return Ok(Some(serde_json::from_slice::<MqttSchema>(&data)?));
+        let merged = merge_dynamic_config(
+            &current,
+            ClusterDynamicConfig::Log,
+            br#"{"level": "debug", "log_path": "/tmp/ignored"}"#,
+        )
+        .unwrap();
+        assert_eq!(merged.log.level, "debug");
+        assert_eq!(merged.log.log_path, current.log.log_path);
     }
 
-    Ok(None)
-}
-
-async fn get_system_monitor(
-    client_pool: &Arc<ClientPool>,
-) -> Result<Option<MqttSystemMonitor>, CommonError> {
-    let cluster_storage = ClusterStorage::new(client_pool.clone());
-    let data = cluster_storage
-        .get_dynamic_config(&ClusterDynamicConfig::MqttSystemMonitor.to_string())
-        .await?;
-
-    if !data.is_empty() {
-        return Ok(Some(serde_json::from_slice::<MqttSystemMonitor>(&data)?));
+    #[test]
+    fn test_merge_dynamic_config_rejects_invalid() {
+        let current = default_broker_config();
+        for (resource_type, data) in [
+            (
+                ClusterDynamicConfig::MqttSystemMonitor,
+                &br#"{"os_cpu_high_watermark": 120.0}"#[..],
+            ),
+            (
+                ClusterDynamicConfig::MqttSystemMonitor,
+                &br#"{"os_cpu_high_watermark": 70.0, "os_cpu_low_watermark": 80.0}"#[..],
+            ),
+            (
+                ClusterDynamicConfig::ClusterLimit,
+                &br#"{"max_network_connection_rate": 0}"#[..],
+            ),
+            (
+                ClusterDynamicConfig::MqttSlowSubscribeConfig,
+                &br#"{"enable": true, "record_time": 0}"#[..],
+            ),
+            (
+                ClusterDynamicConfig::MqttSlowRequest,
+                &br#"{"enable": true, "max_records": 0}"#[..],
+            ),
+            (
+                ClusterDynamicConfig::MqttKeepAlive,
+                &br#"{"enable": true, "timeout_multiplier": 0.5}"#[..],
+            ),
+            (ClusterDynamicConfig::Log, &br#"{"level": "verbose"}"#[..]),
+            (ClusterDynamicConfig::MqttLimit, &b"not json"[..]),
+            (
+                ClusterDynamicConfig::StorageRouting,
+                &br#"{"rules": [{"topic_filter": "", "storage_type": "Memory"}]}"#[..],
+            ),
+            (
+                ClusterDynamicConfig::StorageRouting,
+                &br#"{"rules": [{"topic_filter": "audit/#", "storage_type": "S3"}]}"#[..],
+            ),
+            (
+                ClusterDynamicConfig::MqttTrace,
+                &br#"{"rules": [
+                    {"name": "t1", "tenant": "default", "trace_type": "topic", "value": "a/#"},
+                    {"name": "t1", "tenant": "default", "trace_type": "client_id", "value": "c1"}
+                ]}"#[..],
+            ),
+            (
+                ClusterDynamicConfig::MqttTrace,
+                &br#"{"rules": [{"name": "t1", "tenant": "default", "trace_type": "topic", "value": ""}]}"#[..],
+            ),
+        ] {
+            assert!(
+                merge_dynamic_config(&current, resource_type, data).is_err(),
+                "{} should be rejected",
+                resource_type
+            );
+        }
     }
-
-    Ok(None)
 }
@@ -19,13 +19,16 @@
 };
 use common_config::broker::broker_config;
 use grpc_clients::pool::ClientPool;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::broadcast;
 use tokio::time::{sleep, timeout};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
-use crate::{cache::NodeCacheManager, cluster::ClusterStorage};
+use crate::{
+    cache::NodeCacheManager, cluster::ClusterStorage, config_digest::config_section_digests,
+};
 
 pub async fn register_node(
     client_pool: &Arc<ClientPool>,
@@ -95,8 +98,18 @@ pub async fn report_heartbeat(
     let ac_fn = async || -> ResultCommonError {
         let cluster_storage = ClusterStorage::new(client_pool.clone());
         let config = broker_config();
+        let config_digests = config_section_digests(&cache_manager.get_cluster_config())
+            .unwrap_or_else(|e| {
+                warn!("Failed to compute config digests: {}", e);
+                HashMap::new()
+            });
 
-        match timeout(Duration::from_secs(3), cluster_storage.heartbeat()).await {
+        match timeout(
+            Duration::from_secs(3),
+            cluster_storage.heartbeat(cache_manager.applied_cache_versions(), config_digests),
+        )
+        .await
+        {
             Ok(Ok(())) => {
                 debug!("Heartbeat report success for node {}", config.broker_id);
             }
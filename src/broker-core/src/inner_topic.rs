@@ -19,3 +19,5 @@
 pub const DELAY_QUEUE_INDEX_TOPIC: &str = "$delay-queue-index";
 pub const AGENT_REPORT_INFO_TOPIC: &str = "$agent-report-info";
 pub const QOS2_INNER_TOPIC: &str = "$sys/qos2-inner-topic";
+pub const INFLIGHT_MESSAGE_TOPIC: &str = "$inflight-message";
+pub const METRICS_HISTORY_TOPIC: &str = "$metrics-history";
@@ -15,6 +15,8 @@
 #![allow(clippy::result_large_err)]
 pub mod cache;
 pub mod cluster;
+pub mod config_digest;
+pub mod consistency;
 pub mod dynamic_config;
 pub mod heartbeat;
 pub mod inner_topic;
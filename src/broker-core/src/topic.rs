@@ -82,6 +82,7 @@ pub async fn get_topic(
         let request = ListTopicRequest {
             tenant: tenant.to_owned(),
             topic_name: topic_name.to_owned(),
+            ..Default::default()
         };
 
         let mut data_stream =
@@ -12,18 +12,20 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::load_cache::resync_metadata_cache;
 use crate::update_cache::update_cache;
 use metadata_struct::storage::record::StorageRecord;
 use mqtt_broker::{
     broker::MqttBrokerServerParams, core::inner::send_last_will_message_by_req,
-    core::qos::get_qos_data_by_req,
+    core::qos::get_qos_data_by_req, subscribe::forward::receive_forward_message_by_req,
 };
 use nats_broker::broker::NatsBrokerServerParams;
 use nats_broker::push::nats_fanout::send_packet;
 use protocol::broker::broker::{
-    broker_service_server::BrokerService, GetQosDataByClientIdReply, GetQosDataByClientIdRequest,
-    GetShardSegmentDeleteStatusReply, GetShardSegmentDeleteStatusRequest, QueryReplicaLeoReply,
-    QueryReplicaLeoRequest, SendLastWillMessageReply, SendLastWillMessageRequest,
+    broker_service_server::BrokerService, ForwardMqttMessageReply, ForwardMqttMessageRequest,
+    GetQosDataByClientIdReply, GetQosDataByClientIdRequest, GetShardSegmentDeleteStatusReply,
+    GetShardSegmentDeleteStatusRequest, QueryReplicaLeoReply, QueryReplicaLeoRequest,
+    ResyncCacheReply, ResyncCacheRequest, SendLastWillMessageReply, SendLastWillMessageRequest,
     SendNatsShareGroupMessageReply, SendNatsShareGroupMessageRequest, ShardSegmentDeleteStatus,
     UpdateCacheReply, UpdateCacheRequest,
 };
@@ -33,7 +35,7 @@
 use storage_engine::isr::handle_fetch::FetchEngines;
 use storage_engine::StorageEngineParams;
 use tonic::{Request, Response, Status};
-use tracing::warn;
+use tracing::{info, warn};
 
 pub struct GrpcBrokerService {
     mqtt_params: MqttBrokerServerParams,
@@ -63,7 +65,7 @@ async fn update_cache(
     ) -> Result<Response<UpdateCacheReply>, Status> {
         let req = request.into_inner();
         for record in req.records.iter() {
-            if let Err(e) = update_cache(
+            match update_cache(
                 &self.mqtt_params,
                 &self.nats_params,
                 &self.storage_params,
@@ -71,12 +73,20 @@ async fn update_cache(
             )
             .await
             {
-                warn!(
+                Ok(()) => {
+                    if record.version > 0 {
+                        self.mqtt_params.node_cache.record_applied_cache_version(
+                            record.resource_type().as_str_name(),
+                            record.version,
+                        );
+                    }
+                }
+                Err(e) => warn!(
                     "Failed to update cache for resource type {:?}, action: {:?}, error: {:?}",
                     record.resource_type(),
                     record.action_type(),
                     e
-                );
+                ),
             }
         }
 
@@ -194,4 +204,31 @@ async fn query_replica_leo(
             available: state.available,
         }))
     }
+
+    async fn resync_cache(
+        &self,
+        request: Request<ResyncCacheRequest>,
+    ) -> Result<Response<ResyncCacheReply>, Status> {
+        let req = request.into_inner();
+        info!(
+            "Resyncing metadata cache of node {} after missed cache notifications",
+            req.node_id
+        );
+        resync_metadata_cache(&self.mqtt_params, &self.nats_params, &self.storage_params)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        // The reloaded cache is newer than any notification applied so far.
+        self.mqtt_params.node_cache.clear_applied_cache_versions();
+        Ok(Response::new(ResyncCacheReply {}))
+    }
+
+    async fn forward_mqtt_message(
+        &self,
+        request: Request<ForwardMqttMessageRequest>,
+    ) -> Result<Response<ForwardMqttMessageReply>, Status> {
+        let req = request.into_inner();
+        receive_forward_message_by_req(&self.mqtt_params.subscribe_manager, &req)
+            .map_err(|e| Status::internal(e.to_string()))
+            .map(Response::new)
+    }
 }
@@ -13,15 +13,28 @@
 // limitations under the License.
 
 use crate::connection::network_connection_gc;
-use common_base::{node_status::NodeStatus, task::TaskKind};
+use crate::update_cache::update_cache;
+use broker_core::consistency::{
+    start_consistency_check_thread, ConsistencyChecker, TopicConsistencySource,
+};
+use common_base::{node_status::NodeStatus, runtime::RuntimePool, task::TaskKind};
 use common_group::storage::start_offset_sync_task;
-use common_security::sync::start_auth_sync_thread;
+use common_metrics::core::{
+    exporter::start_metrics_exporter,
+    push::{start_metrics_push, MetricsPusher},
+};
+use common_security::sync::{start_auth_sync_thread, start_password_rehash_thread};
 use connector::start_connector;
 use delay_message::manager::start_delay_message_manager_thread;
 use delay_task::start_delay_task_manager_thread;
+use mqtt_broker::core::consistency::{SessionConsistencySource, UserConsistencySource};
 use network_server::command::CommandRegistry;
 use network_server::common::handler::handler_process;
+use protocol::broker::broker::UpdateCacheRecord;
+use rocksdb_engine::backup::schedule::start_rocksdb_backup;
+use rocksdb_engine::metrics::column_family::start_column_family_metrics_collection;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 use system_info::{start_system_info_collection, start_tokio_runtime_info_collection};
 use tokio::{sync::broadcast, time::sleep};
@@ -35,6 +48,11 @@ extern "C" fn handle_term_signal(_sig: libc::c_int) {
     SHUTDOWN_REQUESTED.store(true, Ordering::SeqCst);
 }
 
+/// Ask a running broker to stop, as SIGINT/SIGTERM would.
+pub fn request_shutdown() {
+    SHUTDOWN_REQUESTED.store(true, Ordering::SeqCst);
+}
+
 /// Register an OS-level SIGINT/SIGTERM handler via libc `sigaction`.
 ///
 /// Uses libc rather than `tokio::signal` because with multiple Tokio runtimes
@@ -113,7 +131,7 @@ pub async fn start_background_services(
         let delay_task_manager = self.delay_task_manager.clone();
         let node_call_manager = self.node_call_manager.clone();
         let task_supervisor = self.task_supervisor.clone();
-        self.server_runtime.spawn(async move {
+        self.background_runtime.spawn(async move {
             if let Err(e) = start_delay_task_manager_thread(
                 &rocksdb_engine_handler,
                 &delay_task_manager,
@@ -141,18 +159,22 @@ pub async fn start_background_services(
         // connection gc
         let connection_manager = self.connection_manager.clone();
         let tx = stop.clone();
-        self.task_supervisor
-            .spawn(TaskKind::NetworkConnectionGC.to_string(), async move {
-                network_connection_gc(connection_manager, tx).await
-            });
+        self.task_supervisor.spawn_on(
+            TaskKind::NetworkConnectionGC.to_string(),
+            RuntimePool::Background,
+            async move { network_connection_gc(connection_manager, tx).await },
+        );
 
         // offset async commit
         let offset_manager = self.offset_manager.clone();
         let stop_send = stop.clone();
-        self.task_supervisor
-            .spawn(TaskKind::OffsetAsyncCommit.to_string(), async move {
+        self.task_supervisor.spawn_on(
+            TaskKind::OffsetAsyncCommit.to_string(),
+            RuntimePool::Background,
+            async move {
                 start_offset_sync_task(offset_manager, stop_send).await;
-            });
+            },
+        );
 
         // sync auth info
         start_auth_sync_thread(
@@ -160,19 +182,34 @@ pub async fn start_background_services(
             self.task_supervisor.clone(),
             stop.clone(),
         );
+        start_password_rehash_thread(
+            self.mqtt_params.security_manager.clone(),
+            self.client_pool.clone(),
+            self.task_supervisor.clone(),
+            stop.clone(),
+        );
 
         // system info collection
         let tx = stop.clone();
-        self.task_supervisor
-            .spawn(TaskKind::SystemInfoCollection.to_string(), async move {
+        self.task_supervisor.spawn_on(
+            TaskKind::SystemInfoCollection.to_string(),
+            RuntimePool::Background,
+            async move {
                 start_system_info_collection(tx, monitor_interval_ms).await;
-            });
+            },
+        );
 
         // tokio runtime info collection
         let runtime_handles = vec![
             ("server".to_string(), self.server_runtime.handle().clone()),
             ("meta".to_string(), self.meta_runtime.handle().clone()),
             ("broker".to_string(), self.broker_runtime.handle().clone()),
+            ("engine".to_string(), self.engine_runtime.handle().clone()),
+            ("push".to_string(), self.push_runtime.handle().clone()),
+            (
+                "background".to_string(),
+                self.background_runtime.handle().clone(),
+            ),
         ];
         let tx = stop.clone();
         self.task_supervisor.spawn(
@@ -182,12 +219,101 @@ pub async fn start_background_services(
             },
         );
 
+        // prometheus dedicated listener
+        let prometheus = self.config.prometheus.clone();
+        if prometheus.enable {
+            let port = prometheus.port;
+            let tx = stop.clone();
+            self.server_runtime.spawn(async move {
+                start_metrics_exporter(port, tx).await;
+            });
+        }
+
+        // prometheus push
+        if prometheus.push.enable {
+            match MetricsPusher::new(
+                &prometheus.push,
+                &self.config.cluster_name,
+                &self.config.broker_id.to_string(),
+            ) {
+                Ok(pusher) => {
+                    let interval_ms = prometheus.push.interval_ms;
+                    let tx = stop.clone();
+                    self.task_supervisor.spawn_on(
+                        TaskKind::MetricsPush.to_string(),
+                        RuntimePool::Background,
+                        async move {
+                            start_metrics_push(pusher, interval_ms, tx).await;
+                        },
+                    );
+                }
+                Err(e) => error!("Failed to start Prometheus metrics push: {}", e),
+            }
+        }
+
+        // rocksdb column family metrics
+        let rocksdb_engine_handler = self.rocksdb_engine_handler.clone();
+        let tx = stop.clone();
+        self.task_supervisor.spawn_on(
+            TaskKind::RocksDBColumnFamilyMetrics.to_string(),
+            RuntimePool::Background,
+            async move {
+                start_column_family_metrics_collection(
+                    rocksdb_engine_handler,
+                    tx,
+                    monitor_interval_ms,
+                )
+                .await;
+            },
+        );
+
+        // rocksdb backup
+        let rocksdb_engine_handler = self.rocksdb_engine_handler.clone();
+        let tx = stop.clone();
+        self.task_supervisor.spawn_on(
+            TaskKind::RocksDBBackup.to_string(),
+            RuntimePool::Background,
+            async move {
+                start_rocksdb_backup(rocksdb_engine_handler, tx).await;
+            },
+        );
+
+        // cache consistency check
+        let checker = Arc::new(ConsistencyChecker::new(
+            self.client_pool.clone(),
+            vec![
+                Arc::new(TopicConsistencySource::new(self.broker_cache.clone())),
+                Arc::new(UserConsistencySource::new(
+                    self.mqtt_params.security_manager.clone(),
+                )),
+                Arc::new(SessionConsistencySource::new(
+                    self.mqtt_params.cache_manager.clone(),
+                )),
+            ],
+        ));
+        let mqtt_params = self.mqtt_params.clone();
+        let nats_params = self.nats_params.clone();
+        let engine_params = self.engine_params.clone();
+        let tx = stop.clone();
+        self.task_supervisor.spawn_on(
+            TaskKind::BrokerCacheConsistencyCheck.to_string(),
+            RuntimePool::Background,
+            async move {
+                let (mqtt_params, nats_params, engine_params) =
+                    (&mqtt_params, &nats_params, &engine_params);
+                let apply = |record: UpdateCacheRecord| async move {
+                    update_cache(mqtt_params, nats_params, engine_params, &record).await
+                };
+                start_consistency_check_thread(checker, apply, tx).await;
+            },
+        );
+
         // connector
         let message_storage = self.mqtt_params.storage_driver_manager.clone();
         let connector_manager = self.mqtt_params.connector_manager.clone();
         let client_pool = self.client_pool.clone();
         let task_supervisor = self.task_supervisor.clone();
-        self.server_runtime.spawn(async move {
+        self.background_runtime.spawn(async move {
             start_connector(
                 &client_pool,
                 &message_storage,
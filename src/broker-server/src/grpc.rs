@@ -13,12 +13,16 @@
 // limitations under the License.
 
 use crate::cluster_service::GrpcBrokerService;
+use crate::grpc_tls::{tls_incoming, GrpcTlsConnectInfo};
 use axum::http::{self};
 use common_base::error::common::CommonError;
 use common_base::role::is_meta_node;
 use common_base::tools::now_millis;
 use common_config::broker::broker_config;
+use common_config::config::MetaServiceAuth;
 use common_metrics::grpc::{extract_grpc_status_code, parse_grpc_path, record_grpc_request};
+use grpc_clients::tls::{grpc_tls, start_grpc_tls_reload_thread};
+use meta_service::server::auth::authorize;
 use meta_service::server::service_common::GrpcPlacementService;
 use meta_service::server::service_engine::GrpcEngineService;
 use meta_service::server::service_mq9::GrpcMq9Service;
@@ -34,9 +38,12 @@
 use protocol::meta::meta_service_mqtt::mqtt_service_server::MqttServiceServer;
 use protocol::meta::meta_service_nats::nats_service_server::NatsServiceServer;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::Duration;
 use storage_engine::StorageEngineParams;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast;
 use tonic::transport::Server;
 use tower::{Layer, Service};
 use tracing::{debug, info, warn};
@@ -52,8 +59,10 @@ pub async fn start_grpc_server(
 ) -> Result<(), CommonError> {
     let ip = format!("0.0.0.0:{grpc_port}").parse()?;
     let cors_layer = tower_http::cors::CorsLayer::very_permissive();
+    let config = broker_config();
     let layer = tower::ServiceBuilder::new()
         .layer(BaseMiddlewareLayer::default())
+        .layer(MetaAuthLayer::new(config.meta_service_auth.clone()))
         .into_inner();
 
     let grpc_max_decoding_message_size = 268435456;
@@ -75,7 +84,6 @@ pub async fn start_grpc_server(
             .max_decoding_message_size(grpc_max_decoding_message_size),
         );
 
-    let config = broker_config();
     if is_meta_node(&config.roles) {
         route = route
             .add_service(
@@ -100,7 +108,21 @@ pub async fn start_grpc_server(
             );
     }
 
-    route.serve(ip).await?;
+    let Some(tls) = grpc_tls() else {
+        route.serve(ip).await?;
+        return Ok(());
+    };
+
+    info!(
+        "Broker Grpc Server TLS enabled, mutual authentication: {}",
+        tls.is_mutual()
+    );
+    let (tls_stop_send, _) = broadcast::channel(2);
+    start_grpc_tls_reload_thread(tls.clone(), tls_stop_send.clone());
+    let listener = TcpListener::bind(ip).await?;
+    let result = route.serve_with_incoming(tls_incoming(listener, tls)).await;
+    let _ = tls_stop_send.send(true);
+    result?;
     Ok(())
 }
 
@@ -224,5 +246,81 @@ fn call(&mut self, req: http::Request<ReqBody>) -> Self::Future {
     }
 }
 
+/// Authorizes calls to the meta service gRPC services, see
+/// [`meta_service::server::auth`]. Rejected calls never reach the service.
+#[derive(Debug, Clone)]
+struct MetaAuthLayer {
+    conf: Arc<MetaServiceAuth>,
+}
+
+impl MetaAuthLayer {
+    fn new(conf: MetaServiceAuth) -> Self {
+        MetaAuthLayer {
+            conf: Arc::new(conf),
+        }
+    }
+}
+
+impl<S> Layer<S> for MetaAuthLayer {
+    type Service = MetaAuthMiddleware<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        MetaAuthMiddleware {
+            inner: service,
+            conf: self.conf.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MetaAuthMiddleware<S> {
+    inner: S,
+    conf: Arc<MetaServiceAuth>,
+}
+
+impl<S, ReqBody, ResBody> Service<http::Request<ReqBody>> for MetaAuthMiddleware<S>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+    ResBody: Default + Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: http::Request<ReqBody>) -> Self::Future {
+        if self.conf.enable {
+            let (service, method) = parse_grpc_path(req.uri().path()).unwrap_or_default();
+            let authorization = req
+                .headers()
+                .get(http::header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok());
+            let peer_ids = req
+                .extensions()
+                .get::<GrpcTlsConnectInfo>()
+                .map(|info| info.peer_ids.as_slice())
+                .unwrap_or_default();
+            if let Err(status) = authorize(&self.conf, &service, &method, authorization, peer_ids) {
+                warn!(
+                    "Rejected gRPC call. service={}, method={}, reason={}",
+                    service,
+                    method,
+                    status.message()
+                );
+                return Box::pin(async move { Ok(status.into_http()) });
+            }
+        }
+
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        Box::pin(async move { inner.call(req).await })
+    }
+}
+
 #[cfg(test)]
 mod test {}
@@ -0,0 +1,151 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use futures::Stream;
+use grpc_clients::tls::{peer_spiffe_ids, GrpcTlsContext};
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::time::{sleep, timeout};
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+use tonic::transport::server::{Connected, TcpConnectInfo};
+use tracing::{debug, error};
+
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+const TLS_ACCEPT_QUEUE_SIZE: usize = 128;
+
+/// A server-side TLS connection of the gRPC port.
+pub struct GrpcTlsStream {
+    inner: TlsStream<TcpStream>,
+    peer_ids: Arc<Vec<String>>,
+}
+
+/// Connection info of a TLS connection, available as a request extension.
+#[derive(Debug, Clone)]
+pub struct GrpcTlsConnectInfo {
+    pub tcp: TcpConnectInfo,
+    /// SPIFFE ids of the verified client certificate, empty without mutual TLS.
+    pub peer_ids: Arc<Vec<String>>,
+}
+
+impl Connected for GrpcTlsStream {
+    type ConnectInfo = GrpcTlsConnectInfo;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        GrpcTlsConnectInfo {
+            tcp: self.inner.get_ref().0.connect_info(),
+            peer_ids: self.peer_ids.clone(),
+        }
+    }
+}
+
+impl AsyncRead for GrpcTlsStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for GrpcTlsStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// Accept TLS connections on `listener`. Handshakes run in their own tasks so
+/// a slow or silent client cannot hold up the accept loop; connections that
+/// fail the handshake or the peer identity check are dropped.
+pub fn tls_incoming(
+    listener: TcpListener,
+    tls: Arc<GrpcTlsContext>,
+) -> impl Stream<Item = io::Result<GrpcTlsStream>> {
+    let (tx, mut rx) = mpsc::channel(TLS_ACCEPT_QUEUE_SIZE);
+    tokio::spawn(Box::pin(async move {
+        loop {
+            let (tcp, addr) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    error!("gRPC TLS listener accept failed: {}", e);
+                    sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+            if let Err(e) = tcp.set_nodelay(true) {
+                debug!("Failed to set TCP_NODELAY for gRPC peer {}: {}", addr, e);
+            }
+
+            let tls = tls.clone();
+            let tx = tx.clone();
+            tokio::spawn(Box::pin(async move {
+                let acceptor = TlsAcceptor::from(tls.server_config());
+                let stream = match timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(tcp)).await {
+                    Ok(Ok(stream)) => stream,
+                    Ok(Err(e)) => {
+                        debug!("gRPC TLS handshake with {} failed: {}", addr, e);
+                        return;
+                    }
+                    Err(_) => {
+                        debug!("gRPC TLS handshake with {} timed out", addr);
+                        return;
+                    }
+                };
+
+                let peer_certs = stream.get_ref().1.peer_certificates();
+                if tls.is_mutual() {
+                    if let Err(e) = tls.verify_peer(peer_certs) {
+                        error!("Rejected gRPC connection from {}: {}", addr, e);
+                        return;
+                    }
+                }
+                let peer_ids = Arc::new(peer_spiffe_ids(peer_certs));
+                let _ = tx
+                    .send(GrpcTlsStream {
+                        inner: stream,
+                        peer_ids,
+                    })
+                    .await;
+            }));
+
+            if tx.is_closed() {
+                return;
+            }
+        }
+    }));
+
+    async_stream::stream! {
+        while let Some(stream) = rx.recv().await {
+            yield Ok(stream);
+        }
+    }
+}
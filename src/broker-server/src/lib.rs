@@ -19,10 +19,12 @@
     heartbeat::{check_meta_service_status, register_node_and_start_heartbeat},
 };
 use common_base::{
+    fault::fault_injector,
     role::is_broker_node,
     runtime::{
-        create_runtime, resolve_broker_worker_threads, resolve_meta_worker_threads,
-        resolve_server_worker_threads,
+        create_runtime, register_runtime_pool, resolve_background_worker_threads,
+        resolve_broker_worker_threads, resolve_meta_worker_threads, resolve_push_worker_threads,
+        resolve_server_worker_threads, resolve_storage_worker_threads, RuntimePool,
     },
     task::TaskSupervisor,
 };
@@ -34,7 +36,9 @@
 use common_security::manager::SecurityManager;
 use delay_message::manager::DelayMessageManager;
 use delay_task::manager::DelayTaskManager;
+use grpc_clients::auth::init_node_token;
 use grpc_clients::pool::ClientPool;
+use grpc_clients::tls::init_grpc_tls;
 use kafka_broker::broker::KafkaBrokerServerParams;
 use llm_engine::embedding::fastembed;
 use meta_service::MetaServiceServerParams;
@@ -43,19 +47,23 @@
 use network_server::command::CommandRegistry;
 use network_server::common::channel::RequestChannel;
 use network_server::common::connection_manager::ConnectionManager as NetworkConnectionManager;
+use network_server::common::write_buffer::WriteCoalesceConfig;
 use node_call::NodeCallManager;
 use rate_limit::global::GlobalRateLimiterManager;
 use rocksdb_engine::{
+    backup::restore_on_start,
     rocksdb::RocksDBEngine,
+    storage::broker::migrate_broker_column_families,
     storage::family::{column_family_list, rocksdb_data_fold},
 };
 use search_engine::lancedb;
 use std::sync::Arc;
+use std::time::Duration;
 use storage_adapter::driver::StorageDriverManager;
 use storage_adapter::topic::init_inner_topics;
 use storage_engine::StorageEngineParams;
 use tokio::{runtime::Runtime, sync::broadcast};
-use tracing::error;
+use tracing::{error, info, warn};
 
 mod amqp;
 mod cluster_service;
@@ -64,6 +72,7 @@
 mod daemon;
 mod engine;
 mod grpc;
+mod grpc_tls;
 mod kafka;
 mod load_cache;
 mod meta;
@@ -72,9 +81,13 @@
 mod server;
 mod update_cache;
 
+pub use daemon::request_shutdown;
+
 /// Shared infrastructure created before any protocol or storage layer.
 struct BaseComponents {
     server_runtime: Runtime,
+    push_runtime: Runtime,
+    background_runtime: Runtime,
     client_pool: Arc<ClientPool>,
     rocksdb_engine_handler: Arc<RocksDBEngine>,
     global_rate_limiter: Arc<GlobalRateLimiterManager>,
@@ -91,6 +104,10 @@ pub struct BrokerServer {
     /// Dedicated runtime for broker tasks.
     pub(crate) broker_runtime: Runtime,
     pub(crate) engine_runtime: Runtime,
+    /// Subscription parsing and push loops, kept apart from packet handling.
+    pub(crate) push_runtime: Runtime,
+    /// Maintenance tasks: GC, metrics collection, delay tasks and connectors.
+    pub(crate) background_runtime: Runtime,
     pub(crate) meta_params: MetaServiceServerParams,
     pub(crate) mqtt_params: MqttBrokerServerParams,
     pub(crate) kafka_params: KafkaBrokerServerParams,
@@ -121,6 +138,10 @@ pub fn new() -> Self {
         init_metrics();
 
         let config: &BrokerConfig = broker_config();
+        if config.fault_injection.enable {
+            warn!("Fault injection is enabled, faults can be injected through the admin API");
+        }
+        fault_injector().set_enabled(config.fault_injection.enable);
 
         let (base, meta_runtime, broker_runtime, engine_runtime) = Self::init_base(config);
 
@@ -146,6 +167,8 @@ pub fn new() -> Self {
             meta_runtime,
             broker_runtime,
             engine_runtime,
+            push_runtime: base.push_runtime,
+            background_runtime: base.background_runtime,
             broker_cache: base.broker_cache,
             client_pool: base.client_pool,
             rocksdb_engine_handler: base.rocksdb_engine_handler,
@@ -169,12 +192,29 @@ pub fn new() -> Self {
     /// Initialize shared infrastructure: runtimes, RocksDB, connection manager,
     /// rate limiter, offset manager, and node call manager.
     fn init_base(config: &BrokerConfig) -> (BaseComponents, Runtime, Runtime, Runtime) {
+        // must run before any client pool opens a channel
+        if let Err(e) = init_grpc_tls(&config.grpc_tls) {
+            panic!("Failed to initialize gRPC TLS: {e}");
+        }
+        if let Err(e) = init_node_token(&config.meta_service_auth.node_token) {
+            panic!("Failed to initialize gRPC node token: {e}");
+        }
         let client_pool = Arc::new(ClientPool::new(config.runtime.channels_per_address));
+        match restore_on_start(config) {
+            Ok(Some(backup_id)) => info!("Restored the broker RocksDB from backup {backup_id}"),
+            Ok(None) => {}
+            Err(e) => panic!("Failed to restore the broker RocksDB: {e}"),
+        }
         let rocksdb_engine_handler = Arc::new(RocksDBEngine::new(
             &rocksdb_data_fold(&config.data_path),
             100000,
             column_family_list(),
         ));
+        match migrate_broker_column_families(&rocksdb_engine_handler) {
+            Ok(0) => {}
+            Ok(moved) => info!("Moved {moved} keys out of the broker column family"),
+            Err(e) => panic!("Failed to migrate the broker column family: {e}"),
+        }
         let global_rate_limiter = Arc::new(
             GlobalRateLimiterManager::new(config.cluster_limit.max_network_connection_rate)
                 .unwrap_or_else(|e| panic!("Failed to create GlobalRateLimiterManager: {e}")),
@@ -184,12 +224,18 @@ fn init_base(config: &BrokerConfig) -> (BaseComponents, Runtime, Runtime, Runtim
             resolve_server_worker_threads(config.runtime.server_worker_threads),
         );
         let broker_cache = Arc::new(NodeCacheManager::new(config.clone()));
-        let connection_manager = Arc::new(NetworkConnectionManager::new());
+        let connection_manager = Arc::new(NetworkConnectionManager::new().with_write_coalesce(
+            WriteCoalesceConfig {
+                window: Duration::from_micros(config.runtime.write_coalesce_window_us),
+                max_buffer_bytes: config.runtime.write_buffer_max_bytes,
+            },
+        ));
         let task_supervisor = Arc::new(TaskSupervisor::new());
         let offset_manager = Arc::new(OffsetManager::new(client_pool.clone()));
         let node_call_manager = Arc::new(NodeCallManager::new(
             client_pool.clone(),
             broker_cache.clone(),
+            rocksdb_engine_handler.clone(),
         ));
 
         // meta_runtime is created here so that Raft::new() tasks (spawned via
@@ -205,11 +251,27 @@ fn init_base(config: &BrokerConfig) -> (BaseComponents, Runtime, Runtime, Runtim
         );
         let engine_runtime = create_runtime(
             "engine-runtime",
-            resolve_server_worker_threads(config.runtime.server_worker_threads),
+            resolve_storage_worker_threads(config.runtime.storage_worker_threads),
+        );
+        let push_runtime = create_runtime(
+            "push-runtime",
+            resolve_push_worker_threads(config.runtime.push_worker_threads),
         );
+        let background_runtime = create_runtime(
+            "background-runtime",
+            resolve_background_worker_threads(config.runtime.background_worker_threads),
+        );
+
+        // Partitioned pools let components pick their runtime without holding handles.
+        register_runtime_pool(RuntimePool::Network, broker_runtime.handle().clone());
+        register_runtime_pool(RuntimePool::Storage, engine_runtime.handle().clone());
+        register_runtime_pool(RuntimePool::Push, push_runtime.handle().clone());
+        register_runtime_pool(RuntimePool::Background, background_runtime.handle().clone());
 
         let base = BaseComponents {
             server_runtime,
+            push_runtime,
+            background_runtime,
             client_pool,
             rocksdb_engine_handler,
             global_rate_limiter,
@@ -247,9 +309,12 @@ fn init_storage(
         let storage_driver_manager = {
             let om = base.offset_manager.clone();
             let seh = engine_params.storage_engine_handler.clone();
+            let storage_runtime = &broker_config().storage_runtime;
+            let memory_adapter = storage_runtime.memory_adapter.clone();
+            let edge_mode = storage_runtime.edge_mode;
             meta_runtime.block_on(async move {
                 match StorageDriverManager::new(om, seh).await {
-                    Ok(s) => Arc::new(s),
+                    Ok(s) => Arc::new(s.with_memory_adapter(memory_adapter, edge_mode)),
                     Err(e) => {
                         error!("Failed to build message storage driver: {}", e);
                         std::process::exit(1);
@@ -258,12 +323,18 @@ fn init_storage(
             })
         };
 
-        let delay_task_manager = Arc::new(DelayTaskManager::new(
-            base.client_pool.clone(),
-            storage_driver_manager.clone(),
-            config.delay_task.delay_task_queue_num as u32,
-            config.delay_task.delay_task_handler_concurrency,
-        ));
+        let delay_task_manager = Arc::new(
+            DelayTaskManager::new(
+                base.client_pool.clone(),
+                storage_driver_manager.clone(),
+                config.delay_task.delay_task_queue_num as u32,
+                config.delay_task.delay_task_handler_concurrency,
+            )
+            .with_scheduling(
+                config.delay_task.delay_task_type_concurrency.clone(),
+                config.delay_task.delay_task_starvation_timeout_ms,
+            ),
+        );
 
         let delay_message_manager = meta_runtime.block_on(async {
             match DelayMessageManager::new(
@@ -387,7 +458,14 @@ fn init_protocol_params(
     pub fn start(&self) {
         // Register the shutdown-signal handler first so signals during startup are captured.
         daemon::register_shutdown_listener();
+        self.run();
+    }
 
+    /// Start every service and block until [`request_shutdown`] is called.
+    /// Unlike [`BrokerServer::start`] no signal handler or force-exit watchdog
+    /// is installed, so the broker can be embedded in another process, e.g. a
+    /// test harness.
+    pub fn run(&self) {
         // Phase 1: Network-facing servers
         self.start_grpc_server();
         self.start_admin_server();
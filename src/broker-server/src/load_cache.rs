@@ -12,39 +12,89 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::update_cache::update_cache;
 use broker_core::cache::NodeCacheManager;
 use broker_core::cluster::ClusterStorage;
 use broker_core::dynamic_config::build_cluster_config;
-use broker_core::share_group::ShareGroupStorage;
-use broker_core::tenant::TenantStorage;
-use broker_core::topic::TopicStorage;
 use common_base::error::common::CommonError;
+use common_base::utils::serialize;
+use common_config::broker::broker_config;
 use common_security::manager::SecurityManager;
-use common_security::storage::acl::AclStorage;
-use common_security::storage::blacklist::BlackListStorage;
-use common_security::storage::user::UserStorage;
 use connector::manager::ConnectorManager;
+use grpc_clients::meta::common::call::bootstrap_cache;
 use grpc_clients::pool::ClientPool;
+use metadata_struct::auth::acl::SecurityAcl;
+use metadata_struct::auth::blacklist::SecurityBlackList;
+use metadata_struct::auth::user::SecurityUser;
+use metadata_struct::connector::MQTTConnector;
+use metadata_struct::meta::bootstrap::BootstrapResource;
+use metadata_struct::mq9::agent::MQ9Agent;
+use metadata_struct::mq9::mail::MQ9Mail;
+use metadata_struct::mqtt::auto_subscribe::MqttAutoSubscribeRule;
+use metadata_struct::mqtt::message_rule::MqttMessageRule;
+use metadata_struct::mqtt::share_group::{ShareGroup, ShareGroupMember};
+use metadata_struct::mqtt::topic_rewrite_rule::MqttTopicRewriteRule;
+use metadata_struct::nats::subscribe::NatsSubscribe;
+use metadata_struct::schema::{SchemaData, SchemaResourceBind};
+use metadata_struct::tenant::Tenant;
+use metadata_struct::topic::Topic;
+use mqtt_broker::broker::MqttBrokerServerParams;
 use mqtt_broker::core::cache::MQTTCacheManager;
 use mqtt_broker::core::error::MqttBrokerError;
 use mqtt_broker::core::tool::ResultMqttBrokerError;
-use mqtt_broker::storage::auto_subscribe::AutoSubscribeStorage;
-use mqtt_broker::storage::connector::ConnectorStorage;
-use mqtt_broker::storage::schema::SchemaStorage;
-use mqtt_broker::storage::topic_rewrite::TopicRewriteStorage;
+use nats_broker::broker::NatsBrokerServerParams;
 use nats_broker::core::cache::NatsCacheManager;
 use nats_broker::push::NatsSubscribeManager;
-use nats_broker::storage::agent::Mq9AgentStorage;
-use nats_broker::storage::mail::Mq9MailStorage;
-use nats_broker::storage::subscribe::NatsSubscribeStorage;
+use protocol::broker::broker::{
+    BrokerUpdateCacheActionType, BrokerUpdateCacheResourceType, UpdateCacheRecord,
+};
+use protocol::meta::meta_service_common::BootstrapCacheRequest;
 use schema_register::schema::SchemaRegisterManager;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use storage_engine::core::cache::StorageCacheManager;
 use storage_engine::core::error::StorageEngineError;
 use storage_engine::core::segment::{list_segment_metas, list_segments};
 use storage_engine::core::shard::list_shards;
-use tracing::info;
+use storage_engine::StorageEngineParams;
+use tracing::{info, warn};
 
+/// Resource types a broker keeps in its local caches. Sessions and
+/// subscriptions are loaded per client on connect, so they are not requested.
+const BROKER_BOOTSTRAP_RESOURCES: [BootstrapResource; 16] = [
+    BootstrapResource::Tenant,
+    BootstrapResource::User,
+    BootstrapResource::Acl,
+    BootstrapResource::Blacklist,
+    BootstrapResource::Topic,
+    BootstrapResource::TopicRewriteRule,
+    BootstrapResource::AutoSubscribeRule,
+    BootstrapResource::MessageRule,
+    BootstrapResource::Connector,
+    BootstrapResource::Schema,
+    BootstrapResource::SchemaBind,
+    BootstrapResource::ShareGroup,
+    BootstrapResource::ShareGroupMember,
+    BootstrapResource::NatsSubscribe,
+    BootstrapResource::Mq9Mail,
+    BootstrapResource::Mq9Agent,
+];
+
+/// Identities of the entries in a metadata snapshot, per resource type.
+type SnapshotKeys = HashMap<BootstrapResource, HashSet<String>>;
+
+struct MetadataCacheTargets<'a> {
+    mqtt_cache_manager: &'a Arc<MQTTCacheManager>,
+    nats_subscribe_manager: &'a Arc<NatsSubscribeManager>,
+    nats_cache_manager: &'a Arc<NatsCacheManager>,
+    connector_manager: &'a Arc<ConnectorManager>,
+    schema_manager: &'a Arc<SchemaRegisterManager>,
+    security_manager: &'a Arc<SecurityManager>,
+}
+
+/// Load the full metadata snapshot from the meta service on startup. It only
+/// upserts; [`resync_metadata_cache`] also removes entries deleted meanwhile.
 pub async fn load_metadata_cache(
     mqtt_cache_manager: &Arc<MQTTCacheManager>,
     nats_subscribe_manager: &Arc<NatsSubscribeManager>,
@@ -55,24 +105,72 @@ pub async fn load_metadata_cache(
     security_manager: &Arc<SecurityManager>,
 ) -> ResultMqttBrokerError {
     info!("Starting to load metadata cache...");
-    load_common_cache(
-        &mqtt_cache_manager.node_cache,
-        client_pool,
+    load_cluster_cache(&mqtt_cache_manager.node_cache, client_pool).await?;
+
+    let targets = MetadataCacheTargets {
+        mqtt_cache_manager,
+        nats_subscribe_manager,
+        nats_cache_manager,
         connector_manager,
         schema_manager,
-    )
-    .await?;
+        security_manager,
+    };
+    let counts = bootstrap_metadata_cache(&targets, client_pool, None).await?;
+    log_loaded_counts(&counts);
+    Ok(())
+}
+
+/// Rebuild the caches of a broker that missed cache notifications: reload the
+/// snapshot, then delete every cached tenant, user, ACL, blacklist, topic,
+/// connector and share group the snapshot no longer has, through the same path
+/// as a delete notification so dependent state is cleaned up too.
+pub async fn resync_metadata_cache(
+    mqtt_params: &MqttBrokerServerParams,
+    nats_params: &NatsBrokerServerParams,
+    storage_params: &StorageEngineParams,
+) -> ResultMqttBrokerError {
+    let client_pool = &mqtt_params.client_pool;
+    load_cluster_cache(&mqtt_params.cache_manager.node_cache, client_pool).await?;
 
-    load_mqtt_cache(mqtt_cache_manager, security_manager, client_pool).await?;
-    load_nats_cache(nats_subscribe_manager, nats_cache_manager, client_pool).await?;
+    let targets = MetadataCacheTargets {
+        mqtt_cache_manager: &mqtt_params.cache_manager,
+        nats_subscribe_manager: &nats_params.subscribe_manager,
+        nats_cache_manager: &nats_params.cache_manager,
+        connector_manager: &mqtt_params.connector_manager,
+        schema_manager: &mqtt_params.schema_manager,
+        security_manager: &mqtt_params.security_manager,
+    };
+    let mut keys = SnapshotKeys::new();
+    let counts = bootstrap_metadata_cache(&targets, client_pool, Some(&mut keys)).await?;
+    log_loaded_counts(&counts);
+
+    let stale = stale_cache_records(&targets, &keys)?;
+    for record in stale.iter() {
+        update_cache(mqtt_params, nats_params, storage_params, record)
+            .await
+            .map_err(|e| {
+                MqttBrokerError::CommonError(format!("Failed to remove stale cache entry: {}", e))
+            })?;
+    }
+    info!(
+        "Metadata cache resynced, {} stale entries removed",
+        stale.len()
+    );
     Ok(())
 }
 
-async fn load_common_cache(
+fn log_loaded_counts(counts: &HashMap<BootstrapResource, usize>) {
+    let summary = BROKER_BOOTSTRAP_RESOURCES
+        .iter()
+        .map(|r| format!("{}={}", r, counts.get(r).copied().unwrap_or(0)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    info!("Metadata cache loaded: {}", summary);
+}
+
+async fn load_cluster_cache(
     broker_cache: &Arc<NodeCacheManager>,
     client_pool: &Arc<ClientPool>,
-    connector_manager: &Arc<ConnectorManager>,
-    schema_manager: &Arc<SchemaRegisterManager>,
 ) -> ResultMqttBrokerError {
     let cluster_storage = ClusterStorage::new(client_pool.clone());
     let nodes = cluster_storage
@@ -88,144 +186,264 @@ async fn load_common_cache(
     })?;
     broker_cache.set_cluster_config(cluster);
 
-    let topic_storage = TopicStorage::new(client_pool.clone());
-    let topic_list = topic_storage
-        .all()
-        .await
-        .map_err(|e| MqttBrokerError::CommonError(format!("Failed to load topics: {}", e)))?;
-    for topic in topic_list.iter() {
-        broker_cache.add_topic(&topic.clone());
-    }
+    info!("Cluster cache loaded: nodes={}", nodes.len());
+    Ok(())
+}
 
-    let connector_storage = ConnectorStorage::new(client_pool.clone());
-    let connectors = connector_storage
-        .list_all_connectors()
-        .await
-        .map_err(|e| MqttBrokerError::CommonError(format!("Failed to load connectors: {}", e)))?;
-    for connector in connectors.iter() {
-        connector_manager.add_connector(connector);
-    }
+async fn bootstrap_metadata_cache(
+    targets: &MetadataCacheTargets<'_>,
+    client_pool: &Arc<ClientPool>,
+    mut keys: Option<&mut SnapshotKeys>,
+) -> Result<HashMap<BootstrapResource, usize>, MqttBrokerError> {
+    let request = BootstrapCacheRequest {
+        resource_types: BROKER_BOOTSTRAP_RESOURCES
+            .iter()
+            .map(|r| r.to_string())
+            .collect(),
+        page_size: 0,
+    };
+    let mut stream = bootstrap_cache(
+        client_pool,
+        &broker_config().get_meta_service_addr(),
+        request,
+    )
+    .await
+    .map_err(|e| MqttBrokerError::CommonError(format!("Failed to bootstrap cache: {}", e)))?;
 
-    let schema_storage = SchemaStorage::new(client_pool.clone());
-    let schemas = schema_storage
-        .list(None, None)
+    let mut counts = HashMap::new();
+    while let Some(reply) = stream
+        .message()
         .await
-        .map_err(|e| MqttBrokerError::CommonError(format!("Failed to load schemas: {}", e)))?;
-    for schema in schemas.iter() {
-        schema_manager.add_schema(schema.clone());
+        .map_err(|e| MqttBrokerError::CommonError(format!("Failed to bootstrap cache: {}", e)))?
+    {
+        let resource: BootstrapResource = reply.resource_type.parse()?;
+        for raw in reply.data.iter() {
+            apply_bootstrap_item(targets, resource, raw).map_err(|e| {
+                MqttBrokerError::CommonError(format!("Failed to load {}: {}", resource, e))
+            })?;
+            if let Some(keys) = keys.as_deref_mut() {
+                if let Some(key) = snapshot_key(resource, raw)? {
+                    keys.entry(resource).or_default().insert(key);
+                }
+            }
+        }
+        *counts.entry(resource).or_insert(0) += reply.data.len();
     }
+    Ok(counts)
+}
 
-    let schema_storage = SchemaStorage::new(client_pool.clone());
-    let schema_binds = schema_storage
-        .list_bind(None)
-        .await
-        .map_err(|e| MqttBrokerError::CommonError(format!("Failed to load schema binds: {}", e)))?;
-    for schema in schema_binds.iter() {
-        schema_manager.add_bind(schema);
+fn apply_bootstrap_item(
+    targets: &MetadataCacheTargets<'_>,
+    resource: BootstrapResource,
+    raw: &[u8],
+) -> Result<(), CommonError> {
+    let node_cache = &targets.mqtt_cache_manager.node_cache;
+    let security = &targets.security_manager.metadata;
+    match resource {
+        BootstrapResource::Tenant => node_cache.add_tenant(Tenant::decode(raw)?),
+        BootstrapResource::User => security.add_user(SecurityUser::decode(raw)?),
+        BootstrapResource::Acl => security.add_acl(SecurityAcl::decode(raw)?),
+        BootstrapResource::Blacklist => security.add_blacklist(SecurityBlackList::decode(raw)?),
+        BootstrapResource::Topic => node_cache.add_topic(&Topic::decode(raw)?),
+        BootstrapResource::TopicRewriteRule => targets
+            .mqtt_cache_manager
+            .add_topic_rewrite_rule(MqttTopicRewriteRule::decode(raw)?),
+        BootstrapResource::AutoSubscribeRule => targets
+            .mqtt_cache_manager
+            .add_auto_subscribe_rule(MqttAutoSubscribeRule::decode(raw)?),
+        BootstrapResource::MessageRule => {
+            let rule = MqttMessageRule::decode(raw)?;
+            if let Err(e) = targets.mqtt_cache_manager.add_message_rule(rule.clone()) {
+                warn!(
+                    "Skipping invalid message rule {}/{}: {}",
+                    rule.tenant, rule.name, e
+                );
+            }
+        }
+        BootstrapResource::Connector => targets
+            .connector_manager
+            .add_connector(&MQTTConnector::decode(raw)?),
+        BootstrapResource::Schema => targets.schema_manager.add_schema(SchemaData::decode(raw)?),
+        BootstrapResource::SchemaBind => targets
+            .schema_manager
+            .add_bind(&SchemaResourceBind::decode(raw)?),
+        BootstrapResource::ShareGroup => node_cache.add_share_group(ShareGroup::decode(raw)?),
+        BootstrapResource::ShareGroupMember => {
+            node_cache.add_share_group_member(&ShareGroupMember::decode(raw)?)
+        }
+        BootstrapResource::NatsSubscribe => targets
+            .nats_subscribe_manager
+            .add_subscribe(NatsSubscribe::decode(raw)?),
+        BootstrapResource::Mq9Mail => targets.nats_cache_manager.add_mail(MQ9Mail::decode(raw)?),
+        BootstrapResource::Mq9Agent => targets.nats_cache_manager.add_agent(MQ9Agent::decode(raw)?),
+        BootstrapResource::Session | BootstrapResource::Subscribe => {}
     }
+    Ok(())
+}
 
-    let tenant_storage = TenantStorage::new(client_pool.clone());
-    let tenants = tenant_storage
-        .list_all()
-        .await
-        .map_err(|e| MqttBrokerError::CommonError(format!("Failed to load tenants: {}", e)))?;
-    for tenant in tenants.iter() {
-        broker_cache.add_tenant(tenant.clone());
-    }
+fn user_key(user: &SecurityUser) -> String {
+    format!("{}/{}", user.tenant, user.username)
+}
 
-    let share_group_storage = ShareGroupStorage::new(client_pool.clone());
-    let share_groups = share_group_storage
-        .list_all()
-        .await
-        .map_err(|e| MqttBrokerError::CommonError(format!("Failed to load share groups: {}", e)))?;
-    let share_group_count = share_groups.len();
-    for group in share_groups {
-        broker_cache.add_share_group(group);
-    }
+fn acl_key(acl: &SecurityAcl) -> String {
+    format!(
+        "{}/{}/{}/{}",
+        acl.tenant, acl.resource_type, acl.resource_name, acl.name
+    )
+}
 
-    let share_group_members = share_group_storage.list_all_members().await.map_err(|e| {
-        MqttBrokerError::CommonError(format!("Failed to load share group members: {}", e))
-    })?;
-    let share_group_member_count = share_group_members.len();
-    for member in share_group_members {
-        broker_cache.add_share_group_member(&member);
-    }
+fn blacklist_key(blacklist: &SecurityBlackList) -> String {
+    format!(
+        "{}/{}/{}",
+        blacklist.tenant, blacklist.blacklist_type, blacklist.name
+    )
+}
 
-    info!(
-        "Common cache loaded: nodes={}, topics={}, connectors={}, schemas={}, schema_binds={}, tenants={}, share_groups={}, share_group_members={}",
-        nodes.len(),
-        topic_list.len(),
-        connectors.len(),
-        schemas.len(),
-        schema_binds.len(),
-        tenants.len(),
-        share_group_count,
-        share_group_member_count,
-    );
+fn topic_key(topic: &Topic) -> String {
+    format!("{}/{}", topic.tenant, topic.topic_name)
+}
 
-    Ok(())
+fn share_group_key(group: &ShareGroup) -> String {
+    format!("{}/{}", group.tenant, group.group_name)
 }
 
-async fn load_mqtt_cache(
-    cache_manager: &Arc<MQTTCacheManager>,
-    security_manager: &Arc<SecurityManager>,
-    client_pool: &Arc<ClientPool>,
-) -> ResultMqttBrokerError {
-    let user_storage = UserStorage::new(client_pool.clone());
-    let user_list = user_storage
-        .user_list()
-        .await
-        .map_err(|e| MqttBrokerError::CommonError(format!("Failed to load users: {}", e)))?;
-    for user in user_list.iter() {
-        security_manager.metadata.add_user(user.clone());
-    }
+/// Identity of a snapshot entry, for the resource types a resync reconciles.
+fn snapshot_key(resource: BootstrapResource, raw: &[u8]) -> Result<Option<String>, CommonError> {
+    let key = match resource {
+        BootstrapResource::Tenant => Tenant::decode(raw)?.tenant_name,
+        BootstrapResource::User => user_key(&SecurityUser::decode(raw)?),
+        BootstrapResource::Acl => acl_key(&SecurityAcl::decode(raw)?),
+        BootstrapResource::Blacklist => blacklist_key(&SecurityBlackList::decode(raw)?),
+        BootstrapResource::Topic => topic_key(&Topic::decode(raw)?),
+        BootstrapResource::Connector => MQTTConnector::decode(raw)?.connector_name,
+        BootstrapResource::ShareGroup => share_group_key(&ShareGroup::decode(raw)?),
+        _ => return Ok(None),
+    };
+    Ok(Some(key))
+}
 
-    let acl_storage = AclStorage::new(client_pool.clone());
-    let acl_list = acl_storage
-        .list_acl()
-        .await
-        .map_err(|e| MqttBrokerError::CommonError(format!("Failed to load ACLs: {}", e)))?;
-    for acl in acl_list.iter() {
-        security_manager.metadata.add_acl(acl.clone());
-    }
+/// Delete records for the cached entries whose identity is missing from the
+/// snapshot.
+fn stale_cache_records(
+    targets: &MetadataCacheTargets<'_>,
+    keys: &SnapshotKeys,
+) -> Result<Vec<UpdateCacheRecord>, CommonError> {
+    let node_cache = &targets.mqtt_cache_manager.node_cache;
+    let security = &targets.security_manager.metadata;
+    let mut records = Vec::new();
 
-    let blacklist_storage = BlackListStorage::new(client_pool.clone());
-    let blacklist_list = blacklist_storage
-        .list_blacklist()
-        .await
-        .map_err(|e| MqttBrokerError::CommonError(format!("Failed to load blacklist: {}", e)))?;
-    for blacklist in blacklist_list.iter() {
-        security_manager.metadata.add_blacklist(blacklist.clone());
-    }
+    let tenants: Vec<Tenant> = node_cache
+        .tenant_list
+        .iter()
+        .map(|e| e.value().clone())
+        .collect();
+    push_stale(
+        &mut records,
+        keys,
+        BootstrapResource::Tenant,
+        BrokerUpdateCacheResourceType::Tenant,
+        tenants,
+        |t| t.tenant_name.clone(),
+    )?;
 
-    let topic_storage = TopicRewriteStorage::new(client_pool.clone());
-    let topic_rewrite_rules = topic_storage.all_topic_rewrite_rule().await.map_err(|e| {
-        MqttBrokerError::CommonError(format!("Failed to load topic rewrite rules: {}", e))
-    })?;
-    for rule in topic_rewrite_rules.iter() {
-        cache_manager.add_topic_rewrite_rule(rule.clone());
-    }
+    let users: Vec<SecurityUser> = security
+        .user_info
+        .iter()
+        .flat_map(|tenant| {
+            tenant
+                .value()
+                .iter()
+                .map(|e| e.value().clone())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    push_stale(
+        &mut records,
+        keys,
+        BootstrapResource::User,
+        BrokerUpdateCacheResourceType::User,
+        users,
+        user_key,
+    )?;
 
-    let auto_subscribe_storage = AutoSubscribeStorage::new(client_pool.clone());
-    let auto_subscribe_rules = auto_subscribe_storage
-        .list_auto_subscribe_rule(None)
-        .await
-        .map_err(|e| {
-            MqttBrokerError::CommonError(format!("Failed to load auto subscribe rules: {}", e))
-        })?;
-    for rule in auto_subscribe_rules.iter() {
-        cache_manager.add_auto_subscribe_rule(rule.clone());
-    }
+    push_stale(
+        &mut records,
+        keys,
+        BootstrapResource::Acl,
+        BrokerUpdateCacheResourceType::Acl,
+        security.get_all_acl(),
+        acl_key,
+    )?;
 
-    info!(
-        "MQTT cache loaded: users={}, acls={}, blacklist={}, topic_rewrite_rules={}, auto_subscribe_rules={}",
-        user_list.len(),
-        acl_list.len(),
-        blacklist_list.len(),
-        topic_rewrite_rules.len(),
-        auto_subscribe_rules.len(),
-    );
+    push_stale(
+        &mut records,
+        keys,
+        BootstrapResource::Blacklist,
+        BrokerUpdateCacheResourceType::Blacklist,
+        security.get_all_blacklist(),
+        blacklist_key,
+    )?;
+
+    let topics: Vec<Topic> = node_cache
+        .topic_list
+        .iter()
+        .map(|e| e.value().clone())
+        .collect();
+    push_stale(
+        &mut records,
+        keys,
+        BootstrapResource::Topic,
+        BrokerUpdateCacheResourceType::Topic,
+        topics,
+        topic_key,
+    )?;
+
+    push_stale(
+        &mut records,
+        keys,
+        BootstrapResource::Connector,
+        BrokerUpdateCacheResourceType::Connector,
+        targets.connector_manager.get_all_connector(),
+        |c| c.connector_name.clone(),
+    )?;
+
+    let groups: Vec<ShareGroup> = node_cache
+        .share_group_list
+        .iter()
+        .map(|e| e.value().clone())
+        .collect();
+    push_stale(
+        &mut records,
+        keys,
+        BootstrapResource::ShareGroup,
+        BrokerUpdateCacheResourceType::ShareGroup,
+        groups,
+        share_group_key,
+    )?;
+
+    Ok(records)
+}
 
+fn push_stale<T: Serialize>(
+    records: &mut Vec<UpdateCacheRecord>,
+    keys: &SnapshotKeys,
+    resource: BootstrapResource,
+    resource_type: BrokerUpdateCacheResourceType,
+    cached: Vec<T>,
+    key: impl Fn(&T) -> String,
+) -> Result<(), CommonError> {
+    let snapshot = keys.get(&resource);
+    for item in cached {
+        if snapshot.is_some_and(|s| s.contains(&key(&item))) {
+            continue;
+        }
+        records.push(UpdateCacheRecord {
+            action_type: BrokerUpdateCacheActionType::Delete.into(),
+            resource_type: resource_type.into(),
+            data: serialize::serialize(&item)?,
+            version: 0,
+        });
+    }
     Ok(())
 }
 
@@ -258,36 +476,3 @@ pub async fn load_engine_cache(
 
     Ok(())
 }
-
-pub async fn load_nats_cache(
-    subscribe_manager: &Arc<NatsSubscribeManager>,
-    cache_manager: &Arc<NatsCacheManager>,
-    client_pool: &Arc<ClientPool>,
-) -> Result<(), CommonError> {
-    let subscribe_storage = NatsSubscribeStorage::new(client_pool.clone());
-    let subscribes = subscribe_storage.list(0).await?;
-    let subscribe_count = subscribes.len();
-    for subscribe in subscribes {
-        subscribe_manager.add_subscribe(subscribe);
-    }
-
-    let mail_storage = Mq9MailStorage::new(client_pool.clone());
-    let mails = mail_storage.list("").await?;
-    let mail_count = mails.len();
-    for mail in mails {
-        cache_manager.add_mail(mail);
-    }
-
-    let agent_storage = Mq9AgentStorage::new(client_pool.clone());
-    let agents = agent_storage.list("").await?;
-    let agent_count = agents.len();
-    for agent in agents {
-        cache_manager.add_agent(agent);
-    }
-
-    info!(
-        "NATS cache loaded: subscribes={}, mails={}, agents={}",
-        subscribe_count, mail_count, agent_count
-    );
-    Ok(())
-}
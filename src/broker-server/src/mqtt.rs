@@ -22,7 +22,10 @@
 use grpc_clients::pool::ClientPool;
 use mqtt_broker::{
     broker::{MqttBrokerServer, MqttBrokerServerParams},
-    core::{cache::MQTTCacheManager as MqttCacheManager, event::EventReportManager},
+    core::{
+        cache::MQTTCacheManager as MqttCacheManager, event::EventReportManager,
+        webhook::WebhookManager,
+    },
     storage::session::SessionBatcher,
     subscribe::{manager::SubscribeManager, PushManager},
 };
@@ -129,7 +132,9 @@ pub(crate) async fn build_broker_mqtt_params(
     ));
 
     let session_batcher = SessionBatcher::new();
-    let event_manager = EventReportManager::new();
+    let event_manager = EventReportManager::with_webhook(WebhookManager::new(
+        broker_cache.get_cluster_config().mqtt_webhook,
+    ));
 
     Ok(MqttBrokerServerParams {
         cache_manager,
@@ -16,11 +16,12 @@
     server::AdminServer,
     state::{HttpState, MQTTContext, NatsContext, StorageEngineContext},
 };
+use broker_core::dynamic_config::{apply_dynamic_config, ClusterDynamicConfig};
 use common_base::role::is_engine_node;
 #[cfg(not(windows))]
 use pprof::ProfilerGuard;
 use std::sync::Arc;
-use tracing::error;
+use tracing::{error, warn};
 
 use crate::{grpc::start_grpc_server, BrokerServer};
 
@@ -88,6 +89,7 @@ pub fn start_admin_server(&self) {
         let rocksdb_engine_handler = self.rocksdb_engine_handler.clone();
         let storage_driver_manager = self.mqtt_params.storage_driver_manager.clone();
         let rate_limiter = self.global_rate_limiter.clone();
+        let delay_task_manager = self.delay_task_manager.clone();
 
         let state = Arc::new(HttpState {
             client_pool,
@@ -98,6 +100,7 @@ pub fn start_admin_server(&self) {
             broker_cache,
             storage_driver_manager,
             rate_limiter,
+            delay_task_manager,
             nats_context: Some(NatsContext {
                 cache_manager: nats_cache_manager,
                 subscribe_manager: nats_subscribe_manager,
@@ -127,6 +130,7 @@ pub fn start_load_cache(&self) {
         let connector_manager = self.mqtt_params.connector_manager.clone();
         let schema_manager = self.mqtt_params.schema_manager.clone();
         let security_manager = self.mqtt_params.security_manager.clone();
+        let global_limit_manager = self.mqtt_params.global_limit_manager.clone();
         self.server_runtime.block_on(async {
             if let Err(e) = crate::load_cache::load_metadata_cache(
                 &mqtt_cache_manager,
@@ -142,6 +146,18 @@ pub fn start_load_cache(&self) {
                 error!("Failed to load metadata cache: {}", e);
                 std::process::exit(1);
             }
+
+            // Dynamic configs loaded from meta-service that are not read from the cache on use.
+            let cluster = mqtt_cache_manager.node_cache.get_cluster_config();
+            if let Err(e) = apply_dynamic_config(ClusterDynamicConfig::Log, &cluster) {
+                warn!("Failed to apply dynamic log level: {}", e);
+            }
+            if let Err(e) = global_limit_manager
+                .set_network_connection_rate(cluster.cluster_limit.max_network_connection_rate)
+                .await
+            {
+                warn!("Failed to apply network connection rate limit: {}", e);
+            }
         });
 
         if is_engine_node(&self.config.roles) {
@@ -156,5 +172,7 @@ pub fn start_load_cache(&self) {
                 }
             });
         }
+
+        self.broker_cache.set_cache_loaded();
     }
 }
@@ -38,8 +38,8 @@
 use protocol::broker::broker::{
     BrokerUpdateCacheActionType, BrokerUpdateCacheResourceType, UpdateCacheRecord,
 };
-use std::str::FromStr;
 use storage_engine::{core::dynamic_cache::update_storage_cache_metadata, StorageEngineParams};
+use tracing::warn;
 
 pub async fn update_cache(
     mqtt_params: &MqttBrokerServerParams,
@@ -52,6 +52,7 @@ pub async fn update_cache(
         BrokerUpdateCacheResourceType::Session
         | BrokerUpdateCacheResourceType::Subscribe
         | BrokerUpdateCacheResourceType::AutoSubscribeRule
+        | BrokerUpdateCacheResourceType::MessageRule
         | BrokerUpdateCacheResourceType::TopicRewriteRule => {
             if let Err(e) = update_mqtt_cache_metadata(
                 &mqtt_params.cache_manager,
@@ -137,8 +138,20 @@ pub async fn update_cluster_cache_metadata(
 
         BrokerUpdateCacheResourceType::ClusterResourceConfig => {
             let config: ResourceConfig = serialize::deserialize(&record.data)?;
-            if let Ok(config_type) = ClusterDynamicConfig::from_str(&config.resource) {
-                update_cluster_dynamic_config(&mqtt_params.node_cache, config_type, config.config)?;
+            let Some(config_type) = ClusterDynamicConfig::from_resource(&config.resource) else {
+                warn!(
+                    "Ignoring unknown dynamic config resource {}",
+                    config.resource
+                );
+                return Ok(());
+            };
+            update_cluster_dynamic_config(&mqtt_params.node_cache, config_type, config.config)?;
+            if config_type == ClusterDynamicConfig::ClusterLimit {
+                let limit = mqtt_params.node_cache.get_cluster_config().cluster_limit;
+                mqtt_params
+                    .global_limit_manager
+                    .set_network_connection_rate(limit.max_network_connection_rate)
+                    .await?;
             }
         }
 
@@ -353,6 +353,7 @@ pub async fn run_placement_list_session_bench(
             let request = ListSessionRequest {
                 tenant: DEFAULT_TENANT.to_string(),
                 client_id: (*local_client_id).clone(),
+                ..Default::default()
             };
 
             let start = Instant::now();
@@ -16,7 +16,13 @@
 use crate::output::OutputFormat;
 use admin_server::{
     client::AdminHttpClient,
-    cluster::{config::ClusterConfigSetReq, tenant::TenantListRow, ClusterInfoResp},
+    cluster::{
+        backup::{BackupInfo, RocksDBBackupListResp, RocksDBBackupRestoreReq},
+        config::ClusterConfigSetReq,
+        metadata::{MetadataDump, MetadataExportReq, MetadataImportReq, MetadataImportResultRaw},
+        tenant::TenantListRow,
+        ClusterInfoResp,
+    },
 };
 use chrono::{Local, TimeZone};
 use common_config::config::BrokerConfig;
@@ -48,6 +54,24 @@ pub enum ClusterActionType {
         node_id: u64,
         force: bool,
     },
+    TransferLeader {
+        node_id: u64,
+        target_node_id: u64,
+        shard: String,
+    },
+    ListBackup,
+    CreateBackup,
+    RestoreBackup {
+        backup_id: Option<u32>,
+    },
+    ExportMetadata {
+        file: String,
+        resource_types: Vec<String>,
+    },
+    ImportMetadata {
+        file: String,
+        dry_run: bool,
+    },
 }
 
 pub struct ClusterCommand {}
@@ -88,6 +112,32 @@ pub async fn start(&self, params: ClusterCliCommandParam) {
             ClusterActionType::LeaveNode { node_id, force } => {
                 self.leave_node(params, node_id, force).await;
             }
+            ClusterActionType::TransferLeader {
+                node_id,
+                target_node_id,
+                shard,
+            } => {
+                self.transfer_leader(params, node_id, target_node_id, shard)
+                    .await;
+            }
+            ClusterActionType::ListBackup => {
+                self.list_backup(params).await;
+            }
+            ClusterActionType::CreateBackup => {
+                self.create_backup(params).await;
+            }
+            ClusterActionType::RestoreBackup { backup_id } => {
+                self.restore_backup(params, backup_id).await;
+            }
+            ClusterActionType::ExportMetadata {
+                file,
+                resource_types,
+            } => {
+                self.export_metadata(params, file, resource_types).await;
+            }
+            ClusterActionType::ImportMetadata { file, dry_run } => {
+                self.import_metadata(params, file, dry_run).await;
+            }
         }
     }
 
@@ -355,6 +405,193 @@ async fn leave_node(&self, params: ClusterCliCommandParam, node_id: u64, force:
             }
         }
     }
+
+    async fn transfer_leader(
+        &self,
+        params: ClusterCliCommandParam,
+        node_id: u64,
+        target_node_id: u64,
+        shard: String,
+    ) {
+        let admin_client = AdminHttpClient::new(format!("http://{}", params.server));
+        let request = admin_server::cluster::node::TransferLeaderReq {
+            node_id,
+            target_node_id,
+            shard,
+        };
+        match admin_client.node_transfer_leader(&request).await {
+            Ok(msg) => println!("{}", msg),
+            Err(e) => {
+                println!("Transfer leader exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn list_backup(&self, params: ClusterCliCommandParam) {
+        let admin_client = AdminHttpClient::new(format!("http://{}", params.server));
+        match admin_client
+            .rocksdb_backup_list::<RocksDBBackupListResp>()
+            .await
+        {
+            Ok(data) => {
+                if matches!(params.output, OutputFormat::Json) {
+                    self.print_json(&data);
+                    return;
+                }
+                println!("backup_dir: {}", data.backup_dir);
+                let mut table = Table::new();
+                table.set_titles(row!["backup_id", "time", "size", "num_files"]);
+                for backup in data.backups {
+                    table.add_row(row![
+                        backup.backup_id,
+                        format_timestamp(backup.timestamp as u64),
+                        backup.size,
+                        backup.num_files
+                    ]);
+                }
+                table.printstd();
+            }
+            Err(e) => {
+                println!("List backup exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn create_backup(&self, params: ClusterCliCommandParam) {
+        let admin_client = AdminHttpClient::new(format!("http://{}", params.server));
+        match admin_client.rocksdb_backup_create::<BackupInfo>().await {
+            Ok(backup) => {
+                if matches!(params.output, OutputFormat::Json) {
+                    self.print_json(&backup);
+                    return;
+                }
+                println!(
+                    "Created backup {} ({} bytes, {} files)",
+                    backup.backup_id, backup.size, backup.num_files
+                );
+            }
+            Err(e) => {
+                println!("Create backup exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn restore_backup(&self, params: ClusterCliCommandParam, backup_id: Option<u32>) {
+        let admin_client = AdminHttpClient::new(format!("http://{}", params.server));
+        let request = RocksDBBackupRestoreReq { backup_id };
+        match admin_client.rocksdb_backup_restore(&request).await {
+            Ok(msg) => println!("{}", msg),
+            Err(e) => {
+                println!("Restore backup exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn export_metadata(
+        &self,
+        params: ClusterCliCommandParam,
+        file: String,
+        resource_types: Vec<String>,
+    ) {
+        let admin_client = AdminHttpClient::new(format!("http://{}", params.server));
+        let request = MetadataExportReq { resource_types };
+        let dump = match admin_client
+            .metadata_export::<MetadataExportReq, MetadataDump>(&request)
+            .await
+        {
+            Ok(dump) => dump,
+            Err(e) => {
+                println!("Export metadata exception");
+                error_info(e.to_string());
+                return;
+            }
+        };
+
+        let raw = match serde_json::to_string_pretty(&dump) {
+            Ok(raw) => raw,
+            Err(e) => {
+                error_info(e.to_string());
+                return;
+            }
+        };
+        if let Err(e) = std::fs::write(&file, raw) {
+            error_info(format!("Failed to write {}: {}", file, e));
+            return;
+        }
+
+        if matches!(params.output, OutputFormat::Json) {
+            let resources: Vec<serde_json::Value> = dump
+                .resources
+                .iter()
+                .map(|resource| {
+                    serde_json::json!({
+                        "resource_type": resource.resource_type,
+                        "records": resource.records.len(),
+                    })
+                })
+                .collect();
+            self.print_json(&serde_json::json!({
+                "file": file,
+                "meta_version": dump.meta_version,
+                "resources": resources,
+            }));
+            return;
+        }
+        println!(
+            "Exported metadata (meta version {}) to {}",
+            dump.meta_version, file
+        );
+        let mut table = Table::new();
+        table.set_titles(row!["resource_type", "records"]);
+        for resource in dump.resources {
+            table.add_row(row![resource.resource_type, resource.records.len()]);
+        }
+        table.printstd();
+    }
+
+    async fn import_metadata(&self, params: ClusterCliCommandParam, file: String, dry_run: bool) {
+        let dump = match std::fs::read_to_string(&file)
+            .map_err(|e| e.to_string())
+            .and_then(|raw| serde_json::from_str::<MetadataDump>(&raw).map_err(|e| e.to_string()))
+        {
+            Ok(dump) => dump,
+            Err(e) => {
+                error_info(format!("Failed to read dump {}: {}", file, e));
+                return;
+            }
+        };
+
+        let admin_client = AdminHttpClient::new(format!("http://{}", params.server));
+        let request = MetadataImportReq { dry_run, dump };
+        match admin_client
+            .metadata_import::<MetadataImportReq, Vec<MetadataImportResultRaw>>(&request)
+            .await
+        {
+            Ok(results) => {
+                if matches!(params.output, OutputFormat::Json) {
+                    self.print_json(&results);
+                    return;
+                }
+                if dry_run {
+                    println!("Dry run, nothing was written");
+                }
+                let mut table = Table::new();
+                table.set_titles(row!["resource_type", "imported", "skipped"]);
+                for result in results {
+                    table.add_row(row![result.resource_type, result.imported, result.skipped]);
+                }
+                table.printstd();
+            }
+            Err(e) => {
+                println!("Import metadata exception");
+                error_info(e.to_string());
+            }
+        }
+    }
 }
 
 fn format_timestamp(secs: u64) -> String {
@@ -17,13 +17,13 @@
 use crate::mqtt::command::{MqttBrokerCommand, MqttCliCommandParam};
 use crate::mqtt::params::{
     process_acl_args, process_auto_subscribe_args, process_blacklist_args, process_connection_args,
-    process_connector_args, process_flapping_detect_args, process_overview, process_publish_args,
-    process_schema_args, process_session_args, process_slow_sub_args, process_subscribe_args,
-    process_subscribes_args, process_system_alarm_args, process_topic_args,
-    process_topic_rewrite_args, process_user_args, AclArgs, AutoSubscribeRuleCommand,
-    BlacklistArgs, ClientsArgs, ConnectorArgs, FlappingDetectArgs, PubSubArgs, SchemaArgs,
-    SessionArgs, SlowSubscribeArgs, SubscribesArgs, SystemAlarmArgs, TopicArgs, TopicRewriteArgs,
-    UserArgs,
+    process_connector_args, process_flapping_detect_args, process_migrate_args, process_overview,
+    process_publish_args, process_schema_args, process_session_args, process_slow_request_args,
+    process_slow_sub_args, process_subscribe_args, process_subscribes_args,
+    process_system_alarm_args, process_topic_args, process_topic_rewrite_args, process_user_args,
+    AclArgs, AutoSubscribeRuleCommand, BlacklistArgs, ClientsArgs, ConnectorArgs,
+    FlappingDetectArgs, MigrateArgs, PubSubArgs, SchemaArgs, SessionArgs, SlowRequestArgs,
+    SlowSubscribeArgs, SubscribesArgs, SystemAlarmArgs, TopicArgs, TopicRewriteArgs, UserArgs,
 };
 use crate::output::OutputFormat;
 use clap::{Parser, Subcommand};
@@ -146,6 +146,7 @@ pub enum MQTTAction {
     Client(ClientsArgs),
     FlappingDetect(FlappingDetectArgs),
     SlowSubscribe(SlowSubscribeArgs),
+    SlowRequest(SlowRequestArgs),
     SystemAlarm(SystemAlarmArgs),
     Topic(TopicArgs),
     TopicRewrite(TopicRewriteArgs),
@@ -154,6 +155,7 @@ pub enum MQTTAction {
     AutoSubscribe(AutoSubscribeRuleCommand),
     Publish(PubSubArgs),
     Subscribe(PubSubArgs),
+    Migrate(MigrateArgs),
 }
 
 #[derive(clap::Args, Debug)]
@@ -177,11 +179,82 @@ pub enum ClusterAction {
     Config(ClusterConfigArgs),
     Tenant(TenantArgs),
     Node(NodeArgs),
+    Backup(BackupArgs),
+    Metadata(MetadataArgs),
+}
+
+// metadata
+#[derive(clap::Args, Debug)]
+#[command(author = "RobustMQ", about = "Cluster metadata dump for disaster recovery: export, import", long_about = None)]
+#[command(next_line_help = true)]
+pub struct MetadataArgs {
+    #[command(subcommand)]
+    pub action: MetadataActionType,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum MetadataActionType {
+    #[command(author = "RobustMQ", about = "Dump the cluster metadata to a file", long_about = None)]
+    Export(ExportMetadataArgs),
+    #[command(author = "RobustMQ", about = "Restore a metadata dump into the cluster", long_about = None)]
+    Import(ImportMetadataArgs),
+}
+
+#[derive(clap::Args, Debug)]
+#[command(next_line_help = true)]
+pub struct ExportMetadataArgs {
+    #[arg(short = 'f', long, help = "File to write the dump to")]
+    pub file: String,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Resource types to dump, e.g. topic,user,acl (default: all)"
+    )]
+    pub resource_types: Vec<String>,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(next_line_help = true)]
+pub struct ImportMetadataArgs {
+    #[arg(short = 'f', long, help = "Dump file to restore")]
+    pub file: String,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Only validate the dump and report what would be imported"
+    )]
+    pub dry_run: bool,
+}
+
+// backup
+#[derive(clap::Args, Debug)]
+#[command(author = "RobustMQ", about = "Backups of the node's local RocksDB: list, create, restore", long_about = None)]
+#[command(next_line_help = true)]
+pub struct BackupArgs {
+    #[command(subcommand)]
+    pub action: BackupActionType,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BackupActionType {
+    #[command(author = "RobustMQ", about = "List the backups", long_about = None)]
+    List,
+    #[command(author = "RobustMQ", about = "Take a backup now", long_about = None)]
+    Create,
+    #[command(author = "RobustMQ", about = "Restore a backup on the next start of the node", long_about = None)]
+    Restore(RestoreBackupArgs),
+}
+
+#[derive(clap::Args, Debug)]
+#[command(next_line_help = true)]
+pub struct RestoreBackupArgs {
+    #[arg(short = 'i', long, help = "Backup ID to restore (default: latest)")]
+    pub backup_id: Option<u32>,
 }
 
 // node
 #[derive(clap::Args, Debug)]
-#[command(author = "RobustMQ", about = "Node management: leave (permanent scale-in)", long_about = None)]
+#[command(author = "RobustMQ", about = "Node management: leave (permanent scale-in), transfer-leader", long_about = None)]
 #[command(next_line_help = true)]
 pub struct NodeArgs {
     #[command(subcommand)]
@@ -192,6 +265,8 @@ pub struct NodeArgs {
 pub enum NodeActionType {
     #[command(author = "RobustMQ", about = "Permanently remove a node from the cluster (stop its process first)", long_about = None)]
     Leave(LeaveNodeArgs),
+    #[command(author = "RobustMQ", about = "Hand a meta node's raft shard leadership over to another node", long_about = None)]
+    TransferLeader(TransferLeaderArgs),
 }
 
 #[derive(clap::Args, Debug)]
@@ -208,6 +283,32 @@ pub struct LeaveNodeArgs {
     pub force: bool,
 }
 
+#[derive(clap::Args, Debug)]
+#[command(next_line_help = true)]
+pub struct TransferLeaderArgs {
+    #[arg(
+        short = 'n',
+        long,
+        required = true,
+        help = "Node ID currently leading the shards"
+    )]
+    pub node_id: u64,
+    #[arg(
+        short = 't',
+        long,
+        required = true,
+        help = "Node ID to hand leadership to"
+    )]
+    pub target_node_id: u64,
+    #[arg(
+        short = 's',
+        long,
+        default_value = "",
+        help = "Raft shard to hand over, e.g. data_3 (default: every shard led by the node)"
+    )]
+    pub shard: String,
+}
+
 // tenant
 #[derive(clap::Args, Debug)]
 #[command(author = "RobustMQ", about = "Tenant management: list, create, delete", long_about = None)]
@@ -390,10 +491,12 @@ pub async fn handle_mqtt(args: MqttArgs) {
             MQTTAction::Topic(args) => process_topic_args(args),
             MQTTAction::TopicRewrite(args) => process_topic_rewrite_args(args),
             MQTTAction::SlowSubscribe(args) => process_slow_sub_args(args),
+            MQTTAction::SlowRequest(args) => process_slow_request_args(args),
             MQTTAction::Publish(args) => process_publish_args(args),
             MQTTAction::Subscribe(args) => process_subscribe_args(args),
             MQTTAction::Schema(args) => process_schema_args(args),
             MQTTAction::AutoSubscribe(args) => process_auto_subscribe_args(args),
+            MQTTAction::Migrate(args) => process_migrate_args(args),
         },
     };
     MqttBrokerCommand::new().start(params).await;
@@ -427,6 +530,28 @@ pub async fn handle_cluster(args: ClusterArgs) {
                 node_id: arg.node_id,
                 force: arg.force,
             },
+            NodeActionType::TransferLeader(arg) => ClusterActionType::TransferLeader {
+                node_id: arg.node_id,
+                target_node_id: arg.target_node_id,
+                shard: arg.shard,
+            },
+        },
+        ClusterAction::Backup(backup_args) => match backup_args.action {
+            BackupActionType::List => ClusterActionType::ListBackup,
+            BackupActionType::Create => ClusterActionType::CreateBackup,
+            BackupActionType::Restore(arg) => ClusterActionType::RestoreBackup {
+                backup_id: arg.backup_id,
+            },
+        },
+        ClusterAction::Metadata(metadata_args) => match metadata_args.action {
+            MetadataActionType::Export(arg) => ClusterActionType::ExportMetadata {
+                file: arg.file,
+                resource_types: arg.resource_types,
+            },
+            MetadataActionType::Import(arg) => ClusterActionType::ImportMetadata {
+                file: arg.file,
+                dry_run: arg.dry_run,
+            },
         },
     };
 
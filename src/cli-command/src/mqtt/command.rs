@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::mqtt::migrate::{build_plan, execute_plan, MigrateArgsRequest};
 use crate::mqtt::pub_sub::{connect_server5, error_info};
 use crate::mqtt::pub_sub::{PublishArgsRequest, SubscribeArgsRequest};
 use crate::output::OutputFormat;
@@ -67,8 +68,16 @@ pub enum MqttActionType {
     // slow subscribe
     ListSlowSubscribe,
 
+    // slow request
+    ListSlowRequest {
+        client_id: Option<String>,
+        packet_type: Option<String>,
+    },
+
     // system alarm
-    ListSystemAlarm,
+    ListSystemAlarm {
+        active: bool,
+    },
 
     // topic rewrite rule
     ListTopicRewrite,
@@ -81,6 +90,9 @@ pub enum MqttActionType {
     // subscribe
     Subscribe(SubscribeArgsRequest),
 
+    // migrate
+    Migrate(MigrateArgsRequest),
+
     // Topic
     ListTopic,
 
@@ -186,9 +198,18 @@ pub async fn start(&self, params: MqttCliCommandParam) {
                 self.list_slow_subscribe(params_clone.clone()).await;
             }
 
+            // slow request
+            MqttActionType::ListSlowRequest {
+                client_id,
+                packet_type,
+            } => {
+                self.list_slow_request(params_clone.clone(), client_id, packet_type)
+                    .await;
+            }
+
             // system alarm
-            MqttActionType::ListSystemAlarm => {
-                self.list_system_alarm(params_clone.clone()).await;
+            MqttActionType::ListSystemAlarm { active } => {
+                self.list_system_alarm(params_clone.clone(), active).await;
             }
 
             // user
@@ -268,8 +289,56 @@ pub async fn start(&self, params: MqttCliCommandParam) {
             MqttActionType::Subscribe(ref request) => {
                 self.subscribe(params.clone(), request.clone()).await;
             }
+            MqttActionType::Migrate(ref request) => {
+                self.migrate(params.clone(), request.clone()).await;
+            }
         }
     }
+
+    async fn migrate(&self, params: MqttCliCommandParam, request: MigrateArgsRequest) {
+        let plan = match build_plan(&request).await {
+            Ok(plan) => plan,
+            Err(e) => {
+                println!("MQTT broker migrate exception");
+                error_info(e);
+                return;
+            }
+        };
+
+        let admin_client = AdminHttpClient::new(format!("http://{}", params.server));
+        let report = execute_plan(plan, &request, &admin_client).await;
+
+        if let Some(path) = &request.report {
+            let written = serde_json::to_string_pretty(&report)
+                .map_err(|e| e.to_string())
+                .and_then(|raw| std::fs::write(path, raw).map_err(|e| e.to_string()));
+            if let Err(e) = written {
+                error_info(e);
+            }
+        }
+
+        if params.output == OutputFormat::Json {
+            self.print_json(&report);
+            return;
+        }
+
+        let mut table = Table::new();
+        table.add_row(row!["kind", "source", "name", "status", "detail"]);
+        for item in &report.items {
+            table.add_row(row![
+                item.kind,
+                item.source,
+                item.name,
+                format!("{:?}", item.status).to_lowercase(),
+                item.detail
+            ]);
+        }
+        table.printstd();
+        println!(
+            "imported: {}, planned: {}, skipped: {}, failed: {}",
+            report.imported, report.planned, report.skipped, report.failed
+        );
+    }
     async fn publish(&self, params: MqttCliCommandParam, args: PublishArgsRequest) {
         // stdin stream
         let stdin = BufReader::new(io::stdin());
@@ -973,27 +1042,102 @@ async fn list_topic(&self, params: MqttCliCommandParam) {
     }
 
     // ---- system alarms ----
-    async fn list_system_alarm(&self, params: MqttCliCommandParam) {
+    // ---- slow request ----
+    async fn list_slow_request(
+        &self,
+        params: MqttCliCommandParam,
+        client_id: Option<String>,
+        packet_type: Option<String>,
+    ) {
         // Create admin HTTP client
         let admin_client = AdminHttpClient::new(format!("http://{}", params.server));
 
-        // Create request for system alarm list
-        let request = admin_server::mqtt::system::SystemAlarmListReq {
+        let request = admin_server::mqtt::system::SlowRequestListReq {
+            tenant: None,
+            client_id,
+            packet_type,
             limit: Some(params.limit),
             page: Some(params.page),
             sort_field: None,
             sort_by: None,
-            filter_field: None,
-            filter_values: None,
-            exact_match: None,
         };
 
         match admin_client
-            .get_system_alarm_list::<admin_server::mqtt::system::SystemAlarmListReq, Vec<admin_server::mqtt::system::SystemAlarmListRow>>(
+            .get_slow_request_list::<admin_server::mqtt::system::SlowRequestListReq, Vec<admin_server::mqtt::system::SlowRequestListRow>>(
                 &request,
             )
             .await
         {
+            Ok(page_data) => {
+                if matches!(params.output, OutputFormat::Json) {
+                    self.print_json(&page_data);
+                    return;
+                }
+                println!("slow request list result:");
+                let mut table = Table::new();
+                table.set_titles(row![
+                    "client_id",
+                    "packet_type",
+                    "topic",
+                    "duration_ms",
+                    "phases",
+                    "create_time"
+                ]);
+                for raw in page_data.data {
+                    let phases = raw
+                        .phases
+                        .iter()
+                        .map(|phase| format!("{}:{}ms", phase.name, phase.duration_ms))
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    table.add_row(row![
+                        raw.client_id,
+                        raw.packet_type,
+                        raw.topic,
+                        raw.duration_ms,
+                        phases,
+                        raw.create_time,
+                    ]);
+                }
+                // output cmd
+                table.printstd()
+            }
+            Err(e) => {
+                println!("MQTT broker list slow request exception");
+                error_info(e.to_string());
+            }
+        }
+    }
+
+    async fn list_system_alarm(&self, params: MqttCliCommandParam, active: bool) {
+        // Create admin HTTP client
+        let admin_client = AdminHttpClient::new(format!("http://{}", params.server));
+
+        // Create request for system alarm list
+        let request = admin_server::mqtt::system::SystemAlarmListReq {
+            limit: Some(params.limit),
+            page: Some(params.page),
+            sort_field: None,
+            sort_by: None,
+            filter_field: None,
+            filter_values: None,
+            exact_match: None,
+        };
+
+        let result = if active {
+            admin_client
+                .get_system_alarm_active_list::<admin_server::mqtt::system::SystemAlarmListReq, Vec<admin_server::mqtt::system::SystemAlarmListRow>>(
+                    &request,
+                )
+                .await
+        } else {
+            admin_client
+                .get_system_alarm_list::<admin_server::mqtt::system::SystemAlarmListReq, Vec<admin_server::mqtt::system::SystemAlarmListRow>>(
+                    &request,
+                )
+                .await
+        };
+        match result {
             Ok(page_data) => {
                 if matches!(params.output, OutputFormat::Json) {
                     self.print_json(&page_data);
@@ -1001,12 +1145,23 @@ async fn list_system_alarm(&self, params: MqttCliCommandParam) {
                 }
                 println!("system alarm list result:");
                 let mut table = Table::new();
-                table.set_titles(row!["name", "message", "create_time"]);
+                table.set_titles(row![
+                    "name",
+                    "message",
+                    "activate_at",
+                    "deactivate_at",
+                    "activated"
+                ]);
                 for alarm in page_data.data {
                     table.add_row(row![
                         alarm.name,
                         alarm.message,
                         alarm.create_time,
+                        alarm
+                            .deactivate_at
+                            .map(|t| t.to_string())
+                            .unwrap_or_default(),
+                        alarm.activated,
                     ]);
                 }
                 // output cmd
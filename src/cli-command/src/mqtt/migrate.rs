@@ -0,0 +1,1099 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Import users, ACL rules and retained messages from EMQX and Mosquitto.
+//!
+//! Sources are first parsed into a [`MigrationPlan`], entries that have no
+//! RobustMQ equivalent are recorded as skipped with the reason. The plan is
+//! then either only reported (dry run) or applied through the admin API, and
+//! retained messages are republished with the retain flag over MQTT.
+
+use admin_server::client::AdminHttpClient;
+use admin_server::cluster::acl::CreateAclReq;
+use admin_server::cluster::user::CreateUserReq;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use common_base::http_response::AdminServerResponse;
+use common_base::uuid::unique_id;
+use paho_mqtt::{DisconnectOptionsBuilder, MessageBuilder};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::mqtt::pub_sub::connect_server5;
+
+const WILDCARD_RESOURCE: &str = "*";
+const EMQX_API_PAGE_LIMIT: usize = 100;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MigrateArgsRequest {
+    pub tenant: String,
+    pub emqx_users: Option<String>,
+    pub emqx_salt_position: String,
+    pub emqx_acl: Option<String>,
+    pub emqx_retained: Option<String>,
+    pub emqx_api: Option<String>,
+    pub emqx_api_key: String,
+    pub emqx_api_secret: String,
+    pub mosquitto_passwd: Option<String>,
+    pub mosquitto_acl: Option<String>,
+    pub default_password: Option<String>,
+    pub mqtt_server: String,
+    pub mqtt_username: String,
+    pub mqtt_password: String,
+    pub dry_run: bool,
+    pub report: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MigrationStatus {
+    Planned,
+    Imported,
+    Skipped,
+    Failed,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct MigrationItem {
+    pub kind: String,
+    pub source: String,
+    pub name: String,
+    pub status: MigrationStatus,
+    pub detail: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetainedMessage {
+    pub topic: String,
+    pub payload: Vec<u8>,
+    pub qos: i32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MigrationPlan {
+    pub users: Vec<(String, String, CreateUserReq)>,
+    pub acls: Vec<(String, CreateAclReq)>,
+    pub retained: Vec<(String, RetainedMessage)>,
+    pub skipped: Vec<MigrationItem>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct MigrationReport {
+    pub dry_run: bool,
+    pub imported: usize,
+    pub planned: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub items: Vec<MigrationItem>,
+}
+
+impl MigrationPlan {
+    fn skip(&mut self, kind: &str, source: &str, name: &str, detail: impl Into<String>) {
+        self.skipped.push(MigrationItem {
+            kind: kind.to_string(),
+            source: source.to_string(),
+            name: name.to_string(),
+            status: MigrationStatus::Skipped,
+            detail: detail.into(),
+        });
+    }
+
+    fn add_user(&mut self, source: &str, detail: impl Into<String>, user: CreateUserReq) {
+        self.users.push((source.to_string(), detail.into(), user));
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn add_acl(
+        &mut self,
+        tenant: &str,
+        source: &str,
+        resource_type: &str,
+        resource_name: &str,
+        topic: &str,
+        ip: Option<String>,
+        action: &str,
+        permission: &str,
+    ) {
+        let name = format!("{}-{}", source, self.acls.len() + 1);
+        self.acls.push((
+            source.to_string(),
+            CreateAclReq {
+                tenant: tenant.to_string(),
+                name,
+                desc: Some(format!("migrated from {source}")),
+                resource_type: resource_type.to_string(),
+                resource_name: resource_name.to_string(),
+                topic: Some(topic.to_string()),
+                ip,
+                action: action.to_string(),
+                permission: permission.to_string(),
+                priority: None,
+            },
+        ));
+    }
+}
+
+/// Parse a Mosquitto password file (`username:password` per line). Mosquitto
+/// stores SHA512 (`$6$`) or PBKDF2 (`$7$`) hashes that cannot be verified by
+/// RobustMQ, those users are only imported when a default password is given.
+pub fn parse_mosquitto_passwd(
+    plan: &mut MigrationPlan,
+    content: &str,
+    tenant: &str,
+    default_password: Option<&str>,
+) {
+    let source = "mosquitto-passwd";
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((username, secret)) = line.split_once(':') else {
+            plan.skip(
+                "user",
+                source,
+                line,
+                "malformed line, expected username:password",
+            );
+            continue;
+        };
+        if !secret.starts_with('$') {
+            plan.add_user(source, "", new_user(tenant, username, secret, None, false));
+            continue;
+        }
+        let scheme = secret.split('$').nth(1).unwrap_or_default();
+        match default_password {
+            Some(password) => plan.add_user(
+                source,
+                format!("${scheme}$ hash is not portable, default password set"),
+                new_user(tenant, username, password, None, false),
+            ),
+            None => plan.skip(
+                "user",
+                source,
+                username,
+                format!("${scheme}$ hash is not supported, pass --default-password to import"),
+            ),
+        }
+    }
+}
+
+/// Parse a Mosquitto ACL file. `topic` lines apply to the preceding `user`,
+/// `pattern` lines apply to every user and keep their `%u`/`%c` placeholders,
+/// which RobustMQ expands the same way.
+pub fn parse_mosquitto_acl(plan: &mut MigrationPlan, content: &str, tenant: &str) {
+    let source = "mosquitto-acl";
+    let mut user: Option<String> = None;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        match keyword {
+            "user" => user = Some(rest.to_string()),
+            "topic" | "pattern" => {
+                let (access, topic) = match rest.split_once(char::is_whitespace) {
+                    Some((access, topic))
+                        if matches!(access, "read" | "write" | "readwrite" | "deny") =>
+                    {
+                        (access, topic.trim())
+                    }
+                    _ => ("readwrite", rest),
+                };
+                if topic.is_empty() {
+                    plan.skip("acl", source, line, "missing topic");
+                    continue;
+                }
+                let (action, permission) = match access {
+                    "read" => ("Subscribe", "Allow"),
+                    "write" => ("Publish", "Allow"),
+                    "deny" => ("All", "Deny"),
+                    _ => ("All", "Allow"),
+                };
+                let resource_name = if keyword == "pattern" {
+                    WILDCARD_RESOURCE.to_string()
+                } else if let Some(user) = &user {
+                    user.clone()
+                } else {
+                    plan.skip(
+                        "acl",
+                        source,
+                        line,
+                        "rules for anonymous clients are not supported",
+                    );
+                    continue;
+                };
+                plan.add_acl(
+                    tenant,
+                    source,
+                    "User",
+                    &resource_name,
+                    topic,
+                    None,
+                    action,
+                    permission,
+                );
+            }
+            _ => plan.skip(
+                "acl",
+                source,
+                line,
+                format!("unsupported directive '{keyword}'"),
+            ),
+        }
+    }
+}
+
+/// Parse an EMQX built-in database user export, either the JSON array or the
+/// CSV file accepted by the EMQX user import API. Salted SHA-256 hashes are
+/// kept as they are when EMQX put the salt in front of the password, which is
+/// how RobustMQ verifies salted passwords.
+pub fn parse_emqx_users(
+    plan: &mut MigrationPlan,
+    content: &str,
+    tenant: &str,
+    salt_position: &str,
+    default_password: Option<&str>,
+) -> Result<(), String> {
+    let source = "emqx-users";
+    let rows = if content.trim_start().starts_with('[') {
+        serde_json::from_str::<Vec<Value>>(content).map_err(|e| e.to_string())?
+    } else {
+        csv_rows(content)
+    };
+
+    for row in rows {
+        let field = |key: &str| match row.get(key) {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Null) | None => String::new(),
+            Some(other) => other.to_string(),
+        };
+        let username = field("user_id");
+        if username.is_empty() {
+            plan.skip("user", source, "", "missing user_id");
+            continue;
+        }
+        let is_superuser = matches!(field("is_superuser").as_str(), "true" | "1");
+
+        let password = field("password");
+        if !password.is_empty() {
+            plan.add_user(
+                source,
+                "",
+                new_user(tenant, &username, &password, None, is_superuser),
+            );
+            continue;
+        }
+
+        let hash = field("password_hash");
+        let salt = field("salt");
+        let reason = if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            Some("only sha256 password hashes are supported")
+        } else if salt.is_empty() {
+            Some("unsalted password hashes are not supported")
+        } else if salt_position != "prefix" {
+            Some("salt position must be prefix to keep the password hash")
+        } else {
+            None
+        };
+        match (reason, default_password) {
+            (None, _) => plan.add_user(
+                source,
+                "",
+                new_user(
+                    tenant,
+                    &username,
+                    &hash.to_lowercase(),
+                    Some(salt),
+                    is_superuser,
+                ),
+            ),
+            (Some(reason), Some(password)) => plan.add_user(
+                source,
+                format!("{reason}, default password set"),
+                new_user(tenant, &username, password, None, is_superuser),
+            ),
+            (Some(reason), None) => plan.skip("user", source, &username, reason),
+        }
+    }
+    Ok(())
+}
+
+fn csv_rows(content: &str) -> Vec<Value> {
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+    let Some(header) = lines.next() else {
+        return Vec::new();
+    };
+    let columns: Vec<&str> = header.split(',').map(str::trim).collect();
+    lines
+        .map(|line| {
+            let row = columns
+                .iter()
+                .zip(line.split(','))
+                .map(|(column, value)| (column.to_string(), Value::String(value.trim().into())))
+                .collect();
+            Value::Object(row)
+        })
+        .collect()
+}
+
+fn new_user(
+    tenant: &str,
+    username: &str,
+    password: &str,
+    salt: Option<String>,
+    is_superuser: bool,
+) -> CreateUserReq {
+    CreateUserReq {
+        tenant: tenant.to_string(),
+        username: username.to_string(),
+        password: password.to_string(),
+        salt,
+        hash_algorithm: None,
+        is_superuser,
+    }
+}
+
+/// Parse EMQX built-in database authorization rules as returned by the EMQX
+/// REST API: `{"data": [{"username"|"clientid": .., "rules": [..]}]}` for the
+/// per user and per client rules and `{"rules": [..]}` for the rules of all
+/// clients. A JSON array of those documents is accepted as well.
+pub fn parse_emqx_acl_json(plan: &mut MigrationPlan, value: &Value, tenant: &str) {
+    let source = "emqx-acl";
+    if let Value::Array(documents) = value {
+        for document in documents {
+            parse_emqx_acl_json(plan, document, tenant);
+        }
+        return;
+    }
+    if let Some(rules) = value.get("rules") {
+        add_emqx_json_rules(plan, tenant, "User", WILDCARD_RESOURCE, rules);
+    }
+    let Some(Value::Array(entries)) = value.get("data") else {
+        return;
+    };
+    for entry in entries {
+        let (resource_type, resource_name) = match (entry.get("username"), entry.get("clientid")) {
+            (Some(Value::String(username)), _) => ("User", username),
+            (_, Some(Value::String(client_id))) => ("ClientId", client_id),
+            _ => {
+                plan.skip(
+                    "acl",
+                    source,
+                    &entry.to_string(),
+                    "missing username or clientid",
+                );
+                continue;
+            }
+        };
+        if let Some(rules) = entry.get("rules") {
+            add_emqx_json_rules(plan, tenant, resource_type, resource_name, rules);
+        }
+    }
+}
+
+fn add_emqx_json_rules(
+    plan: &mut MigrationPlan,
+    tenant: &str,
+    resource_type: &str,
+    resource_name: &str,
+    rules: &Value,
+) {
+    let source = "emqx-acl";
+    let Value::Array(rules) = rules else {
+        return;
+    };
+    for rule in rules {
+        let text = |key: &str| rule.get(key).and_then(Value::as_str).unwrap_or_default();
+        let (Some(action), Some(permission)) = (
+            emqx_action(text("action")),
+            emqx_permission(text("permission")),
+        ) else {
+            plan.skip(
+                "acl",
+                source,
+                &rule.to_string(),
+                "unsupported action or permission",
+            );
+            continue;
+        };
+        plan.add_acl(
+            tenant,
+            source,
+            resource_type,
+            resource_name,
+            &emqx_topic(text("topic")),
+            None,
+            action,
+            permission,
+        );
+    }
+}
+
+fn emqx_action(action: &str) -> Option<&'static str> {
+    match action {
+        "publish" => Some("Publish"),
+        "subscribe" => Some("Subscribe"),
+        "all" => Some("All"),
+        _ => None,
+    }
+}
+
+fn emqx_permission(permission: &str) -> Option<&'static str> {
+    match permission {
+        "allow" => Some("Allow"),
+        "deny" => Some("Deny"),
+        _ => None,
+    }
+}
+
+fn emqx_topic(topic: &str) -> String {
+    topic
+        .replace("${username}", "%u")
+        .replace("${clientid}", "%c")
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum ErlTerm {
+    /// Atoms and, as rules never match on them, numbers.
+    Atom(String),
+    Str(String),
+    Tuple(Vec<ErlTerm>),
+    List(Vec<ErlTerm>),
+}
+
+impl ErlTerm {
+    fn atom(&self) -> Option<&str> {
+        match self {
+            ErlTerm::Atom(atom) => Some(atom),
+            _ => None,
+        }
+    }
+}
+
+struct ErlParser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl ErlParser<'_> {
+    fn skip_blank(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            if c == '%' {
+                while self.chars.next_if(|&c| c != '\n').is_some() {}
+            } else if c.is_whitespace() {
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_blank();
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            other => Err(format!("expected '{expected}', found {other:?}")),
+        }
+    }
+
+    fn sequence(&mut self, close: char) -> Result<Vec<ErlTerm>, String> {
+        let mut terms = Vec::new();
+        self.skip_blank();
+        if self.chars.next_if_eq(&close).is_some() {
+            return Ok(terms);
+        }
+        loop {
+            terms.push(self.term()?);
+            self.skip_blank();
+            match self.chars.next() {
+                Some(',') => continue,
+                Some(c) if c == close => return Ok(terms),
+                other => return Err(format!("expected ',' or '{close}', found {other:?}")),
+            }
+        }
+    }
+
+    fn quoted(&mut self, quote: char) -> Result<String, String> {
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                Some('\\') => value.extend(self.chars.next()),
+                Some(c) if c == quote => return Ok(value),
+                Some(c) => value.push(c),
+                None => return Err("unterminated quoted term".to_string()),
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<ErlTerm, String> {
+        self.skip_blank();
+        match self.chars.next() {
+            Some('{') => Ok(ErlTerm::Tuple(self.sequence('}')?)),
+            Some('[') => Ok(ErlTerm::List(self.sequence(']')?)),
+            Some('"') => Ok(ErlTerm::Str(self.quoted('"')?)),
+            Some('\'') => Ok(ErlTerm::Atom(self.quoted('\'')?)),
+            Some(c) if c.is_ascii_digit() || c == '-' => {
+                let mut digits = c.to_string();
+                while let Some(c) = self.chars.next_if(char::is_ascii_digit) {
+                    digits.push(c);
+                }
+                Ok(ErlTerm::Atom(digits))
+            }
+            Some(c) if c.is_ascii_lowercase() => {
+                let mut atom = c.to_string();
+                while let Some(c) = self
+                    .chars
+                    .next_if(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '@')
+                {
+                    atom.push(c);
+                }
+                Ok(ErlTerm::Atom(atom))
+            }
+            other => Err(format!("unexpected {other:?}")),
+        }
+    }
+
+    fn terms(mut self) -> Result<Vec<ErlTerm>, String> {
+        let mut terms = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.chars.peek().is_none() {
+                return Ok(terms);
+            }
+            terms.push(self.term()?);
+            self.expect('.')?;
+        }
+    }
+}
+
+/// Parse an EMQX `acl.conf` file, the Erlang terms of the file based
+/// authorization source, e.g. `{allow, {username, "u1"}, publish, ["t/#"]}.`.
+/// Regex, `and`/`or` and catch-all rules have no RobustMQ equivalent.
+pub fn parse_emqx_acl_conf(
+    plan: &mut MigrationPlan,
+    content: &str,
+    tenant: &str,
+) -> Result<(), String> {
+    let source = "emqx-acl";
+    let terms = ErlParser {
+        chars: content.chars().peekable(),
+    }
+    .terms()?;
+
+    for term in terms {
+        let name = format!("{term:?}");
+        let ErlTerm::Tuple(parts) = &term else {
+            plan.skip("acl", source, &name, "rule is not a tuple");
+            continue;
+        };
+        if parts.len() == 2 {
+            plan.skip(
+                "acl",
+                source,
+                &name,
+                "catch-all rule, configure the broker's no-match policy instead",
+            );
+            continue;
+        }
+        let [permission, who, action, topics] = parts.as_slice() else {
+            plan.skip(
+                "acl",
+                source,
+                &name,
+                "expected {Permission, Who, Action, Topics}",
+            );
+            continue;
+        };
+
+        let permission = permission.atom().and_then(emqx_permission);
+        let action = match action {
+            ErlTerm::Tuple(action) => action.first().and_then(ErlTerm::atom),
+            action => action.atom(),
+        }
+        .and_then(emqx_action);
+        let who = match who {
+            ErlTerm::Atom(all) if all == "all" => {
+                Some(("User", WILDCARD_RESOURCE.to_string(), None))
+            }
+            ErlTerm::Tuple(who) => match who.as_slice() {
+                [ErlTerm::Atom(kind), ErlTerm::Str(value)] => match kind.as_str() {
+                    "user" | "username" => Some(("User", value.clone(), None)),
+                    "client" | "clientid" => Some(("ClientId", value.clone(), None)),
+                    "ipaddr" => Some(("User", WILDCARD_RESOURCE.to_string(), Some(value.clone()))),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        };
+        let (Some(permission), Some(action), Some((resource_type, resource_name, ip))) =
+            (permission, action, who)
+        else {
+            plan.skip(
+                "acl",
+                source,
+                &name,
+                "unsupported permission, action or client match",
+            );
+            continue;
+        };
+
+        let topics = match topics {
+            ErlTerm::List(topics) => topics.clone(),
+            topic => vec![topic.clone()],
+        };
+        for topic in topics {
+            let topic = match &topic {
+                ErlTerm::Str(topic) => topic.clone(),
+                ErlTerm::Tuple(eq) => match eq.as_slice() {
+                    [ErlTerm::Atom(op), ErlTerm::Str(topic)] if op == "eq" => topic.clone(),
+                    _ => {
+                        plan.skip("acl", source, &name, "unsupported topic match");
+                        continue;
+                    }
+                },
+                _ => {
+                    plan.skip("acl", source, &name, "unsupported topic match");
+                    continue;
+                }
+            };
+            plan.add_acl(
+                tenant,
+                source,
+                resource_type,
+                &resource_name,
+                &emqx_topic(&topic),
+                ip.clone(),
+                action,
+                permission,
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Parse retained messages as returned by the EMQX retainer API, a single
+/// message, a JSON array or a `{"data": [..]}` page. Payloads are base64.
+pub fn parse_emqx_retained(plan: &mut MigrationPlan, value: &Value) {
+    let source = "emqx-retained";
+    let messages = match value {
+        Value::Array(messages) => messages.clone(),
+        Value::Object(_) => match value.get("data") {
+            Some(Value::Array(messages)) => messages.clone(),
+            _ => vec![value.clone()],
+        },
+        _ => Vec::new(),
+    };
+    for message in messages {
+        let Some(topic) = message.get("topic").and_then(Value::as_str) else {
+            plan.skip("retain", source, &message.to_string(), "missing topic");
+            continue;
+        };
+        let Some(payload) = message.get("payload").and_then(Value::as_str) else {
+            plan.skip("retain", source, topic, "missing payload");
+            continue;
+        };
+        let payload = match STANDARD.decode(payload) {
+            Ok(payload) => payload,
+            Err(e) => {
+                plan.skip(
+                    "retain",
+                    source,
+                    topic,
+                    format!("invalid base64 payload: {e}"),
+                );
+                continue;
+            }
+        };
+        let qos = message.get("qos").and_then(Value::as_i64).unwrap_or(0) as i32;
+        plan.retained.push((
+            source.to_string(),
+            RetainedMessage {
+                topic: topic.to_string(),
+                payload,
+                qos,
+            },
+        ));
+    }
+}
+
+struct EmqxApi<'a> {
+    client: reqwest::Client,
+    base: &'a str,
+    key: &'a str,
+    secret: &'a str,
+}
+
+impl EmqxApi<'_> {
+    async fn get(&self, url: reqwest::Url) -> Result<Value, String> {
+        let response = self
+            .client
+            .get(url.clone())
+            .basic_auth(self.key, Some(self.secret))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("{} returned {}", url, response.status()));
+        }
+        response.json().await.map_err(|e| e.to_string())
+    }
+
+    fn url(&self, segments: &[&str], page: Option<usize>) -> Result<reqwest::Url, String> {
+        let mut url = reqwest::Url::parse(self.base).map_err(|e| e.to_string())?;
+        url.path_segments_mut()
+            .map_err(|_| format!("invalid EMQX API address {}", self.base))?
+            .pop_if_empty()
+            .extend(segments);
+        if let Some(page) = page {
+            url.query_pairs_mut()
+                .append_pair("page", &page.to_string())
+                .append_pair("limit", &EMQX_API_PAGE_LIMIT.to_string());
+        }
+        Ok(url)
+    }
+
+    async fn pages(&self, segments: &[&str]) -> Result<Vec<Value>, String> {
+        let mut entries = Vec::new();
+        for page in 1.. {
+            let value = self.get(self.url(segments, Some(page))?).await?;
+            let data = match value.get("data") {
+                Some(Value::Array(data)) => data.clone(),
+                _ => Vec::new(),
+            };
+            let done = data.len() < EMQX_API_PAGE_LIMIT;
+            entries.extend(data);
+            if done {
+                break;
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// Read the built-in database authorization rules and the retained messages
+/// from a running EMQX through its REST API. The API does not expose password
+/// hashes, users have to be migrated from an export file.
+async fn fetch_emqx_api(
+    plan: &mut MigrationPlan,
+    request: &MigrateArgsRequest,
+    base: &str,
+) -> Result<(), String> {
+    let api = EmqxApi {
+        client: reqwest::Client::new(),
+        base,
+        key: &request.emqx_api_key,
+        secret: &request.emqx_api_secret,
+    };
+    let rules = [
+        "api",
+        "v5",
+        "authorization",
+        "sources",
+        "built_in_database",
+        "rules",
+    ];
+    for kind in ["users", "clients"] {
+        let segments: Vec<&str> = rules.iter().copied().chain([kind]).collect();
+        let data = api.pages(&segments).await?;
+        parse_emqx_acl_json(plan, &serde_json::json!({ "data": data }), &request.tenant);
+    }
+    let segments: Vec<&str> = rules.iter().copied().chain(["all"]).collect();
+    parse_emqx_acl_json(
+        plan,
+        &api.get(api.url(&segments, None)?).await?,
+        &request.tenant,
+    );
+
+    for message in api
+        .pages(&["api", "v5", "mqtt", "retainer", "messages"])
+        .await?
+    {
+        let Some(topic) = message.get("topic").and_then(Value::as_str) else {
+            continue;
+        };
+        let url = api.url(&["api", "v5", "mqtt", "retainer", "message", topic], None)?;
+        match api.get(url).await {
+            Ok(message) => parse_emqx_retained(plan, &message),
+            Err(e) => plan.skip("retain", "emqx-retained", topic, e),
+        }
+    }
+    Ok(())
+}
+
+fn read_source(path: &str) -> Result<String, String> {
+    std::fs::read_to_string(path).map_err(|e| format!("failed to read {path}: {e}"))
+}
+
+/// Build the migration plan from every source given on the command line.
+pub async fn build_plan(request: &MigrateArgsRequest) -> Result<MigrationPlan, String> {
+    let mut plan = MigrationPlan::default();
+    let tenant = request.tenant.as_str();
+    let default_password = request.default_password.as_deref();
+
+    if let Some(path) = &request.mosquitto_passwd {
+        parse_mosquitto_passwd(&mut plan, &read_source(path)?, tenant, default_password);
+    }
+    if let Some(path) = &request.mosquitto_acl {
+        parse_mosquitto_acl(&mut plan, &read_source(path)?, tenant);
+    }
+    if let Some(path) = &request.emqx_users {
+        let content = read_source(path)?;
+        parse_emqx_users(
+            &mut plan,
+            &content,
+            tenant,
+            &request.emqx_salt_position,
+            default_password,
+        )?;
+    }
+    if let Some(path) = &request.emqx_acl {
+        let content = read_source(path)?;
+        match serde_json::from_str::<Value>(&content) {
+            Ok(value) => parse_emqx_acl_json(&mut plan, &value, tenant),
+            Err(_) => parse_emqx_acl_conf(&mut plan, &content, tenant)?,
+        }
+    }
+    if let Some(path) = &request.emqx_retained {
+        let value = serde_json::from_str(&read_source(path)?).map_err(|e| e.to_string())?;
+        parse_emqx_retained(&mut plan, &value);
+    }
+    if let Some(base) = &request.emqx_api {
+        fetch_emqx_api(&mut plan, request, base).await?;
+    }
+    Ok(plan)
+}
+
+fn admin_result(result: Result<String, impl std::fmt::Display>) -> Result<(), String> {
+    let raw = result.map_err(|e| e.to_string())?;
+    match serde_json::from_str::<AdminServerResponse<Value>>(&raw) {
+        Ok(resp) if resp.code != 0 => Err(resp.error.unwrap_or_default()),
+        _ => Ok(()),
+    }
+}
+
+fn item(
+    kind: &str,
+    source: &str,
+    name: &str,
+    detail: &str,
+    result: Result<(), String>,
+) -> MigrationItem {
+    let (status, detail) = match result {
+        Ok(()) => (MigrationStatus::Imported, detail.to_string()),
+        Err(e) => (MigrationStatus::Failed, e),
+    };
+    MigrationItem {
+        kind: kind.to_string(),
+        source: source.to_string(),
+        name: name.to_string(),
+        status,
+        detail,
+    }
+}
+
+/// Apply the plan, or only list what would be imported on a dry run.
+pub async fn execute_plan(
+    plan: MigrationPlan,
+    request: &MigrateArgsRequest,
+    admin_client: &AdminHttpClient,
+) -> MigrationReport {
+    let mut items = Vec::new();
+    let planned = |kind: &str, source: &str, name: &str, detail: &str| MigrationItem {
+        kind: kind.to_string(),
+        source: source.to_string(),
+        name: name.to_string(),
+        status: MigrationStatus::Planned,
+        detail: detail.to_string(),
+    };
+
+    for (source, detail, user) in &plan.users {
+        items.push(if request.dry_run {
+            planned("user", source, &user.username, detail)
+        } else {
+            let result = admin_result(admin_client.create_user(user).await);
+            item("user", source, &user.username, detail, result)
+        });
+    }
+
+    for (source, acl) in &plan.acls {
+        let name = format!(
+            "{} {}:{} {} {}",
+            acl.permission,
+            acl.resource_type,
+            acl.resource_name,
+            acl.action,
+            acl.topic.clone().unwrap_or_default()
+        );
+        items.push(if request.dry_run {
+            planned("acl", source, &name, "")
+        } else {
+            let result = admin_result(admin_client.create_acl(acl).await);
+            item("acl", source, &name, "", result)
+        });
+    }
+
+    if request.dry_run {
+        for (source, message) in &plan.retained {
+            items.push(planned("retain", source, &message.topic, ""));
+        }
+    } else if !plan.retained.is_empty() {
+        let cli = connect_server5(
+            &unique_id(),
+            request.mqtt_username.clone(),
+            request.mqtt_password.clone(),
+            &format!("tcp://{}", request.mqtt_server),
+            false,
+            false,
+        );
+        for (source, message) in &plan.retained {
+            let msg = MessageBuilder::new()
+                .topic(message.topic.clone())
+                .payload(message.payload.clone())
+                .qos(message.qos)
+                .retained(true)
+                .finalize();
+            let result = cli.publish(msg).map_err(|e| e.to_string());
+            items.push(item("retain", source, &message.topic, "", result));
+        }
+        if let Err(e) = cli.disconnect(DisconnectOptionsBuilder::new().finalize()) {
+            println!("Warning: disconnect error: {e:?}");
+        }
+    }
+
+    items.extend(plan.skipped);
+    let count = |status| items.iter().filter(|i| i.status == status).count();
+    MigrationReport {
+        dry_run: request.dry_run,
+        imported: count(MigrationStatus::Imported),
+        planned: count(MigrationStatus::Planned),
+        skipped: count(MigrationStatus::Skipped),
+        failed: count(MigrationStatus::Failed),
+        items,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mosquitto_passwd_and_acl() {
+        let mut plan = MigrationPlan::default();
+        let passwd = "# users\nplain:secret\nhashed:$7$101$c2FsdA==$aGFzaA==\nbroken\n";
+        parse_mosquitto_passwd(&mut plan, passwd, "default", None);
+        assert_eq!(plan.users.len(), 1);
+        assert_eq!(plan.users[0].2.password, "secret");
+        assert_eq!(plan.skipped.len(), 2);
+
+        let mut plan = MigrationPlan::default();
+        parse_mosquitto_passwd(&mut plan, passwd, "default", Some("changeme"));
+        assert_eq!(plan.users.len(), 2);
+        assert_eq!(plan.users[1].2.password, "changeme");
+
+        let mut plan = MigrationPlan::default();
+        let acl = "topic read $SYS/#\nuser alice\ntopic sensors/#\ntopic write cmd/1\n\
+                   pattern read devices/%u/%c/#\n";
+        parse_mosquitto_acl(&mut plan, acl, "default");
+        assert_eq!(plan.skipped.len(), 1);
+        let acls: Vec<_> = plan
+            .acls
+            .iter()
+            .map(|(_, a)| {
+                (
+                    a.resource_name.as_str(),
+                    a.action.as_str(),
+                    a.topic.clone().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            acls,
+            vec![
+                ("alice", "All", "sensors/#".to_string()),
+                ("alice", "Publish", "cmd/1".to_string()),
+                ("*", "Subscribe", "devices/%u/%c/#".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_emqx_users() {
+        let hash = "a".repeat(64);
+        let json = format!(
+            r#"[{{"user_id":"u1","password_hash":"{hash}","salt":"s1","is_superuser":true}},
+                {{"user_id":"u2","password":"p2"}},
+                {{"user_id":"u3","password_hash":"$2b$12$abc","salt":""}}]"#
+        );
+        let mut plan = MigrationPlan::default();
+        parse_emqx_users(&mut plan, &json, "default", "prefix", None).unwrap();
+        assert_eq!(plan.users.len(), 2);
+        assert_eq!(plan.users[0].2.salt, Some("s1".to_string()));
+        assert!(plan.users[0].2.is_superuser);
+        assert_eq!(plan.users[1].2.password, "p2");
+        assert_eq!(plan.skipped.len(), 1);
+
+        let csv = format!("user_id,password_hash,salt,is_superuser\nu1,{hash},s1,false\n");
+        let mut plan = MigrationPlan::default();
+        parse_emqx_users(&mut plan, &csv, "default", "suffix", None).unwrap();
+        assert!(plan.users.is_empty());
+        assert_eq!(plan.skipped.len(), 1);
+    }
+
+    #[test]
+    fn test_emqx_acl_conf_and_json() {
+        let conf = r##"
+            %% comment
+            {allow, {username, "dashboard"}, subscribe, ["$SYS/#"]}.
+            {allow, {ipaddr, "127.0.0.1"}, all, ["$SYS/#", {eq, "#"}]}.
+            {deny, all, subscribe, ["${clientid}/#"]}.
+            {allow, {username, {re, "^dash"}}, publish, ["a"]}.
+            {allow, all}.
+        "##;
+        let mut plan = MigrationPlan::default();
+        parse_emqx_acl_conf(&mut plan, conf, "default").unwrap();
+        assert_eq!(plan.acls.len(), 4);
+        assert_eq!(plan.acls[1].1.ip, Some("127.0.0.1".to_string()));
+        assert_eq!(plan.acls[3].1.topic, Some("%c/#".to_string()));
+        assert_eq!(plan.acls[3].1.permission, "Deny");
+        assert_eq!(plan.skipped.len(), 2);
+
+        let json: Value = serde_json::from_str(
+            r#"{"data":[{"clientid":"c1","rules":[
+                {"topic":"t/1","permission":"allow","action":"publish"},
+                {"topic":"t/2","permission":"maybe","action":"publish"}]}]}"#,
+        )
+        .unwrap();
+        let mut plan = MigrationPlan::default();
+        parse_emqx_acl_json(&mut plan, &json, "default");
+        assert_eq!(plan.acls.len(), 1);
+        assert_eq!(plan.acls[0].1.resource_type, "ClientId");
+        assert_eq!(plan.skipped.len(), 1);
+    }
+
+    #[test]
+    fn test_emqx_retained() {
+        let json: Value =
+            serde_json::from_str(r#"[{"topic":"a/b","payload":"aGVsbG8=","qos":1},{"topic":"c"}]"#)
+                .unwrap();
+        let mut plan = MigrationPlan::default();
+        parse_emqx_retained(&mut plan, &json);
+        assert_eq!(plan.retained.len(), 1);
+        assert_eq!(plan.retained[0].1.payload, b"hello");
+        assert_eq!(plan.retained[0].1.qos, 1);
+        assert_eq!(plan.skipped.len(), 1);
+    }
+}
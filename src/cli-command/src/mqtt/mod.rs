@@ -13,5 +13,6 @@
 // limitations under the License.
 
 pub mod command;
+pub mod migrate;
 pub mod params;
 pub mod pub_sub;
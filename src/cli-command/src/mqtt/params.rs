@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use crate::mqtt::command::MqttActionType;
+use crate::mqtt::migrate::MigrateArgsRequest;
 use crate::mqtt::pub_sub::{PublishArgsRequest, SubscribeArgsRequest};
 use admin_server::cluster::connector::FailureStrategy;
 use clap::builder::EnumValueParser;
@@ -178,6 +179,8 @@ pub struct CreateAclArgs {
         default_missing_value = "Allow",
     )]
     pub permission: EnumAclPermission,
+    #[arg(long)]
+    pub priority: Option<u32>,
 }
 
 #[derive(clap::Args, Debug)]
@@ -263,7 +266,35 @@ pub enum SlowSubscribeActionType {
     List,
 }
 
-// ---- system alarm ----
+// ---- slow request ----
+#[derive(clap::Args, Debug)]
+#[command(author = "RobustMQ", about = "related operations of slow request, such as listing", long_about = None)]
+#[command(next_line_help = true)]
+pub struct SlowRequestArgs {
+    #[command(subcommand)]
+    pub action: SlowRequestActionType,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum SlowRequestActionType {
+    #[command(author = "RobustMQ", about = "action: list slow request", long_about = None)]
+    List(ListSlowRequestArgs),
+}
+
+#[derive(clap::Args, Debug)]
+#[command(next_line_help = true)]
+pub struct ListSlowRequestArgs {
+    #[arg(long, required = false, help = "Filter by client id (contains match)")]
+    pub client_id: Option<String>,
+    #[arg(
+        long,
+        required = false,
+        help = "Filter by packet type, such as Publish or Subscribe"
+    )]
+    pub packet_type: Option<String>,
+}
+
+// ---- topic ----
 #[derive(clap::Args, Debug)]
 #[command(author = "RobustMQ", about = "related operations of topic, such as setting and listing", long_about = None
 )]
@@ -292,7 +323,18 @@ pub struct SystemAlarmArgs {
 #[derive(Debug, clap::Subcommand)]
 pub enum SystemAlarmActionType {
     #[command(author = "RobustMQ", about = "action: list system alarm", long_about = None)]
-    List,
+    List(ListSystemAlarmArgs),
+}
+
+#[derive(clap::Args, Debug)]
+#[command(next_line_help = true)]
+pub struct ListSystemAlarmArgs {
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Only list the alarms currently active"
+    )]
+    pub active: bool,
 }
 
 // topic rewrite rule
@@ -376,6 +418,8 @@ pub struct CreateConnectorArgs {
     pub topic_name: String,
     #[arg(short = 'T', long, default_value = "default")]
     pub tenant: String,
+    #[arg(short = 'r', long)]
+    pub max_records_per_sec: Option<u32>,
 }
 
 #[derive(clap::Args, Debug)]
@@ -494,13 +538,22 @@ pub fn process_slow_sub_args(args: SlowSubscribeArgs) -> MqttActionType {
     }
 }
 
+pub fn process_slow_request_args(args: SlowRequestArgs) -> MqttActionType {
+    match args.action {
+        SlowRequestActionType::List(arg) => MqttActionType::ListSlowRequest {
+            client_id: arg.client_id,
+            packet_type: arg.packet_type,
+        },
+    }
+}
+
 pub fn process_flapping_detect_args(_args: FlappingDetectArgs) -> MqttActionType {
     MqttActionType::ListFlappingDetect
 }
 
 pub fn process_system_alarm_args(args: SystemAlarmArgs) -> MqttActionType {
     match args.action {
-        SystemAlarmActionType::List => MqttActionType::ListSystemAlarm,
+        SystemAlarmActionType::List(arg) => MqttActionType::ListSystemAlarm { active: arg.active },
     }
 }
 
@@ -528,6 +581,8 @@ pub fn process_user_args(args: UserArgs) -> MqttActionType {
                 tenant: DEFAULT_TENANT.to_string(),
                 username: arg.username,
                 password: arg.password,
+                salt: None,
+                hash_algorithm: None,
                 is_superuser: arg.is_superuser,
             })
         }
@@ -554,6 +609,7 @@ pub fn process_acl_args(args: AclArgs) -> Result<MqttActionType, Box<dyn std::er
                 ip: arg.ip,
                 action: arg.action.to_string(),
                 permission: arg.permission.to_string(),
+                priority: arg.priority,
             },
         )),
         AclActionType::Delete(arg) => Ok(MqttActionType::DeleteAcl(
@@ -615,6 +671,7 @@ pub fn process_connector_args(args: ConnectorArgs) -> MqttActionType {
                 },
                 tenant: arg.tenant,
                 topic_name: arg.topic_name,
+                max_records_per_sec: arg.max_records_per_sec,
             })
         }
         ConnectorActionType::Delete(arg) => {
@@ -798,3 +855,93 @@ pub fn process_subscribe_args(args: PubSubArgs) -> MqttActionType {
         qos: args.qos,
     })
 }
+
+// ---- migrate ----
+#[derive(clap::Args, Debug)]
+#[command(author = "RobustMQ", about = "Import users, ACL rules and retained messages from EMQX or Mosquitto", long_about = None)]
+#[command(next_line_help = true)]
+pub struct MigrateArgs {
+    #[arg(long, default_value_t = DEFAULT_TENANT.to_string(), help = "Tenant to import into")]
+    pub tenant: String,
+    #[arg(long, help = "EMQX built-in database user export (JSON or CSV)")]
+    pub emqx_users: Option<String>,
+    #[arg(
+        long,
+        default_value = "suffix",
+        value_parser = ["prefix", "suffix"],
+        help = "Salt position of the EMQX sha256 password hashes, only prefix hashes can be kept"
+    )]
+    pub emqx_salt_position: String,
+    #[arg(
+        long,
+        help = "EMQX acl.conf file or built-in database rules (API JSON)"
+    )]
+    pub emqx_acl: Option<String>,
+    #[arg(long, help = "EMQX retained messages (retainer API JSON)")]
+    pub emqx_retained: Option<String>,
+    #[arg(
+        long,
+        help = "EMQX REST API address to read ACL rules and retained messages from, e.g. http://127.0.0.1:18083"
+    )]
+    pub emqx_api: Option<String>,
+    #[arg(long, default_value = "", help = "EMQX API key")]
+    pub emqx_api_key: String,
+    #[arg(long, default_value = "", help = "EMQX API secret")]
+    pub emqx_api_secret: String,
+    #[arg(long, help = "Mosquitto password file")]
+    pub mosquitto_passwd: Option<String>,
+    #[arg(long, help = "Mosquitto ACL file")]
+    pub mosquitto_acl: Option<String>,
+    #[arg(
+        long,
+        help = "Password for users whose password hash cannot be kept (default: skip them)"
+    )]
+    pub default_password: Option<String>,
+    #[arg(
+        long,
+        default_value = "127.0.0.1:1883",
+        help = "MQTT address retained messages are published to"
+    )]
+    pub mqtt_server: String,
+    #[arg(
+        long,
+        default_value = "",
+        help = "MQTT username used to publish retained messages"
+    )]
+    pub mqtt_username: String,
+    #[arg(
+        long,
+        default_value = "",
+        help = "MQTT password used to publish retained messages"
+    )]
+    pub mqtt_password: String,
+    #[arg(
+        long,
+        default_value_t = false,
+        help = "Only report what would be imported"
+    )]
+    pub dry_run: bool,
+    #[arg(long, help = "Write the migration report as JSON to this file")]
+    pub report: Option<String>,
+}
+
+pub fn process_migrate_args(args: MigrateArgs) -> MqttActionType {
+    MqttActionType::Migrate(MigrateArgsRequest {
+        tenant: args.tenant,
+        emqx_users: args.emqx_users,
+        emqx_salt_position: args.emqx_salt_position,
+        emqx_acl: args.emqx_acl,
+        emqx_retained: args.emqx_retained,
+        emqx_api: args.emqx_api,
+        emqx_api_key: args.emqx_api_key,
+        emqx_api_secret: args.emqx_api_secret,
+        mosquitto_passwd: args.mosquitto_passwd,
+        mosquitto_acl: args.mosquitto_acl,
+        default_password: args.default_password,
+        mqtt_server: args.mqtt_server,
+        mqtt_username: args.mqtt_username,
+        mqtt_password: args.mqtt_password,
+        dry_run: args.dry_run,
+        report: args.report,
+    })
+}
@@ -26,6 +26,9 @@ pub enum LogConfigError {
     #[error(transparent)]
     RollingFileAppenderInit(#[from] tracing_appender::rolling::InitError),
 
+    #[error("Invalid log level: {0}")]
+    InvalidLevel(String),
+
     #[error(transparent)]
     Addr(#[from] std::net::AddrParseError),
 }
@@ -0,0 +1,321 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test-only fault injection. Integration tests add faults through the admin
+//! API to delay or fail gRPC client calls, storage adapter operations and Raft
+//! network sends of a node, so failure scenarios can be scripted against a
+//! running cluster. Nothing is injected unless the node enables
+//! `[fault_injection]`, and a disabled injector costs a single atomic load.
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::time::sleep;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum FaultPoint {
+    /// Every attempt of a gRPC client call. The target is matched against
+    /// `<method>@<addr>`, e.g. `PlacementService/CreateTopic@127.0.0.1:1228`.
+    GrpcClient,
+    /// Storage adapter operations of a topic. The target is matched against
+    /// `<tenant>/<topic>`.
+    Storage,
+    /// Raft RPCs sent to a peer. The target is matched against
+    /// `<machine>@<addr>`, e.g. `metadata_0@127.0.0.1:1228`.
+    RaftNetwork,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FaultAction {
+    /// Sleep `delay_ms` before the operation runs.
+    Delay,
+    /// Fail the operation with an application error.
+    Error,
+    /// Fail the operation as if the peer were unreachable.
+    Partition,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FaultRule {
+    pub id: String,
+    pub point: FaultPoint,
+    /// Substring of the fault point target; empty matches every target.
+    #[serde(default)]
+    pub target: String,
+    pub action: FaultAction,
+    #[serde(default)]
+    pub delay_ms: u64,
+    /// Chance in `[0, 1]` that a matching operation is hit.
+    #[serde(default = "default_probability")]
+    pub probability: f64,
+    /// Remove the rule after it has been hit this many times, 0 never does.
+    #[serde(default)]
+    pub max_hits: u64,
+}
+
+fn default_probability() -> f64 {
+    1.0
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FaultRuleState {
+    #[serde(flatten)]
+    pub rule: FaultRule,
+    pub hits: u64,
+}
+
+/// A fault that failed the operation, for the caller to map onto its own error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InjectedFault {
+    Error(String),
+    Partition(String),
+}
+
+impl fmt::Display for InjectedFault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InjectedFault::Error(rule_id) => write!(f, "injected error (fault {})", rule_id),
+            InjectedFault::Partition(rule_id) => {
+                write!(f, "injected partition (fault {})", rule_id)
+            }
+        }
+    }
+}
+
+struct FaultEntry {
+    rule: FaultRule,
+    hits: AtomicU64,
+}
+
+#[derive(Default)]
+pub struct FaultInjector {
+    enabled: AtomicBool,
+    rules: DashMap<String, FaultEntry>,
+}
+
+pub fn fault_injector() -> &'static FaultInjector {
+    static INJECTOR: OnceLock<FaultInjector> = OnceLock::new();
+    INJECTOR.get_or_init(FaultInjector::default)
+}
+
+impl FaultInjector {
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            self.rules.clear();
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Add a rule, replacing any rule with the same id.
+    pub fn add_rule(&self, rule: FaultRule) -> Result<(), String> {
+        if !self.is_enabled() {
+            return Err("fault injection is disabled on this node".to_string());
+        }
+        if rule.id.is_empty() {
+            return Err("fault id cannot be empty".to_string());
+        }
+        if !(0.0..=1.0).contains(&rule.probability) {
+            return Err(format!(
+                "fault probability {} is not in [0, 1]",
+                rule.probability
+            ));
+        }
+        if rule.action == FaultAction::Delay && rule.delay_ms == 0 {
+            return Err("a delay fault needs delay_ms > 0".to_string());
+        }
+        self.rules.insert(
+            rule.id.clone(),
+            FaultEntry {
+                rule,
+                hits: AtomicU64::new(0),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn remove_rule(&self, id: &str) -> bool {
+        self.rules.remove(id).is_some()
+    }
+
+    pub fn clear(&self) {
+        self.rules.clear();
+    }
+
+    pub fn list(&self) -> Vec<FaultRuleState> {
+        let mut rules: Vec<FaultRuleState> = self
+            .rules
+            .iter()
+            .map(|entry| FaultRuleState {
+                rule: entry.rule.clone(),
+                hits: entry.hits.load(Ordering::Relaxed),
+            })
+            .collect();
+        rules.sort_by(|a, b| a.rule.id.cmp(&b.rule.id));
+        rules
+    }
+
+    /// Apply the rules matching `target` at `point`: delays are slept here,
+    /// the first error or partition is returned.
+    pub async fn inject(&self, point: FaultPoint, target: &str) -> Result<(), InjectedFault> {
+        if !self.is_enabled() || self.rules.is_empty() {
+            return Ok(());
+        }
+
+        let mut delay_ms = 0;
+        let mut fault = None;
+        let mut exhausted = Vec::new();
+        for entry in self.rules.iter() {
+            let rule = &entry.rule;
+            if rule.point != point || !target.contains(rule.target.as_str()) {
+                continue;
+            }
+            if rule.probability < 1.0 && rand::random::<f64>() >= rule.probability {
+                continue;
+            }
+            let hits = entry.hits.fetch_add(1, Ordering::Relaxed) + 1;
+            if rule.max_hits > 0 {
+                if hits > rule.max_hits {
+                    continue;
+                }
+                if hits == rule.max_hits {
+                    exhausted.push(rule.id.clone());
+                }
+            }
+            match rule.action {
+                FaultAction::Delay => delay_ms = delay_ms.max(rule.delay_ms),
+                FaultAction::Error => {
+                    fault.get_or_insert(InjectedFault::Error(rule.id.clone()));
+                }
+                FaultAction::Partition => {
+                    fault.get_or_insert(InjectedFault::Partition(rule.id.clone()));
+                }
+            }
+        }
+        for id in exhausted {
+            self.rules.remove(&id);
+        }
+
+        if delay_ms > 0 {
+            sleep(Duration::from_millis(delay_ms)).await;
+        }
+        match fault {
+            Some(fault) => Err(fault),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Shorthand for `fault_injector().inject(point, target)`.
+pub async fn inject_fault(point: FaultPoint, target: &str) -> Result<(), InjectedFault> {
+    fault_injector().inject(point, target).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: &str, point: FaultPoint, target: &str, action: FaultAction) -> FaultRule {
+        FaultRule {
+            id: id.to_string(),
+            point,
+            target: target.to_string(),
+            action,
+            delay_ms: 0,
+            probability: 1.0,
+            max_hits: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_inject_matches_point_and_target() {
+        let injector = FaultInjector::default();
+        let err = rule(
+            "e1",
+            FaultPoint::Storage,
+            "default/orders",
+            FaultAction::Error,
+        );
+        assert!(injector.add_rule(err.clone()).is_err());
+
+        injector.set_enabled(true);
+        injector.add_rule(err).unwrap();
+        assert_eq!(
+            injector.inject(FaultPoint::Storage, "default/orders").await,
+            Err(InjectedFault::Error("e1".to_string()))
+        );
+        assert!(injector
+            .inject(FaultPoint::Storage, "default/payments")
+            .await
+            .is_ok());
+        assert!(injector
+            .inject(FaultPoint::GrpcClient, "default/orders")
+            .await
+            .is_ok());
+
+        assert!(injector.remove_rule("e1"));
+        assert!(injector
+            .inject(FaultPoint::Storage, "default/orders")
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_inject_max_hits_removes_rule() {
+        let injector = FaultInjector::default();
+        injector.set_enabled(true);
+        let mut partition = rule("p1", FaultPoint::RaftNetwork, "", FaultAction::Partition);
+        partition.max_hits = 2;
+        injector.add_rule(partition).unwrap();
+
+        for _ in 0..2 {
+            assert_eq!(
+                injector
+                    .inject(FaultPoint::RaftNetwork, "metadata_0@127.0.0.1:1228")
+                    .await,
+                Err(InjectedFault::Partition("p1".to_string()))
+            );
+        }
+        assert!(injector
+            .inject(FaultPoint::RaftNetwork, "metadata_0@127.0.0.1:1228")
+            .await
+            .is_ok());
+        assert!(injector.list().is_empty());
+    }
+
+    #[test]
+    fn test_add_rule_validation() {
+        let injector = FaultInjector::default();
+        injector.set_enabled(true);
+        let mut delay = rule("d1", FaultPoint::GrpcClient, "", FaultAction::Delay);
+        assert!(injector.add_rule(delay.clone()).is_err());
+        delay.delay_ms = 100;
+        delay.probability = 1.5;
+        assert!(injector.add_rule(delay.clone()).is_err());
+        delay.probability = 0.5;
+        injector.add_rule(delay).unwrap();
+        assert_eq!(injector.list().len(), 1);
+
+        injector.set_enabled(false);
+        assert!(injector.list().is_empty());
+    }
+}
@@ -15,6 +15,7 @@
 #![allow(clippy::result_large_err)]
 pub mod enum_type;
 pub mod error;
+pub mod fault;
 pub mod http_error;
 pub mod http_response;
 pub mod inner_topic;
@@ -16,7 +16,7 @@
 use tracing::{level_filters::LevelFilter, Subscriber};
 use tracing_subscriber::{registry::LookupSpan, Layer};
 
-use crate::logging::config::BoxedLayer;
+use crate::logging::{config::BoxedLayer, level::LevelOverride};
 
 #[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "snake_case")]
@@ -71,16 +71,18 @@ pub(super) fn append_and_box<S, L>(self, layer: L) -> BoxedLayer<S>
             Filter::Target(target) => {
                 let filter = tracing_subscriber::filter::Targets::new()
                     .with_target(target.path, target.level);
-                layer.with_filter(filter).boxed()
+                layer.with_filter(LevelOverride::new(filter)).boxed()
             }
             Filter::Targets(targets) => {
                 let mut filter = tracing_subscriber::filter::Targets::new();
                 for target in targets {
                     filter = filter.with_target(target.path, target.level);
                 }
-                layer.with_filter(filter).boxed()
+                layer.with_filter(LevelOverride::new(filter)).boxed()
             }
-            Filter::Level(level) => layer.with_filter(LevelFilter::from(level)).boxed(),
+            Filter::Level(level) => layer
+                .with_filter(LevelOverride::new(LevelFilter::from(level)))
+                .boxed(),
         }
     }
 }
@@ -0,0 +1,141 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Process-wide log level override that can be changed at runtime.
+//!
+//! Every fmt appender's filter is wrapped in [`LevelOverride`]. While no
+//! override is set the appender's own filter from the logging configuration
+//! file applies; once set, the override level replaces it for all targets.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use tracing::{callsite, level_filters::LevelFilter, subscriber::Interest, Metadata};
+use tracing_subscriber::layer::{Context, Filter};
+
+use crate::error::log_config::LogConfigError;
+
+const NO_OVERRIDE: u8 = u8::MAX;
+
+static LOG_LEVEL_OVERRIDE: AtomicU8 = AtomicU8::new(NO_OVERRIDE);
+
+/// Set the runtime log level. An empty string clears the override and
+/// restores the levels from the logging configuration file.
+pub fn set_log_level_override(level: &str) -> Result<(), LogConfigError> {
+    let value = if level.trim().is_empty() {
+        NO_OVERRIDE
+    } else {
+        encode(parse_level(level)?)
+    };
+
+    if LOG_LEVEL_OVERRIDE.swap(value, Ordering::SeqCst) != value {
+        callsite::rebuild_interest_cache();
+    }
+    Ok(())
+}
+
+pub fn log_level_override() -> Option<LevelFilter> {
+    decode(LOG_LEVEL_OVERRIDE.load(Ordering::Relaxed))
+}
+
+pub fn parse_level(level: &str) -> Result<LevelFilter, LogConfigError> {
+    level
+        .trim()
+        .parse::<LevelFilter>()
+        .map_err(|_| LogConfigError::InvalidLevel(level.to_string()))
+}
+
+fn encode(level: LevelFilter) -> u8 {
+    match level {
+        LevelFilter::OFF => 0,
+        LevelFilter::ERROR => 1,
+        LevelFilter::WARN => 2,
+        LevelFilter::INFO => 3,
+        LevelFilter::DEBUG => 4,
+        _ => 5,
+    }
+}
+
+fn decode(value: u8) -> Option<LevelFilter> {
+    match value {
+        0 => Some(LevelFilter::OFF),
+        1 => Some(LevelFilter::ERROR),
+        2 => Some(LevelFilter::WARN),
+        3 => Some(LevelFilter::INFO),
+        4 => Some(LevelFilter::DEBUG),
+        5 => Some(LevelFilter::TRACE),
+        _ => None,
+    }
+}
+
+pub(super) struct LevelOverride<F> {
+    inner: F,
+}
+
+impl<F> LevelOverride<F> {
+    pub(super) fn new(inner: F) -> Self {
+        LevelOverride { inner }
+    }
+}
+
+impl<S, F> Filter<S> for LevelOverride<F>
+where
+    F: Filter<S>,
+{
+    fn enabled(&self, meta: &Metadata<'_>, cx: &Context<'_, S>) -> bool {
+        match log_level_override() {
+            Some(level) => meta.level() <= &level,
+            None => self.inner.enabled(meta, cx),
+        }
+    }
+
+    fn callsite_enabled(&self, meta: &'static Metadata<'static>) -> Interest {
+        match log_level_override() {
+            Some(level) if meta.level() <= &level => Interest::always(),
+            Some(_) => Interest::never(),
+            None => self.inner.callsite_enabled(meta),
+        }
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        log_level_override().or_else(|| self.inner.max_level_hint())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_level() {
+        assert_eq!(parse_level("debug").unwrap(), LevelFilter::DEBUG);
+        assert_eq!(parse_level(" WARN ").unwrap(), LevelFilter::WARN);
+        assert_eq!(parse_level("off").unwrap(), LevelFilter::OFF);
+        assert!(parse_level("verbose").is_err());
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        for level in [
+            LevelFilter::OFF,
+            LevelFilter::ERROR,
+            LevelFilter::WARN,
+            LevelFilter::INFO,
+            LevelFilter::DEBUG,
+            LevelFilter::TRACE,
+        ] {
+            assert_eq!(decode(encode(level)), Some(level));
+        }
+        assert_eq!(decode(NO_OVERRIDE), None);
+    }
+}
@@ -24,6 +24,7 @@
 mod console;
 mod filter;
 mod fmt;
+pub mod level;
 mod rolling_file;
 mod tokio_console;
 
@@ -12,11 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::fmt;
 use std::sync::atomic::AtomicU32;
 use std::sync::LazyLock;
 
+use dashmap::DashMap;
 use prometheus::{register_int_gauge_vec, IntGaugeVec};
-use tokio::runtime::{Builder, Runtime};
+use tokio::runtime::{Builder, Handle, Runtime};
 
 static GLOBAL_RUNTIME_ID: AtomicU32 = AtomicU32::new(0);
 pub const THREAD_NAME_LABEL: &str = "thread_name";
@@ -39,6 +41,52 @@
     .unwrap()
 });
 
+/// Workload classes the broker partitions its Tokio runtimes into, so that heavy storage
+/// work or subscriber fan-out cannot starve packet processing.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum RuntimePool {
+    /// Network accept and packet handling (the broker runtime).
+    Network,
+    /// Storage engine I/O, segment writes and compaction.
+    Storage,
+    /// Subscription parsing and message push loops.
+    Push,
+    /// Periodic maintenance: GC, metrics, system topics, delay tasks, connectors.
+    Background,
+}
+
+impl fmt::Display for RuntimePool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimePool::Network => write!(f, "network"),
+            RuntimePool::Storage => write!(f, "storage"),
+            RuntimePool::Push => write!(f, "push"),
+            RuntimePool::Background => write!(f, "background"),
+        }
+    }
+}
+
+static RUNTIME_POOL_HANDLES: LazyLock<DashMap<RuntimePool, Handle>> =
+    LazyLock::new(|| DashMap::with_capacity(4));
+
+/// Register the runtime that serves `pool`. Later registrations replace earlier ones.
+pub fn register_runtime_pool(pool: RuntimePool, handle: Handle) {
+    RUNTIME_POOL_HANDLES.insert(pool, handle);
+}
+
+/// Handle of the runtime registered for `pool`, or of the current runtime when the pool
+/// has not been partitioned (tests, embedded use).
+pub fn runtime_pool_handle(pool: RuntimePool) -> Handle {
+    RUNTIME_POOL_HANDLES
+        .get(&pool)
+        .map(|h| h.clone())
+        .unwrap_or_else(Handle::current)
+}
+
+pub fn is_runtime_pool_registered(pool: RuntimePool) -> bool {
+    RUNTIME_POOL_HANDLES.contains_key(&pool)
+}
+
 struct RuntimeBuilder {
     runtime_name: String,
     thread_name: String,
@@ -182,12 +230,45 @@ pub fn resolve_broker_worker_threads(explicit: usize) -> usize {
     }
 }
 
+/// Resolve storage-runtime thread count.
+/// explicit=0 means auto: num_cpus.
+pub fn resolve_storage_worker_threads(explicit: usize) -> usize {
+    if explicit > 0 {
+        explicit
+    } else {
+        num_cpus()
+    }
+}
+
+/// Resolve push-runtime thread count.
+/// explicit=0 means auto: num_cpus (fan-out is CPU bound on encode and write).
+pub fn resolve_push_worker_threads(explicit: usize) -> usize {
+    if explicit > 0 {
+        explicit
+    } else {
+        num_cpus()
+    }
+}
+
+/// Resolve background-runtime thread count.
+/// explicit=0 means auto: max(2, num_cpus / 4), maintenance work is latency tolerant.
+pub fn resolve_background_worker_threads(explicit: usize) -> usize {
+    if explicit > 0 {
+        explicit
+    } else {
+        (num_cpus() / 4).max(2)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::thread::{self, sleep};
     use std::time::Duration;
 
-    use super::create_runtime;
+    use super::{
+        create_runtime, is_runtime_pool_registered, register_runtime_pool,
+        resolve_background_worker_threads, runtime_pool_handle, RuntimePool,
+    };
 
     #[test]
     fn test_metric() {
@@ -225,6 +306,26 @@ fn test_runtime_in_runtime() {
         sleep(Duration::from_secs(5));
     }
 
+    #[test]
+    fn test_runtime_pool_registry() {
+        let rt = create_runtime("test-push", 2);
+        assert!(!is_runtime_pool_registered(RuntimePool::Push));
+        register_runtime_pool(RuntimePool::Push, rt.handle().clone());
+        assert!(is_runtime_pool_registered(RuntimePool::Push));
+
+        let thread_name = rt.block_on(async {
+            runtime_pool_handle(RuntimePool::Push)
+                .spawn(async { thread::current().name().map(|n| n.to_string()) })
+                .await
+                .unwrap()
+        });
+        assert_eq!(thread_name.as_deref(), Some("test-push"));
+
+        assert_eq!(RuntimePool::Background.to_string(), "background");
+        assert!(resolve_background_worker_threads(0) >= 2);
+        assert_eq!(resolve_background_worker_threads(3), 3);
+    }
+
     #[test]
     fn test_get_cpu_num() {
         let num_threads = thread::available_parallelism().unwrap().get();
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::runtime::{runtime_pool_handle, RuntimePool};
 use dashmap::DashMap;
 use tokio::task::JoinHandle;
 use tracing::{debug, error};
@@ -20,35 +21,51 @@
 pub enum TaskKind {
     BrokerNodeCall,
     DelayTaskPop,
+    DelayTaskDispatch,
+    DelayTaskIndexCompact,
     NetworkConnectionGC,
     OffsetAsyncCommit,
     SystemInfoCollection,
     TokioRuntimeInfoCollection,
+    RocksDBColumnFamilyMetrics,
+    RocksDBBackup,
+    MetricsPush,
     ConnectorManager,
     ConnectorHeartbeat,
     BrokerNodeHeartbeat,
+    BrokerCacheConsistencyCheck,
     MetaRaftMachineMonitor,
+    MetaRaftLogCompaction,
     MetaMonitorRaftLeaderChange,
     MetaBrokerHeartbeatCheck,
     DelayMessagePop,
     MQTTSessionBatchSend,
     MQTTEventReport,
+    MQTTWebhook,
     MQTTClientKeepAlive,
     MQTTSecurityUserSync,
     MQTTSecurityAclSync,
     MQTTSecurityBlacklistSync,
+    MQTTSecurityPasswordRehash,
     MQTTCleanFlappingDetect,
     MQTTCleanPkidData,
     MQTTReportSystemTopicData,
+    MQTTMetricsHistory,
     MQTTTopicRewriteConvert,
     MQTTMetricsBasic,
     MQTTMetricsTopic,
     MQTTMetricsSession,
     MQTTMetricsSubscribe,
     MQTTMetricsConnector,
+    MQTTMetricsConsumerLag,
     MQTTSystemAlarm,
+    MQTTOverloadProtection,
+    MQTTLocalLogExpire,
     MQTTSubscribePush,
     MQTTSubscribeParse,
+    MQTTSubscribeRouteSync,
+    MQTTTopicStatsReport,
+    MQTTRetainIndexBackfill,
     StorageMessageMemoryExpire,
     StorageEngineSegmentExpire,
     StorageEngineOrphanClean,
@@ -70,35 +87,51 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             TaskKind::BrokerNodeCall => write!(f, "BrokerNodeCall"),
             TaskKind::DelayTaskPop => write!(f, "DelayTaskPop"),
+            TaskKind::DelayTaskDispatch => write!(f, "DelayTaskDispatch"),
+            TaskKind::DelayTaskIndexCompact => write!(f, "DelayTaskIndexCompact"),
             TaskKind::NetworkConnectionGC => write!(f, "NetworkConnectionGC"),
             TaskKind::OffsetAsyncCommit => write!(f, "OffsetAsyncCommit"),
             TaskKind::SystemInfoCollection => write!(f, "SystemInfoCollection"),
             TaskKind::TokioRuntimeInfoCollection => write!(f, "TokioRuntimeInfoCollection"),
+            TaskKind::RocksDBColumnFamilyMetrics => write!(f, "RocksDBColumnFamilyMetrics"),
+            TaskKind::RocksDBBackup => write!(f, "RocksDBBackup"),
+            TaskKind::MetricsPush => write!(f, "MetricsPush"),
             TaskKind::ConnectorManager => write!(f, "ConnectorManager"),
             TaskKind::ConnectorHeartbeat => write!(f, "ConnectorHeartbeat"),
             TaskKind::BrokerNodeHeartbeat => write!(f, "BrokerNodeHeartbeat"),
+            TaskKind::BrokerCacheConsistencyCheck => write!(f, "BrokerCacheConsistencyCheck"),
             TaskKind::MetaRaftMachineMonitor => write!(f, "MetaRaftMachineMonitor"),
+            TaskKind::MetaRaftLogCompaction => write!(f, "MetaRaftLogCompaction"),
             TaskKind::MetaMonitorRaftLeaderChange => write!(f, "MetaMonitorRaftLeaderChange"),
             TaskKind::MetaBrokerHeartbeatCheck => write!(f, "MetaBrokerHeartbeatCheck"),
             TaskKind::DelayMessagePop => write!(f, "DelayMessagePop"),
             TaskKind::MQTTSessionBatchSend => write!(f, "MQTTSessionBatchSend"),
             TaskKind::MQTTEventReport => write!(f, "MQTTEventReport"),
+            TaskKind::MQTTWebhook => write!(f, "MQTTWebhook"),
             TaskKind::MQTTClientKeepAlive => write!(f, "MQTTClientKeepAlive"),
             TaskKind::MQTTSecurityUserSync => write!(f, "MQTTSecurityUserSync"),
             TaskKind::MQTTSecurityAclSync => write!(f, "MQTTSecurityAclSync"),
             TaskKind::MQTTSecurityBlacklistSync => write!(f, "MQTTSecurityBlacklistSync"),
+            TaskKind::MQTTSecurityPasswordRehash => write!(f, "MQTTSecurityPasswordRehash"),
             TaskKind::MQTTCleanFlappingDetect => write!(f, "MQTTCleanFlappingDetect"),
             TaskKind::MQTTCleanPkidData => write!(f, "MQTTCleanPkidData"),
             TaskKind::MQTTReportSystemTopicData => write!(f, "MQTTReportSystemTopicData"),
+            TaskKind::MQTTMetricsHistory => write!(f, "MQTTMetricsHistory"),
             TaskKind::MQTTTopicRewriteConvert => write!(f, "MQTTTopicRewriteConvert"),
             TaskKind::MQTTMetricsBasic => write!(f, "MQTTMetricsBasic"),
             TaskKind::MQTTMetricsTopic => write!(f, "MQTTMetricsTopic"),
             TaskKind::MQTTMetricsSession => write!(f, "MQTTMetricsSession"),
             TaskKind::MQTTMetricsSubscribe => write!(f, "MQTTMetricsSubscribe"),
             TaskKind::MQTTMetricsConnector => write!(f, "MQTTMetricsConnector"),
+            TaskKind::MQTTMetricsConsumerLag => write!(f, "MQTTMetricsConsumerLag"),
             TaskKind::MQTTSystemAlarm => write!(f, "MQTTSystemAlarm"),
+            TaskKind::MQTTOverloadProtection => write!(f, "MQTTOverloadProtection"),
+            TaskKind::MQTTLocalLogExpire => write!(f, "MQTTLocalLogExpire"),
             TaskKind::MQTTSubscribePush => write!(f, "MQTTSubscribePush"),
             TaskKind::MQTTSubscribeParse => write!(f, "MQTTSubscribeParse"),
+            TaskKind::MQTTSubscribeRouteSync => write!(f, "MQTTSubscribeRouteSync"),
+            TaskKind::MQTTTopicStatsReport => write!(f, "MQTTTopicStatsReport"),
+            TaskKind::MQTTRetainIndexBackfill => write!(f, "MQTTRetainIndexBackfill"),
             TaskKind::StorageMessageMemoryExpire => write!(f, "StorageMessageMemoryExpire"),
             TaskKind::StorageEngineSegmentExpire => write!(f, "StorageEngineSegmentExpire"),
             TaskKind::StorageEngineOrphanClean => write!(f, "StorageEngineOrphanClean"),
@@ -139,32 +172,59 @@ pub fn new() -> Self {
     }
 
     pub fn spawn<F>(&self, kind: String, fut: F) -> JoinHandle<()>
+    where
+        F: std::future::Future<Output = ()> + Send + 'static,
+    {
+        self.spawn_supervised(kind, fut, None)
+    }
+
+    /// Like [`TaskSupervisor::spawn`], but runs the task on the runtime registered for `pool`.
+    pub fn spawn_on<F>(&self, kind: String, pool: RuntimePool, fut: F) -> JoinHandle<()>
+    where
+        F: std::future::Future<Output = ()> + Send + 'static,
+    {
+        self.spawn_supervised(kind, fut, Some(runtime_pool_handle(pool)))
+    }
+
+    fn spawn_supervised<F>(
+        &self,
+        kind: String,
+        fut: F,
+        handle: Option<tokio::runtime::Handle>,
+    ) -> JoinHandle<()>
     where
         F: std::future::Future<Output = ()> + Send + 'static,
     {
         let sup = self.clone();
         let task_name = kind.to_string();
+        let handle = handle.unwrap_or_else(tokio::runtime::Handle::current);
         tokio::task::Builder::new()
             .name(&task_name)
-            .spawn(async move {
-                sup.set_state(kind.clone(), TaskState::Running).await;
-                debug!("Task {} started", kind);
-                let inner = tokio::task::Builder::new()
-                    .name(&format!("{kind}/inner"))
-                    .spawn(fut)
-                    .expect("failed to spawn inner task");
-                match inner.await {
-                    Ok(()) => {
-                        debug!("Task {} stopped normally", kind);
-                        sup.set_state(kind.clone(), TaskState::Stopped).await;
-                    }
-                    Err(e) => {
-                        error!("Task {} failed: join error: {}", kind, e);
-                        sup.set_state(kind.clone(), TaskState::Failed(format!("join error: {e}")))
+            .spawn_on(
+                async move {
+                    sup.set_state(kind.clone(), TaskState::Running).await;
+                    debug!("Task {} started", kind);
+                    let inner = tokio::task::Builder::new()
+                        .name(&format!("{kind}/inner"))
+                        .spawn(fut)
+                        .expect("failed to spawn inner task");
+                    match inner.await {
+                        Ok(()) => {
+                            debug!("Task {} stopped normally", kind);
+                            sup.set_state(kind.clone(), TaskState::Stopped).await;
+                        }
+                        Err(e) => {
+                            error!("Task {} failed: join error: {}", kind, e);
+                            sup.set_state(
+                                kind.clone(),
+                                TaskState::Failed(format!("join error: {e}")),
+                            )
                             .await;
+                        }
                     }
-                }
-            })
+                },
+                &handle,
+            )
             .expect("failed to spawn task")
     }
 
@@ -12,10 +12,41 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::error::common::CommonError;
 use crc32fast::Hasher;
+use serde::Serialize;
 
 pub fn calc_crc32(data: &[u8]) -> u32 {
     let mut hasher = Hasher::new();
     hasher.update(data);
     hasher.finalize()
 }
+
+/// CRC32 over a canonical JSON form of `value`, with map keys sorted, so that
+/// two equal records digest the same even when they hold a `HashMap`.
+pub fn calc_digest<T: Serialize>(value: &T) -> Result<u32, CommonError> {
+    let canonical = serde_json::to_value(value)?;
+    Ok(calc_crc32(&serde_json::to_vec(&canonical)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_calc_digest_ignores_map_order() {
+        let mut a = HashMap::new();
+        let mut b = HashMap::new();
+        for i in 0..32u32 {
+            a.insert(i, i.to_string());
+        }
+        for i in (0..32u32).rev() {
+            b.insert(i, i.to_string());
+        }
+        assert_eq!(calc_digest(&a).unwrap(), calc_digest(&b).unwrap());
+
+        b.insert(32, "32".to_string());
+        assert_ne!(calc_digest(&a).unwrap(), calc_digest(&b).unwrap());
+    }
+}
@@ -49,6 +49,22 @@ pub fn deserialize<T>(bytes: &[u8]) -> Result<T, CommonError>
         .map_err(|e| CommonError::CommonError(format!("Failed to deserialize: {}", e)))
 }
 
+/// Deserialize bytes to data that borrows from them, using bincode
+///
+/// # Arguments
+/// * `bytes` - The bytes to deserialize, borrowed `&[u8]`/`&str` fields point into them
+///
+/// # Returns
+/// * `Ok(T)` - Deserialized data
+/// * `Err(CommonError)` - Deserialization error
+pub fn deserialize_borrowed<'a, T>(bytes: &'a [u8]) -> Result<T, CommonError>
+where
+    T: Deserialize<'a>,
+{
+    bincode::deserialize(bytes)
+        .map_err(|e| CommonError::CommonError(format!("Failed to deserialize: {}", e)))
+}
+
 /// Helper function to encode a protobuf message to Bytes
 ///
 /// This is a common pattern used across all service handlers to convert
@@ -83,6 +99,15 @@ fn test_serialize_deserialize() {
         assert_eq!(data, deserialized);
     }
 
+    #[test]
+    fn test_deserialize_borrowed() {
+        let serialized = serialize(&(7u64, Bytes::from_static(b"payload"))).unwrap();
+        let (id, payload): (u64, &[u8]) = deserialize_borrowed(&serialized).unwrap();
+        assert_eq!(id, 7);
+        assert_eq!(payload, b"payload");
+        assert!(serialized.as_ptr_range().contains(&payload.as_ptr()));
+    }
+
     #[test]
     fn test_deserialize_invalid_data() {
         let invalid_bytes = vec![0xFF, 0xFF, 0xFF];
@@ -43,6 +43,43 @@ pub fn base_topic_name_regex_match(topic_name: &str, regex_topic_name: &str) ->
     false
 }
 
+/// Whether `topic_name` matches the MQTT subscription filter `filter`, level
+/// by level. Topics starting with `$` are not matched by a leading wildcard.
+pub fn topic_filter_match(filter: &str, topic_name: &str) -> bool {
+    if topic_name.starts_with('$') && (filter.starts_with('+') || filter.starts_with('#')) {
+        return false;
+    }
+
+    let mut topic_levels = topic_name.split('/');
+    for filter_level in filter.split('/') {
+        match (filter_level, topic_levels.next()) {
+            ("#", _) => return true,
+            (_, None) => return false,
+            ("+", Some(_)) => {}
+            (filter_level, Some(level)) if filter_level != level => return false,
+            _ => {}
+        }
+    }
+    topic_levels.next().is_none()
+}
+
+/// Literal part of `filter` before its first wildcard level, without the
+/// trailing separator. Every topic matched by the filter starts with it, so it
+/// can narrow a prefix scan.
+pub fn topic_filter_literal_prefix(filter: &str) -> &str {
+    let end = filter
+        .split('/')
+        .scan(0, |offset, level| {
+            let start = *offset;
+            *offset += level.len() + 1;
+            Some((start, level))
+        })
+        .find(|(_, level)| *level == "+" || *level == "#")
+        .map(|(start, _)| start)
+        .unwrap_or(filter.len());
+    filter[..end].trim_end_matches('/')
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -74,4 +111,27 @@ fn test_base_topic_name_regex_match() {
         ));
         assert!(!base_topic_name_regex_match("topic/sub/topic", "another/#"));
     }
+
+    #[test]
+    fn test_topic_filter_match() {
+        assert!(topic_filter_match("a/b", "a/b"));
+        assert!(topic_filter_match("a/+/c", "a/b/c"));
+        assert!(!topic_filter_match("a/+/c", "a/b/d"));
+        assert!(!topic_filter_match("a/+", "a/b/c"));
+        assert!(topic_filter_match("a/#", "a"));
+        assert!(topic_filter_match("a/#", "a/b/c"));
+        assert!(!topic_filter_match("a/#", "ab/c"));
+        assert!(topic_filter_match("#", "a/b"));
+        assert!(!topic_filter_match("#", "$SYS/brokers"));
+        assert!(topic_filter_match("$SYS/#", "$SYS/brokers"));
+    }
+
+    #[test]
+    fn test_topic_filter_literal_prefix() {
+        assert_eq!(topic_filter_literal_prefix("a/b"), "a/b");
+        assert_eq!(topic_filter_literal_prefix("a/b/+/c"), "a/b");
+        assert_eq!(topic_filter_literal_prefix("a/#"), "a");
+        assert_eq!(topic_filter_literal_prefix("+/a"), "");
+        assert_eq!(topic_filter_literal_prefix("#"), "");
+    }
 }
@@ -30,6 +30,10 @@ pub struct Log {
     pub log_config: String,
     #[serde(default = "default_log_path")]
     pub log_path: String,
+    /// Runtime level override for all appenders, empty keeps the levels from
+    /// `log_config`. Only this field is updated by the `Log` dynamic config.
+    #[serde(default)]
+    pub level: String,
 }
 
 impl Default for Log {
@@ -64,6 +68,7 @@ pub fn default_log() -> Log {
     Log {
         log_path: default_log_path(),
         log_config: default_log_config(),
+        level: String::new(),
     }
 }
 
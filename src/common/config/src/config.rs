@@ -14,44 +14,69 @@
 
 use super::default::{
     default_accept_thread_num, default_broker_id, default_broker_ip, default_channels_per_address,
-    default_cluster_name, default_data_path, default_delay_task,
-    default_delay_task_handler_concurrency, default_delay_task_queue_num, default_engine_runtime,
-    default_flapping_ban_time, default_flapping_max_connections, default_flapping_window_time,
-    default_grpc_port, default_handler_thread_num, default_heartbeat_check_time_ms,
-    default_heartbeat_timeout_ms, default_http_port, default_keep_alive_default_time,
-    default_keep_alive_default_timeout, default_keep_alive_enable, default_keep_alive_max_time,
+    default_client_attribute_max_attributes, default_client_attribute_max_value_len,
+    default_client_attribute_user_property_prefix, default_cluster_name, default_data_path,
+    default_delay_task, default_delay_task_handler_concurrency,
+    default_delay_task_index_compact_interval_sec,
+    default_delay_task_index_compact_max_stale_age_sec,
+    default_delay_task_index_compact_min_stale_records, default_delay_task_queue_num,
+    default_delay_task_starvation_timeout_ms, default_engine_runtime,
+    default_flapping_ban_log_retention_sec, default_flapping_ban_time,
+    default_flapping_max_connections, default_flapping_window_time, default_grpc_port,
+    default_handler_thread_num, default_heartbeat_check_time_ms, default_heartbeat_timeout_ms,
+    default_http_port, default_inflight_expire_sec, default_keep_alive_default_time,
+    default_keep_alive_enable, default_keep_alive_max_time, default_keep_alive_timeout_multiplier,
     default_limit_max_connection_rate, default_limit_max_connections_per_node,
-    default_limit_max_publish_rate, default_limit_max_sessions, default_limit_max_topics,
-    default_max_admin_http_uri_rate, default_max_connection_per_ip,
-    default_max_message_expiry_interval, default_max_network_connection,
-    default_max_network_connection_rate, default_max_packet_size,
-    default_max_session_expiry_interval, default_meta_addrs, default_meta_runtime,
+    default_limit_max_publish_rate, default_limit_max_sessions, default_limit_max_subscriptions,
+    default_limit_max_topics, default_max_admin_http_uri_rate, default_max_connection_per_ip,
+    default_max_correlation_data_size, default_max_message_expiry_interval,
+    default_max_network_connection, default_max_network_connection_rate, default_max_packet_size,
+    default_max_session_expiry_interval, default_max_topic_length, default_max_topic_levels,
+    default_meta_addrs, default_meta_runtime, default_metrics_history_interval_ms,
+    default_metrics_history_retention_sec, default_mqtt_client_attribute,
     default_mqtt_flapping_detect, default_mqtt_keep_alive, default_mqtt_limit_cluster,
     default_mqtt_limit_tenant, default_mqtt_offline_message, default_mqtt_protocol,
     default_mqtt_quic_port, default_mqtt_runtime, default_mqtt_runtime_password,
-    default_mqtt_runtime_user, default_mqtt_schema, default_mqtt_server,
-    default_mqtt_slow_subscribe, default_mqtt_system_monitor, default_mqtt_tcp_port,
-    default_mqtt_tls_port, default_mqtt_websocket_port, default_mqtt_websockets_port,
-    default_network, default_offline_message_enable, default_offline_message_expire_ms,
-    default_offline_message_max_num, default_queue_size, default_raft_write_timeout_sec,
-    default_receive_max, default_roles, default_runtime, default_runtime_worker_threads,
-    default_schema_echo_log, default_schema_enable, default_schema_failed_operation,
-    default_schema_log_level, default_schema_strategy, default_session_expiry_interval,
-    default_slow_subscribe_delay_type, default_slow_subscribe_record_time,
+    default_mqtt_runtime_user, default_mqtt_schema, default_mqtt_server, default_mqtt_slow_request,
+    default_mqtt_slow_subscribe, default_mqtt_subscription_metrics, default_mqtt_system_monitor,
+    default_mqtt_tcp_port, default_mqtt_tls_port, default_mqtt_topic_metrics, default_mqtt_trace,
+    default_mqtt_websocket_port, default_mqtt_websockets_port, default_network,
+    default_offline_message_enable, default_offline_message_expire_ms,
+    default_offline_message_max_num, default_offline_message_session_queue_max_bytes,
+    default_offline_message_session_queue_max_messages, default_overload_ack_delay_ms,
+    default_overload_check_interval_ms, default_overload_connect_delay_ms, default_queue_size,
+    default_raft_write_timeout_sec, default_receive_max, default_response_topic_prefix,
+    default_rocksdb_backup, default_rocksdb_backup_interval_sec,
+    default_rocksdb_backup_max_backups, default_roles, default_runtime,
+    default_runtime_worker_threads, default_schema_echo_log, default_schema_enable,
+    default_schema_failed_operation, default_schema_log_level, default_schema_strategy,
+    default_session_expiry_interval, default_slow_request_max_records,
+    default_slow_request_threshold_ms, default_slow_subscribe_delay_type,
+    default_slow_subscribe_log_retention_sec, default_slow_subscribe_record_time,
     default_storage_expire_scan_task_num, default_storage_io_thread_num,
     default_storage_isr_maintain_interval_ms, default_storage_max_segment_size,
     default_storage_metadata_reconcile_interval_ms, default_storage_num_replica_fetchers,
     default_storage_offset_enable_cache, default_storage_replica_fetch_backoff_ms,
     default_storage_replica_fetch_max_wait_ms, default_storage_replica_fetch_min_bytes,
     default_storage_replica_lag_time_max_ms, default_storage_tcp_port,
-    default_system_monitor_cpu_watermark, default_system_monitor_memory_watermark,
-    default_system_monitor_topic_interval_ms, default_tls_cert, default_tls_key,
-    default_topic_alias_max, default_topic_partition_num, default_topic_replica_num,
+    default_subscription_metrics_max_series, default_system_event_retention_sec,
+    default_system_monitor_cpu_low_watermark, default_system_monitor_cpu_watermark,
+    default_system_monitor_memory_low_watermark, default_system_monitor_memory_watermark,
+    default_system_monitor_topic_interval_ms, default_tcp_nodelay, default_tls_cert,
+    default_tls_handshake_queue_size, default_tls_handshake_thread_num,
+    default_tls_handshake_timeout_ms, default_tls_key, default_tls_reload_interval_sec,
+    default_tls_session_cache_size, default_tls_session_ticket_enable, default_topic_alias_max,
+    default_topic_metrics_enable, default_topic_metrics_max_series,
+    default_topic_metrics_prefix_levels, default_topic_partition_num, default_topic_replica_num,
+    default_trace_max_events, default_write_buffer_max_bytes,
 };
 use crate::common::default_log;
 use crate::common::Log;
+use crate::storage::memory::MemoryAdapterConfig;
+use crate::storage::StorageType;
 use common_base::enum_type::delay_type::DelayType;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use toml::Table;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -156,6 +181,18 @@ pub struct BrokerConfig {
     #[serde(default = "default_delay_task")]
     pub delay_task: DelayTask,
 
+    #[serde(default = "default_rocksdb_backup")]
+    pub rocksdb_backup: RocksDBBackup,
+
+    #[serde(default)]
+    pub fault_injection: FaultInjection,
+
+    #[serde(default)]
+    pub storage_routing: StorageRouting,
+
+    #[serde(default)]
+    pub placement: Placement,
+
     // meta
     #[serde(default = "default_meta_runtime")]
     pub meta_runtime: MetaRuntime,
@@ -180,12 +217,21 @@ pub struct BrokerConfig {
     #[serde(default = "default_mqtt_slow_subscribe")]
     pub mqtt_slow_subscribe: MqttSlowSubscribeConfig,
 
+    #[serde(default = "default_mqtt_slow_request")]
+    pub mqtt_slow_request: MqttSlowRequestConfig,
+
+    #[serde(default = "default_mqtt_client_attribute")]
+    pub mqtt_client_attribute: MqttClientAttributeConfig,
+
     #[serde(default = "default_mqtt_flapping_detect")]
     pub mqtt_flapping_detect: MqttFlappingDetect,
 
     #[serde(default = "default_mqtt_protocol")]
     pub mqtt_protocol: MqttProtocolConfig,
 
+    #[serde(default)]
+    pub mqtt_client_id: MqttClientIdConfig,
+
     #[serde(default = "default_mqtt_schema")]
     pub mqtt_schema: MqttSchema,
 
@@ -195,6 +241,36 @@ pub struct BrokerConfig {
     #[serde(default)]
     pub mqtt_limit: MQTTLimit,
 
+    #[serde(default)]
+    pub mqtt_topic_metrics: MqttTopicMetrics,
+
+    #[serde(default)]
+    pub mqtt_subscription_metrics: MqttSubscriptionMetrics,
+
+    #[serde(default)]
+    pub mqtt_trace: MqttTrace,
+
+    #[serde(default)]
+    pub mqtt_webhook: MqttWebhook,
+
+    #[serde(default)]
+    pub mqtt_payload_transform: MqttPayloadTransform,
+
+    #[serde(default)]
+    pub mqtt_tls: MqttTls,
+
+    #[serde(default)]
+    pub mqtt_sn: MqttSnConfig,
+
+    #[serde(default)]
+    pub coap: CoapConfig,
+
+    #[serde(default)]
+    pub mqtt_web_subscribe: MqttWebSubscribeConfig,
+
+    #[serde(default)]
+    pub mqtt_retain: MqttRetainConfig,
+
     // Kafka
     #[serde(default)]
     pub kafka_runtime: KafkaRuntime,
@@ -214,6 +290,22 @@ pub struct BrokerConfig {
     // Admin HTTP API authentication
     #[serde(default)]
     pub admin: AdminConfig,
+
+    // TLS for inter-node gRPC (broker, meta service, storage engine)
+    #[serde(default)]
+    pub grpc_tls: GrpcTls,
+
+    // Authentication and authorization of the meta service gRPC services
+    #[serde(default)]
+    pub meta_service_auth: MetaServiceAuth,
+
+    // Password hashing and complexity rules for built-in users
+    #[serde(default)]
+    pub password_policy: PasswordPolicy,
+
+    // Prometheus scrape listener and push mode
+    #[serde(default)]
+    pub prometheus: Prometheus,
 }
 
 impl Default for BrokerConfig {
@@ -233,6 +325,10 @@ fn default() -> Self {
             llm_client: LLMConfig::default(),
             cluster_limit: ClusterLimit::default(),
             delay_task: default_delay_task(),
+            rocksdb_backup: default_rocksdb_backup(),
+            fault_injection: FaultInjection::default(),
+            storage_routing: StorageRouting::default(),
+            placement: Placement::default(),
 
             // Meta Service
             meta_runtime: default_meta_runtime(),
@@ -246,11 +342,24 @@ fn default() -> Self {
             mqtt_keep_alive: default_mqtt_keep_alive(),
             mqtt_offline_message: default_mqtt_offline_message(),
             mqtt_slow_subscribe: default_mqtt_slow_subscribe(),
+            mqtt_slow_request: default_mqtt_slow_request(),
+            mqtt_client_attribute: default_mqtt_client_attribute(),
             mqtt_flapping_detect: default_mqtt_flapping_detect(),
             mqtt_protocol: default_mqtt_protocol(),
+            mqtt_client_id: MqttClientIdConfig::default(),
             mqtt_schema: default_mqtt_schema(),
             mqtt_system_monitor: default_mqtt_system_monitor(),
             mqtt_limit: MQTTLimit::default(),
+            mqtt_topic_metrics: MqttTopicMetrics::default(),
+            mqtt_subscription_metrics: MqttSubscriptionMetrics::default(),
+            mqtt_trace: MqttTrace::default(),
+            mqtt_webhook: MqttWebhook::default(),
+            mqtt_payload_transform: MqttPayloadTransform::default(),
+            mqtt_tls: MqttTls::default(),
+            mqtt_sn: MqttSnConfig::default(),
+            coap: CoapConfig::default(),
+            mqtt_web_subscribe: MqttWebSubscribeConfig::default(),
+            mqtt_retain: MqttRetainConfig::default(),
 
             // Kafka
             kafka_runtime: KafkaRuntime::default(),
@@ -264,6 +373,10 @@ fn default() -> Self {
             // Shared broker network config
             broker_network: default_network(),
             admin: AdminConfig::default(),
+            grpc_tls: GrpcTls::default(),
+            meta_service_auth: MetaServiceAuth::default(),
+            password_policy: PasswordPolicy::default(),
+            prometheus: Prometheus::default(),
         }
     }
 }
@@ -303,11 +416,26 @@ pub struct Runtime {
     #[serde(default)]
     pub meta_worker_threads: usize,
 
-    /// Worker threads for the broker runtime (MQTT handler pool, push manager).
+    /// Worker threads for the broker runtime (network accept, MQTT handler pool).
     /// 0 = auto: num_cpus.  This is the hot-path runtime.
     #[serde(default)]
     pub broker_worker_threads: usize,
 
+    /// Worker threads for the storage runtime (storage engine I/O and compaction).
+    /// 0 = auto: num_cpus.
+    #[serde(default)]
+    pub storage_worker_threads: usize,
+
+    /// Worker threads for the push runtime (subscription parsing and message fan-out).
+    /// 0 = auto: num_cpus.
+    #[serde(default)]
+    pub push_worker_threads: usize,
+
+    /// Worker threads for the background runtime (GC, metrics, delay tasks, connectors).
+    /// 0 = auto: max(2, num_cpus / 4).
+    #[serde(default)]
+    pub background_worker_threads: usize,
+
     #[serde(default = "default_channels_per_address")]
     pub channels_per_address: usize,
 
@@ -317,6 +445,49 @@ pub struct Runtime {
     #[serde(default = "default_tls_key")]
     pub tls_key: String,
 
+    /// Directory kept up to date by an external ACME client, e.g. certbot's
+    /// `live/<domain>`. When set, its `fullchain.pem` and `privkey.pem` are
+    /// used instead of `tls_cert` and `tls_key`.
+    #[serde(default)]
+    pub tls_acme_dir: String,
+
+    /// How often the certificate files are checked for changes, 0 disables hot reload.
+    #[serde(default = "default_tls_reload_interval_sec")]
+    pub tls_reload_interval_sec: u64,
+
+    /// Tasks running TLS handshakes off the accept path.
+    #[serde(default = "default_tls_handshake_thread_num")]
+    pub tls_handshake_thread_num: usize,
+
+    /// Accepted connections waiting for a handshake task; further
+    /// connections are closed until the queue drains.
+    #[serde(default = "default_tls_handshake_queue_size")]
+    pub tls_handshake_queue_size: usize,
+
+    #[serde(default = "default_tls_handshake_timeout_ms")]
+    pub tls_handshake_timeout_ms: u64,
+
+    /// Sessions kept for session-ID resumption, 0 disables it.
+    #[serde(default = "default_tls_session_cache_size")]
+    pub tls_session_cache_size: usize,
+
+    #[serde(default = "default_tls_session_ticket_enable")]
+    pub tls_session_ticket_enable: bool,
+
+    /// Disables Nagle's algorithm on accepted TCP and TLS connections.
+    #[serde(default = "default_tcp_nodelay")]
+    pub tcp_nodelay: bool,
+
+    /// Packets written to one TCP or TLS connection within this window are
+    /// sent in a single flush, 0 flushes every packet on its own.
+    #[serde(default)]
+    pub write_coalesce_window_us: u64,
+
+    /// Buffered outbound bytes per connection that force a flush before the
+    /// coalescing window ends.
+    #[serde(default = "default_write_buffer_max_bytes")]
+    pub write_buffer_max_bytes: usize,
+
     #[serde(default)]
     pub pprof_enable: bool,
 
@@ -386,6 +557,8 @@ pub struct LimitQuota {
     pub max_sessions: u64,
     #[serde(default = "default_limit_max_publish_rate")]
     pub max_publish_rate: u32,
+    #[serde(default = "default_limit_max_subscriptions")]
+    pub max_subscriptions: u64,
 }
 
 impl Default for LimitQuota {
@@ -396,6 +569,7 @@ fn default() -> Self {
             max_topics: 500000,
             max_sessions: 5000000,
             max_publish_rate: 10000,
+            max_subscriptions: 5000000,
         }
     }
 }
@@ -417,6 +591,7 @@ fn default() -> Self {
                 max_topics: 5000000,
                 max_sessions: 50000000,
                 max_publish_rate: 10000,
+                max_subscriptions: 50000000,
             },
             tenant: LimitQuota {
                 max_connections_per_node: 1000000,
@@ -424,6 +599,7 @@ fn default() -> Self {
                 max_topics: 500000,
                 max_sessions: 5000000,
                 max_publish_rate: 10000,
+                max_subscriptions: 5000000,
             },
         }
     }
@@ -447,6 +623,103 @@ pub struct MetaRuntime {
     pub segment_leader_rebalance_interval_ms: u64,
     #[serde(default = "default_segment_leader_rebalance_max_moves")]
     pub segment_leader_rebalance_max_moves: u32,
+    /// How often connectors are rebalanced across brokers by message rate, 0
+    /// disables rebalancing. Failover of a dead broker's connectors is not
+    /// affected.
+    #[serde(default = "default_connector_rebalance_interval_ms")]
+    pub connector_rebalance_interval_ms: u64,
+    #[serde(default = "default_connector_rebalance_max_moves")]
+    pub connector_rebalance_max_moves: u32,
+    /// Coalesce concurrent writes to a raft shard into one raft entry.
+    #[serde(default)]
+    pub raft_write_batch_enable: bool,
+    #[serde(default = "default_raft_write_batch_max_entries")]
+    pub raft_write_batch_max_entries: usize,
+    /// How long the batcher waits for more writes after the first one.
+    #[serde(default = "default_raft_write_batch_window_ms")]
+    pub raft_write_batch_window_ms: u64,
+    #[serde(default)]
+    pub metadata_raft_log: RaftLogRetention,
+    #[serde(default)]
+    pub offset_raft_log: RaftLogRetention,
+    #[serde(default)]
+    pub data_raft_log: RaftLogRetention,
+    /// How often every shard's log size is checked against its retention, 0
+    /// disables the check.
+    #[serde(default = "default_raft_log_check_interval_ms")]
+    pub raft_log_check_interval_ms: u64,
+    /// Size of one InstallSnapshot chunk sent to a follower.
+    #[serde(default = "default_raft_snapshot_chunk_size")]
+    pub raft_snapshot_chunk_size: u64,
+    /// Bandwidth shared by all snapshot transfers of this node, 0 disables the
+    /// limit.
+    #[serde(default)]
+    pub raft_snapshot_max_bytes_per_sec: u64,
+    /// Timeout of one snapshot chunk; a chunk that times out is resent from
+    /// the same offset.
+    #[serde(default = "default_raft_snapshot_chunk_timeout_ms")]
+    pub raft_snapshot_chunk_timeout_ms: u64,
+}
+
+impl MetaRuntime {
+    pub fn raft_log_retention(&self, group_name: &str) -> &RaftLogRetention {
+        match group_name {
+            "offset" => &self.offset_raft_log,
+            "data" => &self.data_raft_log,
+            _ => &self.metadata_raft_log,
+        }
+    }
+}
+
+/// Log retention of one raft group, applied to each of its shards.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct RaftLogRetention {
+    /// Build a snapshot once this many logs were applied since the last one.
+    #[serde(default = "default_raft_snapshot_interval_logs")]
+    pub snapshot_interval_logs: u64,
+    /// Logs kept behind a snapshot for lagging followers; older ones are purged.
+    #[serde(default = "default_raft_log_max_entries")]
+    pub max_entries: u64,
+    /// Snapshot and purge the whole log once it grows past this size, 0
+    /// disables the limit.
+    #[serde(default = "default_raft_log_max_bytes")]
+    pub max_bytes: u64,
+}
+
+impl Default for RaftLogRetention {
+    fn default() -> Self {
+        RaftLogRetention {
+            snapshot_interval_logs: default_raft_snapshot_interval_logs(),
+            max_entries: default_raft_log_max_entries(),
+            max_bytes: default_raft_log_max_bytes(),
+        }
+    }
+}
+
+fn default_raft_snapshot_interval_logs() -> u64 {
+    100
+}
+
+fn default_raft_log_max_entries() -> u64 {
+    1000
+}
+
+fn default_raft_log_max_bytes() -> u64 {
+    // 64 MiB
+    64 * 1024 * 1024
+}
+
+fn default_raft_log_check_interval_ms() -> u64 {
+    30_000
+}
+
+fn default_raft_snapshot_chunk_size() -> u64 {
+    // 1 MiB
+    1024 * 1024
+}
+
+fn default_raft_snapshot_chunk_timeout_ms() -> u64 {
+    60_000
 }
 
 fn default_raft_sharded_group_num() -> u32 {
@@ -466,6 +739,22 @@ fn default_segment_leader_rebalance_max_moves() -> u32 {
     50
 }
 
+fn default_connector_rebalance_interval_ms() -> u64 {
+    300_000
+}
+
+fn default_connector_rebalance_max_moves() -> u32 {
+    5
+}
+
+fn default_raft_write_batch_max_entries() -> usize {
+    64
+}
+
+fn default_raft_write_batch_window_ms() -> u64 {
+    2
+}
+
 impl Default for MetaRuntime {
     fn default() -> Self {
         default_meta_runtime()
@@ -500,8 +789,13 @@ pub struct MqttKeepAlive {
     pub default_time: u16,
     #[serde(default = "default_keep_alive_max_time")]
     pub max_time: u16,
-    #[serde(default = "default_keep_alive_default_timeout")]
-    pub default_timeout: u16,
+    /// A connection is closed once nothing was received for its negotiated
+    /// keep alive times this multiplier. MQTT recommends 1.5.
+    #[serde(
+        default = "default_keep_alive_timeout_multiplier",
+        alias = "default_timeout"
+    )]
+    pub timeout_multiplier: f32,
 }
 
 impl Default for MqttKeepAlive {
@@ -521,6 +815,11 @@ pub struct MqttRuntime {
     #[serde(default)]
     pub durable_sessions_enable: bool,
 
+    /// Forward published messages directly to the brokers hosting live
+    /// subscribers over gRPC, ahead of their storage polling.
+    #[serde(default)]
+    pub cluster_forward_enable: bool,
+
     #[serde(default)]
     pub secret_free_login: bool,
 
@@ -548,8 +847,53 @@ pub struct MqttSystemMonitor {
     #[serde(default = "default_system_monitor_memory_watermark")]
     pub os_memory_high_watermark: f32,
 
+    /// An active CPU alarm is only deactivated once usage drops below this,
+    /// so usage hovering around the high watermark doesn't flap the alarm.
+    #[serde(default = "default_system_monitor_cpu_low_watermark")]
+    pub os_cpu_low_watermark: f32,
+
+    #[serde(default = "default_system_monitor_memory_low_watermark")]
+    pub os_memory_low_watermark: f32,
+
     #[serde(default = "default_system_monitor_topic_interval_ms")]
     pub system_topic_interval_ms: u64,
+
+    /// Publish per-client lifecycle events to `$SYS/brokers/{node}/clients/{clientid}/...`.
+    #[serde(default)]
+    pub client_event_enable: bool,
+
+    /// Seconds system alarm events are kept in local storage. 0 keeps them forever.
+    #[serde(default = "default_system_event_retention_sec")]
+    pub system_event_retention_sec: u64,
+
+    /// Throttle inbound traffic while CPU or memory usage is above the high
+    /// watermarks, until it drops below the low watermarks again.
+    #[serde(default)]
+    pub overload_protection_enable: bool,
+
+    #[serde(default = "default_overload_check_interval_ms")]
+    pub overload_check_interval_ms: u64,
+
+    /// Delay before a CONNECT is processed at the highest overload level.
+    /// Lower levels use a proportional share of it.
+    #[serde(default = "default_overload_connect_delay_ms")]
+    pub overload_connect_delay_ms: u64,
+
+    /// Delay before PUBACK/PUBREC is sent while publish backpressure is on.
+    #[serde(default = "default_overload_ack_delay_ms")]
+    pub overload_ack_delay_ms: u64,
+
+    /// Persist periodic snapshots of the `$SYS` metrics into the
+    /// `$metrics-history` shard so they can be queried by time range.
+    #[serde(default)]
+    pub metrics_history_enable: bool,
+
+    #[serde(default = "default_metrics_history_interval_ms")]
+    pub metrics_history_interval_ms: u64,
+
+    /// Retention of the `$metrics-history` shard, applied when it is created.
+    #[serde(default = "default_metrics_history_retention_sec")]
+    pub metrics_history_retention_sec: u64,
 }
 
 impl Default for MqttSystemMonitor {
@@ -558,6 +902,118 @@ fn default() -> Self {
     }
 }
 
+/// Per-topic throughput metrics exported to Prometheus, with controls on the
+/// number of distinct `topic` label values.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MqttTopicMetrics {
+    #[serde(default = "default_topic_metrics_enable")]
+    pub enable: bool,
+
+    /// Topic names or filters (`+`/`#`) that always get their own series.
+    #[serde(default)]
+    pub allowlist: Vec<String>,
+
+    /// Other topics are aggregated to their first N levels (`a/b/#`). 0 keeps full names.
+    #[serde(default = "default_topic_metrics_prefix_levels")]
+    pub prefix_levels: usize,
+
+    /// Maximum number of topic series; overflow is reported as `_other`. 0 = unlimited.
+    #[serde(default = "default_topic_metrics_max_series")]
+    pub max_series: usize,
+}
+
+impl Default for MqttTopicMetrics {
+    fn default() -> Self {
+        default_mqtt_topic_metrics()
+    }
+}
+
+/// Per-subscription push metrics exported to Prometheus, with a cap on the
+/// number of subscriptions that get their own series.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MqttSubscriptionMetrics {
+    #[serde(default)]
+    pub enable: bool,
+
+    /// Maximum number of subscriptions with their own series; counters of the
+    /// rest are reported as `_other`. 0 = unlimited.
+    #[serde(default = "default_subscription_metrics_max_series")]
+    pub max_series: usize,
+}
+
+impl Default for MqttSubscriptionMetrics {
+    fn default() -> Self {
+        default_mqtt_subscription_metrics()
+    }
+}
+
+/// Client identifiers generated for clients that connect with an empty client
+/// id. The most specific prefix wins: tenant, then listener, then `prefix`.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+pub struct MqttClientIdConfig {
+    #[serde(default)]
+    pub prefix: String,
+
+    /// Prefix by tenant name.
+    #[serde(default)]
+    pub tenant_prefix: HashMap<String, String>,
+
+    /// Prefix by listener: `tcp`, `tls`, `websocket`, `websockets` or `quic`.
+    #[serde(default)]
+    pub listener_prefix: HashMap<String, String>,
+}
+
+impl MqttClientIdConfig {
+    pub fn prefix_for(&self, tenant: &str, listener: &str) -> &str {
+        self.tenant_prefix
+            .get(tenant)
+            .or_else(|| self.listener_prefix.get(listener))
+            .unwrap_or(&self.prefix)
+    }
+}
+
+/// Targeted message tracing. Messages matched by a rule have each stage of
+/// their path through the broker recorded in a per-node, capped trace store.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct MqttTrace {
+    /// Maximum number of trace events kept on each broker; the oldest are
+    /// dropped first.
+    #[serde(default = "default_trace_max_events")]
+    pub max_events: usize,
+
+    #[serde(default)]
+    pub rules: Vec<MqttTraceRule>,
+}
+
+impl Default for MqttTrace {
+    fn default() -> Self {
+        default_mqtt_trace()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct MqttTraceRule {
+    pub name: String,
+    pub tenant: String,
+    pub trace_type: MqttTraceType,
+    /// A client id for `client_id` rules, a topic name or filter for `topic`
+    /// rules.
+    pub value: String,
+    /// Unix time in seconds the rule stops matching; 0 keeps it until it is
+    /// deleted.
+    #[serde(default)]
+    pub end_time: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MqttTraceType {
+    /// Messages published by or delivered to the client.
+    ClientId,
+    /// Messages published to a matching topic.
+    Topic,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct MqttOfflineMessage {
     #[serde(default = "default_offline_message_enable")]
@@ -568,6 +1024,29 @@ pub struct MqttOfflineMessage {
 
     #[serde(default = "default_offline_message_max_num")]
     pub max_messages_num: u32,
+
+    /// Max messages held in the queue of one offline persistent session.
+    /// 0 disables per-session queueing.
+    #[serde(default = "default_offline_message_session_queue_max_messages")]
+    pub session_queue_max_messages: u64,
+
+    /// Max payload bytes held in the queue of one offline session. 0 = unlimited.
+    #[serde(default = "default_offline_message_session_queue_max_bytes")]
+    pub session_queue_max_bytes: u64,
+
+    /// What to do with a new message when the session queue is full.
+    #[serde(default)]
+    pub session_queue_full_policy: OfflineQueueFullPolicy,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OfflineQueueFullPolicy {
+    /// Drop the oldest queued messages to make room.
+    #[default]
+    DropOldest,
+    /// Keep the queue as is and drop the new message.
+    Reject,
 }
 
 impl Default for MqttOfflineMessage {
@@ -591,6 +1070,30 @@ pub struct DelayTask {
     /// Max concurrent delay message handler tasks. 0 = auto: number of CPUs.
     #[serde(default = "default_delay_task_handler_concurrency")]
     pub delay_task_handler_concurrency: usize,
+
+    /// Max concurrently running tasks per task type, e.g.
+    /// `{ MQTTLastwillExpire = 16 }`. Types not listed share the global limit.
+    #[serde(default)]
+    pub delay_task_type_concurrency: HashMap<String, usize>,
+
+    /// An expired task waiting longer than this is dispatched ahead of
+    /// higher priority tasks.
+    #[serde(default = "default_delay_task_starvation_timeout_ms")]
+    pub delay_task_starvation_timeout_ms: u64,
+
+    /// How often the persistent task index is checked for compaction, also
+    /// used as the grace period before a finished task's record is removed.
+    /// 0 disables compaction.
+    #[serde(default = "default_delay_task_index_compact_interval_sec")]
+    pub delay_task_index_compact_interval_sec: u64,
+
+    /// Compact when at least this many index records are stale, or
+    #[serde(default = "default_delay_task_index_compact_min_stale_records")]
+    pub delay_task_index_compact_min_stale_records: u64,
+
+    /// when the oldest stale record is older than this many seconds.
+    #[serde(default = "default_delay_task_index_compact_max_stale_age_sec")]
+    pub delay_task_index_compact_max_stale_age_sec: u64,
 }
 
 impl Default for DelayTask {
@@ -599,6 +1102,156 @@ fn default() -> Self {
     }
 }
 
+/// Backups of the broker-local RocksDB (delay task index, inflight state,
+/// system events) taken with the RocksDB backup engine. A backup only copies
+/// the files not already held by an earlier one.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RocksDBBackup {
+    /// Take backups every `interval_sec`. Backups requested through the
+    /// admin API are taken either way.
+    #[serde(default)]
+    pub enable: bool,
+
+    /// Empty uses `<data_path>/_rocksdb_backup`.
+    #[serde(default)]
+    pub backup_dir: String,
+
+    #[serde(default = "default_rocksdb_backup_interval_sec")]
+    pub interval_sec: u64,
+
+    /// Older backups are purged after each new one. 0 keeps all of them.
+    #[serde(default = "default_rocksdb_backup_max_backups")]
+    pub max_backups: usize,
+
+    /// Restore the latest backup when the node starts without a local DB.
+    /// A restore requested through the admin API runs on the next start
+    /// regardless.
+    #[serde(default)]
+    pub restore_on_start: bool,
+
+    /// Mirror the backup directory to S3 after each backup; a restore
+    /// downloads it when the local directory holds no backup.
+    #[serde(default)]
+    pub s3: Option<RocksDBBackupS3>,
+}
+
+impl Default for RocksDBBackup {
+    fn default() -> Self {
+        default_rocksdb_backup()
+    }
+}
+
+/// Test-only fault injection into gRPC client calls, storage adapter
+/// operations and Raft network sends. Faults are added and removed through
+/// the admin API; they are rejected unless this is enabled. Never enable it
+/// in production.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct FaultInjection {
+    #[serde(default)]
+    pub enable: bool,
+}
+
+/// Failure domain of this broker, and the spread the meta leader requires when
+/// placing segment replicas.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct Placement {
+    /// Zone of this broker, e.g. a datacenter or availability zone. Empty when
+    /// unknown; all unlabelled brokers count as one zone.
+    #[serde(default)]
+    pub zone: String,
+    /// Rack of this broker within its zone.
+    #[serde(default)]
+    pub rack: String,
+    /// Replicas of a new segment must span at least this many zones, capped at
+    /// the replica count. Creating the segment fails when the live engine nodes
+    /// cannot satisfy it.
+    #[serde(default = "default_min_replica_zones")]
+    pub min_replica_zones: u32,
+}
+
+impl Default for Placement {
+    fn default() -> Self {
+        Placement {
+            zone: String::new(),
+            rack: String::new(),
+            min_replica_zones: default_min_replica_zones(),
+        }
+    }
+}
+
+fn default_min_replica_zones() -> u32 {
+    1
+}
+
+/// Storage type of new topics by tenant and topic name. The first matching
+/// rule wins; a topic that matches none keeps the storage type it was created
+/// with. Topics that already exist are not moved when the rules change.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+pub struct StorageRouting {
+    #[serde(default)]
+    pub rules: Vec<StorageRouteRule>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct StorageRouteRule {
+    /// Empty matches every tenant.
+    #[serde(default)]
+    pub tenant: String,
+    /// A topic name, or a prefix ending in `#`. `$SYS/#` matches `$SYS` and
+    /// every topic under it.
+    pub topic_filter: String,
+    pub storage_type: StorageType,
+}
+
+impl StorageRouting {
+    pub fn route(&self, tenant: &str, topic_name: &str) -> Option<StorageType> {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(tenant, topic_name))
+            .map(|rule| rule.storage_type)
+    }
+}
+
+impl StorageRouteRule {
+    pub fn matches(&self, tenant: &str, topic_name: &str) -> bool {
+        if !self.tenant.is_empty() && self.tenant != tenant {
+            return false;
+        }
+        let Some(prefix) = self.topic_filter.strip_suffix('#') else {
+            return self.topic_filter == topic_name;
+        };
+        match prefix.strip_suffix('/') {
+            Some(parent) => topic_name == parent || topic_name.starts_with(prefix),
+            None => topic_name.starts_with(prefix),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct RocksDBBackupS3 {
+    pub bucket: String,
+    pub region: String,
+
+    #[serde(default)]
+    pub endpoint: String,
+
+    #[serde(default)]
+    pub access_key_id: String,
+
+    #[serde(default)]
+    pub secret_access_key: String,
+
+    #[serde(default)]
+    pub session_token: String,
+
+    #[serde(default)]
+    pub root: String,
+
+    /// Empty uses `rocksdb-backup/<broker_id>`.
+    #[serde(default)]
+    pub prefix: String,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct MqttSchema {
     #[serde(default = "default_schema_enable")]
@@ -642,6 +1295,31 @@ pub struct MqttProtocolConfig {
     pub max_message_expiry_interval: u64,
     #[serde(default)]
     pub client_pkid_persistent: bool,
+    /// Persist QoS 1/2 messages sent to clients until acknowledged, and
+    /// redeliver them when the session resumes.
+    #[serde(default)]
+    pub inflight_persistent: bool,
+    /// Inflight messages older than this are dropped instead of redelivered.
+    /// 0 = never expire.
+    #[serde(default = "default_inflight_expire_sec")]
+    pub inflight_expire_sec: u64,
+    /// First topic level of the broker-managed response topic namespace.
+    /// Clients requesting response information get "{prefix}/{client_id}",
+    /// and only that client may subscribe below it. Empty = disabled.
+    #[serde(default = "default_response_topic_prefix")]
+    pub response_topic_prefix: String,
+    /// Maximum size of the Correlation Data property (bytes). 0 = unlimited.
+    #[serde(default = "default_max_correlation_data_size")]
+    pub max_correlation_data_size: u32,
+    /// Maximum number of levels in a topic name or topic filter.
+    #[serde(default = "default_max_topic_levels")]
+    pub max_topic_levels: u32,
+    /// Maximum length of a topic name or topic filter (bytes).
+    #[serde(default = "default_max_topic_length")]
+    pub max_topic_length: u32,
+    /// Maximum number of subscriptions a single client may hold. 0 = unlimited.
+    #[serde(default)]
+    pub max_subscriptions_per_client: u32,
 }
 
 impl Default for MqttProtocolConfig {
@@ -666,6 +1344,9 @@ pub struct MqttFlappingDetect {
     pub max_client_connections: u64,
     #[serde(default = "default_flapping_ban_time")]
     pub ban_time: u32,
+    /// Seconds ban logs are kept in local storage. 0 keeps them forever.
+    #[serde(default = "default_flapping_ban_log_retention_sec")]
+    pub ban_log_retention_sec: u64,
 }
 
 impl Default for MqttFlappingDetect {
@@ -688,6 +1369,9 @@ pub struct MqttSlowSubscribeConfig {
     pub record_time: u64,
     #[serde(default = "default_slow_subscribe_delay_type")]
     pub delay_type: DelayType,
+    /// Seconds slow subscription logs are kept in local storage. 0 keeps them forever.
+    #[serde(default = "default_slow_subscribe_log_retention_sec")]
+    pub log_retention_sec: u64,
 }
 
 impl Default for MqttSlowSubscribeConfig {
@@ -702,6 +1386,49 @@ pub fn encode(&self) -> Vec<u8> {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MqttSlowRequestConfig {
+    #[serde(default)]
+    pub enable: bool,
+    /// Handling time in milliseconds above which an inbound packet is recorded.
+    #[serde(default = "default_slow_request_threshold_ms")]
+    pub threshold_ms: u64,
+    /// Slow requests kept in memory per node, the oldest are dropped first.
+    #[serde(default = "default_slow_request_max_records")]
+    pub max_records: usize,
+}
+
+impl Default for MqttSlowRequestConfig {
+    fn default() -> Self {
+        default_mqtt_slow_request()
+    }
+}
+
+impl MqttSlowRequestConfig {
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(&self).expect("Failed to serialize MqttSlowRequestConfig")
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MqttClientAttributeConfig {
+    /// MQTT 5 CONNECT user properties whose key starts with this prefix are
+    /// stored as client attributes, without the prefix. Empty turns it off.
+    #[serde(default = "default_client_attribute_user_property_prefix")]
+    pub user_property_prefix: String,
+    #[serde(default = "default_client_attribute_max_attributes")]
+    pub max_attributes: usize,
+    /// Longest attribute value in bytes.
+    #[serde(default = "default_client_attribute_max_value_len")]
+    pub max_value_len: usize,
+}
+
+impl Default for MqttClientAttributeConfig {
+    fn default() -> Self {
+        default_mqtt_client_attribute()
+    }
+}
+
 #[derive(Serialize, Deserialize, Default, Clone, Debug)]
 pub enum SchemaStrategy {
     #[default]
@@ -745,6 +1472,12 @@ pub struct StorageRuntime {
     pub metadata_reconcile_interval_ms: u64,
     #[serde(default = "default_storage_isr_maintain_interval_ms")]
     pub isr_maintain_interval_ms: u64,
+    /// Keep the messages of every topic in broker memory instead of the
+    /// storage engine, for edge nodes without persistent storage.
+    #[serde(default)]
+    pub edge_mode: bool,
+    #[serde(default)]
+    pub memory_adapter: MemoryAdapterConfig,
     #[serde(default = "default_network")]
     pub network: Network,
 }
@@ -947,11 +1680,774 @@ fn default() -> Self {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
+/// TLS for the internal gRPC port. When enabled the gRPC server only accepts
+/// TLS connections and every client pool of this node dials peers over TLS,
+/// so all nodes of a cluster must switch together.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct GrpcTls {
+    #[serde(default)]
+    pub enable: bool,
+
+    /// PEM certificate chain presented by this node, as server and (with
+    /// `mutual`) as client.
+    #[serde(default)]
+    pub cert: String,
+
+    #[serde(default)]
+    pub key: String,
+
+    /// PEM bundle of the CAs trusted to issue peer certificates.
+    #[serde(default)]
+    pub ca_cert: String,
+
+    /// Require and verify client certificates on the server side and present
+    /// `cert` when dialing peers.
+    #[serde(default)]
+    pub mutual: bool,
+
+    /// SPIFFE trust domain. When set, peers must present a certificate with a
+    /// `spiffe://<trust_domain>/...` URI SAN.
+    #[serde(default)]
+    pub trust_domain: String,
+
+    /// SPIFFE ids accepted from peers, e.g. `spiffe://robustmq/meta`. Takes
+    /// precedence over `trust_domain`; empty accepts any id in the domain.
+    #[serde(default)]
+    pub allowed_peer_ids: Vec<String>,
+
+    /// Name verified against the server certificate. Empty uses the host of the
+    /// dialed address.
+    #[serde(default)]
+    pub server_name: String,
+
+    /// Interval for checking the certificate files for changes, 0 disables
+    /// reloading.
+    #[serde(default = "default_tls_reload_interval_sec")]
+    pub reload_interval_sec: u64,
+}
+
+impl Default for GrpcTls {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            cert: String::new(),
+            key: String::new(),
+            ca_cert: String::new(),
+            mutual: false,
+            trust_domain: String::new(),
+            allowed_peer_ids: Vec::new(),
+            server_name: String::new(),
+            reload_interval_sec: default_tls_reload_interval_sec(),
+        }
+    }
+}
+
+/// Caller authentication and per-RPC authorization of the meta service gRPC
+/// services. Callers present a bearer token or, over mutual TLS, a SPIFFE id
+/// mapped to a role.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct MetaServiceAuth {
+    #[serde(default)]
+    pub enable: bool,
+
+    /// Token this node sends on every internal gRPC call. Meta nodes accept it
+    /// with the `broker_node` role, so all nodes of a cluster share it.
+    #[serde(default)]
+    pub node_token: String,
+
+    /// Additional tokens, e.g. for operator tooling or monitoring.
+    #[serde(default)]
+    pub tokens: Vec<MetaAuthToken>,
+
+    /// Roles granted to mutual TLS peers by SPIFFE id.
+    #[serde(default)]
+    pub peer_roles: HashMap<String, MetaAuthRole>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MetaAuthToken {
+    pub name: String,
+    pub token: String,
+    pub role: MetaAuthRole,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MetaAuthRole {
+    /// Read and modify metadata.
+    Admin,
+    /// Everything `admin` may do, plus node registration, heartbeats and raft
+    /// traffic between meta nodes.
+    BrokerNode,
+    /// List and get calls only.
+    ReadOnly,
+}
+
+/// Hashing and complexity rules for passwords of built-in users.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct PasswordPolicy {
+    /// Algorithm used for new passwords and when rehashing legacy entries.
+    #[serde(default)]
+    pub hash_algorithm: PasswordHashAlgorithm,
+
+    #[serde(default = "default_bcrypt_cost")]
+    pub bcrypt_cost: u32,
+
+    /// Rehash a user's password with `hash_algorithm` after a successful login
+    /// against an entry stored with a different algorithm.
+    #[serde(default = "default_rehash_on_login")]
+    pub rehash_on_login: bool,
+
+    #[serde(default = "default_password_min_length")]
+    pub min_length: usize,
+
+    #[serde(default)]
+    pub require_uppercase: bool,
+
+    #[serde(default)]
+    pub require_lowercase: bool,
+
+    #[serde(default)]
+    pub require_digit: bool,
+
+    #[serde(default)]
+    pub require_special: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        PasswordPolicy {
+            hash_algorithm: PasswordHashAlgorithm::default(),
+            bcrypt_cost: default_bcrypt_cost(),
+            rehash_on_login: default_rehash_on_login(),
+            min_length: default_password_min_length(),
+            require_uppercase: false,
+            require_lowercase: false,
+            require_digit: false,
+            require_special: false,
+        }
+    }
+}
+
+fn default_bcrypt_cost() -> u32 {
+    10
+}
+
+fn default_rehash_on_login() -> bool {
+    true
+}
+
+fn default_password_min_length() -> usize {
+    1
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PasswordHashAlgorithm {
+    /// Stored as given. Only kept for compatibility with existing entries.
+    Plain,
+    /// Hex MD5 of salt + password. Only kept for compatibility with existing
+    /// entries.
+    Md5,
+    /// Hex SHA-256 of salt + password. Only kept for compatibility with
+    /// existing entries.
+    Sha256,
+    /// Modular crypt format, the salt is part of the hash.
+    Bcrypt,
+    /// Argon2id PHC string, the salt is part of the hash.
+    #[default]
+    Argon2,
+}
+
+impl std::str::FromStr for PasswordHashAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "plain" => Ok(Self::Plain),
+            "md5" => Ok(Self::Md5),
+            "sha256" => Ok(Self::Sha256),
+            "bcrypt" => Ok(Self::Bcrypt),
+            "argon2" => Ok(Self::Argon2),
+            _ => Err(format!("invalid password hash algorithm: {s}")),
+        }
+    }
+}
+
+/// Exposure of the metrics registry beyond `/metrics` on the admin port,
+/// which is always served.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Prometheus {
+    /// Start a dedicated listener on `port` serving only `/metrics`.
+    #[serde(default)]
+    pub enable: bool,
+
+    #[serde(default = "default_prometheus_port")]
+    pub port: u32,
+
+    #[serde(default)]
+    pub push: PrometheusPush,
+}
+
+impl Default for Prometheus {
+    fn default() -> Self {
+        Prometheus {
+            enable: false,
+            port: default_prometheus_port(),
+            push: PrometheusPush::default(),
+        }
+    }
+}
+
+fn default_prometheus_port() -> u32 {
+    9090
+}
+
+/// Periodic push of the metrics registry, for deployments where Prometheus
+/// cannot scrape the nodes directly.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PrometheusPush {
+    #[serde(default)]
+    pub enable: bool,
+
+    #[serde(default)]
+    pub mode: PrometheusPushMode,
+
+    /// Pushgateway base URL, e.g. `http://pushgateway:9091`, or the full
+    /// remote-write URL, e.g. `http://prometheus:9090/api/v1/write`.
+    #[serde(default)]
+    pub endpoint: String,
+
+    #[serde(default = "default_prometheus_push_interval_ms")]
+    pub interval_ms: u64,
+
+    #[serde(default = "default_prometheus_push_timeout_ms")]
+    pub timeout_ms: u64,
+
+    /// Value of the `job` label attached to every pushed series.
+    #[serde(default = "default_prometheus_push_job")]
+    pub job: String,
+}
+
+impl Default for PrometheusPush {
+    fn default() -> Self {
+        PrometheusPush {
+            enable: false,
+            mode: PrometheusPushMode::default(),
+            endpoint: String::new(),
+            interval_ms: default_prometheus_push_interval_ms(),
+            timeout_ms: default_prometheus_push_timeout_ms(),
+            job: default_prometheus_push_job(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PrometheusPushMode {
+    /// `PUT` the text exposition format to a Pushgateway grouping key.
+    #[default]
+    Pushgateway,
+    /// `POST` a snappy-compressed protobuf `WriteRequest` to a Prometheus
+    /// remote-write receiver.
+    RemoteWrite,
+}
+
+fn default_prometheus_push_interval_ms() -> u64 {
+    15000
+}
+
+fn default_prometheus_push_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_prometheus_push_job() -> String {
+    "robustmq".to_string()
+}
+
+/// HTTP callbacks for broker events (`client.connected`, `client.disconnected`,
+/// `message.publish`, `session.subscribed`, `session.unsubscribed`).
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MqttWebhook {
+    #[serde(default)]
+    pub enable: bool,
+
+    /// Maximum number of events sent in one request.
+    #[serde(default = "default_webhook_batch_size")]
+    pub batch_size: usize,
+
+    /// How long to wait for a batch to fill before sending it.
+    #[serde(default = "default_webhook_batch_interval_ms")]
+    pub batch_interval_ms: u64,
+
+    /// Pending events beyond this are dropped instead of blocking the broker.
+    #[serde(default = "default_webhook_queue_size")]
+    pub queue_size: usize,
+
+    #[serde(default = "default_webhook_max_retries")]
+    pub max_retries: u32,
+
+    /// Base retry delay, doubled on every attempt.
+    #[serde(default = "default_webhook_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+
+    /// Consecutive failed batches before an endpoint's circuit opens. 0 disables the breaker.
+    #[serde(default = "default_webhook_circuit_breaker_threshold")]
+    pub circuit_breaker_threshold: u32,
+
+    /// How long an open circuit rejects events before a trial request is allowed.
+    #[serde(default = "default_webhook_circuit_breaker_open_ms")]
+    pub circuit_breaker_open_ms: u64,
+
+    #[serde(default)]
+    pub endpoints: Vec<WebhookEndpoint>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct WebhookEndpoint {
+    pub name: String,
+    pub url: String,
+    /// Event names this endpoint receives; empty means all events.
+    #[serde(default)]
+    pub events: Vec<String>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default = "default_webhook_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_webhook_batch_size() -> usize {
+    100
+}
+
+fn default_webhook_batch_interval_ms() -> u64 {
+    500
+}
+
+fn default_webhook_queue_size() -> usize {
+    10000
+}
+
+fn default_webhook_max_retries() -> u32 {
+    3
+}
+
+fn default_webhook_retry_backoff_ms() -> u64 {
+    200
+}
+
+fn default_webhook_circuit_breaker_threshold() -> u32 {
+    5
+}
+
+fn default_webhook_circuit_breaker_open_ms() -> u64 {
+    30000
+}
+
+fn default_webhook_timeout_ms() -> u64 {
+    5000
+}
+
+impl Default for MqttWebhook {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            batch_size: default_webhook_batch_size(),
+            batch_interval_ms: default_webhook_batch_interval_ms(),
+            queue_size: default_webhook_queue_size(),
+            max_retries: default_webhook_max_retries(),
+            retry_backoff_ms: default_webhook_retry_backoff_ms(),
+            circuit_breaker_threshold: default_webhook_circuit_breaker_threshold(),
+            circuit_breaker_open_ms: default_webhook_circuit_breaker_open_ms(),
+            endpoints: Vec::new(),
+        }
+    }
+}
+
+/// Per-topic payload transformation applied before messages are stored.
+/// Subscribers opt in to receiving the original payload back.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MqttPayloadTransform {
+    #[serde(default)]
+    pub enable: bool,
+
+    /// Evaluated in order; the first rule whose topic filter matches is used.
+    #[serde(default)]
+    pub rules: Vec<PayloadTransformRule>,
+
+    /// AES-256 keys by key id, base64 encoded. Keys returned by a registered
+    /// key provider take precedence.
+    #[serde(default)]
+    pub keys: HashMap<String, String>,
+
+    /// Largest payload a decompression step may produce when decoding on push.
+    #[serde(default = "default_payload_transform_max_decoded_bytes")]
+    pub max_decoded_bytes: u64,
+}
+
+fn default_payload_transform_max_decoded_bytes() -> u64 {
+    16 * 1024 * 1024
+}
+
+impl Default for MqttPayloadTransform {
+    fn default() -> Self {
+        MqttPayloadTransform {
+            enable: false,
+            rules: Vec::new(),
+            keys: HashMap::new(),
+            max_decoded_bytes: default_payload_transform_max_decoded_bytes(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct PayloadTransformRule {
+    /// Topic name or filter (`+`/`#`).
+    pub topic: String,
+    /// Applied in order on publish and reversed on push.
+    pub steps: Vec<PayloadTransformStep>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadTransformStep {
+    Gzip,
+    Zstd,
+    /// AES-256-GCM with the key registered under `key_id`.
+    Encrypt {
+        key_id: String,
+    },
+    JsonToCbor,
+    CborToJson,
+}
+
+/// Client certificate (mutual TLS) settings for the MQTT TLS listener. The
+/// server certificate itself is configured by `runtime.tls_cert`/`tls_key`.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct MqttTls {
+    #[serde(default)]
+    pub client_auth: TlsClientAuth,
+
+    /// PEM bundle of the CAs trusted to issue client certificates.
+    #[serde(default)]
+    pub ca_cert: String,
+
+    /// PEM CRL files; client certificates listed in any of them are rejected.
+    #[serde(default)]
+    pub crl_files: Vec<String>,
+
+    /// DER-encoded OCSP response for the server certificate, stapled into
+    /// every handshake. Empty disables stapling.
+    #[serde(default)]
+    pub ocsp_response: String,
+
+    /// Certificate field used as the MQTT username. A connection whose username
+    /// is taken from a verified certificate skips password authentication.
+    #[serde(default)]
+    pub peer_cert_as_username: PeerCertField,
+
+    /// Certificate field used as the MQTT client id.
+    #[serde(default)]
+    pub peer_cert_as_client_id: PeerCertField,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TlsClientAuth {
+    /// Client certificates are not requested.
+    #[default]
+    None,
+    /// A certificate is verified if presented, but not required.
+    Optional,
+    Required,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PeerCertField {
+    #[default]
+    None,
+    /// Subject common name.
+    Cn,
+    /// First DNS subject alternative name.
+    Dns,
+    /// First email (RFC 822) subject alternative name.
+    Email,
+    /// First URI subject alternative name.
+    Uri,
+}
+
+/// MQTT-SN gateway: MQTT-SN v1.2 over UDP for sensors that cannot run a TCP
+/// MQTT stack. Every MQTT-SN client is mapped to an MQTT connection.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MqttSnConfig {
+    #[serde(default)]
+    pub enable: bool,
+
+    #[serde(default = "default_mqtt_sn_port")]
+    pub port: u32,
+
+    /// Gateway id returned in GWINFO.
+    #[serde(default = "default_mqtt_sn_gateway_id")]
+    pub gateway_id: u8,
+
+    /// Messages kept for a sleeping client; the oldest are dropped beyond this.
+    #[serde(default = "default_mqtt_sn_sleep_buffer_max_messages")]
+    pub sleep_buffer_max_messages: usize,
+
+    /// Credentials used for the MQTT connections of MQTT-SN clients, which
+    /// cannot authenticate themselves. Empty connects without a login.
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+
+    /// Topic ids known to clients without REGISTER. They are also the only
+    /// topics QoS -1 publishes can use besides short topic names.
+    #[serde(default)]
+    pub predefined_topics: Vec<MqttSnPredefinedTopic>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MqttSnPredefinedTopic {
+    pub topic_id: u16,
+    pub topic_name: String,
+}
+
+fn default_mqtt_sn_port() -> u32 {
+    1884
+}
+
+fn default_mqtt_sn_gateway_id() -> u8 {
+    1
+}
+
+fn default_mqtt_sn_sleep_buffer_max_messages() -> usize {
+    100
+}
+
+impl Default for MqttSnConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            port: default_mqtt_sn_port(),
+            gateway_id: default_mqtt_sn_gateway_id(),
+            sleep_buffer_max_messages: default_mqtt_sn_sleep_buffer_max_messages(),
+            username: String::new(),
+            password: String::new(),
+            predefined_topics: Vec::new(),
+        }
+    }
+}
+
+/// CoAP gateway: CoAP requests are mapped to MQTT publishes and Observe
+/// registrations to subscriptions. Every CoAP endpoint is mapped to an MQTT
+/// connection.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CoapConfig {
+    #[serde(default)]
+    pub enable: bool,
+
+    #[serde(default = "default_coap_port")]
+    pub port: u32,
+
+    /// CoAP over DTLS, using the broker TLS certificate.
+    #[serde(default)]
+    pub dtls_enable: bool,
+
+    #[serde(default = "default_coap_dtls_port")]
+    pub dtls_port: u32,
+
+    /// Leading URI path segments of every publish and observe request, e.g.
+    /// `ps` for `coap://host/ps/{topic}`. Empty maps the whole path to the topic.
+    #[serde(default = "default_coap_uri_path_prefix")]
+    pub uri_path_prefix: String,
+
+    /// Prepended to the topic taken from the URI path.
+    #[serde(default)]
+    pub topic_prefix: String,
+
+    /// Largest block of a block-wise transfer, from 16 to 1024 bytes. Larger
+    /// notifications are sent block by block.
+    #[serde(default = "default_coap_block_size")]
+    pub block_size: usize,
+
+    /// Largest payload reassembled from a block-wise PUT or POST.
+    #[serde(default = "default_coap_max_payload_size")]
+    pub max_payload_size: usize,
+
+    /// The MQTT connection of an endpoint without observations is closed after
+    /// this long without requests.
+    #[serde(default = "default_coap_idle_timeout_sec")]
+    pub idle_timeout_sec: u64,
+
+    /// Credentials of the MQTT connections, used when a request carries no
+    /// `u` and `p` query parameters. Empty connects without a login.
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+}
+
+fn default_coap_port() -> u32 {
+    5683
+}
+
+fn default_coap_dtls_port() -> u32 {
+    5684
+}
+
+fn default_coap_uri_path_prefix() -> String {
+    "ps".to_string()
+}
+
+fn default_coap_block_size() -> usize {
+    1024
+}
+
+fn default_coap_max_payload_size() -> usize {
+    1024 * 1024
+}
+
+fn default_coap_idle_timeout_sec() -> u64 {
+    120
+}
+
+impl Default for CoapConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            port: default_coap_port(),
+            dtls_enable: false,
+            dtls_port: default_coap_dtls_port(),
+            uri_path_prefix: default_coap_uri_path_prefix(),
+            topic_prefix: String::new(),
+            block_size: default_coap_block_size(),
+            max_payload_size: default_coap_max_payload_size(),
+            idle_timeout_sec: default_coap_idle_timeout_sec(),
+            username: String::new(),
+            password: String::new(),
+        }
+    }
+}
+
+/// Browser subscription endpoint: messages on the subscribed topic filters are
+/// streamed as Server-Sent Events or WebSocket JSON frames, without MQTT
+/// framing. Each browser connection is an MQTT connection of the token's user.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MqttWebSubscribeConfig {
+    #[serde(default)]
+    pub enable: bool,
+
+    #[serde(default = "default_mqtt_web_subscribe_port")]
+    pub port: u32,
+
+    /// Secret of the HS256 tokens presented by browsers. The `sub` claim is
+    /// the MQTT username whose ACLs apply. The endpoint does not start without it.
+    #[serde(default)]
+    pub jwt_secret: String,
+
+    #[serde(default = "default_mqtt_web_subscribe_max_topics")]
+    pub max_topics_per_connection: usize,
+
+    /// Interval of SSE keep-alive comments and WebSocket pings.
+    #[serde(default = "default_mqtt_web_subscribe_keep_alive_sec")]
+    pub keep_alive_sec: u64,
+
+    /// Messages queued for a slow browser; newer messages are dropped beyond this.
+    #[serde(default = "default_mqtt_web_subscribe_send_buffer_size")]
+    pub send_buffer_size: usize,
+}
+
+fn default_mqtt_web_subscribe_port() -> u32 {
+    8086
+}
+
+fn default_mqtt_web_subscribe_max_topics() -> usize {
+    10
+}
+
+fn default_mqtt_web_subscribe_keep_alive_sec() -> u64 {
+    15
+}
+
+fn default_mqtt_web_subscribe_send_buffer_size() -> usize {
+    1000
+}
+
+impl Default for MqttWebSubscribeConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            port: default_mqtt_web_subscribe_port(),
+            jwt_secret: String::new(),
+            max_topics_per_connection: default_mqtt_web_subscribe_max_topics(),
+            keep_alive_sec: default_mqtt_web_subscribe_keep_alive_sec(),
+            send_buffer_size: default_mqtt_web_subscribe_send_buffer_size(),
+        }
+    }
+}
+
+/// Retained messages: payloads live in the storage adapter and meta-service
+/// only keeps an index, so each broker caches recently delivered payloads.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MqttRetainConfig {
+    /// Retained payloads kept in the broker LRU cache. 0 disables the cache.
+    #[serde(default = "default_mqtt_retain_cache_capacity")]
+    pub cache_capacity: usize,
+}
+
+fn default_mqtt_retain_cache_capacity() -> usize {
+    10000
+}
+
+impl Default for MqttRetainConfig {
+    fn default() -> Self {
+        Self {
+            cache_capacity: default_mqtt_retain_cache_capacity(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage_routing_first_matching_rule_wins() {
+        let routing: StorageRouting = serde_json::from_str(
+            r#"{"rules": [
+                {"topic_filter": "$SYS/#", "storage_type": "Memory"},
+                {"tenant": "iot", "topic_filter": "telemetry/#", "storage_type": "EngineSegment"},
+                {"topic_filter": "telemetry/raw", "storage_type": "EngineMemory"},
+                {"topic_filter": "audit#", "storage_type": "EngineRocksDB"}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(routing.route("t", "$SYS"), Some(StorageType::Memory));
+        assert_eq!(
+            routing.route("t", "$SYS/brokers/1/uptime"),
+            Some(StorageType::Memory)
+        );
+        assert_eq!(routing.route("t", "$SYSX"), None);
+        assert_eq!(
+            routing.route("iot", "telemetry/raw"),
+            Some(StorageType::EngineSegment)
+        );
+        assert_eq!(
+            routing.route("other", "telemetry/raw"),
+            Some(StorageType::EngineMemory)
+        );
+        assert_eq!(routing.route("other", "telemetry/a"), None);
+        assert_eq!(
+            routing.route("t", "audit-log/1"),
+            Some(StorageType::EngineRocksDB)
+        );
+        assert_eq!(StorageRouting::default().route("t", "a"), None);
+    }
+
+    #[test]
     fn cluster_limit_default_has_max_connection_per_ip() {
         let limit = ClusterLimit::default();
         assert_eq!(limit.max_connection_per_ip, 5000);
@@ -972,4 +2468,29 @@ fn default_max_connection_per_ip_matches_struct_default() {
             ClusterLimit::default().max_connection_per_ip
         );
     }
+
+    #[test]
+    fn prometheus_push_mode_from_toml() {
+        let config: Prometheus = toml::from_str(
+            r#"
+            enable = true
+            port = 9091
+            [push]
+            enable = true
+            mode = "remote_write"
+            endpoint = "http://prometheus:9090/api/v1/write"
+            "#,
+        )
+        .unwrap();
+        assert!(config.enable);
+        assert_eq!(config.port, 9091);
+        assert!(config.push.enable);
+        assert_eq!(config.push.mode, PrometheusPushMode::RemoteWrite);
+        assert_eq!(config.push.interval_ms, 15000);
+        assert_eq!(config.push.job, "robustmq");
+        let default = Prometheus::default();
+        assert!(!default.enable);
+        assert_eq!(default.port, 9090);
+        assert_eq!(default.push.mode, PrometheusPushMode::Pushgateway);
+    }
 }
@@ -13,15 +13,19 @@
 // limitations under the License.
 
 use crate::config::{
-    DelayTask, MetaRuntime, MqttFlappingDetect, MqttKeepAlive, MqttOfflineMessage,
-    MqttProtocolConfig, MqttRuntime, MqttSchema, MqttServer, MqttSlowSubscribeConfig,
-    MqttSystemMonitor, Network, Runtime, SchemaFailedOperation, SchemaStrategy, StorageRuntime,
+    DelayTask, MetaRuntime, MqttClientAttributeConfig, MqttFlappingDetect, MqttKeepAlive,
+    MqttOfflineMessage, MqttProtocolConfig, MqttRuntime, MqttSchema, MqttServer,
+    MqttSlowRequestConfig, MqttSlowSubscribeConfig, MqttSubscriptionMetrics, MqttSystemMonitor,
+    MqttTopicMetrics, MqttTrace, Network, OfflineQueueFullPolicy, RaftLogRetention, RocksDBBackup,
+    Runtime, SchemaFailedOperation, SchemaStrategy, StorageRuntime,
 };
+use crate::storage::memory::MemoryAdapterConfig;
 use crate::storage::{StorageAdapterConfig, StorageType};
 use common_base::enum_type::delay_type::DelayType;
 use common_base::role::{ROLE_BROKER, ROLE_META};
 use common_base::runtime::get_default_runtime_worker_threads;
 use common_base::tools::get_local_ip;
+use std::collections::HashMap;
 use toml::Table;
 
 pub fn default_runtime_worker_threads() -> usize {
@@ -67,9 +71,22 @@ pub fn default_runtime() -> Runtime {
         server_worker_threads: 0,
         meta_worker_threads: 0,
         broker_worker_threads: 0,
+        storage_worker_threads: 0,
+        push_worker_threads: 0,
+        background_worker_threads: 0,
         channels_per_address: 4,
         tls_cert: "./config/certs/cert.pem".to_string(),
         tls_key: "./config/certs/key.pem".to_string(),
+        tls_acme_dir: String::new(),
+        tls_reload_interval_sec: default_tls_reload_interval_sec(),
+        tls_handshake_thread_num: default_tls_handshake_thread_num(),
+        tls_handshake_queue_size: default_tls_handshake_queue_size(),
+        tls_handshake_timeout_ms: default_tls_handshake_timeout_ms(),
+        tls_session_cache_size: default_tls_session_cache_size(),
+        tls_session_ticket_enable: default_tls_session_ticket_enable(),
+        tcp_nodelay: default_tcp_nodelay(),
+        write_coalesce_window_us: 0,
+        write_buffer_max_bytes: default_write_buffer_max_bytes(),
         pprof_enable: false,
         default_topic_partition_num: 3,
         default_topic_replica_num: 2,
@@ -94,6 +111,18 @@ pub fn default_meta_runtime() -> MetaRuntime {
         group_offset_expire_sec: 7 * 24 * 3600,
         segment_leader_rebalance_interval_ms: 60_000,
         segment_leader_rebalance_max_moves: 50,
+        connector_rebalance_interval_ms: 300_000,
+        connector_rebalance_max_moves: 5,
+        raft_write_batch_enable: false,
+        raft_write_batch_max_entries: 64,
+        raft_write_batch_window_ms: 2,
+        metadata_raft_log: RaftLogRetention::default(),
+        offset_raft_log: RaftLogRetention::default(),
+        data_raft_log: RaftLogRetention::default(),
+        raft_log_check_interval_ms: 30_000,
+        raft_snapshot_chunk_size: 1024 * 1024,
+        raft_snapshot_max_bytes_per_sec: 0,
+        raft_snapshot_chunk_timeout_ms: 60_000,
     }
 }
 
@@ -112,7 +141,7 @@ pub fn default_mqtt_keep_alive() -> MqttKeepAlive {
         enable: true,
         max_time: 3600,
         default_time: 180,
-        default_timeout: 2,
+        timeout_multiplier: 1.5,
     }
 }
 
@@ -136,6 +165,7 @@ pub fn default_mqtt_runtime() -> MqttRuntime {
         default_user: "admin".to_string(),
         default_password: "robustmq".to_string(),
         durable_sessions_enable: false, // Default: transient sessions (better performance)
+        cluster_forward_enable: false,
         secret_free_login: false,
         is_self_protection_status: false,
         network: default_network(),
@@ -147,6 +177,9 @@ pub fn default_mqtt_offline_message() -> MqttOfflineMessage {
         enable: true,
         expire_ms: 0,
         max_messages_num: 0,
+        session_queue_max_messages: default_offline_message_session_queue_max_messages(),
+        session_queue_max_bytes: default_offline_message_session_queue_max_bytes(),
+        session_queue_full_policy: OfflineQueueFullPolicy::DropOldest,
     }
 }
 
@@ -155,6 +188,23 @@ pub fn default_mqtt_slow_subscribe() -> MqttSlowSubscribeConfig {
         enable: false,
         record_time: 1000,
         delay_type: DelayType::Whole,
+        log_retention_sec: default_slow_subscribe_log_retention_sec(),
+    }
+}
+
+pub fn default_mqtt_slow_request() -> MqttSlowRequestConfig {
+    MqttSlowRequestConfig {
+        enable: false,
+        threshold_ms: default_slow_request_threshold_ms(),
+        max_records: default_slow_request_max_records(),
+    }
+}
+
+pub fn default_mqtt_client_attribute() -> MqttClientAttributeConfig {
+    MqttClientAttributeConfig {
+        user_property_prefix: default_client_attribute_user_property_prefix(),
+        max_attributes: default_client_attribute_max_attributes(),
+        max_value_len: default_client_attribute_max_value_len(),
     }
 }
 
@@ -164,6 +214,7 @@ pub fn default_mqtt_flapping_detect() -> MqttFlappingDetect {
         window_time: 1,
         max_client_connections: 15,
         ban_time: 5,
+        ban_log_retention_sec: default_flapping_ban_log_retention_sec(),
     }
 }
 
@@ -176,6 +227,13 @@ pub fn default_mqtt_protocol() -> MqttProtocolConfig {
         receive_max: 65535,
         client_pkid_persistent: false,
         max_message_expiry_interval: 3600,
+        inflight_persistent: false,
+        inflight_expire_sec: default_inflight_expire_sec(),
+        response_topic_prefix: default_response_topic_prefix(),
+        max_correlation_data_size: default_max_correlation_data_size(),
+        max_topic_levels: default_max_topic_levels(),
+        max_topic_length: default_max_topic_length(),
+        max_subscriptions_per_client: 0,
     }
 }
 
@@ -194,7 +252,61 @@ pub fn default_mqtt_system_monitor() -> MqttSystemMonitor {
         enable: false,
         os_cpu_high_watermark: 70.0,
         os_memory_high_watermark: 80.0,
+        os_cpu_low_watermark: default_system_monitor_cpu_low_watermark(),
+        os_memory_low_watermark: default_system_monitor_memory_low_watermark(),
         system_topic_interval_ms: 60000,
+        client_event_enable: false,
+        system_event_retention_sec: default_system_event_retention_sec(),
+        overload_protection_enable: false,
+        overload_check_interval_ms: default_overload_check_interval_ms(),
+        overload_connect_delay_ms: default_overload_connect_delay_ms(),
+        overload_ack_delay_ms: default_overload_ack_delay_ms(),
+        metrics_history_enable: false,
+        metrics_history_interval_ms: default_metrics_history_interval_ms(),
+        metrics_history_retention_sec: default_metrics_history_retention_sec(),
+    }
+}
+
+pub fn default_topic_metrics_enable() -> bool {
+    true
+}
+
+pub fn default_topic_metrics_prefix_levels() -> usize {
+    0
+}
+
+pub fn default_topic_metrics_max_series() -> usize {
+    1000
+}
+
+pub fn default_mqtt_topic_metrics() -> MqttTopicMetrics {
+    MqttTopicMetrics {
+        enable: default_topic_metrics_enable(),
+        allowlist: Vec::new(),
+        prefix_levels: default_topic_metrics_prefix_levels(),
+        max_series: default_topic_metrics_max_series(),
+    }
+}
+
+pub fn default_subscription_metrics_max_series() -> usize {
+    1000
+}
+
+pub fn default_mqtt_subscription_metrics() -> MqttSubscriptionMetrics {
+    MqttSubscriptionMetrics {
+        enable: false,
+        max_series: default_subscription_metrics_max_series(),
+    }
+}
+
+pub fn default_trace_max_events() -> usize {
+    10000
+}
+
+pub fn default_mqtt_trace() -> MqttTrace {
+    MqttTrace {
+        max_events: default_trace_max_events(),
+        rules: Vec::new(),
     }
 }
 
@@ -213,6 +325,8 @@ pub fn default_engine_runtime() -> StorageRuntime {
         replica_lag_time_max_ms: 10000,
         metadata_reconcile_interval_ms: 30000,
         isr_maintain_interval_ms: 1000,
+        edge_mode: false,
+        memory_adapter: MemoryAdapterConfig::default(),
         network: default_network(),
     }
 }
@@ -224,6 +338,30 @@ pub fn default_tls_cert() -> String {
 pub fn default_tls_key() -> String {
     "./config/certs/key.pem".to_string()
 }
+pub fn default_tls_reload_interval_sec() -> u64 {
+    30
+}
+pub fn default_tls_handshake_thread_num() -> usize {
+    64
+}
+pub fn default_tls_handshake_queue_size() -> usize {
+    10000
+}
+pub fn default_tls_handshake_timeout_ms() -> u64 {
+    10000
+}
+pub fn default_tls_session_cache_size() -> usize {
+    10240
+}
+pub fn default_tls_session_ticket_enable() -> bool {
+    true
+}
+pub fn default_tcp_nodelay() -> bool {
+    true
+}
+pub fn default_write_buffer_max_bytes() -> usize {
+    64 * 1024
+}
 pub fn default_channels_per_address() -> usize {
     4
 }
@@ -282,8 +420,8 @@ pub fn default_keep_alive_default_time() -> u16 {
 pub fn default_keep_alive_max_time() -> u16 {
     3600
 }
-pub fn default_keep_alive_default_timeout() -> u16 {
-    2
+pub fn default_keep_alive_timeout_multiplier() -> f32 {
+    1.5
 }
 
 // MqttSystemMonitor
@@ -293,9 +431,33 @@ pub fn default_system_monitor_cpu_watermark() -> f32 {
 pub fn default_system_monitor_memory_watermark() -> f32 {
     80.0
 }
+pub fn default_system_monitor_cpu_low_watermark() -> f32 {
+    60.0
+}
+pub fn default_system_monitor_memory_low_watermark() -> f32 {
+    70.0
+}
 pub fn default_system_monitor_topic_interval_ms() -> u64 {
     60000
 }
+pub fn default_system_event_retention_sec() -> u64 {
+    7 * 24 * 3600
+}
+pub fn default_overload_check_interval_ms() -> u64 {
+    5000
+}
+pub fn default_overload_connect_delay_ms() -> u64 {
+    1000
+}
+pub fn default_overload_ack_delay_ms() -> u64 {
+    100
+}
+pub fn default_metrics_history_interval_ms() -> u64 {
+    60000
+}
+pub fn default_metrics_history_retention_sec() -> u64 {
+    7 * 24 * 3600
+}
 
 // MqttOfflineMessage
 pub fn default_offline_message_enable() -> bool {
@@ -307,6 +469,12 @@ pub fn default_offline_message_expire_ms() -> u32 {
 pub fn default_offline_message_max_num() -> u32 {
     100_000 // 0 = unlimited
 }
+pub fn default_offline_message_session_queue_max_messages() -> u64 {
+    1000 // 0 = per-session queue disabled
+}
+pub fn default_offline_message_session_queue_max_bytes() -> u64 {
+    10 * 1024 * 1024 // 0 = unlimited
+}
 
 // MqttSchema
 pub fn default_schema_enable() -> bool {
@@ -345,6 +513,21 @@ pub fn default_receive_max() -> u16 {
 pub fn default_max_message_expiry_interval() -> u64 {
     3600
 }
+pub fn default_inflight_expire_sec() -> u64 {
+    3600
+}
+pub fn default_response_topic_prefix() -> String {
+    "$rpc".to_string()
+}
+pub fn default_max_correlation_data_size() -> u32 {
+    4096
+}
+pub fn default_max_topic_levels() -> u32 {
+    128
+}
+pub fn default_max_topic_length() -> u32 {
+    65535
+}
 
 // MqttFlappingDetect
 pub fn default_flapping_window_time() -> u32 {
@@ -356,6 +539,9 @@ pub fn default_flapping_max_connections() -> u64 {
 pub fn default_flapping_ban_time() -> u32 {
     5
 }
+pub fn default_flapping_ban_log_retention_sec() -> u64 {
+    7 * 24 * 3600
+}
 
 // MqttSlowSubscribeConfig
 pub fn default_slow_subscribe_record_time() -> u64 {
@@ -364,6 +550,28 @@ pub fn default_slow_subscribe_record_time() -> u64 {
 pub fn default_slow_subscribe_delay_type() -> DelayType {
     DelayType::Whole
 }
+pub fn default_slow_subscribe_log_retention_sec() -> u64 {
+    24 * 3600
+}
+
+// MqttSlowRequestConfig
+pub fn default_slow_request_threshold_ms() -> u64 {
+    500
+}
+pub fn default_slow_request_max_records() -> usize {
+    1000
+}
+
+// MqttClientAttributeConfig
+pub fn default_client_attribute_user_property_prefix() -> String {
+    "attr.".to_string()
+}
+pub fn default_client_attribute_max_attributes() -> usize {
+    32
+}
+pub fn default_client_attribute_max_value_len() -> usize {
+    256
+}
 
 // StorageRuntime
 pub fn default_storage_tcp_port() -> u32 {
@@ -439,6 +647,9 @@ pub fn default_limit_max_sessions() -> u64 {
 pub fn default_limit_max_publish_rate() -> u32 {
     10000
 }
+pub fn default_limit_max_subscriptions() -> u64 {
+    5000000
+}
 
 // MQTTLimit — cluster and tenant have different default quotas
 pub fn default_mqtt_limit_cluster() -> crate::config::LimitQuota {
@@ -448,6 +659,7 @@ pub fn default_mqtt_limit_cluster() -> crate::config::LimitQuota {
         max_topics: 5_000_000,
         max_sessions: 50_000_000,
         max_publish_rate: 10_000,
+        max_subscriptions: 50_000_000,
     }
 }
 pub fn default_mqtt_limit_tenant() -> crate::config::LimitQuota {
@@ -457,6 +669,7 @@ pub fn default_mqtt_limit_tenant() -> crate::config::LimitQuota {
         max_topics: 500_000,
         max_sessions: 5_000_000,
         max_publish_rate: 10_000,
+        max_subscriptions: 5_000_000,
     }
 }
 
@@ -465,9 +678,35 @@ pub fn default_delay_task() -> DelayTask {
     DelayTask {
         delay_task_queue_num: default_delay_task_queue_num(),
         delay_task_handler_concurrency: default_delay_task_handler_concurrency(),
+        delay_task_type_concurrency: HashMap::new(),
+        delay_task_starvation_timeout_ms: default_delay_task_starvation_timeout_ms(),
+        delay_task_index_compact_interval_sec: default_delay_task_index_compact_interval_sec(),
+        delay_task_index_compact_min_stale_records:
+            default_delay_task_index_compact_min_stale_records(),
+        delay_task_index_compact_max_stale_age_sec:
+            default_delay_task_index_compact_max_stale_age_sec(),
     }
 }
 
+pub fn default_rocksdb_backup() -> RocksDBBackup {
+    RocksDBBackup {
+        enable: false,
+        backup_dir: String::new(),
+        interval_sec: default_rocksdb_backup_interval_sec(),
+        max_backups: default_rocksdb_backup_max_backups(),
+        restore_on_start: false,
+        s3: None,
+    }
+}
+
+pub fn default_rocksdb_backup_interval_sec() -> u64 {
+    3600
+}
+
+pub fn default_rocksdb_backup_max_backups() -> usize {
+    24
+}
+
 pub fn default_delay_task_queue_num() -> usize {
     8
 }
@@ -477,3 +716,19 @@ pub fn default_delay_task_handler_concurrency() -> usize {
         .map(|n| n.get())
         .unwrap_or(4)
 }
+
+pub fn default_delay_task_starvation_timeout_ms() -> u64 {
+    5000
+}
+
+pub fn default_delay_task_index_compact_interval_sec() -> u64 {
+    300
+}
+
+pub fn default_delay_task_index_compact_min_stale_records() -> u64 {
+    1000
+}
+
+pub fn default_delay_task_index_compact_max_stale_age_sec() -> u64 {
+    3600
+}
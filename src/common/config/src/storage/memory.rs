@@ -67,3 +67,30 @@ pub fn minimal_memory_mode() -> Self {
         }
     }
 }
+
+/// Capacity of each shard of the in-memory storage adapter. Once a limit is
+/// exceeded the oldest records are evicted; 0 leaves the limit unset.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct MemoryAdapterConfig {
+    #[serde(default = "default_memory_adapter_max_records")]
+    pub max_records_per_shard: u64,
+    #[serde(default = "default_memory_adapter_max_bytes")]
+    pub max_bytes_per_shard: u64,
+}
+
+fn default_memory_adapter_max_records() -> u64 {
+    100_000
+}
+
+fn default_memory_adapter_max_bytes() -> u64 {
+    64 * 1024 * 1024
+}
+
+impl Default for MemoryAdapterConfig {
+    fn default() -> Self {
+        Self {
+            max_records_per_shard: default_memory_adapter_max_records(),
+            max_bytes_per_shard: default_memory_adapter_max_bytes(),
+        }
+    }
+}
@@ -49,6 +49,8 @@ pub enum StorageType {
     Mysql,
     MinIO,
     S3,
+    /// In-memory ring buffer per shard, held by the broker itself.
+    Memory,
 }
 
 impl FromStr for StorageType {
@@ -62,6 +64,7 @@ fn from_str(s: &str) -> Result<Self, Self::Err> {
             "MinIO" => Ok(StorageType::MinIO),
             "S3" => Ok(StorageType::S3),
             "Mysql" => Ok(StorageType::Mysql),
+            "Memory" => Ok(StorageType::Memory),
             _ => Err(()),
         }
     }
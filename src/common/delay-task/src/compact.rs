@@ -0,0 +1,286 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compaction of the persistent delay task index.
+//!
+//! Every persistent task appends a record to `$delay-task-index`, and a
+//! recurring task appends a new one on each run. Executed and deleted tasks
+//! only remove the latest record by key, so superseded records accumulate and
+//! recovery has to read through all of them. The compaction job scans the
+//! index and deletes every record that no longer describes a pending task:
+//!
+//! - records superseded by a later (due later) record of the same task,
+//! - records of tasks that are no longer scheduled on this node,
+//! - records that cannot be decoded.
+//!
+//! A record is only treated as no longer scheduled once both its due time and
+//! its creation time are older than the grace period, which covers tasks
+//! being created, waiting for a handler or being rescheduled right now.
+//! Compaction runs when the number of stale records or the age of the oldest
+//! one passes its threshold.
+
+use crate::manager::DelayTaskManager;
+use crate::recover::update_offsets_from_records;
+use crate::DelayTask;
+use broker_core::inner_topic::DELAY_TASK_INDEX_TOPIC;
+use common_base::error::common::CommonError;
+use common_base::tools::{loop_select_ticket, now_second};
+use common_config::broker::broker_config;
+use metadata_struct::adapter::adapter_read_config::AdapterReadConfig;
+use metadata_struct::storage::record::StorageRecord;
+use metadata_struct::tenant::DEFAULT_TENANT;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct IndexEntry {
+    pub shard: String,
+    pub offset: u64,
+    /// `None` when the record could not be decoded.
+    pub task: Option<IndexTask>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct IndexTask {
+    pub task_id: String,
+    pub delay_target_time: u64,
+    pub create_time: u64,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct StaleIndex {
+    /// shard → offsets to delete.
+    pub offsets: HashMap<String, Vec<u64>>,
+    pub count: u64,
+    /// Seconds since the oldest stale record fell due.
+    pub oldest_age: u64,
+}
+
+impl IndexEntry {
+    fn from_record(record: &StorageRecord) -> Self {
+        IndexEntry {
+            shard: record.metadata.shard.clone(),
+            offset: record.metadata.offset,
+            // Compaction works in whole seconds; records due in the same
+            // second are ordered by their position in the scan.
+            task: DelayTask::decode(&record.data).ok().map(|task| IndexTask {
+                task_id: task.task_id,
+                delay_target_time: task.delay_target_time_ms / 1000,
+                create_time: task.create_time_ms / 1000,
+            }),
+        }
+    }
+}
+
+/// Pick the index records that no longer describe a pending task.
+pub(crate) fn find_stale_records(
+    entries: &[IndexEntry],
+    is_pending: impl Fn(&str) -> bool,
+    now: u64,
+    grace_sec: u64,
+) -> StaleIndex {
+    // Latest record of each task: the highest due time, then the highest
+    // position in the scan.
+    let mut latest: HashMap<&str, (u64, usize)> = HashMap::new();
+    for (i, entry) in entries.iter().enumerate() {
+        if let Some(task) = &entry.task {
+            let current = latest
+                .entry(&task.task_id)
+                .or_insert((task.delay_target_time, i));
+            if (task.delay_target_time, i) > *current {
+                *current = (task.delay_target_time, i);
+            }
+        }
+    }
+
+    let mut stale = StaleIndex::default();
+    for (i, entry) in entries.iter().enumerate() {
+        let age = match &entry.task {
+            None => Some(0),
+            Some(task) => {
+                let due_age = now.saturating_sub(task.delay_target_time);
+                if latest.get(task.task_id.as_str()).map(|(_, at)| *at) != Some(i) {
+                    Some(due_age)
+                } else if !is_pending(&task.task_id)
+                    && due_age > grace_sec
+                    && now.saturating_sub(task.create_time) > grace_sec
+                {
+                    Some(due_age)
+                } else {
+                    None
+                }
+            }
+        };
+
+        if let Some(age) = age {
+            stale
+                .offsets
+                .entry(entry.shard.clone())
+                .or_default()
+                .push(entry.offset);
+            stale.count += 1;
+            stale.oldest_age = stale.oldest_age.max(age);
+        }
+    }
+    stale
+}
+
+async fn read_index(
+    delay_task_manager: &Arc<DelayTaskManager>,
+) -> Result<Vec<IndexEntry>, CommonError> {
+    let read_config = AdapterReadConfig {
+        max_record_num: 100,
+        max_size: 10 * 1024 * 1024,
+    };
+
+    let mut offsets = HashMap::new();
+    let mut entries = Vec::new();
+    loop {
+        let data = delay_task_manager
+            .storage_driver_manager
+            .read_by_offset(
+                DEFAULT_TENANT,
+                DELAY_TASK_INDEX_TOPIC,
+                &offsets,
+                &read_config,
+            )
+            .await?;
+        if data.is_empty() {
+            break;
+        }
+        entries.extend(data.iter().map(IndexEntry::from_record));
+        update_offsets_from_records(&data, &mut offsets);
+    }
+
+    // Order by position so "later" means a higher offset within a shard.
+    entries.sort_by(|a, b| (&a.shard, a.offset).cmp(&(&b.shard, b.offset)));
+    Ok(entries)
+}
+
+pub(crate) async fn compact_delay_task_index(
+    delay_task_manager: &Arc<DelayTaskManager>,
+) -> Result<(), CommonError> {
+    if !delay_task_manager.is_recovered() {
+        debug!("Delay task index compaction skipped: queue recovery not finished");
+        return Ok(());
+    }
+
+    let conf = &broker_config().delay_task;
+    let entries = read_index(delay_task_manager).await?;
+    let stale = find_stale_records(
+        &entries,
+        |task_id| delay_task_manager.contains_task(task_id),
+        now_second(),
+        conf.delay_task_index_compact_interval_sec,
+    );
+
+    if stale.count == 0
+        || (stale.count < conf.delay_task_index_compact_min_stale_records
+            && stale.oldest_age < conf.delay_task_index_compact_max_stale_age_sec)
+    {
+        debug!(
+            "Delay task index compaction not needed: records={}, stale={}, oldest_stale_age={}s",
+            entries.len(),
+            stale.count,
+            stale.oldest_age
+        );
+        return Ok(());
+    }
+
+    for (shard, offsets) in stale.offsets.iter() {
+        delay_task_manager
+            .storage_driver_manager
+            .delete_by_shard_offsets(DEFAULT_TENANT, DELAY_TASK_INDEX_TOPIC, shard, offsets)
+            .await?;
+    }
+
+    info!(
+        "Delay task index compacted: removed {} of {} records",
+        stale.count,
+        entries.len()
+    );
+    Ok(())
+}
+
+pub(crate) async fn start_index_compaction(delay_task_manager: Arc<DelayTaskManager>) {
+    let interval_sec = broker_config()
+        .delay_task
+        .delay_task_index_compact_interval_sec;
+    if interval_sec == 0 {
+        info!("Delay task index compaction is disabled");
+        return;
+    }
+
+    let stop_send = delay_task_manager.background_stop.clone();
+    let manager = &delay_task_manager;
+    let ac_fn = async || -> Result<(), CommonError> {
+        if let Err(e) = compact_delay_task_index(manager).await {
+            warn!("Delay task index compaction failed: {}", e);
+        }
+        Ok(())
+    };
+    loop_select_ticket(ac_fn, interval_sec * 1000, &stop_send).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(offset: u64, task_id: &str, target: u64) -> IndexEntry {
+        IndexEntry {
+            shard: "s0".to_string(),
+            offset,
+            task: Some(IndexTask {
+                task_id: task_id.to_string(),
+                delay_target_time: target,
+                create_time: 0,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_find_stale_records() {
+        let now = 10_000;
+        let entries = vec![
+            // Recurring task rescheduled twice, only the last record is live.
+            entry(0, "recurring", 1_000),
+            entry(1, "recurring", 2_000),
+            entry(2, "recurring", 20_000),
+            // Executed long ago, no longer scheduled.
+            entry(3, "done", 5_000),
+            // Due just now and not in the queue: may be waiting for a handler.
+            entry(4, "running", 9_990),
+            // Pending in the queue.
+            entry(5, "pending", 1_000),
+            IndexEntry {
+                shard: "s0".to_string(),
+                offset: 6,
+                task: None,
+            },
+        ];
+
+        let stale = find_stale_records(&entries, |id| id == "pending", now, 60);
+        assert_eq!(stale.offsets.get("s0"), Some(&vec![0, 1, 3, 6]));
+        assert_eq!(stale.count, 4);
+        assert_eq!(stale.oldest_age, 9_000);
+    }
+
+    #[test]
+    fn test_find_stale_records_empty() {
+        let entries = vec![entry(0, "a", 20_000), entry(1, "b", 20_000)];
+        let stale = find_stale_records(&entries, |_| false, 10_000, 60);
+        assert_eq!(stale, StaleIndex::default());
+    }
+}
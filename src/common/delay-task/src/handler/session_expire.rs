@@ -14,12 +14,12 @@
 
 use broker_core::cache::NodeCacheManager;
 use common_base::utils::serialize;
-use common_base::{error::common::CommonError, tools::now_second};
+use common_base::{error::common::CommonError, tools::now_millis};
 use metadata_struct::mqtt::session::MqttSession;
 use node_call::{NodeCallData, NodeCallManager, UpdateCacheData};
 use protocol::broker::broker::{BrokerUpdateCacheActionType, BrokerUpdateCacheResourceType};
 use rocksdb_engine::{
-    keys::meta::storage_key_mqtt_session,
+    keys::meta::{storage_key_mqtt_last_will_claim, storage_key_mqtt_session},
     rocksdb::RocksDBEngine,
     storage::meta_data::{engine_delete_by_meta_data, engine_get_by_meta_data},
 };
@@ -47,22 +47,29 @@ pub async fn handle_session_expire(
             let key = storage_key_mqtt_session(tenant, client_id);
             engine_delete_by_meta_data(rocksdb_engine_handler, &key)?;
         }
+        // The claim of a will dispatched below is recorded again by the
+        // brokers, the next session of the client starts above its epoch.
+        engine_delete_by_meta_data(
+            rocksdb_engine_handler,
+            &storage_key_mqtt_last_will_claim(tenant, client_id),
+        )?;
 
         let data = NodeCallData::UpdateCache(UpdateCacheData {
             action_type: BrokerUpdateCacheActionType::Delete,
             resource_type: BrokerUpdateCacheResourceType::Session,
             data: serialize::serialize(&session)
                 .map_err(|e| CommonError::CommonError(e.to_string()))?,
+            version: 0,
         });
         node_call_manager.send(data).await?;
 
         if let Some(delay_interval) = session.last_will_delay_interval {
-            let delay_target_time = now_second() + delay_interval;
+            let delay_target_time_ms = now_millis() as u64 + delay_interval * 1000;
             delay_task_manager
                 .create_task(DelayTask::build_persistent(
                     client_id.to_string(),
                     DelayTaskData::MQTTLastwillExpire(tenant.to_string(), client_id.to_string()),
-                    delay_target_time,
+                    delay_target_time_ms,
                 ))
                 .await?;
         } else if session.is_contain_last_will {
@@ -12,19 +12,29 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod compact;
 pub mod delay;
 pub mod handler;
 pub mod manager;
 pub mod pop;
 pub mod recover;
+pub mod schedule;
+pub mod scheduler;
 
+use crate::compact::start_index_compaction;
 use crate::manager::DelayTaskManager;
 use crate::pop::spawn_delay_task_pop_threads;
 use crate::recover::recover_delay_queue;
+use crate::schedule::TaskSchedule;
+use crate::scheduler::DelayTaskPriority;
 use broker_core::cache::NodeCacheManager;
-use common_base::tools::now_second;
+use common_base::tools::now_millis;
+use common_base::utils::serialize::deserialize;
 use common_base::uuid::unique_id;
-use common_base::{error::common::CommonError, task::TaskSupervisor};
+use common_base::{
+    error::common::CommonError,
+    task::{TaskKind, TaskSupervisor},
+};
 use node_call::NodeCallManager;
 use rocksdb_engine::rocksdb::RocksDBEngine;
 use serde::{Deserialize, Serialize};
@@ -43,49 +53,168 @@ pub fn task_type_name(&self) -> &'static str {
             DelayTaskData::MQTTLastwillExpire(_, _) => "MQTTLastwillExpire",
         }
     }
+
+    /// Lane the task waits in once expired. Session expiry frees resources
+    /// and goes ahead of last-will delivery.
+    pub fn priority(&self) -> DelayTaskPriority {
+        match self {
+            DelayTaskData::MQTTSessionExpire(_, _) => DelayTaskPriority::High,
+            DelayTaskData::MQTTLastwillExpire(_, _) => DelayTaskPriority::Normal,
+        }
+    }
 }
 
+/// Layout version written with every task, see [`DelayTask::decode`].
+const DELAY_TASK_VERSION: u32 = 3;
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DelayTask {
     pub task_id: String,
     pub data: DelayTaskData,
-    pub delay_target_time: u64,
-    pub create_time: u64,
+    /// Due time, Unix timestamp in milliseconds.
+    pub delay_target_time_ms: u64,
+    /// Unix timestamp in milliseconds.
+    pub create_time_ms: u64,
     pub persistent: bool,
+    pub schedule: TaskSchedule,
+    version: u32,
+}
+
+/// Layout of [`DelayTask`] before `schedule` was added. bincode has no field
+/// defaults, so indexes persisted by older versions are decoded through it.
+#[derive(Deserialize)]
+struct DelayTaskV1 {
+    task_id: String,
+    data: DelayTaskData,
+    delay_target_time: u64,
+    create_time: u64,
+    persistent: bool,
+}
+
+/// Layout of [`DelayTask`] before times were kept in milliseconds.
+#[derive(Deserialize)]
+struct DelayTaskV2 {
+    task_id: String,
+    data: DelayTaskData,
+    delay_target_time: u64,
+    create_time: u64,
+    persistent: bool,
+    schedule: TaskSchedule,
 }
 
 impl DelayTask {
-    pub fn build_persistent(task_id: String, data: DelayTaskData, delay_target_time: u64) -> Self {
-        DelayTask {
-            task_id,
-            data,
-            delay_target_time,
-            create_time: now_second(),
-            persistent: true,
-        }
+    /// `delay_target_time_ms` is a Unix timestamp in milliseconds.
+    pub fn build_persistent(
+        task_id: String,
+        data: DelayTaskData,
+        delay_target_time_ms: u64,
+    ) -> Self {
+        Self::build(task_id, data, delay_target_time_ms, true)
+    }
+
+    pub fn build_persistent_auto_id(data: DelayTaskData, delay_target_time_ms: u64) -> Self {
+        Self::build_persistent(unique_id(), data, delay_target_time_ms)
     }
 
-    pub fn build_persistent_auto_id(data: DelayTaskData, delay_target_time: u64) -> Self {
-        Self::build_persistent(unique_id(), data, delay_target_time)
+    /// `delay_target_time_ms` is a Unix timestamp in milliseconds.
+    pub fn build_ephemeral(
+        task_id: String,
+        data: DelayTaskData,
+        delay_target_time_ms: u64,
+    ) -> Self {
+        Self::build(task_id, data, delay_target_time_ms, false)
     }
 
-    pub fn build_ephemeral(task_id: String, data: DelayTaskData, delay_target_time: u64) -> Self {
+    pub fn build_ephemeral_auto_id(data: DelayTaskData, delay_target_time_ms: u64) -> Self {
+        Self::build_ephemeral(unique_id(), data, delay_target_time_ms)
+    }
+
+    fn build(
+        task_id: String,
+        data: DelayTaskData,
+        delay_target_time_ms: u64,
+        persistent: bool,
+    ) -> Self {
         DelayTask {
             task_id,
             data,
-            delay_target_time,
-            create_time: now_second(),
-            persistent: false,
+            delay_target_time_ms,
+            create_time_ms: now_millis() as u64,
+            persistent,
+            schedule: TaskSchedule::Once,
+            version: DELAY_TASK_VERSION,
         }
     }
 
-    pub fn build_ephemeral_auto_id(data: DelayTaskData, delay_target_time: u64) -> Self {
-        Self::build_ephemeral(unique_id(), data, delay_target_time)
+    /// Make the task recurring. `delay_target_time_ms` is the first run.
+    pub fn with_schedule(mut self, schedule: TaskSchedule) -> Self {
+        self.schedule = schedule;
+        self
     }
 
     pub fn task_type_name(&self) -> &'static str {
         self.data.task_type_name()
     }
+
+    /// The next occurrence of a recurring task, run at `now_ms`. Runs missed
+    /// while the task was late are skipped rather than fired back to back.
+    pub fn next_run(&self, now_ms: u64) -> Option<DelayTask> {
+        let mut target = self.schedule.next_after_ms(self.delay_target_time_ms)?;
+        if target <= now_ms {
+            target = self.schedule.next_after_ms(now_ms)?;
+        }
+        Some(DelayTask {
+            delay_target_time_ms: target,
+            ..self.clone()
+        })
+    }
+
+    /// Decode a persisted task. Each layout is a prefix of the next one, so a
+    /// newer layout never decodes from older bytes; tasks persisted before
+    /// millisecond precision have their times converted.
+    pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
+        if let Ok(task) = deserialize::<DelayTask>(data) {
+            return Ok(task);
+        }
+        if let Ok(task) = deserialize::<DelayTaskV2>(data) {
+            return Ok(Self::from_seconds(
+                task.task_id,
+                task.data,
+                task.delay_target_time,
+                task.create_time,
+                task.persistent,
+                task.schedule,
+            ));
+        }
+        let task = deserialize::<DelayTaskV1>(data)?;
+        Ok(Self::from_seconds(
+            task.task_id,
+            task.data,
+            task.delay_target_time,
+            task.create_time,
+            task.persistent,
+            TaskSchedule::Once,
+        ))
+    }
+
+    fn from_seconds(
+        task_id: String,
+        data: DelayTaskData,
+        delay_target_time: u64,
+        create_time: u64,
+        persistent: bool,
+        schedule: TaskSchedule,
+    ) -> Self {
+        DelayTask {
+            task_id,
+            data,
+            delay_target_time_ms: delay_target_time.saturating_mul(1000),
+            create_time_ms: create_time.saturating_mul(1000),
+            persistent,
+            schedule,
+            version: DELAY_TASK_VERSION,
+        }
+    }
 }
 
 pub async fn start_delay_task_manager_thread(
@@ -105,18 +234,98 @@ pub async fn start_delay_task_manager_thread(
     );
 
     let recover_manager = delay_task_manager.clone();
-    let recover_engine = rocksdb_engine_handler.clone();
-    let node_call_manager = node_call_manager.clone();
-    let broker_cache = broker_cache.clone();
     tokio::spawn(async move {
-        recover_delay_queue(
-            &recover_engine,
-            &recover_manager,
-            &node_call_manager,
-            &broker_cache,
-        )
-        .await;
+        recover_delay_queue(&recover_manager).await;
+    });
+
+    let compact_manager = delay_task_manager.clone();
+    task_supervisor.spawn(TaskKind::DelayTaskIndexCompact.to_string(), async move {
+        start_index_compaction(compact_manager).await;
     });
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common_base::utils::serialize::serialize;
+
+    fn session_expire() -> DelayTaskData {
+        DelayTaskData::MQTTSessionExpire("t1".to_string(), "c1".to_string())
+    }
+
+    #[derive(Serialize)]
+    struct PersistedV1 {
+        task_id: String,
+        data: DelayTaskData,
+        delay_target_time: u64,
+        create_time: u64,
+        persistent: bool,
+    }
+
+    #[derive(Serialize)]
+    struct PersistedV2 {
+        task_id: String,
+        data: DelayTaskData,
+        delay_target_time: u64,
+        create_time: u64,
+        persistent: bool,
+        schedule: TaskSchedule,
+    }
+
+    #[test]
+    fn test_decode_current_layout() {
+        let task =
+            DelayTask::build_persistent("t".to_string(), session_expire(), 1_700_000_000_250)
+                .with_schedule(TaskSchedule::Interval(30));
+        let decoded = DelayTask::decode(&serialize(&task).unwrap()).unwrap();
+        assert_eq!(decoded.delay_target_time_ms, 1_700_000_000_250);
+        assert_eq!(decoded.create_time_ms, task.create_time_ms);
+        assert_eq!(decoded.schedule, TaskSchedule::Interval(30));
+    }
+
+    #[test]
+    fn test_decode_second_layouts() {
+        let v2 = PersistedV2 {
+            task_id: "t".to_string(),
+            data: session_expire(),
+            delay_target_time: 1_700_000_010,
+            create_time: 1_700_000_000,
+            persistent: true,
+            schedule: TaskSchedule::Cron("0 * * * *".to_string()),
+        };
+        let decoded = DelayTask::decode(&serialize(&v2).unwrap()).unwrap();
+        assert_eq!(decoded.delay_target_time_ms, 1_700_000_010_000);
+        assert_eq!(decoded.create_time_ms, 1_700_000_000_000);
+        assert_eq!(
+            decoded.schedule,
+            TaskSchedule::Cron("0 * * * *".to_string())
+        );
+
+        let v1 = PersistedV1 {
+            task_id: "t".to_string(),
+            data: session_expire(),
+            delay_target_time: 1_700_000_010,
+            create_time: 1_700_000_000,
+            persistent: true,
+        };
+        let decoded = DelayTask::decode(&serialize(&v1).unwrap()).unwrap();
+        assert_eq!(decoded.delay_target_time_ms, 1_700_000_010_000);
+        assert_eq!(decoded.schedule, TaskSchedule::Once);
+    }
+
+    #[test]
+    fn test_next_run_keeps_millisecond_offset() {
+        let task = DelayTask::build_ephemeral("t".to_string(), session_expire(), 10_500)
+            .with_schedule(TaskSchedule::Interval(2));
+        assert_eq!(task.next_run(11_000).unwrap().delay_target_time_ms, 12_500);
+        // Late by more than one interval: skip to the next run after now.
+        assert_eq!(task.next_run(15_000).unwrap().delay_target_time_ms, 17_000);
+        assert!(
+            DelayTask::build_ephemeral("t".to_string(), session_expire(), 0)
+                .next_run(0)
+                .is_none()
+        );
+    }
+}
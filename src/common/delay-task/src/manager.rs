@@ -13,13 +13,19 @@
 // limitations under the License.
 
 use crate::delay::{delete_delay_task_index, save_delay_task_index};
+use crate::scheduler::DelayTaskScheduler;
 use crate::DelayTask;
 use common_base::error::common::CommonError;
-use common_base::tools::now_second;
+use common_base::tools::now_millis;
 use common_metrics::mqtt::delay_task::record_delay_task_created;
 use dashmap::DashMap;
 use grpc_clients::pool::ClientPool;
-use std::sync::{atomic::AtomicU32, Arc};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 use storage_adapter::driver::StorageDriverManager;
 use tokio::sync::{broadcast, mpsc, oneshot, Semaphore};
@@ -38,6 +44,32 @@ pub(crate) enum ShardCmd {
 /// Sender half kept in the manager; pop thread owns the receiver.
 pub(crate) type ShardCmdTx = mpsc::UnboundedSender<ShardCmd>;
 
+const DEFAULT_STARVATION_TIMEOUT_MS: u64 = 5000;
+
+/// A task waiting in a shard's queue.
+#[derive(Clone)]
+pub(crate) struct PendingTask {
+    key: delay_queue::Key,
+    persistent: bool,
+    task_type: &'static str,
+    delay_target_time_ms: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct DelayTaskShardInfo {
+    pub shard_no: u32,
+    pub pending_num: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PendingDelayTaskInfo {
+    pub task_id: String,
+    pub task_type: String,
+    pub shard_no: u32,
+    pub delay_target_time_ms: u64,
+    pub persistent: bool,
+}
+
 #[derive(Clone)]
 pub struct DelayTaskManager {
     pub client_pool: Arc<ClientPool>,
@@ -47,9 +79,18 @@ pub struct DelayTaskManager {
     pub delay_queue_pop_thread: DashMap<u32, broadcast::Sender<bool>>,
     pub delay_queue_num: u32,
     pub handler_semaphore: Arc<Semaphore>,
-    incr_no: Arc<AtomicU32>,
-    /// task_id → (shard_no, queue key, persistent).
-    task_key_map: DashMap<String, (u32, delay_queue::Key, bool)>,
+    /// Expired tasks waiting for a handler slot, by priority lane.
+    pub scheduler: Arc<DelayTaskScheduler>,
+    /// Stops the dispatcher and the index compaction job.
+    pub(crate) background_stop: broadcast::Sender<bool>,
+    /// Set once the persistent index has been replayed into the queue.
+    recovered: Arc<AtomicBool>,
+    /// Pending tasks of each shard, indexed by shard_no. A task always lands
+    /// on shard `shard_of(task_id)`.
+    shard_tasks: Arc<Vec<DashMap<String, PendingTask>>>,
+    /// task_id → latest scheduled occurrence of each active recurring task.
+    /// A task removed from here is not re-enqueued after its current run.
+    recurring_tasks: DashMap<String, DelayTask>,
 }
 
 impl DelayTaskManager {
@@ -64,24 +105,69 @@ pub fn new(
             storage_driver_manager,
             shard_cmd_tx: DashMap::with_capacity(8),
             delay_queue_pop_thread: DashMap::with_capacity(8),
-            incr_no: Arc::new(AtomicU32::new(0)),
             delay_queue_num,
             handler_semaphore: Arc::new(Semaphore::new(max_handler_concurrency)),
-            task_key_map: DashMap::new(),
+            scheduler: Arc::new(DelayTaskScheduler::new(
+                HashMap::new(),
+                Duration::from_millis(DEFAULT_STARVATION_TIMEOUT_MS),
+            )),
+            background_stop: broadcast::channel(2).0,
+            recovered: Arc::new(AtomicBool::new(false)),
+            shard_tasks: Arc::new(
+                (0..delay_queue_num.max(1))
+                    .map(|_| DashMap::new())
+                    .collect(),
+            ),
+            recurring_tasks: DashMap::new(),
         }
     }
 
+    /// Per-task-type concurrency quotas (task type name → max running) and
+    /// the wait after which a low priority task is dispatched ahead of higher
+    /// priority ones. Must be set before the pop threads start.
+    pub fn with_scheduling(
+        mut self,
+        type_concurrency: HashMap<String, usize>,
+        starvation_timeout_ms: u64,
+    ) -> Self {
+        self.scheduler = Arc::new(DelayTaskScheduler::new(
+            type_concurrency,
+            Duration::from_millis(starvation_timeout_ms),
+        ));
+        self
+    }
+
     /// Called by pop.rs to register the command-channel sender for a shard.
     pub(crate) fn register_shard_cmd_tx(&self, shard_no: u32, tx: ShardCmdTx) {
         self.shard_cmd_tx.insert(shard_no, tx);
     }
 
-    pub(crate) fn remove_task_key(&self, task_id: &str) {
-        self.task_key_map.remove(task_id);
+    /// Forget the queue key of an expired task, unless the task id has been
+    /// re-created with a new key in the meantime.
+    pub(crate) fn remove_task_key_if(&self, task_id: &str, shard_no: u32, key: delay_queue::Key) {
+        if let Some(tasks) = self.shard_tasks.get(shard_no as usize) {
+            tasks.remove_if(task_id, |_, pending| pending.key == key);
+        }
+    }
+
+    /// Shard a task is placed on, derived from its id alone.
+    pub fn shard_of(&self, task_id: &str) -> u32 {
+        shard_of(task_id, self.shard_tasks.len() as u32)
+    }
+
+    fn pending_tasks(&self, task_id: &str) -> &DashMap<String, PendingTask> {
+        &self.shard_tasks[self.shard_of(task_id) as usize]
+    }
+
+    /// Hand an expired task to the scheduler to wait for a handler slot.
+    pub(crate) fn dispatch_task(&self, task: DelayTask) {
+        self.scheduler.push(task);
     }
 
     pub async fn create_task(&self, task: DelayTask) -> Result<String, CommonError> {
-        if self.task_key_map.contains_key(&task.task_id) {
+        task.schedule.validate()?;
+
+        if self.contains_task(&task.task_id) {
             self.delete_task(&task.task_id).await?;
             debug!(
                 "Replaced existing delay task: task_id={}, task_type={}",
@@ -101,8 +187,10 @@ pub async fn create_task(&self, task: DelayTask) -> Result<String, CommonError>
     }
 
     pub async fn delete_task(&self, task_id: &str) -> Result<(), CommonError> {
-        let entry = match self.task_key_map.remove(task_id) {
-            Some(e) => e,
+        self.recurring_tasks.remove(task_id);
+        let shard_no = self.shard_of(task_id);
+        let pending = match self.pending_tasks(task_id).remove(task_id) {
+            Some((_, pending)) => pending,
             None => {
                 warn!(
                     "Delay task not found when deleting, may have already been executed: task_id={}",
@@ -111,7 +199,6 @@ pub async fn delete_task(&self, task_id: &str) -> Result<(), CommonError> {
                 return Ok(());
             }
         };
-        let (_, (shard_no, key, persistent)) = entry;
 
         let tx = self
             .shard_cmd_tx
@@ -125,14 +212,14 @@ pub async fn delete_task(&self, task_id: &str) -> Result<(), CommonError> {
             })?;
 
         let (done_tx, done_rx) = oneshot::channel();
-        tx.send(ShardCmd::Delete(key, done_tx))
+        tx.send(ShardCmd::Delete(pending.key, done_tx))
             .map_err(|e| CommonError::CommonError(format!("shard cmd send error: {}", e)))?;
 
         // Wait until pop thread has actually removed the entry from the DelayQueue.
         // This prevents a race where delete completes but the task still fires.
         let _ = done_rx.await;
 
-        if persistent {
+        if pending.persistent {
             delete_delay_task_index(&self.storage_driver_manager, task_id).await?;
         }
 
@@ -146,18 +233,16 @@ pub async fn stop(&self) -> Result<(), CommonError> {
                 stop_send.send(true)?;
             }
         }
+        let _ = self.background_stop.send(true);
         tokio::time::sleep(Duration::from_millis(100)).await;
         Ok(())
     }
 
     /// Sends Insert command to the shard's channel and awaits the queue Key reply.
     /// Returns only after the pop thread has inserted the task and the key is
-    /// recorded in task_key_map, so a subsequent delete_task will never miss it.
+    /// recorded in its shard's key map, so a subsequent delete_task will never miss it.
     pub(crate) async fn enqueue_task(&self, task: &DelayTask) {
-        let shard_no = self
-            .incr_no
-            .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
-            % self.delay_queue_num;
+        let shard_no = self.shard_of(&task.task_id);
 
         let tx = if let Some(t) = self.shard_cmd_tx.get(&shard_no) {
             t.clone()
@@ -172,25 +257,23 @@ pub(crate) async fn enqueue_task(&self, task: &DelayTask) {
             return;
         };
 
-        let current_time = now_second();
-        let delay_duration = if task.delay_target_time > current_time {
-            Duration::from_secs(task.delay_target_time - current_time)
-        } else {
-            Duration::from_secs(0)
-        };
+        let current_time_ms = now_millis() as u64;
+        let delay_duration = delay_until(task.delay_target_time_ms, current_time_ms);
         let target_instant = Instant::now() + delay_duration;
 
         debug!(
             "Enqueue delay task. task_id={}, task_type={}, shard_no={}, \
-            delay_target_time={}, current_time={}, delay_duration={}s",
+            delay_target_time_ms={}, current_time_ms={}, delay_duration={}ms",
             task.task_id,
             task.task_type_name(),
             shard_no,
-            task.delay_target_time,
-            current_time,
-            delay_duration.as_secs(),
+            task.delay_target_time_ms,
+            current_time_ms,
+            delay_duration.as_millis(),
         );
 
+        self.register_recurring_task(task);
+
         let (key_tx, key_rx) = oneshot::channel();
         if let Err(e) = tx.send(ShardCmd::Insert(task.clone(), target_instant, key_tx)) {
             error!(
@@ -202,8 +285,15 @@ pub(crate) async fn enqueue_task(&self, task: &DelayTask) {
 
         match key_rx.await {
             Ok(key) => {
-                self.task_key_map
-                    .insert(task.task_id.clone(), (shard_no, key, task.persistent));
+                self.shard_tasks[shard_no as usize].insert(
+                    task.task_id.clone(),
+                    PendingTask {
+                        key,
+                        persistent: task.persistent,
+                        task_type: task.task_type_name(),
+                        delay_target_time_ms: task.delay_target_time_ms,
+                    },
+                );
             }
             Err(_) => {
                 error!(
@@ -214,11 +304,126 @@ pub(crate) async fn enqueue_task(&self, task: &DelayTask) {
         }
     }
 
+    pub(crate) fn set_recovered(&self) {
+        self.recovered.store(true, Ordering::Release);
+    }
+
+    pub fn is_recovered(&self) -> bool {
+        self.recovered.load(Ordering::Acquire)
+    }
+
     pub fn contains_task(&self, task_id: &str) -> bool {
-        self.task_key_map.contains_key(task_id)
+        self.pending_tasks(task_id).contains_key(task_id)
+    }
+
+    /// Number of tasks waiting in each shard.
+    pub fn describe_shards(&self) -> Vec<DelayTaskShardInfo> {
+        self.shard_tasks
+            .iter()
+            .enumerate()
+            .map(|(shard_no, tasks)| DelayTaskShardInfo {
+                shard_no: shard_no as u32,
+                pending_num: tasks.len(),
+            })
+            .collect()
+    }
+
+    /// Tasks waiting in `shard_no`, soonest first. None if there is no such shard.
+    pub fn list_shard_tasks(&self, shard_no: u32) -> Option<Vec<PendingDelayTaskInfo>> {
+        let tasks = self.shard_tasks.get(shard_no as usize)?;
+        let mut list: Vec<PendingDelayTaskInfo> = tasks
+            .iter()
+            .map(|entry| PendingDelayTaskInfo {
+                task_id: entry.key().clone(),
+                task_type: entry.task_type.to_string(),
+                shard_no,
+                delay_target_time_ms: entry.delay_target_time_ms,
+                persistent: entry.persistent,
+            })
+            .collect();
+        list.sort_by(|a, b| {
+            (a.delay_target_time_ms, &a.task_id).cmp(&(b.delay_target_time_ms, &b.task_id))
+        });
+        Some(list)
+    }
+
+    /// Active recurring tasks, each with its next scheduled run.
+    pub fn list_recurring_tasks(&self) -> Vec<DelayTask> {
+        let mut tasks: Vec<DelayTask> = self
+            .recurring_tasks
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        tasks.sort_by(|a, b| a.task_id.cmp(&b.task_id));
+        tasks
+    }
+
+    /// Stop a recurring task: its pending run is removed and a run already in
+    /// progress is not rescheduled. Returns false if no such task exists.
+    pub async fn cancel_recurring_task(&self, task_id: &str) -> Result<bool, CommonError> {
+        if !self.recurring_tasks.contains_key(task_id) {
+            return Ok(false);
+        }
+        self.delete_task(task_id).await?;
+        Ok(true)
+    }
+
+    pub(crate) fn register_recurring_task(&self, task: &DelayTask) {
+        if task.schedule.is_recurring() {
+            self.recurring_tasks
+                .insert(task.task_id.clone(), task.clone());
+        }
+    }
+
+    pub(crate) fn is_recurring_task_active(&self, task_id: &str) -> bool {
+        self.recurring_tasks.contains_key(task_id)
     }
 
     pub fn add_delay_queue_pop_thread(&self, shard_no: u32, stop_send: broadcast::Sender<bool>) {
         self.delay_queue_pop_thread.insert(shard_no, stop_send);
     }
 }
+
+fn shard_of(task_id: &str, shard_num: u32) -> u32 {
+    let mut hasher = DefaultHasher::new();
+    task_id.hash(&mut hasher);
+    (hasher.finish() % shard_num.max(1) as u64) as u32
+}
+
+/// Time left until `target_ms`, zero for a task that is already due.
+pub(crate) fn delay_until(target_ms: u64, now_ms: u64) -> Duration {
+    Duration::from_millis(target_ms.saturating_sub(now_ms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_until_boundaries() {
+        assert_eq!(delay_until(10_000, 10_000), Duration::ZERO);
+        assert_eq!(delay_until(9_999, 10_000), Duration::ZERO);
+        assert_eq!(delay_until(10_001, 10_000), Duration::from_millis(1));
+        // A 1s expiry set 400ms into a second waits the full second.
+        assert_eq!(delay_until(11_400, 10_400), Duration::from_secs(1));
+        assert_eq!(delay_until(10_250, 10_000), Duration::from_millis(250));
+    }
+
+    #[test]
+    fn test_shard_of_is_stable_and_in_range() {
+        for i in 0..1000 {
+            let task_id = format!("client-{}", i);
+            let shard_no = shard_of(&task_id, 8);
+            assert!(shard_no < 8);
+            assert_eq!(shard_no, shard_of(&task_id, 8));
+        }
+        assert_eq!(shard_of("client-1", 1), 0);
+        assert_eq!(shard_of("client-1", 0), 0);
+
+        let mut used = std::collections::HashSet::new();
+        for i in 0..1000 {
+            used.insert(shard_of(&format!("client-{}", i), 8));
+        }
+        assert_eq!(used.len(), 8);
+    }
+}
@@ -20,15 +20,16 @@
 use broker_core::cache::NodeCacheManager;
 use common_base::error::common::CommonError;
 use common_base::task::{TaskKind, TaskSupervisor};
-use common_base::tools::now_second;
+use common_base::tools::now_millis;
 use common_metrics::mqtt::delay_task::{
-    record_delay_task_execute_failed, record_delay_task_executed,
-    record_delay_task_schedule_latency,
+    record_delay_task_execute_duration, record_delay_task_execute_failed,
+    record_delay_task_executed, record_delay_task_queue_wait, record_delay_task_schedule_latency,
 };
 use futures::StreamExt;
 use node_call::NodeCallManager;
 use rocksdb_engine::rocksdb::RocksDBEngine;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::{broadcast, mpsc};
 use tokio::{select, sync::broadcast as bc};
 use tokio_util::time::DelayQueue;
@@ -54,41 +55,39 @@ pub(crate) fn spawn_delay_task_pop_threads(
         let (stop_send, _) = broadcast::channel(2);
         delay_task_manager.add_delay_queue_pop_thread(shard_no, stop_send.clone());
 
-        let raw_rocksdb_engine_handler = rocksdb_engine_handler.clone();
-        let raw_node_call_manager = node_call_manager.clone();
-        let raw_broker_cache = broker_cache.clone();
-
         task_supervisor.spawn(
             format!("{}_{}", TaskKind::DelayTaskPop, shard_no),
             async move {
-                run_shard_loop(
-                    shard_no,
-                    rx,
-                    stop_send,
-                    manager,
-                    raw_rocksdb_engine_handler,
-                    raw_node_call_manager,
-                    raw_broker_cache,
-                )
-                .await;
+                run_shard_loop(shard_no, rx, stop_send, manager).await;
             },
         );
     }
+
+    let manager = delay_task_manager.clone();
+    let raw_rocksdb_engine_handler = rocksdb_engine_handler.clone();
+    let raw_node_call_manager = node_call_manager.clone();
+    let raw_broker_cache = broker_cache.clone();
+    task_supervisor.spawn(TaskKind::DelayTaskDispatch.to_string(), async move {
+        run_dispatch_loop(
+            manager,
+            raw_rocksdb_engine_handler,
+            raw_node_call_manager,
+            raw_broker_cache,
+        )
+        .await;
+    });
 }
 
 /// Per-shard event loop.
 /// Owns the DelayQueue exclusively — no Mutex needed.
 /// Uses select! to react to either:
 ///   - a command from the manager (Insert / Delete)
-///   - a task expiring in the DelayQueue
+///   - a task expiring in the DelayQueue, which is handed to the scheduler
 async fn run_shard_loop(
     shard_no: u32,
     mut rx: mpsc::UnboundedReceiver<ShardCmd>,
     stop_send: bc::Sender<bool>,
     manager: Arc<DelayTaskManager>,
-    rocksdb_engine_handler: Arc<RocksDBEngine>,
-    node_call_manager: Arc<NodeCallManager>,
-    broker_cache: Arc<NodeCacheManager>,
 ) {
     let mut delay_queue: DelayQueue<DelayTask> = DelayQueue::new();
     let mut stop_recv = stop_send.subscribe();
@@ -115,7 +114,7 @@ async fn run_shard_loop(
                 match cmd {
                     Some(ShardCmd::Insert(task, target_instant, key_tx)) => {
                         let key = delay_queue.insert_at(task.clone(), target_instant);
-                        // Reply with the key so manager can record it in task_key_map.
+                        // Reply with the key so manager can record it in the shard key map.
                         let _ = key_tx.send(key);
                     }
                     Some(ShardCmd::Delete(key, done_tx)) => {
@@ -132,70 +131,85 @@ async fn run_shard_loop(
 
             // Expired task
             Some(expired) = delay_queue.next() => {
+                // The key is no longer valid in the queue; forget it before the
+                // task waits for a handler so a delete cannot target it.
+                let key = expired.key();
                 let task = expired.into_inner();
-                spawn_task_process(
-                    rocksdb_engine_handler.clone(),
-                    manager.clone(),
-                    node_call_manager.clone(),
-                    broker_cache.clone(),
-                    task,
-                )
-                .await;
+                manager.remove_task_key_if(&task.task_id, shard_no, key);
+                manager.dispatch_task(task);
             }
         }
     }
 }
 
-pub(crate) async fn spawn_task_process(
+/// Takes expired tasks from the scheduler as handler slots free up and runs
+/// them. A slot is the global handler permit plus the task type quota, both
+/// released when the handler returns.
+async fn run_dispatch_loop(
+    manager: Arc<DelayTaskManager>,
     rocksdb_engine_handler: Arc<RocksDBEngine>,
-    delay_task_manager: Arc<DelayTaskManager>,
     node_call_manager: Arc<NodeCallManager>,
     broker_cache: Arc<NodeCacheManager>,
-    task: DelayTask,
 ) {
-    let permit = match delay_task_manager
-        .handler_semaphore
-        .clone()
-        .acquire_owned()
-        .await
-    {
-        Ok(permit) => permit,
-        Err(e) => {
-            error!(
-                "Failed to acquire delay task handler permit: task_id={}, error={}",
-                task.task_id, e
-            );
-            return;
-        }
-    };
+    let mut stop_recv = manager.background_stop.subscribe();
 
-    tokio::spawn(async move {
-        let _permit = permit;
+    loop {
+        let permit = select! {
+            _ = stop_recv.recv() => break,
+            permit = manager.handler_semaphore.clone().acquire_owned() => match permit {
+                Ok(permit) => permit,
+                Err(e) => {
+                    error!("Delay task handler semaphore closed, stopping dispatcher: {}", e);
+                    break;
+                }
+            },
+        };
+
+        let scheduled = select! {
+            _ = stop_recv.recv() => break,
+            scheduled = manager.scheduler.next() => scheduled,
+        };
+
+        let task = scheduled.task;
         let task_type_str = task.task_type_name();
-        if let Err(e) = delay_task_process(
-            &delay_task_manager,
-            &node_call_manager,
-            &rocksdb_engine_handler,
-            &broker_cache,
-            &task,
-        )
-        .await
-        {
-            record_delay_task_execute_failed(task_type_str);
-            let err_str = e.to_string();
-            if err_str.contains("channel closed") || err_str.contains("channel full") {
-                warn!(
-                    "Delay task skipped (broker shutting down): task_id={}, task_type={}, error={}",
-                    task.task_id, task_type_str, e
-                );
-            } else {
-                error!(
-                    "Failed to process delay task: task_id={}, task_type={}, error={}",
-                    task.task_id, task_type_str, e
-                );
+        record_delay_task_queue_wait(task_type_str, scheduled.wait.as_millis() as f64);
+
+        let manager = manager.clone();
+        let rocksdb_engine_handler = rocksdb_engine_handler.clone();
+        let node_call_manager = node_call_manager.clone();
+        let broker_cache = broker_cache.clone();
+        tokio::spawn(async move {
+            let _permit = permit;
+            let start = Instant::now();
+            let result = delay_task_process(
+                &manager,
+                &node_call_manager,
+                &rocksdb_engine_handler,
+                &broker_cache,
+                &task,
+            )
+            .await;
+            record_delay_task_execute_duration(task_type_str, start.elapsed().as_millis() as f64);
+            manager.scheduler.finish(task_type_str);
+
+            if let Err(e) = result {
+                record_delay_task_execute_failed(task_type_str);
+                let err_str = e.to_string();
+                if err_str.contains("channel closed") || err_str.contains("channel full") {
+                    warn!(
+                        "Delay task skipped (broker shutting down): task_id={}, task_type={}, error={}",
+                        task.task_id, task_type_str, e
+                    );
+                } else {
+                    error!(
+                        "Failed to process delay task: task_id={}, task_type={}, error={}",
+                        task.task_id, task_type_str, e
+                    );
+                }
             }
-        }
-    });
+        });
+    }
+    info!("Delay task dispatcher stopped");
 }
 
 pub async fn delay_task_process(
@@ -211,12 +225,10 @@ pub async fn delay_task_process(
         task.task_id, task_type_str
     );
 
-    delay_task_manager.remove_task_key(&task.task_id);
-
-    let latency_s = now_second().saturating_sub(task.delay_target_time) as f64;
+    let latency_s = (now_millis() as u64).saturating_sub(task.delay_target_time_ms) as f64 / 1000.0;
     record_delay_task_schedule_latency(task_type_str, latency_s);
 
-    match &task.data {
+    let result = match &task.data {
         DelayTaskData::MQTTSessionExpire(tenant, client_id) => {
             handle_session_expire(
                 node_call_manager,
@@ -226,17 +238,54 @@ pub async fn delay_task_process(
                 tenant,
                 client_id,
             )
-            .await?;
+            .await
         }
         DelayTaskData::MQTTLastwillExpire(tenant, client_id) => {
-            handle_lastwill_expire(node_call_manager, tenant, client_id).await?;
+            handle_lastwill_expire(node_call_manager, tenant, client_id).await
         }
-    }
+    };
 
     if task.persistent {
         delete_delay_task_index(&delay_task_manager.storage_driver_manager, &task.task_id).await?;
     }
 
+    // A failed run does not stop a recurring task; the next one is still scheduled.
+    if task.schedule.is_recurring() {
+        reschedule_recurring_task(delay_task_manager, task).await?;
+    }
+
+    result?;
     record_delay_task_executed(task_type_str);
     Ok(())
 }
+
+async fn reschedule_recurring_task(
+    delay_task_manager: &Arc<DelayTaskManager>,
+    task: &DelayTask,
+) -> Result<(), CommonError> {
+    if !delay_task_manager.is_recurring_task_active(&task.task_id) {
+        debug!(
+            "Recurring delay task cancelled, not rescheduling: task_id={}",
+            task.task_id
+        );
+        return Ok(());
+    }
+
+    match task.next_run(now_millis() as u64) {
+        Some(next) => {
+            debug!(
+                "Rescheduling recurring delay task: task_id={}, next_run_ms={}",
+                next.task_id, next.delay_target_time_ms
+            );
+            delay_task_manager.create_task(next).await?;
+        }
+        None => {
+            warn!(
+                "Recurring delay task has no next run, dropping it: task_id={}, schedule={:?}",
+                task.task_id, task.schedule
+            );
+            delay_task_manager.delete_task(&task.task_id).await?;
+        }
+    }
+    Ok(())
+}
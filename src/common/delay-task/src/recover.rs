@@ -13,16 +13,11 @@
 // limitations under the License.
 
 use crate::manager::DelayTaskManager;
-use crate::pop::spawn_task_process;
 use crate::DelayTask;
-use broker_core::cache::NodeCacheManager;
 use broker_core::inner_topic::DELAY_TASK_INDEX_TOPIC;
-use common_base::tools::now_second;
-use common_base::utils::serialize;
+use common_base::tools::now_millis;
 use metadata_struct::adapter::adapter_read_config::AdapterReadConfig;
 use metadata_struct::tenant::DEFAULT_TENANT;
-use node_call::NodeCallManager;
-use rocksdb_engine::rocksdb::RocksDBEngine;
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
@@ -37,12 +32,7 @@ enum ReadBatch {
     Abort,
 }
 
-pub(crate) async fn recover_delay_queue(
-    rocksdb_engine_handler: &Arc<RocksDBEngine>,
-    delay_task_manager: &Arc<DelayTaskManager>,
-    node_call_manager: &Arc<NodeCallManager>,
-    broker_cache: &Arc<NodeCacheManager>,
-) {
+pub(crate) async fn recover_delay_queue(delay_task_manager: &Arc<DelayTaskManager>) {
     info!("Starting delay task queue recovery from persistent storage");
 
     let read_config = AdapterReadConfig {
@@ -75,15 +65,7 @@ pub(crate) async fn recover_delay_queue(
         }
 
         for record in &data {
-            match process_delay_task_record(
-                rocksdb_engine_handler,
-                delay_task_manager,
-                node_call_manager,
-                broker_cache,
-                record,
-            )
-            .await
-            {
+            match process_delay_task_record(delay_task_manager, record).await {
                 RecoverResult::Recovered => {
                     recovered += 1;
                     if recovered - last_progress_log >= PROGRESS_LOG_INTERVAL {
@@ -104,6 +86,7 @@ pub(crate) async fn recover_delay_queue(
         update_offsets_from_records(&data, &mut offsets);
     }
 
+    delay_task_manager.set_recovered();
     info!(
         "Delay task queue recovery completed. recovered: {}, expired: {}",
         recovered, expired
@@ -153,13 +136,10 @@ enum RecoverResult {
 }
 
 async fn process_delay_task_record(
-    rocksdb_engine_handler: &Arc<RocksDBEngine>,
     delay_task_manager: &Arc<DelayTaskManager>,
-    node_call_manager: &Arc<NodeCallManager>,
-    broker_cache: &Arc<NodeCacheManager>,
     record: &metadata_struct::storage::record::StorageRecord,
 ) -> RecoverResult {
-    let task = match serialize::deserialize::<DelayTask>(&record.data) {
+    let task = match DelayTask::decode(&record.data) {
         Ok(t) => t,
         Err(e) => {
             error!(
@@ -170,17 +150,9 @@ async fn process_delay_task_record(
         }
     };
 
-    let now = now_second();
-    if task.delay_target_time < now {
-        handle_expired_delay_task(
-            rocksdb_engine_handler,
-            delay_task_manager,
-            node_call_manager,
-            broker_cache,
-            task,
-            now,
-        )
-        .await;
+    let now_ms = now_millis() as u64;
+    if task.delay_target_time_ms < now_ms {
+        handle_expired_delay_task(delay_task_manager, task, now_ms);
         return RecoverResult::Expired;
     }
 
@@ -188,34 +160,24 @@ async fn process_delay_task_record(
     RecoverResult::Recovered
 }
 
-async fn handle_expired_delay_task(
-    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+fn handle_expired_delay_task(
     delay_task_manager: &Arc<DelayTaskManager>,
-    node_call_manager: &Arc<NodeCallManager>,
-    broker_cache: &Arc<NodeCacheManager>,
     task: DelayTask,
-    now: u64,
+    now_ms: u64,
 ) {
     warn!(
         "Delay task expired during recovery, executing immediately. \
-         task_id={}, task_type={}, expired by: {}s",
+         task_id={}, task_type={}, expired by: {}ms",
         task.task_id,
         task.task_type_name(),
-        now - task.delay_target_time
+        now_ms - task.delay_target_time_ms
     );
 
-    let manager = delay_task_manager.clone();
-    spawn_task_process(
-        rocksdb_engine_handler.clone(),
-        manager,
-        node_call_manager.clone(),
-        broker_cache.clone(),
-        task,
-    )
-    .await;
+    delay_task_manager.register_recurring_task(&task);
+    delay_task_manager.dispatch_task(task);
 }
 
-fn update_offsets_from_records(
+pub(crate) fn update_offsets_from_records(
     data: &[metadata_struct::storage::record::StorageRecord],
     offsets: &mut HashMap<String, u64>,
 ) {
@@ -0,0 +1,321 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Recurrence of a delay task.
+//!
+//! A recurring task is re-enqueued after each execution at the next time its
+//! schedule yields. Cron expressions use the classic five fields, evaluated in
+//! UTC with minute resolution:
+//!
+//! ```text
+//! minute hour day-of-month month day-of-week
+//! */15   *    *            *     1-5
+//! ```
+//!
+//! Each field accepts `*`, numbers, ranges (`a-b`), steps (`*/n`, `a-b/n`)
+//! and comma separated lists. Day-of-week is `0-7`, both `0` and `7` being
+//! Sunday. When both day fields are restricted, a day matches if either does.
+
+use chrono::{DateTime, Datelike, NaiveDate, Timelike};
+use common_base::error::common::CommonError;
+use serde::{Deserialize, Serialize};
+
+/// How far ahead `next_after` searches before giving up on a cron expression
+/// that never matches (e.g. `0 0 30 2 *`). Covers a full leap-year cycle.
+const CRON_SEARCH_SECS: u64 = 5 * 366 * 24 * 3600;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TaskSchedule {
+    /// Run once at `delay_target_time_ms`.
+    #[default]
+    Once,
+    /// Run every N seconds.
+    Interval(u64),
+    /// Run at the times matched by a five-field cron expression (UTC).
+    Cron(String),
+}
+
+impl TaskSchedule {
+    pub fn is_recurring(&self) -> bool {
+        !matches!(self, TaskSchedule::Once)
+    }
+
+    pub fn validate(&self) -> Result<(), CommonError> {
+        match self {
+            TaskSchedule::Once => Ok(()),
+            TaskSchedule::Interval(0) => Err(CommonError::CommonError(
+                "Delay task interval must be greater than 0".to_string(),
+            )),
+            TaskSchedule::Interval(_) => Ok(()),
+            TaskSchedule::Cron(expr) => CronExpr::parse(expr).map(|_| ()),
+        }
+    }
+
+    /// The first run time strictly after `after` (seconds), or `None` for a
+    /// one-shot task or a cron expression that never matches.
+    pub fn next_after(&self, after: u64) -> Option<u64> {
+        match self {
+            TaskSchedule::Once => None,
+            TaskSchedule::Interval(secs) => Some(after + (*secs).max(1)),
+            TaskSchedule::Cron(expr) => CronExpr::parse(expr).ok()?.next_after(after),
+        }
+    }
+
+    /// [`Self::next_after`] for task times in milliseconds. Intervals keep the
+    /// sub-second offset of `after_ms`; cron runs fall on whole minutes.
+    pub fn next_after_ms(&self, after_ms: u64) -> Option<u64> {
+        match self {
+            TaskSchedule::Once => None,
+            TaskSchedule::Interval(secs) => Some(after_ms + (*secs).max(1) * 1000),
+            TaskSchedule::Cron(_) => self.next_after(after_ms / 1000).map(|t| t * 1000),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct CronField {
+    bits: u64,
+    any: bool,
+}
+
+impl CronField {
+    fn parse(spec: &str, min: u32, max: u32) -> Result<Self, CommonError> {
+        let invalid = || CommonError::CommonError(format!("Invalid cron field '{}'", spec));
+
+        let mut bits = 0u64;
+        for part in spec.split(',') {
+            let (range, step) = match part.split_once('/') {
+                Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+                None => (part, 1),
+            };
+            if step == 0 {
+                return Err(invalid());
+            }
+
+            let (start, end) = if range == "*" {
+                (min, max)
+            } else if let Some((a, b)) = range.split_once('-') {
+                let a = a.parse::<u32>().map_err(|_| invalid())?;
+                let b = b.parse::<u32>().map_err(|_| invalid())?;
+                (a, b)
+            } else {
+                let a = range.parse::<u32>().map_err(|_| invalid())?;
+                // `5/10` means "from 5 to the end, every 10".
+                (a, if part.contains('/') { max } else { a })
+            };
+            if start < min || end > max || start > end {
+                return Err(invalid());
+            }
+
+            for value in (start..=end).step_by(step as usize) {
+                bits |= 1 << value;
+            }
+        }
+
+        Ok(CronField {
+            bits,
+            any: spec == "*",
+        })
+    }
+
+    fn contains(&self, value: u32) -> bool {
+        self.bits & (1 << value) != 0
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CronExpr {
+    minute: CronField,
+    hour: CronField,
+    day_of_month: CronField,
+    month: CronField,
+    day_of_week: CronField,
+}
+
+impl CronExpr {
+    pub fn parse(expr: &str) -> Result<Self, CommonError> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(CommonError::CommonError(format!(
+                "Cron expression '{}' must have 5 fields, got {}",
+                expr,
+                fields.len()
+            )));
+        }
+
+        let mut day_of_week = CronField::parse(fields[4], 0, 7)?;
+        if day_of_week.contains(7) {
+            day_of_week.bits |= 1;
+        }
+
+        Ok(CronExpr {
+            minute: CronField::parse(fields[0], 0, 59)?,
+            hour: CronField::parse(fields[1], 0, 23)?,
+            day_of_month: CronField::parse(fields[2], 1, 31)?,
+            month: CronField::parse(fields[3], 1, 12)?,
+            day_of_week,
+        })
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let dom = self.day_of_month.contains(date.day());
+        let dow = self
+            .day_of_week
+            .contains(date.weekday().num_days_from_sunday());
+        match (self.day_of_month.any, self.day_of_week.any) {
+            (false, false) => dom || dow,
+            _ => dom && dow,
+        }
+    }
+
+    /// The first matching minute strictly after `after` (seconds, UTC).
+    pub fn next_after(&self, after: u64) -> Option<u64> {
+        let mut t = (after / 60 + 1) * 60;
+        let limit = t + CRON_SEARCH_SECS;
+
+        while t <= limit {
+            let dt = DateTime::from_timestamp(t as i64, 0)?.naive_utc();
+            let date = dt.date();
+
+            if !self.month.contains(date.month()) {
+                let (year, month) = match date.month() {
+                    12 => (date.year() + 1, 1),
+                    m => (date.year(), m + 1),
+                };
+                t = day_start(NaiveDate::from_ymd_opt(year, month, 1)?);
+                continue;
+            }
+            if !self.day_matches(date) {
+                t = day_start(date.succ_opt()?);
+                continue;
+            }
+            if !self.hour.contains(dt.hour()) {
+                t = (t / 3600 + 1) * 3600;
+                continue;
+            }
+            if !self.minute.contains(dt.minute()) {
+                t += 60;
+                continue;
+            }
+            return Some(t);
+        }
+        None
+    }
+}
+
+fn day_start(date: NaiveDate) -> u64 {
+    date.and_hms_opt(0, 0, 0)
+        .map(|dt| dt.and_utc().timestamp() as u64)
+        .unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> u64 {
+        NaiveDate::from_ymd_opt(y, mo, d)
+            .unwrap()
+            .and_hms_opt(h, mi, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp() as u64
+    }
+
+    #[test]
+    fn test_cron_next_after() {
+        let every_15 = CronExpr::parse("*/15 * * * *").unwrap();
+        assert_eq!(
+            every_15.next_after(ts(2024, 1, 1, 10, 7)),
+            Some(ts(2024, 1, 1, 10, 15))
+        );
+        assert_eq!(
+            every_15.next_after(ts(2024, 1, 1, 10, 15)),
+            Some(ts(2024, 1, 1, 10, 30))
+        );
+
+        // 2024-01-06 is a Saturday, next weekday 02:30 is Monday the 8th.
+        let weekdays = CronExpr::parse("30 2 * * 1-5").unwrap();
+        assert_eq!(
+            weekdays.next_after(ts(2024, 1, 6, 0, 0)),
+            Some(ts(2024, 1, 8, 2, 30))
+        );
+
+        let new_year = CronExpr::parse("0 0 1 1 *").unwrap();
+        assert_eq!(
+            new_year.next_after(ts(2024, 3, 1, 0, 0)),
+            Some(ts(2025, 1, 1, 0, 0))
+        );
+
+        let leap_day = CronExpr::parse("0 12 29 2 *").unwrap();
+        assert_eq!(
+            leap_day.next_after(ts(2024, 3, 1, 0, 0)),
+            Some(ts(2028, 2, 29, 12, 0))
+        );
+
+        assert_eq!(CronExpr::parse("0 0 30 2 *").unwrap().next_after(0), None);
+    }
+
+    #[test]
+    fn test_cron_day_fields() {
+        // Restricted day-of-month and day-of-week match either.
+        let expr = CronExpr::parse("0 0 15 * 0").unwrap();
+        assert_eq!(
+            expr.next_after(ts(2024, 1, 1, 0, 0)),
+            Some(ts(2024, 1, 7, 0, 0))
+        );
+
+        // 7 is Sunday too.
+        let sunday = CronExpr::parse("0 0 * * 7").unwrap();
+        assert_eq!(
+            sunday.next_after(ts(2024, 1, 1, 0, 0)),
+            Some(ts(2024, 1, 7, 0, 0))
+        );
+    }
+
+    #[test]
+    fn test_cron_parse_error() {
+        assert!(CronExpr::parse("* * * *").is_err());
+        assert!(CronExpr::parse("60 * * * *").is_err());
+        assert!(CronExpr::parse("*/0 * * * *").is_err());
+        assert!(CronExpr::parse("5-1 * * * *").is_err());
+        assert!(CronExpr::parse("a * * * *").is_err());
+        assert!(CronExpr::parse("0,30 8-18/2 * 1-12 *").is_ok());
+    }
+
+    #[test]
+    fn test_task_schedule() {
+        assert!(!TaskSchedule::Once.is_recurring());
+        assert_eq!(TaskSchedule::Once.next_after(100), None);
+        assert_eq!(TaskSchedule::Interval(60).next_after(100), Some(160));
+        assert_eq!(TaskSchedule::Once.next_after_ms(100), None);
+        assert_eq!(
+            TaskSchedule::Interval(60).next_after_ms(100_250),
+            Some(160_250)
+        );
+        assert_eq!(
+            TaskSchedule::Cron("* * * * *".to_string()).next_after_ms(119_999),
+            Some(120_000)
+        );
+        assert_eq!(
+            TaskSchedule::Cron("* * * * *".to_string()).next_after_ms(120_000),
+            Some(180_000)
+        );
+        assert!(TaskSchedule::Interval(0).validate().is_err());
+        assert!(TaskSchedule::Cron("bad".to_string()).validate().is_err());
+        assert!(TaskSchedule::Cron("0 * * * *".to_string())
+            .validate()
+            .is_ok());
+    }
+}
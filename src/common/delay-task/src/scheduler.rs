@@ -0,0 +1,205 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Dispatch order of expired delay tasks.
+//!
+//! Expired tasks wait in one lane per [`DelayTaskPriority`]. The dispatcher
+//! takes from the highest non-empty lane, except that a task which has waited
+//! longer than the starvation timeout goes first whatever its lane. On top of
+//! the global handler limit, each task type may have its own concurrency
+//! quota, so a burst of one type cannot occupy every handler slot.
+
+use crate::DelayTask;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DelayTaskPriority {
+    High = 0,
+    Normal = 1,
+    Low = 2,
+}
+
+const LANE_NUM: usize = 3;
+
+struct QueuedTask {
+    task: DelayTask,
+    enqueued_at: Instant,
+}
+
+pub struct ScheduledTask {
+    pub task: DelayTask,
+    /// Time spent waiting for a handler slot.
+    pub wait: Duration,
+}
+
+#[derive(Default)]
+struct SchedulerState {
+    lanes: [VecDeque<QueuedTask>; LANE_NUM],
+    running: HashMap<&'static str, usize>,
+}
+
+pub struct DelayTaskScheduler {
+    state: Mutex<SchedulerState>,
+    notify: Notify,
+    /// task type name → max concurrently running tasks of that type.
+    type_concurrency: HashMap<String, usize>,
+    starvation_timeout: Duration,
+}
+
+impl DelayTaskScheduler {
+    pub fn new(type_concurrency: HashMap<String, usize>, starvation_timeout: Duration) -> Self {
+        DelayTaskScheduler {
+            state: Mutex::new(SchedulerState::default()),
+            notify: Notify::new(),
+            type_concurrency,
+            starvation_timeout,
+        }
+    }
+
+    pub fn push(&self, task: DelayTask) {
+        let lane = task.data.priority() as usize;
+        self.state.lock().unwrap().lanes[lane].push_back(QueuedTask {
+            task,
+            enqueued_at: Instant::now(),
+        });
+        self.notify.notify_one();
+    }
+
+    /// Wait for the next task that may run. The caller must call
+    /// [`DelayTaskScheduler::finish`] once the task has been handled.
+    pub async fn next(&self) -> ScheduledTask {
+        loop {
+            let notified = self.notify.notified();
+            if let Some(task) = self.try_next(Instant::now()) {
+                return task;
+            }
+            notified.await;
+        }
+    }
+
+    pub fn finish(&self, task_type: &'static str) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(running) = state.running.get_mut(task_type) {
+            *running = running.saturating_sub(1);
+        }
+        drop(state);
+        self.notify.notify_one();
+    }
+
+    pub fn pending_num(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        state.lanes.iter().map(|lane| lane.len()).sum()
+    }
+
+    fn try_next(&self, now: Instant) -> Option<ScheduledTask> {
+        let mut state = self.state.lock().unwrap();
+
+        // First runnable task of each lane; tasks of a type at its quota are
+        // skipped so they do not block other types in the same lane.
+        let heads: Vec<(usize, usize, Instant)> = (0..LANE_NUM)
+            .filter_map(|lane| {
+                state.lanes[lane]
+                    .iter()
+                    .position(|q| self.has_quota(&state.running, q.task.task_type_name()))
+                    .map(|index| (lane, index, state.lanes[lane][index].enqueued_at))
+            })
+            .collect();
+
+        let starving = heads
+            .iter()
+            .filter(|(_, _, at)| now.duration_since(*at) >= self.starvation_timeout)
+            .min_by_key(|(_, _, at)| *at);
+        let (lane, index, _) = *starving.or_else(|| heads.first())?;
+
+        let queued = state.lanes[lane].remove(index)?;
+        *state
+            .running
+            .entry(queued.task.task_type_name())
+            .or_insert(0) += 1;
+        Some(ScheduledTask {
+            wait: now.duration_since(queued.enqueued_at),
+            task: queued.task,
+        })
+    }
+
+    fn has_quota(&self, running: &HashMap<&'static str, usize>, task_type: &str) -> bool {
+        match self.type_concurrency.get(task_type) {
+            Some(limit) if *limit > 0 => running.get(task_type).copied().unwrap_or(0) < *limit,
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DelayTaskData;
+
+    fn session_task(id: &str) -> DelayTask {
+        DelayTask::build_ephemeral(
+            id.to_string(),
+            DelayTaskData::MQTTSessionExpire("t".to_string(), id.to_string()),
+            0,
+        )
+    }
+
+    fn lastwill_task(id: &str) -> DelayTask {
+        DelayTask::build_ephemeral(
+            id.to_string(),
+            DelayTaskData::MQTTLastwillExpire("t".to_string(), id.to_string()),
+            0,
+        )
+    }
+
+    fn next_id(scheduler: &DelayTaskScheduler, now: Instant) -> Option<String> {
+        scheduler.try_next(now).map(|s| s.task.task_id)
+    }
+
+    #[test]
+    fn test_scheduler_priority_and_quota() {
+        let quota = HashMap::from([("MQTTLastwillExpire".to_string(), 1)]);
+        let scheduler = DelayTaskScheduler::new(quota, Duration::from_secs(60));
+        scheduler.push(lastwill_task("w1"));
+        scheduler.push(lastwill_task("w2"));
+        scheduler.push(session_task("s1"));
+
+        let now = Instant::now();
+        // Session expiry is in a higher lane than the earlier last-wills.
+        assert_eq!(next_id(&scheduler, now).as_deref(), Some("s1"));
+        assert_eq!(next_id(&scheduler, now).as_deref(), Some("w1"));
+        // Last-will quota is 1 and w1 is still running.
+        assert_eq!(next_id(&scheduler, now), None);
+        assert_eq!(scheduler.pending_num(), 1);
+
+        scheduler.finish("MQTTLastwillExpire");
+        assert_eq!(next_id(&scheduler, now).as_deref(), Some("w2"));
+    }
+
+    #[test]
+    fn test_scheduler_starvation() {
+        let scheduler = DelayTaskScheduler::new(HashMap::new(), Duration::from_secs(5));
+        scheduler.push(lastwill_task("w1"));
+        scheduler.push(session_task("s1"));
+
+        let later = Instant::now() + Duration::from_secs(10);
+        // Both waited past the timeout, the oldest goes first.
+        let scheduled = scheduler.try_next(later).unwrap();
+        assert_eq!(scheduled.task.task_id, "w1");
+        assert!(scheduled.wait >= Duration::from_secs(10));
+        assert_eq!(next_id(&scheduler, later).as_deref(), Some("s1"));
+    }
+}
@@ -18,11 +18,25 @@
     record_storage_engine_ops, record_storage_engine_ops_duration,
 };
 use dashmap::DashMap;
-use grpc_clients::{meta::common::call::get_offset_data, pool::ClientPool};
+use grpc_clients::{
+    meta::common::call::{
+        consumer_group_heartbeat, delete_offset_group, get_offset_data, leave_consumer_group,
+        list_offset_group, reset_offset_data, save_offset_data,
+    },
+    pool::ClientPool,
+};
 use metadata_struct::adapter::adapter_offset::AdapterConsumerGroupOffset;
-use protocol::meta::meta_service_common::GetOffsetDataRequest;
+use protocol::meta::meta_service_common::{
+    ConsumerGroupHeartbeatReply, ConsumerGroupHeartbeatRequest, DeleteOffsetGroupRequest,
+    GetOffsetDataRequest, LeaveConsumerGroupRequest, ListOffsetGroupRequest, OffsetGroupRaw,
+    ResetOffsetDataRequest, SaveOffsetData, SaveOffsetDataRequest, SaveOffsetDataRequestOffset,
+};
 use std::{collections::HashMap, sync::Arc};
 
+/// Cache key of a group. Kept as a (tenant, group) pair so names containing
+/// the separator of a joined string cannot collide across tenants.
+pub(crate) type GroupKey = (String, String);
+
 #[derive(Clone)]
 pub(crate) struct LocalGroupData {
     pub tenant: String,
@@ -32,8 +46,8 @@ pub(crate) struct LocalGroupData {
 #[derive(Clone)]
 pub struct OffsetManager {
     pub(crate) client_pool: Arc<ClientPool>,
-    pub(crate) offset_info: DashMap<String, HashMap<String, u64>>,
-    pub(crate) update_group_info: DashMap<String, LocalGroupData>,
+    pub(crate) offset_info: DashMap<GroupKey, HashMap<String, u64>>,
+    pub(crate) update_group_info: DashMap<GroupKey, LocalGroupData>,
 }
 
 impl OffsetManager {
@@ -73,6 +87,31 @@ pub async fn get_offset(
             return Ok(results);
         }
 
+        let results = self.get_remote_offset(tenant, group).await?;
+
+        // Populate local cache so subsequent calls on this node avoid the RPC.
+        if !results.is_empty() {
+            let shard_map: HashMap<String, u64> = results
+                .iter()
+                .map(|r| (r.shard_name.clone(), r.offset))
+                .collect();
+            self.offset_info.insert(key, shard_map);
+        }
+
+        let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+        record_storage_engine_ops("get_offset_by_group");
+        record_storage_engine_ops_duration("get_offset_by_group", duration_ms);
+        Ok(results)
+    }
+
+    /// Read the group offsets from meta-service, skipping the local cache.
+    /// Used when a shard is taken over from another node, whose commits
+    /// never reached this node's cache.
+    pub async fn get_remote_offset(
+        &self,
+        tenant: &str,
+        group: &str,
+    ) -> Result<Vec<AdapterConsumerGroupOffset>, CommonError> {
         let request = GetOffsetDataRequest {
             tenant: tenant.to_owned(),
             group: group.to_owned(),
@@ -81,29 +120,60 @@ pub async fn get_offset(
         let reply =
             get_offset_data(&self.client_pool, &config.get_meta_service_addr(), request).await?;
 
-        let mut results = Vec::new();
-        for raw in reply.offsets {
-            results.push(AdapterConsumerGroupOffset {
+        Ok(reply
+            .offsets
+            .into_iter()
+            .map(|raw| AdapterConsumerGroupOffset {
                 group: group.to_string(),
                 shard_name: raw.shard_name,
                 offset: raw.offset,
                 ..Default::default()
-            });
-        }
+            })
+            .collect())
+    }
 
-        // Populate local cache so subsequent calls on this node avoid the RPC.
-        if !results.is_empty() {
-            let shard_map: HashMap<String, u64> = results
+    /// Persist the cached offsets of `shards` right away and drop them from
+    /// the local cache, so the member taking the shards over resumes from
+    /// them and later flushes of this group cannot overwrite its progress.
+    pub async fn release_shards(
+        &self,
+        tenant: &str,
+        group_name: &str,
+        shards: &[String],
+    ) -> Result<(), CommonError> {
+        let key = self.key(tenant, group_name);
+        let offsets: Vec<SaveOffsetDataRequestOffset> = match self.offset_info.get(&key) {
+            Some(data) => shards
                 .iter()
-                .map(|r| (r.shard_name.clone(), r.offset))
-                .collect();
-            self.offset_info.insert(key, shard_map);
+                .filter_map(|shard_name| {
+                    data.get(shard_name)
+                        .map(|&offset| SaveOffsetDataRequestOffset {
+                            shard_name: shard_name.clone(),
+                            offset,
+                        })
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        if !offsets.is_empty() {
+            let request = SaveOffsetDataRequest {
+                offsets: vec![SaveOffsetData {
+                    group: group_name.to_string(),
+                    tenant: tenant.to_string(),
+                    offsets,
+                }],
+            };
+            let config = broker_config();
+            save_offset_data(&self.client_pool, &config.get_meta_service_addr(), request).await?;
         }
 
-        let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
-        record_storage_engine_ops("get_offset_by_group");
-        record_storage_engine_ops_duration("get_offset_by_group", duration_ms);
-        Ok(results)
+        if let Some(mut data) = self.offset_info.get_mut(&key) {
+            for shard_name in shards {
+                data.remove(shard_name);
+            }
+        }
+        Ok(())
     }
 
     pub async fn commit_offset(
@@ -132,6 +202,45 @@ pub async fn commit_offset(
         Ok(())
     }
 
+    /// Join the consumer group or keep the member alive, returning the
+    /// shards it may read and the owned shards it has to release.
+    pub async fn group_member_heartbeat(
+        &self,
+        tenant: &str,
+        group_name: &str,
+        member_id: &str,
+        broker_id: u64,
+        shards: Vec<String>,
+        owned: Vec<String>,
+    ) -> Result<ConsumerGroupHeartbeatReply, CommonError> {
+        let request = ConsumerGroupHeartbeatRequest {
+            tenant: tenant.to_string(),
+            group: group_name.to_string(),
+            member_id: member_id.to_string(),
+            broker_id,
+            shards,
+            owned,
+        };
+        let config = broker_config();
+        consumer_group_heartbeat(&self.client_pool, &config.get_meta_service_addr(), request).await
+    }
+
+    pub async fn leave_group(
+        &self,
+        tenant: &str,
+        group_name: &str,
+        member_id: &str,
+    ) -> Result<(), CommonError> {
+        let request = LeaveConsumerGroupRequest {
+            tenant: tenant.to_string(),
+            group: group_name.to_string(),
+            member_id: member_id.to_string(),
+        };
+        let config = broker_config();
+        leave_consumer_group(&self.client_pool, &config.get_meta_service_addr(), request).await?;
+        Ok(())
+    }
+
     pub fn heartbeat(&self, tenant: &str, group_name: &str) {
         if !now_second().is_multiple_of(60) {
             return;
@@ -146,12 +255,87 @@ pub fn heartbeat(&self, tenant: &str, group_name: &str) {
         );
     }
 
+    /// Groups with committed offsets in meta-service. Empty `tenant` or
+    /// `group_name` widen the filter.
+    pub async fn list_groups(
+        &self,
+        tenant: &str,
+        group_name: &str,
+    ) -> Result<Vec<OffsetGroupRaw>, CommonError> {
+        let request = ListOffsetGroupRequest {
+            tenant: tenant.to_string(),
+            group: group_name.to_string(),
+        };
+        let config = broker_config();
+        let reply =
+            list_offset_group(&self.client_pool, &config.get_meta_service_addr(), request).await?;
+        Ok(reply.groups)
+    }
+
+    /// Delete every committed offset of an idle group.
+    pub async fn delete_group(&self, tenant: &str, group_name: &str) -> Result<(), CommonError> {
+        let request = DeleteOffsetGroupRequest {
+            tenant: tenant.to_string(),
+            group: group_name.to_string(),
+        };
+        let config = broker_config();
+        delete_offset_group(&self.client_pool, &config.get_meta_service_addr(), request).await?;
+        self.remove_group(tenant, group_name);
+        Ok(())
+    }
+
+    /// Overwrite the committed offsets of an idle group.
+    pub async fn reset_offset(
+        &self,
+        tenant: &str,
+        group_name: &str,
+        offsets: &HashMap<String, u64>,
+    ) -> Result<(), CommonError> {
+        let request = ResetOffsetDataRequest {
+            tenant: tenant.to_string(),
+            group: group_name.to_string(),
+            offsets: offsets
+                .iter()
+                .map(|(shard_name, &offset)| SaveOffsetDataRequestOffset {
+                    shard_name: shard_name.clone(),
+                    offset,
+                })
+                .collect(),
+        };
+        let config = broker_config();
+        reset_offset_data(&self.client_pool, &config.get_meta_service_addr(), request).await?;
+        self.remove_group(tenant, group_name);
+        Ok(())
+    }
+
     pub fn remove_group(&self, tenant: &str, group_name: &str) {
         let key = self.key(tenant, group_name);
         self.offset_info.remove(&key);
     }
 
-    pub(crate) fn key(&self, tenant: &str, group_name: &str) -> String {
-        format!("{}_{}", tenant, group_name)
+    pub(crate) fn key(&self, tenant: &str, group_name: &str) -> GroupKey {
+        (tenant.to_string(), group_name.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_group_key_does_not_collide() {
+        let manager = OffsetManager::new(Arc::new(ClientPool::new(1)));
+        let first = HashMap::from([("shard".to_string(), 1)]);
+        let second = HashMap::from([("shard".to_string(), 2)]);
+        manager.commit_offset("a_b", "c", &first).await.unwrap();
+        manager.commit_offset("a", "b_c", &second).await.unwrap();
+
+        assert_eq!(manager.offset_info.len(), 2);
+        let offsets = manager.get_offset("a", "b_c").await.unwrap();
+        assert_eq!(offsets.len(), 1);
+        assert_eq!(offsets[0].offset, 2);
+
+        manager.remove_group("a_b", "c");
+        assert!(manager.offset_info.contains_key(&manager.key("a", "b_c")));
     }
 }
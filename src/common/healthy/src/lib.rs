@@ -13,4 +13,5 @@
 // limitations under the License.
 
 pub mod port;
+pub mod probe;
 pub mod ready;
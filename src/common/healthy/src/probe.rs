@@ -0,0 +1,144 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use common_base::{
+    port::is_local_port_listening,
+    role::{is_broker_node, is_engine_node},
+};
+use common_config::config::BrokerConfig;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+}
+
+/// Outcome of a single dependency check, reported by name in a probe response.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub message: String,
+}
+
+impl CheckResult {
+    pub fn pass(name: &str, message: impl Into<String>) -> Self {
+        CheckResult {
+            name: name.to_string(),
+            status: CheckStatus::Pass,
+            message: message.into(),
+        }
+    }
+
+    pub fn fail(name: &str, message: impl Into<String>) -> Self {
+        CheckResult {
+            name: name.to_string(),
+            status: CheckStatus::Fail,
+            message: message.into(),
+        }
+    }
+
+    pub fn is_pass(&self) -> bool {
+        self.status == CheckStatus::Pass
+    }
+}
+
+/// Body of `/livez`, `/readyz` and `/healthz`. The probe passes only when
+/// every check passes.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ProbeReport {
+    pub status: CheckStatus,
+    pub checks: Vec<CheckResult>,
+}
+
+impl ProbeReport {
+    pub fn new(checks: Vec<CheckResult>) -> Self {
+        let status = if checks.iter().all(|c| c.is_pass()) {
+            CheckStatus::Pass
+        } else {
+            CheckStatus::Fail
+        };
+        ProbeReport { status, checks }
+    }
+
+    pub fn is_pass(&self) -> bool {
+        self.status == CheckStatus::Pass
+    }
+}
+
+fn port_check(name: &str, port: u32) -> CheckResult {
+    if is_local_port_listening(port) {
+        CheckResult::pass(name, format!("listening on port {port}"))
+    } else {
+        CheckResult::fail(name, format!("port {port} is not listening"))
+    }
+}
+
+/// One check per listener this node is expected to run given its roles.
+pub fn port_checks(config: &BrokerConfig) -> Vec<CheckResult> {
+    // Admin and gRPC ports are always started by broker-server.
+    let mut checks = vec![
+        port_check("http_port", config.http_port),
+        port_check("grpc_port", config.grpc_port),
+    ];
+
+    // MQTT listeners are only required on broker role nodes.
+    if is_broker_node(&config.roles) {
+        checks.push(port_check("mqtt_tcp_port", config.mqtt_server.tcp_port));
+        checks.push(port_check("mqtt_tls_port", config.mqtt_server.tls_port));
+        checks.push(port_check(
+            "mqtt_websocket_port",
+            config.mqtt_server.websocket_port,
+        ));
+        checks.push(port_check(
+            "mqtt_websockets_port",
+            config.mqtt_server.websockets_port,
+        ));
+        checks.push(port_check("mqtt_quic_port", config.mqtt_server.quic_port));
+    }
+
+    // Storage engine TCP listener is only required on engine role nodes.
+    if is_engine_node(&config.roles) {
+        checks.push(port_check(
+            "engine_tcp_port",
+            config.storage_runtime.tcp_port,
+        ));
+    }
+
+    checks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_fails_when_any_check_fails() {
+        let report = ProbeReport::new(vec![
+            CheckResult::pass("http_port", "ok"),
+            CheckResult::fail("meta_raft", "no leader"),
+        ]);
+        assert!(!report.is_pass());
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["status"], "fail");
+        assert_eq!(json["checks"][1]["name"], "meta_raft");
+        assert_eq!(json["checks"][1]["status"], "fail");
+
+        let report = ProbeReport::new(vec![CheckResult::pass("http_port", "ok")]);
+        assert!(report.is_pass());
+        assert!(ProbeReport::new(Vec::new()).is_pass());
+    }
+}
@@ -12,35 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use common_base::{
-    port::is_local_port_listening,
-    role::{is_broker_node, is_engine_node},
-};
+use crate::probe::port_checks;
 use common_config::broker::broker_config;
 
 pub fn healthy_ready_check() -> bool {
-    let config = broker_config();
-
-    // Admin and gRPC ports are always started by broker-server.
-    if !is_local_port_listening(config.http_port) || !is_local_port_listening(config.grpc_port) {
-        return false;
-    }
-
-    // MQTT listeners are only required on broker role nodes.
-    if is_broker_node(&config.roles)
-        && (!is_local_port_listening(config.mqtt_server.tcp_port)
-            || !is_local_port_listening(config.mqtt_server.tls_port)
-            || !is_local_port_listening(config.mqtt_server.websocket_port)
-            || !is_local_port_listening(config.mqtt_server.websockets_port)
-            || !is_local_port_listening(config.mqtt_server.quic_port))
-    {
-        return false;
-    }
-
-    // Storage engine TCP listener is only required on engine role nodes.
-    if is_engine_node(&config.roles) && !is_local_port_listening(config.storage_runtime.tcp_port) {
-        return false;
-    }
-
-    true
+    port_checks(broker_config()).iter().all(|c| c.is_pass())
 }
@@ -0,0 +1,94 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Encoding and decoding the StorageRecord every message is written and read
+// as: bincode for the whole record, rkyv for the metadata stored in the
+// segment index. `decode_bytes` is the zero-copy read path.
+// Run with: cargo bench -p metadata-struct --bench record_serialization
+
+use bytes::Bytes;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use metadata_struct::storage::record::{
+    StorageHeader, StorageRecord, StorageRecordMetadata, StorageRecordProtocolData,
+    StorageRecordProtocolDataMqtt,
+};
+
+const PAYLOAD_SIZES: [usize; 4] = [128, 1024, 4096, 16384];
+
+fn build_record(payload_size: usize) -> StorageRecord {
+    let data = Bytes::from(vec![7u8; payload_size]);
+    let metadata = StorageRecordMetadata::build(12345, "default_site_1_telemetry".to_string(), 3)
+        .with_header(Some(vec![
+            StorageHeader {
+                name: "content-type".to_string(),
+                value: "application/json".to_string(),
+            },
+            StorageHeader {
+                name: "device-id".to_string(),
+                value: "sensor-001".to_string(),
+            },
+        ]))
+        .with_key(Some("sensor-001".to_string()))
+        .with_tags(Some(vec!["temperature".to_string(), "iot".to_string()]))
+        .with_crc_from_data(&data);
+    StorageRecord {
+        metadata,
+        protocol_data: Some(StorageRecordProtocolData {
+            mqtt: Some(StorageRecordProtocolDataMqtt {
+                client_id: "sensor-001".to_string(),
+                content_type: Some("application/json".to_string()),
+                user_properties: vec![("region".to_string(), "eu-west".to_string())],
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        data,
+    }
+}
+
+fn bench_record(c: &mut Criterion) {
+    let mut group = c.benchmark_group("storage_record");
+    for size in PAYLOAD_SIZES {
+        let record = build_record(size);
+        let encoded = Bytes::from(record.encode().unwrap());
+
+        group.bench_with_input(BenchmarkId::new("encode", size), &size, |b, _| {
+            b.iter(|| black_box(&record).encode().unwrap());
+        });
+        group.bench_with_input(BenchmarkId::new("decode", size), &size, |b, _| {
+            b.iter(|| StorageRecord::decode(black_box(&encoded)).unwrap());
+        });
+        group.bench_with_input(BenchmarkId::new("decode_bytes", size), &size, |b, _| {
+            b.iter(|| StorageRecord::decode_bytes(black_box(&encoded)).unwrap());
+        });
+    }
+    group.finish();
+}
+
+fn bench_metadata(c: &mut Criterion) {
+    let metadata = build_record(0).metadata;
+    let encoded = metadata.encode();
+
+    let mut group = c.benchmark_group("storage_record_metadata");
+    group.bench_function("encode", |b| {
+        b.iter(|| black_box(&metadata).encode());
+    });
+    group.bench_function("decode", |b| {
+        b.iter(|| StorageRecordMetadata::decode(black_box(&encoded)).unwrap());
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_record, bench_metadata);
+criterion_main!(benches);
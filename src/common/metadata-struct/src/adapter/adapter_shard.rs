@@ -39,3 +39,18 @@ pub struct AdapterShardDetailOffset {
     pub end_offset: u64,
     pub high_watermark: u64,
 }
+
+/// Offset bounds of a shard, used to compute consumer lag.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdapterShardWatermarks {
+    pub shard_name: String,
+    /// First offset still stored.
+    pub earliest_offset: u64,
+    /// Offset the next record is written at; equal to `earliest_offset`
+    /// when the shard is empty.
+    pub latest_offset: u64,
+    /// Offset up to which records are replicated to the in-sync replicas.
+    pub high_watermark: u64,
+    /// Creation time of the last record, 0 when the shard is empty.
+    pub latest_timestamp: u64,
+}
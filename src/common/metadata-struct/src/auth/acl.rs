@@ -12,14 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::versioned::{utf8_head, versioned_serde, Versioned};
 use clap::{builder::PossibleValue, ValueEnum};
 use common_base::error::common::CommonError;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
 use std::fmt::Formatter;
 use std::str::FromStr;
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, PartialOrd, Clone)]
+#[serde(remote = "Self")]
 pub struct SecurityAcl {
     pub name: String,
     pub desc: String,
@@ -30,8 +32,57 @@ pub struct SecurityAcl {
     pub ip: String,
     pub action: EnumAclAction,
     pub permission: EnumAclPermission,
+    /// Rules with a higher priority are evaluated first; among matching rules
+    /// of the same priority a Deny overrides an Allow.
+    #[serde(default)]
+    pub priority: u32,
 }
 
+/// Fields of [`SecurityAcl`] after `name`, as laid out before `priority` was
+/// added.
+#[derive(Deserialize)]
+pub(crate) struct SecurityAclV1 {
+    desc: String,
+    tenant: String,
+    resource_type: EnumAclResourceType,
+    resource_name: String,
+    topic: String,
+    ip: String,
+    action: EnumAclAction,
+    permission: EnumAclPermission,
+}
+
+impl Versioned for SecurityAcl {
+    const VERSION: u64 = 2;
+    type LegacyHead = u8;
+    type Legacy = SecurityAclV1;
+
+    fn from_legacy(head: Vec<u8>, legacy: SecurityAclV1) -> Result<Self, String> {
+        Ok(SecurityAcl {
+            name: utf8_head(head)?,
+            desc: legacy.desc,
+            tenant: legacy.tenant,
+            resource_type: legacy.resource_type,
+            resource_name: legacy.resource_name,
+            topic: legacy.topic,
+            ip: legacy.ip,
+            action: legacy.action,
+            permission: legacy.permission,
+            priority: 0,
+        })
+    }
+
+    fn serialize_fields<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SecurityAcl::serialize(self, serializer)
+    }
+
+    fn deserialize_fields<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        SecurityAcl::deserialize(deserializer)
+    }
+}
+
+versioned_serde!(SecurityAcl);
+
 impl SecurityAcl {
     pub fn encode(&self) -> Result<Vec<u8>, CommonError> {
         Ok(serde_json::to_vec(&self)?)
@@ -179,3 +230,65 @@ fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
             .fmt(f)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common_base::utils::serialize;
+
+    #[derive(Serialize)]
+    struct LegacySecurityAcl {
+        name: String,
+        desc: String,
+        tenant: String,
+        resource_type: EnumAclResourceType,
+        resource_name: String,
+        topic: String,
+        ip: String,
+        action: EnumAclAction,
+        permission: EnumAclPermission,
+    }
+
+    #[test]
+    fn test_decode_legacy_acl() {
+        let legacy = LegacySecurityAcl {
+            name: "deny-all".to_string(),
+            desc: "".to_string(),
+            tenant: "default".to_string(),
+            resource_type: EnumAclResourceType::User,
+            resource_name: "user".to_string(),
+            topic: "t/#".to_string(),
+            ip: "*".to_string(),
+            action: EnumAclAction::Publish,
+            permission: EnumAclPermission::Deny,
+        };
+
+        let acl: SecurityAcl =
+            serialize::deserialize(&serialize::serialize(&legacy).unwrap()).unwrap();
+        assert_eq!(acl.name, "deny-all");
+        assert_eq!(acl.tenant, "default");
+        assert_eq!(acl.resource_type, EnumAclResourceType::User);
+        assert_eq!(acl.topic, "t/#");
+        assert_eq!(acl.permission, EnumAclPermission::Deny);
+        assert_eq!(acl.priority, 0);
+    }
+
+    #[test]
+    fn test_encode_decode_acl() {
+        let acl = SecurityAcl {
+            name: "allow".to_string(),
+            desc: "".to_string(),
+            tenant: "default".to_string(),
+            resource_type: EnumAclResourceType::ClientId,
+            resource_name: "c1".to_string(),
+            topic: "t/1".to_string(),
+            ip: "*".to_string(),
+            action: EnumAclAction::All,
+            permission: EnumAclPermission::Allow,
+            priority: 5,
+        };
+        let bytes = serialize::serialize(&acl).unwrap();
+        assert_eq!(serialize::deserialize::<SecurityAcl>(&bytes).unwrap(), acl);
+        assert_eq!(SecurityAcl::decode(&acl.encode().unwrap()).unwrap(), acl);
+    }
+}
@@ -48,6 +48,8 @@ pub enum EnumBlackListType {
     ClientIdMatch,
     UserMatch,
     IPCIDR,
+    ClientIdRegex,
+    UserRegex,
 }
 
 impl FromStr for EnumBlackListType {
@@ -72,6 +74,8 @@ fn value_variants<'a>() -> &'a [Self] {
             Self::ClientIdMatch,
             Self::UserMatch,
             Self::IPCIDR,
+            Self::ClientIdRegex,
+            Self::UserRegex,
         ]
     }
 
@@ -83,6 +87,8 @@ fn to_possible_value(&self) -> Option<PossibleValue> {
             EnumBlackListType::ClientIdMatch => PossibleValue::new("ClientIdMatch"),
             EnumBlackListType::UserMatch => PossibleValue::new("UserMatch"),
             EnumBlackListType::IPCIDR => PossibleValue::new("IPCIDR"),
+            EnumBlackListType::ClientIdRegex => PossibleValue::new("ClientIdRegex"),
+            EnumBlackListType::UserRegex => PossibleValue::new("UserRegex"),
         })
     }
 }
@@ -95,6 +101,8 @@ pub fn get_blacklist_type_by_str(blacklist_type: &str) -> Result<EnumBlackListTy
         "ClientIdMatch" => EnumBlackListType::ClientIdMatch,
         "UserMatch" => EnumBlackListType::UserMatch,
         "IPCIDR" => EnumBlackListType::IPCIDR,
+        "ClientIdRegex" => EnumBlackListType::ClientIdRegex,
+        "UserRegex" => EnumBlackListType::UserRegex,
         _ => {
             return Err(CommonError::CommonError(format!(
                 "Failed BlackList Type: {blacklist_type}",
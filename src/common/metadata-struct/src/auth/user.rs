@@ -12,10 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::versioned::{utf8_head, versioned_serde, Versioned};
 use common_base::{error::common::CommonError, utils::serialize};
-use serde::{Deserialize, Serialize};
+use common_config::config::PasswordHashAlgorithm;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(remote = "Self")]
 pub struct SecurityUser {
     pub tenant: String,
     pub username: String,
@@ -23,9 +26,60 @@ pub struct SecurityUser {
     pub salt: Option<String>,
     pub is_superuser: bool,
     pub create_time: u64,
+    /// How `password` is stored. Entries written before this field existed
+    /// leave it unset, see [`SecurityUser::hash_algorithm`].
+    #[serde(default)]
+    pub hash_algorithm: Option<PasswordHashAlgorithm>,
 }
 
+/// Fields of [`SecurityUser`] after `tenant`, as laid out before
+/// `hash_algorithm` was added.
+#[derive(Deserialize)]
+pub(crate) struct SecurityUserV1 {
+    username: String,
+    password: String,
+    salt: Option<String>,
+    is_superuser: bool,
+    create_time: u64,
+}
+
+impl Versioned for SecurityUser {
+    const VERSION: u64 = 2;
+    type LegacyHead = u8;
+    type Legacy = SecurityUserV1;
+
+    fn from_legacy(head: Vec<u8>, legacy: SecurityUserV1) -> Result<Self, String> {
+        Ok(SecurityUser {
+            tenant: utf8_head(head)?,
+            username: legacy.username,
+            password: legacy.password,
+            salt: legacy.salt,
+            is_superuser: legacy.is_superuser,
+            create_time: legacy.create_time,
+            hash_algorithm: None,
+        })
+    }
+
+    fn serialize_fields<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SecurityUser::serialize(self, serializer)
+    }
+
+    fn deserialize_fields<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        SecurityUser::deserialize(deserializer)
+    }
+}
+
+versioned_serde!(SecurityUser);
+
 impl SecurityUser {
+    pub fn hash_algorithm(&self) -> PasswordHashAlgorithm {
+        match (self.hash_algorithm, &self.salt) {
+            (Some(algorithm), _) => algorithm,
+            (None, Some(_)) => PasswordHashAlgorithm::Sha256,
+            (None, None) => PasswordHashAlgorithm::Plain,
+        }
+    }
+
     pub fn encode(&self) -> Result<Vec<u8>, CommonError> {
         serialize::serialize(self)
     }
@@ -34,3 +88,72 @@ pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
         serialize::deserialize(data)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct LegacySecurityUser {
+        tenant: String,
+        username: String,
+        password: String,
+        salt: Option<String>,
+        is_superuser: bool,
+        create_time: u64,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Wrap<T> {
+        data: T,
+        create_time: u64,
+    }
+
+    #[test]
+    fn test_decode_legacy_user() {
+        let legacy = LegacySecurityUser {
+            tenant: "default".to_string(),
+            username: "user".to_string(),
+            password: "hash".to_string(),
+            salt: Some("salt".to_string()),
+            is_superuser: true,
+            create_time: 10,
+        };
+
+        let user = SecurityUser::decode(&serialize::serialize(&legacy).unwrap()).unwrap();
+        assert_eq!(user.tenant, "default");
+        assert_eq!(user.username, "user");
+        assert_eq!(user.salt.as_deref(), Some("salt"));
+        assert!(user.is_superuser);
+        assert_eq!(user.hash_algorithm, None);
+        assert_eq!(user.hash_algorithm(), PasswordHashAlgorithm::Sha256);
+
+        // Records in storage are wrapped, the user is decoded in place.
+        let wrapped = serialize::serialize(&Wrap {
+            data: legacy,
+            create_time: 20,
+        })
+        .unwrap();
+        let wrapped: Wrap<SecurityUser> = serialize::deserialize(&wrapped).unwrap();
+        assert_eq!(wrapped.data, user);
+        assert_eq!(wrapped.create_time, 20);
+    }
+
+    #[test]
+    fn test_encode_decode_user() {
+        let user = SecurityUser {
+            tenant: "default".to_string(),
+            username: "user".to_string(),
+            password: "$argon2id$hash".to_string(),
+            salt: None,
+            is_superuser: false,
+            create_time: 10,
+            hash_algorithm: Some(PasswordHashAlgorithm::Argon2),
+        };
+        assert_eq!(SecurityUser::decode(&user.encode().unwrap()).unwrap(), user);
+
+        let json = serde_json::to_value(&user).unwrap();
+        assert_eq!(json["hash_algorithm"], "argon2");
+        assert_eq!(serde_json::from_value::<SecurityUser>(json).unwrap(), user);
+    }
+}
@@ -30,6 +30,12 @@ pub enum NetworkConnectionType {
     WebSocket,
     WebSockets,
     QUIC,
+    /// MQTT-SN over UDP, served by the MQTT-SN gateway.
+    MqttSn,
+    /// CoAP over UDP or DTLS, served by the CoAP gateway.
+    CoAP,
+    /// Browser subscription over SSE or WebSocket JSON frames.
+    WebSubscribe,
 }
 
 impl fmt::Display for NetworkConnectionType {
@@ -43,6 +49,9 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
                 NetworkConnectionType::WebSocket => "Websocket",
                 NetworkConnectionType::WebSockets => "Websockets",
                 NetworkConnectionType::QUIC => "Quic",
+                NetworkConnectionType::MqttSn => "MqttSn",
+                NetworkConnectionType::CoAP => "CoAP",
+                NetworkConnectionType::WebSubscribe => "WebSubscribe",
             }
         )
     }
@@ -57,10 +66,25 @@ pub struct NetworkConnection {
     pub last_heartbeat_time: u64,
     pub create_time: u64,
     pub mark_close: u64,
+    #[serde(default)]
+    pub peer_cert: Option<PeerCertIdentity>,
+    /// Username already verified by the transport, e.g. from the token of a
+    /// web subscriber. CONNECT logs in as this user without a password check.
+    #[serde(default)]
+    pub authenticated_user: Option<String>,
     #[serde(skip_serializing, skip_deserializing)]
     pub connection_stop_sx: Option<mpsc::Sender<bool>>,
 }
 
+/// Identity read from a client certificate that passed TLS verification.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PeerCertIdentity {
+    pub common_name: Option<String>,
+    pub dns_names: Vec<String>,
+    pub emails: Vec<String>,
+    pub uris: Vec<String>,
+}
+
 impl NetworkConnection {
     pub fn new(
         connection_type: NetworkConnectionType,
@@ -77,6 +101,8 @@ pub fn new(
             create_time: now_second(),
             connection_stop_sx,
             mark_close: 0,
+            peer_cert: None,
+            authenticated_user: None,
         }
     }
 
@@ -0,0 +1,46 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::{error::common::CommonError, utils::serialize};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Progress of a connector, committed to meta-service as one record so the
+/// source offsets and the sink transaction that wrote everything before them
+/// can never disagree.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct ConnectorCheckpoint {
+    pub tenant: String,
+    pub connector_name: String,
+    /// Broker that committed the checkpoint, which must own the connector.
+    pub broker_id: u64,
+    /// Increases with every commit, a commit not above the stored one is stale.
+    pub sequence: u64,
+    /// (shard, offset the next read starts at)
+    pub source_offsets: HashMap<String, u64>,
+    /// Sink transaction committed together with `source_offsets`, empty for
+    /// sinks without transactions.
+    pub transaction_id: String,
+    pub commit_time: u64,
+}
+
+impl ConnectorCheckpoint {
+    pub fn encode(&self) -> Result<Vec<u8>, CommonError> {
+        serialize::serialize(self)
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
+        serialize::deserialize(data)
+    }
+}
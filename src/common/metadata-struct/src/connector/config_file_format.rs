@@ -0,0 +1,60 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde::{Deserialize, Serialize};
+
+/// Layout of the files written by the local file and S3 connectors.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FileFormat {
+    /// The message payload followed by a newline.
+    #[default]
+    Raw,
+    /// A JSON array of records with their metadata.
+    Json,
+    /// One JSON record with its metadata per line.
+    JsonLines,
+    /// Columnar Parquet file, compressed by the Parquet codec.
+    Parquet,
+}
+
+impl FileFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            FileFormat::Raw => "log",
+            FileFormat::Json => "json",
+            FileFormat::JsonLines => "jsonl",
+            FileFormat::Parquet => "parquet",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FileCompression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl FileCompression {
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            FileCompression::None => None,
+            FileCompression::Gzip => Some("gz"),
+            FileCompression::Zstd => Some("zst"),
+        }
+    }
+}
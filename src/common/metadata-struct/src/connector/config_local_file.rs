@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::config_file_format::{FileCompression, FileFormat};
 use common_base::error::common::CommonError;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
@@ -33,6 +34,33 @@ pub struct LocalFileConnectorConfig {
     pub rotation_strategy: RotationStrategy,
     #[serde(default = "default_max_size_gb")]
     pub max_size_gb: u64,
+    /// Any format other than `raw` or any compression stages records next to
+    /// `local_file_path` and writes each rotation as a new complete file.
+    #[serde(default)]
+    pub format: FileFormat,
+    #[serde(default)]
+    pub compression: FileCompression,
+}
+
+/// Layout of [`LocalFileConnectorConfig`] before file formats and compression
+/// were added, for connectors stored by older versions.
+#[derive(Deserialize)]
+pub(crate) struct LocalFileConnectorConfigV1 {
+    local_file_path: String,
+    rotation_strategy: RotationStrategy,
+    max_size_gb: u64,
+}
+
+impl From<LocalFileConnectorConfigV1> for LocalFileConnectorConfig {
+    fn from(config: LocalFileConnectorConfigV1) -> Self {
+        LocalFileConnectorConfig {
+            local_file_path: config.local_file_path,
+            rotation_strategy: config.rotation_strategy,
+            max_size_gb: config.max_size_gb,
+            format: FileFormat::Raw,
+            compression: FileCompression::None,
+        }
+    }
 }
 
 fn default_max_size_gb() -> u64 {
@@ -40,6 +68,11 @@ fn default_max_size_gb() -> u64 {
 }
 
 impl LocalFileConnectorConfig {
+    /// Whether rotated files are written whole instead of appended to.
+    pub fn is_archive(&self) -> bool {
+        self.format != FileFormat::Raw || self.compression != FileCompression::None
+    }
+
     pub fn validate(&self) -> Result<(), CommonError> {
         if self.local_file_path.is_empty() {
             return Err(CommonError::CommonError(
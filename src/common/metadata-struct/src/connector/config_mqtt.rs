@@ -23,6 +23,29 @@ pub enum MqttProtocolVersion {
     V3,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MqttBridgeDirection {
+    /// Forward local messages to the remote broker.
+    #[default]
+    Out,
+    /// Subscribe on the remote broker and write its messages locally.
+    In,
+    Both,
+}
+
+/// Maps topics matching the `source` filter to `target`, where `${topic}` is
+/// replaced by the matched topic. An empty `target` keeps the topic unchanged.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default)]
+pub struct MqttBridgeTopicMapping {
+    pub source: String,
+    #[serde(default)]
+    pub target: String,
+    /// Messages matching this rule are sent with at most this QoS.
+    #[serde(default)]
+    pub max_qos: Option<i32>,
+}
+
 #[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
 pub struct MqttBridgeConnectorConfig {
     pub server: String,
@@ -47,6 +70,70 @@ pub struct MqttBridgeConnectorConfig {
     pub retain: bool,
     #[serde(default = "default_max_retries")]
     pub max_retries: u32,
+    #[serde(default)]
+    pub direction: MqttBridgeDirection,
+    /// Outbound rules, the first matching one applies. Topics matching no
+    /// rule fall back to `topic_prefix`.
+    #[serde(default)]
+    pub topic_mappings: Vec<MqttBridgeTopicMapping>,
+    /// Inbound subscriptions: `source` is the remote filter, `target` the
+    /// local topic, the connector topic when empty.
+    #[serde(default)]
+    pub remote_topics: Vec<MqttBridgeTopicMapping>,
+    #[serde(default = "default_clean_session")]
+    pub clean_session: bool,
+    /// Fixed client id, required for the remote broker to resume a persistent
+    /// session. Defaults to `robustmq-bridge:<connector>` when `clean_session`
+    /// is off.
+    #[serde(default)]
+    pub client_id: Option<String>,
+    /// Origin id attached to bridged messages to prevent loops, defaults to the
+    /// connector name.
+    #[serde(default)]
+    pub bridge_id: Option<String>,
+}
+
+/// Layout of [`MqttBridgeConnectorConfig`] before inbound bridging and topic
+/// mappings were added, for connectors stored by older versions.
+#[derive(Deserialize)]
+pub(crate) struct MqttBridgeConnectorConfigV1 {
+    server: String,
+    client_id_prefix: Option<String>,
+    username: Option<String>,
+    password: Option<String>,
+    protocol_version: MqttProtocolVersion,
+    keepalive_secs: u64,
+    connect_timeout_secs: u64,
+    enable_tls: bool,
+    topic_prefix: Option<String>,
+    qos: i32,
+    retain: bool,
+    max_retries: u32,
+}
+
+impl From<MqttBridgeConnectorConfigV1> for MqttBridgeConnectorConfig {
+    fn from(config: MqttBridgeConnectorConfigV1) -> Self {
+        MqttBridgeConnectorConfig {
+            server: config.server,
+            client_id_prefix: config.client_id_prefix,
+            username: config.username,
+            password: config.password,
+            protocol_version: config.protocol_version,
+            keepalive_secs: config.keepalive_secs,
+            connect_timeout_secs: config.connect_timeout_secs,
+            enable_tls: config.enable_tls,
+            topic_prefix: config.topic_prefix,
+            qos: config.qos,
+            retain: config.retain,
+            max_retries: config.max_retries,
+            direction: MqttBridgeDirection::Out,
+            topic_mappings: Vec::new(),
+            remote_topics: Vec::new(),
+            clean_session: default_clean_session(),
+            client_id: None,
+            bridge_id: None,
+        }
+    }
 }
 
 fn default_keepalive_secs() -> u64 {
@@ -65,6 +152,10 @@ fn default_max_retries() -> u32 {
     3
 }
 
+fn default_clean_session() -> bool {
+    true
+}
+
 impl MqttBridgeConnectorConfig {
     pub fn validate(&self) -> Result<(), common_base::error::common::CommonError> {
         use common_base::error::common::CommonError;
@@ -113,6 +204,39 @@ pub fn validate(&self) -> Result<(), common_base::error::common::CommonError> {
             }
         }
 
+        if let Some(client_id) = &self.client_id {
+            if client_id.is_empty() || client_id.len() > 128 {
+                return Err(CommonError::CommonError(
+                    "client_id length must be between 1 and 128 characters".to_string(),
+                ));
+            }
+        }
+
+        for mapping in self.topic_mappings.iter().chain(self.remote_topics.iter()) {
+            if mapping.source.is_empty() {
+                return Err(CommonError::CommonError(
+                    "topic mapping source cannot be empty".to_string(),
+                ));
+            }
+            if mapping.target.contains(['+', '#']) {
+                return Err(CommonError::CommonError(format!(
+                    "topic mapping target '{}' cannot contain wildcards",
+                    mapping.target
+                )));
+            }
+            if mapping.max_qos.is_some_and(|qos| !(0..=2).contains(&qos)) {
+                return Err(CommonError::CommonError(
+                    "topic mapping max_qos must be 0, 1 or 2".to_string(),
+                ));
+            }
+        }
+
+        if self.direction != MqttBridgeDirection::Out && self.remote_topics.is_empty() {
+            return Err(CommonError::CommonError(
+                "remote_topics cannot be empty for an inbound bridge".to_string(),
+            ));
+        }
+
         Ok(())
     }
 }
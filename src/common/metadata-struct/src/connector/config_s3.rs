@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::config_file_format::{FileCompression, FileFormat};
 use common_base::error::common::CommonError;
 use serde::{Deserialize, Serialize};
 
@@ -38,16 +39,72 @@ pub struct S3ConnectorConfig {
     #[serde(default = "default_object_key_prefix")]
     pub object_key_prefix: String,
 
-    #[serde(default = "default_file_extension")]
+    /// Defaults to the extension of `format` and `compression`.
+    #[serde(default)]
     pub file_extension: String,
+
+    #[serde(default = "default_format")]
+    pub format: FileFormat,
+
+    #[serde(default)]
+    pub compression: FileCompression,
+
+    /// Roll an object once this many megabytes are staged. With both roll limits
+    /// at 0 every batch is written as its own object.
+    #[serde(default)]
+    pub roll_size_mb: u64,
+
+    #[serde(default)]
+    pub roll_interval_secs: u64,
+
+    /// Local directory records are staged in until their object is rolled.
+    #[serde(default)]
+    pub staging_dir: String,
+}
+
+/// Layout of [`S3ConnectorConfig`] before file formats and object rolling were
+/// added, for connectors stored by older versions. These wrote one JSON object
+/// per batch.
+#[derive(Deserialize)]
+pub(crate) struct S3ConnectorConfigV1 {
+    bucket: String,
+    region: String,
+    endpoint: String,
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: String,
+    root: String,
+    object_key_prefix: String,
+    file_extension: String,
+}
+
+impl From<S3ConnectorConfigV1> for S3ConnectorConfig {
+    fn from(config: S3ConnectorConfigV1) -> Self {
+        S3ConnectorConfig {
+            bucket: config.bucket,
+            region: config.region,
+            endpoint: config.endpoint,
+            access_key_id: config.access_key_id,
+            secret_access_key: config.secret_access_key,
+            session_token: config.session_token,
+            root: config.root,
+            object_key_prefix: config.object_key_prefix,
+            file_extension: config.file_extension,
+            format: FileFormat::Json,
+            compression: FileCompression::None,
+            roll_size_mb: 0,
+            roll_interval_secs: 0,
+            staging_dir: String::new(),
+        }
+    }
 }
 
 fn default_object_key_prefix() -> String {
     "mqtt".to_string()
 }
 
-fn default_file_extension() -> String {
-    "json".to_string()
+fn default_format() -> FileFormat {
+    FileFormat::Json
 }
 
 impl Default for S3ConnectorConfig {
@@ -61,7 +118,12 @@ fn default() -> Self {
             session_token: String::new(),
             root: String::new(),
             object_key_prefix: default_object_key_prefix(),
-            file_extension: default_file_extension(),
+            file_extension: String::new(),
+            format: default_format(),
+            compression: FileCompression::None,
+            roll_size_mb: 0,
+            roll_interval_secs: 0,
+            staging_dir: String::new(),
         }
     }
 }
@@ -119,12 +181,6 @@ pub fn validate(&self) -> Result<(), CommonError> {
             ));
         }
 
-        if self.file_extension.trim().is_empty() {
-            return Err(CommonError::CommonError(
-                "file_extension cannot be empty".to_string(),
-            ));
-        }
-
         if self.file_extension.len() > 16 {
             return Err(CommonError::CommonError(
                 "file_extension length cannot exceed 16 characters".to_string(),
@@ -141,6 +197,18 @@ pub fn validate(&self) -> Result<(), CommonError> {
             ));
         }
 
+        if self.roll_size_mb > 1024 {
+            return Err(CommonError::CommonError(
+                "roll_size_mb cannot exceed 1024".to_string(),
+            ));
+        }
+
+        if self.staging_dir.len() > 4096 {
+            return Err(CommonError::CommonError(
+                "staging_dir length cannot exceed 4096 characters".to_string(),
+            ));
+        }
+
         let has_access_key = !self.access_key_id.is_empty();
         let has_secret_key = !self.secret_access_key.is_empty();
         if has_access_key != has_secret_key {
@@ -18,15 +18,23 @@
 use std::str::FromStr;
 
 use crate::connector::{
-    config_cassandra::CassandraConnectorConfig, config_clickhouse::ClickHouseConnectorConfig,
+    config_cassandra::CassandraConnectorConfig,
+    config_clickhouse::ClickHouseConnectorConfig,
     config_elasticsearch::ElasticsearchConnectorConfig,
-    config_greptimedb::GreptimeDBConnectorConfig, config_influxdb::InfluxDBConnectorConfig,
-    config_kafka::KafkaConnectorConfig, config_local_file::LocalFileConnectorConfig,
-    config_mongodb::MongoDBConnectorConfig, config_mqtt::MqttBridgeConnectorConfig,
-    config_mysql::MySQLConnectorConfig, config_opentsdb::OpenTSDBConnectorConfig,
-    config_postgres::PostgresConnectorConfig, config_pulsar::PulsarConnectorConfig,
-    config_rabbitmq::RabbitMQConnectorConfig, config_redis::RedisConnectorConfig,
-    config_s3::S3ConnectorConfig, config_webhook::WebhookConnectorConfig,
+    config_greptimedb::GreptimeDBConnectorConfig,
+    config_influxdb::InfluxDBConnectorConfig,
+    config_kafka::KafkaConnectorConfig,
+    config_local_file::{LocalFileConnectorConfig, LocalFileConnectorConfigV1},
+    config_mongodb::MongoDBConnectorConfig,
+    config_mqtt::{MqttBridgeConnectorConfig, MqttBridgeConnectorConfigV1},
+    config_mysql::MySQLConnectorConfig,
+    config_opentsdb::OpenTSDBConnectorConfig,
+    config_postgres::PostgresConnectorConfig,
+    config_pulsar::PulsarConnectorConfig,
+    config_rabbitmq::RabbitMQConnectorConfig,
+    config_redis::RedisConnectorConfig,
+    config_s3::{S3ConnectorConfig, S3ConnectorConfigV1},
+    config_webhook::WebhookConnectorConfig,
 };
 
 pub const CONNECTOR_TYPE_FILE: &str = "file";
@@ -68,6 +76,54 @@ pub enum ConnectorType {
     S3(S3ConnectorConfig),
 }
 
+/// [`ConnectorType`] as encoded inside connectors stored by older versions.
+/// bincode identifies variants by index, so the variants keep the order of
+/// [`ConnectorType`].
+#[derive(Deserialize)]
+pub(crate) enum ConnectorTypeV1 {
+    Kafka(KafkaConnectorConfig),
+    LocalFile(LocalFileConnectorConfigV1),
+    GreptimeDB(GreptimeDBConnectorConfig),
+    Pulsar(PulsarConnectorConfig),
+    Postgres(PostgresConnectorConfig),
+    MongoDB(MongoDBConnectorConfig),
+    RabbitMQ(RabbitMQConnectorConfig),
+    MySQL(MySQLConnectorConfig),
+    Elasticsearch(ElasticsearchConnectorConfig),
+    Redis(RedisConnectorConfig),
+    Webhook(WebhookConnectorConfig),
+    OpenTSDB(OpenTSDBConnectorConfig),
+    MqttBridge(MqttBridgeConnectorConfigV1),
+    ClickHouse(ClickHouseConnectorConfig),
+    InfluxDB(InfluxDBConnectorConfig),
+    Cassandra(CassandraConnectorConfig),
+    S3(S3ConnectorConfigV1),
+}
+
+impl From<ConnectorTypeV1> for ConnectorType {
+    fn from(connector_type: ConnectorTypeV1) -> Self {
+        match connector_type {
+            ConnectorTypeV1::Kafka(config) => ConnectorType::Kafka(config),
+            ConnectorTypeV1::LocalFile(config) => ConnectorType::LocalFile(config.into()),
+            ConnectorTypeV1::GreptimeDB(config) => ConnectorType::GreptimeDB(config),
+            ConnectorTypeV1::Pulsar(config) => ConnectorType::Pulsar(config),
+            ConnectorTypeV1::Postgres(config) => ConnectorType::Postgres(config),
+            ConnectorTypeV1::MongoDB(config) => ConnectorType::MongoDB(config),
+            ConnectorTypeV1::RabbitMQ(config) => ConnectorType::RabbitMQ(config),
+            ConnectorTypeV1::MySQL(config) => ConnectorType::MySQL(config),
+            ConnectorTypeV1::Elasticsearch(config) => ConnectorType::Elasticsearch(config),
+            ConnectorTypeV1::Redis(config) => ConnectorType::Redis(config),
+            ConnectorTypeV1::Webhook(config) => ConnectorType::Webhook(config),
+            ConnectorTypeV1::OpenTSDB(config) => ConnectorType::OpenTSDB(config),
+            ConnectorTypeV1::MqttBridge(config) => ConnectorType::MqttBridge(config.into()),
+            ConnectorTypeV1::ClickHouse(config) => ConnectorType::ClickHouse(config),
+            ConnectorTypeV1::InfluxDB(config) => ConnectorType::InfluxDB(config),
+            ConnectorTypeV1::Cassandra(config) => ConnectorType::Cassandra(config),
+            ConnectorTypeV1::S3(config) => ConnectorType::S3(config.into()),
+        }
+    }
+}
+
 impl Default for ConnectorType {
     fn default() -> Self {
         ConnectorType::Kafka(KafkaConnectorConfig::default())
@@ -12,14 +12,18 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::connector::connector_type::ConnectorTypeV1;
 use crate::connector::rule::ETLRule;
+use crate::versioned::{utf8_head, versioned_serde, Versioned};
 use common_base::{error::common::CommonError, utils::serialize};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use status::MQTTStatus;
 
+pub mod checkpoint;
 pub mod config_cassandra;
 pub mod config_clickhouse;
 pub mod config_elasticsearch;
+pub mod config_file_format;
 pub mod config_greptimedb;
 pub mod config_influxdb;
 pub mod config_kafka;
@@ -41,6 +45,7 @@
 pub use connector_type::ConnectorType;
 
 #[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(remote = "Self")]
 pub struct MQTTConnector {
     pub tenant: String,
     pub connector_name: String,
@@ -52,6 +57,9 @@ pub struct MQTTConnector {
     pub broker_id: Option<u64>,
     pub create_time: u64,
     pub update_time: u64,
+    /// Upper bound on records sent per second by this connector, `None` means unthrottled.
+    #[serde(default)]
+    pub max_records_per_sec: Option<u32>,
 }
 
 #[derive(Clone, Default, Serialize, Deserialize, Debug, PartialEq)]
@@ -86,6 +94,53 @@ fn default_wait_time_ms() -> u64 {
     1000
 }
 
+/// Fields of [`MQTTConnector`] after `tenant`, as laid out before
+/// `max_records_per_sec` was added.
+#[derive(Deserialize)]
+pub(crate) struct MQTTConnectorV1 {
+    connector_name: String,
+    connector_type: ConnectorTypeV1,
+    failure_strategy: FailureHandlingStrategy,
+    topic_name: String,
+    status: MQTTStatus,
+    etl_rule: ETLRule,
+    broker_id: Option<u64>,
+    create_time: u64,
+    update_time: u64,
+}
+
+impl Versioned for MQTTConnector {
+    const VERSION: u64 = 2;
+    type LegacyHead = u8;
+    type Legacy = MQTTConnectorV1;
+
+    fn from_legacy(head: Vec<u8>, legacy: MQTTConnectorV1) -> Result<Self, String> {
+        Ok(MQTTConnector {
+            tenant: utf8_head(head)?,
+            connector_name: legacy.connector_name,
+            connector_type: legacy.connector_type.into(),
+            failure_strategy: legacy.failure_strategy,
+            topic_name: legacy.topic_name,
+            status: legacy.status,
+            etl_rule: legacy.etl_rule,
+            broker_id: legacy.broker_id,
+            create_time: legacy.create_time,
+            update_time: legacy.update_time,
+            max_records_per_sec: None,
+        })
+    }
+
+    fn serialize_fields<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        MQTTConnector::serialize(self, serializer)
+    }
+
+    fn deserialize_fields<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        MQTTConnector::deserialize(deserializer)
+    }
+}
+
+versioned_serde!(MQTTConnector);
+
 impl MQTTConnector {
     pub fn encode(&self) -> Result<Vec<u8>, CommonError> {
         serialize::serialize(self)
@@ -95,3 +150,198 @@ pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
         serialize::deserialize(data)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connector::config_file_format::{FileCompression, FileFormat};
+    use crate::connector::config_kafka::KafkaConnectorConfig;
+    use crate::connector::config_local_file::RotationStrategy;
+    use crate::connector::config_mqtt::{MqttBridgeDirection, MqttProtocolVersion};
+
+    #[derive(Serialize)]
+    struct LegacyMQTTConnector<T> {
+        tenant: String,
+        connector_name: String,
+        connector_type: T,
+        failure_strategy: FailureHandlingStrategy,
+        topic_name: String,
+        status: MQTTStatus,
+        etl_rule: ETLRule,
+        broker_id: Option<u64>,
+        create_time: u64,
+        update_time: u64,
+    }
+
+    fn legacy_connector<T>(connector_type: T) -> LegacyMQTTConnector<T> {
+        LegacyMQTTConnector {
+            tenant: "default".to_string(),
+            connector_name: "c1".to_string(),
+            connector_type,
+            failure_strategy: FailureHandlingStrategy::Discard,
+            topic_name: "t1".to_string(),
+            status: MQTTStatus::Running,
+            etl_rule: ETLRule::default(),
+            broker_id: Some(1),
+            create_time: 10,
+            update_time: 20,
+        }
+    }
+
+    #[test]
+    fn test_decode_legacy_connector() {
+        let kafka = ConnectorType::Kafka(KafkaConnectorConfig {
+            bootstrap_servers: "127.0.0.1:9092".to_string(),
+            topic: "k1".to_string(),
+            ..Default::default()
+        });
+        let legacy = legacy_connector(kafka.clone());
+
+        let connector = MQTTConnector::decode(&serialize::serialize(&legacy).unwrap()).unwrap();
+        assert_eq!(connector.tenant, "default");
+        assert_eq!(connector.connector_name, "c1");
+        assert_eq!(connector.connector_type, kafka);
+        assert_eq!(connector.status, MQTTStatus::Running);
+        assert_eq!(connector.broker_id, Some(1));
+        assert_eq!(connector.update_time, 20);
+        assert_eq!(connector.max_records_per_sec, None);
+
+        let throttled = MQTTConnector {
+            max_records_per_sec: Some(100),
+            ..connector
+        };
+        assert_eq!(
+            MQTTConnector::decode(&throttled.encode().unwrap()).unwrap(),
+            throttled
+        );
+    }
+
+    #[derive(Serialize)]
+    #[allow(dead_code)]
+    enum LegacyConnectorType {
+        Kafka,
+        LocalFile(LegacyLocalFileConfig),
+        GreptimeDB,
+        Pulsar,
+        Postgres,
+        MongoDB,
+        RabbitMQ,
+        MySQL,
+        Elasticsearch,
+        Redis,
+        Webhook,
+        OpenTSDB,
+        MqttBridge(LegacyMqttBridgeConfig),
+        ClickHouse,
+        InfluxDB,
+        Cassandra,
+        S3(LegacyS3Config),
+    }
+
+    #[derive(Serialize)]
+    struct LegacyLocalFileConfig {
+        local_file_path: String,
+        rotation_strategy: RotationStrategy,
+        max_size_gb: u64,
+    }
+
+    #[derive(Serialize)]
+    struct LegacyS3Config {
+        bucket: String,
+        region: String,
+        endpoint: String,
+        access_key_id: String,
+        secret_access_key: String,
+        session_token: String,
+        root: String,
+        object_key_prefix: String,
+        file_extension: String,
+    }
+
+    #[derive(Serialize)]
+    struct LegacyMqttBridgeConfig {
+        server: String,
+        client_id_prefix: Option<String>,
+        username: Option<String>,
+        password: Option<String>,
+        protocol_version: MqttProtocolVersion,
+        keepalive_secs: u64,
+        connect_timeout_secs: u64,
+        enable_tls: bool,
+        topic_prefix: Option<String>,
+        qos: i32,
+        retain: bool,
+        max_retries: u32,
+    }
+
+    #[test]
+    fn test_decode_legacy_mqtt_bridge_connector() {
+        let legacy = legacy_connector(LegacyConnectorType::MqttBridge(LegacyMqttBridgeConfig {
+            server: "tcp://127.0.0.1:1883".to_string(),
+            client_id_prefix: None,
+            username: Some("user".to_string()),
+            password: Some("pass".to_string()),
+            protocol_version: MqttProtocolVersion::V4,
+            keepalive_secs: 30,
+            connect_timeout_secs: 5,
+            enable_tls: false,
+            topic_prefix: Some("bridge/".to_string()),
+            qos: 1,
+            retain: false,
+            max_retries: 3,
+        }));
+
+        let connector = MQTTConnector::decode(&serialize::serialize(&legacy).unwrap()).unwrap();
+        let ConnectorType::MqttBridge(config) = connector.connector_type else {
+            panic!("expected an MQTT bridge connector");
+        };
+        assert_eq!(config.server, "tcp://127.0.0.1:1883");
+        assert_eq!(config.username.as_deref(), Some("user"));
+        assert_eq!(config.protocol_version, MqttProtocolVersion::V4);
+        assert_eq!(config.topic_prefix.as_deref(), Some("bridge/"));
+        assert_eq!(config.direction, MqttBridgeDirection::Out);
+        assert!(config.topic_mappings.is_empty());
+        assert!(config.clean_session);
+        assert_eq!(connector.create_time, 10);
+    }
+
+    #[test]
+    fn test_decode_legacy_file_connectors() {
+        let legacy = legacy_connector(LegacyConnectorType::LocalFile(LegacyLocalFileConfig {
+            local_file_path: "/tmp/mqtt.log".to_string(),
+            rotation_strategy: RotationStrategy::Daily,
+            max_size_gb: 2,
+        }));
+        let connector = MQTTConnector::decode(&serialize::serialize(&legacy).unwrap()).unwrap();
+        let ConnectorType::LocalFile(config) = connector.connector_type else {
+            panic!("expected a local file connector");
+        };
+        assert_eq!(config.local_file_path, "/tmp/mqtt.log");
+        assert_eq!(config.rotation_strategy, RotationStrategy::Daily);
+        assert_eq!(config.max_size_gb, 2);
+        assert_eq!(config.format, FileFormat::Raw);
+        assert_eq!(config.compression, FileCompression::None);
+
+        let legacy = legacy_connector(LegacyConnectorType::S3(LegacyS3Config {
+            bucket: "b1".to_string(),
+            region: "us-east-1".to_string(),
+            endpoint: String::new(),
+            access_key_id: "ak".to_string(),
+            secret_access_key: "sk".to_string(),
+            session_token: String::new(),
+            root: String::new(),
+            object_key_prefix: "mqtt".to_string(),
+            file_extension: "json".to_string(),
+        }));
+        let connector = MQTTConnector::decode(&serialize::serialize(&legacy).unwrap()).unwrap();
+        let ConnectorType::S3(config) = connector.connector_type else {
+            panic!("expected an S3 connector");
+        };
+        assert_eq!(config.bucket, "b1");
+        assert_eq!(config.secret_access_key, "sk");
+        assert_eq!(config.file_extension, "json");
+        assert_eq!(config.format, FileFormat::Json);
+        assert_eq!(config.roll_size_mb, 0);
+        assert!(config.staging_dir.is_empty());
+    }
+}
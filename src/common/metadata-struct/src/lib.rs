@@ -27,3 +27,4 @@
 pub mod storage;
 pub mod tenant;
 pub mod topic;
+mod versioned;
@@ -0,0 +1,163 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Resource types streamed by the meta service `BootstrapCache` RPC, which a
+//! broker uses to load a full metadata snapshot on startup or rejoin.
+
+use common_base::error::common::CommonError;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BootstrapResource {
+    Tenant,
+    User,
+    Acl,
+    Blacklist,
+    Topic,
+    TopicRewriteRule,
+    AutoSubscribeRule,
+    MessageRule,
+    Session,
+    Subscribe,
+    Connector,
+    Schema,
+    SchemaBind,
+    ShareGroup,
+    ShareGroupMember,
+    NatsSubscribe,
+    Mq9Mail,
+    Mq9Agent,
+}
+
+impl BootstrapResource {
+    /// All resource types, in the order they are streamed. Tenants come
+    /// first so that tenant-scoped resources always find their tenant.
+    pub const ALL: [BootstrapResource; 18] = [
+        BootstrapResource::Tenant,
+        BootstrapResource::User,
+        BootstrapResource::Acl,
+        BootstrapResource::Blacklist,
+        BootstrapResource::Topic,
+        BootstrapResource::TopicRewriteRule,
+        BootstrapResource::AutoSubscribeRule,
+        BootstrapResource::MessageRule,
+        BootstrapResource::Session,
+        BootstrapResource::Subscribe,
+        BootstrapResource::Connector,
+        BootstrapResource::Schema,
+        BootstrapResource::SchemaBind,
+        BootstrapResource::ShareGroup,
+        BootstrapResource::ShareGroupMember,
+        BootstrapResource::NatsSubscribe,
+        BootstrapResource::Mq9Mail,
+        BootstrapResource::Mq9Agent,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BootstrapResource::Tenant => "tenant",
+            BootstrapResource::User => "user",
+            BootstrapResource::Acl => "acl",
+            BootstrapResource::Blacklist => "blacklist",
+            BootstrapResource::Topic => "topic",
+            BootstrapResource::TopicRewriteRule => "topic_rewrite_rule",
+            BootstrapResource::AutoSubscribeRule => "auto_subscribe_rule",
+            BootstrapResource::MessageRule => "message_rule",
+            BootstrapResource::Session => "session",
+            BootstrapResource::Subscribe => "subscribe",
+            BootstrapResource::Connector => "connector",
+            BootstrapResource::Schema => "schema",
+            BootstrapResource::SchemaBind => "schema_bind",
+            BootstrapResource::ShareGroup => "share_group",
+            BootstrapResource::ShareGroupMember => "share_group_member",
+            BootstrapResource::NatsSubscribe => "nats_subscribe",
+            BootstrapResource::Mq9Mail => "mq9_mail",
+            BootstrapResource::Mq9Agent => "mq9_agent",
+        }
+    }
+
+    /// Parse the requested resource types, keeping the order of [`Self::ALL`]
+    /// and dropping duplicates. An empty list selects every resource type.
+    pub fn parse_list(names: &[String]) -> Result<Vec<BootstrapResource>, CommonError> {
+        if names.is_empty() {
+            return Ok(Self::ALL.to_vec());
+        }
+        let requested = names
+            .iter()
+            .map(|name| name.parse())
+            .collect::<Result<Vec<BootstrapResource>, _>>()?;
+        Ok(Self::ALL
+            .into_iter()
+            .filter(|r| requested.contains(r))
+            .collect())
+    }
+}
+
+impl FromStr for BootstrapResource {
+    type Err = CommonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BootstrapResource::ALL
+            .into_iter()
+            .find(|r| r.as_str() == s)
+            .ok_or_else(|| {
+                CommonError::InvalidParameterFormat(
+                    "resource_type".to_string(),
+                    format!("unknown bootstrap resource type: {}", s),
+                )
+            })
+    }
+}
+
+impl fmt::Display for BootstrapResource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bootstrap_resource_round_trip() {
+        for resource in BootstrapResource::ALL {
+            assert_eq!(
+                resource.as_str().parse::<BootstrapResource>().unwrap(),
+                resource
+            );
+        }
+        assert!("unknown".parse::<BootstrapResource>().is_err());
+    }
+
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(
+            BootstrapResource::parse_list(&[]).unwrap(),
+            BootstrapResource::ALL.to_vec()
+        );
+
+        let names = vec![
+            "topic".to_string(),
+            "tenant".to_string(),
+            "topic".to_string(),
+        ];
+        assert_eq!(
+            BootstrapResource::parse_list(&names).unwrap(),
+            vec![BootstrapResource::Tenant, BootstrapResource::Topic]
+        );
+
+        assert!(BootstrapResource::parse_list(&["bad".to_string()]).is_err());
+    }
+}
@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod bootstrap;
 pub mod extend;
 pub mod node;
 pub mod status;
+pub mod watch;
@@ -13,11 +13,13 @@
 // limitations under the License.
 
 use common_base::{error::common::CommonError, utils::serialize};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::meta::extend::NodeExtend;
+use crate::versioned::{versioned_serde, Versioned};
 
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub struct BrokerNode {
     pub roles: Vec<String>,
     pub extend: NodeExtend,
@@ -29,8 +31,62 @@ pub struct BrokerNode {
     pub start_time: u64,
     pub register_time: u64,
     pub storage_fold: Vec<String>,
+    /// Failure domains of the node, empty when not labelled. Segment replicas
+    /// and connectors are spread across zones first, then racks.
+    #[serde(default)]
+    pub zone: String,
+    #[serde(default)]
+    pub rack: String,
 }
 
+/// Fields of [`BrokerNode`] after `roles`, as laid out before `zone` and
+/// `rack` were added.
+#[derive(Deserialize)]
+pub(crate) struct BrokerNodeV1 {
+    extend: NodeExtend,
+    node_id: u64,
+    node_ip: String,
+    grpc_addr: String,
+    http_addr: String,
+    engine_addr: String,
+    start_time: u64,
+    register_time: u64,
+    storage_fold: Vec<String>,
+}
+
+impl Versioned for BrokerNode {
+    const VERSION: u64 = 2;
+    type LegacyHead = String;
+    type Legacy = BrokerNodeV1;
+
+    fn from_legacy(roles: Vec<String>, legacy: BrokerNodeV1) -> Result<Self, String> {
+        Ok(BrokerNode {
+            roles,
+            extend: legacy.extend,
+            node_id: legacy.node_id,
+            node_ip: legacy.node_ip,
+            grpc_addr: legacy.grpc_addr,
+            http_addr: legacy.http_addr,
+            engine_addr: legacy.engine_addr,
+            start_time: legacy.start_time,
+            register_time: legacy.register_time,
+            storage_fold: legacy.storage_fold,
+            zone: String::new(),
+            rack: String::new(),
+        })
+    }
+
+    fn serialize_fields<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        BrokerNode::serialize(self, serializer)
+    }
+
+    fn deserialize_fields<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        BrokerNode::deserialize(deserializer)
+    }
+}
+
+versioned_serde!(BrokerNode);
+
 impl BrokerNode {
     pub fn encode(&self) -> Result<Vec<u8>, CommonError> {
         serialize::serialize(self)
@@ -40,3 +96,56 @@ pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
         serialize::deserialize(data)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct LegacyBrokerNode {
+        roles: Vec<String>,
+        extend: NodeExtend,
+        node_id: u64,
+        node_ip: String,
+        grpc_addr: String,
+        http_addr: String,
+        engine_addr: String,
+        start_time: u64,
+        register_time: u64,
+        storage_fold: Vec<String>,
+    }
+
+    #[test]
+    fn test_decode_legacy_broker_node() {
+        let legacy = LegacyBrokerNode {
+            roles: vec!["broker".to_string(), "meta".to_string()],
+            extend: NodeExtend::default(),
+            node_id: 2,
+            node_ip: "127.0.0.1".to_string(),
+            grpc_addr: "127.0.0.1:1228".to_string(),
+            http_addr: "127.0.0.1:8080".to_string(),
+            engine_addr: "127.0.0.1:1778".to_string(),
+            start_time: 10,
+            register_time: 20,
+            storage_fold: vec!["/data".to_string()],
+        };
+
+        let node = BrokerNode::decode(&serialize::serialize(&legacy).unwrap()).unwrap();
+        assert_eq!(node.roles, vec!["broker".to_string(), "meta".to_string()]);
+        assert_eq!(node.node_id, 2);
+        assert_eq!(node.grpc_addr, "127.0.0.1:1228");
+        assert_eq!(node.storage_fold, vec!["/data".to_string()]);
+        assert!(node.zone.is_empty());
+        assert!(node.rack.is_empty());
+
+        let labelled = BrokerNode {
+            zone: "az1".to_string(),
+            rack: "r1".to_string(),
+            ..node
+        };
+        let decoded = BrokerNode::decode(&labelled.encode().unwrap()).unwrap();
+        assert_eq!(decoded.node_id, 2);
+        assert_eq!(decoded.zone, "az1");
+        assert_eq!(decoded.rack, "r1");
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Resource and event types streamed by the meta service `WatchResources`
+//! RPC, which external controllers use to follow metadata changes.
+
+use common_base::error::common::CommonError;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WatchResource {
+    User,
+    Topic,
+    Session,
+    Connector,
+}
+
+impl WatchResource {
+    pub const ALL: [WatchResource; 4] = [
+        WatchResource::User,
+        WatchResource::Topic,
+        WatchResource::Session,
+        WatchResource::Connector,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WatchResource::User => "user",
+            WatchResource::Topic => "topic",
+            WatchResource::Session => "session",
+            WatchResource::Connector => "connector",
+        }
+    }
+
+    /// Parse the requested resource types, dropping duplicates. An empty
+    /// list selects every resource type.
+    pub fn parse_list(names: &[String]) -> Result<Vec<WatchResource>, CommonError> {
+        if names.is_empty() {
+            return Ok(Self::ALL.to_vec());
+        }
+        let requested = names
+            .iter()
+            .map(|name| name.parse())
+            .collect::<Result<Vec<WatchResource>, _>>()?;
+        Ok(Self::ALL
+            .into_iter()
+            .filter(|r| requested.contains(r))
+            .collect())
+    }
+}
+
+impl FromStr for WatchResource {
+    type Err = CommonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        WatchResource::ALL
+            .into_iter()
+            .find(|r| r.as_str() == s)
+            .ok_or_else(|| {
+                CommonError::InvalidParameterFormat(
+                    "resource_type".to_string(),
+                    format!("unknown watch resource type: {}", s),
+                )
+            })
+    }
+}
+
+impl fmt::Display for WatchResource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchEventType {
+    /// Carries only a revision. Sent first on every stream so that a client
+    /// always has a token to resume from, even before any change arrives.
+    Bookmark,
+    Create,
+    Update,
+    Delete,
+}
+
+impl WatchEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WatchEventType::Bookmark => "bookmark",
+            WatchEventType::Create => "create",
+            WatchEventType::Update => "update",
+            WatchEventType::Delete => "delete",
+        }
+    }
+}
+
+impl FromStr for WatchEventType {
+    type Err = CommonError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bookmark" => Ok(WatchEventType::Bookmark),
+            "create" => Ok(WatchEventType::Create),
+            "update" => Ok(WatchEventType::Update),
+            "delete" => Ok(WatchEventType::Delete),
+            _ => Err(CommonError::InvalidParameterFormat(
+                "event_type".to_string(),
+                format!("unknown watch event type: {}", s),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for WatchEventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watch_resource_round_trip() {
+        for resource in WatchResource::ALL {
+            assert_eq!(
+                resource.as_str().parse::<WatchResource>().unwrap(),
+                resource
+            );
+        }
+        assert!("acl".parse::<WatchResource>().is_err());
+    }
+
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(
+            WatchResource::parse_list(&[]).unwrap(),
+            WatchResource::ALL.to_vec()
+        );
+
+        let names = vec![
+            "session".to_string(),
+            "user".to_string(),
+            "session".to_string(),
+        ];
+        assert_eq!(
+            WatchResource::parse_list(&names).unwrap(),
+            vec![WatchResource::User, WatchResource::Session]
+        );
+    }
+
+    #[test]
+    fn test_watch_event_type_round_trip() {
+        for event_type in [
+            WatchEventType::Bookmark,
+            WatchEventType::Create,
+            WatchEventType::Update,
+            WatchEventType::Delete,
+        ] {
+            assert_eq!(
+                event_type.as_str().parse::<WatchEventType>().unwrap(),
+                event_type
+            );
+        }
+    }
+}
@@ -15,6 +15,7 @@
 use common_base::tools::now_second;
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct MQTTConnection {
@@ -48,6 +49,8 @@ pub struct MQTTConnection {
     pub request_problem_info: u8,
     // Time when the connection was created
     pub create_time: u64,
+    // Client attributes of the session, used by ACL placeholders and rules
+    pub attributes: BTreeMap<String, String>,
 }
 
 pub struct ConnectionConfig {
@@ -82,6 +85,7 @@ pub fn new(config: ConnectionConfig) -> MQTTConnection {
             source_ip: config.source_ip,
             clean_session: config.clean_session,
             login_user: None,
+            attributes: BTreeMap::new(),
         }
     }
 
@@ -12,18 +12,61 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::versioned::{utf8_head, versioned_serde, Versioned};
 use common_base::{error::common::CommonError, utils::serialize};
 use protocol::mqtt::common::{LastWill, LastWillProperties};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(remote = "Self")]
 pub struct MqttLastWillData {
     pub tenant: String,
     pub client_id: String,
     pub last_will: Option<LastWill>,
     pub last_will_properties: Option<LastWillProperties>,
+    /// Allocated by Meta Service when the will is registered, see
+    /// [`MqttLastWillClaim::next_epoch`]. Every dispatch of the will for one
+    /// session termination carries the same epoch, 0 for wills saved by older
+    /// brokers.
+    #[serde(default)]
+    pub session_epoch: u64,
 }
 
+/// Fields of [`MqttLastWillData`] after `tenant`, as laid out before
+/// `session_epoch` was added.
+#[derive(Deserialize)]
+pub(crate) struct MqttLastWillDataV1 {
+    client_id: String,
+    last_will: Option<LastWill>,
+    last_will_properties: Option<LastWillProperties>,
+}
+
+impl Versioned for MqttLastWillData {
+    const VERSION: u64 = 2;
+    type LegacyHead = u8;
+    type Legacy = MqttLastWillDataV1;
+
+    fn from_legacy(head: Vec<u8>, legacy: MqttLastWillDataV1) -> Result<Self, String> {
+        Ok(MqttLastWillData {
+            tenant: utf8_head(head)?,
+            client_id: legacy.client_id,
+            last_will: legacy.last_will,
+            last_will_properties: legacy.last_will_properties,
+            session_epoch: 0,
+        })
+    }
+
+    fn serialize_fields<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        MqttLastWillData::serialize(self, serializer)
+    }
+
+    fn deserialize_fields<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        MqttLastWillData::deserialize(deserializer)
+    }
+}
+
+versioned_serde!(MqttLastWillData);
+
 impl MqttLastWillData {
     pub fn encode(&self) -> Result<Vec<u8>, CommonError> {
         serialize::serialize(self)
@@ -33,3 +76,109 @@ pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
         serialize::deserialize(data)
     }
 }
+
+/// Will epochs of a client: the last one allocated and the one whose will a
+/// broker was granted the right to publish.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct MqttLastWillClaim {
+    pub tenant: String,
+    pub client_id: String,
+    /// Claimed epoch, 0 until a will of the client is claimed.
+    pub session_epoch: u64,
+    pub broker_id: u64,
+    pub claim_time: u64,
+    pub allocated_epoch: u64,
+}
+
+impl MqttLastWillClaim {
+    /// Epoch for the next will registered by the client. Above every epoch
+    /// allocated or claimed so far, so a new registration always wins over
+    /// older ones.
+    pub fn next_epoch(&self) -> u64 {
+        self.allocated_epoch.max(self.session_epoch) + 1
+    }
+
+    /// Whether `broker_id` may publish the will of `session_epoch` given this
+    /// existing claim: a newer epoch is a new session termination, and the
+    /// broker already holding the claim gets it again when it retries.
+    pub fn grants(&self, session_epoch: u64, broker_id: u64) -> bool {
+        session_epoch > self.session_epoch
+            || (session_epoch == self.session_epoch && broker_id == self.broker_id)
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, CommonError> {
+        serialize::serialize(self)
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
+        serialize::deserialize(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_will_claim_grants() {
+        let claim = MqttLastWillClaim {
+            tenant: "default".to_string(),
+            client_id: "c1".to_string(),
+            session_epoch: 100,
+            broker_id: 1,
+            claim_time: 0,
+            allocated_epoch: 100,
+        };
+
+        assert!(claim.grants(100, 1));
+        assert!(!claim.grants(100, 2));
+        assert!(!claim.grants(99, 1));
+        assert!(claim.grants(101, 2));
+        assert_eq!(claim.next_epoch(), 101);
+
+        let claimed_only = MqttLastWillClaim {
+            allocated_epoch: 0,
+            ..claim
+        };
+        assert_eq!(claimed_only.next_epoch(), 101);
+    }
+
+    #[derive(Serialize)]
+    struct LegacyMqttLastWillData {
+        tenant: String,
+        client_id: String,
+        last_will: Option<LastWill>,
+        last_will_properties: Option<LastWillProperties>,
+    }
+
+    #[test]
+    fn test_decode_legacy_last_will() {
+        let last_will = LastWill {
+            topic: "will/c1".into(),
+            message: "bye".into(),
+            qos: protocol::mqtt::common::QoS::AtLeastOnce,
+            retain: false,
+        };
+        let legacy = LegacyMqttLastWillData {
+            tenant: "default".to_string(),
+            client_id: "c1".to_string(),
+            last_will: Some(last_will.clone()),
+            last_will_properties: None,
+        };
+
+        let data = MqttLastWillData::decode(&serialize::serialize(&legacy).unwrap()).unwrap();
+        assert_eq!(data.tenant, "default");
+        assert_eq!(data.client_id, "c1");
+        assert_eq!(data.last_will, Some(last_will));
+        assert_eq!(data.session_epoch, 0);
+
+        let current = MqttLastWillData {
+            session_epoch: 7,
+            ..data
+        };
+        assert_eq!(
+            MqttLastWillData::decode(&current.encode().unwrap()).unwrap(),
+            current
+        );
+    }
+}
@@ -0,0 +1,55 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::{error::common::CommonError, utils::serialize};
+use serde::{Deserialize, Serialize};
+
+/// A SQL-like rule evaluated against every message published to a topic
+/// matching its `FROM` clause.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct MqttMessageRule {
+    pub tenant: String,
+    pub name: String,
+    pub desc: String,
+    pub sql: String,
+    pub actions: Vec<MqttMessageRuleAction>,
+    pub enable: bool,
+    pub create_time: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MqttMessageRuleAction {
+    /// Publish the rule output (JSON) to `topic`. `${topic}` and
+    /// `${clientid}` are replaced with values from the source message.
+    Republish {
+        topic: String,
+        qos: u8,
+        retain: bool,
+    },
+    /// Write the rule output to the topic consumed by the named connector.
+    Connector { connector_name: String },
+    /// Stop the source message from being stored and delivered.
+    Drop,
+}
+
+impl MqttMessageRule {
+    pub fn encode(&self) -> Result<Vec<u8>, CommonError> {
+        serialize::serialize(self)
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
+        serialize::deserialize(data)
+    }
+}
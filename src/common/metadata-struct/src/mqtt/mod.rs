@@ -16,9 +16,11 @@
 pub mod auto_subscribe;
 pub mod connection;
 pub mod lastwill;
+pub mod message_rule;
 pub mod retain_message;
 pub mod session;
 pub mod share_group;
 pub mod subscribe;
+pub mod system_alarm;
 pub use crate::topic;
 pub mod topic_rewrite_rule;
@@ -34,3 +34,32 @@ pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
         serialize::deserialize(data)
     }
 }
+
+/// Meta-service side record of a retained message. The payload itself lives in
+/// the storage adapter; the index only tells subscribers which topics hold a
+/// retained message and which version of it is current.
+#[derive(Clone, Default, Serialize, Deserialize, Debug, PartialEq)]
+pub struct MQTTRetainIndex {
+    pub tenant: String,
+    pub topic_name: String,
+    pub payload_size: u64,
+    pub expired_at: u64,
+    /// Millisecond timestamp of the publish that set the message. Brokers use
+    /// it to tell whether a cached payload is still current.
+    pub version: u64,
+    pub create_time: u64,
+}
+
+impl MQTTRetainIndex {
+    pub fn encode(&self) -> Result<Vec<u8>, CommonError> {
+        serialize::serialize(self)
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
+        serialize::deserialize(data)
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expired_at > 0 && now >= self.expired_at
+    }
+}
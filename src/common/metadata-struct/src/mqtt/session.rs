@@ -12,10 +12,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::versioned::{utf8_head, versioned_serde, Versioned};
 use common_base::{error::common::CommonError, tools::now_second, utils::serialize};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
 
 #[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(remote = "Self")]
 pub struct MqttSession {
     pub tenant: String,
     pub client_id: String,
@@ -29,8 +32,67 @@ pub struct MqttSession {
     pub broker_id: Option<u64>,
     pub reconnect_time: Option<u64>,
     pub distinct_time: Option<u64>,
+
+    /// Client attributes, such as device model or firmware version, set
+    /// from CONNECT or through the admin API.
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
+
+    /// The client id was generated by the broker and returned to the client
+    /// as the Assigned Client Identifier.
+    #[serde(default)]
+    pub assigned_client_id: bool,
+}
+
+/// Fields of [`MqttSession`] after `tenant`, as laid out before client
+/// attributes and `assigned_client_id` were added.
+#[derive(Deserialize)]
+pub(crate) struct MqttSessionV1 {
+    client_id: String,
+    session_expiry_interval: u64,
+    is_contain_last_will: bool,
+    last_will_delay_interval: Option<u64>,
+    create_time: u64,
+    is_persist_session: bool,
+    connection_id: Option<u64>,
+    broker_id: Option<u64>,
+    reconnect_time: Option<u64>,
+    distinct_time: Option<u64>,
 }
 
+impl Versioned for MqttSession {
+    const VERSION: u64 = 2;
+    type LegacyHead = u8;
+    type Legacy = MqttSessionV1;
+
+    fn from_legacy(head: Vec<u8>, legacy: MqttSessionV1) -> Result<Self, String> {
+        Ok(MqttSession {
+            tenant: utf8_head(head)?,
+            client_id: legacy.client_id,
+            session_expiry_interval: legacy.session_expiry_interval,
+            is_contain_last_will: legacy.is_contain_last_will,
+            last_will_delay_interval: legacy.last_will_delay_interval,
+            create_time: legacy.create_time,
+            is_persist_session: legacy.is_persist_session,
+            connection_id: legacy.connection_id,
+            broker_id: legacy.broker_id,
+            reconnect_time: legacy.reconnect_time,
+            distinct_time: legacy.distinct_time,
+            ..Default::default()
+        })
+    }
+
+    fn serialize_fields<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        MqttSession::serialize(self, serializer)
+    }
+
+    fn deserialize_fields<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        MqttSession::deserialize(deserializer)
+    }
+}
+
+versioned_serde!(MqttSession);
+
 impl MqttSession {
     pub fn new(
         tenant: String,
@@ -52,6 +114,8 @@ pub fn new(
             broker_id: None,
             reconnect_time: None,
             distinct_time: None,
+            attributes: BTreeMap::new(),
+            assigned_client_id: false,
         }
     }
 
@@ -83,3 +147,78 @@ pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
         serialize::deserialize(data)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct LegacyMqttSession {
+        tenant: String,
+        client_id: String,
+        session_expiry_interval: u64,
+        is_contain_last_will: bool,
+        last_will_delay_interval: Option<u64>,
+        create_time: u64,
+        is_persist_session: bool,
+        connection_id: Option<u64>,
+        broker_id: Option<u64>,
+        reconnect_time: Option<u64>,
+        distinct_time: Option<u64>,
+    }
+
+    #[test]
+    fn test_decode_legacy_session() {
+        let legacy = LegacyMqttSession {
+            tenant: "default".to_string(),
+            client_id: "c1".to_string(),
+            session_expiry_interval: 60,
+            is_contain_last_will: true,
+            last_will_delay_interval: Some(5),
+            create_time: 10,
+            is_persist_session: true,
+            connection_id: Some(3),
+            broker_id: Some(1),
+            reconnect_time: None,
+            distinct_time: Some(20),
+        };
+
+        let session = MqttSession::decode(&serialize::serialize(&legacy).unwrap()).unwrap();
+        assert_eq!(session.tenant, "default");
+        assert_eq!(session.client_id, "c1");
+        assert_eq!(session.last_will_delay_interval, Some(5));
+        assert_eq!(session.broker_id, Some(1));
+        assert_eq!(session.distinct_time, Some(20));
+        assert!(session.attributes.is_empty());
+        assert!(!session.assigned_client_id);
+    }
+
+    #[test]
+    fn test_encode_decode_session() {
+        let mut session = MqttSession::new(
+            "default".to_string(),
+            "c1".to_string(),
+            60,
+            false,
+            None,
+            true,
+        );
+        session
+            .attributes
+            .insert("model".to_string(), "x1".to_string());
+        session.assigned_client_id = true;
+
+        assert_eq!(
+            MqttSession::decode(&session.encode().unwrap()).unwrap(),
+            session
+        );
+
+        let json = serde_json::to_value(&session).unwrap();
+        assert_eq!(json["attributes"]["model"], "x1");
+        assert_eq!(json["assigned_client_id"], true);
+        assert_eq!(
+            serde_json::from_value::<MqttSession>(json).unwrap(),
+            session
+        );
+    }
+}
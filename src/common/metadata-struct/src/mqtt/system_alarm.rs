@@ -0,0 +1,146 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::versioned::{utf8_head, versioned_serde, Versioned};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Default, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SystemAlarmDetails {
+    pub usage: f32,
+    pub high_watermark: f32,
+    pub low_watermark: f32,
+}
+
+/// A CPU, memory or config drift alarm transition, published on the sysmon
+/// system topics and kept in the broker's local storage.
+#[derive(Default, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(remote = "Self")]
+pub struct SystemAlarmEventMessage {
+    pub name: String,
+    pub message: String,
+    /// JSON field name is "activate_at" to align with EMQX system topic format.
+    /// "create_time" is kept as alias for backward-compatible deserialization from storage.
+    #[serde(rename = "activate_at", alias = "create_time")]
+    pub create_time: u64,
+    /// Set on deactivation events.
+    #[serde(default)]
+    pub deactivate_at: Option<u64>,
+    pub activated: bool,
+    #[serde(default)]
+    pub details: Option<SystemAlarmDetails>,
+}
+
+/// Fields of [`SystemAlarmEventMessage`] after `name`, as laid out before
+/// `deactivate_at` and `details` were added.
+#[derive(Deserialize)]
+pub(crate) struct SystemAlarmEventMessageV1 {
+    message: String,
+    create_time: u64,
+    activated: bool,
+}
+
+impl Versioned for SystemAlarmEventMessage {
+    const VERSION: u64 = 2;
+    type LegacyHead = u8;
+    type Legacy = SystemAlarmEventMessageV1;
+
+    fn from_legacy(head: Vec<u8>, legacy: SystemAlarmEventMessageV1) -> Result<Self, String> {
+        Ok(SystemAlarmEventMessage {
+            name: utf8_head(head)?,
+            message: legacy.message,
+            create_time: legacy.create_time,
+            deactivate_at: None,
+            activated: legacy.activated,
+            details: None,
+        })
+    }
+
+    fn serialize_fields<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SystemAlarmEventMessage::serialize(self, serializer)
+    }
+
+    fn deserialize_fields<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        SystemAlarmEventMessage::deserialize(deserializer)
+    }
+}
+
+versioned_serde!(SystemAlarmEventMessage);
+
+impl SystemAlarmEventMessage {
+    /// Time of the transition the event records.
+    pub fn event_time(&self) -> u64 {
+        self.deactivate_at.unwrap_or(self.create_time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common_base::utils::serialize;
+
+    #[derive(Serialize)]
+    struct LegacySystemAlarmEventMessage {
+        name: String,
+        message: String,
+        create_time: u64,
+        activated: bool,
+    }
+
+    #[test]
+    fn test_decode_legacy_event() {
+        let legacy = LegacySystemAlarmEventMessage {
+            name: "HighCpuUsage".to_string(),
+            message: "cpu".to_string(),
+            create_time: 10,
+            activated: true,
+        };
+
+        let event: SystemAlarmEventMessage =
+            serialize::deserialize(&serialize::serialize(&legacy).unwrap()).unwrap();
+        assert_eq!(event.name, "HighCpuUsage");
+        assert_eq!(event.message, "cpu");
+        assert_eq!(event.create_time, 10);
+        assert!(event.activated);
+        assert_eq!(event.deactivate_at, None);
+        assert_eq!(event.details, None);
+    }
+
+    #[test]
+    fn test_encode_decode_event() {
+        let event = SystemAlarmEventMessage {
+            name: "HighCpuUsage".to_string(),
+            message: "cpu".to_string(),
+            create_time: 10,
+            deactivate_at: None,
+            activated: true,
+            details: Some(SystemAlarmDetails {
+                usage: 80.0,
+                high_watermark: 70.0,
+                low_watermark: 60.0,
+            }),
+        };
+        let bytes = serialize::serialize(&event).unwrap();
+        assert_eq!(
+            serialize::deserialize::<SystemAlarmEventMessage>(&bytes).unwrap(),
+            event
+        );
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["activate_at"], 10);
+        assert_eq!(
+            serde_json::from_value::<SystemAlarmEventMessage>(json).unwrap(),
+            event
+        );
+    }
+}
@@ -45,7 +45,7 @@ pub fn convert_adapter_record_to_storage(
 
     StorageRecord {
         metadata,
-        protocol_data: record.protocol_data.clone(),
+        protocol_data: record.protocol_data,
         data: record.data,
     }
 }
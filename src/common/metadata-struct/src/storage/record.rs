@@ -174,6 +174,25 @@ pub fn encode(&self) -> Result<Vec<u8>, CommonError> {
     pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
         serialize::deserialize(data)
     }
+
+    /// Like [`decode`](Self::decode), but the payload is a slice of `data`
+    /// instead of a copy of it.
+    pub fn decode_bytes(data: &Bytes) -> Result<Self, CommonError> {
+        // same field order as StorageRecord, Bytes and &[u8] share the encoding
+        #[derive(Deserialize)]
+        struct BorrowedStorageRecord<'a> {
+            metadata: StorageRecordMetadata,
+            protocol_data: Option<StorageRecordProtocolData>,
+            data: &'a [u8],
+        }
+
+        let record: BorrowedStorageRecord = serialize::deserialize_borrowed(data)?;
+        Ok(StorageRecord {
+            metadata: record.metadata,
+            protocol_data: record.protocol_data,
+            data: data.slice_ref(record.data),
+        })
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
@@ -208,3 +227,26 @@ pub struct StorageRecordProtocolDataMq9 {
     pub header: Option<Bytes>,
     pub reply_to: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_bytes_shares_buffer() {
+        let record = StorageRecord {
+            metadata: StorageRecordMetadata::build(3, "shard".to_string(), 0)
+                .with_key(Some("k".to_string())),
+            protocol_data: Some(StorageRecordProtocolData::default()),
+            data: Bytes::from_static(b"payload"),
+        };
+        let encoded = Bytes::from(record.encode().unwrap());
+
+        let decoded = StorageRecord::decode_bytes(&encoded).unwrap();
+        assert_eq!(decoded.metadata.offset, 3);
+        assert_eq!(decoded.metadata.key.as_deref(), Some("k"));
+        assert!(decoded.protocol_data.is_some());
+        assert_eq!(decoded.data, record.data);
+        assert!(encoded.as_ptr_range().contains(&decoded.data.as_ptr()));
+    }
+}
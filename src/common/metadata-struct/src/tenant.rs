@@ -12,12 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::versioned::{utf8_head, versioned_serde, Versioned};
 use common_base::{error::common::CommonError, utils::serialize};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 pub const DEFAULT_TENANT: &str = "default";
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(remote = "Self")]
 pub struct Tenant {
     pub tenant_name: String,
     pub desc: String,
@@ -25,6 +27,41 @@ pub struct Tenant {
     pub create_time: u64,
 }
 
+/// Fields of [`Tenant`] after `tenant_name`, as laid out before
+/// `max_subscriptions` and `max_storage_bytes_per_node` were added to the
+/// config.
+#[derive(Deserialize)]
+pub(crate) struct TenantV1 {
+    desc: String,
+    config: TenantConfigV1,
+    create_time: u64,
+}
+
+impl Versioned for Tenant {
+    const VERSION: u64 = 2;
+    type LegacyHead = u8;
+    type Legacy = TenantV1;
+
+    fn from_legacy(head: Vec<u8>, legacy: TenantV1) -> Result<Self, String> {
+        Ok(Tenant {
+            tenant_name: utf8_head(head)?,
+            desc: legacy.desc,
+            config: legacy.config.into(),
+            create_time: legacy.create_time,
+        })
+    }
+
+    fn serialize_fields<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Tenant::serialize(self, serializer)
+    }
+
+    fn deserialize_fields<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Tenant::deserialize(deserializer)
+    }
+}
+
+versioned_serde!(Tenant);
+
 impl Tenant {
     pub fn encode(&self) -> Result<Vec<u8>, CommonError> {
         serialize::serialize(self)
@@ -36,12 +73,17 @@ pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct TenantConfig {
     pub max_connections_per_node: u64,
     pub max_create_connection_rate_per_second: u32,
     pub max_topics: u64,
     pub max_sessions: u64,
     pub max_publish_rate: u32,
+    pub max_subscriptions: u64,
+    /// Bytes of unexpired messages a tenant may keep in storage, counted per
+    /// broker node. 0 means unlimited.
+    pub max_storage_bytes_per_node: u64,
 }
 
 impl TenantConfig {
@@ -49,8 +91,37 @@ pub fn encode(&self) -> Result<Vec<u8>, CommonError> {
         serialize::serialize(self)
     }
 
+    /// Also accepts configs encoded before `max_subscriptions` and
+    /// `max_storage_bytes_per_node` were added, the missing limits take their
+    /// defaults.
     pub fn decode(data: &[u8]) -> Result<Self, CommonError> {
         serialize::deserialize(data)
+            .or_else(|_| serialize::deserialize::<TenantConfigV1>(data).map(TenantConfig::from))
+    }
+}
+
+/// Layout of [`TenantConfig`] before `max_subscriptions` and
+/// `max_storage_bytes_per_node` were added. bincode has no field defaults, so
+/// configs encoded by older versions are decoded through it.
+#[derive(Deserialize)]
+pub(crate) struct TenantConfigV1 {
+    max_connections_per_node: u64,
+    max_create_connection_rate_per_second: u32,
+    max_topics: u64,
+    max_sessions: u64,
+    max_publish_rate: u32,
+}
+
+impl From<TenantConfigV1> for TenantConfig {
+    fn from(config: TenantConfigV1) -> Self {
+        TenantConfig {
+            max_connections_per_node: config.max_connections_per_node,
+            max_create_connection_rate_per_second: config.max_create_connection_rate_per_second,
+            max_topics: config.max_topics,
+            max_sessions: config.max_sessions,
+            max_publish_rate: config.max_publish_rate,
+            ..Default::default()
+        }
     }
 }
 
@@ -62,6 +133,89 @@ fn default() -> Self {
             max_topics: 5000000,
             max_sessions: 50000000,
             max_publish_rate: 10000,
+            max_subscriptions: 50000000,
+            max_storage_bytes_per_node: 0,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct LegacyTenantConfig {
+        max_connections_per_node: u64,
+        max_create_connection_rate_per_second: u32,
+        max_topics: u64,
+        max_sessions: u64,
+        max_publish_rate: u32,
+    }
+
+    #[derive(Serialize)]
+    struct LegacyTenant {
+        tenant_name: String,
+        desc: String,
+        config: LegacyTenantConfig,
+        create_time: u64,
+    }
+
+    fn legacy_config() -> LegacyTenantConfig {
+        LegacyTenantConfig {
+            max_connections_per_node: 100,
+            max_create_connection_rate_per_second: 10,
+            max_topics: 200,
+            max_sessions: 300,
+            max_publish_rate: 20,
+        }
+    }
+
+    #[test]
+    fn test_decode_legacy_tenant() {
+        let legacy = LegacyTenant {
+            tenant_name: "t1".to_string(),
+            desc: "legacy".to_string(),
+            config: legacy_config(),
+            create_time: 10,
+        };
+
+        let tenant = Tenant::decode(&serialize::serialize(&legacy).unwrap()).unwrap();
+        assert_eq!(tenant.tenant_name, "t1");
+        assert_eq!(tenant.desc, "legacy");
+        assert_eq!(tenant.create_time, 10);
+        assert_eq!(tenant.config.max_connections_per_node, 100);
+        assert_eq!(tenant.config.max_publish_rate, 20);
+        assert_eq!(
+            tenant.config.max_subscriptions,
+            TenantConfig::default().max_subscriptions
+        );
+        assert_eq!(tenant.config.max_storage_bytes_per_node, 0);
+
+        let tenants: Vec<Tenant> =
+            serialize::deserialize(&serialize::serialize(&vec![legacy]).unwrap()).unwrap();
+        assert_eq!(tenants, vec![tenant.clone()]);
+
+        assert_eq!(Tenant::decode(&tenant.encode().unwrap()).unwrap(), tenant);
+    }
+
+    #[test]
+    fn test_decode_legacy_tenant_config() {
+        let config =
+            TenantConfig::decode(&serialize::serialize(&legacy_config()).unwrap()).unwrap();
+        assert_eq!(config.max_sessions, 300);
+        assert_eq!(
+            config.max_subscriptions,
+            TenantConfig::default().max_subscriptions
+        );
+
+        let current = TenantConfig {
+            max_subscriptions: 7,
+            max_storage_bytes_per_node: 1024,
+            ..Default::default()
+        };
+        assert_eq!(
+            TenantConfig::decode(&current.encode().unwrap()).unwrap(),
+            current
+        );
+    }
+}
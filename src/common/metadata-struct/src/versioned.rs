@@ -0,0 +1,160 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Versioned bincode encoding for metadata that is persisted or replicated.
+//!
+//! bincode writes struct fields back to back with no names or defaults, so a
+//! field added to a stored struct makes every record written before it
+//! undecodable. A [`Versioned`] type is instead written as a version marker
+//! followed by its fields, and a record without the marker is decoded through
+//! the legacy layout. Human readable formats such as JSON keep the plain
+//! field map.
+
+use serde::de::{self, DeserializeOwned, SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::marker::PhantomData;
+
+/// Upper bits of the leading `u64` of a versioned record, the low bits hold
+/// the version. A legacy record starts with the length of its first field,
+/// which never comes close to this value.
+const VERSION_MARKER: u64 = 0xFFFF_FFFF_FFFF_0000;
+
+pub(crate) trait Versioned: Sized {
+    /// Version of the current field layout.
+    const VERSION: u64;
+
+    /// Element type of the first field in the legacy layout, `u8` for a
+    /// `String` and `T` for a `Vec<T>`. bincode writes its length first, which
+    /// is where the version marker of a current record sits.
+    type LegacyHead: DeserializeOwned;
+
+    /// The legacy layout without its first field.
+    type Legacy: DeserializeOwned;
+
+    fn from_legacy(head: Vec<Self::LegacyHead>, legacy: Self::Legacy) -> Result<Self, String>;
+
+    /// Writes the fields with the derived implementation, usually generated
+    /// with `#[serde(remote = "Self")]`.
+    fn serialize_fields<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+
+    fn deserialize_fields<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+}
+
+pub(crate) fn utf8_head(head: Vec<u8>) -> Result<String, String> {
+    String::from_utf8(head).map_err(|e| e.to_string())
+}
+
+pub(crate) fn serialize<T: Versioned, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        return value.serialize_fields(serializer);
+    }
+    let mut tuple = serializer.serialize_tuple(2)?;
+    tuple.serialize_element(&(VERSION_MARKER | T::VERSION))?;
+    tuple.serialize_element(&Fields(value))?;
+    tuple.end()
+}
+
+pub(crate) fn deserialize<'de, T: Versioned, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<T, D::Error> {
+    if deserializer.is_human_readable() {
+        return T::deserialize_fields(deserializer);
+    }
+    // The element count of a legacy record depends on the length of its
+    // first field, bincode only needs an upper bound.
+    deserializer.deserialize_tuple(usize::MAX, VersionedVisitor(PhantomData))
+}
+
+/// Implements `Serialize` and `Deserialize` for a [`Versioned`] type.
+macro_rules! versioned_serde {
+    ($ty:ty) => {
+        impl serde::Serialize for $ty {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                $crate::versioned::serialize(self, serializer)
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $ty {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                $crate::versioned::deserialize(deserializer)
+            }
+        }
+    };
+}
+pub(crate) use versioned_serde;
+
+struct Fields<'a, T>(&'a T);
+
+impl<T: Versioned> Serialize for Fields<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize_fields(serializer)
+    }
+}
+
+struct OwnedFields<T>(T);
+
+impl<'de, T: Versioned> Deserialize<'de> for OwnedFields<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize_fields(deserializer).map(OwnedFields)
+    }
+}
+
+struct VersionedVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Versioned> Visitor<'de> for VersionedVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a versioned or legacy record")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<T, A::Error> {
+        let head: u64 = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+
+        if head & VERSION_MARKER == VERSION_MARKER {
+            let version = head & !VERSION_MARKER;
+            if version != T::VERSION {
+                return Err(de::Error::custom(format!(
+                    "unsupported record version {}, expected {}",
+                    version,
+                    T::VERSION
+                )));
+            }
+            let OwnedFields(value) = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+            return Ok(value);
+        }
+
+        let len = usize::try_from(head).map_err(de::Error::custom)?;
+        let mut first = Vec::with_capacity(len.min(4096));
+        for i in 0..len {
+            first.push(
+                seq.next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(i + 1, &self))?,
+            );
+        }
+        let legacy = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(len + 1, &self))?;
+        T::from_legacy(first, legacy).map_err(de::Error::custom)
+    }
+}
@@ -12,7 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{gauge_metric_get, gauge_metric_set, register_gauge_metric};
+use crate::{
+    counter_metric_get, counter_metric_inc, gauge_metric_get, gauge_metric_set,
+    register_counter_metric, register_gauge_metric,
+};
 use prometheus_client::encoding::EncodeLabelSet;
 
 #[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
@@ -24,6 +27,25 @@ pub struct RuntimeLabel {
     pub runtime: String,
 }
 
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct CacheResourceLabel {
+    pub resource_type: String,
+}
+
+register_counter_metric!(
+    BROKER_CACHE_DIVERGENCE_DETECTED,
+    "broker_cache_divergence_detected",
+    "Number of cached records found to differ from the meta service, by resource type",
+    CacheResourceLabel
+);
+
+register_counter_metric!(
+    BROKER_CACHE_DIVERGENCE_REPAIRED,
+    "broker_cache_divergence_repaired",
+    "Number of divergent cached records reloaded from the meta service, by resource type",
+    CacheResourceLabel
+);
+
 register_gauge_metric!(
     TOKIO_RUNTIME_BUSY_RATIO,
     "tokio_runtime_busy_ratio",
@@ -144,6 +166,36 @@ pub fn record_runtime_alive_tasks_set(runtime: &str, value: i64) {
     gauge_metric_set!(TOKIO_RUNTIME_ALIVE_TASKS, label, value);
 }
 
+fn cache_resource_label(resource_type: &str) -> CacheResourceLabel {
+    CacheResourceLabel {
+        resource_type: resource_type.to_string(),
+    }
+}
+
+pub fn record_broker_cache_divergence_detected(resource_type: &str) {
+    let label = cache_resource_label(resource_type);
+    counter_metric_inc!(BROKER_CACHE_DIVERGENCE_DETECTED, label);
+}
+
+pub fn get_broker_cache_divergence_detected(resource_type: &str) -> u64 {
+    let label = cache_resource_label(resource_type);
+    let mut result = 0;
+    counter_metric_get!(BROKER_CACHE_DIVERGENCE_DETECTED, label, result);
+    result
+}
+
+pub fn record_broker_cache_divergence_repaired(resource_type: &str) {
+    let label = cache_resource_label(resource_type);
+    counter_metric_inc!(BROKER_CACHE_DIVERGENCE_REPAIRED, label);
+}
+
+pub fn get_broker_cache_divergence_repaired(resource_type: &str) -> u64 {
+    let label = cache_resource_label(resource_type);
+    let mut result = 0;
+    counter_metric_get!(BROKER_CACHE_DIVERGENCE_REPAIRED, label, result);
+    result
+}
+
 /// Pre-register all gauge metrics in this module to 0 so they appear in
 /// the Prometheus output immediately on startup.
 pub fn init() {
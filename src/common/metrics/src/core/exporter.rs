@@ -0,0 +1,52 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::core::server::dump_metrics;
+use axum::{routing::get, Router};
+use tokio::sync::broadcast;
+use tracing::{error, info};
+
+pub const METRICS_EXPORTER_PATH: &str = "/metrics";
+
+/// Serve `/metrics` on a dedicated port, for scrapers that must not reach the
+/// admin API. Returns when `stop_send` broadcasts `true`.
+pub async fn start_metrics_exporter(port: u32, stop_send: broadcast::Sender<bool>) {
+    let addr = format!("0.0.0.0:{port}");
+    let listener = match tokio::net::TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!(
+                "Failed to bind Prometheus metrics listener on {}: {}",
+                addr, e
+            );
+            return;
+        }
+    };
+    info!("Prometheus metrics listener started on port {}", port);
+
+    let route = Router::new().route(METRICS_EXPORTER_PATH, get(|| async { dump_metrics() }));
+    let mut stop_recv = stop_send.subscribe();
+    let shutdown = async move {
+        while let Ok(flag) = stop_recv.recv().await {
+            if flag {
+                break;
+            }
+        }
+    };
+    if let Err(e) = axum::serve(listener, route)
+        .with_graceful_shutdown(shutdown)
+        .await
+    {
+        error!("Prometheus metrics listener on port {} failed: {}", port, e);
+    }
+}
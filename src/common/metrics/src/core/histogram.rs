@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use prometheus_client::encoding::EncodeLabelSet;
+use prometheus_client::metrics::exemplar::HistogramWithExemplars;
 use prometheus_client::metrics::family::{Family, MetricConstructor};
 use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
 use std::fmt::Debug;
@@ -23,6 +24,11 @@
 
 pub type FamilyHistogram<L> = Arc<RwLock<Family<L, Histogram, BucketType>>>;
 
+/// A histogram family whose buckets keep the label set `S` of their latest
+/// observation as an OpenMetrics exemplar, e.g. a trace id.
+pub type FamilyHistogramWithExemplars<L, S> =
+    Arc<RwLock<Family<L, HistogramWithExemplars<S>, BucketType>>>;
+
 /// BucketType defines the behavior of passing buckets when building a Histogram
 ///
 /// this is usually used when building internal components
@@ -80,6 +86,21 @@ fn new_metric(&self) -> Histogram {
     }
 }
 
+impl<S> MetricConstructor<HistogramWithExemplars<S>> for BucketType {
+    fn new_metric(&self) -> HistogramWithExemplars<S> {
+        match *self {
+            BucketType::PlaintextBucket(items) => {
+                HistogramWithExemplars::new(items.iter().copied())
+            }
+            BucketType::ExponentialBuckets {
+                start,
+                factor,
+                length,
+            } => HistogramWithExemplars::new(exponential_buckets(start, factor, length)),
+        }
+    }
+}
+
 #[macro_export]
 macro_rules! register_histogram_metric {
     ($name:ident, $metric_name:expr, $help:expr, $label:ty, [$($val:expr),* $(,)?]) => {
@@ -152,6 +173,22 @@ pub fn register_histogram_family<L>(
     Arc::new(RwLock::new(family))
 }
 
+pub fn register_histogram_with_exemplars_family<L, S>(
+    name: &str,
+    help: &str,
+    bucket_type: BucketType,
+) -> FamilyHistogramWithExemplars<L, S>
+where
+    L: EncodeLabelSet + Eq + Clone + Hash + Debug + Sync + Send + 'static,
+    S: EncodeLabelSet + Clone + Debug + Sync + Send + 'static,
+{
+    let family =
+        Family::<L, HistogramWithExemplars<S>, BucketType>::new_with_constructor(bucket_type);
+
+    metrics_register_default().register(name, help, family.clone());
+    Arc::new(RwLock::new(family))
+}
+
 /// Pre-register a histogram entry for the given label without recording any
 /// observation.  This creates the histogram (all bucket counts = 0, sum = 0,
 /// count = 0) so it appears in Prometheus output immediately on startup.
@@ -13,6 +13,8 @@
 // limitations under the License.
 
 pub mod counter;
+pub mod exporter;
 pub mod gauge;
 pub mod histogram;
+pub mod push;
 pub mod server;
@@ -0,0 +1,311 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::core::server::dump_metrics;
+use common_base::{
+    error::{common::CommonError, ResultCommonError},
+    tools::{loop_select_ticket, now_millis},
+};
+use common_config::config::{PrometheusPush, PrometheusPushMode};
+use prost::Message;
+use reqwest::Client;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::warn;
+
+const PUSHGATEWAY_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Pushes the metrics registry to a Pushgateway or a remote-write receiver.
+/// Every series carries `job`, `cluster` and `instance` labels so pushes from
+/// different nodes do not overwrite each other.
+pub struct MetricsPusher {
+    client: Client,
+    mode: PrometheusPushMode,
+    endpoint: String,
+    job: String,
+    cluster: String,
+    instance: String,
+}
+
+impl MetricsPusher {
+    pub fn new(
+        config: &PrometheusPush,
+        cluster: &str,
+        instance: &str,
+    ) -> Result<Self, CommonError> {
+        if config.endpoint.is_empty() {
+            return Err(CommonError::CommonError(
+                "prometheus.push.endpoint must be set when push is enabled".to_string(),
+            ));
+        }
+        let client = Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()?;
+        Ok(MetricsPusher {
+            client,
+            mode: config.mode,
+            endpoint: config.endpoint.trim_end_matches('/').to_string(),
+            job: config.job.clone(),
+            cluster: cluster.to_string(),
+            instance: instance.to_string(),
+        })
+    }
+
+    pub async fn push(&self) -> ResultCommonError {
+        let text = dump_metrics();
+        let request = match self.mode {
+            PrometheusPushMode::Pushgateway => self
+                .client
+                .put(self.pushgateway_url())
+                .header("Content-Type", PUSHGATEWAY_CONTENT_TYPE)
+                .body(text),
+            PrometheusPushMode::RemoteWrite => {
+                let body = self.remote_write_body(&text, now_millis() as i64)?;
+                self.client
+                    .post(&self.endpoint)
+                    .header("Content-Type", "application/x-protobuf")
+                    .header("Content-Encoding", "snappy")
+                    .header("X-Prometheus-Remote-Write-Version", "0.1.0")
+                    .body(body)
+            }
+        };
+
+        let resp = request.send().await?;
+        if !resp.status().is_success() {
+            return Err(CommonError::CommonError(format!(
+                "metrics push to {} returned {}",
+                self.endpoint,
+                resp.status()
+            )));
+        }
+        Ok(())
+    }
+
+    fn pushgateway_url(&self) -> String {
+        format!(
+            "{}/metrics/job/{}/cluster/{}/instance/{}",
+            self.endpoint, self.job, self.cluster, self.instance
+        )
+    }
+
+    fn remote_write_body(&self, text: &str, timestamp_ms: i64) -> Result<Vec<u8>, CommonError> {
+        let timeseries = parse_exposition(text)
+            .into_iter()
+            .map(|sample| {
+                let mut labels = vec![
+                    ("__name__".to_string(), sample.name),
+                    ("job".to_string(), self.job.clone()),
+                    ("cluster".to_string(), self.cluster.clone()),
+                    ("instance".to_string(), self.instance.clone()),
+                ];
+                labels.extend(sample.labels);
+                // Remote write requires labels sorted by name. The sort is
+                // stable, so the node labels pushed first win over a series
+                // label of the same name.
+                labels.sort_by(|a, b| a.0.cmp(&b.0));
+                labels.dedup_by(|later, earlier| later.0 == earlier.0);
+                TimeSeries {
+                    labels: labels
+                        .into_iter()
+                        .map(|(name, value)| Label { name, value })
+                        .collect(),
+                    samples: vec![Sample {
+                        value: sample.value,
+                        timestamp: timestamp_ms,
+                    }],
+                }
+            })
+            .collect();
+        let raw = WriteRequest { timeseries }.encode_to_vec();
+        snap::raw::Encoder::new()
+            .compress_vec(&raw)
+            .map_err(|e| CommonError::CommonError(format!("snappy compress failed: {e}")))
+    }
+}
+
+/// Push the registry every `interval_ms` until `stop_send` broadcasts `true`.
+pub async fn start_metrics_push(
+    pusher: MetricsPusher,
+    interval_ms: u64,
+    stop_send: broadcast::Sender<bool>,
+) {
+    let pusher = &pusher;
+    let ac_fn = async || -> ResultCommonError {
+        if let Err(e) = pusher.push().await {
+            warn!("Failed to push metrics: {}", e);
+        }
+        Ok(())
+    };
+    loop_select_ticket(ac_fn, interval_ms.max(1000), &stop_send).await;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ParsedSample {
+    name: String,
+    labels: Vec<(String, String)>,
+    value: f64,
+}
+
+/// Samples of a text exposition, skipping comments and lines that do not parse.
+fn parse_exposition(text: &str) -> Vec<ParsedSample> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(parse_sample_line)
+        .collect()
+}
+
+fn parse_sample_line(line: &str) -> Option<ParsedSample> {
+    let name_end = line.find(['{', ' '])?;
+    let name = line[..name_end].to_string();
+    let (labels, rest) = match line[name_end..].strip_prefix('{') {
+        Some(body) => parse_labels(body)?,
+        None => (Vec::new(), &line[name_end..]),
+    };
+    let value = rest.split_whitespace().next()?.parse::<f64>().ok()?;
+    Some(ParsedSample {
+        name,
+        labels,
+        value,
+    })
+}
+
+/// Labels up to the closing `}`, and the input after it.
+fn parse_labels(mut input: &str) -> Option<(Vec<(String, String)>, &str)> {
+    let mut labels = Vec::new();
+    loop {
+        input = input.trim_start_matches([',', ' ']);
+        if let Some(rest) = input.strip_prefix('}') {
+            return Some((labels, rest));
+        }
+
+        let eq = input.find('=')?;
+        let label_name = input[..eq].trim().to_string();
+        let quoted = input[eq + 1..].strip_prefix('"')?;
+        let mut value = String::new();
+        let mut chars = quoted.char_indices();
+        let end = loop {
+            let (i, c) = chars.next()?;
+            match c {
+                '\\' => match chars.next()?.1 {
+                    'n' => value.push('\n'),
+                    other => value.push(other),
+                },
+                '"' => break i + 1,
+                other => value.push(other),
+            }
+        };
+        labels.push((label_name, value));
+        input = &quoted[end..];
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct WriteRequest {
+    #[prost(message, repeated, tag = "1")]
+    timeseries: Vec<TimeSeries>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct TimeSeries {
+    #[prost(message, repeated, tag = "1")]
+    labels: Vec<Label>,
+    #[prost(message, repeated, tag = "2")]
+    samples: Vec<Sample>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Label {
+    #[prost(string, tag = "1")]
+    name: String,
+    #[prost(string, tag = "2")]
+    value: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Sample {
+    #[prost(double, tag = "1")]
+    value: f64,
+    #[prost(int64, tag = "2")]
+    timestamp: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pusher(mode: PrometheusPushMode) -> MetricsPusher {
+        let config = PrometheusPush {
+            enable: true,
+            mode,
+            endpoint: "http://127.0.0.1:9091/".to_string(),
+            ..Default::default()
+        };
+        MetricsPusher::new(&config, "c1", "1").unwrap()
+    }
+
+    #[test]
+    fn parse_exposition_reads_labels_and_values() {
+        let text = r#"# HELP requests Requests.
+# TYPE requests counter
+requests_total{method="GET",path="/a\"b"} 12
+up 1
+latency_bucket{le="+Inf"} 3
+# EOF
+"#;
+        let samples = parse_exposition(text);
+        assert_eq!(samples.len(), 3);
+        assert_eq!(samples[0].name, "requests_total");
+        assert_eq!(
+            samples[0].labels,
+            vec![
+                ("method".to_string(), "GET".to_string()),
+                ("path".to_string(), "/a\"b".to_string()),
+            ]
+        );
+        assert_eq!(samples[0].value, 12.0);
+        assert!(samples[1].labels.is_empty());
+        assert_eq!(samples[2].labels[0].1, "+Inf");
+        assert_eq!(samples[2].value, 3.0);
+        assert!(parse_sample_line("broken{le=\"1\"").is_none());
+    }
+
+    #[test]
+    fn pushgateway_url_uses_grouping_key() {
+        assert_eq!(
+            pusher(PrometheusPushMode::Pushgateway).pushgateway_url(),
+            "http://127.0.0.1:9091/metrics/job/robustmq/cluster/c1/instance/1"
+        );
+        assert!(MetricsPusher::new(&PrometheusPush::default(), "c1", "1").is_err());
+    }
+
+    #[test]
+    fn remote_write_body_is_snappy_protobuf_with_sorted_labels() {
+        let body = pusher(PrometheusPushMode::RemoteWrite)
+            .remote_write_body("up{zone=\"a\",instance=\"x\"} 1\n", 42)
+            .unwrap();
+        let raw = snap::raw::Decoder::new().decompress_vec(&body).unwrap();
+        let request = WriteRequest::decode(raw.as_slice()).unwrap();
+        assert_eq!(request.timeseries.len(), 1);
+
+        let series = &request.timeseries[0];
+        let names: Vec<&str> = series.labels.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["__name__", "cluster", "instance", "job", "zone"]
+        );
+        assert_eq!(series.labels[2].value, "1");
+        assert_eq!(series.samples[0].value, 1.0);
+        assert_eq!(series.samples[0].timestamp, 42);
+    }
+}
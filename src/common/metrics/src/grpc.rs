@@ -13,7 +13,8 @@
 // limitations under the License.
 
 use crate::{
-    gauge_metric_inc, histogram_metric_observe, register_counter_metric,
+    counter_metric_inc, gauge_metric_inc, gauge_metric_set, histogram_metric_observe,
+    register_counter_metric, register_gauge_metric,
     register_histogram_metric_ms_with_default_buckets,
 };
 use prometheus_client::encoding::EncodeLabelSet;
@@ -33,6 +34,11 @@ pub struct GrpcErrorLabel {
     pub status_code: String,
 }
 
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq, Default)]
+pub struct GrpcEndpointLabel {
+    pub addr: String,
+}
+
 // ── Metrics (Server-side) ────────────────────────────────────────────────────
 
 register_counter_metric!(
@@ -65,6 +71,20 @@ pub struct GrpcErrorLabel {
     GrpcMethodLabel
 );
 
+register_gauge_metric!(
+    GRPC_CLIENT_CIRCUIT_STATE,
+    "grpc_client_circuit_state",
+    "Circuit breaker state per target address: 0 closed, 1 open, 2 half-open",
+    GrpcEndpointLabel
+);
+
+register_counter_metric!(
+    GRPC_CLIENT_CIRCUIT_REJECTED,
+    "grpc_client_circuit_rejected",
+    "Total number of gRPC client attempts skipped because the target circuit was open",
+    GrpcEndpointLabel
+);
+
 // ── Public API ──────────────────────────────────────────────────────────────
 
 pub fn record_grpc_client_call(service: &str, method: &str, duration_ms: f64) {
@@ -75,6 +95,20 @@ pub fn record_grpc_client_call(service: &str, method: &str, duration_ms: f64) {
     histogram_metric_observe!(GRPC_CLIENT_CALL_DURATION_MS, duration_ms, label);
 }
 
+pub fn set_grpc_client_circuit_state(addr: &str, state: i64) {
+    let label = GrpcEndpointLabel {
+        addr: addr.to_string(),
+    };
+    gauge_metric_set!(GRPC_CLIENT_CIRCUIT_STATE, label, state);
+}
+
+pub fn record_grpc_client_circuit_rejected(addr: &str) {
+    let label = GrpcEndpointLabel {
+        addr: addr.to_string(),
+    };
+    counter_metric_inc!(GRPC_CLIENT_CIRCUIT_REJECTED, label);
+}
+
 pub fn record_grpc_request(service: &str, method: &str, status_code: &str, duration_ms: f64) {
     let method_label = GrpcMethodLabel {
         service: service.to_string(),
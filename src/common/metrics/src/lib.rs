@@ -20,8 +20,10 @@
 pub mod meta;
 pub mod mqtt;
 pub mod network;
+pub mod node_call;
 pub mod rocksdb;
 pub mod storage_engine;
+pub mod tls;
 
 /// Pre-register all static-label gauge metrics to 0 so that they appear in
 /// the Prometheus `/metrics` output immediately on startup, even before any
@@ -13,3 +13,4 @@
 // limitations under the License.
 
 pub mod raft;
+pub mod subscribe;
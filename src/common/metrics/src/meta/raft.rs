@@ -13,8 +13,9 @@
 // limitations under the License.
 
 use crate::{
-    counter_metric_inc, counter_metric_touch, gauge_metric_set, histogram_metric_observe,
-    histogram_metric_touch, register_counter_metric, register_gauge_metric,
+    counter_metric_inc, counter_metric_inc_by, counter_metric_touch, gauge_metric_set,
+    histogram_metric_observe, histogram_metric_touch, register_counter_metric,
+    register_gauge_metric, register_histogram_metric,
     register_histogram_metric_ms_with_default_buckets,
 };
 use prometheus_client::encoding::EncodeLabelSet;
@@ -30,6 +31,17 @@ pub struct RaftRpcLabel {
     pub rpc_type: String,
 }
 
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct RaftPeerLabel {
+    pub peer: String,
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct RaftSnapshotLabel {
+    pub machine: String,
+    pub peer: String,
+}
+
 register_counter_metric!(
     RAFT_WRITE_REQUESTS_TOTAL,
     "raft_write_requests",
@@ -58,6 +70,28 @@ pub struct RaftRpcLabel {
     RaftLabel
 );
 
+register_histogram_metric!(
+    RAFT_WRITE_BATCH_SIZE,
+    "raft_write_batch_size",
+    "Number of writes coalesced into one raft entry by the write batcher",
+    RaftLabel,
+    [1, 2, 4, 8, 16, 32, 64, 128, 256, 512]
+);
+
+register_counter_metric!(
+    RAFT_WRITE_BATCHES_TOTAL,
+    "raft_write_batches",
+    "Total number of raft entries proposed by the write batcher",
+    RaftLabel
+);
+
+register_counter_metric!(
+    RAFT_WRITE_BATCHED_ENTRIES_TOTAL,
+    "raft_write_batched_entries",
+    "Total number of writes proposed through the write batcher",
+    RaftLabel
+);
+
 register_gauge_metric!(
     RAFT_APPLY_LAG,
     "raft_apply_lag",
@@ -79,6 +113,27 @@ pub struct RaftRpcLabel {
     RaftLabel
 );
 
+register_gauge_metric!(
+    RAFT_LOG_ENTRIES,
+    "raft_log_entries",
+    "Number of entries currently stored in the Raft log",
+    RaftLabel
+);
+
+register_gauge_metric!(
+    RAFT_LOG_BYTES,
+    "raft_log_bytes",
+    "Total size in bytes of the entries currently stored in the Raft log",
+    RaftLabel
+);
+
+register_counter_metric!(
+    RAFT_LOG_PURGES_TOTAL,
+    "raft_log_purges",
+    "Total number of Raft log purges",
+    RaftLabel
+);
+
 register_histogram_metric_ms_with_default_buckets!(
     RAFT_APPLY_BATCH_DURATION,
     "raft_apply_batch_duration_ms",
@@ -121,6 +176,55 @@ pub struct RaftRpcLabel {
     RaftRpcLabel
 );
 
+register_histogram_metric_ms_with_default_buckets!(
+    RAFT_REPLICATION_LATENCY,
+    "raft_replication_latency_ms",
+    "Round trip of AppendEntries to a peer in milliseconds, across all groups",
+    RaftPeerLabel
+);
+
+register_counter_metric!(
+    RAFT_APPEND_STREAM_OPENED_TOTAL,
+    "raft_append_stream_opened",
+    "Number of AppendEntries streams opened to a peer",
+    RaftPeerLabel
+);
+
+register_counter_metric!(
+    RAFT_APPEND_STREAM_BROKEN_TOTAL,
+    "raft_append_stream_broken",
+    "Number of AppendEntries streams to a peer that failed or were closed",
+    RaftPeerLabel
+);
+
+register_counter_metric!(
+    RAFT_SNAPSHOT_SENT_BYTES_TOTAL,
+    "raft_snapshot_sent_bytes",
+    "Snapshot bytes sent to a peer in InstallSnapshot chunks",
+    RaftSnapshotLabel
+);
+
+register_counter_metric!(
+    RAFT_SNAPSHOT_RECEIVED_BYTES_TOTAL,
+    "raft_snapshot_received_bytes",
+    "Snapshot bytes received in InstallSnapshot chunks",
+    RaftLabel
+);
+
+register_gauge_metric!(
+    RAFT_SNAPSHOT_SEND_OFFSET,
+    "raft_snapshot_send_offset",
+    "Offset of the last snapshot chunk acknowledged by a peer",
+    RaftSnapshotLabel
+);
+
+register_counter_metric!(
+    RAFT_SNAPSHOT_CHUNK_RETRIES_TOTAL,
+    "raft_snapshot_chunk_retries",
+    "Number of snapshot chunks resent to a peer after a failed attempt",
+    RaftSnapshotLabel
+);
+
 pub fn record_write_request(machine: &str) {
     let label = RaftLabel {
         machine: machine.to_string(),
@@ -149,6 +253,16 @@ pub fn record_write_duration(machine: &str, duration_ms: f64) {
     histogram_metric_observe!(RAFT_WRITE_DURATION, duration_ms, label);
 }
 
+pub fn record_write_batch(machine: &str, size: usize) {
+    let label = RaftLabel {
+        machine: machine.to_string(),
+    };
+    let batch_size = size as f64;
+    histogram_metric_observe!(RAFT_WRITE_BATCH_SIZE, batch_size, label);
+    counter_metric_inc!(RAFT_WRITE_BATCHES_TOTAL, label);
+    counter_metric_inc_by!(RAFT_WRITE_BATCHED_ENTRIES_TOTAL, label, size as u64);
+}
+
 pub fn record_rpc_request(machine: &str, rpc_type: &str) {
     let label = RaftRpcLabel {
         machine: machine.to_string(),
@@ -181,6 +295,55 @@ pub fn record_rpc_duration(machine: &str, rpc_type: &str, duration_ms: f64) {
     histogram_metric_observe!(RAFT_RPC_DURATION, duration_ms, label);
 }
 
+pub fn record_replication_latency(peer: &str, duration_ms: f64) {
+    let label = RaftPeerLabel {
+        peer: peer.to_string(),
+    };
+    histogram_metric_observe!(RAFT_REPLICATION_LATENCY, duration_ms, label);
+}
+
+pub fn record_append_stream_opened(peer: &str) {
+    let label = RaftPeerLabel {
+        peer: peer.to_string(),
+    };
+    counter_metric_inc!(RAFT_APPEND_STREAM_OPENED_TOTAL, label);
+}
+
+pub fn record_append_stream_broken(peer: &str) {
+    let label = RaftPeerLabel {
+        peer: peer.to_string(),
+    };
+    counter_metric_inc!(RAFT_APPEND_STREAM_BROKEN_TOTAL, label);
+}
+
+pub fn record_snapshot_chunk_sent(machine: &str, peer: &str, offset: u64, bytes: u64) {
+    let label = RaftSnapshotLabel {
+        machine: machine.to_string(),
+        peer: peer.to_string(),
+    };
+    counter_metric_inc_by!(RAFT_SNAPSHOT_SENT_BYTES_TOTAL, label, bytes);
+    let label = RaftSnapshotLabel {
+        machine: machine.to_string(),
+        peer: peer.to_string(),
+    };
+    gauge_metric_set!(RAFT_SNAPSHOT_SEND_OFFSET, label, (offset + bytes) as i64);
+}
+
+pub fn record_snapshot_chunk_retry(machine: &str, peer: &str) {
+    let label = RaftSnapshotLabel {
+        machine: machine.to_string(),
+        peer: peer.to_string(),
+    };
+    counter_metric_inc!(RAFT_SNAPSHOT_CHUNK_RETRIES_TOTAL, label);
+}
+
+pub fn record_snapshot_chunk_received(machine: &str, bytes: u64) {
+    let label = RaftLabel {
+        machine: machine.to_string(),
+    };
+    counter_metric_inc_by!(RAFT_SNAPSHOT_RECEIVED_BYTES_TOTAL, label, bytes);
+}
+
 /// Pre-register Raft metrics for all known state machines (called at broker init).
 /// Only registers the static `metadata_0` shard; dynamic shard counts are registered
 /// via `init_raft_shards` once the config is available.
@@ -214,6 +377,14 @@ pub fn init_raft_shards_metrics(offset_group_num: u32, data_group_num: u32) {
             machine: shard.clone(),
         };
         gauge_metric_set!(RAFT_LAST_APPLIED, label, 0);
+        let label = RaftLabel {
+            machine: shard.clone(),
+        };
+        gauge_metric_set!(RAFT_LOG_ENTRIES, label, 0);
+        let label = RaftLabel {
+            machine: shard.clone(),
+        };
+        gauge_metric_set!(RAFT_LOG_BYTES, label, 0);
 
         counter_metric_touch!(
             RAFT_WRITE_REQUESTS_TOTAL,
@@ -233,6 +404,12 @@ pub fn init_raft_shards_metrics(offset_group_num: u32, data_group_num: u32) {
                 machine: shard.clone()
             }
         );
+        counter_metric_touch!(
+            RAFT_LOG_PURGES_TOTAL,
+            RaftLabel {
+                machine: shard.clone()
+            }
+        );
 
         histogram_metric_touch!(
             RAFT_WRITE_DURATION,
@@ -308,6 +485,24 @@ pub fn record_raft_apply_lag(machine: &str, last_log: u64, last_applied: u64) {
     gauge_metric_set!(RAFT_LAST_APPLIED, label, last_applied as i64);
 }
 
+pub fn record_raft_log_size(machine: &str, entries: u64, bytes: u64) {
+    let label = RaftLabel {
+        machine: machine.to_string(),
+    };
+    gauge_metric_set!(RAFT_LOG_ENTRIES, label, entries as i64);
+    let label = RaftLabel {
+        machine: machine.to_string(),
+    };
+    gauge_metric_set!(RAFT_LOG_BYTES, label, bytes as i64);
+}
+
+pub fn record_raft_log_purge(machine: &str) {
+    let label = RaftLabel {
+        machine: machine.to_string(),
+    };
+    counter_metric_inc!(RAFT_LOG_PURGES_TOTAL, label);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -334,6 +529,14 @@ fn test_raft_metrics() {
         record_write_success("metadata");
         record_write_failure("mqtt");
         record_write_duration("offset", 12.5);
+        record_raft_log_size("metadata", 100, 4096);
+        record_raft_log_purge("metadata");
+        record_replication_latency("127.0.0.1:1228", 1.5);
+        record_append_stream_opened("127.0.0.1:1228");
+        record_append_stream_broken("127.0.0.1:1228");
+        record_snapshot_chunk_sent("data_0", "127.0.0.1:1228", 0, 1024);
+        record_snapshot_chunk_retry("data_0", "127.0.0.1:1228");
+        record_snapshot_chunk_received("data_0", 1024);
     }
 
     #[test]
@@ -0,0 +1,27 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::core::server::NoLabelSet;
+use crate::{gauge_metric_set, register_gauge_metric};
+
+register_gauge_metric!(
+    META_ORPHANED_SUBSCRIBE_NUM,
+    "meta_orphaned_subscribe_num",
+    "Stored subscriptions whose session has expired or been deleted, as of the last GC scan",
+    NoLabelSet
+);
+
+pub fn record_orphaned_subscribe_num(num: usize) {
+    let label = NoLabelSet;
+    gauge_metric_set!(META_ORPHANED_SUBSCRIBE_NUM, label, num as i64);
+}
@@ -12,12 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{counter_metric_inc, counter_metric_touch, register_counter_metric};
+use crate::{
+    counter_metric_get, counter_metric_inc, counter_metric_touch, register_counter_metric,
+};
 use prometheus_client::encoding::EncodeLabelSet;
 
 #[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
 struct AuthLabel {}
 
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct BlacklistMatchLabel {
+    match_type: String,
+}
+
 register_counter_metric!(
     MQTT_AUTH_SUCCESS,
     "mqtt_auth_success",
@@ -53,6 +60,13 @@ struct AuthLabel {}
     AuthLabel
 );
 
+register_counter_metric!(
+    MQTT_BLACKLIST_BLOCKED_BY_TYPE,
+    "mqtt_blacklist_blocked_by_type",
+    "Number of MQTT connections blocked by blacklist, by matched blacklist type",
+    BlacklistMatchLabel
+);
+
 pub fn record_mqtt_auth_success() {
     let label = AuthLabel {};
     counter_metric_inc!(MQTT_AUTH_SUCCESS, label);
@@ -78,6 +92,22 @@ pub fn record_mqtt_blacklist_blocked() {
     counter_metric_inc!(MQTT_BLACKLIST_BLOCKED, label);
 }
 
+pub fn record_mqtt_blacklist_blocked_by_type(match_type: &str) {
+    let label = BlacklistMatchLabel {
+        match_type: match_type.to_string(),
+    };
+    counter_metric_inc!(MQTT_BLACKLIST_BLOCKED_BY_TYPE, label);
+}
+
+pub fn get_mqtt_blacklist_blocked_by_type(match_type: &str) -> u64 {
+    let label = BlacklistMatchLabel {
+        match_type: match_type.to_string(),
+    };
+    let mut result = 0u64;
+    counter_metric_get!(MQTT_BLACKLIST_BLOCKED_BY_TYPE, label, result);
+    result
+}
+
 pub fn init() {
     counter_metric_touch!(MQTT_AUTH_SUCCESS, AuthLabel {});
     counter_metric_touch!(MQTT_AUTH_FAILED, AuthLabel {});
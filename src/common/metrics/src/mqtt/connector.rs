@@ -116,6 +116,34 @@ macro_rules! get_counter_metric_with_label {
     ConnectorLabel
 );
 
+register_counter_metric!(
+    MQTT_CONNECTOR_SINK_RPC_TOTAL,
+    "mqtt_connector_sink_rpc_total",
+    "Total number of sink calls issued by connector",
+    ConnectorResultLabel
+);
+
+register_counter_metric!(
+    MQTT_CONNECTOR_CPU_TIME_MS,
+    "mqtt_connector_cpu_time_ms",
+    "Total busy time spent by connector inside its sink in milliseconds",
+    ConnectorLabel
+);
+
+register_gauge_metric!(
+    MQTT_CONNECTOR_MEMORY_ESTIMATE_BYTES,
+    "mqtt_connector_memory_estimate_bytes",
+    "Estimated bytes held by the batch the connector is currently processing",
+    ConnectorLabel
+);
+
+register_counter_metric!(
+    MQTT_CONNECTOR_THROTTLED_RECORDS_TOTAL,
+    "mqtt_connector_throttled_records_total",
+    "Total number of records delayed by the connector throttle",
+    ConnectorLabel
+);
+
 register_counter_metric!(
     MQTT_CONNECTOR_MESSAGES_SENT_SUCCESS_TOTAL,
     "mqtt_connector_messages_sent_success_agg",
@@ -282,6 +310,57 @@ pub fn set_connector_up(tenant: &str, connector_type: String, connector_name: St
     gauge_metric_set!(MQTT_CONNECTOR_UP, label, if up { 1 } else { 0 });
 }
 
+pub fn record_connector_sink_call(
+    tenant: &str,
+    connector_type: String,
+    connector_name: String,
+    duration_ms: u64,
+    success: bool,
+) {
+    let label = ConnectorLabel {
+        tenant: tenant.to_string(),
+        connector_type: connector_type.clone(),
+        connector_name: connector_name.clone(),
+    };
+    counter_metric_inc_by!(MQTT_CONNECTOR_CPU_TIME_MS, label, duration_ms);
+
+    let result_label = ConnectorResultLabel {
+        tenant: tenant.to_string(),
+        connector_type,
+        connector_name,
+        result: if success { "success" } else { "failure" }.to_string(),
+    };
+    counter_metric_inc_by!(MQTT_CONNECTOR_SINK_RPC_TOTAL, result_label, 1);
+}
+
+pub fn set_connector_memory_estimate(
+    tenant: &str,
+    connector_type: String,
+    connector_name: String,
+    bytes: u64,
+) {
+    let label = ConnectorLabel {
+        tenant: tenant.to_string(),
+        connector_type,
+        connector_name,
+    };
+    gauge_metric_set!(MQTT_CONNECTOR_MEMORY_ESTIMATE_BYTES, label, bytes as i64);
+}
+
+pub fn record_connector_throttled_records(
+    tenant: &str,
+    connector_type: String,
+    connector_name: String,
+    count: u64,
+) {
+    let label = ConnectorLabel {
+        tenant: tenant.to_string(),
+        connector_type,
+        connector_name,
+    };
+    counter_metric_inc_by!(MQTT_CONNECTOR_THROTTLED_RECORDS_TOTAL, label, count);
+}
+
 pub fn get_connector_messages_sent_success(
     tenant: &str,
     connector_type: &str,
@@ -388,6 +467,20 @@ fn test_connector_metrics_with_label() {
             connector_type.clone(),
             connector_name.clone(),
         );
+        record_connector_sink_call(
+            tenant,
+            connector_type.clone(),
+            connector_name.clone(),
+            12,
+            true,
+        );
+        set_connector_memory_estimate(tenant, connector_type.clone(), connector_name.clone(), 64);
+        record_connector_throttled_records(
+            tenant,
+            connector_type.clone(),
+            connector_name.clone(),
+            3,
+        );
         set_connector_up(tenant, connector_type, connector_name, true);
     }
 
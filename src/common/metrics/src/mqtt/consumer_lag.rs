@@ -0,0 +1,67 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{gauge_metric_get, gauge_metric_set, register_gauge_metric};
+use prometheus_client::encoding::EncodeLabelSet;
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct ConsumerLagLabel {
+    pub tenant: String,
+    pub group_type: String,
+    pub group: String,
+    pub topic: String,
+}
+
+register_gauge_metric!(
+    MQTT_CONSUMER_GROUP_LAG,
+    "mqtt_consumer_group_lag",
+    "Number of messages in the topic not yet committed by the consumer group",
+    ConsumerLagLabel
+);
+
+pub fn record_consumer_group_lag_set(label: ConsumerLagLabel, lag: i64) {
+    gauge_metric_set!(MQTT_CONSUMER_GROUP_LAG, label, lag);
+}
+
+pub fn get_consumer_group_lag(label: ConsumerLagLabel) -> i64 {
+    let mut result = 0i64;
+    gauge_metric_get!(MQTT_CONSUMER_GROUP_LAG, label, result);
+    result
+}
+
+/// Drops the series of a group that no longer consumes the topic, so it
+/// does not keep exporting its last lag.
+pub fn remove_consumer_group_lag(label: &ConsumerLagLabel) {
+    MQTT_CONSUMER_GROUP_LAG.write().unwrap().remove(label);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_consumer_group_lag() {
+        let label = ConsumerLagLabel {
+            tenant: "default".to_string(),
+            group_type: "share".to_string(),
+            group: "g1".to_string(),
+            topic: "sensor/temperature".to_string(),
+        };
+        record_consumer_group_lag_set(label.clone(), 42);
+        assert_eq!(get_consumer_group_lag(label.clone()), 42);
+
+        remove_consumer_group_lag(&label);
+        assert_eq!(get_consumer_group_lag(label), 0);
+    }
+}
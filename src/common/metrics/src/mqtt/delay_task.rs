@@ -14,7 +14,7 @@
 
 use crate::{
     counter_metric_inc, counter_metric_touch, histogram_metric_observe, register_counter_metric,
-    register_histogram_metric,
+    register_histogram_metric, register_histogram_metric_ms_with_default_buckets,
 };
 use prometheus_client::encoding::EncodeLabelSet;
 
@@ -59,6 +59,20 @@ pub struct DelayTaskTypeLabel {
     [0.1, 0.5, 1.0, 2.0, 5.0, 10.0, 30.0, 60.0, 120.0]
 );
 
+register_histogram_metric_ms_with_default_buckets!(
+    DELAY_TASK_QUEUE_WAIT_MS,
+    "delay_task_queue_wait_ms",
+    "Time an expired delay task waited for a handler slot in milliseconds",
+    DelayTaskTypeLabel
+);
+
+register_histogram_metric_ms_with_default_buckets!(
+    DELAY_TASK_EXECUTE_DURATION_MS,
+    "delay_task_execute_duration_ms",
+    "Duration of delay task handler execution in milliseconds",
+    DelayTaskTypeLabel
+);
+
 // ── Public API ──────────────────────────────────────────────────────────────
 
 pub fn record_delay_task_created() {
@@ -87,6 +101,20 @@ pub fn record_delay_task_schedule_latency(task_type: &str, latency_s: f64) {
     histogram_metric_observe!(DELAY_TASK_SCHEDULE_LATENCY_S, latency_s, l);
 }
 
+pub fn record_delay_task_queue_wait(task_type: &str, wait_ms: f64) {
+    let l = DelayTaskTypeLabel {
+        task_type: task_type.to_string(),
+    };
+    histogram_metric_observe!(DELAY_TASK_QUEUE_WAIT_MS, wait_ms, l);
+}
+
+pub fn record_delay_task_execute_duration(task_type: &str, duration_ms: f64) {
+    let l = DelayTaskTypeLabel {
+        task_type: task_type.to_string(),
+    };
+    histogram_metric_observe!(DELAY_TASK_EXECUTE_DURATION_MS, duration_ms, l);
+}
+
 pub fn init() {
     counter_metric_touch!(DELAY_TASK_CREATED_TOTAL, DelayTaskLabel {});
 }
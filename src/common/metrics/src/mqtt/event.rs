@@ -80,6 +80,25 @@ struct EventLabel {}
     EventLabel
 );
 
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct KeepAliveLabel {
+    protocol: String,
+}
+
+register_counter_metric!(
+    MQTT_KEEP_ALIVE_TIMEOUT,
+    "mqtt_keep_alive_timeout",
+    "Number of MQTT connections closed by the server on keep alive timeout, by protocol",
+    KeepAliveLabel
+);
+
+register_counter_metric!(
+    MQTT_KEEP_ALIVE_OVERRIDE,
+    "mqtt_keep_alive_override",
+    "Number of MQTT 5 CONNACKs overriding the client keep alive with Server Keep Alive",
+    EventLabel
+);
+
 register_counter_metric!(
     MQTT_SUBSCRIBE_SUCCESS,
     "mqtt_subscribe_success",
@@ -121,6 +140,18 @@ pub fn record_mqtt_connection_expired() {
     counter_metric_inc!(MQTT_CONNECTION_EXPIRED, label);
 }
 
+pub fn record_mqtt_keep_alive_timeout(protocol: &str) {
+    let label = KeepAliveLabel {
+        protocol: protocol.to_string(),
+    };
+    counter_metric_inc!(MQTT_KEEP_ALIVE_TIMEOUT, label);
+}
+
+pub fn record_mqtt_keep_alive_override() {
+    let label = EventLabel {};
+    counter_metric_inc!(MQTT_KEEP_ALIVE_OVERRIDE, label);
+}
+
 pub fn record_mqtt_subscribe_success() {
     let label = EventLabel {};
     counter_metric_inc!(MQTT_SUBSCRIBE_SUCCESS, label);
@@ -141,6 +172,7 @@ pub fn init() {
     counter_metric_touch!(MQTT_CONNECTION_FAILED, EventLabel {});
     counter_metric_touch!(MQTT_DISCONNECT_SUCCESS, EventLabel {});
     counter_metric_touch!(MQTT_CONNECTION_EXPIRED, EventLabel {});
+    counter_metric_touch!(MQTT_KEEP_ALIVE_OVERRIDE, EventLabel {});
     counter_metric_touch!(MQTT_SUBSCRIBE_SUCCESS, EventLabel {});
     counter_metric_touch!(MQTT_UNSUBSCRIBE_SUCCESS, EventLabel {});
     counter_metric_touch!(MQTT_SUBSCRIBE_FAILED, EventLabel {});
@@ -0,0 +1,131 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! End-to-end latency of the MQTT message path: publish received to stored,
+//! stored to pushed to a subscriber, and pushed to acknowledged by it. Each
+//! observation may carry the message's trace id as an exemplar so a slow
+//! bucket in Grafana links to the trace.
+
+use crate::core::histogram::{
+    register_histogram_with_exemplars_family, FamilyHistogramWithExemplars,
+    DEFAULT_REQUEST_DURATION_BUCKETS,
+};
+use prometheus_client::encoding::EncodeLabelSet;
+use std::sync::LazyLock;
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct MessagePathLabel {
+    pub tenant: String,
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct TraceExemplar {
+    pub trace_id: String,
+}
+
+static MQTT_MESSAGE_RECEIVE_TO_STORE_MS: LazyLock<
+    FamilyHistogramWithExemplars<MessagePathLabel, TraceExemplar>,
+> = LazyLock::new(|| {
+    register_histogram_with_exemplars_family(
+        "mqtt_message_receive_to_store_ms",
+        "Time from receiving a PUBLISH to its message being stored, in milliseconds",
+        DEFAULT_REQUEST_DURATION_BUCKETS,
+    )
+});
+
+static MQTT_MESSAGE_STORE_TO_PUSH_MS: LazyLock<
+    FamilyHistogramWithExemplars<MessagePathLabel, TraceExemplar>,
+> = LazyLock::new(|| {
+    register_histogram_with_exemplars_family(
+        "mqtt_message_store_to_push_ms",
+        "Time from a message being stored to it being pushed to a subscriber, in milliseconds",
+        DEFAULT_REQUEST_DURATION_BUCKETS,
+    )
+});
+
+static MQTT_MESSAGE_PUSH_TO_ACK_MS: LazyLock<
+    FamilyHistogramWithExemplars<MessagePathLabel, TraceExemplar>,
+> = LazyLock::new(|| {
+    register_histogram_with_exemplars_family(
+        "mqtt_message_push_to_ack_ms",
+        "Time from pushing a QoS 1/2 message to a subscriber to its PUBACK or PUBREC, in milliseconds",
+        DEFAULT_REQUEST_DURATION_BUCKETS,
+    )
+});
+
+fn observe(
+    family: &FamilyHistogramWithExemplars<MessagePathLabel, TraceExemplar>,
+    tenant: &str,
+    duration_ms: f64,
+    trace_id: Option<&str>,
+) {
+    let label = MessagePathLabel {
+        tenant: tenant.to_string(),
+    };
+    let exemplar = trace_id.map(|trace_id| TraceExemplar {
+        trace_id: trace_id.to_string(),
+    });
+    {
+        let family_r = family.read().unwrap();
+        if let Some(histogram) = family_r.get(&label) {
+            histogram.observe(duration_ms, exemplar, None);
+            return;
+        }
+    }
+    let family_w = family.write().unwrap();
+    family_w
+        .get_or_create(&label)
+        .observe(duration_ms, exemplar, None);
+}
+
+pub fn record_message_receive_to_store(tenant: &str, duration_ms: f64, trace_id: Option<&str>) {
+    observe(
+        &MQTT_MESSAGE_RECEIVE_TO_STORE_MS,
+        tenant,
+        duration_ms,
+        trace_id,
+    );
+}
+
+pub fn record_message_store_to_push(tenant: &str, duration_ms: f64, trace_id: Option<&str>) {
+    observe(
+        &MQTT_MESSAGE_STORE_TO_PUSH_MS,
+        tenant,
+        duration_ms,
+        trace_id,
+    );
+}
+
+pub fn record_message_push_to_ack(tenant: &str, duration_ms: f64, trace_id: Option<&str>) {
+    observe(&MQTT_MESSAGE_PUSH_TO_ACK_MS, tenant, duration_ms, trace_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::server::dump_metrics;
+
+    #[test]
+    fn exemplar_carries_trace_id() {
+        record_message_push_to_ack(
+            "exemplar-tenant",
+            3.0,
+            Some("4bf92f3577b34da6a3ce929d0e0e4736"),
+        );
+        record_message_push_to_ack("exemplar-tenant", 5.0, None);
+
+        let output = dump_metrics();
+        assert!(output.contains("mqtt_message_push_to_ack_ms_count{tenant=\"exemplar-tenant\"} 2"));
+        assert!(output.contains("trace_id=\"4bf92f3577b34da6a3ce929d0e0e4736\""));
+    }
+}
@@ -14,16 +14,24 @@
 
 pub mod auth;
 pub mod connector;
+pub mod consumer_lag;
 pub mod delay;
 pub mod delay_task;
 pub mod event;
+pub mod message_path;
+pub mod overload;
 pub mod packets;
+pub mod payload_transform;
 pub mod publish;
+pub mod rule_engine;
 pub mod session;
 pub mod statistics;
 pub mod subscribe;
+pub mod subscription;
 pub mod time;
 pub mod topic;
+pub mod topic_throughput;
+pub mod webhook;
 
 pub fn init() {
     statistics::init();
@@ -34,4 +42,5 @@ pub fn init() {
     delay_task::init();
     session::init();
     packets::init();
+    overload::init();
 }
@@ -0,0 +1,85 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    counter_metric_get, counter_metric_inc, counter_metric_touch, gauge_metric_get,
+    gauge_metric_set, register_counter_metric, register_gauge_metric,
+};
+use prometheus_client::encoding::EncodeLabelSet;
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct OverloadLabel {}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct OverloadActionLabel {
+    pub action: String,
+}
+
+register_gauge_metric!(
+    MQTT_OVERLOAD_LEVEL,
+    "mqtt_overload_level",
+    "Current overload protection level of the node (0 = normal, 3 = shedding QoS 0)",
+    OverloadLabel
+);
+
+register_counter_metric!(
+    MQTT_OVERLOAD_SHED_MESSAGES,
+    "mqtt_overload_shed_messages",
+    "Number of QoS 0 messages dropped by overload protection",
+    OverloadLabel
+);
+
+register_counter_metric!(
+    MQTT_OVERLOAD_THROTTLED,
+    "mqtt_overload_throttled",
+    "Number of packets delayed by overload protection",
+    OverloadActionLabel
+);
+
+pub fn record_mqtt_overload_level(level: u8) {
+    let label = OverloadLabel {};
+    gauge_metric_set!(MQTT_OVERLOAD_LEVEL, label, level as i64);
+}
+
+pub fn get_mqtt_overload_level() -> i64 {
+    let label = OverloadLabel {};
+    let mut result = 0i64;
+    gauge_metric_get!(MQTT_OVERLOAD_LEVEL, label, result);
+    result
+}
+
+pub fn record_mqtt_overload_shed() {
+    let label = OverloadLabel {};
+    counter_metric_inc!(MQTT_OVERLOAD_SHED_MESSAGES, label);
+}
+
+pub fn get_mqtt_overload_shed() -> u64 {
+    let label = OverloadLabel {};
+    let mut result = 0u64;
+    counter_metric_get!(MQTT_OVERLOAD_SHED_MESSAGES, label, result);
+    result
+}
+
+/// `action` is `connect` or `ack`.
+pub fn record_mqtt_overload_throttled(action: &str) {
+    let label = OverloadActionLabel {
+        action: action.to_string(),
+    };
+    counter_metric_inc!(MQTT_OVERLOAD_THROTTLED, label);
+}
+
+pub fn init() {
+    record_mqtt_overload_level(0);
+    counter_metric_touch!(MQTT_OVERLOAD_SHED_MESSAGES, OverloadLabel {});
+}
@@ -0,0 +1,47 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{counter_metric_get, counter_metric_inc, register_counter_metric};
+use prometheus_client::encoding::EncodeLabelSet;
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct PayloadTransformLabel {
+    pub direction: String,
+    pub result: String,
+}
+
+register_counter_metric!(
+    MQTT_PAYLOAD_TRANSFORM_TOTAL,
+    "mqtt_payload_transform_total",
+    "Total number of payload transformations, by direction (encode, decode) and result (success, failure)",
+    PayloadTransformLabel
+);
+
+pub fn record_payload_transform(direction: &str, success: bool) {
+    let label = PayloadTransformLabel {
+        direction: direction.to_string(),
+        result: if success { "success" } else { "failure" }.to_string(),
+    };
+    counter_metric_inc!(MQTT_PAYLOAD_TRANSFORM_TOTAL, label);
+}
+
+pub fn get_payload_transform(direction: &str, result: &str) -> u64 {
+    let label = PayloadTransformLabel {
+        direction: direction.to_string(),
+        result: result.to_string(),
+    };
+    let mut result = 0u64;
+    counter_metric_get!(MQTT_PAYLOAD_TRANSFORM_TOTAL, label, result);
+    result
+}
@@ -114,6 +114,42 @@ pub fn record_messages_dropped_no_subscribers_get() -> u64 {
     result
 }
 
+register_counter_metric!(
+    MQTT_MESSAGES_FORWARDED,
+    "mqtt_messages_forwarded",
+    "Number of stored messages forwarded to brokers hosting live subscribers",
+    MessageLabel
+);
+
+register_counter_metric!(
+    MQTT_MESSAGES_FORWARD_FAILED,
+    "mqtt_messages_forward_failed",
+    "Number of messages that could not be forwarded to another broker",
+    MessageLabel
+);
+
+register_counter_metric!(
+    MQTT_MESSAGES_FORWARD_DELIVERED,
+    "mqtt_messages_forward_delivered",
+    "Number of messages pushed from forwarded records instead of a storage read",
+    MessageLabel
+);
+
+pub fn record_mqtt_messages_forwarded(count: u64) {
+    let label = MessageLabel {};
+    counter_metric_inc_by!(MQTT_MESSAGES_FORWARDED, label, count);
+}
+
+pub fn record_mqtt_messages_forward_failed(count: u64) {
+    let label = MessageLabel {};
+    counter_metric_inc_by!(MQTT_MESSAGES_FORWARD_FAILED, label, count);
+}
+
+pub fn record_mqtt_messages_forward_delivered(count: u64) {
+    let label = MessageLabel {};
+    counter_metric_inc_by!(MQTT_MESSAGES_FORWARD_DELIVERED, label, count);
+}
+
 pub fn init() {
     counter_metric_touch!(MQTT_MESSAGES_DELAYED, MessageLabel {});
     counter_metric_touch!(MQTT_MESSAGES_RECEIVED, MessageLabel {});
@@ -121,6 +157,9 @@ pub fn init() {
     counter_metric_touch!(MQTT_MESSAGE_BYTES_SENT, MessageLabel {});
     counter_metric_touch!(MQTT_MESSAGE_BYTES_RECEIVED, MessageLabel {});
     counter_metric_touch!(MQTT_MESSAGES_DROPPED_NO_SUBSCRIBERS, MessageLabel {});
+    counter_metric_touch!(MQTT_MESSAGES_FORWARDED, MessageLabel {});
+    counter_metric_touch!(MQTT_MESSAGES_FORWARD_FAILED, MessageLabel {});
+    counter_metric_touch!(MQTT_MESSAGES_FORWARD_DELIVERED, MessageLabel {});
 }
 
 #[cfg(test)]
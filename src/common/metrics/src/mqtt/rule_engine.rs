@@ -0,0 +1,72 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{counter_metric_get, counter_metric_inc, register_counter_metric};
+use prometheus_client::encoding::EncodeLabelSet;
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct MessageRuleLabel {
+    pub tenant: String,
+    pub rule: String,
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct MessageRuleActionLabel {
+    pub tenant: String,
+    pub rule: String,
+    pub action: String,
+    pub result: String,
+}
+
+register_counter_metric!(
+    MQTT_MESSAGE_RULE_MATCHED_TOTAL,
+    "mqtt_message_rule_matched_total",
+    "Total number of messages matched by a rule (FROM topic and WHERE condition)",
+    MessageRuleLabel
+);
+
+register_counter_metric!(
+    MQTT_MESSAGE_RULE_ACTION_TOTAL,
+    "mqtt_message_rule_action_total",
+    "Total number of rule actions executed, by action type and result (success, failure)",
+    MessageRuleActionLabel
+);
+
+pub fn record_message_rule_matched(tenant: &str, rule: &str) {
+    let label = MessageRuleLabel {
+        tenant: tenant.to_string(),
+        rule: rule.to_string(),
+    };
+    counter_metric_inc!(MQTT_MESSAGE_RULE_MATCHED_TOTAL, label);
+}
+
+pub fn record_message_rule_action(tenant: &str, rule: &str, action: &str, success: bool) {
+    let label = MessageRuleActionLabel {
+        tenant: tenant.to_string(),
+        rule: rule.to_string(),
+        action: action.to_string(),
+        result: if success { "success" } else { "failure" }.to_string(),
+    };
+    counter_metric_inc!(MQTT_MESSAGE_RULE_ACTION_TOTAL, label);
+}
+
+pub fn get_message_rule_matched(tenant: &str, rule: &str) -> u64 {
+    let label = MessageRuleLabel {
+        tenant: tenant.to_string(),
+        rule: rule.to_string(),
+    };
+    let mut result = 0u64;
+    counter_metric_get!(MQTT_MESSAGE_RULE_MATCHED_TOTAL, label, result);
+    result
+}
@@ -13,7 +13,8 @@
 // limitations under the License.
 
 use crate::{
-    counter_metric_get, counter_metric_inc, counter_metric_touch, register_counter_metric,
+    counter_metric_get, counter_metric_inc, counter_metric_inc_by, counter_metric_touch,
+    register_counter_metric,
 };
 use prometheus_client::encoding::EncodeLabelSet;
 
@@ -31,6 +32,16 @@ pub struct ConnectionLabel {
     pub connection_id: u64,
 }
 
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct OfflineQueueDropLabel {
+    pub reason: String,
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct InflightDropLabel {
+    pub reason: String,
+}
+
 register_counter_metric!(
     MQTT_SESSION_CREATED,
     "mqtt_session_created",
@@ -73,6 +84,41 @@ pub struct ConnectionLabel {
     ConnectionLabel
 );
 
+register_counter_metric!(
+    MQTT_OFFLINE_QUEUE_ENQUEUED,
+    "mqtt_offline_queue_enqueued",
+    "Number of messages queued for offline persistent sessions",
+    NetworkLabel
+);
+
+register_counter_metric!(
+    MQTT_OFFLINE_QUEUE_FLUSHED,
+    "mqtt_offline_queue_flushed",
+    "Number of queued offline messages delivered after reconnect",
+    NetworkLabel
+);
+
+register_counter_metric!(
+    MQTT_OFFLINE_QUEUE_DROPPED,
+    "mqtt_offline_queue_dropped",
+    "Number of offline messages dropped from or rejected by a session queue",
+    OfflineQueueDropLabel
+);
+
+register_counter_metric!(
+    MQTT_INFLIGHT_REDELIVERED,
+    "mqtt_inflight_redelivered",
+    "Number of persisted inflight messages redelivered on session resumption",
+    NetworkLabel
+);
+
+register_counter_metric!(
+    MQTT_INFLIGHT_DROPPED,
+    "mqtt_inflight_dropped",
+    "Number of persisted inflight messages dropped instead of redelivered",
+    InflightDropLabel
+);
+
 pub fn record_mqtt_session_created() {
     let label = NetworkLabel {};
     counter_metric_inc!(MQTT_SESSION_CREATED, label);
@@ -83,9 +129,43 @@ pub fn record_mqtt_session_deleted() {
     counter_metric_inc!(MQTT_SESSION_DELETED, label);
 }
 
+pub fn record_mqtt_offline_queue_enqueued() {
+    let label = NetworkLabel {};
+    counter_metric_inc!(MQTT_OFFLINE_QUEUE_ENQUEUED, label);
+}
+
+pub fn record_mqtt_offline_queue_flushed() {
+    let label = NetworkLabel {};
+    counter_metric_inc!(MQTT_OFFLINE_QUEUE_FLUSHED, label);
+}
+
+/// `reason` is one of `full`, `rejected` or `expired`.
+pub fn record_mqtt_offline_queue_dropped(reason: &str, num: u64) {
+    let label = OfflineQueueDropLabel {
+        reason: reason.to_string(),
+    };
+    counter_metric_inc_by!(MQTT_OFFLINE_QUEUE_DROPPED, label, num);
+}
+
+pub fn record_mqtt_inflight_redelivered() {
+    let label = NetworkLabel {};
+    counter_metric_inc!(MQTT_INFLIGHT_REDELIVERED, label);
+}
+
+/// `reason` is one of `clean_start`, `expired` or `record_missing`.
+pub fn record_mqtt_inflight_dropped(reason: &str) {
+    let label = InflightDropLabel {
+        reason: reason.to_string(),
+    };
+    counter_metric_inc!(MQTT_INFLIGHT_DROPPED, label);
+}
+
 pub fn init() {
     counter_metric_touch!(MQTT_SESSION_CREATED, NetworkLabel {});
     counter_metric_touch!(MQTT_SESSION_DELETED, NetworkLabel {});
+    counter_metric_touch!(MQTT_OFFLINE_QUEUE_ENQUEUED, NetworkLabel {});
+    counter_metric_touch!(MQTT_OFFLINE_QUEUE_FLUSHED, NetworkLabel {});
+    counter_metric_touch!(MQTT_INFLIGHT_REDELIVERED, NetworkLabel {});
 }
 
 pub fn record_session_messages_in(tenant: &str, client_id: &str) {
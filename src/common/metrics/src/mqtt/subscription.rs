@@ -0,0 +1,268 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Per-subscription push metrics exported to Prometheus.
+//!
+//! A subscription is one (tenant, client_id, path, topic) delivered by this
+//! broker. The first `max_series` subscriptions seen get their own series;
+//! the counters of later ones are folded into a per-tenant series labelled
+//! [`OTHER_SUBSCRIPTION_LABEL`] and their gauges are not exported.
+
+use crate::{
+    counter_metric_get, counter_metric_inc, gauge_metric_get, gauge_metric_set,
+    register_counter_metric, register_gauge_metric,
+};
+use prometheus_client::encoding::EncodeLabelSet;
+use std::collections::HashSet;
+use std::sync::{LazyLock, RwLock};
+
+pub const OTHER_SUBSCRIPTION_LABEL: &str = "_other";
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SubscriptionLabelPolicy {
+    pub enable: bool,
+    /// Upper bound on subscriptions with their own series; 0 means unlimited.
+    pub max_series: usize,
+}
+
+#[derive(Default)]
+struct SubscriptionLabelState {
+    policy: SubscriptionLabelPolicy,
+    series: HashSet<SubscriptionLabel>,
+}
+
+static SUBSCRIPTION_LABEL_STATE: LazyLock<RwLock<SubscriptionLabelState>> =
+    LazyLock::new(|| RwLock::new(SubscriptionLabelState::default()));
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct SubscriptionLabel {
+    pub tenant: String,
+    pub client_id: String,
+    pub path: String,
+    pub topic: String,
+}
+
+impl SubscriptionLabel {
+    pub fn new(tenant: &str, client_id: &str, path: &str, topic: &str) -> Self {
+        SubscriptionLabel {
+            tenant: tenant.to_string(),
+            client_id: client_id.to_string(),
+            path: path.to_string(),
+            topic: topic.to_string(),
+        }
+    }
+
+    fn other(tenant: &str) -> Self {
+        SubscriptionLabel::new(
+            tenant,
+            OTHER_SUBSCRIPTION_LABEL,
+            OTHER_SUBSCRIPTION_LABEL,
+            OTHER_SUBSCRIPTION_LABEL,
+        )
+    }
+
+    pub fn is_other(&self) -> bool {
+        self.client_id == OTHER_SUBSCRIPTION_LABEL
+    }
+}
+
+register_counter_metric!(
+    MQTT_SUBSCRIPTION_DELIVERED,
+    "mqtt_subscription_delivered",
+    "Total number of messages delivered to a subscription (label subject to cardinality policy)",
+    SubscriptionLabel
+);
+
+register_counter_metric!(
+    MQTT_SUBSCRIPTION_DROPPED,
+    "mqtt_subscription_dropped",
+    "Total number of messages dropped for a subscription because they expired, were too large or were published by the subscriber itself (label subject to cardinality policy)",
+    SubscriptionLabel
+);
+
+register_counter_metric!(
+    MQTT_SUBSCRIPTION_ERRORS,
+    "mqtt_subscription_errors",
+    "Total number of failed pushes to a subscription (label subject to cardinality policy)",
+    SubscriptionLabel
+);
+
+register_gauge_metric!(
+    MQTT_SUBSCRIPTION_OFFSET,
+    "mqtt_subscription_offset",
+    "Committed offset of a subscription, summed across the shards of the topic",
+    SubscriptionLabel
+);
+
+register_gauge_metric!(
+    MQTT_SUBSCRIPTION_LAG,
+    "mqtt_subscription_lag",
+    "Number of messages in the topic not yet committed by a subscription",
+    SubscriptionLabel
+);
+
+register_gauge_metric!(
+    MQTT_SUBSCRIPTION_LAST_PUSH_TIME,
+    "mqtt_subscription_last_push_time",
+    "Unix time in seconds of the last message delivered to a subscription",
+    SubscriptionLabel
+);
+
+/// Replace the active policy. Already exported series are kept, but they no
+/// longer count towards `max_series`.
+pub fn set_subscription_label_policy(policy: SubscriptionLabelPolicy) {
+    let mut state = SUBSCRIPTION_LABEL_STATE.write().unwrap();
+    state.policy = policy;
+    state.series.clear();
+}
+
+pub fn subscription_metrics_enabled() -> bool {
+    SUBSCRIPTION_LABEL_STATE.read().unwrap().policy.enable
+}
+
+/// Resolve the series of a subscription, or `None` when subscription
+/// metrics are disabled.
+pub fn resolve_subscription_label(label: SubscriptionLabel) -> Option<SubscriptionLabel> {
+    {
+        let state = SUBSCRIPTION_LABEL_STATE.read().unwrap();
+        if !state.policy.enable {
+            return None;
+        }
+        if state.policy.max_series == 0 || state.series.contains(&label) {
+            return Some(label);
+        }
+    }
+
+    let mut state = SUBSCRIPTION_LABEL_STATE.write().unwrap();
+    if state.series.contains(&label) {
+        return Some(label);
+    }
+    if state.series.len() < state.policy.max_series {
+        state.series.insert(label.clone());
+        return Some(label);
+    }
+    Some(SubscriptionLabel::other(&label.tenant))
+}
+
+pub fn record_subscription_delivered(label: SubscriptionLabel) {
+    let Some(label) = resolve_subscription_label(label) else {
+        return;
+    };
+    counter_metric_inc!(MQTT_SUBSCRIPTION_DELIVERED, label);
+}
+
+pub fn record_subscription_dropped(label: SubscriptionLabel) {
+    let Some(label) = resolve_subscription_label(label) else {
+        return;
+    };
+    counter_metric_inc!(MQTT_SUBSCRIPTION_DROPPED, label);
+}
+
+pub fn record_subscription_error(label: SubscriptionLabel) {
+    let Some(label) = resolve_subscription_label(label) else {
+        return;
+    };
+    counter_metric_inc!(MQTT_SUBSCRIPTION_ERRORS, label);
+}
+
+/// Set the offset, lag and last push time of a subscription. Ignored for
+/// subscriptions without their own series.
+pub fn record_subscription_progress(
+    label: SubscriptionLabel,
+    offset: u64,
+    lag: u64,
+    last_push_time: u64,
+) {
+    let Some(label) = resolve_subscription_label(label) else {
+        return;
+    };
+    if label.is_other() {
+        return;
+    }
+    gauge_metric_set!(MQTT_SUBSCRIPTION_OFFSET, label, offset as i64);
+    gauge_metric_set!(MQTT_SUBSCRIPTION_LAG, label, lag as i64);
+    gauge_metric_set!(
+        MQTT_SUBSCRIPTION_LAST_PUSH_TIME,
+        label,
+        last_push_time as i64
+    );
+}
+
+/// Drop every series of a removed subscription and free its slot.
+pub fn remove_subscription_series(label: &SubscriptionLabel) {
+    {
+        let mut state = SUBSCRIPTION_LABEL_STATE.write().unwrap();
+        if !state.series.remove(label) && state.policy.max_series != 0 {
+            return;
+        }
+    }
+    MQTT_SUBSCRIPTION_DELIVERED.write().unwrap().remove(label);
+    MQTT_SUBSCRIPTION_DROPPED.write().unwrap().remove(label);
+    MQTT_SUBSCRIPTION_ERRORS.write().unwrap().remove(label);
+    MQTT_SUBSCRIPTION_OFFSET.write().unwrap().remove(label);
+    MQTT_SUBSCRIPTION_LAG.write().unwrap().remove(label);
+    MQTT_SUBSCRIPTION_LAST_PUSH_TIME
+        .write()
+        .unwrap()
+        .remove(label);
+}
+
+pub fn get_subscription_delivered(label: SubscriptionLabel) -> u64 {
+    let mut result = 0u64;
+    counter_metric_get!(MQTT_SUBSCRIPTION_DELIVERED, label, result);
+    result
+}
+
+pub fn get_subscription_lag(label: SubscriptionLabel) -> i64 {
+    let mut result = 0i64;
+    gauge_metric_get!(MQTT_SUBSCRIPTION_LAG, label, result);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscription_series_cap() {
+        set_subscription_label_policy(SubscriptionLabelPolicy {
+            enable: true,
+            max_series: 1,
+        });
+        let tenant = "subscription_metrics_test";
+        let first = SubscriptionLabel::new(tenant, "c1", "a/#", "a/b");
+        let second = SubscriptionLabel::new(tenant, "c2", "a/#", "a/b");
+
+        record_subscription_delivered(first.clone());
+        record_subscription_delivered(second.clone());
+        record_subscription_delivered(second.clone());
+        assert_eq!(get_subscription_delivered(first.clone()), 1);
+        assert_eq!(
+            get_subscription_delivered(SubscriptionLabel::other(tenant)),
+            2
+        );
+
+        record_subscription_progress(first.clone(), 10, 5, 100);
+        record_subscription_progress(second.clone(), 10, 7, 100);
+        assert_eq!(get_subscription_lag(first.clone()), 5);
+        assert_eq!(get_subscription_lag(second.clone()), 0);
+
+        // a removed subscription frees its slot
+        remove_subscription_series(&first);
+        record_subscription_delivered(second.clone());
+        assert_eq!(get_subscription_delivered(second), 1);
+
+        set_subscription_label_policy(SubscriptionLabelPolicy::default());
+    }
+}
@@ -13,7 +13,9 @@
 // limitations under the License.
 
 use crate::{
-    counter_metric_get, counter_metric_inc, counter_metric_inc_by, register_counter_metric,
+    counter_metric_get, counter_metric_inc, counter_metric_inc_by,
+    mqtt::topic_throughput::{lookup_topic_label, topic_label},
+    register_counter_metric,
 };
 use prometheus_client::encoding::EncodeLabelSet;
 
@@ -54,7 +56,7 @@ pub struct TopicLabel {
 pub fn record_topic_messages_written(tenant: &str, topic: &str) {
     let label = TopicLabel {
         tenant: tenant.to_string(),
-        topic: topic.to_string(),
+        topic: topic_label(tenant, topic),
     };
     counter_metric_inc!(TOPIC_MESSAGES_WRITTEN, label);
 }
@@ -62,7 +64,7 @@ pub fn record_topic_messages_written(tenant: &str, topic: &str) {
 pub fn record_topic_bytes_written(tenant: &str, topic: &str, bytes: u64) {
     let label = TopicLabel {
         tenant: tenant.to_string(),
-        topic: topic.to_string(),
+        topic: topic_label(tenant, topic),
     };
     counter_metric_inc_by!(TOPIC_BYTES_WRITTEN, label, bytes);
 }
@@ -70,7 +72,7 @@ pub fn record_topic_bytes_written(tenant: &str, topic: &str, bytes: u64) {
 pub fn record_topic_messages_sent(tenant: &str, topic: &str) {
     let label = TopicLabel {
         tenant: tenant.to_string(),
-        topic: topic.to_string(),
+        topic: topic_label(tenant, topic),
     };
     counter_metric_inc!(TOPIC_MESSAGES_SENT, label);
 }
@@ -78,7 +80,7 @@ pub fn record_topic_messages_sent(tenant: &str, topic: &str) {
 pub fn record_topic_bytes_sent(tenant: &str, topic: &str, bytes: u64) {
     let label = TopicLabel {
         tenant: tenant.to_string(),
-        topic: topic.to_string(),
+        topic: topic_label(tenant, topic),
     };
     counter_metric_inc_by!(TOPIC_BYTES_SENT, label, bytes);
 }
@@ -86,7 +88,7 @@ pub fn record_topic_bytes_sent(tenant: &str, topic: &str, bytes: u64) {
 pub fn get_topic_messages_written(tenant: &str, topic: &str) -> u64 {
     let label = TopicLabel {
         tenant: tenant.to_string(),
-        topic: topic.to_string(),
+        topic: lookup_topic_label(tenant, topic),
     };
     let mut result = 0u64;
     counter_metric_get!(TOPIC_MESSAGES_WRITTEN, label, result);
@@ -96,7 +98,7 @@ pub fn get_topic_messages_written(tenant: &str, topic: &str) -> u64 {
 pub fn get_topic_messages_sent(tenant: &str, topic: &str) -> u64 {
     let label = TopicLabel {
         tenant: tenant.to_string(),
-        topic: topic.to_string(),
+        topic: lookup_topic_label(tenant, topic),
     };
     let mut result = 0u64;
     counter_metric_get!(TOPIC_MESSAGES_SENT, label, result);
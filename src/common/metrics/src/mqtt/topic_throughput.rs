@@ -0,0 +1,259 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Per-topic publish/deliver throughput exported to Prometheus.
+//!
+//! Topic names are unbounded, so the exported `topic` label goes through a
+//! [`TopicLabelPolicy`] first: allowlisted topics keep their own series, other
+//! topics are folded into their first `prefix_levels` levels, and once
+//! `max_series` distinct labels exist every new label collapses into
+//! [`OTHER_TOPIC_LABEL`]. The `topic_*` series of [`super::topic`] share the
+//! same labels through [`topic_label`].
+
+use crate::{
+    counter_metric_get, counter_metric_inc, counter_metric_inc_by, register_counter_metric,
+};
+use common_base::utils::topic_util::topic_filter_match;
+use prometheus_client::encoding::EncodeLabelSet;
+use std::collections::HashSet;
+use std::sync::{LazyLock, RwLock};
+
+pub const OTHER_TOPIC_LABEL: &str = "_other";
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TopicLabelPolicy {
+    pub enable: bool,
+    /// Topic names or MQTT filters (`+` / `#`) that are always exported as-is.
+    pub allowlist: Vec<String>,
+    /// Number of leading topic levels kept for non-allowlisted topics.
+    /// 0 exports the full topic name.
+    pub prefix_levels: usize,
+    /// Upper bound on distinct topic labels; 0 means unlimited.
+    pub max_series: usize,
+}
+
+impl TopicLabelPolicy {
+    /// Resolve the label for `topic`, or `None` when per-topic metrics are disabled.
+    fn label(&self, topic: &str) -> Option<String> {
+        if !self.enable {
+            return None;
+        }
+
+        if self.allowlist.iter().any(|f| topic_filter_match(f, topic)) {
+            return Some(topic.to_string());
+        }
+
+        if self.prefix_levels == 0 {
+            return Some(topic.to_string());
+        }
+
+        let levels: Vec<&str> = topic.split('/').collect();
+        if levels.len() <= self.prefix_levels {
+            return Some(topic.to_string());
+        }
+        Some(format!("{}/#", levels[..self.prefix_levels].join("/")))
+    }
+}
+
+#[derive(Default)]
+struct TopicLabelState {
+    policy: TopicLabelPolicy,
+    series: HashSet<(String, String)>,
+}
+
+static TOPIC_LABEL_STATE: LazyLock<RwLock<TopicLabelState>> =
+    LazyLock::new(|| RwLock::new(TopicLabelState::default()));
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct TopicThroughputLabel {
+    pub tenant: String,
+    pub topic: String,
+}
+
+register_counter_metric!(
+    MQTT_TOPIC_PUBLISH_MESSAGES,
+    "mqtt_topic_publish_messages",
+    "Total number of messages published to a topic (label subject to cardinality policy)",
+    TopicThroughputLabel
+);
+
+register_counter_metric!(
+    MQTT_TOPIC_PUBLISH_BYTES,
+    "mqtt_topic_publish_bytes",
+    "Total payload bytes published to a topic (label subject to cardinality policy)",
+    TopicThroughputLabel
+);
+
+register_counter_metric!(
+    MQTT_TOPIC_DELIVER_MESSAGES,
+    "mqtt_topic_deliver_messages",
+    "Total number of messages delivered to subscribers from a topic (label subject to cardinality policy)",
+    TopicThroughputLabel
+);
+
+register_counter_metric!(
+    MQTT_TOPIC_DELIVER_BYTES,
+    "mqtt_topic_deliver_bytes",
+    "Total payload bytes delivered to subscribers from a topic (label subject to cardinality policy)",
+    TopicThroughputLabel
+);
+
+/// Replace the active policy. Already exported series are kept, but they no
+/// longer count towards `max_series`.
+pub fn set_topic_label_policy(policy: TopicLabelPolicy) {
+    let mut state = TOPIC_LABEL_STATE.write().unwrap();
+    state.policy = policy;
+    state.series.clear();
+}
+
+pub fn topic_label_policy() -> TopicLabelPolicy {
+    TOPIC_LABEL_STATE.read().unwrap().policy.clone()
+}
+
+/// Topic label for `topic` under the active policy, or `None` when per-topic
+/// metrics are disabled. With `reserve`, a new label takes one of the
+/// `max_series` slots; without it, a label that has none reads as
+/// [`OTHER_TOPIC_LABEL`].
+fn resolve_topic(tenant: &str, topic: &str, reserve: bool) -> Option<String> {
+    let key = {
+        let state = TOPIC_LABEL_STATE.read().unwrap();
+        let label = state.policy.label(topic)?;
+        let key = (tenant.to_string(), label);
+        if state.policy.max_series == 0 || state.series.contains(&key) {
+            return Some(key.1);
+        }
+        if !reserve {
+            return Some(OTHER_TOPIC_LABEL.to_string());
+        }
+        key
+    };
+
+    let mut state = TOPIC_LABEL_STATE.write().unwrap();
+    if state.series.contains(&key) {
+        Some(key.1)
+    } else if state.series.len() < state.policy.max_series {
+        state.series.insert(key.clone());
+        Some(key.1)
+    } else {
+        Some(OTHER_TOPIC_LABEL.to_string())
+    }
+}
+
+fn resolve_label(tenant: &str, topic: &str) -> Option<TopicThroughputLabel> {
+    let topic = resolve_topic(tenant, topic, true)?;
+    Some(TopicThroughputLabel {
+        tenant: tenant.to_string(),
+        topic,
+    })
+}
+
+/// Label the `topic_*` series use for `topic`. They are always exported, so
+/// with the policy disabled the topic name is kept as it is.
+pub fn topic_label(tenant: &str, topic: &str) -> String {
+    resolve_topic(tenant, topic, true).unwrap_or_else(|| topic.to_string())
+}
+
+/// Like [`topic_label`], for reading a series without taking a slot for it.
+pub fn lookup_topic_label(tenant: &str, topic: &str) -> String {
+    resolve_topic(tenant, topic, false).unwrap_or_else(|| topic.to_string())
+}
+
+pub fn record_topic_publish(tenant: &str, topic: &str, bytes: u64) {
+    let Some(label) = resolve_label(tenant, topic) else {
+        return;
+    };
+    counter_metric_inc!(MQTT_TOPIC_PUBLISH_MESSAGES, label);
+    counter_metric_inc_by!(MQTT_TOPIC_PUBLISH_BYTES, label, bytes);
+}
+
+pub fn record_topic_deliver(tenant: &str, topic: &str, bytes: u64) {
+    let Some(label) = resolve_label(tenant, topic) else {
+        return;
+    };
+    counter_metric_inc!(MQTT_TOPIC_DELIVER_MESSAGES, label);
+    counter_metric_inc_by!(MQTT_TOPIC_DELIVER_BYTES, label, bytes);
+}
+
+pub fn get_topic_publish_messages(tenant: &str, topic_label: &str) -> u64 {
+    let label = TopicThroughputLabel {
+        tenant: tenant.to_string(),
+        topic: topic_label.to_string(),
+    };
+    let mut result = 0u64;
+    counter_metric_get!(MQTT_TOPIC_PUBLISH_MESSAGES, label, result);
+    result
+}
+
+pub fn get_topic_deliver_messages(tenant: &str, topic_label: &str) -> u64 {
+    let label = TopicThroughputLabel {
+        tenant: tenant.to_string(),
+        topic: topic_label.to_string(),
+    };
+    let mut result = 0u64;
+    counter_metric_get!(MQTT_TOPIC_DELIVER_MESSAGES, label, result);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_label() {
+        let policy = TopicLabelPolicy {
+            enable: true,
+            allowlist: vec!["factory/+/alarm".to_string()],
+            prefix_levels: 2,
+            max_series: 0,
+        };
+        assert_eq!(
+            policy.label("factory/1/alarm"),
+            Some("factory/1/alarm".to_string())
+        );
+        assert_eq!(
+            policy.label("factory/1/sensor/temp"),
+            Some("factory/1/#".to_string())
+        );
+        assert_eq!(policy.label("status"), Some("status".to_string()));
+
+        let disabled = TopicLabelPolicy::default();
+        assert_eq!(disabled.label("a/b"), None);
+    }
+
+    #[test]
+    fn test_topic_throughput_series_cap() {
+        set_topic_label_policy(TopicLabelPolicy {
+            enable: true,
+            allowlist: Vec::new(),
+            prefix_levels: 0,
+            max_series: 1,
+        });
+        let tenant = "topic_throughput_test";
+        record_topic_publish(tenant, "t/1", 10);
+        record_topic_publish(tenant, "t/2", 10);
+        record_topic_deliver(tenant, "t/1", 10);
+
+        assert_eq!(get_topic_publish_messages(tenant, "t/1"), 1);
+        assert_eq!(get_topic_publish_messages(tenant, "t/2"), 0);
+        assert_eq!(get_topic_publish_messages(tenant, OTHER_TOPIC_LABEL), 1);
+        assert_eq!(get_topic_deliver_messages(tenant, "t/1"), 1);
+        assert_eq!(lookup_topic_label(tenant, "t/1"), "t/1");
+        assert_eq!(lookup_topic_label(tenant, "t/3"), OTHER_TOPIC_LABEL);
+
+        set_topic_label_policy(TopicLabelPolicy::default());
+        record_topic_publish(tenant, "t/1", 10);
+        assert_eq!(get_topic_publish_messages(tenant, "t/1"), 1);
+        assert_eq!(topic_label(tenant, "t/3"), "t/3");
+    }
+}
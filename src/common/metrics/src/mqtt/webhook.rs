@@ -0,0 +1,134 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    counter_metric_get, counter_metric_inc_by, gauge_metric_set, histogram_metric_observe,
+    register_counter_metric, register_gauge_metric,
+    register_histogram_metric_ms_with_default_buckets,
+};
+use prometheus_client::encoding::EncodeLabelSet;
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct WebhookEndpointLabel {
+    pub endpoint: String,
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct WebhookResultLabel {
+    pub endpoint: String,
+    pub result: String,
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct WebhookEventLabel {
+    pub event: String,
+}
+
+register_counter_metric!(
+    MQTT_WEBHOOK_EVENTS_TOTAL,
+    "mqtt_webhook_events_total",
+    "Total number of events handled by webhook endpoint, by result (success, failure, circuit_open)",
+    WebhookResultLabel
+);
+
+register_counter_metric!(
+    MQTT_WEBHOOK_REQUESTS_TOTAL,
+    "mqtt_webhook_requests_total",
+    "Total number of HTTP requests issued by webhook endpoint, retries included",
+    WebhookResultLabel
+);
+
+register_histogram_metric_ms_with_default_buckets!(
+    MQTT_WEBHOOK_REQUEST_DURATION_MS,
+    "mqtt_webhook_request_duration_ms",
+    "Duration of webhook HTTP requests in milliseconds",
+    WebhookEndpointLabel
+);
+
+register_gauge_metric!(
+    MQTT_WEBHOOK_CIRCUIT_OPEN,
+    "mqtt_webhook_circuit_open",
+    "Webhook endpoint circuit breaker state, 1 means open and 0 means closed",
+    WebhookEndpointLabel
+);
+
+register_counter_metric!(
+    MQTT_WEBHOOK_EVENTS_DROPPED_TOTAL,
+    "mqtt_webhook_events_dropped_total",
+    "Total number of webhook events dropped because the event queue was full",
+    WebhookEventLabel
+);
+
+pub fn record_webhook_events(endpoint: &str, result: &'static str, count: u64) {
+    let label = WebhookResultLabel {
+        endpoint: endpoint.to_string(),
+        result: result.to_string(),
+    };
+    counter_metric_inc_by!(MQTT_WEBHOOK_EVENTS_TOTAL, label, count);
+}
+
+pub fn record_webhook_request(endpoint: &str, success: bool, duration_ms: f64) {
+    let label = WebhookResultLabel {
+        endpoint: endpoint.to_string(),
+        result: if success { "success" } else { "failure" }.to_string(),
+    };
+    counter_metric_inc_by!(MQTT_WEBHOOK_REQUESTS_TOTAL, label, 1);
+
+    let label = WebhookEndpointLabel {
+        endpoint: endpoint.to_string(),
+    };
+    histogram_metric_observe!(MQTT_WEBHOOK_REQUEST_DURATION_MS, duration_ms, label);
+}
+
+pub fn set_webhook_circuit_open(endpoint: &str, open: bool) {
+    let label = WebhookEndpointLabel {
+        endpoint: endpoint.to_string(),
+    };
+    gauge_metric_set!(MQTT_WEBHOOK_CIRCUIT_OPEN, label, open as i64);
+}
+
+pub fn record_webhook_event_dropped(event: &str) {
+    let label = WebhookEventLabel {
+        event: event.to_string(),
+    };
+    counter_metric_inc_by!(MQTT_WEBHOOK_EVENTS_DROPPED_TOTAL, label, 1);
+}
+
+pub fn get_webhook_events(endpoint: &str, result: &str) -> u64 {
+    let label = WebhookResultLabel {
+        endpoint: endpoint.to_string(),
+        result: result.to_string(),
+    };
+    let mut result = 0u64;
+    counter_metric_get!(MQTT_WEBHOOK_EVENTS_TOTAL, label, result);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_webhook_metrics() {
+        let endpoint = "webhook_metrics_test";
+        record_webhook_events(endpoint, "success", 3);
+        record_webhook_events(endpoint, "failure", 1);
+        record_webhook_request(endpoint, true, 12.0);
+        set_webhook_circuit_open(endpoint, true);
+        record_webhook_event_dropped("client.connected");
+
+        assert_eq!(get_webhook_events(endpoint, "success"), 3);
+        assert_eq!(get_webhook_events(endpoint, "failure"), 1);
+    }
+}
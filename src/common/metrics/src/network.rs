@@ -122,6 +122,27 @@ pub struct BrokerThreadLabel {
     NetworkLabel
 );
 
+register_histogram_metric_ms_with_default_buckets!(
+    WRITE_FLUSH_LATENCY_MS,
+    "write_flush_latency_ms",
+    "Time from the first packet buffered for a client to the coalesced flush completing (ms)",
+    NetworkLabel
+);
+
+register_gauge_metric!(
+    WRITE_FLUSH_TOTAL,
+    "write_flush_total",
+    "Total number of coalesced socket flushes",
+    NetworkLabel
+);
+
+register_gauge_metric!(
+    WRITE_FLUSH_PACKETS_TOTAL,
+    "write_flush_packets_total",
+    "Total number of packets sent by coalesced socket flushes",
+    NetworkLabel
+);
+
 // ── Per-handler-instance metrics ────────────────────────────────────────────
 
 #[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
@@ -246,6 +267,23 @@ pub fn metrics_write_client_ms(network: &NetworkConnectionType, ms: f64) {
     histogram_metric_observe!(WRITE_CLIENT_MS, ms, label);
 }
 
+/// One coalesced flush of `packets` buffered packets, `ms` after the first
+/// of them was buffered.
+pub fn metrics_write_flush(network: &NetworkConnectionType, ms: f64, packets: u64) {
+    let label = NetworkLabel {
+        network: network.to_string(),
+    };
+    histogram_metric_observe!(WRITE_FLUSH_LATENCY_MS, ms, label);
+    let label = NetworkLabel {
+        network: network.to_string(),
+    };
+    gauge_metric_inc_by!(WRITE_FLUSH_TOTAL, label, 1);
+    let label = NetworkLabel {
+        network: network.to_string(),
+    };
+    gauge_metric_inc_by!(WRITE_FLUSH_PACKETS_TOTAL, label, packets as i64);
+}
+
 /// Pre-register all network metrics (Gauges + Histograms) for every known
 /// `NetworkConnectionType` so they appear in `/metrics` on startup.
 pub fn init() {
@@ -272,6 +310,20 @@ pub fn init() {
                 network: net.to_string()
             }
         );
+        let label = NetworkLabel {
+            network: net.to_string(),
+        };
+        gauge_metric_set!(WRITE_FLUSH_TOTAL, label, 0);
+        let label = NetworkLabel {
+            network: net.to_string(),
+        };
+        gauge_metric_set!(WRITE_FLUSH_PACKETS_TOTAL, label, 0);
+        histogram_metric_touch!(
+            WRITE_FLUSH_LATENCY_MS,
+            NetworkLabel {
+                network: net.to_string()
+            }
+        );
 
         // Latency histograms — pre-register so bucket series exist from startup
         histogram_metric_touch!(
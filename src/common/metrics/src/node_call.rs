@@ -0,0 +1,193 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    counter_metric_get, counter_metric_inc, gauge_metric_get, gauge_metric_set,
+    register_counter_metric, register_gauge_metric,
+};
+use prometheus_client::encoding::EncodeLabelSet;
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct NodeCallNodeLabel {
+    pub node_id: String,
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct NodeCallResourceLabel {
+    pub node_id: String,
+    pub resource_type: String,
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct NodeCallPriorityLabel {
+    pub node_id: String,
+    pub priority: String,
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct NodeCallResultLabel {
+    pub node_id: String,
+    pub result: String,
+}
+
+register_gauge_metric!(
+    NODE_CALL_DEAD_LETTER_PENDING,
+    "node_call_dead_letter_pending",
+    "Number of cache notifications waiting in the dead-letter queue of a broker node",
+    NodeCallNodeLabel
+);
+
+register_counter_metric!(
+    NODE_CALL_DEAD_LETTER_REPLAY,
+    "node_call_dead_letter_replay",
+    "Number of dead-letter replay attempts by node and result (success, failure)",
+    NodeCallResultLabel
+);
+
+register_counter_metric!(
+    NODE_CALL_CACHE_RESYNC,
+    "node_call_cache_resync",
+    "Number of full cache resync requests by node and result (success, failure)",
+    NodeCallResultLabel
+);
+
+register_gauge_metric!(
+    NODE_CALL_CACHE_BEHIND_SECONDS,
+    "node_call_cache_behind_seconds",
+    "Seconds a broker node has been behind the cache notifications sent to it, by resource type",
+    NodeCallResourceLabel
+);
+
+register_counter_metric!(
+    NODE_CALL_CACHE_DIVERGENCE,
+    "node_call_cache_divergence",
+    "Number of times a broker node stayed behind its cache notifications past the alarm threshold",
+    NodeCallResourceLabel
+);
+
+register_gauge_metric!(
+    NODE_CALL_QUEUE_DEPTH,
+    "node_call_queue_depth",
+    "Number of node calls waiting in the queues of a broker node, by priority class",
+    NodeCallPriorityLabel
+);
+
+register_counter_metric!(
+    NODE_CALL_QUEUE_DROPPED,
+    "node_call_queue_dropped",
+    "Number of node calls dropped because the queue of a broker node was full, by priority class",
+    NodeCallPriorityLabel
+);
+
+pub fn set_node_call_dead_letter_pending(node_id: u64, pending: u64) {
+    let label = NodeCallNodeLabel {
+        node_id: node_id.to_string(),
+    };
+    gauge_metric_set!(NODE_CALL_DEAD_LETTER_PENDING, label, pending as i64);
+}
+
+pub fn get_node_call_dead_letter_pending(node_id: u64) -> i64 {
+    let label = NodeCallNodeLabel {
+        node_id: node_id.to_string(),
+    };
+    let mut result = 0;
+    gauge_metric_get!(NODE_CALL_DEAD_LETTER_PENDING, label, result);
+    result
+}
+
+fn result_label(node_id: u64, success: bool) -> NodeCallResultLabel {
+    NodeCallResultLabel {
+        node_id: node_id.to_string(),
+        result: if success { "success" } else { "failure" }.to_string(),
+    }
+}
+
+pub fn record_node_call_dead_letter_replay(node_id: u64, success: bool) {
+    let label = result_label(node_id, success);
+    counter_metric_inc!(NODE_CALL_DEAD_LETTER_REPLAY, label);
+}
+
+pub fn record_node_call_cache_resync(node_id: u64, success: bool) {
+    let label = result_label(node_id, success);
+    counter_metric_inc!(NODE_CALL_CACHE_RESYNC, label);
+}
+
+pub fn get_node_call_cache_resync(node_id: u64, success: bool) -> u64 {
+    let label = result_label(node_id, success);
+    let mut result = 0;
+    counter_metric_get!(NODE_CALL_CACHE_RESYNC, label, result);
+    result
+}
+
+fn resource_label(node_id: u64, resource_type: &str) -> NodeCallResourceLabel {
+    NodeCallResourceLabel {
+        node_id: node_id.to_string(),
+        resource_type: resource_type.to_string(),
+    }
+}
+
+pub fn set_node_call_cache_behind_seconds(node_id: u64, resource_type: &str, seconds: u64) {
+    let label = resource_label(node_id, resource_type);
+    gauge_metric_set!(NODE_CALL_CACHE_BEHIND_SECONDS, label, seconds as i64);
+}
+
+pub fn get_node_call_cache_behind_seconds(node_id: u64, resource_type: &str) -> i64 {
+    let label = resource_label(node_id, resource_type);
+    let mut result = 0;
+    gauge_metric_get!(NODE_CALL_CACHE_BEHIND_SECONDS, label, result);
+    result
+}
+
+pub fn record_node_call_cache_divergence(node_id: u64, resource_type: &str) {
+    let label = resource_label(node_id, resource_type);
+    counter_metric_inc!(NODE_CALL_CACHE_DIVERGENCE, label);
+}
+
+pub fn get_node_call_cache_divergence(node_id: u64, resource_type: &str) -> u64 {
+    let label = resource_label(node_id, resource_type);
+    let mut result = 0;
+    counter_metric_get!(NODE_CALL_CACHE_DIVERGENCE, label, result);
+    result
+}
+
+fn priority_label(node_id: u64, priority: &str) -> NodeCallPriorityLabel {
+    NodeCallPriorityLabel {
+        node_id: node_id.to_string(),
+        priority: priority.to_string(),
+    }
+}
+
+pub fn set_node_call_queue_depth(node_id: u64, priority: &str, depth: u64) {
+    let label = priority_label(node_id, priority);
+    gauge_metric_set!(NODE_CALL_QUEUE_DEPTH, label, depth as i64);
+}
+
+pub fn get_node_call_queue_depth(node_id: u64, priority: &str) -> i64 {
+    let label = priority_label(node_id, priority);
+    let mut result = 0;
+    gauge_metric_get!(NODE_CALL_QUEUE_DEPTH, label, result);
+    result
+}
+
+pub fn record_node_call_queue_dropped(node_id: u64, priority: &str) {
+    let label = priority_label(node_id, priority);
+    counter_metric_inc!(NODE_CALL_QUEUE_DROPPED, label);
+}
+
+pub fn get_node_call_queue_dropped(node_id: u64, priority: &str) -> u64 {
+    let label = priority_label(node_id, priority);
+    let mut result = 0;
+    counter_metric_get!(NODE_CALL_QUEUE_DROPPED, label, result);
+    result
+}
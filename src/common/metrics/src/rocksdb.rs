@@ -13,8 +13,8 @@
 // limitations under the License.
 
 use crate::{
-    counter_metric_inc, histogram_metric_observe, register_counter_metric,
-    register_histogram_metric_ms_with_default_buckets,
+    counter_metric_inc, gauge_metric_set, histogram_metric_observe, register_counter_metric,
+    register_gauge_metric, register_histogram_metric_ms_with_default_buckets,
 };
 use prometheus_client::encoding::EncodeLabelSet;
 
@@ -24,6 +24,11 @@ pub struct RocksdbLabel {
     operation: String,
 }
 
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+pub struct RocksdbColumnFamilyLabel {
+    column_family: String,
+}
+
 register_histogram_metric_ms_with_default_buckets!(
     ROCKSDB_OPERATION_MS,
     "rocksdb_operation_ms",
@@ -38,6 +43,75 @@ pub struct RocksdbLabel {
     RocksdbLabel
 );
 
+register_gauge_metric!(
+    ROCKSDB_COLUMN_FAMILY_SST_BYTES,
+    "rocksdb_column_family_sst_bytes",
+    "Total size of the SST files of the column family",
+    RocksdbColumnFamilyLabel
+);
+
+register_gauge_metric!(
+    ROCKSDB_COLUMN_FAMILY_MEMTABLE_BYTES,
+    "rocksdb_column_family_memtable_bytes",
+    "Size of the memtables of the column family",
+    RocksdbColumnFamilyLabel
+);
+
+register_gauge_metric!(
+    ROCKSDB_COLUMN_FAMILY_ESTIMATE_KEYS,
+    "rocksdb_column_family_estimate_keys",
+    "Estimated number of keys in the column family",
+    RocksdbColumnFamilyLabel
+);
+
+register_gauge_metric!(
+    ROCKSDB_COLUMN_FAMILY_PENDING_COMPACTION_BYTES,
+    "rocksdb_column_family_pending_compaction_bytes",
+    "Estimated bytes compaction has to rewrite to bring the column family into shape",
+    RocksdbColumnFamilyLabel
+);
+
+register_gauge_metric!(
+    ROCKSDB_COLUMN_FAMILY_LEVEL0_FILES,
+    "rocksdb_column_family_level0_files",
+    "Number of level 0 SST files of the column family",
+    RocksdbColumnFamilyLabel
+);
+
+pub fn metrics_rocksdb_column_family_set(
+    column_family: &str,
+    sst_bytes: u64,
+    memtable_bytes: u64,
+    estimate_keys: u64,
+    pending_compaction_bytes: u64,
+    level0_files: u64,
+) {
+    let label = RocksdbColumnFamilyLabel {
+        column_family: column_family.to_string(),
+    };
+    gauge_metric_set!(ROCKSDB_COLUMN_FAMILY_SST_BYTES, label, sst_bytes as i64);
+    gauge_metric_set!(
+        ROCKSDB_COLUMN_FAMILY_MEMTABLE_BYTES,
+        label,
+        memtable_bytes as i64
+    );
+    gauge_metric_set!(
+        ROCKSDB_COLUMN_FAMILY_ESTIMATE_KEYS,
+        label,
+        estimate_keys as i64
+    );
+    gauge_metric_set!(
+        ROCKSDB_COLUMN_FAMILY_PENDING_COMPACTION_BYTES,
+        label,
+        pending_compaction_bytes as i64
+    );
+    gauge_metric_set!(
+        ROCKSDB_COLUMN_FAMILY_LEVEL0_FILES,
+        label,
+        level0_files as i64
+    );
+}
+
 pub fn metrics_rocksdb_save_ms(source: &str, ms: f64) {
     let label = RocksdbLabel {
         source: source.to_string(),
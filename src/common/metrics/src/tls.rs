@@ -0,0 +1,114 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    counter_metric_get, counter_metric_inc, gauge_metric_get, gauge_metric_set,
+    histogram_metric_observe, register_counter_metric, register_gauge_metric,
+    register_histogram_metric_ms_with_default_buckets,
+};
+use prometheus_client::encoding::EncodeLabelSet;
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct TlsCertLabel {
+    cert: String,
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct TlsReloadLabel {
+    result: String,
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct TlsHandshakeLabel {
+    kind: String,
+}
+
+#[derive(Eq, Hash, Clone, EncodeLabelSet, Debug, PartialEq)]
+struct TlsHandshakeFailureLabel {
+    reason: String,
+}
+
+register_gauge_metric!(
+    TLS_CERT_EXPIRY_DAYS,
+    "tls_cert_expiry_days",
+    "Days until the TLS certificate served by the broker expires, negative once expired",
+    TlsCertLabel
+);
+
+register_counter_metric!(
+    TLS_CERT_RELOAD,
+    "tls_cert_reload",
+    "Number of TLS certificate reloads, by result (success, failure)",
+    TlsReloadLabel
+);
+
+register_histogram_metric_ms_with_default_buckets!(
+    TLS_HANDSHAKE_MS,
+    "tls_handshake_ms",
+    "Time from accepting a TLS connection to a completed handshake, by kind (full, resumed) (ms)",
+    TlsHandshakeLabel
+);
+
+register_counter_metric!(
+    TLS_HANDSHAKE_FAILURE,
+    "tls_handshake_failure",
+    "Number of TLS connections closed before completing the handshake, by reason (error, timeout, rejected)",
+    TlsHandshakeFailureLabel
+);
+
+pub fn set_tls_cert_expiry_days(cert: &str, days: i64) {
+    let label = TlsCertLabel {
+        cert: cert.to_string(),
+    };
+    gauge_metric_set!(TLS_CERT_EXPIRY_DAYS, label, days);
+}
+
+pub fn get_tls_cert_expiry_days(cert: &str) -> i64 {
+    let label = TlsCertLabel {
+        cert: cert.to_string(),
+    };
+    let mut result = 0i64;
+    gauge_metric_get!(TLS_CERT_EXPIRY_DAYS, label, result);
+    result
+}
+
+pub fn record_tls_cert_reload(success: bool) {
+    let label = TlsReloadLabel {
+        result: if success { "success" } else { "failure" }.to_string(),
+    };
+    counter_metric_inc!(TLS_CERT_RELOAD, label);
+}
+
+pub fn record_tls_handshake(resumed: bool, duration_ms: f64) {
+    let label = TlsHandshakeLabel {
+        kind: if resumed { "resumed" } else { "full" }.to_string(),
+    };
+    histogram_metric_observe!(TLS_HANDSHAKE_MS, duration_ms, label);
+}
+
+pub fn record_tls_handshake_failure(reason: &str) {
+    let label = TlsHandshakeFailureLabel {
+        reason: reason.to_string(),
+    };
+    counter_metric_inc!(TLS_HANDSHAKE_FAILURE, label);
+}
+
+pub fn get_tls_handshake_failure(reason: &str) -> u64 {
+    let label = TlsHandshakeFailureLabel {
+        reason: reason.to_string(),
+    };
+    let mut result = 0;
+    counter_metric_get!(TLS_HANDSHAKE_FAILURE, label, result);
+    result
+}
@@ -0,0 +1,285 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::tls_acceptor::{load_certs, load_key};
+use common_base::error::common::CommonError;
+use common_base::error::ResultCommonError;
+use common_base::tools::{loop_select_ticket, now_second};
+use common_config::broker::broker_config;
+use common_config::config::BrokerConfig;
+use common_metrics::tls::{record_tls_cert_reload, set_tls_cert_expiry_days};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::SystemTime;
+use tokio::sync::broadcast;
+use tokio_rustls::rustls::crypto::ring::sign::any_supported_type;
+use tokio_rustls::rustls::server::{ClientHello, ResolvesServerCert};
+use tokio_rustls::rustls::sign::CertifiedKey;
+use tracing::{error, info};
+use x509_parser::parse_x509_certificate;
+
+const ACME_CERT_FILE: &str = "fullchain.pem";
+const ACME_KEY_FILE: &str = "privkey.pem";
+
+static CERT_MANAGER: OnceLock<Arc<CertManager>> = OnceLock::new();
+
+/// The certificate shared by all TLS listeners of this broker. Every new
+/// handshake resolves the current certificate, so a reload takes effect for new
+/// connections while established ones keep their session.
+#[derive(Debug)]
+pub struct CertManager {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    ocsp_path: String,
+    current: RwLock<Arc<CertifiedKey>>,
+    // newest modification time of the watched files at the last load
+    modified: Mutex<Option<SystemTime>>,
+    // certificate notAfter, unix seconds
+    not_after: AtomicI64,
+    reload_started: AtomicBool,
+}
+
+pub fn cert_manager() -> Result<Arc<CertManager>, CommonError> {
+    if let Some(manager) = CERT_MANAGER.get() {
+        return Ok(manager.clone());
+    }
+    let manager = Arc::new(CertManager::new(broker_config())?);
+    Ok(CERT_MANAGER.get_or_init(|| manager).clone())
+}
+
+struct LoadedCert {
+    certified: CertifiedKey,
+    not_after: i64,
+    modified: Option<SystemTime>,
+}
+
+impl CertManager {
+    pub fn new(conf: &BrokerConfig) -> Result<Self, CommonError> {
+        let (cert_path, key_path) = cert_paths(conf);
+        let ocsp_path = conf.mqtt_tls.ocsp_response.clone();
+        let loaded = load_cert(&cert_path, &key_path, &ocsp_path)?;
+        let manager = CertManager {
+            cert_path,
+            key_path,
+            ocsp_path,
+            current: RwLock::new(Arc::new(loaded.certified)),
+            modified: Mutex::new(loaded.modified),
+            not_after: AtomicI64::new(loaded.not_after),
+            reload_started: AtomicBool::new(false),
+        };
+        manager.update_expiry_metric();
+        Ok(manager)
+    }
+
+    pub fn cert_path(&self) -> &Path {
+        &self.cert_path
+    }
+
+    /// Reload the certificate if any of the watched files changed since the
+    /// last load. On failure the previous certificate stays in use.
+    pub fn reload_if_changed(&self) -> Result<bool, CommonError> {
+        let modified = latest_modified(&self.cert_path, &self.key_path, &self.ocsp_path);
+        if modified == *self.modified.lock().unwrap() {
+            return Ok(false);
+        }
+
+        let loaded = load_cert(&self.cert_path, &self.key_path, &self.ocsp_path)?;
+        *self.current.write().unwrap() = Arc::new(loaded.certified);
+        *self.modified.lock().unwrap() = loaded.modified;
+        self.not_after.store(loaded.not_after, Ordering::Relaxed);
+        self.update_expiry_metric();
+        Ok(true)
+    }
+
+    pub fn expiry_days(&self) -> i64 {
+        (self.not_after.load(Ordering::Relaxed) - now_second() as i64).div_euclid(86400)
+    }
+
+    fn update_expiry_metric(&self) {
+        set_tls_cert_expiry_days(&self.cert_path.to_string_lossy(), self.expiry_days());
+    }
+}
+
+impl ResolvesServerCert for CertManager {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.current.read().unwrap().clone())
+    }
+}
+
+/// Start the background check for certificate changes. Only the first call
+/// starts a task; later calls from other listeners are no-ops.
+pub fn start_cert_reload_thread(manager: Arc<CertManager>, stop_send: broadcast::Sender<bool>) {
+    let interval_sec = broker_config().runtime.tls_reload_interval_sec;
+    if interval_sec == 0 || manager.reload_started.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tokio::spawn(Box::pin(async move {
+        let ac_fn = async || -> ResultCommonError {
+            match manager.reload_if_changed() {
+                Ok(true) => {
+                    record_tls_cert_reload(true);
+                    info!(
+                        "TLS certificate {} reloaded, expires in {} days",
+                        manager.cert_path.display(),
+                        manager.expiry_days()
+                    );
+                }
+                Ok(false) => manager.update_expiry_metric(),
+                Err(e) => {
+                    record_tls_cert_reload(false);
+                    error!(
+                        "Failed to reload TLS certificate {}, keeping the current one: {}",
+                        manager.cert_path.display(),
+                        e
+                    );
+                }
+            }
+            Ok(())
+        };
+        loop_select_ticket(ac_fn, interval_sec * 1000, &stop_send).await;
+    }));
+}
+
+/// Certificate and private key files of the broker, as `(cert, key)`.
+pub fn cert_paths(conf: &BrokerConfig) -> (PathBuf, PathBuf) {
+    if conf.runtime.tls_acme_dir.is_empty() {
+        return (
+            PathBuf::from(&conf.runtime.tls_cert),
+            PathBuf::from(&conf.runtime.tls_key),
+        );
+    }
+    let dir = Path::new(&conf.runtime.tls_acme_dir);
+    (dir.join(ACME_CERT_FILE), dir.join(ACME_KEY_FILE))
+}
+
+fn load_cert(
+    cert_path: &Path,
+    key_path: &Path,
+    ocsp_path: &str,
+) -> Result<LoadedCert, CommonError> {
+    // read the timestamps first so that a write racing with the load is
+    // picked up by the next check
+    let modified = latest_modified(cert_path, key_path, ocsp_path);
+    let certs = load_certs(cert_path)?;
+    let key = load_key(key_path)?;
+    let Some(leaf) = certs.first() else {
+        return Err(CommonError::CommonError(format!(
+            "No certificate found in {}",
+            cert_path.display()
+        )));
+    };
+    let not_after = cert_not_after(leaf.as_ref())?;
+
+    let mut certified = CertifiedKey::new(certs, any_supported_type(&key)?);
+    let ocsp = load_ocsp_response(ocsp_path)?;
+    if !ocsp.is_empty() {
+        certified.ocsp = Some(ocsp);
+    }
+    Ok(LoadedCert {
+        certified,
+        not_after,
+        modified,
+    })
+}
+
+fn latest_modified(cert_path: &Path, key_path: &Path, ocsp_path: &str) -> Option<SystemTime> {
+    [
+        Some(cert_path),
+        Some(key_path),
+        (!ocsp_path.is_empty()).then(|| Path::new(ocsp_path)),
+    ]
+    .into_iter()
+    .flatten()
+    .filter_map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+    .max()
+}
+
+fn cert_not_after(der: &[u8]) -> Result<i64, CommonError> {
+    let (_, cert) = parse_x509_certificate(der)
+        .map_err(|e| CommonError::CommonError(format!("Invalid TLS certificate: {e}")))?;
+    Ok(cert.validity().not_after.timestamp())
+}
+
+fn load_ocsp_response(path: &str) -> Result<Vec<u8>, CommonError> {
+    if path.is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(fs::read(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CERT_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../../config/certs");
+
+    fn test_config(dir: &Path) -> BrokerConfig {
+        let mut conf = BrokerConfig::default();
+        conf.runtime.tls_cert = dir.join("cert.pem").to_string_lossy().to_string();
+        conf.runtime.tls_key = dir.join("key.pem").to_string_lossy().to_string();
+        conf
+    }
+
+    #[test]
+    fn test_cert_paths() {
+        let mut conf = BrokerConfig::default();
+        let (cert, key) = cert_paths(&conf);
+        assert_eq!(cert, PathBuf::from(&conf.runtime.tls_cert));
+        assert_eq!(key, PathBuf::from(&conf.runtime.tls_key));
+
+        conf.runtime.tls_acme_dir = "/etc/letsencrypt/live/mqtt.example.com".to_string();
+        let (cert, key) = cert_paths(&conf);
+        assert_eq!(
+            cert,
+            PathBuf::from("/etc/letsencrypt/live/mqtt.example.com/fullchain.pem")
+        );
+        assert_eq!(
+            key,
+            PathBuf::from("/etc/letsencrypt/live/mqtt.example.com/privkey.pem")
+        );
+    }
+
+    #[test]
+    fn test_reload_if_changed() {
+        let dir = std::env::temp_dir().join(format!("robustmq-cert-manager-{}", now_second()));
+        fs::create_dir_all(&dir).unwrap();
+        for file in ["cert.pem", "key.pem"] {
+            fs::copy(Path::new(CERT_DIR).join(file), dir.join(file)).unwrap();
+        }
+
+        let manager = CertManager::new(&test_config(&dir)).unwrap();
+        assert!(!manager.reload_if_changed().unwrap());
+        assert_eq!(
+            manager.expiry_days(),
+            (cert_not_after(manager.current.read().unwrap().end_entity_cert().unwrap()).unwrap()
+                - now_second() as i64)
+                .div_euclid(86400)
+        );
+
+        // a broken file keeps the old certificate
+        *manager.modified.lock().unwrap() = None;
+        fs::write(dir.join("cert.pem"), "not a certificate").unwrap();
+        assert!(manager.reload_if_changed().is_err());
+        assert!(!manager.current.read().unwrap().cert.is_empty());
+
+        fs::copy(Path::new(CERT_DIR).join("cert.pem"), dir.join("cert.pem")).unwrap();
+        *manager.modified.lock().unwrap() = None;
+        assert!(manager.reload_if_changed().unwrap());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,139 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::tls_acceptor::load_certs;
+use common_base::error::common::CommonError;
+use common_config::config::{MqttTls, TlsClientAuth};
+use metadata_struct::connection::PeerCertIdentity;
+use rustls_pemfile::crls;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+use tokio_rustls::rustls::pki_types::CertificateRevocationListDer;
+use tokio_rustls::rustls::server::danger::ClientCertVerifier;
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::RootCertStore;
+use x509_parser::extensions::GeneralName;
+use x509_parser::parse_x509_certificate;
+
+/// Build the client certificate verifier for `client_auth`, or `None` when
+/// client certificates are not requested.
+pub(crate) fn build_client_verifier(
+    conf: &MqttTls,
+) -> Result<Option<Arc<dyn ClientCertVerifier>>, CommonError> {
+    if conf.client_auth == TlsClientAuth::None {
+        return Ok(None);
+    }
+
+    if conf.ca_cert.is_empty() {
+        return Err(CommonError::CommonError(
+            "mqtt_tls.ca_cert must be set when client_auth is enabled".to_string(),
+        ));
+    }
+
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(Path::new(&conf.ca_cert))? {
+        roots.add(cert)?;
+    }
+
+    let mut builder =
+        WebPkiClientVerifier::builder(Arc::new(roots)).with_crls(load_crls(&conf.crl_files)?);
+    if conf.client_auth == TlsClientAuth::Optional {
+        builder = builder.allow_unauthenticated();
+    }
+    let verifier = builder.build().map_err(|e| {
+        CommonError::CommonError(format!("Failed to build client certificate verifier: {e}"))
+    })?;
+    Ok(Some(verifier))
+}
+
+fn load_crls(paths: &[String]) -> Result<Vec<CertificateRevocationListDer<'static>>, CommonError> {
+    let mut result = Vec::new();
+    for path in paths {
+        for crl in crls(&mut BufReader::new(File::open(path)?)) {
+            result.push(crl?);
+        }
+    }
+    Ok(result)
+}
+
+/// Read the CN and subject alternative names from a DER certificate. The
+/// certificate must already have been verified by the TLS handshake.
+pub fn parse_peer_cert_identity(der: &[u8]) -> Option<PeerCertIdentity> {
+    let (_, cert) = parse_x509_certificate(der).ok()?;
+
+    let mut identity = PeerCertIdentity {
+        common_name: cert
+            .subject()
+            .iter_common_name()
+            .next()
+            .and_then(|cn| cn.as_str().ok())
+            .map(|cn| cn.to_string()),
+        ..Default::default()
+    };
+
+    if let Ok(Some(san)) = cert.subject_alternative_name() {
+        for name in san.value.general_names.iter() {
+            match name {
+                GeneralName::DNSName(dns) => identity.dns_names.push(dns.to_string()),
+                GeneralName::RFC822Name(email) => identity.emails.push(email.to_string()),
+                GeneralName::URI(uri) => identity.uris.push(uri.to_string()),
+                _ => {}
+            }
+        }
+    }
+    Some(identity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_CERT: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../../config/certs/cert.pem"
+    );
+
+    #[test]
+    fn test_parse_peer_cert_identity() {
+        let certs = load_certs(Path::new(TEST_CERT)).unwrap();
+        let identity = parse_peer_cert_identity(certs[0].as_ref()).unwrap();
+        assert_eq!(identity.common_name, None);
+        assert_eq!(identity.dns_names, vec!["localhost".to_string()]);
+        assert!(identity.emails.is_empty());
+
+        assert!(parse_peer_cert_identity(b"not a certificate").is_none());
+    }
+
+    #[test]
+    fn test_build_client_verifier() {
+        let disabled = MqttTls::default();
+        assert!(build_client_verifier(&disabled).unwrap().is_none());
+
+        let missing_ca = MqttTls {
+            client_auth: TlsClientAuth::Required,
+            ..Default::default()
+        };
+        assert!(build_client_verifier(&missing_ca).is_err());
+
+        let required = MqttTls {
+            client_auth: TlsClientAuth::Required,
+            ca_cert: concat!(env!("CARGO_MANIFEST_DIR"), "/../../../config/certs/ca.pem")
+                .to_string(),
+            ..Default::default()
+        };
+        assert!(build_client_verifier(&required).unwrap().is_some());
+    }
+}
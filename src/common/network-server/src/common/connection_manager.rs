@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::common::write_buffer::{CoalescingWriter, WriteCoalesceConfig};
 use crate::quic::stream::QuicFramedWriteStream;
 use axum::extract::ws::{Message, WebSocket};
 use common_base::tools::now_second;
@@ -21,27 +22,28 @@
 use futures::SinkExt;
 use metadata_struct::connection::{NetworkConnection, NetworkConnectionType};
 use protocol::codec::RobustMQCodec;
-use protocol::robust::RobustMQProtocol;
+use protocol::robust::{RobustMQPacketWrapper, RobustMQProtocol};
 use std::net::{IpAddr, SocketAddr};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, Mutex};
 use tokio_util::codec::FramedWrite;
 use tracing::debug;
 
-type TcpWriter =
-    Arc<Mutex<FramedWrite<tokio::io::WriteHalf<tokio::net::TcpStream>, RobustMQCodec>>>;
+type TcpWriter = Arc<CoalescingWriter<tokio::io::WriteHalf<tokio::net::TcpStream>>>;
 type TcpTlsWriter = Arc<
-    Mutex<
-        FramedWrite<
-            tokio::io::WriteHalf<tokio_rustls::server::TlsStream<tokio::net::TcpStream>>,
-            RobustMQCodec,
-        >,
-    >,
+    CoalescingWriter<tokio::io::WriteHalf<tokio_rustls::server::TlsStream<tokio::net::TcpStream>>>,
 >;
 type WebSocketWriter = Arc<Mutex<SplitSink<WebSocket, Message>>>;
 type QuicWriter = Arc<Mutex<QuicFramedWriteStream>>;
+/// MQTT-SN clients share one UDP socket, so their packets go to the gateway
+/// task serving the client, which translates and sends them.
+type MqttSnWriter = mpsc::Sender<RobustMQPacketWrapper>;
+/// CoAP peers are served the same way by the CoAP gateway.
+type CoapWriter = mpsc::Sender<RobustMQPacketWrapper>;
+/// Browser subscribers are served the same way by the web subscribe endpoint.
+type WebSubscribeWriter = mpsc::Sender<RobustMQPacketWrapper>;
 
 pub struct ConnectionManager {
     pub connections: DashMap<u64, NetworkConnection>,
@@ -49,7 +51,11 @@ pub struct ConnectionManager {
     pub tcp_tls_write_list: DashMap<u64, TcpTlsWriter>,
     pub websocket_write_list: DashMap<u64, WebSocketWriter>,
     pub quic_write_list: DashMap<u64, QuicWriter>,
+    pub mqttsn_write_list: DashMap<u64, MqttSnWriter>,
+    pub coap_write_list: DashMap<u64, CoapWriter>,
+    pub web_subscribe_write_list: DashMap<u64, WebSubscribeWriter>,
     pub ip_conn_count: DashMap<IpAddr, AtomicU64>,
+    pub write_coalesce: WriteCoalesceConfig,
 }
 
 impl Default for ConnectionManager {
@@ -66,7 +72,11 @@ fn clone(&self) -> Self {
             tcp_tls_write_list: self.tcp_tls_write_list.clone(),
             websocket_write_list: self.websocket_write_list.clone(),
             quic_write_list: self.quic_write_list.clone(),
+            mqttsn_write_list: self.mqttsn_write_list.clone(),
+            coap_write_list: self.coap_write_list.clone(),
+            web_subscribe_write_list: self.web_subscribe_write_list.clone(),
             ip_conn_count: DashMap::with_capacity(64),
+            write_coalesce: self.write_coalesce,
         }
     }
 }
@@ -79,6 +89,9 @@ pub fn new() -> ConnectionManager {
         let tcp_tls_write_list = DashMap::with_capacity(64);
         let websocket_write_list = DashMap::with_capacity(64);
         let quic_write_list = DashMap::with_capacity(64);
+        let mqttsn_write_list = DashMap::with_capacity(64);
+        let coap_write_list = DashMap::with_capacity(64);
+        let web_subscribe_write_list = DashMap::with_capacity(64);
         let ip_conn_count = DashMap::with_capacity(64);
         ConnectionManager {
             connections,
@@ -86,10 +99,20 @@ pub fn new() -> ConnectionManager {
             tcp_tls_write_list,
             websocket_write_list,
             quic_write_list,
+            mqttsn_write_list,
+            coap_write_list,
+            web_subscribe_write_list,
             ip_conn_count,
+            write_coalesce: WriteCoalesceConfig::default(),
         }
     }
 
+    /// Coalescing applied to TCP and TLS connections added afterwards.
+    pub fn with_write_coalesce(mut self, write_coalesce: WriteCoalesceConfig) -> Self {
+        self.write_coalesce = write_coalesce;
+        self
+    }
+
     pub fn add_connection(&self, connection: NetworkConnection) -> u64 {
         let connection_id = connection.connection_id();
         self.ip_conn_count
@@ -138,6 +161,27 @@ pub fn is_quic(&self, connect_id: u64) -> bool {
         false
     }
 
+    pub fn is_mqttsn(&self, connect_id: u64) -> bool {
+        if let Some(connect) = self.connections.get(&connect_id) {
+            return connect.connection_type == NetworkConnectionType::MqttSn;
+        }
+        false
+    }
+
+    pub fn is_coap(&self, connect_id: u64) -> bool {
+        if let Some(connect) = self.connections.get(&connect_id) {
+            return connect.connection_type == NetworkConnectionType::CoAP;
+        }
+        false
+    }
+
+    pub fn is_web_subscribe(&self, connect_id: u64) -> bool {
+        if let Some(connect) = self.connections.get(&connect_id) {
+            return connect.connection_type == NetworkConnectionType::WebSubscribe;
+        }
+        false
+    }
+
     pub fn get_network_type(&self, connect_id: u64) -> Option<NetworkConnectionType> {
         if let Some(connect) = self.connections.get(&connect_id) {
             return Some(connect.connection_type.clone());
@@ -166,8 +210,8 @@ pub fn add_tcp_write(
         connection_id: u64,
         write: FramedWrite<tokio::io::WriteHalf<tokio::net::TcpStream>, RobustMQCodec>,
     ) {
-        self.tcp_write_list
-            .insert(connection_id, Arc::new(Mutex::new(write)));
+        let writer = CoalescingWriter::new(write, self.write_coalesce, NetworkConnectionType::Tcp);
+        self.tcp_write_list.insert(connection_id, Arc::new(writer));
     }
 
     pub fn add_tcp_tls_write(
@@ -178,8 +222,9 @@ pub fn add_tcp_tls_write(
             RobustMQCodec,
         >,
     ) {
+        let writer = CoalescingWriter::new(write, self.write_coalesce, NetworkConnectionType::Tls);
         self.tcp_tls_write_list
-            .insert(connection_id, Arc::new(Mutex::new(write)));
+            .insert(connection_id, Arc::new(writer));
     }
 
     pub fn add_websocket_write(&self, connection_id: u64, write: SplitSink<WebSocket, Message>) {
@@ -197,6 +242,18 @@ pub fn add_mqtt_quic_write(
             Arc::new(Mutex::new(quic_framed_write_stream)),
         );
     }
+
+    pub fn add_mqttsn_write(&self, connection_id: u64, write: MqttSnWriter) {
+        self.mqttsn_write_list.insert(connection_id, write);
+    }
+
+    pub fn add_coap_write(&self, connection_id: u64, write: CoapWriter) {
+        self.coap_write_list.insert(connection_id, write);
+    }
+
+    pub fn add_web_subscribe_write(&self, connection_id: u64, write: WebSubscribeWriter) {
+        self.web_subscribe_write_list.insert(connection_id, write);
+    }
 }
 
 // Set Protocol
@@ -253,12 +310,7 @@ pub async fn close_connect(&self, connection_id: u64) {
         }
 
         if let Some((id, writer)) = self.tcp_write_list.remove(&connection_id) {
-            match tokio::time::timeout(CLOSE_TIMEOUT, async {
-                let mut stream = writer.lock().await;
-                stream.close().await
-            })
-            .await
-            {
+            match tokio::time::timeout(CLOSE_TIMEOUT, writer.close()).await {
                 Ok(Ok(())) => debug!(
                     "server closes the tcp connection actively, connection id [{}]",
                     id
@@ -272,12 +324,7 @@ pub async fn close_connect(&self, connection_id: u64) {
         }
 
         if let Some((id, writer)) = self.tcp_tls_write_list.remove(&connection_id) {
-            match tokio::time::timeout(CLOSE_TIMEOUT, async {
-                let mut stream = writer.lock().await;
-                stream.close().await
-            })
-            .await
-            {
+            match tokio::time::timeout(CLOSE_TIMEOUT, writer.close()).await {
                 Ok(Ok(())) => debug!(
                     "server closes the tls connection actively, connection id [{}]",
                     id
@@ -315,6 +362,28 @@ pub async fn close_connect(&self, connection_id: u64) {
                 id
             );
         }
+
+        // Dropping the sender ends the gateway task serving the client.
+        if let Some((id, _writer)) = self.mqttsn_write_list.remove(&connection_id) {
+            debug!(
+                "server closes the mqtt-sn connection actively, connection id [{}]",
+                id
+            );
+        }
+
+        if let Some((id, _writer)) = self.coap_write_list.remove(&connection_id) {
+            debug!(
+                "server closes the coap connection actively, connection id [{}]",
+                id
+            );
+        }
+
+        if let Some((id, _writer)) = self.web_subscribe_write_list.remove(&connection_id) {
+            debug!(
+                "server closes the web subscribe connection actively, connection id [{}]",
+                id
+            );
+        }
     }
 }
 
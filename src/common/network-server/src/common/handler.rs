@@ -268,6 +268,39 @@ async fn write_response(
                     error!("{}", e);
                 };
             }
+            NetworkConnectionType::MqttSn => {
+                if let Err(e) = connection_manager
+                    .write_mqttsn_frame(response_package.connection_id, packet_wrapper)
+                    .await
+                {
+                    if client_unavailable_error_by_str(&e.to_string()) {
+                        return;
+                    }
+                    error!("{}", e);
+                };
+            }
+            NetworkConnectionType::CoAP => {
+                if let Err(e) = connection_manager
+                    .write_coap_frame(response_package.connection_id, packet_wrapper)
+                    .await
+                {
+                    if client_unavailable_error_by_str(&e.to_string()) {
+                        return;
+                    }
+                    error!("{}", e);
+                };
+            }
+            NetworkConnectionType::WebSubscribe => {
+                if let Err(e) = connection_manager
+                    .write_web_subscribe_frame(response_package.connection_id, packet_wrapper)
+                    .await
+                {
+                    if client_unavailable_error_by_str(&e.to_string()) {
+                        return;
+                    }
+                    error!("{}", e);
+                };
+            }
         }
     }
 }
@@ -12,7 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod cert_manager;
 pub mod channel;
+pub mod client_cert;
 pub mod connection_manager;
 pub mod handler;
 pub mod metric;
@@ -21,3 +23,4 @@
 pub mod tls_acceptor;
 pub mod tool;
 pub mod write;
+pub mod write_buffer;
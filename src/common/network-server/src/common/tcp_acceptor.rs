@@ -14,10 +14,13 @@
 
 use crate::common::channel::RequestChannel;
 use crate::common::connection_manager::ConnectionManager;
-use crate::common::tool::{check_connection_limit, read_packet};
+use crate::common::tool::{
+    build_connection_codec, check_connection_limit, read_packet, send_decode_error_disconnect,
+};
 use crate::protocol::nats::send_nats_info;
 use broker_core::cache::NodeCacheManager;
 use common_base::task::TaskSupervisor;
+use common_config::broker::broker_config;
 use common_metrics::mqtt::packets::record_received_error_metrics;
 use futures_util::StreamExt;
 use metadata_struct::connection::{NetworkConnection, NetworkConnectionType};
@@ -47,6 +50,7 @@ pub struct TcpAcceptorContext {
 }
 
 pub async fn acceptor_process(ctx: TcpAcceptorContext) {
+    let tcp_nodelay = broker_config().runtime.tcp_nodelay;
     for index in 1..=ctx.accept_thread_num {
         let listener = ctx.listener.clone();
         let connection_manager = ctx.connection_manager.clone();
@@ -92,6 +96,9 @@ pub async fn acceptor_process(ctx: TcpAcceptorContext) {
                         match val{
                             Ok((stream, addr)) => {
                                 debug!("Accept {} connection:{:?}", network_type, addr);
+                                if let Err(e) = stream.set_nodelay(tcp_nodelay) {
+                                    debug!("Failed to set TCP_NODELAY for {}: {}", addr, e);
+                                }
                                 // check connection
                                 if check_connection_limit(&row_global_limit_manager, &row_broker_cache, &connection_manager, &addr).await{
                                     continue;
@@ -102,7 +109,7 @@ pub async fn acceptor_process(ctx: TcpAcceptorContext) {
                                 let conn_codec = match protocol {
                                     RobustMQProtocol::MQTT3
                                     | RobustMQProtocol::MQTT4
-                                    | RobustMQProtocol::MQTT5 => row_codec.clone(),
+                                    | RobustMQProtocol::MQTT5 => build_connection_codec(&row_broker_cache, &row_codec),
                                     _ => RobustMQCodec::new_with_protocol(protocol.clone()),
                                 };
 
@@ -224,6 +231,7 @@ fn read_frame_process(
                                     "{} connection parsing packet format error message :{:?}",
                                     network_type, e
                                 );
+                                send_decode_error_disconnect(&connection_manager, connection_id, &e).await;
                                 connection_manager.mark_close_connect(connection_id).await;
                                 break;
                             }
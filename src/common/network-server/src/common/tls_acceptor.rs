@@ -12,16 +12,22 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::common::cert_manager::{cert_manager, start_cert_reload_thread};
 use crate::common::channel::RequestChannel;
+use crate::common::client_cert::{build_client_verifier, parse_peer_cert_identity};
 use crate::common::connection_manager::ConnectionManager;
-use crate::common::tool::{check_connection_limit, read_packet};
+use crate::common::tool::{
+    build_connection_codec, check_connection_limit, read_packet, send_decode_error_disconnect,
+};
 use crate::protocol::nats::send_nats_info;
 use broker_core::cache::NodeCacheManager;
 use common_base::error::common::CommonError;
 use common_base::error::ResultCommonError;
 use common_base::task::TaskSupervisor;
 use common_config::broker::broker_config;
+use common_config::config::Runtime;
 use common_metrics::mqtt::packets::record_received_error_metrics;
+use common_metrics::tls::{record_tls_handshake, record_tls_handshake_failure};
 use futures_util::StreamExt;
 use metadata_struct::connection::{NetworkConnection, NetworkConnectionType};
 use protocol::codec::{RobustMQCodec, RobustMQCodecWrapper};
@@ -30,17 +36,22 @@
 use rustls_pemfile::{certs, private_key};
 use std::fs::File;
 use std::io::{self, BufReader};
+use std::net::SocketAddr;
 use std::path::Path;
 use std::sync::Arc;
-use tokio::net::TcpListener;
+use std::time::{Duration, Instant};
+use tokio::net::{TcpListener, TcpStream};
 use tokio::select;
 use tokio::sync::mpsc::Receiver;
 use tokio::sync::{broadcast, mpsc};
+use tokio::time::timeout;
+use tokio_rustls::rustls::crypto::ring::Ticketer;
 use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
-use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::rustls::server::{NoServerSessionStorage, ServerSessionMemoryCache};
+use tokio_rustls::rustls::{HandshakeKind, ServerConfig};
 use tokio_rustls::TlsAcceptor;
 use tokio_util::codec::{FramedRead, FramedWrite};
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
 
 pub struct TlsAcceptorContext {
     pub accept_thread_num: usize,
@@ -56,7 +67,7 @@ pub struct TlsAcceptorContext {
     pub task_supervisor: Arc<TaskSupervisor>,
 }
 
-pub(crate) fn load_certs(path: &Path) -> io::Result<Vec<CertificateDer<'static>>> {
+pub fn load_certs(path: &Path) -> io::Result<Vec<CertificateDer<'static>>> {
     certs(&mut BufReader::new(File::open(path)?)).collect()
 }
 
@@ -67,19 +78,58 @@ pub(crate) fn load_key(path: &Path) -> io::Result<PrivateKeyDer<'static>> {
 }
 
 pub async fn acceptor_tls_process(ctx: TlsAcceptorContext) -> ResultCommonError {
-    let tls_acceptor = create_tls_accept()?;
+    let tls_acceptor = create_tls_accept(&ctx.protocol)?;
+    start_cert_reload_thread(cert_manager()?, ctx.stop_sx.clone());
+
+    // Handshakes run on their own tasks so that a reconnect storm only fills
+    // the queue instead of stalling `accept`.
+    let runtime = broker_config().runtime.clone();
+    let (handshake_sx, handshake_rx) =
+        async_channel::bounded::<PendingHandshake>(runtime.tls_handshake_queue_size.max(1));
+    let handshake_timeout = Duration::from_millis(runtime.tls_handshake_timeout_ms);
+    let tcp_nodelay = runtime.tcp_nodelay;
+    for index in 1..=runtime.tls_handshake_thread_num.max(1) {
+        let worker = HandshakeWorker {
+            tls_acceptor: tls_acceptor.clone(),
+            handshake_timeout,
+            network_type: ctx.network_type.clone(),
+            protocol: ctx.protocol.clone(),
+            codec: ctx.codec.clone(),
+            connection_manager: ctx.connection_manager.clone(),
+            broker_cache: ctx.broker_cache.clone(),
+            request_channel: ctx.request_channel.clone(),
+            global_limit_manager: ctx.global_limit_manager.clone(),
+        };
+        let handshake_rx = handshake_rx.clone();
+        let mut stop_rx = ctx.stop_sx.subscribe();
+        let task_name = format!(
+            "{:?}-{}-tls-handshake-{}",
+            ctx.protocol, ctx.network_type, index
+        );
+        ctx.task_supervisor.spawn(task_name, async move {
+            loop {
+                select! {
+                    val = stop_rx.recv() => {
+                        if !matches!(val, Ok(false) | Err(broadcast::error::RecvError::Lagged(_))) {
+                            break;
+                        }
+                    }
+                    val = handshake_rx.recv() => {
+                        let Ok(pending) = val else {
+                            break;
+                        };
+                        worker.handshake(pending).await;
+                    }
+                }
+            }
+        });
+    }
 
     for index in 1..=ctx.accept_thread_num {
         let listener = ctx.listener.clone();
-        let connection_manager = ctx.connection_manager.clone();
         let mut stop_rx = ctx.stop_sx.subscribe();
-        let request_channel = ctx.request_channel.clone();
-        let raw_tls_acceptor = tls_acceptor.clone();
+        let handshake_sx = handshake_sx.clone();
         let network_type = ctx.network_type.clone();
-        let protocol = ctx.protocol.clone();
-        let row_codec = ctx.codec.clone();
-        let row_broker_cache = ctx.broker_cache.clone();
-        let row_global_limit_manager = ctx.global_limit_manager.clone();
         let task_name = format!(
             "{:?}-{}-tls-acceptor-{}",
             ctx.protocol, ctx.network_type, index
@@ -117,36 +167,18 @@ pub async fn acceptor_tls_process(ctx: TlsAcceptorContext) -> ResultCommonError
                         match val{
                             Ok((stream, addr)) => {
                                 debug!("Accept {} tls connection:{:?}", network_type, addr);
-                                let stream = match raw_tls_acceptor.accept(stream).await{
-                                    Ok(da) => da,
-                                    Err(e) => {
-                                        error!("{} Accepter failed to read Stream with error message :{e:?}", network_type);
-                                        continue;
-                                    }
-                                };
-
-                                let (r_stream, w_stream) = tokio::io::split(stream);
-                                let read_frame_stream = FramedRead::new(r_stream, row_codec.clone());
-                                let write_frame_stream = FramedWrite::new(w_stream, row_codec.clone());
-
-                                if check_connection_limit(&row_global_limit_manager, &row_broker_cache, &connection_manager, &addr).await{
-                                    continue;
+                                if let Err(e) = stream.set_nodelay(tcp_nodelay) {
+                                    debug!("Failed to set TCP_NODELAY for {}: {}", addr, e);
                                 }
-
-                                let (connection_stop_sx, connection_stop_rx) = mpsc::channel::<bool>(1);
-                                let connection = NetworkConnection::new(
-                                    NetworkConnectionType::Tls,
+                                let pending = PendingHandshake {
+                                    stream,
                                     addr,
-                                    Some(connection_stop_sx.clone())
-                                );
-                                connection_manager.add_connection(connection.clone());
-                                connection_manager.add_tcp_tls_write(connection.connection_id, write_frame_stream);
-
-                                if protocol.is_nats() {
-                                    send_nats_info(&row_broker_cache, connection.connection_id, &connection_manager,&network_type, &addr).await;
+                                    accepted_at: Instant::now(),
+                                };
+                                if handshake_sx.try_send(pending).is_err() {
+                                    record_tls_handshake_failure("rejected");
+                                    warn!("{} TLS handshake queue is full, closing connection from {}", network_type, addr);
                                 }
-
-                                read_tls_frame_process(row_broker_cache.clone(), connection_manager.clone(),read_frame_stream, connection, request_channel.clone(), connection_stop_rx, network_type.clone());
                             }
                             Err(e) => {
                                 error!("{} accept failed to create connection with error message :{:?}", network_type, e);
@@ -160,6 +192,107 @@ pub async fn acceptor_tls_process(ctx: TlsAcceptorContext) -> ResultCommonError
     Ok(())
 }
 
+struct PendingHandshake {
+    stream: TcpStream,
+    addr: SocketAddr,
+    accepted_at: Instant,
+}
+
+struct HandshakeWorker {
+    tls_acceptor: TlsAcceptor,
+    handshake_timeout: Duration,
+    network_type: NetworkConnectionType,
+    protocol: RobustMQProtocol,
+    codec: RobustMQCodec,
+    connection_manager: Arc<ConnectionManager>,
+    broker_cache: Arc<NodeCacheManager>,
+    request_channel: Arc<RequestChannel>,
+    global_limit_manager: Arc<GlobalRateLimiterManager>,
+}
+
+impl HandshakeWorker {
+    async fn handshake(&self, pending: PendingHandshake) {
+        let PendingHandshake {
+            stream,
+            addr,
+            accepted_at,
+        } = pending;
+        let stream = match timeout(self.handshake_timeout, self.tls_acceptor.accept(stream)).await {
+            Ok(Ok(stream)) => stream,
+            Ok(Err(e)) => {
+                record_tls_handshake_failure("error");
+                error!(
+                    "{} Accepter failed to read Stream with error message :{e:?}",
+                    self.network_type
+                );
+                return;
+            }
+            Err(_) => {
+                record_tls_handshake_failure("timeout");
+                debug!(
+                    "{} TLS handshake from {} timed out after {:?}",
+                    self.network_type, addr, self.handshake_timeout
+                );
+                return;
+            }
+        };
+
+        let tls_connection = stream.get_ref().1;
+        let resumed = tls_connection.handshake_kind() == Some(HandshakeKind::Resumed);
+        record_tls_handshake(resumed, accepted_at.elapsed().as_secs_f64() * 1000.0);
+
+        let peer_cert = tls_connection
+            .peer_certificates()
+            .and_then(|certs| certs.first())
+            .and_then(|cert| parse_peer_cert_identity(cert.as_ref()));
+
+        let (r_stream, w_stream) = tokio::io::split(stream);
+        let codec = build_connection_codec(&self.broker_cache, &self.codec);
+        let read_frame_stream = FramedRead::new(r_stream, codec.clone());
+        let write_frame_stream = FramedWrite::new(w_stream, codec);
+
+        if check_connection_limit(
+            &self.global_limit_manager,
+            &self.broker_cache,
+            &self.connection_manager,
+            &addr,
+        )
+        .await
+        {
+            return;
+        }
+
+        let (connection_stop_sx, connection_stop_rx) = mpsc::channel::<bool>(1);
+        let mut connection =
+            NetworkConnection::new(NetworkConnectionType::Tls, addr, Some(connection_stop_sx));
+        connection.peer_cert = peer_cert;
+        self.connection_manager.add_connection(connection.clone());
+        self.connection_manager
+            .add_tcp_tls_write(connection.connection_id, write_frame_stream);
+
+        if self.protocol.is_nats() {
+            send_nats_info(
+                &self.broker_cache,
+                connection.connection_id,
+                &self.connection_manager,
+                &self.network_type,
+                &addr,
+            )
+            .await;
+        }
+
+        read_tls_frame_process(
+            self.broker_cache.clone(),
+            self.connection_manager.clone(),
+            read_frame_stream,
+            connection,
+            self.request_channel.clone(),
+            connection_stop_rx,
+            self.network_type.clone(),
+        );
+    }
+}
+
 // spawn connection read thread
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn read_tls_frame_process(
@@ -226,6 +359,7 @@ pub(crate) fn read_tls_frame_process(
                                     "{} connection parsing packet format error message :{:?}",
                                     network_type, e
                                 );
+                                send_decode_error_disconnect(&connection_manager, connection.connection_id, &e).await;
                                 connection_manager.mark_close_connect(connection.connection_id).await;
                                 break;
                             }
@@ -241,13 +375,38 @@ pub(crate) fn read_tls_frame_process(
     }));
 }
 
+// Client certificates are only requested on MQTT listeners.
 #[allow(clippy::result_large_err)]
-fn create_tls_accept() -> Result<TlsAcceptor, CommonError> {
+fn create_tls_accept(protocol: &RobustMQProtocol) -> Result<TlsAcceptor, CommonError> {
     let conf = broker_config();
-    let certs = load_certs(Path::new(&conf.runtime.tls_cert))?;
-    let key = load_key(Path::new(&conf.runtime.tls_key))?;
-    let config = ServerConfig::builder()
-        .with_no_client_auth()
-        .with_single_cert(certs, key)?;
+    let verifier = if protocol.is_mqtt() {
+        build_client_verifier(&conf.mqtt_tls)?
+    } else {
+        None
+    };
+    let builder = match verifier {
+        Some(verifier) => ServerConfig::builder().with_client_cert_verifier(verifier),
+        None => ServerConfig::builder().with_no_client_auth(),
+    };
+    let mut config = builder.with_cert_resolver(cert_manager()?);
+    apply_session_resumption(&mut config, &conf.runtime)?;
     Ok(TlsAcceptor::from(Arc::new(config)))
 }
+
+/// Sessions resumed by ID or ticket skip the certificate exchange and the
+/// key agreement, which makes reconnects much cheaper.
+#[allow(clippy::result_large_err)]
+pub(crate) fn apply_session_resumption(
+    config: &mut ServerConfig,
+    runtime: &Runtime,
+) -> ResultCommonError {
+    config.session_storage = if runtime.tls_session_cache_size > 0 {
+        ServerSessionMemoryCache::new(runtime.tls_session_cache_size)
+    } else {
+        Arc::new(NoServerSessionStorage {})
+    };
+    if runtime.tls_session_ticket_enable {
+        config.ticketer = Ticketer::new().map_err(|e| CommonError::CommonError(e.to_string()))?;
+    }
+    Ok(())
+}
@@ -19,11 +19,16 @@
     channel::RequestChannel, connection_manager::ConnectionManager, packet::RequestPackage,
 };
 use broker_core::cache::NodeCacheManager;
+use common_base::error::common::CommonError;
 use common_metrics::mqtt::packets::record_packet_received_metrics;
 use metadata_struct::connection::{NetworkConnection, NetworkConnectionType};
+use protocol::codec::RobustMQCodec;
+use protocol::mqtt::codec::{decode_error_reason_code, MqttPacketWrapper};
+use protocol::mqtt::common::{Disconnect, DisconnectReasonCode};
+use protocol::robust::{RobustMQPacketWrapper, RobustMQProtocol};
 use protocol::{mqtt::common::MqttPacket, robust::RobustMQPacket};
 use rate_limit::global::GlobalRateLimiterManager;
-use tracing::debug;
+use tracing::{debug, warn};
 
 pub fn is_ignore_print(packet: &RobustMQPacket) -> bool {
     if let RobustMQPacket::MQTT(pack) = packet {
@@ -90,9 +95,74 @@ pub async fn check_connection_limit(
     false
 }
 
+/// Clone the listener codec for a new connection, capping inbound MQTT
+/// frames at the cluster `mqtt_protocol.max_packet_size`.
+pub fn build_connection_codec(
+    node_cache: &Arc<NodeCacheManager>,
+    codec: &RobustMQCodec,
+) -> RobustMQCodec {
+    let mut codec = codec.clone();
+    let max_packet_size = node_cache
+        .get_cluster_config()
+        .mqtt_protocol
+        .max_packet_size;
+    codec
+        .mqtt_codec
+        .set_max_packet_size(max_packet_size as usize);
+    codec
+}
+
+/// Map a frame decode error to the DISCONNECT reason an MQTT 5 client should
+/// receive before the connection is closed. Errors that do not come from the
+/// MQTT codec, such as socket failures, have no reason to report.
+pub fn decode_error_disconnect_reason(err: &CommonError) -> Option<DisconnectReasonCode> {
+    match err {
+        CommonError::FromMQTTProtocolError(e) => decode_error_reason_code(e),
+        _ => None,
+    }
+}
+
+/// Tell an MQTT 5 client why its connection is about to be closed after it
+/// sent a frame that could not be decoded, e.g. one larger than the advertised
+/// Maximum Packet Size or one that is malformed. Earlier protocol versions
+/// have no DISCONNECT reason, so they are just closed.
+pub async fn send_decode_error_disconnect(
+    connection_manager: &Arc<ConnectionManager>,
+    connection_id: u64,
+    err: &CommonError,
+) {
+    let Some(reason_code) = decode_error_disconnect_reason(err) else {
+        return;
+    };
+
+    if connection_manager.get_connect_protocol(connection_id) != Some(RobustMQProtocol::MQTT5) {
+        return;
+    }
+
+    let wrapper = RobustMQPacketWrapper::from_mqtt(MqttPacketWrapper {
+        protocol_version: 5,
+        packet: MqttPacket::Disconnect(
+            Disconnect {
+                reason_code: Some(reason_code),
+            },
+            None,
+        ),
+    });
+    if let Err(e) = connection_manager
+        .write_tcp_frame(connection_id, wrapper)
+        .await
+    {
+        warn!(
+            "Failed to send DISCONNECT({:?}) to connection {}: {}",
+            reason_code, connection_id, e
+        );
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use common_base::error::mqtt_protocol_error::MQTTProtocolError;
     use common_config::broker::default_broker_config;
     use rate_limit::global::GlobalRateLimiterManager;
     use std::net::SocketAddr;
@@ -177,4 +247,39 @@ async fn check_connection_limit_different_ips_independent() {
         let result = check_connection_limit(&limit_manager, &node_cache, &cm, &addr_b).await;
         assert!(!result);
     }
+
+    #[test]
+    fn build_connection_codec_uses_cluster_max_packet_size() {
+        let mut config = default_broker_config();
+        config.mqtt_protocol.max_packet_size = 1024;
+        let node_cache = Arc::new(NodeCacheManager::new(config));
+
+        let codec = build_connection_codec(&node_cache, &RobustMQCodec::new());
+        assert_eq!(codec.mqtt_codec.max_packet_size, 1024);
+    }
+
+    #[test]
+    fn decode_error_disconnect_reason_by_error_kind() {
+        let too_large =
+            CommonError::FromMQTTProtocolError(MQTTProtocolError::PayloadSizeLimitExceeded(2048));
+        assert_eq!(
+            decode_error_disconnect_reason(&too_large),
+            Some(DisconnectReasonCode::PacketTooLarge)
+        );
+
+        let malformed = CommonError::FromMQTTProtocolError(MQTTProtocolError::MalformedPacket);
+        assert_eq!(
+            decode_error_disconnect_reason(&malformed),
+            Some(DisconnectReasonCode::MalformedPacket)
+        );
+
+        let io = CommonError::FromMQTTProtocolError(MQTTProtocolError::IoError(
+            std::io::Error::other("reset"),
+        ));
+        assert_eq!(decode_error_disconnect_reason(&io), None);
+        assert_eq!(
+            decode_error_disconnect_reason(&CommonError::CommonError("x".into())),
+            None
+        );
+    }
 }
@@ -148,10 +148,9 @@ async fn write_tcp_frame0(
                 CommonError::NotObtainAvailableConnection("tcp".to_string(), connection_id)
             })?;
 
-        let mut stream = writer.lock().await;
         let write_start = now_millis();
         let result =
-            tokio::time::timeout(Duration::from_secs(WRITE_TIMEOUT_SECS), stream.send(resp)).await;
+            tokio::time::timeout(Duration::from_secs(WRITE_TIMEOUT_SECS), writer.write(resp)).await;
         metrics_write_client_ms(
             &NetworkConnectionType::Tcp,
             now_millis().saturating_sub(write_start) as f64,
@@ -200,10 +199,9 @@ async fn write_tls_frame0(
                 CommonError::NotObtainAvailableConnection("tls".to_string(), connection_id)
             })?;
 
-        let mut stream = writer.lock().await;
         let write_start = now_millis();
         let result =
-            tokio::time::timeout(Duration::from_secs(WRITE_TIMEOUT_SECS), stream.send(resp)).await;
+            tokio::time::timeout(Duration::from_secs(WRITE_TIMEOUT_SECS), writer.write(resp)).await;
         metrics_write_client_ms(
             &NetworkConnectionType::Tls,
             now_millis().saturating_sub(write_start) as f64,
@@ -235,6 +233,110 @@ async fn write_tls_frame0(
         }
     }
 
+    pub async fn write_mqttsn_frame(
+        &self,
+        connection_id: u64,
+        packet_wrapper: RobustMQPacketWrapper,
+    ) -> ResultCommonError {
+        if !is_ignore_print(&packet_wrapper.packet) {
+            debug!("MQTT-SN response packet:{packet_wrapper:?},connection_id:{connection_id}");
+        }
+
+        let writer = self
+            .mqttsn_write_list
+            .get(&connection_id)
+            .map(|entry| entry.value().clone())
+            .ok_or_else(|| {
+                CommonError::NotObtainAvailableConnection("mqtt-sn".to_string(), connection_id)
+            })?;
+
+        let write_start = now_millis();
+        let result = writer.send(packet_wrapper).await;
+        metrics_write_client_ms(
+            &NetworkConnectionType::MqttSn,
+            now_millis().saturating_sub(write_start) as f64,
+        );
+        if let Err(e) = result {
+            self.close_connect(connection_id).await;
+            return Err(CommonError::FailedToWriteClient(
+                "mqtt-sn".to_string(),
+                e.to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub async fn write_coap_frame(
+        &self,
+        connection_id: u64,
+        packet_wrapper: RobustMQPacketWrapper,
+    ) -> ResultCommonError {
+        if !is_ignore_print(&packet_wrapper.packet) {
+            debug!("CoAP response packet:{packet_wrapper:?},connection_id:{connection_id}");
+        }
+
+        let writer = self
+            .coap_write_list
+            .get(&connection_id)
+            .map(|entry| entry.value().clone())
+            .ok_or_else(|| {
+                CommonError::NotObtainAvailableConnection("coap".to_string(), connection_id)
+            })?;
+
+        let write_start = now_millis();
+        let result = writer.send(packet_wrapper).await;
+        metrics_write_client_ms(
+            &NetworkConnectionType::CoAP,
+            now_millis().saturating_sub(write_start) as f64,
+        );
+        if let Err(e) = result {
+            self.close_connect(connection_id).await;
+            return Err(CommonError::FailedToWriteClient(
+                "coap".to_string(),
+                e.to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub async fn write_web_subscribe_frame(
+        &self,
+        connection_id: u64,
+        packet_wrapper: RobustMQPacketWrapper,
+    ) -> ResultCommonError {
+        if !is_ignore_print(&packet_wrapper.packet) {
+            debug!(
+                "Web subscribe response packet:{packet_wrapper:?},connection_id:{connection_id}"
+            );
+        }
+
+        let writer = self
+            .web_subscribe_write_list
+            .get(&connection_id)
+            .map(|entry| entry.value().clone())
+            .ok_or_else(|| {
+                CommonError::NotObtainAvailableConnection(
+                    "web-subscribe".to_string(),
+                    connection_id,
+                )
+            })?;
+
+        let write_start = now_millis();
+        let result = writer.send(packet_wrapper).await;
+        metrics_write_client_ms(
+            &NetworkConnectionType::WebSubscribe,
+            now_millis().saturating_sub(write_start) as f64,
+        );
+        if let Err(e) = result {
+            self.close_connect(connection_id).await;
+            return Err(CommonError::FailedToWriteClient(
+                "web-subscribe".to_string(),
+                e.to_string(),
+            ));
+        }
+        Ok(())
+    }
+
     async fn write_quic_frame0(
         &self,
         connection_id: u64,
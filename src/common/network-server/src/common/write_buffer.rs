@@ -0,0 +1,221 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::error::common::CommonError;
+use common_metrics::network::metrics_write_flush;
+use futures::SinkExt;
+use metadata_struct::connection::NetworkConnectionType;
+use protocol::codec::{RobustMQCodec, RobustMQCodecWrapper};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWrite;
+use tokio::sync::Mutex;
+use tokio_util::codec::FramedWrite;
+use tracing::warn;
+
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How packets written to one connection are batched into socket writes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WriteCoalesceConfig {
+    /// Zero sends every packet as soon as it is written.
+    pub window: Duration,
+    /// Buffered bytes that force a flush before the window ends.
+    pub max_buffer_bytes: usize,
+}
+
+impl WriteCoalesceConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.window.is_zero()
+    }
+}
+
+struct BufferState<W> {
+    framed: FramedWrite<W, RobustMQCodec>,
+    pending_packets: u64,
+    first_pending: Option<Instant>,
+}
+
+/// Framed writer that encodes packets into its buffer and sends everything
+/// written within the coalescing window with one flush, so a burst of small
+/// pushes to the same client costs a few socket writes instead of one each.
+pub struct CoalescingWriter<W> {
+    state: Mutex<BufferState<W>>,
+    config: WriteCoalesceConfig,
+    network: NetworkConnectionType,
+    flush_scheduled: AtomicBool,
+    /// A deferred flush has no caller to report to, so its failure is
+    /// returned by the next write instead.
+    flush_failed: AtomicBool,
+}
+
+impl<W> CoalescingWriter<W>
+where
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    pub fn new(
+        mut framed: FramedWrite<W, RobustMQCodec>,
+        config: WriteCoalesceConfig,
+        network: NetworkConnectionType,
+    ) -> Self {
+        if config.is_enabled() {
+            // Buffered bytes are flushed explicitly once they reach the
+            // limit, so the sink must not start writing earlier on its own.
+            framed.set_backpressure_boundary(config.max_buffer_bytes.max(1));
+        }
+        CoalescingWriter {
+            state: Mutex::new(BufferState {
+                framed,
+                pending_packets: 0,
+                first_pending: None,
+            }),
+            config,
+            network,
+            flush_scheduled: AtomicBool::new(false),
+            flush_failed: AtomicBool::new(false),
+        }
+    }
+
+    pub async fn write(self: &Arc<Self>, packet: RobustMQCodecWrapper) -> Result<(), CommonError> {
+        if self.flush_failed.load(Ordering::Acquire) {
+            return Err(CommonError::CommonError(
+                "a buffered flush to this connection failed".to_string(),
+            ));
+        }
+
+        let mut state = self.state.lock().await;
+        if !self.config.is_enabled() {
+            return state.framed.send(packet).await;
+        }
+
+        state.framed.feed(packet).await?;
+        state.pending_packets += 1;
+        state.first_pending.get_or_insert_with(Instant::now);
+        if state.framed.write_buffer().len() >= self.config.max_buffer_bytes {
+            return self.flush_locked(&mut state).await;
+        }
+        drop(state);
+
+        if !self.flush_scheduled.swap(true, Ordering::AcqRel) {
+            let writer = self.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(writer.config.window).await;
+                writer.flush_scheduled.store(false, Ordering::Release);
+                let mut state = writer.state.lock().await;
+                let result =
+                    tokio::time::timeout(FLUSH_TIMEOUT, writer.flush_locked(&mut state)).await;
+                let error = match result {
+                    Ok(Ok(())) => return,
+                    Ok(Err(e)) => e.to_string(),
+                    Err(_) => format!("flush timeout after {}s", FLUSH_TIMEOUT.as_secs()),
+                };
+                writer.flush_failed.store(true, Ordering::Release);
+                warn!("{} coalesced flush failed: {}", writer.network, error);
+            });
+        }
+        Ok(())
+    }
+
+    /// Flushes anything still buffered and shuts the connection down.
+    pub async fn close(&self) -> Result<(), CommonError> {
+        let mut state = self.state.lock().await;
+        state.framed.close().await
+    }
+
+    async fn flush_locked(&self, state: &mut BufferState<W>) -> Result<(), CommonError> {
+        state.framed.flush().await?;
+        if let Some(first_pending) = state.first_pending.take() {
+            metrics_write_flush(
+                &self.network,
+                first_pending.elapsed().as_secs_f64() * 1000.0,
+                state.pending_packets,
+            );
+            state.pending_packets = 0;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protocol::mqtt::codec::MqttPacketWrapper;
+    use protocol::mqtt::common::{MqttPacket, PingResp};
+    use tokio::io::{AsyncReadExt, DuplexStream};
+
+    fn ping_resp() -> RobustMQCodecWrapper {
+        RobustMQCodecWrapper::MQTT(MqttPacketWrapper {
+            protocol_version: 4,
+            packet: MqttPacket::PingResp(PingResp),
+        })
+    }
+
+    fn writer(config: WriteCoalesceConfig) -> (Arc<CoalescingWriter<DuplexStream>>, DuplexStream) {
+        let (client, server) = tokio::io::duplex(1024);
+        let framed = FramedWrite::new(server, RobustMQCodec::new());
+        let writer = CoalescingWriter::new(framed, config, NetworkConnectionType::Tcp);
+        (Arc::new(writer), client)
+    }
+
+    async fn read_available(client: &mut DuplexStream, wait: Duration) -> Vec<u8> {
+        let mut buf = vec![0u8; 1024];
+        match tokio::time::timeout(wait, client.read(&mut buf)).await {
+            Ok(Ok(n)) => buf[..n].to_vec(),
+            _ => Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn disabled_writes_each_packet_immediately() {
+        let (writer, mut client) = writer(WriteCoalesceConfig::default());
+        writer.write(ping_resp()).await.unwrap();
+        assert_eq!(
+            read_available(&mut client, Duration::from_millis(100)).await,
+            vec![0xD0, 0x00]
+        );
+    }
+
+    #[tokio::test]
+    async fn packets_within_window_are_flushed_together() {
+        let (writer, mut client) = writer(WriteCoalesceConfig {
+            window: Duration::from_millis(50),
+            max_buffer_bytes: 1024,
+        });
+        for _ in 0..3 {
+            writer.write(ping_resp()).await.unwrap();
+        }
+        assert!(read_available(&mut client, Duration::from_millis(10))
+            .await
+            .is_empty());
+        assert_eq!(
+            read_available(&mut client, Duration::from_millis(500)).await,
+            vec![0xD0, 0x00, 0xD0, 0x00, 0xD0, 0x00]
+        );
+    }
+
+    #[tokio::test]
+    async fn full_buffer_is_flushed_before_window_ends() {
+        let (writer, mut client) = writer(WriteCoalesceConfig {
+            window: Duration::from_secs(60),
+            max_buffer_bytes: 4,
+        });
+        writer.write(ping_resp()).await.unwrap();
+        writer.write(ping_resp()).await.unwrap();
+        assert_eq!(
+            read_available(&mut client, Duration::from_millis(100)).await,
+            vec![0xD0, 0x00, 0xD0, 0x00]
+        );
+    }
+}
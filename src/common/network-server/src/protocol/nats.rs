@@ -117,7 +117,10 @@ fn build_nats_info(
 
 fn get_port_by_network_type(conf: &BrokerConfig, network_type: &NetworkConnectionType) -> u32 {
     match network_type.clone() {
-        NetworkConnectionType::QUIC => 0,
+        NetworkConnectionType::QUIC
+        | NetworkConnectionType::MqttSn
+        | NetworkConnectionType::CoAP
+        | NetworkConnectionType::WebSubscribe => 0,
         NetworkConnectionType::Tcp => conf.nats_runtime.tcp_port,
         NetworkConnectionType::Tls => conf.nats_runtime.tls_port,
         NetworkConnectionType::WebSocket => conf.nats_runtime.ws_port,
@@ -156,7 +159,10 @@ fn build_network_connect_urls(
     network_type: &NetworkConnectionType,
 ) -> Vec<String> {
     match network_type {
-        NetworkConnectionType::QUIC => Vec::new(),
+        NetworkConnectionType::QUIC
+        | NetworkConnectionType::MqttSn
+        | NetworkConnectionType::CoAP
+        | NetworkConnectionType::WebSubscribe => Vec::new(),
         NetworkConnectionType::Tls => node_cache
             .node_list()
             .iter()
@@ -12,10 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::common::cert_manager::{cert_manager, start_cert_reload_thread};
 use crate::common::channel::RequestChannel;
 use crate::common::connection_manager::ConnectionManager;
 use crate::common::packet::RequestPackage;
-use crate::common::tool::check_connection_limit;
+use crate::common::tls_acceptor::apply_session_resumption;
+use crate::common::tool::{build_connection_codec, check_connection_limit};
 use axum::extract::ws::{Message, WebSocket};
 use axum::extract::{ConnectInfo, State, WebSocketUpgrade};
 use axum::response::Response;
@@ -34,10 +36,10 @@
 use protocol::robust::{RobustMQPacket, RobustMQProtocol};
 use rate_limit::global::GlobalRateLimiterManager;
 use std::net::SocketAddr;
-use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::select;
 use tokio::sync::broadcast;
+use tokio_rustls::rustls::ServerConfig;
 use tracing::{debug, error, info, warn};
 
 pub const ROUTE_ROOT: &str = "/mqtt";
@@ -82,12 +84,16 @@ pub async fn start_wss(&self) -> ResultCommonError {
         let ip: SocketAddr = format!("0.0.0.0:{}", self.state.wss_port).parse()?;
         let app = routes_v1(self.state.clone());
 
-        let config = broker_config();
-        let tls_config = RustlsConfig::from_pem_file(
-            PathBuf::from(config.runtime.tls_cert.clone()),
-            PathBuf::from(config.runtime.tls_key.clone()),
-        )
-        .await?;
+        // Resolve the certificate through the cert manager so that it can be
+        // reloaded without restarting the listener.
+        let cert_manager = cert_manager()?;
+        start_cert_reload_thread(cert_manager.clone(), self.state.stop_sx.clone());
+        let mut server_config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_cert_resolver(cert_manager);
+        server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        apply_session_resumption(&mut server_config, &broker_config().runtime)?;
+        let tls_config = RustlsConfig::from_config(Arc::new(server_config));
 
         info!(
             "{:?} WebSocket TLS Server start success. addr:{}",
@@ -149,7 +155,7 @@ async fn handle_socket(
     connection_manager.add_connection(connection);
     let mut stop_rx = stop_sx.subscribe();
 
-    let mut codec = RobustMQCodec::new();
+    let mut codec = build_connection_codec(&node_cache, &RobustMQCodec::new());
     if check_connection_limit(
         &global_limit_manager,
         &node_cache,
@@ -0,0 +1,81 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// The in-process part of a node call, everything but the gRPC round trip:
+// stamping the cache version, routing the request to a node worker and
+// building and encoding the batched UpdateCache request.
+// Run with: cargo bench -p node-call --bench dispatch
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use node_call::consumer::worker_index;
+use node_call::handler::build_update_cache_request;
+use node_call::version::CacheVersions;
+use node_call::{NodeCallData, UpdateCacheData, BATCH_SIZE, WORKER_THREAD_NUM};
+use prost::Message;
+use protocol::broker::broker::{BrokerUpdateCacheActionType, BrokerUpdateCacheResourceType};
+
+fn update_cache(version: u64) -> UpdateCacheData {
+    UpdateCacheData {
+        action_type: BrokerUpdateCacheActionType::Create,
+        resource_type: BrokerUpdateCacheResourceType::Subscribe,
+        data: vec![1u8; 256],
+        version,
+    }
+}
+
+fn bench_stamp_version(c: &mut Criterion) {
+    let versions = CacheVersions::default();
+    c.bench_function("node_call_stamp_version", |b| {
+        b.iter(|| versions.next(black_box(BrokerUpdateCacheResourceType::Subscribe)));
+    });
+}
+
+fn bench_route(c: &mut Criterion) {
+    let mut group = c.benchmark_group("node_call_route");
+    let requests = [
+        ("update_cache", NodeCallData::UpdateCache(update_cache(1))),
+        (
+            "last_will",
+            NodeCallData::SendLastWillMessage {
+                tenant: "default".to_string(),
+                client_id: "sensor-000001".to_string(),
+            },
+        ),
+    ];
+    for (name, data) in requests.iter() {
+        group.bench_function(*name, |b| {
+            b.iter(|| worker_index(black_box(data), WORKER_THREAD_NUM));
+        });
+    }
+    group.finish();
+}
+
+fn bench_update_cache_request(c: &mut Criterion) {
+    let mut group = c.benchmark_group("node_call_update_cache_request");
+    for size in [1, 10, BATCH_SIZE] {
+        let batch: Vec<UpdateCacheData> = (0..size as u64).map(update_cache).collect();
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, _| {
+            b.iter(|| build_update_cache_request(black_box(&batch)).encode_to_vec());
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_stamp_version,
+    bench_route,
+    bench_update_cache_request
+);
+criterion_main!(benches);
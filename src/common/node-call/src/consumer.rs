@@ -12,113 +12,117 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::dead_letter::DeadLetterQueue;
 use crate::handler::{send_get_qos_data_batch, send_last_will_batch, send_update_cache_batch};
-use crate::{NodeCallData, NodeCallRequest, BATCH_SIZE, WORKER_THREAD_NUM};
+use crate::queue::{priority_channel, PriorityReceiver, PrioritySender, QueueDepth};
+use crate::{
+    NodeCallData, NodeCallRequest, UpdateCacheData, BATCH_SIZE, NODE_CHANNEL_SIZE,
+    WORKER_THREAD_NUM,
+};
 use grpc_clients::pool::ClientPool;
 use metadata_struct::meta::node::BrokerNode;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 use std::sync::Arc;
-use tokio::sync::{broadcast, mpsc};
-use tracing::info;
+use tokio::sync::broadcast;
+use tokio::sync::mpsc::error::TrySendError;
+use tracing::{info, warn};
 
-const WORKER_CHANNEL_SIZE: usize = BATCH_SIZE * 4;
+/// Queues of the workers delivering node calls to one broker node. The workers
+/// stop once every clone of this handle is dropped.
+#[derive(Clone)]
+pub struct NodeQueues {
+    workers: Vec<PrioritySender>,
+    depth: Arc<QueueDepth>,
+}
+
+impl NodeQueues {
+    /// Queue a request on the worker its partition key maps to. Control calls
+    /// and last-will messages are rejected when that queue is full; cache
+    /// updates wait for room so their order is kept.
+    pub async fn push(
+        &self,
+        request: NodeCallRequest,
+    ) -> Result<(), TrySendError<NodeCallRequest>> {
+        let priority = request.data.priority();
+        let worker = &self.workers[worker_index(&request.data, self.workers.len())];
+
+        self.depth.add(priority);
+        let result = if priority.drop_when_full() {
+            worker.try_send(request)
+        } else {
+            worker
+                .send(request)
+                .await
+                .map_err(|e| TrySendError::Closed(e.0))
+        };
+        if result.is_err() {
+            self.depth.remove(priority, 1);
+        }
+        result
+    }
+
+    pub fn depth(&self) -> &Arc<QueueDepth> {
+        &self.depth
+    }
+}
 
 pub fn start_node_consumer_thread(
     node: BrokerNode,
     client_pool: Arc<ClientPool>,
-    receiver: mpsc::Receiver<NodeCallRequest>,
     stop_send: broadcast::Sender<bool>,
-) {
+    dead_letter: Arc<DeadLetterQueue>,
+) -> NodeQueues {
     let worker_num = WORKER_THREAD_NUM;
-    let mut worker_senders = Vec::with_capacity(worker_num);
+    let depth = Arc::new(QueueDepth::new(node.node_id));
+    let mut workers = Vec::with_capacity(worker_num);
 
     for i in 0..worker_num {
-        let (tx, rx) = mpsc::channel(WORKER_CHANNEL_SIZE);
-        worker_senders.push(tx);
+        let (tx, rx) = priority_channel(NODE_CHANNEL_SIZE);
+        workers.push(tx);
         spawn_worker(
             i,
             node.clone(),
             client_pool.clone(),
             rx,
             stop_send.subscribe(),
+            dead_letter.clone(),
+            depth.clone(),
         );
     }
 
-    spawn_router(
-        node.node_id,
-        receiver,
-        worker_senders,
-        stop_send.subscribe(),
+    info!(
+        "Node consumer started for node {}, workers={}",
+        node.node_id, worker_num
     );
+    NodeQueues { workers, depth }
 }
 
-fn spawn_router(
-    node_id: u64,
-    mut receiver: mpsc::Receiver<NodeCallRequest>,
-    workers: Vec<mpsc::Sender<NodeCallRequest>>,
-    mut stop_receiver: broadcast::Receiver<bool>,
-) {
-    let worker_num = workers.len();
-
-    tokio::spawn(async move {
-        info!(
-            "Node consumer router started for node {}, workers={}",
-            node_id, worker_num
-        );
-
-        loop {
-            let data = tokio::select! {
-                stop = stop_receiver.recv() => {
-                    match stop {
-                        Ok(true) | Err(broadcast::error::RecvError::Closed) => {
-                            info!("Node consumer router received stop signal for node {}", node_id);
-                            break;
-                        }
-                        _ => continue,
-                    }
-                }
-                data = receiver.recv() => {
-                    match data {
-                        Some(msg) => msg,
-                        None => {
-                            info!("Node consumer router channel closed for node {}", node_id);
-                            break;
-                        }
-                    }
-                }
-            };
-
-            let idx = if worker_num <= 1 {
-                0
-            } else {
-                match data.data.partition_key() {
-                    None => 0,
-                    Some(key) => {
-                        let mut hasher = DefaultHasher::new();
-                        key.hash(&mut hasher);
-                        (hasher.finish() as usize % (worker_num - 1)) + 1
-                    }
-                }
-            };
-
-            if workers[idx].send(data).await.is_err() {
-                info!(
-                    "Worker {} channel closed for node {}, stopping router",
-                    idx, node_id
-                );
-                break;
-            }
+/// Worker a request is routed to. Requests without a partition key all go to
+/// worker 0; keyed ones are hashed over the remaining workers so calls for the
+/// same client keep their order.
+pub fn worker_index(data: &NodeCallData, worker_num: usize) -> usize {
+    if worker_num <= 1 {
+        return 0;
+    }
+    match data.partition_key() {
+        None => 0,
+        Some(key) => {
+            let mut hasher = DefaultHasher::new();
+            key.hash(&mut hasher);
+            (hasher.finish() as usize % (worker_num - 1)) + 1
         }
-    });
+    }
 }
 
 fn spawn_worker(
     worker_id: usize,
     node: BrokerNode,
     client_pool: Arc<ClientPool>,
-    mut receiver: mpsc::Receiver<NodeCallRequest>,
+    mut receiver: PriorityReceiver,
     mut stop_receiver: broadcast::Receiver<bool>,
+    dead_letter: Arc<DeadLetterQueue>,
+    depth: Arc<QueueDepth>,
 ) {
     tokio::spawn(async move {
         info!(
@@ -156,19 +160,21 @@ fn spawn_worker(
 
             let mut batch = Vec::with_capacity(BATCH_SIZE);
             batch.push(first);
-            while batch.len() < BATCH_SIZE {
-                match receiver.try_recv() {
-                    Ok(msg) => batch.push(msg),
-                    Err(_) => break,
-                }
-            }
+            receiver.drain_weighted(&mut batch, BATCH_SIZE);
+            depth.remove_batch(&batch);
 
-            dispatch_batch(&client_pool, &node.grpc_addr, batch).await;
+            dispatch_batch(&client_pool, &node, &dead_letter, batch).await;
         }
     });
 }
 
-async fn dispatch_batch(client_pool: &Arc<ClientPool>, addr: &str, batch: Vec<NodeCallRequest>) {
+async fn dispatch_batch(
+    client_pool: &Arc<ClientPool>,
+    node: &BrokerNode,
+    dead_letter: &Arc<DeadLetterQueue>,
+    batch: Vec<NodeCallRequest>,
+) {
+    let addr = node.grpc_addr.as_str();
     let mut cache_updates = Vec::new();
     let mut last_will_messages: Vec<(String, String)> = Vec::new();
     let mut get_qos_data = Vec::new();
@@ -189,7 +195,7 @@ async fn dispatch_batch(client_pool: &Arc<ClientPool>, addr: &str, batch: Vec<No
     tokio::join!(
         async {
             if !cache_updates.is_empty() {
-                send_update_cache_batch(client_pool, addr, &cache_updates).await;
+                send_update_cache(client_pool, node, dead_letter, &cache_updates).await;
             }
         },
         async {
@@ -204,3 +210,53 @@ async fn dispatch_batch(client_pool: &Arc<ClientPool>, addr: &str, batch: Vec<No
         },
     );
 }
+
+/// Deliver cache updates, or queue them in the dead-letter queue when the node
+/// is unreachable or still has older undelivered updates.
+async fn send_update_cache(
+    client_pool: &Arc<ClientPool>,
+    node: &BrokerNode,
+    dead_letter: &Arc<DeadLetterQueue>,
+    cache_updates: &[UpdateCacheData],
+) {
+    if !dead_letter.is_blocked(node.node_id)
+        && send_update_cache_batch(client_pool, &node.grpc_addr, cache_updates).await
+    {
+        return;
+    }
+
+    if let Err(e) = dead_letter.push(node.node_id, cache_updates) {
+        warn!(
+            "Failed to queue {} cache updates for node {}, they are lost: {}",
+            cache_updates.len(),
+            node.node_id,
+            e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_worker_index_keeps_client_on_one_worker() {
+        let last_will = |client_id: &str| NodeCallData::SendLastWillMessage {
+            tenant: "default".to_string(),
+            client_id: client_id.to_string(),
+        };
+
+        let idx = worker_index(&last_will("c1"), WORKER_THREAD_NUM);
+        assert!((1..WORKER_THREAD_NUM).contains(&idx));
+        assert_eq!(worker_index(&last_will("c1"), WORKER_THREAD_NUM), idx);
+
+        assert_eq!(
+            worker_index(
+                &NodeCallData::GetQosData("c1".to_string()),
+                WORKER_THREAD_NUM
+            ),
+            0
+        );
+        assert_eq!(worker_index(&last_will("c1"), 1), 0);
+    }
+}
@@ -0,0 +1,485 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Dead-letter queue for cache notifications a broker node did not accept.
+//!
+//! When `UpdateCache` to a node still fails after the in-line retries, the
+//! batch is persisted to RocksDB instead of being dropped. While a node has
+//! pending records, new notifications for it are appended behind them so the
+//! node always applies changes in order. A replay task re-sends the records
+//! with exponential backoff. If the node stays unreachable for longer than
+//! [`RESYNC_AFTER_MS`], or the queue overflows, the records are discarded and
+//! the node is asked to reload its whole cache with `ResyncCache` once it
+//! answers again.
+
+use crate::{UpdateCacheData, BATCH_SIZE};
+use broker_core::cache::NodeCacheManager;
+use common_base::error::common::CommonError;
+use common_base::error::ResultCommonError;
+use common_base::tools::{loop_select_ticket, now_millis};
+use common_metrics::node_call::{
+    record_node_call_cache_resync, record_node_call_dead_letter_replay,
+    set_node_call_dead_letter_pending,
+};
+use dashmap::DashMap;
+use grpc_clients::broker::common::call::{broker_resync_cache, broker_update_cache};
+use grpc_clients::pool::ClientPool;
+use protocol::broker::broker::{ResyncCacheRequest, UpdateCacheRecord, UpdateCacheRequest};
+use rocksdb_engine::keys::broker::{
+    node_call_dead_letter_key, node_call_dead_letter_prefix_key,
+    node_call_dead_letter_prefix_key_by_node,
+};
+use rocksdb_engine::rocksdb::RocksDBEngine;
+use rocksdb_engine::storage::broker::{
+    engine_delete_by_broker, engine_delete_prefix_by_broker, engine_get_by_broker,
+    engine_prefix_list_by_broker, engine_save_by_broker,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+pub const DEAD_LETTER_MAX_PER_NODE: u64 = 100_000;
+pub const REPLAY_INTERVAL_MS: u64 = 1000;
+pub const REPLAY_BACKOFF_BASE_MS: u64 = 1000;
+pub const REPLAY_BACKOFF_MAX_MS: u64 = 60_000;
+pub const RESYNC_AFTER_MS: u64 = 5 * 60 * 1000;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct DeadLetterRecord {
+    node_id: u64,
+    seq: u64,
+    action_type: i32,
+    resource_type: i32,
+    data: Vec<u8>,
+    version: u64,
+}
+
+#[derive(Default, Clone, Debug)]
+struct NodeDeadLetterState {
+    // Records in [head_seq, next_seq) are pending.
+    head_seq: u64,
+    next_seq: u64,
+    needs_resync: bool,
+    first_failure_ms: u128,
+    failures: u32,
+    next_retry_ms: u128,
+}
+
+impl NodeDeadLetterState {
+    fn pending(&self) -> u64 {
+        self.next_seq - self.head_seq
+    }
+
+    fn is_active(&self) -> bool {
+        self.pending() > 0 || self.needs_resync
+    }
+
+    fn on_first_failure(&mut self, now: u128) {
+        if !self.is_active() {
+            self.first_failure_ms = now;
+            self.failures = 1;
+            self.next_retry_ms = now + backoff_ms(1) as u128;
+        }
+    }
+
+    fn on_failure(&mut self, now: u128) {
+        self.failures = self.failures.saturating_add(1);
+        self.next_retry_ms = now + backoff_ms(self.failures) as u128;
+        if now.saturating_sub(self.first_failure_ms) >= RESYNC_AFTER_MS as u128 {
+            self.needs_resync = true;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.head_seq = self.next_seq;
+        self.needs_resync = false;
+        self.failures = 0;
+        self.first_failure_ms = 0;
+        self.next_retry_ms = 0;
+    }
+}
+
+fn backoff_ms(failures: u32) -> u64 {
+    let shift = failures.saturating_sub(1).min(16);
+    REPLAY_BACKOFF_BASE_MS
+        .saturating_mul(1u64 << shift)
+        .min(REPLAY_BACKOFF_MAX_MS)
+}
+
+pub struct DeadLetterQueue {
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+    nodes: DashMap<u64, NodeDeadLetterState>,
+}
+
+impl DeadLetterQueue {
+    pub fn new(rocksdb_engine_handler: Arc<RocksDBEngine>) -> Self {
+        DeadLetterQueue {
+            rocksdb_engine_handler,
+            nodes: DashMap::with_capacity(8),
+        }
+    }
+
+    /// Rebuild the per-node state from records persisted before a restart.
+    pub fn recover(&self) -> Result<(), CommonError> {
+        let records = engine_prefix_list_by_broker::<DeadLetterRecord>(
+            &self.rocksdb_engine_handler,
+            &node_call_dead_letter_prefix_key(),
+        )?;
+
+        let now = now_millis();
+        for record in records.iter().map(|wrap| &wrap.data) {
+            let mut state = self.nodes.entry(record.node_id).or_default();
+            if state.pending() == 0 {
+                state.head_seq = record.seq;
+                state.first_failure_ms = now;
+            }
+            state.head_seq = state.head_seq.min(record.seq);
+            state.next_seq = state.next_seq.max(record.seq + 1);
+        }
+
+        for entry in self.nodes.iter() {
+            set_node_call_dead_letter_pending(*entry.key(), entry.pending());
+            info!(
+                "Recovered {} dead-letter cache notifications for node {}",
+                entry.pending(),
+                entry.key()
+            );
+        }
+        Ok(())
+    }
+
+    /// True while earlier notifications for the node are still undelivered;
+    /// new ones must then be queued behind them.
+    pub fn is_blocked(&self, node_id: u64) -> bool {
+        self.nodes
+            .get(&node_id)
+            .map(|state| state.is_active())
+            .unwrap_or(false)
+    }
+
+    pub fn pending(&self, node_id: u64) -> u64 {
+        self.nodes
+            .get(&node_id)
+            .map(|state| state.pending())
+            .unwrap_or(0)
+    }
+
+    pub fn push(&self, node_id: u64, data: &[UpdateCacheData]) -> Result<(), CommonError> {
+        let mut state = self.nodes.entry(node_id).or_default();
+        state.on_first_failure(now_millis());
+
+        if state.pending() + data.len() as u64 > DEAD_LETTER_MAX_PER_NODE {
+            warn!(
+                "Dead-letter queue for node {} overflowed, a full cache resync will be requested",
+                node_id
+            );
+            engine_delete_prefix_by_broker(
+                &self.rocksdb_engine_handler,
+                &node_call_dead_letter_prefix_key_by_node(node_id),
+            )?;
+            state.head_seq = state.next_seq;
+            state.needs_resync = true;
+            set_node_call_dead_letter_pending(node_id, 0);
+            return Ok(());
+        }
+
+        for item in data {
+            let record = DeadLetterRecord {
+                node_id,
+                seq: state.next_seq,
+                action_type: item.action_type.into(),
+                resource_type: item.resource_type.into(),
+                data: item.data.clone(),
+                version: item.version,
+            };
+            engine_save_by_broker(
+                &self.rocksdb_engine_handler,
+                &node_call_dead_letter_key(node_id, record.seq),
+                record,
+            )?;
+            state.next_seq += 1;
+        }
+        set_node_call_dead_letter_pending(node_id, state.pending());
+        Ok(())
+    }
+
+    /// Drop everything queued for a node that has left the cluster for good.
+    pub fn remove_node(&self, node_id: u64) -> Result<(), CommonError> {
+        if self.nodes.remove(&node_id).is_some() {
+            engine_delete_prefix_by_broker(
+                &self.rocksdb_engine_handler,
+                &node_call_dead_letter_prefix_key_by_node(node_id),
+            )?;
+            set_node_call_dead_letter_pending(node_id, 0);
+        }
+        Ok(())
+    }
+
+    fn read_batch(&self, node_id: u64) -> Result<(u64, Vec<UpdateCacheRecord>), CommonError> {
+        let Some(state) = self.nodes.get(&node_id).map(|s| s.clone()) else {
+            return Ok((0, Vec::new()));
+        };
+
+        let end = state.next_seq.min(state.head_seq + BATCH_SIZE as u64);
+        let mut records = Vec::with_capacity((end - state.head_seq) as usize);
+        for seq in state.head_seq..end {
+            if let Some(wrap) = engine_get_by_broker::<DeadLetterRecord>(
+                &self.rocksdb_engine_handler,
+                &node_call_dead_letter_key(node_id, seq),
+            )? {
+                records.push(UpdateCacheRecord {
+                    action_type: wrap.data.action_type,
+                    resource_type: wrap.data.resource_type,
+                    data: wrap.data.data,
+                    version: wrap.data.version,
+                });
+            }
+        }
+        Ok((end, records))
+    }
+
+    fn ack_until(&self, node_id: u64, end: u64) -> Result<(), CommonError> {
+        let Some(mut state) = self.nodes.get_mut(&node_id) else {
+            return Ok(());
+        };
+        for seq in state.head_seq..end {
+            engine_delete_by_broker(
+                &self.rocksdb_engine_handler,
+                &node_call_dead_letter_key(node_id, seq),
+            )?;
+        }
+        state.head_seq = state.head_seq.max(end);
+        if state.pending() == 0 && !state.needs_resync {
+            state.reset();
+        }
+        set_node_call_dead_letter_pending(node_id, state.pending());
+        Ok(())
+    }
+
+    fn finish_resync(&self, node_id: u64, resync_seq: u64) -> Result<(), CommonError> {
+        if let Some(mut state) = self.nodes.get_mut(&node_id) {
+            state.needs_resync = false;
+            state.next_retry_ms = 0;
+        }
+        self.ack_until(node_id, resync_seq)
+    }
+
+    fn on_failure(&self, node_id: u64) {
+        if let Some(mut state) = self.nodes.get_mut(&node_id) {
+            state.on_failure(now_millis());
+            if state.needs_resync && state.pending() > 0 {
+                let _ = engine_delete_prefix_by_broker(
+                    &self.rocksdb_engine_handler,
+                    &node_call_dead_letter_prefix_key_by_node(node_id),
+                );
+                state.head_seq = state.next_seq;
+                set_node_call_dead_letter_pending(node_id, 0);
+            }
+        }
+    }
+
+    fn due_nodes(&self) -> Vec<(u64, bool)> {
+        let now = now_millis();
+        self.nodes
+            .iter()
+            .filter(|entry| entry.is_active() && entry.next_retry_ms <= now)
+            .map(|entry| (*entry.key(), entry.needs_resync))
+            .collect()
+    }
+
+    /// One replay round over every node whose backoff has elapsed.
+    pub async fn replay(
+        &self,
+        broker_cache: &Arc<NodeCacheManager>,
+        client_pool: &Arc<ClientPool>,
+    ) {
+        for (node_id, needs_resync) in self.due_nodes() {
+            // A node missing from the list may only be offline or not loaded
+            // yet, so its letters are kept and retried like a failed send.
+            // Decommissioning a node drops them with `remove_node`.
+            let Some(node) = broker_cache.node_lists.get(&node_id).map(|n| n.clone()) else {
+                self.on_failure(node_id);
+                continue;
+            };
+            let addrs = [node.grpc_addr.as_str()];
+
+            if needs_resync {
+                // Records queued from here on may not be covered by the reload
+                // and are replayed after it.
+                let resync_seq = self.nodes.get(&node_id).map(|s| s.next_seq).unwrap_or(0);
+                let request = ResyncCacheRequest { node_id };
+                let success = broker_resync_cache(client_pool, &addrs, request)
+                    .await
+                    .is_ok();
+                record_node_call_cache_resync(node_id, success);
+                if success {
+                    info!(
+                        "Node {} reloaded its cache after missed notifications",
+                        node_id
+                    );
+                    if let Err(e) = self.finish_resync(node_id, resync_seq) {
+                        warn!("Failed to clear dead letters of node {}: {}", node_id, e);
+                    }
+                } else {
+                    self.on_failure(node_id);
+                }
+                continue;
+            }
+
+            loop {
+                let (end, records) = match self.read_batch(node_id) {
+                    Ok(batch) => batch,
+                    Err(e) => {
+                        warn!("Failed to read dead letters of node {}: {}", node_id, e);
+                        break;
+                    }
+                };
+                if self.pending(node_id) == 0 {
+                    break;
+                }
+
+                if !records.is_empty() {
+                    let request = UpdateCacheRequest { records };
+                    let success = broker_update_cache(client_pool, &addrs, request)
+                        .await
+                        .is_ok();
+                    record_node_call_dead_letter_replay(node_id, success);
+                    if !success {
+                        self.on_failure(node_id);
+                        break;
+                    }
+                }
+
+                if let Err(e) = self.ack_until(node_id, end) {
+                    warn!(
+                        "Failed to remove replayed dead letters of node {}: {}",
+                        node_id, e
+                    );
+                    break;
+                }
+                if self.pending(node_id) == 0 {
+                    info!(
+                        "Replayed all dead-letter cache notifications for node {}",
+                        node_id
+                    );
+                    break;
+                }
+            }
+        }
+    }
+}
+
+pub fn start_replay_thread(
+    queue: Arc<DeadLetterQueue>,
+    broker_cache: Arc<NodeCacheManager>,
+    client_pool: Arc<ClientPool>,
+    stop_send: broadcast::Sender<bool>,
+) {
+    tokio::spawn(async move {
+        let queue = &queue;
+        let broker_cache = &broker_cache;
+        let client_pool = &client_pool;
+        let ac_fn = async || -> ResultCommonError {
+            queue.replay(broker_cache, client_pool).await;
+            Ok(())
+        };
+        loop_select_ticket(ac_fn, REPLAY_INTERVAL_MS, &stop_send).await;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common_config::broker::default_broker_config;
+    use protocol::broker::broker::{BrokerUpdateCacheActionType, BrokerUpdateCacheResourceType};
+    use rocksdb_engine::test::test_rocksdb_instance;
+
+    fn update(data: &str) -> UpdateCacheData {
+        UpdateCacheData {
+            action_type: BrokerUpdateCacheActionType::Create,
+            resource_type: BrokerUpdateCacheResourceType::Topic,
+            data: data.as_bytes().to_vec(),
+            version: 0,
+        }
+    }
+
+    #[test]
+    fn test_backoff_ms() {
+        assert_eq!(backoff_ms(1), REPLAY_BACKOFF_BASE_MS);
+        assert_eq!(backoff_ms(2), REPLAY_BACKOFF_BASE_MS * 2);
+        assert_eq!(backoff_ms(30), REPLAY_BACKOFF_MAX_MS);
+    }
+
+    #[test]
+    fn test_push_read_ack_recover() {
+        let rocksdb = test_rocksdb_instance();
+        let queue = DeadLetterQueue::new(rocksdb.clone());
+        assert!(!queue.is_blocked(1));
+
+        queue.push(1, &[update("a"), update("b")]).unwrap();
+        queue.push(1, &[update("c")]).unwrap();
+        assert!(queue.is_blocked(1));
+        assert_eq!(queue.pending(1), 3);
+
+        let (end, records) = queue.read_batch(1).unwrap();
+        assert_eq!(end, 3);
+        let data: Vec<&[u8]> = records.iter().map(|r| r.data.as_slice()).collect();
+        assert_eq!(data, vec![&b"a"[..], &b"b"[..], &b"c"[..]]);
+
+        queue.ack_until(1, 2).unwrap();
+        assert_eq!(queue.pending(1), 1);
+
+        // state survives a restart
+        let recovered = DeadLetterQueue::new(rocksdb);
+        recovered.recover().unwrap();
+        assert_eq!(recovered.pending(1), 1);
+        let (_, records) = recovered.read_batch(1).unwrap();
+        assert_eq!(records[0].data, b"c".to_vec());
+
+        recovered.ack_until(1, 3).unwrap();
+        assert!(!recovered.is_blocked(1));
+    }
+
+    #[test]
+    fn test_prolonged_failure_requests_resync() {
+        let queue = DeadLetterQueue::new(test_rocksdb_instance());
+        queue.push(7, &[update("a")]).unwrap();
+
+        queue.nodes.get_mut(&7).unwrap().first_failure_ms = 0;
+        queue.on_failure(7);
+
+        let state = queue.nodes.get(&7).unwrap().clone();
+        assert!(state.needs_resync);
+        assert_eq!(state.pending(), 0);
+        assert!(queue.is_blocked(7));
+    }
+
+    #[tokio::test]
+    async fn test_replay_keeps_letters_of_unknown_node() {
+        let queue = DeadLetterQueue::new(test_rocksdb_instance());
+        queue.push(9, &[update("a")]).unwrap();
+        queue.nodes.get_mut(&9).unwrap().next_retry_ms = 0;
+
+        let broker_cache = Arc::new(NodeCacheManager::new(default_broker_config()));
+        let client_pool = Arc::new(ClientPool::new(1));
+        queue.replay(&broker_cache, &client_pool).await;
+
+        assert_eq!(queue.pending(9), 1);
+        assert!(queue.nodes.get(&9).unwrap().next_retry_ms > 0);
+
+        queue.remove_node(9).unwrap();
+        assert!(!queue.is_blocked(9));
+    }
+}
@@ -12,27 +12,70 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{consumer, NodeCallRequest, NODE_CHANNEL_SIZE};
+use crate::consumer::{self, NodeQueues};
+use crate::dead_letter::DeadLetterQueue;
+use crate::queue::PriorityReceiver;
+use crate::{NodeCallPriority, NodeCallRequest};
 use broker_core::cache::NodeCacheManager;
+use common_metrics::node_call::record_node_call_queue_dropped;
 use dashmap::DashMap;
+use futures::future::join_all;
 use grpc_clients::pool::ClientPool;
 use metadata_struct::meta::node::BrokerNode;
 use std::sync::Arc;
+use tokio::sync::mpsc::error::TrySendError;
 use tokio::sync::{broadcast, mpsc};
 use tracing::{info, warn};
 
+/// Fan requests out to the node queues. Each priority class is dispatched by
+/// its own task, so cache updates waiting for room on a slow node never hold
+/// back control calls or last-will messages.
 pub async fn run(
-    mut global_receiver: mpsc::Receiver<NodeCallRequest>,
+    global_receiver: PriorityReceiver,
     stop_send: broadcast::Sender<bool>,
-    node_channels: Arc<DashMap<u64, mpsc::Sender<NodeCallRequest>>>,
+    node_channels: Arc<DashMap<u64, NodeQueues>>,
     broker_cache: Arc<NodeCacheManager>,
     client_pool: Arc<ClientPool>,
+    dead_letter: Arc<DeadLetterQueue>,
+) {
+    let handles: Vec<_> = global_receiver
+        .into_receivers()
+        .into_iter()
+        .map(|(priority, receiver)| {
+            tokio::spawn(run_class(
+                priority,
+                receiver,
+                stop_send.clone(),
+                node_channels.clone(),
+                broker_cache.clone(),
+                client_pool.clone(),
+                dead_letter.clone(),
+            ))
+        })
+        .collect();
+    join_all(handles).await;
+
+    let node_ids: Vec<u64> = node_channels.iter().map(|entry| *entry.key()).collect();
+    for node_id in node_ids {
+        remove_node_channel(&node_channels, node_id);
+    }
+    info!("Node call manager dispatcher exited");
+}
+
+async fn run_class(
+    priority: NodeCallPriority,
+    mut receiver: mpsc::Receiver<NodeCallRequest>,
+    stop_send: broadcast::Sender<bool>,
+    node_channels: Arc<DashMap<u64, NodeQueues>>,
+    broker_cache: Arc<NodeCacheManager>,
+    client_pool: Arc<ClientPool>,
+    dead_letter: Arc<DeadLetterQueue>,
 ) {
     let mut stop_receiver = stop_send.subscribe();
 
     loop {
         tokio::select! {
-            maybe_data = global_receiver.recv() => {
+            maybe_data = receiver.recv() => {
                 match maybe_data {
                     Some(mut request) => {
                         // Use the node snapshot bundled in the request (send_with_reply path) so
@@ -45,8 +88,13 @@ pub async fn run(
                         };
 
                         for (idx, node) in nodes.iter().enumerate() {
-                            let sender =
-                                get_or_create_sender(&node_channels, node, &client_pool, &stop_send);
+                            let queues = get_or_create_queues(
+                                &node_channels,
+                                node,
+                                &client_pool,
+                                &stop_send,
+                                &dead_letter,
+                            );
 
                             // Extract the oneshot sender for this node; other slots remain None.
                             let reply_tx = request.reply_txs.get_mut(idx).and_then(|s| s.take());
@@ -56,17 +104,28 @@ pub async fn run(
                                 reply_txs: vec![reply_tx],
                             };
 
-                            if let Err(e) = sender.send(node_request).await {
-                                warn!(
-                                    "Failed to dispatch to node {}, removing channel: {}",
-                                    node.node_id, e
-                                );
-                                remove_node_channel(&node_channels, node.node_id);
+                            match queues.push(node_request).await {
+                                Ok(()) => {}
+                                Err(TrySendError::Full(_)) => {
+                                    record_node_call_queue_dropped(node.node_id, priority.as_str());
+                                    warn!(
+                                        "Node {} {} queue is full, dropping request",
+                                        node.node_id,
+                                        priority.as_str()
+                                    );
+                                }
+                                Err(TrySendError::Closed(_)) => {
+                                    warn!(
+                                        "Failed to dispatch to node {}, removing channel: channel closed",
+                                        node.node_id
+                                    );
+                                    remove_node_channel(&node_channels, node.node_id);
+                                }
                             }
                         }
                     }
                     None => {
-                        info!("Global channel closed, dispatcher stopping");
+                        info!("Global {} channel closed, dispatcher stopping", priority.as_str());
                         break;
                     }
                 }
@@ -74,52 +133,50 @@ pub async fn run(
             stop = stop_receiver.recv() => {
                 match stop {
                     Ok(true) => {
-                        info!("Received stop signal, dispatcher stopping");
+                        info!("Received stop signal, {} dispatcher stopping", priority.as_str());
                         break;
                     }
                     Ok(false) => {}
                     Err(broadcast::error::RecvError::Lagged(_)) => {}
                     Err(broadcast::error::RecvError::Closed) => {
-                        info!("Stop channel closed, dispatcher stopping");
+                        info!("Stop channel closed, {} dispatcher stopping", priority.as_str());
                         break;
                     }
                 }
             }
         };
     }
-
-    let node_ids: Vec<u64> = node_channels.iter().map(|entry| *entry.key()).collect();
-    for node_id in node_ids {
-        remove_node_channel(&node_channels, node_id);
-    }
-    info!("Node call manager dispatcher exited");
 }
 
-fn get_or_create_sender(
-    node_channels: &Arc<DashMap<u64, mpsc::Sender<NodeCallRequest>>>,
+fn get_or_create_queues(
+    node_channels: &Arc<DashMap<u64, NodeQueues>>,
     node: &BrokerNode,
     client_pool: &Arc<ClientPool>,
     stop_send: &broadcast::Sender<bool>,
-) -> mpsc::Sender<NodeCallRequest> {
+    dead_letter: &Arc<DeadLetterQueue>,
+) -> NodeQueues {
     if let Some(entry) = node_channels.get(&node.node_id) {
         return entry.value().clone();
     }
 
-    let (sender, receiver) = mpsc::channel(NODE_CHANNEL_SIZE);
-    consumer::start_node_consumer_thread(
-        node.clone(),
-        client_pool.clone(),
-        receiver,
-        stop_send.clone(),
-    );
-    node_channels.insert(node.node_id, sender.clone());
-    info!("Auto-created channel for node {}", node.node_id);
-    sender
+    // The class dispatchers race to create the queues of a new node; the entry
+    // lock makes sure only one set of workers is started.
+    node_channels
+        .entry(node.node_id)
+        .or_insert_with(|| {
+            info!("Auto-created channel for node {}", node.node_id);
+            consumer::start_node_consumer_thread(
+                node.clone(),
+                client_pool.clone(),
+                stop_send.clone(),
+                dead_letter.clone(),
+            )
+        })
+        .clone()
 }
 
-fn remove_node_channel(
-    node_channels: &Arc<DashMap<u64, mpsc::Sender<NodeCallRequest>>>,
-    node_id: u64,
-) {
-    node_channels.remove(&node_id);
+fn remove_node_channel(node_channels: &Arc<DashMap<u64, NodeQueues>>, node_id: u64) {
+    if let Some((_, queues)) = node_channels.remove(&node_id) {
+        queues.depth().reset();
+    }
 }
@@ -30,21 +30,22 @@
 use tokio::sync::oneshot;
 use tracing::{debug, error, warn};
 
-async fn retry_rpc<F, Fut, R>(addr: &str, label: &str, mut rpc_fn: F)
+/// Returns `false` when every attempt failed.
+async fn retry_rpc<F, Fut, R>(addr: &str, label: &str, mut rpc_fn: F) -> bool
 where
     F: FnMut() -> Fut,
     Fut: Future<Output = Result<R, CommonError>>,
 {
     for attempt in 1..=RPC_MAX_RETRIES {
         match rpc_fn().await {
-            Ok(_) => return,
+            Ok(_) => return true,
             Err(e) => {
                 if attempt >= RPC_MAX_RETRIES {
                     error!(
                         "Failed to {} on broker {} after {} attempts: {}",
                         label, addr, attempt, e
                     );
-                    return;
+                    return false;
                 }
                 debug!(
                     "Failed to {} on broker {} (attempt {}/{}): {}, retrying",
@@ -55,29 +56,34 @@ async fn retry_rpc<F, Fut, R>(addr: &str, label: &str, mut rpc_fn: F)
             }
         }
     }
+    false
 }
 
-pub async fn send_update_cache_batch(
-    client_pool: &Arc<ClientPool>,
-    addr: &str,
-    data: &[UpdateCacheData],
-) {
+pub fn build_update_cache_request(data: &[UpdateCacheData]) -> UpdateCacheRequest {
     let records = data
         .iter()
         .map(|raw| UpdateCacheRecord {
             action_type: raw.action_type.into(),
             resource_type: raw.resource_type.into(),
             data: raw.data.clone(),
+            version: raw.version,
         })
         .collect();
+    UpdateCacheRequest { records }
+}
 
-    let request = UpdateCacheRequest { records };
+pub async fn send_update_cache_batch(
+    client_pool: &Arc<ClientPool>,
+    addr: &str,
+    data: &[UpdateCacheData],
+) -> bool {
+    let request = build_update_cache_request(data);
     let addrs = [addr];
 
     retry_rpc(addr, "update cache", || {
         broker_update_cache(client_pool, &addrs, request.clone())
     })
-    .await;
+    .await
 }
 
 pub async fn send_get_qos_data_batch(
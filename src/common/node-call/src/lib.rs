@@ -15,18 +15,27 @@
 use broker_core::cache::NodeCacheManager;
 use bytes::Bytes;
 use common_base::error::common::CommonError;
+use consumer::NodeQueues;
 use dashmap::DashMap;
+use dead_letter::DeadLetterQueue;
 use futures::future::join_all;
 use grpc_clients::pool::ClientPool;
 use metadata_struct::meta::node::BrokerNode;
 use protocol::broker::broker::{BrokerUpdateCacheActionType, BrokerUpdateCacheResourceType};
+use queue::PrioritySender;
+use rocksdb_engine::rocksdb::RocksDBEngine;
 use std::sync::Arc;
-use tokio::sync::{broadcast, mpsc, oneshot, RwLock};
+use tokio::sync::{broadcast, oneshot, RwLock};
 use tokio::time::{timeout, Duration};
+use tracing::warn;
+use version::CacheVersions;
 
 pub mod consumer;
+pub mod dead_letter;
 pub mod dispatcher;
 pub mod handler;
+pub mod queue;
+pub mod version;
 
 pub const GLOBAL_CHANNEL_SIZE: usize = 10000;
 pub const NODE_CHANNEL_SIZE: usize = 5000;
@@ -40,6 +49,8 @@ pub struct UpdateCacheData {
     pub action_type: BrokerUpdateCacheActionType,
     pub resource_type: BrokerUpdateCacheResourceType,
     pub data: Vec<u8>,
+    // Set by NodeCallManager when the notification is sent.
+    pub version: u64,
 }
 
 #[derive(Clone, Debug)]
@@ -57,7 +68,61 @@ pub struct NodeCallRequest {
     pub reply_txs: Vec<Option<oneshot::Sender<Bytes>>>,
 }
 
+/// Delivery class of a node call. Every class has its own bounded queues, so a
+/// flood of cache updates cannot hold back control calls or last-will messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NodeCallPriority {
+    Control,
+    LastWill,
+    CacheUpdate,
+}
+
+impl NodeCallPriority {
+    /// All classes, from the highest priority to the lowest.
+    pub const ALL: [NodeCallPriority; 3] = [
+        NodeCallPriority::Control,
+        NodeCallPriority::LastWill,
+        NodeCallPriority::CacheUpdate,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NodeCallPriority::Control => "control",
+            NodeCallPriority::LastWill => "last_will",
+            NodeCallPriority::CacheUpdate => "cache_update",
+        }
+    }
+
+    /// Requests a worker takes from this class in each draining round.
+    pub fn weight(&self) -> usize {
+        match self {
+            NodeCallPriority::Control => 8,
+            NodeCallPriority::LastWill => 4,
+            NodeCallPriority::CacheUpdate => 1,
+        }
+    }
+
+    /// Whether a request is dropped rather than waited on when the node queue
+    /// is full. Cache updates must keep their order and already fall back to the
+    /// dead-letter queue when the node is slow, so they wait instead.
+    pub fn drop_when_full(&self) -> bool {
+        !matches!(self, NodeCallPriority::CacheUpdate)
+    }
+
+    pub(crate) fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
 impl NodeCallData {
+    pub fn priority(&self) -> NodeCallPriority {
+        match self {
+            NodeCallData::UpdateCache(_) => NodeCallPriority::CacheUpdate,
+            NodeCallData::SendLastWillMessage { .. } => NodeCallPriority::LastWill,
+            NodeCallData::GetQosData(_) => NodeCallPriority::Control,
+        }
+    }
+
     pub fn partition_key(&self) -> Option<&str> {
         match self {
             NodeCallData::UpdateCache(_) => None,
@@ -68,23 +133,32 @@ pub fn partition_key(&self) -> Option<&str> {
 }
 
 pub struct NodeCallManager {
-    pub global_sender: RwLock<Option<mpsc::Sender<NodeCallRequest>>>,
+    pub global_sender: RwLock<Option<PrioritySender>>,
     broker_cache: Arc<NodeCacheManager>,
-    node_channels: Arc<DashMap<u64, mpsc::Sender<NodeCallRequest>>>,
+    node_channels: Arc<DashMap<u64, NodeQueues>>,
     client_pool: Arc<ClientPool>,
+    dead_letter: Arc<DeadLetterQueue>,
+    cache_versions: CacheVersions,
 }
 
 impl NodeCallManager {
-    pub fn new(client_pool: Arc<ClientPool>, broker_cache: Arc<NodeCacheManager>) -> Self {
+    pub fn new(
+        client_pool: Arc<ClientPool>,
+        broker_cache: Arc<NodeCacheManager>,
+        rocksdb_engine_handler: Arc<RocksDBEngine>,
+    ) -> Self {
         NodeCallManager {
             global_sender: RwLock::new(None),
             broker_cache,
             node_channels: Arc::new(DashMap::with_capacity(8)),
             client_pool,
+            dead_letter: Arc::new(DeadLetterQueue::new(rocksdb_engine_handler)),
+            cache_versions: CacheVersions::default(),
         }
     }
 
     pub async fn send_with_reply(&self, data: NodeCallData) -> Result<Vec<Bytes>, CommonError> {
+        let data = self.stamp_version(data);
         let nodes = self.broker_cache.node_list();
         let node_count = nodes.len();
 
@@ -127,7 +201,7 @@ pub async fn send_with_reply(&self, data: NodeCallData) -> Result<Vec<Bytes>, Co
 
     pub async fn send(&self, data: NodeCallData) -> Result<(), CommonError> {
         let request = NodeCallRequest {
-            data,
+            data: self.stamp_version(data),
             nodes: Vec::new(),
             reply_txs: Vec::new(),
         };
@@ -152,6 +226,21 @@ pub fn broker_cache(&self) -> &Arc<NodeCacheManager> {
         &self.broker_cache
     }
 
+    pub fn dead_letter(&self) -> &Arc<DeadLetterQueue> {
+        &self.dead_letter
+    }
+
+    pub fn cache_versions(&self) -> &CacheVersions {
+        &self.cache_versions
+    }
+
+    fn stamp_version(&self, mut data: NodeCallData) -> NodeCallData {
+        if let NodeCallData::UpdateCache(update) = &mut data {
+            update.version = self.cache_versions.next(update.resource_type);
+        }
+        data
+    }
+
     /// Returns true once `start()` has initialised the global sender channel.
     /// Use this to wait for readiness before calling `send()`.
     pub async fn is_ready(&self) -> bool {
@@ -159,18 +248,30 @@ pub async fn is_ready(&self) -> bool {
     }
 
     pub async fn start(&self, stop_send: broadcast::Sender<bool>) {
-        let (global_sender, global_receiver) = mpsc::channel(GLOBAL_CHANNEL_SIZE);
+        let (global_sender, global_receiver) = queue::priority_channel(GLOBAL_CHANNEL_SIZE);
         {
             let mut write = self.global_sender.write().await;
             *write = Some(global_sender);
             // write guard dropped here, before the dispatcher loop starts
         }
+
+        if let Err(e) = self.dead_letter.recover() {
+            warn!("Failed to recover node call dead letters: {}", e);
+        }
+        dead_letter::start_replay_thread(
+            self.dead_letter.clone(),
+            self.broker_cache.clone(),
+            self.client_pool.clone(),
+            stop_send.clone(),
+        );
+
         dispatcher::run(
             global_receiver,
             stop_send,
             self.node_channels.clone(),
             self.broker_cache.clone(),
             self.client_pool.clone(),
+            self.dead_letter.clone(),
         )
         .await;
     }
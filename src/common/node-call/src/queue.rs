@@ -0,0 +1,276 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{NodeCallPriority, NodeCallRequest};
+use common_metrics::node_call::set_node_call_queue_depth;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::{SendError, TrySendError};
+
+/// Sending half of a set of bounded queues, one per priority class.
+#[derive(Clone)]
+pub struct PrioritySender {
+    senders: Vec<mpsc::Sender<NodeCallRequest>>,
+}
+
+/// Receiving half of a set of bounded queues, one per priority class.
+pub struct PriorityReceiver {
+    receivers: Vec<mpsc::Receiver<NodeCallRequest>>,
+}
+
+/// Create one bounded queue of `capacity` requests for every priority class.
+pub fn priority_channel(capacity: usize) -> (PrioritySender, PriorityReceiver) {
+    let (senders, receivers) = NodeCallPriority::ALL
+        .iter()
+        .map(|_| mpsc::channel(capacity))
+        .unzip();
+    (PrioritySender { senders }, PriorityReceiver { receivers })
+}
+
+impl PrioritySender {
+    pub async fn send(&self, request: NodeCallRequest) -> Result<(), SendError<NodeCallRequest>> {
+        self.sender(&request).send(request).await
+    }
+
+    pub fn try_send(&self, request: NodeCallRequest) -> Result<(), TrySendError<NodeCallRequest>> {
+        self.sender(&request).try_send(request)
+    }
+
+    fn sender(&self, request: &NodeCallRequest) -> &mpsc::Sender<NodeCallRequest> {
+        &self.senders[request.data.priority().index()]
+    }
+}
+
+impl PriorityReceiver {
+    /// Wait for the next request, preferring higher classes when several are
+    /// ready. Returns None once every queue is closed and empty.
+    pub async fn recv(&mut self) -> Option<NodeCallRequest> {
+        let [control, last_will, cache_update] = self.receivers.as_mut_slice() else {
+            unreachable!("one receiver per priority class");
+        };
+        tokio::select! {
+            biased;
+            Some(request) = control.recv() => Some(request),
+            Some(request) = last_will.recv() => Some(request),
+            Some(request) = cache_update.recv() => Some(request),
+            else => None,
+        }
+    }
+
+    /// Move already queued requests into `batch` until it holds `max` of them.
+    /// Classes are drained round-robin by weight, so lower classes still make
+    /// progress while higher ones stay busy.
+    pub fn drain_weighted(&mut self, batch: &mut Vec<NodeCallRequest>, max: usize) {
+        while batch.len() < max {
+            let before = batch.len();
+            for priority in NodeCallPriority::ALL {
+                let receiver = &mut self.receivers[priority.index()];
+                for _ in 0..priority.weight() {
+                    if batch.len() >= max {
+                        return;
+                    }
+                    match receiver.try_recv() {
+                        Ok(request) => batch.push(request),
+                        Err(_) => break,
+                    }
+                }
+            }
+            if batch.len() == before {
+                return;
+            }
+        }
+    }
+
+    /// Split into the queue of every class, highest priority first.
+    pub fn into_receivers(self) -> Vec<(NodeCallPriority, mpsc::Receiver<NodeCallRequest>)> {
+        NodeCallPriority::ALL
+            .into_iter()
+            .zip(self.receivers)
+            .collect()
+    }
+}
+
+/// Requests queued for one node per priority class, mirrored into the
+/// node_call_queue_depth metric.
+pub struct QueueDepth {
+    node_id: u64,
+    counts: [AtomicU64; 3],
+}
+
+impl QueueDepth {
+    pub fn new(node_id: u64) -> Self {
+        QueueDepth {
+            node_id,
+            counts: Default::default(),
+        }
+    }
+
+    pub fn get(&self, priority: NodeCallPriority) -> u64 {
+        self.counts[priority.index()].load(Ordering::Relaxed)
+    }
+
+    pub fn add(&self, priority: NodeCallPriority) {
+        self.counts[priority.index()].fetch_add(1, Ordering::Relaxed);
+        self.publish(priority);
+    }
+
+    pub fn remove(&self, priority: NodeCallPriority, num: u64) {
+        let _ = self.counts[priority.index()].fetch_update(
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+            |count| Some(count.saturating_sub(num)),
+        );
+        self.publish(priority);
+    }
+
+    /// Account for a batch a worker has taken off its queues.
+    pub fn remove_batch(&self, batch: &[NodeCallRequest]) {
+        for priority in NodeCallPriority::ALL {
+            let num = batch
+                .iter()
+                .filter(|request| request.data.priority() == priority)
+                .count() as u64;
+            if num > 0 {
+                self.remove(priority, num);
+            }
+        }
+    }
+
+    pub fn reset(&self) {
+        for priority in NodeCallPriority::ALL {
+            self.counts[priority.index()].store(0, Ordering::Relaxed);
+            self.publish(priority);
+        }
+    }
+
+    fn publish(&self, priority: NodeCallPriority) {
+        set_node_call_queue_depth(self.node_id, priority.as_str(), self.get(priority));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NodeCallData, UpdateCacheData};
+    use protocol::broker::broker::{BrokerUpdateCacheActionType, BrokerUpdateCacheResourceType};
+
+    fn request(data: NodeCallData) -> NodeCallRequest {
+        NodeCallRequest {
+            data,
+            nodes: Vec::new(),
+            reply_txs: Vec::new(),
+        }
+    }
+
+    fn cache_update(version: u64) -> NodeCallRequest {
+        request(NodeCallData::UpdateCache(UpdateCacheData {
+            action_type: BrokerUpdateCacheActionType::Create,
+            resource_type: BrokerUpdateCacheResourceType::Subscribe,
+            data: Vec::new(),
+            version,
+        }))
+    }
+
+    fn last_will(client_id: &str) -> NodeCallRequest {
+        request(NodeCallData::SendLastWillMessage {
+            tenant: "default".to_string(),
+            client_id: client_id.to_string(),
+        })
+    }
+
+    fn priorities(batch: &[NodeCallRequest]) -> Vec<NodeCallPriority> {
+        batch.iter().map(|r| r.data.priority()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_recv_prefers_higher_priority() {
+        let (sender, mut receiver) = priority_channel(16);
+        sender.send(cache_update(1)).await.unwrap();
+        sender.send(last_will("c1")).await.unwrap();
+        sender
+            .send(request(NodeCallData::GetQosData("c1".to_string())))
+            .await
+            .unwrap();
+
+        let order: Vec<_> = [
+            receiver.recv().await.unwrap(),
+            receiver.recv().await.unwrap(),
+            receiver.recv().await.unwrap(),
+        ]
+        .iter()
+        .map(|r| r.data.priority())
+        .collect();
+        assert_eq!(order, NodeCallPriority::ALL.to_vec());
+
+        drop(sender);
+        assert!(receiver.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_drain_weighted_keeps_lower_classes_moving() {
+        let (sender, mut receiver) = priority_channel(64);
+        for i in 0..10 {
+            sender.send(cache_update(i)).await.unwrap();
+            sender.send(last_will(&format!("c{}", i))).await.unwrap();
+        }
+
+        let mut batch = Vec::new();
+        receiver.drain_weighted(&mut batch, 6);
+        let mut expected = vec![NodeCallPriority::LastWill; 4];
+        expected.push(NodeCallPriority::CacheUpdate);
+        expected.push(NodeCallPriority::LastWill);
+        assert_eq!(priorities(&batch), expected);
+
+        batch.clear();
+        receiver.drain_weighted(&mut batch, 100);
+        assert_eq!(batch.len(), 14);
+        let versions: Vec<u64> = batch
+            .iter()
+            .filter_map(|r| match &r.data {
+                NodeCallData::UpdateCache(data) => Some(data.version),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(versions, (1..10).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_try_send_reports_full_queue_per_class() {
+        let (sender, _receiver) = priority_channel(1);
+        sender.try_send(last_will("c1")).unwrap();
+        assert!(matches!(
+            sender.try_send(last_will("c2")),
+            Err(TrySendError::Full(_))
+        ));
+        sender.try_send(cache_update(1)).unwrap();
+    }
+
+    #[test]
+    fn test_queue_depth() {
+        let depth = QueueDepth::new(9001);
+        depth.add(NodeCallPriority::LastWill);
+        depth.add(NodeCallPriority::LastWill);
+        depth.add(NodeCallPriority::CacheUpdate);
+        assert_eq!(depth.get(NodeCallPriority::LastWill), 2);
+
+        depth.remove_batch(&[last_will("c1"), cache_update(1)]);
+        assert_eq!(depth.get(NodeCallPriority::LastWill), 1);
+        assert_eq!(depth.get(NodeCallPriority::CacheUpdate), 0);
+        depth.remove(NodeCallPriority::CacheUpdate, 1);
+        assert_eq!(depth.get(NodeCallPriority::CacheUpdate), 0);
+
+        depth.reset();
+        assert_eq!(depth.get(NodeCallPriority::LastWill), 0);
+    }
+}
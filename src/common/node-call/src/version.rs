@@ -0,0 +1,78 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Version numbers stamped on cache notifications, one sequence per resource
+//! type. Brokers report the highest version they applied on every heartbeat,
+//! which lets Meta Service see a broker whose cache fell behind.
+//!
+//! A version is `max(previous + 1, now in ms)`, so the sequence keeps growing
+//! when a restart or a leader change moves notifications to another node.
+
+use common_base::tools::now_millis;
+use dashmap::DashMap;
+use protocol::broker::broker::BrokerUpdateCacheResourceType;
+
+#[derive(Default)]
+pub struct CacheVersions {
+    latest: DashMap<BrokerUpdateCacheResourceType, u64>,
+}
+
+impl CacheVersions {
+    pub fn next(&self, resource_type: BrokerUpdateCacheResourceType) -> u64 {
+        let now = now_millis() as u64;
+        let mut entry = self.latest.entry(resource_type).or_insert(0);
+        *entry = (*entry + 1).max(now);
+        *entry
+    }
+
+    /// Latest version issued for `resource_type`, if any was issued by this node.
+    pub fn latest(&self, resource_type: BrokerUpdateCacheResourceType) -> Option<u64> {
+        self.latest.get(&resource_type).map(|v| *v)
+    }
+
+    pub fn snapshot(&self) -> Vec<(BrokerUpdateCacheResourceType, u64)> {
+        self.latest
+            .iter()
+            .map(|entry| (*entry.key(), *entry.value()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_grow_per_resource_type() {
+        let versions = CacheVersions::default();
+        assert_eq!(versions.latest(BrokerUpdateCacheResourceType::Topic), None);
+
+        let v1 = versions.next(BrokerUpdateCacheResourceType::Topic);
+        let v2 = versions.next(BrokerUpdateCacheResourceType::Topic);
+        assert!(v2 > v1);
+        assert!(v1 >= 1);
+        assert_eq!(
+            versions.latest(BrokerUpdateCacheResourceType::Topic),
+            Some(v2)
+        );
+
+        // Sequences are independent per resource type.
+        versions.next(BrokerUpdateCacheResourceType::User);
+        assert_eq!(
+            versions.latest(BrokerUpdateCacheResourceType::Topic),
+            Some(v2)
+        );
+        assert_eq!(versions.snapshot().len(), 2);
+    }
+}
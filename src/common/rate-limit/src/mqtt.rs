@@ -26,11 +26,12 @@ pub struct MQTTRateLimiterManager {
     pub node_cache: Arc<NodeCacheManager>,
     // publish
     node_publish_message_rate: ArcLockRateLimiter,
-    tenant_publish_message_rate: DashMap<String, ArcRateLimiter>,
+    // (tenant, (configured rate, limiter))
+    tenant_publish_message_rate: DashMap<String, (u32, ArcRateLimiter)>,
 
     // create connection
     node_create_connection_rate: ArcLockRateLimiter,
-    tenant_create_connection_rate: DashMap<String, ArcRateLimiter>,
+    tenant_create_connection_rate: DashMap<String, (u32, ArcRateLimiter)>,
 }
 
 impl MQTTRateLimiterManager {
@@ -76,9 +77,9 @@ pub fn set_tenant_publish_message_rate(
         })?;
         let limit = Arc::new(RateLimiter::direct(Quota::per_second(non_zero)));
         self.tenant_publish_message_rate
-            .insert(tenant.to_string(), limit.clone());
+            .insert(tenant.to_string(), (rate, limit.clone()));
 
-        Ok(limit.clone())
+        Ok(limit)
     }
 
     pub fn set_tenant_create_connection_rate(
@@ -92,8 +93,8 @@ pub fn set_tenant_create_connection_rate(
             ))
         })?;
         let limit = Arc::new(RateLimiter::direct(Quota::per_second(non_zero)));
-        self.tenant_publish_message_rate
-            .insert(tenant.to_string(), limit.clone());
+        self.tenant_create_connection_rate
+            .insert(tenant.to_string(), (rate, limit.clone()));
 
         Ok(limit)
     }
@@ -120,17 +121,15 @@ pub async fn connection_rate_limit(&self, tenant: &str) -> ResultCommonError {
         let limit = self.node_create_connection_rate.read().await;
         limit.until_ready().await;
 
-        // tenant — clone Arc to release DashMap shard lock before .await
-        if let Some(tenant_limit) = self
-            .tenant_create_connection_rate
-            .get(tenant)
-            .map(|r| r.clone())
-        {
-            tenant_limit.until_ready().await;
-        } else if let Some(ten) = self.node_cache.get_tenant(tenant) {
-            let limit = self
-                .set_tenant_create_connection_rate(tenant, ten.config.max_publish_rate)
-                .map_err(|e| *e)?;
+        // tenant — the limiter is rebuilt when the tenant's configured rate changes
+        if let Some(ten) = self.node_cache.get_tenant(tenant) {
+            let rate = ten.config.max_create_connection_rate_per_second;
+            let limit = match cached_limiter(&self.tenant_create_connection_rate, tenant, rate) {
+                Some(limit) => limit,
+                None => self
+                    .set_tenant_create_connection_rate(tenant, rate)
+                    .map_err(|e| *e)?,
+            };
             limit.until_ready().await;
         }
 
@@ -142,20 +141,30 @@ pub async fn publish_message_rate_limit(&self, tenant: &str) -> ResultCommonErro
         let limit = self.node_publish_message_rate.read().await;
         limit.until_ready().await;
 
-        // tenant — clone Arc to release DashMap shard lock before .await
-        if let Some(tenant_limit) = self
-            .tenant_publish_message_rate
-            .get(tenant)
-            .map(|r| r.clone())
-        {
-            tenant_limit.until_ready().await;
-        } else if let Some(ten) = self.node_cache.get_tenant(tenant) {
-            let limit = self
-                .set_tenant_publish_message_rate(tenant, ten.config.max_publish_rate)
-                .map_err(|e| *e)?;
+        // tenant — the limiter is rebuilt when the tenant's configured rate changes
+        if let Some(ten) = self.node_cache.get_tenant(tenant) {
+            let rate = ten.config.max_publish_rate;
+            let limit = match cached_limiter(&self.tenant_publish_message_rate, tenant, rate) {
+                Some(limit) => limit,
+                None => self
+                    .set_tenant_publish_message_rate(tenant, rate)
+                    .map_err(|e| *e)?,
+            };
             limit.until_ready().await;
         }
 
         Ok(())
     }
 }
+
+// Clone the Arc so the DashMap shard lock is released before `.await`.
+fn cached_limiter(
+    limiters: &DashMap<String, (u32, ArcRateLimiter)>,
+    tenant: &str,
+    rate: u32,
+) -> Option<ArcRateLimiter> {
+    limiters
+        .get(tenant)
+        .filter(|entry| entry.0 == rate)
+        .map(|entry| entry.1.clone())
+}
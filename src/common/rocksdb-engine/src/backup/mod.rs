@@ -0,0 +1,331 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Backups of the broker-local RocksDB, taken with the RocksDB backup engine.
+//!
+//! Backups are consistent point-in-time copies: memtables are flushed first
+//! and the live SST files are copied while file deletions are disabled.
+//! Files shared with an earlier backup are not copied again, so a backup only
+//! costs the data written since the previous one. A backup can only be
+//! restored while the DB is closed, so restores run on start, before the
+//! engine is opened.
+
+use crate::rocksdb::RocksDBEngine;
+use crate::storage::family::rocksdb_data_fold;
+use common_base::error::common::CommonError;
+use common_base::tools::now_second;
+use common_config::config::BrokerConfig;
+use rocksdb::backup::{BackupEngine, BackupEngineInfo, BackupEngineOptions, RestoreOptions};
+use rocksdb::Env;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::info;
+
+pub mod remote;
+pub mod schedule;
+
+/// File in the backup directory holding the backup to restore on the next
+/// start, `latest` or a backup id.
+pub const RESTORE_MARKER: &str = "RESTORE";
+
+const RESTORE_LATEST: &str = "latest";
+
+// A backup directory must not be used by two backup engines at once.
+static BACKUP_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BackupInfo {
+    pub backup_id: u32,
+    pub timestamp: i64,
+    pub size: u64,
+    pub num_files: u32,
+}
+
+impl From<&BackupEngineInfo> for BackupInfo {
+    fn from(info: &BackupEngineInfo) -> Self {
+        BackupInfo {
+            backup_id: info.backup_id,
+            timestamp: info.timestamp,
+            size: info.size,
+            num_files: info.num_files,
+        }
+    }
+}
+
+pub fn rocksdb_backup_dir(config: &BrokerConfig) -> String {
+    if config.rocksdb_backup.backup_dir.is_empty() {
+        format!("{}/_rocksdb_backup", config.data_path)
+    } else {
+        config.rocksdb_backup.backup_dir.clone()
+    }
+}
+
+fn open_backup_engine(backup_dir: &str) -> Result<BackupEngine, CommonError> {
+    std::fs::create_dir_all(backup_dir)?;
+    let opts = BackupEngineOptions::new(backup_dir)?;
+    let env = Env::new()?;
+    Ok(BackupEngine::open(&opts, &env)?)
+}
+
+fn sorted_backups(backup_engine: &BackupEngine) -> Vec<BackupInfo> {
+    let mut backups: Vec<BackupInfo> = backup_engine
+        .get_backup_info()
+        .iter()
+        .map(BackupInfo::from)
+        .collect();
+    backups.sort_by_key(|b| b.backup_id);
+    backups
+}
+
+/// Take a new backup of `engine` and purge all but the newest `max_backups`
+/// (0 keeps all of them).
+pub fn create_backup(
+    engine: &RocksDBEngine,
+    backup_dir: &str,
+    max_backups: usize,
+) -> Result<BackupInfo, CommonError> {
+    let _guard = BACKUP_LOCK.lock().unwrap();
+    let mut backup_engine = open_backup_engine(backup_dir)?;
+    backup_engine.create_new_backup_flush(engine.db.as_ref(), true)?;
+    if max_backups > 0 {
+        backup_engine.purge_old_backups(max_backups)?;
+    }
+    sorted_backups(&backup_engine).pop().ok_or_else(|| {
+        CommonError::CommonError(format!(
+            "No backup found in {backup_dir} after creating one"
+        ))
+    })
+}
+
+pub fn list_backups(backup_dir: &str) -> Result<Vec<BackupInfo>, CommonError> {
+    if !Path::new(backup_dir).exists() {
+        return Ok(Vec::new());
+    }
+    let _guard = BACKUP_LOCK.lock().unwrap();
+    let backup_engine = open_backup_engine(backup_dir)?;
+    Ok(sorted_backups(&backup_engine))
+}
+
+/// Restore a backup, the latest when `backup_id` is `None`, into `db_path`.
+/// The DB at `db_path` must not be open.
+pub fn restore_backup(
+    backup_dir: &str,
+    db_path: &str,
+    backup_id: Option<u32>,
+) -> Result<u32, CommonError> {
+    let _guard = BACKUP_LOCK.lock().unwrap();
+    let mut backup_engine = open_backup_engine(backup_dir)?;
+    let backups = sorted_backups(&backup_engine);
+    let backup_id = match backup_id {
+        Some(id) if backups.iter().any(|b| b.backup_id == id) => id,
+        Some(id) => {
+            return Err(CommonError::CommonError(format!(
+                "RocksDB backup {id} not found in {backup_dir}"
+            )))
+        }
+        None => match backups.last() {
+            Some(b) => b.backup_id,
+            None => {
+                return Err(CommonError::CommonError(format!(
+                    "No RocksDB backup found in {backup_dir}"
+                )))
+            }
+        },
+    };
+    backup_engine.verify_backup(backup_id)?;
+    backup_engine.restore_from_backup(db_path, db_path, &RestoreOptions::default(), backup_id)?;
+    Ok(backup_id)
+}
+
+/// Ask for a backup, the latest when `backup_id` is `None`, to be restored
+/// on the next start.
+pub fn request_restore(backup_dir: &str, backup_id: Option<u32>) -> Result<(), CommonError> {
+    let backups = list_backups(backup_dir)?;
+    if backups.is_empty() {
+        return Err(CommonError::CommonError(format!(
+            "No RocksDB backup found in {backup_dir}"
+        )));
+    }
+    let content = match backup_id {
+        Some(id) if backups.iter().any(|b| b.backup_id == id) => id.to_string(),
+        Some(id) => {
+            return Err(CommonError::CommonError(format!(
+                "RocksDB backup {id} not found in {backup_dir}"
+            )))
+        }
+        None => RESTORE_LATEST.to_string(),
+    };
+    std::fs::write(Path::new(backup_dir).join(RESTORE_MARKER), content)?;
+    Ok(())
+}
+
+fn requested_restore(backup_dir: &str) -> Result<Option<Option<u32>>, CommonError> {
+    let content = match std::fs::read_to_string(Path::new(backup_dir).join(RESTORE_MARKER)) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let content = content.trim();
+    if content.is_empty() || content == RESTORE_LATEST {
+        return Ok(Some(None));
+    }
+    content
+        .parse::<u32>()
+        .map(|id| Some(Some(id)))
+        .map_err(|_| {
+            CommonError::CommonError(format!(
+                "Invalid content '{content}' in {backup_dir}/{RESTORE_MARKER}"
+            ))
+        })
+}
+
+/// Restore before the engine is opened, when a restore was requested through
+/// the admin API or when `restore_on_start` is set and there is no local DB.
+/// A DB that is replaced is kept next to it as `<db>.before-restore-<time>`.
+pub fn restore_on_start(config: &BrokerConfig) -> Result<Option<u32>, CommonError> {
+    let backup_dir = rocksdb_backup_dir(config);
+    let db_path = rocksdb_data_fold(&config.data_path);
+    let db_exists = Path::new(&db_path).join("CURRENT").exists();
+
+    let backup_id = match requested_restore(&backup_dir)? {
+        Some(backup_id) => backup_id,
+        None if config.rocksdb_backup.restore_on_start && !db_exists => None,
+        None => return Ok(None),
+    };
+
+    if let Some(s3) = &config.rocksdb_backup.s3 {
+        if list_backups(&backup_dir)?.is_empty() {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?;
+            let downloaded = runtime.block_on(remote::download_backup_dir(
+                s3,
+                config.broker_id,
+                &backup_dir,
+            ))?;
+            info!(
+                "Downloaded {downloaded} RocksDB backup files from S3 bucket {}",
+                s3.bucket
+            );
+        }
+    }
+
+    if list_backups(&backup_dir)?.is_empty() {
+        if !db_exists {
+            info!("No RocksDB backup found in {backup_dir}, starting with an empty DB");
+            let _ = std::fs::remove_file(Path::new(&backup_dir).join(RESTORE_MARKER));
+            return Ok(None);
+        }
+        return Err(CommonError::CommonError(format!(
+            "A RocksDB restore was requested but no backup was found in {backup_dir}"
+        )));
+    }
+
+    if Path::new(&db_path).exists() {
+        let trimmed = db_path.trim_end_matches('/');
+        let moved = format!("{trimmed}.before-restore-{}", now_second());
+        std::fs::rename(trimmed, &moved)?;
+        info!("Moved the current RocksDB {db_path} to {moved} before restoring");
+    }
+
+    let restored = restore_backup(&backup_dir, &db_path, backup_id)?;
+    let _ = std::fs::remove_file(Path::new(&backup_dir).join(RESTORE_MARKER));
+    info!("Restored RocksDB backup {restored} from {backup_dir} into {db_path}");
+    Ok(Some(restored))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::broker::{engine_get_by_broker, engine_save_by_broker};
+    use crate::storage::family::column_family_list;
+    use common_base::utils::file_utils::test_temp_dir;
+
+    #[test]
+    fn backup_and_restore_test() {
+        let base = test_temp_dir();
+        let db_path = format!("{base}db");
+        let backup_dir = format!("{base}backup");
+
+        {
+            let engine = RocksDBEngine::new(&db_path, 1000, column_family_list());
+            let engine = std::sync::Arc::new(engine);
+            engine_save_by_broker(&engine, "/broker/k1", "v1".to_string()).unwrap();
+            let first = create_backup(&engine, &backup_dir, 2).unwrap();
+            engine_save_by_broker(&engine, "/broker/k2", "v2".to_string()).unwrap();
+            create_backup(&engine, &backup_dir, 2).unwrap();
+            create_backup(&engine, &backup_dir, 2).unwrap();
+
+            let backups = list_backups(&backup_dir).unwrap();
+            assert_eq!(backups.len(), 2);
+            assert!(backups.iter().all(|b| b.backup_id > first.backup_id));
+        }
+
+        let restore_path = format!("{base}restore");
+        restore_backup(&backup_dir, &restore_path, None).unwrap();
+        let engine = std::sync::Arc::new(RocksDBEngine::new(
+            &restore_path,
+            1000,
+            column_family_list(),
+        ));
+        let value = engine_get_by_broker::<String>(&engine, "/broker/k2")
+            .unwrap()
+            .unwrap();
+        assert_eq!(value.data, "v2");
+
+        assert!(restore_backup(&backup_dir, &restore_path, Some(1)).is_err());
+        let _ = std::fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn restore_on_start_test() {
+        let mut config = BrokerConfig::default();
+        config.data_path = test_temp_dir();
+        let backup_dir = rocksdb_backup_dir(&config);
+        assert!(request_restore(&backup_dir, None).is_err());
+
+        {
+            let engine = std::sync::Arc::new(RocksDBEngine::new(
+                &rocksdb_data_fold(&config.data_path),
+                1000,
+                column_family_list(),
+            ));
+            engine_save_by_broker(&engine, "/broker/k1", "v1".to_string()).unwrap();
+            create_backup(&engine, &backup_dir, 0).unwrap();
+            engine_save_by_broker(&engine, "/broker/k2", "v2".to_string()).unwrap();
+        }
+
+        // an existing DB is only replaced when a restore was requested
+        config.rocksdb_backup.restore_on_start = true;
+        assert_eq!(restore_on_start(&config).unwrap(), None);
+
+        request_restore(&backup_dir, None).unwrap();
+        assert!(restore_on_start(&config).unwrap().is_some());
+        assert!(requested_restore(&backup_dir).unwrap().is_none());
+
+        let engine = std::sync::Arc::new(RocksDBEngine::new(
+            &rocksdb_data_fold(&config.data_path),
+            1000,
+            column_family_list(),
+        ));
+        assert!(engine_get_by_broker::<String>(&engine, "/broker/k1")
+            .unwrap()
+            .is_some());
+        assert!(engine_get_by_broker::<String>(&engine, "/broker/k2")
+            .unwrap()
+            .is_none());
+        let _ = std::fs::remove_dir_all(&config.data_path);
+    }
+}
@@ -0,0 +1,186 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Mirror of the local backup directory in S3. Backup files never change
+//! once written, so a file is uploaded when the object is missing or has a
+//! different size, and objects of purged backups are deleted.
+
+use super::RESTORE_MARKER;
+use common_base::error::common::CommonError;
+use common_config::config::RocksDBBackupS3;
+use opendal::{services::S3, Operator};
+use std::collections::HashMap;
+use std::path::Path;
+
+#[allow(clippy::result_large_err)]
+fn build_operator(config: &RocksDBBackupS3) -> Result<Operator, CommonError> {
+    let mut builder = S3::default().bucket(&config.bucket).region(&config.region);
+
+    if !config.endpoint.is_empty() {
+        builder = builder.endpoint(&config.endpoint);
+    }
+
+    if !config.access_key_id.is_empty() {
+        builder = builder.access_key_id(&config.access_key_id);
+        builder = builder.secret_access_key(&config.secret_access_key);
+    }
+
+    if !config.session_token.is_empty() {
+        builder = builder.session_token(&config.session_token);
+    }
+
+    if !config.root.is_empty() {
+        builder = builder.root(&config.root);
+    }
+
+    Ok(Operator::new(builder)?.finish())
+}
+
+fn remote_prefix(config: &RocksDBBackupS3, broker_id: u64) -> String {
+    let prefix = config.prefix.trim_matches('/');
+    if prefix.is_empty() {
+        format!("rocksdb-backup/{broker_id}/")
+    } else {
+        format!("{prefix}/")
+    }
+}
+
+/// Files of the backup directory by path relative to it, with their size.
+/// Files the backup engine is still writing end in `.tmp` and are skipped.
+fn local_files(backup_dir: &Path) -> Result<HashMap<String, u64>, CommonError> {
+    let mut files = HashMap::new();
+    let mut dirs = vec![backup_dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.ends_with(".tmp") || name == RESTORE_MARKER {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(backup_dir) else {
+                continue;
+            };
+            let relative = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.insert(relative, metadata.len());
+        }
+    }
+    Ok(files)
+}
+
+async fn remote_files(
+    operator: &Operator,
+    prefix: &str,
+) -> Result<HashMap<String, u64>, CommonError> {
+    let mut files = HashMap::new();
+    for entry in operator.list_with(prefix).recursive(true).await? {
+        if entry.metadata().is_dir() {
+            continue;
+        }
+        if let Some(relative) = entry.path().strip_prefix(prefix) {
+            files.insert(relative.to_string(), entry.metadata().content_length());
+        }
+    }
+    Ok(files)
+}
+
+/// Upload the backup directory, returning the number of files uploaded.
+pub async fn upload_backup_dir(
+    config: &RocksDBBackupS3,
+    broker_id: u64,
+    backup_dir: &str,
+) -> Result<u64, CommonError> {
+    let operator = build_operator(config)?;
+    let prefix = remote_prefix(config, broker_id);
+    let local = local_files(Path::new(backup_dir))?;
+    let remote = remote_files(&operator, &prefix).await?;
+
+    let mut uploaded = 0;
+    for (relative, size) in &local {
+        if remote.get(relative) == Some(size) {
+            continue;
+        }
+        let data = tokio::fs::read(Path::new(backup_dir).join(relative)).await?;
+        operator.write(&format!("{prefix}{relative}"), data).await?;
+        uploaded += 1;
+    }
+
+    for relative in remote.keys() {
+        if !local.contains_key(relative) {
+            operator.delete(&format!("{prefix}{relative}")).await?;
+        }
+    }
+    Ok(uploaded)
+}
+
+/// Download the mirrored backups into the backup directory, returning the
+/// number of files downloaded.
+pub async fn download_backup_dir(
+    config: &RocksDBBackupS3,
+    broker_id: u64,
+    backup_dir: &str,
+) -> Result<u64, CommonError> {
+    let operator = build_operator(config)?;
+    let prefix = remote_prefix(config, broker_id);
+
+    let mut downloaded = 0;
+    for relative in remote_files(&operator, &prefix).await?.keys() {
+        let path = Path::new(backup_dir).join(relative);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let data = operator.read(&format!("{prefix}{relative}")).await?;
+        tokio::fs::write(&path, data.to_vec()).await?;
+        downloaded += 1;
+    }
+    Ok(downloaded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common_base::utils::file_utils::test_temp_dir;
+
+    #[test]
+    fn local_files_test() {
+        let dir = test_temp_dir();
+        std::fs::create_dir_all(format!("{dir}meta")).unwrap();
+        std::fs::create_dir_all(format!("{dir}private/2.tmp")).unwrap();
+        std::fs::write(format!("{dir}meta/1"), b"meta").unwrap();
+        std::fs::write(format!("{dir}private/2.tmp/000010.sst"), b"sst").unwrap();
+        std::fs::write(format!("{dir}{RESTORE_MARKER}"), b"latest").unwrap();
+
+        let files = local_files(Path::new(&dir)).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files.get("meta/1"), Some(&4));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn remote_prefix_test() {
+        let mut config = RocksDBBackupS3::default();
+        assert_eq!(remote_prefix(&config, 3), "rocksdb-backup/3/");
+        config.prefix = "/backup/node-3/".to_string();
+        assert_eq!(remote_prefix(&config, 3), "backup/node-3/");
+    }
+}
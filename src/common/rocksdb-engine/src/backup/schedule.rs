@@ -0,0 +1,68 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{create_backup, remote::upload_backup_dir, rocksdb_backup_dir, BackupInfo};
+use crate::rocksdb::RocksDBEngine;
+use common_base::error::{common::CommonError, ResultCommonError};
+use common_base::tools::loop_select_ticket;
+use common_config::{broker::broker_config, config::BrokerConfig};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tracing::{error, info};
+
+pub async fn start_rocksdb_backup(
+    rocksdb_engine: Arc<RocksDBEngine>,
+    stop_send: broadcast::Sender<bool>,
+) {
+    let config = broker_config();
+    if !config.rocksdb_backup.enable || config.rocksdb_backup.interval_sec == 0 {
+        return;
+    }
+
+    let ac_fn = async || -> ResultCommonError {
+        if let Err(e) = backup_rocksdb(&rocksdb_engine, config).await {
+            error!("Failed to back up the broker RocksDB: {}", e);
+        }
+        Ok(())
+    };
+    loop_select_ticket(ac_fn, config.rocksdb_backup.interval_sec * 1000, &stop_send).await;
+}
+
+/// Take a backup and, when configured, mirror the backup directory to S3.
+pub async fn backup_rocksdb(
+    rocksdb_engine: &Arc<RocksDBEngine>,
+    config: &BrokerConfig,
+) -> Result<BackupInfo, CommonError> {
+    let backup_dir = rocksdb_backup_dir(config);
+    let max_backups = config.rocksdb_backup.max_backups;
+    let engine = rocksdb_engine.clone();
+    let dir = backup_dir.clone();
+    let backup = tokio::task::spawn_blocking(move || create_backup(&engine, &dir, max_backups))
+        .await
+        .map_err(|e| CommonError::CommonError(e.to_string()))??;
+
+    if let Some(s3) = &config.rocksdb_backup.s3 {
+        let uploaded = upload_backup_dir(s3, config.broker_id, &backup_dir).await?;
+        info!(
+            "Uploaded {} RocksDB backup files to S3 bucket {}",
+            uploaded, s3.bucket
+        );
+    }
+
+    info!(
+        "Created RocksDB backup {} in {}, {} bytes",
+        backup.backup_id, backup_dir, backup.size
+    );
+    Ok(backup)
+}
@@ -62,3 +62,33 @@ pub fn slow_sub_log_prefix_key() -> String {
 pub fn slow_sub_log_prefix_key_by_tenant(tenant: &str) -> String {
     format!("{}slow_sub_log/{}/", PREFIX_BROKER, tenant)
 }
+
+// Node-call dead letters: cache notifications that could not be delivered to a
+// broker node. The sequence is zero-padded so a prefix scan returns them in order.
+pub fn node_call_dead_letter_key(node_id: u64, seq: u64) -> String {
+    format!(
+        "{}node_call_dead_letter/{}/{:020}",
+        PREFIX_BROKER, node_id, seq
+    )
+}
+
+pub fn node_call_dead_letter_prefix_key() -> String {
+    format!("{}node_call_dead_letter/", PREFIX_BROKER)
+}
+
+pub fn node_call_dead_letter_prefix_key_by_node(node_id: u64) -> String {
+    format!("{}node_call_dead_letter/{}/", PREFIX_BROKER, node_id)
+}
+
+// Offline message queue of a persistent session. The sequence is zero-padded so
+// a prefix scan returns the queue in order.
+pub fn offline_message_key(tenant: &str, client_id: &str, seq: u64) -> String {
+    format!(
+        "{}offline_message/{}/{}/{:020}",
+        PREFIX_BROKER, tenant, client_id, seq
+    )
+}
+
+pub fn offline_message_prefix_key(tenant: &str, client_id: &str) -> String {
+    format!("{}offline_message/{}/{}/", PREFIX_BROKER, tenant, client_id)
+}
@@ -37,6 +37,39 @@ pub fn key_node_epoch(node_id: u64) -> String {
     format!("{}clusters/node_epoch/{}", PREFIX_META, node_id)
 }
 
+/// Cordon mark of a node, kept across re-registration of the node.
+#[inline]
+pub fn key_node_cordon(node_id: u64) -> String {
+    format!("{}clusters/node_cordon/{}", PREFIX_META, node_id)
+}
+
+#[inline]
+pub fn key_node_cordon_prefix() -> String {
+    format!("{}clusters/node_cordon/", PREFIX_META)
+}
+
+// KV: expiry index of keys written with a TTL.
+#[inline]
+pub fn key_kv_expire(key: &str) -> String {
+    format!("{}kv_expire/{}", PREFIX_META, key)
+}
+
+#[inline]
+pub fn key_kv_expire_prefix() -> String {
+    format!("{}kv_expire/", PREFIX_META)
+}
+
+// Locks, stored as KV entries.
+#[inline]
+pub fn key_lock(name: &str) -> String {
+    format!("{}lock/{}", PREFIX_META, name)
+}
+
+#[inline]
+pub fn key_lock_prefix() -> String {
+    format!("{}lock/", PREFIX_META)
+}
+
 // Resource config.
 #[inline]
 pub fn key_resource_config(resource_key: &str) -> String {
@@ -167,6 +200,14 @@ pub fn storage_key_mqtt_session_tenant_prefix(tenant: &str) -> String {
     format!("{}mqtt/session/{}/", PREFIX_META, tenant)
 }
 
+#[inline]
+pub fn storage_key_mqtt_last_will_claim(tenant: &str, client_id: &str) -> String {
+    format!(
+        "{}mqtt/last_will_claim/{}/{}",
+        PREFIX_META, tenant, client_id
+    )
+}
+
 // MQTT: shared-subscription groups and members.
 #[inline]
 pub fn storage_key_share_group(tenant: &str, group_name: &str) -> String {
@@ -233,6 +274,14 @@ pub fn storage_key_mqtt_connector_prefix() -> String {
     format!("{}mqtt/connector/", PREFIX_META)
 }
 
+#[inline]
+pub fn storage_key_mqtt_connector_checkpoint(connector_name: &str) -> String {
+    format!(
+        "{}mqtt/connector_checkpoint/{}",
+        PREFIX_META, connector_name
+    )
+}
+
 // MQTT: schemas.
 #[inline]
 pub fn storage_key_mqtt_schema(tenant: &str, schema_name: &str) -> String {
@@ -350,23 +399,55 @@ pub fn storage_key_mqtt_auto_subscribe_rule_tenant_prefix(tenant: &str) -> Strin
     format!("{}mqtt/auto_subscribe_rule/{}/", PREFIX_META, tenant)
 }
 
-// MQTT: retain messages.
+// MQTT: message (rule engine) rules.
+#[inline]
+pub fn storage_key_mqtt_message_rule(tenant: &str, name: &str) -> String {
+    format!("{}mqtt/message_rule/{}/{}", PREFIX_META, tenant, name)
+}
+
+#[inline]
+pub fn storage_key_mqtt_message_rule_prefix() -> String {
+    format!("{}mqtt/message_rule/", PREFIX_META)
+}
+
 #[inline]
-pub fn storage_key_mqtt_retain_message(tenant: &str, topic_name: &str) -> String {
+pub fn storage_key_mqtt_message_rule_tenant_prefix(tenant: &str) -> String {
+    format!("{}mqtt/message_rule/{}/", PREFIX_META, tenant)
+}
+
+// MQTT: subscribe routes, the topic filters hosted by each broker.
+#[inline]
+pub fn storage_key_mqtt_subscribe_route(broker_id: u64, tenant: &str, filter: &str) -> String {
     format!(
-        "{}mqtt/retain_message/{}/{}",
-        PREFIX_META, tenant, topic_name
+        "{}mqtt/subscribe_route/{}/{}/{}",
+        PREFIX_META, broker_id, tenant, filter
     )
 }
 
 #[inline]
-pub fn storage_key_mqtt_retain_message_tenant_prefix(tenant: &str) -> String {
-    format!("{}mqtt/retain_message/{}/", PREFIX_META, tenant)
+pub fn storage_key_mqtt_subscribe_route_prefix() -> String {
+    format!("{}mqtt/subscribe_route/", PREFIX_META)
+}
+
+#[inline]
+pub fn storage_key_mqtt_subscribe_route_node_prefix(broker_id: u64) -> String {
+    format!("{}mqtt/subscribe_route/{}/", PREFIX_META, broker_id)
+}
+
+// MQTT: retain message index (payloads live in the storage adapter).
+#[inline]
+pub fn storage_key_mqtt_retain_index(tenant: &str, topic_name: &str) -> String {
+    format!("{}mqtt/retain_index/{}/{}", PREFIX_META, tenant, topic_name)
+}
+
+#[inline]
+pub fn storage_key_mqtt_retain_index_tenant_prefix(tenant: &str) -> String {
+    format!("{}mqtt/retain_index/{}/", PREFIX_META, tenant)
 }
 
 #[inline]
-pub fn storage_key_mqtt_retain_message_prefix() -> String {
-    format!("{}mqtt/retain_message/", PREFIX_META)
+pub fn storage_key_mqtt_retain_index_prefix() -> String {
+    format!("{}mqtt/retain_index/", PREFIX_META)
 }
 
 // NATS: subscriptions.
@@ -19,4 +19,5 @@
 pub const PREFIX_META: &str = "/meta/";
 pub const PREFIX_BROKER: &str = "/broker/";
 pub const PREFIX_ENGINE: &str = "/engine/";
+pub const PREFIX_METRICS: &str = "/metrics/";
 pub const SEP: char = '/';
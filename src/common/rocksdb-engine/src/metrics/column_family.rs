@@ -0,0 +1,50 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::rocksdb::RocksDBEngine;
+use crate::storage::family::column_family_list;
+use common_base::error::ResultCommonError;
+use common_base::tools::loop_select_ticket;
+use common_metrics::rocksdb::metrics_rocksdb_column_family_set;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+pub async fn start_column_family_metrics_collection(
+    rocksdb_engine: Arc<RocksDBEngine>,
+    stop_send: broadcast::Sender<bool>,
+    interval_ms: u64,
+) {
+    let ac_fn = async || -> ResultCommonError { record_column_family_metrics(&rocksdb_engine) };
+    loop_select_ticket(ac_fn, interval_ms.max(1000), &stop_send).await;
+}
+
+/// Export the size and compaction state of every column family the engine
+/// was opened with.
+pub fn record_column_family_metrics(rocksdb_engine: &Arc<RocksDBEngine>) -> ResultCommonError {
+    for cf in column_family_list() {
+        if rocksdb_engine.cf_handle(&cf).is_none() {
+            continue;
+        }
+        let stats = rocksdb_engine.column_family_stats(&cf)?;
+        metrics_rocksdb_column_family_set(
+            &cf,
+            stats.sst_bytes,
+            stats.memtable_bytes,
+            stats.estimate_keys,
+            stats.pending_compaction_bytes,
+            stats.level0_files,
+        );
+    }
+    Ok(())
+}
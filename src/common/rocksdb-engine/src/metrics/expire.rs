@@ -15,7 +15,7 @@
 use crate::metrics::MetricsValue;
 use crate::rocksdb::RocksDBEngine;
 use crate::storage::broker::{engine_delete_by_broker, engine_delete_prefix_by_broker};
-use crate::storage::family::DB_COLUMN_FAMILY_BROKER;
+use crate::storage::family::DB_COLUMN_FAMILY_BROKER_METRICS;
 use crate::warp::StorageDataWrap;
 use common_base::error::common::CommonError;
 use common_base::error::ResultCommonError;
@@ -64,11 +64,11 @@ fn gc_prefix(
 ) -> Result<(), CommonError> {
     let now_time = now_second();
 
-    let cf = if let Some(cf) = rocksdb_engine.cf_handle(DB_COLUMN_FAMILY_BROKER) {
+    let cf = if let Some(cf) = rocksdb_engine.cf_handle(DB_COLUMN_FAMILY_BROKER_METRICS) {
         cf
     } else {
         return Err(CommonError::RocksDBFamilyNotAvailable(
-            DB_COLUMN_FAMILY_BROKER.to_string(),
+            DB_COLUMN_FAMILY_BROKER_METRICS.to_string(),
         ));
     };
     let mut iter = rocksdb_engine.db.raw_iterator_cf(&cf);
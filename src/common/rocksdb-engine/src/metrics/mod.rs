@@ -16,6 +16,7 @@
 use serde::{Deserialize, Serialize};
 
 pub mod base;
+pub mod column_family;
 pub mod expire;
 pub mod mqtt;
 
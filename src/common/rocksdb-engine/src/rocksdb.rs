@@ -20,6 +20,7 @@
 };
 use serde::{de::DeserializeOwned, Serialize};
 
+use crate::storage::family::{column_family_profile, ColumnFamilyProfile};
 use std::sync::Arc;
 
 #[derive(Debug, Clone)]
@@ -39,6 +40,16 @@ fn default() -> Self {
     }
 }
 
+/// Size and compaction state of a column family, read from RocksDB properties.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnFamilyStats {
+    pub sst_bytes: u64,
+    pub memtable_bytes: u64,
+    pub estimate_keys: u64,
+    pub pending_compaction_bytes: u64,
+    pub level0_files: u64,
+}
+
 #[derive(Debug)]
 pub struct RocksDBEngine {
     pub db: Arc<DB>,
@@ -63,7 +74,9 @@ pub fn new_with_config(
         let cf_column_family: Vec<_> = cf_list
             .into_iter()
             .map(|cf| {
-                let cf_opts = Self::open_cf_opts_with_config(max_open_files, cfg, &shared_cache);
+                let profile = column_family_profile(&cf);
+                let cf_opts =
+                    Self::open_cf_opts_with_config(max_open_files, cfg, &shared_cache, profile);
                 ColumnFamilyDescriptor::new(cf, cf_opts)
             })
             .collect();
@@ -343,41 +356,101 @@ pub fn cf_handle(&self, name: &str) -> Option<Arc<BoundColumnFamily<'_>>> {
         self.db.cf_handle(name)
     }
 
+    pub fn column_family_stats(&self, name: &str) -> Result<ColumnFamilyStats, CommonError> {
+        let cf = self
+            .cf_handle(name)
+            .ok_or_else(|| CommonError::RocksDBFamilyNotAvailable(name.to_string()))?;
+        let property = |property: &str| -> Result<u64, CommonError> {
+            self.db
+                .property_int_value_cf(&cf, property)
+                .map(|value| value.unwrap_or(0))
+                .map_err(|e| {
+                    CommonError::CommonError(format!(
+                        "Failed to read property {property} of CF {name}: {e:?}"
+                    ))
+                })
+        };
+        Ok(ColumnFamilyStats {
+            sst_bytes: property("rocksdb.total-sst-files-size")?,
+            memtable_bytes: property("rocksdb.cur-size-all-mem-tables")?,
+            estimate_keys: property("rocksdb.estimate-num-keys")?,
+            pending_compaction_bytes: property("rocksdb.estimate-pending-compaction-bytes")?,
+            level0_files: property("rocksdb.num-files-at-level0")?,
+        })
+    }
+
     fn open_cf_opts_with_config(
         _max_open_files: i32,
         config: &RocksDBConfig,
         shared_cache: &Cache,
+        profile: ColumnFamilyProfile,
     ) -> Options {
         let mut opts = Options::default();
 
-        opts.set_write_buffer_size(config.write_buffer_size);
+        // Queue and log families hold little live data, so they get smaller
+        // memtables than the configured size meant for records and metadata.
+        let write_buffer_size = match profile {
+            ColumnFamilyProfile::Metadata | ColumnFamilyProfile::Storage => {
+                config.write_buffer_size
+            }
+            ColumnFamilyProfile::Queue => config.write_buffer_size.min(32 * 1024 * 1024),
+            ColumnFamilyProfile::Log => config.write_buffer_size.min(16 * 1024 * 1024),
+        };
+        opts.set_write_buffer_size(write_buffer_size);
         opts.set_max_write_buffer_number(config.max_write_buffer_number);
         opts.set_min_write_buffer_number_to_merge(2);
 
         opts.set_compaction_style(DBCompactionStyle::Level);
         opts.set_level_compaction_dynamic_level_bytes(true);
-        opts.set_level_zero_file_num_compaction_trigger(8);
+        // Queues delete what they write; compacting L0 early drops the
+        // tombstones before prefix scans have to skip them.
+        let l0_compaction_trigger = match profile {
+            ColumnFamilyProfile::Queue => 4,
+            _ => 8,
+        };
+        opts.set_level_zero_file_num_compaction_trigger(l0_compaction_trigger);
         opts.set_level_zero_stop_writes_trigger(32);
         opts.set_level_zero_slowdown_writes_trigger(16);
         opts.set_target_file_size_base(128 * 1024 * 1024);
         opts.set_target_file_size_multiplier(2);
 
         opts.set_compression_type(DBCompressionType::Lz4);
-        opts.set_compression_per_level(&[
-            DBCompressionType::None,
-            DBCompressionType::None,
-            DBCompressionType::Lz4,
-            DBCompressionType::Lz4,
-            DBCompressionType::Zstd,
-        ]);
+        match profile {
+            // History is rarely read back, so it is compressed harder.
+            ColumnFamilyProfile::Log => opts.set_compression_per_level(&[
+                DBCompressionType::None,
+                DBCompressionType::Lz4,
+                DBCompressionType::Zstd,
+                DBCompressionType::Zstd,
+                DBCompressionType::Zstd,
+            ]),
+            _ => opts.set_compression_per_level(&[
+                DBCompressionType::None,
+                DBCompressionType::None,
+                DBCompressionType::Lz4,
+                DBCompressionType::Lz4,
+                DBCompressionType::Zstd,
+            ]),
+        }
 
         let transform = SliceTransform::create_fixed_prefix(10);
         opts.set_prefix_extractor(transform);
         opts.set_memtable_prefix_bloom_ratio(0.2);
 
+        // Logs are read by prefix scans or by keys known to exist, so the
+        // last level can skip its bloom filter.
+        if profile == ColumnFamilyProfile::Log {
+            opts.set_optimize_filters_for_hits(true);
+        }
+
         let mut block_opts = BlockBasedOptions::default();
         block_opts.set_bloom_filter(10.0, false);
-        block_opts.set_block_size(4 * 1024);
+        // Larger blocks suit the sequential scans of records and logs.
+        let block_size = match profile {
+            ColumnFamilyProfile::Storage | ColumnFamilyProfile::Log => 16 * 1024,
+            ColumnFamilyProfile::Metadata | ColumnFamilyProfile::Queue => 4 * 1024,
+        };
+        block_opts.set_block_size(block_size);
 
         block_opts.set_block_cache(shared_cache);
         block_opts.set_cache_index_and_filter_blocks(true);
@@ -505,4 +578,16 @@ async fn base_rw() {
         let res6 = rs.delete(cf.clone(), key);
         assert!(res6.is_ok());
     }
+
+    #[tokio::test]
+    async fn column_family_stats() {
+        let rs = test_rocksdb_instance();
+        let name = default_rocksdb_family();
+        let cf = rs.cf_handle(&name).unwrap();
+        rs.write(cf, "stats", &"value".to_string()).unwrap();
+
+        let stats = rs.column_family_stats(&name).unwrap();
+        assert!(stats.memtable_bytes > 0);
+        assert!(rs.column_family_stats("not_exists").is_err());
+    }
 }
@@ -13,7 +13,11 @@
 // limitations under the License.
 
 use crate::{rocksdb::RocksDBEngine, warp::StorageDataWrap};
-use common_base::{error::common::CommonError, tools::now_millis, utils};
+use common_base::{
+    error::common::CommonError,
+    tools::{now_millis, now_second},
+    utils,
+};
 use common_metrics::rocksdb::{
     metrics_rocksdb_delete_ms, metrics_rocksdb_exist_ms, metrics_rocksdb_get_ms,
     metrics_rocksdb_list_ms, metrics_rocksdb_save_ms,
@@ -186,6 +190,47 @@ pub fn engine_prefix_list<T>(
     })
 }
 
+/// Delete the entries under `prefix_key_name` saved more than `ttl_sec`
+/// seconds ago, judged by the `create_time` of their `StorageDataWrap`.
+/// A zero TTL keeps entries forever. Returns the number of deleted entries.
+pub fn engine_expire_prefix<T>(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    column_family: &str,
+    source: &str,
+    prefix_key_name: &str,
+    ttl_sec: u64,
+) -> Result<u64, CommonError>
+where
+    T: serde::de::DeserializeOwned,
+{
+    use common_base::utils::serialize;
+
+    if ttl_sec == 0 {
+        return Ok(0);
+    }
+
+    with_metrics!(source, metrics_rocksdb_delete_ms, {
+        let cf = get_cf_handle(rocksdb_engine_handler, column_family)?;
+        let expire_before = now_second().saturating_sub(ttl_sec);
+
+        let mut batch = rocksdb::WriteBatch::default();
+        let mut expired = 0;
+        for (key, v) in rocksdb_engine_handler.read_prefix(cf.clone(), prefix_key_name)? {
+            let Ok(wrap) = serialize::deserialize::<StorageDataWrap<T>>(v.as_ref()) else {
+                continue;
+            };
+            if wrap.create_time < expire_before {
+                batch.delete_cf(&cf, key);
+                expired += 1;
+            }
+        }
+        if !batch.is_empty() {
+            rocksdb_engine_handler.write_batch(batch)?;
+        }
+        Ok(expired)
+    })
+}
+
 pub fn engine_list_by_model<T>(
     rocksdb_engine_handler: &Arc<RocksDBEngine>,
     column_family: &str,
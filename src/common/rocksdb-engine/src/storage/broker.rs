@@ -14,14 +14,18 @@
 
 use crate::rocksdb::RocksDBEngine;
 use crate::storage::base::{
-    engine_delete, engine_delete_prefix, engine_exists, engine_get, engine_prefix_list, engine_save,
+    engine_delete, engine_delete_prefix, engine_exists, engine_expire_prefix, engine_get,
+    engine_prefix_list, engine_save,
 };
-use crate::storage::family::DB_COLUMN_FAMILY_BROKER;
+use crate::storage::family::{broker_column_family, DB_COLUMN_FAMILY_BROKER};
 use crate::warp::StorageDataWrap;
 use common_base::error::common::CommonError;
+use rocksdb::WriteBatch;
 use serde::{de::DeserializeOwned, Serialize};
 use std::sync::Arc;
 
+const MIGRATE_BATCH_SIZE: usize = 1000;
+
 pub fn engine_save_by_broker<T>(
     rocksdb_engine_handler: &Arc<RocksDBEngine>,
     key_name: &str,
@@ -32,7 +36,7 @@ pub fn engine_save_by_broker<T>(
 {
     engine_save(
         rocksdb_engine_handler,
-        DB_COLUMN_FAMILY_BROKER,
+        broker_column_family(key_name),
         "broker",
         key_name,
         value,
@@ -48,7 +52,7 @@ pub fn engine_get_by_broker<T>(
 {
     engine_get(
         rocksdb_engine_handler,
-        DB_COLUMN_FAMILY_BROKER,
+        broker_column_family(key_name),
         "broker",
         key_name,
     )
@@ -60,7 +64,7 @@ pub fn engine_exists_by_broker(
 ) -> Result<bool, CommonError> {
     engine_exists(
         rocksdb_engine_handler,
-        DB_COLUMN_FAMILY_BROKER,
+        broker_column_family(key_name),
         "broker",
         key_name,
     )
@@ -72,7 +76,7 @@ pub fn engine_delete_by_broker(
 ) -> Result<(), CommonError> {
     engine_delete(
         rocksdb_engine_handler,
-        DB_COLUMN_FAMILY_BROKER,
+        broker_column_family(key_name),
         "broker",
         key_name,
     )
@@ -87,7 +91,7 @@ pub fn engine_prefix_list_by_broker<T>(
 {
     engine_prefix_list(
         rocksdb_engine_handler,
-        DB_COLUMN_FAMILY_BROKER,
+        broker_column_family(prefix_key_name),
         "broker",
         prefix_key_name,
     )
@@ -99,8 +103,152 @@ pub fn engine_delete_prefix_by_broker(
 ) -> Result<(), CommonError> {
     engine_delete_prefix(
         rocksdb_engine_handler,
-        DB_COLUMN_FAMILY_BROKER,
+        broker_column_family(prefix_key),
+        "broker",
+        prefix_key,
+    )
+}
+
+pub fn engine_expire_prefix_by_broker<T>(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    prefix_key: &str,
+    ttl_sec: u64,
+) -> Result<u64, CommonError>
+where
+    T: DeserializeOwned,
+{
+    engine_expire_prefix::<T>(
+        rocksdb_engine_handler,
+        broker_column_family(prefix_key),
         "broker",
         prefix_key,
+        ttl_sec,
     )
 }
+
+/// Move the keys written by older versions, which kept every broker subsystem
+/// in `DB_COLUMN_FAMILY_BROKER`, into their own column families. Each batch
+/// writes the keys to the new family and deletes them from the old one
+/// atomically, so an interrupted run is resumed by the next start. Returns the
+/// number of keys moved.
+pub fn migrate_broker_column_families(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+) -> Result<u64, CommonError> {
+    let Some(source) = rocksdb_engine_handler.cf_handle(DB_COLUMN_FAMILY_BROKER) else {
+        return Err(CommonError::RocksDBFamilyNotAvailable(
+            DB_COLUMN_FAMILY_BROKER.to_string(),
+        ));
+    };
+
+    let mut moved = 0;
+    let mut batch = WriteBatch::default();
+    let mut iter = rocksdb_engine_handler.db.raw_iterator_cf(&source);
+    iter.seek_to_first();
+    while let (Some(key), Some(value)) = (iter.key(), iter.value()) {
+        let target_name = broker_column_family(&String::from_utf8_lossy(key));
+        if target_name != DB_COLUMN_FAMILY_BROKER {
+            let Some(target) = rocksdb_engine_handler.cf_handle(target_name) else {
+                return Err(CommonError::RocksDBFamilyNotAvailable(
+                    target_name.to_string(),
+                ));
+            };
+            batch.put_cf(&target, key, value);
+            batch.delete_cf(&source, key);
+            moved += 1;
+            if batch.len() >= MIGRATE_BATCH_SIZE * 2 {
+                rocksdb_engine_handler.write_batch(std::mem::take(&mut batch))?;
+            }
+        }
+        iter.next();
+    }
+    iter.status()
+        .map_err(|e| CommonError::CommonError(format!("Failed to scan broker CF: {e:?}")))?;
+    if !batch.is_empty() {
+        rocksdb_engine_handler.write_batch(batch)?;
+    }
+    Ok(moved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::family::DB_COLUMN_FAMILY_BROKER_SYSTEM_EVENT;
+    use crate::test::test_rocksdb_instance;
+    use common_base::tools::now_second;
+
+    #[test]
+    fn engine_expire_prefix_by_broker_test() {
+        let rocksdb_engine_handler = test_rocksdb_instance();
+        let cf = rocksdb_engine_handler
+            .cf_handle(DB_COLUMN_FAMILY_BROKER_SYSTEM_EVENT)
+            .unwrap();
+        for (key, create_time) in [
+            ("/broker/system_alarm/a/1", now_second() - 7200),
+            ("/broker/system_alarm/b/2", now_second()),
+        ] {
+            let wrap = StorageDataWrap {
+                data: "v".to_string(),
+                create_time,
+            };
+            rocksdb_engine_handler
+                .write(cf.clone(), key, &wrap)
+                .unwrap();
+        }
+
+        let prefix = "/broker/system_alarm/";
+        assert_eq!(
+            engine_expire_prefix_by_broker::<String>(&rocksdb_engine_handler, prefix, 0).unwrap(),
+            0
+        );
+        assert_eq!(
+            engine_expire_prefix_by_broker::<String>(&rocksdb_engine_handler, prefix, 3600)
+                .unwrap(),
+            1
+        );
+        let left = engine_prefix_list_by_broker::<String>(&rocksdb_engine_handler, prefix).unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].data, "v");
+        assert!(engine_get_by_broker::<String>(
+            &rocksdb_engine_handler,
+            "/broker/system_alarm/a/1"
+        )
+        .unwrap()
+        .is_none());
+    }
+
+    #[test]
+    fn migrate_broker_column_families_test() {
+        let rocksdb_engine_handler = test_rocksdb_instance();
+        let alarm_key = "/broker/system_alarm/high_cpu/100";
+        let other_key = "/broker/unknown/1";
+        for key in [alarm_key, other_key] {
+            let cf = rocksdb_engine_handler
+                .cf_handle(DB_COLUMN_FAMILY_BROKER)
+                .unwrap();
+            rocksdb_engine_handler
+                .write(cf, key, &StorageDataWrap::new("v".to_string()))
+                .unwrap();
+        }
+
+        assert_eq!(
+            migrate_broker_column_families(&rocksdb_engine_handler).unwrap(),
+            1
+        );
+        let alarm = engine_get_by_broker::<String>(&rocksdb_engine_handler, alarm_key).unwrap();
+        assert_eq!(alarm.unwrap().data, "v");
+        let cf = rocksdb_engine_handler
+            .cf_handle(DB_COLUMN_FAMILY_BROKER_SYSTEM_EVENT)
+            .unwrap();
+        assert!(rocksdb_engine_handler
+            .read::<StorageDataWrap<String>>(cf, alarm_key)
+            .unwrap()
+            .is_some());
+        assert!(engine_exists_by_broker(&rocksdb_engine_handler, other_key).unwrap());
+
+        // Nothing left to move on the next start.
+        assert_eq!(
+            migrate_broker_column_families(&rocksdb_engine_handler).unwrap(),
+            0
+        );
+    }
+}
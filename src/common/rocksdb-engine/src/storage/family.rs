@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::keys::{PREFIX_BROKER, PREFIX_METRICS, SEP};
+
 // metadata service
 pub const DB_COLUMN_FAMILY_META_RAFT: &str = "meta_raft";
 pub const DB_COLUMN_FAMILY_META_DATA: &str = "meta_data";
@@ -19,8 +21,16 @@
 
 // broker service
 pub const DB_COLUMN_FAMILY_BROKER: &str = "broker";
+pub const DB_COLUMN_FAMILY_BROKER_METRICS: &str = "broker_metrics";
+pub const DB_COLUMN_FAMILY_BROKER_SYSTEM_EVENT: &str = "broker_system_event";
+pub const DB_COLUMN_FAMILY_BROKER_OFFLINE_MESSAGE: &str = "broker_offline_message";
+pub const DB_COLUMN_FAMILY_BROKER_DEAD_LETTER: &str = "broker_dead_letter";
 
 // journal engine
+// Records, indexes and offset markers of a shard share this family on purpose:
+// dropping a shard or segment is one prefix delete over `keys::engine`, which
+// splitting them across families would turn into several non-atomic deletes.
+// Delay tasks are not kept here, they are stored in storage engine shards.
 pub const DB_COLUMN_FAMILY_STORAGE_ENGINE: &str = "storage";
 
 pub fn column_family_list() -> Vec<String> {
@@ -29,10 +39,59 @@ pub fn column_family_list() -> Vec<String> {
         DB_COLUMN_FAMILY_META_DATA.to_string(),
         DB_COLUMN_FAMILY_META_METADATA.to_string(),
         DB_COLUMN_FAMILY_BROKER.to_string(),
+        DB_COLUMN_FAMILY_BROKER_METRICS.to_string(),
+        DB_COLUMN_FAMILY_BROKER_SYSTEM_EVENT.to_string(),
+        DB_COLUMN_FAMILY_BROKER_OFFLINE_MESSAGE.to_string(),
+        DB_COLUMN_FAMILY_BROKER_DEAD_LETTER.to_string(),
         DB_COLUMN_FAMILY_STORAGE_ENGINE.to_string(),
     ]
 }
 
+/// How a column family is accessed, which decides its RocksDB options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnFamilyProfile {
+    /// Small values read by key: metadata and raft state.
+    Metadata,
+    /// Message records and their indexes.
+    Storage,
+    /// Entries deleted soon after they are written.
+    Queue,
+    /// Append-only history read by prefix scans.
+    Log,
+}
+
+pub fn column_family_profile(cf: &str) -> ColumnFamilyProfile {
+    match cf {
+        DB_COLUMN_FAMILY_STORAGE_ENGINE => ColumnFamilyProfile::Storage,
+        DB_COLUMN_FAMILY_BROKER_OFFLINE_MESSAGE | DB_COLUMN_FAMILY_BROKER_DEAD_LETTER => {
+            ColumnFamilyProfile::Queue
+        }
+        DB_COLUMN_FAMILY_BROKER_METRICS | DB_COLUMN_FAMILY_BROKER_SYSTEM_EVENT => {
+            ColumnFamilyProfile::Log
+        }
+        _ => ColumnFamilyProfile::Metadata,
+    }
+}
+
+/// Column family holding a broker key, chosen by the subsystem segment of
+/// the key. Prefixes passed to prefix scans and deletes resolve the same way,
+/// as they always include that segment.
+pub fn broker_column_family(key: &str) -> &'static str {
+    if key.starts_with(PREFIX_METRICS) {
+        return DB_COLUMN_FAMILY_BROKER_METRICS;
+    }
+    let subsystem = key
+        .strip_prefix(PREFIX_BROKER)
+        .and_then(|rest| rest.split(SEP).next())
+        .unwrap_or_default();
+    match subsystem {
+        "system_alarm" | "ban_log" | "slow_sub_log" => DB_COLUMN_FAMILY_BROKER_SYSTEM_EVENT,
+        "offline_message" => DB_COLUMN_FAMILY_BROKER_OFFLINE_MESSAGE,
+        "node_call_dead_letter" => DB_COLUMN_FAMILY_BROKER_DEAD_LETTER,
+        _ => DB_COLUMN_FAMILY_BROKER,
+    }
+}
+
 pub fn rocksdb_data_fold(path: &str) -> String {
     let mut result = String::with_capacity(path.len() + 6);
     result.push_str(path);
@@ -49,15 +108,39 @@ pub fn storage_raft_snapshot_fold(path: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use crate::storage::family::{column_family_list, rocksdb_data_fold};
+    use crate::storage::family::{
+        broker_column_family, column_family_list, column_family_profile, rocksdb_data_fold,
+        ColumnFamilyProfile, DB_COLUMN_FAMILY_BROKER, DB_COLUMN_FAMILY_BROKER_DEAD_LETTER,
+        DB_COLUMN_FAMILY_BROKER_SYSTEM_EVENT,
+    };
 
     #[tokio::test]
     async fn column_family_list_test() {
         let list = column_family_list();
-        assert_eq!(list.len(), 5);
+        assert_eq!(list.len(), 9);
         assert_eq!(list[0], "meta_raft");
     }
 
+    #[test]
+    fn broker_column_family_test() {
+        assert_eq!(
+            broker_column_family("/broker/ban_log/t1/user/u1/100"),
+            DB_COLUMN_FAMILY_BROKER_SYSTEM_EVENT
+        );
+        assert_eq!(
+            broker_column_family("/broker/node_call_dead_letter/1/"),
+            DB_COLUMN_FAMILY_BROKER_DEAD_LETTER
+        );
+        assert_eq!(
+            broker_column_family("/broker/other"),
+            DB_COLUMN_FAMILY_BROKER
+        );
+        assert_eq!(
+            column_family_profile(DB_COLUMN_FAMILY_BROKER_SYSTEM_EVENT),
+            ColumnFamilyProfile::Log
+        );
+    }
+
     #[tokio::test]
     async fn storage_data_fold_test() {
         let path = "/tmp/test";
@@ -0,0 +1,117 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Evaluating the ACL of a publish against rule lists of growing size. Rules
+// sit on the username, the client id and the `*` resource, some of them with
+// `%c` / `%u` placeholders, so every list the check walks is populated.
+// `hit` matches a rule early, `miss` walks every rule.
+// Run with: cargo bench -p common-security --bench acl_eval
+
+use common_security::auth::acl::{is_acl_deny, AclCheckRequest};
+use common_security::manager::SecurityManager;
+use common_security::WILDCARD_RESOURCE;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use metadata_struct::auth::acl::{
+    EnumAclAction, EnumAclPermission, EnumAclResourceType, SecurityAcl,
+};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+const TENANT: &str = "default";
+const CLIENT_ID: &str = "device-001";
+const USERNAME: &str = "user-001";
+
+fn acl(
+    resource_type: EnumAclResourceType,
+    resource_name: &str,
+    topic: String,
+    permission: EnumAclPermission,
+    priority: u32,
+) -> SecurityAcl {
+    SecurityAcl {
+        name: format!("{resource_name}-{topic}-{priority}"),
+        desc: String::new(),
+        tenant: TENANT.to_string(),
+        resource_type,
+        resource_name: resource_name.to_string(),
+        topic,
+        ip: "*".to_string(),
+        action: EnumAclAction::Publish,
+        permission,
+        priority,
+    }
+}
+
+// `count` rules split over the three lists, spread over ten priorities.
+fn build_manager(count: usize) -> Arc<SecurityManager> {
+    let manager = Arc::new(SecurityManager::new());
+    for i in 0..count {
+        let priority = (i % 10) as u32;
+        let rule = match i % 3 {
+            0 => acl(
+                EnumAclResourceType::User,
+                USERNAME,
+                format!("site/{i}/device/+/telemetry"),
+                EnumAclPermission::Allow,
+                priority,
+            ),
+            1 => acl(
+                EnumAclResourceType::ClientId,
+                CLIENT_ID,
+                format!("site/{i}/%c/#"),
+                EnumAclPermission::Deny,
+                priority,
+            ),
+            _ => acl(
+                EnumAclResourceType::User,
+                WILDCARD_RESOURCE,
+                format!("tenant/%u/{i}/+"),
+                EnumAclPermission::Allow,
+                priority,
+            ),
+        };
+        manager.metadata.add_acl(rule);
+    }
+    manager
+}
+
+fn bench_acl(c: &mut Criterion) {
+    let attributes = BTreeMap::new();
+    let mut group = c.benchmark_group("acl_eval");
+
+    for count in [10, 100, 1_000, 10_000] {
+        let manager = build_manager(count);
+        for (name, topic) in [
+            ("hit", "site/9/device/7/telemetry".to_string()),
+            ("miss", format!("site/{count}/unknown")),
+        ] {
+            let req = AclCheckRequest {
+                tenant: TENANT,
+                client_id: CLIENT_ID,
+                username: USERNAME,
+                source_ip: "10.0.0.1",
+                topic_name: &topic,
+                action: EnumAclAction::Publish,
+                attributes: &attributes,
+            };
+            group.bench_with_input(BenchmarkId::new(name, count), &count, |b, _| {
+                b.iter(|| is_acl_deny(black_box(&manager), black_box(&req)).unwrap());
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_acl);
+criterion_main!(benches);
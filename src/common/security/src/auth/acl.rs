@@ -13,12 +13,14 @@
 // limitations under the License.
 
 use crate::{
-    auth::common::{ip_match, topic_match},
+    auth::common::{expand_topic_placeholders, ip_match, topic_match},
     manager::SecurityManager,
+    WILDCARD_RESOURCE,
 };
 use common_base::error::common::CommonError;
+use dashmap::DashMap;
 use metadata_struct::auth::acl::{EnumAclAction, EnumAclPermission, SecurityAcl};
-use std::{net::SocketAddr, sync::Arc};
+use std::{collections::BTreeMap, net::SocketAddr, sync::Arc};
 
 pub fn normalize_source_ip(source_ip_addr: &str) -> String {
     if let Ok(socket_addr) = source_ip_addr.parse::<SocketAddr>() {
@@ -32,85 +34,174 @@ pub fn normalize_source_ip(source_ip_addr: &str) -> String {
     source_ip_addr.to_string()
 }
 
-pub fn is_user_acl_deny(
+pub struct AclCheckRequest<'a> {
+    pub tenant: &'a str,
+    pub client_id: &'a str,
+    pub username: &'a str,
+    pub source_ip: &'a str,
+    pub topic_name: &'a str,
+    pub action: EnumAclAction,
+    pub attributes: &'a BTreeMap<String, String>,
+}
+
+/// Evaluate every rule that applies to the client id and the username,
+/// including rules whose resource name is `*`.
+///
+/// Only the matching rules with the highest priority decide: the request is
+/// denied if any of them is a Deny (deny-overrides), allowed otherwise. When no
+/// rule matches the request is not denied.
+pub fn is_acl_deny(
     security_manager: &Arc<SecurityManager>,
-    topic_name: &str,
-    tenant: &str,
-    user: &str,
-    source_ip: &str,
-    action: &EnumAclAction,
+    req: &AclCheckRequest,
 ) -> Result<bool, CommonError> {
-    if let Some(tenant_map) = security_manager.metadata.acl_user.get(tenant) {
-        if let Some(acl_list) = tenant_map.get(user) {
-            return check_acl_rules(&acl_list, action, topic_name, source_ip);
+    let metadata = &security_manager.metadata;
+    let mut decision = AclDecision::default();
+    collect_acl_decision(&metadata.acl_client_id, req.client_id, req, &mut decision)?;
+    if !req.username.is_empty() {
+        collect_acl_decision(&metadata.acl_user, req.username, req, &mut decision)?;
+    }
+    Ok(decision.is_deny())
+}
+
+#[derive(Default)]
+struct AclDecision {
+    // (priority, permission) of the deciding rules
+    best: Option<(u32, EnumAclPermission)>,
+}
+
+impl AclDecision {
+    fn priority(&self) -> Option<u32> {
+        self.best.map(|(priority, _)| priority)
+    }
+
+    fn add(&mut self, priority: u32, permission: EnumAclPermission) {
+        match self.best {
+            Some((best, _)) if priority < best => {}
+            Some((best, EnumAclPermission::Deny)) if priority == best => {}
+            _ => self.best = Some((priority, permission)),
         }
     }
-    Ok(false)
+
+    fn is_deny(&self) -> bool {
+        matches!(self.best, Some((_, EnumAclPermission::Deny)))
+    }
 }
 
-pub fn is_client_id_acl_deny(
-    security_manager: &Arc<SecurityManager>,
-    topic_name: &str,
-    tenant: &str,
-    client_id: &str,
-    source_ip: &str,
-    action: &EnumAclAction,
-) -> Result<bool, CommonError> {
-    if let Some(tenant_map) = security_manager.metadata.acl_client_id.get(tenant) {
-        if let Some(acl_list) = tenant_map.get(client_id) {
-            return check_acl_rules(&acl_list, action, topic_name, source_ip);
+fn collect_acl_decision(
+    acl_map: &DashMap<String, DashMap<String, Vec<SecurityAcl>>>,
+    resource_name: &str,
+    req: &AclCheckRequest,
+    decision: &mut AclDecision,
+) -> Result<(), CommonError> {
+    let Some(tenant_map) = acl_map.get(req.tenant) else {
+        return Ok(());
+    };
+    for name in [resource_name, WILDCARD_RESOURCE] {
+        if let Some(acl_list) = tenant_map.get(name) {
+            check_acl_rules(&acl_list, req, decision)?;
         }
     }
-    Ok(false)
+    Ok(())
 }
 
+// Rule lists are kept sorted by descending priority, so the scan stops at the
+// first rule that can no longer change the decision.
 fn check_acl_rules(
     acl_list: &[SecurityAcl],
-    action: &EnumAclAction,
-    topic_name: &str,
-    source_ip: &str,
-) -> Result<bool, CommonError> {
+    req: &AclCheckRequest,
+    decision: &mut AclDecision,
+) -> Result<(), CommonError> {
     for acl in acl_list.iter() {
-        if acl.action != *action && acl.action != EnumAclAction::All {
+        if decision.priority().is_some_and(|p| acl.priority < p) {
+            break;
+        }
+        if !action_match(&acl.action, &req.action) {
             continue;
         }
-        if !topic_match(topic_name, &acl.topic) || !ip_match(source_ip, &acl.ip)? {
+        let Some(topic) =
+            expand_topic_placeholders(&acl.topic, req.client_id, req.username, req.attributes)
+        else {
+            continue;
+        };
+        if !topic_match(req.topic_name, &topic) || !ip_match(req.source_ip, &acl.ip)? {
             continue;
         }
-        return Ok(acl.permission == EnumAclPermission::Deny);
+        decision.add(acl.priority, acl.permission);
+    }
+    Ok(())
+}
+
+fn action_match(rule_action: &EnumAclAction, action: &EnumAclAction) -> bool {
+    match rule_action {
+        EnumAclAction::All => true,
+        EnumAclAction::PubSub => {
+            matches!(action, EnumAclAction::Publish | EnumAclAction::Subscribe)
+        }
+        rule_action => rule_action == action,
     }
-    Ok(false)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{is_client_id_acl_deny, is_user_acl_deny, normalize_source_ip};
+    use super::{is_acl_deny, normalize_source_ip, AclCheckRequest};
     use crate::manager::SecurityManager;
     use metadata_struct::auth::acl::{
         EnumAclAction, EnumAclPermission, EnumAclResourceType, SecurityAcl,
     };
+    use std::collections::BTreeMap;
     use std::sync::Arc;
 
+    const TENANT: &str = "t1";
+
     fn make_acl(
-        tenant: &str,
-        user: &str,
+        resource_type: EnumAclResourceType,
+        resource_name: &str,
         topic: &str,
         action: EnumAclAction,
         permission: EnumAclPermission,
+        priority: u32,
     ) -> SecurityAcl {
         SecurityAcl {
-            name: format!("{}-{:?}-{:?}", user, action, permission),
+            name: format!("{}-{}-{:?}-{:?}", resource_name, topic, action, permission),
             desc: String::new(),
-            tenant: tenant.to_string(),
-            resource_type: EnumAclResourceType::User,
-            resource_name: user.to_string(),
+            tenant: TENANT.to_string(),
+            resource_type,
+            resource_name: resource_name.to_string(),
             topic: topic.to_string(),
             ip: "*".to_string(),
             action,
             permission,
+            priority,
         }
     }
 
+    fn user_acl(topic: &str, action: EnumAclAction, permission: EnumAclPermission) -> SecurityAcl {
+        make_acl(
+            EnumAclResourceType::User,
+            "user1",
+            topic,
+            action,
+            permission,
+            0,
+        )
+    }
+
+    fn is_deny(sm: &Arc<SecurityManager>, topic_name: &str, action: EnumAclAction) -> bool {
+        is_acl_deny(
+            sm,
+            &AclCheckRequest {
+                tenant: TENANT,
+                client_id: "device-001",
+                username: "user1",
+                source_ip: "1.2.3.4",
+                topic_name,
+                action,
+                attributes: &BTreeMap::new(),
+            },
+        )
+        .unwrap()
+    }
+
     #[test]
     fn test_normalize_source_ip() {
         assert_eq!(normalize_source_ip("192.168.1.1:12345"), "192.168.1.1");
@@ -119,193 +210,140 @@ fn test_normalize_source_ip() {
     }
 
     #[test]
-    fn test_is_user_acl_deny() {
+    fn test_user_acl() {
         let sm = Arc::new(SecurityManager::new());
-        let tenant = "t1";
-        let user = "user1";
-
-        // Deny rule: blocked
-        sm.metadata.add_acl(make_acl(
-            tenant,
-            user,
+        sm.metadata.add_acl(user_acl(
             "sensor/data",
             EnumAclAction::Publish,
             EnumAclPermission::Deny,
         ));
-        assert!(is_user_acl_deny(
-            &sm,
-            "sensor/data",
-            tenant,
-            user,
-            "1.2.3.4",
-            &EnumAclAction::Publish
-        )
-        .unwrap());
+        assert!(is_deny(&sm, "sensor/data", EnumAclAction::Publish));
+        // different action or topic: no match → not denied
+        assert!(!is_deny(&sm, "sensor/data", EnumAclAction::Subscribe));
+        assert!(!is_deny(&sm, "sensor/other", EnumAclAction::Publish));
 
-        // Different action: no match → not denied
-        assert!(!is_user_acl_deny(
-            &sm,
-            "sensor/data",
-            tenant,
-            user,
-            "1.2.3.4",
-            &EnumAclAction::Subscribe
-        )
-        .unwrap());
+        // PubSub covers publish and subscribe but not retain
+        let sm = Arc::new(SecurityManager::new());
+        sm.metadata.add_acl(user_acl(
+            "data/#",
+            EnumAclAction::PubSub,
+            EnumAclPermission::Deny,
+        ));
+        assert!(is_deny(&sm, "data/1", EnumAclAction::Publish));
+        assert!(is_deny(&sm, "data/1", EnumAclAction::Subscribe));
+        assert!(!is_deny(&sm, "data/1", EnumAclAction::Retain));
+    }
 
-        // Unknown user: no rules → not denied
-        assert!(!is_user_acl_deny(
-            &sm,
-            "sensor/data",
-            tenant,
-            "other_user",
-            "1.2.3.4",
-            &EnumAclAction::Publish
-        )
-        .unwrap());
+    #[test]
+    fn test_client_id_acl() {
+        let sm = Arc::new(SecurityManager::new());
+        sm.metadata.add_acl(make_acl(
+            EnumAclResourceType::ClientId,
+            "device-001",
+            "cmd/device",
+            EnumAclAction::Subscribe,
+            EnumAclPermission::Deny,
+            0,
+        ));
+        assert!(is_deny(&sm, "cmd/device", EnumAclAction::Subscribe));
+        assert!(!is_deny(&sm, "cmd/device", EnumAclAction::Publish));
 
-        // Allow rule wins over no prior deny: first-match allow → not denied
-        let sm2 = Arc::new(SecurityManager::new());
-        sm2.metadata.add_acl(make_acl(
-            tenant,
-            user,
+        sm.metadata.add_acl(make_acl(
+            EnumAclResourceType::ClientId,
+            "other-device",
+            "#",
+            EnumAclAction::All,
+            EnumAclPermission::Deny,
+            0,
+        ));
+        assert!(!is_deny(&sm, "cmd/other", EnumAclAction::Subscribe));
+    }
+
+    #[test]
+    fn test_priority_and_deny_overrides() {
+        // same priority: deny overrides allow
+        let sm = Arc::new(SecurityManager::new());
+        sm.metadata.add_acl(user_acl(
             "sensor/data",
             EnumAclAction::Publish,
             EnumAclPermission::Allow,
         ));
-        assert!(!is_user_acl_deny(
-            &sm2,
-            "sensor/data",
-            tenant,
-            user,
-            "1.2.3.4",
-            &EnumAclAction::Publish
-        )
-        .unwrap());
+        sm.metadata.add_acl(user_acl(
+            "*",
+            EnumAclAction::Publish,
+            EnumAclPermission::Deny,
+        ));
+        assert!(is_deny(&sm, "sensor/data", EnumAclAction::Publish));
 
-        // Allow on specific topic, Deny on wildcard: first-match (Allow) wins
-        let sm3 = Arc::new(SecurityManager::new());
-        sm3.metadata.add_acl(make_acl(
-            tenant,
-            user,
-            "sensor/data",
+        // a higher priority allow wins over a lower priority deny, even when
+        // the deny comes from a client id rule
+        sm.metadata.add_acl(make_acl(
+            EnumAclResourceType::User,
+            "user1",
+            "sensor/+",
             EnumAclAction::Publish,
             EnumAclPermission::Allow,
+            10,
         ));
-        sm3.metadata.add_acl(make_acl(
-            tenant,
-            user,
-            "*",
+        assert!(!is_deny(&sm, "sensor/data", EnumAclAction::Publish));
+        assert!(is_deny(&sm, "other/topic", EnumAclAction::Publish));
+
+        sm.metadata.add_acl(make_acl(
+            EnumAclResourceType::ClientId,
+            "device-001",
+            "sensor/#",
             EnumAclAction::Publish,
             EnumAclPermission::Deny,
+            10,
         ));
-        assert!(!is_user_acl_deny(
-            &sm3,
-            "sensor/data",
-            tenant,
-            user,
-            "1.2.3.4",
-            &EnumAclAction::Publish
-        )
-        .unwrap());
-        assert!(is_user_acl_deny(
-            &sm3,
-            "other/topic",
-            tenant,
-            user,
-            "1.2.3.4",
-            &EnumAclAction::Publish
-        )
-        .unwrap());
+        assert!(is_deny(&sm, "sensor/data", EnumAclAction::Publish));
     }
 
     #[test]
-    fn test_is_client_id_acl_deny() {
+    fn test_placeholders_and_wildcard_resource() {
         let sm = Arc::new(SecurityManager::new());
-        let tenant = "t1";
-        let client_id = "device-001";
-
-        sm.metadata.add_acl(SecurityAcl {
-            name: "acl-client".to_string(),
-            desc: String::new(),
-            tenant: tenant.to_string(),
-            resource_type: EnumAclResourceType::ClientId,
-            resource_name: client_id.to_string(),
-            topic: "cmd/device".to_string(),
-            ip: "*".to_string(),
-            action: EnumAclAction::Subscribe,
-            permission: EnumAclPermission::Deny,
-        });
-
-        assert!(is_client_id_acl_deny(
-            &sm,
-            "cmd/device",
-            tenant,
-            client_id,
-            "1.2.3.4",
-            &EnumAclAction::Subscribe
-        )
-        .unwrap());
-        assert!(!is_client_id_acl_deny(
+        // every user may only publish below its own client id and username
+        sm.metadata.add_acl(make_acl(
+            EnumAclResourceType::User,
+            "*",
+            "devices/%c/%u/#",
+            EnumAclAction::Publish,
+            EnumAclPermission::Allow,
+            1,
+        ));
+        sm.metadata.add_acl(make_acl(
+            EnumAclResourceType::User,
+            "*",
+            "devices/#",
+            EnumAclAction::Publish,
+            EnumAclPermission::Deny,
+            0,
+        ));
+        assert!(!is_deny(
             &sm,
-            "cmd/device",
-            tenant,
-            client_id,
-            "1.2.3.4",
-            &EnumAclAction::Publish
-        )
-        .unwrap());
-        assert!(!is_client_id_acl_deny(
+            "devices/device-001/user1/temp",
+            EnumAclAction::Publish
+        ));
+        assert!(is_deny(
             &sm,
-            "cmd/device",
-            tenant,
-            "other-device",
-            "1.2.3.4",
-            &EnumAclAction::Subscribe
-        )
-        .unwrap());
+            "devices/device-002/user1/temp",
+            EnumAclAction::Publish
+        ));
+        assert!(!is_deny(&sm, "public/info", EnumAclAction::Publish));
     }
 
     #[test]
-    fn test_action_all_matches_any_action() {
+    fn test_ip_cidr() {
         let sm = Arc::new(SecurityManager::new());
-        let tenant = "t1";
-        let user = "u1";
-
-        sm.metadata.add_acl(make_acl(
-            tenant,
-            user,
-            "data/#",
-            EnumAclAction::All,
-            EnumAclPermission::Deny,
-        ));
+        let mut acl = user_acl("#", EnumAclAction::All, EnumAclPermission::Deny);
+        acl.ip = "1.2.3.0/24".to_string();
+        sm.metadata.add_acl(acl);
+        assert!(is_deny(&sm, "a/b", EnumAclAction::Publish));
 
-        assert!(is_user_acl_deny(
-            &sm,
-            "data/#",
-            tenant,
-            user,
-            "1.2.3.4",
-            &EnumAclAction::Publish
-        )
-        .unwrap());
-        assert!(is_user_acl_deny(
-            &sm,
-            "data/#",
-            tenant,
-            user,
-            "1.2.3.4",
-            &EnumAclAction::Subscribe
-        )
-        .unwrap());
-        assert!(is_user_acl_deny(
-            &sm,
-            "data/#",
-            tenant,
-            user,
-            "1.2.3.4",
-            &EnumAclAction::Retain
-        )
-        .unwrap());
+        let sm = Arc::new(SecurityManager::new());
+        let mut acl = user_acl("#", EnumAclAction::All, EnumAclPermission::Deny);
+        acl.ip = "10.0.0.0/8".to_string();
+        sm.metadata.add_acl(acl);
+        assert!(!is_deny(&sm, "a/b", EnumAclAction::Publish));
     }
 }
@@ -15,6 +15,8 @@
 use crate::{auth::common::ip_match, manager::SecurityManager};
 use common_base::error::common::CommonError;
 use common_base::tools::now_second;
+use common_metrics::mqtt::auth::record_mqtt_blacklist_blocked_by_type;
+use metadata_struct::auth::blacklist::EnumBlackListType;
 use regex::Regex;
 use std::sync::Arc;
 use tracing::{info, warn};
@@ -31,6 +33,7 @@ pub fn is_user_blacklisted(
         if let Some(data) = tenant_map.get(user) {
             if is_active(data.end_time, now) {
                 info!(username = %user, end_time = data.end_time, "Connection blocked by exact user blacklist");
+                record_blocked(EnumBlackListType::User);
                 return true;
             }
         }
@@ -40,6 +43,17 @@ pub fn is_user_blacklisted(
         for raw in list.iter() {
             if is_active(raw.end_time, now) && is_wildcard_pattern_match(user, &raw.resource_name) {
                 info!(username = %user, pattern = %raw.resource_name, "Connection blocked by user wildcard blacklist");
+                record_blocked(EnumBlackListType::UserMatch);
+                return true;
+            }
+        }
+    }
+
+    if let Some(list) = meta.blacklist_user_regex.get(tenant) {
+        for item in list.iter() {
+            if is_active(item.blacklist.end_time, now) && item.regex.is_match(user) {
+                info!(username = %user, pattern = %item.blacklist.resource_name, "Connection blocked by user regex blacklist");
+                record_blocked(EnumBlackListType::UserRegex);
                 return true;
             }
         }
@@ -60,6 +74,7 @@ pub fn is_client_id_blacklisted(
         if let Some(data) = tenant_map.get(client_id) {
             if is_active(data.end_time, now) {
                 info!(client_id = %client_id, end_time = data.end_time, "Connection blocked by exact client_id blacklist");
+                record_blocked(EnumBlackListType::ClientId);
                 return true;
             }
         }
@@ -71,6 +86,17 @@ pub fn is_client_id_blacklisted(
                 && is_wildcard_pattern_match(client_id, &raw.resource_name)
             {
                 info!(client_id = %client_id, pattern = %raw.resource_name, "Connection blocked by client_id wildcard blacklist");
+                record_blocked(EnumBlackListType::ClientIdMatch);
+                return true;
+            }
+        }
+    }
+
+    if let Some(list) = meta.blacklist_client_id_regex.get(tenant) {
+        for item in list.iter() {
+            if is_active(item.blacklist.end_time, now) && item.regex.is_match(client_id) {
+                info!(client_id = %client_id, pattern = %item.blacklist.resource_name, "Connection blocked by client_id regex blacklist");
+                record_blocked(EnumBlackListType::ClientIdRegex);
                 return true;
             }
         }
@@ -91,6 +117,7 @@ pub fn is_ip_blacklisted(
         if let Some(data) = tenant_map.get(source_ip) {
             if is_active(data.end_time, now) {
                 info!(source_ip = %source_ip, end_time = data.end_time, "Connection blocked by exact IP blacklist");
+                record_blocked(EnumBlackListType::Ip);
                 return Ok(true);
             }
         }
@@ -100,6 +127,7 @@ pub fn is_ip_blacklisted(
         for raw in list.iter() {
             if is_active(raw.end_time, now) && ip_match(source_ip, &raw.resource_name)? {
                 info!(source_ip = %source_ip, pattern = %raw.resource_name, "Connection blocked by IP pattern blacklist");
+                record_blocked(EnumBlackListType::IPCIDR);
                 return Ok(true);
             }
         }
@@ -108,6 +136,31 @@ pub fn is_ip_blacklisted(
     Ok(false)
 }
 
+/// Regex blacklist entries must match the whole client id or username, so the
+/// pattern is anchored at both ends.
+pub fn compile_blacklist_regex(pattern: &str) -> Result<Regex, CommonError> {
+    Regex::new(&format!("^(?:{pattern})$"))
+        .map_err(|e| CommonError::CommonError(format!("Invalid blacklist regex '{pattern}': {e}")))
+}
+
+/// Reject blacklist entries whose resource name can never match, so that a
+/// typo is reported when the entry is created rather than silently ignored.
+pub fn validate_blacklist_resource(
+    blacklist_type: &EnumBlackListType,
+    resource_name: &str,
+) -> Result<(), CommonError> {
+    match blacklist_type {
+        EnumBlackListType::ClientIdRegex | EnumBlackListType::UserRegex => {
+            compile_blacklist_regex(resource_name)?;
+        }
+        EnumBlackListType::Ip | EnumBlackListType::IPCIDR => {
+            ip_match("127.0.0.1", resource_name)?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
 fn wildcard_to_regex(pattern: &str) -> String {
     let mut regex_pattern = String::with_capacity(pattern.len() * 2);
 
@@ -126,6 +179,10 @@ fn wildcard_to_regex(pattern: &str) -> String {
     regex_pattern
 }
 
+fn record_blocked(blacklist_type: EnumBlackListType) {
+    record_mqtt_blacklist_blocked_by_type(&blacklist_type.to_string());
+}
+
 fn is_active(end_time: u64, now: u64) -> bool {
     end_time == 0 || end_time > now
 }
@@ -144,7 +201,8 @@ fn is_wildcard_pattern_match(target: &str, pattern: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::{
-        is_client_id_blacklisted, is_ip_blacklisted, is_user_blacklisted, is_wildcard_pattern_match,
+        is_client_id_blacklisted, is_ip_blacklisted, is_user_blacklisted,
+        is_wildcard_pattern_match, validate_blacklist_resource,
     };
     use crate::manager::SecurityManager;
     use common_base::tools::now_second;
@@ -245,6 +303,69 @@ fn test_is_client_id_blacklisted() {
         assert!(!is_client_id_blacklisted(&sm, tenant, "prod_sensor"));
     }
 
+    #[test]
+    fn test_regex_blacklisted() {
+        let sm = Arc::new(SecurityManager::new());
+        let tenant = "t1";
+        let future = now_second() + 9999;
+
+        sm.metadata.add_blacklist(make_blacklist(
+            tenant,
+            r"sensor-\d{3}",
+            EnumBlackListType::ClientIdRegex,
+            future,
+        ));
+        assert!(is_client_id_blacklisted(&sm, tenant, "sensor-042"));
+        assert!(!is_client_id_blacklisted(&sm, tenant, "sensor-42"));
+        assert!(!is_client_id_blacklisted(&sm, tenant, "my-sensor-042"));
+
+        sm.metadata.add_blacklist(make_blacklist(
+            tenant,
+            "guest|anonymous",
+            EnumBlackListType::UserRegex,
+            now_second() - 1,
+        ));
+        assert!(!is_user_blacklisted(&sm, tenant, "guest"));
+
+        sm.metadata.add_blacklist(make_blacklist(
+            tenant,
+            "(guest|anonymous)_.*",
+            EnumBlackListType::UserRegex,
+            0,
+        ));
+        assert!(is_user_blacklisted(&sm, tenant, "guest_1"));
+        assert!(!is_user_blacklisted(&sm, tenant, "admin"));
+
+        let regex_entry = make_blacklist(
+            tenant,
+            "(guest|anonymous)_.*",
+            EnumBlackListType::UserRegex,
+            0,
+        );
+        sm.metadata.remove_blacklist(regex_entry);
+        assert!(!is_user_blacklisted(&sm, tenant, "guest_1"));
+        assert_eq!(sm.metadata.get_blacklist_by_tenant(tenant).len(), 2);
+
+        // invalid patterns are skipped by the cache
+        sm.metadata.add_blacklist(make_blacklist(
+            tenant,
+            "(unclosed",
+            EnumBlackListType::UserRegex,
+            0,
+        ));
+        assert!(!is_user_blacklisted(&sm, tenant, "(unclosed"));
+    }
+
+    #[test]
+    fn test_validate_blacklist_resource() {
+        assert!(validate_blacklist_resource(&EnumBlackListType::UserRegex, "a.*").is_ok());
+        assert!(validate_blacklist_resource(&EnumBlackListType::UserRegex, "(a").is_err());
+        assert!(validate_blacklist_resource(&EnumBlackListType::IPCIDR, "10.0.0.0/8").is_ok());
+        assert!(validate_blacklist_resource(&EnumBlackListType::IPCIDR, "10.0.0.0/40").is_err());
+        assert!(validate_blacklist_resource(&EnumBlackListType::Ip, "1.2.3").is_err());
+        assert!(validate_blacklist_resource(&EnumBlackListType::ClientIdMatch, "(").is_ok());
+    }
+
     #[test]
     fn test_is_ip_blacklisted() {
         let sm = Arc::new(SecurityManager::new());
@@ -15,7 +15,7 @@
 use crate::WILDCARD_RESOURCE;
 use common_base::error::common::CommonError;
 use ipnet::IpNet;
-use std::{net::IpAddr, str::FromStr};
+use std::{collections::BTreeMap, net::IpAddr, str::FromStr};
 
 pub fn ip_match(source_ip_addr: &str, ip_role: &str) -> Result<bool, CommonError> {
     if ip_role.is_empty() || ip_role == WILDCARD_RESOURCE {
@@ -39,17 +39,81 @@ pub fn ip_match(source_ip_addr: &str, ip_role: &str) -> Result<bool, CommonError
     }
 }
 
+pub const CLIENT_ID_PLACEHOLDER: &str = "%c";
+pub const USERNAME_PLACEHOLDER: &str = "%u";
+pub const CLIENT_ATTRIBUTE_PLACEHOLDER_PREFIX: &str = "${client_attrs.";
+
+/// Match a topic against an ACL topic pattern. The pattern may be `*`, an
+/// exact topic or an MQTT topic filter using `+` and `#`. When `topic_name` is
+/// itself a filter it only matches if every topic it covers is covered by the
+/// pattern.
 pub fn topic_match(topic_name: &str, match_topic_name: &str) -> bool {
-    if match_topic_name == WILDCARD_RESOURCE {
+    if match_topic_name == WILDCARD_RESOURCE || topic_name == match_topic_name {
         return true;
     }
-    topic_name == match_topic_name
+
+    let mut topic_levels = topic_name.split('/');
+    for pattern_level in match_topic_name.split('/') {
+        match (pattern_level, topic_levels.next()) {
+            ("#", _) => return true,
+            (_, None) | (_, Some("#")) => return false,
+            ("+", Some(_)) => {}
+            (_, Some("+")) => return false,
+            (pattern_level, Some(level)) if pattern_level != level => return false,
+            _ => {}
+        }
+    }
+    topic_levels.next().is_none()
+}
+
+/// Replace the `%c` (client id), `%u` (username) and `${client_attrs.KEY}`
+/// (client attribute) placeholders in an ACL topic pattern. Placeholders of
+/// attributes the client does not have are left as is, so the pattern cannot
+/// match a real topic.
+///
+/// Returns `None` when a value used by the pattern contains `/`, `+` or `#`,
+/// as it would widen the pattern beyond the client's own topics.
+pub fn expand_topic_placeholders(
+    topic: &str,
+    client_id: &str,
+    username: &str,
+    attributes: &BTreeMap<String, String>,
+) -> Option<String> {
+    let mut topic = topic.to_string();
+    if !substitute_placeholder(&mut topic, CLIENT_ID_PLACEHOLDER, client_id)
+        || !substitute_placeholder(&mut topic, USERNAME_PLACEHOLDER, username)
+    {
+        return None;
+    }
+    if !topic.contains(CLIENT_ATTRIBUTE_PLACEHOLDER_PREFIX) {
+        return Some(topic);
+    }
+    for (key, value) in attributes.iter() {
+        let placeholder = format!("{}{}}}", CLIENT_ATTRIBUTE_PLACEHOLDER_PREFIX, key);
+        if !substitute_placeholder(&mut topic, &placeholder, value) {
+            return None;
+        }
+    }
+    Some(topic)
+}
+
+fn substitute_placeholder(topic: &mut String, placeholder: &str, value: &str) -> bool {
+    if !topic.contains(placeholder) {
+        return true;
+    }
+    if value.contains(['/', '+', '#']) {
+        return false;
+    }
+    *topic = topic.replace(placeholder, value);
+    true
 }
 
 #[cfg(test)]
 mod test {
+    use std::collections::BTreeMap;
+
     use crate::{
-        auth::common::{ip_match, topic_match},
+        auth::common::{expand_topic_placeholders, ip_match, topic_match},
         WILDCARD_RESOURCE,
     };
 
@@ -58,6 +122,61 @@ fn topic_match_test() {
         assert!(topic_match("t1", WILDCARD_RESOURCE));
         assert!(topic_match("t1", "t1"));
         assert!(!topic_match("t1", "t2"));
+
+        assert!(topic_match("sensor/1/temp", "sensor/+/temp"));
+        assert!(topic_match("sensor/1/temp", "sensor/#"));
+        assert!(topic_match("sensor", "sensor/#"));
+        assert!(!topic_match("sensor/1/hum", "sensor/+/temp"));
+        assert!(!topic_match("sensor/1/temp/x", "sensor/+/temp"));
+
+        // a filter is only covered by an equal or wider pattern
+        assert!(topic_match("sensor/+/temp", "sensor/#"));
+        assert!(topic_match("data/#", "data/#"));
+        assert!(!topic_match("sensor/#", "sensor/+/temp"));
+        assert!(!topic_match("sensor/+", "sensor/1"));
+    }
+
+    #[test]
+    fn expand_topic_placeholders_test() {
+        let mut attributes = BTreeMap::new();
+        assert_eq!(
+            expand_topic_placeholders("devices/%c/%u/#", "dev-1", "alice", &attributes).unwrap(),
+            "devices/dev-1/alice/#"
+        );
+        assert_eq!(
+            expand_topic_placeholders("a/b", "dev-1", "alice", &attributes).unwrap(),
+            "a/b"
+        );
+
+        attributes.insert("model".to_string(), "x1".to_string());
+        assert_eq!(
+            expand_topic_placeholders(
+                "models/${client_attrs.model}/${client_attrs.site}/%c",
+                "dev-1",
+                "alice",
+                &attributes
+            )
+            .unwrap(),
+            "models/x1/${client_attrs.site}/dev-1"
+        );
+
+        // values that would act as wildcards or extra levels are rejected
+        assert!(expand_topic_placeholders("devices/%c/#", "#", "alice", &attributes).is_none());
+        assert!(expand_topic_placeholders("devices/%c/#", "a/b", "alice", &attributes).is_none());
+        assert!(expand_topic_placeholders("users/%u", "dev-1", "+", &attributes).is_none());
+        attributes.insert("site".to_string(), "s1/+".to_string());
+        assert!(expand_topic_placeholders(
+            "sites/${client_attrs.site}",
+            "dev-1",
+            "alice",
+            &attributes
+        )
+        .is_none());
+        // only values the pattern uses are checked
+        assert_eq!(
+            expand_topic_placeholders("devices/%c", "dev-1", "a/+", &attributes).unwrap(),
+            "devices/dev-1"
+        );
     }
 
     #[test]
@@ -11,27 +11,289 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-
 use crate::manager::SecurityManager;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use common_base::error::common::CommonError;
+use common_config::broker::broker_config;
+use common_config::config::{PasswordHashAlgorithm, PasswordPolicy};
+use metadata_struct::auth::user::SecurityUser;
+use rand::RngCore;
 use sha2::{Digest, Sha256};
 use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use tracing::warn;
+
+/// A password rehashed with the policy's algorithm after a successful login,
+/// waiting to be written back by the password rehash task.
+#[derive(Clone, Debug)]
+pub struct RehashedPassword {
+    pub password: String,
+    pub salt: Option<String>,
+    pub hash_algorithm: PasswordHashAlgorithm,
+    /// The stored hash the login was checked against. The entry is dropped if
+    /// the user changed since.
+    pub replaces: String,
+}
 
-pub fn password_check_by_login(
+pub async fn password_check_by_login(
     security_manager: &Arc<SecurityManager>,
     tenant: &str,
     username: &str,
     password: &str,
 ) -> bool {
-    if let Some(tenant_map) = security_manager.metadata.user_info.get(tenant) {
-        if let Some(user) = tenant_map.get(username) {
-            return if let Some(salt) = &user.salt {
-                let hash = Sha256::digest(format!("{}{}", salt, password).as_bytes());
-                let hex_hash: String = hash.iter().map(|b| format!("{:02x}", b)).collect();
-                user.password == hex_hash
-            } else {
-                user.password == password
+    let Some(user) = security_manager
+        .metadata
+        .user_info
+        .get(tenant)
+        .and_then(|tenant_map| tenant_map.get(username).map(|user| user.clone()))
+    else {
+        return false;
+    };
+
+    if !verify_password(&user, password).await {
+        return false;
+    }
+
+    let policy = broker_config().password_policy.clone();
+    let key = (tenant.to_string(), username.to_string());
+    if policy.rehash_on_login
+        && user.hash_algorithm() != policy.hash_algorithm
+        && !security_manager.rehash_queue.contains_key(&key)
+    {
+        // Hash in the background so the login does not wait for it, only the
+        // new hash is queued.
+        let security_manager = security_manager.clone();
+        let password = password.to_string();
+        tokio::spawn(async move {
+            match hash_password(&policy, &password).await {
+                Ok((hash, salt)) => {
+                    security_manager.rehash_queue.insert(
+                        key,
+                        RehashedPassword {
+                            password: hash,
+                            salt,
+                            hash_algorithm: policy.hash_algorithm,
+                            replaces: user.password,
+                        },
+                    );
+                }
+                Err(e) => {
+                    warn!(tenant = %key.0, username = %key.1, error = %e, "Failed to rehash user password");
+                }
+            }
+        });
+    }
+    true
+}
+
+/// Checks `password` against the stored entry. bcrypt and Argon2 are slow by
+/// design and run on the blocking thread pool.
+pub async fn verify_password(user: &SecurityUser, password: &str) -> bool {
+    match user.hash_algorithm() {
+        PasswordHashAlgorithm::Bcrypt | PasswordHashAlgorithm::Argon2 => {
+            let user = user.clone();
+            let password = password.to_string();
+            tokio::task::spawn_blocking(move || verify_password_hash(&user, &password))
+                .await
+                .unwrap_or(false)
+        }
+        _ => verify_password_hash(user, password),
+    }
+}
+
+fn verify_password_hash(user: &SecurityUser, password: &str) -> bool {
+    let salt = user.salt.as_deref().unwrap_or_default();
+    match user.hash_algorithm() {
+        PasswordHashAlgorithm::Plain => user.password.as_bytes().ct_eq(password.as_bytes()).into(),
+        PasswordHashAlgorithm::Md5 => {
+            hex_digest_eq(&user.password, &md5::compute(format!("{salt}{password}")).0)
+        }
+        PasswordHashAlgorithm::Sha256 => {
+            hex_digest_eq(&user.password, &Sha256::digest(format!("{salt}{password}")))
+        }
+        PasswordHashAlgorithm::Bcrypt => bcrypt::verify(password, &user.password).unwrap_or(false),
+        PasswordHashAlgorithm::Argon2 => PasswordHash::new(&user.password)
+            .map(|hash| {
+                Argon2::default()
+                    .verify_password(password.as_bytes(), &hash)
+                    .is_ok()
+            })
+            .unwrap_or(false),
+    }
+}
+
+/// Compares a stored hex digest, in either case, without leaking how many
+/// leading bytes match.
+fn hex_digest_eq(stored: &str, digest: &[u8]) -> bool {
+    hex::decode(stored).is_ok_and(|stored| stored.ct_eq(digest).into())
+}
+
+/// Hashes `password` with the policy's algorithm. Returns the value to store
+/// as `SecurityUser::password` and, for algorithms that keep it separately,
+/// the generated per-user salt. Runs on the blocking thread pool, see
+/// [`verify_password`].
+pub async fn hash_password(
+    policy: &PasswordPolicy,
+    password: &str,
+) -> Result<(String, Option<String>), CommonError> {
+    let policy = policy.clone();
+    let password = password.to_string();
+    tokio::task::spawn_blocking(move || hash_password_blocking(&policy, &password))
+        .await
+        .map_err(|e| CommonError::CommonError(e.to_string()))?
+}
+
+fn hash_password_blocking(
+    policy: &PasswordPolicy,
+    password: &str,
+) -> Result<(String, Option<String>), CommonError> {
+    let mut salt = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut salt);
+
+    match policy.hash_algorithm {
+        PasswordHashAlgorithm::Plain => Ok((password.to_string(), None)),
+        PasswordHashAlgorithm::Md5 => {
+            let salt = hex::encode(salt);
+            let hash = format!("{:x}", md5::compute(format!("{salt}{password}")));
+            Ok((hash, Some(salt)))
+        }
+        PasswordHashAlgorithm::Sha256 => {
+            let salt = hex::encode(salt);
+            let hash = hex::encode(Sha256::digest(format!("{salt}{password}")));
+            Ok((hash, Some(salt)))
+        }
+        PasswordHashAlgorithm::Bcrypt => {
+            let hash = bcrypt::hash_with_salt(password, policy.bcrypt_cost, salt)
+                .map_err(|e| CommonError::CommonError(e.to_string()))?;
+            Ok((hash.to_string(), None))
+        }
+        PasswordHashAlgorithm::Argon2 => {
+            let salt = SaltString::encode_b64(&salt)
+                .map_err(|e| CommonError::CommonError(e.to_string()))?;
+            let hash = Argon2::default()
+                .hash_password(password.as_bytes(), &salt)
+                .map_err(|e| CommonError::CommonError(e.to_string()))?;
+            Ok((hash.to_string(), None))
+        }
+    }
+}
+
+pub fn check_password_policy(policy: &PasswordPolicy, password: &str) -> Result<(), String> {
+    if password.chars().count() < policy.min_length {
+        return Err(format!(
+            "password must be at least {} characters long",
+            policy.min_length
+        ));
+    }
+    let rules = [
+        (
+            policy.require_uppercase,
+            password.chars().any(|c| c.is_uppercase()),
+            "an uppercase letter",
+        ),
+        (
+            policy.require_lowercase,
+            password.chars().any(|c| c.is_lowercase()),
+            "a lowercase letter",
+        ),
+        (
+            policy.require_digit,
+            password.chars().any(|c| c.is_ascii_digit()),
+            "a digit",
+        ),
+        (
+            policy.require_special,
+            password.chars().any(|c| !c.is_alphanumeric()),
+            "a special character",
+        ),
+    ];
+    for (required, present, name) in rules {
+        if required && !present {
+            return Err(format!("password must contain {name}"));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(
+        password: String,
+        salt: Option<String>,
+        algorithm: PasswordHashAlgorithm,
+    ) -> SecurityUser {
+        SecurityUser {
+            tenant: "default".to_string(),
+            username: "user".to_string(),
+            password,
+            salt,
+            is_superuser: false,
+            create_time: 0,
+            hash_algorithm: Some(algorithm),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hash_and_verify_password() {
+        for algorithm in [
+            PasswordHashAlgorithm::Plain,
+            PasswordHashAlgorithm::Md5,
+            PasswordHashAlgorithm::Sha256,
+            PasswordHashAlgorithm::Bcrypt,
+            PasswordHashAlgorithm::Argon2,
+        ] {
+            let policy = PasswordPolicy {
+                hash_algorithm: algorithm,
+                bcrypt_cost: 4,
+                ..Default::default()
             };
+            let (hash, salt) = hash_password(&policy, "secret").await.unwrap();
+            let stored = user(hash, salt, algorithm);
+            assert!(verify_password(&stored, "secret").await, "{algorithm:?}");
+            assert!(!verify_password(&stored, "Secret").await, "{algorithm:?}");
         }
     }
-    false
+
+    #[tokio::test]
+    async fn test_verify_legacy_password() {
+        let mut plain = user("secret".to_string(), None, PasswordHashAlgorithm::Plain);
+        plain.hash_algorithm = None;
+        assert_eq!(plain.hash_algorithm(), PasswordHashAlgorithm::Plain);
+        assert!(verify_password(&plain, "secret").await);
+        assert!(!verify_password(&plain, "secre").await);
+
+        let hash = hex::encode(Sha256::digest("saltsecret"));
+        let mut salted = user(
+            hash,
+            Some("salt".to_string()),
+            PasswordHashAlgorithm::Sha256,
+        );
+        salted.hash_algorithm = None;
+        assert_eq!(salted.hash_algorithm(), PasswordHashAlgorithm::Sha256);
+        assert!(verify_password(&salted, "secret").await);
+        assert!(!verify_password(&salted, "saltsecret").await);
+
+        salted.password = salted.password.to_uppercase();
+        assert!(verify_password(&salted, "secret").await);
+    }
+
+    #[test]
+    fn test_check_password_policy() {
+        let policy = PasswordPolicy {
+            min_length: 8,
+            require_uppercase: true,
+            require_digit: true,
+            require_special: true,
+            ..Default::default()
+        };
+        assert!(check_password_policy(&policy, "Ab1!").is_err());
+        assert!(check_password_policy(&policy, "abcdefg1!").is_err());
+        assert!(check_password_policy(&policy, "Abcdefgh!").is_err());
+        assert!(check_password_policy(&policy, "Abcdefg1").is_err());
+        assert!(check_password_policy(&policy, "Abcdefg1!").is_ok());
+        assert!(check_password_policy(&PasswordPolicy::default(), "a").is_ok());
+    }
 }
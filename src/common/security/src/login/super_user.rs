@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::login::password::hash_password;
 use crate::manager::SecurityManager;
 use crate::storage::user::UserStorage;
 use common_base::error::ResultCommonError;
@@ -24,25 +25,29 @@
 
 pub async fn try_init_system_user(client_pool: &Arc<ClientPool>) -> ResultCommonError {
     let conf = broker_config();
-    let system_user_info = SecurityUser {
-        tenant: DEFAULT_TENANT.to_string(),
-        username: conf.mqtt_runtime.default_user.clone(),
-        password: conf.mqtt_runtime.default_password.clone(),
-        salt: None,
-        is_superuser: true,
-        create_time: now_second(),
-    };
     let user_storage = UserStorage::new(client_pool.clone());
     let res = user_storage
         .get_user(
-            system_user_info.tenant.clone(),
-            system_user_info.username.clone(),
+            DEFAULT_TENANT.to_string(),
+            conf.mqtt_runtime.default_user.clone(),
         )
         .await?;
     if res.is_some() {
         return Ok(());
     }
-    user_storage.save_user(system_user_info.clone()).await?;
+
+    let (password, salt) =
+        hash_password(&conf.password_policy, &conf.mqtt_runtime.default_password).await?;
+    let system_user_info = SecurityUser {
+        tenant: DEFAULT_TENANT.to_string(),
+        username: conf.mqtt_runtime.default_user.clone(),
+        password,
+        salt,
+        is_superuser: true,
+        create_time: now_second(),
+        hash_algorithm: Some(conf.password_policy.hash_algorithm),
+    };
+    user_storage.save_user(system_user_info).await?;
     Ok(())
 }
 
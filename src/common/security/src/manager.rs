@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::login::password::RehashedPassword;
 use crate::metadata::SecurityMetadata;
 use crate::third::build_storage_driver;
 use crate::third::storage_trait::AuthStorageAdapter;
@@ -30,6 +31,10 @@
 pub struct SecurityManager {
     storage_drivers: Arc<DashMap<String, ArcAuthStorageAdapter>>,
     pub metadata: SecurityMetadata,
+    /// New hashes for users that logged in against an entry stored with an
+    /// outdated algorithm, keyed by (tenant, username). Drained by the
+    /// password rehash task.
+    pub rehash_queue: Arc<DashMap<(String, String), RehashedPassword>>,
 }
 
 impl SecurityManager {
@@ -37,6 +42,7 @@ pub fn new() -> SecurityManager {
         SecurityManager {
             storage_drivers: Arc::new(DashMap::new()),
             metadata: SecurityMetadata::new(),
+            rehash_queue: Arc::new(DashMap::new()),
         }
     }
 
@@ -12,11 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::auth::blacklist::compile_blacklist_regex;
 use dashmap::DashMap;
 use metadata_struct::auth::acl::{EnumAclResourceType, SecurityAcl};
 use metadata_struct::auth::blacklist::{EnumBlackListType, SecurityBlackList};
 use metadata_struct::auth::user::SecurityUser;
 use metadata_struct::mqtt::auth::authn_config::AuthnConfig;
+use regex::Regex;
+use tracing::warn;
 
 #[derive(Clone)]
 pub struct SecurityMetadata {
@@ -43,6 +46,17 @@ pub struct SecurityMetadata {
     pub blacklist_user_match: DashMap<String, Vec<SecurityBlackList>>,
     pub blacklist_client_id_match: DashMap<String, Vec<SecurityBlackList>>,
     pub blacklist_ip_match: DashMap<String, Vec<SecurityBlackList>>,
+
+    // (tenant, Vec<BlackListRegex>)
+    pub blacklist_user_regex: DashMap<String, Vec<BlackListRegex>>,
+    pub blacklist_client_id_regex: DashMap<String, Vec<BlackListRegex>>,
+}
+
+/// A regex blacklist entry, compiled once when it is loaded into the cache.
+#[derive(Clone, Debug)]
+pub struct BlackListRegex {
+    pub blacklist: SecurityBlackList,
+    pub regex: Regex,
 }
 
 impl Default for SecurityMetadata {
@@ -66,6 +80,8 @@ pub fn new() -> Self {
             blacklist_user_match: DashMap::with_capacity(2),
             blacklist_client_id_match: DashMap::with_capacity(2),
             blacklist_ip_match: DashMap::with_capacity(2),
+            blacklist_user_regex: DashMap::with_capacity(2),
+            blacklist_client_id_regex: DashMap::with_capacity(2),
 
             // acl
             acl_user: DashMap::with_capacity(2),
@@ -87,6 +103,15 @@ pub fn del_user(&self, tenant: &str, username: &str) {
         }
     }
 
+    /// Tenants in which `username` is registered.
+    pub fn get_user_tenants(&self, username: &str) -> Vec<String> {
+        self.user_info
+            .iter()
+            .filter(|entry| entry.value().contains_key(username))
+            .map(|entry| entry.key().clone())
+            .collect()
+    }
+
     // ACL
     pub fn add_acl(&self, acl: SecurityAcl) {
         self.parse_mqtt_acl(acl);
@@ -101,11 +126,12 @@ pub fn parse_mqtt_acl(&self, acl: SecurityAcl) {
             EnumAclResourceType::ClientId => &self.acl_client_id,
             EnumAclResourceType::User => &self.acl_user,
         };
-        map.entry(acl.tenant.clone())
-            .or_default()
-            .entry(acl.resource_name.clone())
-            .or_default()
-            .push(acl);
+        let tenant_map = map.entry(acl.tenant.clone()).or_default();
+        let mut list = tenant_map.entry(acl.resource_name.clone()).or_default();
+        list.retain(|item| item.name != acl.name);
+        list.push(acl);
+        // highest priority first, insertion order within a priority
+        list.sort_by(|a, b| b.priority.cmp(&a.priority));
     }
 
     pub fn remove_mqtt_acl(&self, acl: SecurityAcl) {
@@ -219,6 +245,48 @@ fn parse_mqtt_blacklist(&self, blacklist: SecurityBlackList) {
                     .or_default()
                     .push(blacklist);
             }
+            EnumBlackListType::ClientIdRegex => {
+                Self::push_blacklist_regex(&self.blacklist_client_id_regex, blacklist);
+            }
+            EnumBlackListType::UserRegex => {
+                Self::push_blacklist_regex(&self.blacklist_user_regex, blacklist);
+            }
+        }
+    }
+
+    fn push_blacklist_regex(
+        map: &DashMap<String, Vec<BlackListRegex>>,
+        blacklist: SecurityBlackList,
+    ) {
+        let regex = match compile_blacklist_regex(&blacklist.resource_name) {
+            Ok(regex) => regex,
+            Err(e) => {
+                warn!(
+                    name = %blacklist.name,
+                    pattern = %blacklist.resource_name,
+                    error = %e,
+                    "Ignoring blacklist with invalid regex"
+                );
+                return;
+            }
+        };
+        let mut list = map.entry(blacklist.tenant.clone()).or_default();
+        list.retain(|item| item.blacklist.name != blacklist.name);
+        list.push(BlackListRegex { blacklist, regex });
+    }
+
+    fn remove_blacklist_regex(
+        map: &DashMap<String, Vec<BlackListRegex>>,
+        tenant: &str,
+        name: &str,
+    ) {
+        let mut remove_key = false;
+        if let Some(mut data) = map.get_mut(tenant) {
+            data.retain(|item| item.blacklist.name != name);
+            remove_key = data.is_empty();
+        }
+        if remove_key {
+            map.remove(tenant);
         }
     }
 
@@ -269,6 +337,20 @@ pub fn remove_mqtt_blacklist(&self, blacklist: SecurityBlackList) {
                     self.blacklist_ip_match.remove(&blacklist.tenant);
                 }
             }
+            EnumBlackListType::ClientIdRegex => {
+                Self::remove_blacklist_regex(
+                    &self.blacklist_client_id_regex,
+                    &blacklist.tenant,
+                    &blacklist.name,
+                );
+            }
+            EnumBlackListType::UserRegex => {
+                Self::remove_blacklist_regex(
+                    &self.blacklist_user_regex,
+                    &blacklist.tenant,
+                    &blacklist.name,
+                );
+            }
         }
     }
 
@@ -292,6 +374,12 @@ pub fn get_all_blacklist(&self) -> Vec<SecurityBlackList> {
         for entry in self.blacklist_ip_match.iter() {
             data.extend(entry.value().iter().cloned());
         }
+        for entry in self.blacklist_user_regex.iter() {
+            data.extend(entry.value().iter().map(|item| item.blacklist.clone()));
+        }
+        for entry in self.blacklist_client_id_regex.iter() {
+            data.extend(entry.value().iter().map(|item| item.blacklist.clone()));
+        }
         data.sort_by(|a, b| {
             (
                 a.blacklist_type.to_string(),
@@ -329,6 +417,12 @@ pub fn get_blacklist_by_tenant(&self, tenant: &str) -> Vec<SecurityBlackList> {
         if let Some(v) = self.blacklist_ip_match.get(tenant) {
             data.extend(v.iter().cloned());
         }
+        if let Some(v) = self.blacklist_user_regex.get(tenant) {
+            data.extend(v.iter().map(|item| item.blacklist.clone()));
+        }
+        if let Some(v) = self.blacklist_client_id_regex.get(tenant) {
+            data.extend(v.iter().map(|item| item.blacklist.clone()));
+        }
         data
     }
 
@@ -379,6 +473,7 @@ pub async fn parse_mqtt_acl_test() {
             ip: "".to_string(),
             action: EnumAclAction::All,
             permission: EnumAclPermission::Allow,
+            priority: 0,
         };
         acl_metadata.parse_mqtt_acl(user_acl.clone());
         acl_metadata.parse_mqtt_acl(SecurityAcl {
@@ -404,6 +499,7 @@ pub async fn parse_mqtt_acl_test() {
             ip: "".to_string(),
             action: EnumAclAction::All,
             permission: EnumAclPermission::Allow,
+            priority: 0,
         });
         assert_eq!(acl_metadata.get_all_acl().len(), 3);
 
@@ -417,6 +513,7 @@ pub async fn parse_mqtt_acl_test() {
             ip: "".to_string(),
             action: EnumAclAction::All,
             permission: EnumAclPermission::Allow,
+            priority: 0,
         });
         assert_eq!(acl_metadata.get_all_acl().len(), 2);
 
@@ -430,6 +527,7 @@ pub async fn parse_mqtt_acl_test() {
             ip: "".to_string(),
             action: EnumAclAction::All,
             permission: EnumAclPermission::Allow,
+            priority: 0,
         });
         assert_eq!(acl_metadata.get_all_acl().len(), 1);
         let key_removed = acl_metadata
@@ -16,13 +16,18 @@
 use common_base::error::ResultCommonError;
 use common_config::broker::broker_config;
 use grpc_clients::meta::mqtt::call::{
-    placement_create_user, placement_delete_user, placement_list_user,
+    placement_create_user, placement_delete_user, placement_list_user, placement_list_user_all,
+    placement_update_user,
 };
 use grpc_clients::pool::ClientPool;
 use metadata_struct::auth::user::SecurityUser;
-use protocol::meta::meta_service_mqtt::{CreateUserRequest, DeleteUserRequest, ListUserRequest};
+use protocol::meta::meta_service_mqtt::{
+    CreateUserRequest, DeleteUserRequest, ListUserRequest, UpdateUserRequest,
+};
 use std::sync::Arc;
 
+const USER_LIST_PAGE_SIZE: u32 = 1000;
+
 pub struct UserStorage {
     client_pool: Arc<ClientPool>,
 }
@@ -42,6 +47,17 @@ pub async fn save_user(&self, user_info: SecurityUser) -> ResultCommonError {
         Ok(())
     }
 
+    pub async fn update_user(&self, user_info: SecurityUser) -> ResultCommonError {
+        let config = broker_config();
+        let request = UpdateUserRequest {
+            tenant: user_info.tenant.clone(),
+            user_name: user_info.username.clone(),
+            content: user_info.encode()?,
+        };
+        placement_update_user(&self.client_pool, &config.get_meta_service_addr(), request).await?;
+        Ok(())
+    }
+
     pub async fn delete_user(&self, tenant: String, user_name: String) -> ResultCommonError {
         let config = broker_config();
         let request = DeleteUserRequest { tenant, user_name };
@@ -59,6 +75,7 @@ pub async fn get_user(
         let request = ListUserRequest {
             tenant,
             user_name: username.clone(),
+            ..Default::default()
         };
 
         let reply =
@@ -78,12 +95,16 @@ pub async fn user_list(&self) -> Result<Vec<SecurityUser>, CommonError> {
             ..Default::default()
         };
 
-        let reply =
-            placement_list_user(&self.client_pool, &config.get_meta_service_addr(), request)
-                .await?;
+        let users = placement_list_user_all(
+            &self.client_pool,
+            &config.get_meta_service_addr(),
+            request,
+            USER_LIST_PAGE_SIZE,
+        )
+        .await?;
 
-        let mut results = Vec::with_capacity(reply.users.len());
-        for raw in reply.users {
+        let mut results = Vec::with_capacity(users.len());
+        for raw in users {
             results.push(SecurityUser::decode(&raw)?);
         }
         Ok(results)
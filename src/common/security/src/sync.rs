@@ -13,13 +13,16 @@
 // limitations under the License.
 
 use crate::manager::SecurityManager;
+use crate::storage::user::UserStorage;
 use common_base::{
     error::ResultCommonError,
     task::{TaskKind, TaskSupervisor},
     tools::loop_select_ticket,
 };
+use grpc_clients::pool::ClientPool;
 use std::sync::Arc;
 use tokio::sync::broadcast;
+use tracing::{info, warn};
 
 pub fn start_auth_sync_thread(
     security_manager: Arc<SecurityManager>,
@@ -104,3 +107,73 @@ fn sync_blacklist_cache(
         },
     );
 }
+
+/// Rewrites the passwords queued by successful logins against entries stored
+/// with an algorithm other than `password_policy.hash_algorithm`. Only users
+/// kept by the meta service are rewritten; the update reaches every broker's
+/// cache through the meta service.
+pub fn start_password_rehash_thread(
+    security_manager: Arc<SecurityManager>,
+    client_pool: Arc<ClientPool>,
+    task_supervisor: Arc<TaskSupervisor>,
+    stop_send: broadcast::Sender<bool>,
+) {
+    task_supervisor.spawn(
+        TaskKind::MQTTSecurityPasswordRehash.to_string(),
+        async move {
+            let ac_fn = async || -> ResultCommonError {
+                rehash_queued_passwords(&security_manager, &client_pool).await;
+                Ok(())
+            };
+            loop_select_ticket(ac_fn, 1000, &stop_send).await;
+        },
+    );
+}
+
+async fn rehash_queued_passwords(
+    security_manager: &Arc<SecurityManager>,
+    client_pool: &Arc<ClientPool>,
+) {
+    let user_storage = UserStorage::new(client_pool.clone());
+    let keys: Vec<(String, String)> = security_manager
+        .rehash_queue
+        .iter()
+        .map(|entry| entry.key().clone())
+        .collect();
+
+    for key in keys {
+        let Some(((tenant, username), rehashed)) = security_manager.rehash_queue.remove(&key)
+        else {
+            continue;
+        };
+
+        let mut user = match user_storage
+            .get_user(tenant.clone(), username.clone())
+            .await
+        {
+            Ok(Some(user)) => user,
+            Ok(None) => continue,
+            Err(e) => {
+                warn!(tenant = %tenant, username = %username, error = %e, "Failed to load user for password rehash");
+                continue;
+            }
+        };
+
+        // Skip entries changed since the login was checked.
+        if user.password != rehashed.replaces {
+            continue;
+        }
+
+        let from = user.hash_algorithm();
+        user.password = rehashed.password;
+        user.salt = rehashed.salt;
+        user.hash_algorithm = Some(rehashed.hash_algorithm);
+
+        if let Err(e) = user_storage.update_user(user.clone()).await {
+            warn!(tenant = %tenant, username = %username, error = %e, "Failed to save rehashed user password");
+            continue;
+        }
+        security_manager.metadata.add_user(user);
+        info!(tenant = %tenant, username = %username, from = ?from, to = ?rehashed.hash_algorithm, "User password rehashed");
+    }
+}
@@ -201,6 +201,7 @@ async fn read_all_user(&self) -> Result<Vec<SecurityUser>, CommonError> {
                 salt: doc.get_str("salt").ok().map(|v| v.to_string()),
                 is_superuser: Self::parse_is_superuser(doc.get("is_superuser")),
                 create_time: Self::parse_created_to_seconds(doc.get("created")),
+                hash_algorithm: None,
             });
         }
 
@@ -273,6 +274,7 @@ async fn read_all_acl(&self) -> Result<Vec<SecurityAcl>, CommonError> {
                     ip: ip.clone(),
                     action,
                     permission,
+                    priority: 0,
                 });
             }
         }
@@ -132,6 +132,7 @@ async fn read_all_user(&self) -> Result<Vec<SecurityUser>, CommonError> {
                 salt,
                 is_superuser: is_superuser == 1,
                 create_time: Self::parse_created_to_seconds(created),
+                hash_algorithm: None,
             });
         }
         Ok(results)
@@ -193,6 +194,7 @@ async fn read_all_acl(&self) -> Result<Vec<SecurityAcl>, CommonError> {
                     5 => EnumAclAction::Qos,
                     _ => return Err(CommonError::InvalidAclAction),
                 },
+                priority: 0,
             };
             results.push(acl);
         }
@@ -141,6 +141,7 @@ async fn read_all_user(&self) -> Result<Vec<SecurityUser>, CommonError> {
                         },
                         is_superuser: redis_user.is_superuser == 1,
                         create_time: redis_user.created.unwrap_or_else(now_second),
+                        hash_algorithm: None,
                     });
                 }
                 Err(e) => {
@@ -201,6 +202,7 @@ async fn read_all_acl(&self) -> Result<Vec<SecurityAcl>, CommonError> {
                             5 => EnumAclAction::Qos,
                             _ => return Err(CommonError::InvalidAclAction),
                         },
+                        priority: 0,
                     };
                     results.push(acl);
                 }
@@ -0,0 +1,219 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Exactly-once offset commit for connectors.
+//!
+//! After every batch a connector commits a [`ConnectorCheckpoint`] to
+//! meta-service: the offsets its next read starts at, together with the id of
+//! the sink transaction that wrote the batch. Both are one record, so they are
+//! committed atomically, and only the broker the connector is assigned to may
+//! commit. On start the connector loads its checkpoint, passes it to
+//! [`ConnectorSink::resume`](crate::traits::ConnectorSink::resume) and reads
+//! from its offsets, so a restart or failover neither skips nor re-reads
+//! committed records.
+//!
+//! A batch the sink wrote but the connector did not checkpoint before it
+//! stopped is written again after the restart. Transactional sinks recognize
+//! it by the transaction id in `resume`; sinks that deduplicate by key get the
+//! same result from the ids of [`IdempotentProducer`].
+
+use crate::storage::connector::ConnectorStorage;
+use common_base::error::common::CommonError;
+use common_base::error::ResultCommonError;
+use common_base::tools::now_second;
+use common_config::broker::broker_config;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::connector::checkpoint::ConnectorCheckpoint;
+use metadata_struct::storage::record::StorageRecord;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+pub struct Checkpointer {
+    storage: ConnectorStorage,
+    last: ConnectorCheckpoint,
+}
+
+impl Checkpointer {
+    /// Load the last committed checkpoint of a connector.
+    pub async fn load(
+        client_pool: &Arc<ClientPool>,
+        tenant: &str,
+        connector_name: &str,
+    ) -> Result<Self, CommonError> {
+        let storage = ConnectorStorage::new(client_pool.clone());
+        let last = storage
+            .get_checkpoint(connector_name)
+            .await?
+            .unwrap_or_else(|| ConnectorCheckpoint {
+                tenant: tenant.to_string(),
+                connector_name: connector_name.to_string(),
+                ..Default::default()
+            });
+        Ok(Checkpointer { storage, last })
+    }
+
+    /// Last committed checkpoint, `None` before the first commit.
+    pub fn committed(&self) -> Option<&ConnectorCheckpoint> {
+        (self.last.sequence > 0).then_some(&self.last)
+    }
+
+    /// Commit that `records` were delivered, by the sink transaction
+    /// `transaction_id` if the sink has transactions.
+    pub async fn commit(
+        &mut self,
+        records: &[StorageRecord],
+        transaction_id: Option<String>,
+    ) -> ResultCommonError {
+        let checkpoint = next_checkpoint(
+            &self.last,
+            broker_config().broker_id,
+            records,
+            transaction_id,
+            now_second(),
+        );
+        self.storage.commit_checkpoint(&checkpoint).await?;
+        self.last = checkpoint;
+        Ok(())
+    }
+}
+
+/// Checkpoint after `records` were delivered: every shard of the batch
+/// resumes after its highest offset, other shards keep their offsets.
+fn next_checkpoint(
+    prev: &ConnectorCheckpoint,
+    broker_id: u64,
+    records: &[StorageRecord],
+    transaction_id: Option<String>,
+    now: u64,
+) -> ConnectorCheckpoint {
+    let mut checkpoint = ConnectorCheckpoint {
+        broker_id,
+        sequence: prev.sequence + 1,
+        transaction_id: transaction_id.unwrap_or_default(),
+        commit_time: now,
+        ..prev.clone()
+    };
+    for record in records {
+        let next = record.metadata.offset + 1;
+        let offset = checkpoint
+            .source_offsets
+            .entry(record.metadata.shard.clone())
+            .or_insert(next);
+        *offset = (*offset).max(next);
+    }
+    checkpoint
+}
+
+/// Deterministic ids for what a sink writes. A batch replayed after a restart
+/// is read from the same offsets and gets the same ids, so a sink that
+/// deduplicates by id (document ids, object keys, message ids) stores it once.
+pub struct IdempotentProducer {
+    connector_name: String,
+}
+
+impl IdempotentProducer {
+    pub fn new(connector_name: impl Into<String>) -> Self {
+        IdempotentProducer {
+            connector_name: connector_name.into(),
+        }
+    }
+
+    /// Id of one record.
+    pub fn record_id(&self, record: &StorageRecord) -> String {
+        format!(
+            "{}-{}-{}",
+            self.connector_name, record.metadata.shard, record.metadata.offset
+        )
+    }
+
+    /// Id of a batch, the offset range it covers in each shard. Usable as the
+    /// transaction id committed with the checkpoint.
+    pub fn batch_id(&self, records: &[StorageRecord]) -> String {
+        let mut ranges: BTreeMap<&str, (u64, u64)> = BTreeMap::new();
+        for record in records {
+            let offset = record.metadata.offset;
+            let range = ranges
+                .entry(record.metadata.shard.as_str())
+                .or_insert((offset, offset));
+            range.0 = range.0.min(offset);
+            range.1 = range.1.max(offset);
+        }
+        let ranges: Vec<String> = ranges
+            .into_iter()
+            .map(|(shard, (first, last))| format!("{shard}:{first}-{last}"))
+            .collect();
+        format!("{}-{}", self.connector_name, ranges.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use metadata_struct::storage::record::StorageRecordMetadata;
+
+    fn record(shard: &str, offset: u64) -> StorageRecord {
+        StorageRecord {
+            metadata: StorageRecordMetadata::build(offset, shard.to_string(), 0),
+            protocol_data: None,
+            data: Bytes::new(),
+        }
+    }
+
+    #[test]
+    fn test_next_checkpoint() {
+        let prev = ConnectorCheckpoint {
+            connector_name: "c1".to_string(),
+            sequence: 4,
+            source_offsets: [("s0".to_string(), 10), ("s1".to_string(), 3)].into(),
+            transaction_id: "tx-4".to_string(),
+            broker_id: 1,
+            ..Default::default()
+        };
+        let records = [record("s0", 12), record("s0", 10), record("s2", 0)];
+        let checkpoint = next_checkpoint(&prev, 2, &records, None, 100);
+
+        assert_eq!(checkpoint.connector_name, "c1");
+        assert_eq!(checkpoint.broker_id, 2);
+        assert_eq!(checkpoint.sequence, 5);
+        assert_eq!(checkpoint.commit_time, 100);
+        assert!(checkpoint.transaction_id.is_empty());
+        assert_eq!(
+            checkpoint.source_offsets,
+            [
+                ("s0".to_string(), 13),
+                ("s1".to_string(), 3),
+                ("s2".to_string(), 1)
+            ]
+            .into()
+        );
+
+        let checkpoint = next_checkpoint(&checkpoint, 2, &[], Some("tx-6".to_string()), 101);
+        assert_eq!(checkpoint.sequence, 6);
+        assert_eq!(checkpoint.transaction_id, "tx-6");
+        assert_eq!(checkpoint.source_offsets["s0"], 13);
+    }
+
+    #[test]
+    fn test_idempotent_producer_ids() {
+        let producer = IdempotentProducer::new("c1");
+        assert_eq!(producer.record_id(&record("s0", 7)), "c1-s0-7");
+
+        let batch = [record("s1", 4), record("s0", 9), record("s0", 7)];
+        assert_eq!(producer.batch_id(&batch), "c1-s0:7-9,s1:4-4");
+        // A replay reads the same records, possibly in another order.
+        let replay = [record("s0", 7), record("s0", 9), record("s1", 4)];
+        assert_eq!(producer.batch_id(&replay), producer.batch_id(&batch));
+    }
+}
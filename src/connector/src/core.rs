@@ -15,11 +15,13 @@
 use common_base::error::common::CommonError;
 use common_base::{error::ResultCommonError, tools::loop_select_ticket};
 use common_config::broker::broker_config;
+use grpc_clients::meta::common::lock::DistributedLock;
 use grpc_clients::pool::ClientPool;
 use metadata_struct::connector::{
     status::MQTTStatus, ConnectorType, FailureHandlingStrategy, MQTTConnector,
 };
 use std::sync::Arc;
+use std::time::Duration;
 use storage_adapter::driver::StorageDriverManager;
 use tokio::sync::{
     broadcast,
@@ -37,7 +39,11 @@
     pulsar::start_pulsar_connector, rabbitmq::start_rabbitmq_connector,
     redis::start_redis_connector, s3::start_s3_connector, webhook::start_webhook_connector,
 };
-use crate::storage::connector::ConnectorStorage;
+use crate::{resource::ConnectorResourceUsage, storage::connector::ConnectorStorage};
+
+// Lease of the lock a broker holds while it runs a connector. A connector
+// moved to another broker starts there at the latest once this has passed.
+const CONNECTOR_LOCK_LEASE_MS: u64 = 15_000;
 
 #[derive(Clone)]
 pub struct BridgePluginReadConfig {
@@ -55,6 +61,7 @@ pub struct BridgePluginThread {
     pub send_fail_total: u64,
     pub stop_send: mpsc::Sender<bool>,
     pub last_msg: Option<String>,
+    pub resource_usage: ConnectorResourceUsage,
 }
 
 pub(crate) async fn start_connector_thread(
@@ -70,7 +77,8 @@ pub(crate) async fn start_connector_thread(
             &connector_manager,
             &client_pool,
             current_broker_id,
-        );
+        )
+        .await;
         gc_connectors(&connector_manager, &client_pool, current_broker_id).await;
         Ok(())
     };
@@ -78,7 +86,7 @@ pub(crate) async fn start_connector_thread(
     loop_select_ticket(ac_fn, 1000, &stop_send).await;
 }
 
-fn start_connectors(
+async fn start_connectors(
     storage_driver_manager: &Arc<StorageDriverManager>,
     connector_manager: &Arc<ConnectorManager>,
     client_pool: &Arc<ClientPool>,
@@ -96,6 +104,17 @@ fn start_connectors(
             continue;
         }
 
+        // Only the holder of the connector lock runs it, so a connector moved
+        // here waits until the previous broker has let go of it.
+        let lock = connector_lock(connector_manager, client_pool, &raw, current_broker_id);
+        if !lock.keep_alive().await {
+            debug!(
+                "Connector '{}' is still locked by another broker",
+                raw.connector_name
+            );
+            continue;
+        }
+
         info!(
             "Starting connector '{}' (type: {:?}, topic: {})",
             raw.connector_name, raw.connector_type, raw.topic_name
@@ -109,6 +128,7 @@ fn start_connectors(
             send_success_total: 0,
             stop_send,
             last_msg: None,
+            resource_usage: ConnectorResourceUsage::default(),
         };
 
         start_thread(
@@ -129,8 +149,13 @@ async fn gc_connectors(
 ) {
     for raw in connector_manager.get_all_connector_thread() {
         let should_stop = match connector_manager.get_connector(&raw.connector_name) {
-            Some(connector) => connector.broker_id != Some(current_broker_id),
-            None => true,
+            Some(connector) if connector.broker_id == Some(current_broker_id) => {
+                match connector_manager.get_connector_lock(&raw.connector_name) {
+                    Some(lock) => !lock.keep_alive().await,
+                    None => false,
+                }
+            }
+            _ => true,
         };
 
         if !should_stop {
@@ -141,6 +166,7 @@ async fn gc_connectors(
             warn!("Failed to stop connector '{}': {}", raw.connector_name, e);
         }
         connector_manager.remove_connector_thread(&raw.connector_name);
+        release_connector_lock(connector_manager, &raw.connector_name).await;
 
         if let Some(mut connector) = connector_manager.get_connector(&raw.connector_name) {
             connector.status = MQTTStatus::Idle;
@@ -153,6 +179,59 @@ async fn gc_connectors(
             }
         }
     }
+
+    // Drop the locks of connectors that never started here and are no
+    // longer assigned to this broker.
+    let stale_locks: Vec<String> = connector_manager
+        .connector_lock
+        .iter()
+        .map(|entry| entry.key().clone())
+        .filter(|name| {
+            connector_manager.get_connector_thread(name).is_none()
+                && connector_manager
+                    .get_connector(name)
+                    .is_none_or(|connector| connector.broker_id != Some(current_broker_id))
+        })
+        .collect();
+    for connector_name in stale_locks {
+        release_connector_lock(connector_manager, &connector_name).await;
+    }
+}
+
+fn connector_lock(
+    connector_manager: &Arc<ConnectorManager>,
+    client_pool: &Arc<ClientPool>,
+    connector: &MQTTConnector,
+    current_broker_id: u64,
+) -> Arc<DistributedLock> {
+    if let Some(lock) = connector_manager.get_connector_lock(&connector.connector_name) {
+        return lock;
+    }
+    let lock = Arc::new(DistributedLock::new(
+        client_pool.clone(),
+        broker_config().get_meta_service_addr(),
+        connector_lock_name(&connector.tenant, &connector.connector_name),
+        current_broker_id.to_string(),
+        Duration::from_millis(CONNECTOR_LOCK_LEASE_MS),
+    ));
+    connector_manager.add_connector_lock(&connector.connector_name, lock.clone());
+    lock
+}
+
+async fn release_connector_lock(connector_manager: &Arc<ConnectorManager>, connector_name: &str) {
+    let Some(lock) = connector_manager.remove_connector_lock(connector_name) else {
+        return;
+    };
+    if let Err(e) = lock.release().await {
+        warn!(
+            "Failed to release lock of connector '{}': {}",
+            connector_name, e
+        );
+    }
+}
+
+fn connector_lock_name(tenant: &str, connector_name: &str) -> String {
+    format!("connector/{}/{}", tenant, connector_name)
 }
 
 fn start_thread(
@@ -402,6 +481,7 @@ fn test_bridge_plugin_thread_creation() {
             send_success_total: 0,
             stop_send: stop_send.clone(),
             last_msg: None,
+            resource_usage: ConnectorResourceUsage::default(),
         };
 
         assert_eq!(thread.connector_name, "test_connector");
@@ -439,6 +519,7 @@ async fn test_stop_thread() {
             send_success_total: 0,
             stop_send: stop_send.clone(),
             last_msg: None,
+            resource_usage: ConnectorResourceUsage::default(),
         };
 
         assert!(stop_thread(thread).await.is_ok());
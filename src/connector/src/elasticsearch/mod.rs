@@ -36,6 +36,7 @@
 use common_base::error::common::CommonError;
 
 use super::{
+    checkpoint::IdempotentProducer,
     core::{BridgePluginReadConfig, BridgePluginThread},
     failure::FailureRecordInfo,
     loops::run_connector_loop,
@@ -46,6 +47,7 @@
 pub struct ElasticsearchBridgePlugin {
     connector: MQTTConnector,
     config: ElasticsearchConnectorConfig,
+    producer: IdempotentProducer,
 }
 
 impl ElasticsearchBridgePlugin {
@@ -59,7 +61,12 @@ pub fn new(connector: MQTTConnector) -> Result<Self, CommonError> {
                 ));
             }
         };
-        Ok(ElasticsearchBridgePlugin { connector, config })
+        let producer = IdempotentProducer::new(connector.connector_name.clone());
+        Ok(ElasticsearchBridgePlugin {
+            connector,
+            config,
+            producer,
+        })
     }
 
     async fn create_client(&self) -> Result<Elasticsearch, CommonError> {
@@ -168,7 +175,8 @@ async fn send_batch(
                 }
             };
 
-            let action = json!({"index": {}});
+            // A replayed record overwrites its document instead of adding one.
+            let action = json!({"index": {"_id": self.producer.record_id(record)}});
             body_parts.push(JsonBody::new(action));
             body_parts.push(JsonBody::new(doc));
         }
@@ -0,0 +1,279 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Write;
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, BinaryArray, RecordBatch, StringArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bytes::Bytes;
+use common_base::error::common::CommonError;
+use flate2::write::GzEncoder;
+use metadata_struct::{
+    connector::config_file_format::{FileCompression, FileFormat},
+    storage::{adapter_record::RecordHeader, record::StorageRecord},
+};
+use parquet::{
+    arrow::ArrowWriter,
+    basic::{Compression, GzipLevel, ZstdLevel},
+    file::properties::WriterProperties,
+};
+use serde::{Deserialize, Serialize};
+
+const ZSTD_LEVEL: i32 = 3;
+
+/// A record as written to files, with the payload after the ETL rule.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FileRecord {
+    pub pkid: u64,
+    pub key: Option<String>,
+    pub headers: Option<Vec<RecordHeader>>,
+    pub tags: Option<Vec<String>>,
+    pub data: Vec<u8>,
+    pub timestamp: u64,
+}
+
+impl FileRecord {
+    pub fn new(record: &StorageRecord, data: &Bytes) -> Self {
+        let headers = record.metadata.header.as_ref().map(|hs| {
+            hs.iter()
+                .map(|h| RecordHeader {
+                    name: h.name.clone(),
+                    value: h.value.clone(),
+                })
+                .collect()
+        });
+        FileRecord {
+            pkid: record.metadata.offset,
+            key: record.metadata.key.clone(),
+            headers,
+            tags: record.metadata.tags.clone(),
+            data: data.to_vec(),
+            timestamp: record.metadata.create_t,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonLineRecord<'a> {
+    offset: u64,
+    key: &'a Option<String>,
+    headers: &'a Option<Vec<RecordHeader>>,
+    tags: &'a Option<Vec<String>>,
+    timestamp: u64,
+    payload: String,
+    /// `utf8`, or `base64` for payloads that are not valid UTF-8.
+    payload_encoding: &'static str,
+}
+
+/// File name extension for the format, including the compression suffix.
+/// Parquet compresses its pages itself and keeps the plain extension.
+pub fn file_extension(format: &FileFormat, compression: &FileCompression) -> String {
+    match (format, compression.extension()) {
+        (FileFormat::Parquet, _) | (_, None) => format.extension().to_string(),
+        (_, Some(suffix)) => format!("{}.{}", format.extension(), suffix),
+    }
+}
+
+/// Encodes records into the content of one complete file.
+pub fn encode_file(
+    records: &[FileRecord],
+    format: &FileFormat,
+    compression: &FileCompression,
+) -> Result<Vec<u8>, CommonError> {
+    let data = match format {
+        FileFormat::Raw => {
+            let mut data = Vec::new();
+            for record in records {
+                data.extend_from_slice(&record.data);
+                data.push(b'\n');
+            }
+            data
+        }
+        FileFormat::Json => serde_json::to_vec(records).map_err(|e| {
+            CommonError::CommonError(format!("Failed to serialize records to JSON: {}", e))
+        })?,
+        FileFormat::JsonLines => encode_json_lines(records)?,
+        FileFormat::Parquet => return encode_parquet(records, compression),
+    };
+    compress(data, compression)
+}
+
+fn encode_json_lines(records: &[FileRecord]) -> Result<Vec<u8>, CommonError> {
+    let mut data = Vec::new();
+    for record in records {
+        let (payload, payload_encoding) = match std::str::from_utf8(&record.data) {
+            Ok(payload) => (payload.to_string(), "utf8"),
+            Err(_) => (STANDARD.encode(&record.data), "base64"),
+        };
+        let line = JsonLineRecord {
+            offset: record.pkid,
+            key: &record.key,
+            headers: &record.headers,
+            tags: &record.tags,
+            timestamp: record.timestamp,
+            payload,
+            payload_encoding,
+        };
+        serde_json::to_writer(&mut data, &line).map_err(|e| {
+            CommonError::CommonError(format!("Failed to serialize record to JSON: {}", e))
+        })?;
+        data.push(b'\n');
+    }
+    Ok(data)
+}
+
+fn encode_parquet(
+    records: &[FileRecord],
+    compression: &FileCompression,
+) -> Result<Vec<u8>, CommonError> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("offset", DataType::UInt64, false),
+        Field::new("key", DataType::Utf8, true),
+        Field::new("tags", DataType::Utf8, true),
+        Field::new("timestamp", DataType::UInt64, false),
+        Field::new("payload", DataType::Binary, false),
+    ]));
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(UInt64Array::from_iter_values(
+            records.iter().map(|r| r.pkid),
+        )),
+        Arc::new(StringArray::from_iter(
+            records.iter().map(|r| r.key.as_deref()),
+        )),
+        Arc::new(StringArray::from_iter(
+            records
+                .iter()
+                .map(|r| r.tags.as_ref().map(|tags| tags.join(","))),
+        )),
+        Arc::new(UInt64Array::from_iter_values(
+            records.iter().map(|r| r.timestamp),
+        )),
+        Arc::new(BinaryArray::from_iter_values(
+            records.iter().map(|r| r.data.as_slice()),
+        )),
+    ];
+    let batch = RecordBatch::try_new(schema.clone(), columns).map_err(|e| {
+        CommonError::CommonError(format!("Failed to build Parquet record batch: {}", e))
+    })?;
+
+    let codec = match compression {
+        FileCompression::None => Compression::UNCOMPRESSED,
+        FileCompression::Gzip => Compression::GZIP(GzipLevel::default()),
+        FileCompression::Zstd => Compression::ZSTD(ZstdLevel::default()),
+    };
+    let props = WriterProperties::builder().set_compression(codec).build();
+
+    let mut data = Vec::new();
+    let parquet_err = |e: parquet::errors::ParquetError| {
+        CommonError::CommonError(format!("Failed to write Parquet file: {}", e))
+    };
+    let mut writer = ArrowWriter::try_new(&mut data, schema, Some(props)).map_err(parquet_err)?;
+    writer.write(&batch).map_err(parquet_err)?;
+    writer.close().map_err(parquet_err)?;
+    Ok(data)
+}
+
+fn compress(data: Vec<u8>, compression: &FileCompression) -> Result<Vec<u8>, CommonError> {
+    match compression {
+        FileCompression::None => Ok(data),
+        FileCompression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&data)?;
+            Ok(encoder.finish()?)
+        }
+        FileCompression::Zstd => zstd::stream::encode_all(data.as_slice(), ZSTD_LEVEL)
+            .map_err(|e| CommonError::CommonError(format!("zstd compress failed: {}", e))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn records() -> Vec<FileRecord> {
+        vec![
+            FileRecord {
+                pkid: 1,
+                key: Some("k1".to_string()),
+                headers: None,
+                tags: Some(vec!["a".to_string(), "b".to_string()]),
+                data: b"hello".to_vec(),
+                timestamp: 100,
+            },
+            FileRecord {
+                pkid: 2,
+                key: None,
+                headers: None,
+                tags: None,
+                data: vec![0xff, 0xfe],
+                timestamp: 101,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_file_extension() {
+        assert_eq!(
+            file_extension(&FileFormat::JsonLines, &FileCompression::Gzip),
+            "jsonl.gz"
+        );
+        assert_eq!(
+            file_extension(&FileFormat::Raw, &FileCompression::None),
+            "log"
+        );
+        assert_eq!(
+            file_extension(&FileFormat::Parquet, &FileCompression::Zstd),
+            "parquet"
+        );
+    }
+
+    #[test]
+    fn test_encode_json_lines_gzip() {
+        let data = encode_file(&records(), &FileFormat::JsonLines, &FileCompression::Gzip).unwrap();
+        let mut content = String::new();
+        GzDecoder::new(data.as_slice())
+            .read_to_string(&mut content)
+            .unwrap();
+
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["payload"], "hello");
+        assert_eq!(lines[0]["payload_encoding"], "utf8");
+        assert_eq!(lines[1]["payload"], STANDARD.encode([0xff, 0xfe]));
+        assert_eq!(lines[1]["payload_encoding"], "base64");
+    }
+
+    #[test]
+    fn test_encode_raw_zstd() {
+        let data = encode_file(&records()[..1], &FileFormat::Raw, &FileCompression::Zstd).unwrap();
+        assert_eq!(
+            zstd::stream::decode_all(data.as_slice()).unwrap(),
+            b"hello\n"
+        );
+    }
+
+    #[test]
+    fn test_encode_parquet() {
+        let data = encode_file(&records(), &FileFormat::Parquet, &FileCompression::Zstd).unwrap();
+        assert_eq!(&data[..4], b"PAR1");
+        assert_eq!(&data[data.len() - 4..], b"PAR1");
+    }
+}
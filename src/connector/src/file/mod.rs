@@ -37,6 +37,19 @@
 use tokio::sync::mpsc::Receiver;
 use tracing::{error, info};
 
+use self::format::{file_extension, FileRecord};
+use self::rolling::{RollPolicy, RollTarget, RollingFileOptions, RollingFileWriter};
+
+pub mod format;
+pub mod rolling;
+
+pub enum LocalFileSink {
+    Append(FileWriter),
+    /// Formats and compressions that cannot be appended to are staged and
+    /// written as a whole file on each rotation.
+    Archive(RollingFileWriter),
+}
+
 pub struct FileWriter {
     writer: BufWriter<File>,
     current_path: PathBuf,
@@ -188,11 +201,52 @@ async fn process_data(&self, data: &Bytes) -> Result<Bytes, CommonError> {
         // let data = String::from_utf8_lossy(&processed_data);
         Ok(processed_data)
     }
+
+    fn roll_policy(&self) -> RollPolicy {
+        match self.config.rotation_strategy {
+            RotationStrategy::None => RollPolicy::default(),
+            RotationStrategy::Size => RollPolicy {
+                max_bytes: self.config.max_size_gb * 1024 * 1024 * 1024,
+                max_age_secs: 0,
+            },
+            RotationStrategy::Hourly => RollPolicy {
+                max_bytes: 0,
+                max_age_secs: 3600,
+            },
+            RotationStrategy::Daily => RollPolicy {
+                max_bytes: 0,
+                max_age_secs: 86400,
+            },
+        }
+    }
+
+    async fn open_archive(&self) -> Result<RollingFileWriter, CommonError> {
+        let path = Path::new(&self.config.local_file_path);
+        let dir = path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."));
+        let stem = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("file")
+            .to_string();
+
+        RollingFileWriter::open(RollingFileOptions {
+            staging_path: PathBuf::from(format!("{}.staging", self.config.local_file_path)),
+            policy: self.roll_policy(),
+            format: self.config.format.clone(),
+            compression: self.config.compression.clone(),
+            extension: file_extension(&self.config.format, &self.config.compression),
+            target: RollTarget::Local { dir, stem },
+        })
+        .await
+    }
 }
 
 #[async_trait]
 impl ConnectorSink for FileBridgePlugin {
-    type SinkResource = FileWriter;
+    type SinkResource = LocalFileSink;
 
     async fn validate(&self) -> Result<(), CommonError> {
         let file_path = Path::new(&self.config.local_file_path);
@@ -208,15 +262,21 @@ async fn validate(&self) -> Result<(), CommonError> {
     }
 
     async fn init_sink(&self) -> Result<Self::SinkResource, CommonError> {
-        FileWriter::new(self.config.clone()).await
+        if self.config.is_archive() {
+            return Ok(LocalFileSink::Archive(self.open_archive().await?));
+        }
+        Ok(LocalFileSink::Append(
+            FileWriter::new(self.config.clone()).await?,
+        ))
     }
 
     async fn send_batch(
         &self,
         records: &[StorageRecord],
-        writer: &mut FileWriter,
+        sink: &mut LocalFileSink,
     ) -> Result<Vec<FailureRecordInfo>, CommonError> {
         let mut fail_messages = Vec::new();
+        let mut archived = Vec::new();
         for record in records {
             let data = match self.process_data(&record.data).await {
                 Ok(data) => data,
@@ -232,15 +292,32 @@ async fn send_batch(
                     continue;
                 }
             };
-            writer.write(data.as_ref()).await?;
-            writer.write(b"\n").await?;
+            match sink {
+                LocalFileSink::Append(writer) => {
+                    writer.write(data.as_ref()).await?;
+                    writer.write(b"\n").await?;
+                }
+                LocalFileSink::Archive(_) => archived.push(FileRecord::new(record, &data)),
+            }
+        }
+
+        match sink {
+            LocalFileSink::Append(writer) => writer.flush().await?,
+            LocalFileSink::Archive(writer) => {
+                writer.append(&archived).await?;
+                if writer.should_roll() {
+                    writer.roll().await?;
+                }
+            }
         }
-        writer.flush().await?;
         Ok(fail_messages)
     }
 
-    async fn cleanup_sink(&self, writer: FileWriter) -> Result<(), CommonError> {
-        writer.shutdown().await?;
+    async fn cleanup_sink(&self, sink: LocalFileSink) -> Result<(), CommonError> {
+        match sink {
+            LocalFileSink::Append(writer) => writer.shutdown().await?,
+            LocalFileSink::Archive(writer) => writer.close().await?,
+        }
         Ok(())
     }
 }
@@ -341,6 +418,7 @@ async fn file_bridge_plugin_test() {
             rotation_strategy:
                 metadata_struct::connector::config_local_file::RotationStrategy::None,
             max_size_gb: 1,
+            ..Default::default()
         };
 
         // create such file
@@ -358,6 +436,7 @@ async fn file_bridge_plugin_test() {
             broker_id: None,
             create_time: now_second(),
             update_time: now_second(),
+            max_records_per_sec: None,
         })
         .unwrap();
 
@@ -43,10 +43,15 @@ async fn report_heartbeat(
     let mut heatbeats = Vec::new();
 
     for (connector_name, heartbeat_time) in connector_manager.get_all_heartbeats() {
+        let message_total = connector_manager
+            .get_connector_thread(&connector_name)
+            .map(|thread| thread.send_success_total)
+            .unwrap_or_default();
         heatbeats.push(ConnectorHeartbeatRaw {
             connector_name,
             heartbeat_time,
             broker_id: conf.broker_id,
+            message_total,
         });
     }
 
@@ -25,6 +25,7 @@
 };
 
 pub mod cassandra;
+pub mod checkpoint;
 pub mod clickhouse_connector;
 pub mod core;
 pub mod elasticsearch;
@@ -44,6 +45,7 @@
 pub mod pulsar;
 pub mod rabbitmq;
 pub mod redis;
+pub mod resource;
 pub mod s3;
 pub mod storage;
 pub mod traits;
@@ -12,9 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::checkpoint::Checkpointer;
 use crate::core::BridgePluginReadConfig;
 use crate::failure::{failure_message_process, FailureRecordInfo};
 use crate::manager::ConnectorManager;
+use crate::resource::{estimate_batch_memory, ConnectorThrottle};
 use crate::storage::connector::ConnectorStorage;
 use crate::traits::ConnectorSink;
 use common_base::error::common::CommonError;
@@ -22,7 +24,8 @@
 use common_metrics::mqtt::connector::{
     record_connector_messages_sent_failure, record_connector_messages_sent_success,
     record_connector_offset_commit_failure, record_connector_send_duration,
-    record_connector_source_read_failure,
+    record_connector_sink_call, record_connector_source_read_failure,
+    record_connector_throttled_records, set_connector_memory_estimate,
 };
 use grpc_clients::pool::ClientPool;
 use metadata_struct::connector::status::MQTTStatus;
@@ -54,6 +57,8 @@ struct SendFailureParams<'a> {
 }
 
 struct SendSuccessParams<'a> {
+    data_list: &'a [StorageRecord],
+    transaction_id: Option<String>,
     strategy: &'a FailureHandlingStrategy,
     fail_messages: &'a [FailureRecordInfo],
     start_time: u128,
@@ -79,6 +84,7 @@ pub async fn run_connector_loop<S: ConnectorSink>(
 ) -> Result<(), CommonError> {
     sink.validate().await?;
 
+    let mut checkpointer = Checkpointer::load(client_pool, &config.tenant, &connector_name).await?;
     let mut resource = Some(sink.init_sink().await?);
     let connector_tenant = config.tenant.clone();
     let connector_type = connector_manager
@@ -100,9 +106,19 @@ pub async fn run_connector_loop<S: ConnectorSink>(
         max_size: 1024 * 1024 * 30,
     };
 
-    let mut run_result: Result<(), CommonError> = Ok(());
+    let mut throttle = ConnectorThrottle::new();
+    let mut run_result = resume_from_checkpoint(
+        sink,
+        &consumer,
+        &config,
+        &checkpointer,
+        resource
+            .as_mut()
+            .expect("sink resource must exist during connector loop"),
+    )
+    .await;
 
-    'run: loop {
+    'run: while run_result.is_ok() {
         select! {
             val = stop_recv.recv() => {
                 match val {
@@ -121,24 +137,43 @@ pub async fn run_connector_loop<S: ConnectorSink>(
                             continue;
                         }
 
-                        let start_time = now_millis();
                         let message_count = data.len() as u64;
+                        apply_throttle(&ctx, &mut throttle, message_count).await;
+                        record_batch_memory(&ctx, estimate_batch_memory(&data));
+
+                        let start_time = now_millis();
                         let mut retry_times: u32 = 0;
 
                         loop {
-                            match sink.send_batch(
+                            let call_start = now_millis();
+                            let send_result = sink.send_batch(
                                 &data,
                                 resource
                                     .as_mut()
                                     .expect("sink resource must exist during connector loop"),
                             )
-                            .await
-                            {
+                            .await;
+                            record_sink_call(
+                                &ctx,
+                                (now_millis() - call_start) as u64,
+                                send_result.is_ok(),
+                            );
+
+                            match send_result {
                                 Ok(fail_messages) => {
+                                    let transaction_id = sink.transaction_id(
+                                        &data,
+                                        resource
+                                            .as_ref()
+                                            .expect("sink resource must exist during connector loop"),
+                                    );
                                     if let Err(e) = handle_send_success(
                                         &ctx,
                                         &consumer,
+                                        &mut checkpointer,
                                         SendSuccessParams {
+                                            data_list: &data,
+                                            transaction_id,
                                             strategy: &config.strategy,
                                             fail_messages: &fail_messages,
                                             start_time,
@@ -156,6 +191,7 @@ pub async fn run_connector_loop<S: ConnectorSink>(
                                     match handle_send_failure(
                                         &ctx,
                                         &consumer,
+                                        &mut checkpointer,
                                         &config,
                                         SendFailureParams {
                                             data_list: &data,
@@ -195,6 +231,8 @@ pub async fn run_connector_loop<S: ConnectorSink>(
         }
     }
 
+    record_batch_memory(&ctx, 0);
+
     if let Some(raw_resource) = resource.take() {
         if let Err(cleanup_err) = sink.cleanup_sink(raw_resource).await {
             if run_result.is_ok() {
@@ -211,12 +249,101 @@ pub async fn run_connector_loop<S: ConnectorSink>(
     run_result
 }
 
+async fn apply_throttle(ctx: &BatchCtx<'_>, throttle: &mut ConnectorThrottle, records: u64) {
+    let rate = ctx
+        .connector_manager
+        .get_connector(ctx.connector_name)
+        .and_then(|c| c.max_records_per_sec);
+    throttle.update_rate(rate);
+    if throttle.rate().is_none() {
+        return;
+    }
+
+    let wait_ms = throttle.acquire(records).await;
+    if wait_ms == 0 {
+        return;
+    }
+
+    record_connector_throttled_records(
+        ctx.tenant,
+        ctx.connector_type.to_string(),
+        ctx.connector_name.to_string(),
+        records,
+    );
+    ctx.connector_manager
+        .update_connector_thread_last_active(ctx.connector_name, |thread| {
+            thread.resource_usage.record_throttle(records, wait_ms);
+        });
+}
+
+fn record_batch_memory(ctx: &BatchCtx<'_>, bytes: u64) {
+    set_connector_memory_estimate(
+        ctx.tenant,
+        ctx.connector_type.to_string(),
+        ctx.connector_name.to_string(),
+        bytes,
+    );
+    ctx.connector_manager
+        .update_connector_thread_last_active(ctx.connector_name, |thread| {
+            thread.resource_usage.record_batch_memory(bytes);
+        });
+}
+
+fn record_sink_call(ctx: &BatchCtx<'_>, duration_ms: u64, success: bool) {
+    record_connector_sink_call(
+        ctx.tenant,
+        ctx.connector_type.to_string(),
+        ctx.connector_name.to_string(),
+        duration_ms,
+        success,
+    );
+    ctx.connector_manager
+        .update_connector_thread_last_active(ctx.connector_name, |thread| {
+            thread.resource_usage.record_sink_call(duration_ms, success);
+        });
+}
+
+/// Continue from the last committed checkpoint, which takes precedence over
+/// the consumer group offsets.
+async fn resume_from_checkpoint<S: ConnectorSink>(
+    sink: &S,
+    consumer: &GroupConsumer,
+    config: &BridgePluginReadConfig,
+    checkpointer: &Checkpointer,
+    resource: &mut S::SinkResource,
+) -> Result<(), CommonError> {
+    let Some(checkpoint) = checkpointer.committed() else {
+        return Ok(());
+    };
+
+    info!(
+        connector_name = checkpoint.connector_name.as_str(),
+        sequence = checkpoint.sequence,
+        transaction_id = checkpoint.transaction_id.as_str(),
+        "resuming connector from checkpoint"
+    );
+    consumer.set_current_offsets(
+        &config.tenant,
+        &config.topic_name,
+        &checkpoint.source_offsets,
+    );
+    sink.resume(checkpoint, resource).await
+}
+
 async fn handle_send_success(
     ctx: &BatchCtx<'_>,
     consumer: &GroupConsumer,
+    checkpointer: &mut Checkpointer,
     params: SendSuccessParams<'_>,
 ) -> Result<(), CommonError> {
-    commit_consumer_offsets(ctx, consumer).await?;
+    commit_offsets(
+        ctx,
+        consumer,
+        checkpointer,
+        params.data_list,
+        params.transaction_id,
+    )
+    .await?;
     process_fail_messages(
         ctx.storage_driver_manager,
         params.strategy,
@@ -238,6 +365,7 @@ async fn handle_send_success(
 async fn handle_send_failure(
     ctx: &BatchCtx<'_>,
     consumer: &GroupConsumer,
+    checkpointer: &mut Checkpointer,
     config: &BridgePluginReadConfig,
     params: SendFailureParams<'_>,
 ) -> Result<SendResultAction, CommonError> {
@@ -278,7 +406,7 @@ async fn handle_send_failure(
     )
     .await
     {
-        commit_consumer_offsets(ctx, consumer).await?;
+        commit_offsets(ctx, consumer, checkpointer, params.data_list, None).await?;
         sleep(Duration::from_millis(100)).await;
         return Ok(SendResultAction::BatchDone);
     }
@@ -343,11 +471,21 @@ async fn process_fail_messages(
     }
 }
 
-async fn commit_consumer_offsets(
+/// Commit the checkpoint of a delivered batch, then the consumer group
+/// offsets. The checkpoint is what the connector resumes from; the group
+/// offsets follow it so consumer lag stays visible.
+async fn commit_offsets(
     ctx: &BatchCtx<'_>,
     consumer: &GroupConsumer,
+    checkpointer: &mut Checkpointer,
+    records: &[StorageRecord],
+    transaction_id: Option<String>,
 ) -> Result<(), CommonError> {
-    consumer.commit().await.inspect_err(|_| {
+    let result = match checkpointer.commit(records, transaction_id).await {
+        Ok(()) => consumer.commit().await,
+        Err(e) => Err(e),
+    };
+    result.inspect_err(|_| {
         record_connector_offset_commit_failure(
             ctx.tenant,
             ctx.connector_type.to_string(),
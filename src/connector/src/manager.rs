@@ -16,7 +16,9 @@
 use common_base::tools::now_second;
 use common_metrics::mqtt::connector::set_connector_up;
 use dashmap::DashMap;
+use grpc_clients::meta::common::lock::DistributedLock;
 use metadata_struct::connector::MQTTConnector;
+use std::sync::Arc;
 
 #[derive(Default)]
 pub struct ConnectorManager {
@@ -28,6 +30,9 @@ pub struct ConnectorManager {
 
     // (tenant, (connector_name, u64))
     pub connector_heartbeat: DashMap<String, DashMap<String, u64>>,
+
+    // (connector_name, DistributedLock)
+    pub connector_lock: DashMap<String, Arc<DistributedLock>>,
 }
 
 impl ConnectorManager {
@@ -36,6 +41,7 @@ pub fn new() -> Self {
             connector_list: DashMap::with_capacity(8),
             connector_thread: DashMap::with_capacity(8),
             connector_heartbeat: DashMap::with_capacity(8),
+            connector_lock: DashMap::with_capacity(8),
         }
     }
 
@@ -158,6 +164,23 @@ pub fn update_connector_thread_last_active(
         }
     }
 
+    // Connector Lock
+    pub fn get_connector_lock(&self, connector_name: &str) -> Option<Arc<DistributedLock>> {
+        self.connector_lock
+            .get(connector_name)
+            .map(|lock| lock.clone())
+    }
+
+    pub fn add_connector_lock(&self, connector_name: &str, lock: Arc<DistributedLock>) {
+        self.connector_lock.insert(connector_name.to_owned(), lock);
+    }
+
+    pub fn remove_connector_lock(&self, connector_name: &str) -> Option<Arc<DistributedLock>> {
+        self.connector_lock
+            .remove(connector_name)
+            .map(|(_, lock)| lock)
+    }
+
     // Connector Heartbeat
     pub fn report_heartbeat(&self, tenant: &str, connector_name: &str) {
         self.connector_heartbeat
@@ -191,6 +214,7 @@ pub fn connector_thread_count(&self) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::resource::ConnectorResourceUsage;
     use metadata_struct::{
         connector::{
             config_local_file::LocalFileConnectorConfig, rule::ETLRule, status::MQTTStatus,
@@ -212,6 +236,7 @@ fn create_test_connector(name: &str) -> MQTTConnector {
             create_time: now_second(),
             update_time: now_second(),
             etl_rule: ETLRule::default(),
+            max_records_per_sec: None,
         }
     }
 
@@ -224,6 +249,7 @@ fn create_test_thread(name: &str) -> BridgePluginThread {
             send_success_total: 0,
             stop_send,
             last_msg: None,
+            resource_usage: ConnectorResourceUsage::default(),
         }
     }
 
@@ -0,0 +1,128 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_base::error::common::CommonError;
+use metadata_struct::{
+    adapter::adapter_record::AdapterWriteRecord,
+    connector::config_mqtt::MqttBridgeTopicMapping,
+    storage::record::{StorageRecordProtocolData, StorageRecordProtocolDataMqtt},
+};
+use paho_mqtt as mqtt;
+use rule_engine::sql::topic_filter_match;
+use storage_adapter::driver::StorageDriverManager;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, warn};
+
+use super::{has_bridge_origin, BRIDGE_ORIGIN_PROPERTY};
+use crate::storage::message::MessageStorage;
+
+pub(super) struct InboundContext {
+    pub storage_driver_manager: Arc<StorageDriverManager>,
+    pub tenant: String,
+    pub connector_name: String,
+    pub local_topic: String,
+    pub client_id: String,
+    pub bridge_id: String,
+    pub remote_topics: Vec<MqttBridgeTopicMapping>,
+}
+
+/// Writes messages received from the remote broker into local topics until
+/// the client is dropped or the task is aborted.
+pub(super) fn start_inbound_thread(
+    context: InboundContext,
+    stream: mqtt::AsyncReceiver<Option<mqtt::Message>>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let message_storage = MessageStorage::new(context.storage_driver_manager.clone());
+        while let Ok(message) = stream.recv().await {
+            let Some(message) = message else {
+                warn!(
+                    "MQTT bridge '{}' lost connection to the remote broker",
+                    context.connector_name
+                );
+                continue;
+            };
+
+            if let Err(e) = write_local_message(&context, &message_storage, &message).await {
+                error!(
+                    "MQTT bridge '{}' failed to write message from remote topic '{}': {}",
+                    context.connector_name,
+                    message.topic(),
+                    e
+                );
+            }
+        }
+        debug!(
+            "MQTT bridge '{}' inbound stream closed",
+            context.connector_name
+        );
+    })
+}
+
+async fn write_local_message(
+    context: &InboundContext,
+    message_storage: &MessageStorage,
+    message: &mqtt::Message,
+) -> Result<(), CommonError> {
+    let mut user_properties: Vec<(String, String)> = message.properties().user_iter().collect();
+    if has_bridge_origin(
+        user_properties
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str())),
+        &context.bridge_id,
+    ) {
+        debug!(
+            "Skipping remote message already bridged by '{}', topic={}",
+            context.bridge_id,
+            message.topic()
+        );
+        return Ok(());
+    }
+    user_properties.push((
+        BRIDGE_ORIGIN_PROPERTY.to_string(),
+        context.bridge_id.clone(),
+    ));
+
+    let topic = local_topic(context, message.topic());
+    let record = AdapterWriteRecord::new(topic.clone(), message.payload().to_vec())
+        .with_protocol_data(Some(StorageRecordProtocolData {
+            mqtt: Some(StorageRecordProtocolDataMqtt {
+                client_id: context.client_id.clone(),
+                retain: message.retained(),
+                user_properties,
+                ..Default::default()
+            }),
+            nats: None,
+            mq9: None,
+        }));
+
+    message_storage
+        .append_topic_message(&context.tenant, &topic, vec![record])
+        .await?;
+    Ok(())
+}
+
+/// The local topic of the first remote subscription matching `remote_topic`,
+/// falling back to the connector topic when its target is empty.
+fn local_topic(context: &InboundContext, remote_topic: &str) -> String {
+    context
+        .remote_topics
+        .iter()
+        .find(|mapping| topic_filter_match(&mapping.source, remote_topic))
+        .filter(|mapping| !mapping.target.is_empty())
+        .map(|mapping| mapping.target.replace("${topic}", remote_topic))
+        .unwrap_or_else(|| context.local_topic.clone())
+}
@@ -16,16 +16,18 @@
 use common_base::error::common::CommonError;
 use grpc_clients::pool::ClientPool;
 use metadata_struct::{
-    connector::config_mqtt::MqttBridgeConnectorConfig, connector::config_mqtt::MqttProtocolVersion,
-    connector::MQTTConnector, storage::record::StorageRecord,
+    connector::config_mqtt::MqttBridgeConnectorConfig,
+    connector::config_mqtt::{MqttBridgeDirection, MqttBridgeTopicMapping, MqttProtocolVersion},
+    connector::MQTTConnector,
+    storage::record::StorageRecord,
 };
 use paho_mqtt as mqtt;
-use rule_engine::apply_rule_engine;
+use rule_engine::{apply_rule_engine, sql::topic_filter_match};
 use std::sync::Arc;
 use std::time::Duration;
 use storage_adapter::driver::StorageDriverManager;
-use tokio::sync::mpsc::Receiver;
-use tracing::{debug, error};
+use tokio::{sync::mpsc::Receiver, task::JoinHandle};
+use tracing::{debug, error, warn};
 
 use super::{
     core::{BridgePluginReadConfig, BridgePluginThread},
@@ -35,14 +37,32 @@
     traits::ConnectorSink,
 };
 
+mod inbound;
+
+/// User property carrying the ids of the bridges a message has passed
+/// through. A bridge drops messages that already carry its own id.
+pub const BRIDGE_ORIGIN_PROPERTY: &str = "robustmq-bridge-origin";
+
+const INBOUND_BUFFER_SIZE: usize = 1024;
+const PERSISTENT_SESSION_EXPIRY_SECS: i32 = 86400;
+
 pub struct MqttBridgePlugin {
     connector: MQTTConnector,
     config: MqttBridgeConnectorConfig,
+    storage_driver_manager: Arc<StorageDriverManager>,
+}
+
+pub struct MqttBridgeResource {
+    client: mqtt::AsyncClient,
+    inbound: Option<JoinHandle<()>>,
 }
 
 impl MqttBridgePlugin {
     #[allow(clippy::result_large_err)]
-    pub fn new(connector: MQTTConnector) -> Result<Self, CommonError> {
+    pub fn new(
+        connector: MQTTConnector,
+        storage_driver_manager: Arc<StorageDriverManager>,
+    ) -> Result<Self, CommonError> {
         let config = match &connector.connector_type {
             metadata_struct::connector::ConnectorType::MqttBridge(config) => config.clone(),
             _ => {
@@ -51,73 +71,212 @@ pub fn new(connector: MQTTConnector) -> Result<Self, CommonError> {
                 ));
             }
         };
-        Ok(MqttBridgePlugin { connector, config })
+        Ok(MqttBridgePlugin {
+            connector,
+            config,
+            storage_driver_manager,
+        })
     }
 
-    fn build_target_topic(&self, record: &StorageRecord) -> String {
+    fn bridge_id(&self) -> &str {
+        self.config
+            .bridge_id
+            .as_deref()
+            .unwrap_or(&self.connector.connector_name)
+    }
+
+    fn client_id(&self) -> String {
+        if let Some(client_id) = &self.config.client_id {
+            return client_id.clone();
+        }
+        if !self.config.clean_session {
+            return format!("robustmq-bridge:{}", self.connector.connector_name);
+        }
+        if let Some(prefix) = &self.config.client_id_prefix {
+            format!("{}:{}", prefix, common_base::uuid::unique_id())
+        } else {
+            format!("robustmq-bridge:{}", common_base::uuid::unique_id())
+        }
+    }
+
+    fn outbound_enabled(&self) -> bool {
+        self.config.direction != MqttBridgeDirection::In
+    }
+
+    fn inbound_enabled(&self) -> bool {
+        self.config.direction != MqttBridgeDirection::Out
+    }
+
+    /// Returns the remote topic and QoS a local record is published with.
+    fn build_target(&self, record: &StorageRecord) -> (String, i32) {
         let original_topic = record
             .metadata
             .key
             .as_deref()
-            .unwrap_or("robustmq/bridge/default");
+            .unwrap_or(&self.connector.topic_name);
+
+        if let Some((topic, max_qos)) = map_topic(&self.config.topic_mappings, original_topic) {
+            return (topic, downgrade_qos(self.config.qos, max_qos));
+        }
 
-        if let Some(prefix) = &self.config.topic_prefix {
+        let topic = if let Some(prefix) = &self.config.topic_prefix {
             format!("{}/{}", prefix.trim_end_matches('/'), original_topic)
         } else {
             original_topic.to_string()
+        };
+        (topic, self.config.qos)
+    }
+
+    fn build_properties(&self, record: &StorageRecord) -> Result<mqtt::Properties, CommonError> {
+        let mut properties = mqtt::Properties::new();
+        let user_properties = record
+            .protocol_data
+            .as_ref()
+            .and_then(|data| data.mqtt.as_ref())
+            .map(|mqtt| mqtt.user_properties.as_slice())
+            .unwrap_or_default();
+
+        let origin = (BRIDGE_ORIGIN_PROPERTY, self.bridge_id());
+        for (key, value) in user_properties
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .chain(std::iter::once(origin))
+        {
+            properties
+                .push_string_pair(mqtt::PropertyCode::UserProperty, key, value)
+                .map_err(|e| {
+                    CommonError::CommonError(format!(
+                        "Failed to set user property '{}' on bridged message: {}",
+                        key, e
+                    ))
+                })?;
+        }
+        Ok(properties)
+    }
+
+    fn build_connect_options(&self) -> Result<mqtt::ConnectOptions, CommonError> {
+        let mut conn_builder = match self.config.protocol_version {
+            MqttProtocolVersion::V5 => mqtt::ConnectOptionsBuilder::new_v5(),
+            _ => mqtt::ConnectOptionsBuilder::new(),
+        };
+        conn_builder
+            .keep_alive_interval(Duration::from_secs(self.config.keepalive_secs))
+            .connect_timeout(Duration::from_secs(self.config.connect_timeout_secs));
+
+        if self.config.protocol_version == MqttProtocolVersion::V5 {
+            conn_builder.clean_start(self.config.clean_session);
+            if !self.config.clean_session {
+                let mut properties = mqtt::Properties::new();
+                properties
+                    .push_int(
+                        mqtt::PropertyCode::SessionExpiryInterval,
+                        PERSISTENT_SESSION_EXPIRY_SECS,
+                    )
+                    .map_err(|e| {
+                        CommonError::CommonError(format!(
+                            "Failed to set session expiry interval: {}",
+                            e
+                        ))
+                    })?;
+                conn_builder.properties(properties);
+            }
+        } else {
+            conn_builder.clean_session(self.config.clean_session);
+        }
+
+        if let Some(username) = &self.config.username {
+            conn_builder.user_name(username);
         }
+        if let Some(password) = &self.config.password {
+            conn_builder.password(password);
+        }
+
+        if self.config.enable_tls {
+            let ssl_opts = mqtt::SslOptionsBuilder::new().finalize();
+            conn_builder.ssl_options(ssl_opts);
+        }
+
+        Ok(conn_builder.finalize())
+    }
+
+    async fn subscribe_remote_topics(&self, client: &mqtt::AsyncClient) -> Result<(), CommonError> {
+        let topics: Vec<String> = self
+            .config
+            .remote_topics
+            .iter()
+            .map(|mapping| mapping.source.clone())
+            .collect();
+        let qos: Vec<i32> = self
+            .config
+            .remote_topics
+            .iter()
+            .map(|mapping| downgrade_qos(self.config.qos, mapping.max_qos))
+            .collect();
+
+        client.subscribe_many(&topics, &qos).await.map_err(|e| {
+            CommonError::CommonError(format!(
+                "Failed to subscribe to remote topics {:?} on {}: {}",
+                topics, self.config.server, e
+            ))
+        })?;
+        Ok(())
     }
 }
 
+/// Applies the first mapping whose `source` filter matches `topic`, returning
+/// the mapped topic and the mapping's QoS cap.
+pub fn map_topic(
+    mappings: &[MqttBridgeTopicMapping],
+    topic: &str,
+) -> Option<(String, Option<i32>)> {
+    let mapping = mappings
+        .iter()
+        .find(|mapping| topic_filter_match(&mapping.source, topic))?;
+    let target = if mapping.target.is_empty() {
+        topic.to_string()
+    } else {
+        mapping.target.replace("${topic}", topic)
+    };
+    Some((target, mapping.max_qos))
+}
+
+pub fn downgrade_qos(qos: i32, max_qos: Option<i32>) -> i32 {
+    max_qos.map_or(qos, |max_qos| qos.min(max_qos))
+}
+
+pub fn has_bridge_origin<'a>(
+    mut user_properties: impl Iterator<Item = (&'a str, &'a str)>,
+    bridge_id: &str,
+) -> bool {
+    user_properties.any(|(key, value)| key == BRIDGE_ORIGIN_PROPERTY && value == bridge_id)
+}
+
 #[async_trait]
 impl ConnectorSink for MqttBridgePlugin {
-    type SinkResource = mqtt::AsyncClient;
+    type SinkResource = MqttBridgeResource;
 
     async fn validate(&self) -> Result<(), CommonError> {
         self.config.validate()
     }
 
     async fn init_sink(&self) -> Result<Self::SinkResource, CommonError> {
-        let client_id = if let Some(prefix) = &self.config.client_id_prefix {
-            format!("{}:{}", prefix, common_base::uuid::unique_id())
-        } else {
-            format!("robustmq-bridge:{}", common_base::uuid::unique_id())
-        };
-
+        let client_id = self.client_id();
         let create_opts = mqtt::CreateOptionsBuilder::new()
             .server_uri(&self.config.server)
             .client_id(&client_id)
             .finalize();
 
-        let client = mqtt::AsyncClient::new(create_opts).map_err(|e| {
+        let mut client = mqtt::AsyncClient::new(create_opts).map_err(|e| {
             CommonError::CommonError(format!("Failed to create MQTT client: {}", e))
         })?;
 
-        let conn_opts = {
-            let mut conn_builder = match self.config.protocol_version {
-                MqttProtocolVersion::V5 => mqtt::ConnectOptionsBuilder::new_v5(),
-                _ => mqtt::ConnectOptionsBuilder::new(),
-            };
-            conn_builder
-                .keep_alive_interval(Duration::from_secs(self.config.keepalive_secs))
-                .connect_timeout(Duration::from_secs(self.config.connect_timeout_secs))
-                .clean_session(true);
-
-            if let Some(username) = &self.config.username {
-                conn_builder.user_name(username);
-            }
-            if let Some(password) = &self.config.password {
-                conn_builder.password(password);
-            }
-
-            if self.config.enable_tls {
-                let ssl_opts = mqtt::SslOptionsBuilder::new().finalize();
-                conn_builder.ssl_options(ssl_opts);
-            }
-
-            conn_builder.finalize()
-        };
+        // The stream must be taken before connecting so that messages of a
+        // resumed session are not dropped.
+        let stream = self
+            .inbound_enabled()
+            .then(|| client.get_stream(INBOUND_BUFFER_SIZE));
 
+        let conn_opts = self.build_connect_options()?;
         client.connect(conn_opts).await.map_err(|e| {
             CommonError::CommonError(format!(
                 "Failed to connect to MQTT broker {}: {}",
@@ -130,27 +289,71 @@ async fn init_sink(&self) -> Result<Self::SinkResource, CommonError> {
             self.config.server, client_id
         );
 
-        Ok(client)
+        let inbound = match stream {
+            Some(stream) => {
+                let handle = inbound::start_inbound_thread(
+                    inbound::InboundContext {
+                        storage_driver_manager: self.storage_driver_manager.clone(),
+                        tenant: self.connector.tenant.clone(),
+                        connector_name: self.connector.connector_name.clone(),
+                        local_topic: self.connector.topic_name.clone(),
+                        client_id: client_id.clone(),
+                        bridge_id: self.bridge_id().to_string(),
+                        remote_topics: self.config.remote_topics.clone(),
+                    },
+                    stream,
+                );
+                if let Err(e) = self.subscribe_remote_topics(&client).await {
+                    handle.abort();
+                    return Err(e);
+                }
+                Some(handle)
+            }
+            None => None,
+        };
+
+        Ok(MqttBridgeResource { client, inbound })
     }
 
     async fn send_batch(
         &self,
         records: &[StorageRecord],
-        client: &mut mqtt::AsyncClient,
+        resource: &mut MqttBridgeResource,
     ) -> Result<Vec<FailureRecordInfo>, CommonError> {
-        if records.is_empty() {
+        if records.is_empty() || !self.outbound_enabled() {
             return Ok(vec![]);
         }
 
+        let client = &resource.client;
         if !client.is_connected() {
             return Err(CommonError::CommonError(
                 "MQTT bridge client is disconnected".to_string(),
             ));
         }
 
+        let bridge_id = self.bridge_id();
         let mut fail_messages = Vec::new();
         for record in records {
-            let topic = self.build_target_topic(record);
+            let user_properties = record
+                .protocol_data
+                .as_ref()
+                .and_then(|data| data.mqtt.as_ref())
+                .map(|mqtt| mqtt.user_properties.as_slice())
+                .unwrap_or_default();
+            if has_bridge_origin(
+                user_properties
+                    .iter()
+                    .map(|(k, v)| (k.as_str(), v.as_str())),
+                bridge_id,
+            ) {
+                debug!(
+                    "Skipping message already bridged by '{}', offset={}",
+                    bridge_id, record.metadata.offset
+                );
+                continue;
+            }
+
+            let (topic, qos) = self.build_target(record);
             let payload = match apply_rule_engine(&self.connector.etl_rule, &record.data).await {
                 Ok(data) => data,
                 Err(e) => {
@@ -166,14 +369,16 @@ async fn send_batch(
                 }
             };
 
-            let msg = mqtt::MessageBuilder::new()
+            let mut builder = mqtt::MessageBuilder::new()
                 .topic(&topic)
                 .payload(payload)
-                .qos(self.config.qos)
-                .retained(self.config.retain)
-                .finalize();
+                .qos(qos)
+                .retained(self.config.retain);
+            if self.config.protocol_version == MqttProtocolVersion::V5 {
+                builder = builder.properties(self.build_properties(record)?);
+            }
 
-            client.publish(msg).await.map_err(|e| {
+            client.publish(builder.finalize()).await.map_err(|e| {
                 CommonError::CommonError(format!(
                     "Failed to publish to remote MQTT broker topic '{}': {}",
                     topic, e
@@ -183,6 +388,21 @@ async fn send_batch(
 
         Ok(fail_messages)
     }
+
+    async fn cleanup_sink(&self, resource: MqttBridgeResource) -> Result<(), CommonError> {
+        if let Some(handle) = resource.inbound {
+            handle.abort();
+        }
+        if resource.client.is_connected() {
+            if let Err(e) = resource.client.disconnect(None).await {
+                warn!(
+                    "Failed to disconnect MQTT bridge '{}' from {}: {}",
+                    self.connector.connector_name, self.config.server, e
+                );
+            }
+        }
+        Ok(())
+    }
 }
 
 pub fn start_mqtt_bridge_connector(
@@ -196,7 +416,8 @@ pub fn start_mqtt_bridge_connector(
     tokio::spawn(Box::pin(async move {
         let connector_name = connector.connector_name.clone();
         let connector_type = connector.connector_type.to_string();
-        let bridge = match MqttBridgePlugin::new(connector.clone()) {
+        let bridge = match MqttBridgePlugin::new(connector.clone(), storage_driver_manager.clone())
+        {
             Ok(bridge) => bridge,
             Err(e) => {
                 error!(
@@ -237,3 +458,52 @@ pub fn start_mqtt_bridge_connector(
         }
     }));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping(source: &str, target: &str, max_qos: Option<i32>) -> MqttBridgeTopicMapping {
+        MqttBridgeTopicMapping {
+            source: source.to_string(),
+            target: target.to_string(),
+            max_qos,
+        }
+    }
+
+    #[test]
+    fn test_map_topic() {
+        let mappings = vec![
+            mapping("sensor/+/temp", "cloud/edge01/${topic}", Some(0)),
+            mapping("device/#", "", None),
+        ];
+        assert_eq!(
+            map_topic(&mappings, "sensor/a/temp"),
+            Some(("cloud/edge01/sensor/a/temp".to_string(), Some(0)))
+        );
+        assert_eq!(
+            map_topic(&mappings, "device/a/status"),
+            Some(("device/a/status".to_string(), None))
+        );
+        assert_eq!(map_topic(&mappings, "sensor/a/humidity"), None);
+    }
+
+    #[test]
+    fn test_downgrade_qos() {
+        assert_eq!(downgrade_qos(2, None), 2);
+        assert_eq!(downgrade_qos(2, Some(1)), 1);
+        assert_eq!(downgrade_qos(0, Some(2)), 0);
+    }
+
+    #[test]
+    fn test_has_bridge_origin() {
+        let props = [
+            ("k", "v"),
+            (BRIDGE_ORIGIN_PROPERTY, "edge01"),
+            (BRIDGE_ORIGIN_PROPERTY, "cloud"),
+        ];
+        assert!(has_bridge_origin(props.iter().copied(), "cloud"));
+        assert!(!has_bridge_origin(props.iter().copied(), "edge02"));
+        assert!(!has_bridge_origin([("k", "cloud")].into_iter(), "cloud"));
+    }
+}
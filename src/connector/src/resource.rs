@@ -0,0 +1,200 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::tools::now_millis;
+use governor::{
+    clock::{QuantaClock, QuantaInstant},
+    middleware::NoOpMiddleware,
+    state::{InMemoryState, NotKeyed},
+    Quota, RateLimiter,
+};
+use metadata_struct::storage::record::StorageRecord;
+use serde::{Deserialize, Serialize};
+use std::num::NonZero;
+
+type DirectRateLimiter =
+    RateLimiter<NotKeyed, InMemoryState, QuantaClock, NoOpMiddleware<QuantaInstant>>;
+
+/// Resource consumption attributed to a single connector thread.
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConnectorResourceUsage {
+    /// Time spent inside the sink while sending batches. Connector tasks share runtime
+    /// threads, so busy time is the closest per-connector approximation of CPU time.
+    pub cpu_time_ms: u64,
+    /// Estimated bytes held by the batch currently being processed.
+    pub memory_estimate_bytes: u64,
+    pub peak_memory_estimate_bytes: u64,
+    /// Number of sink calls (one per batch attempt, retries included).
+    pub rpc_total: u64,
+    pub rpc_failure_total: u64,
+    pub last_sink_latency_ms: u64,
+    pub max_sink_latency_ms: u64,
+    pub total_sink_latency_ms: u64,
+    /// Records that had to wait for the per-connector throttle.
+    pub throttled_records_total: u64,
+    pub throttle_wait_ms: u64,
+}
+
+impl ConnectorResourceUsage {
+    pub fn record_batch_memory(&mut self, bytes: u64) {
+        self.memory_estimate_bytes = bytes;
+        self.peak_memory_estimate_bytes = self.peak_memory_estimate_bytes.max(bytes);
+    }
+
+    pub fn record_sink_call(&mut self, latency_ms: u64, success: bool) {
+        self.rpc_total += 1;
+        if !success {
+            self.rpc_failure_total += 1;
+        }
+        self.cpu_time_ms += latency_ms;
+        self.last_sink_latency_ms = latency_ms;
+        self.max_sink_latency_ms = self.max_sink_latency_ms.max(latency_ms);
+        self.total_sink_latency_ms += latency_ms;
+    }
+
+    pub fn record_throttle(&mut self, records: u64, wait_ms: u64) {
+        self.throttled_records_total += records;
+        self.throttle_wait_ms += wait_ms;
+    }
+
+    pub fn avg_sink_latency_ms(&self) -> u64 {
+        if self.rpc_total == 0 {
+            return 0;
+        }
+        self.total_sink_latency_ms / self.rpc_total
+    }
+}
+
+pub fn estimate_batch_memory(records: &[StorageRecord]) -> u64 {
+    records
+        .iter()
+        .map(|record| {
+            let key_len = record.metadata.key.as_ref().map(|k| k.len()).unwrap_or(0);
+            let tags_len: usize = record
+                .metadata
+                .tags
+                .as_ref()
+                .map(|tags| tags.iter().map(|t| t.len()).sum())
+                .unwrap_or(0);
+            (record.data.len() + key_len + tags_len) as u64
+        })
+        .sum()
+}
+
+/// Records-per-second limiter for one connector. The quota is re-read on every batch so
+/// updating `max_records_per_sec` on the connector takes effect without a restart.
+#[derive(Default)]
+pub struct ConnectorThrottle {
+    rate: Option<u32>,
+    limiter: Option<DirectRateLimiter>,
+}
+
+impl ConnectorThrottle {
+    pub fn new() -> Self {
+        ConnectorThrottle::default()
+    }
+
+    pub fn update_rate(&mut self, rate: Option<u32>) {
+        let rate = rate.filter(|r| *r > 0);
+        if rate == self.rate {
+            return;
+        }
+        self.rate = rate;
+        self.limiter = rate
+            .and_then(NonZero::new)
+            .map(|r| RateLimiter::direct(Quota::per_second(r)));
+    }
+
+    pub fn rate(&self) -> Option<u32> {
+        self.rate
+    }
+
+    /// Waits until `records` permits are available and returns the time spent waiting in
+    /// milliseconds. Batches larger than one second of quota are admitted in slices.
+    pub async fn acquire(&self, records: u64) -> u64 {
+        let (Some(limiter), Some(rate)) = (self.limiter.as_ref(), self.rate) else {
+            return 0;
+        };
+
+        let start = now_millis();
+        let mut remaining = records;
+        while remaining > 0 {
+            let step = remaining.min(rate as u64) as u32;
+            if let Some(n) = NonZero::new(step) {
+                // step never exceeds the burst size, so capacity is always sufficient
+                let _ = limiter.until_n_ready(n).await;
+            }
+            remaining -= step as u64;
+        }
+        (now_millis() - start) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metadata_struct::storage::record::StorageRecordMetadata;
+
+    #[test]
+    fn test_resource_usage_accounting() {
+        let mut usage = ConnectorResourceUsage::default();
+        usage.record_batch_memory(100);
+        usage.record_batch_memory(40);
+        assert_eq!(usage.memory_estimate_bytes, 40);
+        assert_eq!(usage.peak_memory_estimate_bytes, 100);
+
+        usage.record_sink_call(10, true);
+        usage.record_sink_call(30, false);
+        assert_eq!(usage.rpc_total, 2);
+        assert_eq!(usage.rpc_failure_total, 1);
+        assert_eq!(usage.cpu_time_ms, 40);
+        assert_eq!(usage.last_sink_latency_ms, 30);
+        assert_eq!(usage.max_sink_latency_ms, 30);
+        assert_eq!(usage.avg_sink_latency_ms(), 20);
+
+        usage.record_throttle(5, 12);
+        assert_eq!(usage.throttled_records_total, 5);
+        assert_eq!(usage.throttle_wait_ms, 12);
+    }
+
+    #[test]
+    fn test_estimate_batch_memory() {
+        let record = StorageRecord {
+            metadata: StorageRecordMetadata::default().with_key(Some("key".to_string())),
+            protocol_data: None,
+            data: vec![0u8; 10].into(),
+        };
+        assert_eq!(estimate_batch_memory(&[record.clone(), record]), 26);
+        assert_eq!(estimate_batch_memory(&[]), 0);
+    }
+
+    #[tokio::test]
+    async fn test_throttle() {
+        let mut throttle = ConnectorThrottle::new();
+        assert_eq!(throttle.acquire(1000).await, 0);
+
+        throttle.update_rate(Some(0));
+        assert!(throttle.rate().is_none());
+
+        throttle.update_rate(Some(100));
+        assert_eq!(throttle.rate(), Some(100));
+        // the initial burst is admitted immediately
+        assert!(throttle.acquire(100).await < 50);
+        // the next slice has to wait for the quota to refill
+        assert!(throttle.acquire(50).await >= 300);
+
+        throttle.update_rate(None);
+        assert_eq!(throttle.acquire(1000).await, 0);
+    }
+}
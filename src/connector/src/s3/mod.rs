@@ -17,12 +17,11 @@
 use grpc_clients::pool::ClientPool;
 use metadata_struct::{
     connector::{config_s3::S3ConnectorConfig, MQTTConnector},
-    storage::adapter_record::RecordHeader,
     storage::record::StorageRecord,
 };
 use opendal::{services::S3, Operator};
 use rule_engine::apply_rule_engine;
-use serde::Serialize;
+use std::path::PathBuf;
 use std::sync::Arc;
 use storage_adapter::driver::StorageDriverManager;
 use tokio::sync::mpsc::Receiver;
@@ -31,19 +30,22 @@
 use super::{
     core::{BridgePluginReadConfig, BridgePluginThread},
     failure::FailureRecordInfo,
+    file::{
+        format::{encode_file, file_extension, FileRecord},
+        rolling::{
+            default_staging_dir, staging_file, RollPolicy, RollTarget, RollingFileOptions,
+            RollingFileWriter,
+        },
+    },
     loops::run_connector_loop,
     manager::ConnectorManager,
     traits::ConnectorSink,
 };
 
-#[derive(Serialize)]
-struct S3MessageRecord {
-    pkid: u64,
-    key: Option<String>,
-    headers: Option<Vec<RecordHeader>>,
-    tags: Option<Vec<String>>,
-    data: Vec<u8>,
-    timestamp: u64,
+pub struct S3Sink {
+    operator: Operator,
+    /// Set when objects are rolled by size or age instead of per batch.
+    rolling: Option<RollingFileWriter>,
 }
 
 pub struct S3BridgePlugin {
@@ -69,10 +71,34 @@ fn normalize_prefix(prefix: &str) -> &str {
         prefix.trim_matches('/')
     }
 
+    fn extension(&self) -> String {
+        let extension = self.config.file_extension.trim_start_matches('.');
+        if extension.is_empty() {
+            file_extension(&self.config.format, &self.config.compression)
+        } else {
+            extension.to_string()
+        }
+    }
+
     fn build_object_key(&self) -> String {
         let prefix = Self::normalize_prefix(&self.config.object_key_prefix);
-        let extension = self.config.file_extension.trim_start_matches('.');
-        format!("{}/{}-{}.{}", prefix, now_millis(), unique_id(), extension)
+        format!(
+            "{}/{}-{}.{}",
+            prefix,
+            now_millis(),
+            unique_id(),
+            self.extension()
+        )
+    }
+
+    fn roll_policy(&self) -> Option<RollPolicy> {
+        if self.config.roll_size_mb == 0 && self.config.roll_interval_secs == 0 {
+            return None;
+        }
+        Some(RollPolicy {
+            max_bytes: self.config.roll_size_mb * 1024 * 1024,
+            max_age_secs: self.config.roll_interval_secs,
+        })
     }
 
     #[allow(clippy::result_large_err)]
@@ -104,31 +130,60 @@ fn build_operator(&self) -> Result<Operator, CommonError> {
 
 #[async_trait]
 impl ConnectorSink for S3BridgePlugin {
-    type SinkResource = Operator;
+    type SinkResource = S3Sink;
 
     async fn validate(&self) -> Result<(), CommonError> {
         self.config.validate()
     }
 
     async fn init_sink(&self) -> Result<Self::SinkResource, CommonError> {
-        let op = self.build_operator()?;
+        let operator = self.build_operator()?;
         debug!(
             "S3 connector initialized: bucket={}, region={}, root={}",
             self.config.bucket, self.config.region, self.config.root
         );
-        Ok(op)
+
+        let rolling = match self.roll_policy() {
+            Some(policy) => {
+                let staging_dir = if self.config.staging_dir.is_empty() {
+                    default_staging_dir()
+                } else {
+                    PathBuf::from(&self.config.staging_dir)
+                };
+                let writer = RollingFileWriter::open(RollingFileOptions {
+                    staging_path: staging_file(
+                        &staging_dir,
+                        &self.connector.tenant,
+                        &self.connector.connector_name,
+                    ),
+                    policy,
+                    format: self.config.format.clone(),
+                    compression: self.config.compression.clone(),
+                    extension: self.extension(),
+                    target: RollTarget::Object {
+                        operator: operator.clone(),
+                        prefix: Self::normalize_prefix(&self.config.object_key_prefix).to_string(),
+                    },
+                })
+                .await?;
+                Some(writer)
+            }
+            None => None,
+        };
+
+        Ok(S3Sink { operator, rolling })
     }
 
     async fn send_batch(
         &self,
         records: &[StorageRecord],
-        operator: &mut Operator,
+        sink: &mut S3Sink,
     ) -> Result<Vec<FailureRecordInfo>, CommonError> {
         if records.is_empty() {
             return Ok(vec![]);
         }
 
-        let mut payload: Vec<S3MessageRecord> = Vec::with_capacity(records.len());
+        let mut payload: Vec<FileRecord> = Vec::with_capacity(records.len());
         let mut fail_messages = Vec::new();
         for record in records {
             let processed_data =
@@ -147,37 +202,34 @@ async fn send_batch(
                         continue;
                     }
                 };
-
-            let headers = record.metadata.header.as_ref().map(|hs| {
-                hs.iter()
-                    .map(|h| RecordHeader {
-                        name: h.name.clone(),
-                        value: h.value.clone(),
-                    })
-                    .collect()
-            });
-            payload.push(S3MessageRecord {
-                pkid: record.metadata.offset,
-                key: record.metadata.key.clone(),
-                headers,
-                tags: record.metadata.tags.clone(),
-                data: processed_data.to_vec(),
-                timestamp: record.metadata.create_t,
-            });
+            payload.push(FileRecord::new(record, &processed_data));
         }
 
         if payload.is_empty() {
             return Ok(fail_messages);
         }
 
+        if let Some(writer) = sink.rolling.as_mut() {
+            writer.append(&payload).await?;
+            if writer.should_roll() {
+                writer.roll().await?;
+            }
+            return Ok(fail_messages);
+        }
+
         let object_key = self.build_object_key();
-        let bytes = serde_json::to_vec(&payload).map_err(|e| {
-            CommonError::CommonError(format!("Failed to serialize S3 payload to JSON: {}", e))
-        })?;
-        operator.write(&object_key, bytes).await?;
+        let bytes = encode_file(&payload, &self.config.format, &self.config.compression)?;
+        sink.operator.write(&object_key, bytes).await?;
 
         Ok(fail_messages)
     }
+
+    async fn cleanup_sink(&self, sink: S3Sink) -> Result<(), CommonError> {
+        if let Some(writer) = sink.rolling {
+            writer.close().await?;
+        }
+        Ok(())
+    }
 }
 
 pub fn start_s3_connector(
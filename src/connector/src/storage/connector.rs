@@ -19,15 +19,17 @@
 use common_config::broker::broker_config;
 use grpc_clients::{
     meta::mqtt::call::{
-        placement_connector_heartbeat, placement_create_connector, placement_delete_connector,
+        placement_commit_connector_checkpoint, placement_connector_heartbeat,
+        placement_create_connector, placement_delete_connector, placement_get_connector_checkpoint,
         placement_list_connector, placement_update_connector,
     },
     pool::ClientPool,
 };
-use metadata_struct::connector::MQTTConnector;
+use metadata_struct::connector::{checkpoint::ConnectorCheckpoint, MQTTConnector};
 use protocol::meta::meta_service_mqtt::{
-    ConnectorHeartbeatRaw, ConnectorHeartbeatRequest, CreateConnectorRequest,
-    DeleteConnectorRequest, ListConnectorRequest, UpdateConnectorRequest,
+    CommitConnectorCheckpointRequest, ConnectorHeartbeatRaw, ConnectorHeartbeatRequest,
+    CreateConnectorRequest, DeleteConnectorRequest, GetConnectorCheckpointRequest,
+    ListConnectorRequest, UpdateConnectorRequest,
 };
 
 pub struct ConnectorStorage {
@@ -46,6 +48,7 @@ pub async fn list_connector(
         let config = broker_config();
         let request = ListConnectorRequest {
             connector_name: connector_name.to_owned(),
+            ..Default::default()
         };
         let mut stream =
             placement_list_connector(&self.client_pool, &config.get_meta_service_addr(), request)
@@ -108,4 +111,39 @@ pub async fn connector_heartbeat(
             .await?;
         Ok(())
     }
+
+    pub async fn commit_checkpoint(&self, checkpoint: &ConnectorCheckpoint) -> ResultCommonError {
+        let config = broker_config();
+        let request = CommitConnectorCheckpointRequest {
+            connector_name: checkpoint.connector_name.clone(),
+            checkpoint: checkpoint.encode()?,
+        };
+        placement_commit_connector_checkpoint(
+            &self.client_pool,
+            &config.get_meta_service_addr(),
+            request,
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_checkpoint(
+        &self,
+        connector_name: &str,
+    ) -> Result<Option<ConnectorCheckpoint>, CommonError> {
+        let config = broker_config();
+        let request = GetConnectorCheckpointRequest {
+            connector_name: connector_name.to_owned(),
+        };
+        let reply = placement_get_connector_checkpoint(
+            &self.client_pool,
+            &config.get_meta_service_addr(),
+            request,
+        )
+        .await?;
+        if reply.checkpoint.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(ConnectorCheckpoint::decode(&reply.checkpoint)?))
+    }
 }
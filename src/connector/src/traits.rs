@@ -14,6 +14,7 @@
 
 use async_trait::async_trait;
 use common_base::error::common::CommonError;
+use metadata_struct::connector::checkpoint::ConnectorCheckpoint;
 use metadata_struct::storage::record::StorageRecord;
 
 use crate::failure::FailureRecordInfo;
@@ -32,6 +33,28 @@ async fn send_batch(
         resource: &mut Self::SinkResource,
     ) -> Result<Vec<FailureRecordInfo>, CommonError>;
 
+    /// Called before the first batch with the checkpoint the connector resumes
+    /// from. A transactional sink finishes or aborts the transaction it had
+    /// open when the connector stopped.
+    async fn resume(
+        &self,
+        _checkpoint: &ConnectorCheckpoint,
+        _resource: &mut Self::SinkResource,
+    ) -> Result<(), CommonError> {
+        Ok(())
+    }
+
+    /// Id of the sink transaction that made the last `send_batch` durable,
+    /// committed together with the source offsets. `None` for sinks without
+    /// transactions.
+    fn transaction_id(
+        &self,
+        _records: &[StorageRecord],
+        _resource: &Self::SinkResource,
+    ) -> Option<String> {
+        None
+    }
+
     async fn cleanup_sink(&self, _resource: Self::SinkResource) -> Result<(), CommonError> {
         Ok(())
     }
@@ -0,0 +1,57 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use common_base::error::common::CommonError;
+use std::sync::OnceLock;
+use tonic::metadata::{Ascii, MetadataValue};
+use tonic::Request;
+
+static NODE_TOKEN: OnceLock<MetadataValue<Ascii>> = OnceLock::new();
+
+/// Set the token sent as `authorization: Bearer <token>` on every gRPC call of
+/// this process. An empty token sends no header.
+pub fn init_node_token(token: &str) -> Result<(), CommonError> {
+    if token.is_empty() {
+        return Ok(());
+    }
+    let value = MetadataValue::try_from(format!("Bearer {token}"))
+        .map_err(|e| CommonError::CommonError(format!("Invalid gRPC node token: {e}")))?;
+    let _ = NODE_TOKEN.set(value);
+    Ok(())
+}
+
+pub(crate) fn authorized_request<T>(message: T) -> Request<T> {
+    let mut request = Request::new(message);
+    if let Some(token) = NODE_TOKEN.get() {
+        request
+            .metadata_mut()
+            .insert("authorization", token.clone());
+    }
+    request
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn node_token_header() {
+        assert!(init_node_token("bad\ntoken").is_err());
+        init_node_token("node-secret").unwrap();
+        let request = authorized_request(());
+        assert_eq!(
+            request.metadata().get("authorization").unwrap(),
+            "Bearer node-secret"
+        );
+    }
+}
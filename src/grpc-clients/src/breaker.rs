@@ -0,0 +1,270 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::time::{Duration, Instant};
+
+/// Error-rate thresholds of the per-address circuit breakers kept by
+/// [`crate::pool::ClientPool`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CircuitBreakerConfig {
+    /// Failure ratio in `(0, 1]` within one window that opens the circuit.
+    pub failure_rate_threshold: f64,
+    /// Attempts needed in a window before the failure ratio is evaluated.
+    pub min_requests: u32,
+    /// Length of the counting window.
+    pub window: Duration,
+    /// How long an open circuit rejects calls before allowing a trial. A
+    /// trial without an outcome after this long, e.g. because its caller was
+    /// cancelled, is given up and another one is let through.
+    pub open_duration: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        CircuitBreakerConfig {
+            failure_rate_threshold: 0.5,
+            min_requests: 5,
+            window: Duration::from_secs(10),
+            open_duration: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+impl CircuitState {
+    pub fn as_metric(&self) -> i64 {
+        match self {
+            CircuitState::Closed => 0,
+            CircuitState::Open => 1,
+            CircuitState::HalfOpen => 2,
+        }
+    }
+}
+
+/// Circuit breaker for one target address. Only transport failures (the node
+/// is unreachable or does not answer) count as failures; an application error
+/// means the node is up.
+#[derive(Debug)]
+pub(crate) struct EndpointBreaker {
+    state: CircuitState,
+    window_start: Instant,
+    requests: u32,
+    failures: u32,
+    opened_at: Instant,
+    trial_in_flight: bool,
+    trial_started_at: Instant,
+}
+
+impl EndpointBreaker {
+    pub(crate) fn new(now: Instant) -> Self {
+        EndpointBreaker {
+            state: CircuitState::Closed,
+            window_start: now,
+            requests: 0,
+            failures: 0,
+            opened_at: now,
+            trial_in_flight: false,
+            trial_started_at: now,
+        }
+    }
+
+    pub(crate) fn state(&self) -> CircuitState {
+        self.state
+    }
+
+    /// Whether a call may be sent now. Once the open period has passed, a
+    /// single trial call is let through in the half-open state. A trial that
+    /// reports no outcome within another open period no longer holds the slot.
+    pub(crate) fn allow(&mut self, config: &CircuitBreakerConfig, now: Instant) -> bool {
+        match self.state {
+            CircuitState::Closed => true,
+            CircuitState::Open => {
+                if now.duration_since(self.opened_at) < config.open_duration {
+                    return false;
+                }
+                self.state = CircuitState::HalfOpen;
+                self.start_trial(now);
+                true
+            }
+            CircuitState::HalfOpen => {
+                if self.trial_in_flight
+                    && now.duration_since(self.trial_started_at) < config.open_duration
+                {
+                    return false;
+                }
+                self.start_trial(now);
+                true
+            }
+        }
+    }
+
+    pub(crate) fn on_success(&mut self, config: &CircuitBreakerConfig, now: Instant) {
+        if self.state != CircuitState::Closed {
+            self.close(now);
+            return;
+        }
+        self.roll_window(config, now);
+        self.requests += 1;
+    }
+
+    pub(crate) fn on_failure(&mut self, config: &CircuitBreakerConfig, now: Instant) {
+        match self.state {
+            CircuitState::HalfOpen => self.open(now),
+            CircuitState::Open => {}
+            CircuitState::Closed => {
+                self.roll_window(config, now);
+                self.requests += 1;
+                self.failures += 1;
+                if self.requests >= config.min_requests
+                    && self.failures as f64 >= self.requests as f64 * config.failure_rate_threshold
+                {
+                    self.open(now);
+                }
+            }
+        }
+    }
+
+    fn roll_window(&mut self, config: &CircuitBreakerConfig, now: Instant) {
+        if now.duration_since(self.window_start) >= config.window {
+            self.window_start = now;
+            self.requests = 0;
+            self.failures = 0;
+        }
+    }
+
+    fn start_trial(&mut self, now: Instant) {
+        self.trial_in_flight = true;
+        self.trial_started_at = now;
+    }
+
+    fn open(&mut self, now: Instant) {
+        self.state = CircuitState::Open;
+        self.opened_at = now;
+        self.trial_in_flight = false;
+    }
+
+    fn close(&mut self, now: Instant) {
+        self.state = CircuitState::Closed;
+        self.window_start = now;
+        self.requests = 0;
+        self.failures = 0;
+        self.trial_in_flight = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> CircuitBreakerConfig {
+        CircuitBreakerConfig {
+            failure_rate_threshold: 0.5,
+            min_requests: 4,
+            window: Duration::from_secs(10),
+            open_duration: Duration::from_secs(5),
+        }
+    }
+
+    #[test]
+    fn test_opens_on_error_rate() {
+        let config = config();
+        let now = Instant::now();
+        let mut breaker = EndpointBreaker::new(now);
+
+        breaker.on_success(&config, now);
+        breaker.on_failure(&config, now);
+        breaker.on_failure(&config, now);
+        // 2 of 3 failed, but below min_requests
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        breaker.on_failure(&config, now);
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.allow(&config, now + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_window_resets_counts() {
+        let config = config();
+        let now = Instant::now();
+        let mut breaker = EndpointBreaker::new(now);
+
+        for _ in 0..3 {
+            breaker.on_failure(&config, now);
+        }
+        let later = now + Duration::from_secs(11);
+        breaker.on_success(&config, later);
+        breaker.on_success(&config, later);
+        breaker.on_success(&config, later);
+        breaker.on_failure(&config, later);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_half_open_trial() {
+        let config = config();
+        let now = Instant::now();
+        let mut breaker = EndpointBreaker::new(now);
+        for _ in 0..4 {
+            breaker.on_failure(&config, now);
+        }
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        // a single trial after the open period
+        let later = now + Duration::from_secs(5);
+        assert!(breaker.allow(&config, later));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(!breaker.allow(&config, later));
+
+        // a failed trial re-opens
+        breaker.on_failure(&config, later);
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.allow(&config, later + Duration::from_secs(1)));
+
+        // a successful trial closes
+        let later = later + Duration::from_secs(5);
+        assert!(breaker.allow(&config, later));
+        breaker.on_success(&config, later);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.allow(&config, later));
+    }
+
+    #[test]
+    fn test_abandoned_trial_expires() {
+        let config = config();
+        let now = Instant::now();
+        let mut breaker = EndpointBreaker::new(now);
+        for _ in 0..4 {
+            breaker.on_failure(&config, now);
+        }
+
+        // the trial's caller is cancelled and never reports back
+        let trial = now + Duration::from_secs(5);
+        assert!(breaker.allow(&config, trial));
+        assert!(!breaker.allow(&config, trial + Duration::from_secs(4)));
+
+        // after another open period the slot is handed to a new trial
+        let retry = trial + Duration::from_secs(5);
+        assert!(breaker.allow(&config, retry));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(!breaker.allow(&config, retry));
+
+        breaker.on_success(&config, retry);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+}
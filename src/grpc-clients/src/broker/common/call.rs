@@ -14,10 +14,12 @@
 
 use common_base::error::common::CommonError;
 use protocol::broker::broker::{
-    GetQosDataByClientIdReply, GetQosDataByClientIdRequest, GetShardSegmentDeleteStatusReply,
+    ForwardMqttMessageReply, ForwardMqttMessageRequest, GetQosDataByClientIdReply,
+    GetQosDataByClientIdRequest, GetShardSegmentDeleteStatusReply,
     GetShardSegmentDeleteStatusRequest, QueryReplicaLeoReply, QueryReplicaLeoRequest,
-    SendLastWillMessageReply, SendLastWillMessageRequest, SendNatsShareGroupMessageReply,
-    SendNatsShareGroupMessageRequest, UpdateCacheReply, UpdateCacheRequest,
+    ResyncCacheReply, ResyncCacheRequest, SendLastWillMessageReply, SendLastWillMessageRequest,
+    SendNatsShareGroupMessageReply, SendNatsShareGroupMessageRequest, UpdateCacheReply,
+    UpdateCacheRequest,
 };
 
 use crate::pool::ClientPool;
@@ -63,3 +65,11 @@ pub async fn $fn_name(
     QueryReplicaLeoRequest,
     QueryReplicaLeoReply
 );
+
+generate_broker_call!(broker_resync_cache, ResyncCacheRequest, ResyncCacheReply);
+
+generate_broker_call!(
+    broker_forward_mqtt_message,
+    ForwardMqttMessageRequest,
+    ForwardMqttMessageReply
+);
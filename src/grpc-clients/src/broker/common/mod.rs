@@ -14,11 +14,12 @@
 
 use crate::macros::impl_retriable_request;
 use protocol::broker::broker::{
-    broker_service_client::BrokerServiceClient, GetQosDataByClientIdReply,
-    GetQosDataByClientIdRequest, GetShardSegmentDeleteStatusReply,
+    broker_service_client::BrokerServiceClient, ForwardMqttMessageReply, ForwardMqttMessageRequest,
+    GetQosDataByClientIdReply, GetQosDataByClientIdRequest, GetShardSegmentDeleteStatusReply,
     GetShardSegmentDeleteStatusRequest, QueryReplicaLeoReply, QueryReplicaLeoRequest,
-    SendLastWillMessageReply, SendLastWillMessageRequest, SendNatsShareGroupMessageReply,
-    SendNatsShareGroupMessageRequest, UpdateCacheReply, UpdateCacheRequest,
+    ResyncCacheReply, ResyncCacheRequest, SendLastWillMessageReply, SendLastWillMessageRequest,
+    SendNatsShareGroupMessageReply, SendNatsShareGroupMessageRequest, UpdateCacheReply,
+    UpdateCacheRequest,
 };
 use tonic::transport::Channel;
 
@@ -77,3 +78,21 @@
     "BrokerService",
     "QueryReplicaLeo"
 );
+
+impl_retriable_request!(
+    ResyncCacheRequest,
+    BrokerServiceClient<Channel>,
+    ResyncCacheReply,
+    resync_cache,
+    "BrokerService",
+    "ResyncCache"
+);
+
+impl_retriable_request!(
+    ForwardMqttMessageRequest,
+    BrokerServiceClient<Channel>,
+    ForwardMqttMessageReply,
+    forward_mqtt_message,
+    "BrokerService",
+    "ForwardMqttMessage"
+);
@@ -14,9 +14,13 @@
 
 mod macros;
 
+pub mod auth;
+pub mod breaker;
 pub mod broker;
 pub mod meta;
+pub mod policy;
 pub mod pool;
+pub mod tls;
 mod utils;
 // const MAX_RETRY_TIMES: usize = 10;
 
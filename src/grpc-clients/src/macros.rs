@@ -40,7 +40,7 @@ async fn call_once(
                 request: Self,
             ) -> Result<Self::Response, Self::Error> {
                 client
-                    .$op(request)
+                    .$op($crate::auth::authorized_request(request))
                     .await
                     .map(|reply| reply.into_inner())
                     .map_err(Into::into)
@@ -69,7 +69,7 @@ async fn call_once(
                 request: Self,
             ) -> Result<Self::Response, Self::Error> {
                 client
-                    .$op(request)
+                    .$op($crate::auth::authorized_request(request))
                     .await
                     .map(|reply| reply.into_inner())
                     .map_err(Into::into)
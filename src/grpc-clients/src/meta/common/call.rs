@@ -14,24 +14,34 @@
 
 use common_base::error::common::CommonError;
 use protocol::meta::meta_service_common::{
-    AddShareGroupMemberReply, AddShareGroupMemberRequest, AppendReply, AppendRequest,
-    BindSchemaReply, BindSchemaRequest, ClusterStatusReply, ClusterStatusRequest,
+    AcquireLockReply, AcquireLockRequest, AddShareGroupMemberReply, AddShareGroupMemberRequest,
+    AppendReply, AppendRequest, BindSchemaReply, BindSchemaRequest, BootstrapCacheReply,
+    BootstrapCacheRequest, CheckResourceDigestReply, CheckResourceDigestRequest,
+    ClusterStatusReply, ClusterStatusRequest, CompareAndSetReply, CompareAndSetRequest,
+    ConsumerGroupHeartbeatReply, ConsumerGroupHeartbeatRequest, CordonNodeReply, CordonNodeRequest,
     CreateSchemaReply, CreateSchemaRequest, CreateShareGroupReply, CreateShareGroupRequest,
-    CreateTenantReply, CreateTenantRequest, DeleteReply, DeleteRequest, DeleteResourceConfigReply,
-    DeleteResourceConfigRequest, DeleteSchemaReply, DeleteSchemaRequest,
-    DeleteShareGroupMemberReply, DeleteShareGroupMemberRequest, DeleteShareGroupReply,
-    DeleteShareGroupRequest, DeleteTenantReply, DeleteTenantRequest, ExistsReply, ExistsRequest,
-    GetOffsetDataReply, GetOffsetDataRequest, GetPrefixReply, GetPrefixRequest, GetReply,
-    GetRequest, GetResourceConfigReply, GetResourceConfigRequest, HeartbeatReply, HeartbeatRequest,
-    JoinClusterReply, JoinClusterRequest, LeaveClusterReply, LeaveClusterRequest,
-    ListBindSchemaReply, ListBindSchemaRequest, ListSchemaReply, ListSchemaRequest,
+    CreateTenantReply, CreateTenantRequest, DeleteOffsetGroupReply, DeleteOffsetGroupRequest,
+    DeleteReply, DeleteRequest, DeleteResourceConfigReply, DeleteResourceConfigRequest,
+    DeleteSchemaReply, DeleteSchemaRequest, DeleteShareGroupMemberReply,
+    DeleteShareGroupMemberRequest, DeleteShareGroupReply, DeleteShareGroupRequest,
+    DeleteTenantReply, DeleteTenantRequest, ExistsReply, ExistsRequest, ExportMetadataReply,
+    ExportMetadataRequest, GetConfigDriftReply, GetConfigDriftRequest, GetOffsetDataReply,
+    GetOffsetDataRequest, GetPrefixReply, GetPrefixRequest, GetReply, GetRequest,
+    GetResourceConfigReply, GetResourceConfigRequest, HeartbeatReply, HeartbeatRequest,
+    ImportMetadataReply, ImportMetadataRequest, JoinClusterReply, JoinClusterRequest,
+    LeaveClusterReply, LeaveClusterRequest, LeaveConsumerGroupReply, LeaveConsumerGroupRequest,
+    ListBindSchemaReply, ListBindSchemaRequest, ListNodeStatusReply, ListNodeStatusRequest,
+    ListOffsetGroupReply, ListOffsetGroupRequest, ListSchemaReply, ListSchemaRequest,
     ListShareGroupMemberReply, ListShareGroupMemberRequest, ListShareGroupReply,
     ListShareGroupRequest, ListTenantReply, ListTenantRequest, NodeListReply, NodeListRequest,
-    RegisterNodeReply, RegisterNodeRequest, SaveOffsetDataReply, SaveOffsetDataRequest, SetReply,
-    SetRequest, SetResourceConfigReply, SetResourceConfigRequest, SnapshotReply, SnapshotRequest,
-    UnBindSchemaReply, UnBindSchemaRequest, UnRegisterNodeReply, UnRegisterNodeRequest,
-    UpdateSchemaReply, UpdateSchemaRequest, UpdateTenantReply, UpdateTenantRequest, VoteReply,
-    VoteRequest,
+    RegisterNodeReply, RegisterNodeRequest, ReleaseLockReply, ReleaseLockRequest, RenewLockReply,
+    RenewLockRequest, ResetOffsetDataReply, ResetOffsetDataRequest, SaveOffsetDataReply,
+    SaveOffsetDataRequest, SetReply, SetRequest, SetResourceConfigReply, SetResourceConfigRequest,
+    SnapshotReply, SnapshotRequest, TransferLeaderReply, TransferLeaderRequest, TriggerElectReply,
+    TriggerElectRequest, UnBindSchemaReply, UnBindSchemaRequest, UnRegisterNodeReply,
+    UnRegisterNodeRequest, UncordonNodeReply, UncordonNodeRequest, UpdateSchemaReply,
+    UpdateSchemaRequest, UpdateTenantReply, UpdateTenantRequest, VoteReply, VoteRequest,
+    WatchLockReply, WatchLockRequest, WatchResourcesReply, WatchResourcesRequest,
 };
 
 use tonic::Streaming;
@@ -69,7 +79,26 @@ pub async fn $fn_name(
     UnRegisterNodeReply,
     UnRegisterNode
 );
+generate_meta_service_call!(cordon_node, CordonNodeRequest, CordonNodeReply, CordonNode);
+generate_meta_service_call!(
+    uncordon_node,
+    UncordonNodeRequest,
+    UncordonNodeReply,
+    UncordonNode
+);
+generate_meta_service_call!(
+    list_node_status,
+    ListNodeStatusRequest,
+    ListNodeStatusReply,
+    ListNodeStatus
+);
 generate_meta_service_call!(heartbeat, HeartbeatRequest, HeartbeatReply, Heartbeat);
+generate_meta_service_call!(
+    get_config_drift,
+    GetConfigDriftRequest,
+    GetConfigDriftReply,
+    GetConfigDrift
+);
 
 generate_meta_service_call!(
     set_resource_config,
@@ -125,6 +154,41 @@ pub async fn $fn_name(
     ListTenant
 );
 
+generate_meta_service_call!(
+    bootstrap_cache,
+    BootstrapCacheRequest,
+    Streaming<BootstrapCacheReply>,
+    BootstrapCache
+);
+
+generate_meta_service_call!(
+    watch_resources,
+    WatchResourcesRequest,
+    Streaming<WatchResourcesReply>,
+    WatchResources
+);
+
+generate_meta_service_call!(
+    check_resource_digest,
+    CheckResourceDigestRequest,
+    CheckResourceDigestReply,
+    CheckResourceDigest
+);
+
+generate_meta_service_call!(
+    export_metadata,
+    ExportMetadataRequest,
+    ExportMetadataReply,
+    ExportMetadata
+);
+
+generate_meta_service_call!(
+    import_metadata,
+    ImportMetadataRequest,
+    ImportMetadataReply,
+    ImportMetadata
+);
+
 generate_meta_service_call!(
     list_schema,
     ListSchemaRequest,
@@ -176,11 +240,67 @@ pub async fn $fn_name(
     GetOffsetData
 );
 
+generate_meta_service_call!(
+    list_offset_group,
+    ListOffsetGroupRequest,
+    ListOffsetGroupReply,
+    ListOffsetGroup
+);
+
+generate_meta_service_call!(
+    delete_offset_group,
+    DeleteOffsetGroupRequest,
+    DeleteOffsetGroupReply,
+    DeleteOffsetGroup
+);
+
+generate_meta_service_call!(
+    reset_offset_data,
+    ResetOffsetDataRequest,
+    ResetOffsetDataReply,
+    ResetOffsetData
+);
+
+generate_meta_service_call!(
+    consumer_group_heartbeat,
+    ConsumerGroupHeartbeatRequest,
+    ConsumerGroupHeartbeatReply,
+    ConsumerGroupHeartbeat
+);
+
+generate_meta_service_call!(
+    leave_consumer_group,
+    LeaveConsumerGroupRequest,
+    LeaveConsumerGroupReply,
+    LeaveConsumerGroup
+);
+
 generate_meta_service_call!(kv_set, SetRequest, SetReply, Set);
 generate_meta_service_call!(kv_get, GetRequest, GetReply, Get);
 generate_meta_service_call!(kv_delete, DeleteRequest, DeleteReply, Delete);
 generate_meta_service_call!(kv_exists, ExistsRequest, ExistsReply, Exists);
 generate_meta_service_call!(kv_get_prefix, GetPrefixRequest, GetPrefixReply, GetPrefix);
+generate_meta_service_call!(
+    kv_compare_and_set,
+    CompareAndSetRequest,
+    CompareAndSetReply,
+    CompareAndSet
+);
+
+generate_meta_service_call!(
+    acquire_lock,
+    AcquireLockRequest,
+    AcquireLockReply,
+    AcquireLock
+);
+generate_meta_service_call!(renew_lock, RenewLockRequest, RenewLockReply, RenewLock);
+generate_meta_service_call!(
+    release_lock,
+    ReleaseLockRequest,
+    ReleaseLockReply,
+    ReleaseLock
+);
+generate_meta_service_call!(watch_lock, WatchLockRequest, WatchLockReply, WatchLock);
 
 generate_meta_service_call!(placement_openraft_vote, VoteRequest, VoteReply, Vote);
 generate_meta_service_call!(
@@ -207,6 +327,18 @@ pub async fn $fn_name(
     LeaveClusterReply,
     LeaveCluster
 );
+generate_meta_service_call!(
+    transfer_leader,
+    TransferLeaderRequest,
+    TransferLeaderReply,
+    TransferLeader
+);
+generate_meta_service_call!(
+    trigger_elect,
+    TriggerElectRequest,
+    TriggerElectReply,
+    TriggerElect
+);
 
 // ShareGroup
 generate_meta_service_call!(
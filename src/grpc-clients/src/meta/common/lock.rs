@@ -0,0 +1,198 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::meta::common::call::{acquire_lock, release_lock, renew_lock, watch_lock};
+use crate::pool::ClientPool;
+use common_base::error::common::CommonError;
+use protocol::meta::meta_service_common::{
+    AcquireLockRequest, ReleaseLockRequest, RenewLockRequest, WatchLockReply, WatchLockRequest,
+};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Lease based lock held through the meta service. The holder keeps it by
+/// calling [`DistributedLock::keep_alive`] well within the lease, and must
+/// stop the guarded work as soon as it returns false.
+pub struct DistributedLock {
+    client_pool: Arc<ClientPool>,
+    addrs: Vec<String>,
+    name: String,
+    owner: String,
+    lease: Duration,
+    held: Mutex<Option<HeldLease>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct HeldLease {
+    token: u64,
+    // Taken before the request was sent, so the local view of the lease
+    // never outlives the one in the meta service.
+    granted_at: Instant,
+}
+
+impl HeldLease {
+    fn is_valid(&self, lease: Duration, now: Instant) -> bool {
+        now < self.granted_at + lease
+    }
+
+    fn needs_renew(&self, lease: Duration, now: Instant) -> bool {
+        now >= self.granted_at + lease / 2
+    }
+}
+
+impl DistributedLock {
+    pub fn new(
+        client_pool: Arc<ClientPool>,
+        addrs: Vec<String>,
+        name: impl Into<String>,
+        owner: impl Into<String>,
+        lease: Duration,
+    ) -> Self {
+        DistributedLock {
+            client_pool,
+            addrs,
+            name: name.into(),
+            owner: owner.into(),
+            lease,
+            held: Mutex::new(None),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Fencing token of the held lease.
+    pub fn token(&self) -> Option<u64> {
+        self.current().map(|held| held.token)
+    }
+
+    pub fn is_held(&self) -> bool {
+        self.current().is_some()
+    }
+
+    pub async fn try_acquire(&self) -> Result<bool, CommonError> {
+        let granted_at = Instant::now();
+        let request = AcquireLockRequest {
+            name: self.name.clone(),
+            owner: self.owner.clone(),
+            lease_ms: self.lease_ms(),
+        };
+        let reply = acquire_lock(&self.client_pool, &self.addrs, request).await?;
+        self.set_held(reply.acquired.then_some(HeldLease {
+            token: reply.token,
+            granted_at,
+        }));
+        Ok(reply.acquired)
+    }
+
+    pub async fn renew(&self) -> Result<bool, CommonError> {
+        let Some(held) = self.current() else {
+            return Ok(false);
+        };
+        let granted_at = Instant::now();
+        let request = RenewLockRequest {
+            name: self.name.clone(),
+            owner: self.owner.clone(),
+            token: held.token,
+            lease_ms: self.lease_ms(),
+        };
+        let reply = renew_lock(&self.client_pool, &self.addrs, request).await?;
+        self.set_held(reply.renewed.then_some(HeldLease {
+            token: reply.token,
+            granted_at,
+        }));
+        Ok(reply.renewed)
+    }
+
+    /// Acquire the lock when it is not held and renew it once half of the
+    /// lease has passed. Returns whether the lock is held afterwards; a
+    /// failed call keeps it only until the lease runs out.
+    pub async fn keep_alive(&self) -> bool {
+        let result = match self.current() {
+            Some(held) if !held.needs_renew(self.lease, Instant::now()) => return true,
+            Some(_) => self.renew().await,
+            None => self.try_acquire().await,
+        };
+        if let Err(e) = result {
+            warn!("Failed to keep lock {} alive: {}", self.name, e);
+        }
+        self.is_held()
+    }
+
+    pub async fn release(&self) -> Result<bool, CommonError> {
+        let Some(held) = self.held.lock().unwrap().take() else {
+            return Ok(false);
+        };
+        let request = ReleaseLockRequest {
+            name: self.name.clone(),
+            token: held.token,
+        };
+        let reply = release_lock(&self.client_pool, &self.addrs, request).await?;
+        Ok(reply.released)
+    }
+
+    /// Wait until the holder of the lock is no longer `holder`, or until
+    /// `timeout` passes. The meta service caps the wait at a few seconds.
+    pub async fn watch(
+        &self,
+        holder: &str,
+        timeout: Duration,
+    ) -> Result<WatchLockReply, CommonError> {
+        let request = WatchLockRequest {
+            name: self.name.clone(),
+            holder: holder.to_string(),
+            timeout_ms: timeout.as_millis() as u64,
+        };
+        watch_lock(&self.client_pool, &self.addrs, request).await
+    }
+
+    fn current(&self) -> Option<HeldLease> {
+        let mut held = self.held.lock().unwrap();
+        if held.is_some_and(|lease| !lease.is_valid(self.lease, Instant::now())) {
+            *held = None;
+        }
+        *held
+    }
+
+    fn set_held(&self, lease: Option<HeldLease>) {
+        *self.held.lock().unwrap() = lease;
+    }
+
+    fn lease_ms(&self) -> u64 {
+        self.lease.as_millis() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_held_lease_renew_and_expiry() {
+        let granted_at = Instant::now();
+        let held = HeldLease {
+            token: 1,
+            granted_at,
+        };
+        let lease = Duration::from_secs(10);
+
+        assert!(held.is_valid(lease, granted_at));
+        assert!(!held.needs_renew(lease, granted_at + Duration::from_secs(4)));
+        assert!(held.needs_renew(lease, granted_at + Duration::from_secs(5)));
+        assert!(held.is_valid(lease, granted_at + Duration::from_secs(9)));
+        assert!(!held.is_valid(lease, granted_at + lease));
+    }
+}
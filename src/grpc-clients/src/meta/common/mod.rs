@@ -14,24 +14,34 @@
 
 use protocol::meta::meta_service_common::meta_service_service_client::MetaServiceServiceClient;
 use protocol::meta::meta_service_common::{
-    AddShareGroupMemberReply, AddShareGroupMemberRequest, AppendReply, AppendRequest,
-    BindSchemaReply, BindSchemaRequest, ClusterStatusReply, ClusterStatusRequest,
+    AcquireLockReply, AcquireLockRequest, AddShareGroupMemberReply, AddShareGroupMemberRequest,
+    AppendReply, AppendRequest, BindSchemaReply, BindSchemaRequest, BootstrapCacheReply,
+    BootstrapCacheRequest, CheckResourceDigestReply, CheckResourceDigestRequest,
+    ClusterStatusReply, ClusterStatusRequest, CompareAndSetReply, CompareAndSetRequest,
+    ConsumerGroupHeartbeatReply, ConsumerGroupHeartbeatRequest, CordonNodeReply, CordonNodeRequest,
     CreateSchemaReply, CreateSchemaRequest, CreateShareGroupReply, CreateShareGroupRequest,
-    CreateTenantReply, CreateTenantRequest, DeleteReply, DeleteRequest, DeleteResourceConfigReply,
-    DeleteResourceConfigRequest, DeleteSchemaReply, DeleteSchemaRequest,
-    DeleteShareGroupMemberReply, DeleteShareGroupMemberRequest, DeleteShareGroupReply,
-    DeleteShareGroupRequest, DeleteTenantReply, DeleteTenantRequest, ExistsReply, ExistsRequest,
-    GetOffsetDataReply, GetOffsetDataRequest, GetPrefixReply, GetPrefixRequest, GetReply,
-    GetRequest, GetResourceConfigReply, GetResourceConfigRequest, HeartbeatReply, HeartbeatRequest,
-    JoinClusterReply, JoinClusterRequest, LeaveClusterReply, LeaveClusterRequest,
-    ListBindSchemaReply, ListBindSchemaRequest, ListSchemaReply, ListSchemaRequest,
+    CreateTenantReply, CreateTenantRequest, DeleteOffsetGroupReply, DeleteOffsetGroupRequest,
+    DeleteReply, DeleteRequest, DeleteResourceConfigReply, DeleteResourceConfigRequest,
+    DeleteSchemaReply, DeleteSchemaRequest, DeleteShareGroupMemberReply,
+    DeleteShareGroupMemberRequest, DeleteShareGroupReply, DeleteShareGroupRequest,
+    DeleteTenantReply, DeleteTenantRequest, ExistsReply, ExistsRequest, ExportMetadataReply,
+    ExportMetadataRequest, GetConfigDriftReply, GetConfigDriftRequest, GetOffsetDataReply,
+    GetOffsetDataRequest, GetPrefixReply, GetPrefixRequest, GetReply, GetRequest,
+    GetResourceConfigReply, GetResourceConfigRequest, HeartbeatReply, HeartbeatRequest,
+    ImportMetadataReply, ImportMetadataRequest, JoinClusterReply, JoinClusterRequest,
+    LeaveClusterReply, LeaveClusterRequest, LeaveConsumerGroupReply, LeaveConsumerGroupRequest,
+    ListBindSchemaReply, ListBindSchemaRequest, ListNodeStatusReply, ListNodeStatusRequest,
+    ListOffsetGroupReply, ListOffsetGroupRequest, ListSchemaReply, ListSchemaRequest,
     ListShareGroupMemberReply, ListShareGroupMemberRequest, ListShareGroupReply,
     ListShareGroupRequest, ListTenantReply, ListTenantRequest, NodeListReply, NodeListRequest,
-    RegisterNodeReply, RegisterNodeRequest, SaveOffsetDataReply, SaveOffsetDataRequest, SetReply,
-    SetRequest, SetResourceConfigReply, SetResourceConfigRequest, SnapshotReply, SnapshotRequest,
-    UnBindSchemaReply, UnBindSchemaRequest, UnRegisterNodeReply, UnRegisterNodeRequest,
-    UpdateSchemaReply, UpdateSchemaRequest, UpdateTenantReply, UpdateTenantRequest, VoteReply,
-    VoteRequest,
+    RegisterNodeReply, RegisterNodeRequest, ReleaseLockReply, ReleaseLockRequest, RenewLockReply,
+    RenewLockRequest, ResetOffsetDataReply, ResetOffsetDataRequest, SaveOffsetDataReply,
+    SaveOffsetDataRequest, SetReply, SetRequest, SetResourceConfigReply, SetResourceConfigRequest,
+    SnapshotReply, SnapshotRequest, TransferLeaderReply, TransferLeaderRequest, TriggerElectReply,
+    TriggerElectRequest, UnBindSchemaReply, UnBindSchemaRequest, UnRegisterNodeReply,
+    UnRegisterNodeRequest, UncordonNodeReply, UncordonNodeRequest, UpdateSchemaReply,
+    UpdateSchemaRequest, UpdateTenantReply, UpdateTenantRequest, VoteReply, VoteRequest,
+    WatchLockReply, WatchLockRequest, WatchResourcesReply, WatchResourcesRequest,
 };
 use tonic::transport::Channel;
 use tonic::Streaming;
@@ -39,6 +49,7 @@
 use crate::macros::impl_retriable_request;
 
 pub mod call;
+pub mod lock;
 
 impl_retriable_request!(
     ClusterStatusRequest,
@@ -80,6 +91,36 @@
     true
 );
 
+impl_retriable_request!(
+    CordonNodeRequest,
+    MetaServiceServiceClient<Channel>,
+    CordonNodeReply,
+    cordon_node,
+    "PlacementService",
+    "CordonNode",
+    true
+);
+
+impl_retriable_request!(
+    UncordonNodeRequest,
+    MetaServiceServiceClient<Channel>,
+    UncordonNodeReply,
+    uncordon_node,
+    "PlacementService",
+    "UncordonNode",
+    true
+);
+
+impl_retriable_request!(
+    ListNodeStatusRequest,
+    MetaServiceServiceClient<Channel>,
+    ListNodeStatusReply,
+    list_node_status,
+    "PlacementService",
+    "ListNodeStatus",
+    true
+);
+
 impl_retriable_request!(
     HeartbeatRequest,
     MetaServiceServiceClient<Channel>,
@@ -90,6 +131,16 @@
     true
 );
 
+impl_retriable_request!(
+    GetConfigDriftRequest,
+    MetaServiceServiceClient<Channel>,
+    GetConfigDriftReply,
+    get_config_drift,
+    "PlacementService",
+    "GetConfigDrift",
+    true
+);
+
 impl_retriable_request!(
     SetResourceConfigRequest,
     MetaServiceServiceClient<Channel>,
@@ -140,6 +191,56 @@
     true
 );
 
+impl_retriable_request!(
+    ListOffsetGroupRequest,
+    MetaServiceServiceClient<Channel>,
+    ListOffsetGroupReply,
+    list_offset_group,
+    "PlacementService",
+    "ListOffsetGroup",
+    true
+);
+
+impl_retriable_request!(
+    DeleteOffsetGroupRequest,
+    MetaServiceServiceClient<Channel>,
+    DeleteOffsetGroupReply,
+    delete_offset_group,
+    "PlacementService",
+    "DeleteOffsetGroup",
+    true
+);
+
+impl_retriable_request!(
+    ResetOffsetDataRequest,
+    MetaServiceServiceClient<Channel>,
+    ResetOffsetDataReply,
+    reset_offset_data,
+    "PlacementService",
+    "ResetOffsetData",
+    true
+);
+
+impl_retriable_request!(
+    ConsumerGroupHeartbeatRequest,
+    MetaServiceServiceClient<Channel>,
+    ConsumerGroupHeartbeatReply,
+    consumer_group_heartbeat,
+    "PlacementService",
+    "ConsumerGroupHeartbeat",
+    true
+);
+
+impl_retriable_request!(
+    LeaveConsumerGroupRequest,
+    MetaServiceServiceClient<Channel>,
+    LeaveConsumerGroupReply,
+    leave_consumer_group,
+    "PlacementService",
+    "LeaveConsumerGroup",
+    true
+);
+
 impl_retriable_request!(
     CreateTenantRequest,
     MetaServiceServiceClient<Channel>,
@@ -180,6 +281,56 @@
     true
 );
 
+impl_retriable_request!(
+    BootstrapCacheRequest,
+    MetaServiceServiceClient<Channel>,
+    Streaming<BootstrapCacheReply>,
+    bootstrap_cache,
+    "PlacementService",
+    "BootstrapCache",
+    true
+);
+
+// Pinned to the leader so that a resumed watch keeps landing on the node
+// that issued its revision.
+impl_retriable_request!(
+    WatchResourcesRequest,
+    MetaServiceServiceClient<Channel>,
+    Streaming<WatchResourcesReply>,
+    watch_resources,
+    "PlacementService",
+    "WatchResources",
+    true
+);
+
+impl_retriable_request!(
+    CheckResourceDigestRequest,
+    MetaServiceServiceClient<Channel>,
+    CheckResourceDigestReply,
+    check_resource_digest,
+    "PlacementService",
+    "CheckResourceDigest"
+);
+
+impl_retriable_request!(
+    ExportMetadataRequest,
+    MetaServiceServiceClient<Channel>,
+    ExportMetadataReply,
+    export_metadata,
+    "PlacementService",
+    "ExportMetadata"
+);
+
+impl_retriable_request!(
+    ImportMetadataRequest,
+    MetaServiceServiceClient<Channel>,
+    ImportMetadataReply,
+    import_metadata,
+    "PlacementService",
+    "ImportMetadata",
+    true
+);
+
 impl_retriable_request!(
     ListSchemaRequest,
     MetaServiceServiceClient<Channel>,
@@ -300,6 +451,55 @@
     true
 );
 
+impl_retriable_request!(
+    CompareAndSetRequest,
+    MetaServiceServiceClient<Channel>,
+    CompareAndSetReply,
+    compare_and_set,
+    "PlacementService",
+    "CompareAndSet",
+    true
+);
+
+impl_retriable_request!(
+    AcquireLockRequest,
+    MetaServiceServiceClient<Channel>,
+    AcquireLockReply,
+    acquire_lock,
+    "PlacementService",
+    "AcquireLock",
+    true
+);
+
+impl_retriable_request!(
+    RenewLockRequest,
+    MetaServiceServiceClient<Channel>,
+    RenewLockReply,
+    renew_lock,
+    "PlacementService",
+    "RenewLock",
+    true
+);
+
+impl_retriable_request!(
+    ReleaseLockRequest,
+    MetaServiceServiceClient<Channel>,
+    ReleaseLockReply,
+    release_lock,
+    "PlacementService",
+    "ReleaseLock",
+    true
+);
+
+impl_retriable_request!(
+    WatchLockRequest,
+    MetaServiceServiceClient<Channel>,
+    WatchLockReply,
+    watch_lock,
+    "PlacementService",
+    "WatchLock"
+);
+
 impl_retriable_request!(
     VoteRequest,
     MetaServiceServiceClient<Channel>,
@@ -350,6 +550,24 @@
     true
 );
 
+impl_retriable_request!(
+    TransferLeaderRequest,
+    MetaServiceServiceClient<Channel>,
+    TransferLeaderReply,
+    transfer_leader,
+    "PlacementService",
+    "TransferLeader"
+);
+
+impl_retriable_request!(
+    TriggerElectRequest,
+    MetaServiceServiceClient<Channel>,
+    TriggerElectReply,
+    trigger_elect,
+    "PlacementService",
+    "TriggerElect"
+);
+
 // ShareGroup
 impl_retriable_request!(
     ListShareGroupMemberRequest,
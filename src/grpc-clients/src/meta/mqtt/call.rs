@@ -14,21 +14,31 @@
 
 use common_base::error::common::CommonError;
 use protocol::meta::meta_service_mqtt::{
+    AllocateLastWillEpochReply, AllocateLastWillEpochRequest, ClaimLastWillReply,
+    ClaimLastWillRequest, CommitConnectorCheckpointReply, CommitConnectorCheckpointRequest,
     ConnectorHeartbeatReply, ConnectorHeartbeatRequest, CreateAclReply, CreateAclRequest,
     CreateAutoSubscribeRuleReply, CreateAutoSubscribeRuleRequest, CreateBlacklistReply,
-    CreateBlacklistRequest, CreateConnectorReply, CreateConnectorRequest, CreateSessionReply,
-    CreateSessionRequest, CreateTopicReply, CreateTopicRequest, CreateTopicRewriteRuleReply,
-    CreateTopicRewriteRuleRequest, CreateUserReply, CreateUserRequest, DeleteAclReply,
-    DeleteAclRequest, DeleteAutoSubscribeRuleReply, DeleteAutoSubscribeRuleRequest,
-    DeleteBlacklistReply, DeleteBlacklistRequest, DeleteConnectorReply, DeleteConnectorRequest,
-    DeleteSessionReply, DeleteSessionRequest, DeleteSubscribeReply, DeleteSubscribeRequest,
-    DeleteTopicReply, DeleteTopicRequest, DeleteTopicRewriteRuleReply,
-    DeleteTopicRewriteRuleRequest, DeleteUserReply, DeleteUserRequest, ListAclReply,
-    ListAclRequest, ListAutoSubscribeRuleReply, ListAutoSubscribeRuleRequest, ListBlacklistReply,
-    ListBlacklistRequest, ListConnectorReply, ListConnectorRequest, ListSessionReply,
-    ListSessionRequest, ListSubscribeReply, ListSubscribeRequest, ListTopicReply, ListTopicRequest,
-    ListTopicRewriteRuleReply, ListTopicRewriteRuleRequest, ListUserReply, ListUserRequest,
-    SetSubscribeReply, SetSubscribeRequest, UpdateConnectorReply, UpdateConnectorRequest,
+    CreateBlacklistRequest, CreateConnectorReply, CreateConnectorRequest, CreateMessageRuleReply,
+    CreateMessageRuleRequest, CreateSessionReply, CreateSessionRequest, CreateTopicReply,
+    CreateTopicRequest, CreateTopicRewriteRuleReply, CreateTopicRewriteRuleRequest,
+    CreateUserReply, CreateUserRequest, DeleteAclReply, DeleteAclRequest,
+    DeleteAutoSubscribeRuleReply, DeleteAutoSubscribeRuleRequest, DeleteBlacklistReply,
+    DeleteBlacklistRequest, DeleteConnectorReply, DeleteConnectorRequest, DeleteMessageRuleReply,
+    DeleteMessageRuleRequest, DeleteRetainIndexReply, DeleteRetainIndexRequest, DeleteSessionReply,
+    DeleteSessionRequest, DeleteSubscribeReply, DeleteSubscribeRequest, DeleteTopicReply,
+    DeleteTopicRequest, DeleteTopicRewriteRuleReply, DeleteTopicRewriteRuleRequest,
+    DeleteUserReply, DeleteUserRequest, DescribeTopicReply, DescribeTopicRequest,
+    GetConnectorCheckpointReply, GetConnectorCheckpointRequest, GetNodesForTopicReply,
+    GetNodesForTopicRequest, ListAclReply, ListAclRequest, ListAutoSubscribeRuleReply,
+    ListAutoSubscribeRuleRequest, ListBlacklistReply, ListBlacklistRequest, ListConnectorReply,
+    ListConnectorRequest, ListMessageRuleReply, ListMessageRuleRequest, ListRetainIndexReply,
+    ListRetainIndexRequest, ListSessionReply, ListSessionRequest, ListSubscribeReply,
+    ListSubscribeRequest, ListTopicReply, ListTopicRequest, ListTopicRewriteRuleReply,
+    ListTopicRewriteRuleRequest, ListUserReply, ListUserRequest, ReportTopicStatsReply,
+    ReportTopicStatsRequest, SetRetainIndexReply, SetRetainIndexRequest, SetSubscribeReply,
+    SetSubscribeRequest, SyncSubscribeRouteReply, SyncSubscribeRouteRequest, UpdateConnectorReply,
+    UpdateConnectorRequest, UpdateSubscribeRouteReply, UpdateSubscribeRouteRequest,
+    UpdateUserReply, UpdateUserRequest,
 };
 use tonic::Streaming;
 
@@ -52,6 +62,12 @@ pub async fn $fn_name(
     CreateUserReply,
     CreateUser
 );
+generate_mqtt_service_call!(
+    placement_update_user,
+    UpdateUserRequest,
+    UpdateUserReply,
+    UpdateUser
+);
 generate_mqtt_service_call!(
     placement_delete_user,
     DeleteUserRequest,
@@ -64,6 +80,29 @@ pub async fn $fn_name(
     ListUserReply,
     ListUser
 );
+
+/// Fetch every user matching `request` in pages of `page_size`, stopping once
+/// the reply `total` is reached. `offset` and `limit` of `request` are ignored.
+pub async fn placement_list_user_all(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    mut request: ListUserRequest,
+    page_size: u32,
+) -> Result<Vec<Vec<u8>>, CommonError> {
+    request.offset = 0;
+    request.limit = page_size.max(1);
+    let mut users = Vec::new();
+    loop {
+        let reply = placement_list_user(client_pool, addrs, request.clone()).await?;
+        let fetched = reply.users.len() as u32;
+        users.extend(reply.users);
+        if fetched == 0 || users.len() as u64 >= reply.total {
+            return Ok(users);
+        }
+        request.offset += fetched;
+    }
+}
+
 generate_mqtt_service_call!(
     placement_create_topic,
     CreateTopicRequest,
@@ -76,6 +115,42 @@ pub async fn $fn_name(
     DeleteTopicReply,
     DeleteTopic
 );
+
+generate_mqtt_service_call!(
+    placement_report_topic_stats,
+    ReportTopicStatsRequest,
+    ReportTopicStatsReply,
+    ReportTopicStats
+);
+
+generate_mqtt_service_call!(
+    placement_describe_topic,
+    DescribeTopicRequest,
+    DescribeTopicReply,
+    DescribeTopic
+);
+
+generate_mqtt_service_call!(
+    placement_set_retain_index,
+    SetRetainIndexRequest,
+    SetRetainIndexReply,
+    SetRetainIndex
+);
+
+generate_mqtt_service_call!(
+    placement_delete_retain_index,
+    DeleteRetainIndexRequest,
+    DeleteRetainIndexReply,
+    DeleteRetainIndex
+);
+
+generate_mqtt_service_call!(
+    placement_list_retain_index,
+    ListRetainIndexRequest,
+    ListRetainIndexReply,
+    ListRetainIndex
+);
+
 generate_mqtt_service_call!(
     placement_list_topic,
     ListTopicRequest,
@@ -94,6 +169,18 @@ pub async fn $fn_name(
     DeleteSessionReply,
     DeleteSession
 );
+generate_mqtt_service_call!(
+    allocate_last_will_epoch,
+    AllocateLastWillEpochRequest,
+    AllocateLastWillEpochReply,
+    AllocateLastWillEpoch
+);
+generate_mqtt_service_call!(
+    claim_last_will,
+    ClaimLastWillRequest,
+    ClaimLastWillReply,
+    ClaimLastWill
+);
 generate_mqtt_service_call!(
     placement_list_session,
     ListSessionRequest,
@@ -196,6 +283,20 @@ pub async fn $fn_name(
     ConnectorHeartbeat
 );
 
+generate_mqtt_service_call!(
+    placement_commit_connector_checkpoint,
+    CommitConnectorCheckpointRequest,
+    CommitConnectorCheckpointReply,
+    CommitConnectorCheckpoint
+);
+
+generate_mqtt_service_call!(
+    placement_get_connector_checkpoint,
+    GetConnectorCheckpointRequest,
+    GetConnectorCheckpointReply,
+    GetConnectorCheckpoint
+);
+
 generate_mqtt_service_call!(
     placement_list_auto_subscribe_rule,
     ListAutoSubscribeRuleRequest,
@@ -214,3 +315,41 @@ pub async fn $fn_name(
     DeleteAutoSubscribeRuleReply,
     DeleteAutoSubscribeRule
 );
+
+generate_mqtt_service_call!(
+    placement_list_message_rule,
+    ListMessageRuleRequest,
+    ListMessageRuleReply,
+    ListMessageRule
+);
+generate_mqtt_service_call!(
+    placement_create_message_rule,
+    CreateMessageRuleRequest,
+    CreateMessageRuleReply,
+    CreateMessageRule
+);
+generate_mqtt_service_call!(
+    placement_delete_message_rule,
+    DeleteMessageRuleRequest,
+    DeleteMessageRuleReply,
+    DeleteMessageRule
+);
+
+generate_mqtt_service_call!(
+    placement_update_subscribe_route,
+    UpdateSubscribeRouteRequest,
+    UpdateSubscribeRouteReply,
+    UpdateSubscribeRoute
+);
+generate_mqtt_service_call!(
+    placement_sync_subscribe_route,
+    SyncSubscribeRouteRequest,
+    SyncSubscribeRouteReply,
+    SyncSubscribeRoute
+);
+generate_mqtt_service_call!(
+    placement_get_nodes_for_topic,
+    GetNodesForTopicRequest,
+    GetNodesForTopicReply,
+    GetNodesForTopic
+);
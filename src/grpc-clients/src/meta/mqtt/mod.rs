@@ -14,21 +14,28 @@
 
 use protocol::meta::meta_service_mqtt::mqtt_service_client::MqttServiceClient;
 use protocol::meta::meta_service_mqtt::{
+    AllocateLastWillEpochReply, AllocateLastWillEpochRequest, ClaimLastWillReply,
+    ClaimLastWillRequest, CommitConnectorCheckpointReply, CommitConnectorCheckpointRequest,
     ConnectorHeartbeatReply, ConnectorHeartbeatRequest, CreateAclReply, CreateAclRequest,
     CreateAutoSubscribeRuleReply, CreateAutoSubscribeRuleRequest, CreateBlacklistReply,
-    CreateBlacklistRequest, CreateConnectorReply, CreateConnectorRequest, CreateSessionReply,
-    CreateSessionRequest, CreateTopicReply, CreateTopicRequest, CreateTopicRewriteRuleReply,
-    CreateTopicRewriteRuleRequest, CreateUserReply, CreateUserRequest, DeleteAclReply,
-    DeleteAclRequest, DeleteAutoSubscribeRuleReply, DeleteAutoSubscribeRuleRequest,
-    DeleteBlacklistReply, DeleteBlacklistRequest, DeleteConnectorReply, DeleteConnectorRequest,
-    DeleteSessionReply, DeleteSessionRequest, DeleteSubscribeReply, DeleteSubscribeRequest,
-    DeleteTopicReply, DeleteTopicRequest, DeleteTopicRewriteRuleReply,
-    DeleteTopicRewriteRuleRequest, DeleteUserReply, DeleteUserRequest, ListAclReply,
+    CreateBlacklistRequest, CreateConnectorReply, CreateConnectorRequest, CreateMessageRuleReply,
+    CreateMessageRuleRequest, CreateSessionReply, CreateSessionRequest, CreateTopicReply,
+    CreateTopicRequest, CreateTopicRewriteRuleReply, CreateTopicRewriteRuleRequest,
+    CreateUserReply, CreateUserRequest, DeleteAclReply, DeleteAclRequest,
+    DeleteAutoSubscribeRuleReply, DeleteAutoSubscribeRuleRequest, DeleteBlacklistReply,
+    DeleteBlacklistRequest, DeleteConnectorReply, DeleteConnectorRequest, DeleteMessageRuleReply,
+    DeleteMessageRuleRequest, DeleteSessionReply, DeleteSessionRequest, DeleteSubscribeReply,
+    DeleteSubscribeRequest, DeleteTopicReply, DeleteTopicRequest, DeleteTopicRewriteRuleReply,
+    DeleteTopicRewriteRuleRequest, DeleteUserReply, DeleteUserRequest, GetConnectorCheckpointReply,
+    GetConnectorCheckpointRequest, GetNodesForTopicReply, GetNodesForTopicRequest, ListAclReply,
     ListAclRequest, ListAutoSubscribeRuleReply, ListAutoSubscribeRuleRequest, ListBlacklistReply,
-    ListBlacklistRequest, ListConnectorReply, ListConnectorRequest, ListSessionReply,
-    ListSessionRequest, ListSubscribeReply, ListSubscribeRequest, ListTopicReply, ListTopicRequest,
-    ListTopicRewriteRuleReply, ListTopicRewriteRuleRequest, ListUserReply, ListUserRequest,
-    SetSubscribeReply, SetSubscribeRequest, UpdateConnectorReply, UpdateConnectorRequest,
+    ListBlacklistRequest, ListConnectorReply, ListConnectorRequest, ListMessageRuleReply,
+    ListMessageRuleRequest, ListSessionReply, ListSessionRequest, ListSubscribeReply,
+    ListSubscribeRequest, ListTopicReply, ListTopicRequest, ListTopicRewriteRuleReply,
+    ListTopicRewriteRuleRequest, ListUserReply, ListUserRequest, SetSubscribeReply,
+    SetSubscribeRequest, SyncSubscribeRouteReply, SyncSubscribeRouteRequest, UpdateConnectorReply,
+    UpdateConnectorRequest, UpdateSubscribeRouteReply, UpdateSubscribeRouteRequest,
+    UpdateUserReply, UpdateUserRequest,
 };
 use tonic::transport::Channel;
 use tonic::Streaming;
@@ -47,6 +54,16 @@
     true
 );
 
+impl_retriable_request!(
+    UpdateUserRequest,
+    MqttServiceClient<Channel>,
+    UpdateUserReply,
+    update_user,
+    "MqttService",
+    "UpdateUser",
+    true
+);
+
 impl_retriable_request!(
     DeleteUserRequest,
     MqttServiceClient<Channel>,
@@ -117,6 +134,26 @@
     true
 );
 
+impl_retriable_request!(
+    AllocateLastWillEpochRequest,
+    MqttServiceClient<Channel>,
+    AllocateLastWillEpochReply,
+    allocate_last_will_epoch,
+    "MqttService",
+    "AllocateLastWillEpoch",
+    true
+);
+
+impl_retriable_request!(
+    ClaimLastWillRequest,
+    MqttServiceClient<Channel>,
+    ClaimLastWillReply,
+    claim_last_will,
+    "MqttService",
+    "ClaimLastWill",
+    true
+);
+
 impl_retriable_request!(
     ListSessionRequest,
     MqttServiceClient<Channel>,
@@ -297,6 +334,26 @@
     true
 );
 
+impl_retriable_request!(
+    CommitConnectorCheckpointRequest,
+    MqttServiceClient<Channel>,
+    CommitConnectorCheckpointReply,
+    commit_connector_checkpoint,
+    "MqttService",
+    "CommitConnectorCheckpoint",
+    true
+);
+
+impl_retriable_request!(
+    GetConnectorCheckpointRequest,
+    MqttServiceClient<Channel>,
+    GetConnectorCheckpointReply,
+    get_connector_checkpoint,
+    "MqttService",
+    "GetConnectorCheckpoint",
+    true
+);
+
 impl_retriable_request!(
     ListAutoSubscribeRuleRequest,
     MqttServiceClient<Channel>,
@@ -326,3 +383,63 @@
     "DeleteAutoSubscribeRule",
     true
 );
+
+impl_retriable_request!(
+    ListMessageRuleRequest,
+    MqttServiceClient<Channel>,
+    ListMessageRuleReply,
+    list_message_rule,
+    "MqttService",
+    "ListMessageRule",
+    true
+);
+
+impl_retriable_request!(
+    CreateMessageRuleRequest,
+    MqttServiceClient<Channel>,
+    CreateMessageRuleReply,
+    create_message_rule,
+    "MqttService",
+    "CreateMessageRule",
+    true
+);
+
+impl_retriable_request!(
+    DeleteMessageRuleRequest,
+    MqttServiceClient<Channel>,
+    DeleteMessageRuleReply,
+    delete_message_rule,
+    "MqttService",
+    "DeleteMessageRule",
+    true
+);
+
+impl_retriable_request!(
+    UpdateSubscribeRouteRequest,
+    MqttServiceClient<Channel>,
+    UpdateSubscribeRouteReply,
+    update_subscribe_route,
+    "MqttService",
+    "UpdateSubscribeRoute",
+    true
+);
+
+impl_retriable_request!(
+    SyncSubscribeRouteRequest,
+    MqttServiceClient<Channel>,
+    SyncSubscribeRouteReply,
+    sync_subscribe_route,
+    "MqttService",
+    "SyncSubscribeRoute",
+    true
+);
+
+impl_retriable_request!(
+    GetNodesForTopicRequest,
+    MqttServiceClient<Channel>,
+    GetNodesForTopicReply,
+    get_nodes_for_topic,
+    "MqttService",
+    "GetNodesForTopic",
+    true
+);
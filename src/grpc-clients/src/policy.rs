@@ -0,0 +1,111 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use rand::Rng;
+use std::time::Duration;
+
+/// Retry behaviour for gRPC calls made through [`crate::pool::ClientPool`].
+///
+/// Policies are registered per service (`"PlacementService"`) or per method
+/// (`"PlacementService/CreateSession"`) when the pool is built; calls without
+/// a matching entry use the pool's default policy.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// Minimum number of attempts. A call always tries every address at least
+    /// once, so the effective count may be higher.
+    pub max_attempts: usize,
+    /// Backoff before the second attempt, doubled on every further attempt.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Fraction of the backoff that is randomised, in `[0, 1]`. Spreads the
+    /// retries of many callers that failed at the same moment.
+    pub jitter: f64,
+    /// Timeout of a single attempt against one node.
+    pub per_call_timeout: Duration,
+    /// Total time budget of the call across all attempts and backoffs.
+    pub deadline: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        // A few quick retries to ride out transient failures (leader briefly
+        // busy, a peer still starting up) without exceeding callers' own
+        // timeouts — e.g. heartbeat wraps calls in a 3s timeout. Callers
+        // needing longer recovery (e.g. node re-registration) retry at their
+        // own layer.
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+            jitter: 0.2,
+            // Prevents a node that is reachable at TCP level but not
+            // responding (e.g. installing a Raft snapshot) from blocking the
+            // entire retry loop.
+            per_call_timeout: Duration::from_secs(5),
+            deadline: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Backoff to wait after the given (1-based) failed attempt.
+    pub fn backoff(&self, attempt: usize) -> Duration {
+        let exp = attempt.saturating_sub(1).min(16) as u32;
+        let base = self
+            .initial_backoff
+            .saturating_mul(1 << exp)
+            .min(self.max_backoff);
+
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        if jitter == 0.0 || base.is_zero() {
+            return base;
+        }
+        let factor = rand::thread_rng().gen_range(1.0 - jitter..=1.0 + jitter);
+        base.mul_f64(factor).min(self.max_backoff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_is_exponential_and_capped() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+            jitter: 0.0,
+            ..Default::default()
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(4), Duration::from_millis(500));
+        assert_eq!(policy.backoff(100), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_backoff_jitter_stays_in_range() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            jitter: 0.5,
+            ..Default::default()
+        };
+        for _ in 0..100 {
+            let backoff = policy.backoff(2);
+            assert!(backoff >= Duration::from_millis(100), "{backoff:?}");
+            assert!(backoff <= Duration::from_millis(300), "{backoff:?}");
+        }
+    }
+}
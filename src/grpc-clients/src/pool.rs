@@ -12,11 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::breaker::{CircuitBreakerConfig, CircuitState, EndpointBreaker};
+use crate::policy::RetryPolicy;
+use crate::tls::{grpc_tls, GrpcTlsConnector};
+use common_metrics::grpc::{record_grpc_client_circuit_rejected, set_grpc_client_circuit_state};
 use dashmap::mapref::one::Ref;
 use dashmap::DashMap;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tonic::transport::Channel;
 use tracing::info;
 
@@ -47,7 +51,7 @@ fn new(addr: &str, num_channels: usize) -> Self {
     }
 
     fn create_channel(addr: &str) -> Channel {
-        Channel::from_shared(format!("http://{}", addr))
+        let endpoint = Channel::from_shared(format!("http://{}", addr))
             .expect("Invalid gRPC URI")
             .connect_timeout(Duration::from_secs(5))
             .timeout(Duration::from_secs(60))
@@ -56,8 +60,11 @@ fn create_channel(addr: &str) -> Channel {
             .http2_adaptive_window(true)
             .http2_keep_alive_interval(Duration::from_secs(30))
             .keep_alive_timeout(Duration::from_secs(60))
-            .keep_alive_while_idle(true)
-            .connect_lazy()
+            .keep_alive_while_idle(true);
+        match grpc_tls() {
+            Some(tls) => endpoint.connect_with_connector_lazy(GrpcTlsConnector::new(tls)),
+            None => endpoint.connect_lazy(),
+        }
     }
 
     fn get(&self) -> Channel {
@@ -78,12 +85,24 @@ fn get(&self) -> Channel {
 /// let channel = pool.get_channel("127.0.0.1:1228");
 /// let client = MetaServiceServiceClient::new(channel);
 /// ```
+///
+/// Retry behaviour of the generated call functions is configured when the pool
+/// is built, see [`RetryPolicy`]:
+/// ```ignore
+/// let pool = ClientPool::new(4)
+///     .with_retry_policy("BrokerService", RetryPolicy { max_attempts: 1, ..Default::default() });
+/// ```
 #[derive(Clone)]
 pub struct ClientPool {
     channels_per_address: usize,
     channel_pools: Arc<DashMap<String, Arc<ChannelPool>>>,
     // leader cache for write requests (Raft leader routing)
     meta_service_leader_addr_caches: Arc<DashMap<String, String>>,
+    default_retry_policy: Arc<RetryPolicy>,
+    // keyed by service name or "Service/Method"
+    retry_policies: Arc<DashMap<String, Arc<RetryPolicy>>>,
+    circuit_breaker_config: Arc<CircuitBreakerConfig>,
+    circuit_breakers: Arc<DashMap<String, EndpointBreaker>>,
 }
 
 impl ClientPool {
@@ -97,7 +116,43 @@ pub fn new(channels_per_address: usize) -> Self {
             channels_per_address,
             channel_pools: Arc::new(DashMap::with_capacity(8)),
             meta_service_leader_addr_caches: Arc::new(DashMap::with_capacity(2)),
+            default_retry_policy: Arc::new(RetryPolicy::default()),
+            retry_policies: Arc::new(DashMap::with_capacity(2)),
+            circuit_breaker_config: Arc::new(CircuitBreakerConfig::default()),
+            circuit_breakers: Arc::new(DashMap::with_capacity(8)),
+        }
+    }
+
+    pub fn with_circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.circuit_breaker_config = Arc::new(config);
+        self
+    }
+
+    pub fn with_default_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.default_retry_policy = Arc::new(policy);
+        self
+    }
+
+    /// Set the retry policy for a service (`"PlacementService"`) or a single
+    /// method (`"PlacementService/CreateSession"`). Method entries take
+    /// precedence over service entries.
+    pub fn with_retry_policy(self, target: &str, policy: RetryPolicy) -> Self {
+        self.retry_policies
+            .insert(target.to_string(), Arc::new(policy));
+        self
+    }
+
+    /// Resolve the retry policy for a `"Service/Method"` name.
+    pub fn retry_policy(&self, method: &str) -> Arc<RetryPolicy> {
+        if let Some(policy) = self.retry_policies.get(method) {
+            return policy.clone();
+        }
+        if let Some((service, _)) = method.split_once('/') {
+            if let Some(policy) = self.retry_policies.get(service) {
+                return policy.clone();
+            }
         }
+        self.default_retry_policy.clone()
     }
 
     /// Get an HTTP/2 channel for the given address.
@@ -112,6 +167,66 @@ pub fn get_channel(&self, addr: &str) -> Channel {
         pool.get()
     }
 
+    // ----------circuit breaker -------------
+    pub fn circuit_state(&self, addr: &str) -> CircuitState {
+        self.circuit_breakers
+            .get(addr)
+            .map(|b| b.state())
+            .unwrap_or(CircuitState::Closed)
+    }
+
+    /// Whether a call to `addr` may be sent. A rejected call is counted and
+    /// should be routed to another address or fail fast.
+    pub(crate) fn circuit_allow(&self, addr: &str) -> bool {
+        let Some(mut breaker) = self.circuit_breakers.get_mut(addr) else {
+            return true;
+        };
+        let before = breaker.state();
+        let allowed = breaker.allow(&self.circuit_breaker_config, Instant::now());
+        let after = breaker.state();
+        drop(breaker);
+
+        if before != after {
+            set_grpc_client_circuit_state(addr, after.as_metric());
+        }
+        if !allowed {
+            record_grpc_client_circuit_rejected(addr);
+        }
+        allowed
+    }
+
+    pub(crate) fn circuit_on_success(&self, addr: &str) {
+        self.update_circuit(addr, |breaker, config, now| breaker.on_success(config, now));
+    }
+
+    pub(crate) fn circuit_on_failure(&self, addr: &str) {
+        self.update_circuit(addr, |breaker, config, now| breaker.on_failure(config, now));
+    }
+
+    fn update_circuit(
+        &self,
+        addr: &str,
+        f: impl FnOnce(&mut EndpointBreaker, &CircuitBreakerConfig, Instant),
+    ) {
+        let now = Instant::now();
+        let mut breaker = self
+            .circuit_breakers
+            .entry(addr.to_string())
+            .or_insert_with(|| EndpointBreaker::new(now));
+        let before = breaker.state();
+        f(&mut breaker, &self.circuit_breaker_config, now);
+        let after = breaker.state();
+        drop(breaker);
+
+        if before != after {
+            info!(
+                "gRPC circuit for {} changed from {:?} to {:?}",
+                addr, before, after
+            );
+            set_grpc_client_circuit_state(addr, after.as_metric());
+        }
+    }
+
     // ----------leader cache management -------------
     pub fn get_leader_addr(&self, method: &str) -> Option<Ref<'_, String, String>> {
         self.meta_service_leader_addr_caches.get(method)
@@ -134,3 +249,59 @@ pub fn clear_leader_cache(&self) {
         self.meta_service_leader_addr_caches.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_retry_policy_resolution() {
+        let service = RetryPolicy {
+            max_attempts: 5,
+            ..Default::default()
+        };
+        let method = RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        };
+        let default = RetryPolicy {
+            deadline: Duration::from_secs(3),
+            ..Default::default()
+        };
+        let pool = ClientPool::new(1)
+            .with_default_retry_policy(default.clone())
+            .with_retry_policy("BrokerService", service.clone())
+            .with_retry_policy("BrokerService/UpdateCache", method.clone());
+
+        assert_eq!(*pool.retry_policy("BrokerService/UpdateCache"), method);
+        assert_eq!(*pool.retry_policy("BrokerService/ResyncCache"), service);
+        assert_eq!(*pool.retry_policy("PlacementService/NodeList"), default);
+
+        // clones share the same configuration
+        assert_eq!(
+            *pool.clone().retry_policy("BrokerService/ResyncCache"),
+            service
+        );
+    }
+
+    #[test]
+    fn test_circuit_breaker_per_address() {
+        let pool = ClientPool::new(1).with_circuit_breaker(CircuitBreakerConfig {
+            min_requests: 2,
+            ..Default::default()
+        });
+        let bad = "127.0.0.1:7001";
+        let good = "127.0.0.1:7002";
+
+        assert!(pool.circuit_allow(bad));
+        pool.circuit_on_failure(bad);
+        pool.circuit_on_failure(bad);
+        pool.circuit_on_success(good);
+
+        assert_eq!(pool.circuit_state(bad), CircuitState::Open);
+        assert!(!pool.circuit_allow(bad));
+        assert_eq!(pool.circuit_state(good), CircuitState::Closed);
+        assert!(pool.circuit_allow(good));
+    }
+}
@@ -0,0 +1,409 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! TLS for the internal gRPC channels.
+//!
+//! A node has a single TLS context, built from `grpc_tls` in the broker config
+//! at startup. Every [`ClientPool`](crate::pool::ClientPool) dials through it
+//! and the gRPC server accepts with it, so enabling TLS covers broker, meta
+//! service and storage engine traffic alike. Peer identities are SPIFFE ids
+//! carried as URI SANs in the certificates.
+
+use common_base::error::common::CommonError;
+use common_base::error::ResultCommonError;
+use common_base::tools::loop_select_ticket;
+use common_config::config::GrpcTls;
+use hyper_util::rt::TokioIo;
+use rustls_pemfile::{certs, private_key};
+use std::fs::{self, File};
+use std::future::Future;
+use std::io::{self, BufReader};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::task::{Context, Poll};
+use std::time::SystemTime;
+use tokio::net::TcpStream;
+use tokio::sync::broadcast;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore, ServerConfig};
+use tokio_rustls::TlsConnector;
+use tonic::transport::Uri;
+use tower::Service;
+use tracing::{error, info};
+use x509_parser::extensions::GeneralName;
+use x509_parser::parse_x509_certificate;
+
+const SPIFFE_SCHEME: &str = "spiffe://";
+
+static GRPC_TLS: OnceLock<Arc<GrpcTlsContext>> = OnceLock::new();
+
+/// Build the process-wide gRPC TLS context. Returns `None` when TLS is
+/// disabled; only the first successful call builds the context.
+pub fn init_grpc_tls(conf: &GrpcTls) -> Result<Option<Arc<GrpcTlsContext>>, CommonError> {
+    if !conf.enable {
+        return Ok(None);
+    }
+    if let Some(ctx) = GRPC_TLS.get() {
+        return Ok(Some(ctx.clone()));
+    }
+    let ctx = Arc::new(GrpcTlsContext::new(conf.clone())?);
+    Ok(Some(GRPC_TLS.get_or_init(|| ctx).clone()))
+}
+
+pub fn grpc_tls() -> Option<Arc<GrpcTlsContext>> {
+    GRPC_TLS.get().cloned()
+}
+
+/// Server and client TLS configs of this node. A reload swaps both configs, so
+/// new connections use the rotated certificate and CA bundle while
+/// established channels keep their session.
+pub struct GrpcTlsContext {
+    conf: GrpcTls,
+    server: RwLock<Arc<ServerConfig>>,
+    client: RwLock<Arc<ClientConfig>>,
+    // newest modification time of the watched files at the last load
+    modified: Mutex<Option<SystemTime>>,
+    reload_started: AtomicBool,
+}
+
+impl GrpcTlsContext {
+    pub fn new(conf: GrpcTls) -> Result<Self, CommonError> {
+        if conf.cert.is_empty() || conf.key.is_empty() || conf.ca_cert.is_empty() {
+            return Err(CommonError::CommonError(
+                "grpc_tls.cert, grpc_tls.key and grpc_tls.ca_cert must be set when gRPC TLS is enabled"
+                    .to_string(),
+            ));
+        }
+        let modified = latest_modified(&conf);
+        let (server, client) = build_configs(&conf)?;
+        Ok(GrpcTlsContext {
+            conf,
+            server: RwLock::new(Arc::new(server)),
+            client: RwLock::new(Arc::new(client)),
+            modified: Mutex::new(modified),
+            reload_started: AtomicBool::new(false),
+        })
+    }
+
+    pub fn is_mutual(&self) -> bool {
+        self.conf.mutual
+    }
+
+    pub fn server_config(&self) -> Arc<ServerConfig> {
+        self.server.read().unwrap().clone()
+    }
+
+    pub fn client_config(&self) -> Arc<ClientConfig> {
+        self.client.read().unwrap().clone()
+    }
+
+    /// Reload the certificate, key and CA bundle if any of them changed since
+    /// the last load. On failure the previous configs stay in use.
+    pub fn reload_if_changed(&self) -> Result<bool, CommonError> {
+        let modified = latest_modified(&self.conf);
+        if modified == *self.modified.lock().unwrap() {
+            return Ok(false);
+        }
+
+        let (server, client) = build_configs(&self.conf)?;
+        *self.server.write().unwrap() = Arc::new(server);
+        *self.client.write().unwrap() = Arc::new(client);
+        *self.modified.lock().unwrap() = modified;
+        Ok(true)
+    }
+
+    /// Check the SPIFFE id of a peer whose chain was already verified by the
+    /// handshake against `allowed_peer_ids` / `trust_domain`.
+    pub fn verify_peer(&self, peer_certs: Option<&[CertificateDer<'_>]>) -> ResultCommonError {
+        if self.conf.allowed_peer_ids.is_empty() && self.conf.trust_domain.is_empty() {
+            return Ok(());
+        }
+        let ids = peer_spiffe_ids(peer_certs);
+        if peer_id_allowed(&self.conf, &ids) {
+            return Ok(());
+        }
+        Err(CommonError::CommonError(format!(
+            "gRPC peer identity {ids:?} is not allowed"
+        )))
+    }
+
+    fn server_name(&self, host: &str) -> Result<ServerName<'static>, CommonError> {
+        let name = if self.conf.server_name.is_empty() {
+            host.trim_start_matches('[').trim_end_matches(']')
+        } else {
+            self.conf.server_name.as_str()
+        };
+        ServerName::try_from(name.to_string()).map_err(|e| {
+            CommonError::CommonError(format!("Invalid gRPC TLS server name {name}: {e}"))
+        })
+    }
+}
+
+/// Start the background check for certificate changes. Only the first call
+/// starts a task.
+pub fn start_grpc_tls_reload_thread(ctx: Arc<GrpcTlsContext>, stop_send: broadcast::Sender<bool>) {
+    let interval_sec = ctx.conf.reload_interval_sec;
+    if interval_sec == 0 || ctx.reload_started.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tokio::spawn(Box::pin(async move {
+        let ac_fn = async || -> ResultCommonError {
+            match ctx.reload_if_changed() {
+                Ok(true) => info!("gRPC TLS certificate {} reloaded", ctx.conf.cert),
+                Ok(false) => {}
+                Err(e) => error!(
+                    "Failed to reload gRPC TLS certificate {}, keeping the current one: {}",
+                    ctx.conf.cert, e
+                ),
+            }
+            Ok(())
+        };
+        loop_select_ticket(ac_fn, interval_sec * 1000, &stop_send).await;
+    }));
+}
+
+/// Connector used by the channel pool when TLS is enabled. The channel URI
+/// stays `http://host:port`; the TLS handshake happens below HTTP/2.
+#[derive(Clone)]
+pub(crate) struct GrpcTlsConnector {
+    ctx: Arc<GrpcTlsContext>,
+}
+
+impl GrpcTlsConnector {
+    pub(crate) fn new(ctx: Arc<GrpcTlsContext>) -> Self {
+        GrpcTlsConnector { ctx }
+    }
+}
+
+type ConnectFuture =
+    Pin<Box<dyn Future<Output = io::Result<TokioIo<TlsStream<TcpStream>>>> + Send>>;
+
+impl Service<Uri> for GrpcTlsConnector {
+    type Response = TokioIo<TlsStream<TcpStream>>;
+    type Error = io::Error;
+    type Future = ConnectFuture;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let ctx = self.ctx.clone();
+        Box::pin(async move {
+            let (Some(host), Some(port)) = (uri.host(), uri.port_u16()) else {
+                return Err(io::Error::other(format!("Invalid gRPC address {uri}")));
+            };
+            let server_name = ctx.server_name(host).map_err(io::Error::other)?;
+
+            let tcp =
+                TcpStream::connect((host.trim_start_matches('[').trim_end_matches(']'), port))
+                    .await?;
+            tcp.set_nodelay(true)?;
+            let stream = TlsConnector::from(ctx.client_config())
+                .connect(server_name, tcp)
+                .await?;
+            ctx.verify_peer(stream.get_ref().1.peer_certificates())
+                .map_err(io::Error::other)?;
+            Ok(TokioIo::new(stream))
+        })
+    }
+}
+
+fn build_configs(conf: &GrpcTls) -> Result<(ServerConfig, ClientConfig), CommonError> {
+    let cert_chain = load_certs(Path::new(&conf.cert))?;
+    let key = load_key(Path::new(&conf.key))?;
+    if cert_chain.is_empty() {
+        return Err(CommonError::CommonError(format!(
+            "No certificate found in {}",
+            conf.cert
+        )));
+    }
+
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(Path::new(&conf.ca_cert))? {
+        roots.add(cert)?;
+    }
+    let roots = Arc::new(roots);
+
+    let server_builder = if conf.mutual {
+        let verifier = WebPkiClientVerifier::builder(roots.clone())
+            .build()
+            .map_err(|e| {
+                CommonError::CommonError(format!(
+                    "Failed to build gRPC client certificate verifier: {e}"
+                ))
+            })?;
+        ServerConfig::builder().with_client_cert_verifier(verifier)
+    } else {
+        ServerConfig::builder().with_no_client_auth()
+    };
+    let mut server = server_builder.with_single_cert(cert_chain.clone(), key.clone_key())?;
+    // the gRPC port also serves gRPC-Web over HTTP/1.1
+    server.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    let client_builder = ClientConfig::builder().with_root_certificates(roots);
+    let mut client = if conf.mutual {
+        client_builder.with_client_auth_cert(cert_chain, key)?
+    } else {
+        client_builder.with_no_client_auth()
+    };
+    client.alpn_protocols = vec![b"h2".to_vec()];
+
+    Ok((server, client))
+}
+
+fn load_certs(path: &Path) -> io::Result<Vec<CertificateDer<'static>>> {
+    certs(&mut BufReader::new(File::open(path)?)).collect()
+}
+
+fn load_key(path: &Path) -> io::Result<PrivateKeyDer<'static>> {
+    private_key(&mut BufReader::new(File::open(path)?))?.ok_or(io::Error::other(format!(
+        "no private key found in {}",
+        path.display()
+    )))
+}
+
+fn latest_modified(conf: &GrpcTls) -> Option<SystemTime> {
+    [&conf.cert, &conf.key, &conf.ca_cert]
+        .into_iter()
+        .filter_map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+        .max()
+}
+
+/// SPIFFE ids of the leaf certificate of a verified peer chain.
+pub fn peer_spiffe_ids(peer_certs: Option<&[CertificateDer<'_>]>) -> Vec<String> {
+    peer_certs
+        .and_then(|certs| certs.first())
+        .map(|leaf| spiffe_ids(leaf.as_ref()))
+        .unwrap_or_default()
+}
+
+/// SPIFFE ids (`spiffe://` URI SANs) of a DER certificate.
+fn spiffe_ids(der: &[u8]) -> Vec<String> {
+    let Ok((_, cert)) = parse_x509_certificate(der) else {
+        return Vec::new();
+    };
+    let Ok(Some(san)) = cert.subject_alternative_name() else {
+        return Vec::new();
+    };
+    san.value
+        .general_names
+        .iter()
+        .filter_map(|name| match name {
+            GeneralName::URI(uri) if uri.starts_with(SPIFFE_SCHEME) => Some(uri.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn peer_id_allowed(conf: &GrpcTls, ids: &[String]) -> bool {
+    if !conf.allowed_peer_ids.is_empty() {
+        return ids.iter().any(|id| conf.allowed_peer_ids.contains(id));
+    }
+    if !conf.trust_domain.is_empty() {
+        let prefix = format!("{}{}/", SPIFFE_SCHEME, conf.trust_domain);
+        return ids.iter().any(|id| id.starts_with(&prefix));
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CERT_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../config/certs");
+
+    fn test_config() -> GrpcTls {
+        GrpcTls {
+            enable: true,
+            cert: format!("{CERT_DIR}/cert.pem"),
+            key: format!("{CERT_DIR}/key.pem"),
+            ca_cert: format!("{CERT_DIR}/ca.pem"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn peer_id_policy() {
+        let ids = vec!["spiffe://robustmq/meta".to_string()];
+        let mut conf = GrpcTls::default();
+        assert!(peer_id_allowed(&conf, &[]));
+
+        conf.trust_domain = "robustmq".to_string();
+        assert!(peer_id_allowed(&conf, &ids));
+        assert!(!peer_id_allowed(
+            &conf,
+            &["spiffe://other/meta".to_string()]
+        ));
+        assert!(!peer_id_allowed(
+            &conf,
+            &["spiffe://robustmq.evil/meta".to_string()]
+        ));
+        assert!(!peer_id_allowed(&conf, &[]));
+
+        conf.allowed_peer_ids = vec!["spiffe://robustmq/broker".to_string()];
+        assert!(!peer_id_allowed(&conf, &ids));
+        assert!(peer_id_allowed(
+            &conf,
+            &["spiffe://robustmq/broker".to_string()]
+        ));
+    }
+
+    #[test]
+    fn build_and_reload_context() {
+        let mut conf = test_config();
+        conf.mutual = true;
+        let ctx = GrpcTlsContext::new(conf).unwrap();
+        assert!(ctx.is_mutual());
+        assert_eq!(ctx.client_config().alpn_protocols, vec![b"h2".to_vec()]);
+        assert!(!ctx.reload_if_changed().unwrap());
+
+        *ctx.modified.lock().unwrap() = None;
+        assert!(ctx.reload_if_changed().unwrap());
+
+        // no policy configured accepts any verified peer
+        assert!(ctx.verify_peer(None).is_ok());
+
+        let missing = GrpcTls {
+            ca_cert: String::new(),
+            ..test_config()
+        };
+        assert!(GrpcTlsContext::new(missing).is_err());
+    }
+
+    #[test]
+    fn server_name_override() {
+        let mut conf = test_config();
+        let ctx = GrpcTlsContext::new(conf.clone()).unwrap();
+        assert_eq!(
+            ctx.server_name("127.0.0.1").unwrap(),
+            ServerName::try_from("127.0.0.1").unwrap()
+        );
+        assert_eq!(
+            ctx.server_name("[::1]").unwrap(),
+            ServerName::try_from("::1").unwrap()
+        );
+
+        conf.server_name = "meta.robustmq.internal".to_string();
+        let ctx = GrpcTlsContext::new(conf).unwrap();
+        assert_eq!(
+            ctx.server_name("127.0.0.1").unwrap(),
+            ServerName::try_from("meta.robustmq.internal").unwrap()
+        );
+    }
+}
@@ -16,6 +16,7 @@
 use std::time::{Duration, Instant};
 
 use common_base::error::common::CommonError;
+use common_base::fault::{fault_injector, FaultPoint, InjectedFault};
 use common_metrics::grpc::record_grpc_client_call;
 use regex::Regex;
 use tokio::time::sleep;
@@ -77,35 +78,29 @@ async fn retry_call_inner<Req>(
     }
 
     let method = Req::method_name();
-    // A few quick retries to ride out transient failures (leader briefly busy,
-    // a peer still starting up) without exceeding callers' own timeouts — e.g.
-    // heartbeat wraps this in a 3s timeout, so total retry time must stay well
-    // under that. Callers needing longer recovery (e.g. node re-registration)
-    // retry at their own layer.
-    const MIN_ATTEMPTS: usize = 3;
-    const RETRY_INTERVAL_MS: u64 = 100;
-    // Per-attempt timeout: prevents a node that is reachable at TCP level but
-    // not responding (e.g. installing a Raft snapshot) from blocking the entire
-    // retry loop.  On timeout the error is treated as a transport error so the
-    // loop continues to the next address.
-    const PER_CALL_TIMEOUT: Duration = Duration::from_secs(5);
+    let policy = client_pool.retry_policy(method);
+    let started = Instant::now();
     // Try every node at least once before giving up: a write may be pinned to a
     // stale cached leader, and the live leader could be any other node.
-    let max_attempts = MIN_ATTEMPTS.max(retry_times()).max(addrs.len());
+    let max_attempts = policy
+        .max_attempts
+        .max(1)
+        .max(retry_times())
+        .max(addrs.len());
     let mut times = 0;
     loop {
         let index = times % addrs.len();
         times += 1;
-        // Write requests prefer the cached leader when present, falling back to
-        // round-robin otherwise. A transport failure against the cached leader
-        // drops it (see below) so a crashed / re-elected leader is re-discovered.
-        let (target_addr, source) = if Req::IS_WRITE_REQUEST {
-            match client_pool.get_leader_addr(method) {
-                Some(l) => (l.value().to_string(), "cached-leader"),
-                None => (addrs[index].as_ref().to_string(), "round-robin"),
-            }
-        } else {
-            (addrs[index].as_ref().to_string(), "round-robin")
+        let Some((target_addr, source)) =
+            select_target(client_pool, addrs, index, Req::IS_WRITE_REQUEST, method)
+        else {
+            // Every address has an open circuit: fail fast instead of
+            // waiting on nodes that are known to be down.
+            return Err(CommonError::CommonError(format!(
+                "{}: circuit open for all {} target addresses",
+                method,
+                addrs.len()
+            )));
         };
 
         debug!(
@@ -113,42 +108,57 @@ async fn retry_call_inner<Req>(
             method, times, max_attempts, target_addr, source, index
         );
 
-        let mut client = Req::get_client(client_pool, &target_addr);
-
-        let raw = tokio::time::timeout(
-            PER_CALL_TIMEOUT,
-            Req::call_once(&mut client, request.clone()),
+        let call_timeout = policy
+            .per_call_timeout
+            .min(policy.deadline.saturating_sub(started.elapsed()));
+        let err = match call_with_timeout::<Req>(
+            client_pool,
+            &target_addr,
+            request.clone(),
+            call_timeout,
         )
-        .await;
-        let err: CommonError = match raw {
-            Ok(Ok(data)) => return Ok(data),
-            Ok(Err(e)) => e.into(),
-            Err(_elapsed) => {
-                warn!(
-                    "retry_call {} attempt {}/{}: {} did not respond within {:?}",
-                    method, times, max_attempts, target_addr, PER_CALL_TIMEOUT
-                );
-                // Treated as a transport error so the loop continues to the next address.
-                CommonError::CommonError(format!(
-                    "tcp connect error: {} timed out after {:?}",
-                    target_addr, PER_CALL_TIMEOUT
-                ))
+        .await
+        {
+            Ok(data) => {
+                client_pool.circuit_on_success(&target_addr);
+                return Ok(data);
             }
+            Err(e) => e,
         };
-        if err.to_string().contains("forward request to") {
+        if is_transport_error(&err) {
+            client_pool.circuit_on_failure(&target_addr);
+        } else {
+            client_pool.circuit_on_success(&target_addr);
+        }
+
+        if is_not_leader_error(&err) {
             // Not the leader — follow the redirect and cache the real leader.
-            if let Some(leader_addr) = get_forward_addr(&err) {
+            if let Some(leader_addr) =
+                get_forward_addr(&err).filter(|addr| client_pool.circuit_allow(addr))
+            {
                 info!(
                     "retry_call {} attempt {}: {} redirected to leader {}",
                     method, times, target_addr, leader_addr
                 );
                 client_pool.set_leader_addr(method.to_string(), leader_addr.clone());
-                let mut leader_client = Req::get_client(client_pool, &leader_addr);
-                match Req::call_once(&mut leader_client, request.clone()).await {
-                    Ok(data) => return Ok(data),
+                let call_timeout = policy
+                    .per_call_timeout
+                    .min(policy.deadline.saturating_sub(started.elapsed()));
+                match call_with_timeout::<Req>(
+                    client_pool,
+                    &leader_addr,
+                    request.clone(),
+                    call_timeout,
+                )
+                .await
+                {
+                    Ok(data) => {
+                        client_pool.circuit_on_success(&leader_addr);
+                        return Ok(data);
+                    }
                     Err(le) => {
-                        let le: CommonError = le.into();
                         if is_transport_error(&le) {
+                            client_pool.circuit_on_failure(&leader_addr);
                             // The redirected leader is unreachable — drop it
                             // so the next attempt sweeps the node list and
                             // re-discovers it.
@@ -158,6 +168,7 @@ async fn retry_call_inner<Req>(
                             );
                             client_pool.remove_leader_addr(method);
                         } else {
+                            client_pool.circuit_on_success(&leader_addr);
                             // The leader processed and rejected the request
                             // (application error) — authoritative, return now.
                             warn!(
@@ -169,10 +180,14 @@ async fn retry_call_inner<Req>(
                     }
                 }
             } else {
+                // No leader known yet (e.g. an election is in progress), or its
+                // circuit is open: drop the cached leader and retry after a
+                // backoff.
                 warn!(
-                    "retry_call {} attempt {}: {} returned a forward error but no leader addr parsed: {}",
+                    "retry_call {} attempt {}: {} returned a not-leader error without a leader addr: {}",
                     method, times, target_addr, err
                 );
+                client_pool.remove_leader_addr(method);
             }
         } else if is_transport_error(&err) {
             // The node is unreachable (down / not yet listening) — sweep on
@@ -202,10 +217,114 @@ async fn retry_call_inner<Req>(
         if times >= max_attempts {
             return Err(err);
         }
-        sleep(Duration::from_millis(RETRY_INTERVAL_MS)).await;
+
+        let remaining = policy.deadline.saturating_sub(started.elapsed());
+        if remaining.is_zero() {
+            warn!(
+                "retry_call {} gave up after {} attempts: deadline {:?} exceeded",
+                method, times, policy.deadline
+            );
+            return Err(err);
+        }
+        sleep(policy.backoff(times).min(remaining)).await;
     }
 }
 
+/// Pick the address for the next attempt. Write requests prefer the cached
+/// leader when present, falling back to round-robin otherwise; addresses whose
+/// circuit is open are skipped. A transport failure against the cached leader
+/// drops it (see `retry_call_inner`) so a crashed / re-elected leader is
+/// re-discovered. Returns `None` when every address has an open circuit.
+fn select_target(
+    client_pool: &ClientPool,
+    addrs: &[impl AsRef<str>],
+    index: usize,
+    is_write: bool,
+    method: &str,
+) -> Option<(String, &'static str)> {
+    if is_write {
+        let leader = client_pool
+            .get_leader_addr(method)
+            .map(|l| l.value().to_string());
+        if let Some(leader) = leader {
+            if client_pool.circuit_allow(&leader) {
+                return Some((leader, "cached-leader"));
+            }
+            client_pool.remove_leader_addr(method);
+        }
+    }
+
+    (0..addrs.len())
+        .map(|offset| addrs[(index + offset) % addrs.len()].as_ref())
+        .find(|addr| client_pool.circuit_allow(addr))
+        .map(|addr| (addr.to_string(), "round-robin"))
+}
+
+/// One attempt against `addr`. A timeout is reported as a transport error so
+/// the retry loop moves on to the next address.
+async fn call_with_timeout<Req>(
+    client_pool: &ClientPool,
+    addr: &str,
+    request: Req,
+    call_timeout: Duration,
+) -> Result<Req::Response, CommonError>
+where
+    Req: RetriableRequest,
+    Req::Error: Into<CommonError>,
+{
+    let mut client = Req::get_client(client_pool, addr);
+    let call = async {
+        inject_call_fault(Req::method_name(), addr).await?;
+        Req::call_once(&mut client, request)
+            .await
+            .map_err(Into::<CommonError>::into)
+    };
+    match tokio::time::timeout(call_timeout, call).await {
+        Ok(result) => result,
+        Err(_elapsed) => {
+            warn!(
+                "retry_call {}: {} did not respond within {:?}",
+                Req::method_name(),
+                addr,
+                call_timeout
+            );
+            Err(CommonError::CommonError(format!(
+                "tcp connect error: {} timed out after {:?}",
+                addr, call_timeout
+            )))
+        }
+    }
+}
+
+/// Apply the injected faults of this call. A partition fails like an
+/// unreachable node, so the retry loop moves on to the next address.
+async fn inject_call_fault(method: &str, addr: &str) -> Result<(), CommonError> {
+    if !fault_injector().is_enabled() {
+        return Ok(());
+    }
+    let target = format!("{}@{}", method, addr);
+    match fault_injector()
+        .inject(FaultPoint::GrpcClient, &target)
+        .await
+    {
+        Ok(()) => Ok(()),
+        Err(fault @ InjectedFault::Partition(_)) => Err(CommonError::CommonError(format!(
+            "tcp connect error: {} {}",
+            addr, fault
+        ))),
+        Err(fault @ InjectedFault::Error(_)) => {
+            Err(CommonError::CommonError(format!("{}: {}", method, fault)))
+        }
+    }
+}
+
+/// Whether the node rejected the request because it is not the Raft leader.
+/// The error carries the leader address when one is known.
+fn is_not_leader_error(err: &CommonError) -> bool {
+    let s = err.to_string();
+    s.contains("forward request to") || s.contains("ForwardToLeader")
+}
+
 /// Whether the error is a transport/availability failure (the node is
 /// unreachable), as opposed to an application-level rejection. Only transport
 /// failures are worth retrying against other nodes; an application rejection is
@@ -230,6 +349,8 @@ pub fn get_forward_addr(err: &CommonError) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::breaker::{CircuitBreakerConfig, CircuitState};
+    use crate::policy::RetryPolicy;
     use std::sync::{Arc, Mutex};
 
     /// Mock write request: `reject_addr` returns an application-level error,
@@ -359,6 +480,102 @@ async fn tries_every_node_before_succeeding() {
         );
     }
 
+    // The pool's deadline bounds the whole call, even when there are more
+    // addresses left to try.
+    #[tokio::test]
+    async fn deadline_stops_retrying() {
+        let pool = ClientPool::new(1).with_retry_policy(
+            "MockService",
+            RetryPolicy {
+                initial_backoff: Duration::from_millis(50),
+                max_backoff: Duration::from_millis(50),
+                jitter: 0.0,
+                deadline: Duration::from_millis(120),
+                ..Default::default()
+            },
+        );
+        let state = Arc::new(MockState {
+            calls: Mutex::new(Vec::new()),
+            good_addr: "127.0.0.1:6010".to_string(),
+            reject_addr: None,
+        });
+        let addrs: Vec<String> = (6001..=6010).map(|p| format!("127.0.0.1:{p}")).collect();
+        let res = retry_call_inner::<MockReq>(
+            &pool,
+            &addrs,
+            MockReq {
+                state: state.clone(),
+            },
+        )
+        .await;
+
+        assert!(res.is_err(), "the live node is never reached in time");
+        let calls = state.calls.lock().unwrap();
+        assert!(
+            calls.len() < addrs.len(),
+            "must stop at the deadline, got {calls:?}"
+        );
+    }
+
+    // Addresses with an open circuit are skipped in favour of healthy ones,
+    // and a call fails fast once every address is open.
+    #[tokio::test]
+    async fn open_circuit_routes_to_healthy_nodes() {
+        let pool = ClientPool::new(1).with_circuit_breaker(CircuitBreakerConfig {
+            min_requests: 1,
+            ..Default::default()
+        });
+        let state = Arc::new(MockState {
+            calls: Mutex::new(Vec::new()),
+            good_addr: "127.0.0.1:8002".to_string(),
+            reject_addr: None,
+        });
+        let addrs = ["127.0.0.1:8001", "127.0.0.1:8002"];
+        let req = MockReq {
+            state: state.clone(),
+        };
+
+        assert!(retry_call_inner::<MockReq>(&pool, &addrs, req.clone())
+            .await
+            .is_ok());
+        assert_eq!(pool.circuit_state("127.0.0.1:8001"), CircuitState::Open);
+
+        state.calls.lock().unwrap().clear();
+        assert!(retry_call_inner::<MockReq>(&pool, &addrs, req.clone())
+            .await
+            .is_ok());
+        assert_eq!(
+            state.calls.lock().unwrap().as_slice(),
+            ["127.0.0.1:8002"],
+            "the open address must be skipped"
+        );
+
+        state.calls.lock().unwrap().clear();
+        let dead = ["127.0.0.1:8001"];
+        assert!(retry_call_inner::<MockReq>(&pool, &dead, req)
+            .await
+            .is_err());
+        assert!(
+            state.calls.lock().unwrap().is_empty(),
+            "must fail fast without calling an open address"
+        );
+    }
+
+    #[test]
+    fn not_leader_errors_are_detected() {
+        let forward = CommonError::CommonError(
+            "has to forward request to: Some(Node { node_id: 2, rpc_addr: \"127.0.0.1:2228\" })"
+                .to_string(),
+        );
+        assert!(is_not_leader_error(&forward));
+        let unknown = CommonError::CommonError("ForwardToLeader { leader_id: None }".to_string());
+        assert!(is_not_leader_error(&unknown));
+        assert_eq!(get_forward_addr(&unknown), None);
+        assert!(!is_not_leader_error(&CommonError::CommonError(
+            "tcp connect error".to_string()
+        )));
+    }
+
     #[test]
     fn get_forward_addr_parses_and_strips() {
         let err = CommonError::CommonError(
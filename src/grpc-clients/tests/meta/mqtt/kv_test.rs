@@ -16,11 +16,11 @@
 mod tests {
     use crate::common::{get_placement_addr, wait_until};
     use grpc_clients::{
-        meta::common::call::{kv_delete, kv_exists, kv_get, kv_set},
+        meta::common::call::{kv_compare_and_set, kv_delete, kv_exists, kv_get, kv_set},
         pool::ClientPool,
     };
     use protocol::meta::meta_service_common::{
-        DeleteRequest, ExistsRequest, GetRequest, SetRequest,
+        CompareAndSetRequest, DeleteRequest, ExistsRequest, GetRequest, SetRequest,
     };
     use std::sync::Arc;
 
@@ -33,6 +33,7 @@ async fn kv_test() {
         let request = SetRequest {
             key: key.clone(),
             value: value.clone(),
+            ..Default::default()
         };
         match kv_set(&client_pool, &addrs, request).await {
             Ok(_) => {}
@@ -44,6 +45,7 @@ async fn kv_test() {
         let request_key_empty = SetRequest {
             key: "".to_string(),
             value: value.clone(),
+            ..Default::default()
         };
         let err = kv_set(&client_pool, &addrs, request_key_empty)
             .await
@@ -56,6 +58,7 @@ async fn kv_test() {
         let request_value_empty = SetRequest {
             key: key.clone(),
             value: "".to_string(),
+            ..Default::default()
         };
         let err = kv_set(&client_pool, &addrs, request_value_empty)
             .await
@@ -82,7 +85,10 @@ async fn kv_test() {
         .await;
         assert!(got, "key {key} value mismatch after set");
 
-        let exist_req = DeleteRequest { key: key.clone() };
+        let exist_req = DeleteRequest {
+            key: key.clone(),
+            expected_version: None,
+        };
         match kv_delete(&client_pool, &addrs, exist_req).await {
             Ok(_) => {}
             Err(e) => {
@@ -99,4 +105,59 @@ async fn kv_test() {
         .await;
         assert!(absent, "key {key} still visible after delete");
     }
+
+    #[tokio::test]
+    async fn kv_compare_and_set_test() {
+        let client_pool: Arc<ClientPool> = Arc::new(ClientPool::new(1));
+        let addrs = vec![get_placement_addr()];
+        let key = "test-kv-lock".to_string();
+
+        let create = CompareAndSetRequest {
+            key: key.clone(),
+            value: "owner-1".to_string(),
+            expected_version: Some(0),
+            ttl_ms: 60_000,
+            ..Default::default()
+        };
+        let reply = kv_compare_and_set(&client_pool, &addrs, create.clone())
+            .await
+            .unwrap();
+        assert!(reply.succeeded);
+        let version = reply.version;
+
+        let reply = kv_compare_and_set(&client_pool, &addrs, create)
+            .await
+            .unwrap();
+        assert!(!reply.succeeded);
+        assert_eq!(reply.version, version);
+        assert_eq!(reply.current_value, "owner-1");
+
+        let got = kv_get(&client_pool, &addrs, GetRequest { key: key.clone() })
+            .await
+            .unwrap();
+        assert_eq!(got.version, version);
+        assert!(got.expire_at_ms > 0);
+
+        let stale = DeleteRequest {
+            key: key.clone(),
+            expected_version: Some(version + 1),
+        };
+        assert!(
+            !kv_delete(&client_pool, &addrs, stale)
+                .await
+                .unwrap()
+                .deleted
+        );
+
+        let release = DeleteRequest {
+            key: key.clone(),
+            expected_version: Some(version),
+        };
+        assert!(
+            kv_delete(&client_pool, &addrs, release)
+                .await
+                .unwrap()
+                .deleted
+        );
+    }
 }
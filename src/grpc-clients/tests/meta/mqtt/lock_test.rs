@@ -0,0 +1,48 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+mod tests {
+    use crate::common::get_placement_addr;
+    use common_base::uuid::unique_id;
+    use grpc_clients::{meta::common::lock::DistributedLock, pool::ClientPool};
+    use std::{sync::Arc, time::Duration};
+
+    #[tokio::test]
+    async fn lock_test() {
+        let client_pool: Arc<ClientPool> = Arc::new(ClientPool::new(1));
+        let addrs = vec![get_placement_addr()];
+        let name = format!("test-lock-{}", unique_id());
+        let lease = Duration::from_secs(30);
+
+        let lock_a = DistributedLock::new(client_pool.clone(), addrs.clone(), &name, "a", lease);
+        let lock_b = DistributedLock::new(client_pool.clone(), addrs.clone(), &name, "b", lease);
+
+        assert!(lock_a.try_acquire().await.unwrap());
+        assert!(!lock_b.try_acquire().await.unwrap());
+        let token = lock_a.token().unwrap();
+
+        assert!(lock_a.renew().await.unwrap());
+        assert!(lock_a.token().unwrap() > token);
+
+        let watched = lock_b.watch("", Duration::from_secs(1)).await.unwrap();
+        assert_eq!(watched.holder, "a");
+
+        assert!(lock_a.release().await.unwrap());
+        assert!(!lock_a.is_held());
+        assert!(lock_b.try_acquire().await.unwrap());
+        assert!(lock_b.token().unwrap() > token);
+        assert!(lock_b.release().await.unwrap());
+    }
+}
@@ -14,6 +14,7 @@
 
 mod cluster_test;
 mod kv_test;
+mod lock_test;
 mod mqtt_acl_test;
 mod mqtt_blacklist_test;
 mod mqtt_connector_test;
@@ -23,3 +24,4 @@
 mod topic_rewrite_rule;
 mod topic_test;
 mod user_test;
+mod watch_test;
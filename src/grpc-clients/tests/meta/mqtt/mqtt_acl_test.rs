@@ -38,6 +38,7 @@ async fn mqtt_acl_test() {
             ip: "*".to_string(),
             action: EnumAclAction::All,
             permission: EnumAclPermission::Deny,
+            priority: 0,
         };
 
         let request = CreateAclRequest {
@@ -48,6 +49,7 @@ async fn mqtt_acl_test() {
         let present = wait_until(|| async {
             let request = ListAclRequest {
                 tenant: "default".to_string(),
+                ..Default::default()
             };
             match list_acl(&client_pool, &addrs, request).await {
                 Ok(data) => data
@@ -70,6 +72,7 @@ async fn mqtt_acl_test() {
         let absent = wait_until(|| async {
             let request = ListAclRequest {
                 tenant: "default".to_string(),
+                ..Default::default()
             };
             match list_acl(&client_pool, &addrs, request).await {
                 Ok(data) => !data
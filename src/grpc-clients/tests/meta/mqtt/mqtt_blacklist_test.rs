@@ -52,6 +52,7 @@ async fn mqtt_blacklist_test() {
 
         let request = ListBlacklistRequest {
             tenant: "default".to_string(),
+            ..Default::default()
         };
 
         match list_blacklist(&client_pool, &addrs, request).await {
@@ -83,6 +84,7 @@ async fn mqtt_blacklist_test() {
 
         let request = ListBlacklistRequest {
             tenant: "default".to_string(),
+            ..Default::default()
         };
 
         match list_blacklist(&client_pool, &addrs, request).await {
@@ -73,6 +73,7 @@ async fn connector_test() {
         // list the connector we just created
         let list_request = ListConnectorRequest {
             connector_name: connector_name.clone(),
+            ..Default::default()
         };
 
         let ok = wait_until(|| async {
@@ -67,6 +67,7 @@ async fn list_sessions(
         let request = ListSessionRequest {
             tenant: tenant.to_string(),
             client_id: client_id.to_string(),
+            ..Default::default()
         };
         let mut stream = placement_list_session(client_pool, addrs, request)
             .await
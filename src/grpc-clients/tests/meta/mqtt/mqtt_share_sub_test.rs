@@ -56,6 +56,7 @@ async fn mqtt_share_sub_test() {
             start_time: now_second(),
             storage_fold: vec!["./data/broker/engine".to_string()],
             engine_addr: "127.0.0.1:1778".to_string(),
+            ..Default::default()
         };
         register_node(
             &client_pool,
@@ -63,6 +63,7 @@ async fn test_topic_rewrite_rule() {
         let present = wait_until(|| async {
             let req = ListTopicRewriteRuleRequest {
                 tenant: DEFAULT_TENANT.to_string(),
+                ..Default::default()
             };
             match placement_list_topic_rewrite_rule(&client_pool, &addrs, req).await {
                 Ok(resp) => has_rule(&resp.topic_rewrite_rules, &rule_name),
@@ -83,6 +84,7 @@ async fn test_topic_rewrite_rule() {
         let absent = wait_until(|| async {
             let req = ListTopicRewriteRuleRequest {
                 tenant: DEFAULT_TENANT.to_string(),
+                ..Default::default()
             };
             match placement_list_topic_rewrite_rule(&client_pool, &addrs, req).await {
                 Ok(resp) => !has_rule(&resp.topic_rewrite_rules, &rule_name),
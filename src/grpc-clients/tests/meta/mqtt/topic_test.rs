@@ -89,6 +89,7 @@ async fn contain_topic(
             let request = ListTopicRequest {
                 tenant: "".to_string(),
                 topic_name: topic_name.clone(),
+                ..Default::default()
             };
             let mut data_stream = match placement_list_topic(client_pool, addrs, request).await {
                 Ok(s) => s,
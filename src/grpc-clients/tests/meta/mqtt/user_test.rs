@@ -44,6 +44,7 @@ async fn mqtt_user_test() {
             salt: None,
             is_superuser: false,
             create_time: now_second(),
+            hash_algorithm: None,
         };
 
         let request: CreateUserRequest = CreateUserRequest {
@@ -59,6 +60,7 @@ async fn mqtt_user_test() {
             let request = ListUserRequest {
                 tenant: "default".to_string(),
                 user_name: mqtt_user.username.clone(),
+                ..Default::default()
             };
             match placement_list_user(&client_pool, &addrs, request).await {
                 Ok(data) => data
@@ -85,6 +87,7 @@ async fn mqtt_user_test() {
             let request = ListUserRequest {
                 tenant: "default".to_string(),
                 user_name: mqtt_user.username.clone(),
+                ..Default::default()
             };
             match placement_list_user(&client_pool, &addrs, request).await {
                 Ok(data) => !data
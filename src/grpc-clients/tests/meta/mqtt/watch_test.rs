@@ -0,0 +1,112 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use common_base::tools::now_second;
+    use common_base::uuid::unique_id;
+    use grpc_clients::meta::common::call::watch_resources;
+    use grpc_clients::meta::mqtt::call::{placement_create_user, placement_delete_user};
+    use grpc_clients::pool::ClientPool;
+    use metadata_struct::auth::user::SecurityUser;
+    use protocol::meta::meta_service_common::{WatchResourcesReply, WatchResourcesRequest};
+    use protocol::meta::meta_service_mqtt::{CreateUserRequest, DeleteUserRequest};
+    use tonic::Streaming;
+
+    use crate::common::get_placement_addr;
+
+    async fn next_for_key(
+        stream: &mut Streaming<WatchResourcesReply>,
+        key: &str,
+    ) -> WatchResourcesReply {
+        tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let reply = stream.message().await.unwrap().unwrap();
+                if reply.key == key {
+                    return reply;
+                }
+            }
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn watch_resources_test() {
+        let client_pool: Arc<ClientPool> = Arc::new(ClientPool::new(3));
+        let addrs = vec![get_placement_addr()];
+        let user_name = unique_id();
+        let key = format!("default/{}", user_name);
+
+        let request = WatchResourcesRequest {
+            resource_types: vec!["user".to_string()],
+            ..Default::default()
+        };
+        let mut stream = watch_resources(&client_pool, &addrs, request)
+            .await
+            .unwrap();
+        let bookmark = stream.message().await.unwrap().unwrap();
+        assert_eq!(bookmark.event_type, "bookmark");
+
+        let user = SecurityUser {
+            tenant: "default".to_string(),
+            username: user_name.clone(),
+            password: "123456".to_string(),
+            salt: None,
+            is_superuser: false,
+            create_time: now_second(),
+            hash_algorithm: None,
+        };
+        let request = CreateUserRequest {
+            tenant: "default".to_string(),
+            user_name: user_name.clone(),
+            content: user.encode().unwrap(),
+        };
+        placement_create_user(&client_pool, &addrs, request)
+            .await
+            .unwrap();
+
+        let created = next_for_key(&mut stream, &key).await;
+        assert_eq!(created.resource_type, "user");
+        assert_eq!(created.event_type, "create");
+        assert_eq!(SecurityUser::decode(&created.data).unwrap(), user);
+
+        let request = DeleteUserRequest {
+            tenant: "default".to_string(),
+            user_name: user_name.clone(),
+        };
+        placement_delete_user(&client_pool, &addrs, request)
+            .await
+            .unwrap();
+        let deleted = next_for_key(&mut stream, &key).await;
+        assert_eq!(deleted.event_type, "delete");
+
+        // Resuming from the create event replays the delete.
+        let request = WatchResourcesRequest {
+            resource_types: vec!["user".to_string()],
+            revision: created.revision.clone(),
+        };
+        let mut resumed = watch_resources(&client_pool, &addrs, request)
+            .await
+            .unwrap();
+        let bookmark = resumed.message().await.unwrap().unwrap();
+        assert_eq!(bookmark.revision, created.revision);
+        let replayed = next_for_key(&mut resumed, &key).await;
+        assert_eq!(replayed.event_type, "delete");
+        assert_eq!(replayed.revision, deleted.revision);
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::raft::manager::MultiRaftManager;
+use crate::server::services::mqtt::acl::delete_blacklist_by_req;
+use crate::storage::mqtt::blacklist::MqttBlackListStorage;
+use common_base::error::common::CommonError;
+use common_base::error::ResultCommonError;
+use common_base::tools::{loop_select_ticket, now_second};
+use metadata_struct::auth::blacklist::SecurityBlackList;
+use node_call::NodeCallManager;
+use protocol::meta::meta_service_mqtt::DeleteBlacklistRequest;
+use rocksdb_engine::rocksdb::RocksDBEngine;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+// Scan every minute
+const BLACKLIST_GC_INTERVAL_MS: u64 = 60 * 1000;
+
+pub async fn start_blacklist_gc_thread(
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+    raft_manager: Arc<MultiRaftManager>,
+    node_call_manager: Arc<NodeCallManager>,
+    stop_send: broadcast::Sender<bool>,
+) {
+    let ac_fn = async || -> ResultCommonError {
+        gc_expired_blacklists(&rocksdb_engine_handler, &raft_manager, &node_call_manager).await
+    };
+    loop_select_ticket(ac_fn, BLACKLIST_GC_INTERVAL_MS, &stop_send).await;
+}
+
+async fn gc_expired_blacklists(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    raft_manager: &Arc<MultiRaftManager>,
+    node_call_manager: &Arc<NodeCallManager>,
+) -> Result<(), CommonError> {
+    let storage = MqttBlackListStorage::new(rocksdb_engine_handler.clone());
+    let now = now_second();
+
+    for blacklist in storage.list_all()? {
+        if !is_expired(&blacklist, now) {
+            continue;
+        }
+
+        // Delete via raft and notify the brokers so they evict the entry
+        // from their caches as well.
+        let req = DeleteBlacklistRequest {
+            tenant: blacklist.tenant.clone(),
+            name: blacklist.name.clone(),
+        };
+        if let Err(e) = delete_blacklist_by_req(
+            rocksdb_engine_handler,
+            raft_manager,
+            node_call_manager,
+            &req,
+        )
+        .await
+        {
+            warn!(
+                "Failed to delete expired blacklist: tenant={}, name={}, error={}",
+                blacklist.tenant, blacklist.name, e
+            );
+            continue;
+        }
+
+        info!(
+            "Expired blacklist {} cleaned up successfully: tenant={}, type={}, end_time={}",
+            blacklist.name, blacklist.tenant, blacklist.blacklist_type, blacklist.end_time
+        );
+    }
+
+    Ok(())
+}
+
+// end_time == 0 means the entry never expires.
+fn is_expired(blacklist: &SecurityBlackList, now: u64) -> bool {
+    blacklist.end_time != 0 && blacklist.end_time <= now
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_expired;
+    use metadata_struct::auth::blacklist::{EnumBlackListType, SecurityBlackList};
+
+    #[test]
+    fn test_is_expired() {
+        let mut blacklist = SecurityBlackList {
+            name: "b1".to_string(),
+            tenant: "t1".to_string(),
+            blacklist_type: EnumBlackListType::ClientIdRegex,
+            resource_name: "sensor-.*".to_string(),
+            end_time: 0,
+            desc: String::new(),
+        };
+        assert!(!is_expired(&blacklist, 100));
+
+        blacklist.end_time = 100;
+        assert!(is_expired(&blacklist, 100));
+        assert!(!is_expired(&blacklist, 99));
+    }
+}
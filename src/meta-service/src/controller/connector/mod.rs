@@ -0,0 +1,25 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Placement of connectors on brokers, run by the metadata leader.
+//!
+//! Idle connectors are assigned to the least loaded healthy broker. A broker
+//! whose heartbeat timed out is considered failed and its connectors are moved
+//! to healthy brokers. Periodically, connectors are rebalanced by message rate;
+//! placement is sticky: a connector only moves when its broker is clearly
+//! overloaded, and not again within a cooldown.
+
+pub mod rebalance;
+pub mod scheduler;
+pub mod status;
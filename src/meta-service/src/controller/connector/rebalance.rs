@@ -0,0 +1,190 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Message-rate based rebalancing plan for connectors.
+
+use std::collections::{BTreeMap, HashSet};
+
+/// A broker is overloaded when its rate exceeds the average by this ratio.
+pub const REBALANCE_TOLERANCE: f64 = 0.2;
+/// Brokers below this rate (records/s) are never overloaded.
+pub const REBALANCE_MIN_RATE: f64 = 1.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConnectorLoad {
+    pub connector_name: String,
+    pub broker_id: u64,
+    pub message_rate: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConnectorMove {
+    pub connector_name: String,
+    pub from: u64,
+    pub to: u64,
+}
+
+/// Moves connectors from the busiest broker to the idlest one until no broker
+/// is overloaded, at most `max_moves` times. Each move picks the connector
+/// whose rate is closest to half the gap between the two brokers, and only
+/// moves that lower the busier broker's rate are made. Connectors in `pinned`
+/// count towards their broker's rate but are not moved.
+pub fn plan_rebalance(
+    brokers: &[u64],
+    connectors: &[ConnectorLoad],
+    pinned: &HashSet<String>,
+    max_moves: usize,
+) -> Vec<ConnectorMove> {
+    let mut moves = Vec::new();
+    if brokers.len() < 2 {
+        return moves;
+    }
+
+    let mut rates: BTreeMap<u64, f64> = brokers.iter().map(|id| (*id, 0.0)).collect();
+    let mut placed: BTreeMap<u64, Vec<&ConnectorLoad>> = BTreeMap::new();
+    for connector in connectors {
+        if let Some(rate) = rates.get_mut(&connector.broker_id) {
+            *rate += connector.message_rate;
+            placed
+                .entry(connector.broker_id)
+                .or_default()
+                .push(connector);
+        }
+    }
+    let average = rates.values().sum::<f64>() / rates.len() as f64;
+
+    let mut moved = HashSet::new();
+    while moves.len() < max_moves {
+        let Some((hot, hot_rate)) = extreme(&rates, |a, b| a > b) else {
+            break;
+        };
+        let Some((cold, cold_rate)) = extreme(&rates, |a, b| a < b) else {
+            break;
+        };
+        if hot_rate < REBALANCE_MIN_RATE || hot_rate <= average * (1.0 + REBALANCE_TOLERANCE) {
+            break;
+        }
+
+        // A connector at least as busy as the gap would only swap the roles.
+        let gap = hot_rate - cold_rate;
+        let candidate = placed
+            .get(&hot)
+            .into_iter()
+            .flatten()
+            .filter(|c| {
+                c.message_rate > 0.0
+                    && c.message_rate < gap
+                    && !pinned.contains(&c.connector_name)
+                    && !moved.contains(&c.connector_name)
+            })
+            .min_by(|a, b| {
+                (a.message_rate - gap / 2.0)
+                    .abs()
+                    .total_cmp(&(b.message_rate - gap / 2.0).abs())
+            })
+            .copied();
+        let Some(connector) = candidate else {
+            break;
+        };
+
+        *rates.entry(hot).or_default() -= connector.message_rate;
+        *rates.entry(cold).or_default() += connector.message_rate;
+        if let Some(list) = placed.get_mut(&hot) {
+            list.retain(|c| c.connector_name != connector.connector_name);
+        }
+        placed.entry(cold).or_default().push(connector);
+        moved.insert(connector.connector_name.clone());
+        moves.push(ConnectorMove {
+            connector_name: connector.connector_name.clone(),
+            from: hot,
+            to: cold,
+        });
+    }
+    moves
+}
+
+// The broker whose rate wins `better` against all others, lowest id on ties.
+fn extreme(rates: &BTreeMap<u64, f64>, better: impl Fn(f64, f64) -> bool) -> Option<(u64, f64)> {
+    let mut result: Option<(u64, f64)> = None;
+    for (id, rate) in rates {
+        if result.is_none_or(|(_, best)| better(*rate, best)) {
+            result = Some((*id, *rate));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(name: &str, broker_id: u64, message_rate: f64) -> ConnectorLoad {
+        ConnectorLoad {
+            connector_name: name.to_string(),
+            broker_id,
+            message_rate,
+        }
+    }
+
+    fn connector_move(name: &str, from: u64, to: u64) -> ConnectorMove {
+        ConnectorMove {
+            connector_name: name.to_string(),
+            from,
+            to,
+        }
+    }
+
+    #[test]
+    fn test_plan_rebalance_moves_from_hot_broker() {
+        let connectors = vec![
+            load("a", 1, 100.0),
+            load("b", 1, 60.0),
+            load("c", 1, 40.0),
+            load("d", 2, 10.0),
+        ];
+        let moves = plan_rebalance(&[1, 2], &connectors, &HashSet::new(), 10);
+        assert_eq!(moves, vec![connector_move("a", 1, 2)]);
+
+        // A new, empty broker takes the closest fit first.
+        let moves = plan_rebalance(&[1, 2, 3], &connectors, &HashSet::new(), 10);
+        assert_eq!(
+            moves,
+            vec![connector_move("a", 1, 3), connector_move("c", 1, 2)]
+        );
+    }
+
+    #[test]
+    fn test_plan_rebalance_is_sticky() {
+        // Within tolerance of the average: nothing moves.
+        let connectors = vec![load("a", 1, 55.0), load("b", 2, 45.0)];
+        assert!(plan_rebalance(&[1, 2], &connectors, &HashSet::new(), 10).is_empty());
+
+        // One dominant connector cannot be split.
+        let connectors = vec![load("a", 1, 100.0)];
+        assert!(plan_rebalance(&[1, 2], &connectors, &HashSet::new(), 10).is_empty());
+
+        // Below the minimum rate nothing is overloaded.
+        let connectors = vec![load("a", 1, 0.5), load("b", 1, 0.3)];
+        assert!(plan_rebalance(&[1, 2], &connectors, &HashSet::new(), 10).is_empty());
+
+        // Pinned connectors stay, limited moves are honoured.
+        let connectors = vec![load("a", 1, 50.0), load("b", 1, 50.0), load("c", 1, 50.0)];
+        let pinned = HashSet::from(["a".to_string(), "b".to_string(), "c".to_string()]);
+        assert!(plan_rebalance(&[1, 2], &connectors, &pinned, 10).is_empty());
+        assert_eq!(
+            plan_rebalance(&[1, 2, 3], &connectors, &HashSet::new(), 1).len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,631 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    controller::connector::{
+        rebalance::{plan_rebalance, ConnectorLoad},
+        status::ConnectorStatus,
+    },
+    core::{cache::MetaCacheManager, error::MetaServiceError},
+    raft::manager::MultiRaftManager,
+};
+use common_base::{
+    error::ResultCommonError,
+    tools::{loop_select_ticket, now_millis, now_second},
+};
+use common_config::broker::broker_config;
+use dashmap::DashMap;
+use metadata_struct::connector::{status::MQTTStatus, MQTTConnector};
+use node_call::NodeCallManager;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+// A connector moved by rebalancing stays on its new broker at least this long.
+const CONNECTOR_MOVE_COOLDOWN_SEC: u64 = 1800;
+
+pub struct ConnectorScheduler {
+    cache_manager: Arc<MetaCacheManager>,
+    heartbeat_timeout_sec: u64,
+    connector_context: ConnectorStatus,
+    rebalance_interval_ms: u64,
+    rebalance_max_moves: usize,
+    last_rebalance_ms: AtomicU64,
+    // (connector_name, time it was last moved by rebalancing)
+    moved_at: DashMap<String, u64>,
+}
+
+impl ConnectorScheduler {
+    pub fn new(
+        raft_manager: Arc<MultiRaftManager>,
+        node_call_manager: Arc<NodeCallManager>,
+        cache_manager: Arc<MetaCacheManager>,
+    ) -> Self {
+        let config = broker_config();
+        let connector_context =
+            ConnectorStatus::new(raft_manager, node_call_manager, cache_manager.clone());
+        Self {
+            cache_manager,
+            heartbeat_timeout_sec: config.meta_runtime.heartbeat_timeout_ms / 1000,
+            connector_context,
+            rebalance_interval_ms: config.meta_runtime.connector_rebalance_interval_ms,
+            rebalance_max_moves: config.meta_runtime.connector_rebalance_max_moves as usize,
+            // The first rebalance waits a full interval after becoming leader,
+            // so brokers have reported message rates by then.
+            last_rebalance_ms: AtomicU64::new(now_millis() as u64),
+            moved_at: DashMap::new(),
+        }
+    }
+
+    pub async fn run(&self, stop_send: &broadcast::Sender<bool>) {
+        let ac_fn = async move || -> ResultCommonError {
+            if let Err(e) = self.check_heartbeat().await {
+                warn!("Heartbeat check failed: {:?}", e);
+            }
+
+            if let Err(e) = self.check_broker_failure().await {
+                warn!("Broker failure check failed: {:?}", e);
+            }
+
+            if let Err(e) = self.start_stop_connector_thread().await {
+                warn!("Connector scheduling failed: {:?}", e);
+            }
+
+            if let Err(e) = self.rebalance().await {
+                warn!("Connector rebalancing failed: {:?}", e);
+            }
+            Ok(())
+        };
+
+        loop_select_ticket(ac_fn, 1000, stop_send).await;
+    }
+}
+
+impl ConnectorScheduler {
+    async fn check_heartbeat(&self) -> Result<(), MetaServiceError> {
+        let current_time = now_second();
+
+        for heartbeat in self.cache_manager.get_all_connector_heartbeat() {
+            if !self
+                .cache_manager
+                .connector_list
+                .contains_key(&heartbeat.connector_name)
+            {
+                self.cache_manager
+                    .remove_connector_heartbeat(&heartbeat.connector_name);
+                continue;
+            }
+
+            let elapsed_time = current_time - heartbeat.last_heartbeat;
+            if elapsed_time > self.heartbeat_timeout_sec {
+                warn!(
+                    "Connector heartbeat expired: name={}, elapsed={}s, timeout={}s",
+                    heartbeat.connector_name, elapsed_time, self.heartbeat_timeout_sec
+                );
+                if let Err(e) = self
+                    .connector_context
+                    .update_status_to_idle(&heartbeat.connector_name)
+                    .await
+                {
+                    warn!(
+                        "Failed to reset connector {} to Idle: {:?}",
+                        heartbeat.connector_name, e
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Release the connectors of brokers whose heartbeat timed out, so they
+    /// are assigned to healthy brokers on the same tick.
+    async fn check_broker_failure(&self) -> Result<(), MetaServiceError> {
+        let now = now_second();
+        for connector in self.cache_manager.get_all_connector() {
+            let Some(broker_id) = connector.broker_id else {
+                continue;
+            };
+            if connector.status != MQTTStatus::Running
+                || is_broker_alive(
+                    &self.cache_manager,
+                    broker_id,
+                    self.heartbeat_timeout_sec,
+                    now,
+                )
+            {
+                continue;
+            }
+
+            warn!(
+                "Broker {} failed, connector {} will be reassigned",
+                broker_id, connector.connector_name
+            );
+            if let Err(e) = self
+                .connector_context
+                .update_status_to_idle(&connector.connector_name)
+                .await
+            {
+                warn!(
+                    "Failed to release connector {} from Broker {}: {:?}",
+                    connector.connector_name, broker_id, e
+                );
+            }
+        }
+        Ok(())
+    }
+
+    async fn start_stop_connector_thread(&self) -> Result<(), MetaServiceError> {
+        let mut idle_connectors = Vec::new();
+
+        for connector in self.cache_manager.get_all_connector() {
+            if connector.broker_id.is_none() && connector.status == MQTTStatus::Running {
+                let _ = self
+                    .connector_context
+                    .update_status_to_idle(&connector.connector_name)
+                    .await;
+                continue;
+            }
+
+            if connector.status == MQTTStatus::Idle {
+                idle_connectors.push(connector);
+            }
+        }
+
+        self.assign_and_start(&idle_connectors).await
+    }
+
+    async fn assign_and_start(
+        &self,
+        idle_connectors: &[MQTTConnector],
+    ) -> Result<(), MetaServiceError> {
+        if idle_connectors.is_empty() {
+            return Ok(());
+        }
+
+        let mut broker_load =
+            calculate_broker_load_internal(&self.cache_manager, self.heartbeat_timeout_sec)?;
+        let zones = broker_zones(&self.cache_manager);
+
+        for connector in idle_connectors {
+            let mut connector = connector.clone();
+
+            // Do not restart a connector on a failed broker or on one
+            // cordoned for an upgrade.
+            if let Some(broker_id) = connector.broker_id {
+                if !broker_load.contains_key(&broker_id) {
+                    info!(
+                        "Connector {} moves off unavailable Broker {}",
+                        connector.connector_name, broker_id
+                    );
+                    connector.broker_id = None;
+                }
+            }
+
+            if connector.broker_id.is_none() {
+                let broker_id = match pick_broker(&broker_load, &zones) {
+                    Some(id) => id,
+                    None => {
+                        warn!(
+                            "No available broker for connector {}",
+                            connector.connector_name
+                        );
+                        continue;
+                    }
+                };
+
+                connector.broker_id = Some(broker_id);
+                *broker_load.entry(broker_id).or_insert(0) += 1;
+
+                info!(
+                    "Connector {} assigned to Broker {} (load: {})",
+                    connector.connector_name, broker_id, broker_load[&broker_id]
+                );
+            }
+
+            connector.status = MQTTStatus::Running;
+
+            if let Err(e) = self
+                .connector_context
+                .save_connector(connector.clone())
+                .await
+            {
+                warn!(
+                    "Failed to save connector {}: {:?}",
+                    connector.connector_name, e
+                );
+                if let Some(bid) = connector.broker_id {
+                    if let Some(count) = broker_load.get_mut(&bid) {
+                        *count = count.saturating_sub(1);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ConnectorScheduler {
+    /// Move connectors away from brokers with a clearly higher message rate
+    /// than the others, once every `connector_rebalance_interval_ms`.
+    async fn rebalance(&self) -> Result<(), MetaServiceError> {
+        if self.rebalance_interval_ms == 0 {
+            return Ok(());
+        }
+        let now_ms = now_millis() as u64;
+        let last_ms = self.last_rebalance_ms.load(Ordering::Acquire);
+        if now_ms.saturating_sub(last_ms) < self.rebalance_interval_ms {
+            return Ok(());
+        }
+        self.last_rebalance_ms.store(now_ms, Ordering::Release);
+
+        let now = now_second();
+        self.moved_at
+            .retain(|_, moved| now.saturating_sub(*moved) < CONNECTOR_MOVE_COOLDOWN_SEC);
+        let pinned: HashSet<String> = self.moved_at.iter().map(|e| e.key().clone()).collect();
+
+        let mut brokers: Vec<u64> =
+            calculate_broker_load_internal(&self.cache_manager, self.heartbeat_timeout_sec)?
+                .into_keys()
+                .collect();
+        brokers.sort_unstable();
+        let loads = connector_loads(&self.cache_manager);
+
+        for plan in plan_rebalance(&brokers, &loads, &pinned, self.rebalance_max_moves) {
+            let Some(mut connector) = self
+                .cache_manager
+                .connector_list
+                .get(&plan.connector_name)
+                .map(|c| c.clone())
+            else {
+                continue;
+            };
+            if connector.broker_id != Some(plan.from) || connector.status != MQTTStatus::Running {
+                continue;
+            }
+
+            connector.broker_id = Some(plan.to);
+            if let Err(e) = self.connector_context.save_connector(connector).await {
+                warn!(
+                    "Failed to move connector {} to Broker {}: {:?}",
+                    plan.connector_name, plan.to, e
+                );
+                continue;
+            }
+            info!(
+                "Connector {} rebalanced from Broker {} to Broker {}",
+                plan.connector_name, plan.from, plan.to
+            );
+            self.moved_at.insert(plan.connector_name, now);
+        }
+        Ok(())
+    }
+}
+
+/// A broker is alive while it is registered and its heartbeat has not timed
+/// out. A broker with no heartbeat seen yet, e.g. right after this node became
+/// leader, counts as alive.
+fn is_broker_alive(
+    cache_manager: &MetaCacheManager,
+    broker_id: u64,
+    heartbeat_timeout_sec: u64,
+    now: u64,
+) -> bool {
+    if cache_manager.get_broker_node(broker_id).is_none() {
+        return false;
+    }
+    cache_manager
+        .get_broker_heart(broker_id)
+        .is_none_or(|heart| now.saturating_sub(heart.time) < heartbeat_timeout_sec)
+}
+
+/// Zone label of every registered broker.
+fn broker_zones(cache_manager: &MetaCacheManager) -> HashMap<u64, String> {
+    cache_manager
+        .node_list
+        .iter()
+        .map(|node| (node.node_id, node.zone.clone()))
+        .collect()
+}
+
+/// The least-loaded broker; among equally loaded brokers, the one whose zone
+/// runs the fewest connectors, so connectors spread across zones and a zone
+/// failure stops as few of them as possible. Ties go to the lowest broker id.
+fn pick_broker(broker_load: &HashMap<u64, usize>, zones: &HashMap<u64, String>) -> Option<u64> {
+    let zone_of = |id: &u64| zones.get(id).map_or("", |zone| zone.as_str());
+    let mut zone_load: HashMap<&str, usize> = HashMap::new();
+    for (id, count) in broker_load {
+        *zone_load.entry(zone_of(id)).or_insert(0) += count;
+    }
+    broker_load
+        .iter()
+        .min_by_key(|(id, count)| (**count, zone_load[zone_of(*id)], **id))
+        .map(|(id, _)| *id)
+}
+
+/// Message rate of every running connector, by the broker running it.
+fn connector_loads(cache_manager: &MetaCacheManager) -> Vec<ConnectorLoad> {
+    cache_manager
+        .get_all_connector()
+        .into_iter()
+        .filter(|connector| connector.status == MQTTStatus::Running)
+        .filter_map(|connector| {
+            let broker_id = connector.broker_id?;
+            let message_rate = cache_manager
+                .connector_heartbeat
+                .get(&connector.connector_name)
+                .filter(|heartbeat| heartbeat.broker_id == broker_id)
+                .map(|heartbeat| heartbeat.message_rate)
+                .unwrap_or_default();
+            Some(ConnectorLoad {
+                connector_name: connector.connector_name,
+                broker_id,
+                message_rate,
+            })
+        })
+        .collect()
+}
+
+/// Connector count of every broker a connector can be placed on: alive and
+/// not cordoned.
+fn calculate_broker_load_internal(
+    cache_manager: &MetaCacheManager,
+    heartbeat_timeout_sec: u64,
+) -> Result<HashMap<u64, usize>, MetaServiceError> {
+    let now = now_second();
+    let mut broker_load: HashMap<u64, usize> = cache_manager
+        .node_list
+        .iter()
+        .filter(|node| !cache_manager.is_node_cordoned(node.node_id))
+        .filter(|node| is_broker_alive(cache_manager, node.node_id, heartbeat_timeout_sec, now))
+        .map(|node| (node.node_id, 0))
+        .collect();
+
+    if broker_load.is_empty() {
+        return Err(MetaServiceError::NoAvailableBrokerNode);
+    }
+
+    for connector in cache_manager.get_all_connector() {
+        if let Some(count) = connector
+            .broker_id
+            .and_then(|broker_id| broker_load.get_mut(&broker_id))
+        {
+            *count += 1;
+        }
+    }
+
+    Ok(broker_load)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::heartbeat::NodeHeartbeatData;
+    use crate::storage::common::node::NodeCordon;
+    use common_base::tools::now_second;
+    use metadata_struct::connector::rule::ETLRule;
+    use metadata_struct::connector::{
+        status::MQTTStatus, ConnectorType, FailureHandlingStrategy, MQTTConnector,
+    };
+    use metadata_struct::meta::extend::NodeExtend;
+    use metadata_struct::meta::node::BrokerNode;
+    use metadata_struct::tenant::DEFAULT_TENANT;
+    use rocksdb_engine::test::test_rocksdb_instance;
+
+    const TIMEOUT_SEC: u64 = 30;
+
+    fn setup_test_cluster(
+        broker_count: usize,
+        connector_distribution: Vec<usize>,
+    ) -> Arc<MetaCacheManager> {
+        let cache_manager = Arc::new(MetaCacheManager::new(test_rocksdb_instance()));
+
+        for i in 1..=broker_count {
+            cache_manager.add_broker_node(BrokerNode {
+                node_id: i as u64,
+                node_ip: format!("127.0.0.{}", i),
+                grpc_addr: format!("127.0.0.{}:9000", i),
+                extend: NodeExtend::default(),
+                roles: Vec::new(),
+                start_time: now_second(),
+                register_time: now_second(),
+                storage_fold: Vec::new(),
+                ..Default::default()
+            });
+        }
+
+        for (broker_idx, &count) in connector_distribution.iter().enumerate() {
+            let broker_id = (broker_idx + 1) as u64;
+            for j in 0..count {
+                cache_manager.add_connector(MQTTConnector {
+                    connector_name: format!("conn_b{}_n{}", broker_id, j),
+                    connector_type: ConnectorType::LocalFile(
+                        metadata_struct::connector::config_local_file::LocalFileConnectorConfig::default(),
+                    ),
+                    tenant:DEFAULT_TENANT.to_string(),
+                    topic_name: "test_topic".to_string(),
+                    failure_strategy: FailureHandlingStrategy::Discard,
+                    status: MQTTStatus::Idle,
+                    broker_id: Some(broker_id),
+                    create_time: now_second(),
+                    update_time: now_second(),
+                    etl_rule:ETLRule::default(),
+                    max_records_per_sec: None,
+                });
+            }
+        }
+
+        cache_manager
+    }
+
+    fn make_unassigned_connector(name: &str) -> MQTTConnector {
+        MQTTConnector {
+            connector_name: name.to_string(),
+            connector_type: ConnectorType::LocalFile(
+                metadata_struct::connector::config_local_file::LocalFileConnectorConfig::default(),
+            ),
+            tenant: DEFAULT_TENANT.to_string(),
+            topic_name: "test_topic".to_string(),
+            failure_strategy: FailureHandlingStrategy::Discard,
+            status: MQTTStatus::Idle,
+            broker_id: None,
+            create_time: now_second(),
+            update_time: now_second(),
+            etl_rule: ETLRule::default(),
+            max_records_per_sec: None,
+        }
+    }
+
+    #[test]
+    fn test_pick_broker_spreads_ties_across_zones() {
+        // 1 and 2 in az1, 3 in az2; az1 already runs a connector on 1.
+        let zones: HashMap<u64, String> = [(1, "az1"), (2, "az1"), (3, "az2")]
+            .into_iter()
+            .map(|(id, zone)| (id, zone.to_string()))
+            .collect();
+        let load: HashMap<u64, usize> = [(1, 1), (2, 0), (3, 0)].into_iter().collect();
+        assert_eq!(pick_broker(&load, &zones), Some(3));
+
+        // Broker load comes first: the idle broker wins over a quieter zone.
+        let load: HashMap<u64, usize> = [(1, 0), (2, 0), (3, 1)].into_iter().collect();
+        assert_eq!(pick_broker(&load, &zones), Some(1));
+
+        // Without zone labels this is the least-loaded broker, lowest id first.
+        let load: HashMap<u64, usize> = [(4, 2), (5, 0), (6, 0)].into_iter().collect();
+        assert_eq!(pick_broker(&load, &HashMap::new()), Some(5));
+        assert_eq!(pick_broker(&HashMap::new(), &zones), None);
+    }
+
+    #[test]
+    fn test_calculate_broker_load() {
+        // empty cluster returns error
+        let empty = Arc::new(MetaCacheManager::new(test_rocksdb_instance()));
+        assert!(matches!(
+            calculate_broker_load_internal(&empty, TIMEOUT_SEC).unwrap_err(),
+            MetaServiceError::NoAvailableBrokerNode
+        ));
+
+        // normal distribution
+        let cm = setup_test_cluster(3, vec![2, 3, 1]);
+        let load = calculate_broker_load_internal(&cm, TIMEOUT_SEC).unwrap();
+        assert_eq!(load[&1], 2);
+        assert_eq!(load[&2], 3);
+        assert_eq!(load[&3], 1);
+
+        // all idle
+        let cm = setup_test_cluster(3, vec![0, 0, 0]);
+        let load = calculate_broker_load_internal(&cm, TIMEOUT_SEC).unwrap();
+        assert!(load.values().all(|&v| v == 0));
+        assert_eq!(load.len(), 3);
+
+        // unassigned connectors are ignored
+        let cm = setup_test_cluster(2, vec![2, 1]);
+        for i in 0..3 {
+            cm.add_connector(make_unassigned_connector(&format!("unassigned_{}", i)));
+        }
+        let load = calculate_broker_load_internal(&cm, TIMEOUT_SEC).unwrap();
+        assert_eq!(load[&1], 2);
+        assert_eq!(load[&2], 1);
+    }
+
+    #[test]
+    fn test_calculate_broker_load_skips_cordoned() {
+        let cm = setup_test_cluster(3, vec![0, 2, 1]);
+        cm.add_node_cordon(NodeCordon {
+            node_id: 1,
+            reason: "upgrade".to_string(),
+            create_time: now_second(),
+        });
+        let load = calculate_broker_load_internal(&cm, TIMEOUT_SEC).unwrap();
+        assert_eq!(load.len(), 2);
+        assert!(!load.contains_key(&1));
+        assert_eq!(load[&2], 2);
+
+        for node_id in [2, 3] {
+            cm.add_node_cordon(NodeCordon {
+                node_id,
+                ..Default::default()
+            });
+        }
+        assert!(matches!(
+            calculate_broker_load_internal(&cm, TIMEOUT_SEC).unwrap_err(),
+            MetaServiceError::NoAvailableBrokerNode
+        ));
+
+        cm.remove_node_cordon(1);
+        assert_eq!(
+            calculate_broker_load_internal(&cm, TIMEOUT_SEC).unwrap()[&1],
+            0
+        );
+    }
+
+    #[test]
+    fn test_calculate_broker_load_skips_failed() {
+        let cm = setup_test_cluster(3, vec![1, 1, 1]);
+        cm.report_broker_heart(1);
+        cm.node_heartbeat.insert(
+            2,
+            NodeHeartbeatData {
+                node_id: 2,
+                time: now_second() - TIMEOUT_SEC - 1,
+            },
+        );
+
+        // Broker 3 has not sent a heartbeat yet and still counts as alive.
+        let load = calculate_broker_load_internal(&cm, TIMEOUT_SEC).unwrap();
+        assert_eq!(load.len(), 2);
+        assert!(!load.contains_key(&2));
+        assert!(!is_broker_alive(&cm, 2, TIMEOUT_SEC, now_second()));
+        assert!(!is_broker_alive(&cm, 9, TIMEOUT_SEC, now_second()));
+
+        cm.report_broker_heart(2);
+        assert_eq!(
+            calculate_broker_load_internal(&cm, TIMEOUT_SEC)
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_connector_loads() {
+        let cm = setup_test_cluster(2, vec![1, 1]);
+        for mut connector in cm.get_all_connector() {
+            connector.status = MQTTStatus::Running;
+            cm.add_connector(connector);
+        }
+        cm.add_connector(make_unassigned_connector("unassigned"));
+
+        cm.report_connector_heartbeat("conn_b1_n0", 1, 100, 0);
+        cm.report_connector_heartbeat("conn_b1_n0", 1, 110, 1000);
+        // Heartbeat from the broker the connector ran on before.
+        cm.report_connector_heartbeat("conn_b2_n0", 1, 100, 0);
+        cm.report_connector_heartbeat("conn_b2_n0", 1, 110, 1000);
+
+        let mut loads = connector_loads(&cm);
+        loads.sort_by(|a, b| a.connector_name.cmp(&b.connector_name));
+        assert_eq!(loads.len(), 2);
+        assert_eq!(loads[0].broker_id, 1);
+        assert!((loads[0].message_rate - 20.0).abs() < 1e-9);
+        assert_eq!(loads[1].broker_id, 2);
+        assert_eq!(loads[1].message_rate, 0.0);
+    }
+}
@@ -0,0 +1,69 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::raft::manager::MultiRaftManager;
+use crate::server::services::common::kv::delete_by_req;
+use crate::storage::common::kv::KvStorage;
+use common_base::error::common::CommonError;
+use common_base::error::ResultCommonError;
+use common_base::tools::{loop_select_ticket, now_millis};
+use protocol::meta::meta_service_common::DeleteRequest;
+use rocksdb_engine::rocksdb::RocksDBEngine;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+// Expired keys are already hidden from reads, the GC only reclaims them.
+const KV_GC_INTERVAL_MS: u64 = 10 * 1000;
+
+pub async fn start_kv_gc_thread(
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+    raft_manager: Arc<MultiRaftManager>,
+    stop_send: broadcast::Sender<bool>,
+) {
+    let ac_fn = async || -> ResultCommonError {
+        gc_expired_kvs(&rocksdb_engine_handler, &raft_manager).await
+    };
+    loop_select_ticket(ac_fn, KV_GC_INTERVAL_MS, &stop_send).await;
+}
+
+async fn gc_expired_kvs(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    raft_manager: &Arc<MultiRaftManager>,
+) -> Result<(), CommonError> {
+    let storage = KvStorage::new(rocksdb_engine_handler.clone());
+
+    for expired in storage.list_expired(now_millis() as u64)? {
+        // Guard the delete with the expired version, so a key rewritten in
+        // the meantime is left alone.
+        let req = DeleteRequest {
+            key: expired.key.clone(),
+            expected_version: Some(expired.version),
+        };
+        match delete_by_req(raft_manager, &req).await {
+            Ok(reply) if reply.deleted => {
+                debug!(
+                    "Expired kv {} cleaned up, version={}, expire_at_ms={}",
+                    expired.key, expired.version, expired.expire_at_ms
+                );
+            }
+            Ok(_) => {}
+            Err(e) => {
+                warn!("Failed to delete expired kv {}, error={}", expired.key, e);
+            }
+        }
+    }
+
+    Ok(())
+}
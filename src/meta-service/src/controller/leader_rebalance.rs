@@ -101,6 +101,7 @@ async fn rebalance_once(
 /// The active segment should move its leadership back to the preferred replica
 /// (replicas[0], the leader chosen at creation) when that replica is alive and
 /// in-sync — restoring the balanced placement that failover/recovery moved away.
+/// A cordoned preferred replica is left alone until it is uncordoned.
 fn should_rebalance(cache_manager: &Arc<MetaCacheManager>, seg: &EngineSegment) -> bool {
     if seg.status != SegmentStatus::Write {
         return false;
@@ -111,6 +112,7 @@ fn should_rebalance(cache_manager: &Arc<MetaCacheManager>, seg: &EngineSegment)
     preferred != seg.leader
         && seg.isr.contains(&preferred)
         && cache_manager.get_broker_node(preferred).is_some()
+        && !cache_manager.is_node_cordoned(preferred)
 }
 
 async fn switch_to_preferred(
@@ -167,3 +169,48 @@ async fn switch_to_preferred(
     );
     Ok(true)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::common::node::NodeCordon;
+    use metadata_struct::meta::node::BrokerNode;
+    use metadata_struct::storage::segment::Replica;
+    use rocksdb_engine::test::test_rocksdb_instance;
+
+    #[test]
+    fn test_should_rebalance_skips_cordoned_preferred() {
+        let cache_manager = Arc::new(MetaCacheManager::new(test_rocksdb_instance()));
+        for node_id in [1, 2] {
+            cache_manager.add_broker_node(BrokerNode {
+                node_id,
+                ..Default::default()
+            });
+        }
+        let seg = EngineSegment {
+            shard_name: "s1".to_string(),
+            leader: 2,
+            isr: vec![1, 2],
+            replicas: [1, 2]
+                .into_iter()
+                .map(|node_id| Replica {
+                    replica_seq: 0,
+                    node_id,
+                    fold: String::new(),
+                })
+                .collect(),
+            status: SegmentStatus::Write,
+            ..Default::default()
+        };
+        assert!(should_rebalance(&cache_manager, &seg));
+
+        cache_manager.add_node_cordon(NodeCordon {
+            node_id: 1,
+            ..Default::default()
+        });
+        assert!(!should_rebalance(&cache_manager, &seg));
+
+        cache_manager.remove_node_cordon(1);
+        assert!(should_rebalance(&cache_manager, &seg));
+    }
+}
@@ -12,11 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::controller::connector_scheduler::ConnectorScheduler;
+use crate::controller::blacklist_gc::start_blacklist_gc_thread;
+use crate::controller::connector::scheduler::ConnectorScheduler;
 use crate::controller::engine_gc::start_engine_delete_gc_thread;
 use crate::controller::group_gc::start_group_gc_thread;
+use crate::controller::kv_gc::start_kv_gc_thread;
 use crate::controller::leader_rebalance::start_segment_leader_rebalance_thread;
 use crate::controller::mail_gc::start_mail_gc_thread;
+use crate::controller::subscribe_gc::start_subscribe_gc_thread;
 use crate::controller::topic_delete::start_topic_delete_thread;
 use crate::core::cache::MetaCacheManager;
 use crate::core::segment_replica::start_inner_topic_replica_fill_thread;
@@ -29,12 +32,14 @@
 use tokio::sync::broadcast::{self, Sender};
 use tracing::error;
 
-pub mod connector_scheduler;
-pub mod connector_status;
+pub mod blacklist_gc;
+pub mod connector;
 pub mod engine_gc;
 pub mod group_gc;
+pub mod kv_gc;
 pub mod leader_rebalance;
 pub mod mail_gc;
+pub mod subscribe_gc;
 pub mod topic_delete;
 
 pub fn start_controller(
@@ -160,6 +165,46 @@ pub async fn start(&self, stop_send: &broadcast::Sender<bool>) {
             .await;
         }));
 
+        // expired blacklist gc
+        let rocksdb_engine_handler = self.rocksdb_engine_handler.clone();
+        let raft_manager = self.raft_manager.clone();
+        let call_manager = self.node_call_manager.clone();
+        let raw_stop_send = stop_send.clone();
+        tokio::spawn(Box::pin(async move {
+            start_blacklist_gc_thread(
+                rocksdb_engine_handler,
+                raft_manager,
+                call_manager,
+                raw_stop_send,
+            )
+            .await;
+        }));
+
+        // expired kv gc
+        let rocksdb_engine_handler = self.rocksdb_engine_handler.clone();
+        let raft_manager = self.raft_manager.clone();
+        let raw_stop_send = stop_send.clone();
+        tokio::spawn(Box::pin(async move {
+            start_kv_gc_thread(rocksdb_engine_handler, raft_manager, raw_stop_send).await;
+        }));
+
+        // orphaned subscription gc
+        let rocksdb_engine_handler = self.rocksdb_engine_handler.clone();
+        let raft_manager = self.raft_manager.clone();
+        let call_manager = self.node_call_manager.clone();
+        let node_cache = self.node_cache.clone();
+        let raw_stop_send = stop_send.clone();
+        tokio::spawn(Box::pin(async move {
+            start_subscribe_gc_thread(
+                rocksdb_engine_handler,
+                raft_manager,
+                call_manager,
+                node_cache,
+                raw_stop_send,
+            )
+            .await;
+        }));
+
         // topic delete gc
         let rocksdb_engine_handler = self.rocksdb_engine_handler.clone();
         let call_manager = self.node_call_manager.clone();
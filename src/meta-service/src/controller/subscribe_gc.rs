@@ -0,0 +1,215 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::raft::manager::MultiRaftManager;
+use crate::server::services::mqtt::subscribe::delete_subscribe_by_req;
+use crate::storage::mqtt::session::MqttSessionStorage;
+use crate::storage::mqtt::subscribe::MqttSubscribeStorage;
+use broker_core::cache::NodeCacheManager;
+use common_base::error::common::CommonError;
+use common_base::error::ResultCommonError;
+use common_base::tools::{loop_select_ticket, now_second};
+use common_metrics::meta::subscribe::record_orphaned_subscribe_num;
+use dashmap::DashSet;
+use metadata_struct::mqtt::session::MqttSession;
+use metadata_struct::mqtt::subscribe::MqttSubscribe;
+use node_call::NodeCallManager;
+use protocol::meta::meta_service_mqtt::DeleteSubscribeRequest;
+use rocksdb_engine::rocksdb::RocksDBEngine;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+// Scan every minute
+const SUBSCRIBE_GC_INTERVAL_MS: u64 = 60 * 1000;
+
+pub async fn start_subscribe_gc_thread(
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+    raft_manager: Arc<MultiRaftManager>,
+    node_call_manager: Arc<NodeCallManager>,
+    node_cache: Arc<NodeCacheManager>,
+    stop_send: broadcast::Sender<bool>,
+) {
+    // Clients found orphaned by the previous scan. A client is only cleaned
+    // up when two scans in a row find it, so a subscription saved just before
+    // its session is not removed.
+    let suspects = DashSet::new();
+    let ac_fn = async || -> ResultCommonError {
+        gc_orphaned_subscribes(
+            &rocksdb_engine_handler,
+            &raft_manager,
+            &node_call_manager,
+            &node_cache,
+            &suspects,
+        )
+        .await
+    };
+    loop_select_ticket(ac_fn, SUBSCRIBE_GC_INTERVAL_MS, &stop_send).await;
+}
+
+async fn gc_orphaned_subscribes(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    raft_manager: &Arc<MultiRaftManager>,
+    node_call_manager: &Arc<NodeCallManager>,
+    node_cache: &Arc<NodeCacheManager>,
+    suspects: &DashSet<(String, String)>,
+) -> Result<(), CommonError> {
+    let subscribe_storage = MqttSubscribeStorage::new(rocksdb_engine_handler.clone());
+    let session_storage = MqttSessionStorage::new(rocksdb_engine_handler.clone());
+    let now = now_second();
+
+    let subscribes = subscribe_storage.list_all()?;
+    let mut sessions = BTreeMap::new();
+    for subscribe in subscribes.iter() {
+        let key = (subscribe.tenant.clone(), subscribe.client_id.clone());
+        if sessions.contains_key(&key) {
+            continue;
+        }
+        let session = match node_cache.get_session(&subscribe.tenant, &subscribe.client_id) {
+            Some(session) => Some(session),
+            None => session_storage.get(&subscribe.tenant, &subscribe.client_id)?,
+        };
+        sessions.insert(key, session);
+    }
+
+    let orphans = orphaned_clients(&subscribes, &sessions, now);
+    record_orphaned_subscribe_num(orphans.values().sum());
+
+    let previous: HashSet<(String, String)> = suspects.iter().map(|key| key.clone()).collect();
+    suspects.clear();
+    for (key, num) in orphans {
+        if !previous.contains(&key) {
+            suspects.insert(key);
+            continue;
+        }
+
+        // Delete via raft and notify the brokers so they drop the
+        // subscriptions from their caches as well.
+        let (tenant, client_id) = key;
+        let req = DeleteSubscribeRequest {
+            client_id: client_id.clone(),
+            path: String::new(),
+        };
+        if let Err(e) = delete_subscribe_by_req(
+            raft_manager,
+            rocksdb_engine_handler,
+            node_call_manager,
+            &req,
+        )
+        .await
+        {
+            warn!(
+                "Failed to delete orphaned subscriptions: tenant={}, client_id={}, error={}",
+                tenant, client_id, e
+            );
+            suspects.insert((tenant, client_id));
+            continue;
+        }
+
+        info!(
+            "Orphaned subscriptions cleaned up: tenant={}, client_id={}, count={}",
+            tenant, client_id, num
+        );
+    }
+
+    Ok(())
+}
+
+/// Number of subscriptions per (tenant, client_id) whose session no longer
+/// exists or has expired.
+fn orphaned_clients(
+    subscribes: &[MqttSubscribe],
+    sessions: &BTreeMap<(String, String), Option<MqttSession>>,
+    now: u64,
+) -> BTreeMap<(String, String), usize> {
+    let mut orphans = BTreeMap::new();
+    for subscribe in subscribes {
+        let key = (subscribe.tenant.clone(), subscribe.client_id.clone());
+        let is_orphan = match sessions.get(&key) {
+            Some(Some(session)) => is_session_expired(session, now),
+            _ => true,
+        };
+        if is_orphan {
+            *orphans.entry(key).or_insert(0) += 1;
+        }
+    }
+    orphans
+}
+
+// A disconnected session expires session_expiry_interval seconds after it
+// went offline.
+fn is_session_expired(session: &MqttSession, now: u64) -> bool {
+    session.connection_id.is_none()
+        && session.broker_id.is_none()
+        && session
+            .distinct_time
+            .is_some_and(|distinct_time| distinct_time + session.session_expiry_interval <= now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subscribe(tenant: &str, client_id: &str, path: &str) -> MqttSubscribe {
+        MqttSubscribe {
+            tenant: tenant.to_string(),
+            client_id: client_id.to_string(),
+            path: path.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn session(connected: bool, distinct_time: Option<u64>) -> MqttSession {
+        MqttSession {
+            session_expiry_interval: 10,
+            connection_id: connected.then_some(1),
+            broker_id: connected.then_some(1),
+            distinct_time,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_is_session_expired() {
+        assert!(!is_session_expired(&session(true, None), 100));
+        assert!(!is_session_expired(&session(false, None), 100));
+        assert!(!is_session_expired(&session(false, Some(95)), 100));
+        assert!(is_session_expired(&session(false, Some(90)), 100));
+    }
+
+    #[test]
+    fn test_orphaned_clients() {
+        let subscribes = vec![
+            subscribe("t1", "online", "a/b"),
+            subscribe("t1", "expired", "a/b"),
+            subscribe("t1", "expired", "a/c"),
+            subscribe("t1", "deleted", "a/b"),
+        ];
+        let mut sessions = BTreeMap::new();
+        sessions.insert(
+            ("t1".to_string(), "online".to_string()),
+            Some(session(true, None)),
+        );
+        sessions.insert(
+            ("t1".to_string(), "expired".to_string()),
+            Some(session(false, Some(10))),
+        );
+        sessions.insert(("t1".to_string(), "deleted".to_string()), None);
+
+        let orphans = orphaned_clients(&subscribes, &sessions, 100);
+        assert_eq!(orphans.len(), 2);
+        assert_eq!(orphans[&("t1".to_string(), "expired".to_string())], 2);
+        assert_eq!(orphans[&("t1".to_string(), "deleted".to_string())], 1);
+    }
+}
@@ -12,10 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::cache_list::ListCache;
+use super::cache_version::CacheVersionTracker;
+use super::config_drift::ConfigDriftTracker;
+use super::consumer_group::ConsumerGroupCoordinator;
 use super::heartbeat::NodeHeartbeatData;
+use super::subscribe_route::SubscribeRouteTrie;
+use super::topic_stats::TopicStatsCache;
+use super::watch::WatchHub;
 use crate::core::error::MetaServiceError;
 use crate::server::services::mqtt::connector::ConnectorHeartbeat;
-use crate::storage::common::node::NodeStorage;
+use crate::storage::common::node::{NodeCordon, NodeStorage};
 use crate::storage::common::tenant::TenantStorage;
 use crate::storage::journal::segment::SegmentStorage;
 use crate::storage::journal::segment_meta::SegmentMetadataStorage;
@@ -33,6 +40,7 @@
 use metadata_struct::tenant::Tenant;
 use rocksdb_engine::rocksdb::RocksDBEngine;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
@@ -81,6 +89,12 @@ pub struct MetaCacheManager {
     // (node_id, NodeHeartbeatData)
     pub node_heartbeat: DashMap<u64, NodeHeartbeatData>,
 
+    // (node_id, version), as reported by heartbeats
+    pub node_version: DashMap<u64, String>,
+
+    // (node_id, NodeCordon)
+    pub node_cordon: DashMap<u64, NodeCordon>,
+
     // MQTT
     // (client_id, MQTTConnector)
     pub connector_list: DashMap<String, MQTTConnector>,
@@ -110,6 +124,34 @@ pub struct MetaCacheManager {
     // Per-node replica/leader placement load (not persisted; rebuilt on demand).
     #[serde(skip)]
     pub node_load: NodeLoadCache,
+
+    // Encoded snapshots served by the MQTT list RPCs (not persisted).
+    #[serde(skip)]
+    pub list_cache: ListCache,
+
+    // Topic filters hosted by each broker (rebuilt from storage on load).
+    #[serde(skip)]
+    pub subscribe_route: SubscribeRouteTrie,
+
+    // Members and shard assignment of storage consumer groups (not persisted).
+    #[serde(skip)]
+    pub consumer_group: ConsumerGroupCoordinator,
+
+    // Per-topic statistics reported by the brokers (not persisted).
+    #[serde(skip)]
+    pub topic_stats: TopicStatsCache,
+
+    // Brokers falling behind their cache notifications (not persisted).
+    #[serde(skip)]
+    pub cache_version: CacheVersionTracker,
+
+    // Config sections the brokers disagree on (not persisted).
+    #[serde(skip)]
+    pub config_drift: ConfigDriftTracker,
+
+    // Recent metadata changes served by the WatchResources RPC (not persisted).
+    #[serde(skip)]
+    pub watch_hub: WatchHub,
 }
 
 impl MetaCacheManager {
@@ -117,6 +159,8 @@ pub fn new(rocksdb_engine_handler: Arc<RocksDBEngine>) -> MetaCacheManager {
         let mut cache = MetaCacheManager {
             tenant_list: DashMap::with_capacity(8),
             node_heartbeat: DashMap::with_capacity(2),
+            node_version: DashMap::with_capacity(2),
+            node_cordon: DashMap::with_capacity(2),
             node_list: DashMap::with_capacity(2),
             connector_list: DashMap::with_capacity(8),
             connector_heartbeat: DashMap::with_capacity(8),
@@ -127,6 +171,13 @@ pub fn new(rocksdb_engine_handler: Arc<RocksDBEngine>) -> MetaCacheManager {
             wait_delete_segment_list: DashMap::with_capacity(8),
             group_leader: DashMap::with_capacity(8),
             node_load: NodeLoadCache::default(),
+            list_cache: ListCache::default(),
+            subscribe_route: SubscribeRouteTrie::default(),
+            consumer_group: ConsumerGroupCoordinator::default(),
+            topic_stats: TopicStatsCache::default(),
+            cache_version: CacheVersionTracker::default(),
+            config_drift: ConfigDriftTracker::default(),
+            watch_hub: WatchHub::default(),
         };
         cache.load_cache(rocksdb_engine_handler);
         cache
@@ -157,7 +208,12 @@ pub fn add_broker_node(&self, node: BrokerNode) {
     pub fn remove_broker_node(&self, node_id: u64) -> Option<(u64, BrokerNode)> {
         self.node_list.remove(&node_id);
         self.node_heartbeat.remove(&node_id);
+        self.node_version.remove(&node_id);
         self.node_load.remove_node(node_id);
+        self.consumer_group.remove_node(node_id);
+        self.topic_stats.remove_node(node_id);
+        self.cache_version.remove_node(node_id);
+        self.config_drift.remove_node(node_id);
         None
     }
 
@@ -194,6 +250,46 @@ pub fn get_broker_heart(&self, node_id: u64) -> Option<NodeHeartbeatData> {
         None
     }
 
+    pub fn report_broker_version(&self, node_id: u64, version: &str) {
+        self.node_version.insert(node_id, version.to_string());
+    }
+
+    pub fn get_broker_version(&self, node_id: u64) -> Option<String> {
+        self.node_version.get(&node_id).map(|v| v.clone())
+    }
+
+    // Cordon
+    pub fn add_node_cordon(&self, cordon: NodeCordon) {
+        self.node_cordon.insert(cordon.node_id, cordon);
+    }
+
+    pub fn remove_node_cordon(&self, node_id: u64) {
+        self.node_cordon.remove(&node_id);
+    }
+
+    pub fn is_node_cordoned(&self, node_id: u64) -> bool {
+        self.node_cordon.contains_key(&node_id)
+    }
+
+    pub fn cordoned_node_ids(&self) -> HashSet<u64> {
+        self.node_cordon.iter().map(|c| *c.key()).collect()
+    }
+
+    /// Replace the cordon marks with what is stored, e.g. at startup or after
+    /// a raft snapshot was installed.
+    pub fn load_node_cordon(
+        &self,
+        rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    ) -> Result<(), MetaServiceError> {
+        let storage = NodeStorage::new(rocksdb_engine_handler.clone());
+        let cordons = storage.list_cordon()?;
+        self.node_cordon.clear();
+        for cordon in cordons {
+            self.add_node_cordon(cordon);
+        }
+        Ok(())
+    }
+
     pub fn load_cache(&mut self, rocksdb_engine_handler: Arc<RocksDBEngine>) {
         let node = NodeStorage::new(rocksdb_engine_handler);
         if let Ok(result) = node.list() {
@@ -237,5 +333,8 @@ pub fn load_cache_by_rocksdb(
         cache_manager.add_connector(connector);
     }
 
+    cache_manager.subscribe_route.load(rocksdb_engine_handler)?;
+    cache_manager.load_node_cordon(rocksdb_engine_handler)?;
+
     Ok(())
 }
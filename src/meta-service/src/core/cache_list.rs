@@ -0,0 +1,162 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Read-path cache for the MQTT list RPCs (`list_topic`, `list_user`,
+//! `list_session`).
+//!
+//! Each resource type keeps an encoded, sorted snapshot of what is stored in
+//! RocksDB together with the version it was built from. The raft apply path
+//! bumps the version on every write to that resource and the next list call
+//! rebuilds the snapshot; in between, lists are served from memory without
+//! touching RocksDB or re-encoding.
+
+use crate::core::error::MetaServiceError;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ListResource {
+    Topic,
+    User,
+    Session,
+}
+
+#[derive(Clone, Debug)]
+pub struct ListCacheEntry {
+    pub tenant: String,
+    pub name: String,
+    pub data: Vec<u8>,
+    /// Latest create/update time of the resource, in seconds.
+    pub updated_at: u64,
+}
+
+type Snapshot = Option<(u64, Arc<Vec<ListCacheEntry>>)>;
+
+#[derive(Clone, Default, Debug)]
+struct ResourceListCache {
+    version: Arc<AtomicU64>,
+    snapshot: Arc<RwLock<Snapshot>>,
+}
+
+impl ResourceListCache {
+    fn invalidate(&self) {
+        self.version.fetch_add(1, Ordering::AcqRel);
+    }
+
+    fn get_or_load<F>(&self, load: F) -> Result<Arc<Vec<ListCacheEntry>>, MetaServiceError>
+    where
+        F: FnOnce() -> Result<Vec<ListCacheEntry>, MetaServiceError>,
+    {
+        // Read the version before loading: a write applied while we load bumps
+        // it again, so the snapshot stored below is rebuilt on the next call.
+        let version = self.version.load(Ordering::Acquire);
+        if let Some((cached_version, entries)) = self.snapshot.read().unwrap().as_ref() {
+            if *cached_version == version {
+                return Ok(entries.clone());
+            }
+        }
+
+        let mut entries = load()?;
+        entries.sort_by(|a, b| (&a.tenant, &a.name).cmp(&(&b.tenant, &b.name)));
+        let entries = Arc::new(entries);
+
+        let mut snapshot = self.snapshot.write().unwrap();
+        if snapshot.as_ref().is_none_or(|(v, _)| *v < version) {
+            *snapshot = Some((version, entries.clone()));
+        }
+        Ok(entries)
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct ListCache {
+    topic: ResourceListCache,
+    user: ResourceListCache,
+    session: ResourceListCache,
+}
+
+impl ListCache {
+    fn resource(&self, resource: ListResource) -> &ResourceListCache {
+        match resource {
+            ListResource::Topic => &self.topic,
+            ListResource::User => &self.user,
+            ListResource::Session => &self.session,
+        }
+    }
+
+    pub fn invalidate(&self, resource: ListResource) {
+        self.resource(resource).invalidate();
+    }
+
+    pub fn invalidate_all(&self) {
+        self.topic.invalidate();
+        self.user.invalidate();
+        self.session.invalidate();
+    }
+
+    pub fn version(&self, resource: ListResource) -> u64 {
+        self.resource(resource).version.load(Ordering::Acquire)
+    }
+
+    /// Return the snapshot of `resource`, rebuilding it with `load` if a write
+    /// has been applied since it was built.
+    pub fn get_or_load<F>(
+        &self,
+        resource: ListResource,
+        load: F,
+    ) -> Result<Arc<Vec<ListCacheEntry>>, MetaServiceError>
+    where
+        F: FnOnce() -> Result<Vec<ListCacheEntry>, MetaServiceError>,
+    {
+        self.resource(resource).get_or_load(load)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(tenant: &str, name: &str, updated_at: u64) -> ListCacheEntry {
+        ListCacheEntry {
+            tenant: tenant.to_string(),
+            name: name.to_string(),
+            data: name.as_bytes().to_vec(),
+            updated_at,
+        }
+    }
+
+    #[test]
+    fn test_list_cache_version() {
+        let cache = ListCache::default();
+        let loads = AtomicU64::new(0);
+        let load = || {
+            loads.fetch_add(1, Ordering::SeqCst);
+            Ok(vec![entry("t2", "b", 1), entry("t1", "a", 1)])
+        };
+
+        let entries = cache.get_or_load(ListResource::Topic, load).unwrap();
+        assert_eq!(entries[0].name, "a");
+        cache.get_or_load(ListResource::Topic, load).unwrap();
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+
+        cache.invalidate(ListResource::User);
+        cache.get_or_load(ListResource::Topic, load).unwrap();
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+
+        cache.invalidate(ListResource::Topic);
+        assert_eq!(cache.version(ListResource::Topic), 1);
+        cache.get_or_load(ListResource::Topic, load).unwrap();
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+    }
+}
@@ -17,6 +17,9 @@
 use metadata_struct::connector::MQTTConnector;
 use metadata_struct::mqtt::share_group::ShareGroup;
 
+// Weight of the newest sample in a connector's smoothed message rate.
+const CONNECTOR_RATE_SMOOTHING: f64 = 0.2;
+
 impl MetaCacheManager {
     pub fn add_group_leader(&self, group_info: ShareGroup) {
         let key = format!("{}/{}", group_info.tenant, group_info.group_name);
@@ -49,11 +52,34 @@ pub fn get_all_connector(&self) -> Vec<MQTTConnector> {
             .collect()
     }
 
-    pub fn report_connector_heartbeat(&self, connector_name: &str, heartbeat_time: u64) {
+    pub fn report_connector_heartbeat(
+        &self,
+        connector_name: &str,
+        broker_id: u64,
+        heartbeat_time: u64,
+        message_total: u64,
+    ) {
+        let message_rate = match self.connector_heartbeat.get(connector_name) {
+            // The counter restarts when the connector moves or restarts.
+            Some(prev) if prev.broker_id == broker_id && message_total >= prev.message_total => {
+                if heartbeat_time <= prev.last_heartbeat {
+                    return;
+                }
+                let sample = (message_total - prev.message_total) as f64
+                    / (heartbeat_time - prev.last_heartbeat) as f64;
+                prev.message_rate * (1.0 - CONNECTOR_RATE_SMOOTHING)
+                    + sample * CONNECTOR_RATE_SMOOTHING
+            }
+            _ => 0.0,
+        };
+
         let name = connector_name.to_string();
         let heartbeat = ConnectorHeartbeat {
             connector_name: name.clone(),
+            broker_id,
             last_heartbeat: heartbeat_time,
+            message_total,
+            message_rate,
         };
         self.connector_heartbeat.insert(name, heartbeat);
     }
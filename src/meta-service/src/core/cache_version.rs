@@ -0,0 +1,158 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Detects brokers whose cache stopped following the UpdateCache
+//! notifications sent to them. Each heartbeat carries the highest version a
+//! broker applied per resource type. A broker counts as behind while it has
+//! not reached the version that was the latest when it was last seen caught
+//! up, which keeps notifications still in flight from raising an alarm.
+
+use common_base::tools::now_second;
+use common_metrics::node_call::{
+    record_node_call_cache_divergence, set_node_call_cache_behind_seconds,
+};
+use dashmap::DashMap;
+use node_call::version::CacheVersions;
+use protocol::broker::broker::BrokerUpdateCacheResourceType;
+use std::collections::HashMap;
+use tracing::warn;
+
+pub const CACHE_DIVERGENCE_ALARM_SECS: u64 = 60;
+
+#[derive(Clone, Copy, Debug)]
+struct VersionTarget {
+    version: u64,
+    since: u64,
+    alarmed: bool,
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct CacheVersionTracker {
+    // ((node_id, resource_type), VersionTarget)
+    targets: DashMap<(u64, String), VersionTarget>,
+}
+
+impl CacheVersionTracker {
+    pub fn report(&self, node_id: u64, sent: &CacheVersions, applied: &HashMap<String, u64>) {
+        let now = now_second();
+        for (resource_type, latest) in sent.snapshot() {
+            let name = resource_type.as_str_name();
+            // A broker that has applied nothing of a type since it last loaded
+            // its cache from a snapshot counts as caught up.
+            let applied = applied.get(name).copied().unwrap_or(latest);
+            let behind = self.observe(node_id, resource_type, latest, applied, now);
+            set_node_call_cache_behind_seconds(node_id, name, behind);
+        }
+    }
+
+    /// Returns how many seconds the node has been behind for this resource type.
+    fn observe(
+        &self,
+        node_id: u64,
+        resource_type: BrokerUpdateCacheResourceType,
+        latest: u64,
+        applied: u64,
+        now: u64,
+    ) -> u64 {
+        let name = resource_type.as_str_name();
+        let key = (node_id, name.to_string());
+        if applied >= latest {
+            self.targets.remove(&key);
+            return 0;
+        }
+
+        let mut target = self.targets.entry(key).or_insert(VersionTarget {
+            version: latest,
+            since: now,
+            alarmed: false,
+        });
+        if applied >= target.version {
+            *target = VersionTarget {
+                version: latest,
+                since: now,
+                alarmed: false,
+            };
+            return 0;
+        }
+
+        let behind = now.saturating_sub(target.since);
+        if behind >= CACHE_DIVERGENCE_ALARM_SECS && !target.alarmed {
+            target.alarmed = true;
+            record_node_call_cache_divergence(node_id, name);
+            warn!(
+                "Broker node {} has not applied {} cache version {} for {}s (applied {})",
+                node_id, name, target.version, behind, applied
+            );
+        }
+        behind
+    }
+
+    pub fn remove_node(&self, node_id: u64) {
+        self.targets.retain(|(id, resource_type), _| {
+            if *id == node_id {
+                set_node_call_cache_behind_seconds(node_id, resource_type, 0);
+            }
+            *id != node_id
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common_metrics::node_call::get_node_call_cache_divergence;
+
+    const TOPIC: BrokerUpdateCacheResourceType = BrokerUpdateCacheResourceType::Topic;
+
+    #[test]
+    fn test_in_flight_versions_are_not_behind() {
+        let tracker = CacheVersionTracker::default();
+        // Caught up.
+        assert_eq!(tracker.observe(1, TOPIC, 10, 10, 100), 0);
+        // Version 12 is still in flight: the target is set, but not behind yet.
+        assert_eq!(tracker.observe(1, TOPIC, 12, 10, 100), 0);
+        // The target was reached while newer versions keep coming.
+        assert_eq!(tracker.observe(1, TOPIC, 15, 12, 130), 0);
+        assert_eq!(tracker.observe(1, TOPIC, 18, 15, 160), 0);
+        assert!(tracker.targets.contains_key(&(1, "Topic".to_string())));
+    }
+
+    #[test]
+    fn test_stuck_node_raises_one_alarm() {
+        let tracker = CacheVersionTracker::default();
+        assert_eq!(tracker.observe(7, TOPIC, 20, 10, 100), 0);
+        assert_eq!(tracker.observe(7, TOPIC, 25, 10, 130), 30);
+        let before = get_node_call_cache_divergence(7, "Topic");
+
+        assert_eq!(
+            tracker.observe(7, TOPIC, 25, 10, 100 + CACHE_DIVERGENCE_ALARM_SECS),
+            60
+        );
+        assert_eq!(tracker.observe(7, TOPIC, 25, 10, 200), 100);
+        assert_eq!(get_node_call_cache_divergence(7, "Topic"), before + 1);
+
+        // Catching up clears the state.
+        assert_eq!(tracker.observe(7, TOPIC, 25, 25, 210), 0);
+        assert!(tracker.targets.is_empty());
+    }
+
+    #[test]
+    fn test_remove_node() {
+        let tracker = CacheVersionTracker::default();
+        tracker.observe(1, TOPIC, 20, 10, 100);
+        tracker.observe(2, TOPIC, 20, 10, 100);
+        tracker.remove_node(1);
+        assert_eq!(tracker.targets.len(), 1);
+    }
+}
@@ -29,7 +29,7 @@
 };
 use rocksdb_engine::rocksdb::RocksDBEngine;
 use std::sync::Arc;
-use tracing::error;
+use tracing::{error, warn};
 
 pub async fn register_node_by_req(
     meta_cache: &Arc<MetaCacheManager>,
@@ -102,6 +102,12 @@ pub async fn decommission_node(
     if let Some(node) = meta_cache.get_broker_node(node_id) {
         sync_delete_node(raft_manager, &UnRegisterNodeRequest { node_id }).await?;
         send_notify_by_delete_node(call_manager, node.clone()).await?;
+        if let Err(e) = call_manager.dead_letter().remove_node(node_id) {
+            warn!(
+                "Failed to drop dead letters of decommissioned node {}: {}",
+                node_id, e
+            );
+        }
 
         let meta_cache = meta_cache.clone();
         let raft_manager = raft_manager.clone();
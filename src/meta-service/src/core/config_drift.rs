@@ -0,0 +1,147 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Detects brokers running with different cluster-wide settings. Each
+//! heartbeat carries a digest per config section; a section drifts while the
+//! nodes that reported it disagree on its digest.
+
+use common_base::tools::now_second;
+use dashmap::DashMap;
+use std::collections::{BTreeSet, HashMap};
+use tracing::{info, warn};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SectionDrift {
+    pub section: String,
+    // (node_id, digest)
+    pub digests: HashMap<u64, u32>,
+    pub since: u64,
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct ConfigDriftTracker {
+    // (node_id, (section, digest))
+    digests: DashMap<u64, HashMap<String, u32>>,
+    // (section, time the nodes were first seen disagreeing)
+    drifting: DashMap<String, u64>,
+}
+
+impl ConfigDriftTracker {
+    pub fn report(&self, node_id: u64, digests: &HashMap<String, u32>) {
+        // Brokers that predate config digests report nothing.
+        if digests.is_empty() {
+            return;
+        }
+        if self.digests.get(&node_id).is_some_and(|d| *d == *digests) {
+            return;
+        }
+        self.digests.insert(node_id, digests.clone());
+        self.refresh(now_second());
+    }
+
+    pub fn remove_node(&self, node_id: u64) {
+        if self.digests.remove(&node_id).is_some() {
+            self.refresh(now_second());
+        }
+    }
+
+    /// Sections the nodes currently disagree on, by name.
+    pub fn drifts(&self) -> Vec<SectionDrift> {
+        let mut drifts: Vec<SectionDrift> = self
+            .drifting
+            .iter()
+            .map(|entry| SectionDrift {
+                section: entry.key().clone(),
+                digests: self.section_digests(entry.key()),
+                since: *entry.value(),
+            })
+            .collect();
+        drifts.sort_by(|a, b| a.section.cmp(&b.section));
+        drifts
+    }
+
+    fn section_digests(&self, section: &str) -> HashMap<u64, u32> {
+        self.digests
+            .iter()
+            .filter_map(|node| node.value().get(section).map(|d| (*node.key(), *d)))
+            .collect()
+    }
+
+    fn refresh(&self, now: u64) {
+        let sections: BTreeSet<String> = self
+            .digests
+            .iter()
+            .flat_map(|node| node.value().keys().cloned().collect::<Vec<_>>())
+            .collect();
+
+        for section in sections.iter() {
+            let digests = self.section_digests(section);
+            let distinct: BTreeSet<u32> = digests.values().copied().collect();
+            if distinct.len() > 1 {
+                if !self.drifting.contains_key(section) {
+                    self.drifting.insert(section.clone(), now);
+                    warn!(
+                        "Config section {} differs between nodes, digests by node: {:?}",
+                        section, digests
+                    );
+                }
+            } else if self.drifting.remove(section).is_some() {
+                info!(
+                    "Config section {} is consistent across nodes again",
+                    section
+                );
+            }
+        }
+        self.drifting
+            .retain(|section, _| sections.contains(section));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digests(entries: &[(&str, u32)]) -> HashMap<String, u32> {
+        entries
+            .iter()
+            .map(|(section, digest)| (section.to_string(), *digest))
+            .collect()
+    }
+
+    #[test]
+    fn test_config_drift() {
+        let tracker = ConfigDriftTracker::default();
+        tracker.report(1, &digests(&[("mqtt_protocol", 10), ("mqtt_limit", 20)]));
+        tracker.report(2, &digests(&[("mqtt_protocol", 10), ("mqtt_limit", 20)]));
+        assert!(tracker.drifts().is_empty());
+
+        tracker.report(2, &digests(&[("mqtt_protocol", 11), ("mqtt_limit", 20)]));
+        let drifts = tracker.drifts();
+        assert_eq!(drifts.len(), 1);
+        assert_eq!(drifts[0].section, "mqtt_protocol");
+        assert_eq!(drifts[0].digests, HashMap::from([(1, 10), (2, 11)]));
+
+        // A changed digest does not reset the time the drift started.
+        let since = drifts[0].since;
+        tracker.report(1, &digests(&[("mqtt_protocol", 12), ("mqtt_limit", 20)]));
+        assert_eq!(tracker.drifts()[0].since, since);
+
+        // Nodes without digests are ignored.
+        tracker.report(3, &HashMap::new());
+        assert_eq!(tracker.drifts()[0].digests.len(), 2);
+
+        tracker.remove_node(2);
+        assert!(tracker.drifts().is_empty());
+    }
+}
@@ -0,0 +1,310 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Shard assignment for consumer groups of storage topics.
+//!
+//! Members join a group with their first heartbeat and are dropped when they
+//! leave or stop heartbeating for longer than the session timeout. Whenever
+//! the members or the shards they consume change, the generation is bumped
+//! and every shard gets a target member, balancing the shard count per
+//! member. A shard is only handed to its target once the previous owner has
+//! stopped reporting it as owned, so two members never read it at once.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, RwLock};
+
+pub const CONSUMER_GROUP_SESSION_TIMEOUT_MS: u64 = 30_000;
+
+#[derive(Debug, Clone)]
+struct GroupMember {
+    broker_id: u64,
+    shards: BTreeSet<String>,
+    owned: BTreeSet<String>,
+    last_heartbeat_ms: u64,
+}
+
+#[derive(Debug, Default)]
+struct GroupState {
+    generation: u64,
+    // (member_id, GroupMember)
+    members: BTreeMap<String, GroupMember>,
+    // (shard_name, member_id)
+    target: HashMap<String, String>,
+}
+
+impl GroupState {
+    fn expire_members(&mut self, now_ms: u64, session_timeout_ms: u64) -> bool {
+        let before = self.members.len();
+        self.members
+            .retain(|_, m| now_ms.saturating_sub(m.last_heartbeat_ms) <= session_timeout_ms);
+        self.members.len() != before
+    }
+
+    fn rebalance(&mut self) {
+        let mut load: HashMap<&str, usize> = self
+            .members
+            .keys()
+            .map(|member_id| (member_id.as_str(), 0))
+            .collect();
+        let shards: BTreeSet<&String> = self.members.values().flat_map(|m| &m.shards).collect();
+
+        let mut target = HashMap::with_capacity(shards.len());
+        for shard in shards {
+            let Some(member_id) = self
+                .members
+                .iter()
+                .filter(|(_, m)| m.shards.contains(shard))
+                .map(|(member_id, _)| member_id.as_str())
+                .min_by_key(|member_id| (load[member_id], *member_id))
+            else {
+                continue;
+            };
+            *load.get_mut(member_id).unwrap() += 1;
+            target.insert(shard.clone(), member_id.to_string());
+        }
+
+        self.target = target;
+        self.generation += 1;
+    }
+
+    fn owned_by_other(&self, shard: &str, member_id: &str) -> bool {
+        self.members
+            .iter()
+            .any(|(id, m)| id != member_id && m.owned.contains(shard))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GroupAssignment {
+    pub generation: u64,
+    /// Shards the member may read now.
+    pub assigned: Vec<String>,
+    /// Owned shards the member must commit and release.
+    pub revoked: Vec<String>,
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct ConsumerGroupCoordinator {
+    // ((tenant, group), GroupState)
+    groups: Arc<RwLock<HashMap<(String, String), GroupState>>>,
+}
+
+impl ConsumerGroupCoordinator {
+    #[allow(clippy::too_many_arguments)]
+    pub fn heartbeat(
+        &self,
+        tenant: &str,
+        group: &str,
+        member_id: &str,
+        broker_id: u64,
+        shards: &[String],
+        owned: &[String],
+        now_ms: u64,
+    ) -> GroupAssignment {
+        let mut groups = self.groups.write().unwrap();
+        let state = groups
+            .entry((tenant.to_string(), group.to_string()))
+            .or_default();
+
+        let mut changed = state.expire_members(now_ms, CONSUMER_GROUP_SESSION_TIMEOUT_MS);
+        let shards: BTreeSet<String> = shards.iter().cloned().collect();
+        match state.members.get_mut(member_id) {
+            Some(member) => {
+                if member.shards != shards {
+                    member.shards = shards;
+                    changed = true;
+                }
+                member.owned = owned.iter().cloned().collect();
+                member.last_heartbeat_ms = now_ms;
+            }
+            None => {
+                state.members.insert(
+                    member_id.to_string(),
+                    GroupMember {
+                        broker_id,
+                        shards,
+                        owned: owned.iter().cloned().collect(),
+                        last_heartbeat_ms: now_ms,
+                    },
+                );
+                changed = true;
+            }
+        }
+        if changed {
+            state.rebalance();
+        }
+
+        let member = &state.members[member_id];
+        let assigned = state
+            .target
+            .iter()
+            .filter(|(shard, target)| {
+                target.as_str() == member_id && !state.owned_by_other(shard, member_id)
+            })
+            .map(|(shard, _)| shard.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let revoked = member
+            .owned
+            .iter()
+            .filter(|shard| state.target.get(*shard).map(String::as_str) != Some(member_id))
+            .cloned()
+            .collect();
+
+        GroupAssignment {
+            generation: state.generation,
+            assigned,
+            revoked,
+        }
+    }
+
+    /// Remove the member so its shards are reassigned right away instead of
+    /// after the session timeout.
+    pub fn leave(&self, tenant: &str, group: &str, member_id: &str) {
+        let mut groups = self.groups.write().unwrap();
+        let key = (tenant.to_string(), group.to_string());
+        let Some(state) = groups.get_mut(&key) else {
+            return;
+        };
+        if state.members.remove(member_id).is_none() {
+            return;
+        }
+        if state.members.is_empty() {
+            groups.remove(&key);
+        } else {
+            state.rebalance();
+        }
+    }
+
+    /// Drop every member running on `broker_id`.
+    pub fn remove_node(&self, broker_id: u64) {
+        let mut groups = self.groups.write().unwrap();
+        groups.retain(|_, state| {
+            let before = state.members.len();
+            state.members.retain(|_, m| m.broker_id != broker_id);
+            if state.members.len() != before && !state.members.is_empty() {
+                state.rebalance();
+            }
+            !state.members.is_empty()
+        });
+    }
+
+    /// Live members of the group with the shards targeted to each.
+    pub fn members(&self, tenant: &str, group: &str) -> BTreeMap<String, Vec<String>> {
+        let groups = self.groups.read().unwrap();
+        let Some(state) = groups.get(&(tenant.to_string(), group.to_string())) else {
+            return BTreeMap::new();
+        };
+        let mut members: BTreeMap<String, Vec<String>> = state
+            .members
+            .keys()
+            .map(|member_id| (member_id.clone(), Vec::new()))
+            .collect();
+        for (shard, member_id) in &state.target {
+            if let Some(shards) = members.get_mut(member_id) {
+                shards.push(shard.clone());
+            }
+        }
+        for shards in members.values_mut() {
+            shards.sort();
+        }
+        members
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shards(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_single_member_gets_all_shards() {
+        let coordinator = ConsumerGroupCoordinator::default();
+        let all = shards(&["s0", "s1", "s2"]);
+        let result = coordinator.heartbeat("t1", "g1", "m1", 1, &all, &[], 0);
+        assert_eq!(result.generation, 1);
+        assert_eq!(result.assigned, all);
+        assert!(result.revoked.is_empty());
+
+        // Unchanged membership keeps the generation.
+        let result = coordinator.heartbeat("t1", "g1", "m1", 1, &all, &all, 10);
+        assert_eq!(result.generation, 1);
+        assert_eq!(result.assigned, all);
+    }
+
+    #[test]
+    fn test_cooperative_hand_off() {
+        let coordinator = ConsumerGroupCoordinator::default();
+        let all = shards(&["s0", "s1", "s2", "s3"]);
+        coordinator.heartbeat("t1", "g1", "m1", 1, &all, &[], 0);
+        coordinator.heartbeat("t1", "g1", "m1", 1, &all, &all, 5);
+
+        // m2 joins: its shards are still owned by m1, so nothing is assigned yet.
+        let result = coordinator.heartbeat("t1", "g1", "m2", 2, &all, &[], 10);
+        assert_eq!(result.generation, 2);
+        assert!(result.assigned.is_empty());
+
+        // m1 is told to release half of its shards.
+        let result = coordinator.heartbeat("t1", "g1", "m1", 1, &all, &all, 20);
+        assert_eq!(result.assigned, shards(&["s0", "s2"]));
+        assert_eq!(result.revoked, shards(&["s1", "s3"]));
+
+        // Once m1 reports them released, m2 picks them up.
+        coordinator.heartbeat("t1", "g1", "m1", 1, &all, &shards(&["s0", "s2"]), 30);
+        let result = coordinator.heartbeat("t1", "g1", "m2", 2, &all, &[], 40);
+        assert_eq!(result.assigned, shards(&["s1", "s3"]));
+    }
+
+    #[test]
+    fn test_expired_and_leaving_members() {
+        let coordinator = ConsumerGroupCoordinator::default();
+        let all = shards(&["s0", "s1"]);
+        coordinator.heartbeat("t1", "g1", "m1", 1, &all, &[], 0);
+        coordinator.heartbeat("t1", "g1", "m2", 2, &all, &[], 0);
+        assert_eq!(coordinator.members("t1", "g1").len(), 2);
+
+        // m2 stops heartbeating; m1 takes over its shard after the timeout.
+        let result = coordinator.heartbeat(
+            "t1",
+            "g1",
+            "m1",
+            1,
+            &all,
+            &shards(&["s0"]),
+            CONSUMER_GROUP_SESSION_TIMEOUT_MS + 1,
+        );
+        assert_eq!(result.assigned, all);
+        assert_eq!(coordinator.members("t1", "g1").len(), 1);
+
+        coordinator.leave("t1", "g1", "m1");
+        assert!(coordinator.members("t1", "g1").is_empty());
+    }
+
+    #[test]
+    fn test_remove_node() {
+        let coordinator = ConsumerGroupCoordinator::default();
+        let all = shards(&["s0", "s1"]);
+        coordinator.heartbeat("t1", "g1", "m1", 1, &all, &[], 0);
+        coordinator.heartbeat("t1", "g1", "m2", 2, &all, &[], 0);
+
+        coordinator.remove_node(2);
+        let members = coordinator.members("t1", "g1");
+        assert_eq!(members.len(), 1);
+        assert_eq!(members["m1"], all);
+    }
+}
@@ -15,6 +15,7 @@
 use super::heartbeat::BrokerHeartbeat;
 use crate::core::cache::MetaCacheManager;
 use crate::raft::manager::MultiRaftManager;
+use broker_core::cache::NodeCacheManager;
 use common_base::error::ResultCommonError;
 use common_base::tools::loop_select_ticket;
 use common_config::broker::broker_config;
@@ -28,6 +29,7 @@ pub struct ClusterController {
     raft_manager: Arc<MultiRaftManager>,
     node_call_manager: Arc<NodeCallManager>,
     rocksdb_engine_handler: Arc<RocksDBEngine>,
+    node_cache: Arc<NodeCacheManager>,
 }
 
 impl ClusterController {
@@ -36,12 +38,14 @@ pub fn new(
         raft_manager: Arc<MultiRaftManager>,
         node_call_manager: Arc<NodeCallManager>,
         rocksdb_engine_handler: Arc<RocksDBEngine>,
+        node_cache: Arc<NodeCacheManager>,
     ) -> ClusterController {
         ClusterController {
             cluster_cache,
             raft_manager,
             node_call_manager,
             rocksdb_engine_handler,
+            node_cache,
         }
     }
 
@@ -53,6 +57,7 @@ pub async fn start_node_heartbeat_check(&self, stop_send: &broadcast::Sender<boo
             self.raft_manager.clone(),
             self.node_call_manager.clone(),
             self.rocksdb_engine_handler.clone(),
+            self.node_cache.clone(),
         );
 
         let ac_fn = async || -> ResultCommonError {
@@ -108,6 +108,9 @@ pub enum MetaServiceError {
     )]
     NotEnoughEngineNodes(String, u32, u32),
 
+    #[error("{0}, replicas must span {1} zones, but the available engine nodes cover {2}.")]
+    NotEnoughZones(String, u32, u32),
+
     #[error("Execution result is empty, please check whether the server logic is normal")]
     ExecutionResultIsEmpty,
 
@@ -123,6 +126,9 @@ pub enum MetaServiceError {
     #[error("Request parameters [{0}] cannot be null")]
     RequestParamsNotEmpty(String),
 
+    #[error("Invalid list filter: {0}")]
+    InvalidListFilter(String),
+
     #[error("Session {0} does not exist")]
     SessionDoesNotExist(String),
 
@@ -144,6 +150,9 @@ pub enum MetaServiceError {
     #[error("Connector [{0}] already exist")]
     ConnectorAlreadyExist(String),
 
+    #[error("Checkpoint of connector [{0}] rejected: {1}")]
+    ConnectorCheckpointRejected(String, String),
+
     #[error("User [{0}] already exist")]
     UserAlreadyExist(String),
 
@@ -162,6 +171,18 @@ pub enum MetaServiceError {
     #[error("Schema [{0}] already exist")]
     SchemaAlreadyExist(String),
 
+    #[error("Consumer group [{0}] has no committed offsets")]
+    OffsetGroupDoesNotExist(String),
+
+    #[error("Consumer group [{0}] still has {1} active members, stop them first")]
+    OffsetGroupHasActiveMembers(String, usize),
+
+    #[error("Metadata dump is not compatible with this cluster: {0}")]
+    MetadataDumpIncompatible(String),
+
+    #[error("Metadata dump failed validation: {0}")]
+    MetadataDumpInvalid(String),
+
     #[error("{0} has raft stopped")]
     RaftNodeHasStopped(String),
 
@@ -180,4 +201,7 @@ pub enum MetaServiceError {
 
     #[error("UpdateSegmentIsr on {0}/{1}: invalid new_isr {2:?} ({3})")]
     InvalidIsr(String, u32, Vec<u64>, String),
+
+    #[error("Watch revision [{0}] is no longer available, list the resources and watch again")]
+    WatchRevisionExpired(String),
 }
@@ -16,12 +16,14 @@
 use crate::core::notify::send_notify_by_set_share_group;
 use crate::raft::manager::MultiRaftManager;
 use crate::raft::route::data::{StorageData, StorageDataType};
+use crate::storage::mqtt::subscribe::MqttSubscribeStorage;
 use crate::{core::cache::MetaCacheManager, storage::common::share_group::ShareGroupStorage};
 use bytes::Bytes;
+use metadata_struct::mqtt::share_group::ShareGroup;
 use node_call::NodeCallManager;
 use rocksdb_engine::rocksdb::RocksDBEngine;
 use std::{collections::HashMap, sync::Arc};
-use tracing::info;
+use tracing::{info, warn};
 
 pub async fn get_group_leader(
     _raft_manager: &Arc<MultiRaftManager>,
@@ -53,39 +55,189 @@ pub async fn generate_group_leader(
     rocksdb_engine_handler: &Arc<RocksDBEngine>,
     tenant: &str,
 ) -> Result<u64, MetaServiceError> {
-    let storage = ShareGroupStorage::new(rocksdb_engine_handler.clone());
-    let list = storage.list_by_tenant(tenant)?;
-    let broker_ids: Vec<u64> = cache_manager
-        .node_list
+    let leader_count_by_broker =
+        leader_count_by_broker(cache_manager, rocksdb_engine_handler, tenant)?;
+    leader_count_by_broker
         .iter()
-        .map(|node| node.node_id)
+        .min_by_key(|(broker_id, count)| (**count, **broker_id))
+        .map(|(broker_id, _)| *broker_id)
+        .ok_or(MetaServiceError::NoAvailableBrokerNode)
+}
+
+/// Pick the broker that takes over a share group whose leader is gone.
+/// Brokers that already host members of the group win, so the push thread
+/// starts next to its subscribers; ties go to the least-loaded broker.
+pub async fn generate_failover_group_leader(
+    cache_manager: &Arc<MetaCacheManager>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    group: &ShareGroup,
+    member_count_by_broker: &HashMap<u64, u64>,
+) -> Result<u64, MetaServiceError> {
+    let leader_count_by_broker =
+        leader_count_by_broker(cache_manager, rocksdb_engine_handler, &group.tenant)?;
+
+    leader_count_by_broker
+        .iter()
+        .filter(|(broker_id, _)| **broker_id != group.leader_broker)
+        .min_by_key(|(broker_id, count)| {
+            let members = member_count_by_broker.get(broker_id).copied().unwrap_or(0);
+            (std::cmp::Reverse(members), **count, **broker_id)
+        })
+        .map(|(broker_id, _)| *broker_id)
+        .ok_or(MetaServiceError::NoAvailableBrokerNode)
+}
+
+type GroupKey = (String, String);
+
+/// Subscribers of each of `groups` on each broker: NATS/mq9 queue members
+/// plus MQTT `$share/{group}/{topic}` subscriptions, whose group name is the
+/// path without the `$share/` prefix. Both tables are scanned once for the
+/// whole batch.
+fn member_count_by_group(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    groups: &[ShareGroup],
+) -> Result<HashMap<GroupKey, HashMap<u64, u64>>, MetaServiceError> {
+    let mut member_count_by_group: HashMap<GroupKey, HashMap<u64, u64>> = groups
+        .iter()
+        .map(|group| {
+            (
+                (group.tenant.clone(), group.group_name.clone()),
+                HashMap::new(),
+            )
+        })
         .collect();
 
-    if broker_ids.is_empty() {
-        return Err(MetaServiceError::NoAvailableBrokerNode);
+    let storage = ShareGroupStorage::new(rocksdb_engine_handler.clone());
+    for member in storage.list_all_members()? {
+        if let Some(counts) = member_count_by_group.get_mut(&(member.tenant, member.group_name)) {
+            *counts.entry(member.broker_id).or_default() += 1;
+        }
     }
 
-    let mut leader_count_by_broker = HashMap::new();
-    for broker_id in &broker_ids {
-        leader_count_by_broker.insert(*broker_id, 0_u64);
+    let storage = MqttSubscribeStorage::new(rocksdb_engine_handler.clone());
+    for subscribe in storage.list_all()? {
+        let Some(group_name) = subscribe.path.strip_prefix("$share/") else {
+            continue;
+        };
+        let key = (subscribe.tenant, group_name.to_string());
+        if let Some(counts) = member_count_by_group.get_mut(&key) {
+            *counts.entry(subscribe.broker_id).or_default() += 1;
+        }
     }
+    Ok(member_count_by_group)
+}
+
+/// Leaders held by each live broker within a tenant.
+fn leader_count_by_broker(
+    cache_manager: &Arc<MetaCacheManager>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    tenant: &str,
+) -> Result<HashMap<u64, u64>, MetaServiceError> {
+    let mut leader_count_by_broker: HashMap<u64, u64> = cache_manager
+        .node_list
+        .iter()
+        .map(|node| (node.node_id, 0))
+        .collect();
 
-    for leader in list.values() {
+    let storage = ShareGroupStorage::new(rocksdb_engine_handler.clone());
+    for leader in storage.list_by_tenant(tenant)?.values() {
         if let Some(count) = leader_count_by_broker.get_mut(&leader.leader_broker) {
             *count += 1;
         }
     }
+    Ok(leader_count_by_broker)
+}
 
-    let target_broker_id = broker_ids
-        .iter()
-        .min_by_key(|broker_id| {
-            let count = leader_count_by_broker.get(broker_id).copied().unwrap_or(0);
-            (count, **broker_id)
-        })
-        .copied()
-        .ok_or(MetaServiceError::NoAvailableBrokerNode)?;
+/// Move a share group to a live broker and notify every broker, so the new
+/// leader starts pushing from the group's committed offset. A group whose
+/// leader is already alive again is left where it is.
+async fn reassign_group_leader(
+    meta_cache: &Arc<MetaCacheManager>,
+    raft_manager: &Arc<MultiRaftManager>,
+    call_manager: &Arc<NodeCallManager>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    member_count_by_broker: &HashMap<u64, u64>,
+    mut group_leader: ShareGroup,
+) -> Result<u64, MetaServiceError> {
+    if let Some(current) =
+        meta_cache.get_group_leader(&group_leader.tenant, &group_leader.group_name)
+    {
+        if meta_cache.node_list.contains_key(&current.leader_broker) {
+            return Ok(current.leader_broker);
+        }
+    }
 
-    Ok(target_broker_id)
+    let new_leader_broker = generate_failover_group_leader(
+        meta_cache,
+        rocksdb_engine_handler,
+        &group_leader,
+        member_count_by_broker,
+    )
+    .await?;
+    let old_leader_broker = group_leader.leader_broker;
+    group_leader.leader_broker = new_leader_broker;
+
+    let data = StorageData::new(
+        StorageDataType::MqttSetGroupLeader,
+        Bytes::copy_from_slice(&group_leader.encode()?),
+    );
+    raft_manager
+        .write_data(&group_leader.group_name, data)
+        .await?;
+    send_notify_by_set_share_group(call_manager, group_leader.clone()).await?;
+
+    info!(
+        "Share group {}/{} leader moved from broker {} to broker {}",
+        group_leader.tenant, group_leader.group_name, old_leader_broker, new_leader_broker
+    );
+    Ok(new_leader_broker)
+}
+
+async fn reassign_group_leaders(
+    meta_cache: &Arc<MetaCacheManager>,
+    raft_manager: &Arc<MultiRaftManager>,
+    call_manager: &Arc<NodeCallManager>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    groups: Vec<ShareGroup>,
+) -> (u32, u32) {
+    // Without member counts the groups still move, to the least-loaded brokers.
+    let member_count_by_group = member_count_by_group(rocksdb_engine_handler, &groups)
+        .unwrap_or_else(|e| {
+            warn!("Failed to count share group members by broker: {}", e);
+            HashMap::new()
+        });
+    let no_members = HashMap::new();
+
+    let mut switched = 0u32;
+    let mut failed = 0u32;
+    for group_leader in groups {
+        let tenant = group_leader.tenant.clone();
+        let group_name = group_leader.group_name.clone();
+        let member_count_by_broker = member_count_by_group
+            .get(&(tenant.clone(), group_name.clone()))
+            .unwrap_or(&no_members);
+        // One failing group must not leave the rest without a leader.
+        match reassign_group_leader(
+            meta_cache,
+            raft_manager,
+            call_manager,
+            rocksdb_engine_handler,
+            member_count_by_broker,
+            group_leader,
+        )
+        .await
+        {
+            Ok(_) => switched += 1,
+            Err(e) => {
+                failed += 1;
+                warn!(
+                    "Failed to switch the leader of share group {}/{}: {}",
+                    tenant, group_name, e
+                );
+            }
+        }
+    }
+    (switched, failed)
 }
 
 pub async fn group_leader_switch(
@@ -102,34 +254,70 @@ pub async fn group_leader_switch(
         .map(|g| g.clone())
         .collect();
 
-    let mut switched = 0u32;
-    for mut group_leader in affected {
-        let new_leader_broker =
-            generate_group_leader(meta_cache, rocksdb_engine_handler, &group_leader.tenant).await?;
-        group_leader.leader_broker = new_leader_broker;
-
-        let data = StorageData::new(
-            StorageDataType::MqttSetGroupLeader,
-            Bytes::copy_from_slice(&group_leader.encode()?),
-        );
-        raft_manager
-            .write_data(&group_leader.group_name, data)
-            .await?;
-        send_notify_by_set_share_group(call_manager, group_leader).await?;
-        switched += 1;
-    }
+    let (switched, failed) = reassign_group_leaders(
+        meta_cache,
+        raft_manager,
+        call_manager,
+        rocksdb_engine_handler,
+        affected,
+    )
+    .await;
     info!(
-        "group_leader_switch completed, node {} removed, {} group leaders switched",
-        remove_id, switched
+        "group_leader_switch completed, node {} removed, {} group leaders switched, {} failed",
+        remove_id, switched, failed
     );
     Ok(())
 }
 
+/// Share groups whose leader is not a live broker, e.g. because an earlier
+/// switch failed or the meta leader changed halfway through one.
+pub fn orphaned_group_leaders(meta_cache: &Arc<MetaCacheManager>) -> Vec<ShareGroup> {
+    meta_cache
+        .group_leader
+        .iter()
+        .filter(|g| !meta_cache.node_list.contains_key(&g.leader_broker))
+        .map(|g| g.clone())
+        .collect()
+}
+
+/// Reassign every orphaned share group. Runs with the heartbeat check so a
+/// group never stays without a pushing broker after a failover went wrong.
+pub async fn check_group_leader_health(
+    meta_cache: &Arc<MetaCacheManager>,
+    raft_manager: &Arc<MultiRaftManager>,
+    call_manager: &Arc<NodeCallManager>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+) {
+    if meta_cache.node_list.is_empty() {
+        return;
+    }
+
+    let orphaned = orphaned_group_leaders(meta_cache);
+    if orphaned.is_empty() {
+        return;
+    }
+
+    let (switched, failed) = reassign_group_leaders(
+        meta_cache,
+        raft_manager,
+        call_manager,
+        rocksdb_engine_handler,
+        orphaned,
+    )
+    .await;
+    info!(
+        "Share group leader health check reassigned {} orphaned groups, {} failed",
+        switched, failed
+    );
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use common_config::broker::{default_broker_config, init_broker_conf_by_config};
     use metadata_struct::meta::node::BrokerNode;
+    use metadata_struct::mqtt::share_group::ShareGroupMember;
+    use metadata_struct::mqtt::subscribe::MqttSubscribe;
     use rocksdb_engine::test::test_rocksdb_instance;
 
     fn setup() -> (Arc<MetaCacheManager>, Arc<RocksDBEngine>) {
@@ -158,7 +346,6 @@ async fn test_generate_group_leader_choose_least_loaded_broker() {
 
         let tenant = "test_tenant";
         let storage = ShareGroupStorage::new(rocksdb_engine_handler.clone());
-        use metadata_struct::mqtt::share_group::ShareGroup;
         storage
             .save(ShareGroup {
                 tenant: tenant.to_string(),
@@ -189,4 +376,114 @@ async fn test_generate_group_leader_choose_least_loaded_broker() {
             .unwrap();
         assert_eq!(target, 3);
     }
+
+    #[tokio::test]
+    async fn test_failover_group_leader_prefers_member_broker() {
+        let (cache_manager, rocksdb_engine_handler) = setup();
+        for node_id in [2, 3] {
+            cache_manager.add_broker_node(BrokerNode {
+                node_id,
+                ..Default::default()
+            });
+        }
+
+        let tenant = "test_tenant";
+        let storage = ShareGroupStorage::new(rocksdb_engine_handler.clone());
+        let group = ShareGroup {
+            tenant: tenant.to_string(),
+            group_name: "g1/a".to_string(),
+            leader_broker: 1,
+            ..Default::default()
+        };
+        storage.save(group.clone()).unwrap();
+        storage
+            .save(ShareGroup {
+                tenant: tenant.to_string(),
+                group_name: "g2".to_string(),
+                leader_broker: 3,
+                ..Default::default()
+            })
+            .unwrap();
+
+        // Without members the least-loaded live broker takes over.
+        let target = failover(&cache_manager, &rocksdb_engine_handler, &group).await;
+        assert_eq!(target, 2);
+
+        storage
+            .save_member(&ShareGroupMember {
+                tenant: tenant.to_string(),
+                group_name: "g1/a".to_string(),
+                broker_id: 3,
+                connect_id: 1,
+                sid: "s1".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let target = failover(&cache_manager, &rocksdb_engine_handler, &group).await;
+        assert_eq!(target, 3);
+
+        let subscribe_storage = MqttSubscribeStorage::new(rocksdb_engine_handler.clone());
+        for client_id in ["c1", "c2"] {
+            subscribe_storage
+                .save(
+                    client_id,
+                    "$share/g1/a",
+                    MqttSubscribe {
+                        tenant: tenant.to_string(),
+                        client_id: client_id.to_string(),
+                        path: "$share/g1/a".to_string(),
+                        broker_id: 2,
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+        }
+        let target = failover(&cache_manager, &rocksdb_engine_handler, &group).await;
+        assert_eq!(target, 2);
+    }
+
+    async fn failover(
+        cache_manager: &Arc<MetaCacheManager>,
+        rocksdb_engine_handler: &Arc<RocksDBEngine>,
+        group: &ShareGroup,
+    ) -> u64 {
+        let mut member_count_by_group =
+            member_count_by_group(rocksdb_engine_handler, std::slice::from_ref(group)).unwrap();
+        let member_count_by_broker = member_count_by_group
+            .remove(&(group.tenant.clone(), group.group_name.clone()))
+            .unwrap();
+        generate_failover_group_leader(
+            cache_manager,
+            rocksdb_engine_handler,
+            group,
+            &member_count_by_broker,
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_orphaned_group_leaders() {
+        let (cache_manager, _) = setup();
+        cache_manager.add_broker_node(BrokerNode {
+            node_id: 1,
+            ..Default::default()
+        });
+        cache_manager.add_group_leader(ShareGroup {
+            tenant: "t".to_string(),
+            group_name: "alive".to_string(),
+            leader_broker: 1,
+            ..Default::default()
+        });
+        cache_manager.add_group_leader(ShareGroup {
+            tenant: "t".to_string(),
+            group_name: "orphaned".to_string(),
+            leader_broker: 2,
+            ..Default::default()
+        });
+
+        let orphaned = orphaned_group_leaders(&cache_manager);
+        assert_eq!(orphaned.len(), 1);
+        assert_eq!(orphaned[0].group_name, "orphaned");
+    }
 }
@@ -12,14 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::core::group_leader::check_group_leader_health;
+use crate::core::session_takeover::takeover_broker_sessions;
 use crate::core::{cache::MetaCacheManager, cluster::remove_node};
 use crate::raft::manager::MultiRaftManager;
+use broker_core::cache::NodeCacheManager;
 use common_base::tools::now_second;
 use node_call::NodeCallManager;
 use rocksdb_engine::rocksdb::RocksDBEngine;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 #[derive(Clone, Default, Debug, Serialize, Deserialize)]
 pub struct NodeHeartbeatData {
@@ -33,6 +36,7 @@ pub struct BrokerHeartbeat {
     raft_manager: Arc<MultiRaftManager>,
     node_call_manager: Arc<NodeCallManager>,
     rocksdb_engine_handler: Arc<RocksDBEngine>,
+    node_cache: Arc<NodeCacheManager>,
 }
 
 impl BrokerHeartbeat {
@@ -42,6 +46,7 @@ pub fn new(
         raft_manager: Arc<MultiRaftManager>,
         node_call_manager: Arc<NodeCallManager>,
         rocksdb_engine_handler: Arc<RocksDBEngine>,
+        node_cache: Arc<NodeCacheManager>,
     ) -> Self {
         BrokerHeartbeat {
             timeout_ms,
@@ -49,10 +54,21 @@ pub fn new(
             raft_manager,
             node_call_manager,
             rocksdb_engine_handler,
+            node_cache,
         }
     }
 
     pub async fn start(&self) {
+        // Before expiring nodes, so groups of a node removed in this round are
+        // left to its own leader switch.
+        check_group_leader_health(
+            &self.cluster_cache,
+            &self.raft_manager,
+            &self.node_call_manager,
+            &self.rocksdb_engine_handler,
+        )
+        .await;
+
         let actions = self.collect_expired_nodes();
         self.process_expired_nodes(actions).await;
     }
@@ -123,6 +139,22 @@ async fn process_expired_nodes(&self, actions: Vec<NodeAction>) {
                     action.node_id, action.node_ip, action.now_time, action.report_time,
                     (action.now_time - action.report_time), self.timeout_ms
                 );
+
+                // The failed broker cannot clean up after its clients itself.
+                if let Err(e) = takeover_broker_sessions(
+                    &self.raft_manager,
+                    &self.node_call_manager,
+                    &self.rocksdb_engine_handler,
+                    &self.node_cache,
+                    action.node_id,
+                )
+                .await
+                {
+                    warn!(
+                        "Failed to take over the sessions of timed out node {}, error message: {}",
+                        action.node_id, e
+                    );
+                }
             }
         }
     }
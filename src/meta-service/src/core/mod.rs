@@ -14,8 +14,12 @@
 
 pub mod cache;
 pub mod cache_engine;
+pub mod cache_list;
 pub mod cache_mqtt;
+pub mod cache_version;
 pub mod cluster;
+pub mod config_drift;
+pub mod consumer_group;
 pub mod controller;
 pub mod error;
 pub mod group_leader;
@@ -28,4 +32,8 @@
 pub mod segment_leader;
 pub mod segment_meta;
 pub mod segment_replica;
+pub mod session_takeover;
 pub mod shard;
+pub mod subscribe_route;
+pub mod topic_stats;
+pub mod watch;
@@ -22,6 +22,7 @@
 use metadata_struct::mq9::agent::MQ9Agent;
 use metadata_struct::mq9::mail::MQ9Mail;
 use metadata_struct::mqtt::auto_subscribe::MqttAutoSubscribeRule;
+use metadata_struct::mqtt::message_rule::MqttMessageRule;
 use metadata_struct::mqtt::session::MqttSession;
 use metadata_struct::mqtt::share_group::ShareGroup;
 use metadata_struct::mqtt::share_group::ShareGroupMember;
@@ -470,6 +471,33 @@ pub async fn send_notify_by_delete_auto_subscribe_rule(
     .await
 }
 
+// MQTT Message Rule
+pub async fn send_notify_by_create_message_rule(
+    call_manager: &Arc<NodeCallManager>,
+    rule: MqttMessageRule,
+) -> Result<(), MetaServiceError> {
+    send_update_cache(
+        call_manager,
+        BrokerUpdateCacheActionType::Create,
+        BrokerUpdateCacheResourceType::MessageRule,
+        rule.encode()?,
+    )
+    .await
+}
+
+pub async fn send_notify_by_delete_message_rule(
+    call_manager: &Arc<NodeCallManager>,
+    rule: MqttMessageRule,
+) -> Result<(), MetaServiceError> {
+    send_update_cache(
+        call_manager,
+        BrokerUpdateCacheActionType::Delete,
+        BrokerUpdateCacheResourceType::MessageRule,
+        rule.encode()?,
+    )
+    .await
+}
+
 // MQTT Topic Rewrite Rule
 pub async fn send_notify_by_create_topic_rewrite_rule(
     call_manager: &Arc<NodeCallManager>,
@@ -508,6 +536,7 @@ async fn send_update_cache(
         action_type,
         resource_type,
         data,
+        version: 0,
     });
     call_manager.send(data).await?;
     Ok(())
@@ -148,6 +148,8 @@ pub async fn update_segment_status(
             );
 
             segment.status = status;
+            // Writers holding the old epoch are fenced once brokers see it.
+            segment.segment_epoch += 1;
         }
     }
 
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use crate::{
@@ -57,12 +58,14 @@ pub async fn segment_leader_switch(
     // set of inactive segments.
     let (active, inactive): (Vec<_>, Vec<_>) =
         affected.into_iter().partition(|seg| seg.allow_write());
+    let cordoned = Arc::new(meta_cache.cordoned_node_ids());
 
     let active_task = tokio::spawn(active_segment_leader_switch(
         raft_manager.clone(),
         call_manager.clone(),
         rocksdb_engine_handler.clone(),
         remove_id,
+        cordoned.clone(),
         active,
     ));
     let inactive_task = tokio::spawn(inactive_segment_leader_switch(
@@ -70,6 +73,7 @@ pub async fn segment_leader_switch(
         call_manager.clone(),
         rocksdb_engine_handler.clone(),
         remove_id,
+        cordoned,
         inactive,
     ));
 
@@ -84,6 +88,7 @@ async fn active_segment_leader_switch(
     call_manager: Arc<NodeCallManager>,
     rocksdb_engine_handler: Arc<RocksDBEngine>,
     remove_id: u64,
+    cordoned: Arc<HashSet<u64>>,
     segments: Vec<EngineSegment>,
 ) -> Result<(), MetaServiceError> {
     switch_segments_concurrently(
@@ -92,6 +97,7 @@ async fn active_segment_leader_switch(
         call_manager,
         rocksdb_engine_handler,
         remove_id,
+        cordoned,
         segments,
     )
     .await
@@ -102,6 +108,7 @@ async fn inactive_segment_leader_switch(
     call_manager: Arc<NodeCallManager>,
     rocksdb_engine_handler: Arc<RocksDBEngine>,
     remove_id: u64,
+    cordoned: Arc<HashSet<u64>>,
     segments: Vec<EngineSegment>,
 ) -> Result<(), MetaServiceError> {
     switch_segments_concurrently(
@@ -110,6 +117,7 @@ async fn inactive_segment_leader_switch(
         call_manager,
         rocksdb_engine_handler,
         remove_id,
+        cordoned,
         segments,
     )
     .await
@@ -121,6 +129,7 @@ async fn switch_segments_concurrently(
     call_manager: Arc<NodeCallManager>,
     rocksdb_engine_handler: Arc<RocksDBEngine>,
     remove_id: u64,
+    cordoned: Arc<HashSet<u64>>,
     segments: Vec<EngineSegment>,
 ) -> Result<(), MetaServiceError> {
     let mut handles = Vec::new();
@@ -129,12 +138,14 @@ async fn switch_segments_concurrently(
         let raft_manager = raft_manager.clone();
         let call_manager = call_manager.clone();
         let rocksdb_engine_handler = rocksdb_engine_handler.clone();
+        let cordoned = cordoned.clone();
         handles.push(tokio::spawn(async move {
             switch_segment_chunk(
                 &raft_manager,
                 &call_manager,
                 &rocksdb_engine_handler,
                 remove_id,
+                &cordoned,
                 chunk,
             )
             .await
@@ -175,6 +186,7 @@ async fn switch_segment_chunk(
     call_manager: &Arc<NodeCallManager>,
     rocksdb_engine_handler: &Arc<RocksDBEngine>,
     remove_id: u64,
+    cordoned: &HashSet<u64>,
     segments: Vec<EngineSegment>,
 ) -> Result<(u32, u32), MetaServiceError> {
     let node_storage = NodeStorage::new(rocksdb_engine_handler.clone());
@@ -182,13 +194,16 @@ async fn switch_segment_chunk(
     let mut switched = 0u32;
     let mut unavailable = 0u32;
     for segment in segments {
-        let new_leader = segment.isr.iter().copied().find(|id| *id != remove_id);
-        let new_leader_broker_epoch = match new_leader {
+        let new_leader_broker_epoch = match elect_leader(&segment, remove_id, cordoned) {
             Some(id) => node_storage.get_broker_epoch(id)?,
             None => 0,
         };
-        let new_segment =
-            compute_segment_after_leader_failure(&segment, remove_id, new_leader_broker_epoch);
+        let new_segment = compute_segment_after_leader_failure(
+            &segment,
+            remove_id,
+            cordoned,
+            new_leader_broker_epoch,
+        );
 
         if new_segment.status == SegmentStatus::Unavailable {
             warn!(
@@ -236,17 +251,26 @@ fn join_result(
     }
 }
 
-/// Decide a segment's new state after its leader `remove_id` fails. Elects the
-/// first surviving ISR member (never a replica outside ISR, which could be
-/// missing committed data). With no surviving ISR member the segment becomes
-/// Unavailable rather than risking an unclean election.
+/// The first surviving ISR member that is not cordoned, or the first surviving
+/// one if all are cordoned: a cordoned leader beats an unavailable segment.
+fn elect_leader(segment: &EngineSegment, remove_id: u64, cordoned: &HashSet<u64>) -> Option<u64> {
+    let mut survivors = segment.isr.iter().copied().filter(|id| *id != remove_id);
+    let first = survivors.clone().next();
+    survivors.find(|id| !cordoned.contains(id)).or(first)
+}
+
+/// Decide a segment's new state after its leader `remove_id` fails. Elects a
+/// surviving ISR member (never a replica outside ISR, which could be missing
+/// committed data), see [`elect_leader`]. With no surviving ISR member the
+/// segment becomes Unavailable rather than risking an unclean election.
 fn compute_segment_after_leader_failure(
     segment: &EngineSegment,
     remove_id: u64,
+    cordoned: &HashSet<u64>,
     new_leader_broker_epoch: u64,
 ) -> EngineSegment {
     let mut new_segment = segment.clone();
-    let new_leader = segment.isr.iter().copied().find(|id| *id != remove_id);
+    let new_leader = elect_leader(segment, remove_id, cordoned);
 
     new_segment.isr.retain(|id| *id != remove_id);
     new_segment.segment_epoch += 1;
@@ -271,6 +295,7 @@ fn compute_segment_after_leader_failure(
 mod tests {
     use super::compute_segment_after_leader_failure;
     use metadata_struct::storage::segment::{EngineSegment, Replica, SegmentStatus};
+    use std::collections::HashSet;
 
     fn segment(leader: u64, isr: Vec<u64>, replicas: Vec<u64>) -> EngineSegment {
         EngineSegment {
@@ -296,7 +321,7 @@ fn segment(leader: u64, isr: Vec<u64>, replicas: Vec<u64>) -> EngineSegment {
     #[test]
     fn elects_from_isr_and_bumps_epochs() {
         let seg = segment(1, vec![1, 2, 3], vec![1, 2, 3]);
-        let out = compute_segment_after_leader_failure(&seg, 1, 7);
+        let out = compute_segment_after_leader_failure(&seg, 1, &HashSet::new(), 7);
 
         assert_eq!(out.leader, 2);
         assert_eq!(out.leader_epoch, 6);
@@ -309,7 +334,7 @@ fn elects_from_isr_and_bumps_epochs() {
     #[test]
     fn never_elects_replica_outside_isr() {
         let seg = segment(1, vec![1], vec![1, 2, 3]);
-        let out = compute_segment_after_leader_failure(&seg, 1, 0);
+        let out = compute_segment_after_leader_failure(&seg, 1, &HashSet::new(), 0);
 
         assert_eq!(out.status, SegmentStatus::Unavailable);
         assert!(out.isr.is_empty());
@@ -322,12 +347,25 @@ fn never_elects_replica_outside_isr() {
     #[test]
     fn empty_isr_marks_unavailable_with_last_known() {
         let seg = segment(1, vec![1, 2], vec![1, 2, 3]);
-        let out = compute_segment_after_leader_failure(&seg, 1, 0);
+        let out = compute_segment_after_leader_failure(&seg, 1, &HashSet::new(), 0);
         assert_eq!(out.leader, 2);
 
         let seg2 = segment(2, vec![2], vec![1, 2, 3]);
-        let out2 = compute_segment_after_leader_failure(&seg2, 2, 0);
+        let out2 = compute_segment_after_leader_failure(&seg2, 2, &HashSet::new(), 0);
         assert_eq!(out2.status, SegmentStatus::Unavailable);
         assert_eq!(out2.last_known_isr, vec![2]);
     }
+
+    #[test]
+    fn prefers_isr_member_not_cordoned() {
+        let seg = segment(1, vec![1, 2, 3], vec![1, 2, 3]);
+        let out = compute_segment_after_leader_failure(&seg, 1, &HashSet::from([2]), 0);
+        assert_eq!(out.leader, 3);
+        assert_eq!(out.isr, vec![2, 3]);
+
+        // All survivors cordoned: still elect rather than go Unavailable.
+        let out = compute_segment_after_leader_failure(&seg, 1, &HashSet::from([2, 3]), 0);
+        assert_eq!(out.leader, 2);
+        assert_eq!(out.status, SegmentStatus::Write);
+    }
 }
@@ -20,6 +20,8 @@
 use crate::storage::common::node::NodeStorage;
 use common_base::error::ResultCommonError;
 use common_base::tools::loop_select_ticket;
+use common_config::broker::broker_config;
+use metadata_struct::meta::node::BrokerNode;
 use metadata_struct::storage::segment::{EngineSegment, Replica, SegmentStatus};
 use metadata_struct::storage::shard::EngineShard;
 use node_call::NodeCallManager;
@@ -77,9 +79,10 @@ pub fn node_loads(&self) -> (HashMap<u64, u64>, HashMap<u64, u64>) {
 
 /// Build the initial replica/leader placement for a new segment.
 ///
-/// Replicas are placed on the least replica-loaded nodes and the leader is the
-/// least leader-loaded among them, so both replica and leadership load spread
-/// evenly across the cluster (instead of the previous random placement). The
+/// Replicas are spread across zones and racks first, then placed on the least
+/// replica-loaded nodes, and the leader is the least leader-loaded among them,
+/// so both replica and leadership load spread evenly across the cluster
+/// (instead of the previous random placement). The
 /// elected leader is kept at `replicas[0]` so it matches the preferred-replica
 /// that the leader-rebalance controller tries to hold leadership on.
 pub async fn build_segment(
@@ -92,11 +95,9 @@ pub async fn build_segment(
         return Ok(segment);
     }
 
-    let alive: Vec<u64> = cache_manager
-        .get_engine_node_list()
-        .iter()
-        .map(|n| n.node_id)
-        .collect();
+    let nodes = cache_manager.get_engine_node_list();
+    let alive: Vec<u64> = nodes.iter().map(|n| n.node_id).collect();
+    let domains = failure_domains(&nodes);
 
     let target_replicas = effective_replica_num(
         shard_info.config.is_inner_topic,
@@ -106,7 +107,16 @@ pub async fn build_segment(
 
     let (replica_load, leader_load) = cache_manager.node_loads();
 
-    let chosen = select_least_loaded(&alive, &replica_load, target_replicas);
+    let chosen = select_spread(&alive, &domains, &replica_load, target_replicas, &[]);
+    if !shard_info.config.is_inner_topic {
+        check_zone_spread(
+            "CreateSegment",
+            &chosen,
+            &domains,
+            target_replicas,
+            broker_config().placement.min_replica_zones,
+        )?;
+    }
     let leader = pick_leader(&chosen, &leader_load)?;
     let ordered = order_leader_first(chosen, leader);
 
@@ -167,12 +177,90 @@ fn effective_replica_num(
     }
 }
 
-/// Pick the `count` least-loaded nodes, breaking ties by node id (deterministic).
-fn select_least_loaded(candidates: &[u64], load: &HashMap<u64, u64>, count: usize) -> Vec<u64> {
-    let mut sorted = candidates.to_vec();
-    sorted.sort_by_key(|id| (*load.get(id).unwrap_or(&0), *id));
-    sorted.truncate(count);
-    sorted
+/// Zone and rack of every node.
+pub(crate) type FailureDomains = HashMap<u64, (String, String)>;
+
+pub(crate) fn failure_domains(nodes: &[BrokerNode]) -> FailureDomains {
+    nodes
+        .iter()
+        .map(|n| (n.node_id, (n.zone.clone(), n.rack.clone())))
+        .collect()
+}
+
+/// Pick `count` nodes, one at a time: each pick takes a node from the zone,
+/// then the rack, holding the fewest of the segment's replicas so far
+/// (`placed` plus earlier picks), and the least-loaded one among those, ties
+/// broken by node id (deterministic). Without zone labels this is a plain
+/// least-loaded pick.
+fn select_spread(
+    candidates: &[u64],
+    domains: &FailureDomains,
+    load: &HashMap<u64, u64>,
+    count: usize,
+    placed: &[u64],
+) -> Vec<u64> {
+    let unlabelled = (String::new(), String::new());
+    let domain = |id: &u64| domains.get(id).unwrap_or(&unlabelled);
+    let mut zone_count: HashMap<&str, usize> = HashMap::new();
+    let mut rack_count: HashMap<(&str, &str), usize> = HashMap::new();
+    for id in placed {
+        let (zone, rack) = domain(id);
+        *zone_count.entry(zone.as_str()).or_insert(0) += 1;
+        *rack_count
+            .entry((zone.as_str(), rack.as_str()))
+            .or_insert(0) += 1;
+    }
+
+    let mut remaining = candidates.to_vec();
+    let mut chosen = Vec::with_capacity(count.min(remaining.len()));
+    while chosen.len() < count {
+        let Some((idx, _)) = remaining.iter().enumerate().min_by_key(|(_, id)| {
+            let (zone, rack) = domain(*id);
+            (
+                zone_count.get(zone.as_str()).copied().unwrap_or(0),
+                rack_count
+                    .get(&(zone.as_str(), rack.as_str()))
+                    .copied()
+                    .unwrap_or(0),
+                *load.get(*id).unwrap_or(&0),
+                **id,
+            )
+        }) else {
+            break;
+        };
+        let id = remaining.swap_remove(idx);
+        let (zone, rack) = domain(&id);
+        *zone_count.entry(zone.as_str()).or_insert(0) += 1;
+        *rack_count
+            .entry((zone.as_str(), rack.as_str()))
+            .or_insert(0) += 1;
+        chosen.push(id);
+    }
+    chosen
+}
+
+/// Fails when `nodes` span fewer zones than a segment with `replica_num`
+/// replicas must: `min_replica_zones`, capped at the replica count.
+pub(crate) fn check_zone_spread(
+    op: &str,
+    nodes: &[u64],
+    domains: &FailureDomains,
+    replica_num: usize,
+    min_replica_zones: u32,
+) -> Result<(), MetaServiceError> {
+    let required = (min_replica_zones as usize).clamp(1, replica_num.max(1));
+    let zones: HashSet<&str> = nodes
+        .iter()
+        .map(|id| domains.get(id).map_or("", |(zone, _)| zone.as_str()))
+        .collect();
+    if zones.len() < required {
+        return Err(MetaServiceError::NotEnoughZones(
+            op.to_string(),
+            required as u32,
+            zones.len() as u32,
+        ));
+    }
+    Ok(())
 }
 
 /// Among `nodes`, pick the least leader-loaded, breaking ties by node id.
@@ -198,7 +286,8 @@ fn order_leader_first(mut nodes: Vec<u64>, leader: u64) -> Vec<u64> {
 
 /// Background thread (meta leader only): periodically scans inner/system topics
 /// and tops up any segment whose replica count is below `replica_num`, placing
-/// the extra replicas on the least-loaded live nodes. New replicas are added to
+/// the extra replicas in the zones holding the fewest of its replicas, on the
+/// least-loaded live nodes. New replicas are added to
 /// the replica set only (not the ISR) — they catch up from the leader and the
 /// ISR maintainer admits them once in sync. If no node is available to take a
 /// replica it is left as-is (no error) and retried next tick.
@@ -232,14 +321,12 @@ async fn fill_inner_topic_replicas_once(
         return;
     }
 
-    let alive: Vec<u64> = cache_manager
-        .get_engine_node_list()
-        .iter()
-        .map(|n| n.node_id)
-        .collect();
+    let nodes = cache_manager.get_engine_node_list();
+    let alive: Vec<u64> = nodes.iter().map(|n| n.node_id).collect();
     if alive.is_empty() {
         return;
     }
+    let domains = failure_domains(&nodes);
 
     // Snapshot of current load, updated locally as replicas are added so
     // successive fills within the same tick keep spreading load.
@@ -252,14 +339,14 @@ async fn fill_inner_topic_replicas_once(
             if segment.replicas.len() >= target {
                 continue;
             }
-            let existing: HashSet<u64> = segment.replicas.iter().map(|r| r.node_id).collect();
+            let existing: Vec<u64> = segment.replicas.iter().map(|r| r.node_id).collect();
             let candidates: Vec<u64> = alive
                 .iter()
                 .copied()
                 .filter(|n| !existing.contains(n))
                 .collect();
             let need = target - segment.replicas.len();
-            let to_add = select_least_loaded(&candidates, &load, need);
+            let to_add = select_spread(&candidates, &domains, &load, need, &existing);
             if to_add.is_empty() {
                 continue;
             }
@@ -388,19 +475,68 @@ fn node_load_tracks_set_update_remove() {
         assert!(leader.values().all(|&c| c == 0));
     }
 
+    fn domains(pairs: &[(u64, &str, &str)]) -> FailureDomains {
+        pairs
+            .iter()
+            .map(|(id, zone, rack)| (*id, (zone.to_string(), rack.to_string())))
+            .collect()
+    }
+
     #[test]
-    fn select_least_loaded_picks_lowest_and_breaks_ties_by_id() {
+    fn select_spread_without_zones_picks_lowest_and_breaks_ties_by_id() {
         let candidates = [1, 2, 3, 4];
         let l = load(&[(1, 5), (2, 0), (3, 0), (4, 2)]);
         // counts: 2->0, 3->0, 4->2, 1->5; pick 2 least → [2, 3] (tie 2,3 → by id).
-        assert_eq!(select_least_loaded(&candidates, &l, 2), vec![2, 3]);
+        assert_eq!(
+            select_spread(&candidates, &HashMap::new(), &l, 2, &[]),
+            vec![2, 3]
+        );
     }
 
     #[test]
-    fn select_least_loaded_truncates_to_available() {
+    fn select_spread_truncates_to_available() {
         let candidates = [7, 9];
         let l = load(&[(7, 0), (9, 0)]);
-        assert_eq!(select_least_loaded(&candidates, &l, 5), vec![7, 9]);
+        assert_eq!(
+            select_spread(&candidates, &HashMap::new(), &l, 5, &[]),
+            vec![7, 9]
+        );
+    }
+
+    #[test]
+    fn select_spread_prefers_other_zones_then_racks() {
+        // 1,2 in az1 (different racks), 3,4 in az2 (same rack), 5 in az3.
+        let d = domains(&[
+            (1, "az1", "r1"),
+            (2, "az1", "r2"),
+            (3, "az2", "r1"),
+            (4, "az2", "r1"),
+            (5, "az3", "r1"),
+        ]);
+        // Node 5 is busiest, yet each zone gets one replica first.
+        let l = load(&[(1, 0), (2, 0), (3, 0), (4, 0), (5, 9)]);
+        let mut chosen = select_spread(&[1, 2, 3, 4, 5], &d, &l, 3, &[]);
+        chosen.sort();
+        assert_eq!(chosen, vec![1, 3, 5]);
+
+        // Topping up a segment already on 1 and 3: az3 first, then the other
+        // rack of az1 over the same rack of az2.
+        assert_eq!(select_spread(&[2, 4, 5], &d, &l, 2, &[1, 3]), vec![5, 2]);
+    }
+
+    #[test]
+    fn check_zone_spread_counts_distinct_zones() {
+        let d = domains(&[(1, "az1", ""), (2, "az1", ""), (3, "az2", "")]);
+        assert!(check_zone_spread("CreateSegment", &[1, 2], &d, 2, 1).is_ok());
+        assert!(matches!(
+            check_zone_spread("CreateSegment", &[1, 2], &d, 2, 2),
+            Err(MetaServiceError::NotEnoughZones(_, 2, 1))
+        ));
+        assert!(check_zone_spread("CreateSegment", &[1, 3], &d, 2, 2).is_ok());
+        // Capped at the replica count: one replica needs one zone.
+        assert!(check_zone_spread("CreateSegment", &[1], &d, 1, 3).is_ok());
+        // Unlabelled nodes count as one zone.
+        assert!(check_zone_spread("CreateSegment", &[7, 8], &d, 2, 2).is_err());
     }
 
     #[test]
@@ -0,0 +1,175 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::core::error::MetaServiceError;
+use crate::raft::manager::MultiRaftManager;
+use crate::server::services::mqtt::session::create_session_by_req;
+use crate::storage::mqtt::session::MqttSessionStorage;
+use broker_core::cache::NodeCacheManager;
+use common_base::tools::now_second;
+use metadata_struct::mqtt::session::MqttSession;
+use node_call::{NodeCallData, NodeCallManager};
+use protocol::meta::meta_service_mqtt::{CreateSessionRaw, CreateSessionRequest};
+use rocksdb_engine::rocksdb::RocksDBEngine;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+// Sessions written per raft proposal.
+const SESSION_TAKEOVER_BATCH_SIZE: usize = 100;
+
+/// Clean up after a broker whose heartbeat lapsed: its clients can no longer
+/// be connected, so their sessions are marked disconnected (which schedules
+/// the session-expiry delay tasks when the write is applied) and wills without
+/// a delay interval are published through a surviving broker. Wills with a
+/// delay interval stay on the session and are handled when it expires, as for
+/// any other disconnect. Returns the number of sessions taken over.
+pub async fn takeover_broker_sessions(
+    raft_manager: &Arc<MultiRaftManager>,
+    call_manager: &Arc<NodeCallManager>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    node_cache: &Arc<NodeCacheManager>,
+    broker_id: u64,
+) -> Result<usize, MetaServiceError> {
+    let storage = MqttSessionStorage::new(rocksdb_engine_handler.clone());
+    let sessions: Vec<MqttSession> = storage
+        .list()?
+        .into_iter()
+        .chain(node_cache.session_list.iter().map(|e| e.value().clone()))
+        .filter(|session| is_connected_to(session, broker_id))
+        .collect();
+    if sessions.is_empty() {
+        return Ok(0);
+    }
+
+    let now = now_second();
+    let mut last_wills = Vec::new();
+    let disconnected: Vec<MqttSession> = sessions
+        .iter()
+        .map(|session| {
+            let (session, send_will) = disconnect_session(session, now);
+            if send_will {
+                last_wills.push((session.tenant.clone(), session.client_id.clone()));
+            }
+            session
+        })
+        .collect();
+
+    for chunk in disconnected.chunks(SESSION_TAKEOVER_BATCH_SIZE) {
+        let mut raws = Vec::with_capacity(chunk.len());
+        for session in chunk {
+            raws.push(CreateSessionRaw {
+                client_id: session.client_id.clone(),
+                session: session.encode()?,
+            });
+        }
+        create_session_by_req(
+            raft_manager,
+            call_manager,
+            &CreateSessionRequest { sessions: raws },
+        )
+        .await?;
+    }
+
+    for (tenant, client_id) in last_wills {
+        if let Err(e) = call_manager
+            .send(NodeCallData::SendLastWillMessage {
+                tenant: tenant.clone(),
+                client_id: client_id.clone(),
+            })
+            .await
+        {
+            warn!(
+                "Failed to dispatch last will of a failed broker's client: broker_id={}, tenant={}, client_id={}, error={}",
+                broker_id, tenant, client_id, e
+            );
+        }
+    }
+
+    info!(
+        "Took over {} sessions of failed broker {}",
+        disconnected.len(),
+        broker_id
+    );
+    Ok(disconnected.len())
+}
+
+fn is_connected_to(session: &MqttSession, broker_id: u64) -> bool {
+    session.broker_id == Some(broker_id) && session.connection_id.is_some()
+}
+
+/// The session as the broker would have saved it on an abnormal disconnect,
+/// and whether its will must be sent now. A will sent here is removed from the
+/// session so that session expiry does not send it again.
+fn disconnect_session(session: &MqttSession, now: u64) -> (MqttSession, bool) {
+    let mut session = session.clone();
+    session.connection_id = None;
+    session.broker_id = None;
+    session.reconnect_time = None;
+    session.distinct_time = Some(now);
+
+    let send_will =
+        session.is_contain_last_will && session.last_will_delay_interval.unwrap_or_default() == 0;
+    if send_will {
+        session.is_contain_last_will = false;
+        session.last_will_delay_interval = None;
+    }
+    (session, send_will)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connected_session(will: bool, will_delay: Option<u64>) -> MqttSession {
+        MqttSession {
+            tenant: "t1".to_string(),
+            client_id: "c1".to_string(),
+            session_expiry_interval: 60,
+            is_contain_last_will: will,
+            last_will_delay_interval: will_delay,
+            connection_id: Some(7),
+            broker_id: Some(2),
+            reconnect_time: Some(10),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_is_connected_to() {
+        let session = connected_session(false, None);
+        assert!(is_connected_to(&session, 2));
+        assert!(!is_connected_to(&session, 3));
+
+        let (offline, _) = disconnect_session(&session, 100);
+        assert!(!is_connected_to(&offline, 2));
+    }
+
+    #[test]
+    fn test_disconnect_session() {
+        let (session, send_will) = disconnect_session(&connected_session(false, None), 100);
+        assert!(!send_will);
+        assert!(session.connection_id.is_none());
+        assert!(session.broker_id.is_none());
+        assert!(session.reconnect_time.is_none());
+        assert_eq!(session.distinct_time, Some(100));
+
+        let (session, send_will) = disconnect_session(&connected_session(true, None), 100);
+        assert!(send_will);
+        assert!(!session.is_contain_last_will);
+
+        let (session, send_will) = disconnect_session(&connected_session(true, Some(30)), 100);
+        assert!(!send_will);
+        assert!(session.is_contain_last_will);
+        assert_eq!(session.last_will_delay_interval, Some(30));
+    }
+}
@@ -0,0 +1,231 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Cluster subscription routing table.
+//!
+//! Every broker registers the topic filters its local subscriptions use,
+//! aggregated per node: one entry per distinct filter no matter how many
+//! clients subscribe with it. The entries are written through raft, so every
+//! meta node applies them to its own copy of this trie, which answers which
+//! nodes host a subscription matching a topic.
+
+use crate::core::error::MetaServiceError;
+use crate::storage::mqtt::subscribe_route::MqttSubscribeRouteStorage;
+use rocksdb_engine::rocksdb::RocksDBEngine;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+#[derive(Default, Debug)]
+struct RouteTrieNode {
+    children: HashMap<String, RouteTrieNode>,
+    // Nodes registering the filter that ends at this level
+    node_ids: HashSet<u64>,
+}
+
+impl RouteTrieNode {
+    fn is_empty(&self) -> bool {
+        self.children.is_empty() && self.node_ids.is_empty()
+    }
+
+    /// Remove `node_id` from the filter below `levels`, pruning emptied
+    /// levels. Returns whether this level is empty afterwards.
+    fn remove(&mut self, levels: &[&str], node_id: u64) -> bool {
+        match levels.split_first() {
+            None => {
+                self.node_ids.remove(&node_id);
+            }
+            Some((level, rest)) => {
+                if let Some(child) = self.children.get_mut(*level) {
+                    if child.remove(rest, node_id) {
+                        self.children.remove(*level);
+                    }
+                }
+            }
+        }
+        self.is_empty()
+    }
+
+    fn remove_node(&mut self, node_id: u64) -> bool {
+        self.node_ids.remove(&node_id);
+        self.children.retain(|_, child| !child.remove_node(node_id));
+        self.is_empty()
+    }
+
+    fn collect(&self, levels: &[&str], first: bool, nodes: &mut BTreeSet<u64>) {
+        // Wildcards at the first level do not match topics starting with '$'.
+        let match_wildcard = !(first && levels.first().is_some_and(|l| l.starts_with('$')));
+        if match_wildcard {
+            // '#' also matches the parent level, "a/#" matches "a".
+            if let Some(child) = self.children.get("#") {
+                nodes.extend(child.node_ids.iter());
+            }
+        }
+
+        let Some((level, rest)) = levels.split_first() else {
+            nodes.extend(self.node_ids.iter());
+            return;
+        };
+        if match_wildcard {
+            if let Some(child) = self.children.get("+") {
+                child.collect(rest, false, nodes);
+            }
+        }
+        if let Some(child) = self.children.get(*level) {
+            child.collect(rest, false, nodes);
+        }
+    }
+
+    fn filter_count(&self) -> usize {
+        self.node_ids.len()
+            + self
+                .children
+                .values()
+                .map(|child| child.filter_count())
+                .sum::<usize>()
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct SubscribeRouteTrie {
+    // (tenant, root level)
+    tenants: Arc<RwLock<HashMap<String, RouteTrieNode>>>,
+}
+
+impl SubscribeRouteTrie {
+    pub fn insert(&self, tenant: &str, filter: &str, node_id: u64) {
+        let mut tenants = self.tenants.write().unwrap();
+        insert_route(&mut tenants, tenant, filter, node_id);
+    }
+
+    pub fn remove(&self, tenant: &str, filter: &str, node_id: u64) {
+        let mut tenants = self.tenants.write().unwrap();
+        if let Some(root) = tenants.get_mut(tenant) {
+            let levels: Vec<&str> = filter.split('/').collect();
+            if root.remove(&levels, node_id) {
+                tenants.remove(tenant);
+            }
+        }
+    }
+
+    /// Drop every filter registered by `node_id`.
+    pub fn remove_node(&self, node_id: u64) {
+        self.tenants
+            .write()
+            .unwrap()
+            .retain(|_, root| !root.remove_node(node_id));
+    }
+
+    /// Nodes hosting a subscription whose filter matches `topic_name`, in
+    /// ascending order.
+    pub fn nodes_for_topic(&self, tenant: &str, topic_name: &str) -> Vec<u64> {
+        let tenants = self.tenants.read().unwrap();
+        let Some(root) = tenants.get(tenant) else {
+            return Vec::new();
+        };
+        let levels: Vec<&str> = topic_name.split('/').collect();
+        let mut nodes = BTreeSet::new();
+        root.collect(&levels, true, &mut nodes);
+        nodes.into_iter().collect()
+    }
+
+    /// Registered (node, filter) pairs across all tenants.
+    pub fn filter_count(&self) -> usize {
+        self.tenants
+            .read()
+            .unwrap()
+            .values()
+            .map(|root| root.filter_count())
+            .sum()
+    }
+
+    /// Rebuild the trie from what is stored, e.g. at startup or after a raft
+    /// snapshot was installed.
+    pub fn load(
+        &self,
+        rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    ) -> Result<(), MetaServiceError> {
+        let storage = MqttSubscribeRouteStorage::new(rocksdb_engine_handler.clone());
+        let mut tenants = HashMap::new();
+        for route in storage.list_all()? {
+            insert_route(&mut tenants, &route.tenant, &route.filter, route.broker_id);
+        }
+        *self.tenants.write().unwrap() = tenants;
+        Ok(())
+    }
+}
+
+fn insert_route(
+    tenants: &mut HashMap<String, RouteTrieNode>,
+    tenant: &str,
+    filter: &str,
+    node_id: u64,
+) {
+    let mut node = tenants.entry(tenant.to_string()).or_default();
+    for level in filter.split('/') {
+        node = node.children.entry(level.to_string()).or_default();
+    }
+    node.node_ids.insert(node_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nodes_for_topic() {
+        let trie = SubscribeRouteTrie::default();
+        trie.insert("t1", "a/b", 1);
+        trie.insert("t1", "a/+", 2);
+        trie.insert("t1", "a/#", 3);
+        trie.insert("t1", "#", 4);
+        trie.insert("t1", "+/b/c", 5);
+        trie.insert("t2", "a/b", 6);
+
+        assert_eq!(trie.nodes_for_topic("t1", "a/b"), vec![1, 2, 3, 4]);
+        assert_eq!(trie.nodes_for_topic("t1", "a"), vec![3, 4]);
+        assert_eq!(trie.nodes_for_topic("t1", "a/b/c"), vec![3, 4, 5]);
+        assert_eq!(trie.nodes_for_topic("t1", "x/y"), vec![4]);
+        assert_eq!(trie.nodes_for_topic("t2", "a/b"), vec![6]);
+        assert!(trie.nodes_for_topic("t3", "a/b").is_empty());
+
+        // Wildcards at the first level skip '$' topics.
+        assert!(trie.nodes_for_topic("t1", "$SYS/b/c").is_empty());
+        trie.insert("t1", "$SYS/#", 7);
+        assert_eq!(trie.nodes_for_topic("t1", "$SYS/b/c"), vec![7]);
+    }
+
+    #[test]
+    fn test_remove_routes() {
+        let trie = SubscribeRouteTrie::default();
+        trie.insert("t1", "a/b", 1);
+        trie.insert("t1", "a/b", 2);
+        trie.insert("t1", "a/+/c", 1);
+        trie.insert("t2", "#", 1);
+        assert_eq!(trie.filter_count(), 4);
+
+        trie.remove("t1", "a/b", 1);
+        assert_eq!(trie.nodes_for_topic("t1", "a/b"), vec![2]);
+        trie.remove("t1", "a/x", 2);
+        assert_eq!(trie.filter_count(), 3);
+
+        trie.remove_node(1);
+        assert_eq!(trie.filter_count(), 1);
+        assert!(trie.nodes_for_topic("t2", "a").is_empty());
+        assert!(trie.nodes_for_topic("t1", "a/b/c").is_empty());
+
+        trie.remove("t1", "a/b", 2);
+        assert_eq!(trie.filter_count(), 0);
+        assert!(trie.tenants.read().unwrap().is_empty());
+    }
+}
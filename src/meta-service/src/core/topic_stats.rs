@@ -0,0 +1,181 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Per-topic statistics reported by the brokers.
+//!
+//! Each broker periodically sends the full set of its topic counters, which
+//! replaces its previous report. The reports are kept in memory only, by the
+//! meta node that receives them, and are summed per topic on describe. A
+//! broker that stops reporting drops out after [`TOPIC_STATS_EXPIRE_MS`].
+
+use dashmap::DashMap;
+use protocol::meta::meta_service_mqtt::{DescribeTopicReply, TopicNodeStats, TopicStatsRaw};
+use std::collections::HashSet;
+
+/// Reports older than this are ignored and pruned.
+pub const TOPIC_STATS_EXPIRE_MS: u64 = 5 * 60 * 1000;
+
+#[derive(Debug, Clone)]
+struct BrokerTopicStats {
+    report_time: u64,
+    stats: TopicStatsRaw,
+}
+
+#[derive(Default)]
+pub struct TopicStatsCache {
+    // ((tenant, topic_name), (broker_id, BrokerTopicStats))
+    topics: DashMap<(String, String), DashMap<u64, BrokerTopicStats>>,
+}
+
+impl TopicStatsCache {
+    /// Replace everything `broker_id` reported before with `stats`.
+    pub fn report(&self, broker_id: u64, report_time: u64, stats: Vec<TopicStatsRaw>) {
+        let mut reported = HashSet::with_capacity(stats.len());
+        for raw in stats {
+            let key = (raw.tenant.clone(), raw.topic_name.clone());
+            self.topics.entry(key.clone()).or_default().insert(
+                broker_id,
+                BrokerTopicStats {
+                    report_time,
+                    stats: raw,
+                },
+            );
+            reported.insert(key);
+        }
+
+        self.topics.retain(|key, brokers| {
+            if !reported.contains(key) {
+                brokers.remove(&broker_id);
+            }
+            brokers.retain(|_, raw| {
+                report_time.saturating_sub(raw.report_time) < TOPIC_STATS_EXPIRE_MS
+            });
+            !brokers.is_empty()
+        });
+    }
+
+    pub fn remove_node(&self, broker_id: u64) {
+        self.topics.retain(|_, brokers| {
+            brokers.remove(&broker_id);
+            !brokers.is_empty()
+        });
+    }
+
+    /// Statistics of a topic summed over the brokers whose report is still
+    /// fresh at `now`. A topic nobody reported comes back with zero counters.
+    pub fn describe(&self, tenant: &str, topic_name: &str, now: u64) -> DescribeTopicReply {
+        let mut total = TopicStatsRaw {
+            tenant: tenant.to_string(),
+            topic_name: topic_name.to_string(),
+            ..Default::default()
+        };
+        let mut nodes = Vec::new();
+
+        if let Some(brokers) = self
+            .topics
+            .get(&(tenant.to_string(), topic_name.to_string()))
+        {
+            for entry in brokers.iter() {
+                if now.saturating_sub(entry.report_time) >= TOPIC_STATS_EXPIRE_MS {
+                    continue;
+                }
+                let raw = &entry.stats;
+                total.message_count += raw.message_count;
+                total.byte_count += raw.byte_count;
+                total.subscriber_count += raw.subscriber_count;
+                total.last_publish_time = total.last_publish_time.max(raw.last_publish_time);
+                if raw.retained_update_time >= total.retained_update_time {
+                    total.retained_update_time = raw.retained_update_time;
+                    total.retained = raw.retained;
+                }
+                nodes.push(TopicNodeStats {
+                    broker_id: *entry.key(),
+                    report_time: entry.report_time,
+                    stats: Some(raw.clone()),
+                });
+            }
+        }
+        nodes.sort_by_key(|node| node.broker_id);
+
+        DescribeTopicReply {
+            stats: Some(total),
+            nodes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw(
+        topic: &str,
+        messages: u64,
+        publish: u64,
+        retained: bool,
+        retained_at: u64,
+    ) -> TopicStatsRaw {
+        TopicStatsRaw {
+            tenant: "t1".to_string(),
+            topic_name: topic.to_string(),
+            message_count: messages,
+            byte_count: messages * 10,
+            last_publish_time: publish,
+            retained,
+            retained_update_time: retained_at,
+            subscriber_count: 1,
+        }
+    }
+
+    #[test]
+    fn test_describe_sums_brokers() {
+        let cache = TopicStatsCache::default();
+        cache.report(1, 1000, vec![raw("a", 3, 900, true, 100)]);
+        cache.report(2, 1000, vec![raw("a", 2, 950, false, 200)]);
+
+        let reply = cache.describe("t1", "a", 1000);
+        let stats = reply.stats.unwrap();
+        assert_eq!(stats.message_count, 5);
+        assert_eq!(stats.byte_count, 50);
+        assert_eq!(stats.subscriber_count, 2);
+        assert_eq!(stats.last_publish_time, 950);
+        assert!(!stats.retained);
+        assert_eq!(reply.nodes.len(), 2);
+        assert_eq!(reply.nodes[0].broker_id, 1);
+
+        let unknown = cache.describe("t1", "b", 1000).stats.unwrap();
+        assert_eq!(unknown.message_count, 0);
+        assert_eq!(unknown.last_publish_time, 0);
+    }
+
+    #[test]
+    fn test_report_replaces_and_expires() {
+        let cache = TopicStatsCache::default();
+        cache.report(1, 1000, vec![raw("a", 3, 900, false, 0)]);
+        cache.report(2, 1000, vec![raw("a", 1, 900, false, 0)]);
+
+        // Broker 1 no longer has the topic.
+        cache.report(1, 2000, vec![raw("b", 1, 1900, false, 0)]);
+        let reply = cache.describe("t1", "a", 2000);
+        assert_eq!(reply.nodes.len(), 1);
+        assert_eq!(reply.stats.unwrap().message_count, 1);
+
+        // Broker 2 stopped reporting.
+        let later = 1000 + TOPIC_STATS_EXPIRE_MS;
+        assert!(cache.describe("t1", "a", later).nodes.is_empty());
+
+        cache.remove_node(1);
+        assert!(cache.describe("t1", "b", 2000).nodes.is_empty());
+    }
+}
@@ -0,0 +1,236 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Change feed behind the `WatchResources` RPC. Every node records the
+//! metadata changes it applies from raft into a bounded in-memory backlog
+//! and fans them out to the open watch streams.
+//!
+//! Revisions are local to a node and to one run of it: a token is
+//! `{epoch}-{revision}`, where the epoch is generated at startup. A client
+//! that resumes with a token from another node or an earlier run, or from a
+//! revision that has already left the backlog, must list the resources again.
+
+use crate::core::error::MetaServiceError;
+use common_base::uuid::unique_id;
+use metadata_struct::meta::watch::{WatchEventType, WatchResource};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+pub const WATCH_BACKLOG_CAPACITY: usize = 10_000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WatchEvent {
+    pub revision: u64,
+    pub resource: WatchResource,
+    pub event_type: WatchEventType,
+    // "{tenant}/{name}"
+    pub key: String,
+    // Encoded resource; empty for deletes.
+    pub data: Vec<u8>,
+}
+
+pub struct WatchSubscription {
+    // Revision the subscription starts after: the resume point, or the head
+    // when no token was given.
+    pub revision: u64,
+    pub backlog: Vec<WatchEvent>,
+    pub receiver: broadcast::Receiver<WatchEvent>,
+}
+
+struct WatchLog {
+    revision: u64,
+    events: VecDeque<WatchEvent>,
+}
+
+#[derive(Clone)]
+pub struct WatchHub {
+    epoch: Arc<String>,
+    capacity: usize,
+    log: Arc<Mutex<WatchLog>>,
+    sender: broadcast::Sender<WatchEvent>,
+}
+
+impl WatchHub {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        WatchHub {
+            epoch: Arc::new(unique_id()),
+            capacity,
+            log: Arc::new(Mutex::new(WatchLog {
+                revision: 0,
+                events: VecDeque::with_capacity(capacity.min(1024)),
+            })),
+            sender,
+        }
+    }
+
+    pub fn publish(
+        &self,
+        resource: WatchResource,
+        event_type: WatchEventType,
+        key: String,
+        data: Vec<u8>,
+    ) {
+        let mut log = self.log.lock().unwrap();
+        log.revision += 1;
+        let event = WatchEvent {
+            revision: log.revision,
+            resource,
+            event_type,
+            key,
+            data,
+        };
+        if log.events.len() >= self.capacity {
+            log.events.pop_front();
+        }
+        log.events.push_back(event.clone());
+        // Sent under the lock so that subscribe() never sees an event both in
+        // the backlog and on the receiver. No receivers is not an error.
+        let _ = self.sender.send(event);
+    }
+
+    /// Start a watch. Without a token the stream begins at the current
+    /// revision; with one, every retained event after it is replayed first.
+    pub fn subscribe(&self, token: Option<&str>) -> Result<WatchSubscription, MetaServiceError> {
+        let log = self.log.lock().unwrap();
+        let receiver = self.sender.subscribe();
+
+        let from = match token {
+            None => {
+                return Ok(WatchSubscription {
+                    revision: log.revision,
+                    backlog: Vec::new(),
+                    receiver,
+                })
+            }
+            Some(token) => self.parse_token(token)?,
+        };
+
+        let oldest = log
+            .events
+            .front()
+            .map(|e| e.revision)
+            .unwrap_or(log.revision + 1);
+        if from > log.revision || from + 1 < oldest {
+            return Err(MetaServiceError::WatchRevisionExpired(
+                token.unwrap().to_string(),
+            ));
+        }
+
+        Ok(WatchSubscription {
+            revision: from,
+            backlog: log
+                .events
+                .iter()
+                .filter(|e| e.revision > from)
+                .cloned()
+                .collect(),
+            receiver,
+        })
+    }
+
+    pub fn token(&self, revision: u64) -> String {
+        format!("{}-{}", self.epoch, revision)
+    }
+
+    fn parse_token(&self, token: &str) -> Result<u64, MetaServiceError> {
+        token
+            .rsplit_once('-')
+            .filter(|(epoch, _)| *epoch == self.epoch.as_str())
+            .and_then(|(_, revision)| revision.parse().ok())
+            .ok_or_else(|| MetaServiceError::WatchRevisionExpired(token.to_string()))
+    }
+}
+
+impl Default for WatchHub {
+    fn default() -> Self {
+        WatchHub::new(WATCH_BACKLOG_CAPACITY)
+    }
+}
+
+impl fmt::Debug for WatchHub {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WatchHub")
+            .field("epoch", &self.epoch)
+            .field("revision", &self.log.lock().unwrap().revision)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn publish_user(hub: &WatchHub, name: &str) {
+        hub.publish(
+            WatchResource::User,
+            WatchEventType::Create,
+            format!("default/{}", name),
+            name.as_bytes().to_vec(),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_from_now() {
+        let hub = WatchHub::new(16);
+        publish_user(&hub, "u1");
+
+        let mut sub = hub.subscribe(None).unwrap();
+        assert_eq!(sub.revision, 1);
+        assert!(sub.backlog.is_empty());
+
+        publish_user(&hub, "u2");
+        let event = sub.receiver.recv().await.unwrap();
+        assert_eq!(event.revision, 2);
+        assert_eq!(event.key, "default/u2");
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_token() {
+        let hub = WatchHub::new(16);
+        publish_user(&hub, "u1");
+        let token = hub.token(1);
+        publish_user(&hub, "u2");
+        publish_user(&hub, "u3");
+
+        let sub = hub.subscribe(Some(&token)).unwrap();
+        assert_eq!(sub.revision, 1);
+        let keys: Vec<_> = sub.backlog.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(keys, vec!["default/u2", "default/u3"]);
+
+        // Resuming at the head replays nothing.
+        let sub = hub.subscribe(Some(&hub.token(3))).unwrap();
+        assert!(sub.backlog.is_empty());
+    }
+
+    #[test]
+    fn test_expired_tokens() {
+        let hub = WatchHub::new(2);
+        for name in ["u1", "u2", "u3", "u4"] {
+            publish_user(&hub, name);
+        }
+
+        // Only revisions 3 and 4 are retained, so the oldest usable token is 2.
+        assert!(hub.subscribe(Some(&hub.token(2))).is_ok());
+        assert!(hub.subscribe(Some(&hub.token(1))).is_err());
+        assert!(hub.subscribe(Some(&hub.token(5))).is_err());
+
+        // Tokens from another node or an earlier run are rejected.
+        let other = WatchHub::new(2);
+        assert!(hub.subscribe(Some(&other.token(3))).is_err());
+        assert!(hub.subscribe(Some("garbage")).is_err());
+    }
+}
@@ -16,6 +16,7 @@
 use crate::core::cache::{load_cache_by_rocksdb, MetaCacheManager};
 use crate::core::controller::ClusterController;
 use crate::core::error::MetaServiceError;
+use crate::raft::compaction::start_raft_log_compaction;
 use crate::raft::manager::MultiRaftManager;
 use broker_core::cache::NodeCacheManager;
 use common_base::task::{TaskKind, TaskSupervisor};
@@ -93,6 +94,15 @@ async fn start_background_services(&self) {
                 raft_manager.start_metrics_monitor(stop).await;
             });
 
+        // raft log compaction
+        let raft_manager = self.raft_manager.clone();
+        let rocksdb_engine_handler = self.rocksdb_engine_handler.clone();
+        let stop = self.stop.clone();
+        self.task_supervisor
+            .spawn(TaskKind::MetaRaftLogCompaction.to_string(), async move {
+                start_raft_log_compaction(raft_manager, rocksdb_engine_handler, stop).await;
+            });
+
         // monitor leader change
         let cache_manager = self.cache_manager.clone();
         let raft_manager = self.raft_manager.clone();
@@ -123,6 +133,7 @@ async fn start_background_services(&self) {
             self.raft_manager.clone(),
             self.node_call_manager.clone(),
             self.rocksdb_engine_handler.clone(),
+            self.node_cache.clone(),
         );
         let stop = self.stop.clone();
         self.task_supervisor.spawn(
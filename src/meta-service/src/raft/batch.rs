@@ -0,0 +1,225 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::core::error::MetaServiceError;
+use crate::raft::route::data::{decode_batch_response, StorageData};
+use crate::raft::route::AppResponseData;
+use crate::raft::type_config::TypeConfig;
+use common_metrics::meta::raft::record_write_batch;
+use openraft::error::RaftError;
+use openraft::raft::{ClientWriteResponse, ClientWriteResult};
+use openraft::Raft;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{timeout_at, Instant};
+use tracing::warn;
+
+// queue capacity in multiples of the batch size
+const BATCH_QUEUE_FACTOR: usize = 16;
+
+pub type WriteResult = Result<ClientWriteResponse<TypeConfig>, MetaServiceError>;
+
+struct WriteRequest {
+    data: StorageData,
+    reply: oneshot::Sender<WriteResult>,
+}
+
+/// Coalesces writes to one raft shard. Writes that arrive within `window` of
+/// the first one, up to `max_entries`, are proposed as a single `Batch` entry;
+/// every caller still receives the response of its own write.
+///
+/// Batches are handed to raft in arrival order without waiting for the
+/// previous one to commit, so batching adds at most `window` of latency.
+#[derive(Clone)]
+pub struct RaftWriteBatcher {
+    sender: mpsc::Sender<WriteRequest>,
+}
+
+impl RaftWriteBatcher {
+    pub fn new(
+        shard_name: String,
+        raft: Raft<TypeConfig>,
+        max_entries: usize,
+        window: Duration,
+    ) -> Self {
+        let max_entries = max_entries.max(1);
+        let (sender, receiver) = mpsc::channel(max_entries * BATCH_QUEUE_FACTOR);
+        tokio::spawn(Box::pin(run_batcher(
+            shard_name,
+            raft,
+            receiver,
+            max_entries,
+            window,
+        )));
+        RaftWriteBatcher { sender }
+    }
+
+    pub async fn write(&self, data: StorageData) -> WriteResult {
+        let (reply, receiver) = oneshot::channel();
+        self.sender
+            .send(WriteRequest { data, reply })
+            .await
+            .map_err(|_| MetaServiceError::CommonError("Raft write batcher stopped".to_string()))?;
+        receiver.await.map_err(|_| {
+            MetaServiceError::CommonError("Raft write batcher dropped the write".to_string())
+        })?
+    }
+}
+
+async fn run_batcher(
+    shard_name: String,
+    raft: Raft<TypeConfig>,
+    mut receiver: mpsc::Receiver<WriteRequest>,
+    max_entries: usize,
+    window: Duration,
+) {
+    while let Some(first) = receiver.recv().await {
+        let batch = collect_batch(&mut receiver, first, max_entries, window).await;
+        record_write_batch(&shard_name, batch.len());
+        propose(&shard_name, &raft, batch).await;
+    }
+}
+
+async fn collect_batch<T>(
+    receiver: &mut mpsc::Receiver<T>,
+    first: T,
+    max_entries: usize,
+    window: Duration,
+) -> Vec<T> {
+    let mut batch = vec![first];
+    let deadline = Instant::now() + window;
+    while batch.len() < max_entries {
+        // take what is already queued before waiting
+        match receiver.try_recv() {
+            Ok(item) => {
+                batch.push(item);
+                continue;
+            }
+            Err(mpsc::error::TryRecvError::Disconnected) => break,
+            Err(mpsc::error::TryRecvError::Empty) => {}
+        }
+        match timeout_at(deadline, receiver.recv()).await {
+            Ok(Some(item)) => batch.push(item),
+            _ => break,
+        }
+    }
+    batch
+}
+
+/// Hand one batch to raft and reply to its writers once it is applied.
+async fn propose(shard_name: &str, raft: &Raft<TypeConfig>, mut batch: Vec<WriteRequest>) {
+    let single = batch.len() == 1;
+    let payload = if single {
+        batch[0].data.clone()
+    } else {
+        let entries: Vec<StorageData> = batch.iter().map(|req| req.data.clone()).collect();
+        match StorageData::batch(&entries) {
+            Ok(payload) => payload,
+            Err(e) => {
+                for req in batch {
+                    let _ = req.reply.send(Err(MetaServiceError::CommonError(format!(
+                        "Failed to encode raft write batch: {e}"
+                    ))));
+                }
+                return;
+            }
+        }
+    };
+
+    let receiver = match raft.client_write_ff(payload).await {
+        Ok(receiver) => receiver,
+        Err(fatal) => {
+            for req in batch {
+                let _ = req.reply.send(Err(RaftError::Fatal(fatal.clone()).into()));
+            }
+            return;
+        }
+    };
+
+    let shard_name = shard_name.to_string();
+    tokio::spawn(Box::pin(async move {
+        let result: ClientWriteResult<TypeConfig> = match receiver.await {
+            Ok(result) => result,
+            Err(_) => {
+                for req in batch {
+                    let _ = req.reply.send(Err(MetaServiceError::CommonError(format!(
+                        "Raft shard {shard_name} stopped before the write was applied"
+                    ))));
+                }
+                return;
+            }
+        };
+
+        let response = match result {
+            Ok(response) => response,
+            Err(e) => {
+                for req in batch {
+                    let _ = req.reply.send(Err(RaftError::APIError(e.clone()).into()));
+                }
+                return;
+            }
+        };
+
+        if single {
+            let _ = batch.remove(0).reply.send(Ok(response));
+            return;
+        }
+
+        let values = match response.data.value.as_deref().map(decode_batch_response) {
+            Some(Ok(values)) if values.len() == batch.len() => values,
+            _ => {
+                warn!(
+                    "Raft write batch on {} was applied but returned no per-write responses",
+                    shard_name
+                );
+                vec![None; batch.len()]
+            }
+        };
+        for (req, value) in batch.into_iter().zip(values) {
+            let _ = req.reply.send(Ok(ClientWriteResponse {
+                log_id: response.log_id,
+                data: AppResponseData { value },
+                membership: None,
+            }));
+        }
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn collect_batch_limits() {
+        let (sender, mut receiver) = mpsc::channel(16);
+        for i in 1..5 {
+            sender.send(i).await.unwrap();
+        }
+
+        // queued items are taken up to the batch size
+        let batch = collect_batch(&mut receiver, 0, 3, Duration::from_millis(1)).await;
+        assert_eq!(batch, vec![0, 1, 2]);
+
+        // the window closes the batch when nothing else arrives
+        let batch = collect_batch(&mut receiver, 10, 8, Duration::from_millis(5)).await;
+        assert_eq!(batch, vec![10, 3, 4]);
+
+        let late = sender.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            late.send(21).await.unwrap();
+        });
+        let batch = collect_batch(&mut receiver, 20, 2, Duration::from_secs(5)).await;
+        assert_eq!(batch, vec![20, 21]);
+    }
+}
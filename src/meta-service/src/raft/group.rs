@@ -17,9 +17,11 @@
     collections::HashMap,
     hash::{Hash, Hasher},
     sync::Arc,
+    time::Duration,
 };
 
 use common_base::error::common::CommonError;
+use common_config::broker::broker_config;
 use common_metrics::meta::raft::{
     record_write_duration, record_write_failure, record_write_request, record_write_success,
 };
@@ -34,7 +36,9 @@
 use crate::{
     core::error::MetaServiceError,
     raft::{
+        batch::RaftWriteBatcher,
         manager::{MultiRaftManager, SLOW_RAFT_WRITE_WARN_THRESHOLD_MS},
+        network::{stream::AppendStreamPool, throttle::SnapshotThrottle},
         route::{data::StorageData, DataRoute},
         type_config::TypeConfig,
     },
@@ -44,6 +48,8 @@ pub struct RaftGroup {
     pub group_name: String,
     pub group_num: u32,
     pub raft_group: HashMap<String, Raft<TypeConfig>>,
+    // per shard, empty when write batching is disabled
+    batchers: HashMap<String, RaftWriteBatcher>,
     pub stop: Arc<RwLock<bool>>,
 }
 
@@ -52,27 +58,46 @@ pub async fn new(
         group_name: &str,
         group_num: u32,
         client_pool: Arc<ClientPool>,
+        append_streams: Arc<AppendStreamPool>,
+        snapshot_throttle: Arc<SnapshotThrottle>,
         rocksdb_engine_handler: Arc<rocksdb_engine::rocksdb::RocksDBEngine>,
         route: Arc<DataRoute>,
     ) -> Result<Self, CommonError> {
         let group_num = group_num.max(1);
+        let meta_runtime = &broker_config().meta_runtime;
         let mut raft_group = HashMap::new();
+        let mut batchers = HashMap::new();
         for i in 0..group_num {
             let shard_name = Self::shard_name(group_name, i);
             info!("Creating raft shard: {}", shard_name);
             let raft_node = MultiRaftManager::create_raft_node(
                 &shard_name,
+                meta_runtime.raft_log_retention(group_name),
                 &client_pool,
+                &append_streams,
+                &snapshot_throttle,
                 &rocksdb_engine_handler,
                 &route,
             )
             .await?;
+            if meta_runtime.raft_write_batch_enable {
+                batchers.insert(
+                    shard_name.clone(),
+                    RaftWriteBatcher::new(
+                        shard_name.clone(),
+                        raft_node.clone(),
+                        meta_runtime.raft_write_batch_max_entries,
+                        Duration::from_millis(meta_runtime.raft_write_batch_window_ms),
+                    ),
+                );
+            }
             raft_group.insert(shard_name, raft_node);
         }
 
         Ok(RaftGroup {
             group_name: group_name.to_string(),
             raft_group,
+            batchers,
             group_num,
             stop: Arc::new(RwLock::new(false)),
         })
@@ -168,7 +193,17 @@ pub async fn write(
         })?;
         record_write_request(&shard);
         let start = Instant::now();
-        let result = timeout(write_timeout, raft.client_write(data)).await;
+        let result = match self.batchers.get(&shard) {
+            Some(batcher) => timeout(write_timeout, batcher.write(data)).await,
+            None => {
+                timeout(write_timeout, async {
+                    raft.client_write(data)
+                        .await
+                        .map_err(MetaServiceError::from)
+                })
+                .await
+            }
+        };
 
         let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
         record_write_duration(&shard, duration_ms);
@@ -198,7 +233,7 @@ pub async fn write(
                         shard, data_type, duration_ms, e_str
                     );
                 }
-                Err(e.into())
+                Err(e)
             }
             Err(_) => {
                 record_write_failure(&shard);
@@ -13,6 +13,8 @@
 // limitations under the License.
 
 use super::network::client::Network;
+use super::network::stream::AppendStreamPool;
+use super::network::throttle::SnapshotThrottle;
 use super::store::new_storage;
 use super::type_config::TypeConfig;
 use crate::core::error::MetaServiceError;
@@ -21,6 +23,7 @@
 use crate::raft::route::DataRoute;
 use common_base::error::common::CommonError;
 use common_config::broker::broker_config;
+use common_config::config::RaftLogRetention;
 use common_metrics::meta::raft::{init_raft_shards_metrics, record_raft_apply_lag};
 use grpc_clients::meta::common::call::join_cluster;
 use grpc_clients::pool::ClientPool;
@@ -35,6 +38,9 @@
 
 pub const DEFAULT_RAFT_WRITE_TIMEOUT_SEC: u64 = 30;
 pub const SLOW_RAFT_WRITE_WARN_THRESHOLD_MS: f64 = 5.0;
+// Keeps a snapshot chunk well under the 256 MiB gRPC message limit.
+const MIN_SNAPSHOT_CHUNK_SIZE: u64 = 64 * 1024;
+const MAX_SNAPSHOT_CHUNK_SIZE: u64 = 64 * 1024 * 1024;
 type RaftShardNodes = Vec<(String, Raft<TypeConfig>)>;
 type MetricsGroups = Vec<(String, RaftShardNodes)>;
 
@@ -103,11 +109,17 @@ pub async fn new(
             meta_rt.offset_raft_group_num, meta_rt.data_raft_group_num
         );
         init_raft_shards_metrics(meta_rt.offset_raft_group_num, meta_rt.data_raft_group_num);
+        let append_streams = Arc::new(AppendStreamPool::new(client_pool.clone()));
+        let snapshot_throttle = Arc::new(SnapshotThrottle::new(
+            meta_rt.raft_snapshot_max_bytes_per_sec,
+        ));
 
         let metadata = RaftGroup::new(
             "metadata",
             1,
             client_pool.clone(),
+            append_streams.clone(),
+            snapshot_throttle.clone(),
             rocksdb_engine_handler.clone(),
             route.clone(),
         )
@@ -117,6 +129,8 @@ pub async fn new(
             "offset",
             meta_rt.offset_raft_group_num,
             client_pool.clone(),
+            append_streams.clone(),
+            snapshot_throttle.clone(),
             rocksdb_engine_handler.clone(),
             route.clone(),
         )
@@ -126,6 +140,8 @@ pub async fn new(
             "data",
             meta_rt.data_raft_group_num,
             client_pool.clone(),
+            append_streams.clone(),
+            snapshot_throttle.clone(),
             rocksdb_engine_handler.clone(),
             route.clone(),
         )
@@ -379,7 +395,10 @@ async fn find_reachable_peer(peers: &[(u64, String)]) -> Option<(u64, String)> {
 
     pub async fn create_raft_node(
         shard_name: &str,
+        retention: &RaftLogRetention,
         client_pool: &Arc<ClientPool>,
+        append_streams: &Arc<AppendStreamPool>,
+        snapshot_throttle: &Arc<SnapshotThrottle>,
         rocksdb_engine_handler: &Arc<rocksdb_engine::rocksdb::RocksDBEngine>,
         route: &Arc<DataRoute>,
     ) -> Result<Raft<TypeConfig>, CommonError> {
@@ -387,14 +406,23 @@ pub async fn create_raft_node(
             heartbeat_interval: 500,
             election_timeout_min: 10000,
             election_timeout_max: 20000,
-            // Build a snapshot every 100 applied logs and keep a small log tail
-            // afterwards. Without an active snapshot policy, openraft purges logs
-            // while the persisted snapshot lags behind last_applied, so on restart
-            // purge_upto ends up greater than snapshot_last_log_id and RaftCore
-            // panics ("invalid state"). A modest threshold keeps snapshot and
-            // applied state in sync across restarts.
-            snapshot_policy: SnapshotPolicy::LogsSinceLast(100),
-            max_in_snapshot_log_to_keep: 1000,
+            // Build a snapshot every `snapshot_interval_logs` applied logs and
+            // keep a bounded log tail afterwards. Without an active snapshot
+            // policy, openraft purges logs while the persisted snapshot lags
+            // behind last_applied, so on restart purge_upto ends up greater than
+            // snapshot_last_log_id and RaftCore panics ("invalid state"). A
+            // modest threshold keeps snapshot and applied state in sync across
+            // restarts.
+            snapshot_policy: SnapshotPolicy::LogsSinceLast(retention.snapshot_interval_logs.max(1)),
+            max_in_snapshot_log_to_keep: retention.max_entries,
+            // Snapshots are sent in chunks that are written to the follower's
+            // receiving file as they arrive, so neither side holds a whole
+            // snapshot in memory. A failed chunk is resent from its offset.
+            snapshot_max_chunk_size: snapshot_chunk_size(),
+            install_snapshot_timeout: broker_config()
+                .meta_runtime
+                .raft_snapshot_chunk_timeout_ms
+                .max(1),
             ..Default::default()
         };
 
@@ -410,7 +438,12 @@ pub async fn create_raft_node(
         let (log_store, state_machine_store) =
             new_storage(shard_name, rocksdb_engine_handler.clone(), route.clone()).await;
 
-        let network = Network::new(shard_name.to_string(), client_pool.clone());
+        let network = Network::new(
+            shard_name.to_string(),
+            client_pool.clone(),
+            append_streams.clone(),
+            snapshot_throttle.clone(),
+        );
 
         match Raft::new(
             conf.broker_id,
@@ -432,3 +465,10 @@ pub async fn create_raft_node(
         }
     }
 }
+
+fn snapshot_chunk_size() -> u64 {
+    broker_config()
+        .meta_runtime
+        .raft_snapshot_chunk_size
+        .clamp(MIN_SNAPSHOT_CHUNK_SIZE, MAX_SNAPSHOT_CHUNK_SIZE)
+}
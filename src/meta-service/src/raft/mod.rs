@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod batch;
+pub mod compaction;
 pub mod error;
 pub mod group;
 pub mod leadership;
@@ -18,17 +18,28 @@
 use openraft::RaftNetworkFactory;
 
 use super::connection::NetworkConnection;
+use super::stream::AppendStreamPool;
+use super::throttle::SnapshotThrottle;
 use crate::raft::type_config::{Node, NodeId, TypeConfig};
 
 pub struct Network {
     client_pool: Arc<ClientPool>,
+    append_streams: Arc<AppendStreamPool>,
+    snapshot_throttle: Arc<SnapshotThrottle>,
     machine: String,
 }
 
 impl Network {
-    pub fn new(machine: String, client_pool: Arc<ClientPool>) -> Network {
+    pub fn new(
+        machine: String,
+        client_pool: Arc<ClientPool>,
+        append_streams: Arc<AppendStreamPool>,
+        snapshot_throttle: Arc<SnapshotThrottle>,
+    ) -> Network {
         Network {
             client_pool,
+            append_streams,
+            snapshot_throttle,
             machine,
         }
     }
@@ -42,6 +53,12 @@ impl RaftNetworkFactory<TypeConfig> for Network {
     #[tracing::instrument(level = "debug", skip_all)]
     async fn new_client(&mut self, _: NodeId, node: &Node) -> Self::Network {
         let addr = node.rpc_addr.to_string();
-        NetworkConnection::new(self.machine.clone(), addr, self.client_pool.clone())
+        NetworkConnection::new(
+            self.machine.clone(),
+            addr,
+            self.client_pool.clone(),
+            self.append_streams.clone(),
+            self.snapshot_throttle.clone(),
+        )
     }
 }
@@ -12,11 +12,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::stream::AppendStreamPool;
+use super::throttle::SnapshotThrottle;
 use crate::raft::error::{to_bincode_error, to_grpc_error, to_rpc_error};
 use crate::raft::type_config::{Node, NodeId, TypeConfig};
 use bincode::{deserialize, serialize_into};
+use common_base::fault::{fault_injector, FaultPoint};
+use common_config::broker::broker_config;
 use common_metrics::meta::raft::{
-    record_rpc_duration, record_rpc_failure, record_rpc_request, record_rpc_success,
+    record_replication_latency, record_rpc_duration, record_rpc_failure, record_rpc_request,
+    record_rpc_success, record_snapshot_chunk_retry, record_snapshot_chunk_sent,
 };
 use grpc_clients::pool::ClientPool;
 use openraft::error::{InstallSnapshotError, RPCError, RaftError};
@@ -33,23 +38,41 @@
 use std::time::{Duration, Instant};
 use tokio::time::timeout;
 use tonic::transport::Channel;
-use tracing::warn;
+use tracing::{info, warn};
 
 const SLOW_RPC_WARN_THRESHOLD_MS: f64 = 1000.0;
 const RPC_TIMEOUT: Duration = Duration::from_secs(10);
-const SNAPSHOT_RPC_TIMEOUT: Duration = Duration::from_secs(60);
 
 pub struct NetworkConnection {
     addr: String,
     machine: String,
     client_pool: Arc<ClientPool>,
+    append_streams: Arc<AppendStreamPool>,
+    snapshot_throttle: Arc<SnapshotThrottle>,
+    snapshot_chunk_timeout: Duration,
+    // snapshot id and offset of the last chunk sent, to tell resends apart
+    last_snapshot_chunk: Option<(String, u64)>,
 }
 
 impl NetworkConnection {
-    pub fn new(machine: String, addr: String, client_pool: Arc<ClientPool>) -> Self {
+    pub fn new(
+        machine: String,
+        addr: String,
+        client_pool: Arc<ClientPool>,
+        append_streams: Arc<AppendStreamPool>,
+        snapshot_throttle: Arc<SnapshotThrottle>,
+    ) -> Self {
+        let chunk_timeout_ms = broker_config()
+            .meta_runtime
+            .raft_snapshot_chunk_timeout_ms
+            .max(1);
         NetworkConnection {
             addr,
             client_pool,
+            append_streams,
+            snapshot_throttle,
+            snapshot_chunk_timeout: Duration::from_millis(chunk_timeout_ms),
+            last_snapshot_chunk: None,
             machine,
         }
     }
@@ -68,11 +91,25 @@ fn deserialize_from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, bincod
         deserialize(bytes)
     }
 
+    /// Apply the injected faults of a Raft RPC to this peer.
+    async fn inject_fault<E: std::error::Error + 'static + Clone>(
+        &self,
+    ) -> Result<(), RPCError<NodeId, Node, E>> {
+        if !fault_injector().is_enabled() {
+            return Ok(());
+        }
+        let target = format!("{}@{}", self.machine, self.addr);
+        fault_injector()
+            .inject(FaultPoint::RaftNetwork, &target)
+            .await
+            .map_err(|fault| to_rpc_error(format!("Raft RPC to {} failed: {}", self.addr, fault)))
+    }
+
     async fn append_entries_internal(
         &mut self,
         req: AppendEntriesRequest<TypeConfig>,
     ) -> Result<AppendEntriesResponse<NodeId>, RPCError<NodeId, Node, RaftError<NodeId>>> {
-        let mut c = self.c();
+        self.inject_fault::<RaftError<NodeId>>().await?;
 
         let value = match Self::serialize_to_bytes(&req) {
             Ok(data) => data,
@@ -84,6 +121,34 @@ async fn append_entries_internal(
             }
         };
 
+        let reply_value = match self.append_streams.stream(&self.addr).await {
+            Ok(Some(stream)) => stream
+                .append(&self.machine, value, RPC_TIMEOUT)
+                .await
+                .map_err(to_rpc_error)?,
+            Ok(None) => self.append_unary(value).await?,
+            Err(e) => return Err(to_rpc_error(e)),
+        };
+
+        let result = match Self::deserialize_from_bytes(&reply_value) {
+            Ok(data) => data,
+            Err(e) => {
+                return Err(to_bincode_error(
+                    e,
+                    "Failed to deserialize AppendEntriesResponse",
+                ))
+            }
+        };
+
+        Ok(result)
+    }
+
+    /// AppendEntries as a unary RPC, for peers that do not serve AppendStream.
+    async fn append_unary(
+        &self,
+        value: Vec<u8>,
+    ) -> Result<Vec<u8>, RPCError<NodeId, Node, RaftError<NodeId>>> {
+        let mut c = self.c();
         let request = AppendRequest {
             machine: self.machine.clone(),
             value,
@@ -106,18 +171,7 @@ async fn append_entries_internal(
                 )));
             }
         };
-
-        let result = match Self::deserialize_from_bytes(&reply.value) {
-            Ok(data) => data,
-            Err(e) => {
-                return Err(to_bincode_error(
-                    e,
-                    "Failed to deserialize AppendEntriesResponse",
-                ))
-            }
-        };
-
-        Ok(result)
+        Ok(reply.value)
     }
 
     async fn install_snapshot_internal(
@@ -127,6 +181,40 @@ async fn install_snapshot_internal(
         InstallSnapshotResponse<NodeId>,
         RPCError<NodeId, Node, RaftError<NodeId, InstallSnapshotError>>,
     > {
+        let snapshot_id = req.meta.snapshot_id.clone();
+        let offset = req.offset;
+        let chunk_len = req.data.len() as u64;
+        let done = req.done;
+        let chunk = Some((snapshot_id.clone(), offset));
+        if self.last_snapshot_chunk == chunk {
+            record_snapshot_chunk_retry(&self.machine, &self.addr);
+            info!(
+                "Resending snapshot chunk. machine={}, target={}, snapshot_id={}, offset={}",
+                self.machine, self.addr, snapshot_id, offset
+            );
+        }
+        self.last_snapshot_chunk = chunk;
+
+        self.inject_fault::<RaftError<NodeId, InstallSnapshotError>>()
+            .await?;
+
+        // The throttle may use at most half of the chunk timeout, the RPC gets
+        // the rest. A chunk that cannot be paced in time fails and is resent.
+        let wait_start = Instant::now();
+        if !self
+            .snapshot_throttle
+            .acquire(chunk_len, self.snapshot_chunk_timeout / 2)
+            .await
+        {
+            return Err(to_rpc_error(format!(
+                "InstallSnapshot chunk to {} at offset {} exceeded the snapshot bandwidth limit",
+                self.addr, offset
+            )));
+        }
+        let rpc_timeout = self
+            .snapshot_chunk_timeout
+            .saturating_sub(wait_start.elapsed());
+
         let mut c = self.c();
 
         let value = match Self::serialize_to_bytes(&req) {
@@ -144,22 +232,24 @@ async fn install_snapshot_internal(
             value,
         };
 
-        let reply =
-            match timeout(SNAPSHOT_RPC_TIMEOUT, c.snapshot(request)).await {
-                Ok(Ok(reply)) => reply.into_inner(),
-                Ok(Err(e)) => return Err(to_grpc_error(e, "Failed to send InstallSnapshot RPC")),
-                Err(_) => {
-                    warn!(
-                    "Raft RPC timed out. machine={}, op=install_snapshot, target={}, timeout={}s",
-                    self.machine, self.addr, SNAPSHOT_RPC_TIMEOUT.as_secs()
+        let reply = match timeout(rpc_timeout, c.snapshot(request)).await {
+            Ok(Ok(reply)) => reply.into_inner(),
+            Ok(Err(e)) => return Err(to_grpc_error(e, "Failed to send InstallSnapshot RPC")),
+            Err(_) => {
+                warn!(
+                    "Raft RPC timed out. machine={}, op=install_snapshot, target={}, offset={}, timeout={}ms",
+                    self.machine,
+                    self.addr,
+                    offset,
+                    rpc_timeout.as_millis()
                 );
-                    return Err(to_rpc_error(format!(
-                        "InstallSnapshot RPC to {} timed out after {}s",
-                        self.addr,
-                        SNAPSHOT_RPC_TIMEOUT.as_secs()
-                    )));
-                }
-            };
+                return Err(to_rpc_error(format!(
+                    "InstallSnapshot RPC to {} timed out after {}ms",
+                    self.addr,
+                    rpc_timeout.as_millis()
+                )));
+            }
+        };
 
         let result = match Self::deserialize_from_bytes(&reply.value) {
             Ok(data) => data,
@@ -171,6 +261,18 @@ async fn install_snapshot_internal(
             }
         };
 
+        record_snapshot_chunk_sent(&self.machine, &self.addr, offset, chunk_len);
+        if done {
+            self.last_snapshot_chunk = None;
+            info!(
+                "Snapshot sent. machine={}, target={}, snapshot_id={}, bytes={}",
+                self.machine,
+                self.addr,
+                snapshot_id,
+                offset + chunk_len
+            );
+        }
+
         Ok(result)
     }
 
@@ -178,6 +280,7 @@ async fn vote_internal(
         &mut self,
         req: VoteRequest<NodeId>,
     ) -> Result<VoteResponse<NodeId>, RPCError<NodeId, Node, RaftError<NodeId>>> {
+        self.inject_fault::<RaftError<NodeId>>().await?;
         let mut c = self.c();
 
         let value = match Self::serialize_to_bytes(&req) {
@@ -241,6 +344,7 @@ async fn append_entries(
         match result {
             Ok(response) => {
                 record_rpc_success(&self.machine, "append_entries");
+                record_replication_latency(&self.addr, duration_ms);
                 Ok(response)
             }
             Err(e) => {
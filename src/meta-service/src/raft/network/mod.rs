@@ -14,3 +14,5 @@
 
 pub mod client;
 pub mod connection;
+pub mod stream;
+pub mod throttle;
@@ -0,0 +1,244 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_metrics::meta::raft::{record_append_stream_broken, record_append_stream_opened};
+use dashmap::DashMap;
+use grpc_clients::pool::ClientPool;
+use protocol::meta::meta_service_common::meta_service_service_client::MetaServiceServiceClient;
+use protocol::meta::meta_service_common::{AppendStreamReply, AppendStreamRequest};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::time::timeout;
+use tonic::{Code, Streaming};
+use tracing::{debug, info, warn};
+
+const STREAM_CHANNEL_SIZE: usize = 1024;
+const STREAM_OPEN_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long a peer without AppendStream is sent unary Append RPCs before
+/// the stream is tried again, so a peer upgraded in place picks it up.
+const UNSUPPORTED_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+/// One long-lived AppendEntries stream per peer, shared by every Raft group
+/// of this node. A broken stream fails the calls waiting on it and is opened
+/// again by the next call.
+pub struct AppendStreamPool {
+    client_pool: Arc<ClientPool>,
+    peers: DashMap<String, Arc<PeerStream>>,
+    open_locks: DashMap<String, Arc<Mutex<()>>>,
+    /// Peers that answered Unimplemented, with the time they did.
+    unsupported: DashMap<String, Instant>,
+}
+
+pub struct PeerStream {
+    addr: String,
+    sender: mpsc::Sender<AppendStreamRequest>,
+    state: Arc<StreamState>,
+}
+
+#[derive(Default)]
+struct StreamState {
+    next_seq: AtomicU64,
+    pending: DashMap<u64, oneshot::Sender<AppendStreamReply>>,
+    closed: AtomicBool,
+}
+
+impl AppendStreamPool {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        AppendStreamPool {
+            client_pool,
+            peers: DashMap::new(),
+            open_locks: DashMap::new(),
+            unsupported: DashMap::new(),
+        }
+    }
+
+    /// The stream to `addr`, opened if there is none. None if the peer does
+    /// not serve AppendStream and unary RPCs have to be used.
+    pub async fn stream(&self, addr: &str) -> Result<Option<Arc<PeerStream>>, String> {
+        if self.is_unsupported(addr) {
+            return Ok(None);
+        }
+        if let Some(peer) = self.live(addr) {
+            return Ok(Some(peer));
+        }
+
+        let lock = self.open_locks.entry(addr.to_string()).or_default().clone();
+        let _guard = lock.lock().await;
+        if let Some(peer) = self.live(addr) {
+            return Ok(Some(peer));
+        }
+
+        let peer = match self.open(addr).await? {
+            Some(peer) => Arc::new(peer),
+            None => return Ok(None),
+        };
+        self.peers.insert(addr.to_string(), peer.clone());
+        Ok(Some(peer))
+    }
+
+    fn live(&self, addr: &str) -> Option<Arc<PeerStream>> {
+        self.peers
+            .get(addr)
+            .filter(|peer| !peer.state.closed.load(Ordering::SeqCst))
+            .map(|peer| peer.clone())
+    }
+
+    fn is_unsupported(&self, addr: &str) -> bool {
+        let expired = match self.unsupported.get(addr) {
+            Some(since) => since.elapsed() >= UNSUPPORTED_RETRY_INTERVAL,
+            None => return false,
+        };
+        if expired {
+            self.unsupported.remove(addr);
+        }
+        !expired
+    }
+
+    async fn open(&self, addr: &str) -> Result<Option<PeerStream>, String> {
+        let (sender, mut receiver) = mpsc::channel::<AppendStreamRequest>(STREAM_CHANNEL_SIZE);
+        let outbound = async_stream::stream! {
+            while let Some(req) = receiver.recv().await {
+                yield req;
+            }
+        };
+
+        let mut client = MetaServiceServiceClient::new(self.client_pool.get_channel(addr));
+        let inbound = match timeout(STREAM_OPEN_TIMEOUT, client.append_stream(outbound)).await {
+            Ok(Ok(response)) => response.into_inner(),
+            Ok(Err(status)) if status.code() == Code::Unimplemented => {
+                info!(
+                    "Peer {} does not serve AppendStream, sending unary AppendEntries",
+                    addr
+                );
+                self.unsupported.insert(addr.to_string(), Instant::now());
+                return Ok(None);
+            }
+            Ok(Err(status)) => {
+                return Err(format!(
+                    "Failed to open AppendEntries stream to {}: {}",
+                    addr, status
+                ))
+            }
+            Err(_) => {
+                return Err(format!(
+                    "Opening AppendEntries stream to {} timed out after {}s",
+                    addr,
+                    STREAM_OPEN_TIMEOUT.as_secs()
+                ))
+            }
+        };
+
+        let state = Arc::new(StreamState::default());
+        tokio::spawn(read_replies(addr.to_string(), inbound, state.clone()));
+        record_append_stream_opened(addr);
+        debug!("AppendEntries stream to {} opened", addr);
+        Ok(Some(PeerStream {
+            addr: addr.to_string(),
+            sender,
+            state,
+        }))
+    }
+}
+
+impl PeerStream {
+    /// Send one serialized AppendEntriesRequest and wait for its reply. A
+    /// timeout closes the stream, since later replies are unlikely to arrive
+    /// on it either.
+    pub async fn append(
+        &self,
+        machine: &str,
+        value: Vec<u8>,
+        rpc_timeout: Duration,
+    ) -> Result<Vec<u8>, String> {
+        let seq = self.state.next_seq.fetch_add(1, Ordering::Relaxed);
+        let (reply_tx, reply_rx) = oneshot::channel();
+        self.state.pending.insert(seq, reply_tx);
+        // The reader clears pending after setting closed, so a request
+        // registered after that is caught here.
+        if self.state.closed.load(Ordering::SeqCst) {
+            self.state.pending.remove(&seq);
+            return Err(format!("AppendEntries stream to {} is closed", self.addr));
+        }
+
+        let request = AppendStreamRequest {
+            seq,
+            machine: machine.to_string(),
+            value,
+        };
+        let result = timeout(rpc_timeout, async {
+            self.sender
+                .send(request)
+                .await
+                .map_err(|_| format!("AppendEntries stream to {} is closed", self.addr))?;
+            reply_rx.await.map_err(|_| {
+                format!(
+                    "AppendEntries stream to {} broke before the reply",
+                    self.addr
+                )
+            })
+        })
+        .await;
+
+        match result {
+            Ok(Ok(reply)) if reply.error.is_empty() => Ok(reply.value),
+            Ok(Ok(reply)) => Err(reply.error),
+            Ok(Err(e)) => Err(e),
+            Err(_) => {
+                self.state.pending.remove(&seq);
+                self.state.closed.store(true, Ordering::SeqCst);
+                warn!(
+                    "AppendEntries over stream timed out, closing it. machine={}, target={}, timeout={}s",
+                    machine,
+                    self.addr,
+                    rpc_timeout.as_secs()
+                );
+                Err(format!(
+                    "AppendEntries RPC to {} timed out after {}s",
+                    self.addr,
+                    rpc_timeout.as_secs()
+                ))
+            }
+        }
+    }
+}
+
+async fn read_replies(
+    addr: String,
+    mut inbound: Streaming<AppendStreamReply>,
+    state: Arc<StreamState>,
+) {
+    loop {
+        match inbound.message().await {
+            Ok(Some(reply)) => {
+                // A reply whose caller timed out has no waiter left.
+                if let Some((_, reply_tx)) = state.pending.remove(&reply.seq) {
+                    let _ = reply_tx.send(reply);
+                }
+            }
+            Ok(None) => {
+                debug!("AppendEntries stream to {} closed", addr);
+                break;
+            }
+            Err(status) => {
+                warn!("AppendEntries stream to {} failed: {}", addr, status);
+                break;
+            }
+        }
+    }
+    state.closed.store(true, Ordering::SeqCst);
+    state.pending.clear();
+    record_append_stream_broken(&addr);
+}
@@ -0,0 +1,106 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::time::sleep_until;
+
+/// Paces the snapshot chunks of all raft groups of this node so that their
+/// combined rate stays under `max_bytes_per_sec`.
+pub struct SnapshotThrottle {
+    max_bytes_per_sec: u64,
+    next_free: Mutex<Instant>,
+}
+
+impl SnapshotThrottle {
+    pub fn new(max_bytes_per_sec: u64) -> Self {
+        SnapshotThrottle {
+            max_bytes_per_sec,
+            next_free: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Wait until `bytes` may be sent. Returns false without reserving any
+    /// bandwidth when the wait would exceed `max_wait`; the caller then fails
+    /// the chunk and raft resends it from the same offset.
+    pub async fn acquire(&self, bytes: u64, max_wait: Duration) -> bool {
+        if self.max_bytes_per_sec == 0 {
+            return true;
+        }
+        let now = Instant::now();
+        let reserved = {
+            let mut next_free = self.next_free.lock().unwrap();
+            reserve(&mut next_free, now, bytes, self.max_bytes_per_sec, max_wait)
+        };
+        let start = match reserved {
+            Some(start) => start,
+            None => {
+                sleep_until(tokio::time::Instant::from_std(now + max_wait)).await;
+                return false;
+            }
+        };
+        sleep_until(tokio::time::Instant::from_std(start)).await;
+        true
+    }
+}
+
+/// Reserve the slot for `bytes` after everything already reserved, returning
+/// when it starts, or None if it starts later than `max_wait` from now.
+fn reserve(
+    next_free: &mut Instant,
+    now: Instant,
+    bytes: u64,
+    max_bytes_per_sec: u64,
+    max_wait: Duration,
+) -> Option<Instant> {
+    let start = (*next_free).max(now);
+    if start.duration_since(now) > max_wait {
+        return None;
+    }
+    let cost = Duration::from_secs_f64(bytes as f64 / max_bytes_per_sec as f64);
+    *next_free = start + cost;
+    Some(start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reserve_paces_and_bounds_wait() {
+        let now = Instant::now();
+        let mut next_free = now;
+        let max_wait = Duration::from_secs(2);
+
+        // 1 MiB/s: each 1 MiB chunk starts one second after the previous one.
+        assert_eq!(
+            reserve(&mut next_free, now, 1 << 20, 1 << 20, max_wait),
+            Some(now)
+        );
+        assert_eq!(
+            reserve(&mut next_free, now, 1 << 20, 1 << 20, max_wait),
+            Some(now + Duration::from_secs(1))
+        );
+        assert_eq!(
+            reserve(&mut next_free, now, 1 << 20, 1 << 20, max_wait),
+            Some(now + Duration::from_secs(2))
+        );
+
+        // The next slot is 3s away, beyond max_wait: nothing is reserved.
+        assert_eq!(
+            reserve(&mut next_free, now, 1 << 20, 1 << 20, max_wait),
+            None
+        );
+        assert_eq!(next_free, now + Duration::from_secs(3));
+    }
+}
@@ -19,10 +19,10 @@
 use metadata_struct::tenant::{Tenant, TenantConfig};
 use prost::Message as _;
 use protocol::meta::meta_service_common::{
-    BindSchemaRequest, CreateSchemaRequest, CreateTenantRequest, DeleteResourceConfigRequest,
-    DeleteSchemaRequest, DeleteShareGroupRequest, DeleteTenantRequest, RegisterNodeRequest,
-    SaveOffsetDataRequest, SetResourceConfigRequest, UnBindSchemaRequest, UnRegisterNodeRequest,
-    UpdateTenantRequest,
+    BindSchemaRequest, CordonNodeRequest, CreateSchemaRequest, CreateTenantRequest,
+    DeleteResourceConfigRequest, DeleteSchemaRequest, DeleteShareGroupRequest, DeleteTenantRequest,
+    RegisterNodeRequest, SaveOffsetDataRequest, SetResourceConfigRequest, UnBindSchemaRequest,
+    UnRegisterNodeRequest, UncordonNodeRequest, UpdateTenantRequest,
 };
 use rocksdb_engine::rocksdb::RocksDBEngine;
 use std::sync::Arc;
@@ -30,10 +30,11 @@
 use crate::core::cache::MetaCacheManager;
 use crate::core::error::MetaServiceError;
 use crate::storage::common::config::ResourceConfigStorage;
-use crate::storage::common::node::NodeStorage;
+use crate::storage::common::node::{NodeCordon, NodeStorage};
 use crate::storage::common::offset::{OffsetData, OffsetStorage};
 use crate::storage::common::schema::SchemaStorage;
 use crate::storage::common::tenant::TenantStorage;
+use crate::storage::mqtt::subscribe_route::MqttSubscribeRouteStorage;
 
 #[derive(Clone)]
 pub struct DataRouteCluster {
@@ -67,6 +68,31 @@ pub async fn delete_node(&self, value: Bytes) -> Result<(), MetaServiceError> {
         let node_storage = NodeStorage::new(self.rocksdb_engine_handler.clone());
         node_storage.delete(req.node_id)?;
         self.cluster_cache.remove_broker_node(req.node_id);
+
+        let route_storage = MqttSubscribeRouteStorage::new(self.rocksdb_engine_handler.clone());
+        route_storage.delete_by_node(req.node_id)?;
+        self.cluster_cache.subscribe_route.remove_node(req.node_id);
+        Ok(())
+    }
+
+    pub fn cordon_node(&self, value: Bytes) -> Result<(), MetaServiceError> {
+        let req = CordonNodeRequest::decode(value.as_ref())?;
+        let cordon = NodeCordon {
+            node_id: req.node_id,
+            reason: req.reason,
+            create_time: now_second(),
+        };
+        let node_storage = NodeStorage::new(self.rocksdb_engine_handler.clone());
+        node_storage.save_cordon(&cordon)?;
+        self.cluster_cache.add_node_cordon(cordon);
+        Ok(())
+    }
+
+    pub fn uncordon_node(&self, value: Bytes) -> Result<(), MetaServiceError> {
+        let req = UncordonNodeRequest::decode(value.as_ref())?;
+        let node_storage = NodeStorage::new(self.rocksdb_engine_handler.clone());
+        node_storage.delete_cordon(req.node_id)?;
+        self.cluster_cache.remove_node_cordon(req.node_id);
         Ok(())
     }
 
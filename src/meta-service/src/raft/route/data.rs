@@ -27,6 +27,27 @@ impl StorageData {
     pub fn new(data_type: StorageDataType, value: Bytes) -> StorageData {
         StorageData { data_type, value }
     }
+
+    /// Pack several writes into one raft entry. The apply layer routes them in
+    /// order and answers with one response per write, see [`decode_batch_response`].
+    pub fn batch(entries: &[StorageData]) -> Result<StorageData, bincode::Error> {
+        Ok(StorageData::new(
+            StorageDataType::Batch,
+            Bytes::from(bincode::serialize(entries)?),
+        ))
+    }
+
+    pub fn decode_batch(&self) -> Result<Vec<StorageData>, bincode::Error> {
+        bincode::deserialize(&self.value)
+    }
+}
+
+pub fn encode_batch_response(values: &[Option<Bytes>]) -> Result<Bytes, bincode::Error> {
+    Ok(Bytes::from(bincode::serialize(values)?))
+}
+
+pub fn decode_batch_response(value: &[u8]) -> Result<Vec<Option<Bytes>>, bincode::Error> {
+    bincode::deserialize(value)
 }
 
 impl fmt::Display for StorageData {
@@ -102,6 +123,24 @@ pub enum StorageDataType {
     Mq9DeleteMail,
     Mq9CreateAgent,
     Mq9DeleteAgent,
+
+    // Several writes coalesced by the raft write batcher
+    Batch,
+
+    // Raft log entries encode these variants by index with bincode, so
+    // existing variants keep their position and new ones go at the end.
+    MqttCreateMessageRule,
+    MqttDeleteMessageRule,
+    MqttUpdateSubscribeRoute,
+    MqttSyncSubscribeRoute,
+    MqttSetConnectorCheckpoint,
+    MqttSetRetainIndex,
+    MqttDeleteRetainIndex,
+    KvWrite,
+    MqttAllocateLastWillEpoch,
+    MqttClaimLastWill,
+    ClusterCordonNode,
+    ClusterUncordonNode,
 }
 
 impl fmt::Display for StorageDataType {
@@ -109,8 +148,11 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             StorageDataType::ClusterAddNode => write!(f, "ClusterAddNode"),
             StorageDataType::ClusterDeleteNode => write!(f, "ClusterDeleteNode"),
+            StorageDataType::ClusterCordonNode => write!(f, "ClusterCordonNode"),
+            StorageDataType::ClusterUncordonNode => write!(f, "ClusterUncordonNode"),
 
             StorageDataType::KvSet => write!(f, "KvSet"),
+            StorageDataType::KvWrite => write!(f, "KvWrite"),
             StorageDataType::KvDelete => write!(f, "KvDelete"),
 
             StorageDataType::TenantCreate => write!(f, "TenantCreate"),
@@ -144,8 +186,12 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             StorageDataType::MqttDeleteUser => write!(f, "MqttDeleteUser"),
             StorageDataType::MqttSetTopic => write!(f, "MqttSetTopic"),
             StorageDataType::MqttDeleteTopic => write!(f, "MqttDeleteTopic"),
+            StorageDataType::MqttSetRetainIndex => write!(f, "MqttSetRetainIndex"),
+            StorageDataType::MqttDeleteRetainIndex => write!(f, "MqttDeleteRetainIndex"),
             StorageDataType::MqttSetSession => write!(f, "MqttSetSession"),
             StorageDataType::MqttDeleteSession => write!(f, "MqttDeleteSession"),
+            StorageDataType::MqttAllocateLastWillEpoch => write!(f, "MqttAllocateLastWillEpoch"),
+            StorageDataType::MqttClaimLastWill => write!(f, "MqttClaimLastWill"),
             StorageDataType::MqttSetAcl => write!(f, "MqttSetAcl"),
             StorageDataType::MqttDeleteAcl => write!(f, "MqttDeleteAcl"),
             StorageDataType::MqttSetBlacklist => write!(f, "MqttSetBlacklist"),
@@ -156,12 +202,17 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             StorageDataType::MqttDeleteSubscribe => write!(f, "MqttDeleteSubscribe"),
             StorageDataType::MqttSetConnector => write!(f, "MqttSetConnector"),
             StorageDataType::MqttDeleteConnector => write!(f, "MqttDeleteConnector"),
+            StorageDataType::MqttSetConnectorCheckpoint => {
+                write!(f, "MqttSetConnectorCheckpoint")
+            }
             StorageDataType::MqttCreateAutoSubscribeRule => {
                 write!(f, "MqttCreateAutoSubscribeRule")
             }
             StorageDataType::MqttDeleteAutoSubscribeRule => {
                 write!(f, "MqttDeleteAutoSubscribeRule")
             }
+            StorageDataType::MqttCreateMessageRule => write!(f, "MqttCreateMessageRule"),
+            StorageDataType::MqttDeleteMessageRule => write!(f, "MqttDeleteMessageRule"),
             StorageDataType::MqttSetGroupLeader => {
                 write!(f, "MqttSetGroupLeader")
             }
@@ -174,6 +225,8 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             StorageDataType::MqttDeleteGroupMember => {
                 write!(f, "MqttDeleteGroupMember")
             }
+            StorageDataType::MqttUpdateSubscribeRoute => write!(f, "MqttUpdateSubscribeRoute"),
+            StorageDataType::MqttSyncSubscribeRoute => write!(f, "MqttSyncSubscribeRoute"),
 
             StorageDataType::NatsSetSubscribe => write!(f, "NatsSetSubscribe"),
             StorageDataType::NatsDeleteSubscribe => write!(f, "NatsDeleteSubscribe"),
@@ -182,6 +235,110 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             StorageDataType::Mq9DeleteMail => write!(f, "Mq9DeleteMail"),
             StorageDataType::Mq9CreateAgent => write!(f, "Mq9CreateAgent"),
             StorageDataType::Mq9DeleteAgent => write!(f, "Mq9DeleteAgent"),
+
+            StorageDataType::Batch => write!(f, "Batch"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_round_trip() {
+        let entries = vec![
+            StorageData::new(StorageDataType::KvSet, Bytes::from_static(b"a")),
+            StorageData::new(StorageDataType::MqttSetUser, Bytes::from_static(b"b")),
+        ];
+        let batch = StorageData::batch(&entries).unwrap();
+        assert_eq!(batch.data_type.to_string(), "Batch");
+
+        let decoded = batch.decode_batch().unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[1].data_type.to_string(), "MqttSetUser");
+        assert_eq!(decoded[1].value, Bytes::from_static(b"b"));
+
+        let values = vec![None, Some(Bytes::from_static(b"epoch"))];
+        let response = encode_batch_response(&values).unwrap();
+        assert_eq!(decode_batch_response(&response).unwrap(), values);
+    }
+
+    #[test]
+    fn data_type_discriminants_are_stable() {
+        // Raft log entries written by older nodes must decode to the same
+        // operation, so a variant's bincode index never changes.
+        let pinned = [
+            (StorageDataType::ClusterAddNode, 0),
+            (StorageDataType::ClusterDeleteNode, 1),
+            (StorageDataType::KvSet, 2),
+            (StorageDataType::KvDelete, 3),
+            (StorageDataType::TenantCreate, 4),
+            (StorageDataType::TenantUpdate, 5),
+            (StorageDataType::TenantDelete, 6),
+            (StorageDataType::SchemaSet, 7),
+            (StorageDataType::SchemaDelete, 8),
+            (StorageDataType::SchemaBindSet, 9),
+            (StorageDataType::SchemaBindDelete, 10),
+            (StorageDataType::ResourceConfigSet, 11),
+            (StorageDataType::ResourceConfigDelete, 12),
+            (StorageDataType::OffsetSet, 13),
+            (StorageDataType::OffsetDelete, 14),
+            (StorageDataType::StorageEngineSetShard, 15),
+            (StorageDataType::StorageEngineDeleteShard, 16),
+            (StorageDataType::StorageEngineSetSegment, 17),
+            (StorageDataType::StorageEngineDeleteSegment, 18),
+            (StorageDataType::StorageEngineSetSegmentMetadata, 19),
+            (StorageDataType::StorageEngineDeleteSegmentMetadata, 20),
+            (StorageDataType::StorageEngineUpdateSegmentIsr, 21),
+            (StorageDataType::MqttSetUser, 22),
+            (StorageDataType::MqttDeleteUser, 23),
+            (StorageDataType::MqttSetTopic, 24),
+            (StorageDataType::MqttDeleteTopic, 25),
+            (StorageDataType::MqttSetSession, 26),
+            (StorageDataType::MqttDeleteSession, 27),
+            (StorageDataType::MqttSetAcl, 28),
+            (StorageDataType::MqttDeleteAcl, 29),
+            (StorageDataType::MqttSetBlacklist, 30),
+            (StorageDataType::MqttDeleteBlacklist, 31),
+            (StorageDataType::MqttCreateTopicRewriteRule, 32),
+            (StorageDataType::MqttDeleteTopicRewriteRule, 33),
+            (StorageDataType::MqttSetSubscribe, 34),
+            (StorageDataType::MqttDeleteSubscribe, 35),
+            (StorageDataType::MqttSetConnector, 36),
+            (StorageDataType::MqttDeleteConnector, 37),
+            (StorageDataType::MqttCreateAutoSubscribeRule, 38),
+            (StorageDataType::MqttDeleteAutoSubscribeRule, 39),
+            (StorageDataType::MqttSetGroupLeader, 40),
+            (StorageDataType::MqttDeleteGroupLeader, 41),
+            (StorageDataType::MqttAddGroupMember, 42),
+            (StorageDataType::MqttDeleteGroupMember, 43),
+            (StorageDataType::NatsSetSubscribe, 44),
+            (StorageDataType::NatsDeleteSubscribe, 45),
+            (StorageDataType::Mq9CreateMail, 46),
+            (StorageDataType::Mq9DeleteMail, 47),
+            (StorageDataType::Mq9CreateAgent, 48),
+            (StorageDataType::Mq9DeleteAgent, 49),
+            (StorageDataType::Batch, 50),
+            (StorageDataType::MqttCreateMessageRule, 51),
+            (StorageDataType::MqttDeleteMessageRule, 52),
+            (StorageDataType::MqttUpdateSubscribeRoute, 53),
+            (StorageDataType::MqttSyncSubscribeRoute, 54),
+            (StorageDataType::MqttSetConnectorCheckpoint, 55),
+            (StorageDataType::MqttSetRetainIndex, 56),
+            (StorageDataType::MqttDeleteRetainIndex, 57),
+            (StorageDataType::KvWrite, 58),
+            (StorageDataType::MqttAllocateLastWillEpoch, 59),
+            (StorageDataType::MqttClaimLastWill, 60),
+            (StorageDataType::ClusterCordonNode, 61),
+            (StorageDataType::ClusterUncordonNode, 62),
+        ];
+        for (data_type, index) in pinned {
+            assert_eq!(
+                bincode::serialize(&data_type).unwrap(),
+                u32::to_le_bytes(index),
+                "{data_type} moved"
+            );
         }
     }
 }
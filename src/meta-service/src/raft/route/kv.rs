@@ -17,11 +17,23 @@
 use bytes::Bytes;
 use prost::Message as _;
 use protocol::meta::meta_service_common::{DeleteRequest, SetRequest};
+use serde::{Deserialize, Serialize};
 
 use crate::core::error::MetaServiceError;
-use crate::storage::common::kv::KvStorage;
+use crate::storage::common::kv::{KvCondition, KvSetResult, KvStorage};
 use rocksdb_engine::rocksdb::RocksDBEngine;
 
+/// Raft payload of a KV write. `expire_at_ms` and `now_ms` are fixed on the
+/// proposing node so that every replica applies the write identically.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct KvWriteCommand {
+    pub key: String,
+    pub value: String,
+    pub expire_at_ms: u64,
+    pub condition: Option<KvCondition>,
+    pub now_ms: u64,
+}
+
 #[derive(Debug, Clone)]
 pub struct DataRouteKv {
     kv_storage: KvStorage,
@@ -32,13 +44,36 @@ pub fn new(rocksdb_engine_handler: Arc<RocksDBEngine>) -> Self {
         let kv_storage = KvStorage::new(rocksdb_engine_handler);
         DataRouteKv { kv_storage }
     }
+
+    // Entries proposed before KV writes carried a version and TTL.
     pub fn set(&self, value: Bytes) -> Result<(), MetaServiceError> {
         let req: SetRequest = SetRequest::decode(value.as_ref())?;
-        Ok(self.kv_storage.set(req.key, req.value)?)
+        self.kv_storage.set(&req.key, req.value, 0)?;
+        Ok(())
+    }
+
+    pub fn write(&self, value: Bytes) -> Result<Bytes, MetaServiceError> {
+        let cmd: KvWriteCommand = bincode::deserialize(value.as_ref())?;
+        let result = match &cmd.condition {
+            Some(condition) => self.kv_storage.compare_and_set(
+                &cmd.key,
+                cmd.value,
+                cmd.expire_at_ms,
+                condition,
+                cmd.now_ms,
+            )?,
+            None => KvSetResult {
+                succeeded: true,
+                version: self.kv_storage.set(&cmd.key, cmd.value, cmd.expire_at_ms)?,
+                current_value: String::new(),
+            },
+        };
+        Ok(Bytes::from(bincode::serialize(&result)?))
     }
 
-    pub fn delete(&self, value: Bytes) -> Result<(), MetaServiceError> {
+    pub fn delete(&self, value: Bytes) -> Result<Bytes, MetaServiceError> {
         let req: DeleteRequest = DeleteRequest::decode(value.as_ref())?;
-        Ok(self.kv_storage.delete(req.key)?)
+        let deleted = self.kv_storage.delete(&req.key, req.expected_version)?;
+        Ok(Bytes::from(vec![deleted as u8]))
     }
 }
@@ -22,7 +22,7 @@
 use crate::raft::route::nats::DataRouteNats;
 use broker_core::cache::NodeCacheManager;
 use bytes::Bytes;
-use data::{StorageData, StorageDataType};
+use data::{encode_batch_response, StorageData, StorageDataType};
 use delay_task::manager::DelayTaskManager;
 use rocksdb_engine::rocksdb::RocksDBEngine;
 use serde::{Deserialize, Serialize};
@@ -43,6 +43,7 @@ pub struct AppResponseData {
 
 #[derive(Clone)]
 pub struct DataRoute {
+    cache_manager: Arc<MetaCacheManager>,
     route_kv: DataRouteKv,
     route_mq9: DataRouteMq9,
     route_mqtt: DataRouteMqtt,
@@ -70,8 +71,9 @@ pub fn new(
         let route_nats = DataRouteNats::new(rocksdb_engine_handler.clone());
         let route_cluster =
             DataRouteCluster::new(rocksdb_engine_handler.clone(), cache_manager.clone());
-        let route_journal = DataRouteJournal::new(rocksdb_engine_handler, cache_manager);
+        let route_journal = DataRouteJournal::new(rocksdb_engine_handler, cache_manager.clone());
         DataRoute {
+            cache_manager,
             route_kv,
             route_mq9,
             route_mqtt,
@@ -81,6 +83,25 @@ pub fn new(
         }
     }
 
+    /// Drop every cached list snapshot, e.g. after a raft snapshot replaced
+    /// the state machine data underneath the apply path.
+    pub fn invalidate_list_cache(&self) {
+        self.cache_manager.list_cache.invalidate_all();
+    }
+
+    /// Rebuild the subscribe route trie from the state machine data, after a
+    /// raft snapshot replaced it.
+    pub fn reload_subscribe_route(&self) -> Result<(), MetaServiceError> {
+        self.cache_manager
+            .subscribe_route
+            .load(&self.route_mqtt.rocksdb_engine_handler)
+    }
+
+    pub fn reload_node_cordon(&self) -> Result<(), MetaServiceError> {
+        self.cache_manager
+            .load_node_cordon(&self.route_mqtt.rocksdb_engine_handler)
+    }
+
     //Receive write operations performed by the Raft state machine and write subsequent service data after Raft state machine synchronization is complete.
     pub async fn route(
         &self,
@@ -92,9 +113,9 @@ pub async fn route(
                 self.route_kv.set(storage_data.value.clone())?;
                 Ok(None)
             }
+            StorageDataType::KvWrite => Ok(Some(self.route_kv.write(storage_data.value.clone())?)),
             StorageDataType::KvDelete => {
-                self.route_kv.delete(storage_data.value.clone())?;
-                Ok(None)
+                Ok(Some(self.route_kv.delete(storage_data.value.clone())?))
             }
             StorageDataType::ClusterAddNode => {
                 let broker_epoch = self
@@ -109,6 +130,15 @@ pub async fn route(
                     .await?;
                 Ok(None)
             }
+            StorageDataType::ClusterCordonNode => {
+                self.route_cluster.cordon_node(storage_data.value.clone())?;
+                Ok(None)
+            }
+            StorageDataType::ClusterUncordonNode => {
+                self.route_cluster
+                    .uncordon_node(storage_data.value.clone())?;
+                Ok(None)
+            }
 
             StorageDataType::ResourceConfigSet => {
                 self.route_cluster
@@ -243,6 +273,16 @@ pub async fn route(
                 self.route_mqtt.delete_topic(storage_data.value.clone())?;
                 Ok(None)
             }
+            StorageDataType::MqttSetRetainIndex => {
+                self.route_mqtt
+                    .set_retain_index(storage_data.value.clone())?;
+                Ok(None)
+            }
+            StorageDataType::MqttDeleteRetainIndex => {
+                self.route_mqtt
+                    .delete_retain_index(storage_data.value.clone())?;
+                Ok(None)
+            }
             StorageDataType::MqttSetSession => {
                 self.route_mqtt
                     .create_session(storage_data.value.clone())
@@ -253,6 +293,14 @@ pub async fn route(
                 self.route_mqtt.delete_session(storage_data.value.clone())?;
                 Ok(None)
             }
+            StorageDataType::MqttAllocateLastWillEpoch => Ok(Some(
+                self.route_mqtt
+                    .allocate_last_will_epoch(storage_data.value.clone())?,
+            )),
+            StorageDataType::MqttClaimLastWill => Ok(Some(
+                self.route_mqtt
+                    .claim_last_will(storage_data.value.clone())?,
+            )),
             StorageDataType::MqttCreateTopicRewriteRule => {
                 self.route_mqtt
                     .create_topic_rewrite_rule(storage_data.value.clone())?;
@@ -281,6 +329,11 @@ pub async fn route(
                     .delete_connector(storage_data.value.clone())?;
                 Ok(None)
             }
+            StorageDataType::MqttSetConnectorCheckpoint => {
+                self.route_mqtt
+                    .set_connector_checkpoint(storage_data.value.clone())?;
+                Ok(None)
+            }
             StorageDataType::MqttSetGroupLeader => {
                 self.route_mqtt
                     .create_group_leader(storage_data.value.clone())?;
@@ -314,6 +367,30 @@ pub async fn route(
                 Ok(None)
             }
 
+            // message rule
+            StorageDataType::MqttCreateMessageRule => {
+                self.route_mqtt
+                    .create_message_rule(storage_data.value.clone())?;
+                Ok(None)
+            }
+            StorageDataType::MqttDeleteMessageRule => {
+                self.route_mqtt
+                    .delete_message_rule(storage_data.value.clone())?;
+                Ok(None)
+            }
+
+            // subscribe route
+            StorageDataType::MqttUpdateSubscribeRoute => {
+                self.route_mqtt
+                    .update_subscribe_route(storage_data.value.clone())?;
+                Ok(None)
+            }
+            StorageDataType::MqttSyncSubscribeRoute => {
+                self.route_mqtt
+                    .sync_subscribe_route(storage_data.value.clone())?;
+                Ok(None)
+            }
+
             // nats subscribe
             StorageDataType::NatsSetSubscribe => {
                 self.route_nats.set_subscribe(storage_data.value.clone())?;
@@ -342,6 +419,15 @@ pub async fn route(
                 self.route_mq9.delete_agent(storage_data.value.clone())?;
                 Ok(None)
             }
+
+            StorageDataType::Batch => {
+                let entries = storage_data.decode_batch()?;
+                let mut values = Vec::with_capacity(entries.len());
+                for entry in entries.iter() {
+                    values.push(Box::pin(self.route(entry)).await?);
+                }
+                Ok(Some(encode_batch_response(&values)?))
+            }
         }
     }
 }
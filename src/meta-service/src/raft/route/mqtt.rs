@@ -13,27 +13,35 @@
 // limitations under the License.
 
 use crate::core::cache::MetaCacheManager;
+use crate::core::cache_list::ListResource;
 use crate::core::error::MetaServiceError;
 use crate::storage::common::share_group::ShareGroupStorage;
 use crate::storage::mqtt::acl::AclStorage;
 use crate::storage::mqtt::blacklist::MqttBlackListStorage;
 use crate::storage::mqtt::connector::MqttConnectorStorage;
+use crate::storage::mqtt::message_rule::MqttMessageRuleStorage;
 use crate::storage::mqtt::session::MqttSessionStorage;
 use crate::storage::mqtt::subscribe::MqttSubscribeStorage;
+use crate::storage::mqtt::subscribe_route::{MqttSubscribeRoute, MqttSubscribeRouteStorage};
 use crate::storage::mqtt::topic::MqttTopicStorage;
 use crate::storage::mqtt::user::SecurityUserStorage;
 use crate::storage::topic_delete::TopicDeleteStorage;
 use broker_core::cache::NodeCacheManager;
 use bytes::Bytes;
-use common_base::tools::now_millis;
+use common_base::tools::{now_millis, now_second};
 use common_base::utils::serialize;
 use delay_task::manager::DelayTaskManager;
 use delay_task::{DelayTask, DelayTaskData};
 use metadata_struct::auth::acl::SecurityAcl;
 use metadata_struct::auth::blacklist::SecurityBlackList;
 use metadata_struct::auth::user::SecurityUser;
+use metadata_struct::connector::checkpoint::ConnectorCheckpoint;
 use metadata_struct::connector::MQTTConnector;
+use metadata_struct::meta::watch::{WatchEventType, WatchResource};
 use metadata_struct::mqtt::auto_subscribe::MqttAutoSubscribeRule;
+use metadata_struct::mqtt::lastwill::MqttLastWillClaim;
+use metadata_struct::mqtt::message_rule::MqttMessageRule;
+use metadata_struct::mqtt::retain_message::MQTTRetainIndex;
 use metadata_struct::mqtt::session::MqttSession;
 use metadata_struct::mqtt::share_group::{ShareGroup, ShareGroupMember};
 use metadata_struct::mqtt::subscribe::MqttSubscribe;
@@ -44,14 +52,19 @@
     AddShareGroupMemberRequest, DeleteShareGroupMemberRequest,
 };
 use protocol::meta::meta_service_mqtt::{
-    CreateAclRequest, CreateAutoSubscribeRuleRequest, CreateBlacklistRequest,
-    CreateConnectorRequest, CreateSessionRequest, CreateTopicRequest,
+    AllocateLastWillEpochReply, AllocateLastWillEpochRequest, ClaimLastWillReply,
+    ClaimLastWillRequest, CommitConnectorCheckpointRequest, CreateAclRequest,
+    CreateAutoSubscribeRuleRequest, CreateBlacklistRequest, CreateConnectorRequest,
+    CreateMessageRuleRequest, CreateSessionRequest, CreateTopicRequest,
     CreateTopicRewriteRuleRequest, CreateUserRequest, DeleteAclRequest,
     DeleteAutoSubscribeRuleRequest, DeleteBlacklistRequest, DeleteConnectorRequest,
-    DeleteSessionRequest, DeleteSubscribeRequest, DeleteTopicRequest,
-    DeleteTopicRewriteRuleRequest, DeleteUserRequest, SetSubscribeRequest,
+    DeleteMessageRuleRequest, DeleteRetainIndexRequest, DeleteSessionRequest,
+    DeleteSubscribeRequest, DeleteTopicRequest, DeleteTopicRewriteRuleRequest, DeleteUserRequest,
+    SetRetainIndexRequest, SetSubscribeRequest, SubscribeRoute, SyncSubscribeRouteRequest,
+    UpdateSubscribeRouteRequest,
 };
 use rocksdb_engine::rocksdb::RocksDBEngine;
+use std::collections::HashSet;
 use std::sync::Arc;
 
 #[derive(Clone)]
@@ -76,12 +89,40 @@ pub fn new(
         }
     }
 
+    fn upsert_event(exists: bool) -> WatchEventType {
+        if exists {
+            WatchEventType::Update
+        } else {
+            WatchEventType::Create
+        }
+    }
+
+    fn publish_watch(
+        &self,
+        resource: WatchResource,
+        event_type: WatchEventType,
+        key: String,
+        data: Vec<u8>,
+    ) {
+        self.cache_manager
+            .watch_hub
+            .publish(resource, event_type, key, data);
+    }
+
     // User
     pub fn create_user(&self, value: Bytes) -> Result<(), MetaServiceError> {
         let req = CreateUserRequest::decode(value.as_ref())?;
         let storage = SecurityUserStorage::new(self.rocksdb_engine_handler.clone());
         let user = SecurityUser::decode(&req.content)?;
+        let event_type = Self::upsert_event(storage.get(&req.tenant, &req.user_name)?.is_some());
         storage.save(&req.tenant, &req.user_name, user.clone())?;
+        self.cache_manager.list_cache.invalidate(ListResource::User);
+        self.publish_watch(
+            WatchResource::User,
+            event_type,
+            format!("{}/{}", req.tenant, req.user_name),
+            req.content,
+        );
         Ok(())
     }
 
@@ -89,6 +130,13 @@ pub fn delete_user(&self, value: Bytes) -> Result<(), MetaServiceError> {
         let req = DeleteUserRequest::decode(value.as_ref())?;
         let storage = SecurityUserStorage::new(self.rocksdb_engine_handler.clone());
         storage.delete(&req.tenant, &req.user_name)?;
+        self.cache_manager.list_cache.invalidate(ListResource::User);
+        self.publish_watch(
+            WatchResource::User,
+            WatchEventType::Delete,
+            format!("{}/{}", req.tenant, req.user_name),
+            Vec::new(),
+        );
         Ok(())
     }
 
@@ -97,7 +145,18 @@ pub async fn create_topic(&self, value: Bytes) -> Result<(), MetaServiceError> {
         let req = CreateTopicRequest::decode(value.as_ref())?;
         let topic = Topic::decode(&req.content)?;
         let storage = MqttTopicStorage::new(self.rocksdb_engine_handler.clone());
+        let event_type =
+            Self::upsert_event(storage.get(&topic.tenant, &topic.topic_name)?.is_some());
         storage.save(topic.clone())?;
+        self.cache_manager
+            .list_cache
+            .invalidate(ListResource::Topic);
+        self.publish_watch(
+            WatchResource::Topic,
+            event_type,
+            format!("{}/{}", topic.tenant, topic.topic_name),
+            req.content,
+        );
         Ok(())
     }
 
@@ -110,16 +169,44 @@ pub fn delete_topic(&self, value: Bytes) -> Result<(), MetaServiceError> {
         };
         let delete_storage = TopicDeleteStorage::new(self.rocksdb_engine_handler.clone());
 
-        if topic.mark_delete {
+        let (event_type, data) = if topic.mark_delete {
             // Final cleanup: shards already confirmed deleted, remove all records.
             delete_storage.delete(&topic.topic_id)?;
             topic_storage.delete(&req.tenant, &req.topic_name)?;
+            topic_storage.delete_retain_index(&req.tenant, &req.topic_name)?;
+            (WatchEventType::Delete, Vec::new())
         } else {
             // Initial mark: flag topic for deletion and enqueue in TopicDeleteStorage.
             topic.mark_delete = true;
             topic_storage.save(topic.clone())?;
             delete_storage.save(&topic)?;
-        }
+            (WatchEventType::Update, topic.encode()?)
+        };
+        self.cache_manager
+            .list_cache
+            .invalidate(ListResource::Topic);
+        self.publish_watch(
+            WatchResource::Topic,
+            event_type,
+            format!("{}/{}", req.tenant, req.topic_name),
+            data,
+        );
+        Ok(())
+    }
+
+    // Retain Index
+    pub fn set_retain_index(&self, value: Bytes) -> Result<(), MetaServiceError> {
+        let req = SetRetainIndexRequest::decode(value.as_ref())?;
+        let index = MQTTRetainIndex::decode(&req.index)?;
+        let topic_storage = MqttTopicStorage::new(self.rocksdb_engine_handler.clone());
+        topic_storage.save_retain_index(index)?;
+        Ok(())
+    }
+
+    pub fn delete_retain_index(&self, value: Bytes) -> Result<(), MetaServiceError> {
+        let req = DeleteRetainIndexRequest::decode(value.as_ref())?;
+        let topic_storage = MqttTopicStorage::new(self.rocksdb_engine_handler.clone());
+        topic_storage.delete_retain_index(&req.tenant, &req.topic_name)?;
         Ok(())
     }
 
@@ -129,8 +216,17 @@ pub async fn create_session(&self, value: Bytes) -> Result<(), MetaServiceError>
         let storage = MqttSessionStorage::new(self.rocksdb_engine_handler.clone());
 
         let mut persist_sessions = Vec::new();
+        let mut events = Vec::new();
         for raw in &req.sessions {
             let session = MqttSession::decode(&raw.session)?;
+            let key = format!("{}/{}", session.tenant, session.client_id);
+            let exists = if session.is_persist_session {
+                storage.get(&session.tenant, &session.client_id)?.is_some()
+            } else {
+                self.node_cache.session_list.contains_key(&key)
+            };
+            events.push((Self::upsert_event(exists), key, raw.session.clone()));
+
             if session.is_persist_session {
                 persist_sessions.push(session.clone());
             } else {
@@ -144,14 +240,16 @@ pub async fn create_session(&self, value: Bytes) -> Result<(), MetaServiceError>
             // If it is a disconnected connection, it needs to be added to the queue for session expiration
             if is_session_expire {
                 if let Some(distinct_time) = session.distinct_time {
-                    let target_time = session.session_expiry_interval + distinct_time;
+                    let target_time_ms = distinct_time
+                        .saturating_add(session.session_expiry_interval)
+                        .saturating_mul(1000);
                     let task = DelayTask::build_ephemeral(
                         session.client_id.clone(),
                         DelayTaskData::MQTTSessionExpire(
                             session.tenant.clone(),
                             session.client_id.clone(),
                         ),
-                        target_time,
+                        target_time_ms,
                     );
 
                     self.delay_task_manager.create_task(task).await?;
@@ -165,8 +263,14 @@ pub async fn create_session(&self, value: Bytes) -> Result<(), MetaServiceError>
 
         if !persist_sessions.is_empty() {
             storage.save_batch(&persist_sessions)?;
+            self.cache_manager
+                .list_cache
+                .invalidate(ListResource::Session);
         }
 
+        for (event_type, key, data) in events {
+            self.publish_watch(WatchResource::Session, event_type, key, data);
+        }
         Ok(())
     }
 
@@ -174,10 +278,45 @@ pub fn delete_session(&self, value: Bytes) -> Result<(), MetaServiceError> {
         let req = DeleteSessionRequest::decode(value.as_ref())?;
         let storage = MqttSessionStorage::new(self.rocksdb_engine_handler.clone());
         storage.delete(&req.tenant, &req.client_id)?;
+        storage.delete_last_will_claim(&req.tenant, &req.client_id)?;
         self.node_cache.delete_session(&req.tenant, &req.client_id);
+        self.cache_manager
+            .list_cache
+            .invalidate(ListResource::Session);
+        self.publish_watch(
+            WatchResource::Session,
+            WatchEventType::Delete,
+            format!("{}/{}", req.tenant, req.client_id),
+            Vec::new(),
+        );
         Ok(())
     }
 
+    pub fn allocate_last_will_epoch(&self, value: Bytes) -> Result<Bytes, MetaServiceError> {
+        let req = AllocateLastWillEpochRequest::decode(value.as_ref())?;
+        let storage = MqttSessionStorage::new(self.rocksdb_engine_handler.clone());
+        let session_epoch = storage.allocate_last_will_epoch(&req.tenant, &req.client_id)?;
+        Ok(Bytes::from(
+            AllocateLastWillEpochReply { session_epoch }.encode_to_vec(),
+        ))
+    }
+
+    /// Grant the requesting broker the will of a session epoch if no other
+    /// broker holds it. The decision is the write's response value.
+    pub fn claim_last_will(&self, value: Bytes) -> Result<Bytes, MetaServiceError> {
+        let req = ClaimLastWillRequest::decode(value.as_ref())?;
+        let storage = MqttSessionStorage::new(self.rocksdb_engine_handler.clone());
+        let claimed = storage.claim_last_will(&MqttLastWillClaim {
+            tenant: req.tenant,
+            client_id: req.client_id,
+            session_epoch: req.session_epoch,
+            broker_id: req.broker_id,
+            claim_time: now_second(),
+            allocated_epoch: 0,
+        })?;
+        Ok(Bytes::from(ClaimLastWillReply { claimed }.encode_to_vec()))
+    }
+
     // TopicRewriteRule
     pub fn create_topic_rewrite_rule(&self, value: Bytes) -> Result<(), MetaServiceError> {
         let req = CreateTopicRewriteRuleRequest::decode(value.as_ref())?;
@@ -229,8 +368,11 @@ pub fn set_connector(&self, value: Bytes) -> Result<(), MetaServiceError> {
         let storage = MqttConnectorStorage::new(self.rocksdb_engine_handler.clone());
         let req = CreateConnectorRequest::decode(value.as_ref())?;
         let connector = MQTTConnector::decode(&req.connector)?;
+        let event_type = Self::upsert_event(storage.get(&req.connector_name)?.is_some());
+        let key = format!("{}/{}", connector.tenant, req.connector_name);
         storage.save(&req.connector_name, &connector)?;
         self.cache_manager.add_connector(connector);
+        self.publish_watch(WatchResource::Connector, event_type, key, req.connector);
         Ok(())
     }
 
@@ -238,7 +380,29 @@ pub fn delete_connector(&self, value: Bytes) -> Result<(), MetaServiceError> {
         let storage = MqttConnectorStorage::new(self.rocksdb_engine_handler.clone());
         let req = DeleteConnectorRequest::decode(value.as_ref())?;
         storage.delete(&req.connector_name)?;
+        storage.delete_checkpoint(&req.connector_name)?;
         self.cache_manager.remove_connector(&req.connector_name);
+        self.publish_watch(
+            WatchResource::Connector,
+            WatchEventType::Delete,
+            format!("{}/{}", req.tenant, req.connector_name),
+            Vec::new(),
+        );
+        Ok(())
+    }
+
+    pub fn set_connector_checkpoint(&self, value: Bytes) -> Result<(), MetaServiceError> {
+        let storage = MqttConnectorStorage::new(self.rocksdb_engine_handler.clone());
+        let req = CommitConnectorCheckpointRequest::decode(value.as_ref())?;
+        let checkpoint = ConnectorCheckpoint::decode(&req.checkpoint)?;
+        // Commits are checked before they are proposed; this keeps a stale
+        // one that raced another commit from moving the checkpoint back.
+        if let Some(stored) = storage.get_checkpoint(&checkpoint.connector_name)? {
+            if stored.sequence >= checkpoint.sequence {
+                return Ok(());
+            }
+        }
+        storage.save_checkpoint(&checkpoint)?;
         Ok(())
     }
 
@@ -321,4 +485,84 @@ pub fn delete_auto_subscribe_rule(&self, value: Bytes) -> Result<(), MetaService
         let storage = MqttSubscribeStorage::new(self.rocksdb_engine_handler.clone());
         storage.delete_auto_subscribe_rule(&req.tenant, &req.name)
     }
+
+    // MessageRule
+    pub fn create_message_rule(&self, value: Bytes) -> Result<(), MetaServiceError> {
+        let req = CreateMessageRuleRequest::decode(value.as_ref())?;
+        let rule = MqttMessageRule::decode(&req.content)?;
+        let storage = MqttMessageRuleStorage::new(self.rocksdb_engine_handler.clone());
+        storage.save(&rule)
+    }
+
+    pub fn delete_message_rule(&self, value: Bytes) -> Result<(), MetaServiceError> {
+        let req = DeleteMessageRuleRequest::decode(value.as_ref())?;
+        let storage = MqttMessageRuleStorage::new(self.rocksdb_engine_handler.clone());
+        storage.delete(&req.tenant, &req.name)
+    }
+
+    // SubscribeRoute
+    pub fn update_subscribe_route(&self, value: Bytes) -> Result<(), MetaServiceError> {
+        let req = UpdateSubscribeRouteRequest::decode(value.as_ref())?;
+        let storage = MqttSubscribeRouteStorage::new(self.rocksdb_engine_handler.clone());
+        for raw in req.remove.iter() {
+            let route = subscribe_route(req.broker_id, raw);
+            storage.delete(&route)?;
+            self.cache_manager.subscribe_route.remove(
+                &route.tenant,
+                &route.filter,
+                route.broker_id,
+            );
+        }
+        for raw in req.add.iter() {
+            let route = subscribe_route(req.broker_id, raw);
+            storage.save(&route)?;
+            self.cache_manager.subscribe_route.insert(
+                &route.tenant,
+                &route.filter,
+                route.broker_id,
+            );
+        }
+        Ok(())
+    }
+
+    pub fn sync_subscribe_route(&self, value: Bytes) -> Result<(), MetaServiceError> {
+        let req = SyncSubscribeRouteRequest::decode(value.as_ref())?;
+        let storage = MqttSubscribeRouteStorage::new(self.rocksdb_engine_handler.clone());
+        let routes: HashSet<MqttSubscribeRoute> = req
+            .routes
+            .iter()
+            .map(|raw| subscribe_route(req.broker_id, raw))
+            .collect();
+
+        let mut registered = HashSet::new();
+        for route in storage.list_by_node(req.broker_id)? {
+            if routes.contains(&route) {
+                registered.insert(route);
+                continue;
+            }
+            storage.delete(&route)?;
+            self.cache_manager.subscribe_route.remove(
+                &route.tenant,
+                &route.filter,
+                route.broker_id,
+            );
+        }
+        for route in routes.difference(&registered) {
+            storage.save(route)?;
+            self.cache_manager.subscribe_route.insert(
+                &route.tenant,
+                &route.filter,
+                route.broker_id,
+            );
+        }
+        Ok(())
+    }
+}
+
+fn subscribe_route(broker_id: u64, raw: &SubscribeRoute) -> MqttSubscribeRoute {
+    MqttSubscribeRoute {
+        broker_id,
+        tenant: raw.tenant.clone(),
+        filter: raw.filter.clone(),
+    }
 }
@@ -12,19 +12,38 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::pin::Pin;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use crate::raft::manager::MultiRaftManager;
+use crate::raft::type_config::TypeConfig;
 use crate::{core::error::MetaServiceError, raft::type_config::Node};
 use bincode::{deserialize, serialize};
+use common_config::broker::broker_config;
+use common_metrics::meta::raft::record_snapshot_chunk_received;
+use grpc_clients::meta::common::call::trigger_elect;
+use grpc_clients::pool::ClientPool;
+use openraft::raft::InstallSnapshotRequest;
+use openraft::{Raft, RaftMetrics};
+use prost_validate::Validator;
 use protocol::meta::meta_service_common::{
-    AppendReply, AppendRequest, JoinClusterReply, JoinClusterRequest, LeaveClusterReply,
-    LeaveClusterRequest, SnapshotReply, SnapshotRequest, VoteReply, VoteRequest,
+    AppendReply, AppendRequest, AppendStreamReply, AppendStreamRequest, JoinClusterReply,
+    JoinClusterRequest, LeaveClusterReply, LeaveClusterRequest, SnapshotReply, SnapshotRequest,
+    TransferLeaderReply, TransferLeaderRequest, TransferLeaderResult, TriggerElectReply,
+    TriggerElectRequest, VoteReply, VoteRequest,
 };
-use tracing::warn;
+use tokio::sync::mpsc;
+use tonic::codegen::tokio_stream::Stream;
+use tonic::{Status, Streaming};
+use tracing::{debug, info, warn};
 
 const SLOW_RAFT_HANDLER_THRESHOLD_MS: f64 = 500.0;
+const APPEND_STREAM_CHANNEL_SIZE: usize = 1024;
+const TRANSFER_LEADER_CATCH_UP_TIMEOUT: Duration = Duration::from_secs(10);
+// Must outlast the followers' leader lease (election_timeout_max).
+const TRANSFER_LEADER_TIMEOUT: Duration = Duration::from_secs(60);
+const TRANSFER_LEADER_RETRY_INTERVAL: Duration = Duration::from_secs(1);
 
 fn deserialize_from_slice<T: serde::de::DeserializeOwned>(
     bytes: &[u8],
@@ -84,16 +103,75 @@ pub async fn append_by_req(
     result
 }
 
+type AppendStream = Pin<Box<dyn Stream<Item = Result<AppendStreamReply, Status>> + Send>>;
+
+/// Serve the AppendEntries stream of one peer. Each request is handled on
+/// its own task, so a slow group does not hold up the others on the stream.
+pub fn append_stream_by_req(
+    raft_manager: &Arc<MultiRaftManager>,
+    mut inbound: Streaming<AppendStreamRequest>,
+) -> AppendStream {
+    let (reply_tx, mut reply_rx) = mpsc::channel::<AppendStreamReply>(APPEND_STREAM_CHANNEL_SIZE);
+    let raft_manager = raft_manager.clone();
+    tokio::spawn(async move {
+        loop {
+            let req = match inbound.message().await {
+                Ok(Some(req)) => req,
+                Ok(None) => break,
+                Err(status) => {
+                    debug!("AppendEntries stream from peer ended: {}", status);
+                    break;
+                }
+            };
+            let raft_manager = raft_manager.clone();
+            let reply_tx = reply_tx.clone();
+            tokio::spawn(async move {
+                let seq = req.seq;
+                let req = AppendRequest {
+                    machine: req.machine,
+                    value: req.value,
+                };
+                let result = match req.validate() {
+                    Ok(()) => append_by_req(&raft_manager, &req).await,
+                    Err(e) => Err(MetaServiceError::CommonError(e.to_string())),
+                };
+                let reply = match result {
+                    Ok(reply) => AppendStreamReply {
+                        seq,
+                        value: reply.value,
+                        error: String::new(),
+                    },
+                    Err(e) => AppendStreamReply {
+                        seq,
+                        value: Vec::new(),
+                        error: e.to_string(),
+                    },
+                };
+                let _ = reply_tx.send(reply).await;
+            });
+        }
+    });
+
+    let output = async_stream::stream! {
+        while let Some(reply) = reply_rx.recv().await {
+            yield Ok(reply);
+        }
+    };
+    Box::pin(output)
+}
+
 pub async fn snapshot_by_req(
     raft_manager: &Arc<MultiRaftManager>,
     req: &SnapshotRequest,
 ) -> Result<SnapshotReply, MetaServiceError> {
     let start = Instant::now();
-    let snapshot_data = deserialize_from_slice(&req.value)?;
+    let snapshot_data: InstallSnapshotRequest<TypeConfig> = deserialize_from_slice(&req.value)?;
+    let chunk_len = snapshot_data.data.len() as u64;
     let raft_node = raft_manager.get_raft_node(&req.machine)?;
     let result = raft_node
         .install_snapshot(snapshot_data)
         .await
+        .inspect(|_| record_snapshot_chunk_received(&req.machine, chunk_len))
         .map_err(|e| MetaServiceError::CommonError(e.to_string()))
         .and_then(|res| {
             serialize(&res)
@@ -258,3 +336,185 @@ pub async fn leave_cluster_by_req(
     tracing::info!("Node {} successfully left the cluster", node_id);
     Ok(LeaveClusterReply {})
 }
+
+/// Hand leadership of one shard, or of every shard led by this node, over to
+/// `target_node_id` so the node can be drained for maintenance.
+///
+/// Must be called on the current leader of the shards. openraft 0.9 has no
+/// native leadership transfer, so per shard the leader waits for the target to
+/// catch up on the log, stops sending heartbeats and asks the target to
+/// campaign until the followers' leader lease lapses and the target wins.
+/// Shards are handed over one at a time.
+pub async fn transfer_leader_by_req(
+    raft_manager: &Arc<MultiRaftManager>,
+    client_pool: &Arc<ClientPool>,
+    req: &TransferLeaderRequest,
+) -> Result<TransferLeaderReply, MetaServiceError> {
+    let local_id = broker_config().broker_id;
+    if req.target_node_id == local_id {
+        return Err(MetaServiceError::CommonError(format!(
+            "node {} cannot transfer leadership to itself",
+            local_id
+        )));
+    }
+
+    let shards: Vec<(String, Raft<TypeConfig>)> = if req.machine.is_empty() {
+        raft_manager
+            .all_shards()
+            .filter(|(_, raft)| raft.metrics().borrow().current_leader == Some(local_id))
+            .map(|(name, raft)| (name.clone(), raft.clone()))
+            .collect()
+    } else {
+        let raft = raft_manager.get_raft_node(&req.machine)?;
+        let current_leader = raft.metrics().borrow().current_leader;
+        if current_leader != Some(local_id) {
+            return Err(MetaServiceError::CommonError(format!(
+                "[{}] node {} is not the leader, current leader is {:?}",
+                req.machine, local_id, current_leader
+            )));
+        }
+        vec![(req.machine.clone(), raft.clone())]
+    };
+
+    let mut results = Vec::with_capacity(shards.len());
+    for (machine, raft) in shards {
+        let current_leader =
+            transfer_shard_leader(client_pool, &machine, &raft, local_id, req.target_node_id)
+                .await?;
+        info!(
+            "[{}] Leadership transferred from node {} to node {}",
+            machine, local_id, current_leader
+        );
+        results.push(TransferLeaderResult {
+            machine,
+            previous_leader: local_id,
+            current_leader,
+        });
+    }
+
+    Ok(TransferLeaderReply { results })
+}
+
+async fn transfer_shard_leader(
+    client_pool: &Arc<ClientPool>,
+    machine: &str,
+    raft: &Raft<TypeConfig>,
+    local_id: u64,
+    target_node_id: u64,
+) -> Result<u64, MetaServiceError> {
+    let metrics = raft.metrics().borrow().clone();
+    let target_addr = target_rpc_addr(&metrics, target_node_id).ok_or_else(|| {
+        MetaServiceError::CommonError(format!(
+            "[{}] node {} is not a voter of the shard",
+            machine, target_node_id
+        ))
+    })?;
+
+    // A lagging target would lose the election to an up-to-date follower.
+    let last_log_index = metrics.last_log_index;
+    raft.wait(Some(TRANSFER_LEADER_CATCH_UP_TIMEOUT))
+        .metrics(
+            |m| replication_caught_up(m, target_node_id, last_log_index),
+            "transfer leader target caught up",
+        )
+        .await
+        .map_err(|e| {
+            MetaServiceError::CommonError(format!(
+                "[{}] node {} did not catch up on the log: {}",
+                machine, target_node_id, e
+            ))
+        })?;
+
+    raft.runtime_config().heartbeat(false);
+    let result = hand_over(
+        client_pool,
+        machine,
+        raft,
+        local_id,
+        target_node_id,
+        &target_addr,
+    )
+    .await;
+    raft.runtime_config().heartbeat(true);
+    result
+}
+
+async fn hand_over(
+    client_pool: &Arc<ClientPool>,
+    machine: &str,
+    raft: &Raft<TypeConfig>,
+    local_id: u64,
+    target_node_id: u64,
+    target_addr: &str,
+) -> Result<u64, MetaServiceError> {
+    let deadline = Instant::now() + TRANSFER_LEADER_TIMEOUT;
+    while Instant::now() < deadline {
+        let req = TriggerElectRequest {
+            machine: machine.to_string(),
+        };
+        if let Err(e) = trigger_elect(client_pool, &[target_addr], req).await {
+            warn!(
+                "[{}] Failed to ask node {} to campaign: {}",
+                machine, target_node_id, e
+            );
+        }
+
+        if let Ok(metrics) = raft
+            .wait(Some(TRANSFER_LEADER_RETRY_INTERVAL))
+            .metrics(
+                |m| m.current_leader.is_some() && m.current_leader != Some(local_id),
+                "leadership moved",
+            )
+            .await
+        {
+            // Another follower may have won the race; the node is drained either way.
+            return Ok(metrics.current_leader.unwrap_or(target_node_id));
+        }
+    }
+
+    Err(MetaServiceError::CommonError(format!(
+        "[{}] leadership did not move to node {} within {}s",
+        machine,
+        target_node_id,
+        TRANSFER_LEADER_TIMEOUT.as_secs()
+    )))
+}
+
+fn target_rpc_addr(metrics: &RaftMetrics<u64, Node>, target_node_id: u64) -> Option<String> {
+    let membership = metrics.membership_config.membership();
+    if !membership.voter_ids().any(|id| id == target_node_id) {
+        return None;
+    }
+    membership
+        .get_node(&target_node_id)
+        .map(|node| node.rpc_addr.clone())
+}
+
+fn replication_caught_up(
+    metrics: &RaftMetrics<u64, Node>,
+    target_node_id: u64,
+    last_log_index: Option<u64>,
+) -> bool {
+    let matched = metrics
+        .replication
+        .as_ref()
+        .and_then(|replication| replication.get(&target_node_id))
+        .and_then(|log_id| log_id.map(|log_id| log_id.index));
+    matched >= last_log_index
+}
+
+/// Ask this node to campaign for `machine` right away. Sent by the shard leader
+/// while it hands leadership over.
+pub async fn trigger_elect_by_req(
+    raft_manager: &Arc<MultiRaftManager>,
+    req: &TriggerElectRequest,
+) -> Result<TriggerElectReply, MetaServiceError> {
+    let raft_node = raft_manager.get_raft_node(&req.machine)?;
+    raft_node.trigger().elect().await.map_err(|e| {
+        MetaServiceError::CommonError(format!(
+            "[{}] Failed to trigger election: {}",
+            req.machine, e
+        ))
+    })?;
+    Ok(TriggerElectReply {})
+}
@@ -17,7 +17,7 @@
 };
 use crate::raft::type_config::{NodeId, StorageResult, TypeConfig};
 use bincode::{deserialize, serialize};
-use common_metrics::meta::raft::record_log_append_batch_duration;
+use common_metrics::meta::raft::{record_log_append_batch_duration, record_raft_log_purge};
 use openraft::storage::{LogFlushed, RaftLogStorage};
 use openraft::{
     AnyError, Entry, LogId, LogState, OptionalSend, RaftLogReader, StorageError, StorageIOError,
@@ -132,6 +132,30 @@ fn set_vote_(&self, vote: &Vote<NodeId>) -> StorageResult<()> {
         Ok(())
     }
 
+    /// Number of entries and total encoded size in bytes of this shard's log.
+    pub fn log_size(&self) -> StorageResult<(u64, u64)> {
+        let start = key_raft_log(&self.machine, 0);
+        let end = key_raft_log(&self.machine, u64::MAX);
+
+        // total_order_seek (see try_get_log_entries), bounded to this shard.
+        let mut read_opts = ReadOptions::default();
+        read_opts.set_total_order_seek(true);
+        read_opts.set_iterate_upper_bound(end);
+
+        let mut entries = 0;
+        let mut bytes = 0;
+        for item in self.db.iterator_cf_opt(
+            &self.store(),
+            read_opts,
+            IteratorMode::From(&start, Direction::Forward),
+        ) {
+            let (key, val) = item.map_err(|e| sto_read_logs(&e))?;
+            entries += 1;
+            bytes += (key.len() + val.len()) as u64;
+        }
+        Ok((entries, bytes))
+    }
+
     fn get_vote_(&self) -> StorageResult<Option<Vote<NodeId>>> {
         match self.db.get_cf(&self.store(), key_vote(&self.machine)) {
             Ok(Some(v)) => {
@@ -289,6 +313,7 @@ async fn purge(&mut self, log_id: LogId<NodeId>) -> Result<(), StorageError<Node
             .map_err(|e| sto_write_logs(&e))?;
 
         self.set_last_purged_(log_id)?;
+        record_raft_log_purge(&self.machine);
 
         Ok(())
     }
@@ -560,4 +585,40 @@ fn test_multiple_machines_isolation() {
         assert_eq!(result1.index, 100);
         assert_eq!(result2.index, 200);
     }
+
+    #[tokio::test]
+    async fn test_log_size() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        let db = Arc::new(
+            DB::open_cf(&opts, temp_dir.path(), vec![DB_COLUMN_FAMILY_META_RAFT]).unwrap(),
+        );
+        let mut log_store1 = LogStore {
+            machine: "machine1".to_string(),
+            db: db.clone(),
+        };
+        let mut log_store2 = LogStore {
+            machine: "machine2".to_string(),
+            db: db.clone(),
+        };
+        assert_eq!(log_store1.log_size().unwrap(), (0, 0));
+
+        let entries: Vec<_> = (1..=10).map(|i| create_entry(1, 1, i)).collect();
+        append_entries(&mut log_store1, entries).await.unwrap();
+        let entries: Vec<_> = (1..=3).map(|i| create_entry(1, 1, i)).collect();
+        append_entries(&mut log_store2, entries).await.unwrap();
+
+        let (entries, bytes) = log_store1.log_size().unwrap();
+        assert_eq!(entries, 10);
+        assert!(bytes > 10 * 17);
+        assert_eq!(log_store2.log_size().unwrap().0, 3);
+
+        log_store1.purge(create_log_id(1, 1, 4)).await.unwrap();
+        let (entries, purged_bytes) = log_store1.log_size().unwrap();
+        assert_eq!(entries, 6);
+        assert!(purged_bytes < bytes);
+        assert_eq!(log_store2.log_size().unwrap().0, 3);
+    }
 }
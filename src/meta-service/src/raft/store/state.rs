@@ -265,6 +265,15 @@ async fn install_snapshot(
             },
         )
         .await?;
+        self.data.route.invalidate_list_cache();
+        self.data
+            .route
+            .reload_subscribe_route()
+            .map_err(|e| sto_read_msg(format!("Failed to reload subscribe routes: {}", e)))?;
+        self.data
+            .route
+            .reload_node_cordon()
+            .map_err(|e| sto_read_msg(format!("Failed to reload node cordons: {}", e)))?;
 
         // After importing the snapshot data, the state machine now reflects the
         // snapshot's coverage. Persist last_applied / last_membership so that on
@@ -0,0 +1,272 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Authorization of the meta service gRPC services.
+//!
+//! Every RPC needs one [`MetaPermission`]; a caller's role, resolved from its
+//! bearer token or mutual TLS SPIFFE id, grants a set of permissions:
+//!
+//! | Role          | Read | Write | Node |
+//! |---------------|------|-------|------|
+//! | `admin`       | yes  | yes   |      |
+//! | `broker_node` | yes  | yes   | yes  |
+//! | `read_only`   | yes  |       |      |
+
+use common_config::config::{MetaAuthRole, MetaServiceAuth};
+use subtle::ConstantTimeEq;
+use tonic::Status;
+
+const META_SERVICE_PREFIX: &str = "meta.service.";
+const BEARER_PREFIX: &str = "Bearer ";
+
+const READ_METHOD_PREFIXES: [&str; 5] = ["List", "Get", "Exists", "Search", "Watch"];
+const READ_METHODS: [&str; 4] = [
+    "ClusterStatus",
+    "NodeList",
+    "BootstrapCache",
+    "CheckResourceDigest",
+];
+const NODE_METHODS: [&str; 16] = [
+    "RegisterNode",
+    "UnRegisterNode",
+    "Heartbeat",
+    "ReportMonitor",
+    "ConnectorHeartbeat",
+    "CommitConnectorCheckpoint",
+    "UpdateSubscribeRoute",
+    "SyncSubscribeRoute",
+    "ConsumerGroupHeartbeat",
+    "LeaveConsumerGroup",
+    "JoinCluster",
+    "Vote",
+    "Append",
+    "AppendStream",
+    "Snapshot",
+    "TriggerElect",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetaPermission {
+    /// Listing and reading metadata.
+    Read,
+    /// Creating, updating and deleting metadata.
+    Write,
+    /// Node lifecycle and raft traffic, only issued by cluster members.
+    Node,
+}
+
+/// gRPC services served by the meta service, by their full proto name.
+pub fn is_meta_service(service: &str) -> bool {
+    service.starts_with(META_SERVICE_PREFIX)
+}
+
+pub fn required_permission(method: &str) -> MetaPermission {
+    if NODE_METHODS.contains(&method) {
+        return MetaPermission::Node;
+    }
+    if READ_METHODS.contains(&method)
+        || READ_METHOD_PREFIXES
+            .iter()
+            .any(|prefix| method.starts_with(prefix))
+    {
+        return MetaPermission::Read;
+    }
+    MetaPermission::Write
+}
+
+pub fn role_allows(role: MetaAuthRole, permission: MetaPermission) -> bool {
+    match role {
+        MetaAuthRole::BrokerNode => true,
+        MetaAuthRole::Admin => permission != MetaPermission::Node,
+        MetaAuthRole::ReadOnly => permission == MetaPermission::Read,
+    }
+}
+
+/// Resolve the caller role from the `authorization` header value and the
+/// SPIFFE ids of a verified mutual TLS peer. A token wins over the peer id.
+pub fn authenticate(
+    conf: &MetaServiceAuth,
+    authorization: Option<&str>,
+    peer_ids: &[String],
+) -> Option<MetaAuthRole> {
+    if let Some(token) = authorization.and_then(|value| value.strip_prefix(BEARER_PREFIX)) {
+        if !conf.node_token.is_empty() && token_eq(token, &conf.node_token) {
+            return Some(MetaAuthRole::BrokerNode);
+        }
+        // compare against every entry so the time taken does not reveal
+        // which configured token matched
+        return conf.tokens.iter().fold(None, |role, entry| {
+            if token_eq(token, &entry.token) {
+                role.or(Some(entry.role))
+            } else {
+                role
+            }
+        });
+    }
+
+    peer_ids
+        .iter()
+        .find_map(|id| conf.peer_roles.get(id).copied())
+}
+
+/// Constant-time token comparison, so a caller cannot guess a token byte
+/// by byte from response times.
+fn token_eq(presented: &str, configured: &str) -> bool {
+    presented.as_bytes().ct_eq(configured.as_bytes()).into()
+}
+
+/// Check one call of `service`/`method`. Calls to other services pass.
+pub fn authorize(
+    conf: &MetaServiceAuth,
+    service: &str,
+    method: &str,
+    authorization: Option<&str>,
+    peer_ids: &[String],
+) -> Result<(), Status> {
+    if !conf.enable || !is_meta_service(service) {
+        return Ok(());
+    }
+
+    let Some(role) = authenticate(conf, authorization, peer_ids) else {
+        return Err(Status::unauthenticated(format!(
+            "{service}/{method} requires a valid token or client certificate"
+        )));
+    };
+
+    let permission = required_permission(method);
+    if !role_allows(role, permission) {
+        return Err(Status::permission_denied(format!(
+            "role {role:?} is not allowed to call {service}/{method}"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common_config::config::MetaAuthToken;
+    use std::collections::HashMap;
+    use tonic::Code;
+
+    const COMMON: &str = "meta.service.common.MetaServiceService";
+    const MQTT: &str = "meta.service.mqtt.MqttService";
+
+    fn test_config() -> MetaServiceAuth {
+        MetaServiceAuth {
+            enable: true,
+            node_token: "node-secret".to_string(),
+            tokens: vec![
+                MetaAuthToken {
+                    name: "ops".to_string(),
+                    token: "ops-secret".to_string(),
+                    role: MetaAuthRole::Admin,
+                },
+                MetaAuthToken {
+                    name: "monitor".to_string(),
+                    token: "monitor-secret".to_string(),
+                    role: MetaAuthRole::ReadOnly,
+                },
+            ],
+            peer_roles: HashMap::from([(
+                "spiffe://robustmq/broker".to_string(),
+                MetaAuthRole::BrokerNode,
+            )]),
+        }
+    }
+
+    #[test]
+    fn method_permissions() {
+        assert_eq!(required_permission("ListUser"), MetaPermission::Read);
+        assert_eq!(required_permission("GetPrefix"), MetaPermission::Read);
+        assert_eq!(required_permission("BootstrapCache"), MetaPermission::Read);
+        assert_eq!(required_permission("CreateUser"), MetaPermission::Write);
+        assert_eq!(required_permission("DeleteTopic"), MetaPermission::Write);
+        assert_eq!(required_permission("LeaveCluster"), MetaPermission::Write);
+        assert_eq!(required_permission("CordonNode"), MetaPermission::Write);
+        assert_eq!(required_permission("TransferLeader"), MetaPermission::Write);
+        assert_eq!(required_permission("TriggerElect"), MetaPermission::Node);
+        assert_eq!(required_permission("AcquireLock"), MetaPermission::Write);
+        assert_eq!(required_permission("WatchLock"), MetaPermission::Read);
+        assert_eq!(
+            required_permission("CheckResourceDigest"),
+            MetaPermission::Read
+        );
+        assert_eq!(required_permission("ListNodeStatus"), MetaPermission::Read);
+        assert_eq!(required_permission("Heartbeat"), MetaPermission::Node);
+        assert_eq!(required_permission("GetConfigDrift"), MetaPermission::Read);
+        assert_eq!(required_permission("Append"), MetaPermission::Node);
+        assert_eq!(required_permission("AppendStream"), MetaPermission::Node);
+        assert_eq!(
+            required_permission("ConsumerGroupHeartbeat"),
+            MetaPermission::Node
+        );
+        assert_eq!(
+            required_permission("LeaveConsumerGroup"),
+            MetaPermission::Node
+        );
+        assert_eq!(
+            required_permission("SyncSubscribeRoute"),
+            MetaPermission::Node
+        );
+        assert_eq!(
+            required_permission("GetNodesForTopic"),
+            MetaPermission::Read
+        );
+        assert_eq!(
+            required_permission("CommitConnectorCheckpoint"),
+            MetaPermission::Node
+        );
+    }
+
+    #[test]
+    fn role_permissions() {
+        assert!(role_allows(MetaAuthRole::BrokerNode, MetaPermission::Node));
+        assert!(role_allows(MetaAuthRole::Admin, MetaPermission::Write));
+        assert!(!role_allows(MetaAuthRole::Admin, MetaPermission::Node));
+        assert!(role_allows(MetaAuthRole::ReadOnly, MetaPermission::Read));
+        assert!(!role_allows(MetaAuthRole::ReadOnly, MetaPermission::Write));
+    }
+
+    #[test]
+    fn authorize_calls() {
+        let conf = test_config();
+        let peer = vec!["spiffe://robustmq/broker".to_string()];
+
+        assert!(authorize(&conf, MQTT, "CreateUser", Some("Bearer node-secret"), &[]).is_ok());
+        assert!(authorize(&conf, MQTT, "CreateUser", Some("Bearer ops-secret"), &[]).is_ok());
+        assert!(authorize(&conf, MQTT, "ListUser", Some("Bearer monitor-secret"), &[]).is_ok());
+        assert!(authorize(&conf, COMMON, "Heartbeat", None, &peer).is_ok());
+
+        let denied = authorize(
+            &conf,
+            MQTT,
+            "DeleteTopic",
+            Some("Bearer monitor-secret"),
+            &[],
+        );
+        assert_eq!(denied.unwrap_err().code(), Code::PermissionDenied);
+        let denied = authorize(&conf, COMMON, "Vote", Some("Bearer ops-secret"), &peer);
+        assert_eq!(denied.unwrap_err().code(), Code::PermissionDenied);
+
+        let missing = authorize(&conf, MQTT, "CreateUser", None, &[]);
+        assert_eq!(missing.unwrap_err().code(), Code::Unauthenticated);
+        let unknown = authorize(&conf, MQTT, "ListUser", Some("Bearer guess"), &peer);
+        assert_eq!(unknown.unwrap_err().code(), Code::Unauthenticated);
+
+        // other services and a disabled config are not checked
+        assert!(authorize(&conf, "broker.BrokerService", "UpdateCache", None, &[]).is_ok());
+        let disabled = MetaServiceAuth::default();
+        assert!(authorize(&disabled, MQTT, "CreateUser", None, &[]).is_ok());
+    }
+}
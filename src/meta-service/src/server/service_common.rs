@@ -17,15 +17,27 @@
 use crate::core::isr_recovery::recover_unavailable_segments_on_node_join;
 use crate::raft::manager::MultiRaftManager;
 use crate::raft::services::{
-    append_by_req, join_cluster_by_req, leave_cluster_by_req, snapshot_by_req, vote_by_req,
+    append_by_req, append_stream_by_req, join_cluster_by_req, leave_cluster_by_req,
+    snapshot_by_req, transfer_leader_by_req, trigger_elect_by_req, vote_by_req,
 };
+use crate::server::services::common::bootstrap::bootstrap_cache_by_req;
+use crate::server::services::common::digest::check_resource_digest_by_req;
 use crate::server::services::common::inner::{
-    cluster_status_by_req, delete_resource_config_by_req, get_offset_data_by_req,
-    get_resource_config_by_req, heartbeat_by_req, node_list_by_req, save_offset_data_by_req,
-    set_resource_config_by_req,
+    cluster_status_by_req, consumer_group_heartbeat_by_req, cordon_node_by_req,
+    delete_offset_group_by_req, delete_resource_config_by_req, get_config_drift_by_req,
+    get_offset_data_by_req, get_resource_config_by_req, heartbeat_by_req,
+    leave_consumer_group_by_req, list_node_status_by_req, list_offset_group_by_req,
+    node_list_by_req, reset_offset_data_by_req, save_offset_data_by_req,
+    set_resource_config_by_req, uncordon_node_by_req,
 };
 use crate::server::services::common::kv::{
-    delete_by_req, exists_by_req, get_by_req, get_prefix_by_req, set_by_req,
+    compare_and_set_by_req, delete_by_req, exists_by_req, get_by_req, get_prefix_by_req, set_by_req,
+};
+use crate::server::services::common::lock::{
+    acquire_lock_by_req, release_lock_by_req, renew_lock_by_req, watch_lock_by_req,
+};
+use crate::server::services::common::metadata_dump::{
+    export_metadata_by_req, import_metadata_by_req,
 };
 use crate::server::services::common::schema::{
     bind_schema_req, create_schema_req, delete_schema_req, list_bind_schema_req, list_schema_req,
@@ -34,6 +46,7 @@
 use crate::server::services::common::tenant::{
     create_tenant_by_req, delete_tenant_by_req, list_tenant_by_req, update_tenant_by_req,
 };
+use crate::server::services::common::watch::watch_resources_by_req;
 use crate::server::services::mqtt::share_group::{
     add_share_group_member_by_req, create_share_group_by_req, delete_share_group_by_req,
     delete_share_group_member_by_req, list_share_group_by_req, list_share_group_member_by_req,
@@ -44,30 +57,41 @@
 use prost_validate::Validator;
 use protocol::meta::meta_service_common::meta_service_service_server::MetaServiceService;
 use protocol::meta::meta_service_common::{
-    AddShareGroupMemberReply, AddShareGroupMemberRequest, AppendReply, AppendRequest,
-    BindSchemaReply, BindSchemaRequest, ClusterStatusReply, ClusterStatusRequest,
-    CreateSchemaReply, CreateSchemaRequest, CreateShareGroupReply, CreateShareGroupRequest,
-    CreateTenantReply, CreateTenantRequest, DeleteReply, DeleteRequest, DeleteResourceConfigReply,
-    DeleteResourceConfigRequest, DeleteSchemaReply, DeleteSchemaRequest,
+    AcquireLockReply, AcquireLockRequest, AddShareGroupMemberReply, AddShareGroupMemberRequest,
+    AppendReply, AppendRequest, AppendStreamReply, AppendStreamRequest, BindSchemaReply,
+    BindSchemaRequest, BootstrapCacheReply, BootstrapCacheRequest, CheckResourceDigestReply,
+    CheckResourceDigestRequest, ClusterStatusReply, ClusterStatusRequest, CompareAndSetReply,
+    CompareAndSetRequest, ConsumerGroupHeartbeatReply, ConsumerGroupHeartbeatRequest,
+    CordonNodeReply, CordonNodeRequest, CreateSchemaReply, CreateSchemaRequest,
+    CreateShareGroupReply, CreateShareGroupRequest, CreateTenantReply, CreateTenantRequest,
+    DeleteOffsetGroupReply, DeleteOffsetGroupRequest, DeleteReply, DeleteRequest,
+    DeleteResourceConfigReply, DeleteResourceConfigRequest, DeleteSchemaReply, DeleteSchemaRequest,
     DeleteShareGroupMemberReply, DeleteShareGroupMemberRequest, DeleteShareGroupReply,
     DeleteShareGroupRequest, DeleteTenantReply, DeleteTenantRequest, ExistsReply, ExistsRequest,
+    ExportMetadataReply, ExportMetadataRequest, GetConfigDriftReply, GetConfigDriftRequest,
     GetOffsetDataReply, GetOffsetDataRequest, GetPrefixReply, GetPrefixRequest, GetReply,
     GetRequest, GetResourceConfigReply, GetResourceConfigRequest, HeartbeatReply, HeartbeatRequest,
-    JoinClusterReply, JoinClusterRequest, LeaveClusterReply, LeaveClusterRequest,
-    ListBindSchemaReply, ListBindSchemaRequest, ListSchemaReply, ListSchemaRequest,
+    ImportMetadataReply, ImportMetadataRequest, JoinClusterReply, JoinClusterRequest,
+    LeaveClusterReply, LeaveClusterRequest, LeaveConsumerGroupReply, LeaveConsumerGroupRequest,
+    ListBindSchemaReply, ListBindSchemaRequest, ListNodeStatusReply, ListNodeStatusRequest,
+    ListOffsetGroupReply, ListOffsetGroupRequest, ListSchemaReply, ListSchemaRequest,
     ListShareGroupMemberReply, ListShareGroupMemberRequest, ListShareGroupReply,
     ListShareGroupRequest, ListTenantReply, ListTenantRequest, NodeListReply, NodeListRequest,
-    RegisterNodeReply, RegisterNodeRequest, ReportMonitorReply, ReportMonitorRequest,
-    SaveOffsetDataReply, SaveOffsetDataRequest, SetReply, SetRequest, SetResourceConfigReply,
-    SetResourceConfigRequest, SnapshotReply, SnapshotRequest, UnBindSchemaReply,
-    UnBindSchemaRequest, UnRegisterNodeReply, UnRegisterNodeRequest, UpdateSchemaReply,
-    UpdateSchemaRequest, UpdateTenantReply, UpdateTenantRequest, VoteReply, VoteRequest,
+    RegisterNodeReply, RegisterNodeRequest, ReleaseLockReply, ReleaseLockRequest, RenewLockReply,
+    RenewLockRequest, ReportMonitorReply, ReportMonitorRequest, ResetOffsetDataReply,
+    ResetOffsetDataRequest, SaveOffsetDataReply, SaveOffsetDataRequest, SetReply, SetRequest,
+    SetResourceConfigReply, SetResourceConfigRequest, SnapshotReply, SnapshotRequest,
+    TransferLeaderReply, TransferLeaderRequest, TriggerElectReply, TriggerElectRequest,
+    UnBindSchemaReply, UnBindSchemaRequest, UnRegisterNodeReply, UnRegisterNodeRequest,
+    UncordonNodeReply, UncordonNodeRequest, UpdateSchemaReply, UpdateSchemaRequest,
+    UpdateTenantReply, UpdateTenantRequest, VoteReply, VoteRequest, WatchLockReply,
+    WatchLockRequest, WatchResourcesReply, WatchResourcesRequest,
 };
 use rocksdb_engine::rocksdb::RocksDBEngine;
 use std::pin::Pin;
 use std::sync::Arc;
 use tonic::codegen::tokio_stream::Stream;
-use tonic::{Request, Response, Status};
+use tonic::{Request, Response, Status, Streaming};
 
 pub struct GrpcPlacementService {
     raft_manager: Arc<MultiRaftManager>,
@@ -112,6 +136,11 @@ impl MetaServiceService for GrpcPlacementService {
     type ListBindSchemaStream =
         Pin<Box<dyn Stream<Item = Result<ListBindSchemaReply, Status>> + Send>>;
     type ListTenantStream = Pin<Box<dyn Stream<Item = Result<ListTenantReply, Status>> + Send>>;
+    type BootstrapCacheStream =
+        Pin<Box<dyn Stream<Item = Result<BootstrapCacheReply, Status>> + Send>>;
+    type WatchResourcesStream =
+        Pin<Box<dyn Stream<Item = Result<WatchResourcesReply, Status>> + Send>>;
+    type AppendStreamStream = Pin<Box<dyn Stream<Item = Result<AppendStreamReply, Status>> + Send>>;
 
     // Cluster
     async fn cluster_status(
@@ -194,6 +223,45 @@ async fn un_register_node(
         .map(Response::new)
     }
 
+    async fn cordon_node(
+        &self,
+        request: Request<CordonNodeRequest>,
+    ) -> Result<Response<CordonNodeReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        cordon_node_by_req(&self.cluster_cache, &self.raft_manager, &req)
+            .await
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
+    async fn uncordon_node(
+        &self,
+        request: Request<UncordonNodeRequest>,
+    ) -> Result<Response<UncordonNodeReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        uncordon_node_by_req(&self.cluster_cache, &self.raft_manager, &req)
+            .await
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
+    async fn list_node_status(
+        &self,
+        request: Request<ListNodeStatusRequest>,
+    ) -> Result<Response<ListNodeStatusReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        list_node_status_by_req(&self.cluster_cache, &req)
+            .await
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
     // Heartbeat
     async fn heartbeat(
         &self,
@@ -202,7 +270,20 @@ async fn heartbeat(
         let req = request.into_inner();
         self.validate_request(&req)?;
 
-        heartbeat_by_req(&self.cluster_cache, &req)
+        heartbeat_by_req(&self.cluster_cache, &self.mqtt_call_manager, &req)
+            .await
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
+    async fn get_config_drift(
+        &self,
+        request: Request<GetConfigDriftRequest>,
+    ) -> Result<Response<GetConfigDriftReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        get_config_drift_by_req(&self.cluster_cache, &req)
             .await
             .map_err(Self::to_status)
             .map(Response::new)
@@ -286,6 +367,81 @@ async fn get_offset_data(
             .map(Response::new)
     }
 
+    async fn list_offset_group(
+        &self,
+        request: Request<ListOffsetGroupRequest>,
+    ) -> Result<Response<ListOffsetGroupReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        list_offset_group_by_req(&self.rocksdb_engine_handler, &self.cluster_cache, &req)
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
+    async fn delete_offset_group(
+        &self,
+        request: Request<DeleteOffsetGroupRequest>,
+    ) -> Result<Response<DeleteOffsetGroupReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        delete_offset_group_by_req(
+            &self.raft_manager,
+            &self.rocksdb_engine_handler,
+            &self.cluster_cache,
+            &self.mqtt_call_manager,
+            &req,
+        )
+        .await
+        .map_err(Self::to_status)
+        .map(Response::new)
+    }
+
+    async fn reset_offset_data(
+        &self,
+        request: Request<ResetOffsetDataRequest>,
+    ) -> Result<Response<ResetOffsetDataReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        reset_offset_data_by_req(
+            &self.raft_manager,
+            &self.rocksdb_engine_handler,
+            &self.cluster_cache,
+            &self.mqtt_call_manager,
+            &req,
+        )
+        .await
+        .map_err(Self::to_status)
+        .map(Response::new)
+    }
+
+    // Consumer Group
+    async fn consumer_group_heartbeat(
+        &self,
+        request: Request<ConsumerGroupHeartbeatRequest>,
+    ) -> Result<Response<ConsumerGroupHeartbeatReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        consumer_group_heartbeat_by_req(&self.cluster_cache, &req)
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
+    async fn leave_consumer_group(
+        &self,
+        request: Request<LeaveConsumerGroupRequest>,
+    ) -> Result<Response<LeaveConsumerGroupReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        leave_consumer_group_by_req(&self.cluster_cache, &req)
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
     // Schema
     async fn list_schema(
         &self,
@@ -468,6 +624,78 @@ async fn list_tenant(
             .map(Response::new)
     }
 
+    // Cache bootstrap
+    async fn bootstrap_cache(
+        &self,
+        request: Request<BootstrapCacheRequest>,
+    ) -> Result<Response<Self::BootstrapCacheStream>, Status> {
+        let req = request.into_inner();
+
+        bootstrap_cache_by_req(&self.rocksdb_engine_handler, &req)
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
+    // Metadata watch
+    async fn watch_resources(
+        &self,
+        request: Request<WatchResourcesRequest>,
+    ) -> Result<Response<Self::WatchResourcesStream>, Status> {
+        let req = request.into_inner();
+
+        watch_resources_by_req(&self.cluster_cache, &req)
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
+    async fn check_resource_digest(
+        &self,
+        request: Request<CheckResourceDigestRequest>,
+    ) -> Result<Response<CheckResourceDigestReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        check_resource_digest_by_req(
+            &self.rocksdb_engine_handler,
+            self.mqtt_call_manager.broker_cache(),
+            &req,
+        )
+        .map_err(Self::to_status)
+        .map(Response::new)
+    }
+
+    // Metadata dump
+    async fn export_metadata(
+        &self,
+        request: Request<ExportMetadataRequest>,
+    ) -> Result<Response<ExportMetadataReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        export_metadata_by_req(&self.rocksdb_engine_handler, &req)
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
+    async fn import_metadata(
+        &self,
+        request: Request<ImportMetadataRequest>,
+    ) -> Result<Response<ImportMetadataReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        import_metadata_by_req(
+            &self.raft_manager,
+            &self.mqtt_call_manager,
+            &self.cluster_cache,
+            &self.rocksdb_engine_handler,
+            &req,
+        )
+        .await
+        .map_err(Self::to_status)
+        .map(Response::new)
+    }
+
     // KV Operations
     async fn set(&self, request: Request<SetRequest>) -> Result<Response<SetReply>, Status> {
         let req = request.into_inner();
@@ -528,6 +756,72 @@ async fn get_prefix(
             .map(Response::new)
     }
 
+    async fn compare_and_set(
+        &self,
+        request: Request<CompareAndSetRequest>,
+    ) -> Result<Response<CompareAndSetReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        compare_and_set_by_req(&self.raft_manager, &req)
+            .await
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
+    // Lock
+    async fn acquire_lock(
+        &self,
+        request: Request<AcquireLockRequest>,
+    ) -> Result<Response<AcquireLockReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        acquire_lock_by_req(&self.raft_manager, &req)
+            .await
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
+    async fn renew_lock(
+        &self,
+        request: Request<RenewLockRequest>,
+    ) -> Result<Response<RenewLockReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        renew_lock_by_req(&self.raft_manager, &req)
+            .await
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
+    async fn release_lock(
+        &self,
+        request: Request<ReleaseLockRequest>,
+    ) -> Result<Response<ReleaseLockReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        release_lock_by_req(&self.raft_manager, &req)
+            .await
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
+    async fn watch_lock(
+        &self,
+        request: Request<WatchLockRequest>,
+    ) -> Result<Response<WatchLockReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        watch_lock_by_req(&self.rocksdb_engine_handler, &req)
+            .await
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
     // Raft Internal
     async fn append(
         &self,
@@ -542,6 +836,16 @@ async fn append(
             .map(Response::new)
     }
 
+    async fn append_stream(
+        &self,
+        request: Request<Streaming<AppendStreamRequest>>,
+    ) -> Result<Response<Self::AppendStreamStream>, Status> {
+        Ok(Response::new(append_stream_by_req(
+            &self.raft_manager,
+            request.into_inner(),
+        )))
+    }
+
     async fn snapshot(
         &self,
         request: Request<SnapshotRequest>,
@@ -577,6 +881,32 @@ async fn leave_cluster(
             .map(Response::new)
     }
 
+    async fn transfer_leader(
+        &self,
+        request: Request<TransferLeaderRequest>,
+    ) -> Result<Response<TransferLeaderReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        transfer_leader_by_req(&self.raft_manager, &self.client_pool, &req)
+            .await
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
+    async fn trigger_elect(
+        &self,
+        request: Request<TriggerElectRequest>,
+    ) -> Result<Response<TriggerElectReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        trigger_elect_by_req(&self.raft_manager, &req)
+            .await
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
     async fn vote(&self, request: Request<VoteRequest>) -> Result<Response<VoteReply>, Status> {
         let req = request.into_inner();
         self.validate_request(&req)?;
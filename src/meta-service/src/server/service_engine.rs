@@ -93,6 +93,7 @@ fn to_status(e: MetaServiceError) -> Status {
             | MetaServiceError::InvalidSegmentLessThan(_, _) => Status::invalid_argument(msg),
 
             MetaServiceError::NotEnoughEngineNodes(_, _, _)
+            | MetaServiceError::NotEnoughZones(_, _, _)
             | MetaServiceError::ShardHasEnoughSegment(_)
             | MetaServiceError::NumberOfReplicasIsIncorrect(_, _)
             | MetaServiceError::NoAvailableBrokerNode
@@ -13,28 +13,41 @@
 // limitations under the License.
 
 use crate::core::cache::MetaCacheManager;
+use crate::core::error::MetaServiceError;
 use crate::raft::manager::MultiRaftManager;
 use crate::server::services::mqtt::acl::{
     create_acl_by_req, create_blacklist_by_req, delete_acl_by_req, delete_blacklist_by_req,
     list_acl_by_req, list_blacklist_by_req,
 };
 use crate::server::services::mqtt::connector::{
-    connector_heartbeat_by_req, create_connector_by_req, delete_connector_by_req,
-    list_connectors_by_req, update_connector_by_req,
+    commit_connector_checkpoint_by_req, connector_heartbeat_by_req, create_connector_by_req,
+    delete_connector_by_req, get_connector_checkpoint_by_req, list_connectors_by_req,
+    update_connector_by_req,
+};
+use crate::server::services::mqtt::message_rule::{
+    create_message_rule_by_req, delete_message_rule_by_req, list_message_rule_by_req,
+};
+use crate::server::services::mqtt::retain::{
+    delete_retain_index_by_req, list_retain_index_by_req, set_retain_index_by_req,
 };
 use crate::server::services::mqtt::session::{
-    create_session_by_req, delete_session_by_req, list_session_by_req,
+    allocate_last_will_epoch_by_req, claim_last_will_by_req, create_session_by_req,
+    delete_session_by_req, list_session_by_req,
 };
 use crate::server::services::mqtt::subscribe::{
     create_auto_subscribe_rule_by_req, delete_auto_subscribe_rule_by_req, delete_subscribe_by_req,
     list_auto_subscribe_rule_by_req, list_subscribe_by_req, set_subscribe_by_req,
 };
+use crate::server::services::mqtt::subscribe_route::{
+    get_nodes_for_topic_by_req, sync_subscribe_route_by_req, update_subscribe_route_by_req,
+};
 use crate::server::services::mqtt::topic::{
     create_topic_by_req, create_topic_rewrite_rule_by_req, delete_topic_by_req,
-    delete_topic_rewrite_rule_by_req, list_topic_by_req, list_topic_rewrite_rule_by_req,
+    delete_topic_rewrite_rule_by_req, describe_topic_by_req, list_topic_by_req,
+    list_topic_rewrite_rule_by_req, report_topic_stats_by_req,
 };
 use crate::server::services::mqtt::user::{
-    create_user_by_req, delete_user_by_req, list_user_by_req,
+    create_user_by_req, delete_user_by_req, list_user_by_req, update_user_by_req,
 };
 use broker_core::cache::NodeCacheManager;
 use delay_task::manager::DelayTaskManager;
@@ -42,21 +55,31 @@
 use prost_validate::Validator;
 use protocol::meta::meta_service_mqtt::mqtt_service_server::MqttService;
 use protocol::meta::meta_service_mqtt::{
+    AllocateLastWillEpochReply, AllocateLastWillEpochRequest, ClaimLastWillReply,
+    ClaimLastWillRequest, CommitConnectorCheckpointReply, CommitConnectorCheckpointRequest,
     ConnectorHeartbeatReply, ConnectorHeartbeatRequest, CreateAclReply, CreateAclRequest,
     CreateAutoSubscribeRuleReply, CreateAutoSubscribeRuleRequest, CreateBlacklistReply,
-    CreateBlacklistRequest, CreateConnectorReply, CreateConnectorRequest, CreateSessionReply,
-    CreateSessionRequest, CreateTopicReply, CreateTopicRequest, CreateTopicRewriteRuleReply,
-    CreateTopicRewriteRuleRequest, CreateUserReply, CreateUserRequest, DeleteAclReply,
-    DeleteAclRequest, DeleteAutoSubscribeRuleReply, DeleteAutoSubscribeRuleRequest,
-    DeleteBlacklistReply, DeleteBlacklistRequest, DeleteConnectorReply, DeleteConnectorRequest,
-    DeleteSessionReply, DeleteSessionRequest, DeleteSubscribeReply, DeleteSubscribeRequest,
-    DeleteTopicReply, DeleteTopicRequest, DeleteTopicRewriteRuleReply,
-    DeleteTopicRewriteRuleRequest, DeleteUserReply, DeleteUserRequest, ListAclReply,
-    ListAclRequest, ListAutoSubscribeRuleReply, ListAutoSubscribeRuleRequest, ListBlacklistReply,
-    ListBlacklistRequest, ListConnectorReply, ListConnectorRequest, ListSessionReply,
-    ListSessionRequest, ListSubscribeReply, ListSubscribeRequest, ListTopicReply, ListTopicRequest,
-    ListTopicRewriteRuleReply, ListTopicRewriteRuleRequest, ListUserReply, ListUserRequest,
-    SetSubscribeReply, SetSubscribeRequest, UpdateConnectorReply, UpdateConnectorRequest,
+    CreateBlacklistRequest, CreateConnectorReply, CreateConnectorRequest, CreateMessageRuleReply,
+    CreateMessageRuleRequest, CreateSessionReply, CreateSessionRequest, CreateTopicReply,
+    CreateTopicRequest, CreateTopicRewriteRuleReply, CreateTopicRewriteRuleRequest,
+    CreateUserReply, CreateUserRequest, DeleteAclReply, DeleteAclRequest,
+    DeleteAutoSubscribeRuleReply, DeleteAutoSubscribeRuleRequest, DeleteBlacklistReply,
+    DeleteBlacklistRequest, DeleteConnectorReply, DeleteConnectorRequest, DeleteMessageRuleReply,
+    DeleteMessageRuleRequest, DeleteRetainIndexReply, DeleteRetainIndexRequest, DeleteSessionReply,
+    DeleteSessionRequest, DeleteSubscribeReply, DeleteSubscribeRequest, DeleteTopicReply,
+    DeleteTopicRequest, DeleteTopicRewriteRuleReply, DeleteTopicRewriteRuleRequest,
+    DeleteUserReply, DeleteUserRequest, DescribeTopicReply, DescribeTopicRequest,
+    GetConnectorCheckpointReply, GetConnectorCheckpointRequest, GetNodesForTopicReply,
+    GetNodesForTopicRequest, ListAclReply, ListAclRequest, ListAutoSubscribeRuleReply,
+    ListAutoSubscribeRuleRequest, ListBlacklistReply, ListBlacklistRequest, ListConnectorReply,
+    ListConnectorRequest, ListMessageRuleReply, ListMessageRuleRequest, ListRetainIndexReply,
+    ListRetainIndexRequest, ListSessionReply, ListSessionRequest, ListSubscribeReply,
+    ListSubscribeRequest, ListTopicReply, ListTopicRequest, ListTopicRewriteRuleReply,
+    ListTopicRewriteRuleRequest, ListUserReply, ListUserRequest, ReportTopicStatsReply,
+    ReportTopicStatsRequest, SetRetainIndexReply, SetRetainIndexRequest, SetSubscribeReply,
+    SetSubscribeRequest, SyncSubscribeRouteReply, SyncSubscribeRouteRequest, UpdateConnectorReply,
+    UpdateConnectorRequest, UpdateSubscribeRouteReply, UpdateSubscribeRouteRequest,
+    UpdateUserReply, UpdateUserRequest,
 };
 use rocksdb_engine::rocksdb::RocksDBEngine;
 use std::pin::Pin;
@@ -102,6 +125,14 @@ fn validate_request<T: Validator>(&self, req: &T) -> Result<(), Status> {
     fn to_status<E: ToString>(e: E) -> Status {
         Status::internal(e.to_string())
     }
+
+    // Helper: Convert list errors, reporting bad filters as invalid arguments
+    fn to_list_status(e: MetaServiceError) -> Status {
+        match e {
+            MetaServiceError::InvalidListFilter(_) => Status::invalid_argument(e.to_string()),
+            _ => Status::internal(e.to_string()),
+        }
+    }
 }
 
 #[tonic::async_trait]
@@ -116,8 +147,8 @@ async fn list_user(
         let req = request.into_inner();
         self.validate_request(&req)?;
 
-        list_user_by_req(&self.rocksdb_engine_handler, &req)
-            .map_err(Self::to_status)
+        list_user_by_req(&self.cache_manager, &self.rocksdb_engine_handler, &req)
+            .map_err(Self::to_list_status)
             .map(Response::new)
     }
 
@@ -139,6 +170,24 @@ async fn create_user(
         .map(Response::new)
     }
 
+    async fn update_user(
+        &self,
+        request: Request<UpdateUserRequest>,
+    ) -> Result<Response<UpdateUserReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        update_user_by_req(
+            &self.raft_manager,
+            &self.call_manager,
+            &self.rocksdb_engine_handler,
+            &req,
+        )
+        .await
+        .map_err(Self::to_status)
+        .map(Response::new)
+    }
+
     async fn delete_user(
         &self,
         request: Request<DeleteUserRequest>,
@@ -165,9 +214,14 @@ async fn list_session(
         let req = request.into_inner();
         self.validate_request(&req)?;
 
-        list_session_by_req(&self.node_cache, &self.rocksdb_engine_handler, &req)
-            .map_err(Self::to_status)
-            .map(Response::new)
+        list_session_by_req(
+            &self.cache_manager,
+            &self.node_cache,
+            &self.rocksdb_engine_handler,
+            &req,
+        )
+        .map_err(Self::to_list_status)
+        .map(Response::new)
     }
 
     async fn create_session(
@@ -203,6 +257,32 @@ async fn delete_session(
         .map(Response::new)
     }
 
+    async fn allocate_last_will_epoch(
+        &self,
+        request: Request<AllocateLastWillEpochRequest>,
+    ) -> Result<Response<AllocateLastWillEpochReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        allocate_last_will_epoch_by_req(&self.raft_manager, &req)
+            .await
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
+    async fn claim_last_will(
+        &self,
+        request: Request<ClaimLastWillRequest>,
+    ) -> Result<Response<ClaimLastWillReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        claim_last_will_by_req(&self.raft_manager, &req)
+            .await
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
     // Topic
     type ListTopicStream = Pin<Box<dyn Stream<Item = Result<ListTopicReply, Status>> + Send>>;
 
@@ -221,9 +301,9 @@ async fn list_topic(
         let req = request.into_inner();
         self.validate_request(&req)?;
 
-        list_topic_by_req(&self.rocksdb_engine_handler, &req)
+        list_topic_by_req(&self.cache_manager, &self.rocksdb_engine_handler, &req)
             .await
-            .map_err(Self::to_status)
+            .map_err(Self::to_list_status)
             .map(Response::new)
     }
 
@@ -262,6 +342,69 @@ async fn delete_topic(
         .map(Response::new)
     }
 
+    async fn report_topic_stats(
+        &self,
+        request: Request<ReportTopicStatsRequest>,
+    ) -> Result<Response<ReportTopicStatsReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        report_topic_stats_by_req(&self.cache_manager, &req)
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
+    async fn describe_topic(
+        &self,
+        request: Request<DescribeTopicRequest>,
+    ) -> Result<Response<DescribeTopicReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        describe_topic_by_req(&self.cache_manager, &self.rocksdb_engine_handler, &req)
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
+    // Retain Index
+    async fn set_retain_index(
+        &self,
+        request: Request<SetRetainIndexRequest>,
+    ) -> Result<Response<SetRetainIndexReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        set_retain_index_by_req(&self.raft_manager, &req)
+            .await
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
+    async fn delete_retain_index(
+        &self,
+        request: Request<DeleteRetainIndexRequest>,
+    ) -> Result<Response<DeleteRetainIndexReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        delete_retain_index_by_req(&self.raft_manager, &req)
+            .await
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
+    async fn list_retain_index(
+        &self,
+        request: Request<ListRetainIndexRequest>,
+    ) -> Result<Response<ListRetainIndexReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        list_retain_index_by_req(&self.rocksdb_engine_handler, &req)
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
     // ACL
     async fn list_acl(
         &self,
@@ -271,7 +414,7 @@ async fn list_acl(
         self.validate_request(&req)?;
 
         list_acl_by_req(&self.rocksdb_engine_handler, &req)
-            .map_err(Self::to_status)
+            .map_err(Self::to_list_status)
             .map(Response::new)
     }
 
@@ -315,7 +458,7 @@ async fn list_blacklist(
         self.validate_request(&req)?;
 
         list_blacklist_by_req(&self.rocksdb_engine_handler, &req)
-            .map_err(Self::to_status)
+            .map_err(Self::to_list_status)
             .map(Response::new)
     }
 
@@ -395,7 +538,7 @@ async fn list_topic_rewrite_rule(
         self.validate_request(&req)?;
 
         list_topic_rewrite_rule_by_req(&self.rocksdb_engine_handler, &req)
-            .map_err(Self::to_status)
+            .map_err(Self::to_list_status)
             .map(Response::new)
     }
 
@@ -407,7 +550,7 @@ async fn list_subscribe(
         self.validate_request(&req)?;
 
         list_subscribe_by_req(&self.rocksdb_engine_handler, &req)
-            .map_err(Self::to_status)
+            .map_err(Self::to_list_status)
             .map(Response::new)
     }
 
@@ -450,7 +593,7 @@ async fn list_connectors(
         self.validate_request(&req)?;
 
         list_connectors_by_req(&self.rocksdb_engine_handler, &req)
-            .map_err(Self::to_status)
+            .map_err(Self::to_list_status)
             .map(Response::new)
     }
 
@@ -522,6 +665,36 @@ async fn connector_heartbeat(
             .map(Response::new)
     }
 
+    async fn commit_connector_checkpoint(
+        &self,
+        request: Request<CommitConnectorCheckpointRequest>,
+    ) -> Result<Response<CommitConnectorCheckpointReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        commit_connector_checkpoint_by_req(
+            &self.rocksdb_engine_handler,
+            &self.raft_manager,
+            &self.cache_manager,
+            &req,
+        )
+        .await
+        .map_err(Self::to_status)
+        .map(Response::new)
+    }
+
+    async fn get_connector_checkpoint(
+        &self,
+        request: Request<GetConnectorCheckpointRequest>,
+    ) -> Result<Response<GetConnectorCheckpointReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        get_connector_checkpoint_by_req(&self.rocksdb_engine_handler, &req)
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
     // Auto Subscribe Rule
     async fn create_auto_subscribe_rule(
         &self,
@@ -567,6 +740,94 @@ async fn list_auto_subscribe_rule(
         self.validate_request(&req)?;
 
         list_auto_subscribe_rule_by_req(&self.rocksdb_engine_handler, &req)
+            .map_err(Self::to_list_status)
+            .map(Response::new)
+    }
+
+    // Message Rule
+    async fn create_message_rule(
+        &self,
+        request: Request<CreateMessageRuleRequest>,
+    ) -> Result<Response<CreateMessageRuleReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        create_message_rule_by_req(
+            &self.raft_manager,
+            &self.rocksdb_engine_handler,
+            &self.call_manager,
+            &req,
+        )
+        .await
+        .map_err(Self::to_status)
+        .map(Response::new)
+    }
+
+    async fn delete_message_rule(
+        &self,
+        request: Request<DeleteMessageRuleRequest>,
+    ) -> Result<Response<DeleteMessageRuleReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        delete_message_rule_by_req(
+            &self.raft_manager,
+            &self.rocksdb_engine_handler,
+            &self.call_manager,
+            &req,
+        )
+        .await
+        .map_err(Self::to_status)
+        .map(Response::new)
+    }
+
+    async fn list_message_rule(
+        &self,
+        request: Request<ListMessageRuleRequest>,
+    ) -> Result<Response<ListMessageRuleReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        list_message_rule_by_req(&self.rocksdb_engine_handler, &req)
+            .map_err(Self::to_list_status)
+            .map(Response::new)
+    }
+
+    // Subscribe Route
+    async fn update_subscribe_route(
+        &self,
+        request: Request<UpdateSubscribeRouteRequest>,
+    ) -> Result<Response<UpdateSubscribeRouteReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        update_subscribe_route_by_req(&self.raft_manager, &req)
+            .await
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
+    async fn sync_subscribe_route(
+        &self,
+        request: Request<SyncSubscribeRouteRequest>,
+    ) -> Result<Response<SyncSubscribeRouteReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        sync_subscribe_route_by_req(&self.raft_manager, &self.rocksdb_engine_handler, &req)
+            .await
+            .map_err(Self::to_status)
+            .map(Response::new)
+    }
+
+    async fn get_nodes_for_topic(
+        &self,
+        request: Request<GetNodesForTopicRequest>,
+    ) -> Result<Response<GetNodesForTopicReply>, Status> {
+        let req = request.into_inner();
+        self.validate_request(&req)?;
+
+        get_nodes_for_topic_by_req(&self.cache_manager, &req)
             .map_err(Self::to_status)
             .map(Response::new)
     }
@@ -0,0 +1,213 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::core::error::MetaServiceError;
+use crate::storage::common::schema::SchemaStorage;
+use crate::storage::common::share_group::ShareGroupStorage;
+use crate::storage::common::tenant::TenantStorage;
+use crate::storage::mq9::agent::Mq9AgentStorage;
+use crate::storage::mq9::mail::Mq9MailStorage;
+use crate::storage::mqtt::acl::AclStorage;
+use crate::storage::mqtt::blacklist::MqttBlackListStorage;
+use crate::storage::mqtt::connector::MqttConnectorStorage;
+use crate::storage::mqtt::message_rule::MqttMessageRuleStorage;
+use crate::storage::mqtt::session::MqttSessionStorage;
+use crate::storage::mqtt::subscribe::MqttSubscribeStorage;
+use crate::storage::mqtt::topic::MqttTopicStorage;
+use crate::storage::mqtt::user::SecurityUserStorage;
+use crate::storage::nats::subscribe::NatsSubscribeStorage;
+use common_base::error::common::CommonError;
+use metadata_struct::meta::bootstrap::BootstrapResource;
+use protocol::meta::meta_service_common::{BootstrapCacheReply, BootstrapCacheRequest};
+use rocksdb_engine::rocksdb::RocksDBEngine;
+use std::pin::Pin;
+use std::sync::Arc;
+use tonic::codegen::tokio_stream::Stream;
+use tonic::Status;
+
+pub const BOOTSTRAP_DEFAULT_PAGE_SIZE: usize = 500;
+pub const BOOTSTRAP_MAX_PAGE_SIZE: usize = 10_000;
+
+type BootstrapCacheStream = Result<
+    Pin<Box<dyn Stream<Item = Result<BootstrapCacheReply, Status>> + Send>>,
+    MetaServiceError,
+>;
+
+pub fn bootstrap_cache_by_req(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    req: &BootstrapCacheRequest,
+) -> BootstrapCacheStream {
+    let resources = BootstrapResource::parse_list(&req.resource_types)?;
+    let page_size = bootstrap_page_size(req.page_size);
+    let rocksdb_engine_handler = rocksdb_engine_handler.clone();
+
+    // Resource types are read one at a time so that only a single type is
+    // held in memory while its pages are streamed.
+    let output = async_stream::try_stream! {
+        for resource in resources {
+            let items = load_resource(&rocksdb_engine_handler, resource)
+                .map_err(|e| Status::internal(e.to_string()))?;
+            for page in items.chunks(page_size) {
+                yield BootstrapCacheReply {
+                    resource_type: resource.to_string(),
+                    data: page.to_vec(),
+                };
+            }
+        }
+    };
+
+    Ok(Box::pin(output))
+}
+
+fn bootstrap_page_size(page_size: u32) -> usize {
+    match page_size as usize {
+        0 => BOOTSTRAP_DEFAULT_PAGE_SIZE,
+        size => size.min(BOOTSTRAP_MAX_PAGE_SIZE),
+    }
+}
+
+fn encode_all<T>(
+    items: impl IntoIterator<Item = T>,
+    encode: impl Fn(&T) -> Result<Vec<u8>, CommonError>,
+) -> Result<Vec<Vec<u8>>, MetaServiceError> {
+    Ok(items
+        .into_iter()
+        .map(|item| encode(&item))
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+pub(crate) fn load_resource(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    resource: BootstrapResource,
+) -> Result<Vec<Vec<u8>>, MetaServiceError> {
+    let handler = rocksdb_engine_handler.clone();
+    match resource {
+        BootstrapResource::Tenant => {
+            encode_all(TenantStorage::new(handler).list()?, |t| t.encode())
+        }
+        BootstrapResource::User => encode_all(SecurityUserStorage::new(handler).list_all()?, |u| {
+            u.encode()
+        }),
+        BootstrapResource::Acl => encode_all(AclStorage::new(handler).list_all()?, |a| a.encode()),
+        BootstrapResource::Blacklist => {
+            encode_all(MqttBlackListStorage::new(handler).list_all()?, |b| {
+                b.encode()
+            })
+        }
+        BootstrapResource::Topic => {
+            encode_all(MqttTopicStorage::new(handler).list()?, |t| t.encode())
+        }
+        BootstrapResource::TopicRewriteRule => encode_all(
+            MqttTopicStorage::new(handler).list_all_topic_rewrite_rules()?,
+            |r| r.encode(),
+        ),
+        BootstrapResource::AutoSubscribeRule => encode_all(
+            MqttSubscribeStorage::new(handler).list_all_auto_subscribe_rules()?,
+            |r| r.encode(),
+        ),
+        BootstrapResource::MessageRule => {
+            encode_all(MqttMessageRuleStorage::new(handler).list_all()?, |r| {
+                r.encode()
+            })
+        }
+        BootstrapResource::Session => {
+            encode_all(MqttSessionStorage::new(handler).list()?, |s| s.encode())
+        }
+        BootstrapResource::Subscribe => {
+            encode_all(MqttSubscribeStorage::new(handler).list_all()?, |s| {
+                s.encode()
+            })
+        }
+        BootstrapResource::Connector => {
+            encode_all(MqttConnectorStorage::new(handler).list()?, |c| c.encode())
+        }
+        BootstrapResource::Schema => {
+            encode_all(SchemaStorage::new(handler).list()?, |s| s.encode())
+        }
+        BootstrapResource::SchemaBind => {
+            encode_all(SchemaStorage::new(handler).list_bind()?, |b| b.encode())
+        }
+        BootstrapResource::ShareGroup => encode_all(
+            ShareGroupStorage::new(handler).list_all()?.into_values(),
+            |g| g.encode(),
+        ),
+        BootstrapResource::ShareGroupMember => {
+            encode_all(ShareGroupStorage::new(handler).list_all_members()?, |m| {
+                m.encode()
+            })
+        }
+        BootstrapResource::NatsSubscribe => {
+            encode_all(NatsSubscribeStorage::new(handler).list()?, |s| s.encode())
+        }
+        BootstrapResource::Mq9Mail => {
+            encode_all(Mq9MailStorage::new(handler).list()?, |m| m.encode())
+        }
+        BootstrapResource::Mq9Agent => {
+            encode_all(Mq9AgentStorage::new(handler).list()?, |a| a.encode())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common_base::tools::now_second;
+    use common_config::broker::{default_broker_config, init_broker_conf_by_config};
+    use metadata_struct::tenant::Tenant;
+    use metadata_struct::topic::Topic;
+    use rocksdb_engine::test::test_rocksdb_instance;
+
+    #[test]
+    fn test_bootstrap_page_size() {
+        assert_eq!(bootstrap_page_size(0), BOOTSTRAP_DEFAULT_PAGE_SIZE);
+        assert_eq!(bootstrap_page_size(10), 10);
+        assert_eq!(bootstrap_page_size(u32::MAX), BOOTSTRAP_MAX_PAGE_SIZE);
+    }
+
+    #[test]
+    fn test_load_resource() {
+        init_broker_conf_by_config(default_broker_config());
+        let rocksdb_engine_handler = test_rocksdb_instance();
+        TenantStorage::new(rocksdb_engine_handler.clone())
+            .save(&Tenant {
+                tenant_name: "t1".to_string(),
+                create_time: now_second(),
+                ..Default::default()
+            })
+            .unwrap();
+        let topic_storage = MqttTopicStorage::new(rocksdb_engine_handler.clone());
+        for name in ["a", "b", "c"] {
+            topic_storage
+                .save(Topic {
+                    tenant: "t1".to_string(),
+                    topic_name: name.to_string(),
+                    create_time: now_second(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let tenants = load_resource(&rocksdb_engine_handler, BootstrapResource::Tenant).unwrap();
+        assert_eq!(tenants.len(), 1);
+        assert_eq!(Tenant::decode(&tenants[0]).unwrap().tenant_name, "t1");
+
+        let topics = load_resource(&rocksdb_engine_handler, BootstrapResource::Topic).unwrap();
+        assert_eq!(topics.len(), 3);
+        for raw in topics.iter() {
+            assert_eq!(Topic::decode(raw).unwrap().tenant, "t1");
+        }
+
+        let users = load_resource(&rocksdb_engine_handler, BootstrapResource::User).unwrap();
+        assert!(users.is_empty());
+    }
+}
@@ -0,0 +1,166 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::core::error::MetaServiceError;
+use crate::storage::mqtt::session::MqttSessionStorage;
+use crate::storage::mqtt::topic::MqttTopicStorage;
+use crate::storage::mqtt::user::SecurityUserStorage;
+use broker_core::cache::NodeCacheManager;
+use common_base::error::common::CommonError;
+use common_base::utils::crc::calc_digest;
+use metadata_struct::meta::bootstrap::BootstrapResource;
+use protocol::meta::meta_service_common::{
+    CheckResourceDigestReply, CheckResourceDigestRequest, ResourceDivergence,
+};
+use rocksdb_engine::rocksdb::RocksDBEngine;
+use std::sync::Arc;
+
+pub fn check_resource_digest_by_req(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    node_cache: &Arc<NodeCacheManager>,
+    req: &CheckResourceDigestRequest,
+) -> Result<CheckResourceDigestReply, MetaServiceError> {
+    let resource: BootstrapResource = req.resource_type.parse()?;
+    let mut divergences = Vec::new();
+    for item in &req.digests {
+        let divergence = match load_record(rocksdb_engine_handler, node_cache, resource, &item.key)?
+        {
+            Some((data, digest)) if digest != item.digest => ResourceDivergence {
+                key: item.key.clone(),
+                exists: true,
+                data,
+                digest,
+            },
+            Some(_) => continue,
+            None => ResourceDivergence {
+                key: item.key.clone(),
+                exists: false,
+                ..Default::default()
+            },
+        };
+        divergences.push(divergence);
+    }
+    Ok(CheckResourceDigestReply { divergences })
+}
+
+// Returns the encoded record and its digest.
+fn load_record(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    node_cache: &Arc<NodeCacheManager>,
+    resource: BootstrapResource,
+    key: &str,
+) -> Result<Option<(Vec<u8>, u32)>, MetaServiceError> {
+    let Some((tenant, name)) = key.split_once('/') else {
+        return Err(CommonError::InvalidParameterFormat(
+            "key".to_string(),
+            format!("expected {{tenant}}/{{name}}, got {}", key),
+        )
+        .into());
+    };
+    let handler = rocksdb_engine_handler.clone();
+    let record = match resource {
+        BootstrapResource::Topic => match MqttTopicStorage::new(handler).get(tenant, name)? {
+            Some(topic) => Some((topic.encode()?, calc_digest(&topic)?)),
+            None => None,
+        },
+        BootstrapResource::User => match SecurityUserStorage::new(handler).get(tenant, name)? {
+            Some(user) => Some((user.encode()?, calc_digest(&user)?)),
+            None => None,
+        },
+        BootstrapResource::Session => {
+            // Sessions that are not persisted only live in the node cache.
+            let session = match MqttSessionStorage::new(handler).get(tenant, name)? {
+                Some(session) => Some(session),
+                None => node_cache.get_session(tenant, name),
+            };
+            match session {
+                Some(session) => Some((session.encode()?, calc_digest(&session)?)),
+                None => None,
+            }
+        }
+        other => {
+            return Err(CommonError::InvalidParameterFormat(
+                "resource_type".to_string(),
+                format!("digest check is not supported for {}", other),
+            )
+            .into())
+        }
+    };
+    Ok(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common_config::broker::default_broker_config;
+    use metadata_struct::auth::user::SecurityUser;
+    use protocol::meta::meta_service_common::ResourceDigest;
+    use rocksdb_engine::test::test_rocksdb_instance;
+
+    #[test]
+    fn test_check_resource_digest() {
+        let rocksdb_engine_handler = test_rocksdb_instance();
+        let node_cache = Arc::new(NodeCacheManager::new(default_broker_config()));
+        let user = SecurityUser {
+            tenant: "t1".to_string(),
+            username: "u1".to_string(),
+            password: "p1".to_string(),
+            salt: None,
+            is_superuser: false,
+            create_time: 1,
+            hash_algorithm: None,
+        };
+        SecurityUserStorage::new(rocksdb_engine_handler.clone())
+            .save("t1", "u1", user.clone())
+            .unwrap();
+
+        let mut stale = user.clone();
+        stale.password = "old".to_string();
+        let req = CheckResourceDigestRequest {
+            resource_type: "user".to_string(),
+            digests: vec![
+                ResourceDigest {
+                    key: "t1/u1".to_string(),
+                    digest: calc_digest(&user).unwrap(),
+                },
+                ResourceDigest {
+                    key: "t1/u1".to_string(),
+                    digest: calc_digest(&stale).unwrap(),
+                },
+                ResourceDigest {
+                    key: "t1/gone".to_string(),
+                    digest: 1,
+                },
+            ],
+        };
+        let reply =
+            check_resource_digest_by_req(&rocksdb_engine_handler, &node_cache, &req).unwrap();
+        assert_eq!(reply.divergences.len(), 2);
+        assert!(reply.divergences[0].exists);
+        assert_eq!(
+            SecurityUser::decode(&reply.divergences[0].data).unwrap(),
+            user
+        );
+        assert_eq!(reply.divergences[1].key, "t1/gone");
+        assert!(!reply.divergences[1].exists);
+
+        let req = CheckResourceDigestRequest {
+            resource_type: "acl".to_string(),
+            digests: vec![ResourceDigest {
+                key: "t1/a".to_string(),
+                digest: 0,
+            }],
+        };
+        assert!(check_resource_digest_by_req(&rocksdb_engine_handler, &node_cache, &req).is_err());
+    }
+}
@@ -13,22 +13,29 @@
 // limitations under the License.
 
 use crate::core::cache::MetaCacheManager;
+use crate::core::consumer_group::CONSUMER_GROUP_SESSION_TIMEOUT_MS;
 use crate::core::error::MetaServiceError;
-use crate::core::notify::send_notify_by_set_resource_config;
+use crate::core::notify::{send_notify_by_delete_group_offset, send_notify_by_set_resource_config};
 use crate::raft::manager::MultiRaftManager;
 use crate::raft::route::data::{StorageData, StorageDataType};
 use crate::storage::common::config::ResourceConfigStorage;
 use crate::storage::common::offset::OffsetStorage;
-use common_base::tools::now_second;
+use common_base::tools::{now_millis, now_second};
 use common_base::utils::serialize::encode_to_bytes;
 use metadata_struct::resource_config::ResourceConfig;
 use node_call::NodeCallManager;
 use protocol::meta::meta_service_common::{
-    ClusterStatusReply, DeleteResourceConfigReply, DeleteResourceConfigRequest, GetOffsetDataReply,
+    ClusterStatusReply, ConfigSectionDrift, ConsumerGroupHeartbeatReply,
+    ConsumerGroupHeartbeatRequest, CordonNodeReply, CordonNodeRequest, DeleteOffsetGroupReply,
+    DeleteOffsetGroupRequest, DeleteResourceConfigReply, DeleteResourceConfigRequest,
+    DeleteShareGroupRequest, GetConfigDriftReply, GetConfigDriftRequest, GetOffsetDataReply,
     GetOffsetDataReplyOffset, GetOffsetDataRequest, GetResourceConfigReply,
-    GetResourceConfigRequest, HeartbeatReply, HeartbeatRequest, NodeListReply, NodeListRequest,
-    SaveOffsetData, SaveOffsetDataReply, SaveOffsetDataRequest, SetResourceConfigReply,
-    SetResourceConfigRequest,
+    GetResourceConfigRequest, HeartbeatReply, HeartbeatRequest, LeaveConsumerGroupReply,
+    LeaveConsumerGroupRequest, ListNodeStatusReply, ListNodeStatusRequest, ListOffsetGroupReply,
+    ListOffsetGroupRequest, NodeListReply, NodeListRequest, NodeStatus, OffsetGroupMemberRaw,
+    OffsetGroupRaw, ResetOffsetDataReply, ResetOffsetDataRequest, SaveOffsetData,
+    SaveOffsetDataReply, SaveOffsetDataRequest, SetResourceConfigReply, SetResourceConfigRequest,
+    UncordonNodeReply, UncordonNodeRequest,
 };
 use rocksdb_engine::rocksdb::RocksDBEngine;
 use std::collections::{BTreeMap, HashMap};
@@ -67,9 +74,83 @@ pub async fn node_list_by_req(
     Ok(NodeListReply { nodes })
 }
 
+/// Exclude a node from connector placement and segment leadership transfer,
+/// e.g. before it is restarted for an upgrade. The mark is kept until the node
+/// is uncordoned, also across its re-registration.
+pub async fn cordon_node_by_req(
+    cluster_cache: &Arc<MetaCacheManager>,
+    raft_manager: &Arc<MultiRaftManager>,
+    req: &CordonNodeRequest,
+) -> Result<CordonNodeReply, MetaServiceError> {
+    if cluster_cache.get_broker_node(req.node_id).is_none() {
+        return Err(MetaServiceError::NodeDoesNotExist(req.node_id));
+    }
+
+    let data = StorageData::new(StorageDataType::ClusterCordonNode, encode_to_bytes(req));
+    raft_manager.write_metadata(data).await?;
+    Ok(CordonNodeReply::default())
+}
+
+pub async fn uncordon_node_by_req(
+    cluster_cache: &Arc<MetaCacheManager>,
+    raft_manager: &Arc<MultiRaftManager>,
+    req: &UncordonNodeRequest,
+) -> Result<UncordonNodeReply, MetaServiceError> {
+    if !cluster_cache.is_node_cordoned(req.node_id) {
+        return Ok(UncordonNodeReply::default());
+    }
+
+    let data = StorageData::new(StorageDataType::ClusterUncordonNode, encode_to_bytes(req));
+    raft_manager.write_metadata(data).await?;
+    Ok(UncordonNodeReply::default())
+}
+
+/// Version and cordon state of every registered or cordoned node.
+pub async fn list_node_status_by_req(
+    cluster_cache: &Arc<MetaCacheManager>,
+    _req: &ListNodeStatusRequest,
+) -> Result<ListNodeStatusReply, MetaServiceError> {
+    let mut statuses: BTreeMap<u64, NodeStatus> = BTreeMap::new();
+    for node in cluster_cache.node_list.iter() {
+        statuses.insert(
+            node.node_id,
+            NodeStatus {
+                node_id: node.node_id,
+                node_ip: node.node_ip.clone(),
+                version: cluster_cache
+                    .get_broker_version(node.node_id)
+                    .unwrap_or_default(),
+                heartbeat_time: cluster_cache
+                    .get_broker_heart(node.node_id)
+                    .map(|heart| heart.time)
+                    .unwrap_or_default(),
+                ..Default::default()
+            },
+        );
+    }
+
+    // A cordoned node may be down for its upgrade and not registered.
+    for cordon in cluster_cache.node_cordon.iter() {
+        let status = statuses
+            .entry(cordon.node_id)
+            .or_insert_with(|| NodeStatus {
+                node_id: cordon.node_id,
+                ..Default::default()
+            });
+        status.cordoned = true;
+        status.cordon_reason = cordon.reason.clone();
+        status.cordon_time = cordon.create_time;
+    }
+
+    Ok(ListNodeStatusReply {
+        nodes: statuses.into_values().collect(),
+    })
+}
+
 // Heartbeat
 pub async fn heartbeat_by_req(
     cluster_cache: &Arc<MetaCacheManager>,
+    call_manager: &Arc<NodeCallManager>,
     req: &HeartbeatRequest,
 ) -> Result<HeartbeatReply, MetaServiceError> {
     // Check if node exists
@@ -84,10 +165,38 @@ pub async fn heartbeat_by_req(
     );
 
     cluster_cache.report_broker_heart(req.node_id);
+    if !req.version.is_empty() {
+        cluster_cache.report_broker_version(req.node_id, &req.version);
+    }
+    cluster_cache.cache_version.report(
+        req.node_id,
+        call_manager.cache_versions(),
+        &req.cache_versions,
+    );
+    cluster_cache
+        .config_drift
+        .report(req.node_id, &req.config_digests);
 
     Ok(HeartbeatReply::default())
 }
 
+pub async fn get_config_drift_by_req(
+    cluster_cache: &Arc<MetaCacheManager>,
+    _req: &GetConfigDriftRequest,
+) -> Result<GetConfigDriftReply, MetaServiceError> {
+    let drifts = cluster_cache
+        .config_drift
+        .drifts()
+        .into_iter()
+        .map(|drift| ConfigSectionDrift {
+            section: drift.section,
+            digests: drift.digests,
+            since: drift.since,
+        })
+        .collect();
+    Ok(GetConfigDriftReply { drifts })
+}
+
 // Resource Config
 pub async fn set_resource_config_by_req(
     raft_manager: &Arc<MultiRaftManager>,
@@ -185,3 +294,165 @@ pub async fn get_offset_data_by_req(
 
     Ok(GetOffsetDataReply { offsets })
 }
+
+pub fn list_offset_group_by_req(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    cluster_cache: &Arc<MetaCacheManager>,
+    req: &ListOffsetGroupRequest,
+) -> Result<ListOffsetGroupReply, MetaServiceError> {
+    let offset_storage = OffsetStorage::new(rocksdb_engine_handler.clone());
+    let offset_data = if !req.tenant.is_empty() && !req.group.is_empty() {
+        offset_storage.group_offset(&req.tenant, &req.group)?
+    } else {
+        offset_storage.list_all()?
+    };
+
+    let mut groups: BTreeMap<(String, String), OffsetGroupRaw> = BTreeMap::new();
+    for offset in offset_data {
+        if !req.tenant.is_empty() && offset.tenant != req.tenant {
+            continue;
+        }
+        let raw = groups
+            .entry((offset.tenant.clone(), offset.group.clone()))
+            .or_insert_with(|| OffsetGroupRaw {
+                tenant: offset.tenant.clone(),
+                group: offset.group.clone(),
+                ..Default::default()
+            });
+        raw.last_commit_time = raw.last_commit_time.max(offset.timestamp);
+        raw.offsets.push(GetOffsetDataReplyOffset {
+            shard_name: offset.shard_name,
+            offset: offset.offset,
+        });
+    }
+
+    for ((tenant, group), raw) in groups.iter_mut() {
+        raw.members = cluster_cache
+            .consumer_group
+            .members(tenant, group)
+            .into_iter()
+            .map(|(member_id, shards)| OffsetGroupMemberRaw { member_id, shards })
+            .collect();
+    }
+
+    Ok(ListOffsetGroupReply {
+        groups: groups.into_values().collect(),
+    })
+}
+
+fn check_offset_group_idle(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    cluster_cache: &Arc<MetaCacheManager>,
+    tenant: &str,
+    group: &str,
+) -> Result<(), MetaServiceError> {
+    let members = cluster_cache.consumer_group.members(tenant, group);
+    if !members.is_empty() {
+        return Err(MetaServiceError::OffsetGroupHasActiveMembers(
+            format!("{}/{}", tenant, group),
+            members.len(),
+        ));
+    }
+
+    let offset_storage = OffsetStorage::new(rocksdb_engine_handler.clone());
+    if offset_storage.group_offset(tenant, group)?.is_empty() {
+        return Err(MetaServiceError::OffsetGroupDoesNotExist(format!(
+            "{}/{}",
+            tenant, group
+        )));
+    }
+    Ok(())
+}
+
+pub async fn delete_offset_group_by_req(
+    raft_manager: &Arc<MultiRaftManager>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    cluster_cache: &Arc<MetaCacheManager>,
+    call_manager: &Arc<NodeCallManager>,
+    req: &DeleteOffsetGroupRequest,
+) -> Result<DeleteOffsetGroupReply, MetaServiceError> {
+    check_offset_group_idle(
+        rocksdb_engine_handler,
+        cluster_cache,
+        &req.tenant,
+        &req.group,
+    )?;
+
+    let delete_req = DeleteShareGroupRequest {
+        tenant: req.tenant.clone(),
+        group: req.group.clone(),
+    };
+    let data = StorageData::new(StorageDataType::OffsetDelete, encode_to_bytes(&delete_req));
+    raft_manager
+        .write_offset(&format!("{}/{}", req.tenant, req.group), data)
+        .await?;
+
+    send_notify_by_delete_group_offset(call_manager, &req.tenant, &req.group).await?;
+    Ok(DeleteOffsetGroupReply::default())
+}
+
+pub async fn reset_offset_data_by_req(
+    raft_manager: &Arc<MultiRaftManager>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    cluster_cache: &Arc<MetaCacheManager>,
+    call_manager: &Arc<NodeCallManager>,
+    req: &ResetOffsetDataRequest,
+) -> Result<ResetOffsetDataReply, MetaServiceError> {
+    check_offset_group_idle(
+        rocksdb_engine_handler,
+        cluster_cache,
+        &req.tenant,
+        &req.group,
+    )?;
+
+    let offsets = vec![SaveOffsetData {
+        tenant: req.tenant.clone(),
+        group: req.group.clone(),
+        offsets: req.offsets.clone(),
+    }];
+    let data = StorageData::new(
+        StorageDataType::OffsetSet,
+        encode_to_bytes(&SaveOffsetDataRequest { offsets }),
+    );
+    raft_manager
+        .write_offset(&format!("{}/{}", req.tenant, req.group), data)
+        .await?;
+
+    // Brokers cache committed offsets per group, drop them so the next
+    // consumer starts from the reset position.
+    send_notify_by_delete_group_offset(call_manager, &req.tenant, &req.group).await?;
+    Ok(ResetOffsetDataReply::default())
+}
+
+// Consumer Group
+pub fn consumer_group_heartbeat_by_req(
+    cluster_cache: &Arc<MetaCacheManager>,
+    req: &ConsumerGroupHeartbeatRequest,
+) -> Result<ConsumerGroupHeartbeatReply, MetaServiceError> {
+    let assignment = cluster_cache.consumer_group.heartbeat(
+        &req.tenant,
+        &req.group,
+        &req.member_id,
+        req.broker_id,
+        &req.shards,
+        &req.owned,
+        now_millis() as u64,
+    );
+
+    Ok(ConsumerGroupHeartbeatReply {
+        generation: assignment.generation,
+        assigned: assignment.assigned,
+        revoked: assignment.revoked,
+        session_timeout_ms: CONSUMER_GROUP_SESSION_TIMEOUT_MS,
+    })
+}
+
+pub fn leave_consumer_group_by_req(
+    cluster_cache: &Arc<MetaCacheManager>,
+    req: &LeaveConsumerGroupRequest,
+) -> Result<LeaveConsumerGroupReply, MetaServiceError> {
+    cluster_cache
+        .consumer_group
+        .leave(&req.tenant, &req.group, &req.member_id);
+    Ok(LeaveConsumerGroupReply::default())
+}
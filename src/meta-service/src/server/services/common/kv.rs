@@ -15,11 +15,14 @@
 use crate::core::error::MetaServiceError;
 use crate::raft::manager::MultiRaftManager;
 use crate::raft::route::data::{StorageData, StorageDataType};
-use crate::storage::common::kv::KvStorage;
+use crate::raft::route::kv::KvWriteCommand;
+use crate::storage::common::kv::{KvCondition, KvSetResult, KvStorage};
+use bytes::Bytes;
+use common_base::tools::now_millis;
 use common_base::utils::serialize::encode_to_bytes;
 use protocol::meta::meta_service_common::{
-    DeleteReply, DeleteRequest, ExistsReply, ExistsRequest, GetPrefixReply, GetPrefixRequest,
-    GetReply, GetRequest, SetReply, SetRequest,
+    CompareAndSetReply, CompareAndSetRequest, DeleteReply, DeleteRequest, ExistsReply,
+    ExistsRequest, GetPrefixReply, GetPrefixRequest, GetReply, GetRequest, SetReply, SetRequest,
 };
 use rocksdb_engine::rocksdb::RocksDBEngine;
 use std::sync::Arc;
@@ -34,6 +37,41 @@ fn validate_non_empty(value: &str, field_name: &str) -> Result<(), MetaServiceEr
     Ok(())
 }
 
+// Helper: Propose a KV write through raft and decode its outcome
+pub(crate) async fn write_kv(
+    raft_manager: &Arc<MultiRaftManager>,
+    key: &str,
+    value: &str,
+    ttl_ms: u64,
+    condition: Option<KvCondition>,
+) -> Result<KvSetResult, MetaServiceError> {
+    let now_ms = now_millis() as u64;
+    let cmd = KvWriteCommand {
+        key: key.to_string(),
+        value: value.to_string(),
+        expire_at_ms: if ttl_ms > 0 {
+            now_ms.saturating_add(ttl_ms)
+        } else {
+            0
+        },
+        condition,
+        now_ms,
+    };
+    let data = StorageData::new(
+        StorageDataType::KvWrite,
+        Bytes::from(bincode::serialize(&cmd)?),
+    );
+    let response = raft_manager
+        .write_metadata(data)
+        .await?
+        .ok_or(MetaServiceError::ExecutionResultIsEmpty)?;
+    let value = response
+        .data
+        .value
+        .ok_or(MetaServiceError::ExecutionResultIsEmpty)?;
+    Ok(bincode::deserialize(&value)?)
+}
+
 // KV Operations
 pub async fn set_by_req(
     raft_manager: &Arc<MultiRaftManager>,
@@ -42,10 +80,38 @@ pub async fn set_by_req(
     validate_non_empty(&req.key, "key")?;
     validate_non_empty(&req.value, "value")?;
 
-    let data = StorageData::new(StorageDataType::KvSet, encode_to_bytes(req));
-    raft_manager.write_metadata(data).await?;
+    let result = write_kv(raft_manager, &req.key, &req.value, req.ttl_ms, None).await?;
+
+    Ok(SetReply {
+        version: result.version,
+    })
+}
+
+pub async fn compare_and_set_by_req(
+    raft_manager: &Arc<MultiRaftManager>,
+    req: &CompareAndSetRequest,
+) -> Result<CompareAndSetReply, MetaServiceError> {
+    validate_non_empty(&req.key, "key")?;
+    validate_non_empty(&req.value, "value")?;
 
-    Ok(SetReply::default())
+    let condition = KvCondition {
+        expected_version: req.expected_version,
+        expected_value: req.expected_value.clone(),
+    };
+    let result = write_kv(
+        raft_manager,
+        &req.key,
+        &req.value,
+        req.ttl_ms,
+        Some(condition),
+    )
+    .await?;
+
+    Ok(CompareAndSetReply {
+        succeeded: result.succeeded,
+        version: result.version,
+        current_value: result.current_value,
+    })
 }
 
 pub async fn get_by_req(
@@ -55,12 +121,16 @@ pub async fn get_by_req(
     validate_non_empty(&req.key, "key")?;
 
     let kv_storage = KvStorage::new(rocksdb_engine_handler.clone());
-    let value = kv_storage
-        .get(req.key.clone())
+    let entry = kv_storage
+        .get(&req.key)
         .map_err(|e| MetaServiceError::CommonError(e.to_string()))?
         .unwrap_or_default();
 
-    Ok(GetReply { value })
+    Ok(GetReply {
+        value: entry.value,
+        version: entry.version,
+        expire_at_ms: entry.expire_at_ms,
+    })
 }
 
 pub async fn delete_by_req(
@@ -70,9 +140,13 @@ pub async fn delete_by_req(
     validate_non_empty(&req.key, "key")?;
 
     let data = StorageData::new(StorageDataType::KvDelete, encode_to_bytes(req));
-    raft_manager.write_metadata(data).await?;
+    let deleted = raft_manager
+        .write_metadata(data)
+        .await?
+        .and_then(|response| response.data.value)
+        .is_some_and(|value| value.first() == Some(&1));
 
-    Ok(DeleteReply::default())
+    Ok(DeleteReply { deleted })
 }
 
 pub async fn exists_by_req(
@@ -82,7 +156,7 @@ pub async fn exists_by_req(
     validate_non_empty(&req.key, "key")?;
 
     let kv_storage = KvStorage::new(rocksdb_engine_handler.clone());
-    let flag = kv_storage.exists(req.key.clone())?;
+    let flag = kv_storage.exists(&req.key)?;
 
     Ok(ExistsReply { flag })
 }
@@ -94,7 +168,7 @@ pub async fn get_prefix_by_req(
     validate_non_empty(&req.prefix, "prefix")?;
 
     let kv_storage = KvStorage::new(rocksdb_engine_handler.clone());
-    let values = kv_storage.get_prefix(req.prefix.clone())?;
+    let values = kv_storage.get_prefix(&req.prefix)?;
 
     Ok(GetPrefixReply { values })
 }
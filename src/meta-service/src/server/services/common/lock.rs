@@ -0,0 +1,139 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::core::error::MetaServiceError;
+use crate::raft::manager::MultiRaftManager;
+use crate::server::services::common::kv::write_kv;
+use crate::storage::common::kv::{KvCondition, KvStorage};
+use protocol::meta::meta_service_common::{
+    AcquireLockReply, AcquireLockRequest, ReleaseLockReply, ReleaseLockRequest, RenewLockReply,
+    RenewLockRequest, WatchLockReply, WatchLockRequest,
+};
+use rocksdb_engine::keys::meta::key_lock;
+use rocksdb_engine::rocksdb::RocksDBEngine;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+
+// Kept below the per-call timeout of the gRPC clients.
+const WATCH_LOCK_MAX_TIMEOUT_MS: u64 = 3000;
+const WATCH_LOCK_POLL_INTERVAL_MS: u64 = 100;
+// A released lock is rewritten with a lease that has run out right away.
+const RELEASED_LOCK_TTL_MS: u64 = 1;
+
+// A lock is a KV entry holding the owner, written with the lease as TTL.
+// Its KV version is the fencing token, so every acquire or renew returns a
+// larger token than any earlier holder got. The entry is never deleted,
+// a release only lets the lease run out.
+pub async fn acquire_lock_by_req(
+    raft_manager: &Arc<MultiRaftManager>,
+    req: &AcquireLockRequest,
+) -> Result<AcquireLockReply, MetaServiceError> {
+    let key = key_lock(&req.name);
+    let free = KvCondition {
+        expected_version: Some(0),
+        expected_value: None,
+    };
+    let mut result = write_kv(raft_manager, &key, &req.owner, req.lease_ms, Some(free)).await?;
+
+    // Acquiring a lock the owner already holds refreshes its lease.
+    if !result.succeeded && result.current_value == req.owner {
+        let held = KvCondition {
+            expected_version: Some(result.version),
+            expected_value: Some(req.owner.clone()),
+        };
+        result = write_kv(raft_manager, &key, &req.owner, req.lease_ms, Some(held)).await?;
+    }
+
+    if result.succeeded {
+        return Ok(AcquireLockReply {
+            acquired: true,
+            token: result.version,
+            holder: req.owner.clone(),
+        });
+    }
+    Ok(AcquireLockReply {
+        acquired: false,
+        token: 0,
+        holder: result.current_value,
+    })
+}
+
+pub async fn renew_lock_by_req(
+    raft_manager: &Arc<MultiRaftManager>,
+    req: &RenewLockRequest,
+) -> Result<RenewLockReply, MetaServiceError> {
+    let held = KvCondition {
+        expected_version: Some(req.token),
+        expected_value: Some(req.owner.clone()),
+    };
+    let result = write_kv(
+        raft_manager,
+        &key_lock(&req.name),
+        &req.owner,
+        req.lease_ms,
+        Some(held),
+    )
+    .await?;
+
+    Ok(RenewLockReply {
+        renewed: result.succeeded,
+        token: if result.succeeded { result.version } else { 0 },
+    })
+}
+
+pub async fn release_lock_by_req(
+    raft_manager: &Arc<MultiRaftManager>,
+    req: &ReleaseLockRequest,
+) -> Result<ReleaseLockReply, MetaServiceError> {
+    let held = KvCondition {
+        expected_version: Some(req.token),
+        expected_value: None,
+    };
+    let result = write_kv(
+        raft_manager,
+        &key_lock(&req.name),
+        "",
+        RELEASED_LOCK_TTL_MS,
+        Some(held),
+    )
+    .await?;
+
+    Ok(ReleaseLockReply {
+        released: result.succeeded,
+    })
+}
+
+// Long poll until the holder differs from the one the caller last saw.
+pub async fn watch_lock_by_req(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    req: &WatchLockRequest,
+) -> Result<WatchLockReply, MetaServiceError> {
+    let storage = KvStorage::new(rocksdb_engine_handler.clone());
+    let key = key_lock(&req.name);
+    let deadline =
+        Instant::now() + Duration::from_millis(req.timeout_ms.min(WATCH_LOCK_MAX_TIMEOUT_MS));
+
+    loop {
+        let entry = storage.get(&key)?.unwrap_or_default();
+        if entry.value != req.holder || Instant::now() >= deadline {
+            return Ok(WatchLockReply {
+                holder: entry.value,
+                token: entry.version,
+                expire_at_ms: entry.expire_at_ms,
+            });
+        }
+        sleep(Duration::from_millis(WATCH_LOCK_POLL_INTERVAL_MS)).await;
+    }
+}
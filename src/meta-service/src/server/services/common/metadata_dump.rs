@@ -0,0 +1,611 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Disaster recovery dumps of the cluster metadata. An export reads every
+//! restorable resource type in the encoding used by the cache bootstrap. An
+//! import checks the dump version and that every record's tenant, schema and
+//! session references resolve, then replays the records through the regular
+//! create paths so that they go through raft and reach the brokers. Records
+//! that already exist are left untouched, which makes an import safe to retry.
+
+use crate::core::cache::MetaCacheManager;
+use crate::core::error::MetaServiceError;
+use crate::raft::manager::MultiRaftManager;
+use crate::server::services::common::bootstrap::load_resource;
+use crate::server::services::common::schema::{bind_schema_req, create_schema_req};
+use crate::server::services::common::tenant::create_tenant_by_req;
+use crate::server::services::mqtt::acl::{create_acl_by_req, create_blacklist_by_req};
+use crate::server::services::mqtt::connector::create_connector_by_req;
+use crate::server::services::mqtt::message_rule::create_message_rule_by_req;
+use crate::server::services::mqtt::session::create_session_by_req;
+use crate::server::services::mqtt::subscribe::{
+    create_auto_subscribe_rule_by_req, set_subscribe_by_req,
+};
+use crate::server::services::mqtt::topic::{create_topic_by_req, create_topic_rewrite_rule_by_req};
+use crate::server::services::mqtt::user::create_user_by_req;
+use crate::storage::common::schema::SchemaStorage;
+use crate::storage::common::tenant::TenantStorage;
+use crate::storage::mqtt::acl::AclStorage;
+use crate::storage::mqtt::blacklist::MqttBlackListStorage;
+use crate::storage::mqtt::connector::MqttConnectorStorage;
+use crate::storage::mqtt::message_rule::MqttMessageRuleStorage;
+use crate::storage::mqtt::session::MqttSessionStorage;
+use crate::storage::mqtt::subscribe::MqttSubscribeStorage;
+use crate::storage::mqtt::topic::MqttTopicStorage;
+use crate::storage::mqtt::user::SecurityUserStorage;
+use common_base::tools::now_second;
+use common_base::utils::serialize;
+use common_base::version::version;
+use metadata_struct::auth::acl::SecurityAcl;
+use metadata_struct::auth::blacklist::SecurityBlackList;
+use metadata_struct::auth::user::SecurityUser;
+use metadata_struct::connector::MQTTConnector;
+use metadata_struct::meta::bootstrap::BootstrapResource;
+use metadata_struct::mqtt::auto_subscribe::MqttAutoSubscribeRule;
+use metadata_struct::mqtt::message_rule::MqttMessageRule;
+use metadata_struct::mqtt::session::MqttSession;
+use metadata_struct::mqtt::subscribe::MqttSubscribe;
+use metadata_struct::mqtt::topic_rewrite_rule::MqttTopicRewriteRule;
+use metadata_struct::schema::{SchemaData, SchemaResourceBind};
+use metadata_struct::tenant::{Tenant, DEFAULT_TENANT};
+use metadata_struct::topic::Topic;
+use node_call::NodeCallManager;
+use protocol::meta::meta_service_common::{
+    BindSchemaRequest, CreateSchemaRequest, CreateTenantRequest, ExportMetadataReply,
+    ExportMetadataRequest, ImportMetadataReply, ImportMetadataRequest, MetadataDumpResource,
+    MetadataImportResult,
+};
+use protocol::meta::meta_service_mqtt::{
+    CreateAclRequest, CreateAutoSubscribeRuleRequest, CreateBlacklistRequest,
+    CreateConnectorRequest, CreateMessageRuleRequest, CreateSessionRaw, CreateSessionRequest,
+    CreateTopicRequest, CreateTopicRewriteRuleRequest, CreateUserRequest, SetSubscribeRequest,
+};
+use rocksdb_engine::rocksdb::RocksDBEngine;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+pub const METADATA_DUMP_FORMAT_VERSION: u32 = 1;
+
+/// Resource types carried by a dump, in restore order: tenants first, and
+/// every record after the records it refers to. Share groups, NATS and mq9
+/// state is runtime state that clients rebuild, it is not dumped.
+pub const METADATA_DUMP_RESOURCES: [BootstrapResource; 13] = [
+    BootstrapResource::Tenant,
+    BootstrapResource::User,
+    BootstrapResource::Acl,
+    BootstrapResource::Blacklist,
+    BootstrapResource::Topic,
+    BootstrapResource::TopicRewriteRule,
+    BootstrapResource::AutoSubscribeRule,
+    BootstrapResource::MessageRule,
+    BootstrapResource::Session,
+    BootstrapResource::Subscribe,
+    BootstrapResource::Connector,
+    BootstrapResource::Schema,
+    BootstrapResource::SchemaBind,
+];
+
+pub fn export_metadata_by_req(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    req: &ExportMetadataRequest,
+) -> Result<ExportMetadataReply, MetaServiceError> {
+    let resources = if req.resource_types.is_empty() {
+        METADATA_DUMP_RESOURCES.to_vec()
+    } else {
+        BootstrapResource::parse_list(&req.resource_types)?
+    };
+
+    let mut dump = Vec::with_capacity(resources.len());
+    for resource in resources {
+        if !METADATA_DUMP_RESOURCES.contains(&resource) {
+            return Err(MetaServiceError::MetadataDumpIncompatible(format!(
+                "resource type {} cannot be dumped",
+                resource
+            )));
+        }
+        dump.push(MetadataDumpResource {
+            resource_type: resource.to_string(),
+            records: load_resource(rocksdb_engine_handler, resource)?,
+        });
+    }
+
+    Ok(ExportMetadataReply {
+        format_version: METADATA_DUMP_FORMAT_VERSION,
+        meta_version: version(),
+        export_time: now_second(),
+        resources: dump,
+    })
+}
+
+/// Dumps of a newer format, or taken on a newer release than the one running
+/// here, may carry records this cluster cannot decode.
+pub fn check_dump_version(
+    format_version: u32,
+    dump_meta_version: &str,
+    meta_version: &str,
+) -> Result<(), MetaServiceError> {
+    if format_version > METADATA_DUMP_FORMAT_VERSION {
+        return Err(MetaServiceError::MetadataDumpIncompatible(format!(
+            "dump format version {} is newer than the supported version {}",
+            format_version, METADATA_DUMP_FORMAT_VERSION
+        )));
+    }
+    if let (Some(dump), Some(current)) = (
+        release_version(dump_meta_version),
+        release_version(meta_version),
+    ) {
+        if dump > current {
+            return Err(MetaServiceError::MetadataDumpIncompatible(format!(
+                "dump was taken on {}, which is newer than this cluster ({})",
+                dump_meta_version, meta_version
+            )));
+        }
+    }
+    Ok(())
+}
+
+// (major, minor) of a `v0.3.1` or `0.3.1-rc1` style version.
+fn release_version(version: &str) -> Option<(u64, u64)> {
+    let mut parts = version.trim().trim_start_matches('v').split(['.', '-']);
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+enum DumpRecord {
+    Tenant(Tenant),
+    User(SecurityUser),
+    Acl(SecurityAcl),
+    Blacklist(SecurityBlackList),
+    Topic(Topic),
+    TopicRewriteRule(MqttTopicRewriteRule),
+    AutoSubscribeRule(MqttAutoSubscribeRule),
+    MessageRule(MqttMessageRule),
+    Session(MqttSession),
+    Subscribe(MqttSubscribe),
+    Connector(MQTTConnector),
+    Schema(SchemaData),
+    SchemaBind(SchemaResourceBind),
+}
+
+impl DumpRecord {
+    fn decode(resource: BootstrapResource, data: &[u8]) -> Result<Self, MetaServiceError> {
+        let record = match resource {
+            BootstrapResource::Tenant => DumpRecord::Tenant(serialize::deserialize(data)?),
+            BootstrapResource::User => DumpRecord::User(serialize::deserialize(data)?),
+            BootstrapResource::Acl => DumpRecord::Acl(serialize::deserialize(data)?),
+            BootstrapResource::Blacklist => DumpRecord::Blacklist(serialize::deserialize(data)?),
+            BootstrapResource::Topic => DumpRecord::Topic(serialize::deserialize(data)?),
+            BootstrapResource::TopicRewriteRule => {
+                DumpRecord::TopicRewriteRule(serialize::deserialize(data)?)
+            }
+            BootstrapResource::AutoSubscribeRule => {
+                DumpRecord::AutoSubscribeRule(serialize::deserialize(data)?)
+            }
+            BootstrapResource::MessageRule => {
+                DumpRecord::MessageRule(serialize::deserialize(data)?)
+            }
+            BootstrapResource::Session => DumpRecord::Session(serialize::deserialize(data)?),
+            BootstrapResource::Subscribe => DumpRecord::Subscribe(serialize::deserialize(data)?),
+            BootstrapResource::Connector => DumpRecord::Connector(serialize::deserialize(data)?),
+            BootstrapResource::Schema => DumpRecord::Schema(serialize::deserialize(data)?),
+            BootstrapResource::SchemaBind => DumpRecord::SchemaBind(serialize::deserialize(data)?),
+            other => {
+                return Err(MetaServiceError::MetadataDumpIncompatible(format!(
+                    "resource type {} cannot be restored",
+                    other
+                )))
+            }
+        };
+        Ok(record)
+    }
+
+    fn tenant(&self) -> &str {
+        match self {
+            DumpRecord::Tenant(r) => &r.tenant_name,
+            DumpRecord::User(r) => &r.tenant,
+            DumpRecord::Acl(r) => &r.tenant,
+            DumpRecord::Blacklist(r) => &r.tenant,
+            DumpRecord::Topic(r) => &r.tenant,
+            DumpRecord::TopicRewriteRule(r) => &r.tenant,
+            DumpRecord::AutoSubscribeRule(r) => &r.tenant,
+            DumpRecord::MessageRule(r) => &r.tenant,
+            DumpRecord::Session(r) => &r.tenant,
+            DumpRecord::Subscribe(r) => &r.tenant,
+            DumpRecord::Connector(r) => &r.tenant,
+            DumpRecord::Schema(r) => &r.tenant,
+            DumpRecord::SchemaBind(r) => &r.tenant,
+        }
+    }
+
+    fn name(&self) -> String {
+        match self {
+            DumpRecord::Tenant(r) => r.tenant_name.clone(),
+            DumpRecord::User(r) => r.username.clone(),
+            DumpRecord::Acl(r) => r.name.clone(),
+            DumpRecord::Blacklist(r) => r.name.clone(),
+            DumpRecord::Topic(r) => r.topic_name.clone(),
+            DumpRecord::TopicRewriteRule(r) => r.name.clone(),
+            DumpRecord::AutoSubscribeRule(r) => r.name.clone(),
+            DumpRecord::MessageRule(r) => r.name.clone(),
+            DumpRecord::Session(r) => r.client_id.clone(),
+            DumpRecord::Subscribe(r) => format!("{}/{}", r.client_id, r.path),
+            DumpRecord::Connector(r) => r.connector_name.clone(),
+            DumpRecord::Schema(r) => r.name.clone(),
+            DumpRecord::SchemaBind(r) => format!("{}/{}", r.schema_name, r.resource_name),
+        }
+    }
+
+    fn exists(
+        &self,
+        rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    ) -> Result<bool, MetaServiceError> {
+        let handler = rocksdb_engine_handler.clone();
+        let exists = match self {
+            DumpRecord::Tenant(r) => TenantStorage::new(handler).get(&r.tenant_name)?.is_some(),
+            DumpRecord::User(r) => SecurityUserStorage::new(handler)
+                .get(&r.tenant, &r.username)?
+                .is_some(),
+            DumpRecord::Acl(r) => AclStorage::new(handler).get(&r.tenant, &r.name)?.is_some(),
+            DumpRecord::Blacklist(r) => MqttBlackListStorage::new(handler)
+                .get(&r.tenant, &r.name)?
+                .is_some(),
+            DumpRecord::Topic(r) => MqttTopicStorage::new(handler)
+                .get(&r.tenant, &r.topic_name)?
+                .is_some(),
+            DumpRecord::TopicRewriteRule(r) => MqttTopicStorage::new(handler)
+                .get_topic_rewrite_rule(&r.tenant, &r.name)?
+                .is_some(),
+            DumpRecord::AutoSubscribeRule(r) => MqttSubscribeStorage::new(handler)
+                .get_auto_subscribe_rule(&r.tenant, &r.name)?
+                .is_some(),
+            DumpRecord::MessageRule(r) => MqttMessageRuleStorage::new(handler)
+                .get(&r.tenant, &r.name)?
+                .is_some(),
+            DumpRecord::Session(r) => MqttSessionStorage::new(handler)
+                .get(&r.tenant, &r.client_id)?
+                .is_some(),
+            DumpRecord::Subscribe(r) => MqttSubscribeStorage::new(handler)
+                .get(&r.client_id, &r.path)?
+                .is_some(),
+            DumpRecord::Connector(r) => MqttConnectorStorage::new(handler)
+                .get(&r.connector_name)?
+                .is_some(),
+            DumpRecord::Schema(r) => SchemaStorage::new(handler)
+                .get(&r.tenant, &r.name)?
+                .is_some(),
+            DumpRecord::SchemaBind(r) => SchemaStorage::new(handler)
+                .get_bind(&r.tenant, &r.resource_name, &r.schema_name)?
+                .is_some(),
+        };
+        Ok(exists)
+    }
+}
+
+struct DumpResource {
+    resource: BootstrapResource,
+    records: Vec<(DumpRecord, Vec<u8>)>,
+}
+
+fn decode_dump(resources: &[MetadataDumpResource]) -> Result<Vec<DumpResource>, MetaServiceError> {
+    let mut decoded = Vec::with_capacity(resources.len());
+    for raw in resources {
+        let resource: BootstrapResource = raw.resource_type.parse()?;
+        let records = raw
+            .records
+            .iter()
+            .map(|data| Ok((DumpRecord::decode(resource, data)?, data.clone())))
+            .collect::<Result<Vec<_>, MetaServiceError>>()?;
+        decoded.push(DumpResource { resource, records });
+    }
+    decoded.sort_by_key(|r| {
+        METADATA_DUMP_RESOURCES
+            .iter()
+            .position(|resource| *resource == r.resource)
+    });
+    Ok(decoded)
+}
+
+/// Returns one message per record whose tenant, schema or session exists
+/// neither in the dump nor in the cluster.
+fn check_references(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    dump: &[DumpResource],
+) -> Result<Vec<String>, MetaServiceError> {
+    let mut tenants: HashSet<String> = TenantStorage::new(rocksdb_engine_handler.clone())
+        .list()?
+        .into_iter()
+        .map(|t| t.tenant_name)
+        .collect();
+    tenants.insert(DEFAULT_TENANT.to_string());
+    let mut schemas = HashSet::new();
+    let mut sessions = HashSet::new();
+    for (record, _) in dump.iter().flat_map(|r| r.records.iter()) {
+        match record {
+            DumpRecord::Tenant(t) => {
+                tenants.insert(t.tenant_name.clone());
+            }
+            DumpRecord::Schema(s) => {
+                schemas.insert((s.tenant.clone(), s.name.clone()));
+            }
+            DumpRecord::Session(s) => {
+                sessions.insert((s.tenant.clone(), s.client_id.clone()));
+            }
+            _ => {}
+        }
+    }
+
+    let schema_storage = SchemaStorage::new(rocksdb_engine_handler.clone());
+    let session_storage = MqttSessionStorage::new(rocksdb_engine_handler.clone());
+    let mut errors = Vec::new();
+    for resource in dump {
+        for (record, _) in &resource.records {
+            let tenant = record.tenant();
+            if !tenants.contains(tenant) {
+                errors.push(format!(
+                    "{} {}: tenant {} does not exist",
+                    resource.resource,
+                    record.name(),
+                    tenant
+                ));
+            }
+            let missing = match record {
+                DumpRecord::SchemaBind(b) => {
+                    !schemas.contains(&(b.tenant.clone(), b.schema_name.clone()))
+                        && schema_storage.get(&b.tenant, &b.schema_name)?.is_none()
+                }
+                DumpRecord::Subscribe(s) => {
+                    !sessions.contains(&(s.tenant.clone(), s.client_id.clone()))
+                        && session_storage.get(&s.tenant, &s.client_id)?.is_none()
+                }
+                _ => false,
+            };
+            if missing {
+                let referenced = match record {
+                    DumpRecord::SchemaBind(b) => format!("schema {}", b.schema_name),
+                    DumpRecord::Subscribe(s) => format!("session {}", s.client_id),
+                    _ => unreachable!(),
+                };
+                errors.push(format!(
+                    "{} {}: {} does not exist",
+                    resource.resource,
+                    record.name(),
+                    referenced
+                ));
+            }
+        }
+    }
+    Ok(errors)
+}
+
+pub async fn import_metadata_by_req(
+    raft_manager: &Arc<MultiRaftManager>,
+    call_manager: &Arc<NodeCallManager>,
+    cache_manager: &Arc<MetaCacheManager>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    req: &ImportMetadataRequest,
+) -> Result<ImportMetadataReply, MetaServiceError> {
+    check_dump_version(req.format_version, &req.meta_version, &version())?;
+    let dump = decode_dump(&req.resources)?;
+    let errors = check_references(rocksdb_engine_handler, &dump)?;
+    if !errors.is_empty() {
+        return Err(MetaServiceError::MetadataDumpInvalid(errors.join("; ")));
+    }
+
+    let mut results = Vec::with_capacity(dump.len());
+    for resource in &dump {
+        let mut result = MetadataImportResult {
+            resource_type: resource.resource.to_string(),
+            ..Default::default()
+        };
+        for (record, data) in &resource.records {
+            if record.exists(rocksdb_engine_handler)? {
+                result.skipped += 1;
+                continue;
+            }
+            if !req.dry_run {
+                import_record(
+                    raft_manager,
+                    call_manager,
+                    cache_manager,
+                    rocksdb_engine_handler,
+                    record,
+                    data,
+                )
+                .await?;
+            }
+            result.imported += 1;
+        }
+        results.push(result);
+    }
+    Ok(ImportMetadataReply { results })
+}
+
+async fn import_record(
+    raft_manager: &Arc<MultiRaftManager>,
+    call_manager: &Arc<NodeCallManager>,
+    cache_manager: &Arc<MetaCacheManager>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    record: &DumpRecord,
+    data: &[u8],
+) -> Result<(), MetaServiceError> {
+    match record {
+        DumpRecord::Tenant(r) => {
+            let req = CreateTenantRequest {
+                tenant_name: r.tenant_name.clone(),
+                desc: r.desc.clone(),
+                config: r.config.encode()?,
+            };
+            create_tenant_by_req(raft_manager, call_manager, rocksdb_engine_handler, &req).await?;
+        }
+        DumpRecord::User(r) => {
+            let req = CreateUserRequest {
+                tenant: r.tenant.clone(),
+                user_name: r.username.clone(),
+                content: data.to_vec(),
+            };
+            create_user_by_req(raft_manager, call_manager, rocksdb_engine_handler, &req).await?;
+        }
+        DumpRecord::Acl(_) => {
+            let req = CreateAclRequest { acl: data.to_vec() };
+            create_acl_by_req(raft_manager, call_manager, &req).await?;
+        }
+        DumpRecord::Blacklist(_) => {
+            let req = CreateBlacklistRequest {
+                blacklist: data.to_vec(),
+            };
+            create_blacklist_by_req(raft_manager, call_manager, &req).await?;
+        }
+        DumpRecord::Topic(r) => {
+            let req = CreateTopicRequest {
+                tenant: r.tenant.clone(),
+                topic_name: r.topic_name.clone(),
+                content: data.to_vec(),
+            };
+            create_topic_by_req(raft_manager, call_manager, rocksdb_engine_handler, &req).await?;
+        }
+        DumpRecord::TopicRewriteRule(r) => {
+            let req = CreateTopicRewriteRuleRequest {
+                name: r.name.clone(),
+                desc: r.desc.clone(),
+                tenant: r.tenant.clone(),
+                action: r.action.clone(),
+                source_topic: r.source_topic.clone(),
+                dest_topic: r.dest_topic.clone(),
+                regex: r.regex.clone(),
+            };
+            create_topic_rewrite_rule_by_req(
+                raft_manager,
+                rocksdb_engine_handler,
+                call_manager,
+                &req,
+            )
+            .await?;
+        }
+        DumpRecord::AutoSubscribeRule(_) => {
+            let req = CreateAutoSubscribeRuleRequest {
+                content: data.to_vec(),
+            };
+            create_auto_subscribe_rule_by_req(
+                raft_manager,
+                rocksdb_engine_handler,
+                call_manager,
+                &req,
+            )
+            .await?;
+        }
+        DumpRecord::MessageRule(_) => {
+            let req = CreateMessageRuleRequest {
+                content: data.to_vec(),
+            };
+            create_message_rule_by_req(raft_manager, rocksdb_engine_handler, call_manager, &req)
+                .await?;
+        }
+        DumpRecord::Session(r) => {
+            let req = CreateSessionRequest {
+                sessions: vec![CreateSessionRaw {
+                    client_id: r.client_id.clone(),
+                    session: data.to_vec(),
+                }],
+            };
+            create_session_by_req(raft_manager, call_manager, &req).await?;
+        }
+        DumpRecord::Subscribe(r) => {
+            let req = SetSubscribeRequest {
+                client_id: r.client_id.clone(),
+                path: r.path.clone(),
+                subscribe: data.to_vec(),
+            };
+            set_subscribe_by_req(raft_manager, call_manager, &req).await?;
+        }
+        DumpRecord::Connector(r) => {
+            let req = CreateConnectorRequest {
+                connector_name: r.connector_name.clone(),
+                connector: data.to_vec(),
+            };
+            create_connector_by_req(
+                rocksdb_engine_handler,
+                raft_manager,
+                call_manager,
+                cache_manager,
+                &req,
+            )
+            .await?;
+        }
+        DumpRecord::Schema(r) => {
+            let req = CreateSchemaRequest {
+                tenant: r.tenant.clone(),
+                schema_name: r.name.clone(),
+                schema: data.to_vec(),
+            };
+            create_schema_req(raft_manager, call_manager, &req, rocksdb_engine_handler).await?;
+        }
+        DumpRecord::SchemaBind(r) => {
+            let req = BindSchemaRequest {
+                tenant: r.tenant.clone(),
+                schema_name: r.schema_name.clone(),
+                resource_name: r.resource_name.clone(),
+            };
+            bind_schema_req(rocksdb_engine_handler, raft_manager, call_manager, &req).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_dump_version() {
+        assert!(check_dump_version(1, "v0.3.0", "v0.3.2").is_ok());
+        assert!(check_dump_version(1, "0.2.9", "0.3.0").is_ok());
+        assert!(check_dump_version(1, "unknown", "0.3.0").is_ok());
+        assert!(check_dump_version(1, "0.4.0-rc1", "0.3.5").is_err());
+        assert!(check_dump_version(METADATA_DUMP_FORMAT_VERSION + 1, "0.3.0", "0.3.0").is_err());
+    }
+
+    #[test]
+    fn test_decode_dump_orders_resources() {
+        let tenant = Tenant {
+            tenant_name: "t1".to_string(),
+            ..Default::default()
+        };
+        let bind = SchemaResourceBind {
+            tenant: "t1".to_string(),
+            schema_name: "s1".to_string(),
+            resource_name: "topic1".to_string(),
+        };
+        let dump = decode_dump(&[
+            MetadataDumpResource {
+                resource_type: BootstrapResource::SchemaBind.to_string(),
+                records: vec![serialize::serialize(&bind).unwrap()],
+            },
+            MetadataDumpResource {
+                resource_type: BootstrapResource::Tenant.to_string(),
+                records: vec![tenant.encode().unwrap()],
+            },
+        ])
+        .unwrap();
+        assert_eq!(dump[0].resource, BootstrapResource::Tenant);
+        assert_eq!(dump[1].resource, BootstrapResource::SchemaBind);
+        assert_eq!(dump[1].records[0].0.name(), "s1/topic1");
+
+        let unsupported = decode_dump(&[MetadataDumpResource {
+            resource_type: BootstrapResource::ShareGroup.to_string(),
+            records: vec![vec![0]],
+        }]);
+        assert!(unsupported.is_err());
+    }
+}
@@ -12,7 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod bootstrap;
+pub mod digest;
 pub mod inner;
 pub mod kv;
+pub mod lock;
+pub mod metadata_dump;
 pub mod schema;
 pub mod tenant;
+pub mod watch;
@@ -0,0 +1,134 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::core::cache::MetaCacheManager;
+use crate::core::error::MetaServiceError;
+use crate::core::watch::{WatchEvent, WatchHub};
+use metadata_struct::meta::watch::{WatchEventType, WatchResource};
+use protocol::meta::meta_service_common::{WatchResourcesReply, WatchResourcesRequest};
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tonic::codegen::tokio_stream::Stream;
+use tonic::Status;
+
+type WatchResourcesStream = Result<
+    Pin<Box<dyn Stream<Item = Result<WatchResourcesReply, Status>> + Send>>,
+    MetaServiceError,
+>;
+
+pub fn watch_resources_by_req(
+    cache_manager: &Arc<MetaCacheManager>,
+    req: &WatchResourcesRequest,
+) -> WatchResourcesStream {
+    let resources = WatchResource::parse_list(&req.resource_types)?;
+    let hub = cache_manager.watch_hub.clone();
+    let token = (!req.revision.is_empty()).then_some(req.revision.as_str());
+    let subscription = hub.subscribe(token)?;
+
+    let output = async_stream::try_stream! {
+        let start = hub.token(subscription.revision);
+        yield WatchResourcesReply {
+            revision: start.clone(),
+            event_type: WatchEventType::Bookmark.to_string(),
+            ..Default::default()
+        };
+
+        for event in subscription.backlog {
+            if resources.contains(&event.resource) {
+                yield event_reply(&hub, event);
+            }
+        }
+
+        let mut receiver = subscription.receiver;
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    if resources.contains(&event.resource) {
+                        yield event_reply(&hub, event);
+                    }
+                }
+                // This stream fell further behind than the backlog holds, so
+                // some events are gone; the client has to relist.
+                Err(RecvError::Lagged(_)) => {
+                    Err(Status::internal(
+                        MetaServiceError::WatchRevisionExpired(start.clone()).to_string(),
+                    ))?;
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    };
+
+    Ok(Box::pin(output))
+}
+
+fn event_reply(hub: &WatchHub, event: WatchEvent) -> WatchResourcesReply {
+    WatchResourcesReply {
+        revision: hub.token(event.revision),
+        resource_type: event.resource.to_string(),
+        event_type: event.event_type.to_string(),
+        key: event.key,
+        data: event.data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::codegen::tokio_stream::StreamExt;
+
+    #[tokio::test]
+    async fn test_watch_resources_filters_types() {
+        let cache_manager = Arc::new(MetaCacheManager::default());
+        let hub = cache_manager.watch_hub.clone();
+        let req = WatchResourcesRequest {
+            resource_types: vec!["topic".to_string()],
+            revision: String::new(),
+        };
+        let mut stream = watch_resources_by_req(&cache_manager, &req).unwrap();
+
+        let bookmark = stream.next().await.unwrap().unwrap();
+        assert_eq!(bookmark.event_type, "bookmark");
+        assert_eq!(bookmark.revision, hub.token(0));
+
+        hub.publish(
+            WatchResource::User,
+            WatchEventType::Create,
+            "default/u1".to_string(),
+            Vec::new(),
+        );
+        hub.publish(
+            WatchResource::Topic,
+            WatchEventType::Delete,
+            "default/t1".to_string(),
+            Vec::new(),
+        );
+
+        let reply = stream.next().await.unwrap().unwrap();
+        assert_eq!(reply.resource_type, "topic");
+        assert_eq!(reply.event_type, "delete");
+        assert_eq!(reply.key, "default/t1");
+        assert_eq!(reply.revision, hub.token(2));
+    }
+
+    #[tokio::test]
+    async fn test_watch_resources_rejects_stale_revision() {
+        let cache_manager = Arc::new(MetaCacheManager::default());
+        let req = WatchResourcesRequest {
+            resource_types: Vec::new(),
+            revision: "unknown-1".to_string(),
+        };
+        assert!(watch_resources_by_req(&cache_manager, &req).is_err());
+    }
+}
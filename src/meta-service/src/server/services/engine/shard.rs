@@ -15,9 +15,11 @@
 use crate::core::cache::MetaCacheManager;
 use crate::core::error::MetaServiceError;
 use crate::core::segment::create_segment;
+use crate::core::segment_replica::{check_zone_spread, failure_domains};
 use crate::core::shard::{create_shard, update_shard_status};
 use crate::raft::manager::MultiRaftManager;
 use crate::storage::journal::shard::ShardStorage;
+use common_config::broker::broker_config;
 use metadata_struct::storage::shard::{EngineShard, EngineShardConfig, EngineShardStatus};
 use node_call::NodeCallManager;
 use protocol::meta::meta_service_journal::{
@@ -71,12 +73,13 @@ pub async fn create_shard_by_req(
 ) -> Result<CreateShardReply, MetaServiceError> {
     let shard_config: EngineShardConfig = EngineShardConfig::decode(&req.shard_config)?;
 
-    // Regular topics need enough engine nodes for the full replica set before we
-    // create the shard — otherwise create_segment fails afterwards and leaves an
+    // Regular topics need enough engine nodes (and zones) for the full replica
+    // set before we create the shard — otherwise create_segment fails afterwards and leaves an
     // orphan shard. Inner/system topics are exempt: they may start
     // under-replicated and are topped up by the background fill task.
     if !shard_config.is_inner_topic {
-        let engine_node_num = cache_manager.get_engine_node_list().len() as u32;
+        let nodes = cache_manager.get_engine_node_list();
+        let engine_node_num = nodes.len() as u32;
         if engine_node_num < shard_config.replica_num {
             return Err(MetaServiceError::NotEnoughEngineNodes(
                 "CreateShard".to_string(),
@@ -84,6 +87,14 @@ pub async fn create_shard_by_req(
                 engine_node_num,
             ));
         }
+        let node_ids: Vec<u64> = nodes.iter().map(|n| n.node_id).collect();
+        check_zone_spread(
+            "CreateShard",
+            &node_ids,
+            &failure_domains(&nodes),
+            shard_config.replica_num as usize,
+            broker_config().placement.min_replica_zones,
+        )?;
     }
 
     let already_exists = cache_manager.shard_list.contains_key(&req.shard_name);
@@ -18,6 +18,7 @@
     send_notify_by_delete_blacklist,
 };
 use crate::raft::manager::MultiRaftManager;
+use crate::server::services::mqtt::list::ListQuery;
 use crate::storage::mqtt::blacklist::MqttBlackListStorage;
 use crate::{
     raft::route::data::{StorageData, StorageDataType},
@@ -40,18 +41,26 @@ pub fn list_acl_by_req(
     rocksdb_engine_handler: &Arc<RocksDBEngine>,
     req: &ListAclRequest,
 ) -> Result<ListAclReply, MetaServiceError> {
+    let query = ListQuery {
+        tenant: &req.tenant,
+        name_prefix: &req.name_prefix,
+        offset: req.offset,
+        limit: req.limit,
+        ..Default::default()
+    }
+    .with_name_regex(&req.name_regex)?;
     let acl_storage = AclStorage::new(rocksdb_engine_handler.clone());
     let acls = if req.tenant.is_empty() {
         acl_storage.list_all()?
     } else {
         acl_storage.list_by_tenant(&req.tenant)?
     };
-    let encoded = acls
-        .into_iter()
-        .map(|acl| acl.encode())
-        .collect::<Result<Vec<_>, _>>()?;
+    let page = query.page(acls).encode(|acl| acl.encode())?;
 
-    Ok(ListAclReply { acls: encoded })
+    Ok(ListAclReply {
+        acls: page.items,
+        total: page.total,
+    })
 }
 
 pub async fn create_acl_by_req(
@@ -91,18 +100,26 @@ pub fn list_blacklist_by_req(
     rocksdb_engine_handler: &Arc<RocksDBEngine>,
     req: &ListBlacklistRequest,
 ) -> Result<ListBlacklistReply, MetaServiceError> {
+    let query = ListQuery {
+        tenant: &req.tenant,
+        name_prefix: &req.name_prefix,
+        offset: req.offset,
+        limit: req.limit,
+        ..Default::default()
+    }
+    .with_name_regex(&req.name_regex)?;
     let blacklist_storage = MqttBlackListStorage::new(rocksdb_engine_handler.clone());
     let items = if req.tenant.is_empty() {
         blacklist_storage.list_all()?
     } else {
         blacklist_storage.list_by_tenant(&req.tenant)?
     };
-    let blacklists = items
-        .into_iter()
-        .map(|item| item.encode())
-        .collect::<Result<Vec<_>, _>>()?;
+    let page = query.page(items).encode(|item| item.encode())?;
 
-    Ok(ListBlacklistReply { blacklists })
+    Ok(ListBlacklistReply {
+        blacklists: page.items,
+        total: page.total,
+    })
 }
 
 pub async fn create_blacklist_by_req(
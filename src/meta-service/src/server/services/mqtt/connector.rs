@@ -12,20 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::controller::connector_status::ConnectorStatus;
+use crate::controller::connector::status::ConnectorStatus;
 use crate::core::cache::MetaCacheManager;
 use crate::core::error::MetaServiceError;
 use crate::core::notify::send_notify_by_delete_connector;
 use crate::raft::manager::MultiRaftManager;
 use crate::raft::route::data::{StorageData, StorageDataType};
+use crate::server::services::mqtt::list::ListQuery;
 use crate::storage::mqtt::connector::MqttConnectorStorage;
 use common_base::utils::serialize::encode_to_bytes;
+use metadata_struct::connector::checkpoint::ConnectorCheckpoint;
 use metadata_struct::connector::MQTTConnector;
 use node_call::NodeCallManager;
 use protocol::meta::meta_service_mqtt::{
-    ConnectorHeartbeatReply, ConnectorHeartbeatRequest, CreateConnectorReply,
-    CreateConnectorRequest, DeleteConnectorReply, DeleteConnectorRequest, ListConnectorReply,
-    ListConnectorRequest, UpdateConnectorReply, UpdateConnectorRequest,
+    CommitConnectorCheckpointReply, CommitConnectorCheckpointRequest, ConnectorHeartbeatReply,
+    ConnectorHeartbeatRequest, CreateConnectorReply, CreateConnectorRequest, DeleteConnectorReply,
+    DeleteConnectorRequest, GetConnectorCheckpointReply, GetConnectorCheckpointRequest,
+    ListConnectorReply, ListConnectorRequest, UpdateConnectorReply, UpdateConnectorRequest,
 };
 use rocksdb_engine::rocksdb::RocksDBEngine;
 use serde::{Deserialize, Serialize};
@@ -43,7 +46,11 @@
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ConnectorHeartbeat {
     pub connector_name: String,
+    pub broker_id: u64,
     pub last_heartbeat: u64,
+    pub message_total: u64,
+    // Smoothed records per second, the connector's load when rebalancing
+    pub message_rate: f64,
 }
 
 // Connector Heartbeat
@@ -63,7 +70,12 @@ pub fn connector_heartbeat_by_req(
                 continue;
             }
 
-            cache_manager.report_connector_heartbeat(&raw.connector_name, raw.heartbeat_time);
+            cache_manager.report_connector_heartbeat(
+                &raw.connector_name,
+                raw.broker_id,
+                raw.heartbeat_time,
+                raw.message_total,
+            );
         }
     }
     Ok(ConnectorHeartbeatReply {})
@@ -74,24 +86,27 @@ pub fn list_connectors_by_req(
     rocksdb_engine_handler: &Arc<RocksDBEngine>,
     req: &ListConnectorRequest,
 ) -> ListConnectorStream {
+    let query = ListQuery {
+        tenant: &req.tenant,
+        name_prefix: &req.name_prefix,
+        offset: req.offset,
+        limit: req.limit,
+        ..Default::default()
+    }
+    .with_name_regex(&req.name_regex)?;
     let storage = MqttConnectorStorage::new(rocksdb_engine_handler.clone());
 
-    let connectors = if !req.connector_name.is_empty() {
-        match storage.get(&req.connector_name)? {
-            Some(data) => vec![data.encode()?],
-            None => Vec::new(),
-        }
+    let connectors: Vec<MQTTConnector> = if !req.connector_name.is_empty() {
+        storage.get(&req.connector_name)?.into_iter().collect()
     } else {
-        storage
-            .list()?
-            .into_iter()
-            .map(|raw| raw.encode())
-            .collect::<Result<Vec<_>, _>>()?
+        storage.list()?
     };
+    let page = query.page(connectors).encode(|raw| raw.encode())?;
+    let total = page.total;
 
     let output = async_stream::try_stream! {
-        for connector in connectors {
-            yield ListConnectorReply { connector };
+        for connector in page.items {
+            yield ListConnectorReply { connector, total };
         }
     };
 
@@ -172,3 +187,130 @@ pub async fn delete_connector_by_req(
 
     Ok(DeleteConnectorReply {})
 }
+
+// Connector Checkpoint
+pub async fn commit_connector_checkpoint_by_req(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    raft_manager: &Arc<MultiRaftManager>,
+    cache_manager: &Arc<MetaCacheManager>,
+    req: &CommitConnectorCheckpointRequest,
+) -> Result<CommitConnectorCheckpointReply, MetaServiceError> {
+    let checkpoint = ConnectorCheckpoint::decode(&req.checkpoint)?;
+    let storage = MqttConnectorStorage::new(rocksdb_engine_handler.clone());
+    let stored = storage.get_checkpoint(&req.connector_name)?;
+    let owner = cache_manager
+        .connector_list
+        .get(&req.connector_name)
+        .map(|connector| connector.broker_id)
+        .ok_or_else(|| MetaServiceError::ConnectorNotFound(req.connector_name.clone()))?;
+
+    if !check_checkpoint(&req.connector_name, &checkpoint, owner, stored.as_ref())? {
+        // A retry of a commit that was already applied.
+        return Ok(CommitConnectorCheckpointReply {});
+    }
+
+    let data = StorageData::new(
+        StorageDataType::MqttSetConnectorCheckpoint,
+        encode_to_bytes(req),
+    );
+    raft_manager.write_metadata(data).await?;
+    Ok(CommitConnectorCheckpointReply {})
+}
+
+pub fn get_connector_checkpoint_by_req(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    req: &GetConnectorCheckpointRequest,
+) -> Result<GetConnectorCheckpointReply, MetaServiceError> {
+    let storage = MqttConnectorStorage::new(rocksdb_engine_handler.clone());
+    let checkpoint = match storage.get_checkpoint(&req.connector_name)? {
+        Some(checkpoint) => checkpoint.encode()?,
+        None => Vec::new(),
+    };
+    Ok(GetConnectorCheckpointReply { checkpoint })
+}
+
+/// Whether `checkpoint` should be written. Only the broker the connector is
+/// assigned to may commit, which fences a broker that lost the connector in a
+/// failover, and sequences must increase. Returns `Ok(false)` for a retry of
+/// the stored commit.
+fn check_checkpoint(
+    connector_name: &str,
+    checkpoint: &ConnectorCheckpoint,
+    owner: Option<u64>,
+    stored: Option<&ConnectorCheckpoint>,
+) -> Result<bool, MetaServiceError> {
+    let reject = |reason: String| {
+        Err(MetaServiceError::ConnectorCheckpointRejected(
+            connector_name.to_string(),
+            reason,
+        ))
+    };
+
+    if checkpoint.connector_name != connector_name {
+        return reject(format!(
+            "checkpoint belongs to connector {}",
+            checkpoint.connector_name
+        ));
+    }
+    if owner != Some(checkpoint.broker_id) {
+        return reject(format!(
+            "broker {} does not own the connector",
+            checkpoint.broker_id
+        ));
+    }
+    let Some(stored) = stored else {
+        return Ok(true);
+    };
+    if checkpoint.sequence > stored.sequence {
+        return Ok(true);
+    }
+    if checkpoint.sequence == stored.sequence
+        && checkpoint.source_offsets == stored.source_offsets
+        && checkpoint.transaction_id == stored.transaction_id
+    {
+        return Ok(false);
+    }
+    reject(format!(
+        "sequence {} is not above the committed sequence {}",
+        checkpoint.sequence, stored.sequence
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkpoint(sequence: u64, broker_id: u64, offset: u64) -> ConnectorCheckpoint {
+        ConnectorCheckpoint {
+            connector_name: "c1".to_string(),
+            broker_id,
+            sequence,
+            source_offsets: [("s0".to_string(), offset)].into(),
+            transaction_id: format!("tx-{sequence}"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_check_checkpoint() {
+        // First commit of the owner.
+        assert!(check_checkpoint("c1", &checkpoint(1, 1, 10), Some(1), None).unwrap());
+
+        // Another broker, or an unassigned connector, is fenced off.
+        for owner in [Some(2), None] {
+            assert!(matches!(
+                check_checkpoint("c1", &checkpoint(1, 1, 10), owner, None),
+                Err(MetaServiceError::ConnectorCheckpointRejected(_, _))
+            ));
+        }
+        assert!(check_checkpoint("c2", &checkpoint(1, 1, 10), Some(1), None).is_err());
+
+        let stored = checkpoint(5, 1, 50);
+        assert!(check_checkpoint("c1", &checkpoint(6, 1, 60), Some(1), Some(&stored)).unwrap());
+        // Retried commit.
+        assert!(!check_checkpoint("c1", &checkpoint(5, 1, 50), Some(1), Some(&stored)).unwrap());
+        // Stale commits.
+        assert!(check_checkpoint("c1", &checkpoint(5, 1, 40), Some(1), Some(&stored)).is_err());
+        assert!(check_checkpoint("c1", &checkpoint(4, 1, 60), Some(1), Some(&stored)).is_err());
+    }
+}
@@ -0,0 +1,335 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::core::cache_list::ListCacheEntry;
+use crate::core::error::MetaServiceError;
+use metadata_struct::auth::acl::SecurityAcl;
+use metadata_struct::auth::blacklist::SecurityBlackList;
+use metadata_struct::auth::user::SecurityUser;
+use metadata_struct::connector::MQTTConnector;
+use metadata_struct::mqtt::auto_subscribe::MqttAutoSubscribeRule;
+use metadata_struct::mqtt::message_rule::MqttMessageRule;
+use metadata_struct::mqtt::subscribe::MqttSubscribe;
+use metadata_struct::mqtt::topic_rewrite_rule::MqttTopicRewriteRule;
+use regex::Regex;
+
+/// A resource returned by a paged list RPC.
+pub trait ListItem {
+    fn list_tenant(&self) -> &str;
+
+    /// The name `name_prefix` / `name_regex` filters apply to.
+    fn list_name(&self) -> &str;
+
+    /// Latest create/update time in seconds, used by `updated_since`.
+    fn list_updated_at(&self) -> u64 {
+        0
+    }
+}
+
+impl<T: ListItem> ListItem for &T {
+    fn list_tenant(&self) -> &str {
+        (*self).list_tenant()
+    }
+
+    fn list_name(&self) -> &str {
+        (*self).list_name()
+    }
+
+    fn list_updated_at(&self) -> u64 {
+        (*self).list_updated_at()
+    }
+}
+
+/// Paging and filtering options shared by the MQTT list RPCs. Empty strings
+/// and zeros mean "any".
+#[derive(Clone, Debug, Default)]
+pub struct ListQuery<'a> {
+    pub tenant: &'a str,
+    pub name: &'a str,
+    pub name_prefix: &'a str,
+    pub name_regex: Option<Regex>,
+    pub offset: u32,
+    pub limit: u32,
+    pub updated_since: u64,
+}
+
+/// One page of a list result and the number of matches before paging.
+pub struct ListPage<T> {
+    pub items: Vec<T>,
+    pub total: u64,
+}
+
+impl ListQuery<'_> {
+    pub fn with_name_regex(mut self, pattern: &str) -> Result<Self, MetaServiceError> {
+        if !pattern.is_empty() {
+            let regex = Regex::new(pattern)
+                .map_err(|e| MetaServiceError::InvalidListFilter(e.to_string()))?;
+            self.name_regex = Some(regex);
+        }
+        Ok(self)
+    }
+
+    pub fn matches(&self, item: &impl ListItem) -> bool {
+        let name = item.list_name();
+        (self.tenant.is_empty() || item.list_tenant() == self.tenant)
+            && (self.name.is_empty() || name == self.name)
+            && name.starts_with(self.name_prefix)
+            && self.name_regex.as_ref().is_none_or(|r| r.is_match(name))
+            && item.list_updated_at() >= self.updated_since
+    }
+
+    /// Apply the filters, count the matches and cut the offset/limit window.
+    pub fn page<T: ListItem>(&self, items: impl IntoIterator<Item = T>) -> ListPage<T> {
+        let limit = match self.limit {
+            0 => usize::MAX,
+            limit => limit as usize,
+        };
+        let offset = self.offset as usize;
+
+        let mut total = 0u64;
+        let mut page = Vec::new();
+        for item in items.into_iter().filter(|item| self.matches(item)) {
+            let index = total as usize;
+            total += 1;
+            if index >= offset && page.len() < limit {
+                page.push(item);
+            }
+        }
+        ListPage { items: page, total }
+    }
+
+    /// [`ListQuery::page`] over cached entries, returning the encoded data.
+    pub fn page_cached<'e>(
+        &self,
+        entries: impl IntoIterator<Item = &'e ListCacheEntry>,
+    ) -> ListPage<Vec<u8>> {
+        let page = self.page(entries);
+        ListPage {
+            items: page.items.into_iter().map(|e| e.data.clone()).collect(),
+            total: page.total,
+        }
+    }
+}
+
+impl<T> ListPage<T> {
+    /// Encode the page items, keeping the total.
+    pub fn encode<F, E>(self, encode: F) -> Result<ListPage<Vec<u8>>, MetaServiceError>
+    where
+        F: Fn(&T) -> Result<Vec<u8>, E>,
+        MetaServiceError: From<E>,
+    {
+        let items = self
+            .items
+            .iter()
+            .map(|item| encode(item).map_err(MetaServiceError::from))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ListPage {
+            items,
+            total: self.total,
+        })
+    }
+}
+
+impl ListItem for ListCacheEntry {
+    fn list_tenant(&self) -> &str {
+        &self.tenant
+    }
+
+    fn list_name(&self) -> &str {
+        &self.name
+    }
+
+    fn list_updated_at(&self) -> u64 {
+        self.updated_at
+    }
+}
+
+impl ListItem for SecurityUser {
+    fn list_tenant(&self) -> &str {
+        &self.tenant
+    }
+
+    fn list_name(&self) -> &str {
+        &self.username
+    }
+
+    fn list_updated_at(&self) -> u64 {
+        self.create_time
+    }
+}
+
+impl ListItem for SecurityAcl {
+    fn list_tenant(&self) -> &str {
+        &self.tenant
+    }
+
+    fn list_name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl ListItem for SecurityBlackList {
+    fn list_tenant(&self) -> &str {
+        &self.tenant
+    }
+
+    fn list_name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl ListItem for MqttTopicRewriteRule {
+    fn list_tenant(&self) -> &str {
+        &self.tenant
+    }
+
+    fn list_name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl ListItem for MqttSubscribe {
+    fn list_tenant(&self) -> &str {
+        &self.tenant
+    }
+
+    fn list_name(&self) -> &str {
+        &self.client_id
+    }
+
+    fn list_updated_at(&self) -> u64 {
+        self.create_time
+    }
+}
+
+impl ListItem for MQTTConnector {
+    fn list_tenant(&self) -> &str {
+        &self.tenant
+    }
+
+    fn list_name(&self) -> &str {
+        &self.connector_name
+    }
+
+    fn list_updated_at(&self) -> u64 {
+        self.update_time.max(self.create_time)
+    }
+}
+
+impl ListItem for MqttAutoSubscribeRule {
+    fn list_tenant(&self) -> &str {
+        &self.tenant
+    }
+
+    fn list_name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl ListItem for MqttMessageRule {
+    fn list_tenant(&self) -> &str {
+        &self.tenant
+    }
+
+    fn list_name(&self) -> &str {
+        &self.name
+    }
+
+    fn list_updated_at(&self) -> u64 {
+        self.create_time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(tenant: &str, name: &str, updated_at: u64) -> ListCacheEntry {
+        ListCacheEntry {
+            tenant: tenant.to_string(),
+            name: name.to_string(),
+            data: name.as_bytes().to_vec(),
+            updated_at,
+        }
+    }
+
+    fn names(page: ListPage<Vec<u8>>) -> Vec<String> {
+        page.items
+            .into_iter()
+            .map(|d| String::from_utf8(d).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_list_query_page() {
+        let entries = [
+            entry("t1", "sensor-a", 10),
+            entry("t1", "sensor-b", 20),
+            entry("t1", "device-c", 30),
+            entry("t2", "sensor-a", 40),
+        ];
+
+        let all = ListQuery::default().page_cached(entries.iter());
+        assert_eq!(all.total, 4);
+        assert_eq!(all.items.len(), 4);
+
+        let page = ListQuery {
+            tenant: "t1",
+            offset: 1,
+            limit: 1,
+            ..Default::default()
+        }
+        .page_cached(entries.iter());
+        assert_eq!(page.total, 3);
+        assert_eq!(names(page), vec!["sensor-b"]);
+
+        let prefix = ListQuery {
+            name_prefix: "sensor-",
+            ..Default::default()
+        }
+        .page_cached(entries.iter());
+        assert_eq!(prefix.total, 3);
+
+        let since = ListQuery {
+            updated_since: 25,
+            ..Default::default()
+        }
+        .page_cached(entries.iter());
+        assert_eq!(names(since), vec!["device-c", "sensor-a"]);
+
+        let past_end = ListQuery {
+            offset: 10,
+            ..Default::default()
+        }
+        .page_cached(entries.iter());
+        assert_eq!(past_end.total, 4);
+        assert!(past_end.items.is_empty());
+    }
+
+    #[test]
+    fn test_list_query_regex() {
+        let entries = [entry("t1", "sensor-1", 0), entry("t1", "sensor-x", 0)];
+        let query = ListQuery::default()
+            .with_name_regex(r"^sensor-\d+$")
+            .unwrap();
+        assert_eq!(names(query.page_cached(entries.iter())), vec!["sensor-1"]);
+
+        assert!(ListQuery::default().with_name_regex("(").is_err());
+        assert!(ListQuery::default()
+            .with_name_regex("")
+            .unwrap()
+            .name_regex
+            .is_none());
+    }
+}
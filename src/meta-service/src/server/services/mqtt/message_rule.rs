@@ -0,0 +1,107 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    core::error::MetaServiceError,
+    core::notify::{send_notify_by_create_message_rule, send_notify_by_delete_message_rule},
+    raft::{
+        manager::MultiRaftManager,
+        route::data::{StorageData, StorageDataType},
+    },
+    server::services::mqtt::list::ListQuery,
+    storage::mqtt::message_rule::MqttMessageRuleStorage,
+};
+use common_base::utils::serialize::encode_to_bytes;
+use metadata_struct::mqtt::message_rule::MqttMessageRule;
+use node_call::NodeCallManager;
+use protocol::meta::meta_service_mqtt::{
+    CreateMessageRuleReply, CreateMessageRuleRequest, DeleteMessageRuleReply,
+    DeleteMessageRuleRequest, ListMessageRuleReply, ListMessageRuleRequest,
+};
+use rocksdb_engine::rocksdb::RocksDBEngine;
+use std::sync::Arc;
+
+pub async fn create_message_rule_by_req(
+    raft_manager: &Arc<MultiRaftManager>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    call_manager: &Arc<NodeCallManager>,
+    req: &CreateMessageRuleRequest,
+) -> Result<CreateMessageRuleReply, MetaServiceError> {
+    let rule = MqttMessageRule::decode(&req.content)
+        .map_err(|e| MetaServiceError::CommonError(e.to_string()))?;
+
+    let storage = MqttMessageRuleStorage::new(rocksdb_engine_handler.clone());
+    if storage.get(&rule.tenant, &rule.name)?.is_some() {
+        return Err(MetaServiceError::CommonError(format!(
+            "Message rule '{}' for tenant '{}' already exists",
+            rule.name, rule.tenant
+        )));
+    }
+
+    let data = StorageData::new(StorageDataType::MqttCreateMessageRule, encode_to_bytes(req));
+    raft_manager.write_metadata(data).await?;
+
+    send_notify_by_create_message_rule(call_manager, rule).await?;
+
+    Ok(CreateMessageRuleReply {})
+}
+
+pub async fn delete_message_rule_by_req(
+    raft_manager: &Arc<MultiRaftManager>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    call_manager: &Arc<NodeCallManager>,
+    req: &DeleteMessageRuleRequest,
+) -> Result<DeleteMessageRuleReply, MetaServiceError> {
+    let storage = MqttMessageRuleStorage::new(rocksdb_engine_handler.clone());
+    let rule = storage.get(&req.tenant, &req.name)?.ok_or_else(|| {
+        MetaServiceError::CommonError(format!(
+            "Message rule '{}' for tenant '{}' does not exist",
+            req.name, req.tenant
+        ))
+    })?;
+
+    let data = StorageData::new(StorageDataType::MqttDeleteMessageRule, encode_to_bytes(req));
+    raft_manager.write_metadata(data).await?;
+
+    send_notify_by_delete_message_rule(call_manager, rule).await?;
+
+    Ok(DeleteMessageRuleReply {})
+}
+
+pub fn list_message_rule_by_req(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    req: &ListMessageRuleRequest,
+) -> Result<ListMessageRuleReply, MetaServiceError> {
+    let query = ListQuery {
+        tenant: &req.tenant,
+        name_prefix: &req.name_prefix,
+        offset: req.offset,
+        limit: req.limit,
+        ..Default::default()
+    }
+    .with_name_regex(&req.name_regex)?;
+    let storage = MqttMessageRuleStorage::new(rocksdb_engine_handler.clone());
+    let data = if req.tenant.is_empty() {
+        storage.list_all()?
+    } else {
+        storage.list_by_tenant(&req.tenant)?
+    };
+
+    let page = query.page(data).encode(|raw| raw.encode())?;
+
+    Ok(ListMessageRuleReply {
+        message_rules: page.items,
+        total: page.total,
+    })
+}
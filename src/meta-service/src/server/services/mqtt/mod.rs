@@ -14,8 +14,12 @@
 
 pub mod acl;
 pub mod connector;
+pub mod list;
+pub mod message_rule;
+pub mod retain;
 pub mod session;
 pub mod share_group;
 pub mod subscribe;
+pub mod subscribe_route;
 pub mod topic;
 pub mod user;
@@ -0,0 +1,133 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::core::error::MetaServiceError;
+use crate::raft::manager::MultiRaftManager;
+use crate::raft::route::data::{StorageData, StorageDataType};
+use crate::storage::mqtt::topic::MqttTopicStorage;
+use common_base::utils::serialize::encode_to_bytes;
+use common_base::utils::topic_util::{topic_filter_literal_prefix, topic_filter_match};
+use metadata_struct::mqtt::retain_message::MQTTRetainIndex;
+use protocol::meta::meta_service_mqtt::{
+    DeleteRetainIndexReply, DeleteRetainIndexRequest, ListRetainIndexReply, ListRetainIndexRequest,
+    SetRetainIndexReply, SetRetainIndexRequest,
+};
+use rocksdb_engine::rocksdb::RocksDBEngine;
+use std::sync::Arc;
+
+pub async fn set_retain_index_by_req(
+    raft_manager: &Arc<MultiRaftManager>,
+    req: &SetRetainIndexRequest,
+) -> Result<SetRetainIndexReply, MetaServiceError> {
+    MQTTRetainIndex::decode(&req.index)?;
+    let data = StorageData::new(StorageDataType::MqttSetRetainIndex, encode_to_bytes(req));
+    raft_manager.write_data(&req.topic_name, data).await?;
+    Ok(SetRetainIndexReply {})
+}
+
+pub async fn delete_retain_index_by_req(
+    raft_manager: &Arc<MultiRaftManager>,
+    req: &DeleteRetainIndexRequest,
+) -> Result<DeleteRetainIndexReply, MetaServiceError> {
+    let data = StorageData::new(StorageDataType::MqttDeleteRetainIndex, encode_to_bytes(req));
+    raft_manager.write_data(&req.topic_name, data).await?;
+    Ok(DeleteRetainIndexReply {})
+}
+
+/// Index entries matching `topic_filter`. A plain topic is a point lookup;
+/// a wildcard filter scans the keys under its literal prefix. Expired entries
+/// are returned too, the broker deletes them together with their payload.
+pub fn list_retain_index_by_req(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    req: &ListRetainIndexRequest,
+) -> Result<ListRetainIndexReply, MetaServiceError> {
+    let storage = MqttTopicStorage::new(rocksdb_engine_handler.clone());
+    let candidates = if req.topic_filter.contains(['+', '#']) {
+        storage.list_retain_index_by_prefix(
+            &req.tenant,
+            topic_filter_literal_prefix(&req.topic_filter),
+        )?
+    } else {
+        storage
+            .get_retain_index(&req.tenant, &req.topic_filter)?
+            .into_iter()
+            .collect()
+    };
+
+    let mut indexes = Vec::new();
+    for index in candidates {
+        if topic_filter_match(&req.topic_filter, &index.topic_name) {
+            indexes.push(index.encode()?);
+        }
+    }
+    Ok(ListRetainIndexReply { indexes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common_config::broker::{default_broker_config, init_broker_conf_by_config};
+    use metadata_struct::tenant::DEFAULT_TENANT;
+    use rocksdb_engine::test::test_rocksdb_instance;
+
+    fn save(storage: &MqttTopicStorage, topic: &str) {
+        storage
+            .save_retain_index(MQTTRetainIndex {
+                tenant: DEFAULT_TENANT.to_string(),
+                topic_name: topic.to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+    }
+
+    fn list(rocksdb: &Arc<RocksDBEngine>, filter: &str) -> Vec<String> {
+        let reply = list_retain_index_by_req(
+            rocksdb,
+            &ListRetainIndexRequest {
+                tenant: DEFAULT_TENANT.to_string(),
+                topic_filter: filter.to_string(),
+            },
+        )
+        .unwrap();
+        let mut topics: Vec<String> = reply
+            .indexes
+            .iter()
+            .map(|raw| MQTTRetainIndex::decode(raw).unwrap().topic_name)
+            .collect();
+        topics.sort();
+        topics
+    }
+
+    #[test]
+    fn test_list_retain_index_by_filter() {
+        init_broker_conf_by_config(default_broker_config());
+        let rocksdb = test_rocksdb_instance();
+        let storage = MqttTopicStorage::new(rocksdb.clone());
+        save(&storage, "sensor/1/temp");
+        save(&storage, "sensor/2/temp");
+        save(&storage, "sensor/2/humidity");
+        save(&storage, "sensor");
+        save(&storage, "sensors/1/temp");
+        save(&storage, "$SYS/brokers");
+
+        assert_eq!(list(&rocksdb, "sensor/2/temp"), vec!["sensor/2/temp"]);
+        assert!(list(&rocksdb, "sensor/9/temp").is_empty());
+        assert_eq!(
+            list(&rocksdb, "sensor/+/temp"),
+            vec!["sensor/1/temp", "sensor/2/temp"]
+        );
+        assert_eq!(list(&rocksdb, "sensor/#").len(), 4);
+        assert_eq!(list(&rocksdb, "#").len(), 5);
+        assert_eq!(list(&rocksdb, "$SYS/#"), vec!["$SYS/brokers"]);
+    }
+}
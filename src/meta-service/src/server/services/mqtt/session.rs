@@ -12,24 +12,29 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::core::cache::MetaCacheManager;
+use crate::core::cache_list::{ListCacheEntry, ListResource};
 use crate::core::error::MetaServiceError;
 use crate::core::notify::{send_notify_by_add_session, send_notify_by_delete_session};
 use crate::raft::manager::MultiRaftManager;
+use crate::server::services::mqtt::list::ListQuery;
 use crate::storage::mqtt::subscribe::MqttSubscribeStorage;
 use crate::{
     raft::route::data::{StorageData, StorageDataType},
     storage::mqtt::session::MqttSessionStorage,
 };
 use broker_core::cache::NodeCacheManager;
-use common_base::tools::now_second;
+use common_base::tools::now_millis;
 use common_base::utils::serialize::encode_to_bytes;
 use delay_task::manager::DelayTaskManager;
 use delay_task::{DelayTask, DelayTaskData};
 use metadata_struct::mqtt::session::MqttSession;
 use node_call::NodeCallManager;
+use prost::Message;
 use protocol::meta::meta_service_mqtt::{
-    CreateSessionReply, CreateSessionRequest, DeleteSessionReply, DeleteSessionRequest,
-    DeleteSubscribeRequest, ListSessionReply, ListSessionRequest,
+    AllocateLastWillEpochReply, AllocateLastWillEpochRequest, ClaimLastWillReply,
+    ClaimLastWillRequest, CreateSessionReply, CreateSessionRequest, DeleteSessionReply,
+    DeleteSessionRequest, DeleteSubscribeRequest, ListSessionReply, ListSessionRequest,
 };
 use rocksdb_engine::rocksdb::RocksDBEngine;
 use std::pin::Pin;
@@ -42,18 +47,32 @@
 
 // Session Operations
 pub fn list_session_by_req(
+    cache_manager: &Arc<MetaCacheManager>,
     node_cache_manager: &Arc<NodeCacheManager>,
     rocksdb_engine_handler: &Arc<RocksDBEngine>,
     req: &ListSessionRequest,
 ) -> ListSessionStream {
-    let mut sessions = read_not_persist_session(node_cache_manager, &req.tenant, &req.client_id)?;
-    let persist_sessions =
-        read_persist_session(rocksdb_engine_handler, &req.tenant, &req.client_id)?;
-    sessions.extend(persist_sessions);
+    let query = ListQuery {
+        tenant: &req.tenant,
+        name: &req.client_id,
+        name_prefix: &req.name_prefix,
+        offset: req.offset,
+        limit: req.limit,
+        updated_since: req.updated_since,
+        ..Default::default()
+    }
+    .with_name_regex(&req.name_regex)?;
+
+    // Non-persistent sessions only live in memory; persistent ones are served
+    // from the list cache. Pagination runs over both, in that order.
+    let not_persist_sessions = read_not_persist_session(node_cache_manager, &query)?;
+    let persist_sessions = read_persist_session(cache_manager, rocksdb_engine_handler, &query)?;
+    let page = query.page_cached(not_persist_sessions.iter().chain(persist_sessions.iter()));
+    let total = page.total;
 
     let output = async_stream::try_stream! {
-        for session in sessions {
-            yield ListSessionReply { session };
+        for session in page.items {
+            yield ListSessionReply { session, total };
         }
     };
 
@@ -62,60 +81,76 @@ pub fn list_session_by_req(
 
 fn read_not_persist_session(
     node_cache_manager: &Arc<NodeCacheManager>,
-    tenant: &str,
-    client_id: &str,
-) -> Result<Vec<Vec<u8>>, MetaServiceError> {
-    if !client_id.is_empty() {
-        if let Some(session) = node_cache_manager.get_session(tenant, client_id) {
-            return Ok(vec![session.encode()?]);
-        }
-        return Ok(vec![]);
-    }
-
-    if !tenant.is_empty() {
-        let sessions = node_cache_manager
-            .list_sessions_by_tenant(tenant)
+    query: &ListQuery,
+) -> Result<Vec<ListCacheEntry>, MetaServiceError> {
+    let sessions: Vec<MqttSession> = if !query.name.is_empty() {
+        node_cache_manager
+            .get_session(query.tenant, query.name)
             .into_iter()
-            .map(|s| s.encode())
-            .collect::<Result<Vec<_>, _>>()?;
-        return Ok(sessions);
-    }
+            .collect()
+    } else if !query.tenant.is_empty() {
+        node_cache_manager.list_sessions_by_tenant(query.tenant)
+    } else {
+        node_cache_manager
+            .session_list
+            .iter()
+            .map(|e| e.value().clone())
+            .collect()
+    };
 
-    let sessions = node_cache_manager
-        .session_list
-        .iter()
-        .map(|e| e.value().encode())
+    let mut entries = sessions
+        .into_iter()
+        .map(session_list_entry)
         .collect::<Result<Vec<_>, _>>()?;
-
-    Ok(sessions)
+    entries.sort_by(|a, b| (&a.tenant, &a.name).cmp(&(&b.tenant, &b.name)));
+    Ok(entries)
 }
 
+// A lookup by client id reads the single record instead of the list snapshot,
+// which would be rebuilt after every session write.
 fn read_persist_session(
+    cache_manager: &Arc<MetaCacheManager>,
     rocksdb_engine_handler: &Arc<RocksDBEngine>,
-    tenant: &str,
-    client_id: &str,
-) -> Result<Vec<Vec<u8>>, MetaServiceError> {
-    let storage = MqttSessionStorage::new(rocksdb_engine_handler.clone());
-    let mut sessions = Vec::new();
-
-    if !client_id.is_empty() {
-        if let Some(data) = storage.get(tenant, client_id)? {
-            sessions.push(data.encode()?);
-        }
-    } else if !tenant.is_empty() {
-        let data = storage.list_by_tenant(tenant)?;
-        sessions = data
-            .into_iter()
-            .map(|raw| raw.encode())
-            .collect::<Result<Vec<_>, _>>()?;
-    } else {
-        let data = storage.list()?;
-        sessions = data
+    query: &ListQuery,
+) -> Result<Arc<Vec<ListCacheEntry>>, MetaServiceError> {
+    if !query.tenant.is_empty() && !query.name.is_empty() {
+        let storage = MqttSessionStorage::new(rocksdb_engine_handler.clone());
+        let entries = storage
+            .get(query.tenant, query.name)?
             .into_iter()
-            .map(|raw| raw.encode())
+            .map(session_list_entry)
             .collect::<Result<Vec<_>, _>>()?;
+        return Ok(Arc::new(entries));
     }
-    Ok(sessions)
+    cache_manager
+        .list_cache
+        .get_or_load(ListResource::Session, || {
+            load_session_list_cache(rocksdb_engine_handler)
+        })
+}
+
+fn load_session_list_cache(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+) -> Result<Vec<ListCacheEntry>, MetaServiceError> {
+    let storage = MqttSessionStorage::new(rocksdb_engine_handler.clone());
+    storage
+        .list()?
+        .into_iter()
+        .map(session_list_entry)
+        .collect()
+}
+
+fn session_list_entry(session: MqttSession) -> Result<ListCacheEntry, MetaServiceError> {
+    let updated_at = session
+        .create_time
+        .max(session.reconnect_time.unwrap_or_default())
+        .max(session.distinct_time.unwrap_or_default());
+    Ok(ListCacheEntry {
+        data: session.encode()?,
+        updated_at,
+        tenant: session.tenant,
+        name: session.client_id,
+    })
 }
 
 pub async fn create_session_by_req(
@@ -182,7 +217,7 @@ pub async fn delete_session_by_req(
             .create_task(DelayTask::build_persistent(
                 req.client_id.clone(),
                 DelayTaskData::MQTTLastwillExpire(req.tenant.clone(), req.client_id.clone()),
-                now_second() + delay,
+                now_millis() as u64 + delay * 1000,
             ))
             .await?;
     }
@@ -190,3 +225,42 @@ pub async fn delete_session_by_req(
 
     Ok(DeleteSessionReply {})
 }
+
+/// Allocate the epoch of a will through the data raft group, so epochs of a
+/// client grow whichever broker it connects to.
+pub async fn allocate_last_will_epoch_by_req(
+    raft_manager: &Arc<MultiRaftManager>,
+    req: &AllocateLastWillEpochRequest,
+) -> Result<AllocateLastWillEpochReply, MetaServiceError> {
+    let data = StorageData::new(
+        StorageDataType::MqttAllocateLastWillEpoch,
+        encode_to_bytes(req),
+    );
+    let response = raft_manager
+        .write_data(&req.client_id, data)
+        .await?
+        .ok_or(MetaServiceError::ExecutionResultIsEmpty)?;
+    let value = response
+        .data
+        .value
+        .ok_or(MetaServiceError::ExecutionResultIsEmpty)?;
+    Ok(AllocateLastWillEpochReply::decode(value.as_ref())?)
+}
+
+/// Decide through the data raft group which broker publishes the will of a
+/// session termination, so a will dispatched to several brokers goes out once.
+pub async fn claim_last_will_by_req(
+    raft_manager: &Arc<MultiRaftManager>,
+    req: &ClaimLastWillRequest,
+) -> Result<ClaimLastWillReply, MetaServiceError> {
+    let data = StorageData::new(StorageDataType::MqttClaimLastWill, encode_to_bytes(req));
+    let response = raft_manager
+        .write_data(&req.client_id, data)
+        .await?
+        .ok_or(MetaServiceError::ExecutionResultIsEmpty)?;
+    let value = response
+        .data
+        .value
+        .ok_or(MetaServiceError::ExecutionResultIsEmpty)?;
+    Ok(ClaimLastWillReply::decode(value.as_ref())?)
+}
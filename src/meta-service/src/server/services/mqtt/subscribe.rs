@@ -22,6 +22,7 @@
         manager::MultiRaftManager,
         route::data::{StorageData, StorageDataType},
     },
+    server::services::mqtt::list::ListQuery,
     storage::mqtt::subscribe::MqttSubscribeStorage,
 };
 use common_base::utils::serialize::encode_to_bytes;
@@ -80,18 +81,23 @@ pub async fn delete_subscribe_by_req(
 
 pub fn list_subscribe_by_req(
     rocksdb_engine_handler: &Arc<RocksDBEngine>,
-    _req: &ListSubscribeRequest,
+    req: &ListSubscribeRequest,
 ) -> ListSubscribeStream {
+    let query = ListQuery {
+        tenant: &req.tenant,
+        name_prefix: &req.name_prefix,
+        offset: req.offset,
+        limit: req.limit,
+        ..Default::default()
+    }
+    .with_name_regex(&req.name_regex)?;
     let storage = MqttSubscribeStorage::new(rocksdb_engine_handler.clone());
-    let subscribes = storage
-        .list_all()?
-        .into_iter()
-        .map(|raw| raw.encode())
-        .collect::<Result<Vec<_>, _>>()?;
+    let page = query.page(storage.list_all()?).encode(|raw| raw.encode())?;
+    let total = page.total;
 
     let output = async_stream::try_stream! {
-        for subscribe in subscribes {
-            yield ListSubscribeReply { subscribe };
+        for subscribe in page.items {
+            yield ListSubscribeReply { subscribe, total };
         }
     };
 
@@ -177,6 +183,14 @@ pub fn list_auto_subscribe_rule_by_req(
     rocksdb_engine_handler: &Arc<RocksDBEngine>,
     req: &ListAutoSubscribeRuleRequest,
 ) -> Result<ListAutoSubscribeRuleReply, MetaServiceError> {
+    let query = ListQuery {
+        tenant: &req.tenant,
+        name_prefix: &req.name_prefix,
+        offset: req.offset,
+        limit: req.limit,
+        ..Default::default()
+    }
+    .with_name_regex(&req.name_regex)?;
     let storage = MqttSubscribeStorage::new(rocksdb_engine_handler.clone());
     let data = if req.tenant.is_empty() {
         storage.list_all_auto_subscribe_rules()?
@@ -184,12 +198,10 @@ pub fn list_auto_subscribe_rule_by_req(
         storage.list_auto_subscribe_rules_by_tenant(&req.tenant)?
     };
 
-    let auto_subscribe_rules = data
-        .into_iter()
-        .map(|raw| raw.encode())
-        .collect::<Result<Vec<_>, _>>()?;
+    let page = query.page(data).encode(|raw| raw.encode())?;
 
     Ok(ListAutoSubscribeRuleReply {
-        auto_subscribe_rules,
+        auto_subscribe_rules: page.items,
+        total: page.total,
     })
 }
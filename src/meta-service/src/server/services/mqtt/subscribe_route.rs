@@ -0,0 +1,103 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    core::{cache::MetaCacheManager, error::MetaServiceError},
+    raft::{
+        manager::MultiRaftManager,
+        route::data::{StorageData, StorageDataType},
+    },
+    storage::mqtt::subscribe_route::{MqttSubscribeRoute, MqttSubscribeRouteStorage},
+};
+use common_base::utils::serialize::encode_to_bytes;
+use protocol::meta::meta_service_mqtt::{
+    GetNodesForTopicReply, GetNodesForTopicRequest, SubscribeRoute, SyncSubscribeRouteReply,
+    SyncSubscribeRouteRequest, UpdateSubscribeRouteReply, UpdateSubscribeRouteRequest,
+};
+use rocksdb_engine::rocksdb::RocksDBEngine;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+pub async fn update_subscribe_route_by_req(
+    raft_manager: &Arc<MultiRaftManager>,
+    req: &UpdateSubscribeRouteRequest,
+) -> Result<UpdateSubscribeRouteReply, MetaServiceError> {
+    check_routes(req.add.iter().chain(req.remove.iter()))?;
+    if req.add.is_empty() && req.remove.is_empty() {
+        return Ok(UpdateSubscribeRouteReply {});
+    }
+
+    let data = StorageData::new(
+        StorageDataType::MqttUpdateSubscribeRoute,
+        encode_to_bytes(req),
+    );
+    raft_manager.write_metadata(data).await?;
+    Ok(UpdateSubscribeRouteReply {})
+}
+
+/// Anti-entropy full sync of the routes of one broker. Only written through
+/// raft when the registered routes differ from the broker's.
+pub async fn sync_subscribe_route_by_req(
+    raft_manager: &Arc<MultiRaftManager>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    req: &SyncSubscribeRouteRequest,
+) -> Result<SyncSubscribeRouteReply, MetaServiceError> {
+    check_routes(req.routes.iter())?;
+
+    let storage = MqttSubscribeRouteStorage::new(rocksdb_engine_handler.clone());
+    let registered: HashSet<MqttSubscribeRoute> =
+        storage.list_by_node(req.broker_id)?.into_iter().collect();
+    let routes: HashSet<MqttSubscribeRoute> = req
+        .routes
+        .iter()
+        .map(|raw| MqttSubscribeRoute {
+            broker_id: req.broker_id,
+            tenant: raw.tenant.clone(),
+            filter: raw.filter.clone(),
+        })
+        .collect();
+    if registered == routes {
+        return Ok(SyncSubscribeRouteReply { changed: false });
+    }
+
+    let data = StorageData::new(
+        StorageDataType::MqttSyncSubscribeRoute,
+        encode_to_bytes(req),
+    );
+    raft_manager.write_metadata(data).await?;
+    Ok(SyncSubscribeRouteReply { changed: true })
+}
+
+pub fn get_nodes_for_topic_by_req(
+    cache_manager: &Arc<MetaCacheManager>,
+    req: &GetNodesForTopicRequest,
+) -> Result<GetNodesForTopicReply, MetaServiceError> {
+    Ok(GetNodesForTopicReply {
+        node_ids: cache_manager
+            .subscribe_route
+            .nodes_for_topic(&req.tenant, &req.topic_name),
+    })
+}
+
+fn check_routes<'a>(
+    mut routes: impl Iterator<Item = &'a SubscribeRoute>,
+) -> Result<(), MetaServiceError> {
+    match routes.find(|route| route.tenant.is_empty() || route.filter.is_empty()) {
+        Some(route) => Err(MetaServiceError::CommonError(format!(
+            "Invalid subscribe route, tenant '{}', filter '{}'",
+            route.tenant, route.filter
+        ))),
+        None => Ok(()),
+    }
+}
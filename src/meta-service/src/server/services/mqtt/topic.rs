@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::core::cache::MetaCacheManager;
+use crate::core::cache_list::{ListCacheEntry, ListResource};
 use crate::core::error::MetaServiceError;
 use crate::core::notify::{
     send_notify_by_create_topic_rewrite_rule, send_notify_by_delete_topic,
@@ -19,6 +21,7 @@
 };
 use crate::raft::manager::MultiRaftManager;
 use crate::raft::route::data::{StorageData, StorageDataType};
+use crate::server::services::mqtt::list::ListQuery;
 use crate::storage::mqtt::topic::MqttTopicStorage;
 use common_base::tools::now_millis;
 use common_base::utils::serialize::encode_to_bytes;
@@ -28,8 +31,9 @@
 use protocol::meta::meta_service_mqtt::{
     CreateTopicReply, CreateTopicRequest, CreateTopicRewriteRuleReply,
     CreateTopicRewriteRuleRequest, DeleteTopicReply, DeleteTopicRequest,
-    DeleteTopicRewriteRuleReply, DeleteTopicRewriteRuleRequest, ListTopicReply, ListTopicRequest,
-    ListTopicRewriteRuleReply, ListTopicRewriteRuleRequest,
+    DeleteTopicRewriteRuleReply, DeleteTopicRewriteRuleRequest, DescribeTopicReply,
+    DescribeTopicRequest, ListTopicReply, ListTopicRequest, ListTopicRewriteRuleReply,
+    ListTopicRewriteRuleRequest, ReportTopicStatsReply, ReportTopicStatsRequest,
 };
 use rocksdb_engine::rocksdb::RocksDBEngine;
 use std::pin::Pin;
@@ -39,46 +43,72 @@
 
 // Topic Operations
 pub async fn list_topic_by_req(
+    cache_manager: &Arc<MetaCacheManager>,
     rocksdb_engine_handler: &Arc<RocksDBEngine>,
     req: &ListTopicRequest,
 ) -> Result<Pin<Box<dyn Stream<Item = Result<ListTopicReply, Status>> + Send>>, MetaServiceError> {
-    let storage = MqttTopicStorage::new(rocksdb_engine_handler.clone());
-    let mut topics = Vec::new();
-
-    if !req.topic_name.is_empty() {
-        let tenant = if req.tenant.is_empty() {
-            ""
-        } else {
-            &req.tenant
-        };
-        if let Some(topic) = storage.get(tenant, &req.topic_name)? {
-            topics.push(topic.encode()?);
-        }
-    } else if !req.tenant.is_empty() {
-        let data = storage.list_by_tenant(&req.tenant)?;
-        topics = data
-            .into_iter()
-            .map(|raw| raw.encode())
-            .collect::<Result<Vec<_>, _>>()?;
-    } else {
-        let data = storage.list()?;
-        topics = data
-            .into_iter()
-            .map(|raw| raw.encode())
-            .collect::<Result<Vec<_>, _>>()?;
+    let query = ListQuery {
+        tenant: &req.tenant,
+        name: &req.topic_name,
+        name_prefix: &req.name_prefix,
+        offset: req.offset,
+        limit: req.limit,
+        updated_since: req.updated_since,
+        ..Default::default()
     }
+    .with_name_regex(&req.name_regex)?;
+    let entries = read_topic_entries(cache_manager, rocksdb_engine_handler, &query)?;
+    let page = query.page_cached(entries.iter());
+    let total = page.total;
 
     let output = async_stream::try_stream! {
-        for topic in topics {
-            yield ListTopicReply {
-                topic,
-            };
+        for topic in page.items {
+            yield ListTopicReply { topic, total };
         }
     };
 
     Ok(Box::pin(output))
 }
 
+// A lookup by topic name reads the single record instead of the list
+// snapshot, which would be rebuilt after every topic write.
+fn read_topic_entries(
+    cache_manager: &Arc<MetaCacheManager>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    query: &ListQuery,
+) -> Result<Arc<Vec<ListCacheEntry>>, MetaServiceError> {
+    if !query.tenant.is_empty() && !query.name.is_empty() {
+        let storage = MqttTopicStorage::new(rocksdb_engine_handler.clone());
+        let entries = storage
+            .get(query.tenant, query.name)?
+            .into_iter()
+            .map(topic_list_entry)
+            .collect::<Result<Vec<_>, _>>()?;
+        return Ok(Arc::new(entries));
+    }
+    cache_manager
+        .list_cache
+        .get_or_load(ListResource::Topic, || {
+            load_topic_list_cache(rocksdb_engine_handler)
+        })
+}
+
+fn load_topic_list_cache(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+) -> Result<Vec<ListCacheEntry>, MetaServiceError> {
+    let storage = MqttTopicStorage::new(rocksdb_engine_handler.clone());
+    storage.list()?.into_iter().map(topic_list_entry).collect()
+}
+
+fn topic_list_entry(topic: Topic) -> Result<ListCacheEntry, MetaServiceError> {
+    Ok(ListCacheEntry {
+        data: topic.encode()?,
+        updated_at: topic.create_time,
+        tenant: topic.tenant,
+        name: topic.topic_name,
+    })
+}
+
 pub async fn create_topic_by_req(
     raft_manager: &Arc<MultiRaftManager>,
     call_manager: &Arc<NodeCallManager>,
@@ -122,6 +152,32 @@ pub async fn delete_topic_by_req(
     Ok(DeleteTopicReply {})
 }
 
+// Topic Statistics
+pub fn report_topic_stats_by_req(
+    cache_manager: &Arc<MetaCacheManager>,
+    req: &ReportTopicStatsRequest,
+) -> Result<ReportTopicStatsReply, MetaServiceError> {
+    cache_manager
+        .topic_stats
+        .report(req.broker_id, now_millis() as u64, req.stats.clone());
+    Ok(ReportTopicStatsReply {})
+}
+
+pub fn describe_topic_by_req(
+    cache_manager: &Arc<MetaCacheManager>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    req: &DescribeTopicRequest,
+) -> Result<DescribeTopicReply, MetaServiceError> {
+    let topic_storage = MqttTopicStorage::new(rocksdb_engine_handler.clone());
+    if topic_storage.get(&req.tenant, &req.topic_name)?.is_none() {
+        return Err(MetaServiceError::TopicDoesNotExist(req.topic_name.clone()));
+    }
+
+    Ok(cache_manager
+        .topic_stats
+        .describe(&req.tenant, &req.topic_name, now_millis() as u64))
+}
+
 // Topic Rewrite Rule Operations
 pub async fn create_topic_rewrite_rule_by_req(
     raft_manager: &Arc<MultiRaftManager>,
@@ -192,6 +248,14 @@ pub fn list_topic_rewrite_rule_by_req(
     rocksdb_engine_handler: &Arc<RocksDBEngine>,
     req: &ListTopicRewriteRuleRequest,
 ) -> Result<ListTopicRewriteRuleReply, MetaServiceError> {
+    let query = ListQuery {
+        tenant: &req.tenant,
+        name_prefix: &req.name_prefix,
+        offset: req.offset,
+        limit: req.limit,
+        ..Default::default()
+    }
+    .with_name_regex(&req.name_regex)?;
     let storage = MqttTopicStorage::new(rocksdb_engine_handler.clone());
     let data = if req.tenant.is_empty() {
         storage.list_all_topic_rewrite_rules()?
@@ -199,11 +263,9 @@ pub fn list_topic_rewrite_rule_by_req(
         storage.list_topic_rewrite_rules_by_tenant(&req.tenant)?
     };
 
-    let rules = data
-        .into_iter()
-        .map(|raw| raw.encode())
-        .collect::<Result<Vec<_>, _>>()?;
+    let page = query.page(data).encode(|raw| raw.encode())?;
     Ok(ListTopicRewriteRuleReply {
-        topic_rewrite_rules: rules,
+        topic_rewrite_rules: page.items,
+        total: page.total,
     })
 }
@@ -13,12 +13,15 @@
 // limitations under the License.
 
 use crate::{
+    core::cache::MetaCacheManager,
+    core::cache_list::{ListCacheEntry, ListResource},
     core::error::MetaServiceError,
     core::notify::{send_notify_by_add_user, send_notify_by_delete_user},
     raft::{
         manager::MultiRaftManager,
         route::data::{StorageData, StorageDataType},
     },
+    server::services::mqtt::list::ListQuery,
     storage::mqtt::user::SecurityUserStorage,
 };
 use common_base::utils::serialize::encode_to_bytes;
@@ -26,37 +29,76 @@
 use node_call::NodeCallManager;
 use protocol::meta::meta_service_mqtt::{
     CreateUserReply, CreateUserRequest, DeleteUserReply, DeleteUserRequest, ListUserReply,
-    ListUserRequest,
+    ListUserRequest, UpdateUserReply, UpdateUserRequest,
 };
 use rocksdb_engine::rocksdb::RocksDBEngine;
 use std::sync::Arc;
 
 pub fn list_user_by_req(
+    cache_manager: &Arc<MetaCacheManager>,
     rocksdb_engine_handler: &Arc<RocksDBEngine>,
     req: &ListUserRequest,
 ) -> Result<ListUserReply, MetaServiceError> {
-    let storage = SecurityUserStorage::new(rocksdb_engine_handler.clone());
-    let mut users = Vec::new();
-
-    if !req.user_name.is_empty() {
-        if let Some(data) = storage.get(&req.tenant, &req.user_name)? {
-            users.push(data.encode()?);
-        }
-    } else if !req.tenant.is_empty() {
-        let user_list = storage.list_by_tenant(&req.tenant)?;
-        users = user_list
-            .into_iter()
-            .map(|user| user.encode())
-            .collect::<Result<Vec<_>, _>>()?;
-    } else {
-        let user_list = storage.list_all()?;
-        users = user_list
+    let query = ListQuery {
+        tenant: &req.tenant,
+        name: &req.user_name,
+        name_prefix: &req.name_prefix,
+        offset: req.offset,
+        limit: req.limit,
+        updated_since: req.updated_since,
+        ..Default::default()
+    }
+    .with_name_regex(&req.name_regex)?;
+    let entries = read_user_entries(cache_manager, rocksdb_engine_handler, &query)?;
+    let page = query.page_cached(entries.iter());
+
+    Ok(ListUserReply {
+        users: page.items,
+        total: page.total,
+    })
+}
+
+// A lookup by user name reads the single record instead of the list snapshot,
+// which would be rebuilt after every user write.
+fn read_user_entries(
+    cache_manager: &Arc<MetaCacheManager>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    query: &ListQuery,
+) -> Result<Arc<Vec<ListCacheEntry>>, MetaServiceError> {
+    if !query.tenant.is_empty() && !query.name.is_empty() {
+        let storage = SecurityUserStorage::new(rocksdb_engine_handler.clone());
+        let entries = storage
+            .get(query.tenant, query.name)?
             .into_iter()
-            .map(|user| user.encode())
+            .map(user_list_entry)
             .collect::<Result<Vec<_>, _>>()?;
+        return Ok(Arc::new(entries));
     }
+    cache_manager
+        .list_cache
+        .get_or_load(ListResource::User, || {
+            load_user_list_cache(rocksdb_engine_handler)
+        })
+}
 
-    Ok(ListUserReply { users })
+fn load_user_list_cache(
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+) -> Result<Vec<ListCacheEntry>, MetaServiceError> {
+    let storage = SecurityUserStorage::new(rocksdb_engine_handler.clone());
+    storage
+        .list_all()?
+        .into_iter()
+        .map(user_list_entry)
+        .collect()
+}
+
+fn user_list_entry(user: SecurityUser) -> Result<ListCacheEntry, MetaServiceError> {
+    Ok(ListCacheEntry {
+        data: user.encode()?,
+        updated_at: user.create_time,
+        tenant: user.tenant,
+        name: user.username,
+    })
 }
 
 pub async fn create_user_by_req(
@@ -80,6 +122,32 @@ pub async fn create_user_by_req(
     Ok(CreateUserReply {})
 }
 
+pub async fn update_user_by_req(
+    raft_manager: &Arc<MultiRaftManager>,
+    call_manager: &Arc<NodeCallManager>,
+    rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    req: &UpdateUserRequest,
+) -> Result<UpdateUserReply, MetaServiceError> {
+    let storage = SecurityUserStorage::new(rocksdb_engine_handler.clone());
+
+    if storage.get(&req.tenant, &req.user_name)?.is_none() {
+        return Err(MetaServiceError::UserDoesNotExist(req.user_name.clone()));
+    }
+
+    let set_req = CreateUserRequest {
+        tenant: req.tenant.clone(),
+        user_name: req.user_name.clone(),
+        content: req.content.clone(),
+    };
+    let data = StorageData::new(StorageDataType::MqttSetUser, encode_to_bytes(&set_req));
+    raft_manager.write_metadata(data).await?;
+
+    let user = SecurityUser::decode(&req.content)?;
+    send_notify_by_add_user(call_manager, user).await?;
+
+    Ok(UpdateUserReply {})
+}
+
 pub async fn delete_user_by_req(
     raft_manager: &Arc<MultiRaftManager>,
     call_manager: &Arc<NodeCallManager>,
@@ -15,12 +15,56 @@
 use std::sync::Arc;
 
 use common_base::error::common::CommonError;
-
+use common_base::tools::now_millis;
+use common_base::utils::serialize;
+use rocksdb_engine::keys::meta::{key_kv_expire, key_kv_expire_prefix, key_lock_prefix};
 use rocksdb_engine::rocksdb::RocksDBEngine;
+use rocksdb_engine::storage::base::get_cf_handle;
+use rocksdb_engine::storage::family::DB_COLUMN_FAMILY_META_METADATA;
 use rocksdb_engine::storage::meta_metadata::{
-    engine_delete_by_meta_metadata, engine_exists_by_meta_metadata, engine_get_by_meta_metadata,
+    engine_delete_by_meta_metadata, engine_get_by_meta_metadata,
     engine_prefix_list_by_meta_metadata, engine_save_by_meta_metadata,
 };
+use rocksdb_engine::warp::StorageDataWrap;
+use serde::{Deserialize, Serialize};
+
+/// A KV value with its version and expiry. The version starts at 1 and grows
+/// by one on every write of the key; an `expire_at_ms` of 0 never expires.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct KvEntry {
+    pub value: String,
+    pub version: u64,
+    pub expire_at_ms: u64,
+}
+
+impl KvEntry {
+    pub fn is_expired(&self, now_ms: u64) -> bool {
+        self.expire_at_ms > 0 && self.expire_at_ms <= now_ms
+    }
+}
+
+/// Expiry index record of a key written with a TTL, scanned by the KV GC.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct KvExpireEntry {
+    pub key: String,
+    pub version: u64,
+    pub expire_at_ms: u64,
+}
+
+/// Conditions of a compare-and-set, a `None` condition is not checked.
+/// An expected version of 0 requires the key to be absent or expired.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct KvCondition {
+    pub expected_version: Option<u64>,
+    pub expected_value: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct KvSetResult {
+    pub succeeded: bool,
+    pub version: u64,
+    pub current_value: String,
+}
 
 #[derive(Debug, Clone)]
 pub struct KvStorage {
@@ -34,44 +78,185 @@ pub fn new(rocksdb_engine_handler: Arc<RocksDBEngine>) -> Self {
         }
     }
 
-    pub fn set(&self, key: String, value: String) -> Result<(), CommonError> {
-        engine_save_by_meta_metadata(&self.rocksdb_engine_handler, &key, value)
+    /// Write `value` unconditionally and return the new version of the key.
+    pub fn set(&self, key: &str, value: String, expire_at_ms: u64) -> Result<u64, CommonError> {
+        let version = self.next_version(key)?;
+        self.save_entry(
+            key,
+            KvEntry {
+                value,
+                version,
+                expire_at_ms,
+            },
+        )?;
+        Ok(version)
     }
 
-    pub fn delete(&self, key: String) -> Result<(), CommonError> {
-        engine_delete_by_meta_metadata(&self.rocksdb_engine_handler, &key)
+    /// Write `value` only if the live entry of the key matches `condition`.
+    /// `now_ms` is carried in the raft entry so that every replica judges
+    /// expiry the same way.
+    pub fn compare_and_set(
+        &self,
+        key: &str,
+        value: String,
+        expire_at_ms: u64,
+        condition: &KvCondition,
+        now_ms: u64,
+    ) -> Result<KvSetResult, CommonError> {
+        let current = self.get_entry(key)?;
+        let live = current.as_ref().filter(|entry| !entry.is_expired(now_ms));
+
+        let version_matches = match condition.expected_version {
+            None => true,
+            Some(0) => live.is_none(),
+            Some(version) => live.is_some_and(|entry| entry.version == version),
+        };
+        let value_matches = match &condition.expected_value {
+            None => true,
+            Some(expected) => live.is_some_and(|entry| entry.value == *expected),
+        };
+
+        if !version_matches || !value_matches {
+            return Ok(KvSetResult {
+                succeeded: false,
+                version: live.map(|entry| entry.version).unwrap_or(0),
+                current_value: live.map(|entry| entry.value.clone()).unwrap_or_default(),
+            });
+        }
+
+        // Versions keep growing across expiry, so a delayed GC delete of the
+        // expired entry can never remove the new one.
+        let version = current.map(|entry| entry.version + 1).unwrap_or(1);
+        self.save_entry(
+            key,
+            KvEntry {
+                value,
+                version,
+                expire_at_ms,
+            },
+        )?;
+        Ok(KvSetResult {
+            succeeded: true,
+            version,
+            current_value: String::new(),
+        })
     }
 
-    pub fn get(&self, key: String) -> Result<Option<String>, CommonError> {
-        if let Some(data) =
-            engine_get_by_meta_metadata::<String>(&self.rocksdb_engine_handler, &key)?
-        {
-            return Ok(Some(data.data));
+    /// Delete the key, or only while it is at `expected_version` when given.
+    /// Returns whether an entry was removed.
+    pub fn delete(&self, key: &str, expected_version: Option<u64>) -> Result<bool, CommonError> {
+        let Some(entry) = self.get_entry(key)? else {
+            return Ok(false);
+        };
+        if expected_version.is_some_and(|version| version != entry.version) {
+            return Ok(false);
+        }
+        engine_delete_by_meta_metadata(&self.rocksdb_engine_handler, key)?;
+        if entry.expire_at_ms > 0 {
+            engine_delete_by_meta_metadata(&self.rocksdb_engine_handler, &key_kv_expire(key))?;
         }
-        Ok(None)
+        Ok(true)
     }
 
-    pub fn exists(&self, key: String) -> Result<bool, CommonError> {
-        engine_exists_by_meta_metadata(&self.rocksdb_engine_handler, &key)
+    /// The live entry of the key, expired entries are treated as absent.
+    pub fn get(&self, key: &str) -> Result<Option<KvEntry>, CommonError> {
+        let now_ms = now_millis() as u64;
+        Ok(self
+            .get_entry(key)?
+            .filter(|entry| !entry.is_expired(now_ms)))
     }
 
-    pub fn get_prefix(&self, prefix: String) -> Result<Vec<String>, CommonError> {
-        match engine_prefix_list_by_meta_metadata::<String>(&self.rocksdb_engine_handler, &prefix) {
-            Ok(data) => {
-                let mut result = Vec::new();
-                for item in data {
-                    result.push(item.data);
-                }
-                Ok(result)
+    pub fn exists(&self, key: &str) -> Result<bool, CommonError> {
+        Ok(self.get(key)?.is_some())
+    }
+
+    pub fn get_prefix(&self, prefix: &str) -> Result<Vec<String>, CommonError> {
+        let now_ms = now_millis() as u64;
+        let cf = get_cf_handle(&self.rocksdb_engine_handler, DB_COLUMN_FAMILY_META_METADATA)?;
+        let mut result = Vec::new();
+        for (_key, data) in self.rocksdb_engine_handler.read_prefix(cf, prefix)? {
+            let Ok(entry) = decode_entry(&data) else {
+                continue;
+            };
+            if !entry.is_expired(now_ms) {
+                result.push(entry.value);
             }
-            Err(e) => Err(e),
         }
+        Ok(result)
+    }
+
+    /// Keys written with a TTL that has passed by `now_ms`.
+    pub fn list_expired(&self, now_ms: u64) -> Result<Vec<KvExpireEntry>, CommonError> {
+        Ok(engine_prefix_list_by_meta_metadata::<KvExpireEntry>(
+            &self.rocksdb_engine_handler,
+            &key_kv_expire_prefix(),
+        )?
+        .into_iter()
+        .map(|wrap| wrap.data)
+        .filter(|index| index.expire_at_ms <= now_ms)
+        .collect())
     }
+
+    /// The stored entry of the key, including an expired one.
+    fn get_entry(&self, key: &str) -> Result<Option<KvEntry>, CommonError> {
+        match engine_get_by_meta_metadata::<KvEntry>(&self.rocksdb_engine_handler, key) {
+            Ok(data) => Ok(data.map(|wrap| wrap.data)),
+            // Values written before keys were versioned are plain strings.
+            Err(_) => Ok(
+                engine_get_by_meta_metadata::<String>(&self.rocksdb_engine_handler, key)?
+                    .map(|wrap| legacy_entry(wrap.data)),
+            ),
+        }
+    }
+
+    fn next_version(&self, key: &str) -> Result<u64, CommonError> {
+        Ok(self
+            .get_entry(key)?
+            .map(|entry| entry.version + 1)
+            .unwrap_or(1))
+    }
+
+    fn save_entry(&self, key: &str, entry: KvEntry) -> Result<(), CommonError> {
+        // Lock records are never collected: their versions are the fencing
+        // tokens and must keep growing after a release or an expired lease.
+        let index_key = key_kv_expire(key);
+        if entry.expire_at_ms > 0 && !key.starts_with(&key_lock_prefix()) {
+            engine_save_by_meta_metadata(
+                &self.rocksdb_engine_handler,
+                &index_key,
+                KvExpireEntry {
+                    key: key.to_string(),
+                    version: entry.version,
+                    expire_at_ms: entry.expire_at_ms,
+                },
+            )?;
+        } else {
+            engine_delete_by_meta_metadata(&self.rocksdb_engine_handler, &index_key)?;
+        }
+        engine_save_by_meta_metadata(&self.rocksdb_engine_handler, key, entry)
+    }
+}
+
+fn legacy_entry(value: String) -> KvEntry {
+    KvEntry {
+        value,
+        version: 1,
+        expire_at_ms: 0,
+    }
+}
+
+fn decode_entry(data: &[u8]) -> Result<KvEntry, CommonError> {
+    if let Ok(wrap) = serialize::deserialize::<StorageDataWrap<KvEntry>>(data) {
+        return Ok(wrap.data);
+    }
+    let wrap = serialize::deserialize::<StorageDataWrap<String>>(data)?;
+    Ok(legacy_entry(wrap.data))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rocksdb_engine::keys::meta::key_lock;
     use rocksdb_engine::storage::family::column_family_list;
     use tempfile::tempdir;
 
@@ -82,66 +267,91 @@ fn setup_kv_storage() -> KvStorage {
         KvStorage::new(Arc::new(engine))
     }
 
+    fn value_of(kv: &KvStorage, key: &str) -> Option<String> {
+        kv.get(key).unwrap().map(|entry| entry.value)
+    }
+
     #[test]
     fn test_set_and_get() {
         let kv = setup_kv_storage();
-        kv.set("key1".to_string(), "value1".to_string()).unwrap();
-        assert_eq!(
-            kv.get("key1".to_string()).unwrap(),
-            Some("value1".to_string())
-        );
+        assert_eq!(kv.set("key1", "value1".to_string(), 0).unwrap(), 1);
+        assert_eq!(value_of(&kv, "key1"), Some("value1".to_string()));
     }
 
     #[test]
     fn test_set_overwrite() {
         let kv = setup_kv_storage();
-        kv.set("key1".to_string(), "value1".to_string()).unwrap();
-        kv.set("key1".to_string(), "value2".to_string()).unwrap();
-        assert_eq!(
-            kv.get("key1".to_string()).unwrap(),
-            Some("value2".to_string())
-        );
+        kv.set("key1", "value1".to_string(), 0).unwrap();
+        assert_eq!(kv.set("key1", "value2".to_string(), 0).unwrap(), 2);
+        let entry = kv.get("key1").unwrap().unwrap();
+        assert_eq!(entry.value, "value2");
+        assert_eq!(entry.version, 2);
     }
 
     #[test]
     fn test_get_non_existent() {
         let kv = setup_kv_storage();
-        assert_eq!(kv.get("nonexistent".to_string()).unwrap(), None);
+        assert_eq!(kv.get("nonexistent").unwrap(), None);
+    }
+
+    #[test]
+    fn test_get_legacy_value() {
+        let kv = setup_kv_storage();
+        engine_save_by_meta_metadata(&kv.rocksdb_engine_handler, "legacy", "old".to_string())
+            .unwrap();
+        assert_eq!(
+            kv.get("legacy").unwrap(),
+            Some(KvEntry {
+                value: "old".to_string(),
+                version: 1,
+                expire_at_ms: 0,
+            })
+        );
+        assert_eq!(kv.get_prefix("leg").unwrap(), vec!["old".to_string()]);
+        assert_eq!(kv.set("legacy", "new".to_string(), 0).unwrap(), 2);
     }
 
     #[test]
     fn test_delete_existing() {
         let kv = setup_kv_storage();
-        kv.set("key1".to_string(), "value1".to_string()).unwrap();
-        kv.delete("key1".to_string()).unwrap();
-        assert!(!kv.exists("key1".to_string()).unwrap());
+        kv.set("key1", "value1".to_string(), 0).unwrap();
+        assert!(kv.delete("key1", None).unwrap());
+        assert!(!kv.exists("key1").unwrap());
     }
 
     #[test]
     fn test_delete_non_existent() {
         let kv = setup_kv_storage();
-        kv.delete("nonexistent".to_string()).unwrap();
+        assert!(!kv.delete("nonexistent", None).unwrap());
+    }
+
+    #[test]
+    fn test_delete_expected_version() {
+        let kv = setup_kv_storage();
+        kv.set("key1", "value1".to_string(), 0).unwrap();
+        kv.set("key1", "value2".to_string(), 0).unwrap();
+        assert!(!kv.delete("key1", Some(1)).unwrap());
+        assert!(kv.exists("key1").unwrap());
+        assert!(kv.delete("key1", Some(2)).unwrap());
+        assert!(!kv.exists("key1").unwrap());
     }
 
     #[test]
     fn test_exists() {
         let kv = setup_kv_storage();
-        assert!(!kv.exists("key1".to_string()).unwrap());
-        kv.set("key1".to_string(), "value1".to_string()).unwrap();
-        assert!(kv.exists("key1".to_string()).unwrap());
+        assert!(!kv.exists("key1").unwrap());
+        kv.set("key1", "value1".to_string(), 0).unwrap();
+        assert!(kv.exists("key1").unwrap());
     }
 
     #[test]
     fn test_get_prefix() {
         let kv = setup_kv_storage();
-        kv.set("prefix/key1".to_string(), "value1".to_string())
-            .unwrap();
-        kv.set("prefix/key2".to_string(), "value2".to_string())
-            .unwrap();
-        kv.set("other/key3".to_string(), "value3".to_string())
-            .unwrap();
+        kv.set("prefix/key1", "value1".to_string(), 0).unwrap();
+        kv.set("prefix/key2", "value2".to_string(), 0).unwrap();
+        kv.set("other/key3", "value3".to_string(), 0).unwrap();
 
-        let mut result = kv.get_prefix("prefix/".to_string()).unwrap();
+        let mut result = kv.get_prefix("prefix/").unwrap();
         result.sort();
         assert_eq!(result, vec!["value1".to_string(), "value2".to_string()]);
     }
@@ -149,7 +359,102 @@ fn test_get_prefix() {
     #[test]
     fn test_get_prefix_non_existent() {
         let kv = setup_kv_storage();
-        let result = kv.get_prefix("nonexistent/".to_string()).unwrap();
+        let result = kv.get_prefix("nonexistent/").unwrap();
         assert!(result.is_empty());
     }
+
+    #[test]
+    fn test_expired_key_is_absent() {
+        let kv = setup_kv_storage();
+        kv.set("prefix/live", "live".to_string(), u64::MAX).unwrap();
+        kv.set("prefix/gone", "gone".to_string(), 1).unwrap();
+
+        assert!(kv.exists("prefix/live").unwrap());
+        assert!(!kv.exists("prefix/gone").unwrap());
+        assert_eq!(kv.get_prefix("prefix/").unwrap(), vec!["live".to_string()]);
+
+        let expired = kv.list_expired(1000).unwrap();
+        assert_eq!(
+            expired,
+            vec![KvExpireEntry {
+                key: "prefix/gone".to_string(),
+                version: 1,
+                expire_at_ms: 1,
+            }]
+        );
+
+        // Rewriting without a TTL drops the key from the expiry index.
+        kv.set("prefix/gone", "back".to_string(), 0).unwrap();
+        assert!(kv.list_expired(1000).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_lock_key_is_not_collected() {
+        let kv = setup_kv_storage();
+        kv.set(&key_lock("l1"), "owner".to_string(), 1).unwrap();
+        assert!(!kv.exists(&key_lock("l1")).unwrap());
+        assert!(kv.list_expired(1000).unwrap().is_empty());
+        assert_eq!(kv.set(&key_lock("l1"), "owner".to_string(), 0).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_compare_and_set_version() {
+        let kv = setup_kv_storage();
+        let create = KvCondition {
+            expected_version: Some(0),
+            expected_value: None,
+        };
+        let result = kv
+            .compare_and_set("lock", "a".to_string(), 0, &create, 10)
+            .unwrap();
+        assert!(result.succeeded);
+        assert_eq!(result.version, 1);
+
+        let result = kv
+            .compare_and_set("lock", "b".to_string(), 0, &create, 10)
+            .unwrap();
+        assert_eq!(
+            result,
+            KvSetResult {
+                succeeded: false,
+                version: 1,
+                current_value: "a".to_string(),
+            }
+        );
+
+        let update = KvCondition {
+            expected_version: Some(1),
+            expected_value: Some("a".to_string()),
+        };
+        let result = kv
+            .compare_and_set("lock", "b".to_string(), 0, &update, 10)
+            .unwrap();
+        assert!(result.succeeded);
+        assert_eq!(result.version, 2);
+        assert_eq!(value_of(&kv, "lock"), Some("b".to_string()));
+    }
+
+    #[test]
+    fn test_compare_and_set_expired_key() {
+        let kv = setup_kv_storage();
+        kv.set("lease", "owner-1".to_string(), 100).unwrap();
+
+        let acquire = KvCondition {
+            expected_version: Some(0),
+            expected_value: None,
+        };
+        let result = kv
+            .compare_and_set("lease", "owner-2".to_string(), 200, &acquire, 50)
+            .unwrap();
+        assert!(!result.succeeded);
+
+        let result = kv
+            .compare_and_set("lease", "owner-2".to_string(), 200, &acquire, 100)
+            .unwrap();
+        assert!(result.succeeded);
+        assert_eq!(result.version, 2);
+
+        // The GC delete queued for the expired version leaves the new one.
+        assert!(!kv.delete("lease", Some(1)).unwrap());
+    }
 }
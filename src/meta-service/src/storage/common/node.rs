@@ -14,14 +14,26 @@
 
 use common_base::error::common::CommonError;
 use metadata_struct::meta::node::BrokerNode;
-use rocksdb_engine::keys::meta::{key_node, key_node_epoch, key_node_prefix};
+use rocksdb_engine::keys::meta::{
+    key_node, key_node_cordon, key_node_cordon_prefix, key_node_epoch, key_node_prefix,
+};
 use rocksdb_engine::rocksdb::RocksDBEngine;
 use rocksdb_engine::storage::meta_metadata::{
     engine_delete_by_meta_metadata, engine_get_by_meta_metadata,
     engine_prefix_list_by_meta_metadata, engine_save_by_meta_metadata,
 };
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
+/// A node excluded from connector placement and leadership transfer, e.g.
+/// while it is upgraded.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeCordon {
+    pub node_id: u64,
+    pub reason: String,
+    pub create_time: u64,
+}
+
 pub struct NodeStorage {
     rocksdb_engine_handler: Arc<RocksDBEngine>,
 }
@@ -73,6 +85,25 @@ pub fn get_broker_epoch(&self, node_id: u64) -> Result<u64, CommonError> {
         )
     }
 
+    pub fn save_cordon(&self, cordon: &NodeCordon) -> Result<(), CommonError> {
+        let key = key_node_cordon(cordon.node_id);
+        engine_save_by_meta_metadata(&self.rocksdb_engine_handler, &key, cordon.clone())
+    }
+
+    pub fn delete_cordon(&self, node_id: u64) -> Result<(), CommonError> {
+        let key = key_node_cordon(node_id);
+        engine_delete_by_meta_metadata(&self.rocksdb_engine_handler, &key)
+    }
+
+    pub fn list_cordon(&self) -> Result<Vec<NodeCordon>, CommonError> {
+        let prefix_key = key_node_cordon_prefix();
+        let data = engine_prefix_list_by_meta_metadata::<NodeCordon>(
+            &self.rocksdb_engine_handler,
+            &prefix_key,
+        )?;
+        Ok(data.into_iter().map(|raw| raw.data).collect())
+    }
+
     pub fn list(&self) -> Result<Vec<BrokerNode>, CommonError> {
         let prefix_key = key_node_prefix();
 
@@ -146,6 +177,25 @@ fn test_broker_epoch_strictly_increases() {
         assert_eq!(kv.get_broker_epoch(1).unwrap(), 3);
     }
 
+    #[test]
+    fn test_cordon_survives_node_deletion() {
+        let kv = setup_kv_storage();
+        let node = get_test_node();
+        kv.save(&node).unwrap();
+        let cordon = NodeCordon {
+            node_id: node.node_id,
+            reason: "upgrade".to_string(),
+            create_time: 1,
+        };
+        kv.save_cordon(&cordon).unwrap();
+        kv.delete(node.node_id).unwrap();
+        assert_eq!(kv.list().unwrap().len(), 0);
+        assert_eq!(kv.list_cordon().unwrap(), vec![cordon]);
+
+        kv.delete_cordon(node.node_id).unwrap();
+        assert!(kv.list_cordon().unwrap().is_empty());
+    }
+
     #[test]
     fn test_broker_epoch_survives_node_deletion() {
         let kv = setup_kv_storage();
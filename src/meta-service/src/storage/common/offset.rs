@@ -88,7 +88,13 @@ pub fn group_offset(&self, tenant: &str, group: &str) -> Result<Vec<OffsetData>,
             &prefix_key,
         )?;
 
-        Ok(data.into_iter().map(|row| row.data).collect())
+        // The prefix scan also matches groups whose name extends this one
+        // past a '/', e.g. "g" and "g/x", so keep exact matches only.
+        Ok(data
+            .into_iter()
+            .map(|row| row.data)
+            .filter(|offset| offset.tenant == tenant && offset.group == group)
+            .collect())
     }
 }
 
@@ -149,6 +155,24 @@ fn test_offset_delete() {
         assert_eq!(remaining[0].offset, 200);
     }
 
+    #[test]
+    fn test_group_offset_exact_match() {
+        let storage = OffsetStorage::new(test_rocksdb_instance());
+        let offsets = vec![
+            create_offset_data("tenant1", "group", "shard1", 100),
+            create_offset_data("tenant1", "group/sub", "shard1", 200),
+        ];
+        storage.save(&offsets).unwrap();
+
+        let list = storage.group_offset("tenant1", "group").unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].offset, 100);
+
+        let list = storage.group_offset("tenant1", "group/sub").unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].offset, 200);
+    }
+
     #[test]
     fn test_group_offset_empty() {
         let storage = OffsetStorage::new(test_rocksdb_instance());
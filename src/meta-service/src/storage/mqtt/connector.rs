@@ -27,9 +27,13 @@
 use std::sync::Arc;
 
 use common_base::error::common::CommonError;
+use metadata_struct::connector::checkpoint::ConnectorCheckpoint;
 use metadata_struct::connector::MQTTConnector;
 
-use rocksdb_engine::keys::meta::{storage_key_mqtt_connector, storage_key_mqtt_connector_prefix};
+use rocksdb_engine::keys::meta::{
+    storage_key_mqtt_connector, storage_key_mqtt_connector_checkpoint,
+    storage_key_mqtt_connector_prefix,
+};
 use rocksdb_engine::rocksdb::RocksDBEngine;
 use rocksdb_engine::storage::meta_metadata::{
     engine_delete_by_meta_metadata, engine_get_by_meta_metadata,
@@ -72,6 +76,27 @@ pub fn delete(&self, connector_name: &str) -> Result<(), CommonError> {
         let key = storage_key_mqtt_connector(connector_name);
         engine_delete_by_meta_metadata(&self.rocksdb_engine_handler, &key)
     }
+
+    pub fn save_checkpoint(&self, checkpoint: &ConnectorCheckpoint) -> Result<(), CommonError> {
+        let key = storage_key_mqtt_connector_checkpoint(&checkpoint.connector_name);
+        engine_save_by_meta_metadata(&self.rocksdb_engine_handler, &key, checkpoint)
+    }
+
+    pub fn get_checkpoint(
+        &self,
+        connector_name: &str,
+    ) -> Result<Option<ConnectorCheckpoint>, CommonError> {
+        let key = storage_key_mqtt_connector_checkpoint(connector_name);
+        Ok(
+            engine_get_by_meta_metadata::<ConnectorCheckpoint>(&self.rocksdb_engine_handler, &key)?
+                .map(|data| data.data),
+        )
+    }
+
+    pub fn delete_checkpoint(&self, connector_name: &str) -> Result<(), CommonError> {
+        let key = storage_key_mqtt_connector_checkpoint(connector_name);
+        engine_delete_by_meta_metadata(&self.rocksdb_engine_handler, &key)
+    }
 }
 
 #[cfg(test)]
@@ -107,6 +132,30 @@ fn test_connector_crud() {
         assert_eq!(storage.list().unwrap().len(), 1);
     }
 
+    #[test]
+    fn test_connector_checkpoint() {
+        let storage = setup_storage();
+        assert!(storage.get_checkpoint("connector_a").unwrap().is_none());
+
+        let checkpoint = ConnectorCheckpoint {
+            connector_name: "connector_a".to_string(),
+            sequence: 3,
+            source_offsets: [("s0".to_string(), 10)].into(),
+            transaction_id: "tx-3".to_string(),
+            ..Default::default()
+        };
+        storage.save_checkpoint(&checkpoint).unwrap();
+        assert_eq!(
+            storage.get_checkpoint("connector_a").unwrap(),
+            Some(checkpoint)
+        );
+        // Checkpoints are not listed as connectors.
+        assert!(storage.list().unwrap().is_empty());
+
+        storage.delete_checkpoint("connector_a").unwrap();
+        assert!(storage.get_checkpoint("connector_a").unwrap().is_none());
+    }
+
     #[test]
     fn test_get_nonexistent() {
         let storage = setup_storage();
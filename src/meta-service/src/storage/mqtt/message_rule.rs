@@ -0,0 +1,125 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::core::error::MetaServiceError;
+use metadata_struct::mqtt::message_rule::MqttMessageRule;
+use rocksdb_engine::keys::meta::{
+    storage_key_mqtt_message_rule, storage_key_mqtt_message_rule_prefix,
+    storage_key_mqtt_message_rule_tenant_prefix,
+};
+use rocksdb_engine::rocksdb::RocksDBEngine;
+use rocksdb_engine::storage::meta_metadata::{
+    engine_delete_by_meta_metadata, engine_get_by_meta_metadata,
+    engine_prefix_list_by_meta_metadata, engine_save_by_meta_metadata,
+};
+use std::sync::Arc;
+
+pub struct MqttMessageRuleStorage {
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+}
+
+impl MqttMessageRuleStorage {
+    pub fn new(rocksdb_engine_handler: Arc<RocksDBEngine>) -> Self {
+        MqttMessageRuleStorage {
+            rocksdb_engine_handler,
+        }
+    }
+
+    pub fn save(&self, rule: &MqttMessageRule) -> Result<(), MetaServiceError> {
+        let key = storage_key_mqtt_message_rule(&rule.tenant, &rule.name);
+        engine_save_by_meta_metadata(&self.rocksdb_engine_handler, &key, rule.clone())?;
+        Ok(())
+    }
+
+    pub fn get(
+        &self,
+        tenant: &str,
+        name: &str,
+    ) -> Result<Option<MqttMessageRule>, MetaServiceError> {
+        let key = storage_key_mqtt_message_rule(tenant, name);
+        Ok(
+            engine_get_by_meta_metadata::<MqttMessageRule>(&self.rocksdb_engine_handler, &key)?
+                .map(|raw| raw.data),
+        )
+    }
+
+    pub fn delete(&self, tenant: &str, name: &str) -> Result<(), MetaServiceError> {
+        let key = storage_key_mqtt_message_rule(tenant, name);
+        engine_delete_by_meta_metadata(&self.rocksdb_engine_handler, &key)?;
+        Ok(())
+    }
+
+    pub fn list_all(&self) -> Result<Vec<MqttMessageRule>, MetaServiceError> {
+        let prefix_key = storage_key_mqtt_message_rule_prefix();
+        let data = engine_prefix_list_by_meta_metadata::<MqttMessageRule>(
+            &self.rocksdb_engine_handler,
+            &prefix_key,
+        )?;
+        Ok(data.into_iter().map(|raw| raw.data).collect())
+    }
+
+    pub fn list_by_tenant(&self, tenant: &str) -> Result<Vec<MqttMessageRule>, MetaServiceError> {
+        let prefix_key = storage_key_mqtt_message_rule_tenant_prefix(tenant);
+        let data = engine_prefix_list_by_meta_metadata::<MqttMessageRule>(
+            &self.rocksdb_engine_handler,
+            &prefix_key,
+        )?;
+        Ok(data.into_iter().map(|raw| raw.data).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common_config::broker::{default_broker_config, init_broker_conf_by_config};
+    use metadata_struct::mqtt::message_rule::MqttMessageRuleAction;
+    use rocksdb_engine::test::test_rocksdb_instance;
+
+    fn setup_storage() -> MqttMessageRuleStorage {
+        let config = default_broker_config();
+        init_broker_conf_by_config(config.clone());
+        MqttMessageRuleStorage::new(test_rocksdb_instance())
+    }
+
+    fn create_rule(tenant: &str, name: &str) -> MqttMessageRule {
+        MqttMessageRule {
+            tenant: tenant.to_string(),
+            name: name.to_string(),
+            desc: String::new(),
+            sql: "SELECT * FROM \"sensors/#\"".to_string(),
+            actions: vec![MqttMessageRuleAction::Drop],
+            enable: true,
+            create_time: 0,
+        }
+    }
+
+    #[test]
+    fn test_message_rule_crud() {
+        let storage = setup_storage();
+
+        storage.save(&create_rule("tenant-1", "rule-1")).unwrap();
+        storage.save(&create_rule("tenant-1", "rule-2")).unwrap();
+        storage.save(&create_rule("tenant-2", "rule-1")).unwrap();
+
+        assert_eq!(storage.list_all().unwrap().len(), 3);
+        assert_eq!(storage.list_by_tenant("tenant-1").unwrap().len(), 2);
+
+        let found = storage.get("tenant-2", "rule-1").unwrap().unwrap();
+        assert_eq!(found.actions, vec![MqttMessageRuleAction::Drop]);
+
+        storage.delete("tenant-1", "rule-1").unwrap();
+        assert!(storage.get("tenant-1", "rule-1").unwrap().is_none());
+        assert_eq!(storage.list_by_tenant("tenant-1").unwrap().len(), 1);
+    }
+}
@@ -27,7 +27,9 @@
 pub mod acl;
 pub mod blacklist;
 pub mod connector;
+pub mod message_rule;
 pub mod session;
 pub mod subscribe;
+pub mod subscribe_route;
 pub mod topic;
 pub mod user;
@@ -25,15 +25,16 @@
 // limitations under the License.
 
 use common_base::error::common::CommonError;
+use metadata_struct::mqtt::lastwill::MqttLastWillClaim;
 use metadata_struct::mqtt::session::MqttSession;
 use rocksdb_engine::keys::meta::{
-    storage_key_mqtt_session, storage_key_mqtt_session_prefix,
+    storage_key_mqtt_last_will_claim, storage_key_mqtt_session, storage_key_mqtt_session_prefix,
     storage_key_mqtt_session_tenant_prefix,
 };
 use rocksdb_engine::rocksdb::RocksDBEngine;
 use rocksdb_engine::storage::meta_data::{
     engine_batch_save_by_meta_data, engine_delete_by_meta_data, engine_get_by_meta_data,
-    engine_prefix_list_by_meta_data,
+    engine_prefix_list_by_meta_data, engine_save_by_meta_data,
 };
 use std::sync::Arc;
 
@@ -91,6 +92,58 @@ pub fn delete(&self, tenant: &str, client_id: &str) -> Result<(), CommonError> {
         let key = storage_key_mqtt_session(tenant, client_id);
         engine_delete_by_meta_data(&self.rocksdb_engine_handler, &key)
     }
+
+    pub fn get_last_will_claim(
+        &self,
+        tenant: &str,
+        client_id: &str,
+    ) -> Result<Option<MqttLastWillClaim>, CommonError> {
+        let key = storage_key_mqtt_last_will_claim(tenant, client_id);
+        Ok(
+            engine_get_by_meta_data::<MqttLastWillClaim>(&self.rocksdb_engine_handler, &key)?
+                .map(|data| data.data),
+        )
+    }
+
+    /// Hand out the epoch of a will being registered by the client.
+    pub fn allocate_last_will_epoch(
+        &self,
+        tenant: &str,
+        client_id: &str,
+    ) -> Result<u64, CommonError> {
+        let mut record = self
+            .get_last_will_claim(tenant, client_id)?
+            .unwrap_or_else(|| MqttLastWillClaim {
+                tenant: tenant.to_string(),
+                client_id: client_id.to_string(),
+                ..Default::default()
+            });
+        let epoch = record.next_epoch();
+        record.allocated_epoch = epoch;
+        let key = storage_key_mqtt_last_will_claim(tenant, client_id);
+        engine_save_by_meta_data(&self.rocksdb_engine_handler, &key, &record)?;
+        Ok(epoch)
+    }
+
+    /// Record `claim` unless another broker already holds the will of its
+    /// session epoch. Returns whether the claim was granted.
+    pub fn claim_last_will(&self, claim: &MqttLastWillClaim) -> Result<bool, CommonError> {
+        let mut record = claim.clone();
+        if let Some(existing) = self.get_last_will_claim(&claim.tenant, &claim.client_id)? {
+            if !existing.grants(claim.session_epoch, claim.broker_id) {
+                return Ok(false);
+            }
+            record.allocated_epoch = existing.allocated_epoch;
+        }
+        let key = storage_key_mqtt_last_will_claim(&claim.tenant, &claim.client_id);
+        engine_save_by_meta_data(&self.rocksdb_engine_handler, &key, &record)?;
+        Ok(true)
+    }
+
+    pub fn delete_last_will_claim(&self, tenant: &str, client_id: &str) -> Result<(), CommonError> {
+        let key = storage_key_mqtt_last_will_claim(tenant, client_id);
+        engine_delete_by_meta_data(&self.rocksdb_engine_handler, &key)
+    }
 }
 
 #[cfg(test)]
@@ -184,4 +237,54 @@ fn test_save_overwrites_existing() {
         let all_sessions = storage.list().unwrap();
         assert_eq!(all_sessions.len(), 1);
     }
+
+    #[test]
+    fn test_claim_last_will_once_per_epoch() {
+        let storage = setup_storage();
+        let claim = |session_epoch: u64, broker_id: u64| MqttLastWillClaim {
+            tenant: "t1".to_string(),
+            client_id: "c1".to_string(),
+            session_epoch,
+            broker_id,
+            claim_time: 0,
+            allocated_epoch: 0,
+        };
+
+        assert!(storage.claim_last_will(&claim(10, 1)).unwrap());
+        assert!(!storage.claim_last_will(&claim(10, 2)).unwrap());
+        assert!(storage.claim_last_will(&claim(10, 1)).unwrap());
+
+        assert!(storage.claim_last_will(&claim(11, 2)).unwrap());
+        assert!(!storage.claim_last_will(&claim(10, 1)).unwrap());
+        assert_eq!(
+            storage
+                .get_last_will_claim("t1", "c1")
+                .unwrap()
+                .unwrap()
+                .broker_id,
+            2
+        );
+    }
+
+    #[test]
+    fn test_allocate_last_will_epoch() {
+        let storage = setup_storage();
+        assert_eq!(storage.allocate_last_will_epoch("t1", "c1").unwrap(), 1);
+        assert_eq!(storage.allocate_last_will_epoch("t1", "c1").unwrap(), 2);
+        assert_eq!(storage.allocate_last_will_epoch("t1", "c2").unwrap(), 1);
+
+        let claim = MqttLastWillClaim {
+            tenant: "t1".to_string(),
+            client_id: "c1".to_string(),
+            session_epoch: 2,
+            broker_id: 1,
+            claim_time: 0,
+            allocated_epoch: 0,
+        };
+        assert!(storage.claim_last_will(&claim).unwrap());
+        assert_eq!(storage.allocate_last_will_epoch("t1", "c1").unwrap(), 3);
+
+        storage.delete_last_will_claim("t1", "c1").unwrap();
+        assert!(storage.get_last_will_claim("t1", "c1").unwrap().is_none());
+    }
 }
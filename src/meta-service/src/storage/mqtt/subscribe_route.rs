@@ -0,0 +1,123 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::core::error::MetaServiceError;
+use rocksdb_engine::keys::meta::{
+    storage_key_mqtt_subscribe_route, storage_key_mqtt_subscribe_route_node_prefix,
+    storage_key_mqtt_subscribe_route_prefix,
+};
+use rocksdb_engine::rocksdb::RocksDBEngine;
+use rocksdb_engine::storage::meta_metadata::{
+    engine_delete_by_meta_metadata, engine_prefix_list_by_meta_metadata,
+    engine_save_by_meta_metadata,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// A topic filter hosted by a broker.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct MqttSubscribeRoute {
+    pub broker_id: u64,
+    pub tenant: String,
+    pub filter: String,
+}
+
+pub struct MqttSubscribeRouteStorage {
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+}
+
+impl MqttSubscribeRouteStorage {
+    pub fn new(rocksdb_engine_handler: Arc<RocksDBEngine>) -> Self {
+        MqttSubscribeRouteStorage {
+            rocksdb_engine_handler,
+        }
+    }
+
+    pub fn save(&self, route: &MqttSubscribeRoute) -> Result<(), MetaServiceError> {
+        let key = storage_key_mqtt_subscribe_route(route.broker_id, &route.tenant, &route.filter);
+        engine_save_by_meta_metadata(&self.rocksdb_engine_handler, &key, route.clone())?;
+        Ok(())
+    }
+
+    pub fn delete(&self, route: &MqttSubscribeRoute) -> Result<(), MetaServiceError> {
+        let key = storage_key_mqtt_subscribe_route(route.broker_id, &route.tenant, &route.filter);
+        engine_delete_by_meta_metadata(&self.rocksdb_engine_handler, &key)?;
+        Ok(())
+    }
+
+    pub fn list_all(&self) -> Result<Vec<MqttSubscribeRoute>, MetaServiceError> {
+        let prefix_key = storage_key_mqtt_subscribe_route_prefix();
+        let data = engine_prefix_list_by_meta_metadata::<MqttSubscribeRoute>(
+            &self.rocksdb_engine_handler,
+            &prefix_key,
+        )?;
+        Ok(data.into_iter().map(|raw| raw.data).collect())
+    }
+
+    pub fn list_by_node(
+        &self,
+        broker_id: u64,
+    ) -> Result<Vec<MqttSubscribeRoute>, MetaServiceError> {
+        let prefix_key = storage_key_mqtt_subscribe_route_node_prefix(broker_id);
+        let data = engine_prefix_list_by_meta_metadata::<MqttSubscribeRoute>(
+            &self.rocksdb_engine_handler,
+            &prefix_key,
+        )?;
+        Ok(data.into_iter().map(|raw| raw.data).collect())
+    }
+
+    pub fn delete_by_node(&self, broker_id: u64) -> Result<(), MetaServiceError> {
+        for route in self.list_by_node(broker_id)? {
+            self.delete(&route)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common_config::broker::{default_broker_config, init_broker_conf_by_config};
+    use rocksdb_engine::test::test_rocksdb_instance;
+
+    fn route(broker_id: u64, tenant: &str, filter: &str) -> MqttSubscribeRoute {
+        MqttSubscribeRoute {
+            broker_id,
+            tenant: tenant.to_string(),
+            filter: filter.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_subscribe_route_crud() {
+        init_broker_conf_by_config(default_broker_config());
+        let storage = MqttSubscribeRouteStorage::new(test_rocksdb_instance());
+
+        storage.save(&route(1, "t1", "a/+")).unwrap();
+        storage.save(&route(1, "t1", "a/#")).unwrap();
+        storage.save(&route(12, "t1", "a/+")).unwrap();
+        assert_eq!(storage.list_all().unwrap().len(), 3);
+        assert_eq!(storage.list_by_node(1).unwrap().len(), 2);
+
+        storage.delete(&route(1, "t1", "a/+")).unwrap();
+        assert_eq!(
+            storage.list_by_node(1).unwrap(),
+            vec![route(1, "t1", "a/#")]
+        );
+
+        storage.delete_by_node(1).unwrap();
+        assert!(storage.list_by_node(1).unwrap().is_empty());
+        assert_eq!(storage.list_by_node(12).unwrap().len(), 1);
+    }
+}
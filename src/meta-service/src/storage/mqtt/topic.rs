@@ -13,12 +13,12 @@
 // limitations under the License.
 
 use crate::core::error::MetaServiceError;
-use metadata_struct::mqtt::retain_message::MQTTRetainMessage;
+use metadata_struct::mqtt::retain_message::MQTTRetainIndex;
 use metadata_struct::mqtt::topic::Topic;
 use metadata_struct::mqtt::topic_rewrite_rule::MqttTopicRewriteRule;
 use rocksdb_engine::keys::meta::{
-    storage_key_mqtt_retain_message, storage_key_mqtt_retain_message_prefix,
-    storage_key_mqtt_retain_message_tenant_prefix, storage_key_mqtt_topic,
+    storage_key_mqtt_retain_index, storage_key_mqtt_retain_index_prefix,
+    storage_key_mqtt_retain_index_tenant_prefix, storage_key_mqtt_topic,
     storage_key_mqtt_topic_cluster_prefix, storage_key_mqtt_topic_rewrite_rule,
     storage_key_mqtt_topic_rewrite_rule_prefix, storage_key_mqtt_topic_rewrite_rule_tenant_prefix,
     storage_key_mqtt_topic_tenant_prefix,
@@ -138,54 +138,57 @@ pub fn list_topic_rewrite_rules_by_tenant(
         Ok(data.into_iter().map(|raw| raw.data).collect())
     }
 
-    // Retain Message
-    pub fn save_retain_message(
-        &self,
-        retain_message: MQTTRetainMessage,
-    ) -> Result<(), MetaServiceError> {
-        let key =
-            storage_key_mqtt_retain_message(&retain_message.tenant, &retain_message.topic_name);
-        engine_save_by_meta_data(&self.rocksdb_engine_handler, &key, retain_message)?;
+    // Retain Index
+    pub fn save_retain_index(&self, index: MQTTRetainIndex) -> Result<(), MetaServiceError> {
+        let key = storage_key_mqtt_retain_index(&index.tenant, &index.topic_name);
+        engine_save_by_meta_data(&self.rocksdb_engine_handler, &key, index)?;
         Ok(())
     }
 
-    pub fn delete_retain_message(
+    pub fn delete_retain_index(
         &self,
         tenant: &str,
         topic_name: &str,
     ) -> Result<(), MetaServiceError> {
-        let key = storage_key_mqtt_retain_message(tenant, topic_name);
+        let key = storage_key_mqtt_retain_index(tenant, topic_name);
         engine_delete_by_meta_data(&self.rocksdb_engine_handler, &key)?;
         Ok(())
     }
 
-    pub fn get_retain_message(
+    pub fn get_retain_index(
         &self,
         tenant: &str,
         topic_name: &str,
-    ) -> Result<Option<MQTTRetainMessage>, MetaServiceError> {
-        let key = storage_key_mqtt_retain_message(tenant, topic_name);
+    ) -> Result<Option<MQTTRetainIndex>, MetaServiceError> {
+        let key = storage_key_mqtt_retain_index(tenant, topic_name);
         Ok(
-            engine_get_by_meta_data::<MQTTRetainMessage>(&self.rocksdb_engine_handler, &key)?
+            engine_get_by_meta_data::<MQTTRetainIndex>(&self.rocksdb_engine_handler, &key)?
                 .map(|data| data.data),
         )
     }
 
-    pub fn list_retain_messages_by_tenant(
+    /// Index entries of `tenant` whose topic name starts with `topic_prefix`.
+    /// An empty prefix lists the whole tenant.
+    pub fn list_retain_index_by_prefix(
         &self,
         tenant: &str,
-    ) -> Result<Vec<MQTTRetainMessage>, MetaServiceError> {
-        let prefix_key = storage_key_mqtt_retain_message_tenant_prefix(tenant);
-        let data = engine_prefix_list_by_meta_data::<MQTTRetainMessage>(
+        topic_prefix: &str,
+    ) -> Result<Vec<MQTTRetainIndex>, MetaServiceError> {
+        let prefix_key = format!(
+            "{}{}",
+            storage_key_mqtt_retain_index_tenant_prefix(tenant),
+            topic_prefix
+        );
+        let data = engine_prefix_list_by_meta_data::<MQTTRetainIndex>(
             &self.rocksdb_engine_handler,
             &prefix_key,
         )?;
         Ok(data.into_iter().map(|raw| raw.data).collect())
     }
 
-    pub fn list_all_retain_messages(&self) -> Result<Vec<MQTTRetainMessage>, MetaServiceError> {
-        let prefix_key = storage_key_mqtt_retain_message_prefix();
-        let data = engine_prefix_list_by_meta_data::<MQTTRetainMessage>(
+    pub fn list_all_retain_index(&self) -> Result<Vec<MQTTRetainIndex>, MetaServiceError> {
+        let prefix_key = storage_key_mqtt_retain_index_prefix();
+        let data = engine_prefix_list_by_meta_data::<MQTTRetainIndex>(
             &self.rocksdb_engine_handler,
             &prefix_key,
         )?;
@@ -198,7 +201,6 @@ mod tests {
     use super::*;
     use common_base::tools::now_second;
     use common_config::broker::{default_broker_config, init_broker_conf_by_config};
-    use metadata_struct::mqtt::retain_message::MQTTRetainMessage;
     use metadata_struct::mqtt::topic_rewrite_rule::MqttTopicRewriteRule;
     use metadata_struct::tenant::DEFAULT_TENANT;
     use rocksdb_engine::test::test_rocksdb_instance;
@@ -236,13 +238,13 @@ fn create_rewrite_rule(
         }
     }
 
-    fn create_retain_message(tenant: &str, topic: &str, message: &[u8]) -> MQTTRetainMessage {
-        use bytes::Bytes;
-        MQTTRetainMessage {
+    fn create_retain_index(tenant: &str, topic: &str, version: u64) -> MQTTRetainIndex {
+        MQTTRetainIndex {
             tenant: tenant.to_string(),
             topic_name: topic.to_string(),
-            payload: Bytes::from(message.to_vec()),
+            payload_size: 14,
             expired_at: now_second() + 3600,
+            version,
             create_time: now_second(),
         }
     }
@@ -300,23 +302,56 @@ fn test_topic_rewrite_rule() {
     }
 
     #[test]
-    fn test_retain_message() {
+    fn test_retain_index() {
         let storage = setup_storage();
         let tenant = DEFAULT_TENANT;
-        let topic = "sensor/data";
 
-        // Save retain message
-        let msg = create_retain_message(tenant, topic, b"temperature:25");
-        storage.save_retain_message(msg).unwrap();
+        // Save & Get
+        storage
+            .save_retain_index(create_retain_index(tenant, "sensor/data", 1))
+            .unwrap();
+        let retrieved = storage.get_retain_index(tenant, "sensor/data").unwrap();
+        assert_eq!(retrieved.unwrap().version, 1);
 
-        // Get message
-        let retrieved = storage.get_retain_message(tenant, topic).unwrap();
-        assert!(retrieved.is_some());
-        assert_eq!(retrieved.unwrap().payload.as_ref(), b"temperature:25");
+        // Overwrite bumps the version
+        storage
+            .save_retain_index(create_retain_index(tenant, "sensor/data", 2))
+            .unwrap();
+        let retrieved = storage.get_retain_index(tenant, "sensor/data").unwrap();
+        assert_eq!(retrieved.unwrap().version, 2);
 
-        // Delete message
-        storage.delete_retain_message(tenant, topic).unwrap();
-        assert!(storage.get_retain_message(tenant, topic).unwrap().is_none());
+        // Prefix listing stays inside the tenant
+        storage
+            .save_retain_index(create_retain_index(tenant, "sensor/temp", 1))
+            .unwrap();
+        storage
+            .save_retain_index(create_retain_index(tenant, "alarm/1", 1))
+            .unwrap();
+        storage
+            .save_retain_index(create_retain_index("t2", "sensor/data", 1))
+            .unwrap();
+        assert_eq!(
+            storage
+                .list_retain_index_by_prefix(tenant, "sensor/")
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(
+            storage
+                .list_retain_index_by_prefix(tenant, "")
+                .unwrap()
+                .len(),
+            3
+        );
+        assert_eq!(storage.list_all_retain_index().unwrap().len(), 4);
+
+        // Delete
+        storage.delete_retain_index(tenant, "sensor/data").unwrap();
+        assert!(storage
+            .get_retain_index(tenant, "sensor/data")
+            .unwrap()
+            .is_none());
     }
 
     #[test]
@@ -324,7 +359,7 @@ fn test_get_nonexistent() {
         let storage = setup_storage();
         assert!(storage.get("", "nonexistent").unwrap().is_none());
         assert!(storage
-            .get_retain_message(DEFAULT_TENANT, "nonexistent")
+            .get_retain_index(DEFAULT_TENANT, "nonexistent")
             .unwrap()
             .is_none());
     }
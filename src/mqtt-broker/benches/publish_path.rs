@@ -0,0 +1,150 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Carrying a PUBLISH payload from the decoded network frame, through the
+// stored record, to the Publish pushed to a subscriber. `copy` copies the
+// payload at every hop, `shared` slices the original buffers the way the
+// broker does. Allocations per message are printed before the timings.
+// Run with: cargo bench -p mqtt-broker --bench publish_path
+
+use bytes::{Bytes, BytesMut};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use metadata_struct::adapter::adapter_record::AdapterWriteRecord;
+use metadata_struct::storage::convert::convert_adapter_record_to_storage;
+use metadata_struct::storage::record::StorageRecord;
+use protocol::mqtt::codec::{MqttCodec, MqttPacketWrapper};
+use protocol::mqtt::common::{MqttPacket, Publish, QoS};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio_util::codec::{Decoder, Encoder};
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const TOPIC: &str = "site/1/device/1/telemetry";
+
+fn encode_frame(payload_size: usize) -> BytesMut {
+    let publish = Publish {
+        dup: false,
+        qos: QoS::AtLeastOnce,
+        p_kid: 1,
+        retain: false,
+        topic: Bytes::from_static(TOPIC.as_bytes()),
+        payload: Bytes::from(vec![7u8; payload_size]),
+    };
+    let mut frame = BytesMut::new();
+    MqttCodec::new(Some(5))
+        .encode(
+            MqttPacketWrapper {
+                protocol_version: 5,
+                packet: MqttPacket::Publish(publish, None),
+            },
+            &mut frame,
+        )
+        .unwrap();
+    frame
+}
+
+fn decode_publish(frame: &BytesMut) -> Publish {
+    let mut stream = frame.clone();
+    match MqttCodec::new(Some(5)).decode(&mut stream) {
+        Ok(Some(MqttPacket::Publish(publish, _))) => publish,
+        other => panic!("unexpected decode result: {other:?}"),
+    }
+}
+
+fn publish_to_subscriber(data: Bytes) -> Publish {
+    Publish {
+        dup: false,
+        qos: QoS::AtLeastOnce,
+        p_kid: 2,
+        retain: false,
+        topic: Bytes::from_static(TOPIC.as_bytes()),
+        payload: data,
+    }
+}
+
+fn copy_path(frame: &BytesMut) -> Publish {
+    let publish = decode_publish(frame);
+    let record = AdapterWriteRecord::new(TOPIC, publish.payload.to_vec());
+    let stored = convert_adapter_record_to_storage(record, "shard", 0);
+    let encoded = Bytes::from(stored.encode().unwrap());
+    let read = StorageRecord::decode(&encoded).unwrap();
+    publish_to_subscriber(Bytes::copy_from_slice(&read.data))
+}
+
+fn shared_path(frame: &BytesMut) -> Publish {
+    let publish = decode_publish(frame);
+    let record = AdapterWriteRecord::new(TOPIC, publish.payload);
+    let stored = convert_adapter_record_to_storage(record, "shard", 0);
+    let encoded = Bytes::from(stored.encode().unwrap());
+    let read = StorageRecord::decode_bytes(&encoded).unwrap();
+    publish_to_subscriber(read.data)
+}
+
+fn allocations_per_message(frame: &BytesMut, path: fn(&BytesMut) -> Publish) -> usize {
+    const ROUNDS: usize = 1000;
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..ROUNDS {
+        black_box(path(frame));
+    }
+    (ALLOCATIONS.load(Ordering::Relaxed) - before) / ROUNDS
+}
+
+fn bench_publish_path(c: &mut Criterion) {
+    let mut group = c.benchmark_group("publish_path");
+    for payload_size in [256, 4096, 65536] {
+        let frame = encode_frame(payload_size);
+        println!(
+            "payload {payload_size} bytes: copy {} allocations/message, shared {} allocations/message",
+            allocations_per_message(&frame, copy_path),
+            allocations_per_message(&frame, shared_path),
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("copy", payload_size),
+            &frame,
+            |b, frame| b.iter(|| copy_path(black_box(frame))),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("shared", payload_size),
+            &frame,
+            |b, frame| b.iter(|| shared_path(black_box(frame))),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_publish_path);
+criterion_main!(benches);
@@ -0,0 +1,85 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Matching a topic against the subscriptions of a tenant, scanning every
+// filter versus looking it up in the topic trie.
+// Run with: cargo bench -p mqtt-broker --bench topic_trie
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use mqtt_broker::subscribe::common::is_match_sub_and_topic;
+use mqtt_broker::subscribe::topic_trie::SubscribeTopicTrie;
+
+const TENANT: &str = "default";
+
+// One exact, one '+' and one '#' filter per device, spread over sites.
+fn build_filters(count: usize) -> Vec<(String, String)> {
+    (0..count)
+        .map(|i| {
+            let site = i % 100;
+            let device = i / 3;
+            let path = match i % 3 {
+                0 => format!("site/{site}/device/{device}/telemetry"),
+                1 => format!("site/{site}/device/+/status"),
+                _ => format!("site/{site}/device/{device}/#"),
+            };
+            (format!("client-{i}"), path)
+        })
+        .collect()
+}
+
+fn bench_match(c: &mut Criterion) {
+    let mut group = c.benchmark_group("subscribe_match");
+    group.sample_size(10);
+
+    for count in [1_000, 10_000, 100_000, 200_000] {
+        let filters = build_filters(count);
+        let trie = SubscribeTopicTrie::default();
+        for (client_id, path) in filters.iter() {
+            trie.insert(TENANT, client_id, path);
+        }
+        let topic = format!("site/7/device/{}/telemetry", count / 6);
+
+        group.bench_with_input(BenchmarkId::new("scan", count), &count, |b, _| {
+            b.iter(|| {
+                filters
+                    .iter()
+                    .filter(|(_, path)| is_match_sub_and_topic(path, black_box(&topic)).is_ok())
+                    .count()
+            });
+        });
+
+        group.bench_with_input(BenchmarkId::new("trie", count), &count, |b, _| {
+            b.iter(|| trie.matches(TENANT, black_box(&topic)).len());
+        });
+    }
+    group.finish();
+}
+
+fn bench_update(c: &mut Criterion) {
+    let filters = build_filters(100_000);
+    let trie = SubscribeTopicTrie::default();
+    for (client_id, path) in filters.iter() {
+        trie.insert(TENANT, client_id, path);
+    }
+
+    c.bench_function("subscribe_trie_insert_remove_100000", |b| {
+        b.iter(|| {
+            trie.insert(TENANT, "bench-client", "site/1/device/+/status");
+            trie.remove(TENANT, "bench-client", "site/1/device/+/status");
+        });
+    });
+}
+
+criterion_group!(benches, bench_match, bench_update);
+criterion_main!(benches);
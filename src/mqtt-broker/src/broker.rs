@@ -14,21 +14,30 @@
 
 #![allow(clippy::result_large_err)]
 use crate::core::cache::MQTTCacheManager;
+use crate::core::consumer_lag::ConsumerLagExporter;
 use crate::core::event::EventReportManager;
 use crate::core::flapping_detect::clean_flapping_detect;
 use crate::core::keep_alive::ClientKeepAlive;
+use crate::core::local_log_expire::start_local_log_expire;
+use crate::core::metrics::{apply_subscription_metrics_config, apply_topic_metrics_config};
 use crate::core::metrics_cache::metrics_record_thread;
+use crate::core::overload::start_overload_protection;
 use crate::core::pkid_manager::clean_pkid_data;
+use crate::core::retain::backfill_retain_index;
 use crate::core::system_alarm::SystemAlarm;
 use crate::core::tool::ResultMqttBrokerError;
 use crate::core::topic_rewrite::start_topic_rewrite_convert_thread;
+use crate::core::topic_stats::start_topic_stats_report;
 use crate::server::{Server, TcpServerContext};
 use crate::storage::session::SessionBatcher;
 use crate::subscribe::manager::SubscribeManager;
 use crate::subscribe::parse::{start_update_parse_thread, ParseSubscribeData};
+use crate::subscribe::route::start_subscribe_route_sync;
 use crate::subscribe::PushManager;
+use crate::system_topic::metrics_history::start_metrics_history_thread;
 use crate::system_topic::SystemTopic;
 use broker_core::cache::NodeCacheManager;
+use common_base::runtime::RuntimePool;
 use common_base::task::{TaskKind, TaskSupervisor};
 use common_config::broker::broker_config;
 use common_group::manager::OffsetManager;
@@ -49,7 +58,7 @@
 use storage_adapter::driver::StorageDriverManager;
 use tokio::sync::broadcast::{self};
 use tokio::sync::mpsc;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 #[derive(Clone)]
 pub struct MqttBrokerServerParams {
@@ -97,7 +106,10 @@ pub struct MqttBrokerServer {
 impl MqttBrokerServer {
     pub async fn new(params: MqttBrokerServerParams, stop: broadcast::Sender<bool>) -> Self {
         let request_channel = params.request_channel.clone();
-        let limit_config = params.node_cache.get_cluster_config().mqtt_limit;
+        let cluster_config = params.node_cache.get_cluster_config();
+        apply_topic_metrics_config(&cluster_config.mqtt_topic_metrics);
+        apply_subscription_metrics_config(&cluster_config.mqtt_subscription_metrics);
+        let limit_config = cluster_config.mqtt_limit;
         let limit_manager = Arc::new(
             match MQTTRateLimiterManager::new(
                 params.node_cache.clone(),
@@ -130,6 +142,7 @@ pub async fn new(params: MqttBrokerServerParams, stop: broadcast::Sender<bool>)
                 node_call: params.node_call.clone(),
                 task_supervisor: params.task_supervisor.clone(),
                 event_manager: params.event_manager.clone(),
+                connector_manager: params.connector_manager.clone(),
             },
             request_channel,
         );
@@ -187,6 +200,19 @@ async fn start_daemon_thread(&self) -> ResultMqttBrokerError {
                     .await;
             });
 
+        // webhook dispatcher
+        let webhook = self.event_manager.webhook();
+        if webhook.is_enable() {
+            let stop_send = self.stop.clone();
+            self.task_supervisor.spawn_on(
+                TaskKind::MQTTWebhook.to_string(),
+                RuntimePool::Background,
+                async move {
+                    webhook.start(stop_send).await;
+                },
+            );
+        }
+
         // client keep alive
         let raw_stop_send = self.stop.clone();
         let keep_alive = ClientKeepAlive::new(
@@ -195,6 +221,7 @@ async fn start_daemon_thread(&self) -> ResultMqttBrokerError {
             self.connection_manager.clone(),
             self.subscribe_manager.clone(),
             self.cache_manager.clone(),
+            self.event_manager.clone(),
         );
         self.task_supervisor.spawn(
             TaskKind::MQTTClientKeepAlive.to_string(),
@@ -206,18 +233,24 @@ async fn start_daemon_thread(&self) -> ResultMqttBrokerError {
         // flapping detect
         let stop_send = self.stop.clone();
         let cache_manager = self.cache_manager.clone();
-        self.task_supervisor
-            .spawn(TaskKind::MQTTCleanFlappingDetect.to_string(), async move {
+        self.task_supervisor.spawn_on(
+            TaskKind::MQTTCleanFlappingDetect.to_string(),
+            RuntimePool::Background,
+            async move {
                 clean_flapping_detect(cache_manager, stop_send).await;
-            });
+            },
+        );
 
         // clean expired pkid data
         let stop_send = self.stop.clone();
         let cache_manager = self.cache_manager.clone();
-        self.task_supervisor
-            .spawn(TaskKind::MQTTCleanPkidData.to_string(), async move {
+        self.task_supervisor.spawn_on(
+            TaskKind::MQTTCleanPkidData.to_string(),
+            RuntimePool::Background,
+            async move {
                 clean_pkid_data(cache_manager, stop_send).await;
-            });
+            },
+        );
 
         // report system topic info
         let raw_stop_send = self.stop.clone();
@@ -226,8 +259,9 @@ async fn start_daemon_thread(&self) -> ResultMqttBrokerError {
             self.storage_driver_manager.clone(),
             self.client_pool.clone(),
         );
-        self.task_supervisor.spawn(
+        self.task_supervisor.spawn_on(
             TaskKind::MQTTReportSystemTopicData.to_string(),
+            RuntimePool::Background,
             Box::pin(async move {
                 system_topic.start_thread(raw_stop_send).await;
             }),
@@ -241,6 +275,51 @@ async fn start_daemon_thread(&self) -> ResultMqttBrokerError {
                 start_topic_rewrite_convert_thread(metadata_cache, stop_send).await;
             });
 
+        // register local subscribe routes in meta-service
+        let cache_manager = self.cache_manager.clone();
+        let client_pool = self.client_pool.clone();
+        let subscribe_manager = self.subscribe_manager.clone();
+        let stop_send = self.stop.clone();
+        self.task_supervisor.spawn_on(
+            TaskKind::MQTTSubscribeRouteSync.to_string(),
+            RuntimePool::Background,
+            async move {
+                start_subscribe_route_sync(
+                    cache_manager,
+                    client_pool,
+                    subscribe_manager,
+                    stop_send,
+                )
+                .await;
+            },
+        );
+
+        // index retained messages stored before the retain index existed
+        let storage_driver_manager = self.storage_driver_manager.clone();
+        let cache_manager = self.cache_manager.clone();
+        self.task_supervisor.spawn_on(
+            TaskKind::MQTTRetainIndexBackfill.to_string(),
+            RuntimePool::Background,
+            async move {
+                if let Err(e) = backfill_retain_index(&storage_driver_manager, &cache_manager).await
+                {
+                    warn!("Failed to backfill the retain index: {}", e);
+                }
+            },
+        );
+
+        // report per-topic statistics to meta-service
+        let cache_manager = self.cache_manager.clone();
+        let subscribe_manager = self.subscribe_manager.clone();
+        let stop_send = self.stop.clone();
+        self.task_supervisor.spawn_on(
+            TaskKind::MQTTTopicStatsReport.to_string(),
+            RuntimePool::Background,
+            async move {
+                start_topic_stats_report(cache_manager, subscribe_manager, stop_send).await;
+            },
+        );
+
         // metrics record
         metrics_record_thread(
             self.metrics_cache_manager.clone(),
@@ -253,6 +332,35 @@ async fn start_daemon_thread(&self) -> ResultMqttBrokerError {
             self.task_supervisor.clone(),
         );
 
+        // consumer lag of subscription groups
+        let consumer_lag = ConsumerLagExporter::new(
+            self.cache_manager.clone(),
+            self.subscribe_manager.clone(),
+            self.connector_manager.clone(),
+            self.storage_driver_manager.clone(),
+            self.client_pool.clone(),
+        );
+        let raw_stop_send = self.stop.clone();
+        self.task_supervisor.spawn_on(
+            TaskKind::MQTTMetricsConsumerLag.to_string(),
+            RuntimePool::Background,
+            async move {
+                consumer_lag.start(raw_stop_send).await;
+            },
+        );
+
+        // expiry of local alarm, ban and slow subscription logs
+        let rocksdb_engine_handler = self.rocksdb_engine_handler.clone();
+        let cache_manager = self.cache_manager.clone();
+        let raw_stop_send = self.stop.clone();
+        self.task_supervisor.spawn_on(
+            TaskKind::MQTTLocalLogExpire.to_string(),
+            RuntimePool::Background,
+            async move {
+                start_local_log_expire(rocksdb_engine_handler, cache_manager, raw_stop_send).await;
+            },
+        );
+
         // system alarm
         let system_alarm = SystemAlarm::new(
             self.client_pool.clone(),
@@ -263,12 +371,47 @@ async fn start_daemon_thread(&self) -> ResultMqttBrokerError {
         let raw_stop_send = self.stop.clone();
         let config = broker_config();
         if config.mqtt_system_monitor.enable {
-            self.task_supervisor
-                .spawn(TaskKind::MQTTSystemAlarm.to_string(), async move {
+            self.task_supervisor.spawn_on(
+                TaskKind::MQTTSystemAlarm.to_string(),
+                RuntimePool::Background,
+                async move {
                     if let Err(e) = system_alarm.start(raw_stop_send).await {
                         error!("Failed to start system alarm monitoring: {}", e);
                     }
-                });
+                },
+            );
+        }
+
+        // overload protection, checks the switch on every tick so it can be
+        // turned on and off at runtime
+        let cache_manager = self.cache_manager.clone();
+        let raw_stop_send = self.stop.clone();
+        let interval_ms = config.mqtt_system_monitor.overload_check_interval_ms;
+        self.task_supervisor.spawn_on(
+            TaskKind::MQTTOverloadProtection.to_string(),
+            RuntimePool::Background,
+            async move {
+                start_overload_protection(cache_manager, interval_ms, raw_stop_send).await;
+            },
+        );
+
+        // metrics history, the shard is only created when this is enabled
+        if config.mqtt_system_monitor.metrics_history_enable {
+            let storage_driver_manager = self.storage_driver_manager.clone();
+            let raw_stop_send = self.stop.clone();
+            let interval_ms = config.mqtt_system_monitor.metrics_history_interval_ms;
+            self.task_supervisor.spawn_on(
+                TaskKind::MQTTMetricsHistory.to_string(),
+                RuntimePool::Background,
+                async move {
+                    start_metrics_history_thread(
+                        storage_driver_manager,
+                        interval_ms,
+                        raw_stop_send,
+                    )
+                    .await;
+                },
+            );
         }
         Ok(())
     }
@@ -287,10 +430,13 @@ async fn start_subscribe_push(&self) {
         // start push manager
         let stop_send = self.stop.clone();
         let push_manager = self.push_manager.clone();
-        self.task_supervisor
-            .spawn(TaskKind::MQTTSubscribePush.to_string(), async move {
+        self.task_supervisor.spawn_on(
+            TaskKind::MQTTSubscribePush.to_string(),
+            RuntimePool::Push,
+            async move {
                 push_manager.start(&stop_send).await;
-            });
+            },
+        );
 
         // parse subscribe data
         let (sx, rx) = mpsc::channel::<ParseSubscribeData>(2000);
@@ -299,8 +445,10 @@ async fn start_subscribe_push(&self) {
         let client_pool = self.client_pool.clone();
         let cache_manager = self.cache_manager.clone();
         let stop_send = self.stop.clone();
-        self.task_supervisor
-            .spawn(TaskKind::MQTTSubscribeParse.to_string(), async move {
+        self.task_supervisor.spawn_on(
+            TaskKind::MQTTSubscribeParse.to_string(),
+            RuntimePool::Push,
+            async move {
                 start_update_parse_thread(
                     client_pool,
                     cache_manager,
@@ -309,7 +457,8 @@ async fn start_subscribe_push(&self) {
                     stop_send,
                 )
                 .await;
-            });
+            },
+        );
     }
 
     pub async fn awaiting_stop(&self) {
@@ -0,0 +1,185 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::{Bytes, BytesMut};
+use protocol::coap::packet::BlockOption;
+use std::collections::HashMap;
+
+pub enum Block1Result {
+    /// More blocks are expected, answered with 2.31 Continue.
+    Continue,
+    Complete(Bytes),
+    /// The block does not follow the blocks received so far.
+    Incomplete,
+    TooLarge,
+}
+
+struct PartialPayload {
+    data: BytesMut,
+    updated_at: u64,
+}
+
+/// Reassembles block-wise PUT and POST payloads (Block1, RFC 7959), one
+/// transfer per URI.
+#[derive(Default)]
+pub struct Block1Assembler {
+    transfers: HashMap<String, PartialPayload>,
+}
+
+impl Block1Assembler {
+    pub fn push(
+        &mut self,
+        key: &str,
+        block: BlockOption,
+        payload: &[u8],
+        max_size: usize,
+        now: u64,
+    ) -> Block1Result {
+        let offset = block.num as usize * block.size();
+        if block.num == 0 {
+            self.transfers.insert(
+                key.to_string(),
+                PartialPayload {
+                    data: BytesMut::new(),
+                    updated_at: now,
+                },
+            );
+        }
+        let Some(transfer) = self.transfers.get_mut(key) else {
+            return Block1Result::Incomplete;
+        };
+        // Every block but the last fills the block size exactly.
+        if transfer.data.len() != offset || (block.more && payload.len() != block.size()) {
+            self.transfers.remove(key);
+            return Block1Result::Incomplete;
+        }
+        if offset + payload.len() > max_size {
+            self.transfers.remove(key);
+            return Block1Result::TooLarge;
+        }
+
+        transfer.data.extend_from_slice(payload);
+        transfer.updated_at = now;
+        if block.more {
+            return Block1Result::Continue;
+        }
+        let transfer = self.transfers.remove(key).unwrap();
+        Block1Result::Complete(transfer.data.freeze())
+    }
+
+    /// Drop transfers the client abandoned.
+    pub fn expire(&mut self, now: u64, timeout_sec: u64) {
+        self.transfers
+            .retain(|_, transfer| now.saturating_sub(transfer.updated_at) < timeout_sec);
+    }
+}
+
+/// Block `requested` of `payload` (Block2, RFC 7959), served in blocks of at
+/// most `2^(max_szx + 4)` bytes. A client asking for larger blocks gets the
+/// block of the smaller size starting at the same offset. Returns the block
+/// option of the response and the block, or None past the end.
+pub fn block2_slice(
+    payload: &Bytes,
+    requested: BlockOption,
+    max_szx: u8,
+) -> Option<(BlockOption, Bytes)> {
+    let szx = requested.szx.min(max_szx);
+    let offset = requested.num as usize * requested.size();
+    if offset >= payload.len() && offset > 0 {
+        return None;
+    }
+    let size = 1usize << (szx + 4);
+    let end = (offset + size).min(payload.len());
+    Some((
+        BlockOption {
+            num: (offset / size) as u32,
+            more: end < payload.len(),
+            szx,
+        },
+        payload.slice(offset..end),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(num: u32, more: bool) -> BlockOption {
+        // 16 byte blocks
+        BlockOption { num, more, szx: 0 }
+    }
+
+    #[test]
+    fn test_block1_reassembly() {
+        let mut assembler = Block1Assembler::default();
+        let first = [1u8; 16];
+        let second = [2u8; 16];
+        assert!(matches!(
+            assembler.push("t", block(0, true), &first, 1024, 1),
+            Block1Result::Continue
+        ));
+        assert!(matches!(
+            assembler.push("t", block(1, true), &second, 1024, 2),
+            Block1Result::Continue
+        ));
+        let Block1Result::Complete(payload) = assembler.push("t", block(2, false), b"end", 1024, 3)
+        else {
+            panic!("transfer should be complete");
+        };
+        assert_eq!(payload.len(), 35);
+        assert_eq!(&payload[32..], b"end");
+
+        // out of order
+        assert!(matches!(
+            assembler.push("t", block(1, true), &second, 1024, 4),
+            Block1Result::Incomplete
+        ));
+        // too large
+        assembler.push("t", block(0, true), &first, 20, 5);
+        assert!(matches!(
+            assembler.push("t", block(1, true), &second, 20, 6),
+            Block1Result::TooLarge
+        ));
+
+        assembler.push("t", block(0, true), &first, 1024, 10);
+        assembler.expire(100, 60);
+        assert!(matches!(
+            assembler.push("t", block(1, false), b"x", 1024, 100),
+            Block1Result::Incomplete
+        ));
+    }
+
+    #[test]
+    fn test_block2_slice() {
+        let payload = Bytes::from(vec![7u8; 40]);
+        let (option, data) = block2_slice(&payload, block(0, false), 0).unwrap();
+        assert_eq!(option, block(0, true));
+        assert_eq!(data.len(), 16);
+
+        let (option, data) = block2_slice(&payload, block(2, false), 0).unwrap();
+        assert_eq!(option, block(2, false));
+        assert_eq!(data.len(), 8);
+        assert!(block2_slice(&payload, block(3, false), 0).is_none());
+
+        // the client asks for 32 byte blocks, the gateway serves 16
+        let requested = BlockOption {
+            num: 1,
+            more: false,
+            szx: 1,
+        };
+        let (option, data) = block2_slice(&payload, requested, 0).unwrap();
+        assert_eq!(option, block(2, false));
+        assert_eq!(data.len(), 8);
+    }
+}
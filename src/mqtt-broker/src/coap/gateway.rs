@@ -0,0 +1,335 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::peer::{CoapPeer, CoapPeerHandle};
+use crate::core::cache::MQTTCacheManager;
+use crate::core::error::MqttBrokerError;
+use crate::core::tool::ResultMqttBrokerError;
+use common_config::broker::broker_config;
+use dashmap::DashMap;
+use metadata_struct::connection::{NetworkConnection, NetworkConnectionType};
+use network_server::command::ArcCommandAdapter;
+use network_server::common::cert_manager::cert_paths;
+use network_server::common::connection_manager::ConnectionManager;
+use network_server::common::tls_acceptor::load_certs;
+use protocol::coap::codec::{decode, encode};
+use protocol::coap::packet::{coap_message_to_string, CoapCode, CoapMessage, CoapType};
+use protocol::robust::RobustMQProtocol;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::UdpSocket;
+use tokio::select;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, info, warn};
+use webrtc_dtls::config::{Config, ExtendedMasterSecretType};
+use webrtc_dtls::crypto::{Certificate, CryptoPrivateKey};
+use webrtc_dtls::listener::listen;
+use webrtc_util::conn::{Conn, Listener};
+
+const MAX_DATAGRAM_SIZE: usize = 65535;
+const PEER_CHANNEL_SIZE: usize = 1000;
+
+/// (endpoint address, over DTLS)
+pub type CoapPeerKey = (SocketAddr, bool);
+
+/// Where messages to an endpoint go: the shared UDP socket, or the DTLS
+/// connection of the endpoint.
+#[derive(Clone)]
+pub enum CoapTransport {
+    Udp(Arc<UdpSocket>),
+    Dtls(Arc<dyn Conn + Send + Sync>),
+}
+
+impl CoapTransport {
+    pub fn is_dtls(&self) -> bool {
+        matches!(self, CoapTransport::Dtls(_))
+    }
+
+    pub async fn send(&self, addr: SocketAddr, message: &CoapMessage) {
+        let data = encode(message);
+        let result = match self {
+            CoapTransport::Udp(socket) => socket
+                .send_to(&data, addr)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            CoapTransport::Dtls(conn) => conn
+                .send(&data)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+        };
+        if let Err(e) = result {
+            debug!("Failed to send CoAP message to {}: {}", addr, e);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct CoapContext {
+    pub command: ArcCommandAdapter,
+    pub cache_manager: Arc<MQTTCacheManager>,
+    pub connection_manager: Arc<ConnectionManager>,
+    pub peers: Arc<DashMap<CoapPeerKey, CoapPeerHandle>>,
+}
+
+/// UDP and DTLS listeners of the CoAP gateway. Every message is dispatched to
+/// the task serving its endpoint, started by the first request.
+pub struct CoapGateway {
+    command: ArcCommandAdapter,
+    cache_manager: Arc<MQTTCacheManager>,
+    connection_manager: Arc<ConnectionManager>,
+    stop_sx: broadcast::Sender<bool>,
+}
+
+impl CoapGateway {
+    pub fn new(
+        command: ArcCommandAdapter,
+        cache_manager: Arc<MQTTCacheManager>,
+        connection_manager: Arc<ConnectionManager>,
+        stop_sx: broadcast::Sender<bool>,
+    ) -> Self {
+        CoapGateway {
+            command,
+            cache_manager,
+            connection_manager,
+            stop_sx,
+        }
+    }
+
+    pub async fn start(&self) -> ResultMqttBrokerError {
+        let conf = broker_config();
+        let context = CoapContext {
+            command: self.command.clone(),
+            cache_manager: self.cache_manager.clone(),
+            connection_manager: self.connection_manager.clone(),
+            peers: Arc::new(DashMap::new()),
+        };
+
+        let addr = format!("0.0.0.0:{}", conf.coap.port);
+        let socket = UdpSocket::bind(&addr).await.map_err(|e| {
+            MqttBrokerError::CommonError(format!("Failed to bind CoAP gateway to {}: {}", addr, e))
+        })?;
+        let socket = Arc::new(socket);
+        let transport = CoapTransport::Udp(socket.clone());
+        let udp_context = context.clone();
+        let mut stop_rx = self.stop_sx.subscribe();
+        tokio::spawn(Box::pin(async move {
+            let mut buf = vec![0u8; MAX_DATAGRAM_SIZE];
+            loop {
+                select! {
+                    val = stop_rx.recv() => {
+                        if let Ok(true) = val {
+                            // Dropping the handles disconnects every endpoint.
+                            udp_context.peers.clear();
+                            debug!("CoAP gateway stopped successfully.");
+                            break;
+                        }
+                    }
+                    val = socket.recv_from(&mut buf) => {
+                        match val {
+                            Ok((len, addr)) => {
+                                handle_datagram(&udp_context, &transport, addr, &buf[..len]).await;
+                            }
+                            Err(e) => {
+                                debug!("CoAP gateway failed to receive datagram: {}", e);
+                            }
+                        }
+                    }
+                }
+            }
+        }));
+        info!("CoAP gateway started successfully, port:{}", conf.coap.port);
+
+        if conf.coap.dtls_enable {
+            self.start_dtls(context).await?;
+        }
+        Ok(())
+    }
+
+    async fn start_dtls(&self, context: CoapContext) -> ResultMqttBrokerError {
+        let conf = broker_config();
+        let config = Config {
+            certificates: vec![load_dtls_certificate()?],
+            extended_master_secret: ExtendedMasterSecretType::Require,
+            ..Default::default()
+        };
+        let addr = format!("0.0.0.0:{}", conf.coap.dtls_port);
+        let listener = listen(addr.clone(), config).await.map_err(|e| {
+            MqttBrokerError::CommonError(format!(
+                "Failed to bind CoAP DTLS listener to {}: {}",
+                addr, e
+            ))
+        })?;
+
+        let mut stop_rx = self.stop_sx.subscribe();
+        tokio::spawn(Box::pin(async move {
+            loop {
+                select! {
+                    val = stop_rx.recv() => {
+                        if let Ok(true) = val {
+                            if let Err(e) = listener.close().await {
+                                debug!("Failed to close CoAP DTLS listener: {}", e);
+                            }
+                            break;
+                        }
+                    }
+                    val = listener.accept() => {
+                        match val {
+                            Ok((conn, addr)) => {
+                                tokio::spawn(Box::pin(read_dtls_connection(
+                                    context.clone(),
+                                    conn,
+                                    addr,
+                                )));
+                            }
+                            Err(e) => {
+                                // A failed handshake only affects that endpoint.
+                                debug!("CoAP DTLS handshake failed: {}", e);
+                            }
+                        }
+                    }
+                }
+            }
+        }));
+        info!(
+            "CoAP gateway DTLS listener started successfully, port:{}",
+            conf.coap.dtls_port
+        );
+        Ok(())
+    }
+}
+
+/// The broker TLS certificate and key, converted for the DTLS stack.
+fn load_dtls_certificate() -> Result<Certificate, MqttBrokerError> {
+    let (cert_path, key_path) = cert_paths(broker_config());
+    let certificate = load_certs(&cert_path).map_err(|e| {
+        MqttBrokerError::CommonError(format!(
+            "Failed to load CoAP DTLS certificate {}: {}",
+            cert_path.display(),
+            e
+        ))
+    })?;
+    let key_pem = std::fs::read_to_string(&key_path).map_err(|e| {
+        MqttBrokerError::CommonError(format!(
+            "Failed to read CoAP DTLS private key {}: {}",
+            key_path.display(),
+            e
+        ))
+    })?;
+    let key_pair = rcgen::KeyPair::from_pem(&key_pem)
+        .map_err(|e| MqttBrokerError::CommonError(format!("Invalid DTLS private key: {}", e)))?;
+    let private_key = CryptoPrivateKey::from_key_pair(&key_pair)
+        .map_err(|e| MqttBrokerError::CommonError(format!("Invalid DTLS private key: {}", e)))?;
+    Ok(Certificate {
+        certificate,
+        private_key,
+    })
+}
+
+async fn read_dtls_connection(
+    context: CoapContext,
+    conn: Arc<dyn Conn + Send + Sync>,
+    addr: SocketAddr,
+) {
+    let transport = CoapTransport::Dtls(conn.clone());
+    let mut buf = vec![0u8; MAX_DATAGRAM_SIZE];
+    loop {
+        match conn.recv(&mut buf).await {
+            Ok(len) => handle_datagram(&context, &transport, addr, &buf[..len]).await,
+            Err(e) => {
+                debug!("CoAP DTLS connection {} closed: {}", addr, e);
+                break;
+            }
+        }
+    }
+    context.peers.remove(&(addr, true));
+}
+
+async fn handle_datagram(
+    context: &CoapContext,
+    transport: &CoapTransport,
+    addr: SocketAddr,
+    datagram: &[u8],
+) {
+    let message = match decode(datagram) {
+        Ok(message) => message,
+        Err(e) => {
+            debug!("Invalid CoAP message from {}: {}", addr, e);
+            return;
+        }
+    };
+
+    let key = (addr, transport.is_dtls());
+    if let Some(handle) = context.peers.get(&key) {
+        if handle.inbound.try_send(message).is_err() {
+            warn!("CoAP endpoint {} is not keeping up, message dropped", addr);
+        }
+        return;
+    }
+
+    match message.msg_type {
+        // Nothing is outstanding for an unknown endpoint.
+        CoapType::Acknowledgement | CoapType::Reset => {}
+        // CoAP ping, an empty confirmable message, is answered with RST.
+        _ if message.code == CoapCode::EMPTY => {
+            if message.msg_type == CoapType::Confirmable {
+                transport
+                    .send(
+                        addr,
+                        &CoapMessage::empty(CoapType::Reset, message.message_id),
+                    )
+                    .await;
+            }
+        }
+        _ if message.code.is_request() => {
+            start_peer(context, transport, addr, message);
+        }
+        _ => {
+            debug!(
+                "Unexpected CoAP {} from unknown endpoint {}",
+                coap_message_to_string(&message),
+                addr
+            );
+        }
+    }
+}
+
+fn start_peer(
+    context: &CoapContext,
+    transport: &CoapTransport,
+    addr: SocketAddr,
+    first: CoapMessage,
+) {
+    let mut network = NetworkConnection::new(NetworkConnectionType::CoAP, addr, None);
+    network.set_protocol(RobustMQProtocol::MQTT4);
+    let connect_id = context.connection_manager.add_connection(network.clone());
+
+    let (outbound_sx, outbound_rx) = mpsc::channel(PEER_CHANNEL_SIZE);
+    context
+        .connection_manager
+        .add_coap_write(connect_id, outbound_sx);
+
+    let (inbound_sx, inbound_rx) = mpsc::channel(PEER_CHANNEL_SIZE);
+    context.peers.insert(
+        (addr, transport.is_dtls()),
+        CoapPeerHandle {
+            connect_id,
+            inbound: inbound_sx,
+        },
+    );
+
+    let peer = CoapPeer::new(context.clone(), transport.clone(), addr, network);
+    tokio::spawn(Box::pin(peer.run(first, inbound_rx, outbound_rx)));
+}
@@ -0,0 +1,26 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! CoAP gateway.
+//!
+//! CoAP endpoints on UDP, or DTLS, publish and subscribe through the MQTT
+//! pipeline: each endpoint is mapped to an MQTT 3.1.1 connection of this
+//! broker. PUT and POST to `/{uri_path_prefix}/{topic}` publish the payload,
+//! and a GET with Observe subscribes, every message on the topic becoming a
+//! notification. Payloads larger than a block are transferred block-wise.
+
+pub mod block;
+pub mod gateway;
+pub mod peer;
+pub mod topic;
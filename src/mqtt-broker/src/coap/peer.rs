@@ -0,0 +1,637 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::block::{block2_slice, Block1Assembler, Block1Result};
+use super::gateway::{CoapContext, CoapTransport};
+use super::topic::{query_value, uri_path_to_topic};
+use crate::core::sub_wildcards::is_wildcards;
+use crate::subscribe::common::is_match_sub_and_topic;
+use bytes::Bytes;
+use common_base::tools::now_second;
+use common_config::broker::broker_config;
+use metadata_struct::connection::NetworkConnection;
+use protocol::coap::packet::{
+    BlockOption, CoapCode, CoapMessage, CoapType, OBSERVE_DEREGISTER, OBSERVE_REGISTER,
+    OPTION_BLOCK1, OPTION_BLOCK2, OPTION_OBSERVE, OPTION_SIZE1, OPTION_SIZE2,
+};
+use protocol::mqtt::common::{
+    Connect, ConnectReturnCode, Disconnect, Filter, Login, MqttPacket, PingReq, PubAck,
+    PubAckReason, PubComp, PubRec, PubRecReason, PubRel, Publish, QoS, Subscribe,
+    SubscribeReasonCode, Unsubscribe,
+};
+use protocol::robust::{RobustMQPacket, RobustMQPacketWrapper, RobustMQProtocol};
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::select;
+use tokio::sync::mpsc;
+use tracing::debug;
+
+const PEER_TICK_INTERVAL: Duration = Duration::from_secs(5);
+/// Abandoned block-wise transfers are dropped after this long.
+const BLOCK_TRANSFER_TIMEOUT_SEC: u64 = 60;
+/// EXCHANGE_LIFETIME of RFC 7252: how long a retransmitted request may still
+/// arrive and must get the same response.
+const EXCHANGE_LIFETIME_SEC: u64 = 247;
+const MAX_CACHED_RESPONSES: usize = 64;
+/// Observe sequence numbers are 24 bit.
+const OBSERVE_SEQ_MASK: u32 = 0xFF_FFFF;
+
+pub struct CoapPeerHandle {
+    pub connect_id: u64,
+    pub inbound: mpsc::Sender<CoapMessage>,
+}
+
+struct Observer {
+    token: Vec<u8>,
+    seq: u32,
+    // message id of the last notification, answered with RST to cancel
+    last_message_id: Option<u16>,
+}
+
+/// Responses to recent requests, so that a retransmitted request is answered
+/// again instead of being handled twice.
+#[derive(Default)]
+struct ResponseCache {
+    // (message id, time, response)
+    responses: VecDeque<(u16, u64, CoapMessage)>,
+}
+
+impl ResponseCache {
+    fn get(&self, message_id: u16) -> Option<&CoapMessage> {
+        self.responses
+            .iter()
+            .find(|(id, _, _)| *id == message_id)
+            .map(|(_, _, response)| response)
+    }
+
+    fn insert(&mut self, message_id: u16, now: u64, response: CoapMessage) {
+        if self.responses.len() >= MAX_CACHED_RESPONSES {
+            self.responses.pop_front();
+        }
+        self.responses.push_back((message_id, now, response));
+    }
+
+    fn expire(&mut self, now: u64) {
+        while self
+            .responses
+            .front()
+            .is_some_and(|(_, at, _)| now.saturating_sub(*at) >= EXCHANGE_LIFETIME_SEC)
+        {
+            self.responses.pop_front();
+        }
+    }
+}
+
+/// One CoAP endpoint, mapped to an MQTT 3.1.1 connection of this broker.
+/// Requests are translated and handled by the MQTT command pipeline, and
+/// publishes the broker sends to the connection become notifications of the
+/// matching observations.
+pub struct CoapPeer {
+    context: CoapContext,
+    transport: CoapTransport,
+    addr: SocketAddr,
+    network: NetworkConnection,
+    client_id: String,
+    // (topic filter, Observer)
+    observers: HashMap<String, Observer>,
+    block1: Block1Assembler,
+    // (topic filter, last notification payload), for Block2 follow-up requests
+    block2: HashMap<String, Bytes>,
+    responses: ResponseCache,
+    last_message_id: u16,
+    last_pkid: u16,
+    last_ping: u64,
+}
+
+impl CoapPeer {
+    pub fn new(
+        context: CoapContext,
+        transport: CoapTransport,
+        addr: SocketAddr,
+        network: NetworkConnection,
+    ) -> Self {
+        let last_message_id = network.connection_id as u16;
+        CoapPeer {
+            context,
+            transport,
+            addr,
+            network,
+            client_id: String::new(),
+            observers: HashMap::new(),
+            block1: Block1Assembler::default(),
+            block2: HashMap::new(),
+            responses: ResponseCache::default(),
+            last_message_id,
+            last_pkid: 0,
+            last_ping: now_second(),
+        }
+    }
+
+    pub async fn run(
+        mut self,
+        first: CoapMessage,
+        mut inbound: mpsc::Receiver<CoapMessage>,
+        mut outbound: mpsc::Receiver<RobustMQPacketWrapper>,
+    ) {
+        if self.connect(&first).await {
+            self.handle_message(first).await;
+            let mut tick = tokio::time::interval(PEER_TICK_INTERVAL);
+            loop {
+                select! {
+                    val = inbound.recv() => {
+                        let Some(message) = val else {
+                            // The DTLS connection closed, or the gateway stopped.
+                            self.apply(MqttPacket::Disconnect(Disconnect { reason_code: None }, None))
+                                .await;
+                            break;
+                        };
+                        self.handle_message(message).await;
+                    }
+                    val = outbound.recv() => {
+                        let Some(wrapper) = val else {
+                            // The broker closed the MQTT connection.
+                            self.end_observations().await;
+                            break;
+                        };
+                        if let Some(packet) = wrapper.packet.get_mqtt_packet() {
+                            if !self.handle_broker_packet(packet).await {
+                                break;
+                            }
+                        }
+                    }
+                    _ = tick.tick() => {
+                        self.on_tick().await;
+                    }
+                }
+            }
+        }
+
+        let connect_id = self.network.connection_id;
+        self.context
+            .peers
+            .retain(|_, handle| handle.connect_id != connect_id);
+        self.context
+            .connection_manager
+            .close_connect(connect_id)
+            .await;
+    }
+
+    /// Connect the MQTT connection of the endpoint. The client id and
+    /// credentials come from the `c`, `u` and `p` query parameters of the first
+    /// request, falling back to the endpoint address and the configured login.
+    async fn connect(&mut self, first: &CoapMessage) -> bool {
+        let conf = broker_config();
+        let query = first.uri_query();
+        self.client_id = query_value(&query, "c")
+            .map(|c| c.to_string())
+            .unwrap_or_else(|| format!("coap-{}", self.addr));
+        let (username, password) = match query_value(&query, "u") {
+            Some(username) => (
+                username.to_string(),
+                query_value(&query, "p").unwrap_or_default().to_string(),
+            ),
+            None => (conf.coap.username.clone(), conf.coap.password.clone()),
+        };
+        let login = (!username.is_empty()).then_some(Login { username, password });
+        let packet = MqttPacket::Connect(
+            RobustMQProtocol::MQTT4.to_u8(),
+            Connect {
+                keep_alive: conf.coap.idle_timeout_sec.min(u16::MAX as u64) as u16,
+                client_id: self.client_id.clone(),
+                clean_session: true,
+            },
+            None,
+            None,
+            None,
+            login,
+        );
+
+        match self.apply(packet).await {
+            Some(MqttPacket::ConnAck(ack, _)) if ack.code == ConnectReturnCode::Success => true,
+            resp => {
+                debug!(
+                    "CoAP endpoint {} ({}) was refused by the broker: {:?}",
+                    self.addr, self.client_id, resp
+                );
+                let response = self.response(first, CoapCode::UNAUTHORIZED);
+                self.send(&response).await;
+                false
+            }
+        }
+    }
+
+    async fn handle_message(&mut self, message: CoapMessage) {
+        match message.msg_type {
+            CoapType::Reset => {
+                // RST to a notification cancels the observation, RFC 7641.
+                let filter = self
+                    .observers
+                    .iter()
+                    .find(|(_, observer)| observer.last_message_id == Some(message.message_id))
+                    .map(|(filter, _)| filter.clone());
+                if let Some(filter) = filter {
+                    self.cancel_observation(&filter).await;
+                }
+                return;
+            }
+            // Notifications are non-confirmable, nothing waits for an ACK.
+            CoapType::Acknowledgement => return,
+            _ => {}
+        }
+
+        if message.code == CoapCode::EMPTY {
+            if message.msg_type == CoapType::Confirmable {
+                self.send(&CoapMessage::empty(CoapType::Reset, message.message_id))
+                    .await;
+            }
+            return;
+        }
+        if !message.code.is_request() {
+            return;
+        }
+
+        if let Some(response) = self.responses.get(message.message_id) {
+            let response = response.clone();
+            self.send(&response).await;
+            return;
+        }
+        let response = self.handle_request(&message).await;
+        self.responses
+            .insert(message.message_id, now_second(), response.clone());
+        self.send(&response).await;
+    }
+
+    async fn handle_request(&mut self, request: &CoapMessage) -> CoapMessage {
+        let conf = broker_config();
+        let Some(topic) = uri_path_to_topic(
+            &conf.coap.uri_path_prefix,
+            &conf.coap.topic_prefix,
+            &request.uri_path(),
+        ) else {
+            return self.response(request, CoapCode::NOT_FOUND);
+        };
+
+        match request.code {
+            CoapCode::PUT | CoapCode::POST => self.publish(request, topic).await,
+            CoapCode::GET => match request.observe() {
+                Some(OBSERVE_REGISTER) => self.observe(request, topic).await,
+                Some(OBSERVE_DEREGISTER) => {
+                    self.cancel_observation(&topic).await;
+                    self.response(request, CoapCode::CONTENT)
+                }
+                _ => self.next_block(request, &topic),
+            },
+            _ => self.response(request, CoapCode::METHOD_NOT_ALLOWED),
+        }
+    }
+
+    async fn publish(&mut self, request: &CoapMessage, topic: String) -> CoapMessage {
+        if is_wildcards(&topic) {
+            return self.response(request, CoapCode::BAD_REQUEST);
+        }
+
+        let max_payload_size = broker_config().coap.max_payload_size;
+        let payload = match request.block1() {
+            Some(block) => {
+                match self.block1.push(
+                    &topic,
+                    block,
+                    &request.payload,
+                    max_payload_size,
+                    now_second(),
+                ) {
+                    Block1Result::Continue => {
+                        let mut response = self.response(request, CoapCode::CONTINUE);
+                        response.add_uint_option(OPTION_BLOCK1, block.to_uint());
+                        return response;
+                    }
+                    Block1Result::Complete(payload) => payload,
+                    Block1Result::Incomplete => {
+                        return self.response(request, CoapCode::REQUEST_ENTITY_INCOMPLETE);
+                    }
+                    Block1Result::TooLarge => {
+                        let mut response =
+                            self.response(request, CoapCode::REQUEST_ENTITY_TOO_LARGE);
+                        response.add_uint_option(OPTION_SIZE1, max_payload_size as u32);
+                        return response;
+                    }
+                }
+            }
+            None if request.payload.len() > max_payload_size => {
+                let mut response = self.response(request, CoapCode::REQUEST_ENTITY_TOO_LARGE);
+                response.add_uint_option(OPTION_SIZE1, max_payload_size as u32);
+                return response;
+            }
+            None => request.payload.clone(),
+        };
+
+        let query = request.uri_query();
+        let qos = parse_qos(query_value(&query, "qos"));
+        let retain = query_value(&query, "retain") == Some("true");
+        let pkid = if qos == QoS::AtMostOnce {
+            0
+        } else {
+            self.next_pkid()
+        };
+        let packet = MqttPacket::Publish(
+            Publish {
+                qos,
+                p_kid: pkid,
+                retain,
+                topic: topic.into(),
+                payload,
+                ..Default::default()
+            },
+            None,
+        );
+
+        let accepted = match self.apply(packet).await {
+            Some(MqttPacket::PubAck(ack, _)) => matches!(
+                ack.reason,
+                None | Some(PubAckReason::Success) | Some(PubAckReason::NoMatchingSubscribers)
+            ),
+            Some(MqttPacket::PubRec(rec, _)) => {
+                let accepted = matches!(
+                    rec.reason,
+                    None | Some(PubRecReason::Success) | Some(PubRecReason::NoMatchingSubscribers)
+                );
+                // CoAP has no second phase, complete the QoS 2 flow at once.
+                self.apply(MqttPacket::PubRel(
+                    PubRel {
+                        pkid: rec.pkid,
+                        reason: None,
+                    },
+                    None,
+                ))
+                .await;
+                accepted
+            }
+            _ => qos == QoS::AtMostOnce,
+        };
+
+        let code = if accepted {
+            CoapCode::CHANGED
+        } else {
+            CoapCode::FORBIDDEN
+        };
+        let mut response = self.response(request, code);
+        if let Some(block) = request.block1() {
+            response.add_uint_option(OPTION_BLOCK1, block.to_uint());
+        }
+        response
+    }
+
+    async fn observe(&mut self, request: &CoapMessage, filter: String) -> CoapMessage {
+        let query = request.uri_query();
+        let qos = parse_qos(query_value(&query, "qos"));
+        let packet = MqttPacket::Subscribe(
+            Subscribe {
+                packet_identifier: self.next_pkid(),
+                filters: vec![Filter {
+                    path: filter.clone(),
+                    qos,
+                    ..Default::default()
+                }],
+            },
+            None,
+        );
+
+        let granted = match self.apply(packet).await {
+            Some(MqttPacket::SubAck(ack, _)) => matches!(
+                ack.return_codes.first(),
+                Some(SubscribeReasonCode::QoS0)
+                    | Some(SubscribeReasonCode::QoS1)
+                    | Some(SubscribeReasonCode::QoS2)
+                    | Some(SubscribeReasonCode::Success(_))
+            ),
+            _ => false,
+        };
+        if !granted {
+            return self.response(request, CoapCode::FORBIDDEN);
+        }
+
+        // Registering again with the same URI replaces the observation.
+        self.observers.insert(
+            filter,
+            Observer {
+                token: request.token.clone(),
+                seq: 0,
+                last_message_id: None,
+            },
+        );
+        let mut response = self.response(request, CoapCode::CONTENT);
+        response.add_uint_option(OPTION_OBSERVE, 0);
+        response
+    }
+
+    async fn cancel_observation(&mut self, filter: &str) {
+        if self.observers.remove(filter).is_none() {
+            return;
+        }
+        self.block2.remove(filter);
+        let pkid = self.next_pkid();
+        self.apply(MqttPacket::Unsubscribe(
+            Unsubscribe {
+                pkid,
+                filters: vec![filter.to_string()],
+            },
+            None,
+        ))
+        .await;
+    }
+
+    /// GET for the next block of a notification too large for one message.
+    fn next_block(&mut self, request: &CoapMessage, filter: &str) -> CoapMessage {
+        let Some(block) = request.block2() else {
+            return self.response(request, CoapCode::METHOD_NOT_ALLOWED);
+        };
+        let slice = self
+            .block2
+            .get(filter)
+            .map(|payload| block2_slice(payload, block, max_szx()));
+        let (block, data) = match slice {
+            Some(Some(slice)) => slice,
+            Some(None) => return self.response(request, CoapCode::BAD_OPTION),
+            None => return self.response(request, CoapCode::NOT_FOUND),
+        };
+        let mut response = self.response(request, CoapCode::CONTENT);
+        response.add_uint_option(OPTION_BLOCK2, block.to_uint());
+        response.payload = data;
+        response
+    }
+
+    /// Returns false when the broker closed the connection.
+    async fn handle_broker_packet(&mut self, packet: MqttPacket) -> bool {
+        match packet {
+            MqttPacket::Publish(publish, _) => {
+                match publish.qos {
+                    QoS::AtMostOnce => {}
+                    QoS::AtLeastOnce => {
+                        self.apply(MqttPacket::PubAck(
+                            PubAck {
+                                pkid: publish.p_kid,
+                                reason: None,
+                            },
+                            None,
+                        ))
+                        .await;
+                    }
+                    QoS::ExactlyOnce => {
+                        self.apply(MqttPacket::PubRec(
+                            PubRec {
+                                pkid: publish.p_kid,
+                                reason: None,
+                            },
+                            None,
+                        ))
+                        .await;
+                    }
+                }
+                self.notify(publish).await;
+            }
+            MqttPacket::PubRel(rel, _) => {
+                self.apply(MqttPacket::PubComp(
+                    PubComp {
+                        pkid: rel.pkid,
+                        reason: None,
+                    },
+                    None,
+                ))
+                .await;
+            }
+            MqttPacket::Disconnect(_, _) => {
+                self.end_observations().await;
+                return false;
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// Send `publish` as a notification of every observation it matches.
+    async fn notify(&mut self, publish: Publish) {
+        let topic = String::from_utf8_lossy(&publish.topic).to_string();
+        let block_size = 1usize << (max_szx() + 4);
+        let filters: Vec<String> = self
+            .observers
+            .keys()
+            .filter(|filter| is_match_sub_and_topic(filter, &topic).is_ok())
+            .cloned()
+            .collect();
+
+        for filter in filters {
+            let message_id = self.next_message_id();
+            let Some(observer) = self.observers.get_mut(&filter) else {
+                continue;
+            };
+            observer.seq = (observer.seq + 1) & OBSERVE_SEQ_MASK;
+            observer.last_message_id = Some(message_id);
+
+            let mut notification = CoapMessage::new(
+                CoapType::NonConfirmable,
+                CoapCode::CONTENT,
+                message_id,
+                observer.token.clone(),
+            );
+            notification.add_uint_option(OPTION_OBSERVE, observer.seq);
+            if publish.payload.len() > block_size {
+                let first = BlockOption {
+                    num: 0,
+                    more: true,
+                    szx: max_szx(),
+                };
+                if let Some((block, data)) = block2_slice(&publish.payload, first, max_szx()) {
+                    notification.add_uint_option(OPTION_BLOCK2, block.to_uint());
+                    notification.add_uint_option(OPTION_SIZE2, publish.payload.len() as u32);
+                    notification.payload = data;
+                }
+                self.block2.insert(filter, publish.payload.clone());
+            } else {
+                notification.payload = publish.payload.clone();
+            }
+            self.send(&notification).await;
+        }
+    }
+
+    /// A non-2.xx notification ends an observation on the client, RFC 7641.
+    async fn end_observations(&mut self) {
+        let observers: Vec<Observer> = self.observers.drain().map(|(_, o)| o).collect();
+        for observer in observers {
+            let message_id = self.next_message_id();
+            let notification = CoapMessage::new(
+                CoapType::NonConfirmable,
+                CoapCode::SERVICE_UNAVAILABLE,
+                message_id,
+                observer.token,
+            );
+            self.send(&notification).await;
+        }
+    }
+
+    async fn on_tick(&mut self) {
+        let now = now_second();
+        self.block1.expire(now, BLOCK_TRANSFER_TIMEOUT_SEC);
+        self.responses.expire(now);
+
+        // An observing endpoint sends nothing while it waits for
+        // notifications, so keep its MQTT connection alive. Without
+        // observations the connection expires once the endpoint is idle.
+        let keep_alive = broker_config().coap.idle_timeout_sec;
+        if !self.observers.is_empty() && now.saturating_sub(self.last_ping) >= keep_alive / 2 {
+            self.last_ping = now;
+            self.apply(MqttPacket::PingReq(PingReq)).await;
+        }
+    }
+
+    fn response(&mut self, request: &CoapMessage, code: CoapCode) -> CoapMessage {
+        let message_id = self.next_message_id();
+        CoapMessage::response_to(request, code, message_id)
+    }
+
+    fn next_message_id(&mut self) -> u16 {
+        self.last_message_id = self.last_message_id.wrapping_add(1);
+        self.last_message_id
+    }
+
+    fn next_pkid(&mut self) -> u16 {
+        self.last_pkid = self.last_pkid.wrapping_add(1).max(1);
+        self.last_pkid
+    }
+
+    async fn apply(&self, packet: MqttPacket) -> Option<MqttPacket> {
+        let resp = self
+            .context
+            .command
+            .apply(&self.network, &self.addr, &RobustMQPacket::MQTT(packet))
+            .await?;
+        resp.packet.get_mqtt_packet()
+    }
+
+    async fn send(&self, message: &CoapMessage) {
+        self.transport.send(self.addr, message).await;
+    }
+}
+
+fn parse_qos(value: Option<&str>) -> QoS {
+    match value {
+        Some("1") => QoS::AtLeastOnce,
+        Some("2") => QoS::ExactlyOnce,
+        _ => QoS::AtMostOnce,
+    }
+}
+
+fn max_szx() -> u8 {
+    BlockOption::szx_for_size(broker_config().coap.block_size)
+}
@@ -0,0 +1,76 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Topic addressed by a request URI. The segments of `uri_path_prefix` must
+/// lead the path and are stripped; the rest is joined with `/` after
+/// `topic_prefix`. Returns None for a path outside the prefix.
+pub fn uri_path_to_topic(
+    uri_path_prefix: &str,
+    topic_prefix: &str,
+    uri_path: &[String],
+) -> Option<String> {
+    let prefix: Vec<&str> = uri_path_prefix
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    if uri_path.len() <= prefix.len()
+        || prefix
+            .iter()
+            .zip(uri_path.iter())
+            .any(|(expected, segment)| *expected != segment)
+    {
+        return None;
+    }
+    Some(format!(
+        "{}{}",
+        topic_prefix,
+        uri_path[prefix.len()..].join("/")
+    ))
+}
+
+/// Value of query parameter `key` of a request.
+pub fn query_value<'a>(query: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    query
+        .iter()
+        .find(|(name, _)| name == key)
+        .map(|(_, value)| value.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(segments: &[&str]) -> Vec<String> {
+        segments.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_uri_path_to_topic() {
+        assert_eq!(
+            uri_path_to_topic("ps", "", &path(&["ps", "sensors", "t1"])).as_deref(),
+            Some("sensors/t1")
+        );
+        assert_eq!(
+            uri_path_to_topic("/mqtt/ps/", "coap/", &path(&["mqtt", "ps", "a", "+"])).as_deref(),
+            Some("coap/a/+")
+        );
+        assert_eq!(
+            uri_path_to_topic("", "", &path(&["a", "b"])).as_deref(),
+            Some("a/b")
+        );
+        assert!(uri_path_to_topic("ps", "", &path(&["ps"])).is_none());
+        assert!(uri_path_to_topic("ps", "", &path(&["other", "a"])).is_none());
+        assert!(uri_path_to_topic("", "", &[]).is_none());
+    }
+}
@@ -12,8 +12,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::core::error::MqttBrokerError;
 use crate::core::flapping_detect::FlappingDetectCondition;
+use crate::core::message_replay::MessageReplayTasks;
+use crate::core::message_rule::{compile_message_rule, CompiledMessageRule};
+use crate::core::message_trace::MessageTraceLog;
+use crate::core::overload::OverloadController;
 use crate::core::pkid_manager::PkidManager;
+use crate::core::retain_cache::RetainMessageCache;
+use crate::core::slow_request::SlowRequestLog;
+use crate::core::tenant::TenantStorageUsage;
+use crate::core::topic_stats::TopicStats;
 use broker_core::cache::NodeCacheManager;
 use common_base::enum_type::time_unit_enum::TimeUnit;
 use common_base::tools::convert_seconds;
@@ -23,6 +32,7 @@
 use grpc_clients::pool::ClientPool;
 use metadata_struct::mqtt::auto_subscribe::MqttAutoSubscribeRule;
 use metadata_struct::mqtt::connection::MQTTConnection;
+use metadata_struct::mqtt::message_rule::MqttMessageRule;
 use metadata_struct::mqtt::session::MqttSession;
 use metadata_struct::mqtt::topic_rewrite_rule::MqttTopicRewriteRule;
 use protocol::mqtt::common::{MqttProtocol, PublishProperties};
@@ -62,6 +72,8 @@ pub struct ConnectionLiveTime {
 pub struct QosAckPacketInfo {
     pub sx: mpsc::Sender<QosAckPackageData>,
     pub create_time: u128,
+    // trace id of the pushed message, kept as the push→ack exemplar
+    pub trace_id: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -123,8 +135,32 @@ pub struct MQTTCacheManager {
     // All auto subscribe rule: outer key = tenant, inner key = topic
     pub auto_subscribe_rule: DashMap<String, DashMap<String, MqttAutoSubscribeRule>>,
 
+    // Compiled message (rule engine) rules: outer key = tenant, inner key = rule name
+    pub message_rule: DashMap<String, DashMap<String, Arc<CompiledMessageRule>>>,
+
     // Topic is Validator
     pub topic_is_validator: DashMap<String, bool>,
+
+    // (tenant, unexpired bytes written to storage through this node)
+    pub tenant_storage_usage: DashMap<String, TenantStorageUsage>,
+
+    // Inbound packets that took longer than the slow request threshold
+    pub slow_request_log: Arc<SlowRequestLog>,
+
+    // Message replays started on this node
+    pub message_replay: Arc<MessageReplayTasks>,
+
+    // Stages of messages selected by trace rules, recorded on this node
+    pub message_trace: Arc<MessageTraceLog>,
+
+    // Per-topic message statistics of this node
+    pub topic_stats: Arc<TopicStats>,
+
+    // Recently used retained payloads, validated against the meta-service index
+    pub retain_cache: Arc<RetainMessageCache>,
+
+    // Overload protection level of this node
+    pub overload: Arc<OverloadController>,
 }
 
 impl MQTTCacheManager {
@@ -140,10 +176,18 @@ pub fn new(client_pool: Arc<ClientPool>, broker_cache: Arc<NodeCacheManager>) ->
             pkid_manager: PkidManager::new(),
             topic_rewrite_rule: DashMap::with_capacity(8),
             auto_subscribe_rule: DashMap::with_capacity(8),
+            message_rule: DashMap::with_capacity(8),
             topic_is_validator: DashMap::with_capacity(8),
+            tenant_storage_usage: DashMap::with_capacity(8),
             re_calc_topic_rewrite: Arc::new(RwLock::new(false)),
             topic_rewrite_new_name: DashMap::with_capacity(8),
             flapping_detect_map: DashMap::new(),
+            slow_request_log: Arc::new(SlowRequestLog::default()),
+            message_replay: Arc::new(MessageReplayTasks::default()),
+            message_trace: Arc::new(MessageTraceLog::default()),
+            topic_stats: Arc::new(TopicStats::default()),
+            retain_cache: Arc::new(RetainMessageCache::default()),
+            overload: Arc::new(OverloadController::default()),
         }
     }
 
@@ -244,6 +288,20 @@ pub fn get_connection_count_by_tenant(&self, tenant: &str) -> usize {
             .unwrap_or(0)
     }
 
+    pub fn add_tenant_storage_bytes(&self, tenant: &str, bytes: u64, expire_at: u64) {
+        self.tenant_storage_usage
+            .entry(tenant.to_string())
+            .or_default()
+            .record(bytes, expire_at);
+    }
+
+    pub fn get_tenant_storage_bytes(&self, tenant: &str, now: u64) -> u64 {
+        self.tenant_storage_usage
+            .get_mut(tenant)
+            .map(|mut usage| usage.used(now))
+            .unwrap_or(0)
+    }
+
     // topic rewrite rule
     pub fn add_topic_rewrite_rule(&self, topic_rewrite_rule: MqttTopicRewriteRule) {
         self.topic_rewrite_rule
@@ -292,6 +350,12 @@ pub fn get_new_rewrite_name(&self, tenant: &str, topic_name: &str) -> Option<Str
             .and_then(|inner| inner.get(topic_name).map(|v| v.clone()))
     }
 
+    pub fn has_rewrite_name(&self, tenant: &str) -> bool {
+        self.topic_rewrite_new_name
+            .get(tenant)
+            .is_some_and(|inner| !inner.is_empty())
+    }
+
     pub fn clear_rewrite_new_name(&self) {
         self.topic_rewrite_new_name.clear();
     }
@@ -352,6 +416,13 @@ pub fn report_heartbeat(&self, client_id: String, live_time: ConnectionLiveTime)
         self.heartbeat_data.insert(client_id, live_time);
     }
 
+    /// Any control packet from the client counts as activity for keep alive.
+    pub fn refresh_heartbeat(&self, client_id: &str) {
+        if let Some(mut live_time) = self.heartbeat_data.get_mut(client_id) {
+            live_time.heartbeat = now_second();
+        }
+    }
+
     pub fn get_heartbeat(&self, client_id: &str) -> Option<ConnectionLiveTime> {
         self.heartbeat_data.get(client_id).map(|data| data.clone())
     }
@@ -419,6 +490,22 @@ pub fn delete_auto_subscribe_rule(&self, tenant: &str, name: &str) {
             tenant_map.remove(name);
         }
     }
+
+    // message rule
+    pub fn add_message_rule(&self, rule: MqttMessageRule) -> Result<(), MqttBrokerError> {
+        let compiled = compile_message_rule(rule)?;
+        self.message_rule
+            .entry(compiled.rule.tenant.clone())
+            .or_default()
+            .insert(compiled.rule.name.clone(), Arc::new(compiled));
+        Ok(())
+    }
+
+    pub fn delete_message_rule(&self, tenant: &str, name: &str) {
+        if let Some(tenant_map) = self.message_rule.get(tenant) {
+            tenant_map.remove(name);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -511,10 +598,11 @@ async fn connection_info_operations() {
     async fn heartbeat_data_operations() {
         let cache_manager = test_build_mqtt_cache_manager().await;
         let client_id = "test_client_heartbeat";
+        let stale = now_second() - 10;
         let live_time = ConnectionLiveTime {
             protocol: MqttProtocol::Mqtt3,
             keep_live: 60,
-            heartbeat: now_second(),
+            heartbeat: stale,
         };
 
         cache_manager.report_heartbeat(client_id.to_string(), live_time);
@@ -522,6 +610,9 @@ async fn heartbeat_data_operations() {
         assert!(heartbeat.is_some());
         assert_eq!(heartbeat.unwrap().keep_live, 60);
 
+        cache_manager.refresh_heartbeat(client_id);
+        assert!(cache_manager.get_heartbeat(client_id).unwrap().heartbeat > stale);
+
         cache_manager.remove_heartbeat(client_id);
         assert!(cache_manager.get_heartbeat(client_id).is_none());
     }
@@ -626,4 +717,35 @@ async fn topic_alias_operations() {
             Some(topic_name.to_string())
         );
     }
+
+    #[tokio::test]
+    async fn message_rule_operations() {
+        use metadata_struct::mqtt::message_rule::MqttMessageRuleAction;
+
+        let cache_manager = test_build_mqtt_cache_manager().await;
+        let mut rule = MqttMessageRule {
+            tenant: "tenant-1".to_string(),
+            name: "rule-1".to_string(),
+            desc: String::new(),
+            sql: "SELECT * FROM \"sensors/#\"".to_string(),
+            actions: vec![MqttMessageRuleAction::Drop],
+            enable: true,
+            create_time: 0,
+        };
+
+        cache_manager.add_message_rule(rule.clone()).unwrap();
+        assert!(cache_manager
+            .message_rule
+            .get(&rule.tenant)
+            .is_some_and(|m| m.contains_key(&rule.name)));
+
+        rule.sql = "SELECT FROM".to_string();
+        assert!(cache_manager.add_message_rule(rule.clone()).is_err());
+
+        cache_manager.delete_message_rule(&rule.tenant, &rule.name);
+        assert!(cache_manager
+            .message_rule
+            .get(&rule.tenant)
+            .is_some_and(|m| m.is_empty()));
+    }
 }
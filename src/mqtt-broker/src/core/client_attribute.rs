@@ -0,0 +1,233 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Client attributes: key/value pairs such as a device model or firmware
+//! version attached to a client.
+//!
+//! They are stored in the session in meta-service, so they survive reconnects
+//! of a persistent session, and are copied onto the live connection for ACL
+//! placeholders, rule conditions and `$SYS` client events. Attributes are set
+//! from MQTT 5 CONNECT user properties or through the admin API.
+
+use super::cache::MQTTCacheManager;
+use super::error::MqttBrokerError;
+use crate::storage::session::SessionStorage;
+use common_config::broker::broker_config;
+use common_config::config::MqttClientAttributeConfig;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::session::MqttSession;
+use protocol::mqtt::common::ConnectProperties;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Attributes carried by CONNECT user properties. Properties with an empty
+/// name or a value over the length limit are skipped.
+pub fn connect_attributes(
+    config: &MqttClientAttributeConfig,
+    connect_properties: &Option<ConnectProperties>,
+) -> BTreeMap<String, String> {
+    let mut attributes = BTreeMap::new();
+    let Some(properties) = connect_properties else {
+        return attributes;
+    };
+    if config.user_property_prefix.is_empty() {
+        return attributes;
+    }
+
+    for (key, value) in properties.user_properties.iter() {
+        let Some(name) = key.strip_prefix(&config.user_property_prefix) else {
+            continue;
+        };
+        if name.is_empty() || value.len() > config.max_value_len {
+            continue;
+        }
+        if attributes.len() >= config.max_attributes && !attributes.contains_key(name) {
+            break;
+        }
+        attributes.insert(name.to_string(), value.clone());
+    }
+    attributes
+}
+
+/// Merge CONNECT attributes into those kept by a resumed session. CONNECT
+/// values win, and new names are only added while under the limit.
+pub fn merge_connect_attributes(
+    config: &MqttClientAttributeConfig,
+    attributes: &mut BTreeMap<String, String>,
+    connect: BTreeMap<String, String>,
+) {
+    for (key, value) in connect {
+        if attributes.len() >= config.max_attributes && !attributes.contains_key(&key) {
+            continue;
+        }
+        attributes.insert(key, value);
+    }
+}
+
+pub fn validate_client_attributes(
+    config: &MqttClientAttributeConfig,
+    attributes: &BTreeMap<String, String>,
+) -> Result<(), MqttBrokerError> {
+    if attributes.len() > config.max_attributes {
+        return Err(MqttBrokerError::CommonError(format!(
+            "A client can have at most {} attributes",
+            config.max_attributes
+        )));
+    }
+    for (key, value) in attributes.iter() {
+        if key.is_empty() {
+            return Err(MqttBrokerError::CommonError(
+                "Client attribute name cannot be empty".to_string(),
+            ));
+        }
+        if value.len() > config.max_value_len {
+            return Err(MqttBrokerError::CommonError(format!(
+                "Client attribute '{}' is longer than {} bytes",
+                key, config.max_value_len
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Remove `remove`, then set `set` on the client's stored session, returning
+/// the resulting attributes. Meta-service pushes the session to every broker,
+/// which refreshes the live connection wherever the client is connected.
+pub async fn update_client_attributes(
+    client_pool: &Arc<ClientPool>,
+    cache_manager: &Arc<MQTTCacheManager>,
+    tenant: &str,
+    client_id: &str,
+    set: BTreeMap<String, String>,
+    remove: &[String],
+) -> Result<BTreeMap<String, String>, MqttBrokerError> {
+    let session_storage = SessionStorage::new(client_pool.clone());
+    let Some(mut session) = session_storage
+        .get_session(tenant.to_string(), client_id.to_string())
+        .await?
+    else {
+        return Err(MqttBrokerError::SessionDoesNotExist);
+    };
+
+    for key in remove.iter() {
+        session.attributes.remove(key);
+    }
+    session.attributes.extend(set);
+    let config = cache_manager.node_cache.cluster_config.load();
+    validate_client_attributes(&config.mqtt_client_attribute, &session.attributes)?;
+
+    session_storage
+        .set_session(client_id.to_string(), &session)
+        .await?;
+    cache_manager.add_session(client_id, &session);
+    sync_connection_attributes(cache_manager, &session);
+    Ok(session.attributes)
+}
+
+/// Copy the session's attributes onto its connection when the client is
+/// connected to this broker.
+pub fn sync_connection_attributes(cache_manager: &Arc<MQTTCacheManager>, session: &MqttSession) {
+    if session.broker_id != Some(broker_config().broker_id) {
+        return;
+    }
+    let Some(connect_id) = session.connection_id else {
+        return;
+    };
+    if let Some(mut connection) = cache_manager.connection_info.get_mut(&connect_id) {
+        if connection.tenant == session.tenant && connection.client_id == session.client_id {
+            connection.attributes = session.attributes.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(max_attributes: usize) -> MqttClientAttributeConfig {
+        MqttClientAttributeConfig {
+            max_attributes,
+            max_value_len: 8,
+            ..Default::default()
+        }
+    }
+
+    fn properties(user_properties: &[(&str, &str)]) -> Option<ConnectProperties> {
+        Some(ConnectProperties {
+            user_properties: user_properties
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_connect_attributes() {
+        let attributes = connect_attributes(
+            &config(2),
+            &properties(&[
+                ("attr.model", "x1"),
+                ("trace", "ignored"),
+                ("attr.", "no-name"),
+                ("attr.firmware", "too-long-value"),
+                ("attr.region", "eu"),
+                ("attr.site", "over-limit"),
+            ]),
+        );
+        assert_eq!(attributes.len(), 2);
+        assert_eq!(attributes["model"], "x1");
+        assert_eq!(attributes["region"], "eu");
+
+        let disabled = MqttClientAttributeConfig {
+            user_property_prefix: String::new(),
+            ..config(2)
+        };
+        assert!(connect_attributes(&disabled, &properties(&[("attr.model", "x1")])).is_empty());
+        assert!(connect_attributes(&config(2), &None).is_empty());
+    }
+
+    #[test]
+    fn test_merge_connect_attributes() {
+        let mut attributes = BTreeMap::new();
+        attributes.insert("model".to_string(), "x1".to_string());
+        attributes.insert("site".to_string(), "s1".to_string());
+
+        let mut connect = BTreeMap::new();
+        connect.insert("model".to_string(), "x2".to_string());
+        connect.insert("region".to_string(), "eu".to_string());
+        merge_connect_attributes(&config(2), &mut attributes, connect);
+
+        assert_eq!(attributes.len(), 2);
+        assert_eq!(attributes["model"], "x2");
+        assert!(!attributes.contains_key("region"));
+    }
+
+    #[test]
+    fn test_validate_client_attributes() {
+        let mut attributes = BTreeMap::new();
+        attributes.insert("model".to_string(), "x1".to_string());
+        assert!(validate_client_attributes(&config(1), &attributes).is_ok());
+
+        attributes.insert("firmware".to_string(), "1.0".to_string());
+        assert!(validate_client_attributes(&config(1), &attributes).is_err());
+
+        attributes.insert("firmware".to_string(), "too-long-value".to_string());
+        assert!(validate_client_attributes(&config(4), &attributes).is_err());
+
+        let mut empty_name = BTreeMap::new();
+        empty_name.insert(String::new(), "x".to_string());
+        assert!(validate_client_attributes(&config(4), &empty_name).is_err());
+    }
+}
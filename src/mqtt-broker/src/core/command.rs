@@ -16,6 +16,11 @@
 use crate::core::connection::{build_server_disconnect_conn_context, disconnect_connection};
 use crate::core::error::MqttBrokerError;
 use crate::core::event::EventReportManager;
+use crate::core::peer_cert::apply_peer_cert_identity;
+use crate::core::slow_request::{
+    build_slow_request_data, mark_phase, trace_slow_request, SlowRequestTimer,
+    SLOW_REQUEST_PHASE_CONNECTION, SLOW_REQUEST_PHASE_FINISH, SLOW_REQUEST_PHASE_HANDLE,
+};
 use crate::mqtt::connect::build_connect_ack_fail_packet;
 use crate::mqtt::disconnect::build_distinct_packet;
 use crate::mqtt::{MqttService, MqttServiceConnectContext, MqttServiceContext};
@@ -25,12 +30,14 @@
 use async_trait::async_trait;
 use broker_core::cache::NodeCacheManager;
 use common_base::tools::now_millis;
+use common_config::broker::broker_config;
 use common_metrics::mqtt::event::{
     record_mqtt_connection_failed, record_mqtt_connection_success, record_mqtt_subscribe_failed,
     record_mqtt_subscribe_success, record_mqtt_unsubscribe_success,
 };
 use common_metrics::mqtt::time::record_packet_process_duration;
 use common_security::manager::SecurityManager;
+use connector::manager::ConnectorManager;
 use delay_message::manager::DelayMessageManager;
 use grpc_clients::pool::ClientPool;
 use metadata_struct::connection::NetworkConnection;
@@ -54,7 +61,7 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
 use storage_adapter::driver::StorageDriverManager;
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
 
 // S: message storage adapter
 #[derive(Clone)]
@@ -87,6 +94,7 @@ pub struct CommandContext {
     pub global_limit_manager: Arc<GlobalRateLimiterManager>,
     pub node_call: Arc<NodeCallManager>,
     pub event_manager: Arc<EventReportManager>,
+    pub connector_manager: Arc<ConnectorManager>,
     pub stop_sx: tokio::sync::broadcast::Sender<bool>,
 }
 
@@ -97,6 +105,20 @@ async fn apply(
         tcp_connection: &NetworkConnection,
         addr: &SocketAddr,
         robust_packet: &RobustMQPacket,
+    ) -> Option<ResponsePackage> {
+        let (resp_package, timer) =
+            trace_slow_request(self.process_packet(tcp_connection, addr, robust_packet)).await;
+        self.record_slow_request(tcp_connection, robust_packet, &timer);
+        resp_package
+    }
+}
+
+impl MQTTHandlerCommand {
+    async fn process_packet(
+        &self,
+        tcp_connection: &NetworkConnection,
+        addr: &SocketAddr,
+        robust_packet: &RobustMQPacket,
     ) -> Option<ResponsePackage> {
         let start = now_millis();
         let packet = robust_packet.get_mqtt_packet().unwrap();
@@ -140,6 +162,12 @@ async fn apply(
             ));
         }
 
+        if !is_connect_pkg {
+            self.cache_manager.refresh_heartbeat(&connection.client_id);
+        }
+
+        mark_phase(SLOW_REQUEST_PHASE_CONNECTION);
+
         let resp_package = match packet.clone() {
             MqttPacket::Connect(
                 protocol_version,
@@ -234,7 +262,7 @@ async fn apply(
             }
         };
 
-        // resp_package is ready as-is; no per-response timing fields needed
+        mark_phase(SLOW_REQUEST_PHASE_HANDLE);
 
         if let Err(e) = self
             .try_process_distinct_packet(tcp_connection, &resp_package)
@@ -254,11 +282,50 @@ async fn apply(
             &mqtt_packet_to_string(&packet),
             (now_millis() - start) as f64,
         );
+        mark_phase(SLOW_REQUEST_PHASE_FINISH);
         resp_package
     }
-}
 
-impl MQTTHandlerCommand {
+    fn record_slow_request(
+        &self,
+        tcp_connection: &NetworkConnection,
+        robust_packet: &RobustMQPacket,
+        timer: &SlowRequestTimer,
+    ) {
+        // Runs for every packet, so read the config without cloning it.
+        let cluster_config = self.cache_manager.node_cache.cluster_config.load();
+        let config = &cluster_config.mqtt_slow_request;
+        if !config.enable || timer.elapsed_ms() < config.threshold_ms {
+            return;
+        }
+        let Some(packet) = robust_packet.get_mqtt_packet() else {
+            return;
+        };
+
+        let (tenant, client_id) = self
+            .cache_manager
+            .get_connection(tcp_connection.connection_id)
+            .map(|connection| (connection.tenant, connection.client_id))
+            .unwrap_or_default();
+        let data = build_slow_request_data(
+            &tenant,
+            &client_id,
+            tcp_connection.connection_id,
+            &packet,
+            timer,
+        );
+        warn!(
+            connect_id = data.connect_id,
+            client_id = %data.client_id,
+            packet_type = %data.packet_type,
+            topic = %data.topic,
+            duration_ms = data.duration_ms,
+            phases = ?data.phases,
+            "Slow MQTT request"
+        );
+        self.cache_manager.slow_request_log.record(config, data);
+    }
+
     async fn try_process_distinct_packet(
         &self,
         tcp_connection: &NetworkConnection,
@@ -287,15 +354,29 @@ pub async fn process_connect(
         tcp_connection: &NetworkConnection,
         addr: &SocketAddr,
         protocol_version: u8,
-        connect: Connect,
+        mut connect: Connect,
         properties: Option<ConnectProperties>,
         last_will: Option<LastWill>,
         last_will_properties: Option<LastWillProperties>,
-        login: Option<Login>,
+        mut login: Option<Login>,
     ) -> Option<ResponsePackage> {
         self.connection_manager
             .set_mqtt_connect_protocol(tcp_connection.connection_id, protocol_version.to_owned());
 
+        let mut cert_authenticated = apply_peer_cert_identity(
+            &broker_config().mqtt_tls,
+            tcp_connection.peer_cert.as_ref(),
+            &mut connect,
+            &mut login,
+        );
+        if let Some(username) = &tcp_connection.authenticated_user {
+            login = Some(Login {
+                username: username.clone(),
+                password: String::new(),
+            });
+            cert_authenticated = true;
+        }
+
         let resp_pkg = if is_mqtt3(protocol_version.to_owned()) {
             let connect_context = MqttServiceConnectContext {
                 connect_id: tcp_connection.connection_id,
@@ -305,6 +386,8 @@ pub async fn process_connect(
                 last_will_properties: last_will_properties.clone(),
                 login: login.clone(),
                 addr: *addr,
+                cert_authenticated,
+                connection_type: tcp_connection.connection_type.clone(),
             };
             Some(self.mqtt3_service.connect(connect_context).await)
         } else if is_mqtt4(protocol_version.to_owned()) {
@@ -316,6 +399,8 @@ pub async fn process_connect(
                 last_will_properties: last_will_properties.clone(),
                 login: login.clone(),
                 addr: *addr,
+                cert_authenticated,
+                connection_type: tcp_connection.connection_type.clone(),
             };
             Some(self.mqtt4_service.connect(connect_context).await)
         } else if is_mqtt5(protocol_version.to_owned()) {
@@ -327,6 +412,8 @@ pub async fn process_connect(
                 last_will_properties: last_will_properties.clone(),
                 login: login.clone(),
                 addr: *addr,
+                cert_authenticated,
+                connection_type: tcp_connection.connection_type.clone(),
             };
             Some(self.mqtt5_service.connect(connect_context).await)
         } else {
@@ -684,6 +771,7 @@ pub fn new(context: CommandContext) -> Self {
             limit_manager: context.mqtt_limit_manager.clone(),
             node_call: context.node_call.clone(),
             event_manager: context.event_manager.clone(),
+            connector_manager: context.connector_manager.clone(),
             stop_sx: context.stop_sx.clone(),
         };
         let mqtt3_service = MqttService::new(mqtt3_context);
@@ -702,6 +790,7 @@ pub fn new(context: CommandContext) -> Self {
             limit_manager: context.mqtt_limit_manager.clone(),
             node_call: context.node_call.clone(),
             event_manager: context.event_manager.clone(),
+            connector_manager: context.connector_manager.clone(),
             stop_sx: context.stop_sx.clone(),
         };
         let mqtt4_service = MqttService::new(mqtt4_context);
@@ -720,6 +809,7 @@ pub fn new(context: CommandContext) -> Self {
             limit_manager: context.mqtt_limit_manager.clone(),
             node_call: context.node_call.clone(),
             event_manager: context.event_manager.clone(),
+            connector_manager: context.connector_manager.clone(),
             stop_sx: context.stop_sx.clone(),
         };
         let mqtt5_service = MqttService::new(mqtt5_context);
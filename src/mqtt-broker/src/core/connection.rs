@@ -15,6 +15,7 @@
 use super::cache::MQTTCacheManager;
 use super::keep_alive::client_keep_live_time;
 use crate::core::error::MqttBrokerError;
+use crate::core::request_response::response_topic_namespace;
 use crate::core::session::delete_session_by_local;
 use crate::core::tool::ResultMqttBrokerError;
 use crate::mqtt::connect::build_connect_ack_fail_packet;
@@ -22,6 +23,7 @@
 use crate::subscribe::manager::SubscribeManager;
 use common_base::tools::now_second;
 use common_base::uuid::unique_id;
+use common_config::config::{MqttClientIdConfig, MqttProtocolConfig};
 use common_security::auth::acl::normalize_source_ip;
 use grpc_clients::pool::ClientPool;
 use metadata_struct::mqtt::connection::{ConnectionConfig, MQTTConnection};
@@ -33,8 +35,6 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
 
-pub const REQUEST_RESPONSE_PREFIX_NAME: &str = "$SYS/request_response";
-
 #[derive(Clone)]
 pub struct DisconnectConnectionContext {
     pub cache_manager: Arc<MQTTCacheManager>,
@@ -174,11 +174,28 @@ pub fn get_client_id(
     }
 }
 
-pub fn response_information(connect_properties: &Option<ConnectProperties>) -> Option<String> {
+/// The Assigned Client Identifier for a client that connected without a client
+/// id. `@` is dropped from the prefix so the id is not read as
+/// `<tenant>@<client_id>` when the client reconnects with it.
+pub fn assigned_client_id(conf: &MqttClientIdConfig, tenant: &str, listener: &str) -> String {
+    let prefix: String = conf
+        .prefix_for(tenant, listener)
+        .chars()
+        .filter(|c| *c != '@')
+        .collect();
+    format!("{}{}", prefix, unique_id())
+}
+
+/// The client's response topic namespace, if it asked for Response Information.
+pub fn response_information(
+    conf: &MqttProtocolConfig,
+    client_id: &str,
+    connect_properties: &Option<ConnectProperties>,
+) -> Option<String> {
     if let Some(properties) = connect_properties {
         if let Some(request_response_info) = properties.request_response_info {
             if request_response_info == 1 {
-                return Some(REQUEST_RESPONSE_PREFIX_NAME.to_string());
+                return response_topic_namespace(conf, client_id);
             }
         }
     }
@@ -315,9 +332,11 @@ fn get_session_expiry_interval(
 
 #[cfg(test)]
 mod test {
-    use super::{build_connection, response_information, REQUEST_RESPONSE_PREFIX_NAME};
+    use super::{assigned_client_id, build_connection, response_information};
+    use crate::core::tenant::try_decode_client_id;
     use crate::core::tool::test_build_mqtt_cache_manager;
     use common_config::broker::default_broker_config;
+    use common_config::config::{MqttClientIdConfig, MqttProtocolConfig};
     use protocol::mqtt::common::{Connect, ConnectProperties};
 
     #[tokio::test]
@@ -409,23 +428,55 @@ pub async fn get_client_id_test() {
         assert!(auto);
     }
 
+    #[test]
+    fn assigned_client_id_test() {
+        let mut conf = MqttClientIdConfig {
+            prefix: "auto-".to_string(),
+            ..Default::default()
+        };
+        conf.listener_prefix
+            .insert("websocket".to_string(), "ws-".to_string());
+        conf.tenant_prefix
+            .insert("acme".to_string(), "acme@dev-".to_string());
+
+        let id = assigned_client_id(&conf, "default", "tcp");
+        assert!(id.starts_with("auto-"));
+        assert_ne!(id, assigned_client_id(&conf, "default", "tcp"));
+        assert!(assigned_client_id(&conf, "default", "websocket").starts_with("ws-"));
+
+        let id = assigned_client_id(&conf, "acme", "websocket");
+        assert!(id.starts_with("acmedev-"));
+        assert_eq!(try_decode_client_id(&id), id);
+
+        let id = assigned_client_id(&MqttClientIdConfig::default(), "default", "tcp");
+        assert!(!id.is_empty());
+    }
+
     #[tokio::test]
     pub async fn response_information_test() {
         let connect_properties = ConnectProperties {
             request_response_info: Some(1),
             ..Default::default()
         };
-        let res = response_information(&Some(connect_properties));
-        assert_eq!(res.unwrap(), REQUEST_RESPONSE_PREFIX_NAME.to_string());
+        let conf = MqttProtocolConfig::default();
+        let res = response_information(&conf, "c1", &Some(connect_properties.clone()));
+        assert_eq!(res.unwrap(), "$rpc/c1".to_string());
+
+        let res = response_information(&conf, "c1", &Some(ConnectProperties::default()));
+        assert!(res.is_none());
 
-        let res = response_information(&Some(ConnectProperties::default()));
+        let disabled = MqttProtocolConfig {
+            response_topic_prefix: String::new(),
+            ..Default::default()
+        };
+        let res = response_information(&disabled, "c1", &Some(connect_properties));
         assert!(res.is_none());
 
         let connect_properties = ConnectProperties {
             request_response_info: Some(0),
             ..Default::default()
         };
-        let res = response_information(&Some(connect_properties));
+        let res = response_information(&conf, "c1", &Some(connect_properties));
         assert!(res.is_none());
     }
 }
@@ -0,0 +1,182 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use super::cache::MQTTCacheManager;
+use broker_core::consistency::{CachedRecord, ConsistencySource};
+use common_base::error::common::CommonError;
+use common_base::utils::crc::calc_digest;
+use common_security::manager::SecurityManager;
+use metadata_struct::meta::bootstrap::BootstrapResource;
+use protocol::broker::broker::BrokerUpdateCacheResourceType;
+use std::sync::Arc;
+
+pub struct UserConsistencySource {
+    security_manager: Arc<SecurityManager>,
+}
+
+impl UserConsistencySource {
+    pub fn new(security_manager: Arc<SecurityManager>) -> Self {
+        UserConsistencySource { security_manager }
+    }
+}
+
+impl ConsistencySource for UserConsistencySource {
+    fn resource(&self) -> BootstrapResource {
+        BootstrapResource::User
+    }
+
+    fn update_type(&self) -> BrokerUpdateCacheResourceType {
+        BrokerUpdateCacheResourceType::User
+    }
+
+    fn keys(&self) -> Vec<String> {
+        let mut keys = Vec::new();
+        for tenant in self.security_manager.metadata.user_info.iter() {
+            for user in tenant.value().iter() {
+                keys.push(format!("{}/{}", tenant.key(), user.key()));
+            }
+        }
+        keys
+    }
+
+    fn get(&self, key: &str) -> Result<Option<CachedRecord>, CommonError> {
+        let Some((tenant, username)) = key.split_once('/') else {
+            return Ok(None);
+        };
+        let Some(user) = self
+            .security_manager
+            .metadata
+            .user_info
+            .get(tenant)
+            .and_then(|users| users.get(username).map(|u| u.clone()))
+        else {
+            return Ok(None);
+        };
+        Ok(Some(CachedRecord {
+            data: user.encode()?,
+            digest: calc_digest(&user)?,
+        }))
+    }
+
+    // Users can also be loaded from external authentication storage.
+    fn delete_missing(&self) -> bool {
+        false
+    }
+}
+
+pub struct SessionConsistencySource {
+    cache_manager: Arc<MQTTCacheManager>,
+}
+
+impl SessionConsistencySource {
+    pub fn new(cache_manager: Arc<MQTTCacheManager>) -> Self {
+        SessionConsistencySource { cache_manager }
+    }
+}
+
+impl ConsistencySource for SessionConsistencySource {
+    fn resource(&self) -> BootstrapResource {
+        BootstrapResource::Session
+    }
+
+    fn update_type(&self) -> BrokerUpdateCacheResourceType {
+        BrokerUpdateCacheResourceType::Session
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.cache_manager
+            .session_info
+            .iter()
+            .map(|entry| format!("{}/{}", entry.value().tenant, entry.key()))
+            .collect()
+    }
+
+    fn get(&self, key: &str) -> Result<Option<CachedRecord>, CommonError> {
+        let Some((tenant, client_id)) = key.split_once('/') else {
+            return Ok(None);
+        };
+        let Some(session) = self
+            .cache_manager
+            .get_session_info_by_tenant(tenant, client_id)
+        else {
+            return Ok(None);
+        };
+
+        // A session connected to this broker is owned here and written back
+        // to the meta service by the session batcher.
+        if session
+            .connection_id
+            .is_some_and(|id| self.cache_manager.connection_info.contains_key(&id))
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(CachedRecord {
+            data: session.encode()?,
+            digest: calc_digest(&session)?,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::tool::test_build_mqtt_cache_manager;
+    use metadata_struct::auth::user::SecurityUser;
+    use metadata_struct::mqtt::connection::MQTTConnection;
+    use metadata_struct::mqtt::session::MqttSession;
+
+    #[test]
+    fn test_user_source() {
+        let security_manager = Arc::new(SecurityManager::new());
+        security_manager.metadata.add_user(SecurityUser {
+            tenant: "t1".to_string(),
+            username: "u1".to_string(),
+            password: "p1".to_string(),
+            salt: None,
+            is_superuser: false,
+            create_time: 1,
+            hash_algorithm: None,
+        });
+        let source = UserConsistencySource::new(security_manager);
+        assert_eq!(source.keys(), vec!["t1/u1".to_string()]);
+        assert!(source.get("t1/u1").unwrap().is_some());
+        assert!(source.get("t1/u2").unwrap().is_none());
+        assert!(!source.delete_missing());
+    }
+
+    #[tokio::test]
+    async fn test_session_source_skips_connected_sessions() {
+        let cache_manager = test_build_mqtt_cache_manager().await;
+        let mut session =
+            MqttSession::new("t1".to_string(), "c1".to_string(), 60, false, None, true);
+        session.update_connection_id(Some(7));
+        cache_manager.add_session("c1", &session);
+        let source = SessionConsistencySource::new(cache_manager.clone());
+        assert_eq!(source.keys(), vec!["t1/c1".to_string()]);
+
+        // The connection lives on another broker or is already gone.
+        let cached = source.get("t1/c1").unwrap().unwrap();
+        assert_eq!(MqttSession::decode(&cached.data).unwrap(), session);
+        assert!(source.get("t2/c1").unwrap().is_none());
+
+        let connection = MQTTConnection {
+            connect_id: 7,
+            tenant: "t1".to_string(),
+            client_id: "c1".to_string(),
+            ..Default::default()
+        };
+        cache_manager.add_connection(7, connection);
+        assert!(source.get("t1/c1").unwrap().is_none());
+    }
+}
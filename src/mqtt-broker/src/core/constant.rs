@@ -18,6 +18,9 @@
 // Maximum concurrent tasks for sending retain messages
 pub const MAX_RETAIN_MESSAGE_SEND_CONCURRENCY: usize = 10;
 
+// Topics looked up per storage read when backfilling the retain index
+pub const RETAIN_INDEX_BACKFILL_BATCH: usize = 100;
+
 pub const METRICS_KEY_PROTOCOL_NAME: &str = "protocol";
 pub const METRICS_KEY_NETWORK_TYPE: &str = "network";
 pub const METRICS_KEY_LABEL_NAME: &str = "label";
@@ -0,0 +1,346 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Consumer lag of the subscription groups served by this broker: shared
+//! subscription groups and the consumer groups of local connectors.
+//!
+//! For each shard of a consumed topic the lag is the distance between the
+//! offset the next record is written at and the group's committed offset.
+//! A shard the group has not committed on yet, or whose committed records
+//! have expired, counts from its earliest stored offset.
+//!
+//! When subscription metrics are enabled, the committed offset and lag of
+//! each exported non-shared subscription are set here as well.
+
+use crate::core::cache::MQTTCacheManager;
+use crate::core::sub_share::is_mqtt_share_subscribe;
+use crate::subscribe::directly_push::directly_group_name;
+use crate::subscribe::manager::SubscribeManager;
+use crate::system_topic::packet::lag::report_broker_metrics_subscriptions_lag;
+use common_base::error::common::CommonError;
+use common_base::error::ResultCommonError;
+use common_base::tools::loop_select_ticket;
+use common_metrics::mqtt::consumer_lag::{
+    record_consumer_group_lag_set, remove_consumer_group_lag, ConsumerLagLabel,
+};
+use common_metrics::mqtt::subscription::{
+    record_subscription_progress, resolve_subscription_label, subscription_metrics_enabled,
+};
+use connector::manager::ConnectorManager;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::adapter::adapter_offset::AdapterConsumerGroupOffset;
+use metadata_struct::adapter::adapter_shard::AdapterShardWatermarks;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use storage_adapter::driver::StorageDriverManager;
+use tokio::sync::broadcast;
+use tracing::warn;
+
+pub const CONSUMER_LAG_INTERVAL_MS: u64 = 30_000;
+
+const GROUP_TYPE_SHARE: &str = "share";
+const GROUP_TYPE_CONNECTOR: &str = "connector";
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct LagGroup {
+    tenant: String,
+    group_type: &'static str,
+    group: String,
+    topic: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardLag {
+    pub shard_name: String,
+    pub latest_offset: u64,
+    pub committed_offset: Option<u64>,
+    pub lag: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsumerGroupLag {
+    pub tenant: String,
+    pub group_type: String,
+    pub group: String,
+    pub topic: String,
+    pub lag: u64,
+    pub shards: Vec<ShardLag>,
+}
+
+impl ConsumerGroupLag {
+    fn label(&self) -> ConsumerLagLabel {
+        ConsumerLagLabel {
+            tenant: self.tenant.clone(),
+            group_type: self.group_type.clone(),
+            group: self.group.clone(),
+            topic: self.topic.clone(),
+        }
+    }
+}
+
+pub fn shard_lags(
+    watermarks: &[AdapterShardWatermarks],
+    offsets: &[AdapterConsumerGroupOffset],
+) -> Vec<ShardLag> {
+    let committed: HashMap<&str, u64> = offsets
+        .iter()
+        .map(|offset| (offset.shard_name.as_str(), offset.offset))
+        .collect();
+    watermarks
+        .iter()
+        .map(|watermark| {
+            let committed_offset = committed.get(watermark.shard_name.as_str()).copied();
+            let from = committed_offset
+                .unwrap_or(watermark.earliest_offset)
+                .max(watermark.earliest_offset);
+            ShardLag {
+                shard_name: watermark.shard_name.clone(),
+                latest_offset: watermark.latest_offset,
+                committed_offset,
+                lag: watermark.latest_offset.saturating_sub(from),
+            }
+        })
+        .collect()
+}
+
+/// Per-shard lag of a consumer group on a topic.
+pub async fn group_shard_lags(
+    storage_driver_manager: &Arc<StorageDriverManager>,
+    tenant: &str,
+    group: &str,
+    topic: &str,
+) -> Result<Vec<ShardLag>, CommonError> {
+    let watermarks = storage_driver_manager
+        .shard_watermarks(tenant, topic)
+        .await?;
+    let offsets = storage_driver_manager
+        .get_offset_by_group(tenant, group)
+        .await?;
+    Ok(shard_lags(&watermarks, &offsets))
+}
+
+pub struct ConsumerLagExporter {
+    cache_manager: Arc<MQTTCacheManager>,
+    subscribe_manager: Arc<SubscribeManager>,
+    connector_manager: Arc<ConnectorManager>,
+    storage_driver_manager: Arc<StorageDriverManager>,
+    client_pool: Arc<ClientPool>,
+    exported: Mutex<HashSet<ConsumerLagLabel>>,
+}
+
+impl ConsumerLagExporter {
+    pub fn new(
+        cache_manager: Arc<MQTTCacheManager>,
+        subscribe_manager: Arc<SubscribeManager>,
+        connector_manager: Arc<ConnectorManager>,
+        storage_driver_manager: Arc<StorageDriverManager>,
+        client_pool: Arc<ClientPool>,
+    ) -> Self {
+        ConsumerLagExporter {
+            cache_manager,
+            subscribe_manager,
+            connector_manager,
+            storage_driver_manager,
+            client_pool,
+            exported: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub async fn start(&self, stop_send: broadcast::Sender<bool>) {
+        let record_func = async || self.record().await;
+        loop_select_ticket(record_func, CONSUMER_LAG_INTERVAL_MS, &stop_send).await;
+    }
+
+    async fn record(&self) -> ResultCommonError {
+        let mut lags = Vec::new();
+        for group in self.groups() {
+            match self.group_lag(&group).await {
+                Ok(lag) => lags.push(lag),
+                Err(e) => warn!(
+                    "Failed to compute lag of {} group {} on topic {}: {}",
+                    group.group_type, group.group, group.topic, e
+                ),
+            }
+        }
+
+        self.export(&lags);
+        if subscription_metrics_enabled() {
+            self.record_subscriptions().await;
+        }
+        report_broker_metrics_subscriptions_lag(
+            &self.client_pool,
+            &self.cache_manager,
+            &self.storage_driver_manager,
+            lags,
+        )
+        .await;
+        Ok(())
+    }
+
+    fn groups(&self) -> BTreeSet<LagGroup> {
+        let mut groups = BTreeSet::new();
+        for tenant_entry in self.subscribe_manager.share_group_topics.iter() {
+            for group_entry in tenant_entry.value().iter() {
+                for info in group_entry.value().iter() {
+                    groups.insert(LagGroup {
+                        tenant: tenant_entry.key().clone(),
+                        group_type: GROUP_TYPE_SHARE,
+                        group: group_entry.key().clone(),
+                        topic: info.topic.clone(),
+                    });
+                }
+            }
+        }
+
+        for tenant_entry in self.connector_manager.connector_thread.iter() {
+            for thread_entry in tenant_entry.value().iter() {
+                let Some(connector) = self
+                    .connector_manager
+                    .get_connector_by_tenant(tenant_entry.key(), thread_entry.key())
+                else {
+                    continue;
+                };
+                groups.insert(LagGroup {
+                    tenant: connector.tenant,
+                    group_type: GROUP_TYPE_CONNECTOR,
+                    group: connector.connector_name,
+                    topic: connector.topic_name,
+                });
+            }
+        }
+        groups
+    }
+
+    async fn group_lag(&self, group: &LagGroup) -> Result<ConsumerGroupLag, CommonError> {
+        let shards = group_shard_lags(
+            &self.storage_driver_manager,
+            &group.tenant,
+            &group.group,
+            &group.topic,
+        )
+        .await?;
+        Ok(ConsumerGroupLag {
+            tenant: group.tenant.clone(),
+            group_type: group.group_type.to_string(),
+            group: group.group.clone(),
+            topic: group.topic.clone(),
+            lag: shards.iter().map(|shard| shard.lag).sum(),
+            shards,
+        })
+    }
+
+    /// Sets the offset, lag and last push time of every non-shared
+    /// subscription with its own series. Shared subscriptions are covered
+    /// by the lag of their group.
+    async fn record_subscriptions(&self) {
+        for (label, stats) in self.subscribe_manager.subscription_stats.list() {
+            if is_mqtt_share_subscribe(&label.path) {
+                continue;
+            }
+            match resolve_subscription_label(label.clone()) {
+                Some(resolved) if !resolved.is_other() => {}
+                _ => continue,
+            }
+
+            let group = directly_group_name(&label.client_id, &label.path, &label.topic);
+            match group_shard_lags(
+                &self.storage_driver_manager,
+                &label.tenant,
+                &group,
+                &label.topic,
+            )
+            .await
+            {
+                Ok(shards) => {
+                    let offset = shards
+                        .iter()
+                        .filter_map(|shard| shard.committed_offset)
+                        .sum();
+                    let lag = shards.iter().map(|shard| shard.lag).sum();
+                    record_subscription_progress(label, offset, lag, stats.last_push_time);
+                }
+                Err(e) => warn!(
+                    "Failed to compute lag of subscription {}/{} on topic {}: {}",
+                    label.client_id, label.path, label.topic, e
+                ),
+            }
+        }
+    }
+
+    /// Sets the gauge of every group and drops the series of groups that are
+    /// gone since the last round.
+    fn export(&self, lags: &[ConsumerGroupLag]) {
+        let current: HashSet<ConsumerLagLabel> = lags.iter().map(|lag| lag.label()).collect();
+        for lag in lags {
+            record_consumer_group_lag_set(lag.label(), lag.lag as i64);
+        }
+
+        let mut exported = self.exported.lock().unwrap();
+        for label in exported.difference(&current) {
+            remove_consumer_group_lag(label);
+        }
+        *exported = current;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watermark(
+        shard_name: &str,
+        earliest_offset: u64,
+        latest_offset: u64,
+    ) -> AdapterShardWatermarks {
+        AdapterShardWatermarks {
+            shard_name: shard_name.to_string(),
+            earliest_offset,
+            latest_offset,
+            ..Default::default()
+        }
+    }
+
+    fn offset(shard_name: &str, offset: u64) -> AdapterConsumerGroupOffset {
+        AdapterConsumerGroupOffset {
+            group: "g1".to_string(),
+            shard_name: shard_name.to_string(),
+            segment_no: 0,
+            offset,
+        }
+    }
+
+    #[test]
+    fn test_shard_lags() {
+        let watermarks = vec![
+            watermark("s0", 0, 100),
+            watermark("s1", 10, 50),
+            watermark("s2", 40, 60),
+            watermark("s3", 0, 0),
+        ];
+        let offsets = vec![offset("s0", 80), offset("s2", 20), offset("s3", 0)];
+
+        let lags = shard_lags(&watermarks, &offsets);
+        // committed
+        assert_eq!(lags[0].lag, 20);
+        assert_eq!(lags[0].committed_offset, Some(80));
+        // never committed, counted from the earliest offset
+        assert_eq!(lags[1].lag, 40);
+        assert_eq!(lags[1].committed_offset, None);
+        // committed records expired
+        assert_eq!(lags[2].lag, 20);
+        // empty shard
+        assert_eq!(lags[3].lag, 0);
+    }
+}
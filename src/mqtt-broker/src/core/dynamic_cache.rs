@@ -13,12 +13,14 @@
 // limitations under the License.
 
 use super::cache::MQTTCacheManager;
+use super::client_attribute::sync_connection_attributes;
 use crate::core::session::delete_session_by_local;
 use crate::core::tool::ResultMqttBrokerError;
 use crate::subscribe::manager::SubscribeManager;
 use crate::subscribe::parse::ParseSubscribeData;
 use common_base::utils::serialize;
 use metadata_struct::mqtt::auto_subscribe::MqttAutoSubscribeRule;
+use metadata_struct::mqtt::message_rule::MqttMessageRule;
 use metadata_struct::mqtt::session::MqttSession;
 use metadata_struct::mqtt::subscribe::MqttSubscribe;
 use metadata_struct::mqtt::topic_rewrite_rule::MqttTopicRewriteRule;
@@ -37,6 +39,9 @@ pub async fn update_mqtt_cache_metadata(
             BrokerUpdateCacheActionType::Create | BrokerUpdateCacheActionType::Update => {
                 let session = serialize::deserialize::<MqttSession>(&record.data)?;
                 cache_manager.add_session(&session.client_id, &session);
+                sync_connection_attributes(cache_manager, &session);
+                // The session may have moved to another broker.
+                subscribe_manager.forward_routes.invalidate();
             }
             BrokerUpdateCacheActionType::Delete => {
                 let session = serialize::deserialize::<MqttSession>(&record.data)?;
@@ -93,18 +98,32 @@ pub async fn update_mqtt_cache_metadata(
                 cache_manager.delete_auto_subscribe_rule(&rule.tenant, &rule.name);
             }
         },
+        BrokerUpdateCacheResourceType::MessageRule => match record.action_type() {
+            BrokerUpdateCacheActionType::Create | BrokerUpdateCacheActionType::Update => {
+                let rule = MqttMessageRule::decode(&record.data)
+                    .map_err(|e| crate::core::error::MqttBrokerError::CommonError(e.to_string()))?;
+                cache_manager.add_message_rule(rule)?;
+            }
+            BrokerUpdateCacheActionType::Delete => {
+                let rule = MqttMessageRule::decode(&record.data)
+                    .map_err(|e| crate::core::error::MqttBrokerError::CommonError(e.to_string()))?;
+                cache_manager.delete_message_rule(&rule.tenant, &rule.name);
+            }
+        },
         BrokerUpdateCacheResourceType::TopicRewriteRule => match record.action_type() {
             BrokerUpdateCacheActionType::Create | BrokerUpdateCacheActionType::Update => {
                 let rule = MqttTopicRewriteRule::decode(&record.data)
                     .map_err(|e| crate::core::error::MqttBrokerError::CommonError(e.to_string()))?;
                 cache_manager.add_topic_rewrite_rule(rule);
                 cache_manager.set_re_calc_topic_rewrite(true).await;
+                subscribe_manager.forward_routes.invalidate();
             }
             BrokerUpdateCacheActionType::Delete => {
                 let rule = MqttTopicRewriteRule::decode(&record.data)
                     .map_err(|e| crate::core::error::MqttBrokerError::CommonError(e.to_string()))?;
                 cache_manager.delete_topic_rewrite_rule(&rule.tenant, &rule.name);
                 cache_manager.set_re_calc_topic_rewrite(true).await;
+                subscribe_manager.forward_routes.invalidate();
             }
         },
         _ => {}
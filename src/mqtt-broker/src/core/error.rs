@@ -106,6 +106,15 @@ pub enum MqttBrokerError {
     #[error("Topic {0} does not exist")]
     TopicDoesNotExist(String),
 
+    #[error("Response topic {0} is invalid: {1}")]
+    InvalidResponseTopic(String, String),
+
+    #[error("Response topic {0} belongs to another client's response namespace")]
+    ResponseTopicNotAuthorized(String),
+
+    #[error("Correlation data size {0} exceeds the limit of {1} bytes")]
+    CorrelationDataTooLarge(usize, usize),
+
     #[error("Connection ID [0] information not found in cache.")]
     NotFoundConnectionInCache(u64),
 
@@ -177,6 +186,9 @@ pub enum MqttBrokerError {
     #[error("Connection {0} is null, skip push message")]
     ConnectionNullSkipPushMessage(String),
 
+    #[error("Message to client {0} stays inflight until the session resumes")]
+    InflightRedeliveryPending(String),
+
     #[error("kafka error: {0}")]
     KafkaError(#[from] KafkaError),
 
@@ -207,6 +219,18 @@ pub enum MqttBrokerError {
     #[error("Tenant [{0}] does not exist.")]
     TenantNotFound(String),
 
+    #[error("Tenant [{0}] has exceeded its {1} quota")]
+    TenantQuotaExceeded(String, String),
+
+    #[error("Topic {0} exceeds the maximum length. Max :{1}, current :{2}")]
+    TopicLengthExceeded(String, u32, usize),
+
+    #[error("Topic {0} exceeds the maximum number of levels. Max :{1}, current :{2}")]
+    TopicLevelsExceeded(String, u32, usize),
+
+    #[error("Client [{0}] has exceeded the maximum of {1} subscriptions")]
+    ClientSubscriptionsExceeded(String, u32),
+
     #[error("ACL authentication failed. Access denied for topic: {0}")]
     NotAclAuth(String),
 
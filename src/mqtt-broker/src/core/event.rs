@@ -14,8 +14,14 @@
 
 use crate::core::cache::MQTTCacheManager;
 use crate::core::topic::try_init_topic;
+use crate::core::webhook::{WebhookEventType, WebhookManager, WebhookPublishEvent};
 use crate::storage::message::MessageStorage;
 use crate::system_topic::build_system_topic_payload;
+use crate::system_topic::client_event::{
+    client_event_topic_name, disconnect_reason, report_client_event,
+    SYSTEM_TOPIC_BROKERS_CLIENT_CONNECTED, SYSTEM_TOPIC_BROKERS_CLIENT_DISCONNECTED,
+    SYSTEM_TOPIC_BROKERS_CLIENT_SUBSCRIBED, SYSTEM_TOPIC_BROKERS_CLIENT_UNSUBSCRIBED,
+};
 use common_base::tools::now_millis;
 use grpc_clients::pool::ClientPool;
 use metadata_struct::mqtt::session::MqttSession;
@@ -26,7 +32,7 @@
 use network_server::common::connection_manager::ConnectionManager;
 use protocol::mqtt::common::{DisconnectReasonCode, Subscribe, Unsubscribe};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 use storage_adapter::driver::StorageDriverManager;
 use tokio::sync::mpsc;
@@ -56,6 +62,7 @@ pub struct SystemTopicConnectedEventMessage {
     #[serde(rename = "clientid")]
     pub client_id: String,
     pub clean_start: bool,
+    pub client_attrs: BTreeMap<String, String>,
 }
 
 #[derive(Default, Serialize, Deserialize)]
@@ -65,6 +72,7 @@ pub struct SystemTopicDisConnectedEventMessage {
     #[serde(rename = "sockport")]
     pub sock_port: u16,
     pub reason: String,
+    pub reason_code: u8,
     pub proto_ver: u8,
     pub proto_name: String,
     #[serde(rename = "ipaddress")]
@@ -72,6 +80,7 @@ pub struct SystemTopicDisConnectedEventMessage {
     pub disconnected_at: u128,
     #[serde(rename = "clientid")]
     pub client_id: String,
+    pub client_attrs: BTreeMap<String, String>,
 }
 
 #[derive(Default, Serialize, Deserialize)]
@@ -112,6 +121,35 @@ pub enum EventData {
     Unsubscribed(SystemTopicUnSubscribedEventMessage),
 }
 
+impl EventData {
+    fn client_event_topic(&self) -> String {
+        match self {
+            EventData::Connected(d) => {
+                client_event_topic_name(SYSTEM_TOPIC_BROKERS_CLIENT_CONNECTED, &d.client_id)
+            }
+            EventData::Disconnected(d) => {
+                client_event_topic_name(SYSTEM_TOPIC_BROKERS_CLIENT_DISCONNECTED, &d.client_id)
+            }
+            EventData::Subscribed(d) => {
+                client_event_topic_name(SYSTEM_TOPIC_BROKERS_CLIENT_SUBSCRIBED, &d.client_id)
+            }
+            EventData::Unsubscribed(d) => {
+                client_event_topic_name(SYSTEM_TOPIC_BROKERS_CLIENT_UNSUBSCRIBED, &d.client_id)
+            }
+        }
+    }
+
+    /// Plain event JSON without the node envelope, as published by EMQX.
+    fn client_event_payload(&self) -> Result<String, serde_json::Error> {
+        match self {
+            EventData::Connected(d) => serde_json::to_string(d),
+            EventData::Disconnected(d) => serde_json::to_string(d),
+            EventData::Subscribed(d) => serde_json::to_string(d),
+            EventData::Unsubscribed(d) => serde_json::to_string(d),
+        }
+    }
+}
+
 pub struct EventMessage {
     pub data: EventData,
 }
@@ -119,17 +157,27 @@ pub struct EventMessage {
 pub struct EventReportManager {
     tx: mpsc::Sender<EventMessage>,
     consumer: std::sync::Mutex<Option<mpsc::Receiver<EventMessage>>>,
+    webhook: Arc<WebhookManager>,
 }
 
 impl EventReportManager {
     pub fn new() -> Arc<Self> {
+        Self::with_webhook(WebhookManager::new(Default::default()))
+    }
+
+    pub fn with_webhook(webhook: Arc<WebhookManager>) -> Arc<Self> {
         let (tx, rx) = mpsc::channel(EVENT_CHANNEL_SIZE);
         Arc::new(EventReportManager {
             tx,
             consumer: std::sync::Mutex::new(Some(rx)),
+            webhook,
         })
     }
 
+    pub fn webhook(&self) -> Arc<WebhookManager> {
+        self.webhook.clone()
+    }
+
     pub async fn start(
         &self,
         cache_manager: Arc<MQTTCacheManager>,
@@ -146,10 +194,36 @@ pub async fn start(
     }
 
     pub async fn report(&self, msg: EventMessage) {
+        self.report_webhook(&msg.data);
         if let Err(e) = self.tx.send(msg).await {
             error!("report event channel closed: {}", e);
         }
     }
+
+    /// Publishes are only forwarded to webhooks, they are not written to `$SYS/events`.
+    pub fn report_message_publish(&self, event: WebhookPublishEvent) {
+        self.webhook
+            .report(WebhookEventType::MessagePublish, &event);
+    }
+
+    /// Whether a webhook endpoint takes `message.publish`, checked before the
+    /// event is built so publishes pay nothing when none does.
+    pub fn is_message_publish_subscribed(&self) -> bool {
+        self.webhook.is_subscribed(WebhookEventType::MessagePublish)
+    }
+
+    fn report_webhook(&self, data: &EventData) {
+        match data {
+            EventData::Connected(d) => self.webhook.report(WebhookEventType::ClientConnected, d),
+            EventData::Disconnected(d) => {
+                self.webhook.report(WebhookEventType::ClientDisconnected, d)
+            }
+            EventData::Subscribed(d) => self.webhook.report(WebhookEventType::SessionSubscribed, d),
+            EventData::Unsubscribed(d) => self
+                .webhook
+                .report(WebhookEventType::SessionUnsubscribed, d),
+        }
+    }
 }
 
 async fn event_batch_consumer(
@@ -199,21 +273,51 @@ async fn event_batch_consumer(
                 Err(mpsc::error::TryRecvError::Empty) => break,
                 Err(mpsc::error::TryRecvError::Disconnected) => {
                     info!("EventReportManager channel closed during batch collection");
-                    flush_batch(batch, &topic.topic_name, &message_storage).await;
+                    flush_batch(
+                        batch,
+                        &topic.topic_name,
+                        &message_storage,
+                        &cache_manager,
+                        &storage_driver_manager,
+                        &client_pool,
+                    )
+                    .await;
                     return;
                 }
             }
         }
 
-        flush_batch(batch, &topic.topic_name, &message_storage).await;
+        flush_batch(
+            batch,
+            &topic.topic_name,
+            &message_storage,
+            &cache_manager,
+            &storage_driver_manager,
+            &client_pool,
+        )
+        .await;
     }
 }
 
-async fn flush_batch(batch: Vec<EventMessage>, topic_name: &str, message_storage: &MessageStorage) {
+async fn flush_batch(
+    batch: Vec<EventMessage>,
+    topic_name: &str,
+    message_storage: &MessageStorage,
+    cache_manager: &Arc<MQTTCacheManager>,
+    storage_driver_manager: &Arc<StorageDriverManager>,
+    client_pool: &Arc<ClientPool>,
+) {
+    let client_event_enable = cache_manager
+        .node_cache
+        .get_cluster_config()
+        .mqtt_system_monitor
+        .client_event_enable;
+
     let mut records = Vec::with_capacity(batch.len());
+    let mut client_events = Vec::new();
 
     for msg in batch {
-        let payload = match serialize_event_data(msg.data) {
+        let payload = match serialize_event_data(&msg.data) {
             Ok(p) => p,
             Err(e) => {
                 warn!("EventReportManager: failed to serialize event: {}", e);
@@ -223,24 +327,43 @@ async fn flush_batch(batch: Vec<EventMessage>, topic_name: &str, message_storage
 
         let record = AdapterWriteRecord::new(topic_name.to_string(), payload);
         records.push(record);
+
+        if client_event_enable {
+            match msg.data.client_event_payload() {
+                Ok(payload) => client_events.push((msg.data.client_event_topic(), payload)),
+                Err(e) => warn!(
+                    "EventReportManager: failed to serialize client event: {}",
+                    e
+                ),
+            }
+        }
     }
 
-    if records.is_empty() {
-        return;
+    if !records.is_empty() {
+        if let Err(e) = message_storage
+            .append_topic_message(DEFAULT_TENANT, topic_name, records)
+            .await
+        {
+            warn!(
+                "EventReportManager: failed to write events to {}: {}",
+                topic_name, e
+            );
+        }
     }
 
-    if let Err(e) = message_storage
-        .append_topic_message(DEFAULT_TENANT, topic_name, records)
-        .await
-    {
-        warn!(
-            "EventReportManager: failed to write events to {}: {}",
-            topic_name, e
-        );
+    for (client_topic, payload) in client_events {
+        report_client_event(
+            client_pool,
+            cache_manager,
+            storage_driver_manager,
+            client_topic,
+            payload,
+        )
+        .await;
     }
 }
 
-fn serialize_event_data(data: EventData) -> Result<String, serde_json::Error> {
+fn serialize_event_data(data: &EventData) -> Result<String, serde_json::Error> {
     match data {
         EventData::Connected(d) => build_system_topic_payload(d),
         EventData::Disconnected(d) => build_system_topic_payload(d),
@@ -274,6 +397,7 @@ pub async fn st_report_connected_event(
             connect_ack: 0,
             client_id: session.client_id.to_string(),
             clean_start: !session.is_persist_session,
+            client_attrs: connection.attributes.clone(),
         };
         event_manager
             .report(EventMessage {
@@ -292,11 +416,13 @@ pub async fn st_report_disconnected_event(
     reason: Option<DisconnectReasonCode>,
 ) {
     if let Some(network_connection) = connection_manager.get_connect(connect_id) {
+        let (reason, reason_code) = disconnect_reason(reason);
         let event_data = SystemTopicDisConnectedEventMessage {
             username: connection.login_user.clone().unwrap_or_default(),
             ts: now_millis(),
             sock_port: network_connection.addr.port(),
-            reason: format!("{:?}", reason),
+            reason,
+            reason_code,
             proto_ver: network_connection
                 .protocol
                 .as_ref()
@@ -306,6 +432,7 @@ pub async fn st_report_disconnected_event(
             ip_address: connection.source_ip_addr.clone(),
             client_id: session.client_id.to_string(),
             disconnected_at: now_millis(),
+            client_attrs: connection.attributes.clone(),
         };
         event_manager
             .report(EventMessage {
@@ -323,7 +450,7 @@ pub async fn st_report_subscribed_event(
     subscribe: &Subscribe,
 ) {
     let username = connection.login_user.clone().unwrap_or_default();
-    if let Some(network_connection) = connection_manager.get_connect(connect_id) {
+    if connection_manager.get_connect(connect_id).is_some() {
         for filter in subscribe.filters.iter() {
             let subopts = SystemTopicSubscribedEventMessageSupports {
                 sub_props: HashMap::new(),
@@ -338,7 +465,7 @@ pub async fn st_report_subscribed_event(
                 ts: now_millis(),
                 subopts,
                 topic: filter.path.clone(),
-                protocol: format!("{:?}", network_connection.protocol),
+                protocol: "mqtt".to_string(),
                 client_id: connection.client_id.to_string(),
             };
             event_manager
@@ -358,13 +485,13 @@ pub async fn st_report_unsubscribed_event(
     un_subscribe: &Unsubscribe,
 ) {
     let username = connection.login_user.clone().unwrap_or_default();
-    if let Some(network_connection) = connection_manager.get_connect(connect_id) {
+    if connection_manager.get_connect(connect_id).is_some() {
         for path in un_subscribe.filters.iter() {
             let event_data = SystemTopicUnSubscribedEventMessage {
                 username: username.clone(),
                 ts: now_millis(),
                 topic: path.clone(),
-                protocol: format!("{:?}", network_connection.protocol),
+                protocol: "mqtt".to_string(),
                 client_id: connection.client_id.to_string(),
             };
             event_manager
@@ -0,0 +1,366 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Persistent inflight state of QoS 1/2 messages sent to clients.
+//!
+//! With `mqtt_protocol.inflight_persistent` on, the exclusive push path saves
+//! an entry (packet id, record position, send count) before a Publish goes
+//! out. PUBACK and PUBCOMP delete it, PUBREC moves a QoS 2 entry to the PUBREL
+//! stage. Entries that survive a disconnect or a broker restart are
+//! redelivered when the session resumes: Publish stage entries are sent again
+//! with the DUP flag and the same packet id, PUBREL stage entries resend
+//! PUBREL. A clean start drops them, and entries older than
+//! `inflight_expire_sec` are dropped instead of redelivered.
+
+use crate::core::cache::MQTTCacheManager;
+use crate::core::error::MqttBrokerError;
+use crate::core::tool::ResultMqttBrokerError;
+use crate::storage::inflight::InflightStorage;
+use crate::subscribe::common::{client_unavailable_error, message_is_expire};
+use crate::subscribe::common::{SubPublishParam, Subscriber};
+use crate::subscribe::push::{
+    build_dup_publish_param, build_publish_message_with_pkid, resend_pubrel_to_client,
+    send_publish_packet_to_client,
+};
+use common_base::tools::now_second;
+use common_metrics::mqtt::session::{
+    record_mqtt_inflight_dropped, record_mqtt_inflight_redelivered,
+};
+use metadata_struct::adapter::adapter_read_config::AdapterReadConfig;
+use metadata_struct::storage::record::StorageRecord;
+use network_server::common::connection_manager::ConnectionManager;
+use protocol::mqtt::common::{MqttPacket, PubRel, PubRelReason, QoS};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InflightStage {
+    /// Publish sent, waiting for PUBACK (QoS 1) or PUBREC (QoS 2).
+    Publish,
+    /// PUBREC received, waiting for PUBCOMP.
+    PubRel,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InflightMessage {
+    pub tenant: String,
+    pub client_id: String,
+    pub pkid: u16,
+    pub qos: QoS,
+    pub stage: InflightStage,
+    pub subscriber: Subscriber,
+    /// Position of the record in the subscribed topic.
+    pub shard: String,
+    pub offset: u64,
+    pub send_count: u32,
+    pub create_time: u64,
+}
+
+impl InflightMessage {
+    pub fn new(subscriber: &Subscriber, record: &StorageRecord, param: &SubPublishParam) -> Self {
+        InflightMessage {
+            tenant: subscriber.tenant.clone(),
+            client_id: subscriber.client_id.clone(),
+            pkid: param.p_kid,
+            qos: param.qos,
+            stage: InflightStage::Publish,
+            subscriber: subscriber.clone(),
+            shard: record.metadata.shard.clone(),
+            offset: record.metadata.offset,
+            send_count: 1,
+            create_time: now_second(),
+        }
+    }
+
+    pub fn is_expired(&self, expire_sec: u64, now: u64) -> bool {
+        expire_sec > 0 && now.saturating_sub(self.create_time) > expire_sec
+    }
+}
+
+pub fn inflight_persistent_enabled(cache_manager: &Arc<MQTTCacheManager>) -> bool {
+    cache_manager
+        .node_cache
+        .get_cluster_config()
+        .mqtt_protocol
+        .inflight_persistent
+}
+
+pub struct InflightRedeliveryContext {
+    pub cache_manager: Arc<MQTTCacheManager>,
+    pub connection_manager: Arc<ConnectionManager>,
+    pub inflight_storage: InflightStorage,
+    pub tenant: String,
+    pub client_id: String,
+    pub stop_sx: broadcast::Sender<bool>,
+}
+
+/// Redeliver the persisted inflight messages of a resumed session, oldest
+/// first. A new session drops them instead.
+pub async fn resume_inflight_messages(
+    context: &InflightRedeliveryContext,
+    new_session: bool,
+) -> ResultMqttBrokerError {
+    let mut messages = context
+        .inflight_storage
+        .list_inflight_message(&context.tenant, &context.client_id)
+        .await?;
+    if messages.is_empty() {
+        return Ok(());
+    }
+
+    if new_session {
+        for message in messages.iter() {
+            delete_inflight(context, message, "clean_start").await;
+        }
+        return Ok(());
+    }
+
+    let expire_sec = context
+        .cache_manager
+        .node_cache
+        .get_cluster_config()
+        .mqtt_protocol
+        .inflight_expire_sec;
+    let now = now_second();
+    messages.sort_by_key(|message| (message.create_time, message.pkid));
+
+    // Take every packet id up front so new deliveries cannot reuse one that
+    // is about to be redelivered. An id already in use is still owned by a
+    // delivery that started before the disconnect; its ack removes the entry.
+    let mut pending = Vec::new();
+    for message in messages {
+        if message.is_expired(expire_sec, now) {
+            delete_inflight(context, &message, "expired").await;
+            continue;
+        }
+        if !context
+            .cache_manager
+            .pkid_manager
+            .reserve_publish_to_client_pkid(&context.client_id, message.pkid)
+        {
+            debug!(
+                "Inflight message still being delivered, client_id={}, pkid={}",
+                context.client_id, message.pkid
+            );
+            continue;
+        }
+        pending.push(message);
+    }
+
+    info!(
+        "Redelivering {} inflight messages, client_id={}",
+        pending.len(),
+        context.client_id
+    );
+
+    let mut pending = pending.into_iter();
+    for message in pending.by_ref() {
+        let result = redeliver_inflight_message(context, message.clone()).await;
+        context
+            .cache_manager
+            .pkid_manager
+            .remove_publish_to_client_pkid(&context.client_id, message.pkid);
+        match result {
+            Ok(()) => {}
+            Err(e) if client_unavailable_error(&e) => {
+                // Left in storage for the next resumption.
+                break;
+            }
+            Err(e) => {
+                warn!(
+                    "Inflight message redelivery failed, client_id={}, pkid={}, error={}",
+                    message.client_id, message.pkid, e
+                );
+                delete_inflight(context, &message, "").await;
+            }
+        }
+    }
+
+    for message in pending {
+        context
+            .cache_manager
+            .pkid_manager
+            .remove_publish_to_client_pkid(&context.client_id, message.pkid);
+    }
+    Ok(())
+}
+
+async fn redeliver_inflight_message(
+    context: &InflightRedeliveryContext,
+    mut message: InflightMessage,
+) -> ResultMqttBrokerError {
+    let result = match message.stage {
+        InflightStage::Publish => {
+            let Some(record) = read_inflight_record(context, &message).await? else {
+                delete_inflight(context, &message, "record_missing").await;
+                return Ok(());
+            };
+
+            let param = build_publish_message_with_pkid(
+                &context.cache_manager,
+                &context.connection_manager,
+                &record,
+                &message.subscriber,
+                message.pkid,
+            )?;
+            let param = build_dup_publish_param(&param)?;
+
+            message.send_count += 1;
+            if let Err(e) = context
+                .inflight_storage
+                .save_inflight_message(&message)
+                .await
+            {
+                warn!(
+                    "Failed to update inflight send count, client_id={}, pkid={}, error={}",
+                    message.client_id, message.pkid, e
+                );
+            }
+
+            send_publish_packet_to_client(
+                &context.connection_manager,
+                &context.cache_manager,
+                &param,
+                &context.stop_sx,
+            )
+            .await
+        }
+        InflightStage::PubRel => {
+            let param = SubPublishParam {
+                packet: MqttPacket::PubRel(
+                    PubRel {
+                        pkid: message.pkid,
+                        reason: Some(PubRelReason::Success),
+                    },
+                    None,
+                ),
+                create_time: now_second(),
+                client_id: message.client_id.clone(),
+                p_kid: message.pkid,
+                qos: message.qos,
+            };
+            resend_pubrel_to_client(
+                &context.connection_manager,
+                &context.cache_manager,
+                &param,
+                &context.stop_sx,
+            )
+            .await
+        }
+    };
+
+    if result.is_ok() {
+        record_mqtt_inflight_redelivered();
+    }
+    result
+}
+
+async fn read_inflight_record(
+    context: &InflightRedeliveryContext,
+    message: &InflightMessage,
+) -> Result<Option<StorageRecord>, MqttBrokerError> {
+    let read_config = AdapterReadConfig {
+        max_record_num: 1,
+        max_size: 10 * 1024 * 1024,
+    };
+    let records = context
+        .inflight_storage
+        .storage_driver_manager()
+        .read_by_shard_offset(
+            &message.tenant,
+            &message.subscriber.topic_name,
+            &message.shard,
+            message.offset,
+            &read_config,
+        )
+        .await?;
+    Ok(records
+        .into_iter()
+        .find(|record| record.metadata.offset == message.offset && !message_is_expire(record)))
+}
+
+/// `reason` is recorded in the drop metric; empty for failed deliveries.
+async fn delete_inflight(
+    context: &InflightRedeliveryContext,
+    message: &InflightMessage,
+    reason: &str,
+) {
+    if !reason.is_empty() {
+        record_mqtt_inflight_dropped(reason);
+    }
+    if let Err(e) = context
+        .inflight_storage
+        .delete_inflight_message(&message.tenant, &message.client_id, message.pkid)
+        .await
+    {
+        warn!(
+            "Failed to delete inflight message, client_id={}, pkid={}, error={}",
+            message.client_id, message.pkid, e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use metadata_struct::storage::record::StorageRecordMetadata;
+
+    #[test]
+    fn test_inflight_message_new_and_expire() {
+        let subscriber = Subscriber {
+            client_id: "c1".to_string(),
+            tenant: "t1".to_string(),
+            topic_name: "a/b".to_string(),
+            qos: QoS::ExactlyOnce,
+            ..Default::default()
+        };
+        let record = StorageRecord {
+            metadata: StorageRecordMetadata {
+                shard: "s0".to_string(),
+                offset: 42,
+                ..Default::default()
+            },
+            protocol_data: None,
+            data: Bytes::new(),
+        };
+        let param = SubPublishParam {
+            packet: MqttPacket::PubRel(
+                PubRel {
+                    pkid: 7,
+                    reason: None,
+                },
+                None,
+            ),
+            create_time: 0,
+            client_id: "c1".to_string(),
+            p_kid: 7,
+            qos: QoS::ExactlyOnce,
+        };
+
+        let mut message = InflightMessage::new(&subscriber, &record, &param);
+        assert_eq!(message.tenant, "t1");
+        assert_eq!(message.pkid, 7);
+        assert_eq!(message.stage, InflightStage::Publish);
+        assert_eq!((message.shard.as_str(), message.offset), ("s0", 42));
+        assert_eq!(message.send_count, 1);
+
+        message.create_time = 1_000;
+        assert!(!message.is_expired(0, 10_000));
+        assert!(!message.is_expired(60, 1_060));
+        assert!(message.is_expired(60, 1_061));
+    }
+}
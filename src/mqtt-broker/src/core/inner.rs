@@ -16,6 +16,7 @@
 use crate::core::error::MqttBrokerError;
 use crate::core::last_will::send_last_will_message;
 use crate::storage::last_will::LastWillStorage;
+use crate::storage::session::SessionStorage;
 use broker_core::tool::wait_cluster_running;
 use grpc_clients::pool::ClientPool;
 use protocol::broker::broker::{SendLastWillMessageReply, SendLastWillMessageRequest};
@@ -39,6 +40,7 @@ pub async fn send_last_will_message_by_req(
     );
 
     let last_will_storage = LastWillStorage::new(storage_driver_manager.clone());
+    let session_storage = SessionStorage::new(client_pool.clone());
 
     for item in &req.items {
         let data = match last_will_storage
@@ -62,6 +64,36 @@ pub async fn send_last_will_message_by_req(
             }
         };
 
+        // Every broker receives the notification; only the one granted the
+        // session epoch publishes. Wills saved before epochs existed are sent
+        // as before.
+        if data.session_epoch > 0 {
+            match session_storage
+                .claim_last_will(
+                    item.tenant.clone(),
+                    item.client_id.clone(),
+                    data.session_epoch,
+                )
+                .await
+            {
+                Ok(true) => {}
+                Ok(false) => {
+                    debug!(
+                        "Last will already claimed by another broker, tenant={}, client_id={}, session_epoch={}",
+                        item.tenant, item.client_id, data.session_epoch
+                    );
+                    continue;
+                }
+                Err(e) => {
+                    warn!(
+                        "Failed to claim last will message for tenant={}, client_id={}: {}",
+                        item.tenant, item.client_id, e
+                    );
+                    continue;
+                }
+            }
+        }
+
         if let Err(e) =
             send_last_will_message(cache_manager, storage_driver_manager, client_pool, &data).await
         {
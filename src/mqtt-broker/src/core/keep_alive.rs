@@ -16,6 +16,7 @@
 use super::connection::disconnect_connection;
 use crate::core::connection::build_server_disconnect_conn_context;
 use crate::core::error::MqttBrokerError;
+use crate::core::event::{st_report_disconnected_event, EventReportManager};
 use crate::mqtt::disconnect::build_distinct_packet;
 use crate::storage::session::SessionBatcher;
 use crate::subscribe::manager::SubscribeManager;
@@ -23,7 +24,8 @@
 use bytes::BytesMut;
 use common_base::error::ResultCommonError;
 use common_base::tools::{loop_select_ticket, now_second};
-use common_metrics::mqtt::event::record_mqtt_connection_expired;
+use common_config::config::MqttKeepAlive;
+use common_metrics::mqtt::event::{record_mqtt_connection_expired, record_mqtt_keep_alive_timeout};
 use grpc_clients::pool::ClientPool;
 use metadata_struct::connection::NetworkConnection;
 use metadata_struct::mqtt::connection::MQTTConnection;
@@ -58,6 +60,7 @@ pub struct ClientKeepAlive {
     session_batcher: Arc<SessionBatcher>,
     connection_manager: Arc<ConnectionManager>,
     subscribe_manager: Arc<SubscribeManager>,
+    event_manager: Arc<EventReportManager>,
 }
 
 impl ClientKeepAlive {
@@ -67,6 +70,7 @@ pub fn new(
         connection_manager: Arc<ConnectionManager>,
         subscribe_manager: Arc<SubscribeManager>,
         cache_manager: Arc<MQTTCacheManager>,
+        event_manager: Arc<EventReportManager>,
     ) -> Self {
         ClientKeepAlive {
             client_pool,
@@ -74,6 +78,7 @@ pub fn new(
             connection_manager,
             subscribe_manager,
             cache_manager,
+            event_manager,
         }
     }
 
@@ -96,12 +101,24 @@ async fn keep_alive(&self) -> ResultCommonError {
             };
 
             if let Some(network) = self.connection_manager.get_connect(connect_id) {
+                if let Some(session) = self.cache_manager.get_session_info(&connection.client_id) {
+                    st_report_disconnected_event(
+                        &self.event_manager,
+                        &self.connection_manager,
+                        connect_id,
+                        &connection,
+                        &session,
+                        Some(DisconnectReasonCode::KeepAliveTimeout),
+                    )
+                    .await;
+                }
+
                 let protocol = network.protocol.clone().unwrap();
                 let resp = build_distinct_packet(
                     &self.cache_manager,
                     connect_id,
                     &protocol.to_mqtt(),
-                    Some(DisconnectReasonCode::NormalDisconnection),
+                    Some(DisconnectReasonCode::KeepAliveTimeout),
                     None,
                     Some("keep alive timeout".to_string()),
                 );
@@ -173,6 +190,7 @@ async fn keep_alive(&self) -> ResultCommonError {
                             }
                         } else {
                             record_mqtt_connection_expired();
+                            record_mqtt_keep_alive_timeout(&String::from(protocol.clone()));
                             info!(
                                 "Heartbeat timeout, purged zombie connection {} (network already closed)",
                                 connect_id
@@ -205,6 +223,7 @@ async fn keep_alive(&self) -> ResultCommonError {
     }
 
     async fn get_expire_connection(&self) -> Vec<u64> {
+        let config = self.cache_manager.node_cache.cluster_config.load();
         let mut expire_connection = Vec::new();
         for (connect_id, connection) in self
             .cache_manager
@@ -219,10 +238,10 @@ async fn get_expire_connection(&self) -> Vec<u64> {
                 .get(&connection.client_id)
                 .map(|r| r.clone())
             {
-                // Clone releases the DashMap shard lock before .await
-                let max_timeout = keep_live_time(&self.cache_manager, time.keep_live).await as u64;
+                // Clone releases the DashMap shard lock
+                let max_timeout = keep_alive_timeout(&config.mqtt_keep_alive, time.keep_live);
                 let now = now_second();
-                if (now - time.heartbeat) >= max_timeout {
+                if now.saturating_sub(time.heartbeat) >= max_timeout {
                     debug!("{},client_id:{},now:{},heartbeat:{}","Connection was closed by the server because the heartbeat timeout was not reported.",connection.client_id,now,time.heartbeat);
                     expire_connection.push(connect_id);
                 }
@@ -262,6 +281,18 @@ async fn try_send_disconnect(context: &TrySendDistinctPacketContext) {
             cm.write_quic_frame(connect_id, response)
                 .await
                 .map_err(|e| e.to_string())
+        } else if cm.is_mqttsn(connect_id) {
+            cm.write_mqttsn_frame(connect_id, response)
+                .await
+                .map_err(|e| e.to_string())
+        } else if cm.is_coap(connect_id) {
+            cm.write_coap_frame(connect_id, response)
+                .await
+                .map_err(|e| e.to_string())
+        } else if cm.is_web_subscribe(connect_id) {
+            cm.write_web_subscribe_frame(connect_id, response)
+                .await
+                .map_err(|e| e.to_string())
         } else {
             cm.write_tcp_frame(connect_id, response)
                 .await
@@ -304,28 +335,45 @@ async fn close_connect(context: &TrySendDistinctPacketContext) -> Result<(), Mqt
     )?;
     disconnect_connection(context).await?;
     record_mqtt_connection_expired();
+    record_mqtt_keep_alive_timeout(&String::from(context.protocol.clone()));
     Ok(())
 }
 
-pub async fn keep_live_time(cache_manager: &Arc<MQTTCacheManager>, keep_alive: u16) -> u16 {
-    let config = cache_manager.node_cache.get_cluster_config();
-    keep_alive * config.mqtt_keep_alive.default_timeout
+pub async fn keep_live_time(cache_manager: &Arc<MQTTCacheManager>, keep_alive: u16) -> u64 {
+    let config = cache_manager.node_cache.cluster_config.load();
+    keep_alive_timeout(&config.mqtt_keep_alive, keep_alive)
 }
 
-pub async fn client_keep_live_time(
-    cache_manager: &Arc<MQTTCacheManager>,
-    mut keep_alive: u16,
-) -> u16 {
-    let config = cache_manager.node_cache.get_cluster_config();
-    if keep_alive == 0 {
-        keep_alive = config.mqtt_keep_alive.default_time;
-    }
-    if keep_alive > config.mqtt_keep_alive.max_time {
-        keep_alive = config.mqtt_keep_alive.max_time / config.mqtt_keep_alive.default_timeout;
+pub async fn client_keep_live_time(cache_manager: &Arc<MQTTCacheManager>, keep_alive: u16) -> u16 {
+    let config = cache_manager.node_cache.cluster_config.load();
+    negotiate_keep_alive(&config.mqtt_keep_alive, keep_alive)
+}
+
+/// The keep alive a connection runs with. A client asking for none gets
+/// `default_time`, and values whose timeout would exceed `max_time` are
+/// lowered; MQTT 5 clients are told through Server Keep Alive in CONNACK.
+pub fn negotiate_keep_alive(config: &MqttKeepAlive, keep_alive: u16) -> u16 {
+    let keep_alive = if keep_alive == 0 {
+        config.default_time
+    } else {
+        keep_alive
+    };
+    if keep_alive > config.max_time {
+        return (config.max_time as f32 / timeout_multiplier(config)) as u16;
     }
     keep_alive
 }
 
+/// Seconds without any packet from the client after which it is
+/// disconnected with Keep Alive Timeout.
+pub fn keep_alive_timeout(config: &MqttKeepAlive, keep_alive: u16) -> u64 {
+    (keep_alive as f32 * timeout_multiplier(config)).ceil() as u64
+}
+
+fn timeout_multiplier(config: &MqttKeepAlive) -> f32 {
+    config.timeout_multiplier.max(1.0)
+}
+
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub struct KeepAliveRunInfo {
     pub start_time: u128,
@@ -335,7 +383,8 @@ pub struct KeepAliveRunInfo {
 
 #[cfg(test)]
 mod test {
-    use super::keep_live_time;
+    use super::{keep_alive_timeout, keep_live_time, negotiate_keep_alive};
+    use crate::core::event::EventReportManager;
     use crate::core::keep_alive::{client_keep_live_time, ClientKeepAlive};
     use crate::core::tool::test_build_mqtt_cache_manager;
     use crate::storage::session::SessionBatcher;
@@ -343,6 +392,7 @@ mod test {
     use common_base::tools::{local_hostname, now_second};
 
     use common_base::uuid::unique_id;
+    use common_config::config::MqttKeepAlive;
     use grpc_clients::pool::ClientPool;
     use metadata_struct::mqtt::connection::{ConnectionConfig, MQTTConnection};
     use metadata_struct::mqtt::session::MqttSession;
@@ -359,29 +409,50 @@ pub async fn keep_live_test() {
         let keep_alive = 0;
         let client_live = client_keep_live_time(&cache_manager, keep_alive).await;
         assert_eq!(client_live, 180);
-        assert_eq!(keep_live_time(&cache_manager, client_live).await, 360);
+        assert_eq!(keep_live_time(&cache_manager, client_live).await, 270);
 
         let keep_alive = 50;
         let client_live = client_keep_live_time(&cache_manager, keep_alive).await;
         assert_eq!(client_live, 50);
-        assert_eq!(keep_live_time(&cache_manager, client_live).await, 100);
+        assert_eq!(keep_live_time(&cache_manager, client_live).await, 75);
 
         let keep_alive = 100;
         let client_live = client_keep_live_time(&cache_manager, keep_alive).await;
         assert_eq!(client_live, 100);
-        assert_eq!(keep_live_time(&cache_manager, client_live).await, 200);
+        assert_eq!(keep_live_time(&cache_manager, client_live).await, 150);
 
         let keep_alive = 500;
         let client_live = client_keep_live_time(&cache_manager, keep_alive).await;
         assert_eq!(client_live, 500);
-        assert_eq!(keep_live_time(&cache_manager, client_live).await, 1000);
+        assert_eq!(keep_live_time(&cache_manager, client_live).await, 750);
 
         let keep_alive = 4000;
         let client_live = client_keep_live_time(&cache_manager, keep_alive).await;
-        assert_eq!(client_live, 1800);
+        assert_eq!(client_live, 2400);
         assert_eq!(keep_live_time(&cache_manager, client_live).await, 3600);
     }
 
+    #[test]
+    fn keep_alive_multiplier_test() {
+        let mut config = MqttKeepAlive {
+            enable: true,
+            default_time: 60,
+            max_time: 600,
+            timeout_multiplier: 2.0,
+        };
+        assert_eq!(negotiate_keep_alive(&config, 0), 60);
+        assert_eq!(negotiate_keep_alive(&config, 1000), 300);
+        assert_eq!(keep_alive_timeout(&config, 300), 600);
+        assert_eq!(keep_alive_timeout(&config, 5), 10);
+
+        config.timeout_multiplier = 1.5;
+        assert_eq!(keep_alive_timeout(&config, 5), 8);
+
+        // below 1.0 would close connections before the client is due to ping
+        config.timeout_multiplier = 0.5;
+        assert_eq!(keep_alive_timeout(&config, 10), 10);
+    }
+
     #[tokio::test]
     pub async fn get_expire_connection_test() {
         let client_pool = Arc::new(ClientPool::new(100));
@@ -395,6 +466,7 @@ pub async fn get_expire_connection_test() {
             connection_manager,
             subscribe_manager,
             cache_manager.clone(),
+            EventReportManager::new(),
         );
 
         let client_id = unique_id();
@@ -438,7 +510,7 @@ pub async fn get_expire_connection_test() {
         }
         assert_eq!(
             (now_second() - start),
-            keep_live_time(&cache_manager, keep_alive).await as u64
+            keep_live_time(&cache_manager, keep_alive).await
         );
     }
 }
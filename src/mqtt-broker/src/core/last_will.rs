@@ -20,6 +20,7 @@
 use crate::core::{retain::save_retain_message, tool::ResultMqttBrokerError};
 use crate::storage::last_will::LastWillStorage;
 use crate::storage::message::MessageStorage;
+use crate::storage::session::SessionStorage;
 use bytes::Bytes;
 use grpc_clients::pool::ClientPool;
 use metadata_struct::mqtt::lastwill::MqttLastWillData;
@@ -129,17 +130,22 @@ pub async fn save_last_will_message(
     last_will: &Option<LastWill>,
     last_will_properties: &Option<LastWillProperties>,
     storage_driver_manager: &Arc<StorageDriverManager>,
+    client_pool: &Arc<ClientPool>,
 ) -> ResultMqttBrokerError {
     if last_will.is_none() {
         return Ok(());
     }
 
+    let session_epoch = SessionStorage::new(client_pool.clone())
+        .allocate_last_will_epoch(tenant.to_string(), client_id.to_string())
+        .await?;
     let last_will_storage = LastWillStorage::new(storage_driver_manager.clone());
     let lastwill = MqttLastWillData {
         tenant: tenant.to_string(),
         client_id: client_id.to_string(),
         last_will: last_will.clone(),
         last_will_properties: last_will_properties.clone(),
+        session_epoch,
     };
 
     last_will_storage
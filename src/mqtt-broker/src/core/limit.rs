@@ -15,6 +15,10 @@
 use metadata_struct::mqtt::connection::MQTTConnection;
 
 use crate::core::cache::MQTTCacheManager;
+use crate::core::error::MqttBrokerError;
+use crate::subscribe::manager::SubscribeManager;
+use common_base::tools::now_second;
+use common_config::config::MqttProtocolConfig;
 use std::sync::Arc;
 
 pub async fn connection_total_num_limit(
@@ -92,6 +96,93 @@ pub async fn topic_total_num_limit(cache_manager: &Arc<MQTTCacheManager>, tenant
     false
 }
 
+pub fn subscribe_total_num_limit(
+    cache_manager: &Arc<MQTTCacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    tenant: &str,
+    new_num: usize,
+) -> bool {
+    // cluster
+    let count = subscribe_manager.subscribe_count() + new_num;
+    let limit_count = cache_manager
+        .node_cache
+        .get_cluster_config()
+        .mqtt_limit
+        .cluster
+        .max_subscriptions as usize;
+    if count > limit_count {
+        return true;
+    }
+
+    // tenant
+    if let Some(ten) = cache_manager.node_cache.get_tenant(tenant) {
+        let count = subscribe_manager.subscribe_count_by_tenant(tenant) + new_num;
+        if count > ten.config.max_subscriptions as usize {
+            return true;
+        }
+    }
+
+    false
+}
+
+pub fn client_subscribe_num_limit(
+    cache_manager: &Arc<MQTTCacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    tenant: &str,
+    client_id: &str,
+    new_num: usize,
+) -> bool {
+    let limit = cache_manager
+        .node_cache
+        .get_cluster_config()
+        .mqtt_protocol
+        .max_subscriptions_per_client;
+    if limit == 0 {
+        return false;
+    }
+    subscribe_manager.subscribe_count_by_client(tenant, client_id) + new_num > limit as usize
+}
+
+/// Checks a topic name, or a topic filter with its `$share`/`$exclusive`
+/// prefix stripped, against the cluster length and level limits.
+pub fn topic_depth_limit(
+    protocol: &MqttProtocolConfig,
+    topic: &str,
+) -> Result<(), MqttBrokerError> {
+    if topic.len() > protocol.max_topic_length as usize {
+        return Err(MqttBrokerError::TopicLengthExceeded(
+            topic.to_string(),
+            protocol.max_topic_length,
+            topic.len(),
+        ));
+    }
+
+    let levels = topic.split('/').count();
+    if levels > protocol.max_topic_levels as usize {
+        return Err(MqttBrokerError::TopicLevelsExceeded(
+            topic.to_string(),
+            protocol.max_topic_levels,
+            levels,
+        ));
+    }
+    Ok(())
+}
+
+pub fn storage_bytes_limit(
+    cache_manager: &Arc<MQTTCacheManager>,
+    tenant: &str,
+    new_bytes: u64,
+) -> bool {
+    if let Some(ten) = cache_manager.node_cache.get_tenant(tenant) {
+        let limit = ten.config.max_storage_bytes_per_node;
+        if limit > 0 {
+            let used = cache_manager.get_tenant_storage_bytes(tenant, now_second());
+            return used + new_bytes > limit;
+        }
+    }
+    false
+}
+
 pub fn qos_flight_message_num_limit(
     cache_manager: &Arc<MQTTCacheManager>,
     connection: &MQTTConnection,
@@ -101,3 +192,28 @@ pub fn qos_flight_message_num_limit(
         .get_qos_pkid_data_len_by_client_id(&connection.client_id);
     len > connection.client_max_receive_maximum as usize
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn topic_depth_limit_test() {
+        let protocol = MqttProtocolConfig {
+            max_topic_levels: 3,
+            max_topic_length: 16,
+            ..Default::default()
+        };
+
+        assert!(topic_depth_limit(&protocol, "a/b/c").is_ok());
+        assert!(topic_depth_limit(&protocol, "a/b/#").is_ok());
+        assert!(matches!(
+            topic_depth_limit(&protocol, "a/b/c/d"),
+            Err(MqttBrokerError::TopicLevelsExceeded(_, 3, 4))
+        ));
+        assert!(matches!(
+            topic_depth_limit(&protocol, "sensor/temperature"),
+            Err(MqttBrokerError::TopicLengthExceeded(_, 16, 18))
+        ));
+    }
+}
@@ -0,0 +1,66 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Expiry of the records the broker keeps in local RocksDB: system alarm
+//! events, ban logs and slow subscription logs. Each type has its own
+//! retention in the cluster config, read again on every sweep so dynamic
+//! config updates apply without a restart.
+
+use crate::core::cache::MQTTCacheManager;
+use crate::core::error::MqttBrokerError;
+use crate::storage::local::LocalStorage;
+use common_base::error::common::CommonError;
+use common_base::error::ResultCommonError;
+use common_base::tools::loop_select_ticket;
+use rocksdb_engine::rocksdb::RocksDBEngine;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tracing::debug;
+
+const LOCAL_LOG_EXPIRE_INTERVAL_MS: u64 = 10 * 60 * 1000;
+
+pub async fn start_local_log_expire(
+    rocksdb_engine_handler: Arc<RocksDBEngine>,
+    cache_manager: Arc<MQTTCacheManager>,
+    stop_send: broadcast::Sender<bool>,
+) {
+    let local_storage = LocalStorage::new(rocksdb_engine_handler);
+    let ac_fn = async || -> ResultCommonError {
+        expire_local_log(&local_storage, &cache_manager)
+            .await
+            .map_err(|e| CommonError::CommonError(e.to_string()))
+    };
+    loop_select_ticket(ac_fn, LOCAL_LOG_EXPIRE_INTERVAL_MS, &stop_send).await;
+}
+
+async fn expire_local_log(
+    local_storage: &LocalStorage,
+    cache_manager: &Arc<MQTTCacheManager>,
+) -> Result<(), MqttBrokerError> {
+    let config = cache_manager.node_cache.get_cluster_config();
+    let system_events = local_storage
+        .expire_system_event(config.mqtt_system_monitor.system_event_retention_sec)
+        .await?;
+    let ban_logs = local_storage
+        .expire_ban_log(config.mqtt_flapping_detect.ban_log_retention_sec)
+        .await?;
+    let slow_sub_logs = local_storage
+        .expire_slow_sub_log(config.mqtt_slow_subscribe.log_retention_sec)
+        .await?;
+    debug!(
+        "Expired local logs: {} system events, {} ban logs, {} slow subscription logs",
+        system_events, ban_logs, slow_sub_logs
+    );
+    Ok(())
+}
@@ -0,0 +1,168 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Message path latency tracking.
+//!
+//! Stored messages carry their store time in the internal
+//! `robustmq-store-ms` record header, which is stripped before a message is
+//! pushed to MQTT subscribers. Together with the receive time in the publish
+//! handler and the send time kept with a pending QoS ack, it yields the
+//! receive→store, store→push and push→ack histograms. A trace id is taken
+//! from the W3C `traceparent` user property, or from `trace-id`, and attached
+//! to each observation as an exemplar.
+
+use common_base::tools::now_millis;
+use common_metrics::mqtt::message_path::record_message_store_to_push;
+use metadata_struct::adapter::adapter_record::RecordHeader;
+use metadata_struct::storage::record::StorageRecord;
+use protocol::mqtt::common::{MqttPacket, PublishProperties};
+
+/// Record header holding the time, in milliseconds, the message was stored.
+pub const MESSAGE_STORE_TIME_HEADER: &str = "robustmq-store-ms";
+
+const TRACEPARENT_PROPERTY: &str = "traceparent";
+const TRACE_ID_PROPERTY: &str = "trace-id";
+
+// OpenMetrics caps an exemplar's label set at 128 characters.
+const MAX_TRACE_ID_LEN: usize = 64;
+
+pub fn store_time_header() -> RecordHeader {
+    RecordHeader {
+        name: MESSAGE_STORE_TIME_HEADER.to_string(),
+        value: now_millis().to_string(),
+    }
+}
+
+/// Trace id of a message, from `traceparent` (`00-<trace-id>-<span-id>-<flags>`)
+/// or a plain `trace-id` user property.
+pub fn trace_id_from_user_properties(user_properties: &[(String, String)]) -> Option<String> {
+    let value = |key: &str| {
+        user_properties
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.trim())
+    };
+
+    if let Some(traceparent) = value(TRACEPARENT_PROPERTY) {
+        let mut parts = traceparent.split('-');
+        if let (Some(_version), Some(trace_id)) = (parts.next(), parts.next()) {
+            if trace_id.len() == 32 && trace_id.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Some(trace_id.to_ascii_lowercase());
+            }
+        }
+    }
+
+    value(TRACE_ID_PROPERTY)
+        .filter(|id| !id.is_empty() && id.len() <= MAX_TRACE_ID_LEN)
+        .map(str::to_string)
+}
+
+pub fn trace_id_from_publish_properties(properties: &Option<PublishProperties>) -> Option<String> {
+    properties
+        .as_ref()
+        .and_then(|p| trace_id_from_user_properties(&p.user_properties))
+}
+
+pub fn trace_id_from_packet(packet: &MqttPacket) -> Option<String> {
+    match packet {
+        MqttPacket::Publish(_, properties) => trace_id_from_publish_properties(properties),
+        _ => None,
+    }
+}
+
+pub fn trace_id_from_record(record: &StorageRecord) -> Option<String> {
+    record
+        .protocol_data
+        .as_ref()
+        .and_then(|p| p.mqtt.as_ref())
+        .and_then(|mqtt| trace_id_from_user_properties(&mqtt.user_properties))
+}
+
+fn store_time_ms(record: &StorageRecord) -> Option<u128> {
+    record
+        .metadata
+        .header
+        .as_ref()?
+        .iter()
+        .find(|h| h.name == MESSAGE_STORE_TIME_HEADER)?
+        .value
+        .parse()
+        .ok()
+}
+
+/// Observe store→push for a stored message about to be pushed. Messages
+/// stored before the header existed are skipped.
+pub fn record_store_to_push(tenant: &str, record: &StorageRecord) {
+    let Some(stored_at) = store_time_ms(record) else {
+        return;
+    };
+    let duration_ms = now_millis().saturating_sub(stored_at) as f64;
+    record_message_store_to_push(tenant, duration_ms, trace_id_from_record(record).as_deref());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metadata_struct::storage::record::{StorageHeader, StorageRecordMetadata};
+
+    fn props(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn trace_id_prefers_traceparent() {
+        let properties = props(&[
+            ("trace-id", "plain"),
+            (
+                "traceparent",
+                "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            ),
+        ]);
+        assert_eq!(
+            trace_id_from_user_properties(&properties).as_deref(),
+            Some("4bf92f3577b34da6a3ce929d0e0e4736")
+        );
+
+        let properties = props(&[("traceparent", "garbage"), ("trace-id", "abc")]);
+        assert_eq!(
+            trace_id_from_user_properties(&properties).as_deref(),
+            Some("abc")
+        );
+
+        let long = "x".repeat(MAX_TRACE_ID_LEN + 1);
+        assert_eq!(
+            trace_id_from_user_properties(&props(&[("trace-id", &long)])),
+            None
+        );
+        assert_eq!(trace_id_from_user_properties(&[]), None);
+    }
+
+    #[test]
+    fn store_time_read_from_header() {
+        let mut record = StorageRecord {
+            metadata: StorageRecordMetadata::build(1, "shard".to_string(), 0),
+            protocol_data: None,
+            data: Default::default(),
+        };
+        assert_eq!(store_time_ms(&record), None);
+
+        record.metadata.header = Some(vec![StorageHeader {
+            name: MESSAGE_STORE_TIME_HEADER.to_string(),
+            value: "1700000000123".to_string(),
+        }]);
+        assert_eq!(store_time_ms(&record), Some(1700000000123));
+    }
+}
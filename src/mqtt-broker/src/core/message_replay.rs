@@ -0,0 +1,610 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Replay of stored messages.
+//!
+//! A replay reads a topic's shards between two offsets or timestamps with
+//! [`StorageDriverManager::read_by_shard_offset`] and either appends the
+//! records to another topic, where the usual push delivers them, or pushes
+//! them straight to one connected client. Replays run in the background at a
+//! bounded rate; their progress is kept in [`MessageReplayTasks`] for the
+//! admin API.
+
+use crate::core::cache::MQTTCacheManager;
+use crate::core::error::MqttBrokerError;
+use crate::core::topic::try_init_topic;
+use crate::storage::message::MessageStorage;
+use crate::subscribe::common::Subscriber;
+use crate::subscribe::push::push_data;
+use common_base::tools::now_second;
+use dashmap::DashMap;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::adapter::adapter_offset::AdapterOffsetStrategy;
+use metadata_struct::storage::adapter_read_config::AdapterReadConfig;
+use metadata_struct::storage::convert::convert_storage_record_to_adapter;
+use metadata_struct::storage::record::StorageRecord;
+use network_server::common::connection_manager::ConnectionManager;
+use protocol::mqtt::common::{MqttProtocol, QoS, RetainHandling};
+use rocksdb_engine::rocksdb::RocksDBEngine;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use storage_adapter::driver::StorageDriverManager;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+const REPLAY_BATCH_SIZE: u64 = 100;
+const REPLAY_MAX_FINISHED_TASKS: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MessageReplayTarget {
+    /// Append the records to this topic of the same tenant.
+    Topic(String),
+    /// Push the records to this connected client.
+    Client(String),
+}
+
+/// Range of a replay. Offsets are per shard, `end_offset` is exclusive;
+/// times are record creation times in seconds, both inclusive. Unset bounds
+/// are open.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageReplayRange {
+    pub start_offset: Option<u64>,
+    pub end_offset: Option<u64>,
+    pub start_time: Option<u64>,
+    pub end_time: Option<u64>,
+}
+
+impl MessageReplayRange {
+    pub fn validate(&self) -> Result<(), MqttBrokerError> {
+        if let (Some(start), Some(end)) = (self.start_offset, self.end_offset) {
+            if start >= end {
+                return Err(MqttBrokerError::CommonError(format!(
+                    "start_offset {} must be less than end_offset {}",
+                    start, end
+                )));
+            }
+        }
+        if let (Some(start), Some(end)) = (self.start_time, self.end_time) {
+            if start > end {
+                return Err(MqttBrokerError::CommonError(format!(
+                    "start_time {} must not be after end_time {}",
+                    start, end
+                )));
+            }
+        }
+        if self.end_offset.is_none() && self.end_time.is_none() {
+            return Err(MqttBrokerError::CommonError(
+                "Either end_offset or end_time must be set".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// What to do with a record read from a shard.
+    fn check(&self, record: &StorageRecord) -> ReplayDecision {
+        if self
+            .end_offset
+            .is_some_and(|end| record.metadata.offset >= end)
+            || self
+                .end_time
+                .is_some_and(|end| record.metadata.create_t > end)
+        {
+            return ReplayDecision::Stop;
+        }
+        if self
+            .start_time
+            .is_some_and(|start| record.metadata.create_t < start)
+        {
+            return ReplayDecision::Skip;
+        }
+        ReplayDecision::Replay
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ReplayDecision {
+    Replay,
+    Skip,
+    Stop,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageReplayTask {
+    pub tenant: String,
+    pub topic_name: String,
+    /// Replay one shard of the topic, or all of them when unset.
+    pub shard_name: Option<String>,
+    pub range: MessageReplayRange,
+    pub target: MessageReplayTarget,
+    /// QoS of messages pushed to a client.
+    pub qos: QoS,
+    /// Messages per second, 0 for no limit.
+    pub rate_limit: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MessageReplayStatus {
+    Running,
+    Finished,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageReplayProgress {
+    pub id: u64,
+    pub task: MessageReplayTask,
+    pub status: MessageReplayStatus,
+    pub replayed: u64,
+    /// Records read but outside the time range.
+    pub skipped: u64,
+    /// Shard being read and the next offset to read from it.
+    pub current_shard: String,
+    pub current_offset: u64,
+    pub error: Option<String>,
+    pub create_time: u64,
+    pub finish_time: u64,
+}
+
+struct ReplayEntry {
+    progress: MessageReplayProgress,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Replays of this node. Finished replays are kept for the admin API,
+/// dropping the oldest beyond a fixed cap.
+#[derive(Default)]
+pub struct MessageReplayTasks {
+    next_id: AtomicU64,
+    tasks: DashMap<u64, ReplayEntry>,
+}
+
+impl MessageReplayTasks {
+    fn add(&self, task: MessageReplayTask) -> (u64, Arc<AtomicBool>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.tasks.insert(
+            id,
+            ReplayEntry {
+                progress: MessageReplayProgress {
+                    id,
+                    task,
+                    status: MessageReplayStatus::Running,
+                    replayed: 0,
+                    skipped: 0,
+                    current_shard: String::new(),
+                    current_offset: 0,
+                    error: None,
+                    create_time: now_second(),
+                    finish_time: 0,
+                },
+                cancelled: cancelled.clone(),
+            },
+        );
+        self.trim();
+        (id, cancelled)
+    }
+
+    fn update(&self, id: u64, f: impl FnOnce(&mut MessageReplayProgress)) {
+        if let Some(mut entry) = self.tasks.get_mut(&id) {
+            f(&mut entry.progress);
+        }
+    }
+
+    fn finish(&self, id: u64, status: MessageReplayStatus, error: Option<String>) {
+        self.update(id, |progress| {
+            progress.status = status;
+            progress.error = error;
+            progress.finish_time = now_second();
+        });
+    }
+
+    fn trim(&self) {
+        let mut finished: Vec<u64> = self
+            .tasks
+            .iter()
+            .filter(|entry| entry.progress.status != MessageReplayStatus::Running)
+            .map(|entry| *entry.key())
+            .collect();
+        if finished.len() <= REPLAY_MAX_FINISHED_TASKS {
+            return;
+        }
+        finished.sort_unstable();
+        for id in &finished[..finished.len() - REPLAY_MAX_FINISHED_TASKS] {
+            self.tasks.remove(id);
+        }
+    }
+
+    pub fn get(&self, id: u64) -> Option<MessageReplayProgress> {
+        self.tasks.get(&id).map(|entry| entry.progress.clone())
+    }
+
+    pub fn list(&self) -> Vec<MessageReplayProgress> {
+        let mut results: Vec<MessageReplayProgress> = self
+            .tasks
+            .iter()
+            .map(|entry| entry.progress.clone())
+            .collect();
+        results.sort_by_key(|progress| progress.id);
+        results
+    }
+
+    /// Ask a running replay to stop after the message in flight.
+    pub fn cancel(&self, id: u64) -> Result<(), MqttBrokerError> {
+        let Some(entry) = self.tasks.get(&id) else {
+            return Err(MqttBrokerError::CommonError(format!(
+                "Message replay {} does not exist",
+                id
+            )));
+        };
+        if entry.progress.status != MessageReplayStatus::Running {
+            return Err(MqttBrokerError::CommonError(format!(
+                "Message replay {} is not running",
+                id
+            )));
+        }
+        entry.cancelled.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct MessageReplayContext {
+    pub cache_manager: Arc<MQTTCacheManager>,
+    pub connection_manager: Arc<ConnectionManager>,
+    pub storage_driver_manager: Arc<StorageDriverManager>,
+    pub rocksdb_engine_handler: Arc<RocksDBEngine>,
+    pub client_pool: Arc<ClientPool>,
+}
+
+/// Validate `task` and start it in the background, returning its id.
+pub async fn start_message_replay(
+    context: MessageReplayContext,
+    task: MessageReplayTask,
+) -> Result<u64, MqttBrokerError> {
+    task.range.validate()?;
+    let shards = context
+        .storage_driver_manager
+        .topic_shard_names(&task.tenant, &task.topic_name)?;
+    let shards = match &task.shard_name {
+        Some(shard_name) => {
+            if !shards.contains(shard_name) {
+                return Err(MqttBrokerError::CommonError(format!(
+                    "Shard {} does not belong to topic {}",
+                    shard_name, task.topic_name
+                )));
+            }
+            vec![shard_name.clone()]
+        }
+        None => shards,
+    };
+
+    let target = match &task.target {
+        MessageReplayTarget::Topic(topic_name) => {
+            if *topic_name == task.topic_name {
+                return Err(MqttBrokerError::CommonError(
+                    "Cannot replay a topic into itself".to_string(),
+                ));
+            }
+            try_init_topic(
+                &task.tenant,
+                topic_name,
+                false,
+                &context.cache_manager,
+                &context.storage_driver_manager,
+                &context.client_pool,
+            )
+            .await?;
+            ReplayOutput::Topic(topic_name.clone())
+        }
+        MessageReplayTarget::Client(client_id) => {
+            ReplayOutput::Client(Box::new(replay_subscriber(&context, &task, client_id)?))
+        }
+    };
+
+    let (id, cancelled) = context.cache_manager.message_replay.add(task.clone());
+    info!(
+        "Message replay {} started, topic {}, target {:?}",
+        id, task.topic_name, task.target
+    );
+    tokio::spawn(Box::pin(async move {
+        let result = run_replay(&context, id, &task, &shards, &target, &cancelled).await;
+        let tasks = &context.cache_manager.message_replay;
+        match result {
+            Ok(()) if cancelled.load(Ordering::Relaxed) => {
+                tasks.finish(id, MessageReplayStatus::Cancelled, None)
+            }
+            Ok(()) => tasks.finish(id, MessageReplayStatus::Finished, None),
+            Err(e) => {
+                warn!("Message replay {} failed: {}", id, e);
+                tasks.finish(id, MessageReplayStatus::Failed, Some(e.to_string()));
+            }
+        }
+    }));
+    Ok(id)
+}
+
+enum ReplayOutput {
+    Topic(String),
+    Client(Box<Subscriber>),
+}
+
+/// The pseudo subscription a client replay is pushed through. Messages keep
+/// the source topic name.
+fn replay_subscriber(
+    context: &MessageReplayContext,
+    task: &MessageReplayTask,
+    client_id: &str,
+) -> Result<Subscriber, MqttBrokerError> {
+    let connection = context
+        .cache_manager
+        .get_connect_id(client_id)
+        .and_then(|connect_id| context.cache_manager.get_connection(connect_id))
+        .ok_or_else(|| MqttBrokerError::ClientNoAvailableConnection(client_id.to_string()))?;
+    if connection.tenant != task.tenant {
+        return Err(MqttBrokerError::ClientNoAvailableConnection(
+            client_id.to_string(),
+        ));
+    }
+    let is_mqtt5 = context
+        .connection_manager
+        .get_connect_protocol(connection.connect_id)
+        .is_some_and(|protocol| protocol.is_mqtt5());
+    Ok(Subscriber {
+        client_id: client_id.to_string(),
+        sub_path: task.topic_name.clone(),
+        rewrite_sub_path: None,
+        tenant: task.tenant.clone(),
+        topic_name: task.topic_name.clone(),
+        group_name: String::new(),
+        protocol: if is_mqtt5 {
+            MqttProtocol::Mqtt5
+        } else {
+            MqttProtocol::Mqtt4
+        },
+        qos: task.qos,
+        no_local: false,
+        preserve_retain: false,
+        retain_forward_rule: RetainHandling::OnEverySubscribe,
+        subscription_identifier: None,
+        payload_decode: false,
+        create_time: now_second(),
+    })
+}
+
+async fn run_replay(
+    context: &MessageReplayContext,
+    id: u64,
+    task: &MessageReplayTask,
+    shards: &[String],
+    target: &ReplayOutput,
+    cancelled: &AtomicBool,
+) -> Result<(), MqttBrokerError> {
+    let tasks = &context.cache_manager.message_replay;
+    let message_storage = MessageStorage::new(context.storage_driver_manager.clone());
+    // push_data only uses the stop channel to abort its retries; replays are
+    // stopped through `cancelled` instead.
+    let (stop_sx, _) = broadcast::channel(1);
+    let read_config = AdapterReadConfig {
+        max_record_num: REPLAY_BATCH_SIZE,
+        max_size: 1024 * 1024 * 30,
+    };
+    let start = Instant::now();
+    let mut replayed: u64 = 0;
+
+    for shard_name in shards {
+        let mut offset = start_offset(context, task, shard_name).await?;
+        'shard: loop {
+            tasks.update(id, |progress| {
+                progress.current_shard = shard_name.clone();
+                progress.current_offset = offset;
+            });
+            if cancelled.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            let records = context
+                .storage_driver_manager
+                .read_by_shard_offset(
+                    &task.tenant,
+                    &task.topic_name,
+                    shard_name,
+                    offset,
+                    &read_config,
+                )
+                .await?;
+            if records.is_empty() {
+                break;
+            }
+
+            for record in records {
+                if cancelled.load(Ordering::Relaxed) {
+                    return Ok(());
+                }
+                offset = record.metadata.offset + 1;
+                match task.range.check(&record) {
+                    ReplayDecision::Stop => break 'shard,
+                    ReplayDecision::Skip => {
+                        tasks.update(id, |progress| progress.skipped += 1);
+                        continue;
+                    }
+                    ReplayDecision::Replay => {}
+                }
+
+                match target {
+                    ReplayOutput::Topic(topic_name) => {
+                        let protocol_data = record.protocol_data.clone();
+                        let mut write = convert_storage_record_to_adapter(record);
+                        write.topic = topic_name.clone();
+                        write.protocol_data = protocol_data;
+                        message_storage
+                            .append_topic_message(&task.tenant, topic_name, vec![write])
+                            .await?;
+                    }
+                    ReplayOutput::Client(subscriber) => {
+                        push_data(
+                            &context.connection_manager,
+                            &context.cache_manager,
+                            &context.rocksdb_engine_handler,
+                            subscriber,
+                            &record,
+                            &stop_sx,
+                            None,
+                        )
+                        .await?;
+                    }
+                }
+                replayed += 1;
+                tasks.update(id, |progress| {
+                    progress.replayed = replayed;
+                    progress.current_offset = offset;
+                });
+
+                let delay = replay_delay(replayed, task.rate_limit, start.elapsed());
+                if !delay.is_zero() {
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// First offset to read from a shard: `start_offset`, or the first record at
+/// or after `start_time`, but never before the earliest stored record.
+async fn start_offset(
+    context: &MessageReplayContext,
+    task: &MessageReplayTask,
+    shard_name: &str,
+) -> Result<u64, MqttBrokerError> {
+    let watermarks = context
+        .storage_driver_manager
+        .shard_watermarks(&task.tenant, &task.topic_name)
+        .await?;
+    let earliest = watermarks
+        .iter()
+        .find(|w| w.shard_name == shard_name)
+        .map(|w| w.earliest_offset)
+        .unwrap_or(0);
+    let offset = match (task.range.start_offset, task.range.start_time) {
+        (Some(offset), _) => offset,
+        (None, Some(timestamp)) => {
+            context
+                .storage_driver_manager
+                .get_offset_by_timestamp(
+                    &task.tenant,
+                    &task.topic_name,
+                    timestamp,
+                    AdapterOffsetStrategy::Earliest,
+                )
+                .await?
+        }
+        (None, None) => earliest,
+    };
+    Ok(offset.max(earliest))
+}
+
+/// How long to wait after `replayed` messages so the replay stays under
+/// `rate_limit` messages per second.
+fn replay_delay(replayed: u64, rate_limit: u32, elapsed: Duration) -> Duration {
+    if rate_limit == 0 {
+        return Duration::ZERO;
+    }
+    let expected = Duration::from_secs_f64(replayed as f64 / rate_limit as f64);
+    expected.saturating_sub(elapsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metadata_struct::storage::record::StorageRecordMetadata;
+
+    fn record(offset: u64, create_t: u64) -> StorageRecord {
+        let mut metadata = StorageRecordMetadata::build(offset, "s".to_string(), 0);
+        metadata.create_t = create_t;
+        StorageRecord {
+            metadata,
+            protocol_data: None,
+            data: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_range() {
+        let range = MessageReplayRange {
+            start_offset: Some(5),
+            end_offset: Some(10),
+            ..Default::default()
+        };
+        assert!(range.validate().is_ok());
+        assert_eq!(range.check(&record(5, 0)), ReplayDecision::Replay);
+        assert_eq!(range.check(&record(10, 0)), ReplayDecision::Stop);
+
+        let range = MessageReplayRange {
+            start_time: Some(100),
+            end_time: Some(200),
+            ..Default::default()
+        };
+        assert!(range.validate().is_ok());
+        assert_eq!(range.check(&record(0, 99)), ReplayDecision::Skip);
+        assert_eq!(range.check(&record(1, 200)), ReplayDecision::Replay);
+        assert_eq!(range.check(&record(2, 201)), ReplayDecision::Stop);
+
+        assert!(MessageReplayRange::default().validate().is_err());
+        assert!(MessageReplayRange {
+            start_offset: Some(10),
+            end_offset: Some(10),
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_replay_delay() {
+        assert_eq!(replay_delay(100, 0, Duration::ZERO), Duration::ZERO);
+        assert_eq!(
+            replay_delay(10, 10, Duration::from_millis(400)),
+            Duration::from_millis(600)
+        );
+        assert_eq!(replay_delay(10, 10, Duration::from_secs(2)), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_tasks() {
+        let tasks = MessageReplayTasks::default();
+        let task = MessageReplayTask {
+            tenant: "default".to_string(),
+            topic_name: "t1".to_string(),
+            shard_name: None,
+            range: MessageReplayRange {
+                end_offset: Some(10),
+                ..Default::default()
+            },
+            target: MessageReplayTarget::Topic("t2".to_string()),
+            qos: QoS::AtMostOnce,
+            rate_limit: 0,
+        };
+        let (id, cancelled) = tasks.add(task);
+        assert_eq!(tasks.get(id).unwrap().status, MessageReplayStatus::Running);
+
+        tasks.cancel(id).unwrap();
+        assert!(cancelled.load(Ordering::Relaxed));
+        tasks.finish(id, MessageReplayStatus::Cancelled, None);
+        assert!(tasks.cancel(id).is_err());
+        assert!(tasks.cancel(id + 1).is_err());
+        assert_eq!(tasks.list().len(), 1);
+    }
+}
@@ -0,0 +1,336 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rule engine on the publish path.
+//!
+//! Every message published by a client is matched against the tenant's
+//! enabled rules. A rule whose `FROM` filter and `WHERE` condition match runs
+//! its actions in order: republishing the `SELECT` output, writing it to a
+//! connector's source topic, or dropping the original message. Action failures
+//! are logged and counted but never fail the client's publish.
+
+use crate::core::cache::MQTTCacheManager;
+use crate::core::error::MqttBrokerError;
+use crate::core::offline_message::{save_message, SaveMessageContext};
+use crate::core::topic::try_init_topic;
+use crate::storage::message::MessageStorage;
+use crate::subscribe::manager::SubscribeManager;
+use bytes::Bytes;
+use common_base::tools::now_millis;
+use common_metrics::mqtt::rule_engine::{record_message_rule_action, record_message_rule_matched};
+use connector::manager::ConnectorManager;
+use delay_message::manager::DelayMessageManager;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::connection::MQTTConnection;
+use metadata_struct::mqtt::message_rule::{MqttMessageRule, MqttMessageRuleAction};
+use metadata_struct::storage::adapter_record::AdapterWriteRecord;
+use protocol::mqtt::common::{qos, Publish};
+use rule_engine::sql::{message_context, RuleMessage, RuleSql};
+use std::sync::Arc;
+use storage_adapter::driver::StorageDriverManager;
+use tracing::warn;
+
+pub struct CompiledMessageRule {
+    pub rule: MqttMessageRule,
+    pub sql: RuleSql,
+}
+
+/// Check a rule before it is stored or loaded into the cache.
+pub fn compile_message_rule(rule: MqttMessageRule) -> Result<CompiledMessageRule, MqttBrokerError> {
+    let sql = RuleSql::parse(&rule.sql)?;
+    if rule.actions.is_empty() {
+        return Err(MqttBrokerError::CommonError(format!(
+            "Message rule '{}' has no actions",
+            rule.name
+        )));
+    }
+
+    for action in rule.actions.iter() {
+        match action {
+            MqttMessageRuleAction::Republish {
+                topic, qos: num, ..
+            } => {
+                if topic.is_empty() || topic.contains('+') || topic.contains('#') {
+                    return Err(MqttBrokerError::CommonError(format!(
+                        "Message rule '{}' republish topic '{}' must be a non-empty topic name without wildcards",
+                        rule.name, topic
+                    )));
+                }
+                if qos(*num).is_none() {
+                    return Err(MqttBrokerError::CommonError(format!(
+                        "Message rule '{}' republish QoS {} is invalid",
+                        rule.name, num
+                    )));
+                }
+            }
+            MqttMessageRuleAction::Connector { connector_name } => {
+                if connector_name.is_empty() {
+                    return Err(MqttBrokerError::CommonError(format!(
+                        "Message rule '{}' connector action requires a connector name",
+                        rule.name
+                    )));
+                }
+            }
+            MqttMessageRuleAction::Drop => {}
+        }
+    }
+    Ok(CompiledMessageRule { rule, sql })
+}
+
+#[derive(Clone)]
+pub struct MessageRuleContext {
+    pub cache_manager: Arc<MQTTCacheManager>,
+    pub storage_driver_manager: Arc<StorageDriverManager>,
+    pub delay_message_manager: Arc<DelayMessageManager>,
+    pub subscribe_manager: Arc<SubscribeManager>,
+    pub client_pool: Arc<ClientPool>,
+    pub connector_manager: Arc<ConnectorManager>,
+}
+
+fn action_name(action: &MqttMessageRuleAction) -> &'static str {
+    match action {
+        MqttMessageRuleAction::Republish { .. } => "republish",
+        MqttMessageRuleAction::Connector { .. } => "connector",
+        MqttMessageRuleAction::Drop => "drop",
+    }
+}
+
+fn render_topic(template: &str, topic: &str, client_id: &str) -> String {
+    template
+        .replace("${topic}", topic)
+        .replace("${clientid}", client_id)
+}
+
+/// Run the tenant's rules for one publish. Returns `true` when a matching
+/// rule asked for the original message to be dropped.
+pub async fn apply_message_rules(
+    context: &MessageRuleContext,
+    connection: &MQTTConnection,
+    topic_name: &str,
+    publish: &Publish,
+) -> bool {
+    let rules: Vec<Arc<CompiledMessageRule>> =
+        match context.cache_manager.message_rule.get(&connection.tenant) {
+            Some(tenant_rules) => tenant_rules
+                .iter()
+                .filter(|entry| entry.rule.enable && entry.sql.match_topic(topic_name))
+                .map(|entry| entry.value().clone())
+                .collect(),
+            None => return false,
+        };
+    if rules.is_empty() {
+        return false;
+    }
+
+    let ctx = message_context(&RuleMessage {
+        topic: topic_name,
+        client_id: &connection.client_id,
+        username: connection.login_user.as_deref().unwrap_or_default(),
+        qos: publish.qos.into(),
+        retain: publish.retain,
+        payload: &publish.payload,
+        timestamp: now_millis(),
+        client_attrs: &connection.attributes,
+    });
+
+    let mut drop = false;
+    for compiled in rules {
+        let Some(output) = compiled.sql.evaluate(&ctx) else {
+            continue;
+        };
+        let rule = &compiled.rule;
+        record_message_rule_matched(&rule.tenant, &rule.name);
+
+        let payload = match serde_json::to_vec(&output) {
+            Ok(data) => Bytes::from(data),
+            Err(e) => {
+                warn!("Message rule {} failed to encode output: {}", rule.name, e);
+                continue;
+            }
+        };
+
+        for action in rule.actions.iter() {
+            let result = match action {
+                MqttMessageRuleAction::Republish {
+                    topic,
+                    qos: num,
+                    retain,
+                } => {
+                    let target = render_topic(topic, topic_name, &connection.client_id);
+                    republish(context, connection, &target, *num, *retain, payload.clone()).await
+                }
+                MqttMessageRuleAction::Connector { connector_name } => {
+                    forward_to_connector(context, &rule.tenant, connector_name, payload.clone())
+                        .await
+                }
+                MqttMessageRuleAction::Drop => {
+                    drop = true;
+                    Ok(())
+                }
+            };
+
+            if let Err(e) = &result {
+                warn!(
+                    "Message rule {} action {} failed: {}",
+                    rule.name,
+                    action_name(action),
+                    e
+                );
+            }
+            record_message_rule_action(
+                &rule.tenant,
+                &rule.name,
+                action_name(action),
+                result.is_ok(),
+            );
+        }
+    }
+    drop
+}
+
+async fn republish(
+    context: &MessageRuleContext,
+    connection: &MQTTConnection,
+    topic_name: &str,
+    num: u8,
+    retain: bool,
+    payload: Bytes,
+) -> Result<(), MqttBrokerError> {
+    let topic = try_init_topic(
+        &connection.tenant,
+        topic_name,
+        false,
+        &context.cache_manager,
+        &context.storage_driver_manager,
+        &context.client_pool,
+    )
+    .await?;
+
+    // written straight to storage, so a republished message is never
+    // evaluated against the rules again
+    let publish = Publish {
+        dup: false,
+        qos: qos(num).unwrap_or_default(),
+        p_kid: 0,
+        retain,
+        topic: Bytes::from(topic_name.to_string()),
+        payload,
+    };
+    save_message(SaveMessageContext {
+        storage_driver_manager: context.storage_driver_manager.clone(),
+        delay_message_manager: context.delay_message_manager.clone(),
+        cache_manager: context.cache_manager.clone(),
+        client_pool: context.client_pool.clone(),
+        publish,
+        publish_properties: None,
+        subscribe_manager: context.subscribe_manager.clone(),
+        client_id: connection.client_id.clone(),
+        topic,
+        delay_info: None,
+    })
+    .await?;
+    Ok(())
+}
+
+async fn forward_to_connector(
+    context: &MessageRuleContext,
+    tenant: &str,
+    connector_name: &str,
+    payload: Bytes,
+) -> Result<(), MqttBrokerError> {
+    let Some(connector) = context
+        .connector_manager
+        .get_connector_by_tenant(tenant, connector_name)
+    else {
+        return Err(MqttBrokerError::CommonError(format!(
+            "connector {} not found",
+            connector_name
+        )));
+    };
+
+    let topic = try_init_topic(
+        tenant,
+        &connector.topic_name,
+        false,
+        &context.cache_manager,
+        &context.storage_driver_manager,
+        &context.client_pool,
+    )
+    .await?;
+    let record = AdapterWriteRecord::new(topic.topic_name.clone(), payload);
+    let message_storage = MessageStorage::new(context.storage_driver_manager.clone());
+    message_storage
+        .append_topic_message(tenant, &topic.topic_name, vec![record])
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(sql: &str, actions: Vec<MqttMessageRuleAction>) -> MqttMessageRule {
+        MqttMessageRule {
+            tenant: "default".to_string(),
+            name: "r1".to_string(),
+            desc: String::new(),
+            sql: sql.to_string(),
+            actions,
+            enable: true,
+            create_time: 0,
+        }
+    }
+
+    #[test]
+    fn test_compile_message_rule() {
+        let republish = MqttMessageRuleAction::Republish {
+            topic: "alarms/${clientid}".to_string(),
+            qos: 1,
+            retain: false,
+        };
+        let compiled = compile_message_rule(rule(
+            "SELECT payload.temp AS t FROM \"sensors/#\" WHERE payload.temp > 30",
+            vec![republish, MqttMessageRuleAction::Drop],
+        ))
+        .unwrap();
+        assert!(compiled.sql.match_topic("sensors/1"));
+
+        assert!(compile_message_rule(rule(
+            "SELECT * FROM sensors",
+            vec![MqttMessageRuleAction::Drop]
+        ))
+        .is_err());
+        assert!(compile_message_rule(rule("SELECT * FROM \"a\"", vec![])).is_err());
+        let bad_qos = MqttMessageRuleAction::Republish {
+            topic: "b".to_string(),
+            qos: 3,
+            retain: false,
+        };
+        assert!(compile_message_rule(rule("SELECT * FROM \"a\"", vec![bad_qos])).is_err());
+        let wildcard = MqttMessageRuleAction::Republish {
+            topic: "b/#".to_string(),
+            qos: 0,
+            retain: false,
+        };
+        assert!(compile_message_rule(rule("SELECT * FROM \"a\"", vec![wildcard])).is_err());
+    }
+
+    #[test]
+    fn test_render_topic() {
+        assert_eq!(
+            render_topic("rules/${clientid}/${topic}", "sensors/1", "c1"),
+            "rules/c1/sensors/1"
+        );
+    }
+}
@@ -0,0 +1,336 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Targeted message tracing.
+//!
+//! Rules in `[mqtt_trace]` select messages by the client that publishes or
+//! receives them, or by topic. Every stage a selected message passes on this
+//! broker (received, stored, matched, pushed, acked) is kept in a capped
+//! in-memory [`MessageTraceLog`] and queried through the admin API. The rules
+//! are a dynamic cluster config, so all brokers trace the same messages.
+
+use super::cache::MQTTCacheManager;
+use common_base::tools::{now_millis, now_second};
+use common_base::utils::topic_util::topic_filter_match;
+use common_config::config::{MqttTrace, MqttTraceRule, MqttTraceType};
+use metadata_struct::storage::record::StorageRecord;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageTraceStage {
+    /// PUBLISH received from the publisher.
+    Received,
+    /// PUBLISH refused before it was stored, e.g. by ACL or a quota.
+    Rejected,
+    /// Written to storage.
+    Stored,
+    /// Accepted but not stored: consumed by a rule, or no subscribers while
+    /// offline messages are disabled.
+    Dropped,
+    /// Subscriptions on this broker matching the topic.
+    Matched,
+    /// PUBLISH sent to a subscriber.
+    Pushed,
+    /// PUBACK or PUBCOMP received from the subscriber.
+    Acked,
+    /// Sending to the subscriber failed.
+    PushFailed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageTraceEvent {
+    /// Increases with every event recorded on this node.
+    pub id: u64,
+    pub trace_name: String,
+    pub tenant: String,
+    pub broker_id: u64,
+    pub stage: MessageTraceStage,
+    /// Publisher of the message.
+    pub client_id: String,
+    pub topic: String,
+    /// Receiving client, set for the push stages.
+    pub subscriber: Option<String>,
+    pub detail: String,
+    pub create_time_ms: u64,
+}
+
+/// The message a stage is recorded for.
+pub struct TracedMessage<'a> {
+    pub tenant: &'a str,
+    /// Publisher of the message.
+    pub client_id: &'a str,
+    pub topic: &'a str,
+    pub subscriber: Option<&'a str>,
+}
+
+impl<'a> TracedMessage<'a> {
+    /// A stored message pushed to `subscriber`.
+    pub fn pushed(
+        tenant: &'a str,
+        topic: &'a str,
+        subscriber: &'a str,
+        record: &'a StorageRecord,
+    ) -> Self {
+        let client_id = record
+            .protocol_data
+            .as_ref()
+            .and_then(|data| data.mqtt.as_ref())
+            .map(|mqtt| mqtt.client_id.as_str())
+            .unwrap_or_default();
+        TracedMessage {
+            tenant,
+            client_id,
+            topic,
+            subscriber: Some(subscriber),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MessageTraceQuery {
+    pub trace_name: Option<String>,
+    pub client_id: Option<String>,
+    pub stage: Option<MessageTraceStage>,
+    /// Most recent events returned; 0 returns all of them.
+    #[serde(default)]
+    pub limit: usize,
+}
+
+#[derive(Default)]
+struct MessageTraceRecords {
+    next_id: u64,
+    records: VecDeque<MessageTraceEvent>,
+}
+
+#[derive(Default)]
+pub struct MessageTraceLog {
+    inner: Mutex<MessageTraceRecords>,
+}
+
+impl MessageTraceLog {
+    /// Record `stage` once for every active rule matching the message,
+    /// dropping the oldest events beyond `max_events`. Returns the number of
+    /// events recorded.
+    pub fn record(
+        &self,
+        config: &MqttTrace,
+        broker_id: u64,
+        message: &TracedMessage,
+        stage: MessageTraceStage,
+        detail: &str,
+    ) -> usize {
+        let now = now_second();
+        let names: Vec<&str> = config
+            .rules
+            .iter()
+            .filter(|rule| rule_matches(rule, message, now))
+            .map(|rule| rule.name.as_str())
+            .collect();
+        if names.is_empty() {
+            return 0;
+        }
+
+        let create_time_ms = now_millis() as u64;
+        let mut inner = self.inner.lock().unwrap();
+        for name in names.iter() {
+            inner.next_id += 1;
+            let event = MessageTraceEvent {
+                id: inner.next_id,
+                trace_name: name.to_string(),
+                tenant: message.tenant.to_string(),
+                broker_id,
+                stage,
+                client_id: message.client_id.to_string(),
+                topic: message.topic.to_string(),
+                subscriber: message.subscriber.map(str::to_string),
+                detail: detail.to_string(),
+                create_time_ms,
+            };
+            inner.records.push_back(event);
+        }
+        while inner.records.len() > config.max_events {
+            inner.records.pop_front();
+        }
+        names.len()
+    }
+
+    pub fn list(&self, query: &MessageTraceQuery) -> Vec<MessageTraceEvent> {
+        let inner = self.inner.lock().unwrap();
+        let mut events: Vec<MessageTraceEvent> = inner
+            .records
+            .iter()
+            .rev()
+            .filter(|event| {
+                query
+                    .trace_name
+                    .as_ref()
+                    .is_none_or(|name| event.trace_name == *name)
+                    && query.client_id.as_ref().is_none_or(|client_id| {
+                        event.client_id == *client_id
+                            || event.subscriber.as_ref() == Some(client_id)
+                    })
+                    && query.stage.is_none_or(|stage| event.stage == stage)
+            })
+            .take(if query.limit == 0 {
+                usize::MAX
+            } else {
+                query.limit
+            })
+            .cloned()
+            .collect();
+        events.reverse();
+        events
+    }
+
+    /// Drop the events of a deleted trace.
+    pub fn remove_trace(&self, trace_name: &str) {
+        self.inner
+            .lock()
+            .unwrap()
+            .records
+            .retain(|event| event.trace_name != trace_name);
+    }
+}
+
+/// Record `stage` of a message if a trace rule matches it. `detail` is only
+/// built when at least one rule is configured.
+pub fn trace_message(
+    cache_manager: &MQTTCacheManager,
+    message: &TracedMessage,
+    stage: MessageTraceStage,
+    detail: impl FnOnce() -> String,
+) {
+    // Runs for every message, so read the config without cloning it.
+    let cluster_config = cache_manager.node_cache.cluster_config.load();
+    let config = &cluster_config.mqtt_trace;
+    if config.rules.is_empty() {
+        return;
+    }
+    cache_manager
+        .message_trace
+        .record(config, cluster_config.broker_id, message, stage, &detail());
+}
+
+fn rule_matches(rule: &MqttTraceRule, message: &TracedMessage, now: u64) -> bool {
+    if rule.tenant != message.tenant || (rule.end_time != 0 && rule.end_time <= now) {
+        return false;
+    }
+    match rule.trace_type {
+        MqttTraceType::ClientId => {
+            message.client_id == rule.value || message.subscriber == Some(rule.value.as_str())
+        }
+        MqttTraceType::Topic => topic_filter_match(&rule.value, message.topic),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, trace_type: MqttTraceType, value: &str) -> MqttTraceRule {
+        MqttTraceRule {
+            name: name.to_string(),
+            tenant: "default".to_string(),
+            trace_type,
+            value: value.to_string(),
+            end_time: 0,
+        }
+    }
+
+    fn message<'a>(
+        client_id: &'a str,
+        topic: &'a str,
+        subscriber: Option<&'a str>,
+    ) -> TracedMessage<'a> {
+        TracedMessage {
+            tenant: "default",
+            client_id,
+            topic,
+            subscriber,
+        }
+    }
+
+    #[test]
+    fn test_message_trace_log() {
+        let mut expired = rule("expired", MqttTraceType::Topic, "#");
+        expired.end_time = 1;
+        let config = MqttTrace {
+            max_events: 3,
+            rules: vec![
+                rule("by_client", MqttTraceType::ClientId, "c1"),
+                rule("by_topic", MqttTraceType::Topic, "sensor/+/temp"),
+                expired,
+            ],
+        };
+        let log = MessageTraceLog::default();
+
+        // published by the traced client to a traced topic
+        let recorded = log.record(
+            &config,
+            1,
+            &message("c1", "sensor/1/temp", None),
+            MessageTraceStage::Received,
+            "",
+        );
+        assert_eq!(recorded, 2);
+        // delivered to the traced client
+        let recorded = log.record(
+            &config,
+            1,
+            &message("c2", "other", Some("c1")),
+            MessageTraceStage::Pushed,
+            "",
+        );
+        assert_eq!(recorded, 1);
+        // another tenant
+        let mut other_tenant = message("c1", "sensor/1/temp", None);
+        other_tenant.tenant = "t2";
+        assert_eq!(
+            log.record(&config, 1, &other_tenant, MessageTraceStage::Received, ""),
+            0
+        );
+
+        let events = log.list(&MessageTraceQuery {
+            trace_name: Some("by_client".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].stage, MessageTraceStage::Pushed);
+
+        // capped at max_events, the oldest go first
+        log.record(
+            &config,
+            1,
+            &message("c3", "sensor/2/temp", None),
+            MessageTraceStage::Stored,
+            "",
+        );
+        let events = log.list(&MessageTraceQuery::default());
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].trace_name, "by_client");
+        assert_eq!(events[2].client_id, "c3");
+
+        let events = log.list(&MessageTraceQuery {
+            limit: 1,
+            ..Default::default()
+        });
+        assert_eq!(events[0].client_id, "c3");
+
+        log.remove_trace("by_topic");
+        assert_eq!(log.list(&MessageTraceQuery::default()).len(), 1);
+    }
+}
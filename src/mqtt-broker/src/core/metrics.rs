@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use common_base::tools::now_millis;
+use common_config::config::{MqttSubscriptionMetrics, MqttTopicMetrics};
 use common_metrics::mqtt::{
     packets::record_packet_send_metrics,
     publish::{
@@ -23,11 +24,15 @@
         record_connection_messages_in, record_connection_messages_out, record_session_messages_in,
         record_session_messages_out,
     },
+    subscription::{set_subscription_label_policy, SubscriptionLabelPolicy},
     time::record_packet_send_duration,
     topic::{
         record_topic_bytes_sent, record_topic_bytes_written, record_topic_messages_sent,
         record_topic_messages_written,
     },
+    topic_throughput::{
+        record_topic_deliver, record_topic_publish, set_topic_label_policy, TopicLabelPolicy,
+    },
 };
 use metadata_struct::connection::NetworkConnectionType;
 use protocol::mqtt::{
@@ -35,6 +40,22 @@
     common::{mqtt_packet_to_string, MqttPacket},
 };
 
+pub fn apply_topic_metrics_config(config: &MqttTopicMetrics) {
+    set_topic_label_policy(TopicLabelPolicy {
+        enable: config.enable,
+        allowlist: config.allowlist.clone(),
+        prefix_levels: config.prefix_levels,
+        max_series: config.max_series,
+    });
+}
+
+pub fn apply_subscription_metrics_config(config: &MqttSubscriptionMetrics) {
+    set_subscription_label_policy(SubscriptionLabelPolicy {
+        enable: config.enable,
+        max_series: config.max_series,
+    });
+}
+
 pub fn record_publish_receive_metrics(
     tenant: &str,
     client_id: &str,
@@ -47,6 +68,7 @@ pub fn record_publish_receive_metrics(
 
     record_topic_messages_written(tenant, topic_name);
     record_topic_bytes_written(tenant, topic_name, payload_len);
+    record_topic_publish(tenant, topic_name, payload_len);
 
     record_session_messages_in(tenant, client_id);
     record_connection_messages_in(connection_id);
@@ -63,6 +85,7 @@ pub fn record_publish_send_metrics(
     record_mqtt_message_bytes_sent(payload_len);
     record_topic_messages_sent(tenant, topic_name);
     record_topic_bytes_sent(tenant, topic_name, payload_len);
+    record_topic_deliver(tenant, topic_name, payload_len);
 
     record_session_messages_out(tenant, client_id);
     record_connection_messages_out(connection_id);
@@ -14,6 +14,7 @@
 
 use crate::{core::cache::MQTTCacheManager, subscribe::manager::SubscribeManager};
 use common_base::error::ResultCommonError;
+use common_base::runtime::RuntimePool;
 use common_base::task::{TaskKind, TaskSupervisor};
 use common_base::tools::{loop_select_ticket, now_second};
 use common_metrics::mqtt::connector::{
@@ -535,58 +536,79 @@ pub fn metrics_record_thread(
     let sm = subscribe_manager.clone();
     let conm = connection_manager;
     let stop = stop_send.clone();
-    task_supervisor.spawn(TaskKind::MQTTMetricsBasic.to_string(), async move {
-        let record_func = async || {
-            record_basic_metrics(
-                mcm.clone(),
-                cm.clone(),
-                sm.clone(),
-                conm.clone(),
-                time_window,
-            )
-            .await
-        };
-        loop_select_ticket(record_func, time_window * 1000, &stop).await;
-    });
+    task_supervisor.spawn_on(
+        TaskKind::MQTTMetricsBasic.to_string(),
+        RuntimePool::Background,
+        async move {
+            let record_func = async || {
+                record_basic_metrics(
+                    mcm.clone(),
+                    cm.clone(),
+                    sm.clone(),
+                    conm.clone(),
+                    time_window,
+                )
+                .await
+            };
+            loop_select_ticket(record_func, time_window * 1000, &stop).await;
+        },
+    );
 
     // Topic metrics thread
     let mcm = metrics_cache_manager.clone();
     let cm = cache_manager.clone();
     let stop = stop_send.clone();
-    task_supervisor.spawn(TaskKind::MQTTMetricsTopic.to_string(), async move {
-        let record_func = async || record_topic_metrics(mcm.clone(), cm.clone(), time_window).await;
-        loop_select_ticket(record_func, time_window * 1000, &stop).await;
-    });
+    task_supervisor.spawn_on(
+        TaskKind::MQTTMetricsTopic.to_string(),
+        RuntimePool::Background,
+        async move {
+            let record_func =
+                async || record_topic_metrics(mcm.clone(), cm.clone(), time_window).await;
+            loop_select_ticket(record_func, time_window * 1000, &stop).await;
+        },
+    );
 
     // Subscribe metrics thread
     let mcm = metrics_cache_manager.clone();
     let sm = subscribe_manager;
     let stop = stop_send.clone();
-    task_supervisor.spawn(TaskKind::MQTTMetricsSubscribe.to_string(), async move {
-        let record_func =
-            async || record_subscribe_metrics(mcm.clone(), sm.clone(), time_window).await;
-        loop_select_ticket(record_func, time_window * 1000, &stop).await;
-    });
+    task_supervisor.spawn_on(
+        TaskKind::MQTTMetricsSubscribe.to_string(),
+        RuntimePool::Background,
+        async move {
+            let record_func =
+                async || record_subscribe_metrics(mcm.clone(), sm.clone(), time_window).await;
+            loop_select_ticket(record_func, time_window * 1000, &stop).await;
+        },
+    );
 
     // Session metrics thread
     let mcm = metrics_cache_manager.clone();
     let cm = cache_manager;
     let stop = stop_send.clone();
-    task_supervisor.spawn(TaskKind::MQTTMetricsSession.to_string(), async move {
-        let record_func =
-            async || record_session_metrics(mcm.clone(), cm.clone(), time_window).await;
-        loop_select_ticket(record_func, time_window * 1000, &stop).await;
-    });
+    task_supervisor.spawn_on(
+        TaskKind::MQTTMetricsSession.to_string(),
+        RuntimePool::Background,
+        async move {
+            let record_func =
+                async || record_session_metrics(mcm.clone(), cm.clone(), time_window).await;
+            loop_select_ticket(record_func, time_window * 1000, &stop).await;
+        },
+    );
 
     // Connector metrics thread
     let mcm = metrics_cache_manager;
     let stop = stop_send;
-    task_supervisor.spawn(TaskKind::MQTTMetricsConnector.to_string(), async move {
-        let record_func = async || {
-            record_connector_metrics(mcm.clone(), connector_manager.clone(), time_window).await
-        };
-        loop_select_ticket(record_func, time_window * 1000, &stop).await;
-    });
+    task_supervisor.spawn_on(
+        TaskKind::MQTTMetricsConnector.to_string(),
+        RuntimePool::Background,
+        async move {
+            let record_func = async || {
+                record_connector_metrics(mcm.clone(), connector_manager.clone(), time_window).await
+            };
+            loop_select_ticket(record_func, time_window * 1000, &stop).await;
+        },
+    );
 }
 
 fn calc_value(max_value: u64, min_value: u64, time_window: u64) -> u64 {
@@ -13,28 +13,45 @@
 // limitations under the License.
 
 pub mod cache;
+pub mod client_attribute;
 pub mod command;
 pub mod connection;
+pub mod consistency;
 pub mod constant;
+pub mod consumer_lag;
 pub mod content_type;
 pub mod delay_message;
 pub mod dynamic_cache;
 pub mod error;
 pub mod event;
 pub mod flapping_detect;
+pub mod inflight;
 pub mod inner;
 pub mod keep_alive;
 pub mod last_will;
 pub mod limit;
+pub mod local_log_expire;
 pub mod message;
+pub mod message_path;
+pub mod message_replay;
+pub mod message_rule;
+pub mod message_trace;
 pub mod metrics;
 pub mod metrics_cache;
 pub mod offline_message;
+pub mod offline_queue;
+pub mod overload;
+pub mod payload_transform;
+pub mod peer_cert;
 pub mod pkid_manager;
 pub mod qos;
+pub mod request_response;
 pub mod retain;
+pub mod retain_cache;
 pub mod security;
 pub mod session;
+pub mod session_migration;
+pub mod slow_request;
 pub mod string_validator;
 pub mod sub_auto;
 pub mod sub_exclusive;
@@ -48,3 +65,5 @@
 pub mod tool;
 pub mod topic;
 pub mod topic_rewrite;
+pub mod topic_stats;
+pub mod webhook;
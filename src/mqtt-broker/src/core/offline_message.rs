@@ -18,12 +18,15 @@
     cache::MQTTCacheManager,
     delay_message::{save_delay_message, DelayPublishTopic},
     error::MqttBrokerError,
+    limit::storage_bytes_limit,
     message::build_message_expire,
+    message_path::store_time_header,
+    payload_transform::{encode_topic_payload, PAYLOAD_TRANSFORM_PROPERTY},
 };
 use crate::{
     core::{qos::save_temporary_qos2_message, retain::save_retain_message},
     storage::message::MessageStorage,
-    subscribe::manager::SubscribeManager,
+    subscribe::{forward::forward_message, manager::SubscribeManager},
 };
 use common_metrics::mqtt::publish::record_messages_dropped_no_subscribers_incr;
 use delay_message::manager::DelayMessageManager;
@@ -32,6 +35,7 @@
     mqtt::topic::Topic,
     storage::{
         adapter_record::AdapterWriteRecord,
+        convert::convert_adapter_record_to_storage,
         record::{StorageRecordProtocolData, StorageRecordProtocolDataMqtt},
     },
 };
@@ -78,12 +82,8 @@ pub async fn save_message(context: SaveMessageContext) -> Result<Option<String>,
     .await?;
 
     // offline message
-    let offline_message_disabled = !context
-        .cache_manager
-        .node_cache
-        .get_cluster_config()
-        .mqtt_offline_message
-        .enable;
+    let cluster_config = context.cache_manager.node_cache.get_cluster_config();
+    let offline_message_disabled = !cluster_config.mqtt_offline_message.enable;
 
     let not_exist_subscribe = !is_exist_subscribe(
         &context.subscribe_manager,
@@ -109,56 +109,90 @@ pub async fn save_message(context: SaveMessageContext) -> Result<Option<String>,
     // save message
     let message_expire =
         build_message_expire(&context.cache_manager, &context.publish_properties).await;
-    let mqtt_data = build_mqtt_protocol_data(
+    let mut mqtt_data = build_mqtt_protocol_data(
         &context.client_id,
         &context.publish,
         &context.publish_properties,
     )
     .await;
 
-    let record = AdapterWriteRecord::new(
-        context.topic.topic_name.clone(),
-        context.publish.payload.clone(),
-    )
-    .with_protocol_data(Some(StorageRecordProtocolData {
-        mqtt: Some(mqtt_data),
-        nats: None,
-        mq9: None,
-    }))
-    .with_expire_at(message_expire);
-
-    save_simple_message(
-        &context.storage_driver_manager,
-        &context.client_id,
-        &context.topic,
-        &context.publish,
-        &record,
-    )
-    .await
+    // Only the stored stream copy is transformed; retained and delayed
+    // messages above keep the original payload.
+    let payload = match encode_topic_payload(
+        &cluster_config.mqtt_payload_transform,
+        &context.topic.topic_name,
+        &context.publish.payload,
+    )? {
+        Some((payload, marker)) => {
+            mqtt_data.format_indicator = None;
+            mqtt_data
+                .user_properties
+                .push((PAYLOAD_TRANSFORM_PROPERTY.to_string(), marker));
+            payload
+        }
+        None => context.publish.payload.clone(),
+    };
+
+    let payload_bytes = payload.len() as u64;
+    if storage_bytes_limit(&context.cache_manager, &context.topic.tenant, payload_bytes) {
+        return Err(MqttBrokerError::TenantQuotaExceeded(
+            context.topic.tenant.clone(),
+            "storage bytes".to_string(),
+        ));
+    }
+
+    let record = AdapterWriteRecord::new(context.topic.topic_name.clone(), payload)
+        .with_header(vec![store_time_header()])
+        .with_protocol_data(Some(StorageRecordProtocolData {
+            mqtt: Some(mqtt_data),
+            nats: None,
+            mq9: None,
+        }))
+        .with_expire_at(message_expire);
+
+    let offset = save_simple_message(&context, record).await?;
+    context.cache_manager.add_tenant_storage_bytes(
+        &context.topic.tenant,
+        payload_bytes,
+        message_expire,
+    );
+    Ok(offset)
 }
 
 async fn save_simple_message(
-    storage_driver_manager: &Arc<StorageDriverManager>,
-    client_id: &str,
-    topic: &Topic,
-    publish: &Publish,
-    record: &AdapterWriteRecord,
+    context: &SaveMessageContext,
+    record: AdapterWriteRecord,
 ) -> Result<Option<String>, MqttBrokerError> {
-    let offsets = if publish.qos == QoS::ExactlyOnce {
+    let topic = &context.topic;
+    let offsets = if context.publish.qos == QoS::ExactlyOnce {
         save_temporary_qos2_message(
-            storage_driver_manager,
+            &context.storage_driver_manager,
             record,
             &topic.tenant,
             &topic.topic_name,
-            client_id,
-            publish.p_kid,
+            &context.client_id,
+            context.publish.p_kid,
         )
         .await?
     } else {
-        let message_storage = MessageStorage::new(storage_driver_manager.clone());
-        message_storage
-            .append_topic_message(&topic.tenant, &topic.topic_name, vec![record.clone()])
-            .await?
+        let message_storage = MessageStorage::new(context.storage_driver_manager.clone());
+        // the clone shares the payload, only the record fields are copied
+        let (shard, offsets) = message_storage
+            .append_topic_message_with_shard(&topic.tenant, &topic.topic_name, vec![record.clone()])
+            .await?;
+        let records = offsets
+            .first()
+            .map(|offset| vec![convert_adapter_record_to_storage(record, &shard, *offset)])
+            .unwrap_or_default();
+        forward_message(
+            &context.cache_manager,
+            &context.client_pool,
+            &context.subscribe_manager,
+            &topic.tenant,
+            &topic.topic_name,
+            records,
+        );
+        offsets
     };
 
     Ok(Some(format!("{offsets:?}")))
@@ -177,7 +211,13 @@ pub async fn build_mqtt_protocol_data(
             response_topic: properties.response_topic.clone(),
             correlation_data: properties.correlation_data.clone(),
             content_type: properties.content_type.clone(),
-            user_properties: properties.user_properties.clone(),
+            // the marker is only trusted when the broker set it on store
+            user_properties: properties
+                .user_properties
+                .iter()
+                .filter(|(k, _)| k != PAYLOAD_TRANSFORM_PROPERTY)
+                .cloned()
+                .collect(),
         }
     } else {
         StorageRecordProtocolDataMqtt {
@@ -0,0 +1,435 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Per-session queues of messages for offline persistent sessions.
+//!
+//! When a QoS 1/2 message cannot be pushed because the subscriber's session
+//! has no connection, it is appended to that session's queue in the
+//! broker-local RocksDB instead of being skipped with the committed offset.
+//! Once the session is connected again, the push thread delivers the queue
+//! before new messages of the subscription.
+//!
+//! A queue is bounded by `session_queue_max_messages` and
+//! `session_queue_max_bytes`. When it is full, the oldest messages are dropped
+//! or the new one is rejected, depending on `session_queue_full_policy`.
+
+use crate::subscribe::common::Subscriber;
+use common_base::error::common::CommonError;
+use common_base::tools::now_millis;
+use common_config::config::{MqttOfflineMessage, OfflineQueueFullPolicy};
+use dashmap::{DashMap, DashSet};
+use metadata_struct::storage::record::StorageRecord;
+use rocksdb_engine::keys::broker::{offline_message_key, offline_message_prefix_key};
+use rocksdb_engine::rocksdb::RocksDBEngine;
+use rocksdb_engine::storage::broker::{
+    engine_delete_by_broker, engine_delete_prefix_by_broker, engine_get_by_broker,
+    engine_prefix_list_by_broker, engine_save_by_broker,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OfflineQueueMessage {
+    pub seq: u64,
+    pub subscriber: Subscriber,
+    pub record: StorageRecord,
+    pub enqueue_time_ms: u64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum EnqueueResult {
+    /// Queued, after dropping `dropped` of the oldest messages.
+    Queued {
+        dropped: u64,
+    },
+    Rejected,
+}
+
+/// Position and size of one queue; the messages themselves are in RocksDB
+/// under `[head, tail)`.
+#[derive(Clone, Debug, Default)]
+struct QueueMeta {
+    head: u64,
+    tail: u64,
+    bytes: u64,
+    sizes: VecDeque<u64>,
+}
+
+impl QueueMeta {
+    fn len(&self) -> u64 {
+        self.tail - self.head
+    }
+
+    fn push_back(&mut self, size: u64) {
+        self.tail += 1;
+        self.bytes += size;
+        self.sizes.push_back(size);
+    }
+
+    fn pop_front(&mut self) {
+        self.head += 1;
+        self.bytes -= self.sizes.pop_front().unwrap_or(0);
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct OfflineQueues {
+    // (tenant/client_id, QueueMeta), loaded from RocksDB on first access
+    queues: DashMap<String, QueueMeta>,
+
+    // (tenant, client_id) of removed sessions whose messages are still in RocksDB
+    cleared: DashSet<(String, String)>,
+
+    // tenant/client_id of queues being delivered
+    flushing: Arc<DashSet<String>>,
+}
+
+/// Marks a queue as being delivered until dropped.
+pub struct FlushGuard {
+    flushing: Arc<DashSet<String>>,
+    key: String,
+}
+
+impl Drop for FlushGuard {
+    fn drop(&mut self) {
+        self.flushing.remove(&self.key);
+    }
+}
+
+impl OfflineQueues {
+    pub fn enqueue(
+        &self,
+        rocksdb_engine_handler: &Arc<RocksDBEngine>,
+        conf: &MqttOfflineMessage,
+        subscriber: &Subscriber,
+        record: &StorageRecord,
+    ) -> Result<EnqueueResult, CommonError> {
+        let (tenant, client_id) = (&subscriber.tenant, &subscriber.client_id);
+        self.ensure_loaded(rocksdb_engine_handler, tenant, client_id)?;
+
+        let size = record.data.len() as u64;
+        let max_bytes = conf.session_queue_max_bytes;
+        if max_bytes > 0 && size > max_bytes {
+            return Ok(EnqueueResult::Rejected);
+        }
+
+        let mut meta = self.queues.entry(queue_key(tenant, client_id)).or_default();
+        let mut dropped = 0;
+        while meta.len() >= conf.session_queue_max_messages
+            || (max_bytes > 0 && meta.bytes + size > max_bytes)
+        {
+            if conf.session_queue_full_policy == OfflineQueueFullPolicy::Reject || meta.len() == 0 {
+                return Ok(EnqueueResult::Rejected);
+            }
+            engine_delete_by_broker(
+                rocksdb_engine_handler,
+                &offline_message_key(tenant, client_id, meta.head),
+            )?;
+            meta.pop_front();
+            dropped += 1;
+        }
+
+        let message = OfflineQueueMessage {
+            seq: meta.tail,
+            subscriber: subscriber.clone(),
+            record: record.clone(),
+            enqueue_time_ms: now_millis() as u64,
+        };
+        engine_save_by_broker(
+            rocksdb_engine_handler,
+            &offline_message_key(tenant, client_id, meta.tail),
+            message,
+        )?;
+        meta.push_back(size);
+        Ok(EnqueueResult::Queued { dropped })
+    }
+
+    pub fn len(
+        &self,
+        rocksdb_engine_handler: &Arc<RocksDBEngine>,
+        tenant: &str,
+        client_id: &str,
+    ) -> Result<u64, CommonError> {
+        self.ensure_loaded(rocksdb_engine_handler, tenant, client_id)?;
+        Ok(self
+            .queues
+            .get(&queue_key(tenant, client_id))
+            .map(|meta| meta.len())
+            .unwrap_or(0))
+    }
+
+    /// Up to `limit` messages from the head of the queue, oldest first.
+    pub fn peek(
+        &self,
+        rocksdb_engine_handler: &Arc<RocksDBEngine>,
+        tenant: &str,
+        client_id: &str,
+        limit: u64,
+    ) -> Result<Vec<OfflineQueueMessage>, CommonError> {
+        self.ensure_loaded(rocksdb_engine_handler, tenant, client_id)?;
+        let key = queue_key(tenant, client_id);
+
+        loop {
+            let (head, tail) = match self.queues.get(&key) {
+                Some(meta) if meta.len() > 0 => (meta.head, meta.tail),
+                _ => return Ok(Vec::new()),
+            };
+
+            let end = tail.min(head + limit.max(1));
+            let mut messages = Vec::new();
+            for seq in head..end {
+                if let Some(wrap) = engine_get_by_broker::<OfflineQueueMessage>(
+                    rocksdb_engine_handler,
+                    &offline_message_key(tenant, client_id, seq),
+                )? {
+                    messages.push(wrap.data);
+                }
+            }
+            if !messages.is_empty() {
+                return Ok(messages);
+            }
+
+            // Nothing left of this range in RocksDB, skip past it.
+            self.remove_through(rocksdb_engine_handler, tenant, client_id, end - 1)?;
+        }
+    }
+
+    /// Remove every message up to and including `seq` once delivered or dropped.
+    pub fn remove_through(
+        &self,
+        rocksdb_engine_handler: &Arc<RocksDBEngine>,
+        tenant: &str,
+        client_id: &str,
+        seq: u64,
+    ) -> Result<(), CommonError> {
+        if let Some(mut meta) = self.queues.get_mut(&queue_key(tenant, client_id)) {
+            while meta.len() > 0 && meta.head <= seq {
+                engine_delete_by_broker(
+                    rocksdb_engine_handler,
+                    &offline_message_key(tenant, client_id, meta.head),
+                )?;
+                meta.pop_front();
+            }
+        }
+        Ok(())
+    }
+
+    /// Start delivering a queue. Returns `None` if it is already being delivered.
+    pub fn try_start_flush(&self, tenant: &str, client_id: &str) -> Option<FlushGuard> {
+        let key = queue_key(tenant, client_id);
+        if !self.flushing.insert(key.clone()) {
+            return None;
+        }
+        Some(FlushGuard {
+            flushing: self.flushing.clone(),
+            key,
+        })
+    }
+
+    /// Discard the queue of a removed session. Its messages are deleted from
+    /// RocksDB before the queue is used again, or by [`Self::purge_cleared`].
+    pub fn clear(&self, tenant: &str, client_id: &str) {
+        self.cleared
+            .insert((tenant.to_string(), client_id.to_string()));
+        self.queues.remove(&queue_key(tenant, client_id));
+    }
+
+    pub fn purge_cleared(
+        &self,
+        rocksdb_engine_handler: &Arc<RocksDBEngine>,
+    ) -> Result<(), CommonError> {
+        let cleared: Vec<(String, String)> = self.cleared.iter().map(|k| k.clone()).collect();
+        for (tenant, client_id) in cleared {
+            engine_delete_prefix_by_broker(
+                rocksdb_engine_handler,
+                &offline_message_prefix_key(&tenant, &client_id),
+            )?;
+            self.cleared.remove(&(tenant, client_id));
+        }
+        Ok(())
+    }
+
+    fn ensure_loaded(
+        &self,
+        rocksdb_engine_handler: &Arc<RocksDBEngine>,
+        tenant: &str,
+        client_id: &str,
+    ) -> Result<(), CommonError> {
+        let cleared_key = (tenant.to_string(), client_id.to_string());
+        if self.cleared.contains(&cleared_key) {
+            engine_delete_prefix_by_broker(
+                rocksdb_engine_handler,
+                &offline_message_prefix_key(tenant, client_id),
+            )?;
+            self.cleared.remove(&cleared_key);
+        }
+
+        let key = queue_key(tenant, client_id);
+        if self.queues.contains_key(&key) {
+            return Ok(());
+        }
+
+        let messages = engine_prefix_list_by_broker::<OfflineQueueMessage>(
+            rocksdb_engine_handler,
+            &offline_message_prefix_key(tenant, client_id),
+        )?;
+        let mut meta = QueueMeta::default();
+        if let Some(first) = messages.first() {
+            meta.head = first.data.seq;
+            meta.tail = first.data.seq;
+        }
+        for message in messages {
+            // Gaps left by a crash between delete and meta update count as
+            // empty slots and are skipped by `peek`.
+            while meta.tail < message.data.seq {
+                meta.push_back(0);
+            }
+            meta.push_back(message.data.record.data.len() as u64);
+        }
+        self.queues.entry(key).or_insert(meta);
+        Ok(())
+    }
+}
+
+fn queue_key(tenant: &str, client_id: &str) -> String {
+    format!("{}/{}", tenant, client_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use metadata_struct::storage::record::StorageRecordMetadata;
+    use rocksdb_engine::test::test_rocksdb_instance;
+
+    fn subscriber() -> Subscriber {
+        Subscriber {
+            tenant: "t1".to_string(),
+            client_id: "c1".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn record(payload: &str) -> StorageRecord {
+        StorageRecord {
+            metadata: StorageRecordMetadata::default(),
+            protocol_data: None,
+            data: Bytes::from(payload.to_string()),
+        }
+    }
+
+    fn conf(
+        max_messages: u64,
+        max_bytes: u64,
+        policy: OfflineQueueFullPolicy,
+    ) -> MqttOfflineMessage {
+        MqttOfflineMessage {
+            session_queue_max_messages: max_messages,
+            session_queue_max_bytes: max_bytes,
+            session_queue_full_policy: policy,
+            ..Default::default()
+        }
+    }
+
+    fn payloads(queues: &OfflineQueues, db: &Arc<RocksDBEngine>) -> Vec<String> {
+        queues
+            .peek(db, "t1", "c1", 100)
+            .unwrap()
+            .into_iter()
+            .map(|m| String::from_utf8(m.record.data.to_vec()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_offline_queue_drop_oldest() {
+        let db = test_rocksdb_instance();
+        let queues = OfflineQueues::default();
+        let conf = conf(2, 0, OfflineQueueFullPolicy::DropOldest);
+
+        for p in ["a", "b", "c"] {
+            queues
+                .enqueue(&db, &conf, &subscriber(), &record(p))
+                .unwrap();
+        }
+        assert_eq!(
+            queues
+                .enqueue(&db, &conf, &subscriber(), &record("d"))
+                .unwrap(),
+            EnqueueResult::Queued { dropped: 1 }
+        );
+        assert_eq!(payloads(&queues, &db), vec!["c", "d"]);
+
+        // Reloaded from RocksDB after a restart.
+        let reloaded = OfflineQueues::default();
+        assert_eq!(reloaded.len(&db, "t1", "c1").unwrap(), 2);
+        assert_eq!(payloads(&reloaded, &db), vec!["c", "d"]);
+    }
+
+    #[test]
+    fn test_offline_queue_reject_and_bytes() {
+        let db = test_rocksdb_instance();
+        let queues = OfflineQueues::default();
+        let conf = conf(10, 4, OfflineQueueFullPolicy::Reject);
+
+        assert_eq!(
+            queues
+                .enqueue(&db, &conf, &subscriber(), &record("aaa"))
+                .unwrap(),
+            EnqueueResult::Queued { dropped: 0 }
+        );
+        assert_eq!(
+            queues
+                .enqueue(&db, &conf, &subscriber(), &record("bb"))
+                .unwrap(),
+            EnqueueResult::Rejected
+        );
+        assert_eq!(
+            queues
+                .enqueue(&db, &conf, &subscriber(), &record("too long"))
+                .unwrap(),
+            EnqueueResult::Rejected
+        );
+        assert_eq!(payloads(&queues, &db), vec!["aaa"]);
+    }
+
+    #[test]
+    fn test_offline_queue_flush_and_clear() {
+        let db = test_rocksdb_instance();
+        let queues = OfflineQueues::default();
+        let conf = conf(10, 0, OfflineQueueFullPolicy::DropOldest);
+        for p in ["a", "b", "c"] {
+            queues
+                .enqueue(&db, &conf, &subscriber(), &record(p))
+                .unwrap();
+        }
+
+        let guard = queues.try_start_flush("t1", "c1");
+        assert!(guard.is_some());
+        assert!(queues.try_start_flush("t1", "c1").is_none());
+        drop(guard);
+        assert!(queues.try_start_flush("t1", "c1").is_some());
+
+        let first = queues.peek(&db, "t1", "c1", 1).unwrap();
+        queues
+            .remove_through(&db, "t1", "c1", first[0].seq)
+            .unwrap();
+        assert_eq!(payloads(&queues, &db), vec!["b", "c"]);
+
+        queues.clear("t1", "c1");
+        queues.purge_cleared(&db).unwrap();
+        assert_eq!(queues.len(&db, "t1", "c1").unwrap(), 0);
+        assert_eq!(OfflineQueues::default().len(&db, "t1", "c1").unwrap(), 0);
+    }
+}
@@ -0,0 +1,290 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Adaptive overload protection of the node.
+//!
+//! CPU and memory usage above the high watermark are mapped onto three levels
+//! that split the range between the watermark and 100% into equal bands:
+//!
+//! 1. `Throttle`: CONNECT packets are delayed.
+//! 2. `Backpressure`: PUBACK/PUBREC are delayed as well, slowing down publishers.
+//! 3. `Shed`: QoS 0 publishes are dropped.
+//!
+//! A level is only left once usage falls the distance between the high and
+//! the low watermark below the band it was entered at, so usage hovering
+//! around a band boundary doesn't flap the level. The node runs at the higher
+//! of the CPU and the memory level.
+
+use crate::core::cache::MQTTCacheManager;
+use common_base::error::ResultCommonError;
+use common_base::tools::loop_select_ticket;
+use common_config::config::MqttSystemMonitor;
+use common_metrics::mqtt::overload::{
+    record_mqtt_overload_level, record_mqtt_overload_shed, record_mqtt_overload_throttled,
+};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use system_info::{process_cpu_usage, process_memory_usage};
+use tokio::sync::broadcast;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OverloadLevel {
+    Normal = 0,
+    Throttle = 1,
+    Backpressure = 2,
+    Shed = 3,
+}
+
+impl OverloadLevel {
+    fn from_u8(level: u8) -> Self {
+        match level {
+            0 => OverloadLevel::Normal,
+            1 => OverloadLevel::Throttle,
+            2 => OverloadLevel::Backpressure,
+            _ => OverloadLevel::Shed,
+        }
+    }
+}
+
+const MAX_LEVEL: u8 = OverloadLevel::Shed as u8;
+
+/// Usage above which `level` is entered.
+fn level_threshold(level: u8, high_watermark: f32) -> f32 {
+    high_watermark + (100.0 - high_watermark) * (level - 1) as f32 / MAX_LEVEL as f32
+}
+
+/// Level for `usage`, given the level the resource is currently at.
+fn next_level(current: u8, usage: f32, high_watermark: f32, low_watermark: f32) -> u8 {
+    let gap = (high_watermark - low_watermark).max(0.0);
+    let mut level = current.min(MAX_LEVEL);
+    while level < MAX_LEVEL && usage > level_threshold(level + 1, high_watermark) {
+        level += 1;
+    }
+    while level > 0 && usage < level_threshold(level, high_watermark) - gap {
+        level -= 1;
+    }
+    level
+}
+
+#[derive(Default)]
+pub struct OverloadController {
+    cpu_level: AtomicU8,
+    memory_level: AtomicU8,
+}
+
+impl OverloadController {
+    pub fn level(&self) -> OverloadLevel {
+        OverloadLevel::from_u8(
+            self.cpu_level
+                .load(Ordering::Relaxed)
+                .max(self.memory_level.load(Ordering::Relaxed)),
+        )
+    }
+
+    /// Applies the latest usage samples and returns the resulting level.
+    pub fn update(
+        &self,
+        monitor: &MqttSystemMonitor,
+        cpu_usage: f32,
+        memory_usage: f32,
+    ) -> OverloadLevel {
+        let cpu = next_level(
+            self.cpu_level.load(Ordering::Relaxed),
+            cpu_usage,
+            monitor.os_cpu_high_watermark,
+            monitor.os_cpu_low_watermark,
+        );
+        let memory = next_level(
+            self.memory_level.load(Ordering::Relaxed),
+            memory_usage,
+            monitor.os_memory_high_watermark,
+            monitor.os_memory_low_watermark,
+        );
+        self.cpu_level.store(cpu, Ordering::Relaxed);
+        self.memory_level.store(memory, Ordering::Relaxed);
+        self.level()
+    }
+
+    pub fn reset(&self) {
+        self.cpu_level.store(0, Ordering::Relaxed);
+        self.memory_level.store(0, Ordering::Relaxed);
+    }
+
+    /// Delay before a CONNECT is processed, growing with the level.
+    pub fn connect_delay(&self, monitor: &MqttSystemMonitor) -> Option<Duration> {
+        let level = self.level() as u64;
+        if level == 0 || monitor.overload_connect_delay_ms == 0 {
+            return None;
+        }
+        Some(Duration::from_millis(
+            monitor.overload_connect_delay_ms * level / MAX_LEVEL as u64,
+        ))
+    }
+
+    /// Delay before PUBACK/PUBREC is sent.
+    pub fn ack_delay(&self, monitor: &MqttSystemMonitor) -> Option<Duration> {
+        if self.level() < OverloadLevel::Backpressure || monitor.overload_ack_delay_ms == 0 {
+            return None;
+        }
+        Some(Duration::from_millis(monitor.overload_ack_delay_ms))
+    }
+
+    pub fn shed_qos0(&self) -> bool {
+        self.level() == OverloadLevel::Shed
+    }
+}
+
+/// Sleeps for the CONNECT delay of the current level.
+pub async fn throttle_connect(cache_manager: &Arc<MQTTCacheManager>) {
+    let monitor = cache_manager
+        .node_cache
+        .get_cluster_config()
+        .mqtt_system_monitor;
+    if let Some(delay) = cache_manager.overload.connect_delay(&monitor) {
+        record_mqtt_overload_throttled("connect");
+        sleep(delay).await;
+    }
+}
+
+/// Sleeps for the PUBACK/PUBREC delay of the current level.
+pub async fn throttle_publish_ack(cache_manager: &Arc<MQTTCacheManager>) {
+    let monitor = cache_manager
+        .node_cache
+        .get_cluster_config()
+        .mqtt_system_monitor;
+    if let Some(delay) = cache_manager.overload.ack_delay(&monitor) {
+        record_mqtt_overload_throttled("ack");
+        sleep(delay).await;
+    }
+}
+
+/// Whether a QoS 0 publish should be dropped, counting it if so.
+pub fn shed_qos0_publish(cache_manager: &Arc<MQTTCacheManager>) -> bool {
+    if cache_manager.overload.shed_qos0() {
+        record_mqtt_overload_shed();
+        return true;
+    }
+    false
+}
+
+pub async fn start_overload_protection(
+    cache_manager: Arc<MQTTCacheManager>,
+    interval_ms: u64,
+    stop_send: broadcast::Sender<bool>,
+) {
+    let ac_fn = async || -> ResultCommonError {
+        let monitor = cache_manager
+            .node_cache
+            .get_cluster_config()
+            .mqtt_system_monitor;
+        let before = cache_manager.overload.level();
+        let after = if monitor.overload_protection_enable {
+            let cpu_usage = process_cpu_usage().await;
+            let memory_usage = process_memory_usage();
+            cache_manager
+                .overload
+                .update(&monitor, cpu_usage, memory_usage)
+        } else {
+            cache_manager.overload.reset();
+            OverloadLevel::Normal
+        };
+
+        if before != after {
+            if after > before {
+                warn!(
+                    "Overload protection level raised from {:?} to {:?}",
+                    before, after
+                );
+            } else {
+                info!(
+                    "Overload protection level lowered from {:?} to {:?}",
+                    before, after
+                );
+            }
+        }
+        record_mqtt_overload_level(after as u8);
+        Ok(())
+    };
+
+    loop_select_ticket(ac_fn, interval_ms, &stop_send).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor() -> MqttSystemMonitor {
+        MqttSystemMonitor {
+            os_cpu_high_watermark: 70.0,
+            os_cpu_low_watermark: 60.0,
+            os_memory_high_watermark: 80.0,
+            os_memory_low_watermark: 70.0,
+            overload_connect_delay_ms: 900,
+            overload_ack_delay_ms: 100,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_next_level_hysteresis() {
+        // bands above 70%: (70, 80], (80, 90], (90, 100]
+        assert_eq!(next_level(0, 65.0, 70.0, 60.0), 0);
+        assert_eq!(next_level(0, 75.0, 70.0, 60.0), 1);
+        assert_eq!(next_level(0, 95.0, 70.0, 60.0), 3);
+
+        // stays until usage falls 10 points below the band it was entered at
+        assert_eq!(next_level(3, 85.0, 70.0, 60.0), 3);
+        assert_eq!(next_level(3, 79.0, 70.0, 60.0), 2);
+        assert_eq!(next_level(1, 65.0, 70.0, 60.0), 1);
+        assert_eq!(next_level(1, 59.0, 70.0, 60.0), 0);
+        assert_eq!(next_level(3, 50.0, 70.0, 60.0), 0);
+    }
+
+    #[test]
+    fn test_controller_actions() {
+        let monitor = monitor();
+        let controller = OverloadController::default();
+        assert_eq!(
+            controller.update(&monitor, 10.0, 10.0),
+            OverloadLevel::Normal
+        );
+        assert!(controller.connect_delay(&monitor).is_none());
+        assert!(controller.ack_delay(&monitor).is_none());
+        assert!(!controller.shed_qos0());
+
+        // memory drives the level while CPU is fine
+        assert_eq!(
+            controller.update(&monitor, 10.0, 90.0),
+            OverloadLevel::Backpressure
+        );
+        assert_eq!(
+            controller.connect_delay(&monitor),
+            Some(Duration::from_millis(600))
+        );
+        assert_eq!(
+            controller.ack_delay(&monitor),
+            Some(Duration::from_millis(100))
+        );
+        assert!(!controller.shed_qos0());
+
+        assert_eq!(controller.update(&monitor, 99.0, 90.0), OverloadLevel::Shed);
+        assert!(controller.shed_qos0());
+
+        controller.reset();
+        assert_eq!(controller.level(), OverloadLevel::Normal);
+    }
+}
@@ -0,0 +1,415 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Per-topic payload transformation.
+//!
+//! Messages published to a topic matched by a `mqtt_payload_transform` rule
+//! are stored with the rule's steps applied, and the applied steps are
+//! recorded in the `payload-transform` user property. Subscribers that set the
+//! `payload-decode = true` user property on SUBSCRIBE get the steps reversed
+//! on push; everyone else receives the stored payload together with the
+//! property describing how to decode it.
+
+use super::cache::MQTTCacheManager;
+use super::error::MqttBrokerError;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use base64::Engine;
+use bytes::Bytes;
+use common_config::config::{MqttPayloadTransform, PayloadTransformStep};
+use common_metrics::mqtt::payload_transform::record_payload_transform;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use metadata_struct::storage::record::StorageRecord;
+use protocol::mqtt::common::SubscribeProperties;
+use rule_engine::sql::topic_filter_match;
+use std::io::{Read, Write};
+use std::sync::{Arc, OnceLock};
+use tracing::warn;
+
+/// User property recording the steps applied to a stored payload.
+pub const PAYLOAD_TRANSFORM_PROPERTY: &str = "payload-transform";
+
+/// SUBSCRIBE user property a subscriber sets to receive decoded payloads.
+pub const PAYLOAD_DECODE_PROPERTY: &str = "payload-decode";
+
+const AES_GCM_NONCE_LEN: usize = 12;
+const AES_256_KEY_LEN: usize = 32;
+const ZSTD_LEVEL: i32 = 3;
+
+/// Key management hook for the `encrypt` step, e.g. backed by a KMS.
+pub trait PayloadKeyProvider: Send + Sync {
+    /// Returns the 32-byte AES key registered under `key_id`, if any.
+    fn get_key(&self, key_id: &str) -> Option<Vec<u8>>;
+}
+
+static KEY_PROVIDER: OnceLock<Arc<dyn PayloadKeyProvider>> = OnceLock::new();
+
+/// Register the key provider consulted before the keys in the configuration.
+/// Can only be called once.
+pub fn register_payload_key_provider(
+    provider: Arc<dyn PayloadKeyProvider>,
+) -> Result<(), MqttBrokerError> {
+    KEY_PROVIDER.set(provider).map_err(|_| {
+        MqttBrokerError::CommonError("Payload key provider is already registered".to_string())
+    })
+}
+
+pub fn is_payload_decode_requested(properties: &Option<SubscribeProperties>) -> bool {
+    properties.as_ref().is_some_and(|p| {
+        p.user_properties
+            .iter()
+            .any(|(k, v)| k == PAYLOAD_DECODE_PROPERTY && v.eq_ignore_ascii_case("true"))
+    })
+}
+
+/// Apply the pipeline configured for `topic_name`. Returns the new payload and
+/// the marker to store alongside it, or `None` when no rule matches.
+pub fn encode_topic_payload(
+    config: &MqttPayloadTransform,
+    topic_name: &str,
+    payload: &[u8],
+) -> Result<Option<(Bytes, String)>, MqttBrokerError> {
+    if !config.enable {
+        return Ok(None);
+    }
+    let Some(rule) = config
+        .rules
+        .iter()
+        .find(|rule| topic_filter_match(&rule.topic, topic_name))
+    else {
+        return Ok(None);
+    };
+    if rule.steps.is_empty() {
+        return Ok(None);
+    }
+
+    let result = encode_payload(config, &rule.steps, payload);
+    record_payload_transform("encode", result.is_ok());
+    let data = result?;
+    Ok(Some((Bytes::from(data), steps_to_marker(&rule.steps))))
+}
+
+/// Reverse the steps recorded on a stored message for a subscriber that opted
+/// in. Returns `None` when the message was not transformed or cannot be
+/// decoded, in which case the stored payload is delivered unchanged.
+pub fn decode_record_payload(
+    cache_manager: &Arc<MQTTCacheManager>,
+    record: &StorageRecord,
+) -> Option<Bytes> {
+    let marker = record
+        .protocol_data
+        .as_ref()
+        .and_then(|p| p.mqtt.as_ref())
+        .and_then(|mqtt| {
+            mqtt.user_properties
+                .iter()
+                .find(|(k, _)| k == PAYLOAD_TRANSFORM_PROPERTY)
+                .map(|(_, v)| v.clone())
+        })?;
+
+    let config = cache_manager
+        .node_cache
+        .get_cluster_config()
+        .mqtt_payload_transform;
+    let result = decode_payload(&config, &marker, &record.data);
+    record_payload_transform("decode", result.is_ok());
+    match result {
+        Ok(data) => Some(Bytes::from(data)),
+        Err(e) => {
+            warn!(
+                "Failed to decode payload transformed with [{}], delivering it as stored: {}",
+                marker, e
+            );
+            None
+        }
+    }
+}
+
+pub fn encode_payload(
+    config: &MqttPayloadTransform,
+    steps: &[PayloadTransformStep],
+    payload: &[u8],
+) -> Result<Vec<u8>, MqttBrokerError> {
+    let mut data = payload.to_vec();
+    for step in steps {
+        data = apply_step(config, step, &data, true)?;
+    }
+    Ok(data)
+}
+
+pub fn decode_payload(
+    config: &MqttPayloadTransform,
+    marker: &str,
+    payload: &[u8],
+) -> Result<Vec<u8>, MqttBrokerError> {
+    let steps = marker_to_steps(marker)?;
+    let mut data = payload.to_vec();
+    for step in steps.iter().rev() {
+        data = apply_step(config, step, &data, false)?;
+    }
+    Ok(data)
+}
+
+fn apply_step(
+    config: &MqttPayloadTransform,
+    step: &PayloadTransformStep,
+    data: &[u8],
+    encode: bool,
+) -> Result<Vec<u8>, MqttBrokerError> {
+    match (step, encode) {
+        (PayloadTransformStep::Gzip, true) => gzip_compress(data),
+        (PayloadTransformStep::Gzip, false) => read_limited(
+            GzDecoder::new(data),
+            config.max_decoded_bytes,
+            "gzip decompress",
+        ),
+        (PayloadTransformStep::Zstd, true) => zstd::stream::encode_all(data, ZSTD_LEVEL)
+            .map_err(|e| transform_err(format!("zstd compress failed: {}", e))),
+        (PayloadTransformStep::Zstd, false) => {
+            let decoder = zstd::stream::read::Decoder::new(data)
+                .map_err(|e| transform_err(format!("zstd decompress failed: {}", e)))?;
+            read_limited(decoder, config.max_decoded_bytes, "zstd decompress")
+        }
+        (PayloadTransformStep::Encrypt { key_id }, true) => {
+            aes_gcm_encrypt(&resolve_key(config, key_id)?, data)
+        }
+        (PayloadTransformStep::Encrypt { key_id }, false) => {
+            aes_gcm_decrypt(&resolve_key(config, key_id)?, data)
+        }
+        (PayloadTransformStep::JsonToCbor, true) | (PayloadTransformStep::CborToJson, false) => {
+            json_to_cbor(data)
+        }
+        (PayloadTransformStep::CborToJson, true) | (PayloadTransformStep::JsonToCbor, false) => {
+            cbor_to_json(data)
+        }
+    }
+}
+
+fn transform_err(msg: String) -> MqttBrokerError {
+    MqttBrokerError::CommonError(format!("payload transform: {}", msg))
+}
+
+fn steps_to_marker(steps: &[PayloadTransformStep]) -> String {
+    steps
+        .iter()
+        .map(|step| match step {
+            PayloadTransformStep::Gzip => "gzip".to_string(),
+            PayloadTransformStep::Zstd => "zstd".to_string(),
+            PayloadTransformStep::Encrypt { key_id } => format!("aes_gcm:{}", key_id),
+            PayloadTransformStep::JsonToCbor => "json_to_cbor".to_string(),
+            PayloadTransformStep::CborToJson => "cbor_to_json".to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn marker_to_steps(marker: &str) -> Result<Vec<PayloadTransformStep>, MqttBrokerError> {
+    marker
+        .split(',')
+        .map(|tag| match tag {
+            "gzip" => Ok(PayloadTransformStep::Gzip),
+            "zstd" => Ok(PayloadTransformStep::Zstd),
+            "json_to_cbor" => Ok(PayloadTransformStep::JsonToCbor),
+            "cbor_to_json" => Ok(PayloadTransformStep::CborToJson),
+            _ => match tag.strip_prefix("aes_gcm:") {
+                Some(key_id) if !key_id.is_empty() => Ok(PayloadTransformStep::Encrypt {
+                    key_id: key_id.to_string(),
+                }),
+                _ => Err(transform_err(format!("unknown step '{}'", tag))),
+            },
+        })
+        .collect()
+}
+
+fn resolve_key(config: &MqttPayloadTransform, key_id: &str) -> Result<Vec<u8>, MqttBrokerError> {
+    let key = match KEY_PROVIDER.get().and_then(|p| p.get_key(key_id)) {
+        Some(key) => key,
+        None => {
+            let encoded = config
+                .keys
+                .get(key_id)
+                .ok_or_else(|| transform_err(format!("encryption key '{}' not found", key_id)))?;
+            BASE64_STANDARD.decode(encoded).map_err(|e| {
+                transform_err(format!("encryption key '{}' is not base64: {}", key_id, e))
+            })?
+        }
+    };
+    if key.len() != AES_256_KEY_LEN {
+        return Err(transform_err(format!(
+            "encryption key '{}' must be {} bytes, got {}",
+            key_id,
+            AES_256_KEY_LEN,
+            key.len()
+        )));
+    }
+    Ok(key)
+}
+
+fn gzip_compress(data: &[u8]) -> Result<Vec<u8>, MqttBrokerError> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(data)
+        .and_then(|_| encoder.finish())
+        .map_err(|e| transform_err(format!("gzip compress failed: {}", e)))
+}
+
+/// Reads a decompressing reader to the end, failing once it produces more than
+/// `limit` bytes so a small stored payload cannot expand without bound.
+fn read_limited<R: Read>(reader: R, limit: u64, step: &str) -> Result<Vec<u8>, MqttBrokerError> {
+    let mut out = Vec::new();
+    reader
+        .take(limit.saturating_add(1))
+        .read_to_end(&mut out)
+        .map_err(|e| transform_err(format!("{} failed: {}", step, e)))?;
+    if out.len() as u64 > limit {
+        return Err(transform_err(format!(
+            "{} output exceeds {} bytes",
+            step, limit
+        )));
+    }
+    Ok(out)
+}
+
+/// Output layout: 12-byte random nonce followed by the ciphertext and tag.
+fn aes_gcm_encrypt(key: &[u8], data: &[u8]) -> Result<Vec<u8>, MqttBrokerError> {
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(|e| transform_err(e.to_string()))?;
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, data)
+        .map_err(|e| transform_err(format!("encrypt failed: {}", e)))?;
+    let mut out = Vec::with_capacity(AES_GCM_NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+fn aes_gcm_decrypt(key: &[u8], data: &[u8]) -> Result<Vec<u8>, MqttBrokerError> {
+    if data.len() < AES_GCM_NONCE_LEN {
+        return Err(transform_err("encrypted payload is too short".to_string()));
+    }
+    let (nonce, ciphertext) = data.split_at(AES_GCM_NONCE_LEN);
+    let cipher = Aes256Gcm::new_from_slice(key).map_err(|e| transform_err(e.to_string()))?;
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|e| transform_err(format!("decrypt failed: {}", e)))
+}
+
+fn json_to_cbor(data: &[u8]) -> Result<Vec<u8>, MqttBrokerError> {
+    let value: serde_json::Value = serde_json::from_slice(data)
+        .map_err(|e| transform_err(format!("payload is not JSON: {}", e)))?;
+    let mut out = Vec::new();
+    ciborium::into_writer(&value, &mut out)
+        .map_err(|e| transform_err(format!("CBOR encode failed: {}", e)))?;
+    Ok(out)
+}
+
+fn cbor_to_json(data: &[u8]) -> Result<Vec<u8>, MqttBrokerError> {
+    let value: serde_json::Value = ciborium::from_reader(data)
+        .map_err(|e| transform_err(format!("payload is not CBOR: {}", e)))?;
+    serde_json::to_vec(&value).map_err(|e| transform_err(format!("JSON encode failed: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common_config::config::PayloadTransformRule;
+    use std::collections::HashMap;
+
+    fn build_config(steps: Vec<PayloadTransformStep>) -> MqttPayloadTransform {
+        let mut keys = HashMap::new();
+        keys.insert("k1".to_string(), BASE64_STANDARD.encode([7u8; 32]));
+        MqttPayloadTransform {
+            enable: true,
+            rules: vec![PayloadTransformRule {
+                topic: "sensors/#".to_string(),
+                steps,
+            }],
+            keys,
+            max_decoded_bytes: 1024,
+        }
+    }
+
+    #[test]
+    fn test_payload_round_trip() {
+        let payload = br#"{"temp":35.5,"unit":"C"}"#;
+        let config = build_config(vec![
+            PayloadTransformStep::JsonToCbor,
+            PayloadTransformStep::Gzip,
+            PayloadTransformStep::Zstd,
+            PayloadTransformStep::Encrypt {
+                key_id: "k1".to_string(),
+            },
+        ]);
+
+        let (encoded, marker) = encode_topic_payload(&config, "sensors/room1", payload)
+            .unwrap()
+            .unwrap();
+        assert_eq!(marker, "json_to_cbor,gzip,zstd,aes_gcm:k1");
+        assert_ne!(encoded.as_ref(), payload.as_slice());
+
+        let decoded = decode_payload(&config, &marker, &encoded).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&decoded).unwrap();
+        assert_eq!(value, serde_json::json!({"temp": 35.5, "unit": "C"}));
+    }
+
+    #[test]
+    fn test_payload_transform_skip_and_errors() {
+        let config = build_config(vec![PayloadTransformStep::JsonToCbor]);
+        assert!(encode_topic_payload(&config, "other/topic", b"x")
+            .unwrap()
+            .is_none());
+        assert!(encode_topic_payload(&config, "sensors/a", b"not json").is_err());
+
+        let missing_key = build_config(vec![PayloadTransformStep::Encrypt {
+            key_id: "missing".to_string(),
+        }]);
+        assert!(encode_topic_payload(&missing_key, "sensors/a", b"x").is_err());
+
+        assert!(decode_payload(&config, "rot13", b"x").is_err());
+
+        let mut disabled = config.clone();
+        disabled.enable = false;
+        assert!(encode_topic_payload(&disabled, "sensors/a", b"{}")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_decode_output_is_capped() {
+        let payload = vec![b'a'; 4096];
+        for step in [PayloadTransformStep::Gzip, PayloadTransformStep::Zstd] {
+            let config = build_config(vec![step.clone()]);
+            let encoded = encode_payload(&config, &[step], &payload).unwrap();
+            let marker = steps_to_marker(&config.rules[0].steps);
+            assert!(decode_payload(&config, &marker, &encoded).is_err());
+
+            let mut larger = config.clone();
+            larger.max_decoded_bytes = 4096;
+            assert_eq!(decode_payload(&larger, &marker, &encoded).unwrap(), payload);
+        }
+    }
+
+    #[test]
+    fn test_is_payload_decode_requested() {
+        assert!(!is_payload_decode_requested(&None));
+        let properties = SubscribeProperties {
+            subscription_identifier: None,
+            user_properties: vec![(PAYLOAD_DECODE_PROPERTY.to_string(), "true".to_string())],
+        };
+        assert!(is_payload_decode_requested(&Some(properties)));
+    }
+}
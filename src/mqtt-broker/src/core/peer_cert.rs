@@ -0,0 +1,129 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_config::config::{MqttTls, PeerCertField};
+use metadata_struct::connection::PeerCertIdentity;
+use protocol::mqtt::common::{Connect, Login};
+
+/// Apply `peer_cert_as_client_id` and `peer_cert_as_username` to a CONNECT
+/// packet. Returns true when the username was taken from the certificate, in
+/// which case the certificate replaces password authentication.
+pub fn apply_peer_cert_identity(
+    conf: &MqttTls,
+    identity: Option<&PeerCertIdentity>,
+    connect: &mut Connect,
+    login: &mut Option<Login>,
+) -> bool {
+    let Some(identity) = identity else {
+        return false;
+    };
+
+    if let Some(client_id) = peer_cert_field(identity, conf.peer_cert_as_client_id) {
+        connect.client_id = client_id;
+    }
+
+    let Some(username) = peer_cert_field(identity, conf.peer_cert_as_username) else {
+        return false;
+    };
+    let password = login.take().map(|l| l.password).unwrap_or_default();
+    *login = Some(Login { username, password });
+    true
+}
+
+pub fn peer_cert_field(identity: &PeerCertIdentity, field: PeerCertField) -> Option<String> {
+    let value = match field {
+        PeerCertField::None => None,
+        PeerCertField::Cn => identity.common_name.clone(),
+        PeerCertField::Dns => identity.dns_names.first().cloned(),
+        PeerCertField::Email => identity.emails.first().cloned(),
+        PeerCertField::Uri => identity.uris.first().cloned(),
+    };
+    value.filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity() -> PeerCertIdentity {
+        PeerCertIdentity {
+            common_name: Some("device-001".to_string()),
+            dns_names: vec!["device-001.iot.local".to_string()],
+            emails: vec![],
+            uris: vec!["spiffe://iot/device-001".to_string()],
+        }
+    }
+
+    fn connect() -> Connect {
+        Connect {
+            keep_alive: 10,
+            client_id: "original".to_string(),
+            clean_session: true,
+        }
+    }
+
+    #[test]
+    fn test_apply_peer_cert_identity() {
+        let conf = MqttTls {
+            peer_cert_as_username: PeerCertField::Cn,
+            peer_cert_as_client_id: PeerCertField::Uri,
+            ..Default::default()
+        };
+        let mut connect = connect();
+        let mut login = Some(Login {
+            username: "spoofed".to_string(),
+            password: "pwd".to_string(),
+        });
+        assert!(apply_peer_cert_identity(
+            &conf,
+            Some(&identity()),
+            &mut connect,
+            &mut login
+        ));
+        assert_eq!(connect.client_id, "spiffe://iot/device-001");
+        assert_eq!(login.unwrap().username, "device-001");
+    }
+
+    #[test]
+    fn test_apply_peer_cert_identity_unmapped() {
+        let mut connect = connect();
+        let mut login = None;
+
+        // no certificate
+        let conf = MqttTls {
+            peer_cert_as_username: PeerCertField::Cn,
+            ..Default::default()
+        };
+        assert!(!apply_peer_cert_identity(
+            &conf,
+            None,
+            &mut connect,
+            &mut login
+        ));
+
+        // the configured field is missing from the certificate
+        let conf = MqttTls {
+            peer_cert_as_username: PeerCertField::Email,
+            ..Default::default()
+        };
+        assert!(!apply_peer_cert_identity(
+            &conf,
+            Some(&identity()),
+            &mut connect,
+            &mut login
+        ));
+        assert_eq!(connect.client_id, "original");
+        assert!(login.is_none());
+    }
+}
@@ -105,13 +105,23 @@ pub fn get_qos_pkid_data(&self, client_id: &str, pkid: u16) -> Option<ReceiveQos
         None
     }
 
+    /// Packet ids still in flight. Released QoS 2 ids are only kept to answer
+    /// a re-sent PUBREL and do not count.
     pub fn get_qos_pkid_data_len_by_client_id(&self, client_id: &str) -> usize {
         if let Some(inner) = self.qos_pkid_data.get(client_id) {
-            return inner.len();
+            return inner
+                .iter()
+                .filter(|e| e.value().ack_enum != PkidAckEnum::PubComp)
+                .count();
         }
         0
     }
 
+    pub fn is_qos_pkid_in_use(&self, client_id: &str, pkid: u16) -> bool {
+        self.get_qos_pkid_data(client_id, pkid)
+            .is_some_and(|data| data.ack_enum != PkidAckEnum::PubComp)
+    }
+
     // publish to client pkid
     pub async fn generate_publish_to_client_pkid(&self, client_id: &str, qos: &QoS) -> u16 {
         if *qos == QoS::AtMostOnce {
@@ -151,6 +161,21 @@ pub async fn generate_publish_to_client_pkid(&self, client_id: &str, qos: &QoS)
         }
     }
 
+    /// Take a specific packet id for a client, e.g. to redeliver an inflight
+    /// message. Returns false if it is already in use.
+    pub fn reserve_publish_to_client_pkid(&self, client_id: &str, pkid: u16) -> bool {
+        let inner = self
+            .publish_to_client_pkid_cache
+            .entry(client_id.to_string())
+            .or_default();
+        let pkid_key = pkid.to_string();
+        if inner.contains_key(&pkid_key) {
+            return false;
+        }
+        inner.insert(pkid_key, now_second());
+        true
+    }
+
     pub fn remove_publish_to_client_pkid(&self, client_id: &str, pkid: u16) {
         let pkid_key = pkid.to_string();
         let mut remove_outer = false;
@@ -243,3 +268,28 @@ pub async fn clean_pkid_data(
 
     loop_select_ticket(ac_fn, PKID_CLEAN_INTERVAL_MS, &stop_send).await;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pkid_data(pkid: u16, ack_enum: PkidAckEnum) -> ReceiveQosPkidData {
+        ReceiveQosPkidData {
+            ack_enum,
+            pkid,
+            create_time: now_second(),
+        }
+    }
+
+    #[test]
+    fn released_qos2_pkid_is_not_in_flight() {
+        let manager = PkidManager::new();
+        manager.add_qos_pkid_data("c1", pkid_data(1, PkidAckEnum::PubRec));
+        manager.add_qos_pkid_data("c1", pkid_data(2, PkidAckEnum::PubComp));
+
+        assert_eq!(manager.get_qos_pkid_data_len_by_client_id("c1"), 1);
+        assert!(manager.is_qos_pkid_in_use("c1", 1));
+        assert!(!manager.is_qos_pkid_in_use("c1", 2));
+        assert!(!manager.is_qos_pkid_in_use("c1", 3));
+    }
+}
@@ -12,12 +12,25 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+//! Exactly-once receive state of QoS 2 messages published by clients.
+//!
+//! A QoS 2 PUBLISH is stored in the inner topic keyed by tenant, client id and
+//! packet id, and only written to its real topic when PUBREL arrives. The stored
+//! record is the persisted "received, not yet released" state: it survives
+//! reconnects and broker restarts, so PUBREL can be completed by any broker.
+//! Once released the record is deleted and the packet id is remembered as
+//! released for a while, so a re-sent PUBREL gets PUBCOMP without publishing
+//! the message twice. A clean start discards the unreleased records.
+
 use crate::core::{
-    cache::MQTTCacheManager, error::MqttBrokerError, pkid_manager::ReceiveQosPkidData,
+    cache::MQTTCacheManager,
+    error::MqttBrokerError,
+    pkid_manager::{PkidAckEnum, ReceiveQosPkidData},
 };
+use crate::storage::message::MessageStorage;
 use broker_core::inner_topic::QOS2_INNER_TOPIC;
+use common_base::tools::now_second;
 use common_base::utils::serialize;
-use connector::storage::message::MessageStorage;
 use metadata_struct::{storage::adapter_record::AdapterWriteRecord, tenant::DEFAULT_TENANT};
 use node_call::{NodeCallData, NodeCallManager};
 use prost::Message;
@@ -26,6 +39,7 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 use storage_adapter::driver::StorageDriverManager;
+use tracing::{debug, warn};
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct Qos2TemporaryMessage {
@@ -34,9 +48,49 @@ pub struct Qos2TemporaryMessage {
     pub record: AdapterWriteRecord,
 }
 
+/// How the broker answers an inbound QoS 2 packet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Qos2ReceiveAction {
+    /// New PUBLISH: store it and answer PUBREC.
+    Store,
+    /// PUBLISH re-sent before PUBREL: answer PUBREC without storing again.
+    ResendPubRec,
+    /// PUBREL for a stored message: publish it and answer PUBCOMP.
+    Release,
+    /// PUBREL re-sent after release: answer PUBCOMP without publishing again.
+    ResendPubComp,
+    /// PUBREL for a packet id that was never received.
+    NotFound,
+}
+
+/// `state` is the in-memory receive state of the packet id. After the
+/// release the packet id is free again, so a PUBLISH reusing it is new.
+pub fn qos2_publish_action(state: Option<&PkidAckEnum>) -> Qos2ReceiveAction {
+    match state {
+        Some(PkidAckEnum::PubRec) => Qos2ReceiveAction::ResendPubRec,
+        _ => Qos2ReceiveAction::Store,
+    }
+}
+
+/// `stored` tells whether the message is in the inner topic. The in-memory
+/// state may be gone after a reconnect to another broker or a restart, in
+/// which case the stored record alone decides.
+pub fn qos2_pubrel_action(state: Option<&PkidAckEnum>, stored: bool) -> Qos2ReceiveAction {
+    // A new PUBLISH on the same packet id resets the state to PubRec first,
+    // so a stored record here is one whose delete failed after the release.
+    if state == Some(&PkidAckEnum::PubComp) {
+        return Qos2ReceiveAction::ResendPubComp;
+    }
+    if stored {
+        Qos2ReceiveAction::Release
+    } else {
+        Qos2ReceiveAction::NotFound
+    }
+}
+
 pub async fn save_temporary_qos2_message(
     storage_driver_manager: &Arc<StorageDriverManager>,
-    record: &AdapterWriteRecord,
+    record: AdapterWriteRecord,
     tenant: &str,
     topic_name: &str,
     client_id: &str,
@@ -46,12 +100,13 @@ pub async fn save_temporary_qos2_message(
     let qos2_message = Qos2TemporaryMessage {
         tenant: tenant.to_string(),
         topic: topic_name.to_string(),
-        record: record.clone(),
+        record,
     };
 
     let data = serialize::serialize(&qos2_message)?;
     let new_record = AdapterWriteRecord::new(QOS2_INNER_TOPIC.to_string(), data)
-        .with_key(uniq_key(client_id, pkid));
+        .with_key(uniq_key(tenant, client_id, pkid))
+        .with_tags(vec![session_tag(tenant, client_id)]);
 
     let offsets = message_storage
         .append_topic_message(DEFAULT_TENANT, QOS2_INNER_TOPIC, vec![new_record])
@@ -61,10 +116,11 @@ pub async fn save_temporary_qos2_message(
 
 pub async fn get_temporary_qos2_message(
     storage_driver_manager: &Arc<StorageDriverManager>,
+    tenant: &str,
     client_id: &str,
     pkid: u16,
 ) -> Result<Option<Qos2TemporaryMessage>, MqttBrokerError> {
-    let key = uniq_key(client_id, pkid);
+    let key = uniq_key(tenant, client_id, pkid);
     let results = storage_driver_manager
         .read_by_keys(DEFAULT_TENANT, QOS2_INNER_TOPIC, &[key.as_str()])
         .await?
@@ -92,6 +148,100 @@ pub async fn get_temporary_qos2_message(
     Ok(Some(qos2_msg))
 }
 
+pub async fn delete_temporary_qos2_message(
+    storage_driver_manager: &Arc<StorageDriverManager>,
+    tenant: &str,
+    client_id: &str,
+    pkid: u16,
+) -> Result<(), MqttBrokerError> {
+    let key = uniq_key(tenant, client_id, pkid);
+    storage_driver_manager
+        .delete_by_keys(DEFAULT_TENANT, QOS2_INNER_TOPIC, &[key.as_str()])
+        .await?;
+    Ok(())
+}
+
+/// Packet ids of the QoS 2 messages a session has received but not released.
+pub async fn list_temporary_qos2_pkids(
+    storage_driver_manager: &Arc<StorageDriverManager>,
+    tenant: &str,
+    client_id: &str,
+) -> Result<Vec<u16>, MqttBrokerError> {
+    let records = MessageStorage::new(storage_driver_manager.clone())
+        .read_all_by_tag(
+            DEFAULT_TENANT,
+            QOS2_INNER_TOPIC,
+            &session_tag(tenant, client_id),
+        )
+        .await?;
+
+    let mut pkids = Vec::new();
+    for record in records {
+        if let Some(pkid) = record
+            .metadata
+            .key
+            .as_deref()
+            .and_then(|key| parse_uniq_key(tenant, client_id, key))
+        {
+            if !pkids.contains(&pkid) {
+                pkids.push(pkid);
+            }
+        }
+    }
+    Ok(pkids)
+}
+
+/// Bring back the receive state of a resumed session, so re-sent PUBLISHes
+/// are answered with PUBREC instead of being stored again. A new session
+/// drops the unreleased messages of the previous one.
+pub async fn resume_qos2_receive_state(
+    cache_manager: &Arc<MQTTCacheManager>,
+    storage_driver_manager: &Arc<StorageDriverManager>,
+    tenant: &str,
+    client_id: &str,
+    new_session: bool,
+) -> Result<(), MqttBrokerError> {
+    if new_session {
+        cache_manager.pkid_manager.qos_pkid_data.remove(client_id);
+    }
+
+    let pkids = list_temporary_qos2_pkids(storage_driver_manager, tenant, client_id).await?;
+    if pkids.is_empty() {
+        return Ok(());
+    }
+
+    if new_session {
+        for pkid in pkids {
+            if let Err(e) =
+                delete_temporary_qos2_message(storage_driver_manager, tenant, client_id, pkid).await
+            {
+                warn!(
+                    "Failed to drop unreleased QoS2 message, client_id={}, pkid={}, error={}",
+                    client_id, pkid, e
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    debug!(
+        "Resuming {} unreleased QoS2 packet ids, client_id={}",
+        pkids.len(),
+        client_id
+    );
+    for pkid in pkids {
+        cache_manager.pkid_manager.add_qos_pkid_data(
+            client_id,
+            ReceiveQosPkidData {
+                ack_enum: PkidAckEnum::PubRec,
+                pkid,
+                create_time: now_second(),
+            },
+        );
+    }
+    Ok(())
+}
+
 pub async fn persistent_save_qos2_message(
     storage_driver_manager: &Arc<StorageDriverManager>,
     qos2_msg: Qos2TemporaryMessage,
@@ -171,6 +321,84 @@ pub async fn get_qos_data_by_req(
     Ok(GetQosDataByClientIdReply { data })
 }
 
-fn uniq_key(client_id: &str, pkid: u16) -> String {
-    format!("{}_{}", client_id, pkid)
+fn uniq_key(tenant: &str, client_id: &str, pkid: u16) -> String {
+    format!("{}/{}", session_tag(tenant, client_id), pkid)
+}
+
+fn parse_uniq_key(tenant: &str, client_id: &str, key: &str) -> Option<u16> {
+    key.strip_prefix(&session_tag(tenant, client_id))?
+        .strip_prefix('/')?
+        .parse()
+        .ok()
+}
+
+fn session_tag(tenant: &str, client_id: &str) -> String {
+    format!("{}/{}", tenant, client_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every packet the client can send for one packet id, against every
+    // receive state the broker can be in.
+    #[test]
+    fn qos2_publish_action_test() {
+        // first PUBLISH, or the state was lost with the previous broker
+        assert_eq!(qos2_publish_action(None), Qos2ReceiveAction::Store);
+        // PUBREC lost, client re-sends PUBLISH with DUP
+        assert_eq!(
+            qos2_publish_action(Some(&PkidAckEnum::PubRec)),
+            Qos2ReceiveAction::ResendPubRec
+        );
+        // packet id reused after PUBCOMP
+        assert_eq!(
+            qos2_publish_action(Some(&PkidAckEnum::PubComp)),
+            Qos2ReceiveAction::Store
+        );
+    }
+
+    #[test]
+    fn qos2_pubrel_action_test() {
+        // PUBREL after PUBREC
+        assert_eq!(
+            qos2_pubrel_action(Some(&PkidAckEnum::PubRec), true),
+            Qos2ReceiveAction::Release
+        );
+        // PUBREL after reconnect to another broker or a restart
+        assert_eq!(qos2_pubrel_action(None, true), Qos2ReceiveAction::Release);
+        // PUBCOMP lost, client re-sends PUBREL
+        assert_eq!(
+            qos2_pubrel_action(Some(&PkidAckEnum::PubComp), false),
+            Qos2ReceiveAction::ResendPubComp
+        );
+        // released message could not be deleted, client re-sends PUBREL
+        assert_eq!(
+            qos2_pubrel_action(Some(&PkidAckEnum::PubComp), true),
+            Qos2ReceiveAction::ResendPubComp
+        );
+        // PUBREL without PUBLISH, or after a clean start
+        assert_eq!(qos2_pubrel_action(None, false), Qos2ReceiveAction::NotFound);
+        assert_eq!(
+            qos2_pubrel_action(Some(&PkidAckEnum::PubRec), false),
+            Qos2ReceiveAction::NotFound
+        );
+    }
+
+    #[test]
+    fn uniq_key_round_trip_test() {
+        assert_eq!(
+            parse_uniq_key("t1", "c1", &uniq_key("t1", "c1", 7)),
+            Some(7)
+        );
+        assert_eq!(
+            parse_uniq_key("t1", "c_1", &uniq_key("t1", "c_1", 65535)),
+            Some(65535)
+        );
+        assert_eq!(parse_uniq_key("t1", "c1", &uniq_key("t1", "c10", 7)), None);
+        // the same client id in another tenant is another session
+        assert_ne!(uniq_key("t1", "c1", 7), uniq_key("t2", "c1", 7));
+        assert_eq!(parse_uniq_key("t1", "c1", &uniq_key("t2", "c1", 7)), None);
+        assert_eq!(parse_uniq_key("t1", "c1", "t1/c1/x"), None);
+    }
 }
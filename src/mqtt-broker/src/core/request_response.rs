@@ -0,0 +1,190 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! MQTT 5 request/response support.
+//!
+//! Every client owns a response topic namespace `{prefix}/{client_id}` (by
+//! default `$rpc/{client_id}`), returned as Response Information when the
+//! client asks for it in CONNECT. Only the owner may subscribe below its
+//! namespace, and a request may only name a response topic inside the
+//! requester's own namespace, so a client can neither read nor redirect
+//! another client's responses. Topics are tenant scoped, so the namespace is
+//! unique per tenant.
+
+use crate::core::error::MqttBrokerError;
+use crate::core::sub_exclusive::decode_exclusive_sub_path_to_topic_name;
+use crate::core::sub_share::{decode_share_info, is_mqtt_share_subscribe};
+use common_config::config::MqttProtocolConfig;
+use protocol::mqtt::common::PublishProperties;
+
+/// Response Information returned in CONNACK, `None` if the namespace is
+/// disabled or the client cannot own one.
+pub fn response_topic_namespace(conf: &MqttProtocolConfig, client_id: &str) -> Option<String> {
+    if conf.response_topic_prefix.is_empty() || !can_own_response_namespace(client_id) {
+        return None;
+    }
+    Some(format!("{}/{}", conf.response_topic_prefix, client_id))
+}
+
+/// A client id with `/`, `+` or `#` would span several levels or act as a
+/// wildcard inside the namespace and reach other clients' topics, so such a
+/// client owns no namespace.
+fn can_own_response_namespace(client_id: &str) -> bool {
+    !client_id.contains(['/', '+', '#'])
+}
+
+fn is_response_topic_owner(owner: &str, client_id: &str) -> bool {
+    owner == client_id && can_own_response_namespace(client_id)
+}
+
+/// Owner of a topic name or filter inside the response namespace. `None` if
+/// the topic is outside the namespace.
+fn response_topic_owner<'a>(prefix: &str, topic: &'a str) -> Option<&'a str> {
+    if prefix.is_empty() {
+        return None;
+    }
+    let mut levels = topic.split('/');
+    if levels.next() != Some(prefix) {
+        return None;
+    }
+    Some(levels.next().unwrap_or(""))
+}
+
+/// Whether the client may subscribe to a filter. Filters inside the response
+/// namespace must name the subscriber literally as the owner level, so
+/// wildcards cannot reach other clients' namespaces.
+pub fn allow_subscribe_response_topic(
+    conf: &MqttProtocolConfig,
+    client_id: &str,
+    sub_path: &str,
+) -> bool {
+    let path = if is_mqtt_share_subscribe(sub_path) {
+        let (_, topic_path) = decode_share_info(sub_path);
+        topic_path.trim_start_matches('/').to_string()
+    } else {
+        decode_exclusive_sub_path_to_topic_name(sub_path)
+            .trim_start_matches('/')
+            .to_string()
+    };
+
+    match response_topic_owner(&conf.response_topic_prefix, &path) {
+        Some(owner) => is_response_topic_owner(owner, client_id),
+        None => true,
+    }
+}
+
+/// Check the request/response properties of a PUBLISH.
+pub fn check_request_properties(
+    conf: &MqttProtocolConfig,
+    client_id: &str,
+    properties: &PublishProperties,
+) -> Result<(), MqttBrokerError> {
+    if let Some(response_topic) = &properties.response_topic {
+        if response_topic.is_empty() || response_topic.contains(['+', '#']) {
+            return Err(MqttBrokerError::InvalidResponseTopic(
+                response_topic.clone(),
+                "must be a non-empty topic name without wildcards".to_string(),
+            ));
+        }
+        if let Some(owner) = response_topic_owner(&conf.response_topic_prefix, response_topic) {
+            if !is_response_topic_owner(owner, client_id) {
+                return Err(MqttBrokerError::ResponseTopicNotAuthorized(
+                    response_topic.clone(),
+                ));
+            }
+        }
+    }
+
+    if let Some(correlation_data) = &properties.correlation_data {
+        let max = conf.max_correlation_data_size as usize;
+        if max > 0 && correlation_data.len() > max {
+            return Err(MqttBrokerError::CorrelationDataTooLarge(
+                correlation_data.len(),
+                max,
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+
+    #[test]
+    fn test_response_topic_namespace() {
+        let mut conf = MqttProtocolConfig::default();
+        assert_eq!(
+            response_topic_namespace(&conf, "c1"),
+            Some("$rpc/c1".to_string())
+        );
+        conf.response_topic_prefix = String::new();
+        assert_eq!(response_topic_namespace(&conf, "c1"), None);
+        assert!(allow_subscribe_response_topic(&conf, "c1", "$rpc/c2/#"));
+    }
+
+    #[test]
+    fn test_allow_subscribe_response_topic() {
+        let conf = MqttProtocolConfig::default();
+        assert!(allow_subscribe_response_topic(&conf, "c1", "$rpc/c1/#"));
+        assert!(allow_subscribe_response_topic(&conf, "c1", "$rpc/c1"));
+        assert!(allow_subscribe_response_topic(&conf, "c1", "a/b"));
+        assert!(allow_subscribe_response_topic(&conf, "c1", "$rpcx/c2"));
+        assert!(!allow_subscribe_response_topic(&conf, "c1", "$rpc/c2/#"));
+        assert!(!allow_subscribe_response_topic(&conf, "c1", "$rpc/+/reply"));
+        assert!(!allow_subscribe_response_topic(&conf, "c1", "$rpc/#"));
+        assert!(!allow_subscribe_response_topic(
+            &conf,
+            "c1",
+            "$share/g/$rpc/c2/#"
+        ));
+        assert!(allow_subscribe_response_topic(
+            &conf,
+            "c1",
+            "$share/g/$rpc/c1/#"
+        ));
+        assert!(!allow_subscribe_response_topic(
+            &conf,
+            "c1",
+            "$exclusive/$rpc/c2"
+        ));
+
+        // client ids that would act as wildcards or extra levels own nothing
+        assert_eq!(response_topic_namespace(&conf, "+"), None);
+        assert_eq!(response_topic_namespace(&conf, "a/b"), None);
+        assert!(!allow_subscribe_response_topic(&conf, "+", "$rpc/+/#"));
+        assert!(!allow_subscribe_response_topic(&conf, "#", "$rpc/#"));
+        assert!(!allow_subscribe_response_topic(&conf, "a/b", "$rpc/a/#"));
+        assert!(allow_subscribe_response_topic(&conf, "a/b", "a/b/#"));
+    }
+
+    #[test]
+    fn test_check_request_properties() {
+        let conf = MqttProtocolConfig::default();
+        let properties = |topic: &str, data_len: usize| PublishProperties {
+            response_topic: Some(topic.to_string()),
+            correlation_data: Some(Bytes::from(vec![0u8; data_len])),
+            ..Default::default()
+        };
+
+        assert!(check_request_properties(&conf, "c1", &properties("$rpc/c1/r", 16)).is_ok());
+        assert!(check_request_properties(&conf, "c1", &properties("reply/c1", 16)).is_ok());
+        assert!(check_request_properties(&conf, "c1", &properties("$rpc/c2/r", 16)).is_err());
+        assert!(check_request_properties(&conf, "c1", &properties("$rpc/c1/+", 16)).is_err());
+        assert!(check_request_properties(&conf, "c1", &properties("$rpc/c1/r", 4097)).is_err());
+        assert!(check_request_properties(&conf, "c1", &PublishProperties::default()).is_ok());
+        assert!(check_request_properties(&conf, "a/b", &properties("$rpc/a/b", 16)).is_err());
+    }
+}
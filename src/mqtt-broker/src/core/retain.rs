@@ -14,30 +14,31 @@
 
 use super::cache::MQTTCacheManager;
 use super::constant::{
-    MAX_RETAIN_MESSAGE_SEND_CONCURRENCY, SUB_RETAIN_MESSAGE_PUSH_FLAG,
+    MAX_RETAIN_MESSAGE_SEND_CONCURRENCY, RETAIN_INDEX_BACKFILL_BATCH, SUB_RETAIN_MESSAGE_PUSH_FLAG,
     SUB_RETAIN_MESSAGE_PUSH_FLAG_VALUE,
 };
 use super::message::build_message_expire;
 use crate::core::error::MqttBrokerError;
-use crate::core::sub_option::is_send_retain_msg_by_retain_handling;
-use crate::core::subscribe::is_new_sub;
+use crate::core::sub_option::{is_send_retain_msg_by_retain_handling, retain_message_send_qos};
 use crate::core::tool::ResultMqttBrokerError;
-use crate::storage::retain::RetainStorage;
+use crate::storage::retain::{RetainIndexStorage, RetainStorage};
+use crate::subscribe::common::client_unavailable_error;
 use crate::subscribe::common::SubPublishParam;
-use crate::subscribe::common::{client_unavailable_error, get_sub_topic_name_list};
-use crate::subscribe::manager::SubscribeManager;
 use crate::subscribe::push::send_publish_packet_to_client;
 use bytes::Bytes;
-use common_base::tools::now_second;
+use common_base::tools::{now_millis, now_second};
+use common_config::broker::broker_config;
 use common_metrics::mqtt::packets::{record_retain_recv_metrics, record_retain_sent_metrics};
 use common_metrics::mqtt::statistics::{record_mqtt_retained_dec, record_mqtt_retained_inc};
-use metadata_struct::mqtt::retain_message::MQTTRetainMessage;
+use dashmap::DashMap;
+use metadata_struct::mqtt::retain_message::{MQTTRetainIndex, MQTTRetainMessage};
 use network_server::common::connection_manager::ConnectionManager;
-use protocol::mqtt::common::{MqttPacket, Publish, PublishProperties, QoS, Subscribe};
+use protocol::mqtt::common::{MqttPacket, Publish, PublishProperties, Subscribe};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use storage_adapter::driver::StorageDriverManager;
 use tokio::sync::{broadcast, Semaphore};
-use tracing::{debug, warn};
+use tracing::{debug, info, warn};
 
 pub async fn save_retain_message(
     storage_driver_manager: &Arc<StorageDriverManager>,
@@ -51,61 +52,184 @@ pub async fn save_retain_message(
         return Ok(());
     }
 
-    let topic_storage = RetainStorage::new(storage_driver_manager.clone());
-    let had_retain = topic_storage
-        .get_retain_message(tenant, topic_name)
-        .await?
-        .is_some();
+    // Only this broker's view, used for the retained gauge. It saves a meta
+    // service call on every retained publish.
+    let had_retain = cache_manager
+        .topic_stats
+        .get(tenant, topic_name)
+        .is_some_and(|stats| stats.retained);
 
-    if had_retain && publish.payload.is_empty() {
-        topic_storage
-            .delete_retain_message(tenant, topic_name)
-            .await?;
-        record_mqtt_retained_dec();
+    if publish.payload.is_empty() {
+        // The message may have been retained through another broker or before
+        // a restart, clearing it is harmless when there is none.
+        delete_retain_message(storage_driver_manager, cache_manager, tenant, topic_name).await?;
+        if had_retain {
+            record_mqtt_retained_dec();
+        }
+        cache_manager
+            .topic_stats
+            .set_retained(tenant, topic_name, false);
         return Ok(());
     }
 
-    if !publish.payload.is_empty() {
-        record_retain_recv_metrics(publish.qos);
-        if !had_retain {
-            record_mqtt_retained_inc();
-        }
+    record_retain_recv_metrics(publish.qos);
+    if !had_retain {
+        record_mqtt_retained_inc();
+    }
 
-        let expired_at = build_message_expire(cache_manager, publish_properties).await;
-        let retain_message = MQTTRetainMessage {
-            tenant: tenant.to_string(),
-            topic_name: topic_name.to_string(),
-            payload: publish.payload.clone(),
-            expired_at,
-            create_time: now_second(),
-        };
-
-        topic_storage
-            .set_retain_message(tenant, topic_name, &retain_message)
-            .await?;
+    let expired_at = build_message_expire(cache_manager, publish_properties).await;
+    let retain_message = MQTTRetainMessage {
+        tenant: tenant.to_string(),
+        topic_name: topic_name.to_string(),
+        payload: publish.payload.clone(),
+        expired_at,
+        create_time: now_second(),
+    };
+    let index = build_retain_index(&retain_message, now_millis() as u64);
+
+    // The payload goes first so that an index entry always points to a
+    // stored payload.
+    let topic_storage = RetainStorage::new(storage_driver_manager.clone());
+    topic_storage
+        .set_retain_message(tenant, topic_name, &retain_message)
+        .await?;
+    RetainIndexStorage::new(cache_manager.client_pool.clone())
+        .set_retain_index(&index)
+        .await?;
+    cache_manager.retain_cache.put(
+        broker_config().mqtt_retain.cache_capacity,
+        index.version,
+        retain_message,
+    );
+    cache_manager
+        .topic_stats
+        .set_retained(tenant, topic_name, true);
+
+    Ok(())
+}
+
+fn build_retain_index(message: &MQTTRetainMessage, version: u64) -> MQTTRetainIndex {
+    MQTTRetainIndex {
+        tenant: message.tenant.clone(),
+        topic_name: message.topic_name.clone(),
+        payload_size: message.payload.len() as u64,
+        expired_at: message.expired_at,
+        version,
+        create_time: message.create_time,
+    }
+}
+
+/// Index the retained messages stored before the retain index existed, so
+/// subscribers keep receiving them after an upgrade. Every cached topic
+/// without an index entry is looked up in the retain storage once.
+///
+/// The version is derived from the stored message, so brokers backfilling
+/// at the same time write the same entry.
+pub async fn backfill_retain_index(
+    storage_driver_manager: &Arc<StorageDriverManager>,
+    cache_manager: &Arc<MQTTCacheManager>,
+) -> ResultMqttBrokerError {
+    let mut topics: HashMap<String, Vec<String>> = HashMap::new();
+    for topic in cache_manager.node_cache.topic_list.iter() {
+        topics
+            .entry(topic.tenant.clone())
+            .or_default()
+            .push(topic.topic_name.clone());
     }
 
+    let index_storage = RetainIndexStorage::new(cache_manager.client_pool.clone());
+    let storage = RetainStorage::new(storage_driver_manager.clone());
+    let mut backfilled = 0;
+    for (tenant, topic_names) in topics {
+        let indexed: HashSet<String> = index_storage
+            .list_retain_index(&tenant, "#")
+            .await?
+            .into_iter()
+            .map(|index| index.topic_name)
+            .collect();
+        let missing: Vec<&str> = topic_names
+            .iter()
+            .filter(|topic_name| !indexed.contains(*topic_name))
+            .map(String::as_str)
+            .collect();
+
+        for batch in missing.chunks(RETAIN_INDEX_BACKFILL_BATCH) {
+            for message in storage.get_retain_messages(&tenant, batch).await? {
+                let index = build_retain_index(&message, message.create_time * 1000);
+                index_storage.set_retain_index(&index).await?;
+                backfilled += 1;
+            }
+        }
+    }
+
+    if backfilled > 0 {
+        info!("Backfilled {} retain index entries", backfilled);
+    }
     Ok(())
 }
 
+/// Remove the retained message of `topic_name` from the index, the storage
+/// adapter and the local cache. The index goes first so that subscribers stop
+/// seeing the topic before its payload disappears.
+async fn delete_retain_message(
+    storage_driver_manager: &Arc<StorageDriverManager>,
+    cache_manager: &Arc<MQTTCacheManager>,
+    tenant: &str,
+    topic_name: &str,
+) -> ResultMqttBrokerError {
+    RetainIndexStorage::new(cache_manager.client_pool.clone())
+        .delete_retain_index(tenant, topic_name)
+        .await?;
+    cache_manager.retain_cache.remove(tenant, topic_name);
+    RetainStorage::new(storage_driver_manager.clone())
+        .delete_retain_message(tenant, topic_name)
+        .await
+}
+
+/// Payload of the retained message `index` refers to, from the local cache or
+/// else from the storage adapter.
+async fn load_retain_message(
+    storage_driver_manager: &Arc<StorageDriverManager>,
+    cache_manager: &Arc<MQTTCacheManager>,
+    index: &MQTTRetainIndex,
+) -> Result<Option<MQTTRetainMessage>, MqttBrokerError> {
+    if let Some(message) =
+        cache_manager
+            .retain_cache
+            .get(&index.tenant, &index.topic_name, index.version)
+    {
+        return Ok(Some(message));
+    }
+
+    let storage = RetainStorage::new(storage_driver_manager.clone());
+    let Some(message) = storage
+        .get_retain_message(&index.tenant, &index.topic_name)
+        .await?
+    else {
+        return Ok(None);
+    };
+    cache_manager.retain_cache.put(
+        broker_config().mqtt_retain.cache_capacity,
+        index.version,
+        message.clone(),
+    );
+    Ok(Some(message))
+}
+
 pub struct SendRetainContext<'a> {
     pub storage_driver_manager: &'a Arc<StorageDriverManager>,
     pub cache_manager: &'a Arc<MQTTCacheManager>,
     pub connection_manager: &'a Arc<ConnectionManager>,
-    pub subscribe_manager: &'a Arc<SubscribeManager>,
     pub tenant: &'a str,
     pub client_id: &'a str,
     pub subscribe: &'a Subscribe,
+    /// Filter path → whether it did not exist before this SUBSCRIBE, taken
+    /// before the subscription is saved.
+    pub is_new_subs: &'a DashMap<String, bool>,
     pub stop_sx: &'a broadcast::Sender<bool>,
 }
 
 pub async fn try_send_retain_message(ctx: SendRetainContext<'_>) -> Result<(), MqttBrokerError> {
-    let is_new_subs = is_new_sub(
-        ctx.tenant,
-        ctx.client_id,
-        ctx.subscribe,
-        ctx.subscribe_manager,
-    );
     let semaphore = Arc::new(Semaphore::new(MAX_RETAIN_MESSAGE_SEND_CONCURRENCY));
     let mut handles = Vec::new();
 
@@ -113,7 +237,7 @@ pub async fn try_send_retain_message(ctx: SendRetainContext<'_>) -> Result<(), M
         if !is_send_retain_msg_by_retain_handling(
             &filter.path,
             &filter.retain_handling,
-            &is_new_subs,
+            ctx.is_new_subs,
         ) {
             debug!(
                 "retain messages: Determine whether to send retained messages based on the \
@@ -123,94 +247,125 @@ pub async fn try_send_retain_message(ctx: SendRetainContext<'_>) -> Result<(), M
             continue;
         }
 
-        let topic_name_list = get_sub_topic_name_list(ctx.cache_manager, &filter.path).await;
-
-        for topic_name in topic_name_list {
-            let storage = RetainStorage::new(ctx.storage_driver_manager.clone());
-            let retain_message = match storage.get_retain_message(ctx.tenant, &topic_name).await? {
-                Some(msg) => msg,
-                None => continue,
-            };
+        let index_storage = RetainIndexStorage::new(ctx.cache_manager.client_pool.clone());
+        let index_list = index_storage
+            .list_retain_index(ctx.tenant, &filter.path)
+            .await?;
 
-            if retain_message.expired_at > 0 && now_second() >= retain_message.expired_at {
-                // Clean up expired retain message from storage and update metrics
-                if let Err(e) = storage.delete_retain_message(ctx.tenant, &topic_name).await {
+        for index in index_list {
+            if index.is_expired(now_second()) {
+                // Clean up the expired retain message and update metrics
+                if let Err(e) = delete_retain_message(
+                    ctx.storage_driver_manager,
+                    ctx.cache_manager,
+                    ctx.tenant,
+                    &index.topic_name,
+                )
+                .await
+                {
                     warn!(
                         "Failed to delete expired retain message: topic={}, error={}",
-                        topic_name, e
+                        index.topic_name, e
                     );
                 } else {
                     record_mqtt_retained_dec();
-                    debug!("Expired retain message cleaned up: topic={}", topic_name);
+                    debug!(
+                        "Expired retain message cleaned up: topic={}",
+                        index.topic_name
+                    );
                 }
                 continue;
             }
 
             let semaphore_clone = semaphore.clone();
+            let storage_driver_manager = ctx.storage_driver_manager.clone();
             let cache_manager = ctx.cache_manager.clone();
             let connection_manager = ctx.connection_manager.clone();
             let stop_sx = ctx.stop_sx.clone();
             let client_id = ctx.client_id.to_string();
+            let qos = retain_message_send_qos(filter);
 
-            let handle = tokio::spawn(async move {
-                let _permit = match semaphore_clone.acquire_owned().await {
-                    Ok(p) => p,
-                    Err(e) => {
-                        warn!("Failed to acquire semaphore for retain send: {}", e);
-                        return;
-                    }
-                };
-
-                let qos = QoS::AtLeastOnce;
-                let p_kid = cache_manager
-                    .pkid_manager
-                    .generate_publish_to_client_pkid(&client_id, &qos)
-                    .await;
-
-                let publish = Publish {
-                    dup: false,
-                    qos,
-                    p_kid,
-                    retain: false,
-                    topic: Bytes::copy_from_slice(retain_message.topic_name.as_bytes()),
-                    payload: retain_message.payload.clone(),
-                };
-
-                let publish_properties = PublishProperties {
-                    user_properties: vec![(
-                        SUB_RETAIN_MESSAGE_PUSH_FLAG.to_string(),
-                        SUB_RETAIN_MESSAGE_PUSH_FLAG_VALUE.to_string(),
-                    )],
-                    ..Default::default()
-                };
-
-                let packet = MqttPacket::Publish(publish, Some(publish_properties));
-                let sub_pub_param = SubPublishParam {
-                    packet,
-                    create_time: now_second(),
-                    client_id: client_id.clone(),
-                    p_kid,
-                    qos,
-                };
-
-                if let Err(e) = send_publish_packet_to_client(
-                    &connection_manager,
-                    &cache_manager,
-                    &sub_pub_param,
-                    &stop_sx,
-                )
-                .await
-                {
-                    if !client_unavailable_error(&e) {
-                        warn!(
-                            "Sending retain message failed: client_id={}, topic={}, error={}",
-                            client_id, retain_message.topic_name, e
-                        );
+            let handle =
+                tokio::spawn(async move {
+                    let _permit = match semaphore_clone.acquire_owned().await {
+                        Ok(p) => p,
+                        Err(e) => {
+                            warn!("Failed to acquire semaphore for retain send: {}", e);
+                            return;
+                        }
+                    };
+
+                    // Payloads are only loaded once a subscription matches them.
+                    let retain_message =
+                        match load_retain_message(&storage_driver_manager, &cache_manager, &index)
+                            .await
+                        {
+                            Ok(Some(message)) => message,
+                            Ok(None) => {
+                                debug!(
+                                    "Retain index without stored payload: topic={}",
+                                    index.topic_name
+                                );
+                                return;
+                            }
+                            Err(e) => {
+                                warn!(
+                                    "Failed to load retain message: topic={}, error={}",
+                                    index.topic_name, e
+                                );
+                                return;
+                            }
+                        };
+
+                    let p_kid = cache_manager
+                        .pkid_manager
+                        .generate_publish_to_client_pkid(&client_id, &qos)
+                        .await;
+
+                    let publish = Publish {
+                        dup: false,
+                        qos,
+                        p_kid,
+                        retain: true,
+                        topic: Bytes::copy_from_slice(retain_message.topic_name.as_bytes()),
+                        payload: retain_message.payload.clone(),
+                    };
+
+                    let publish_properties = PublishProperties {
+                        user_properties: vec![(
+                            SUB_RETAIN_MESSAGE_PUSH_FLAG.to_string(),
+                            SUB_RETAIN_MESSAGE_PUSH_FLAG_VALUE.to_string(),
+                        )],
+                        ..Default::default()
+                    };
+
+                    let packet = MqttPacket::Publish(publish, Some(publish_properties));
+                    let sub_pub_param = SubPublishParam {
+                        packet,
+                        create_time: now_second(),
+                        client_id: client_id.clone(),
+                        p_kid,
+                        qos,
+                    };
+
+                    if let Err(e) = send_publish_packet_to_client(
+                        &connection_manager,
+                        &cache_manager,
+                        &sub_pub_param,
+                        &stop_sx,
+                    )
+                    .await
+                    {
+                        if !client_unavailable_error(&e) {
+                            warn!(
+                                "Sending retain message failed: client_id={}, topic={}, error={}",
+                                client_id, retain_message.topic_name, e
+                            );
+                        }
+                    } else {
+                        record_retain_sent_metrics(qos);
                     }
-                } else {
-                    record_retain_sent_metrics(qos);
-                }
-            });
+                });
 
             handles.push(handle);
         }
@@ -0,0 +1,164 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use metadata_struct::mqtt::retain_message::MQTTRetainMessage;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+type RetainKey = (String, String);
+
+struct RetainCacheEntry {
+    version: u64,
+    tick: u64,
+    message: MQTTRetainMessage,
+}
+
+#[derive(Default)]
+struct RetainCacheRecords {
+    tick: u64,
+    entries: HashMap<RetainKey, RetainCacheEntry>,
+    // tick of last use -> key, the first entry is the least recently used
+    order: BTreeMap<u64, RetainKey>,
+}
+
+impl RetainCacheRecords {
+    fn touch(&mut self, key: &RetainKey) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.order.remove(&entry.tick);
+            entry.tick = tick;
+            self.order.insert(tick, key.clone());
+        }
+    }
+
+    fn remove(&mut self, key: &RetainKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.order.remove(&entry.tick);
+        }
+    }
+}
+
+/// LRU cache of retained payloads loaded from the storage adapter. Entries are
+/// tagged with the index version they were loaded for, so a payload replaced
+/// through another broker is never served stale.
+#[derive(Default)]
+pub struct RetainMessageCache {
+    inner: Mutex<RetainCacheRecords>,
+}
+
+impl RetainMessageCache {
+    /// Cached payload of `topic_name` if it belongs to index `version`.
+    pub fn get(&self, tenant: &str, topic_name: &str, version: u64) -> Option<MQTTRetainMessage> {
+        let key = (tenant.to_string(), topic_name.to_string());
+        let mut inner = self.inner.lock().unwrap();
+        match inner.entries.get(&key) {
+            Some(entry) if entry.version == version => {
+                let message = entry.message.clone();
+                inner.touch(&key);
+                Some(message)
+            }
+            Some(_) => {
+                inner.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Keep `message` as the payload of index `version`, evicting the least
+    /// recently used entries beyond `capacity`.
+    pub fn put(&self, capacity: usize, version: u64, message: MQTTRetainMessage) {
+        if capacity == 0 {
+            return;
+        }
+        let key = (message.tenant.clone(), message.topic_name.clone());
+        let mut inner = self.inner.lock().unwrap();
+        inner.remove(&key);
+        inner.tick += 1;
+        let tick = inner.tick;
+        inner.order.insert(tick, key.clone());
+        inner.entries.insert(
+            key,
+            RetainCacheEntry {
+                version,
+                tick,
+                message,
+            },
+        );
+        while inner.entries.len() > capacity {
+            let Some((_, oldest)) = inner.order.pop_first() else {
+                break;
+            };
+            inner.entries.remove(&oldest);
+        }
+    }
+
+    pub fn remove(&self, tenant: &str, topic_name: &str) {
+        let key = (tenant.to_string(), topic_name.to_string());
+        self.inner.lock().unwrap().remove(&key);
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(topic: &str) -> MQTTRetainMessage {
+        MQTTRetainMessage {
+            tenant: "t1".to_string(),
+            topic_name: topic.to_string(),
+            payload: topic.as_bytes().to_vec().into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_version_mismatch_is_a_miss() {
+        let cache = RetainMessageCache::default();
+        cache.put(10, 1, message("a"));
+        assert!(cache.get("t1", "a", 1).is_some());
+        assert!(cache.get("t2", "a", 1).is_none());
+
+        assert!(cache.get("t1", "a", 2).is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let cache = RetainMessageCache::default();
+        cache.put(2, 1, message("a"));
+        cache.put(2, 1, message("b"));
+        assert!(cache.get("t1", "a", 1).is_some());
+
+        cache.put(2, 1, message("c"));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("t1", "b", 1).is_none());
+        assert!(cache.get("t1", "a", 1).is_some());
+        assert!(cache.get("t1", "c", 1).is_some());
+
+        cache.remove("t1", "a");
+        assert_eq!(cache.len(), 1);
+
+        cache.put(0, 1, message("d"));
+        assert!(cache.get("t1", "d", 1).is_none());
+    }
+}
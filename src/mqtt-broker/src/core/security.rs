@@ -16,8 +16,10 @@
 use crate::core::{error::MqttBrokerError, tenant::try_decode_username};
 use crate::subscribe::common::get_sub_topic_name_list;
 use broker_core::cache::NodeCacheManager;
-use common_metrics::mqtt::auth::{record_mqtt_acl_failed, record_mqtt_acl_success};
-use common_security::auth::acl::{is_client_id_acl_deny, is_user_acl_deny};
+use common_metrics::mqtt::auth::{
+    record_mqtt_acl_failed, record_mqtt_acl_success, record_mqtt_blacklist_blocked,
+};
+use common_security::auth::acl::{is_acl_deny, AclCheckRequest};
 use common_security::auth::blacklist::{
     is_client_id_blacklisted, is_ip_blacklisted, is_user_blacklisted,
 };
@@ -58,7 +60,8 @@ pub async fn security_login_check(
                 if let Some(user_info) = login {
                     let username = try_decode_username(&user_info.username);
                     let password = user_info.password.clone();
-                    if password_check_by_login(security_manager, tenant, &username, &password) {
+                    if password_check_by_login(security_manager, tenant, &username, &password).await
+                    {
                         return Ok(true);
                     }
                 }
@@ -79,11 +82,13 @@ pub async fn security_is_allow_connect(
 ) -> Result<bool, MqttBrokerError> {
     let login = login.clone().unwrap_or_default();
 
-    Ok(
-        !is_user_blacklisted(security_manager, tenant, &login.username)
-            && !is_client_id_blacklisted(security_manager, tenant, client_id)
-            && !is_ip_blacklisted(security_manager, tenant, source_ip)?,
-    )
+    let blocked = is_user_blacklisted(security_manager, tenant, &login.username)
+        || is_client_id_blacklisted(security_manager, tenant, client_id)
+        || is_ip_blacklisted(security_manager, tenant, source_ip)?;
+    if blocked {
+        record_mqtt_blacklist_blocked();
+    }
+    Ok(!blocked)
 }
 
 pub enum ConnectAuthResult {
@@ -92,6 +97,7 @@ pub enum ConnectAuthResult {
     NotAuthorized,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn security_check_connect(
     security_manager: &Arc<SecurityManager>,
     node_cache: &Arc<NodeCacheManager>,
@@ -100,10 +106,14 @@ pub async fn security_check_connect(
     source_ip: &str,
     login: &Option<Login>,
     connect_properties: &Option<ConnectProperties>,
+    cert_authenticated: bool,
 ) -> Result<ConnectAuthResult, MqttBrokerError> {
     if !security_is_allow_connect(security_manager, tenant, client_id, source_ip, login).await? {
         return Ok(ConnectAuthResult::Banned);
     }
+    if cert_authenticated {
+        return Ok(ConnectAuthResult::Allowed);
+    }
     if security_login_check(
         security_manager,
         node_cache,
@@ -130,56 +140,23 @@ pub async fn security_is_allow_publish(
         return Ok(true);
     }
 
-    let source_ip = connection.source_ip.as_str();
-
-    if is_client_id_acl_deny(
-        security_manager,
-        topic_name,
-        &connection.tenant,
-        &connection.client_id,
-        source_ip,
-        &EnumAclAction::Publish,
-    )? {
-        record_mqtt_acl_failed();
-        return Ok(false);
-    }
-
-    if is_user_acl_deny(
+    if is_connection_acl_deny(
         security_manager,
-        topic_name,
-        &connection.tenant,
+        connection,
         &user,
-        source_ip,
-        &EnumAclAction::Publish,
-    )? {
-        record_mqtt_acl_failed();
-        return Ok(false);
-    }
-
-    if retain {
-        if is_client_id_acl_deny(
-            security_manager,
-            topic_name,
-            &connection.tenant,
-            &connection.client_id,
-            source_ip,
-            &EnumAclAction::Retain,
-        )? {
-            record_mqtt_acl_failed();
-            return Ok(false);
-        }
-
-        if is_user_acl_deny(
+        topic_name,
+        EnumAclAction::Publish,
+    )? || (retain
+        && is_connection_acl_deny(
             security_manager,
-            topic_name,
-            &connection.tenant,
+            connection,
             &user,
-            source_ip,
-            &EnumAclAction::Retain,
-        )? {
-            record_mqtt_acl_failed();
-            return Ok(false);
-        }
+            topic_name,
+            EnumAclAction::Retain,
+        )?)
+    {
+        record_mqtt_acl_failed();
+        return Ok(false);
     }
 
     record_mqtt_acl_success();
@@ -198,30 +175,15 @@ pub async fn security_is_allow_subscribe(
         return Ok(true);
     }
 
-    let source_ip = connection.source_ip.as_str();
-
     for filter in subscribe.filters.iter() {
         let topic_list = get_sub_topic_name_list(cache_manager, &filter.path).await;
         for topic_name in topic_list {
-            if is_client_id_acl_deny(
+            if is_connection_acl_deny(
                 security_manager,
-                &topic_name,
-                &connection.tenant,
-                &connection.client_id,
-                source_ip,
-                &EnumAclAction::Subscribe,
-            )? {
-                record_mqtt_acl_failed();
-                return Ok(false);
-            }
-
-            if is_user_acl_deny(
-                security_manager,
-                &topic_name,
-                &connection.tenant,
+                connection,
                 &user,
-                source_ip,
-                &EnumAclAction::Subscribe,
+                &topic_name,
+                EnumAclAction::Subscribe,
             )? {
                 record_mqtt_acl_failed();
                 return Ok(false);
@@ -231,3 +193,24 @@ pub async fn security_is_allow_subscribe(
 
     Ok(true)
 }
+
+fn is_connection_acl_deny(
+    security_manager: &Arc<SecurityManager>,
+    connection: &MQTTConnection,
+    user: &str,
+    topic_name: &str,
+    action: EnumAclAction,
+) -> Result<bool, MqttBrokerError> {
+    Ok(is_acl_deny(
+        security_manager,
+        &AclCheckRequest {
+            tenant: &connection.tenant,
+            client_id: &connection.client_id,
+            username: user,
+            source_ip: &connection.source_ip,
+            topic_name,
+            action,
+            attributes: &connection.attributes,
+        },
+    )?)
+}
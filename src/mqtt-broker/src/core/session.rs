@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use super::cache::MQTTCacheManager;
+use super::client_attribute::{connect_attributes, merge_connect_attributes};
 use super::error::MqttBrokerError;
 use super::last_will::last_will_delay_interval;
 use crate::core::limit::session_total_num_limit;
@@ -33,6 +34,8 @@ pub struct BuildSessionContext {
     pub tenant: String,
     pub connect_id: u64,
     pub client_id: String,
+    /// `client_id` was generated by the broker.
+    pub assigned_client_id: bool,
     pub connect: Connect,
     pub connect_properties: Option<ConnectProperties>,
     pub last_will: Option<LastWill>,
@@ -66,13 +69,20 @@ pub async fn session_process(
     let session_storage = SessionStorage::new(context.client_pool.clone());
     if context.connect.clean_session {
         // Clean Session = 1
+        // A client reconnecting with the id it was assigned keeps it marked
+        // as assigned.
+        let was_assigned = context
+            .cache_manager
+            .get_session_info(&context.client_id)
+            .is_some_and(|session| session.assigned_client_id);
         delete_session_by_local(
             &context.cache_manager,
             &context.subscribe_manager,
             &context.tenant,
             &context.client_id,
         );
-        let session = build_new_session(&context).await;
+        let mut session = build_new_session(&context).await;
+        session.assigned_client_id |= was_assigned;
         if protocol.is_mqtt5() {
             save_session(
                 session.clone(),
@@ -98,6 +108,12 @@ pub async fn session_process(
         session.update_broker_id(Some(conf.broker_id));
         session.update_reconnect_time();
         session.distinct_time = None;
+        let config = context.cache_manager.node_cache.cluster_config.load();
+        merge_connect_attributes(
+            &config.mqtt_client_attribute,
+            &mut session.attributes,
+            connect_attributes(&config.mqtt_client_attribute, &context.connect_properties),
+        );
         save_session(
             session.clone(),
             context.client_id.clone(),
@@ -131,6 +147,7 @@ pub fn delete_session_by_local(
     client_id: &str,
 ) {
     subscribe_manager.remove_by_client_id(tenant, client_id);
+    subscribe_manager.offline_queues.clear(tenant, client_id);
     cache_manager.remove_session(client_id);
 }
 
@@ -151,6 +168,16 @@ async fn build_new_session(context: &BuildSessionContext) -> MqttSession {
     session.update_connection_id(Some(context.connect_id));
     session.update_broker_id(Some(conf.broker_id));
     session.update_reconnect_time();
+    session.attributes = connect_attributes(
+        &context
+            .cache_manager
+            .node_cache
+            .cluster_config
+            .load()
+            .mqtt_client_attribute,
+        &context.connect_properties,
+    );
+    session.assigned_client_id = context.assigned_client_id;
     session
 }
 
@@ -0,0 +1,407 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Export and import of persistent session state, used to move clients
+//! between clusters or to take them over from another broker.
+//!
+//! An export holds, per session, the session itself, its subscriptions, its
+//! will message, its inflight packets and the messages queued while it was
+//! offline. Inflight packets carry their record, since the target cluster
+//! does not hold the source topic data: on import, packets waiting for
+//! PUBACK/PUBREC are queued for redelivery and packets waiting for PUBCOMP
+//! are kept as inflight, so PUBREL is resent with the same packet id.
+//!
+//! Queued messages live in the broker-local RocksDB of the importing node,
+//! clients should reconnect to that node first.
+
+use crate::core::cache::MQTTCacheManager;
+use crate::core::error::MqttBrokerError;
+use crate::core::inflight::{InflightMessage, InflightStage};
+use crate::core::offline_queue::{EnqueueResult, OfflineQueueMessage};
+use crate::storage::inflight::InflightStorage;
+use crate::storage::last_will::LastWillStorage;
+use crate::storage::session::SessionStorage;
+use crate::subscribe::common::Subscriber;
+use crate::subscribe::manager::SubscribeManager;
+use common_base::tools::now_second;
+use common_config::broker::broker_config;
+use grpc_clients::meta::mqtt::call::placement_set_subscribe;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::adapter::adapter_read_config::AdapterReadConfig;
+use metadata_struct::mqtt::lastwill::MqttLastWillData;
+use metadata_struct::mqtt::session::MqttSession;
+use metadata_struct::mqtt::subscribe::MqttSubscribe;
+use metadata_struct::storage::record::StorageRecord;
+use protocol::meta::meta_service_mqtt::SetSubscribeRequest;
+use rocksdb_engine::rocksdb::RocksDBEngine;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use storage_adapter::driver::StorageDriverManager;
+
+pub const SESSION_EXPORT_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SessionExport {
+    pub version: u32,
+    pub broker_id: u64,
+    pub export_time: u64,
+    pub sessions: Vec<SessionSnapshot>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub session: MqttSession,
+    pub subscriptions: Vec<MqttSubscribe>,
+    pub last_will: Option<MqttLastWillData>,
+    pub inflight: Vec<InflightSnapshot>,
+    pub queued: Vec<OfflineQueueMessage>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InflightSnapshot {
+    pub message: InflightMessage,
+    /// None when the record has expired or the packet waits for PUBCOMP.
+    pub record: Option<StorageRecord>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SessionImportResult {
+    pub tenant: String,
+    pub client_id: String,
+    pub imported: bool,
+    /// Why the session was skipped.
+    pub reason: Option<String>,
+    pub subscriptions: usize,
+    pub inflight: usize,
+    pub queued: usize,
+    /// Messages that could not be queued: offline queue disabled or full,
+    /// or an inflight packet without its record.
+    pub dropped: usize,
+}
+
+#[derive(Clone)]
+pub struct SessionMigrationContext {
+    pub cache_manager: Arc<MQTTCacheManager>,
+    pub subscribe_manager: Arc<SubscribeManager>,
+    pub client_pool: Arc<ClientPool>,
+    pub storage_driver_manager: Arc<StorageDriverManager>,
+    pub rocksdb_engine_handler: Arc<RocksDBEngine>,
+}
+
+/// Export one client, or every session held by this node when `client_id`
+/// is None. `tenant` narrows a node export to one tenant.
+pub async fn export_sessions(
+    context: &SessionMigrationContext,
+    tenant: Option<&str>,
+    client_id: Option<&str>,
+) -> Result<SessionExport, MqttBrokerError> {
+    let sessions = match client_id {
+        Some(client_id) => {
+            let Some(tenant) = tenant else {
+                return Err(MqttBrokerError::CommonError(
+                    "tenant is required when exporting a single client".to_string(),
+                ));
+            };
+            let session_storage = SessionStorage::new(context.client_pool.clone());
+            match session_storage
+                .get_session(tenant.to_string(), client_id.to_string())
+                .await?
+            {
+                Some(session) => vec![session],
+                None => return Err(MqttBrokerError::SessionDoesNotExist),
+            }
+        }
+        None => context
+            .cache_manager
+            .session_info
+            .iter()
+            .filter(|e| tenant.is_none_or(|t| e.value().tenant == t))
+            .map(|e| e.value().clone())
+            .collect(),
+    };
+
+    let mut snapshots = Vec::with_capacity(sessions.len());
+    for session in sessions {
+        snapshots.push(export_session(context, session).await?);
+    }
+
+    Ok(SessionExport {
+        version: SESSION_EXPORT_VERSION,
+        broker_id: broker_config().broker_id,
+        export_time: now_second(),
+        sessions: snapshots,
+    })
+}
+
+async fn export_session(
+    context: &SessionMigrationContext,
+    session: MqttSession,
+) -> Result<SessionSnapshot, MqttBrokerError> {
+    let (tenant, client_id) = (session.tenant.clone(), session.client_id.clone());
+
+    let subscriptions = context
+        .subscribe_manager
+        .subscribe_list
+        .get(&tenant)
+        .map(|list| {
+            list.iter()
+                .filter(|e| e.value().client_id == client_id)
+                .map(|e| e.value().clone())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let last_will = LastWillStorage::new(context.storage_driver_manager.clone())
+        .get_last_will_message(&tenant, &client_id)
+        .await?;
+
+    let inflight_storage = InflightStorage::new(context.storage_driver_manager.clone());
+    let mut inflight = Vec::new();
+    for message in inflight_storage
+        .list_inflight_message(&tenant, &client_id)
+        .await?
+    {
+        let record = if message.stage == InflightStage::Publish {
+            read_inflight_record(context, &message).await?
+        } else {
+            None
+        };
+        inflight.push(InflightSnapshot { message, record });
+    }
+    inflight.sort_by_key(|i| (i.message.create_time, i.message.pkid));
+
+    let queues = &context.subscribe_manager.offline_queues;
+    let len = queues.len(&context.rocksdb_engine_handler, &tenant, &client_id)?;
+    let queued = if len > 0 {
+        queues.peek(&context.rocksdb_engine_handler, &tenant, &client_id, len)?
+    } else {
+        Vec::new()
+    };
+
+    Ok(SessionSnapshot {
+        session,
+        subscriptions,
+        last_will,
+        inflight,
+        queued,
+    })
+}
+
+async fn read_inflight_record(
+    context: &SessionMigrationContext,
+    message: &InflightMessage,
+) -> Result<Option<StorageRecord>, MqttBrokerError> {
+    let read_config = AdapterReadConfig {
+        max_record_num: 1,
+        max_size: 10 * 1024 * 1024,
+    };
+    let records = context
+        .storage_driver_manager
+        .read_by_shard_offset(
+            &message.tenant,
+            &message.subscriber.topic_name,
+            &message.shard,
+            message.offset,
+            &read_config,
+        )
+        .await?;
+    Ok(records
+        .into_iter()
+        .find(|record| record.metadata.offset == message.offset))
+}
+
+/// Import an export into this cluster. A session that is connected, or
+/// that already exists when `overwrite` is false, is skipped.
+pub async fn import_sessions(
+    context: &SessionMigrationContext,
+    export: SessionExport,
+    overwrite: bool,
+) -> Result<Vec<SessionImportResult>, MqttBrokerError> {
+    if export.version != SESSION_EXPORT_VERSION {
+        return Err(MqttBrokerError::CommonError(format!(
+            "Unsupported session export version {}, expected {}",
+            export.version, SESSION_EXPORT_VERSION
+        )));
+    }
+
+    let session_storage = SessionStorage::new(context.client_pool.clone());
+    let mut results = Vec::with_capacity(export.sessions.len());
+    for snapshot in export.sessions {
+        let mut result = SessionImportResult {
+            tenant: snapshot.session.tenant.clone(),
+            client_id: snapshot.session.client_id.clone(),
+            ..Default::default()
+        };
+
+        let existing = session_storage
+            .get_session(result.tenant.clone(), result.client_id.clone())
+            .await?;
+        result.reason = match existing {
+            Some(session) if session.connection_id.is_some() => {
+                Some("client is connected".to_string())
+            }
+            Some(_) if !overwrite => Some("session already exists".to_string()),
+            _ => None,
+        };
+        if result.reason.is_none() {
+            import_session(context, &session_storage, snapshot, &mut result).await?;
+            result.imported = true;
+        }
+        results.push(result);
+    }
+    Ok(results)
+}
+
+async fn import_session(
+    context: &SessionMigrationContext,
+    session_storage: &SessionStorage,
+    snapshot: SessionSnapshot,
+    result: &mut SessionImportResult,
+) -> Result<(), MqttBrokerError> {
+    let conf = broker_config();
+    let session = offline_session(snapshot.session, now_second());
+    let (tenant, client_id) = (session.tenant.clone(), session.client_id.clone());
+    session_storage
+        .set_session(client_id.clone(), &session)
+        .await?;
+
+    for mut subscribe in snapshot.subscriptions {
+        subscribe.broker_id = conf.broker_id;
+        let request = SetSubscribeRequest {
+            client_id: client_id.clone(),
+            path: subscribe.path.clone(),
+            subscribe: subscribe.encode()?,
+        };
+        placement_set_subscribe(&context.client_pool, &conf.get_meta_service_addr(), request)
+            .await?;
+        result.subscriptions += 1;
+    }
+
+    if let Some(last_will) = snapshot.last_will {
+        LastWillStorage::new(context.storage_driver_manager.clone())
+            .save_last_will_message(&tenant, &client_id, &last_will)
+            .await?;
+    }
+
+    let inflight_storage = InflightStorage::new(context.storage_driver_manager.clone());
+    let mut redeliver = Vec::new();
+    for inflight in snapshot.inflight {
+        match (inflight.message.stage, inflight.record) {
+            (InflightStage::PubRel, _) => {
+                inflight_storage
+                    .save_inflight_message(&inflight.message)
+                    .await?;
+                result.inflight += 1;
+            }
+            (InflightStage::Publish, Some(record)) => {
+                redeliver.push((inflight.message.subscriber, record));
+            }
+            (InflightStage::Publish, None) => result.dropped += 1,
+        }
+    }
+    redeliver.extend(
+        snapshot
+            .queued
+            .into_iter()
+            .map(|message| (message.subscriber, message.record)),
+    );
+
+    let offline_conf = context
+        .cache_manager
+        .node_cache
+        .get_cluster_config()
+        .mqtt_offline_message;
+    for (subscriber, record) in redeliver {
+        if !offline_conf.enable {
+            result.dropped += 1;
+            continue;
+        }
+        let subscriber = Subscriber {
+            tenant: tenant.clone(),
+            client_id: client_id.clone(),
+            ..subscriber
+        };
+        match context.subscribe_manager.offline_queues.enqueue(
+            &context.rocksdb_engine_handler,
+            &offline_conf,
+            &subscriber,
+            &record,
+        )? {
+            EnqueueResult::Queued { dropped } => {
+                result.queued += 1;
+                result.dropped += dropped as usize;
+            }
+            EnqueueResult::Rejected => result.dropped += 1,
+        }
+    }
+    Ok(())
+}
+
+/// The imported session is offline until the client reconnects.
+fn offline_session(mut session: MqttSession, now: u64) -> MqttSession {
+    session.connection_id = None;
+    session.broker_id = None;
+    session.reconnect_time = None;
+    session.distinct_time = Some(now);
+    session
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offline_session() {
+        let mut session =
+            MqttSession::new("t1".to_string(), "c1".to_string(), 3600, false, None, true);
+        session.connection_id = Some(7);
+        session.broker_id = Some(2);
+        session.reconnect_time = Some(100);
+
+        let session = offline_session(session, 200);
+        assert_eq!(session.connection_id, None);
+        assert_eq!(session.broker_id, None);
+        assert_eq!(session.reconnect_time, None);
+        assert_eq!(session.distinct_time, Some(200));
+        assert_eq!(session.session_expiry_interval, 3600);
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let export = SessionExport {
+            version: SESSION_EXPORT_VERSION,
+            broker_id: 1,
+            export_time: 100,
+            sessions: vec![SessionSnapshot {
+                session: MqttSession::new(
+                    "t1".to_string(),
+                    "c1".to_string(),
+                    3600,
+                    false,
+                    None,
+                    true,
+                ),
+                subscriptions: Vec::new(),
+                last_will: None,
+                inflight: Vec::new(),
+                queued: Vec::new(),
+            }],
+        };
+
+        let data = serde_json::to_string(&export).unwrap();
+        let decoded: SessionExport = serde_json::from_str(&data).unwrap();
+        assert_eq!(decoded.version, SESSION_EXPORT_VERSION);
+        assert_eq!(decoded.sessions.len(), 1);
+        assert_eq!(decoded.sessions[0].session.client_id, "c1");
+    }
+}
@@ -0,0 +1,270 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Slow request tracing for inbound MQTT packets.
+//!
+//! Every packet is handled inside [`trace_slow_request`], which keeps a
+//! [`SlowRequestTimer`] in a task local. Handlers call [`mark_phase`] at the
+//! end of each step, so a phase is the time since the previous mark. Packets
+//! that took longer than the configured threshold are kept in a capped
+//! in-memory [`SlowRequestLog`], queried through the admin API and published
+//! to `$SYS/brokers/slow_requests`.
+
+use common_base::tools::now_second;
+use common_config::config::MqttSlowRequestConfig;
+use protocol::mqtt::common::{mqtt_packet_to_string, MqttPacket};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Instant;
+
+pub const SLOW_REQUEST_PHASE_CONNECTION: &str = "connection";
+pub const SLOW_REQUEST_PHASE_HANDLE: &str = "handle";
+pub const SLOW_REQUEST_PHASE_FINISH: &str = "finish";
+
+tokio::task_local! {
+    static SLOW_REQUEST_TIMER: RefCell<SlowRequestTimer>;
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Default, Clone)]
+pub struct SlowRequestPhase {
+    pub name: String,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, Default, Clone)]
+pub struct SlowRequestData {
+    /// Increases with every slow request recorded on this node.
+    pub id: u64,
+    pub tenant: String,
+    pub client_id: String,
+    pub connect_id: u64,
+    pub packet_type: String,
+    /// Publish topic, or the comma separated filters of a (un)subscribe.
+    pub topic: String,
+    pub duration_ms: u64,
+    pub phases: Vec<SlowRequestPhase>,
+    pub create_time: u64,
+}
+
+pub struct SlowRequestTimer {
+    start: Instant,
+    last: Instant,
+    phases: Vec<SlowRequestPhase>,
+}
+
+impl Default for SlowRequestTimer {
+    fn default() -> Self {
+        let now = Instant::now();
+        SlowRequestTimer {
+            start: now,
+            last: now,
+            phases: Vec::new(),
+        }
+    }
+}
+
+impl SlowRequestTimer {
+    pub fn phase(&mut self, name: &str) {
+        let now = Instant::now();
+        self.phases.push(SlowRequestPhase {
+            name: name.to_string(),
+            duration_ms: now.duration_since(self.last).as_millis() as u64,
+        });
+        self.last = now;
+    }
+
+    pub fn elapsed_ms(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+
+    pub fn phases(&self) -> &[SlowRequestPhase] {
+        &self.phases
+    }
+}
+
+/// Run `fut` with a fresh timer and return the timer with its output.
+pub async fn trace_slow_request<F: Future>(fut: F) -> (F::Output, SlowRequestTimer) {
+    SLOW_REQUEST_TIMER
+        .scope(RefCell::new(SlowRequestTimer::default()), async {
+            let output = fut.await;
+            let timer = SLOW_REQUEST_TIMER.with(|timer| timer.take());
+            (output, timer)
+        })
+        .await
+}
+
+/// End the current phase of the traced request. A no-op outside
+/// [`trace_slow_request`].
+pub fn mark_phase(name: &str) {
+    let _ = SLOW_REQUEST_TIMER.try_with(|timer| timer.borrow_mut().phase(name));
+}
+
+#[derive(Default)]
+struct SlowRequestRecords {
+    next_id: u64,
+    reported_id: u64,
+    records: VecDeque<SlowRequestData>,
+}
+
+#[derive(Default)]
+pub struct SlowRequestLog {
+    inner: Mutex<SlowRequestRecords>,
+}
+
+impl SlowRequestLog {
+    /// Keep `data` if the request took longer than the threshold, dropping
+    /// the oldest records beyond `max_records`.
+    pub fn record(&self, config: &MqttSlowRequestConfig, mut data: SlowRequestData) -> bool {
+        if !config.enable || data.duration_ms < config.threshold_ms {
+            return false;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.next_id += 1;
+        data.id = inner.next_id;
+        inner.records.push_back(data);
+        while inner.records.len() > config.max_records {
+            inner.records.pop_front();
+        }
+        true
+    }
+
+    pub fn list(&self) -> Vec<SlowRequestData> {
+        self.inner.lock().unwrap().records.iter().cloned().collect()
+    }
+
+    /// Records kept since the previous call.
+    pub fn take_unreported(&self) -> Vec<SlowRequestData> {
+        let mut inner = self.inner.lock().unwrap();
+        let reported_id = inner.reported_id;
+        inner.reported_id = inner.next_id;
+        inner
+            .records
+            .iter()
+            .filter(|data| data.id > reported_id)
+            .cloned()
+            .collect()
+    }
+}
+
+pub fn build_slow_request_data(
+    tenant: &str,
+    client_id: &str,
+    connect_id: u64,
+    packet: &MqttPacket,
+    timer: &SlowRequestTimer,
+) -> SlowRequestData {
+    let topic = match packet {
+        MqttPacket::Publish(publish, _) => String::from_utf8_lossy(&publish.topic).to_string(),
+        MqttPacket::Subscribe(subscribe, _) => subscribe
+            .filters
+            .iter()
+            .map(|filter| filter.path.as_str())
+            .collect::<Vec<_>>()
+            .join(","),
+        MqttPacket::Unsubscribe(unsubscribe, _) => unsubscribe.filters.join(","),
+        _ => String::new(),
+    };
+
+    SlowRequestData {
+        id: 0,
+        tenant: tenant.to_string(),
+        client_id: client_id.to_string(),
+        connect_id,
+        packet_type: mqtt_packet_to_string(packet),
+        topic,
+        duration_ms: timer.elapsed_ms(),
+        phases: timer.phases().to_vec(),
+        create_time: now_second(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(max_records: usize) -> MqttSlowRequestConfig {
+        MqttSlowRequestConfig {
+            enable: true,
+            threshold_ms: 100,
+            max_records,
+        }
+    }
+
+    fn data(duration_ms: u64) -> SlowRequestData {
+        SlowRequestData {
+            client_id: "c1".to_string(),
+            packet_type: "Publish".to_string(),
+            duration_ms,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_record_threshold_and_cap() {
+        let log = SlowRequestLog::default();
+        assert!(!log.record(&config(2), data(99)));
+        assert!(!log.record(
+            &MqttSlowRequestConfig {
+                enable: false,
+                ..config(2)
+            },
+            data(500)
+        ));
+
+        for duration_ms in [100, 200, 300] {
+            assert!(log.record(&config(2), data(duration_ms)));
+        }
+        let list = log.list();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].duration_ms, 200);
+        assert_eq!(list[1].id, 3);
+    }
+
+    #[test]
+    fn test_take_unreported() {
+        let log = SlowRequestLog::default();
+        log.record(&config(10), data(100));
+        assert_eq!(log.take_unreported().len(), 1);
+        assert!(log.take_unreported().is_empty());
+
+        log.record(&config(10), data(200));
+        let unreported = log.take_unreported();
+        assert_eq!(unreported.len(), 1);
+        assert_eq!(unreported[0].duration_ms, 200);
+    }
+
+    #[tokio::test]
+    async fn test_trace_slow_request_phases() {
+        let (output, timer) = trace_slow_request(async {
+            mark_phase("validate");
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            mark_phase("store");
+            7
+        })
+        .await;
+        assert_eq!(output, 7);
+        let phases = timer.phases();
+        assert_eq!(phases.len(), 2);
+        assert_eq!(phases[0].name, "validate");
+        assert!(phases[1].duration_ms >= 20);
+        assert!(timer.elapsed_ms() >= 20);
+
+        // outside a traced request marks are ignored
+        mark_phase("ignored");
+    }
+}
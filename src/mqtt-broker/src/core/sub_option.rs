@@ -14,9 +14,10 @@
 
 use dashmap::DashMap;
 use metadata_struct::storage::record::StorageRecord;
-use protocol::mqtt::common::RetainHandling;
+use protocol::mqtt::common::{Filter, QoS, RetainHandling};
 
-use crate::subscribe::common::Subscriber;
+use crate::core::sub_share::is_mqtt_share_subscribe;
+use crate::subscribe::common::{min_qos, Subscriber};
 
 // No Local
 // The only values available for No Local are 0 and 1, where 1 means that the server cannot forward the message to the client that posted it, and 0 is the opposite.
@@ -31,12 +32,45 @@ pub fn message_is_same_client(subscriber: &Subscriber, record: &StorageRecord) -
     false
 }
 
+// It is a Protocol Error to set the No Local bit to 1 on a Shared Subscription.
+pub fn no_local_on_share_subscribe(filter: &Filter) -> bool {
+    filter.no_local && is_mqtt_share_subscribe(&filter.path)
+}
+
+// Retain As Published
+// If 1, messages forwarded using this subscription keep the RETAIN flag they were published with.
+// If 0, messages forwarded using this subscription have the RETAIN flag set to 0.
+// Retained messages sent when the subscription is established always have the RETAIN flag set to 1.
+pub fn build_retain_flag(record: &StorageRecord, preserve_retain: bool) -> bool {
+    if !preserve_retain {
+        return false;
+    }
+
+    record
+        .protocol_data
+        .as_ref()
+        .and_then(|pd| pd.mqtt.as_ref())
+        .map(|m| m.retain)
+        .unwrap_or(false)
+}
+
+// Retained messages are sent at the subscription QoS, capped at QoS 1 because
+// the publish QoS of a retained message is not stored.
+pub fn retain_message_send_qos(filter: &Filter) -> QoS {
+    min_qos(QoS::AtLeastOnce, filter.qos)
+}
+
 // Retain Handling
+// Retained messages are not sent for a Shared Subscription.
 pub fn is_send_retain_msg_by_retain_handling(
     path: &str,
     retain_handling: &RetainHandling,
     is_new_subs: &DashMap<String, bool>,
 ) -> bool {
+    if is_mqtt_share_subscribe(path) {
+        return false;
+    }
+
     if *retain_handling == RetainHandling::OnEverySubscribe {
         return true;
     }
@@ -62,9 +96,102 @@ pub fn is_send_retain_msg_by_retain_handling(
 
 #[cfg(test)]
 mod tests {
-    use crate::core::sub_option::is_send_retain_msg_by_retain_handling;
+    use crate::core::sub_option::{
+        build_retain_flag, is_send_retain_msg_by_retain_handling, message_is_same_client,
+        no_local_on_share_subscribe, retain_message_send_qos,
+    };
+    use crate::subscribe::common::Subscriber;
+    use bytes::Bytes;
     use dashmap::DashMap;
-    use protocol::mqtt::common::RetainHandling;
+    use metadata_struct::storage::record::{
+        StorageRecord, StorageRecordMetadata, StorageRecordProtocolData,
+        StorageRecordProtocolDataMqtt,
+    };
+    use protocol::mqtt::common::{Filter, QoS, RetainHandling};
+
+    fn build_record(client_id: &str, retain: bool) -> StorageRecord {
+        StorageRecord {
+            metadata: StorageRecordMetadata::default(),
+            protocol_data: Some(StorageRecordProtocolData {
+                mqtt: Some(StorageRecordProtocolDataMqtt {
+                    client_id: client_id.to_string(),
+                    retain,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            data: Bytes::from("data"),
+        }
+    }
+
+    fn build_filter(path: &str, qos: QoS, no_local: bool) -> Filter {
+        Filter {
+            path: path.to_string(),
+            qos,
+            no_local,
+            preserve_retain: false,
+            retain_handling: RetainHandling::OnEverySubscribe,
+        }
+    }
+
+    #[test]
+    fn message_is_same_client_test() {
+        let record = build_record("c1", false);
+        for (no_local, client_id, expect) in [
+            (true, "c1", true),
+            (true, "c2", false),
+            (false, "c1", false),
+            (false, "c2", false),
+        ] {
+            let subscriber = Subscriber {
+                client_id: client_id.to_string(),
+                no_local,
+                ..Default::default()
+            };
+            assert_eq!(message_is_same_client(&subscriber, &record), expect);
+        }
+    }
+
+    #[test]
+    fn build_retain_flag_test() {
+        for (preserve_retain, retain, expect) in [
+            (true, true, true),
+            (true, false, false),
+            (false, true, false),
+            (false, false, false),
+        ] {
+            let record = build_record("c1", retain);
+            assert_eq!(build_retain_flag(&record, preserve_retain), expect);
+        }
+    }
+
+    #[test]
+    fn no_local_and_qos_test() {
+        assert!(no_local_on_share_subscribe(&build_filter(
+            "$share/g/a",
+            QoS::AtMostOnce,
+            true
+        )));
+        assert!(!no_local_on_share_subscribe(&build_filter(
+            "$share/g/a",
+            QoS::AtMostOnce,
+            false
+        )));
+        assert!(!no_local_on_share_subscribe(&build_filter(
+            "a",
+            QoS::AtMostOnce,
+            true
+        )));
+
+        assert_eq!(
+            retain_message_send_qos(&build_filter("a", QoS::AtMostOnce, false)),
+            QoS::AtMostOnce
+        );
+        assert_eq!(
+            retain_message_send_qos(&build_filter("a", QoS::ExactlyOnce, false)),
+            QoS::AtLeastOnce
+        );
+    }
 
     #[test]
     fn is_send_retain_msg_by_retain_handling_test() {
@@ -108,5 +235,12 @@ fn is_send_retain_msg_by_retain_handling_test() {
             &RetainHandling::OnNewSubscribe,
             &DashMap::new()
         ));
+
+        // Shared subscription → never send
+        assert!(!is_send_retain_msg_by_retain_handling(
+            "$share/g/path",
+            &RetainHandling::OnEverySubscribe,
+            &DashMap::new()
+        ));
     }
 }
@@ -12,28 +12,48 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+//! CPU, memory and config drift alarms of the node.
+//!
+//! An alarm is activated when usage rises above the high watermark and only
+//! deactivated once it drops below the low watermark, so usage hovering around
+//! one threshold doesn't flap the alarm. Only the transitions are published and
+//! stored, an alarm that stays active is not reported again.
+//!
+//! The config drift alarm is active while Meta Service reports brokers that
+//! have disagreed on a cluster-wide config section for
+//! [`CONFIG_DRIFT_ALARM_SECS`].
+
 use crate::storage::local::LocalStorage;
 use crate::system_topic::report_system_data;
 use crate::{core::cache::MQTTCacheManager, core::tool::ResultMqttBrokerError};
+use broker_core::cluster::ClusterStorage;
 use common_base::error::ResultCommonError;
 use common_base::tools::{loop_select_ticket, now_second};
-use common_config::broker::broker_config;
 use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::system_alarm::{SystemAlarmDetails, SystemAlarmEventMessage};
 use rocksdb_engine::rocksdb::RocksDBEngine;
-use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use storage_adapter::driver::StorageDriverManager;
 use system_info::{process_cpu_usage, process_memory_usage};
 use tokio::sync::broadcast;
+use tracing::warn;
 
 // System alarm
 pub const SYSTEM_TOPIC_BROKERS_ALARMS_ALERT: &str = "$SYS/brokers/alarms/alert";
+pub const SYSTEM_TOPIC_BROKERS_ALARMS_ACTIVATE: &str = "$SYS/brokers/${node}/alarms/activate";
+pub const SYSTEM_TOPIC_BROKERS_ALARMS_DEACTIVATE: &str = "$SYS/brokers/${node}/alarms/deactivate";
+
+// Disagreements shorter than this are config changes still being applied.
+pub const CONFIG_DRIFT_ALARM_SECS: u64 = 60;
 
 #[allow(clippy::enum_variant_names)]
+#[derive(Clone, Copy)]
 enum AlarmType {
     HighCpuUsage,
     HighMemoryUsage,
+    ConfigDrift,
 }
 
 impl fmt::Display for AlarmType {
@@ -41,20 +61,120 @@ fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             AlarmType::HighCpuUsage => write!(f, "HighCpuUsage"),
             AlarmType::HighMemoryUsage => write!(f, "HighMemoryUsage"),
+            AlarmType::ConfigDrift => write!(f, "ConfigDrift"),
         }
     }
 }
 
-// sysmon topic
-#[derive(Default, Serialize, Deserialize, Clone)]
-pub struct SystemAlarmEventMessage {
-    pub name: String,
-    pub message: String,
-    /// JSON field name is "activate_at" to align with EMQX system topic format.
-    /// "create_time" is kept as alias for backward-compatible deserialization from storage.
-    #[serde(rename = "activate_at", alias = "create_time")]
-    pub create_time: u64,
-    pub activated: bool,
+/// Alarms whose latest stored transition is an activation.
+pub fn active_system_alarms(
+    mut events: Vec<SystemAlarmEventMessage>,
+) -> Vec<SystemAlarmEventMessage> {
+    events.sort_by_key(|e| e.event_time());
+    let mut latest: HashMap<String, SystemAlarmEventMessage> = HashMap::new();
+    for event in events {
+        latest.insert(event.name.clone(), event);
+    }
+    let mut active: Vec<SystemAlarmEventMessage> =
+        latest.into_values().filter(|e| e.activated).collect();
+    active.sort_by(|a, b| a.name.cmp(&b.name));
+    active
+}
+
+/// Active alarms by name.
+#[derive(Default)]
+struct AlarmState {
+    active: HashMap<String, SystemAlarmEventMessage>,
+}
+
+impl AlarmState {
+    /// The transition caused by `usage`, if any.
+    fn check(
+        &mut self,
+        alarm_type: AlarmType,
+        usage: f32,
+        high_watermark: f32,
+        low_watermark: f32,
+        now: u64,
+    ) -> Option<SystemAlarmEventMessage> {
+        let name = alarm_type.to_string();
+        let low_watermark = low_watermark.min(high_watermark);
+        let details = SystemAlarmDetails {
+            usage,
+            high_watermark,
+            low_watermark,
+        };
+
+        match self.active.get(&name) {
+            None if usage > high_watermark => {
+                let event = SystemAlarmEventMessage {
+                    name: name.clone(),
+                    message: format!(
+                        "{alarm_type} is {usage}%, above the high watermark {high_watermark}%"
+                    ),
+                    create_time: now,
+                    deactivate_at: None,
+                    activated: true,
+                    details: Some(details),
+                };
+                self.active.insert(name, event.clone());
+                Some(event)
+            }
+            Some(active) if usage < low_watermark => {
+                let event = SystemAlarmEventMessage {
+                    name: name.clone(),
+                    message: format!(
+                        "{alarm_type} is {usage}%, below the low watermark {low_watermark}%"
+                    ),
+                    create_time: active.create_time,
+                    deactivate_at: Some(now),
+                    activated: false,
+                    details: Some(details),
+                };
+                self.active.remove(&name);
+                Some(event)
+            }
+            _ => None,
+        }
+    }
+
+    /// The transition caused by a condition that is either met or not.
+    fn check_condition(
+        &mut self,
+        alarm_type: AlarmType,
+        active: bool,
+        message: String,
+        now: u64,
+    ) -> Option<SystemAlarmEventMessage> {
+        let name = alarm_type.to_string();
+        match self.active.get(&name) {
+            None if active => {
+                let event = SystemAlarmEventMessage {
+                    name: name.clone(),
+                    message,
+                    create_time: now,
+                    deactivate_at: None,
+                    activated: true,
+                    details: None,
+                };
+                self.active.insert(name, event.clone());
+                Some(event)
+            }
+            Some(current) if !active => {
+                let event = SystemAlarmEventMessage {
+                    name: name.clone(),
+                    message,
+                    create_time: current.create_time,
+                    deactivate_at: Some(now),
+                    activated: false,
+                    details: None,
+                };
+                self.active.remove(&name);
+                Some(event)
+            }
+            _ => None,
+        }
+    }
 }
 
 pub struct SystemAlarm {
@@ -62,6 +182,7 @@ pub struct SystemAlarm {
     metadata_cache: Arc<MQTTCacheManager>,
     storage_driver_manager: Arc<StorageDriverManager>,
     rocksdb_engine_handler: Arc<RocksDBEngine>,
+    state: Mutex<AlarmState>,
 }
 
 impl SystemAlarm {
@@ -76,28 +197,47 @@ pub fn new(
             metadata_cache,
             storage_driver_manager,
             rocksdb_engine_handler,
+            state: Mutex::new(AlarmState::default()),
         }
     }
 
     pub async fn start(&self, stop_send: broadcast::Sender<bool>) -> ResultMqttBrokerError {
+        // pick up alarms left active by the previous run so they are
+        // deactivated instead of activated again
+        let log_storage = LocalStorage::new(self.rocksdb_engine_handler.clone());
+        match log_storage.list_active_system_alarm().await {
+            Ok(active) => {
+                let mut state = self.state.lock().unwrap();
+                for event in active {
+                    state.active.insert(event.name.clone(), event);
+                }
+            }
+            Err(e) => warn!("Failed to load active system alarms: {}", e),
+        }
+
         let record_func = async || -> ResultCommonError {
-            let mqtt_conf = broker_config();
+            let mqtt_conf = self.metadata_cache.node_cache.get_cluster_config();
+            let monitor = &mqtt_conf.mqtt_system_monitor;
             let cpu_usage = process_cpu_usage().await;
 
-            self.try_send_a_new_system_event(
+            self.check_alarm(
                 AlarmType::HighCpuUsage,
                 cpu_usage,
-                mqtt_conf.mqtt_system_monitor.os_cpu_high_watermark,
+                monitor.os_cpu_high_watermark,
+                monitor.os_cpu_low_watermark,
             )
             .await?;
 
             let memory_usage = process_memory_usage();
-            self.try_send_a_new_system_event(
+            self.check_alarm(
                 AlarmType::HighMemoryUsage,
                 memory_usage,
-                mqtt_conf.mqtt_system_monitor.os_memory_high_watermark,
+                monitor.os_memory_high_watermark,
+                monitor.os_memory_low_watermark,
             )
             .await?;
+
+            self.check_config_drift().await?;
             Ok(())
         };
 
@@ -105,32 +245,159 @@ pub async fn start(&self, stop_send: broadcast::Sender<bool>) -> ResultMqttBroke
         Ok(())
     }
 
-    async fn try_send_a_new_system_event(
+    async fn check_alarm(
         &self,
         alarm_type: AlarmType,
         current_usage: f32,
-        config_usage: f32,
+        high_watermark: f32,
+        low_watermark: f32,
     ) -> ResultCommonError {
-        if current_usage > config_usage {
-            let message = SystemAlarmEventMessage {
-                name: alarm_type.to_string(),
-                message: format!("{alarm_type} is {current_usage}%, but config is {config_usage}%"),
-                create_time: now_second(),
-                activated: true,
-            };
+        let Some(event) = self.state.lock().unwrap().check(
+            alarm_type,
+            current_usage,
+            high_watermark,
+            low_watermark,
+            now_second(),
+        ) else {
+            return Ok(());
+        };
+        self.publish_event(event).await
+    }
 
-            let raw_message = message.clone();
-            report_system_data(
-                &self.client_pool,
-                &self.metadata_cache,
-                &self.storage_driver_manager,
-                SYSTEM_TOPIC_BROKERS_ALARMS_ALERT,
-                || async move { raw_message.clone() },
+    async fn check_config_drift(&self) -> ResultCommonError {
+        let cluster_storage = ClusterStorage::new(self.client_pool.clone());
+        let drifts = match cluster_storage.get_config_drift().await {
+            Ok(drifts) => drifts,
+            Err(e) => {
+                warn!("Failed to get the config drift report: {}", e);
+                return Ok(());
+            }
+        };
+
+        let now = now_second();
+        let sections: Vec<String> = drifts
+            .into_iter()
+            .filter(|drift| now.saturating_sub(drift.since) >= CONFIG_DRIFT_ALARM_SECS)
+            .map(|drift| drift.section)
+            .collect();
+        let message = if sections.is_empty() {
+            "Brokers agree on the cluster-wide config sections".to_string()
+        } else {
+            format!(
+                "Brokers disagree on config sections: {}",
+                sections.join(", ")
             )
-            .await;
-            let log_storage = LocalStorage::new(self.rocksdb_engine_handler.clone());
-            log_storage.save_system_event(message).await?;
+        };
+
+        let Some(event) = self.state.lock().unwrap().check_condition(
+            AlarmType::ConfigDrift,
+            !sections.is_empty(),
+            message,
+            now,
+        ) else {
+            return Ok(());
+        };
+        self.publish_event(event).await
+    }
+
+    async fn publish_event(&self, event: SystemAlarmEventMessage) -> ResultCommonError {
+        if event.activated {
+            self.report(SYSTEM_TOPIC_BROKERS_ALARMS_ALERT, &event).await;
+            self.report(SYSTEM_TOPIC_BROKERS_ALARMS_ACTIVATE, &event)
+                .await;
+        } else {
+            self.report(SYSTEM_TOPIC_BROKERS_ALARMS_DEACTIVATE, &event)
+                .await;
         }
-        Ok(())
+        let log_storage = LocalStorage::new(self.rocksdb_engine_handler.clone());
+        log_storage.save_system_event(event).await
+    }
+
+    async fn report(&self, topic: &str, event: &SystemAlarmEventMessage) {
+        let raw_message = event.clone();
+        report_system_data(
+            &self.client_pool,
+            &self.metadata_cache,
+            &self.storage_driver_manager,
+            topic,
+            || async move { raw_message },
+        )
+        .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alarm_hysteresis() {
+        let mut state = AlarmState::default();
+        assert!(state
+            .check(AlarmType::HighCpuUsage, 50.0, 70.0, 60.0, 1)
+            .is_none());
+
+        let event = state
+            .check(AlarmType::HighCpuUsage, 80.0, 70.0, 60.0, 2)
+            .unwrap();
+        assert!(event.activated);
+        assert_eq!(event.create_time, 2);
+
+        // still active: neither repeated nor deactivated between the watermarks
+        assert!(state
+            .check(AlarmType::HighCpuUsage, 85.0, 70.0, 60.0, 3)
+            .is_none());
+        assert!(state
+            .check(AlarmType::HighCpuUsage, 65.0, 70.0, 60.0, 4)
+            .is_none());
+
+        let event = state
+            .check(AlarmType::HighCpuUsage, 55.0, 70.0, 60.0, 5)
+            .unwrap();
+        assert!(!event.activated);
+        assert_eq!(event.create_time, 2);
+        assert_eq!(event.deactivate_at, Some(5));
+        assert!(state.active.is_empty());
+    }
+
+    #[test]
+    fn test_alarm_condition() {
+        let mut state = AlarmState::default();
+        assert!(state
+            .check_condition(AlarmType::ConfigDrift, false, String::new(), 1)
+            .is_none());
+        let event = state
+            .check_condition(AlarmType::ConfigDrift, true, "drift".to_string(), 2)
+            .unwrap();
+        assert!(event.activated);
+        assert!(state
+            .check_condition(AlarmType::ConfigDrift, true, "drift".to_string(), 3)
+            .is_none());
+        let event = state
+            .check_condition(AlarmType::ConfigDrift, false, "agree".to_string(), 4)
+            .unwrap();
+        assert!(!event.activated);
+        assert_eq!(event.create_time, 2);
+        assert_eq!(event.deactivate_at, Some(4));
+        assert!(state.active.is_empty());
+    }
+
+    #[test]
+    fn test_active_system_alarms() {
+        let event =
+            |name: &str, activate_at: u64, deactivate_at: Option<u64>| SystemAlarmEventMessage {
+                name: name.to_string(),
+                create_time: activate_at,
+                deactivate_at,
+                activated: deactivate_at.is_none(),
+                ..Default::default()
+            };
+        let active = active_system_alarms(vec![
+            event("HighCpuUsage", 10, Some(20)),
+            event("HighCpuUsage", 10, None),
+            event("HighMemoryUsage", 30, None),
+        ]);
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].name, "HighMemoryUsage");
     }
 }
@@ -13,8 +13,10 @@
 // limitations under the License.
 
 use crate::core::{cache::MQTTCacheManager, error::MqttBrokerError};
+use common_security::manager::SecurityManager;
 use metadata_struct::tenant::DEFAULT_TENANT;
 use protocol::mqtt::common::{ConnectProperties, Login};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 /// The separator used to embed tenant name inside client_id and username.
@@ -35,11 +37,15 @@
 /// Returns `TenantNotFound` if the resolved name does not match any known tenant.
 pub fn get_tenant_info(
     cache_manager: &Arc<MQTTCacheManager>,
+    security_manager: &Arc<SecurityManager>,
     client_id: &str,
     connect_properties: &Option<ConnectProperties>,
     login: &Option<Login>,
 ) -> Result<metadata_struct::tenant::Tenant, MqttBrokerError> {
-    let tenant_name = decode_tenant_name(client_id, connect_properties, login)?;
+    let credential_tenant = login
+        .as_ref()
+        .and_then(|login| credential_tenant(security_manager, &login.username));
+    let tenant_name = decode_tenant_name(client_id, connect_properties, login, credential_tenant)?;
     cache_manager
         .node_cache
         .get_tenant(&tenant_name)
@@ -48,7 +54,7 @@ pub fn get_tenant_info(
 
 /// Determine the tenant name from an MQTT CONNECT packet.
 ///
-/// Three sources are tried in priority order (highest → lowest):
+/// Sources are tried in priority order (highest → lowest):
 ///
 /// 1. **MQTT v5 user-property** (`"tenant"` key in CONNECT properties)
 ///    - Explicit, takes precedence over everything else.
@@ -62,12 +68,17 @@ pub fn get_tenant_info(
 ///    - Format: `<tenant>@<real_client_id>`, e.g. `"acme@device-001"` → tenant = `"acme"`.
 ///    - Skipped if client_id contains no `@`.
 ///
-/// 4. **Default tenant fallback**
+/// 4. **Credential mapping** (the tenant the username is registered in)
+///    - Used when the username is a user of exactly one tenant, so clients
+///      with plain credentials land in their own tenant without any prefix.
+///
+/// 5. **Default tenant fallback**
 ///    - Used when none of the above sources yield a tenant.
 fn decode_tenant_name(
     client_id: &str,
     connect_properties: &Option<ConnectProperties>,
     login: &Option<Login>,
+    credential_tenant: Option<String>,
 ) -> Result<String, MqttBrokerError> {
     // Priority 1: MQTT v5 user-property "tenant"
     if let Some(props) = connect_properties {
@@ -90,10 +101,44 @@ fn decode_tenant_name(
         return Ok(tenant);
     }
 
-    // Priority 4: fall back to the default tenant
+    // Priority 4: the single tenant the username is registered in
+    if let Some(tenant) = credential_tenant {
+        return Ok(tenant);
+    }
+
+    // Priority 5: fall back to the default tenant
     Ok(DEFAULT_TENANT.to_string())
 }
 
+/// The tenant owning `username`, or `None` when the username is unknown or
+/// registered in more than one tenant (the mapping would be ambiguous).
+fn credential_tenant(security_manager: &Arc<SecurityManager>, username: &str) -> Option<String> {
+    let mut tenants = security_manager.metadata.get_user_tenants(username);
+    if tenants.len() == 1 {
+        tenants.pop()
+    } else {
+        None
+    }
+}
+
+/// Bytes a tenant has written to storage through this node that have not yet
+/// expired, grouped by expiry minute so expired data can be dropped cheaply.
+#[derive(Default, Clone)]
+pub struct TenantStorageUsage {
+    buckets: BTreeMap<u64, u64>,
+}
+
+impl TenantStorageUsage {
+    pub fn record(&mut self, bytes: u64, expire_at: u64) {
+        *self.buckets.entry(expire_at / 60 + 1).or_default() += bytes;
+    }
+
+    pub fn used(&mut self, now: u64) -> u64 {
+        self.buckets = self.buckets.split_off(&(now / 60 + 1));
+        self.buckets.values().sum()
+    }
+}
+
 /// Extract the tenant from `<tenant>@<rest>`.
 ///
 /// Returns `None` if the string contains no `@`, or if the prefix is empty
@@ -158,6 +203,7 @@ fn test_priority_order() {
                 username: "username-tenant@admin".to_string(),
                 password: "pass".to_string(),
             }),
+            Some("credential-tenant".to_string()),
         )
         .unwrap();
         assert_eq!(result, "prop-tenant");
@@ -180,7 +226,7 @@ fn test_try_decode_client_id() {
     #[test]
     fn test_fallback_chain() {
         assert_eq!(
-            decode_tenant_name("plain-device", &None, &None).unwrap(),
+            decode_tenant_name("plain-device", &None, &None, None).unwrap(),
             DEFAULT_TENANT
         );
         assert_eq!(
@@ -190,14 +236,38 @@ fn test_fallback_chain() {
                 &Some(Login {
                     username: "biz@admin".to_string(),
                     password: "pass".to_string(),
-                })
+                }),
+                Some("credential".to_string()),
             )
             .unwrap(),
             "biz"
         );
         assert_eq!(
-            decode_tenant_name("biz@device-001", &None, &None).unwrap(),
+            decode_tenant_name("biz@device-001", &None, &None, None).unwrap(),
             "biz"
         );
+        assert_eq!(
+            decode_tenant_name(
+                "plain-device",
+                &None,
+                &Some(Login {
+                    username: "alice".to_string(),
+                    password: "pass".to_string(),
+                }),
+                Some("credential".to_string()),
+            )
+            .unwrap(),
+            "credential"
+        );
+    }
+
+    #[test]
+    fn test_tenant_storage_usage() {
+        let mut usage = TenantStorageUsage::default();
+        usage.record(100, 120);
+        usage.record(50, 600);
+        assert_eq!(usage.used(0), 150);
+        assert_eq!(usage.used(180), 50);
+        assert_eq!(usage.used(660), 0);
     }
 }
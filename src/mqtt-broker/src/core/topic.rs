@@ -201,8 +201,7 @@ pub async fn try_init_topic(
             client_pool,
             &topic,
         )
-        .await?;
-        topic
+        .await?
     };
     Ok(topic)
 }
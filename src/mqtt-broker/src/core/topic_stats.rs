@@ -0,0 +1,282 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Per-topic statistics of the messages published through this broker.
+//!
+//! Every accepted publish bumps the message and byte counters of its topic
+//! in [`TopicStats`], and retained publishes update the retained flag. The
+//! counters are cumulative since the broker started. A background task sends
+//! the full set to meta-service, which sums the reports of all brokers for
+//! `DescribeTopic`; the topics that received the most messages since the
+//! previous report are also published to `$SYS/brokers/stats/topics/hot`.
+
+use crate::core::cache::MQTTCacheManager;
+use crate::core::tool::ResultMqttBrokerError;
+use crate::storage::topic_stats::TopicStatsStorage;
+use crate::subscribe::manager::SubscribeManager;
+use common_base::error::ResultCommonError;
+use common_base::tools::{loop_select_ticket, now_millis};
+use dashmap::DashMap;
+use protocol::meta::meta_service_mqtt::TopicStatsRaw;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tracing::warn;
+
+const TOPIC_STATS_REPORT_INTERVAL_MS: u64 = 10_000;
+
+/// Topics listed in one `$SYS/brokers/stats/topics/hot` report.
+pub const TOPIC_STATS_HOT_TOPICS: usize = 10;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TopicStatsData {
+    pub tenant: String,
+    pub topic_name: String,
+    pub message_count: u64,
+    pub byte_count: u64,
+    /// Unix millis of the last publish, 0 if none.
+    pub last_publish_time: u64,
+    pub retained: bool,
+    /// Unix millis the retained flag last changed, 0 if never.
+    pub retained_update_time: u64,
+    // message_count at the previous hot topic report
+    #[serde(skip)]
+    hot_reported_count: u64,
+}
+
+/// Messages a topic received between two hot topic reports.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HotTopic {
+    pub tenant: String,
+    pub topic_name: String,
+    pub messages: u64,
+    pub message_count: u64,
+    pub byte_count: u64,
+    pub last_publish_time: u64,
+}
+
+#[derive(Default)]
+pub struct TopicStats {
+    // (tenant, (topic_name, TopicStatsData))
+    topics: DashMap<String, DashMap<String, TopicStatsData>>,
+}
+
+impl TopicStats {
+    fn entry<R>(
+        &self,
+        tenant: &str,
+        topic_name: &str,
+        f: impl FnOnce(&mut TopicStatsData) -> R,
+    ) -> R {
+        let tenant_map = self.topics.entry(tenant.to_owned()).or_default();
+        let mut data = tenant_map
+            .entry(topic_name.to_owned())
+            .or_insert_with(|| TopicStatsData {
+                tenant: tenant.to_owned(),
+                topic_name: topic_name.to_owned(),
+                ..Default::default()
+            });
+        f(&mut data)
+    }
+
+    pub fn record_publish(&self, tenant: &str, topic_name: &str, bytes: u64) {
+        let now = now_millis() as u64;
+        self.entry(tenant, topic_name, |data| {
+            data.message_count += 1;
+            data.byte_count += bytes;
+            data.last_publish_time = now;
+        });
+    }
+
+    /// A retained message was stored (`true`) or cleared (`false`).
+    pub fn set_retained(&self, tenant: &str, topic_name: &str, retained: bool) {
+        let now = now_millis() as u64;
+        self.entry(tenant, topic_name, |data| {
+            data.retained = retained;
+            data.retained_update_time = now;
+        });
+    }
+
+    pub fn get(&self, tenant: &str, topic_name: &str) -> Option<TopicStatsData> {
+        self.topics
+            .get(tenant)
+            .and_then(|tenant_map| tenant_map.get(topic_name).map(|data| data.clone()))
+    }
+
+    pub fn remove_topic(&self, tenant: &str, topic_name: &str) {
+        if let Some(tenant_map) = self.topics.get(tenant) {
+            tenant_map.remove(topic_name);
+        }
+    }
+
+    /// Statistics of every topic published or subscribed on this broker,
+    /// with the number of local subscriptions to the topic.
+    pub fn snapshot(&self, subscribe_manager: &SubscribeManager) -> Vec<TopicStatsRaw> {
+        let mut list = Vec::new();
+        let mut published = HashSet::new();
+        for tenant_map in self.topics.iter() {
+            for data in tenant_map.iter() {
+                published.insert((data.tenant.clone(), data.topic_name.clone()));
+                list.push(TopicStatsRaw {
+                    tenant: data.tenant.clone(),
+                    topic_name: data.topic_name.clone(),
+                    message_count: data.message_count,
+                    byte_count: data.byte_count,
+                    last_publish_time: data.last_publish_time,
+                    retained: data.retained,
+                    retained_update_time: data.retained_update_time,
+                    subscriber_count: subscriber_count(
+                        subscribe_manager,
+                        &data.tenant,
+                        &data.topic_name,
+                    ),
+                });
+            }
+        }
+
+        // Topics with subscribers but no publish yet are the dead ones.
+        for tenant_map in subscribe_manager.topic_subscribes.iter() {
+            for topic in tenant_map.iter() {
+                let key = (tenant_map.key().clone(), topic.key().clone());
+                if !topic.is_empty() && !published.contains(&key) {
+                    list.push(TopicStatsRaw {
+                        tenant: key.0,
+                        topic_name: key.1,
+                        subscriber_count: topic.len() as u64,
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+        list
+    }
+
+    /// The `limit` topics that received the most messages since the previous
+    /// call, busiest first. Topics without new messages are left out.
+    pub fn take_hot(&self, limit: usize) -> Vec<HotTopic> {
+        let mut hot = Vec::new();
+        for tenant_map in self.topics.iter() {
+            for mut data in tenant_map.iter_mut() {
+                let messages = data.message_count - data.hot_reported_count;
+                data.hot_reported_count = data.message_count;
+                if messages == 0 {
+                    continue;
+                }
+                hot.push(HotTopic {
+                    tenant: data.tenant.clone(),
+                    topic_name: data.topic_name.clone(),
+                    messages,
+                    message_count: data.message_count,
+                    byte_count: data.byte_count,
+                    last_publish_time: data.last_publish_time,
+                });
+            }
+        }
+        hot.sort_by(|a, b| b.messages.cmp(&a.messages));
+        hot.truncate(limit);
+        hot
+    }
+}
+
+fn subscriber_count(subscribe_manager: &SubscribeManager, tenant: &str, topic_name: &str) -> u64 {
+    subscribe_manager
+        .topic_subscribes
+        .get(tenant)
+        .and_then(|tenant_map| tenant_map.get(topic_name).map(|list| list.len() as u64))
+        .unwrap_or(0)
+}
+
+pub async fn start_topic_stats_report(
+    cache_manager: Arc<MQTTCacheManager>,
+    subscribe_manager: Arc<SubscribeManager>,
+    stop_send: broadcast::Sender<bool>,
+) {
+    let ac_fn = async || -> ResultCommonError {
+        if let Err(e) = report_topic_stats(&cache_manager, &subscribe_manager).await {
+            warn!("Failed to report topic statistics: {}", e);
+        }
+        Ok(())
+    };
+
+    loop_select_ticket(ac_fn, TOPIC_STATS_REPORT_INTERVAL_MS, &stop_send).await;
+}
+
+async fn report_topic_stats(
+    cache_manager: &Arc<MQTTCacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+) -> ResultMqttBrokerError {
+    let stats = cache_manager.topic_stats.snapshot(subscribe_manager);
+    TopicStatsStorage::new(cache_manager.client_pool.clone())
+        .report_topic_stats(stats)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_snapshot() {
+        let stats = TopicStats::default();
+        stats.record_publish("t1", "a", 10);
+        stats.record_publish("t1", "a", 5);
+        stats.set_retained("t1", "a", true);
+
+        let data = stats.get("t1", "a").unwrap();
+        assert_eq!(data.message_count, 2);
+        assert_eq!(data.byte_count, 15);
+        assert!(data.last_publish_time > 0);
+        assert!(data.retained);
+
+        let subscribe_manager = SubscribeManager::new();
+        subscribe_manager.add_topic_subscribe("t1", "a", "c1", "a");
+        subscribe_manager.add_topic_subscribe("t1", "idle", "c2", "idle");
+
+        let mut snapshot = stats.snapshot(&subscribe_manager);
+        snapshot.sort_by(|a, b| a.topic_name.cmp(&b.topic_name));
+        assert_eq!(snapshot.len(), 2);
+        assert_eq!(snapshot[0].topic_name, "a");
+        assert_eq!(snapshot[0].subscriber_count, 1);
+        assert_eq!(snapshot[1].topic_name, "idle");
+        assert_eq!(snapshot[1].message_count, 0);
+        assert_eq!(snapshot[1].subscriber_count, 1);
+
+        stats.set_retained("t1", "a", false);
+        assert!(!stats.get("t1", "a").unwrap().retained);
+        stats.remove_topic("t1", "a");
+        assert!(stats.get("t1", "a").is_none());
+    }
+
+    #[test]
+    fn test_take_hot() {
+        let stats = TopicStats::default();
+        for _ in 0..3 {
+            stats.record_publish("t1", "busy", 1);
+        }
+        stats.record_publish("t1", "quiet", 1);
+
+        let hot = stats.take_hot(1);
+        assert_eq!(hot.len(), 1);
+        assert_eq!(hot[0].topic_name, "busy");
+        assert_eq!(hot[0].messages, 3);
+
+        stats.record_publish("t1", "quiet", 1);
+        let hot = stats.take_hot(TOPIC_STATS_HOT_TOPICS);
+        assert_eq!(hot.len(), 1);
+        assert_eq!(hot[0].topic_name, "quiet");
+        assert_eq!(hot[0].messages, 1);
+        assert_eq!(hot[0].message_count, 2);
+    }
+}
@@ -0,0 +1,417 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use base64::Engine;
+use common_base::tools::now_millis;
+use common_config::broker::broker_config;
+use common_config::config::{MqttWebhook, WebhookEndpoint};
+use common_metrics::mqtt::webhook::{
+    record_webhook_event_dropped, record_webhook_events, record_webhook_request,
+    set_webhook_circuit_open,
+};
+use futures::future::join_all;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::{sleep, timeout};
+use tracing::{error, info, warn};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WebhookEventType {
+    ClientConnected,
+    ClientDisconnected,
+    MessagePublish,
+    SessionSubscribed,
+    SessionUnsubscribed,
+}
+
+impl WebhookEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEventType::ClientConnected => "client.connected",
+            WebhookEventType::ClientDisconnected => "client.disconnected",
+            WebhookEventType::MessagePublish => "message.publish",
+            WebhookEventType::SessionSubscribed => "session.subscribed",
+            WebhookEventType::SessionUnsubscribed => "session.unsubscribed",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WebhookEvent {
+    pub event: String,
+    pub node_id: u64,
+    pub ts: u128,
+    pub data: serde_json::Value,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct WebhookPublishEvent {
+    pub tenant: String,
+    #[serde(rename = "clientid")]
+    pub client_id: String,
+    pub username: String,
+    pub topic: String,
+    pub qos: u8,
+    pub retain: bool,
+    /// Base64 of the payload, which need not be valid UTF-8.
+    pub payload: String,
+}
+
+impl WebhookPublishEvent {
+    pub fn encode_payload(payload: &[u8]) -> String {
+        BASE64_STANDARD.encode(payload)
+    }
+}
+
+/// Consecutive-failure circuit breaker. Once open, events are rejected until
+/// `open_ms` has passed; the next batch is then a trial that either closes the
+/// circuit or re-opens it.
+pub(crate) struct CircuitBreaker {
+    threshold: u32,
+    open_ms: u64,
+    consecutive_failures: u32,
+    open_until_ms: u128,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(threshold: u32, open_ms: u64) -> Self {
+        CircuitBreaker {
+            threshold,
+            open_ms,
+            consecutive_failures: 0,
+            open_until_ms: 0,
+        }
+    }
+
+    pub(crate) fn allow(&self, now: u128) -> bool {
+        now >= self.open_until_ms
+    }
+
+    pub(crate) fn is_open(&self) -> bool {
+        self.threshold > 0 && self.consecutive_failures >= self.threshold
+    }
+
+    pub(crate) fn on_success(&mut self) {
+        self.consecutive_failures = 0;
+        self.open_until_ms = 0;
+    }
+
+    pub(crate) fn on_failure(&mut self, now: u128) {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        if self.is_open() {
+            self.open_until_ms = now + self.open_ms as u128;
+        }
+    }
+}
+
+struct EndpointSender {
+    config: WebhookEndpoint,
+    client: Client,
+    breaker: CircuitBreaker,
+}
+
+impl EndpointSender {
+    fn accepts(&self, event: &str) -> bool {
+        endpoint_accepts(&self.config, event)
+    }
+
+    async fn send(&self, body: &str) -> Result<(), String> {
+        let mut req = self
+            .client
+            .post(&self.config.url)
+            .header("Content-Type", "application/json");
+        for (key, value) in &self.config.headers {
+            req = req.header(key, value);
+        }
+
+        let start = now_millis();
+        let result = match req.body(body.to_string()).send().await {
+            Ok(resp) if resp.status().is_success() => Ok(()),
+            Ok(resp) => Err(format!("HTTP {}", resp.status())),
+            Err(e) => Err(e.to_string()),
+        };
+        record_webhook_request(
+            &self.config.name,
+            result.is_ok(),
+            (now_millis() - start) as f64,
+        );
+        result
+    }
+}
+
+fn endpoint_accepts(endpoint: &WebhookEndpoint, event: &str) -> bool {
+    endpoint.events.is_empty() || endpoint.events.iter().any(|e| e == event)
+}
+
+pub struct WebhookManager {
+    config: MqttWebhook,
+    tx: mpsc::Sender<WebhookEvent>,
+    consumer: std::sync::Mutex<Option<mpsc::Receiver<WebhookEvent>>>,
+}
+
+impl WebhookManager {
+    pub fn new(config: MqttWebhook) -> Arc<Self> {
+        let (tx, rx) = mpsc::channel(config.queue_size.max(1));
+        Arc::new(WebhookManager {
+            config,
+            tx,
+            consumer: std::sync::Mutex::new(Some(rx)),
+        })
+    }
+
+    pub fn is_enable(&self) -> bool {
+        self.config.enable && !self.config.endpoints.is_empty()
+    }
+
+    pub fn is_subscribed(&self, event: WebhookEventType) -> bool {
+        self.is_enable()
+            && self
+                .config
+                .endpoints
+                .iter()
+                .any(|e| endpoint_accepts(e, event.as_str()))
+    }
+
+    /// Queue an event for delivery. Never blocks: when the queue is full the
+    /// event is dropped and counted.
+    pub fn report<T: Serialize>(&self, event: WebhookEventType, data: &T) {
+        if !self.is_subscribed(event) {
+            return;
+        }
+
+        let data = match serde_json::to_value(data) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!(
+                    "Webhook: failed to serialize {} event: {}",
+                    event.as_str(),
+                    e
+                );
+                return;
+            }
+        };
+
+        let msg = WebhookEvent {
+            event: event.as_str().to_string(),
+            node_id: broker_config().broker_id,
+            ts: now_millis(),
+            data,
+        };
+        if self.tx.try_send(msg).is_err() {
+            record_webhook_event_dropped(event.as_str());
+        }
+    }
+
+    pub async fn start(&self, stop_send: broadcast::Sender<bool>) {
+        if !self.is_enable() {
+            return;
+        }
+
+        let Some(mut rx) = self.consumer.lock().unwrap().take() else {
+            error!("WebhookManager::start must be called exactly once");
+            return;
+        };
+
+        let mut senders = Vec::with_capacity(self.config.endpoints.len());
+        for endpoint in self.config.endpoints.iter() {
+            let client = match Client::builder()
+                .timeout(Duration::from_millis(endpoint.timeout_ms))
+                .build()
+            {
+                Ok(client) => client,
+                Err(e) => {
+                    error!(
+                        "Webhook: failed to build HTTP client for endpoint {}: {}",
+                        endpoint.name, e
+                    );
+                    continue;
+                }
+            };
+            set_webhook_circuit_open(&endpoint.name, false);
+            senders.push(EndpointSender {
+                config: endpoint.clone(),
+                client,
+                breaker: CircuitBreaker::new(
+                    self.config.circuit_breaker_threshold,
+                    self.config.circuit_breaker_open_ms,
+                ),
+            });
+        }
+
+        let mut stop_rx = stop_send.subscribe();
+        let batch_interval = Duration::from_millis(self.config.batch_interval_ms);
+        let batch_size = self.config.batch_size.max(1);
+        loop {
+            let first = tokio::select! {
+                val = stop_rx.recv() => {
+                    if matches!(val, Ok(false) | Err(broadcast::error::RecvError::Lagged(_))) {
+                        continue;
+                    }
+                    info!("Webhook event dispatcher stopped");
+                    return;
+                }
+                item = rx.recv() => match item {
+                    Some(item) => item,
+                    None => return,
+                }
+            };
+
+            let mut batch = vec![first];
+            let deadline = tokio::time::Instant::now() + batch_interval;
+            while batch.len() < batch_size {
+                match timeout(
+                    deadline.saturating_duration_since(tokio::time::Instant::now()),
+                    rx.recv(),
+                )
+                .await
+                {
+                    Ok(Some(item)) => batch.push(item),
+                    _ => break,
+                }
+            }
+
+            // A slow endpoint must not hold back the others.
+            join_all(
+                senders
+                    .iter_mut()
+                    .map(|sender| self.deliver(sender, &batch)),
+            )
+            .await;
+        }
+    }
+
+    async fn deliver(&self, sender: &mut EndpointSender, batch: &[WebhookEvent]) {
+        let events: Vec<&WebhookEvent> =
+            batch.iter().filter(|e| sender.accepts(&e.event)).collect();
+        if events.is_empty() {
+            return;
+        }
+        let count = events.len() as u64;
+
+        if !sender.breaker.allow(now_millis()) {
+            record_webhook_events(&sender.config.name, "circuit_open", count);
+            return;
+        }
+
+        let body = match serde_json::to_string(&events) {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Webhook: failed to serialize batch: {}", e);
+                return;
+            }
+        };
+
+        let mut attempt = 0;
+        loop {
+            match sender.send(&body).await {
+                Ok(()) => {
+                    sender.breaker.on_success();
+                    set_webhook_circuit_open(&sender.config.name, false);
+                    record_webhook_events(&sender.config.name, "success", count);
+                    return;
+                }
+                Err(e) => {
+                    if attempt >= self.config.max_retries {
+                        warn!(
+                            "Webhook: endpoint {} failed after {} attempts: {}",
+                            sender.config.name,
+                            attempt + 1,
+                            e
+                        );
+                        break;
+                    }
+                    let backoff = self
+                        .config
+                        .retry_backoff_ms
+                        .saturating_mul(1 << attempt.min(16));
+                    sleep(Duration::from_millis(backoff)).await;
+                    attempt += 1;
+                }
+            }
+        }
+
+        sender.breaker.on_failure(now_millis());
+        if sender.breaker.is_open() {
+            set_webhook_circuit_open(&sender.config.name, true);
+        }
+        record_webhook_events(&sender.config.name, "failure", count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn endpoint(events: Vec<&str>) -> WebhookEndpoint {
+        WebhookEndpoint {
+            name: "test".to_string(),
+            url: "http://127.0.0.1:1/hook".to_string(),
+            events: events.into_iter().map(|e| e.to_string()).collect(),
+            headers: HashMap::new(),
+            timeout_ms: 100,
+        }
+    }
+
+    #[test]
+    fn test_circuit_breaker() {
+        let mut breaker = CircuitBreaker::new(2, 1000);
+        assert!(breaker.allow(0));
+
+        breaker.on_failure(0);
+        assert!(!breaker.is_open());
+        assert!(breaker.allow(0));
+
+        breaker.on_failure(10);
+        assert!(breaker.is_open());
+        assert!(!breaker.allow(500));
+        assert!(breaker.allow(1010));
+
+        // a failed trial re-opens immediately
+        breaker.on_failure(1010);
+        assert!(!breaker.allow(1500));
+
+        breaker.on_success();
+        assert!(!breaker.is_open());
+        assert!(breaker.allow(1500));
+
+        let disabled = CircuitBreaker::new(0, 1000);
+        assert!(!disabled.is_open());
+    }
+
+    #[test]
+    fn test_event_filter() {
+        let all = endpoint(vec![]);
+        assert!(endpoint_accepts(&all, "message.publish"));
+
+        let connected = endpoint(vec!["client.connected"]);
+        assert!(endpoint_accepts(&connected, "client.connected"));
+        assert!(!endpoint_accepts(&connected, "message.publish"));
+
+        let manager = WebhookManager::new(MqttWebhook {
+            enable: true,
+            endpoints: vec![connected],
+            ..Default::default()
+        });
+        assert!(manager.is_subscribed(WebhookEventType::ClientConnected));
+        assert!(!manager.is_subscribed(WebhookEventType::MessagePublish));
+
+        let disabled = WebhookManager::new(MqttWebhook::default());
+        assert!(!disabled.is_subscribed(WebhookEventType::ClientConnected));
+    }
+}
@@ -14,9 +14,12 @@
 
 #![allow(clippy::result_large_err)]
 pub mod broker;
+pub mod coap;
 pub mod core;
 pub mod mqtt;
+pub mod mqttsn;
 pub mod server;
 pub mod storage;
 pub mod subscribe;
 pub mod system_topic;
+pub mod web_subscribe;
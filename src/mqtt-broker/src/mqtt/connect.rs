@@ -15,31 +15,43 @@
 use super::{MqttService, MqttServiceConnectContext};
 use crate::core::cache::ConnectionLiveTime;
 use crate::core::connection::response_information;
-use crate::core::connection::{build_connection, get_client_id};
+use crate::core::connection::{assigned_client_id, build_connection, get_client_id};
 use crate::core::content_type::payload_format_indicator_check_by_lastwill;
 use crate::core::error::MqttBrokerError;
 use crate::core::event::st_report_connected_event;
 use crate::core::flapping_detect::check_flapping_detect;
+use crate::core::inflight::{
+    inflight_persistent_enabled, resume_inflight_messages, InflightRedeliveryContext,
+};
 use crate::core::last_will::save_last_will_message;
 use crate::core::limit::connection_total_num_limit;
+use crate::core::overload::throttle_connect;
+use crate::core::qos::resume_qos2_receive_state;
 use crate::core::security::{security_check_connect, ConnectAuthResult};
 use crate::core::session::{session_process, BuildSessionContext};
 use crate::core::string_validator::{validate_client_id, validate_password, validate_username};
 use crate::core::sub_auto::try_auto_subscribe;
 use crate::core::tenant::{get_tenant_info, try_decode_client_id};
 use crate::core::topic::topic_name_validator;
+use crate::storage::inflight::InflightStorage;
 use common_base::tools::now_second;
 use common_config::config::BrokerConfig;
 use common_metrics::mqtt::auth::{record_mqtt_auth_failed, record_mqtt_auth_success};
+use common_metrics::mqtt::event::record_mqtt_keep_alive_override;
 use protocol::mqtt::common::{
     ConnAck, ConnAckProperties, Connect, ConnectProperties, ConnectReturnCode, LastWill,
     LastWillProperties, Login, MqttPacket, MqttProtocol,
 };
 use std::cmp::min;
+use std::time::Duration;
+use tokio::time::sleep;
 use tracing::warn;
 
+const INFLIGHT_RESUME_DELAY_MS: u64 = 100;
+
 impl MqttService {
     pub async fn connect(&self, context: MqttServiceConnectContext) -> MqttPacket {
+        throttle_connect(&self.cache_manager).await;
         let cluster = self.cache_manager.node_cache.get_cluster_config();
 
         if let Some(res) = connect_validator(
@@ -78,6 +90,7 @@ pub async fn connect(&self, context: MqttServiceConnectContext) -> MqttPacket {
         // decode tenant
         let tenant = match get_tenant_info(
             &self.cache_manager,
+            &self.security_manager,
             &client_id,
             &context.connect_properties,
             &context.login,
@@ -100,10 +113,18 @@ pub async fn connect(&self, context: MqttServiceConnectContext) -> MqttPacket {
             return pkt;
         }
 
-        client_id = try_decode_client_id(&client_id);
+        client_id = if new_client_id {
+            assigned_client_id(
+                &cluster.mqtt_client_id,
+                &tenant.tenant_name,
+                &context.connection_type.to_string().to_lowercase(),
+            )
+        } else {
+            try_decode_client_id(&client_id)
+        };
 
         // build connection
-        let connection = build_connection(
+        let mut connection = build_connection(
             &tenant.tenant_name,
             context.connect_id,
             client_id.clone(),
@@ -142,6 +163,7 @@ pub async fn connect(&self, context: MqttServiceConnectContext) -> MqttPacket {
             &connection.source_ip,
             &context.login,
             &context.connect_properties,
+            context.cert_authenticated,
         )
         .await
         {
@@ -182,6 +204,7 @@ pub async fn connect(&self, context: MqttServiceConnectContext) -> MqttPacket {
                 tenant: tenant.tenant_name.clone(),
                 connect_id: context.connect_id,
                 client_id: client_id.clone(),
+                assigned_client_id: new_client_id,
                 connect: context.connect.clone(),
                 connect_properties: context.connect_properties.clone(),
                 last_will: context.last_will.clone(),
@@ -210,6 +233,7 @@ pub async fn connect(&self, context: MqttServiceConnectContext) -> MqttPacket {
             &context.last_will,
             &context.last_will_properties,
             &self.storage_driver_manager,
+            &self.client_pool,
         )
         .await
         {
@@ -241,14 +265,34 @@ pub async fn connect(&self, context: MqttServiceConnectContext) -> MqttPacket {
         }
         let live_time = ConnectionLiveTime {
             protocol: self.protocol.clone(),
-            keep_live: context.connect.keep_alive,
+            keep_live: connection.keep_alive,
             heartbeat: now_second(),
         };
         self.cache_manager
             .report_heartbeat(client_id.clone(), live_time);
         self.cache_manager.add_session(&client_id, &session);
+        connection.attributes = session.attributes.clone();
         self.cache_manager
             .add_connection(context.connect_id, connection.clone());
+        // Before CONNACK, so a clean start cannot drop a QoS 2 message the new
+        // session has already sent.
+        if let Err(e) = resume_qos2_receive_state(
+            &self.cache_manager,
+            &self.storage_driver_manager,
+            &tenant.tenant_name,
+            &client_id,
+            new_session,
+        )
+        .await
+        {
+            warn!(
+                "Failed to resume QoS2 receive state, client_id={}, error={}",
+                client_id, e
+            );
+        }
+        if inflight_persistent_enabled(&self.cache_manager) {
+            self.spawn_inflight_resume(&tenant.tenant_name, &client_id, new_session);
+        }
         st_report_connected_event(
             &self.event_manager,
             &self.connection_manager,
@@ -266,10 +310,32 @@ pub async fn connect(&self, context: MqttServiceConnectContext) -> MqttPacket {
             session_expiry_interval: session.session_expiry_interval as u32,
             session_present: !new_session,
             keep_alive: connection.keep_alive,
+            client_keep_alive: context.connect.keep_alive,
             connect_properties: context.connect_properties.clone(),
         })
     }
 
+    fn spawn_inflight_resume(&self, tenant: &str, client_id: &str, new_session: bool) {
+        let context = InflightRedeliveryContext {
+            cache_manager: self.cache_manager.clone(),
+            connection_manager: self.connection_manager.clone(),
+            inflight_storage: InflightStorage::new(self.storage_driver_manager.clone()),
+            tenant: tenant.to_string(),
+            client_id: client_id.to_string(),
+            stop_sx: self.stop_sx.clone(),
+        };
+        tokio::spawn(async move {
+            // Let CONNACK go out before any redelivered packet.
+            sleep(Duration::from_millis(INFLIGHT_RESUME_DELAY_MS)).await;
+            if let Err(e) = resume_inflight_messages(&context, new_session).await {
+                warn!(
+                    "Failed to resume inflight messages, client_id={}, error={}",
+                    context.client_id, e
+                );
+            }
+        });
+    }
+
     async fn check_connection_limit(
         &self,
         tenant_name: &str,
@@ -309,6 +375,7 @@ struct ResponsePacketMqttConnectSuccessContext {
     pub session_expiry_interval: u32,
     pub session_present: bool,
     pub keep_alive: u16,
+    pub client_keep_alive: u16,
     pub connect_properties: Option<ConnectProperties>,
 }
 
@@ -325,6 +392,11 @@ fn build_connect_ack_success_packet(
         );
     }
 
+    let response_info = response_information(
+        &context.cluster.mqtt_protocol,
+        &context.client_id,
+        &context.connect_properties,
+    );
     let assigned_client_identifier = if context.auto_client_id {
         Some(context.client_id)
     } else {
@@ -344,8 +416,8 @@ fn build_connect_ack_success_packet(
         wildcard_subscription_available: Some(1),
         subscription_identifiers_available: Some(1),
         shared_subscription_available: Some(1),
-        server_keep_alive: Some(context.keep_alive),
-        response_information: response_information(&context.connect_properties),
+        server_keep_alive: server_keep_alive(context.keep_alive, context.client_keep_alive),
+        response_information: response_info,
         server_reference: None,
         authentication_method: None,
         authentication_data: None,
@@ -562,6 +634,16 @@ fn connect_validator(
     None
 }
 
+/// Server Keep Alive is only sent when the broker overrides the value the
+/// client asked for; otherwise the client keeps using its own.
+fn server_keep_alive(keep_alive: u16, client_keep_alive: u16) -> Option<u16> {
+    if keep_alive == client_keep_alive {
+        return None;
+    }
+    record_mqtt_keep_alive_override();
+    Some(keep_alive)
+}
+
 fn connection_max_packet_size(
     connect_properties: &Option<ConnectProperties>,
     cluster: &BrokerConfig,
@@ -805,4 +887,11 @@ fn test_valid_connect() {
         );
         assert!(result.is_none());
     }
+
+    #[test]
+    fn test_server_keep_alive_only_on_override() {
+        assert_eq!(server_keep_alive(60, 60), None);
+        assert_eq!(server_keep_alive(180, 0), Some(180));
+        assert_eq!(server_keep_alive(2400, 4000), Some(2400));
+    }
 }
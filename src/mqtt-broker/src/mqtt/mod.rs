@@ -20,7 +20,9 @@
 pub mod subscribe;
 
 use common_security::manager::SecurityManager;
+use connector::manager::ConnectorManager;
 use grpc_clients::pool::ClientPool;
+use metadata_struct::connection::NetworkConnectionType;
 use network_server::common::connection_manager::ConnectionManager;
 use node_call::NodeCallManager;
 use protocol::mqtt::common::{
@@ -57,6 +59,7 @@ pub struct MqttService {
     rocksdb_engine_handler: Arc<RocksDBEngine>,
     limit_manager: Arc<MQTTRateLimiterManager>,
     event_manager: Arc<EventReportManager>,
+    connector_manager: Arc<ConnectorManager>,
     pub(crate) stop_sx: broadcast::Sender<bool>,
 }
 
@@ -76,6 +79,7 @@ pub struct MqttServiceContext {
     pub limit_manager: Arc<MQTTRateLimiterManager>,
     pub node_call: Arc<NodeCallManager>,
     pub event_manager: Arc<EventReportManager>,
+    pub connector_manager: Arc<ConnectorManager>,
     pub stop_sx: broadcast::Sender<bool>,
 }
 
@@ -88,6 +92,9 @@ pub struct MqttServiceConnectContext {
     pub last_will_properties: Option<LastWillProperties>,
     pub login: Option<Login>,
     pub addr: SocketAddr,
+    /// The username was taken from a verified TLS client certificate.
+    pub cert_authenticated: bool,
+    pub connection_type: NetworkConnectionType,
 }
 
 impl MqttService {
@@ -106,6 +113,7 @@ pub fn new(context: MqttServiceContext) -> Self {
             rocksdb_engine_handler: context.rocksdb_engine_handler,
             limit_manager: context.limit_manager,
             event_manager: context.event_manager,
+            connector_manager: context.connector_manager,
             stop_sx: context.stop_sx,
         }
     }
@@ -18,24 +18,37 @@
 use crate::core::content_type::payload_format_indicator_check_by_publish;
 use crate::core::delay_message::{decode_delay_topic, is_delay_topic};
 use crate::core::error::MqttBrokerError;
-use crate::core::limit::qos_flight_message_num_limit;
+use crate::core::limit::{qos_flight_message_num_limit, topic_depth_limit};
+use crate::core::message_path::trace_id_from_publish_properties;
+use crate::core::message_rule::{apply_message_rules, MessageRuleContext};
+use crate::core::message_trace::{trace_message, MessageTraceStage, TracedMessage};
 use crate::core::metrics::record_publish_receive_metrics;
 use crate::core::offline_message::{save_message, SaveMessageContext};
+use crate::core::overload::{shed_qos0_publish, throttle_publish_ack};
 use crate::core::pkid_manager::{PkidAckEnum, ReceiveQosPkidData};
-use crate::core::qos::{get_temporary_qos2_message, persistent_save_qos2_message};
+use crate::core::qos::{
+    delete_temporary_qos2_message, get_temporary_qos2_message, persistent_save_qos2_message,
+    qos2_publish_action, qos2_pubrel_action, Qos2ReceiveAction,
+};
+use crate::core::request_response::check_request_properties;
 use crate::core::security::security_is_allow_publish;
+use crate::core::slow_request::mark_phase;
 use crate::core::topic::{get_topic_name, try_init_topic};
+use crate::core::webhook::WebhookPublishEvent;
+use crate::mqtt::disconnect::build_distinct_packet;
 use common_base::tools::now_second;
+use common_metrics::mqtt::message_path::record_message_receive_to_store;
 use common_metrics::mqtt::publish::record_mqtt_messages_delayed_inc;
 use metadata_struct::mqtt::connection::MQTTConnection;
 use protocol::mqtt::common::{
-    MqttPacket, MqttProtocol, PubAck, PubAckProperties, PubAckReason, PubComp, PubCompProperties,
-    PubCompReason, PubRec, PubRecProperties, PubRecReason, PubRel, PubRelProperties, Publish,
-    PublishProperties, QoS,
+    DisconnectReasonCode, MqttPacket, MqttProtocol, PubAck, PubAckProperties, PubAckReason,
+    PubComp, PubCompProperties, PubCompReason, PubRec, PubRecProperties, PubRecReason, PubRel,
+    PubRelProperties, Publish, PublishProperties, QoS,
 };
 use std::cmp::min;
 use std::sync::Arc;
-use tracing::debug;
+use std::time::Instant;
+use tracing::{debug, warn};
 
 const PUBLISH_QOS_DUMP: &str = "PUBLISH_QOS_DUMP";
 
@@ -46,10 +59,23 @@ pub async fn publish(
         publish: &Publish,
         publish_properties: &Option<PublishProperties>,
     ) -> Option<MqttPacket> {
+        let receive_at = Instant::now();
         let is_pub_ack = publish.qos != QoS::ExactlyOnce;
         if let Some(reason_info) =
             publish_validator(&self.cache_manager, connection, publish, publish_properties).await
         {
+            // QoS 0 has no acknowledgement to carry the reason, so an invalid
+            // topic name ends the connection with DISCONNECT instead.
+            if publish.qos == QoS::AtMostOnce && reason_info.1 == PubAckReason::TopicNameInvalid {
+                return Some(build_distinct_packet(
+                    &self.cache_manager,
+                    connection.connect_id,
+                    &self.protocol,
+                    Some(DisconnectReasonCode::TopicNameInvalid),
+                    None,
+                    Some(reason_info.2),
+                ));
+            }
             return qos_response(
                 &publish.qos,
                 Some(build_pub_ack_fail(
@@ -63,20 +89,48 @@ pub async fn publish(
             );
         }
 
+        mark_phase("validate");
+
+        // QoS 0 is dropped first when the node is overloaded, it carries no
+        // delivery guarantee
+        if publish.qos == QoS::AtMostOnce && shed_qos0_publish(&self.cache_manager) {
+            return None;
+        }
+
         if let Some(packet) = self.qos_pre_process(connection, publish).await {
             return Some(packet);
         }
+        mark_phase("qos");
 
         let (offset, topic_name) = match self
-            .process_publish0(connection, publish, publish_properties)
+            .process_publish0(connection, publish, publish_properties, receive_at)
             .await
         {
             Ok(data) => data,
             Err(e) => {
+                let topic = String::from_utf8_lossy(&publish.topic);
+                trace_message(
+                    &self.cache_manager,
+                    &TracedMessage {
+                        tenant: &connection.tenant,
+                        client_id: &connection.client_id,
+                        topic: &topic,
+                        subscriber: None,
+                    },
+                    MessageTraceStage::Rejected,
+                    || e.to_string(),
+                );
+                // Not stored, so a re-sent PUBLISH must be processed again.
+                self.cache_manager
+                    .pkid_manager
+                    .remove_qos_pkid_data(&connection.client_id, publish.p_kid);
                 let (pub_rec_reason, pub_ack_reason) = match &e {
                     MqttBrokerError::NotAclAuth(_) | MqttBrokerError::NotBlacklistAuth => {
                         (PubRecReason::NotAuthorized, PubAckReason::NotAuthorized)
                     }
+                    MqttBrokerError::TenantQuotaExceeded(_, _) => {
+                        (PubRecReason::QuotaExceeded, PubAckReason::QuotaExceeded)
+                    }
                     _ => (
                         PubRecReason::UnspecifiedError,
                         PubAckReason::UnspecifiedError,
@@ -105,6 +159,28 @@ pub async fn publish(
             &topic_name,
             publish.payload.len() as u64,
         );
+        self.cache_manager.topic_stats.record_publish(
+            &connection.tenant,
+            &topic_name,
+            publish.payload.len() as u64,
+        );
+
+        if self.event_manager.is_message_publish_subscribed() {
+            self.event_manager
+                .report_message_publish(WebhookPublishEvent {
+                    tenant: connection.tenant.clone(),
+                    client_id: connection.client_id.clone(),
+                    username: connection.login_user.clone().unwrap_or_default(),
+                    topic: topic_name.clone(),
+                    qos: publish.qos.into(),
+                    retain: publish.retain,
+                    payload: WebhookPublishEvent::encode_payload(&publish.payload),
+                });
+        }
+
+        if publish.qos != QoS::AtMostOnce {
+            throttle_publish_ack(&self.cache_manager).await;
+        }
 
         match publish.qos {
             QoS::AtMostOnce => None,
@@ -139,6 +215,7 @@ async fn process_publish0(
         connection: &MQTTConnection,
         publish: &Publish,
         publish_properties: &Option<PublishProperties>,
+        receive_at: Instant,
     ) -> Result<(String, String), MqttBrokerError> {
         let mut topic_name = get_topic_name(
             &self.cache_manager,
@@ -158,6 +235,27 @@ async fn process_publish0(
             None
         };
 
+        let traced = TracedMessage {
+            tenant: &connection.tenant,
+            client_id: &connection.client_id,
+            topic: &topic_name,
+            subscriber: None,
+        };
+        trace_message(
+            &self.cache_manager,
+            &traced,
+            MessageTraceStage::Received,
+            || {
+                format!(
+                    "qos={:?} retain={} pkid={} payload_size={}",
+                    publish.qos,
+                    publish.retain,
+                    publish.p_kid,
+                    publish.payload.len()
+                )
+            },
+        );
+
         if !security_is_allow_publish(
             &self.security_manager,
             connection,
@@ -168,6 +266,7 @@ async fn process_publish0(
         {
             return Err(MqttBrokerError::NotAclAuth(topic_name.clone()));
         }
+        mark_phase("acl");
 
         let topic = try_init_topic(
             &connection.tenant,
@@ -178,6 +277,7 @@ async fn process_publish0(
             &self.client_pool,
         )
         .await?;
+        mark_phase("topic");
 
         if delay_info.is_some() {
             let mut new_delay_info = delay_info.unwrap();
@@ -200,21 +300,80 @@ async fn process_publish0(
             }
         }
 
-        let client_id = connection.client_id.clone();
-
-        let offset = save_message(SaveMessageContext {
+        let rule_context = MessageRuleContext {
+            cache_manager: self.cache_manager.clone(),
             storage_driver_manager: self.storage_driver_manager.clone(),
             delay_message_manager: self.delay_message_manager.clone(),
-            cache_manager: self.cache_manager.clone(),
-            client_pool: self.client_pool.clone(),
-            publish: publish.clone(),
-            publish_properties: publish_properties.clone(),
             subscribe_manager: self.subscribe_manager.clone(),
-            client_id: client_id.clone(),
-            topic: topic.clone(),
-            delay_info,
-        })
-        .await?;
+            client_pool: self.client_pool.clone(),
+            connector_manager: self.connector_manager.clone(),
+        };
+        // a rule with a drop action consumes the message before it is stored
+        let dropped = apply_message_rules(&rule_context, connection, &topic_name, publish).await;
+        mark_phase("rule");
+
+        let client_id = connection.client_id.clone();
+
+        let offset = if dropped {
+            None
+        } else {
+            save_message(SaveMessageContext {
+                storage_driver_manager: self.storage_driver_manager.clone(),
+                delay_message_manager: self.delay_message_manager.clone(),
+                cache_manager: self.cache_manager.clone(),
+                client_pool: self.client_pool.clone(),
+                publish: publish.clone(),
+                publish_properties: publish_properties.clone(),
+                subscribe_manager: self.subscribe_manager.clone(),
+                client_id: client_id.clone(),
+                topic: topic.clone(),
+                delay_info,
+            })
+            .await?
+        };
+        mark_phase("store");
+        if offset.is_some() {
+            record_message_receive_to_store(
+                &connection.tenant,
+                receive_at.elapsed().as_secs_f64() * 1000.0,
+                trace_id_from_publish_properties(publish_properties).as_deref(),
+            );
+        }
+
+        match &offset {
+            Some(offset) => trace_message(
+                &self.cache_manager,
+                &traced,
+                MessageTraceStage::Stored,
+                || format!("offset={}", offset),
+            ),
+            None => trace_message(
+                &self.cache_manager,
+                &traced,
+                MessageTraceStage::Dropped,
+                || {
+                    if dropped {
+                        "dropped by message rule".to_string()
+                    } else {
+                        "not persisted".to_string()
+                    }
+                },
+            ),
+        }
+        trace_message(
+            &self.cache_manager,
+            &traced,
+            MessageTraceStage::Matched,
+            || {
+                let matched = self
+                    .subscribe_manager
+                    .topic_subscribes
+                    .get(&connection.tenant)
+                    .and_then(|topics| topics.get(&topic_name).map(|subs| subs.len()))
+                    .unwrap_or(0);
+                format!("matched {} local subscriptions", matched)
+            },
+        );
 
         Ok((format!("{:?}", offset), topic_name))
     }
@@ -228,6 +387,26 @@ async fn qos_pre_process(
             return None;
         }
 
+        // QOS 2: a re-sent PUBLISH is answered without storing it again
+        if publish.qos == QoS::ExactlyOnce {
+            let state = self
+                .cache_manager
+                .pkid_manager
+                .get_qos_pkid_data(&connection.client_id, publish.p_kid)
+                .map(|data| data.ack_enum);
+            if qos2_publish_action(state.as_ref()) == Qos2ReceiveAction::ResendPubRec {
+                return Some(build_pub_rec(
+                    &self.cache_manager,
+                    connection.connect_id,
+                    &self.protocol,
+                    publish.p_kid,
+                    PubRecReason::Success,
+                    None,
+                    vec![(PUBLISH_QOS_DUMP.to_string(), "true".to_string())],
+                ));
+            }
+        }
+
         // flight limit
         if qos_flight_message_num_limit(&self.cache_manager, connection) {
             if publish.qos == QoS::AtLeastOnce {
@@ -254,39 +433,6 @@ async fn qos_pre_process(
             }
         }
 
-        // QOS 2
-        if publish.qos == QoS::ExactlyOnce {
-            if let Some(data) = self
-                .cache_manager
-                .pkid_manager
-                .get_qos_pkid_data(&connection.client_id, publish.p_kid)
-            {
-                if data.ack_enum == PkidAckEnum::PubRec {
-                    return Some(build_pub_rec(
-                        &self.cache_manager,
-                        connection.connect_id,
-                        &self.protocol,
-                        publish.p_kid,
-                        PubRecReason::Success,
-                        None,
-                        vec![(PUBLISH_QOS_DUMP.to_string(), "true".to_string())],
-                    ));
-                }
-
-                if data.ack_enum == PkidAckEnum::PubComp {
-                    return Some(build_pub_comp(
-                        &self.cache_manager,
-                        connection.connect_id,
-                        &self.protocol,
-                        publish.p_kid,
-                        PubCompReason::Success,
-                        None,
-                        vec![(PUBLISH_QOS_DUMP.to_string(), "true".to_string())],
-                    ));
-                }
-            }
-        }
-
         self.cache_manager.pkid_manager.add_qos_pkid_data(
             &connection.client_id,
             ReceiveQosPkidData {
@@ -307,31 +453,56 @@ pub async fn publish_rel(
     ) -> MqttPacket {
         let data = match get_temporary_qos2_message(
             &self.storage_driver_manager,
+            &connection.tenant,
             &connection.client_id,
             pub_rel.pkid,
         )
         .await
         {
-            Ok(Some(data)) => data,
-            Ok(None) => {
+            Ok(data) => data,
+            Err(e) => {
                 return build_pub_comp(
                     &self.cache_manager,
                     connection.connect_id,
                     &self.protocol,
                     pub_rel.pkid,
                     PubCompReason::PacketIdentifierNotFound,
-                    Some("packet identifier not found".to_string()),
+                    Some(e.to_string()),
                     Vec::new(),
                 );
             }
-            Err(e) => {
+        };
+
+        let state = self
+            .cache_manager
+            .pkid_manager
+            .get_qos_pkid_data(&connection.client_id, pub_rel.pkid)
+            .map(|data| data.ack_enum);
+        let data = match (qos2_pubrel_action(state.as_ref(), data.is_some()), data) {
+            (Qos2ReceiveAction::Release, Some(data)) => data,
+            (Qos2ReceiveAction::ResendPubComp, data) => {
+                if data.is_some() {
+                    self.delete_released_qos2_message(connection, pub_rel.pkid)
+                        .await;
+                }
+                return build_pub_comp(
+                    &self.cache_manager,
+                    connection.connect_id,
+                    &self.protocol,
+                    pub_rel.pkid,
+                    PubCompReason::Success,
+                    None,
+                    Vec::new(),
+                );
+            }
+            _ => {
                 return build_pub_comp(
                     &self.cache_manager,
                     connection.connect_id,
                     &self.protocol,
                     pub_rel.pkid,
                     PubCompReason::PacketIdentifierNotFound,
-                    Some(e.to_string()),
+                    Some("packet identifier not found".to_string()),
                     Vec::new(),
                 );
             }
@@ -349,9 +520,18 @@ pub async fn publish_rel(
             );
         }
 
-        self.cache_manager
-            .pkid_manager
-            .remove_qos_pkid_data(&connection.client_id, pub_rel.pkid);
+        // Mark the packet id released before dropping the stored message, so
+        // a PUBREL re-sent in between is never published twice.
+        self.cache_manager.pkid_manager.add_qos_pkid_data(
+            &connection.client_id,
+            ReceiveQosPkidData {
+                ack_enum: PkidAckEnum::PubComp,
+                pkid: pub_rel.pkid,
+                create_time: now_second(),
+            },
+        );
+        self.delete_released_qos2_message(connection, pub_rel.pkid)
+            .await;
 
         build_pub_comp(
             &self.cache_manager,
@@ -363,6 +543,22 @@ pub async fn publish_rel(
             Vec::new(),
         )
     }
+
+    async fn delete_released_qos2_message(&self, connection: &MQTTConnection, pkid: u16) {
+        if let Err(e) = delete_temporary_qos2_message(
+            &self.storage_driver_manager,
+            &connection.tenant,
+            &connection.client_id,
+            pkid,
+        )
+        .await
+        {
+            warn!(
+                "Failed to delete released QoS2 message, client_id={}, pkid={}, error={}",
+                connection.client_id, pkid, e
+            );
+        }
+    }
 }
 
 fn build_pub_ack_fail(
@@ -555,6 +751,17 @@ async fn publish_validator(
 
     let cluster = cache_manager.node_cache.get_cluster_config();
 
+    if !publish.topic.is_empty() {
+        let topic = String::from_utf8_lossy(&publish.topic);
+        if let Err(e) = topic_depth_limit(&cluster.mqtt_protocol, &topic) {
+            return Some((
+                PubRecReason::TopicNameInvalid,
+                PubAckReason::TopicNameInvalid,
+                e.to_string(),
+            ));
+        }
+    }
+
     let max_packet_size = min(
         cluster.mqtt_protocol.max_packet_size,
         connection.max_packet_size,
@@ -593,6 +800,25 @@ async fn publish_validator(
                 ));
             }
         }
+
+        if let Err(e) =
+            check_request_properties(&cluster.mqtt_protocol, &connection.client_id, properties)
+        {
+            let (rec_reason, ack_reason) = match e {
+                MqttBrokerError::ResponseTopicNotAuthorized(_) => {
+                    (PubRecReason::NotAuthorized, PubAckReason::NotAuthorized)
+                }
+                MqttBrokerError::InvalidResponseTopic(_, _) => (
+                    PubRecReason::TopicNameInvalid,
+                    PubAckReason::TopicNameInvalid,
+                ),
+                _ => (
+                    PubRecReason::ImplementationSpecificError,
+                    PubAckReason::ImplementationSpecificError,
+                ),
+            };
+            return Some((rec_reason, ack_reason, e.to_string()));
+        }
     }
 
     None
@@ -624,6 +850,7 @@ fn build_test_connection(topic_alias_max: u16, max_packet_size: u32) -> MQTTConn
             topic_alias_max,
             request_problem_info: 1,
             create_time: now_second(),
+            attributes: Default::default(),
         }
     }
 
@@ -688,6 +915,27 @@ async fn test_topic_alias_zero() {
         assert!(result.unwrap().2.contains("must not be 0"));
     }
 
+    #[tokio::test]
+    async fn test_response_topic_of_other_client() {
+        let cache_manager = test_build_mqtt_cache_manager().await;
+        let connection = build_test_connection(10, 1024 * 1024);
+        let publish = build_test_publish("service/request", QoS::AtLeastOnce, 1, 100);
+        let properties = Some(PublishProperties {
+            response_topic: Some("$rpc/other_client/reply".to_string()),
+            ..Default::default()
+        });
+
+        let result = publish_validator(&cache_manager, &connection, &publish, &properties).await;
+        assert_eq!(result.unwrap().1, PubAckReason::NotAuthorized);
+
+        let properties = Some(PublishProperties {
+            response_topic: Some("$rpc/test_client/reply".to_string()),
+            ..Default::default()
+        });
+        let result = publish_validator(&cache_manager, &connection, &publish, &properties).await;
+        assert!(result.is_none());
+    }
+
     #[tokio::test]
     async fn test_topic_alias_exceeds_max() {
         let cache_manager = test_build_mqtt_cache_manager().await;
@@ -715,6 +963,21 @@ async fn test_payload_too_large() {
         assert_eq!(reason_ack, PubAckReason::PayloadFormatInvalid);
     }
 
+    #[tokio::test]
+    async fn test_topic_too_deep() {
+        let cache_manager = test_build_mqtt_cache_manager().await;
+        let mut cluster = cache_manager.node_cache.get_cluster_config();
+        cluster.mqtt_protocol.max_topic_levels = 2;
+        cache_manager.node_cache.set_cluster_config(cluster);
+        let connection = build_test_connection(10, 1024 * 1024);
+        let publish = build_test_publish("a/b/c", QoS::AtLeastOnce, 1, 10);
+
+        let result = publish_validator(&cache_manager, &connection, &publish, &None).await;
+        let (reason_rec, reason_ack, _) = result.unwrap();
+        assert_eq!(reason_rec, PubRecReason::TopicNameInvalid);
+        assert_eq!(reason_ack, PubAckReason::TopicNameInvalid);
+    }
+
     #[tokio::test]
     async fn test_empty_payload_is_valid() {
         let cache_manager = test_build_mqtt_cache_manager().await;
@@ -14,12 +14,15 @@
 
 use super::MqttService;
 use crate::core::cache::{QosAckPackageData, QosAckPackageType};
+use crate::core::inflight::inflight_persistent_enabled;
+use crate::storage::inflight::InflightStorage;
 use common_base::tools::now_millis;
+use common_metrics::mqtt::message_path::record_message_push_to_ack;
 use metadata_struct::mqtt::connection::MQTTConnection;
 use protocol::mqtt::common::{
     MqttPacket, PubAck, PubAckProperties, PubComp, PubCompProperties, PubRec, PubRecProperties,
 };
-use tracing::debug;
+use tracing::{debug, warn};
 
 impl MqttService {
     pub async fn publish_ack(
@@ -34,6 +37,13 @@ pub async fn publish_ack(
             .pkid_manager
             .get_publish_to_client_qos_ack_data(&connection.client_id, pkid)
         {
+            record_message_push_to_ack(
+                &connection.tenant,
+                now_millis().saturating_sub(data.create_time) as f64,
+                data.trace_id.as_deref(),
+            );
+            self.update_inflight_message(connection, pkid, QosAckPackageType::PubAck)
+                .await;
             if let Err(e) = data
                 .sx
                 .send(QosAckPackageData {
@@ -70,6 +80,13 @@ pub async fn publish_rec(
             .pkid_manager
             .get_publish_to_client_qos_ack_data(&connection.client_id, pkid)
         {
+            record_message_push_to_ack(
+                &connection.tenant,
+                now_millis().saturating_sub(data.create_time) as f64,
+                data.trace_id.as_deref(),
+            );
+            self.update_inflight_message(connection, pkid, QosAckPackageType::PubRec)
+                .await;
             if let Err(e) = data
                 .sx
                 .send(QosAckPackageData {
@@ -106,6 +123,8 @@ pub async fn publish_comp(
             .pkid_manager
             .get_publish_to_client_qos_ack_data(&connection.client_id, pkid)
         {
+            self.update_inflight_message(connection, pkid, QosAckPackageType::PubComp)
+                .await;
             if let Err(e) = data
                 .sx
                 .send(QosAckPackageData {
@@ -129,4 +148,32 @@ pub async fn publish_comp(
 
         None
     }
+
+    async fn update_inflight_message(
+        &self,
+        connection: &MQTTConnection,
+        pkid: u16,
+        ack_type: QosAckPackageType,
+    ) {
+        if !inflight_persistent_enabled(&self.cache_manager) {
+            return;
+        }
+
+        let storage = InflightStorage::new(self.storage_driver_manager.clone());
+        let result = if ack_type == QosAckPackageType::PubRec {
+            storage
+                .mark_inflight_pubrel(&connection.tenant, &connection.client_id, pkid)
+                .await
+        } else {
+            storage
+                .delete_inflight_message(&connection.tenant, &connection.client_id, pkid)
+                .await
+        };
+        if let Err(e) = result {
+            warn!(
+                "Failed to update inflight message, client_id={}, pkid={}, ack={:?}, error={}",
+                connection.client_id, pkid, ack_type, e
+            );
+        }
+    }
 }
@@ -17,16 +17,22 @@
 use crate::core::connection::is_request_problem_info;
 use crate::core::error::MqttBrokerError;
 use crate::core::event::{st_report_subscribed_event, st_report_unsubscribed_event};
+use crate::core::limit::{
+    client_subscribe_num_limit, subscribe_total_num_limit, topic_depth_limit,
+};
 use crate::core::pkid_manager::{PkidAckEnum, ReceiveQosPkidData};
+use crate::core::request_response::allow_subscribe_response_topic;
 use crate::core::security::security_is_allow_subscribe;
 use crate::core::sub_exclusive::{allow_exclusive_subscribe, already_exclusive_subscribe};
+use crate::core::sub_option::no_local_on_share_subscribe;
 use crate::core::sub_share::{
     decode_share_info, full_group_name, is_mqtt_share_subscribe, resolve_share_sub_leader_id,
 };
 use crate::core::sub_wildcards::sub_path_validator;
-use crate::core::subscribe::remove_subscribe;
+use crate::core::subscribe::{is_new_sub, remove_subscribe};
 use crate::core::subscribe::{save_subscribe, SaveSubscribeContext};
-use crate::subscribe::common::min_qos;
+use crate::mqtt::disconnect::build_distinct_packet;
+use crate::subscribe::common::{decode_sub_path, min_qos};
 use crate::subscribe::manager::SubscribeManager;
 use broker_core::share_group::ShareGroupStorage;
 use common_base::tools::now_second;
@@ -49,6 +55,30 @@ pub async fn subscribe(
         subscribe: &Subscribe,
         subscribe_properties: &Option<SubscribeProperties>,
     ) -> MqttPacket {
+        // MQTT5 makes No Local on a shared subscription a Protocol Error, which
+        // closes the connection rather than failing the filter in the SUBACK.
+        if self.protocol.is_mqtt5() {
+            let share_no_local: Vec<&str> = subscribe
+                .filters
+                .iter()
+                .filter(|filter| no_local_on_share_subscribe(filter))
+                .map(|filter| filter.path.as_str())
+                .collect();
+            if !share_no_local.is_empty() {
+                return build_distinct_packet(
+                    &self.cache_manager,
+                    connection.connect_id,
+                    &self.protocol,
+                    Some(DisconnectReasonCode::ProtocolError),
+                    None,
+                    Some(format!(
+                        "No Local must not be set on shared subscription(s): {}",
+                        share_no_local.join(", ")
+                    )),
+                );
+            }
+        }
+
         let (reason_codes, reason) = subscribe_validator(
             &self.cache_manager,
             &self.security_manager,
@@ -107,6 +137,15 @@ pub async fn subscribe(
             },
         );
 
+        // Retain Handling 1 depends on whether the subscription existed before
+        // this packet, so look before it is saved.
+        let is_new_subs = is_new_sub(
+            &connection.tenant,
+            &connection.client_id,
+            subscribe,
+            &self.subscribe_manager,
+        );
+
         if let Err(e) = save_subscribe(SaveSubscribeContext {
             tenant: connection.tenant.clone(),
             client_id: connection.client_id.clone(),
@@ -134,10 +173,10 @@ pub async fn subscribe(
                 storage_driver_manager: &self.storage_driver_manager,
                 cache_manager: &self.cache_manager,
                 connection_manager: &self.connection_manager,
-                subscribe_manager: &self.subscribe_manager,
                 tenant: &connection.tenant,
                 client_id: &connection.client_id,
                 subscribe,
+                is_new_subs: &is_new_subs,
                 stop_sx: &self.stop_sx,
             })
             .await
@@ -421,8 +460,7 @@ async fn subscribe_validator(
 
     if cache_manager
         .pkid_manager
-        .get_qos_pkid_data(&connection.client_id, subscribe.packet_identifier)
-        .is_some()
+        .is_qos_pkid_in_use(&connection.client_id, subscribe.packet_identifier)
     {
         return (
             vec![SubscribeReasonCode::PkidInUse],
@@ -450,6 +488,25 @@ async fn subscribe_validator(
         return (return_codes, error_msg);
     }
 
+    let protocol_conf = cache_manager.node_cache.get_cluster_config().mqtt_protocol;
+    for filter in &subscribe.filters {
+        if let Err(e) = topic_depth_limit(&protocol_conf, &decode_sub_path(&filter.path)) {
+            return (vec![SubscribeReasonCode::TopicFilterInvalid], e.to_string());
+        }
+    }
+
+    if let Some(filter) = subscribe.filters.iter().find(|filter| {
+        !allow_subscribe_response_topic(&protocol_conf, &connection.client_id, &filter.path)
+    }) {
+        return (
+            vec![SubscribeReasonCode::NotAuthorized],
+            format!(
+                "Topic filter {} is inside another client's response namespace",
+                filter.path
+            ),
+        );
+    }
+
     if !allow_exclusive_subscribe(subscribe) {
         return (
             vec![SubscribeReasonCode::ExclusiveSubscriptionDisabled],
@@ -479,6 +536,54 @@ async fn subscribe_validator(
         );
     }
 
+    // Re-subscribing to an existing filter replaces it and does not count
+    // against the quota.
+    let new_num = subscribe
+        .filters
+        .iter()
+        .filter(|filter| {
+            subscribe_manager
+                .get_subscribe(&connection.tenant, &connection.client_id, &filter.path)
+                .is_none()
+        })
+        .count();
+    if new_num > 0
+        && subscribe_total_num_limit(
+            cache_manager,
+            subscribe_manager,
+            &connection.tenant,
+            new_num,
+        )
+    {
+        return (
+            vec![SubscribeReasonCode::QuotaExceeded],
+            MqttBrokerError::TenantQuotaExceeded(
+                connection.tenant.clone(),
+                "subscriptions".to_string(),
+            )
+            .to_string(),
+        );
+    }
+
+    if new_num > 0
+        && client_subscribe_num_limit(
+            cache_manager,
+            subscribe_manager,
+            &connection.tenant,
+            &connection.client_id,
+            new_num,
+        )
+    {
+        return (
+            vec![SubscribeReasonCode::QuotaExceeded],
+            MqttBrokerError::ClientSubscriptionsExceeded(
+                connection.client_id.clone(),
+                protocol_conf.max_subscriptions_per_client,
+            )
+            .to_string(),
+        );
+    }
+
     (Vec::new(), "".to_string())
 }
 
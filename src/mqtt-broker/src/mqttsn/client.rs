@@ -0,0 +1,736 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::gateway::MqttSnContext;
+use super::topic::{is_wildcard_topic, topic_id, MqttSnTopicRegistry};
+use common_base::tools::now_second;
+use common_config::broker::broker_config;
+use metadata_struct::connection::NetworkConnection;
+use protocol::mqtt::common::{
+    Connect, ConnectReturnCode, Disconnect, Filter, Login, MqttPacket, PingReq, PubAck,
+    PubAckReason, PubComp, PubRec, PubRel, Publish, QoS, Subscribe, SubscribeReasonCode,
+    Unsubscribe,
+};
+use protocol::mqttsn::codec::encode;
+use protocol::mqttsn::packet::{
+    MqttSnConnect, MqttSnFlags, MqttSnPacket, MqttSnPubAck, MqttSnPublish, MqttSnQoS, MqttSnRegAck,
+    MqttSnRegister, MqttSnReturnCode, MqttSnSubAck, MqttSnTopic,
+};
+use protocol::robust::{RobustMQPacket, RobustMQPacketWrapper, RobustMQProtocol};
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::select;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+const CLIENT_TICK_INTERVAL: Duration = Duration::from_secs(5);
+const REGISTER_RETRY_SEC: u64 = 10;
+
+pub struct MqttSnClientHandle {
+    pub connect_id: u64,
+    pub client_id: String,
+    pub inbound: mpsc::Sender<(SocketAddr, MqttSnPacket)>,
+}
+
+/// Publishes kept for a sleeping client, dropping the oldest beyond the cap.
+#[derive(Debug, Default)]
+pub struct SleepBuffer {
+    messages: VecDeque<Publish>,
+    max_messages: usize,
+    dropped: u64,
+}
+
+impl SleepBuffer {
+    pub fn new(max_messages: usize) -> Self {
+        SleepBuffer {
+            messages: VecDeque::new(),
+            max_messages,
+            dropped: 0,
+        }
+    }
+
+    pub fn push(&mut self, publish: Publish) {
+        if self.max_messages == 0 {
+            self.dropped += 1;
+            return;
+        }
+        while self.messages.len() >= self.max_messages {
+            self.messages.pop_front();
+            self.dropped += 1;
+        }
+        self.messages.push_back(publish);
+    }
+
+    pub fn drain(&mut self) -> Vec<Publish> {
+        self.messages.drain(..).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+enum ClientState {
+    Active,
+    /// The client sleeps until `until`, waking up with PINGREQ to collect
+    /// what was buffered for it.
+    Asleep {
+        until: u64,
+        duration: u64,
+    },
+}
+
+struct PendingRegister {
+    topic_id: u16,
+    topic_name: String,
+    sent_at: u64,
+    publishes: Vec<Publish>,
+}
+
+/// One MQTT-SN client, mapped to an MQTT 3.1.1 connection of this broker.
+/// Packets from the client are translated and handled by the MQTT command
+/// pipeline, and packets the broker sends to the connection arrive through
+/// the connection manager and are translated back.
+pub struct MqttSnClient {
+    context: MqttSnContext,
+    addr: SocketAddr,
+    network: NetworkConnection,
+    client_id: String,
+    topics: MqttSnTopicRegistry,
+    state: ClientState,
+    sleep_buffer: SleepBuffer,
+    last_register_msg_id: u16,
+    // (msg_id, PendingRegister)
+    pending_register: HashMap<u16, PendingRegister>,
+}
+
+impl MqttSnClient {
+    pub fn new(
+        context: MqttSnContext,
+        addr: SocketAddr,
+        network: NetworkConnection,
+        client_id: String,
+    ) -> Self {
+        let conf = broker_config();
+        MqttSnClient {
+            context,
+            addr,
+            network,
+            client_id,
+            topics: MqttSnTopicRegistry::new(&conf.mqtt_sn.predefined_topics),
+            state: ClientState::Active,
+            sleep_buffer: SleepBuffer::new(conf.mqtt_sn.sleep_buffer_max_messages),
+            last_register_msg_id: 0,
+            pending_register: HashMap::new(),
+        }
+    }
+
+    pub async fn run(
+        mut self,
+        connect: MqttSnConnect,
+        mut inbound: mpsc::Receiver<(SocketAddr, MqttSnPacket)>,
+        mut outbound: mpsc::Receiver<RobustMQPacketWrapper>,
+    ) {
+        if self.connect(&connect).await {
+            let mut tick = tokio::time::interval(CLIENT_TICK_INTERVAL);
+            loop {
+                select! {
+                    val = inbound.recv() => {
+                        let Some((addr, packet)) = val else {
+                            // Replaced by a new CONNECT from the same address, or
+                            // the gateway stopped.
+                            self.apply(MqttPacket::Disconnect(Disconnect { reason_code: None }, None))
+                                .await;
+                            break;
+                        };
+                        self.addr = addr;
+                        self.context
+                            .connection_manager
+                            .report_heartbeat(self.network.connection_id, now_second());
+                        if !self.handle_client_packet(packet).await {
+                            break;
+                        }
+                    }
+                    val = outbound.recv() => {
+                        let Some(wrapper) = val else {
+                            // The broker closed the MQTT connection.
+                            self.send(&MqttSnPacket::Disconnect { duration: None }).await;
+                            break;
+                        };
+                        if let Some(packet) = wrapper.packet.get_mqtt_packet() {
+                            if !self.handle_broker_packet(packet).await {
+                                break;
+                            }
+                        }
+                    }
+                    _ = tick.tick() => {
+                        self.on_tick().await;
+                    }
+                }
+            }
+        }
+
+        let connect_id = self.network.connection_id;
+        self.context
+            .clients
+            .retain(|_, handle| handle.connect_id != connect_id);
+        self.context
+            .connection_manager
+            .close_connect(connect_id)
+            .await;
+        if !self.sleep_buffer.is_empty() || self.sleep_buffer.dropped() > 0 {
+            debug!(
+                "MQTT-SN client {} closed with {} buffered messages, {} dropped while asleep",
+                self.client_id,
+                self.sleep_buffer.len(),
+                self.sleep_buffer.dropped()
+            );
+        }
+    }
+
+    async fn connect(&mut self, connect: &MqttSnConnect) -> bool {
+        if connect.flags.will {
+            // The will topic and message exchange is not supported.
+            self.send_connack(MqttSnReturnCode::NotSupported).await;
+            return false;
+        }
+
+        let conf = broker_config();
+        let login = (!conf.mqtt_sn.username.is_empty()).then(|| Login {
+            username: conf.mqtt_sn.username.clone(),
+            password: conf.mqtt_sn.password.clone(),
+        });
+        let packet = MqttPacket::Connect(
+            RobustMQProtocol::MQTT4.to_u8(),
+            Connect {
+                keep_alive: connect.duration,
+                client_id: connect.client_id.clone(),
+                clean_session: connect.flags.clean_session,
+            },
+            None,
+            None,
+            None,
+            login,
+        );
+
+        match self.apply(packet).await {
+            Some(MqttPacket::ConnAck(ack, _)) if ack.code == ConnectReturnCode::Success => {
+                self.send_connack(MqttSnReturnCode::Accepted).await;
+                true
+            }
+            resp => {
+                debug!(
+                    "MQTT-SN client {} was refused by the broker: {:?}",
+                    connect.client_id, resp
+                );
+                self.send_connack(MqttSnReturnCode::NotSupported).await;
+                false
+            }
+        }
+    }
+
+    /// Returns false when the client is gone.
+    async fn handle_client_packet(&mut self, packet: MqttSnPacket) -> bool {
+        match packet {
+            MqttSnPacket::Connect(connect) => {
+                // A sleeping client reconnecting with the same client id
+                // resumes its MQTT connection.
+                if connect.client_id == self.client_id {
+                    self.state = ClientState::Active;
+                    self.send_connack(MqttSnReturnCode::Accepted).await;
+                    self.flush_sleep_buffer().await;
+                }
+            }
+            MqttSnPacket::Register(register) => {
+                let (topic_id, return_code) = if is_wildcard_topic(&register.topic_name) {
+                    (0, MqttSnReturnCode::NotSupported)
+                } else {
+                    (
+                        self.topics.register(&register.topic_name),
+                        MqttSnReturnCode::Accepted,
+                    )
+                };
+                self.send(&MqttSnPacket::RegAck(MqttSnRegAck {
+                    topic_id,
+                    msg_id: register.msg_id,
+                    return_code,
+                }))
+                .await;
+            }
+            MqttSnPacket::RegAck(ack) => {
+                if let Some(pending) = self.pending_register.remove(&ack.msg_id) {
+                    if ack.return_code == MqttSnReturnCode::Accepted {
+                        for publish in pending.publishes {
+                            self.send_publish(MqttSnTopic::Id(pending.topic_id), publish)
+                                .await;
+                        }
+                    } else {
+                        warn!(
+                            "MQTT-SN client {} rejected topic {}, {} messages dropped",
+                            self.client_id,
+                            pending.topic_name,
+                            pending.publishes.len()
+                        );
+                        self.topics.forget(pending.topic_id);
+                    }
+                }
+            }
+            MqttSnPacket::Publish(publish) => self.publish(publish).await,
+            MqttSnPacket::PubAck(ack) => {
+                if ack.return_code == MqttSnReturnCode::InvalidTopicId {
+                    self.topics.forget(ack.topic_id);
+                }
+                self.apply(MqttPacket::PubAck(
+                    PubAck {
+                        pkid: ack.msg_id,
+                        reason: None,
+                    },
+                    None,
+                ))
+                .await;
+            }
+            MqttSnPacket::PubRec { msg_id } => {
+                self.apply(MqttPacket::PubRec(
+                    PubRec {
+                        pkid: msg_id,
+                        reason: None,
+                    },
+                    None,
+                ))
+                .await;
+            }
+            MqttSnPacket::PubRel { msg_id } => {
+                let resp = self
+                    .apply(MqttPacket::PubRel(
+                        PubRel {
+                            pkid: msg_id,
+                            reason: None,
+                        },
+                        None,
+                    ))
+                    .await;
+                if let Some(MqttPacket::PubComp(comp, _)) = resp {
+                    self.send(&MqttSnPacket::PubComp { msg_id: comp.pkid })
+                        .await;
+                }
+            }
+            MqttSnPacket::PubComp { msg_id } => {
+                self.apply(MqttPacket::PubComp(
+                    PubComp {
+                        pkid: msg_id,
+                        reason: None,
+                    },
+                    None,
+                ))
+                .await;
+            }
+            MqttSnPacket::Subscribe(subscribe) => {
+                self.subscribe(subscribe.msg_id, subscribe.flags.qos, subscribe.topic)
+                    .await
+            }
+            MqttSnPacket::Unsubscribe(unsubscribe) => {
+                let Some(topic_name) = self.topics.resolve(&unsubscribe.topic) else {
+                    self.send(&MqttSnPacket::UnsubAck {
+                        msg_id: unsubscribe.msg_id,
+                    })
+                    .await;
+                    return true;
+                };
+                self.apply(MqttPacket::Unsubscribe(
+                    Unsubscribe {
+                        pkid: unsubscribe.msg_id,
+                        filters: vec![topic_name],
+                    },
+                    None,
+                ))
+                .await;
+                self.send(&MqttSnPacket::UnsubAck {
+                    msg_id: unsubscribe.msg_id,
+                })
+                .await;
+            }
+            MqttSnPacket::PingReq { .. } => {
+                if let ClientState::Asleep { duration, .. } = self.state {
+                    // Awake period: deliver what was buffered, then the
+                    // client goes back to sleep.
+                    self.flush_sleep_buffer().await;
+                    self.state = ClientState::Asleep {
+                        until: now_second() + duration,
+                        duration,
+                    };
+                    self.context
+                        .cache_manager
+                        .refresh_heartbeat(&self.client_id);
+                } else {
+                    self.apply(MqttPacket::PingReq(PingReq)).await;
+                }
+                self.send(&MqttSnPacket::PingResp).await;
+            }
+            MqttSnPacket::Disconnect {
+                duration: Some(duration),
+            } if duration > 0 => {
+                self.state = ClientState::Asleep {
+                    until: now_second() + duration as u64,
+                    duration: duration as u64,
+                };
+                self.send(&MqttSnPacket::Disconnect { duration: None })
+                    .await;
+            }
+            MqttSnPacket::Disconnect { .. } => {
+                self.apply(MqttPacket::Disconnect(
+                    Disconnect { reason_code: None },
+                    None,
+                ))
+                .await;
+                self.send(&MqttSnPacket::Disconnect { duration: None })
+                    .await;
+                return false;
+            }
+            packet => {
+                debug!(
+                    "MQTT-SN client {} sent an unexpected packet: {:?}",
+                    self.client_id, packet
+                );
+            }
+        }
+        true
+    }
+
+    /// Returns false when the broker closed the connection.
+    async fn handle_broker_packet(&mut self, packet: MqttPacket) -> bool {
+        match packet {
+            MqttPacket::Publish(publish, _) => {
+                if matches!(self.state, ClientState::Asleep { .. }) {
+                    self.sleep_buffer.push(publish);
+                } else {
+                    self.deliver(publish).await;
+                }
+            }
+            MqttPacket::PubRel(rel, _) => {
+                self.send(&MqttSnPacket::PubRel { msg_id: rel.pkid }).await;
+            }
+            MqttPacket::Disconnect(_, _) => {
+                self.send(&MqttSnPacket::Disconnect { duration: None })
+                    .await;
+                return false;
+            }
+            _ => {}
+        }
+        true
+    }
+
+    async fn publish(&mut self, publish: MqttSnPublish) {
+        let topic_id = topic_id(&publish.topic);
+        let Some(topic_name) = self.topics.resolve(&publish.topic) else {
+            if publish.flags.qos != MqttSnQoS::AtMostOnce {
+                self.send_puback(topic_id, publish.msg_id, MqttSnReturnCode::InvalidTopicId)
+                    .await;
+            }
+            return;
+        };
+
+        let qos = match publish.flags.qos {
+            MqttSnQoS::AtLeastOnce => QoS::AtLeastOnce,
+            MqttSnQoS::ExactlyOnce => QoS::ExactlyOnce,
+            MqttSnQoS::AtMostOnce | MqttSnQoS::NoSession => QoS::AtMostOnce,
+        };
+        let packet = MqttPacket::Publish(
+            Publish {
+                dup: publish.flags.dup,
+                qos,
+                p_kid: publish.msg_id,
+                retain: publish.flags.retain,
+                topic: topic_name.into(),
+                payload: publish.data,
+            },
+            None,
+        );
+
+        match self.apply(packet).await {
+            Some(MqttPacket::PubAck(ack, _)) => {
+                let return_code = if matches!(
+                    ack.reason,
+                    None | Some(PubAckReason::Success) | Some(PubAckReason::NoMatchingSubscribers)
+                ) {
+                    MqttSnReturnCode::Accepted
+                } else {
+                    MqttSnReturnCode::NotSupported
+                };
+                self.send_puback(topic_id, ack.pkid, return_code).await;
+            }
+            Some(MqttPacket::PubRec(rec, _)) => {
+                self.send(&MqttSnPacket::PubRec { msg_id: rec.pkid }).await;
+            }
+            _ => {}
+        }
+    }
+
+    async fn subscribe(&mut self, msg_id: u16, qos: MqttSnQoS, topic: MqttSnTopic) {
+        let Some(topic_name) = self.topics.resolve(&topic) else {
+            self.send_suback(
+                0,
+                msg_id,
+                MqttSnQoS::AtMostOnce,
+                MqttSnReturnCode::InvalidTopicId,
+            )
+            .await;
+            return;
+        };
+        let qos = match qos {
+            MqttSnQoS::AtLeastOnce => QoS::AtLeastOnce,
+            MqttSnQoS::ExactlyOnce => QoS::ExactlyOnce,
+            MqttSnQoS::AtMostOnce | MqttSnQoS::NoSession => QoS::AtMostOnce,
+        };
+        let packet = MqttPacket::Subscribe(
+            Subscribe {
+                packet_identifier: msg_id,
+                filters: vec![Filter {
+                    path: topic_name.clone(),
+                    qos,
+                    ..Default::default()
+                }],
+            },
+            None,
+        );
+
+        let granted = match self.apply(packet).await {
+            Some(MqttPacket::SubAck(ack, _)) => match ack.return_codes.first() {
+                Some(SubscribeReasonCode::QoS0) => Some(MqttSnQoS::AtMostOnce),
+                Some(SubscribeReasonCode::QoS1) => Some(MqttSnQoS::AtLeastOnce),
+                Some(SubscribeReasonCode::QoS2) => Some(MqttSnQoS::ExactlyOnce),
+                Some(SubscribeReasonCode::Success(qos)) => Some(match qos {
+                    QoS::AtMostOnce => MqttSnQoS::AtMostOnce,
+                    QoS::AtLeastOnce => MqttSnQoS::AtLeastOnce,
+                    QoS::ExactlyOnce => MqttSnQoS::ExactlyOnce,
+                }),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        let Some(granted) = granted else {
+            self.send_suback(
+                0,
+                msg_id,
+                MqttSnQoS::AtMostOnce,
+                MqttSnReturnCode::NotSupported,
+            )
+            .await;
+            return;
+        };
+        // Topic names without wildcards get an id the client uses from now on.
+        let topic_id = match topic {
+            MqttSnTopic::Name(name) if !is_wildcard_topic(&name) => self.topics.register(&name),
+            MqttSnTopic::Name(_) | MqttSnTopic::Short(_) => 0,
+            MqttSnTopic::Id(id) | MqttSnTopic::Predefined(id) => id,
+        };
+        self.send_suback(topic_id, msg_id, granted, MqttSnReturnCode::Accepted)
+            .await;
+    }
+
+    /// Send a publish from the broker, registering its topic with the client
+    /// first when the client has no id for it.
+    async fn deliver(&mut self, publish: Publish) {
+        let topic_name = String::from_utf8_lossy(&publish.topic).to_string();
+        if let Some(pending) = self
+            .pending_register
+            .values_mut()
+            .find(|pending| pending.topic_name == topic_name)
+        {
+            pending.publishes.push(publish);
+            return;
+        }
+        if let Some(topic) = self.topics.publish_topic(&topic_name) {
+            self.send_publish(topic, publish).await;
+            return;
+        }
+
+        let topic_id = self.topics.register(&topic_name);
+        self.last_register_msg_id = self.last_register_msg_id.wrapping_add(1).max(1);
+        let msg_id = self.last_register_msg_id;
+        self.send(&MqttSnPacket::Register(MqttSnRegister {
+            topic_id,
+            msg_id,
+            topic_name: topic_name.clone(),
+        }))
+        .await;
+        self.pending_register.insert(
+            msg_id,
+            PendingRegister {
+                topic_id,
+                topic_name,
+                sent_at: now_second(),
+                publishes: vec![publish],
+            },
+        );
+    }
+
+    async fn flush_sleep_buffer(&mut self) {
+        for publish in self.sleep_buffer.drain() {
+            self.deliver(publish).await;
+        }
+    }
+
+    async fn on_tick(&mut self) {
+        let now = now_second();
+        if let ClientState::Asleep { until, .. } = self.state {
+            // Keep the MQTT connection alive for the announced sleep. A client
+            // that oversleeps is expired by the normal keep alive check.
+            if now < until {
+                self.context
+                    .cache_manager
+                    .refresh_heartbeat(&self.client_id);
+                self.context
+                    .connection_manager
+                    .report_heartbeat(self.network.connection_id, now);
+            }
+            return;
+        }
+
+        let stale: Vec<(u16, u16, String)> = self
+            .pending_register
+            .iter_mut()
+            .filter(|(_, pending)| now.saturating_sub(pending.sent_at) >= REGISTER_RETRY_SEC)
+            .map(|(msg_id, pending)| {
+                pending.sent_at = now;
+                (*msg_id, pending.topic_id, pending.topic_name.clone())
+            })
+            .collect();
+        for (msg_id, topic_id, topic_name) in stale {
+            self.send(&MqttSnPacket::Register(MqttSnRegister {
+                topic_id,
+                msg_id,
+                topic_name,
+            }))
+            .await;
+        }
+    }
+
+    async fn apply(&self, packet: MqttPacket) -> Option<MqttPacket> {
+        let resp = self
+            .context
+            .command
+            .apply(&self.network, &self.addr, &RobustMQPacket::MQTT(packet))
+            .await?;
+        resp.packet.get_mqtt_packet()
+    }
+
+    async fn send_publish(&self, topic: MqttSnTopic, publish: Publish) {
+        let qos = match publish.qos {
+            QoS::AtMostOnce => MqttSnQoS::AtMostOnce,
+            QoS::AtLeastOnce => MqttSnQoS::AtLeastOnce,
+            QoS::ExactlyOnce => MqttSnQoS::ExactlyOnce,
+        };
+        self.send(&MqttSnPacket::Publish(MqttSnPublish {
+            flags: MqttSnFlags {
+                dup: publish.dup,
+                qos,
+                retain: publish.retain,
+                ..Default::default()
+            },
+            topic,
+            msg_id: publish.p_kid,
+            data: publish.payload,
+        }))
+        .await;
+    }
+
+    async fn send_connack(&self, return_code: MqttSnReturnCode) {
+        self.send(&MqttSnPacket::ConnAck { return_code }).await;
+    }
+
+    async fn send_puback(&self, topic_id: u16, msg_id: u16, return_code: MqttSnReturnCode) {
+        self.send(&MqttSnPacket::PubAck(MqttSnPubAck {
+            topic_id,
+            msg_id,
+            return_code,
+        }))
+        .await;
+    }
+
+    async fn send_suback(
+        &self,
+        topic_id: u16,
+        msg_id: u16,
+        qos: MqttSnQoS,
+        return_code: MqttSnReturnCode,
+    ) {
+        self.send(&MqttSnPacket::SubAck(MqttSnSubAck {
+            flags: MqttSnFlags {
+                qos,
+                ..Default::default()
+            },
+            topic_id,
+            msg_id,
+            return_code,
+        }))
+        .await;
+    }
+
+    async fn send(&self, packet: &MqttSnPacket) {
+        if let Err(e) = self
+            .context
+            .socket
+            .send_to(&encode(packet), self.addr)
+            .await
+        {
+            debug!(
+                "Failed to send MQTT-SN packet to {} ({}): {}",
+                self.addr, self.client_id, e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn publish(payload: &'static str) -> Publish {
+        Publish {
+            topic: "sensors/t1".into(),
+            payload: payload.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sleep_buffer_keeps_newest() {
+        let mut buffer = SleepBuffer::new(2);
+        buffer.push(publish("a"));
+        buffer.push(publish("b"));
+        buffer.push(publish("c"));
+        assert_eq!(buffer.len(), 2);
+        assert_eq!(buffer.dropped(), 1);
+
+        let drained = buffer.drain();
+        assert_eq!(drained[0].payload, "b");
+        assert_eq!(drained[1].payload, "c");
+        assert!(buffer.is_empty());
+
+        let mut disabled = SleepBuffer::new(0);
+        disabled.push(publish("a"));
+        assert!(disabled.is_empty());
+        assert_eq!(disabled.dropped(), 1);
+    }
+}
@@ -0,0 +1,386 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::client::{MqttSnClient, MqttSnClientHandle};
+use super::topic::MqttSnTopicRegistry;
+use crate::core::cache::MQTTCacheManager;
+use crate::core::error::MqttBrokerError;
+use crate::core::tool::ResultMqttBrokerError;
+use common_config::broker::broker_config;
+use dashmap::DashMap;
+use metadata_struct::connection::{NetworkConnection, NetworkConnectionType};
+use network_server::command::ArcCommandAdapter;
+use network_server::common::connection_manager::ConnectionManager;
+use protocol::mqtt::common::{
+    Connect, ConnectReturnCode, Login, MqttPacket, PingReq, Publish, QoS,
+};
+use protocol::mqttsn::codec::{decode, encode};
+use protocol::mqttsn::packet::{
+    mqtt_sn_packet_to_string, MqttSnConnect, MqttSnPacket, MqttSnPublish, MqttSnQoS, MqttSnTopic,
+};
+use protocol::robust::{RobustMQPacket, RobustMQProtocol};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::select;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, info, warn};
+
+const MAX_DATAGRAM_SIZE: usize = 65535;
+const CLIENT_CHANNEL_SIZE: usize = 1000;
+const NO_SESSION_KEEP_ALIVE_SEC: u16 = 60;
+
+#[derive(Clone)]
+pub struct MqttSnContext {
+    pub command: ArcCommandAdapter,
+    pub cache_manager: Arc<MQTTCacheManager>,
+    pub connection_manager: Arc<ConnectionManager>,
+    pub socket: Arc<UdpSocket>,
+    // (client address, handle)
+    pub clients: Arc<DashMap<SocketAddr, MqttSnClientHandle>>,
+}
+
+/// UDP listener of the MQTT-SN gateway. Every datagram is one MQTT-SN
+/// message, dispatched to the task serving its client. QoS -1 publishes are
+/// sent through a connection owned by the gateway itself.
+pub struct MqttSnGateway {
+    command: ArcCommandAdapter,
+    cache_manager: Arc<MQTTCacheManager>,
+    connection_manager: Arc<ConnectionManager>,
+    stop_sx: broadcast::Sender<bool>,
+}
+
+impl MqttSnGateway {
+    pub fn new(
+        command: ArcCommandAdapter,
+        cache_manager: Arc<MQTTCacheManager>,
+        connection_manager: Arc<ConnectionManager>,
+        stop_sx: broadcast::Sender<bool>,
+    ) -> Self {
+        MqttSnGateway {
+            command,
+            cache_manager,
+            connection_manager,
+            stop_sx,
+        }
+    }
+
+    pub async fn start(&self) -> ResultMqttBrokerError {
+        let conf = broker_config();
+        let addr = format!("0.0.0.0:{}", conf.mqtt_sn.port);
+        let socket = UdpSocket::bind(&addr).await.map_err(|e| {
+            MqttBrokerError::CommonError(format!(
+                "Failed to bind MQTT-SN gateway to {}: {}",
+                addr, e
+            ))
+        })?;
+
+        let context = MqttSnContext {
+            command: self.command.clone(),
+            cache_manager: self.cache_manager.clone(),
+            connection_manager: self.connection_manager.clone(),
+            socket: Arc::new(socket),
+            clients: Arc::new(DashMap::new()),
+        };
+
+        let (no_session_sx, no_session_rx) = mpsc::channel(CLIENT_CHANNEL_SIZE);
+        tokio::spawn(Box::pin(run_no_session_publisher(
+            context.clone(),
+            no_session_rx,
+            self.stop_sx.subscribe(),
+        )));
+
+        let mut stop_rx = self.stop_sx.subscribe();
+        tokio::spawn(Box::pin(async move {
+            let mut buf = vec![0u8; MAX_DATAGRAM_SIZE];
+            loop {
+                select! {
+                    val = stop_rx.recv() => {
+                        if let Ok(flag) = val {
+                            if flag {
+                                // Dropping the handles disconnects every client.
+                                context.clients.clear();
+                                debug!("MQTT-SN gateway stopped successfully.");
+                                break;
+                            }
+                        }
+                    }
+                    val = context.socket.recv_from(&mut buf) => {
+                        match val {
+                            Ok((len, addr)) => {
+                                handle_datagram(&context, &no_session_sx, addr, &buf[..len]).await;
+                            }
+                            Err(e) => {
+                                debug!("MQTT-SN gateway failed to receive datagram: {}", e);
+                            }
+                        }
+                    }
+                }
+            }
+        }));
+
+        info!(
+            "MQTT-SN gateway started successfully, port:{}",
+            conf.mqtt_sn.port
+        );
+        Ok(())
+    }
+}
+
+async fn handle_datagram(
+    context: &MqttSnContext,
+    no_session_sx: &mpsc::Sender<MqttSnPublish>,
+    addr: SocketAddr,
+    datagram: &[u8],
+) {
+    let packet = match decode(datagram) {
+        Ok(packet) => packet,
+        Err(e) => {
+            debug!("Invalid MQTT-SN datagram from {}: {}", addr, e);
+            return;
+        }
+    };
+
+    match packet {
+        MqttSnPacket::SearchGw { .. } => {
+            let gw_id = broker_config().mqtt_sn.gateway_id;
+            send_to(context, addr, &MqttSnPacket::GwInfo { gw_id }).await;
+        }
+        MqttSnPacket::Publish(publish) if publish.flags.qos == MqttSnQoS::NoSession => {
+            if no_session_sx.try_send(publish).is_err() {
+                warn!(
+                    "MQTT-SN QoS -1 publish from {} dropped, queue is full",
+                    addr
+                );
+            }
+        }
+        MqttSnPacket::Connect(connect) => {
+            let resumed = context
+                .clients
+                .get(&addr)
+                .is_some_and(|handle| handle.client_id == connect.client_id);
+            if resumed {
+                forward(context, addr, MqttSnPacket::Connect(connect));
+                return;
+            }
+            context.clients.remove(&addr);
+            start_client(context, addr, connect);
+        }
+        MqttSnPacket::PingReq {
+            client_id: Some(client_id),
+        } if !context.clients.contains_key(&addr) => {
+            // A sleeping client may wake up with a new address.
+            let old_addr = context
+                .clients
+                .iter()
+                .find(|entry| entry.value().client_id == client_id)
+                .map(|entry| *entry.key());
+            let handle = old_addr.and_then(|old_addr| context.clients.remove(&old_addr));
+            let Some((_, handle)) = handle else {
+                send_to(context, addr, &MqttSnPacket::Disconnect { duration: None }).await;
+                return;
+            };
+            context.clients.insert(addr, handle);
+            forward(
+                context,
+                addr,
+                MqttSnPacket::PingReq {
+                    client_id: Some(client_id),
+                },
+            );
+        }
+        packet => {
+            if context.clients.contains_key(&addr) {
+                forward(context, addr, packet);
+            } else {
+                // Tell an unknown client to connect again.
+                debug!(
+                    "MQTT-SN {} from unknown client {}",
+                    mqtt_sn_packet_to_string(&packet),
+                    addr
+                );
+                send_to(context, addr, &MqttSnPacket::Disconnect { duration: None }).await;
+            }
+        }
+    }
+}
+
+fn forward(context: &MqttSnContext, addr: SocketAddr, packet: MqttSnPacket) {
+    if let Some(handle) = context.clients.get(&addr) {
+        if handle.inbound.try_send((addr, packet)).is_err() {
+            warn!(
+                "MQTT-SN client {} is not keeping up, packet dropped",
+                handle.client_id
+            );
+        }
+    }
+}
+
+fn start_client(context: &MqttSnContext, addr: SocketAddr, connect: MqttSnConnect) {
+    let mut network = NetworkConnection::new(NetworkConnectionType::MqttSn, addr, None);
+    network.set_protocol(RobustMQProtocol::MQTT4);
+    let connect_id = context.connection_manager.add_connection(network.clone());
+
+    let (outbound_sx, outbound_rx) = mpsc::channel(CLIENT_CHANNEL_SIZE);
+    context
+        .connection_manager
+        .add_mqttsn_write(connect_id, outbound_sx);
+
+    let (inbound_sx, inbound_rx) = mpsc::channel(CLIENT_CHANNEL_SIZE);
+    context.clients.insert(
+        addr,
+        MqttSnClientHandle {
+            connect_id,
+            client_id: connect.client_id.clone(),
+            inbound: inbound_sx,
+        },
+    );
+
+    let client = MqttSnClient::new(context.clone(), addr, network, connect.client_id.clone());
+    tokio::spawn(Box::pin(client.run(connect, inbound_rx, outbound_rx)));
+}
+
+async fn send_to(context: &MqttSnContext, addr: SocketAddr, packet: &MqttSnPacket) {
+    if let Err(e) = context.socket.send_to(&encode(packet), addr).await {
+        debug!("Failed to send MQTT-SN packet to {}: {}", addr, e);
+    }
+}
+
+/// Publishes QoS -1 messages through a connection of the gateway, which is
+/// connected on first use and again whenever the broker dropped it.
+async fn run_no_session_publisher(
+    context: MqttSnContext,
+    mut publish_rx: mpsc::Receiver<MqttSnPublish>,
+    mut stop_rx: broadcast::Receiver<bool>,
+) {
+    let conf = broker_config();
+    let topics = MqttSnTopicRegistry::new(&conf.mqtt_sn.predefined_topics);
+    let client_id = format!("mqttsn-gateway-{}", conf.broker_id);
+    let addr: SocketAddr = ([127, 0, 0, 1], 0).into();
+    let mut network: Option<NetworkConnection> = None;
+    let mut ping = tokio::time::interval(Duration::from_secs(NO_SESSION_KEEP_ALIVE_SEC as u64 / 2));
+
+    loop {
+        select! {
+            val = stop_rx.recv() => {
+                if let Ok(true) = val {
+                    break;
+                }
+            }
+            val = publish_rx.recv() => {
+                let Some(publish) = val else {
+                    break;
+                };
+                // QoS -1 can only address topics without REGISTER.
+                if matches!(publish.topic, MqttSnTopic::Id(_)) {
+                    continue;
+                }
+                let Some(topic_name) = topics.resolve(&publish.topic) else {
+                    debug!("MQTT-SN QoS -1 publish to unknown topic {:?}", publish.topic);
+                    continue;
+                };
+
+                let alive = network
+                    .as_ref()
+                    .is_some_and(|n| context.cache_manager.get_connection(n.connection_id).is_some());
+                if !alive {
+                    network = connect_no_session(&context, addr, &client_id).await;
+                }
+                let Some(conn) = network.as_ref() else {
+                    continue;
+                };
+
+                let packet = MqttPacket::Publish(
+                    Publish {
+                        qos: QoS::AtMostOnce,
+                        retain: publish.flags.retain,
+                        topic: topic_name.into(),
+                        payload: publish.data,
+                        ..Default::default()
+                    },
+                    None,
+                );
+                context
+                    .command
+                    .apply(conn, &addr, &RobustMQPacket::MQTT(packet))
+                    .await;
+            }
+            _ = ping.tick() => {
+                if let Some(conn) = network.as_ref() {
+                    context
+                        .command
+                        .apply(conn, &addr, &RobustMQPacket::MQTT(MqttPacket::PingReq(PingReq)))
+                        .await;
+                }
+            }
+        }
+    }
+
+    if let Some(conn) = network {
+        context
+            .connection_manager
+            .close_connect(conn.connection_id)
+            .await;
+        context.cache_manager.remove_connection(conn.connection_id);
+    }
+}
+
+async fn connect_no_session(
+    context: &MqttSnContext,
+    addr: SocketAddr,
+    client_id: &str,
+) -> Option<NetworkConnection> {
+    let conf = broker_config();
+    let mut network = NetworkConnection::new(NetworkConnectionType::MqttSn, addr, None);
+    network.set_protocol(RobustMQProtocol::MQTT4);
+    context.connection_manager.add_connection(network.clone());
+
+    let login = (!conf.mqtt_sn.username.is_empty()).then(|| Login {
+        username: conf.mqtt_sn.username.clone(),
+        password: conf.mqtt_sn.password.clone(),
+    });
+    let packet = MqttPacket::Connect(
+        RobustMQProtocol::MQTT4.to_u8(),
+        Connect {
+            keep_alive: NO_SESSION_KEEP_ALIVE_SEC,
+            client_id: client_id.to_string(),
+            clean_session: true,
+        },
+        None,
+        None,
+        None,
+        login,
+    );
+    let resp = context
+        .command
+        .apply(&network, &addr, &RobustMQPacket::MQTT(packet))
+        .await
+        .and_then(|resp| resp.packet.get_mqtt_packet());
+    if let Some(MqttPacket::ConnAck(ack, _)) = resp {
+        if ack.code == ConnectReturnCode::Success {
+            return Some(network);
+        }
+    }
+
+    warn!(
+        "MQTT-SN gateway failed to connect {} for QoS -1 publishes",
+        client_id
+    );
+    context
+        .connection_manager
+        .close_connect(network.connection_id)
+        .await;
+    None
+}
@@ -0,0 +1,26 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! MQTT-SN gateway.
+//!
+//! Sensors speaking MQTT-SN v1.2 over UDP are served by the same publish and
+//! subscribe pipeline as MQTT clients: each MQTT-SN client is mapped to an
+//! MQTT 3.1.1 connection of this broker, and its packets are translated in
+//! both directions. The gateway keeps the topic ids of each client, buffers
+//! messages for sleeping clients, and publishes QoS -1 messages, which are
+//! sent without a connection, through a connection of its own.
+
+pub mod client;
+pub mod gateway;
+pub mod topic;
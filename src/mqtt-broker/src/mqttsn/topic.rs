@@ -0,0 +1,167 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_config::config::MqttSnPredefinedTopic;
+use protocol::mqttsn::packet::MqttSnTopic;
+use std::collections::HashMap;
+
+/// Topic ids of one MQTT-SN client. Normal ids are assigned per client by
+/// REGISTER and SUBACK; predefined ids come from the gateway config and are
+/// the same for every client.
+#[derive(Debug, Default)]
+pub struct MqttSnTopicRegistry {
+    predefined: HashMap<u16, String>,
+    by_id: HashMap<u16, String>,
+    by_name: HashMap<String, u16>,
+    last_id: u16,
+}
+
+impl MqttSnTopicRegistry {
+    pub fn new(predefined: &[MqttSnPredefinedTopic]) -> Self {
+        MqttSnTopicRegistry {
+            predefined: predefined
+                .iter()
+                .map(|topic| (topic.topic_id, topic.topic_name.clone()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Id of `topic_name`, assigning the next free one when it is new. Ids 0
+    /// and 0xFFFF are reserved by the protocol.
+    pub fn register(&mut self, topic_name: &str) -> u16 {
+        if let Some(id) = self.by_name.get(topic_name) {
+            return *id;
+        }
+        loop {
+            self.last_id = self.last_id.wrapping_add(1);
+            if self.last_id != 0
+                && self.last_id != u16::MAX
+                && !self.by_id.contains_key(&self.last_id)
+            {
+                break;
+            }
+        }
+        self.by_id.insert(self.last_id, topic_name.to_string());
+        self.by_name.insert(topic_name.to_string(), self.last_id);
+        self.last_id
+    }
+
+    pub fn forget(&mut self, topic_id: u16) {
+        if let Some(name) = self.by_id.remove(&topic_id) {
+            self.by_name.remove(&name);
+        }
+    }
+
+    /// Topic name a PUBLISH or SUBSCRIBE refers to.
+    pub fn resolve(&self, topic: &MqttSnTopic) -> Option<String> {
+        match topic {
+            MqttSnTopic::Id(id) => self.by_id.get(id).cloned(),
+            MqttSnTopic::Predefined(id) => self.predefined.get(id).cloned(),
+            MqttSnTopic::Short(name) | MqttSnTopic::Name(name) => Some(name.clone()),
+        }
+    }
+
+    /// How to address `topic_name` in a PUBLISH to the client without
+    /// registering it first, if possible.
+    pub fn publish_topic(&self, topic_name: &str) -> Option<MqttSnTopic> {
+        if let Some(id) = self.by_name.get(topic_name) {
+            return Some(MqttSnTopic::Id(*id));
+        }
+        if let Some((id, _)) = self
+            .predefined
+            .iter()
+            .find(|(_, name)| name.as_str() == topic_name)
+        {
+            return Some(MqttSnTopic::Predefined(*id));
+        }
+        if topic_name.len() == 2 {
+            return Some(MqttSnTopic::Short(topic_name.to_string()));
+        }
+        None
+    }
+}
+
+pub fn topic_id(topic: &MqttSnTopic) -> u16 {
+    match topic {
+        MqttSnTopic::Id(id) | MqttSnTopic::Predefined(id) => *id,
+        MqttSnTopic::Short(name) => {
+            let bytes = name.as_bytes();
+            u16::from_be_bytes([
+                bytes.first().copied().unwrap_or(0),
+                bytes.get(1).copied().unwrap_or(0),
+            ])
+        }
+        MqttSnTopic::Name(_) => 0,
+    }
+}
+
+pub fn is_wildcard_topic(topic_name: &str) -> bool {
+    topic_name.contains('+') || topic_name.contains('#')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_and_resolve() {
+        let mut registry = MqttSnTopicRegistry::new(&[MqttSnPredefinedTopic {
+            topic_id: 10,
+            topic_name: "sensors/status".to_string(),
+        }]);
+
+        let id = registry.register("sensors/t1");
+        assert_eq!(id, 1);
+        assert_eq!(registry.register("sensors/t1"), 1);
+        assert_eq!(registry.register("sensors/t2"), 2);
+        assert_eq!(
+            registry.resolve(&MqttSnTopic::Id(1)).as_deref(),
+            Some("sensors/t1")
+        );
+        assert_eq!(
+            registry.resolve(&MqttSnTopic::Predefined(10)).as_deref(),
+            Some("sensors/status")
+        );
+        assert!(registry.resolve(&MqttSnTopic::Id(9)).is_none());
+
+        registry.forget(1);
+        assert!(registry.resolve(&MqttSnTopic::Id(1)).is_none());
+        assert_eq!(registry.register("sensors/t3"), 3);
+    }
+
+    #[test]
+    fn test_publish_topic() {
+        let mut registry = MqttSnTopicRegistry::new(&[MqttSnPredefinedTopic {
+            topic_id: 10,
+            topic_name: "sensors/status".to_string(),
+        }]);
+        registry.register("sensors/t1");
+
+        assert_eq!(
+            registry.publish_topic("sensors/t1"),
+            Some(MqttSnTopic::Id(1))
+        );
+        assert_eq!(
+            registry.publish_topic("sensors/status"),
+            Some(MqttSnTopic::Predefined(10))
+        );
+        assert_eq!(
+            registry.publish_topic("ab"),
+            Some(MqttSnTopic::Short("ab".to_string()))
+        );
+        assert!(registry.publish_topic("sensors/t2").is_none());
+        assert_eq!(topic_id(&MqttSnTopic::Short("ab".to_string())), 0x6162);
+    }
+}
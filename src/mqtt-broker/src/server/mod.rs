@@ -12,11 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::coap::gateway::CoapGateway;
 use crate::core::command::create_command;
 use crate::core::event::EventReportManager;
 
 use crate::core::tool::ResultMqttBrokerError;
+use crate::mqttsn::gateway::MqttSnGateway;
 use crate::storage::session::SessionBatcher;
+use crate::web_subscribe::server::WebSubscribeServer;
 use crate::{
     core::{cache::MQTTCacheManager, command::CommandContext},
     subscribe::manager::SubscribeManager,
@@ -25,6 +28,7 @@
 use common_base::task::TaskSupervisor;
 use common_config::broker::broker_config;
 use common_security::manager::SecurityManager;
+use connector::manager::ConnectorManager;
 use delay_message::manager::DelayMessageManager;
 use grpc_clients::pool::ClientPool;
 use metadata_struct::connection::NetworkConnectionType;
@@ -50,6 +54,9 @@ pub struct Server {
     tls_server: TcpServer,
     ws_server: WebSocketServer,
     quic_server: QuicServer,
+    mqttsn_gateway: MqttSnGateway,
+    coap_gateway: CoapGateway,
+    web_subscribe_server: WebSubscribeServer,
 }
 
 #[derive(Clone)]
@@ -71,6 +78,7 @@ pub struct TcpServerContext {
     pub global_limit_manager: Arc<GlobalRateLimiterManager>,
     pub node_call: Arc<NodeCallManager>,
     pub event_manager: Arc<EventReportManager>,
+    pub connector_manager: Arc<ConnectorManager>,
 }
 
 impl Server {
@@ -95,6 +103,7 @@ pub fn new(
             global_limit_manager: context.global_limit_manager.clone(),
             node_call: context.node_call.clone(),
             event_manager: context.event_manager.clone(),
+            connector_manager: context.connector_manager.clone(),
             stop_sx: context.stop_sx.clone(),
         };
 
@@ -130,11 +139,35 @@ pub fn new(
         server_context.network_type = NetworkConnectionType::QUIC;
         let quic_server = QuicServer::new(name.clone(), server_context);
 
+        let mqttsn_gateway = MqttSnGateway::new(
+            command.clone(),
+            context.cache_manager.clone(),
+            context.connection_manager.clone(),
+            context.stop_sx.clone(),
+        );
+
+        let coap_gateway = CoapGateway::new(
+            command.clone(),
+            context.cache_manager.clone(),
+            context.connection_manager.clone(),
+            context.stop_sx.clone(),
+        );
+
+        let web_subscribe_server = WebSubscribeServer::new(
+            command.clone(),
+            context.cache_manager.clone(),
+            context.connection_manager.clone(),
+            context.stop_sx.clone(),
+        );
+
         let server = Server {
             tcp_server,
             tls_server,
             ws_server,
             quic_server,
+            mqttsn_gateway,
+            coap_gateway,
+            web_subscribe_server,
         };
         (server, command)
     }
@@ -167,6 +200,18 @@ pub async fn start(&self) -> ResultMqttBrokerError {
         }));
 
         self.quic_server.start(conf.mqtt_server.quic_port).await?;
+
+        if conf.mqtt_sn.enable {
+            self.mqttsn_gateway.start().await?;
+        }
+
+        if conf.coap.enable {
+            self.coap_gateway.start().await?;
+        }
+
+        if conf.mqtt_web_subscribe.enable {
+            self.web_subscribe_server.start().await?;
+        }
         Ok(())
     }
 
@@ -44,6 +44,7 @@ pub async fn list_auto_subscribe_rule(
         let config = broker_config();
         let request = ListAutoSubscribeRuleRequest {
             tenant: tenant.unwrap_or_default(),
+            ..Default::default()
         };
         let reply = placement_list_auto_subscribe_rule(
             &self.client_pool,
@@ -48,6 +48,7 @@ pub async fn list_connector(
         let config = broker_config();
         let request = ListConnectorRequest {
             connector_name: connector_name.to_owned(),
+            ..Default::default()
         };
         let mut stream =
             placement_list_connector(&self.client_pool, &config.get_meta_service_addr(), request)
@@ -0,0 +1,142 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::core::error::MqttBrokerError;
+use crate::core::inflight::{InflightMessage, InflightStage};
+use crate::core::tool::ResultMqttBrokerError;
+use crate::storage::message::MessageStorage;
+use broker_core::inner_topic::INFLIGHT_MESSAGE_TOPIC;
+use common_base::utils::serialize;
+use metadata_struct::adapter::adapter_record::AdapterWriteRecord;
+// Like "$last-will-message", the inner topic lives under DEFAULT_TENANT. The key
+// "{tenant}/{client_id}/{pkid}" identifies one inflight packet and the tag
+// "{tenant}/{client_id}" groups the packets of a session.
+use metadata_struct::tenant::DEFAULT_TENANT;
+use std::collections::HashMap;
+use std::sync::Arc;
+use storage_adapter::driver::StorageDriverManager;
+
+#[derive(Clone)]
+pub struct InflightStorage {
+    storage_driver_manager: Arc<StorageDriverManager>,
+}
+
+impl InflightStorage {
+    pub fn new(storage_driver_manager: Arc<StorageDriverManager>) -> Self {
+        InflightStorage {
+            storage_driver_manager,
+        }
+    }
+
+    pub fn storage_driver_manager(&self) -> &Arc<StorageDriverManager> {
+        &self.storage_driver_manager
+    }
+
+    /// Save the state of an inflight packet, replacing any earlier state of
+    /// the same packet id.
+    pub async fn save_inflight_message(&self, message: &InflightMessage) -> ResultMqttBrokerError {
+        let key = inflight_key(&message.tenant, &message.client_id, message.pkid);
+        let data = serialize::serialize(message)?;
+        self.storage_driver_manager
+            .delete_by_keys(DEFAULT_TENANT, INFLIGHT_MESSAGE_TOPIC, &[key.as_str()])
+            .await?;
+        let record = AdapterWriteRecord::new(INFLIGHT_MESSAGE_TOPIC, data)
+            .with_key(&key)
+            .with_tags(vec![inflight_tag(&message.tenant, &message.client_id)]);
+        self.storage_driver_manager
+            .write(DEFAULT_TENANT, INFLIGHT_MESSAGE_TOPIC, &[record], 1)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_inflight_message(
+        &self,
+        tenant: &str,
+        client_id: &str,
+        pkid: u16,
+    ) -> Result<Option<InflightMessage>, MqttBrokerError> {
+        let key = inflight_key(tenant, client_id, pkid);
+        let records = self
+            .storage_driver_manager
+            .read_by_keys(DEFAULT_TENANT, INFLIGHT_MESSAGE_TOPIC, &[key.as_str()])
+            .await?
+            .remove(&key)
+            .unwrap_or_default();
+        match records.last() {
+            Some(record) => Ok(Some(serialize::deserialize::<InflightMessage>(
+                &record.data,
+            )?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Inflight packets of a session, one per packet id.
+    pub async fn list_inflight_message(
+        &self,
+        tenant: &str,
+        client_id: &str,
+    ) -> Result<Vec<InflightMessage>, MqttBrokerError> {
+        let records = MessageStorage::new(self.storage_driver_manager.clone())
+            .read_all_by_tag(
+                DEFAULT_TENANT,
+                INFLIGHT_MESSAGE_TOPIC,
+                &inflight_tag(tenant, client_id),
+            )
+            .await?;
+
+        let mut messages: HashMap<u16, InflightMessage> = HashMap::new();
+        for record in records {
+            let message = serialize::deserialize::<InflightMessage>(&record.data)?;
+            messages.insert(message.pkid, message);
+        }
+        Ok(messages.into_values().collect())
+    }
+
+    /// Record that PUBREC has been received for a QoS 2 packet.
+    pub async fn mark_inflight_pubrel(
+        &self,
+        tenant: &str,
+        client_id: &str,
+        pkid: u16,
+    ) -> ResultMqttBrokerError {
+        if let Some(mut message) = self.get_inflight_message(tenant, client_id, pkid).await? {
+            if message.stage != InflightStage::PubRel {
+                message.stage = InflightStage::PubRel;
+                self.save_inflight_message(&message).await?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn delete_inflight_message(
+        &self,
+        tenant: &str,
+        client_id: &str,
+        pkid: u16,
+    ) -> ResultMqttBrokerError {
+        let key = inflight_key(tenant, client_id, pkid);
+        self.storage_driver_manager
+            .delete_by_keys(DEFAULT_TENANT, INFLIGHT_MESSAGE_TOPIC, &[key.as_str()])
+            .await?;
+        Ok(())
+    }
+}
+
+fn inflight_key(tenant: &str, client_id: &str, pkid: u16) -> String {
+    format!("{}/{}/{}", tenant, client_id, pkid)
+}
+
+fn inflight_tag(tenant: &str, client_id: &str) -> String {
+    format!("{}/{}", tenant, client_id)
+}
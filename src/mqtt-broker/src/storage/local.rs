@@ -17,7 +17,9 @@
 use common_base::error::ResultCommonError;
 use rocksdb_engine::{
     rocksdb::RocksDBEngine,
-    storage::broker::{engine_prefix_list_by_broker, engine_save_by_broker},
+    storage::broker::{
+        engine_expire_prefix_by_broker, engine_prefix_list_by_broker, engine_save_by_broker,
+    },
 };
 
 use rocksdb_engine::keys::broker::{
@@ -28,8 +30,9 @@
 
 use crate::core::{
     error::MqttBrokerError, flapping_detect::BanLog, sub_slow::SlowSubscribeData,
-    system_alarm::SystemAlarmEventMessage,
+    system_alarm::active_system_alarms,
 };
+use metadata_struct::mqtt::system_alarm::SystemAlarmEventMessage;
 
 pub struct LocalStorage {
     rocksdb_engine_handler: Arc<RocksDBEngine>,
@@ -43,7 +46,7 @@ pub fn new(rocksdb_engine_handler: Arc<RocksDBEngine>) -> Self {
     }
 
     pub async fn save_system_event(&self, alarm: SystemAlarmEventMessage) -> ResultCommonError {
-        let key = system_event_key(&alarm.name, alarm.create_time as i64);
+        let key = system_event_key(&alarm.name, alarm.event_time() as i64);
         engine_save_by_broker(&self.rocksdb_engine_handler, &key, alarm)
     }
 
@@ -56,6 +59,20 @@ pub async fn list_system_event(&self) -> Result<Vec<SystemAlarmEventMessage>, Mq
         Ok(data.into_iter().map(|raw| raw.data).collect())
     }
 
+    pub async fn list_active_system_alarm(
+        &self,
+    ) -> Result<Vec<SystemAlarmEventMessage>, MqttBrokerError> {
+        Ok(active_system_alarms(self.list_system_event().await?))
+    }
+
+    pub async fn expire_system_event(&self, ttl_sec: u64) -> Result<u64, MqttBrokerError> {
+        Ok(engine_expire_prefix_by_broker::<SystemAlarmEventMessage>(
+            &self.rocksdb_engine_handler,
+            &system_event_prefix_key(),
+            ttl_sec,
+        )?)
+    }
+
     pub async fn save_ban_log(&self, log: BanLog) -> ResultCommonError {
         let key = ban_log_key(
             &log.tenant,
@@ -76,6 +93,14 @@ pub async fn list_ban_log(&self, tenant: Option<&str>) -> Result<Vec<BanLog>, Mq
         Ok(data.into_iter().map(|raw| raw.data).collect())
     }
 
+    pub async fn expire_ban_log(&self, ttl_sec: u64) -> Result<u64, MqttBrokerError> {
+        Ok(engine_expire_prefix_by_broker::<BanLog>(
+            &self.rocksdb_engine_handler,
+            &ban_log_prefix_key(),
+            ttl_sec,
+        )?)
+    }
+
     pub async fn save_slow_sub_log(&self, log: SlowSubscribeData) -> ResultCommonError {
         let key = slow_sub_log_key(&log.tenant, &log.client_id, &log.topic_name);
         engine_save_by_broker(&self.rocksdb_engine_handler, &key, log)
@@ -95,6 +120,14 @@ pub async fn list_slow_sub_log(
         )?;
         Ok(data.into_iter().map(|raw| raw.data).collect())
     }
+
+    pub async fn expire_slow_sub_log(&self, ttl_sec: u64) -> Result<u64, MqttBrokerError> {
+        Ok(engine_expire_prefix_by_broker::<SlowSubscribeData>(
+            &self.rocksdb_engine_handler,
+            &slow_sub_log_prefix_key(),
+            ttl_sec,
+        )?)
+    }
 }
 
 #[cfg(test)]
@@ -102,9 +135,8 @@ mod tests {
     use super::*;
     use rocksdb_engine::test::test_rocksdb_instance;
 
-    use crate::core::{
-        flapping_detect::BanLog, sub_slow::SlowSubscribeData, system_alarm::SystemAlarmEventMessage,
-    };
+    use crate::core::{flapping_detect::BanLog, sub_slow::SlowSubscribeData};
+    use metadata_struct::mqtt::system_alarm::SystemAlarmDetails;
 
     #[tokio::test]
     async fn test_system_event_save_and_list() {
@@ -117,12 +149,56 @@ async fn test_system_event_save_and_list() {
                 message: format!("message_{}", i),
                 create_time: 1000 + i,
                 activated: true,
+                ..Default::default()
             };
             storage.save_system_event(event).await.unwrap();
         }
 
         let list = storage.list_system_event().await.unwrap();
         assert_eq!(list.len(), 5);
+
+        // the deactivation is stored next to the activation it ends
+        storage
+            .save_system_event(SystemAlarmEventMessage {
+                name: "alarm_0".to_string(),
+                create_time: 1000,
+                deactivate_at: Some(2000),
+                activated: false,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(storage.list_system_event().await.unwrap().len(), 6);
+        assert_eq!(storage.list_active_system_alarm().await.unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_system_event_round_trip() {
+        let db = test_rocksdb_instance();
+        let storage = LocalStorage::new(db);
+
+        let event = SystemAlarmEventMessage {
+            name: "HighCpuUsage".to_string(),
+            message: "cpu above the high watermark".to_string(),
+            create_time: 1000,
+            deactivate_at: None,
+            activated: true,
+            details: Some(SystemAlarmDetails {
+                usage: 80.0,
+                high_watermark: 70.0,
+                low_watermark: 60.0,
+            }),
+        };
+        storage.save_system_event(event.clone()).await.unwrap();
+
+        assert_eq!(
+            storage.list_system_event().await.unwrap(),
+            vec![event.clone()]
+        );
+        assert_eq!(
+            storage.list_active_system_alarm().await.unwrap(),
+            vec![event]
+        );
     }
 
     #[tokio::test]
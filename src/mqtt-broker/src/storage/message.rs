@@ -36,9 +36,22 @@ pub async fn append_topic_message(
         topic_name: &str,
         records: Vec<AdapterWriteRecord>,
     ) -> Result<Vec<u64>, CommonError> {
-        let results = self
+        let (_, offsets) = self
+            .append_topic_message_with_shard(tenant, topic_name, records)
+            .await?;
+        Ok(offsets)
+    }
+
+    /// Like `append_topic_message`, also returning the shard written to.
+    pub async fn append_topic_message_with_shard(
+        &self,
+        tenant: &str,
+        topic_name: &str,
+        records: Vec<AdapterWriteRecord>,
+    ) -> Result<(String, Vec<u64>), CommonError> {
+        let (shard, results) = self
             .storage_driver_manager
-            .write(tenant, topic_name, &records, 1)
+            .write_with_shard(tenant, topic_name, &records, 1)
             .await?;
         let mut offsets = Vec::new();
         for row in results {
@@ -47,7 +60,7 @@ pub async fn append_topic_message(
             }
             offsets.push(row.offset);
         }
-        Ok(offsets)
+        Ok((shard, offsets))
     }
 
     pub async fn read_topic_message(
@@ -67,6 +80,39 @@ pub async fn read_topic_message(
             .await
     }
 
+    /// Every record of the topic carrying `tag`, reading each shard to its end.
+    pub async fn read_all_by_tag(
+        &self,
+        tenant: &str,
+        topic_name: &str,
+        tag: &str,
+    ) -> Result<Vec<StorageRecord>, CommonError> {
+        let read_config = AdapterReadConfig {
+            max_record_num: 100,
+            max_size: 10 * 1024 * 1024,
+        };
+
+        let mut offsets: HashMap<String, u64> = HashMap::new();
+        let mut results = Vec::new();
+        loop {
+            let records = self
+                .storage_driver_manager
+                .read_by_tag(tenant, topic_name, tag, &offsets, &read_config)
+                .await?;
+            if records.is_empty() {
+                break;
+            }
+
+            for record in records.iter() {
+                let next = record.metadata.offset + 1;
+                let offset = offsets.entry(record.metadata.shard.clone()).or_insert(0);
+                *offset = (*offset).max(next);
+            }
+            results.extend(records);
+        }
+        Ok(results)
+    }
+
     pub async fn get_group_offset(
         &self,
         tenant: &str,
@@ -0,0 +1,79 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use common_config::broker::broker_config;
+use grpc_clients::meta::mqtt::call::{
+    placement_create_message_rule, placement_delete_message_rule, placement_list_message_rule,
+};
+use grpc_clients::pool::ClientPool;
+use metadata_struct::mqtt::message_rule::MqttMessageRule;
+use protocol::meta::meta_service_mqtt::{
+    CreateMessageRuleRequest, DeleteMessageRuleRequest, ListMessageRuleRequest,
+};
+
+use crate::core::error::MqttBrokerError;
+use crate::core::tool::ResultMqttBrokerError;
+
+pub struct MessageRuleStorage {
+    client_pool: Arc<ClientPool>,
+}
+
+impl MessageRuleStorage {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        MessageRuleStorage { client_pool }
+    }
+
+    pub async fn list_message_rule(
+        &self,
+        tenant: Option<String>,
+    ) -> Result<Vec<MqttMessageRule>, MqttBrokerError> {
+        let config = broker_config();
+        let request = ListMessageRuleRequest {
+            tenant: tenant.unwrap_or_default(),
+            ..Default::default()
+        };
+        let reply = placement_list_message_rule(
+            &self.client_pool,
+            &config.get_meta_service_addr(),
+            request,
+        )
+        .await?;
+        let mut list = Vec::new();
+        for raw in reply.message_rules {
+            list.push(MqttMessageRule::decode(&raw)?);
+        }
+        Ok(list)
+    }
+
+    pub async fn create_message_rule(&self, rule: MqttMessageRule) -> ResultMqttBrokerError {
+        let config = broker_config();
+        let content = rule
+            .encode()
+            .map_err(|e| MqttBrokerError::CommonError(e.to_string()))?;
+        let request = CreateMessageRuleRequest { content };
+        placement_create_message_rule(&self.client_pool, &config.get_meta_service_addr(), request)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_message_rule(&self, tenant: String, name: String) -> ResultMqttBrokerError {
+        let config = broker_config();
+        let request = DeleteMessageRuleRequest { tenant, name };
+        placement_delete_message_rule(&self.client_pool, &config.get_meta_service_addr(), request)
+            .await?;
+        Ok(())
+    }
+}
@@ -14,10 +14,13 @@
 
 pub mod auto_subscribe;
 pub mod connector;
+pub mod inflight;
 pub mod last_will;
 pub mod local;
 pub mod message;
+pub mod message_rule;
 pub mod retain;
 pub mod schema;
 pub mod session;
 pub mod topic_rewrite;
+pub mod topic_stats;
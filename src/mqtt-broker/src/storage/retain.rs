@@ -15,8 +15,16 @@
 use crate::core::error::MqttBrokerError;
 use crate::core::tool::ResultMqttBrokerError;
 use broker_core::inner_topic::RETAIN_MESSAGE_TOPIC;
+use common_config::broker::broker_config;
+use grpc_clients::meta::mqtt::call::{
+    placement_delete_retain_index, placement_list_retain_index, placement_set_retain_index,
+};
+use grpc_clients::pool::ClientPool;
 use metadata_struct::adapter::adapter_record::AdapterWriteRecord;
-use metadata_struct::mqtt::retain_message::MQTTRetainMessage;
+use metadata_struct::mqtt::retain_message::{MQTTRetainIndex, MQTTRetainMessage};
+use protocol::meta::meta_service_mqtt::{
+    DeleteRetainIndexRequest, ListRetainIndexRequest, SetRetainIndexRequest,
+};
 // The inner topic "$retain-message" is a single broker-wide topic created under DEFAULT_TENANT.
 // To isolate retain messages across tenants and avoid key collisions between topics with the same
 // name in different tenants, the storage key is composed as "{tenant}/{topic_name}".
@@ -80,6 +88,95 @@ pub async fn get_retain_message(
         }
         Ok(None)
     }
+
+    /// Stored retained messages of `topic_names`, read in one call. Topics
+    /// without a retained message are left out.
+    pub async fn get_retain_messages(
+        &self,
+        tenant: &str,
+        topic_names: &[&str],
+    ) -> Result<Vec<MQTTRetainMessage>, MqttBrokerError> {
+        let keys: Vec<String> = topic_names
+            .iter()
+            .map(|topic_name| retain_key(tenant, topic_name))
+            .collect();
+        let key_refs: Vec<&str> = keys.iter().map(String::as_str).collect();
+        let mut records = self
+            .storage_driver_manager
+            .read_by_keys(DEFAULT_TENANT, RETAIN_MESSAGE_TOPIC, &key_refs)
+            .await?;
+
+        let mut messages = Vec::new();
+        for key in keys {
+            if let Some(record) = records.remove(&key).and_then(|r| r.into_iter().next()) {
+                messages.push(MQTTRetainMessage::decode(&record.data)?);
+            }
+        }
+        Ok(messages)
+    }
+}
+
+/// Index of retained messages kept in meta-service. It records which topics
+/// hold a retained message; the payloads stay in the storage adapter.
+pub struct RetainIndexStorage {
+    client_pool: Arc<ClientPool>,
+}
+
+impl RetainIndexStorage {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        RetainIndexStorage { client_pool }
+    }
+
+    pub async fn set_retain_index(&self, index: &MQTTRetainIndex) -> ResultMqttBrokerError {
+        let config = broker_config();
+        let request = SetRetainIndexRequest {
+            tenant: index.tenant.clone(),
+            topic_name: index.topic_name.clone(),
+            index: index.encode()?,
+        };
+        placement_set_retain_index(&self.client_pool, &config.get_meta_service_addr(), request)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_retain_index(
+        &self,
+        tenant: &str,
+        topic_name: &str,
+    ) -> ResultMqttBrokerError {
+        let config = broker_config();
+        let request = DeleteRetainIndexRequest {
+            tenant: tenant.to_owned(),
+            topic_name: topic_name.to_owned(),
+        };
+        placement_delete_retain_index(&self.client_pool, &config.get_meta_service_addr(), request)
+            .await?;
+        Ok(())
+    }
+
+    /// Unexpired index entries of the topics matched by `topic_filter`.
+    pub async fn list_retain_index(
+        &self,
+        tenant: &str,
+        topic_filter: &str,
+    ) -> Result<Vec<MQTTRetainIndex>, MqttBrokerError> {
+        let config = broker_config();
+        let request = ListRetainIndexRequest {
+            tenant: tenant.to_owned(),
+            topic_filter: topic_filter.to_owned(),
+        };
+        let reply = placement_list_retain_index(
+            &self.client_pool,
+            &config.get_meta_service_addr(),
+            request,
+        )
+        .await?;
+        let mut results = Vec::with_capacity(reply.indexes.len());
+        for raw in reply.indexes {
+            results.push(MQTTRetainIndex::decode(&raw)?);
+        }
+        Ok(results)
+    }
 }
 
 fn retain_key(tenant: &str, topic_name: &str) -> String {
@@ -16,12 +16,14 @@
 use common_config::broker::broker_config;
 use dashmap::DashMap;
 use grpc_clients::meta::mqtt::call::{
-    placement_create_session, placement_delete_session, placement_list_session,
+    allocate_last_will_epoch, claim_last_will, placement_create_session, placement_delete_session,
+    placement_list_session,
 };
 use grpc_clients::pool::ClientPool;
 use metadata_struct::mqtt::session::MqttSession;
 use protocol::meta::meta_service_mqtt::{
-    CreateSessionRaw, CreateSessionRequest, DeleteSessionRequest, ListSessionRequest,
+    AllocateLastWillEpochRequest, ClaimLastWillRequest, CreateSessionRaw, CreateSessionRequest,
+    DeleteSessionRequest, ListSessionRequest,
 };
 use std::sync::Arc;
 use tokio::sync::{mpsc, oneshot};
@@ -179,13 +181,52 @@ pub async fn delete_session(
         Ok(())
     }
 
+    /// Epoch of a will the client registers now, allocated by Meta Service so
+    /// it is above the epochs of the client's earlier wills.
+    pub async fn allocate_last_will_epoch(
+        &self,
+        tenant: String,
+        client_id: String,
+    ) -> Result<u64, CommonError> {
+        let config = broker_config();
+        let request = AllocateLastWillEpochRequest { tenant, client_id };
+        let reply =
+            allocate_last_will_epoch(&self.client_pool, &config.get_meta_service_addr(), request)
+                .await?;
+        Ok(reply.session_epoch)
+    }
+
+    /// Ask Meta Service whether this broker publishes the will of
+    /// `session_epoch`. Only one broker is granted each epoch.
+    pub async fn claim_last_will(
+        &self,
+        tenant: String,
+        client_id: String,
+        session_epoch: u64,
+    ) -> Result<bool, CommonError> {
+        let config = broker_config();
+        let request = ClaimLastWillRequest {
+            tenant,
+            client_id,
+            session_epoch,
+            broker_id: config.broker_id,
+        };
+        let reply =
+            claim_last_will(&self.client_pool, &config.get_meta_service_addr(), request).await?;
+        Ok(reply.claimed)
+    }
+
     pub async fn get_session(
         &self,
         tenant: String,
         client_id: String,
     ) -> Result<Option<MqttSession>, CommonError> {
         let config = broker_config();
-        let request = ListSessionRequest { tenant, client_id };
+        let request = ListSessionRequest {
+            tenant,
+            client_id,
+            ..Default::default()
+        };
 
         let mut stream =
             placement_list_session(&self.client_pool, &config.get_meta_service_addr(), request)
@@ -208,6 +249,7 @@ pub async fn list_session(
         let request = ListSessionRequest {
             tenant,
             client_id: client_id.unwrap_or_default(),
+            ..Default::default()
         };
 
         let mut stream =
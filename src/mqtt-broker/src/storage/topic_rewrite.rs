@@ -48,6 +48,7 @@ pub async fn topic_rewrite_rule_by_tenant(
         let config = broker_config();
         let request = ListTopicRewriteRuleRequest {
             tenant: tenant.to_string(),
+            ..Default::default()
         };
         let reply = placement_list_topic_rewrite_rule(
             &self.client_pool,
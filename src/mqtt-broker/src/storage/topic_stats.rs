@@ -0,0 +1,61 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::core::error::MqttBrokerError;
+use crate::core::tool::ResultMqttBrokerError;
+use common_config::broker::broker_config;
+use grpc_clients::meta::mqtt::call::{placement_describe_topic, placement_report_topic_stats};
+use grpc_clients::pool::ClientPool;
+use protocol::meta::meta_service_mqtt::{
+    DescribeTopicReply, DescribeTopicRequest, ReportTopicStatsRequest, TopicStatsRaw,
+};
+use std::sync::Arc;
+
+pub struct TopicStatsStorage {
+    client_pool: Arc<ClientPool>,
+}
+
+impl TopicStatsStorage {
+    pub fn new(client_pool: Arc<ClientPool>) -> Self {
+        TopicStatsStorage { client_pool }
+    }
+
+    pub async fn report_topic_stats(&self, stats: Vec<TopicStatsRaw>) -> ResultMqttBrokerError {
+        let config = broker_config();
+        let request = ReportTopicStatsRequest {
+            broker_id: config.broker_id,
+            stats,
+        };
+        placement_report_topic_stats(&self.client_pool, &config.get_meta_service_addr(), request)
+            .await?;
+        Ok(())
+    }
+
+    /// Statistics of a topic aggregated over all brokers.
+    pub async fn describe_topic(
+        &self,
+        tenant: &str,
+        topic_name: &str,
+    ) -> Result<DescribeTopicReply, MqttBrokerError> {
+        let config = broker_config();
+        let request = DescribeTopicRequest {
+            tenant: tenant.to_owned(),
+            topic_name: topic_name.to_owned(),
+        };
+        let reply =
+            placement_describe_topic(&self.client_pool, &config.get_meta_service_addr(), request)
+                .await?;
+        Ok(reply)
+    }
+}
@@ -13,23 +13,73 @@
 // limitations under the License.
 
 use crate::subscribe::common::Subscriber;
+use common_base::tools::now_second;
 use common_base::uuid::unique_id;
 use dashmap::DashMap;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::sync::atomic::AtomicU64;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::broadcast::Sender;
 
-#[derive(Clone, Serialize)]
+/// Counters of one push thread, updated by the thread while it runs.
+#[derive(Default)]
+pub struct PushThreadStats {
+    push_success_record_num: AtomicU64,
+    push_error_record_num: AtomicU64,
+    last_push_time: AtomicU64,
+    last_run_time: AtomicU64,
+}
+
+impl PushThreadStats {
+    pub fn record_success(&self) {
+        self.push_success_record_num.fetch_add(1, Ordering::Relaxed);
+        self.last_push_time.store(now_second(), Ordering::Relaxed);
+    }
+
+    pub fn record_error(&self) {
+        self.push_error_record_num.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_run(&self) {
+        self.last_run_time.store(now_second(), Ordering::Relaxed);
+    }
+}
+
+#[derive(Clone)]
 pub struct SubPushThreadData {
+    pub stats: Arc<PushThreadStats>,
+    pub create_time: u64,
+    pub sender: Sender<bool>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct SubPushThreadSnapshot {
     pub push_success_record_num: u64,
     pub push_error_record_num: u64,
     pub last_push_time: u64,
     pub last_run_time: u64,
     pub create_time: u64,
-    #[serde(skip_serializing, skip_deserializing)]
-    pub sender: Sender<bool>,
+}
+
+impl SubPushThreadData {
+    pub fn new(sender: Sender<bool>) -> Self {
+        SubPushThreadData {
+            stats: Arc::new(PushThreadStats::default()),
+            create_time: now_second(),
+            sender,
+        }
+    }
+
+    pub fn snapshot(&self) -> SubPushThreadSnapshot {
+        SubPushThreadSnapshot {
+            push_success_record_num: self.stats.push_success_record_num.load(Ordering::Relaxed),
+            push_error_record_num: self.stats.push_error_record_num.load(Ordering::Relaxed),
+            last_push_time: self.stats.last_push_time.load(Ordering::Relaxed),
+            last_run_time: self.stats.last_run_time.load(Ordering::Relaxed),
+            create_time: self.create_time,
+        }
+    }
 }
 
 #[derive(Clone, Default)]
@@ -273,6 +323,7 @@ fn create_sub(client_id: &str, sub_path: &str) -> Subscriber {
             preserve_retain: false,
             retain_forward_rule: RetainHandling::OnNewSubscribe,
             subscription_identifier: None,
+            payload_decode: false,
             create_time: 0,
         }
     }
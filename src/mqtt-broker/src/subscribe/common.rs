@@ -47,6 +47,8 @@ pub struct Subscriber {
     pub preserve_retain: bool,
     pub retain_forward_rule: RetainHandling,
     pub subscription_identifier: Option<usize>,
+    // Subscriber asked for transformed payloads to be decoded on push
+    pub payload_decode: bool,
     pub create_time: u64,
 }
 
@@ -92,6 +94,7 @@ pub fn client_unavailable_error(e: &MqttBrokerError) -> bool {
         e,
         MqttBrokerError::SessionNullSkipPushMessage(_)
             | MqttBrokerError::ConnectionNullSkipPushMessage(_)
+            | MqttBrokerError::InflightRedeliveryPending(_)
             | MqttBrokerError::NotObtainAvailableConnection(_, _)
             | MqttBrokerError::OperationTimeout(_, _)
     )
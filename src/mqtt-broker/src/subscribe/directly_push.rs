@@ -14,7 +14,10 @@
 
 use crate::core::cache::MQTTCacheManager;
 use crate::core::error::MqttBrokerError;
+use crate::core::offline_queue::EnqueueResult;
 use crate::core::sub_option::message_is_same_client;
+use crate::storage::inflight::InflightStorage;
+use crate::subscribe::buckets::PushThreadStats;
 use crate::subscribe::common::{
     client_unavailable_error, message_is_exceeds_max_message_size, message_is_expire,
     record_sub_send_metrics, stale_subscriber_error, Subscriber,
@@ -22,24 +25,39 @@
 use crate::subscribe::manager::SubscribeManager;
 use crate::subscribe::push::{adaptive_sleep, handle_stop_signal, push_data, BATCH_SIZE};
 use crate::subscribe::push_model::{get_push_model, PushModel};
+use common_base::tools::now_millis;
+use common_metrics::mqtt::publish::record_mqtt_messages_forward_delivered;
+use common_metrics::mqtt::session::{
+    record_mqtt_offline_queue_dropped, record_mqtt_offline_queue_enqueued,
+    record_mqtt_offline_queue_flushed,
+};
 use dashmap::DashMap;
 use metadata_struct::storage::adapter_read_config::AdapterReadConfig;
 use metadata_struct::storage::record::StorageRecord;
 use network_server::common::connection_manager::ConnectionManager;
+use protocol::mqtt::common::QoS;
 use rocksdb_engine::rocksdb::RocksDBEngine;
 use std::sync::Arc;
 use storage_adapter::{consumer::GroupConsumer, driver::StorageDriverManager};
 use tokio::{select, sync::broadcast::Sender};
 use tracing::{debug, error, warn};
 
+/// While forwarded records keep a subscriber busy, storage is still read at
+/// this interval for records that were never forwarded.
+const FORWARD_STORAGE_READ_INTERVAL_MS: u128 = 1000;
+
 pub struct DirectlyPushManager {
     subscribe_manager: Arc<SubscribeManager>,
     connection_manager: Arc<ConnectionManager>,
     cache_manager: Arc<MQTTCacheManager>,
     rocksdb_engine_handler: Arc<RocksDBEngine>,
     storage_driver_manager: Arc<StorageDriverManager>,
+    inflight_storage: InflightStorage,
     consumers: DashMap<String, Arc<GroupConsumer>>,
+    // (group_name, last storage read time in ms)
+    storage_read_time: DashMap<String, u128>,
     uuid: String,
+    thread_stats: Arc<PushThreadStats>,
 }
 
 impl DirectlyPushManager {
@@ -50,15 +68,19 @@ pub fn new(
         connection_manager: Arc<ConnectionManager>,
         rocksdb_engine_handler: Arc<RocksDBEngine>,
         uuid: String,
+        thread_stats: Arc<PushThreadStats>,
     ) -> Self {
         DirectlyPushManager {
             subscribe_manager,
+            inflight_storage: InflightStorage::new(storage_driver_manager.clone()),
             storage_driver_manager,
             cache_manager,
             rocksdb_engine_handler,
             connection_manager,
             consumers: DashMap::with_capacity(2),
+            storage_read_time: DashMap::with_capacity(2),
             uuid,
+            thread_stats,
         }
     }
 
@@ -74,7 +96,11 @@ pub async fn start(&self, stop_sx: &Sender<bool>) {
                 res = self.send_messages(stop_sx) => {
                     match res {
                         Ok(processed_count) => {
-                            adaptive_sleep(processed_count as u64).await;
+                            // Forwarded records end the idle wait early.
+                            select! {
+                                _ = adaptive_sleep(processed_count as u64) => {}
+                                _ = self.subscribe_manager.forward_buffer.notified() => {}
+                            }
                         }
                         Err(e) => {
                             error!("DirectlyPushManager[{}] send messages failed: {}", self.uuid, e);
@@ -87,6 +113,7 @@ pub async fn start(&self, stop_sx: &Sender<bool>) {
     }
 
     pub async fn send_messages(&self, stop_sx: &Sender<bool>) -> Result<usize, MqttBrokerError> {
+        self.thread_stats.record_run();
         let mut processed_count = 0;
         // (tenant, client_id, sub_path, group_name) of subscribers whose topic no longer exists.
         let mut stale_subs: Vec<(String, String, String, String)> = Vec::new();
@@ -129,6 +156,7 @@ pub async fn send_messages(&self, stop_sx: &Sender<bool>) -> Result<usize, MqttB
             self.subscribe_manager
                 .remove_by_sub(&tenant, &client_id, &sub_path);
             self.consumers.remove(&group_name);
+            self.storage_read_time.remove(&group_name);
         }
 
         Ok(processed_count)
@@ -139,7 +167,7 @@ async fn process_subscriber_messages(
         subscriber: &Subscriber,
         stop_sx: &Sender<bool>,
     ) -> Result<usize, MqttBrokerError> {
-        let mut processed_count = 0;
+        let mut processed_count = self.flush_offline_queue(subscriber, stop_sx).await?;
 
         let read_config = AdapterReadConfig {
             max_record_num: BATCH_SIZE,
@@ -162,18 +190,28 @@ async fn process_subscriber_messages(
             .expect("consumer just inserted")
             .clone();
 
-        let data_list = consumer
-            .next_messages(&subscriber.tenant, &subscriber.topic_name, &read_config)
-            .await?;
+        let data_list = match self.take_forwarded_messages(&consumer, subscriber) {
+            Some(records) => records,
+            None => {
+                self.storage_read_time
+                    .insert(subscriber.group_name.clone(), now_millis());
+                consumer
+                    .next_messages(&subscriber.tenant, &subscriber.topic_name, &read_config)
+                    .await?
+            }
+        };
 
         if data_list.is_empty() {
-            return Ok(0);
+            return Ok(processed_count);
         }
 
         let model = get_push_model(&subscriber.client_id, &subscriber.topic_name);
 
         for record in data_list {
             if is_discard_message(&self.cache_manager, &record, subscriber).await? {
+                self.subscribe_manager
+                    .subscription_stats
+                    .record_dropped(subscriber);
                 continue;
             }
 
@@ -184,17 +222,27 @@ async fn process_subscriber_messages(
                 subscriber,
                 &record,
                 stop_sx,
+                Some(&self.inflight_storage),
             )
             .await
             {
                 Ok(pushed) => {
                     if pushed {
                         processed_count += 1;
+                        self.thread_stats.record_success();
+                        self.subscribe_manager
+                            .subscription_stats
+                            .record_delivered(subscriber);
                     }
                     pushed
                 }
                 Err(e) => {
-                    if !client_unavailable_error(&e) {
+                    self.thread_stats.record_error();
+                    self.subscribe_manager
+                        .subscription_stats
+                        .record_error(subscriber);
+                    let client_unavailable = client_unavailable_error(&e);
+                    if !client_unavailable {
                         warn!(
                             "Directly push fail, offset [{}], error: {}",
                             record.metadata.offset, e
@@ -204,6 +252,12 @@ async fn process_subscriber_messages(
                         // Skip commit so this record is re-delivered on the next iteration.
                         return Ok(processed_count);
                     }
+                    // A persisted inflight message is redelivered on resumption.
+                    if client_unavailable
+                        && !matches!(e, MqttBrokerError::InflightRedeliveryPending(_))
+                    {
+                        self.save_offline_message(subscriber, &record);
+                    }
                     false
                 }
             };
@@ -221,6 +275,176 @@ async fn process_subscriber_messages(
         consumer.commit().await?;
         Ok(processed_count)
     }
+
+    /// Forwarded records that continue where the consumer stopped, staged on
+    /// the consumer as if they had been read from storage. `None` means storage
+    /// has to be read.
+    fn take_forwarded_messages(
+        &self,
+        consumer: &GroupConsumer,
+        subscriber: &Subscriber,
+    ) -> Option<Vec<StorageRecord>> {
+        let last_read = self
+            .storage_read_time
+            .get(&subscriber.group_name)
+            .map(|time| *time)?;
+        if now_millis().saturating_sub(last_read) >= FORWARD_STORAGE_READ_INTERVAL_MS {
+            return None;
+        }
+
+        let (tenant, topic_name) = (&subscriber.tenant, &subscriber.topic_name);
+        let records = self.subscribe_manager.forward_buffer.take(
+            tenant,
+            topic_name,
+            |shard| consumer.next_offset(tenant, topic_name, shard),
+            BATCH_SIZE as usize,
+        )?;
+        for record in records.iter() {
+            consumer.stage_shard_offset(
+                tenant,
+                topic_name,
+                &record.metadata.shard,
+                record.metadata.offset,
+            );
+        }
+        record_mqtt_messages_forward_delivered(records.len() as u64);
+        Some(records)
+    }
+
+    /// Queue a QoS 1/2 message for a persistent session that is offline, so it
+    /// is delivered on reconnect instead of being skipped.
+    fn save_offline_message(&self, subscriber: &Subscriber, record: &StorageRecord) {
+        let conf = self
+            .cache_manager
+            .node_cache
+            .get_cluster_config()
+            .mqtt_offline_message;
+        if !conf.enable || conf.session_queue_max_messages == 0 || subscriber.qos == QoS::AtMostOnce
+        {
+            return;
+        }
+
+        let offline = self
+            .cache_manager
+            .get_session_info(&subscriber.client_id)
+            .is_some_and(|session| session.connection_id.is_none());
+        if !offline {
+            return;
+        }
+
+        match self.subscribe_manager.offline_queues.enqueue(
+            &self.rocksdb_engine_handler,
+            &conf,
+            subscriber,
+            record,
+        ) {
+            Ok(EnqueueResult::Queued { dropped }) => {
+                record_mqtt_offline_queue_enqueued();
+                if dropped > 0 {
+                    record_mqtt_offline_queue_dropped("full", dropped);
+                }
+            }
+            Ok(EnqueueResult::Rejected) => {
+                record_mqtt_offline_queue_dropped("rejected", 1);
+                debug!(
+                    "Offline queue of client [{}] is full, message at offset [{}] dropped",
+                    subscriber.client_id, record.metadata.offset
+                );
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to queue offline message for client [{}], offset [{}], error: {}",
+                    subscriber.client_id, record.metadata.offset, e
+                );
+            }
+        }
+    }
+
+    /// Deliver the offline queue of the subscriber's session once it is connected
+    /// again. Stops at the first message the client cannot take; the rest stays
+    /// queued for the next round.
+    async fn flush_offline_queue(
+        &self,
+        subscriber: &Subscriber,
+        stop_sx: &Sender<bool>,
+    ) -> Result<usize, MqttBrokerError> {
+        let (tenant, client_id) = (&subscriber.tenant, &subscriber.client_id);
+        let queues = &self.subscribe_manager.offline_queues;
+        if self.cache_manager.get_connect_id(client_id).is_none()
+            || queues.len(&self.rocksdb_engine_handler, tenant, client_id)? == 0
+        {
+            return Ok(0);
+        }
+
+        // Another subscription of the same session is already delivering it.
+        let Some(_guard) = queues.try_start_flush(tenant, client_id) else {
+            return Ok(0);
+        };
+
+        let expire_ms = self
+            .cache_manager
+            .node_cache
+            .get_cluster_config()
+            .mqtt_offline_message
+            .expire_ms as u64;
+        let mut flushed = 0;
+        loop {
+            let messages =
+                queues.peek(&self.rocksdb_engine_handler, tenant, client_id, BATCH_SIZE)?;
+            if messages.is_empty() {
+                break;
+            }
+
+            for message in messages {
+                let expired = message_is_expire(&message.record)
+                    || (expire_ms > 0
+                        && now_millis() as u64 >= message.enqueue_time_ms + expire_ms);
+                if expired {
+                    record_mqtt_offline_queue_dropped("expired", 1);
+                } else {
+                    match push_data(
+                        &self.connection_manager,
+                        &self.cache_manager,
+                        &self.rocksdb_engine_handler,
+                        &message.subscriber,
+                        &message.record,
+                        stop_sx,
+                        Some(&self.inflight_storage),
+                    )
+                    .await
+                    {
+                        Ok(_) => {
+                            flushed += 1;
+                            record_mqtt_offline_queue_flushed();
+                        }
+                        Err(MqttBrokerError::InflightRedeliveryPending(_)) => {
+                            queues.remove_through(
+                                &self.rocksdb_engine_handler,
+                                tenant,
+                                client_id,
+                                message.seq,
+                            )?;
+                            return Ok(flushed);
+                        }
+                        Err(e) if client_unavailable_error(&e) => return Ok(flushed),
+                        Err(e) => {
+                            warn!(
+                                "Failed to deliver queued offline message to client [{}], dropping it: {}",
+                                client_id, e
+                            );
+                        }
+                    }
+                }
+                queues.remove_through(
+                    &self.rocksdb_engine_handler,
+                    tenant,
+                    client_id,
+                    message.seq,
+                )?;
+            }
+        }
+        Ok(flushed)
+    }
 }
 
 pub fn directly_group_name(client_id: &str, path: &str, topic_name: &str) -> String {
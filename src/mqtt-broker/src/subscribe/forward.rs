@@ -0,0 +1,441 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Broker-to-broker forwarding of published messages.
+//!
+//! Without forwarding, a subscriber's broker only sees a new message when its
+//! push loop next polls storage. With `mqtt_runtime.cluster_forward_enable`,
+//! the broker that stored a QoS 0/1 message also sends the stored record over
+//! gRPC to every broker hosting a live session with a matching subscription
+//! (or keeps it in memory when that broker is itself). The receiving broker
+//! buffers the record and wakes its push loops, which push it without reading
+//! storage if it continues exactly where the subscriber's consumer stopped.
+//!
+//! Storage stays the source of truth: a record that is not forwarded, arrives
+//! out of order or is evicted from the buffer is read from storage as before,
+//! and offline sessions are served by the storage path and offline queue.
+//! Offsets decide what has been delivered, so a forwarded record is never
+//! pushed twice.
+
+use crate::core::cache::MQTTCacheManager;
+use crate::core::error::MqttBrokerError;
+use crate::core::offline_message::is_exist_subscribe;
+use crate::core::sub_exclusive::decode_exclusive_sub_path_to_topic_name;
+use crate::core::sub_share::is_mqtt_share_subscribe;
+use crate::subscribe::common::is_match_sub_and_topic;
+use crate::subscribe::manager::SubscribeManager;
+use common_base::tools::now_second;
+use common_config::broker::broker_config;
+use common_metrics::mqtt::publish::{
+    record_mqtt_messages_forward_failed, record_mqtt_messages_forwarded,
+};
+use dashmap::DashMap;
+use grpc_clients::broker::common::call::broker_forward_mqtt_message;
+use grpc_clients::pool::ClientPool;
+use metadata_struct::storage::record::StorageRecord;
+use protocol::broker::broker::{ForwardMqttMessageReply, ForwardMqttMessageRequest};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::futures::Notified;
+use tokio::sync::Notify;
+use tracing::{debug, warn};
+
+/// Forwarded records kept per shard; older ones are evicted first.
+const FORWARD_BUFFER_MAX_RECORDS: usize = 1000;
+/// Forwarded records older than this are evicted, their subscribers have
+/// fallen back to storage reads by then.
+const FORWARD_BUFFER_TTL_SEC: u64 = 30;
+
+/// Brokers hosting live subscribers of a topic.
+///
+/// Built from the cluster-wide subscription list and session cache, both kept
+/// in sync by meta-service notifications, and cached per topic until a
+/// subscription or session changes.
+#[derive(Default)]
+pub struct ForwardRouteTable {
+    // (tenant, (topic_name, (version, broker_ids)))
+    routes: DashMap<String, DashMap<String, (u64, Vec<u64>)>>,
+    version: AtomicU64,
+}
+
+impl ForwardRouteTable {
+    pub fn invalidate(&self) {
+        self.version.fetch_add(1, Ordering::SeqCst);
+        self.routes.clear();
+    }
+
+    pub fn get_or_build(
+        &self,
+        tenant: &str,
+        topic_name: &str,
+        build: impl FnOnce() -> Vec<u64>,
+    ) -> Vec<u64> {
+        let version = self.version.load(Ordering::SeqCst);
+        if let Some(tenant_routes) = self.routes.get(tenant) {
+            if let Some(route) = tenant_routes.get(topic_name) {
+                if route.0 == version {
+                    return route.1.clone();
+                }
+            }
+        }
+
+        // An entry built across an invalidation keeps the old version and is
+        // ignored by the next lookup.
+        let brokers = build();
+        self.routes
+            .entry(tenant.to_string())
+            .or_default()
+            .insert(topic_name.to_string(), (version, brokers.clone()));
+        brokers
+    }
+}
+
+/// Records forwarded to this broker, waiting for the push loops.
+#[derive(Default)]
+pub struct ForwardBuffer {
+    // ((tenant, topic_name), (shard, (offset, record)))
+    records: DashMap<(String, String), HashMap<String, BTreeMap<u64, StorageRecord>>>,
+    notify: Notify,
+}
+
+impl ForwardBuffer {
+    pub fn push(&self, tenant: &str, topic_name: &str, records: Vec<StorageRecord>) {
+        if records.is_empty() {
+            return;
+        }
+
+        let now = now_second();
+        {
+            let mut shards = self
+                .records
+                .entry((tenant.to_string(), topic_name.to_string()))
+                .or_default();
+            for record in records {
+                shards
+                    .entry(record.metadata.shard.clone())
+                    .or_default()
+                    .insert(record.metadata.offset, record);
+            }
+            for shard in shards.values_mut() {
+                shard.retain(|_, record| {
+                    now.saturating_sub(record.metadata.create_t) <= FORWARD_BUFFER_TTL_SEC
+                });
+                while shard.len() > FORWARD_BUFFER_MAX_RECORDS {
+                    shard.pop_first();
+                }
+            }
+            shards.retain(|_, shard| !shard.is_empty());
+        }
+        self.notify.notify_waiters();
+    }
+
+    /// Buffered records that continue from `next_offset` of every shard, up to
+    /// `max` records. `None` if nothing continues, or if a shard has a gap
+    /// before its buffered records or no known position, in which case the
+    /// caller has to read storage.
+    pub fn take(
+        &self,
+        tenant: &str,
+        topic_name: &str,
+        next_offset: impl Fn(&str) -> Option<u64>,
+        max: usize,
+    ) -> Option<Vec<StorageRecord>> {
+        let shards = self
+            .records
+            .get(&(tenant.to_string(), topic_name.to_string()))?;
+
+        let mut results = Vec::new();
+        for (shard, records) in shards.iter() {
+            let next = next_offset(shard)?;
+            let mut expected = next;
+            for (offset, record) in records.range(next..) {
+                if *offset != expected {
+                    if expected == next {
+                        return None;
+                    }
+                    break;
+                }
+                if results.len() >= max {
+                    break;
+                }
+                results.push(record.clone());
+                expected += 1;
+            }
+        }
+
+        if results.is_empty() {
+            None
+        } else {
+            Some(results)
+        }
+    }
+
+    pub fn remove_topic(&self, tenant: &str, topic_name: &str) {
+        self.records
+            .remove(&(tenant.to_string(), topic_name.to_string()));
+    }
+
+    /// Completes when records are pushed into the buffer.
+    pub fn notified(&self) -> Notified<'_> {
+        self.notify.notified()
+    }
+}
+
+pub fn cluster_forward_enabled(cache_manager: &Arc<MQTTCacheManager>) -> bool {
+    cache_manager
+        .node_cache
+        .get_cluster_config()
+        .mqtt_runtime
+        .cluster_forward_enable
+}
+
+/// Forward records just stored on a topic to the brokers hosting live
+/// subscribers of it. Returns immediately; remote brokers are called in the
+/// background, and a failed call only costs the fast path.
+pub fn forward_message(
+    cache_manager: &Arc<MQTTCacheManager>,
+    client_pool: &Arc<ClientPool>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    tenant: &str,
+    topic_name: &str,
+    records: Vec<StorageRecord>,
+) {
+    if records.is_empty() || !cluster_forward_enabled(cache_manager) {
+        return;
+    }
+
+    let brokers = subscribe_manager
+        .forward_routes
+        .get_or_build(tenant, topic_name, || {
+            live_subscriber_brokers(cache_manager, subscribe_manager, tenant, topic_name)
+        });
+    if brokers.is_empty() {
+        return;
+    }
+
+    let local_broker_id = broker_config().broker_id;
+    let mut encoded = Vec::with_capacity(records.len());
+    for record in records.iter() {
+        match record.encode() {
+            Ok(data) => encoded.push(data),
+            Err(e) => {
+                warn!(
+                    "Failed to encode forwarded record of topic {}: {}",
+                    topic_name, e
+                );
+                return;
+            }
+        }
+    }
+
+    for broker_id in brokers {
+        if broker_id == local_broker_id {
+            subscribe_manager
+                .forward_buffer
+                .push(tenant, topic_name, records.clone());
+            continue;
+        }
+
+        let Some(addr) = cache_manager
+            .node_cache
+            .node_lists
+            .get(&broker_id)
+            .map(|node| node.grpc_addr.clone())
+        else {
+            debug!("Forward target broker {} not found", broker_id);
+            record_mqtt_messages_forward_failed(encoded.len() as u64);
+            continue;
+        };
+
+        let request = ForwardMqttMessageRequest {
+            tenant: tenant.to_string(),
+            topic_name: topic_name.to_string(),
+            records: encoded.clone(),
+        };
+        let client_pool = client_pool.clone();
+        tokio::spawn(async move {
+            let count = request.records.len() as u64;
+            match broker_forward_mqtt_message(&client_pool, &[addr.as_str()], request).await {
+                Ok(_) => record_mqtt_messages_forwarded(count),
+                Err(e) => {
+                    record_mqtt_messages_forward_failed(count);
+                    debug!("Failed to forward messages to broker {}: {}", addr, e);
+                }
+            }
+        });
+    }
+}
+
+/// Handle records forwarded by another broker.
+pub fn receive_forward_message_by_req(
+    subscribe_manager: &Arc<SubscribeManager>,
+    req: &ForwardMqttMessageRequest,
+) -> Result<ForwardMqttMessageReply, MqttBrokerError> {
+    if !is_exist_subscribe(subscribe_manager, &req.tenant, &req.topic_name) {
+        return Ok(ForwardMqttMessageReply {});
+    }
+
+    let mut records = Vec::with_capacity(req.records.len());
+    for data in req.records.iter() {
+        records.push(StorageRecord::decode(data)?);
+    }
+    subscribe_manager
+        .forward_buffer
+        .push(&req.tenant, &req.topic_name, records);
+    Ok(ForwardMqttMessageReply {})
+}
+
+fn live_subscriber_brokers(
+    cache_manager: &Arc<MQTTCacheManager>,
+    subscribe_manager: &Arc<SubscribeManager>,
+    tenant: &str,
+    topic_name: &str,
+) -> Vec<u64> {
+    let subscribes = if cache_manager.has_rewrite_name(tenant) {
+        let Some(subscribes) = subscribe_manager.subscribe_list.get(tenant) else {
+            return Vec::new();
+        };
+        subscribes.iter().map(|row| row.value().clone()).collect()
+    } else {
+        subscribe_manager.candidate_subscribes(tenant, topic_name)
+    };
+
+    let mut brokers = Vec::new();
+    for subscribe in subscribes.iter() {
+        // Shared subscriptions are delivered by the group leader.
+        if is_mqtt_share_subscribe(&subscribe.filter.path) {
+            continue;
+        }
+
+        let Some(broker_id) = cache_manager
+            .get_session_info_by_tenant(tenant, &subscribe.client_id)
+            .filter(|session| session.connection_id.is_some())
+            .and_then(|session| session.broker_id)
+        else {
+            continue;
+        };
+        if brokers.contains(&broker_id) {
+            continue;
+        }
+
+        let rewrite_sub_path = cache_manager.get_new_rewrite_name(tenant, &subscribe.filter.path);
+        if subscribe_match_topic(
+            &subscribe.filter.path,
+            rewrite_sub_path.as_deref(),
+            topic_name,
+        ) {
+            brokers.push(broker_id);
+        }
+    }
+    brokers
+}
+
+fn subscribe_match_topic(sub_path: &str, rewrite_sub_path: Option<&str>, topic_name: &str) -> bool {
+    let path = rewrite_sub_path.unwrap_or(decode_exclusive_sub_path_to_topic_name(sub_path));
+    is_match_sub_and_topic(path, topic_name).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use metadata_struct::storage::record::StorageRecordMetadata;
+
+    fn record(shard: &str, offset: u64) -> StorageRecord {
+        StorageRecord {
+            metadata: StorageRecordMetadata::build(offset, shard.to_string(), 0),
+            protocol_data: None,
+            data: Bytes::from(format!("{shard}-{offset}")),
+        }
+    }
+
+    fn offsets(records: &[StorageRecord]) -> Vec<(String, u64)> {
+        let mut offsets: Vec<_> = records
+            .iter()
+            .map(|r| (r.metadata.shard.clone(), r.metadata.offset))
+            .collect();
+        offsets.sort();
+        offsets
+    }
+
+    #[test]
+    fn test_forward_buffer_take() {
+        let buffer = ForwardBuffer::default();
+        buffer.push("t1", "a/b", vec![record("s0", 5), record("s0", 6)]);
+        buffer.push("t1", "a/b", vec![record("s0", 8), record("s1", 2)]);
+
+        // Continues both shards, stopping at the gap after offset 6.
+        let next = |shard: &str| match shard {
+            "s0" => Some(5),
+            "s1" => Some(2),
+            _ => None,
+        };
+        let records = buffer.take("t1", "a/b", next, 100).unwrap();
+        assert_eq!(
+            offsets(&records),
+            vec![
+                ("s0".to_string(), 5),
+                ("s0".to_string(), 6),
+                ("s1".to_string(), 2)
+            ]
+        );
+
+        // Gap before the buffered records, or no known position: read storage.
+        assert!(buffer.take("t1", "a/b", |_| Some(4), 100).is_none());
+        assert!(buffer.take("t1", "a/b", |_| None, 100).is_none());
+        // Everything already consumed.
+        assert!(buffer.take("t1", "a/b", |_| Some(9), 100).is_none());
+
+        let records = buffer.take("t1", "a/b", next, 1).unwrap();
+        assert_eq!(records.len(), 1);
+
+        buffer.remove_topic("t1", "a/b");
+        assert!(buffer.take("t1", "a/b", next, 100).is_none());
+    }
+
+    #[test]
+    fn test_forward_buffer_eviction() {
+        let buffer = ForwardBuffer::default();
+        let records = (0..FORWARD_BUFFER_MAX_RECORDS as u64 + 10)
+            .map(|offset| record("s0", offset))
+            .collect();
+        buffer.push("t1", "a/b", records);
+        assert!(buffer.take("t1", "a/b", |_| Some(0), usize::MAX).is_none());
+        let records = buffer.take("t1", "a/b", |_| Some(10), usize::MAX).unwrap();
+        assert_eq!(records.len(), FORWARD_BUFFER_MAX_RECORDS);
+
+        let mut expired = record("s1", 0);
+        expired.metadata.create_t = now_second() - FORWARD_BUFFER_TTL_SEC - 1;
+        buffer.push("t1", "c", vec![expired]);
+        assert!(buffer.take("t1", "c", |_| Some(0), 100).is_none());
+    }
+
+    #[test]
+    fn test_forward_route_table() {
+        let table = ForwardRouteTable::default();
+        assert_eq!(table.get_or_build("t1", "a/b", || vec![1, 2]), vec![1, 2]);
+        assert_eq!(table.get_or_build("t1", "a/b", || vec![3]), vec![1, 2]);
+        table.invalidate();
+        assert_eq!(table.get_or_build("t1", "a/b", || vec![3]), vec![3]);
+    }
+
+    #[test]
+    fn test_subscribe_match_topic() {
+        assert!(subscribe_match_topic("a/+", None, "a/b"));
+        assert!(subscribe_match_topic("$exclusive/a/b", None, "a/b"));
+        assert!(!subscribe_match_topic("a/c", None, "a/b"));
+        assert!(subscribe_match_topic("x/y", Some("a/#"), "a/b"));
+    }
+}
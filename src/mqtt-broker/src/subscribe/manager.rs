@@ -13,8 +13,16 @@
 // limitations under the License.
 
 use crate::{
-    core::sub_exclusive::is_exclusive_sub,
-    subscribe::{buckets::BucketsManager, common::Subscriber, parse::ParseSubscribeData},
+    core::{offline_queue::OfflineQueues, sub_exclusive::is_exclusive_sub},
+    subscribe::{
+        buckets::BucketsManager,
+        common::Subscriber,
+        forward::{ForwardBuffer, ForwardRouteTable},
+        parse::ParseSubscribeData,
+        route::SubscribeRouteState,
+        stats::SubscriptionStatsManager,
+        topic_trie::SubscribeTopicTrie,
+    },
 };
 use common_base::tools::now_second;
 use dashmap::DashMap;
@@ -40,6 +48,9 @@ pub struct SubscribeManager {
     // (tenant, (client_id#path, MqttSubscribe))
     pub subscribe_list: DashMap<String, DashMap<String, MqttSubscribe>>,
 
+    // Filters of subscribe_list indexed by topic level
+    pub topic_trie: Arc<SubscribeTopicTrie>,
+
     // directly sub
     pub directly_push: BucketsManager,
 
@@ -60,6 +71,21 @@ pub struct SubscribeManager {
     // (tenant, (client_id, last_not_push_time))
     pub not_push_client: DashMap<String, DashMap<String, u64>>,
 
+    // Queued messages of offline persistent sessions
+    pub offline_queues: OfflineQueues,
+
+    // Brokers hosting live subscribers, per topic
+    pub forward_routes: Arc<ForwardRouteTable>,
+
+    // Records forwarded to this broker
+    pub forward_buffer: Arc<ForwardBuffer>,
+
+    // Topic filters of local subscriptions registered in meta-service
+    pub subscribe_route: Arc<SubscribeRouteState>,
+
+    // Push statistics per (tenant, client_id, path, topic)
+    pub subscription_stats: Arc<SubscriptionStatsManager>,
+
     pub update_cache_sender: Arc<RwLock<Option<Sender<ParseSubscribeData>>>>,
 }
 
@@ -67,11 +93,17 @@ impl SubscribeManager {
     pub fn new() -> Self {
         SubscribeManager {
             subscribe_list: DashMap::with_capacity(128),
+            topic_trie: Arc::new(SubscribeTopicTrie::default()),
             topic_subscribes: DashMap::with_capacity(64),
             not_push_client: DashMap::with_capacity(32),
             directly_push: BucketsManager::new(None, 10000),
             share_push: DashMap::with_capacity(8),
             share_group_topics: DashMap::with_capacity(8),
+            offline_queues: OfflineQueues::default(),
+            forward_routes: Arc::new(ForwardRouteTable::default()),
+            forward_buffer: Arc::new(ForwardBuffer::default()),
+            subscribe_route: Arc::new(SubscribeRouteState::default()),
+            subscription_stats: Arc::new(SubscriptionStatsManager::default()),
             update_cache_sender: Arc::new(RwLock::new(None)),
         }
     }
@@ -83,6 +115,10 @@ pub fn add_subscribe(&self, subscribe: &MqttSubscribe) {
             .entry(subscribe.tenant.clone())
             .or_default()
             .insert(key, subscribe.clone());
+        self.topic_trie
+            .insert(&subscribe.tenant, &subscribe.client_id, &subscribe.path);
+        self.forward_routes.invalidate();
+        self.subscribe_route.mark_dirty();
     }
 
     pub fn get_subscribe(
@@ -97,10 +133,46 @@ pub fn get_subscribe(
         })
     }
 
+    /// Subscriptions of `tenant` whose filter may match `topic_name`, found
+    /// through the topic trie. Filters are matched as subscribed, so tenants
+    /// with topic rewrite rules still need to check every subscription.
+    pub fn candidate_subscribes(&self, tenant: &str, topic_name: &str) -> Vec<MqttSubscribe> {
+        let Some(tenant_map) = self.subscribe_list.get(tenant) else {
+            return Vec::new();
+        };
+        self.topic_trie
+            .matches(tenant, topic_name)
+            .into_iter()
+            .filter_map(|(client_id, path)| {
+                tenant_map
+                    .get(&self.subscribe_key(&client_id, &path))
+                    .map(|v| v.clone())
+            })
+            .collect()
+    }
+
     pub fn subscribe_count(&self) -> usize {
         self.subscribe_list.iter().map(|e| e.value().len()).sum()
     }
 
+    pub fn subscribe_count_by_tenant(&self, tenant: &str) -> usize {
+        self.subscribe_list
+            .get(tenant)
+            .map(|m| m.len())
+            .unwrap_or(0)
+    }
+
+    pub fn subscribe_count_by_client(&self, tenant: &str, client_id: &str) -> usize {
+        self.subscribe_list
+            .get(tenant)
+            .map(|m| {
+                m.iter()
+                    .filter(|e| e.value().client_id == client_id)
+                    .count()
+            })
+            .unwrap_or(0)
+    }
+
     // directly && share
     pub fn add_directly_sub(&self, subscriber: &Subscriber) {
         self.add_topic_subscribe(
@@ -144,8 +216,16 @@ pub fn add_share_sub(&self, subscriber: &Subscriber) {
 
     // remove
     pub fn remove_by_client_id(&self, tenant: &str, client_id: &str) {
+        self.forward_routes.invalidate();
+        self.subscribe_route.mark_dirty();
         if let Some(tenant_map) = self.subscribe_list.get(tenant) {
-            tenant_map.retain(|_, subscribe| subscribe.client_id != *client_id);
+            tenant_map.retain(|_, subscribe| {
+                if subscribe.client_id != *client_id {
+                    return true;
+                }
+                self.topic_trie.remove(tenant, client_id, &subscribe.path);
+                false
+            });
         }
         self.subscribe_list.retain(|_, m| !m.is_empty());
 
@@ -161,6 +241,8 @@ pub fn remove_by_client_id(&self, tenant: &str, client_id: &str) {
             tenant_map.remove(client_id);
         }
         self.directly_push.remove_by_client_id(client_id);
+        self.subscription_stats
+            .remove_by_client_id(tenant, client_id);
 
         if let Some(tenant_share) = self.share_push.get(tenant) {
             for row in tenant_share.iter() {
@@ -170,10 +252,13 @@ pub fn remove_by_client_id(&self, tenant: &str, client_id: &str) {
     }
 
     pub fn remove_by_sub(&self, tenant: &str, client_id: &str, sub_path: &str) {
+        self.forward_routes.invalidate();
+        self.subscribe_route.mark_dirty();
         let key = self.subscribe_key(client_id, sub_path);
         if let Some(tenant_map) = self.subscribe_list.get(tenant) {
             tenant_map.remove(&key);
         }
+        self.topic_trie.remove(tenant, client_id, sub_path);
 
         // Clean up topic_subscribes
         if let Some(tenant_topics) = self.topic_subscribes.get(tenant) {
@@ -184,6 +269,8 @@ pub fn remove_by_sub(&self, tenant: &str, client_id: &str, sub_path: &str) {
         }
 
         self.directly_push.remove_by_sub(client_id, sub_path);
+        self.subscription_stats
+            .remove_by_sub(tenant, client_id, sub_path);
 
         if let Some(tenant_share) = self.share_push.get(tenant) {
             for row in tenant_share.iter() {
@@ -193,6 +280,9 @@ pub fn remove_by_sub(&self, tenant: &str, client_id: &str, sub_path: &str) {
     }
 
     pub fn remove_by_topic(&self, tenant: &str, topic_name: &str) {
+        self.forward_routes.invalidate();
+        self.subscribe_route.mark_dirty();
+        self.forward_buffer.remove_topic(tenant, topic_name);
         if let Some(tenant_topics) = self.topic_subscribes.get(tenant) {
             tenant_topics.remove(topic_name);
         }
@@ -201,11 +291,18 @@ pub fn remove_by_topic(&self, tenant: &str, topic_name: &str) {
         // subscribe_list reflects the deletion. Wildcard subscriptions are left intact
         // since they still match other topics.
         if let Some(tenant_map) = self.subscribe_list.get(tenant) {
-            tenant_map.retain(|_, sub| sub.path != topic_name);
+            tenant_map.retain(|_, sub| {
+                if sub.path != topic_name {
+                    return true;
+                }
+                self.topic_trie.remove(tenant, &sub.client_id, &sub.path);
+                false
+            });
         }
         self.subscribe_list.retain(|_, m| !m.is_empty());
 
         self.directly_push.remove_by_topic(topic_name);
+        self.subscription_stats.remove_by_topic(tenant, topic_name);
 
         // Remove all share_push entries whose key ends with "/topic_name".
         if let Some(tenant_share) = self.share_push.get(tenant) {
@@ -355,6 +452,42 @@ fn test_subscribe_key_no_conflict() {
         );
     }
 
+    #[test]
+    fn test_candidate_subscribes() {
+        let mgr = SubscribeManager::new();
+        mgr.add_subscribe(&create_subscribe("c1", "a/+"));
+        mgr.add_subscribe(&create_subscribe("c2", "$share/g1/a/#"));
+        mgr.add_subscribe(&create_subscribe("c3", "b/c"));
+
+        let mut clients: Vec<String> = mgr
+            .candidate_subscribes(DEFAULT_TENANT, "a/b")
+            .into_iter()
+            .map(|sub| sub.client_id)
+            .collect();
+        clients.sort();
+        assert_eq!(clients, vec!["c1", "c2"]);
+
+        mgr.remove_by_client_id(DEFAULT_TENANT, "c1");
+        mgr.remove_by_sub(DEFAULT_TENANT, "c2", "$share/g1/a/#");
+        assert!(mgr.candidate_subscribes(DEFAULT_TENANT, "a/b").is_empty());
+
+        mgr.remove_by_topic(DEFAULT_TENANT, "b/c");
+        assert!(mgr.candidate_subscribes(DEFAULT_TENANT, "b/c").is_empty());
+    }
+
+    #[test]
+    fn test_subscribe_count_by_client() {
+        let mgr = SubscribeManager::new();
+        mgr.add_subscribe(&create_subscribe("c1", "a/b"));
+        mgr.add_subscribe(&create_subscribe("c1", "a/c"));
+        mgr.add_subscribe(&create_subscribe("c2", "a/b"));
+
+        assert_eq!(mgr.subscribe_count_by_client(DEFAULT_TENANT, "c1"), 2);
+        assert_eq!(mgr.subscribe_count_by_client(DEFAULT_TENANT, "c2"), 1);
+        assert_eq!(mgr.subscribe_count_by_client(DEFAULT_TENANT, "c3"), 0);
+        assert_eq!(mgr.subscribe_count_by_client("other", "c1"), 0);
+    }
+
     #[test]
     fn test_add_and_get_subscribe() {
         let mgr = SubscribeManager::new();
@@ -19,15 +19,14 @@
         share_push::SharePushManager,
     },
 };
-use common_base::{
-    error::ResultCommonError,
-    tools::{loop_select_ticket, now_second},
-};
+use common_base::{error::ResultCommonError, tools::loop_select_ticket};
 use common_config::broker::broker_config;
 use dashmap::DashMap;
+use grpc_clients::meta::common::lock::DistributedLock;
 use network_server::common::connection_manager::ConnectionManager;
 use rocksdb_engine::rocksdb::RocksDBEngine;
 use std::sync::Arc;
+use std::time::Duration;
 use storage_adapter::driver::StorageDriverManager;
 use tokio::sync::broadcast;
 use tracing::{debug, info, warn};
@@ -35,11 +34,20 @@
 pub mod buckets;
 pub mod common;
 pub mod directly_push;
+pub mod forward;
 pub mod manager;
 pub mod parse;
 pub mod push;
 pub mod push_model;
+pub mod route;
 pub mod share_push;
+pub mod stats;
+pub mod topic_trie;
+
+// Lease of the lock the leader of a share group holds while it pushes. A
+// group moved to another broker is pushed there at the latest once this
+// has passed.
+const SHARE_GROUP_LOCK_LEASE_MS: u64 = 10_000;
 
 #[derive(Clone)]
 pub struct PushManager {
@@ -53,6 +61,8 @@ pub struct PushManager {
     pub directly_buckets_push_thread: DashMap<String, SubPushThreadData>,
     //(bucket_id,SubPushThreadData)
     pub share_buckets_push_thread: DashMap<String, SubPushThreadData>,
+    //(tenant#group_name,DistributedLock)
+    share_group_locks: DashMap<String, Arc<DistributedLock>>,
 }
 
 impl PushManager {
@@ -71,6 +81,7 @@ pub fn new(
             subscribe_manager,
             directly_buckets_push_thread: DashMap::new(),
             share_buckets_push_thread: DashMap::new(),
+            share_group_locks: DashMap::new(),
         }
     }
 
@@ -82,8 +93,14 @@ pub async fn start(&self, stop_sx: &broadcast::Sender<bool>) {
 
             // share
             self.cleanup_empty_share_groups();
-            self.start_share_push_thread();
+            self.start_share_push_thread().await;
+            self.refresh_share_group_locks().await;
             self.stop_share_push_thread();
+
+            // offline queues of removed sessions
+            self.subscribe_manager
+                .offline_queues
+                .purge_cleared(&self.rocksdb_engine_handler)?;
             Ok(())
         };
         let ac_fn = async || -> ResultCommonError {
@@ -138,14 +155,7 @@ pub fn start_directly_push_thread(&self) {
                 info!("Starting push thread for bucket: {}", bucket_id);
 
                 let (sub_thread_stop_sx, _) = broadcast::channel(1);
-                let thread_data = SubPushThreadData {
-                    push_error_record_num: 0,
-                    push_success_record_num: 0,
-                    last_push_time: 0,
-                    last_run_time: 0,
-                    create_time: now_second(),
-                    sender: sub_thread_stop_sx.clone(),
-                };
+                let thread_data = SubPushThreadData::new(sub_thread_stop_sx.clone());
 
                 let push_manager = DirectlyPushManager::new(
                     self.subscribe_manager.clone(),
@@ -154,6 +164,7 @@ pub fn start_directly_push_thread(&self) {
                     self.connection_manager.clone(),
                     self.rocksdb_engine_handler.clone(),
                     bucket_id.clone(),
+                    thread_data.stats.clone(),
                 );
 
                 let stop_sx = sub_thread_stop_sx.clone();
@@ -267,8 +278,9 @@ fn cleanup_empty_share_groups(&self) {
         }
     }
 
-    pub fn start_share_push_thread(&self) {
+    pub async fn start_share_push_thread(&self) {
         let conf = broker_config();
+        let mut to_start = Vec::new();
         for tenant_entry in self.subscribe_manager.share_push.iter() {
             let tenant = tenant_entry.key().clone();
             for row in tenant_entry.value().iter() {
@@ -289,13 +301,11 @@ pub fn start_share_push_thread(&self) {
                 let Some(sample) = sample else {
                     continue;
                 };
-                let group_name = &sample.group_name;
-                let topic_name = &sample.topic_name;
 
                 let is_leader = if let Some(group) = self
                     .cache_manager
                     .node_cache
-                    .get_share_group(&tenant, group_name)
+                    .get_share_group(&tenant, &sample.group_name)
                 {
                     group.leader_broker == conf.broker_id
                 } else {
@@ -303,78 +313,143 @@ pub fn start_share_push_thread(&self) {
                 };
 
                 if is_leader && !self.share_buckets_push_thread.contains_key(&thread_key) {
-                    info!(
-                        "Starting share push thread for {}/{}/{}",
-                        tenant, group_name, topic_name
-                    );
+                    to_start.push((thread_key, tenant.clone(), sample));
+                }
+            }
+        }
 
-                    let (sub_thread_stop_sx, _) = broadcast::channel(1);
-                    let thread_data = SubPushThreadData {
-                        push_error_record_num: 0,
-                        push_success_record_num: 0,
-                        last_push_time: 0,
-                        last_run_time: 0,
-                        create_time: now_second(),
-                        sender: sub_thread_stop_sx.clone(),
-                    };
-
-                    let push_manager = SharePushManager::new(
-                        self.subscribe_manager.clone(),
-                        self.cache_manager.clone(),
-                        self.storage_driver_manager.clone(),
-                        self.connection_manager.clone(),
-                        self.rocksdb_engine_handler.clone(),
-                        tenant.clone(),
-                        group_name.clone(),
-                        topic_name.clone(),
-                    );
+        // The maps above are no longer borrowed, the lock calls can await.
+        for (thread_key, tenant, sample) in to_start {
+            let group_name = &sample.group_name;
+            let topic_name = &sample.topic_name;
+
+            // Only the holder of the group lock pushes, so a group moved here
+            // waits until the previous leader has stopped pushing.
+            if !self
+                .share_group_lock(&tenant, group_name)
+                .keep_alive()
+                .await
+            {
+                debug!(
+                    "Share group {}/{} is still locked by another broker",
+                    tenant, group_name
+                );
+                continue;
+            }
 
-                    let stop_sx = sub_thread_stop_sx.clone();
-                    tokio::spawn(async move {
-                        let mut push_manager = push_manager;
-                        push_manager.start(&stop_sx).await;
-                    });
+            info!(
+                "Starting share push thread for {}/{}/{}",
+                tenant, group_name, topic_name
+            );
+
+            let (sub_thread_stop_sx, _) = broadcast::channel(1);
+            let thread_data = SubPushThreadData::new(sub_thread_stop_sx.clone());
+
+            let push_manager = SharePushManager::new(
+                self.subscribe_manager.clone(),
+                self.cache_manager.clone(),
+                self.storage_driver_manager.clone(),
+                self.connection_manager.clone(),
+                self.rocksdb_engine_handler.clone(),
+                tenant.clone(),
+                group_name.clone(),
+                topic_name.clone(),
+                thread_data.stats.clone(),
+            );
+
+            let stop_sx = sub_thread_stop_sx.clone();
+            tokio::spawn(async move {
+                let mut push_manager = push_manager;
+                push_manager.start(&stop_sx).await;
+            });
+
+            self.share_buckets_push_thread
+                .insert(thread_key, thread_data);
+        }
+    }
+
+    /// Renew the locks of share groups this broker pushes, and release the
+    /// ones whose push threads are all gone.
+    async fn refresh_share_group_locks(&self) {
+        let pushed_groups: Vec<String> = self
+            .share_buckets_push_thread
+            .iter()
+            .filter_map(|row| {
+                let (tenant, _) = split_thread_key(row.key());
+                self.thread_group_name(row.key())
+                    .map(|group_name| share_lock_key(tenant, &group_name))
+            })
+            .collect();
+        let locks: Vec<(String, Arc<DistributedLock>)> = self
+            .share_group_locks
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
 
-                    self.share_buckets_push_thread
-                        .insert(thread_key, thread_data);
+        for (lock_key, lock) in locks {
+            if pushed_groups.contains(&lock_key) {
+                if !lock.keep_alive().await {
+                    warn!("Lost the lock of share group {}", lock.name());
                 }
+                continue;
+            }
+
+            self.share_group_locks.remove(&lock_key);
+            if let Err(e) = lock.release().await {
+                warn!("Failed to release lock {}: {}", lock.name(), e);
             }
         }
     }
 
+    fn share_group_lock(&self, tenant: &str, group_name: &str) -> Arc<DistributedLock> {
+        let conf = broker_config();
+        self.share_group_locks
+            .entry(share_lock_key(tenant, group_name))
+            .or_insert_with(|| {
+                Arc::new(DistributedLock::new(
+                    self.cache_manager.client_pool.clone(),
+                    conf.get_meta_service_addr(),
+                    format!("share_group/{}/{}", tenant, group_name),
+                    conf.broker_id.to_string(),
+                    Duration::from_millis(SHARE_GROUP_LOCK_LEASE_MS),
+                ))
+            })
+            .clone()
+    }
+
+    /// Group name of a share push thread, read from one of its subscribers.
+    fn thread_group_name(&self, thread_key: &str) -> Option<String> {
+        // thread_key format: "tenant#share_key"
+        // share_key format: "{group_name_full}/{topic_name}"
+        // group_name_full may contain '/', so we must not use split_once to extract it.
+        // Instead read the canonical group_name from a subscriber in share_push.
+        let (tenant, share_key) = split_thread_key(thread_key);
+        self.subscribe_manager
+            .share_push
+            .get(tenant)
+            .and_then(|t| t.get(share_key).map(|b| b.clone()))
+            .and_then(|buckets| {
+                buckets.buckets_data_list.iter().find_map(|bucket| {
+                    bucket
+                        .value()
+                        .iter()
+                        .next()
+                        .map(|e| e.value().group_name.clone())
+                })
+            })
+    }
+
     pub fn stop_share_push_thread(&self) {
         let conf = broker_config();
         let threads_to_stop: Vec<String> = self
             .share_buckets_push_thread
             .iter()
             .filter(|row| {
-                // thread_key format: "tenant#share_key"
-                // share_key format: "{group_name_full}/{topic_name}"
-                // group_name_full may contain '/', so we must not use split_once to extract it.
-                // Instead read the canonical group_name from a subscriber in share_push.
-                let (tenant, share_key) = split_thread_key(row.key());
-
-                // Look up the group_name_full from a subscriber in share_push.
                 // If the share_key no longer exists, stop the thread.
-                let group_name = self
-                    .subscribe_manager
-                    .share_push
-                    .get(tenant)
-                    .and_then(|t| t.get(share_key).map(|b| b.clone()))
-                    .and_then(|buckets| {
-                        buckets.buckets_data_list.iter().find_map(|bucket| {
-                            bucket
-                                .value()
-                                .iter()
-                                .next()
-                                .map(|e| e.value().group_name.clone())
-                        })
-                    });
-
-                let Some(group_name) = group_name else {
-                    // share_key no longer in share_push — stop the thread.
+                let Some(group_name) = self.thread_group_name(row.key()) else {
                     return true;
                 };
+                let (tenant, _) = split_thread_key(row.key());
 
                 let is_leader = self
                     .cache_manager
@@ -383,7 +458,13 @@ pub fn stop_share_push_thread(&self) {
                     .map(|group| group.leader_broker == conf.broker_id)
                     .unwrap_or(false);
 
-                !is_leader
+                // A lost lock means another broker may push the group already.
+                let holds_lock = self
+                    .share_group_locks
+                    .get(&share_lock_key(tenant, &group_name))
+                    .is_some_and(|lock| lock.is_held());
+
+                !is_leader || !holds_lock
             })
             .map(|row| row.key().clone())
             .collect();
@@ -404,10 +485,16 @@ pub fn stop_share_push_thread(&self) {
 
 /// Compose the push-thread map key.
 /// Format: "{tenant}#{group_name}/{topic_name}"
-fn share_thread_key(tenant: &str, share_key: &str) -> String {
+pub fn share_thread_key(tenant: &str, share_key: &str) -> String {
     format!("{}#{}", tenant, share_key)
 }
 
+/// Compose the share group lock map key.
+/// Format: "{tenant}#{group_name}"
+fn share_lock_key(tenant: &str, group_name: &str) -> String {
+    format!("{}#{}", tenant, group_name)
+}
+
 /// Split a thread key back into (tenant, share_key).
 /// share_key format: "group_name/topic_name"
 fn split_thread_key(key: &str) -> (&str, &str) {
@@ -15,6 +15,7 @@
 use crate::{
     core::{
         cache::MQTTCacheManager,
+        payload_transform::is_payload_decode_requested,
         sub_exclusive::{decode_exclusive_sub_path_to_topic_name, is_exclusive_sub},
         sub_share::{
             decode_share_info, full_group_name, is_mqtt_share_subscribe, is_share_sub_leader,
@@ -59,6 +60,7 @@ struct AddDirectlyPushContext {
     pub client_id: String,
     pub protocol: MqttProtocol,
     pub sub_identifier: Option<usize>,
+    pub payload_decode: bool,
     pub filter: Filter,
     pub rewrite_sub_path: Option<String>,
 }
@@ -71,6 +73,7 @@ struct AddSharePushContext {
     pub client_id: String,
     pub protocol: MqttProtocol,
     pub sub_identifier: Option<usize>,
+    pub payload_decode: bool,
     pub filter: Filter,
     pub rewrite_sub_path: Option<String>,
 }
@@ -132,6 +135,7 @@ pub async fn start_update_parse_thread(
                         if let Some(topic) = data.topic {
                             info!("Removing subscriptions for deleted topic '{}'", topic.topic_name);
                             subscribe_manager.remove_by_topic(&topic.tenant, &topic.topic_name);
+                            cache_manager.topic_stats.remove_topic(&topic.tenant, &topic.topic_name);
                         }
                     }
                     (BrokerUpdateCacheResourceType::Subscribe, BrokerUpdateCacheActionType::Create) => {
@@ -201,24 +205,31 @@ pub async fn parse_subscribe_by_new_topic(
     let broker_id = broker_config().broker_id;
 
     // Collect subscriptions first to release DashMap shard locks before any .await
-    let subscribes: Vec<_> = subscribe_manager
-        .subscribe_list
-        .get(&topic.tenant)
-        .map(|tenant_subs| {
-            debug!(
-                "Matching new topic '{}' against {} subscriptions under tenant '{}'",
-                topic.topic_name,
-                tenant_subs.len(),
-                topic.tenant
-            );
-            tenant_subs
-                .value()
-                .iter()
-                .filter(|row| row.value().broker_id == broker_id)
-                .map(|row| row.value().clone())
-                .collect()
-        })
-        .unwrap_or_default();
+    let subscribes: Vec<_> = if cache_manager.has_rewrite_name(&topic.tenant) {
+        subscribe_manager
+            .subscribe_list
+            .get(&topic.tenant)
+            .map(|tenant_subs| {
+                tenant_subs
+                    .value()
+                    .iter()
+                    .map(|row| row.value().clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    } else {
+        subscribe_manager.candidate_subscribes(&topic.tenant, &topic.topic_name)
+    };
+    debug!(
+        "Matching new topic '{}' against {} subscriptions under tenant '{}'",
+        topic.topic_name,
+        subscribes.len(),
+        topic.tenant
+    );
+    let subscribes: Vec<_> = subscribes
+        .into_iter()
+        .filter(|subscribe| subscribe.broker_id == broker_id)
+        .collect();
 
     let rewrite_sub_path = cache_manager.get_new_rewrite_name(&topic.tenant, &topic.topic_name);
     for subscribe in subscribes {
@@ -247,6 +258,7 @@ fn create_subscriber(
     group_name: String,
     filter: &Filter,
     sub_identifier: Option<usize>,
+    payload_decode: bool,
     sub_path: String,
     rewrite_sub_path: Option<String>,
 ) -> Subscriber {
@@ -261,6 +273,7 @@ fn create_subscriber(
         preserve_retain: filter.preserve_retain,
         retain_forward_rule: filter.retain_handling.clone(),
         subscription_identifier: sub_identifier,
+        payload_decode,
         sub_path,
         rewrite_sub_path,
         create_time: now_second(),
@@ -278,6 +291,7 @@ async fn parse_subscribe(
         .subscribe_properties
         .as_ref()
         .and_then(|p| p.subscription_identifier);
+    let payload_decode = is_payload_decode_requested(&sub.subscribe_properties);
 
     let new_topic_name = cache_manager
         .get_new_rewrite_name(&sub.tenant, &context.topic.topic_name)
@@ -294,6 +308,7 @@ async fn parse_subscribe(
                 client_id: sub.client_id.clone(),
                 protocol: sub.protocol.clone(),
                 sub_identifier,
+                payload_decode,
                 filter: sub.filter.clone(),
                 rewrite_sub_path: context.rewrite_sub_path.clone(),
             },
@@ -307,6 +322,7 @@ async fn parse_subscribe(
             client_id: sub.client_id.clone(),
             protocol: sub.protocol.clone(),
             sub_identifier,
+            payload_decode,
             filter: sub.filter.clone(),
             rewrite_sub_path: context.rewrite_sub_path.clone(),
         })?;
@@ -350,6 +366,7 @@ async fn add_share_push(
             group_name_full,
             &req.filter,
             req.sub_identifier,
+            req.payload_decode,
             req.filter.path.clone(),
             req.rewrite_sub_path.clone(),
         );
@@ -385,6 +402,7 @@ fn add_directly_push(context: AddDirectlyPushContext) -> ResultMqttBrokerError {
             group_name,
             &context.filter,
             context.sub_identifier,
+            context.payload_decode,
             context.filter.path.clone(),
             context.rewrite_sub_path,
         );
@@ -421,6 +439,7 @@ fn test_create_subscriber() {
             "group1".to_string(),
             &filter,
             Some(123),
+            true,
             "test/topic".to_string(),
             None,
         );
@@ -430,6 +449,7 @@ fn test_create_subscriber() {
         assert_eq!(sub.group_name, "group1");
         assert_eq!(sub.qos, QoS::AtMostOnce);
         assert_eq!(sub.subscription_identifier, Some(123));
+        assert!(sub.payload_decode);
         assert!(sub.preserve_retain);
     }
 
@@ -18,10 +18,18 @@
     MQTTCacheManager, QosAckPackageData, QosAckPackageType, QosAckPacketInfo,
 };
 use crate::core::error::MqttBrokerError;
+use crate::core::inflight::{inflight_persistent_enabled, InflightMessage};
+use crate::core::message_path::{
+    record_store_to_push, trace_id_from_packet, MESSAGE_STORE_TIME_HEADER,
+};
+use crate::core::message_trace::{trace_message, MessageTraceStage, TracedMessage};
 use crate::core::metrics::record_publish_send_metrics;
 use crate::core::metrics::record_send_metrics;
+use crate::core::payload_transform::{decode_record_payload, PAYLOAD_TRANSFORM_PROPERTY};
+use crate::core::sub_option::build_retain_flag;
 use crate::core::sub_slow::record_slow_subscribe_data;
 use crate::core::tool::ResultMqttBrokerError;
+use crate::storage::inflight::InflightStorage;
 use crate::subscribe::common::{client_unavailable_error, SubPublishParam};
 use axum::extract::ws::Message;
 use bytes::{Bytes, BytesMut};
@@ -69,6 +77,7 @@ pub async fn push_data(
     subscriber: &Subscriber,
     record: &StorageRecord,
     stop_sx: &Sender<bool>,
+    inflight_storage: Option<&InflightStorage>,
 ) -> Result<bool, MqttBrokerError> {
     let sub_pub_param = if let Some(params) =
         build_publish_message(cache_manager, connection_manager, record, subscriber).await?
@@ -77,9 +86,77 @@ pub async fn push_data(
     } else {
         return Ok(false);
     };
+    record_store_to_push(&subscriber.tenant, record);
+
+    // Persist the packet before it leaves the broker, so it can be redelivered
+    // with the same packet id if the broker restarts before the client acks it.
+    let inflight = match inflight_storage {
+        Some(storage)
+            if sub_pub_param.qos != QoS::AtMostOnce
+                && inflight_persistent_enabled(cache_manager) =>
+        {
+            let message = InflightMessage::new(subscriber, record, &sub_pub_param);
+            if let Err(e) = storage.save_inflight_message(&message).await {
+                cache_manager
+                    .pkid_manager
+                    .remove_publish_to_client_pkid(&sub_pub_param.client_id, sub_pub_param.p_kid);
+                return Err(e);
+            }
+            Some((storage, message))
+        }
+        _ => None,
+    };
 
-    send_publish_packet_to_client(connection_manager, cache_manager, &sub_pub_param, stop_sx)
-        .await?;
+    let traced = TracedMessage::pushed(
+        &subscriber.tenant,
+        &subscriber.topic_name,
+        &subscriber.client_id,
+        record,
+    );
+    trace_message(cache_manager, &traced, MessageTraceStage::Pushed, || {
+        format!(
+            "qos={:?} pkid={} shard={} offset={}",
+            sub_pub_param.qos, sub_pub_param.p_kid, record.metadata.shard, record.metadata.offset
+        )
+    });
+
+    if let Err(e) =
+        send_publish_packet_to_client(connection_manager, cache_manager, &sub_pub_param, stop_sx)
+            .await
+    {
+        trace_message(
+            cache_manager,
+            &traced,
+            MessageTraceStage::PushFailed,
+            || e.to_string(),
+        );
+        if let Some((storage, message)) = inflight {
+            // The client went away: the persisted entry is redelivered when
+            // the session resumes, so the message must not be queued again.
+            if client_unavailable_error(&e) {
+                return Err(MqttBrokerError::InflightRedeliveryPending(
+                    message.client_id,
+                ));
+            }
+            if let Err(delete_err) = storage
+                .delete_inflight_message(&message.tenant, &message.client_id, message.pkid)
+                .await
+            {
+                warn!(
+                    "Failed to delete inflight message, client_id={}, pkid={}, error={}",
+                    message.client_id, message.pkid, delete_err
+                );
+            }
+        }
+        return Err(e);
+    }
+
+    // QoS 1/2 sends only return once PUBACK or PUBCOMP has arrived.
+    if sub_pub_param.qos != QoS::AtMostOnce {
+        trace_message(cache_manager, &traced, MessageTraceStage::Acked, || {
+            format!("pkid={}", sub_pub_param.p_kid)
+        });
+    }
 
     record_slow_subscribe_data(
         cache_manager,
@@ -99,11 +176,14 @@ pub async fn build_publish_message(
     msg: &StorageRecord,
     subscriber: &Subscriber,
 ) -> Result<Option<SubPublishParam>, MqttBrokerError> {
-    let connect_id = cache_manager
+    if cache_manager
         .get_connect_id(&subscriber.client_id)
-        .ok_or_else(|| {
-            MqttBrokerError::ConnectionNullSkipPushMessage(subscriber.client_id.to_owned())
-        })?;
+        .is_none()
+    {
+        return Err(MqttBrokerError::ConnectionNullSkipPushMessage(
+            subscriber.client_id.to_owned(),
+        ));
+    }
 
     let qos = build_pub_qos(subscriber);
     let p_kid = cache_manager
@@ -111,37 +191,64 @@ pub async fn build_publish_message(
         .generate_publish_to_client_pkid(&subscriber.client_id, &qos)
         .await;
 
+    match build_publish_message_with_pkid(cache_manager, connection_manager, msg, subscriber, p_kid)
+    {
+        Ok(param) => Ok(Some(param)),
+        Err(e) => {
+            cache_manager
+                .pkid_manager
+                .remove_publish_to_client_pkid(&subscriber.client_id, p_kid);
+            Err(e)
+        }
+    }
+}
+
+/// Build the Publish packet of a record with an already allocated packet id.
+pub fn build_publish_message_with_pkid(
+    cache_manager: &Arc<MQTTCacheManager>,
+    connection_manager: &Arc<ConnectionManager>,
+    msg: &StorageRecord,
+    subscriber: &Subscriber,
+    p_kid: u16,
+) -> Result<SubPublishParam, MqttBrokerError> {
+    let connect_id = cache_manager
+        .get_connect_id(&subscriber.client_id)
+        .ok_or_else(|| {
+            MqttBrokerError::ConnectionNullSkipPushMessage(subscriber.client_id.to_owned())
+        })?;
+
+    let qos = build_pub_qos(subscriber);
     let retain = build_retain_flag(msg, subscriber.preserve_retain);
+    let decoded = if subscriber.payload_decode {
+        decode_record_payload(cache_manager, msg)
+    } else {
+        None
+    };
+    let payload_decoded = decoded.is_some();
     let publish = Publish {
         dup: false,
         qos,
         p_kid,
         retain,
         topic: Bytes::copy_from_slice(subscriber.topic_name.as_bytes()),
-        payload: msg.data.clone(),
+        payload: decoded.unwrap_or_else(|| msg.data.clone()),
     };
 
-    let properties = build_publish_properties(connection_manager, msg, connect_id, subscriber);
+    let properties = build_publish_properties(
+        connection_manager,
+        msg,
+        connect_id,
+        subscriber,
+        payload_decoded,
+    );
     let packet = MqttPacket::Publish(publish, properties);
-    Ok(Some(SubPublishParam {
+    Ok(SubPublishParam {
         packet,
         create_time: now_second(),
         client_id: subscriber.client_id.to_string(),
         p_kid,
         qos,
-    }))
-}
-
-fn build_retain_flag(msg: &StorageRecord, preserve_retain: bool) -> bool {
-    if !preserve_retain {
-        return false;
-    }
-
-    msg.protocol_data
-        .as_ref()
-        .and_then(|pd| pd.mqtt.as_ref())
-        .map(|m| m.retain)
-        .unwrap_or(false)
+    })
 }
 
 fn build_publish_properties(
@@ -149,6 +256,7 @@ fn build_publish_properties(
     msg: &StorageRecord,
     connect_id: u64,
     subscriber: &Subscriber,
+    payload_decoded: bool,
 ) -> Option<PublishProperties> {
     let contain_properties = connection_manager
         .get_connect_protocol(connect_id)
@@ -158,17 +266,27 @@ fn build_publish_properties(
         return None;
     }
 
+    // only the fields that end up in the packet are cloned from the record
     let mut user_properties = Vec::new();
-    if let Some(header) = msg.metadata.header.clone() {
+    if let Some(header) = &msg.metadata.header {
         for row in header {
-            user_properties.push((row.name, row.value));
+            if row.name == MESSAGE_STORE_TIME_HEADER {
+                continue;
+            }
+            user_properties.push((row.name.clone(), row.value.clone()));
         }
     }
 
     let mut properties = PublishProperties::default();
-    if let Some(protocol_data) = msg.protocol_data.clone() {
-        if let Some(mqtt_data) = protocol_data.mqtt {
-            user_properties.extend(mqtt_data.user_properties);
+    if let Some(protocol_data) = &msg.protocol_data {
+        if let Some(mqtt_data) = &protocol_data.mqtt {
+            user_properties.extend(
+                mqtt_data
+                    .user_properties
+                    .iter()
+                    .filter(|(k, _)| !payload_decoded || k != PAYLOAD_TRANSFORM_PROPERTY)
+                    .cloned(),
+            );
             properties = PublishProperties {
                 payload_format_indicator: mqtt_data.format_indicator,
                 topic_alias: None,
@@ -207,6 +325,7 @@ pub async fn send_publish_packet_to_client(
                     QosAckPacketInfo {
                         sx: wait_puback_sx.clone(),
                         create_time: now_millis(),
+                        trace_id: trace_id_from_packet(&sub_pub_param.packet),
                     },
                 );
 
@@ -237,6 +356,7 @@ pub async fn send_publish_packet_to_client(
                     QosAckPacketInfo {
                         sx: wait_ack_sx.clone(),
                         create_time: now_millis(),
+                        trace_id: trace_id_from_packet(&sub_pub_param.packet),
                     },
                 );
 
@@ -257,6 +377,47 @@ pub async fn send_publish_packet_to_client(
     }
 }
 
+/// Resume a QoS 2 delivery whose PUBREC was already received: send PUBREL
+/// again and wait for PUBCOMP.
+pub async fn resend_pubrel_to_client(
+    connection_manager: &Arc<ConnectionManager>,
+    cache_manager: &Arc<MQTTCacheManager>,
+    sub_pub_param: &SubPublishParam,
+    stop_sx: &broadcast::Sender<bool>,
+) -> ResultMqttBrokerError {
+    let (wait_ack_sx, wait_ack_rx) = mpsc::channel(1);
+    let pkid = sub_pub_param.p_kid;
+    cache_manager
+        .pkid_manager
+        .add_publish_to_client_qos_ack_data(
+            &sub_pub_param.client_id,
+            pkid,
+            QosAckPacketInfo {
+                sx: wait_ack_sx.clone(),
+                create_time: now_millis(),
+                trace_id: None,
+            },
+        );
+
+    let result = async {
+        qos2_send_pubrel(cache_manager, sub_pub_param, connection_manager, stop_sx).await?;
+        wait_pub_comp(
+            cache_manager,
+            connection_manager,
+            sub_pub_param,
+            stop_sx,
+            wait_ack_rx,
+        )
+        .await
+    }
+    .await;
+
+    cache_manager
+        .pkid_manager
+        .remove_publish_to_client_pkid(&sub_pub_param.client_id, pkid);
+    result
+}
+
 fn build_pub_qos(subscriber: &Subscriber) -> QoS {
     min_qos(QoS::ExactlyOnce, subscriber.qos)
 }
@@ -403,6 +564,21 @@ pub async fn send_message_to_client(
                 .write_quic_frame(resp.connection_id, response)
                 .await?;
         }
+        (false, false) if connection_manager.is_mqttsn(resp.connection_id) => {
+            connection_manager
+                .write_mqttsn_frame(resp.connection_id, response)
+                .await?;
+        }
+        (false, false) if connection_manager.is_coap(resp.connection_id) => {
+            connection_manager
+                .write_coap_frame(resp.connection_id, response)
+                .await?;
+        }
+        (false, false) if connection_manager.is_web_subscribe(resp.connection_id) => {
+            connection_manager
+                .write_web_subscribe_frame(resp.connection_id, response)
+                .await?;
+        }
         (false, false) => {
             connection_manager
                 .write_tcp_frame(resp.connection_id, response)
@@ -490,7 +666,7 @@ pub async fn wait_pub_ack(
     }
 }
 
-fn build_dup_publish_param(
+pub(crate) fn build_dup_publish_param(
     sub_pub_param: &SubPublishParam,
 ) -> Result<SubPublishParam, MqttBrokerError> {
     match &sub_pub_param.packet {
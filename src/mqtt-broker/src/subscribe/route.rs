@@ -0,0 +1,249 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Registration of the topic filters hosted by this broker in the cluster
+//! subscribe route table of meta-service.
+//!
+//! The routes of a broker are the distinct filters of its local subscriptions
+//! per tenant, with share and exclusive prefixes stripped; a tenant with more
+//! than [`ROUTE_MAX_FILTERS_PER_TENANT`] filters is registered as `#`, which
+//! bounds the table size at the cost of some unneeded forwards. Subscription
+//! changes are sent as incremental updates, and a periodic full sync repairs
+//! anything an update missed. Routes are only registered while
+//! `mqtt_runtime.cluster_forward_enable` is on; turning it off clears them.
+
+use crate::core::cache::MQTTCacheManager;
+use crate::core::sub_exclusive::decode_exclusive_sub_path_to_topic_name;
+use crate::core::sub_share::{decode_share_info, is_mqtt_share_subscribe};
+use crate::core::tool::ResultMqttBrokerError;
+use crate::subscribe::forward::cluster_forward_enabled;
+use crate::subscribe::manager::SubscribeManager;
+use common_base::error::ResultCommonError;
+use common_base::tools::{loop_select_ticket, now_second};
+use common_config::broker::broker_config;
+use grpc_clients::meta::mqtt::call::{
+    placement_sync_subscribe_route, placement_update_subscribe_route,
+};
+use grpc_clients::pool::ClientPool;
+use protocol::meta::meta_service_mqtt::{
+    SubscribeRoute, SyncSubscribeRouteRequest, UpdateSubscribeRouteRequest,
+};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+const ROUTE_FLUSH_INTERVAL_MS: u64 = 1000;
+const ROUTE_FULL_SYNC_INTERVAL_SEC: u64 = 60;
+/// Distinct filters registered per tenant before the tenant collapses to `#`.
+pub const ROUTE_MAX_FILTERS_PER_TENANT: usize = 1000;
+
+// (tenant, filter)
+type RouteSet = HashSet<(String, String)>;
+
+#[derive(Default)]
+pub struct SubscribeRouteState {
+    dirty: AtomicBool,
+    // Routes meta-service has accepted
+    registered: Mutex<RouteSet>,
+    last_full_sync: AtomicU64,
+}
+
+impl SubscribeRouteState {
+    /// Local subscriptions changed, send an update on the next flush.
+    pub fn mark_dirty(&self) {
+        self.dirty.store(true, Ordering::Release);
+    }
+}
+
+pub async fn start_subscribe_route_sync(
+    cache_manager: Arc<MQTTCacheManager>,
+    client_pool: Arc<ClientPool>,
+    subscribe_manager: Arc<SubscribeManager>,
+    stop_send: broadcast::Sender<bool>,
+) {
+    let ac_fn = async || -> ResultCommonError {
+        if let Err(e) = sync_subscribe_route(&cache_manager, &client_pool, &subscribe_manager).await
+        {
+            warn!("Failed to register subscribe routes: {}", e);
+        }
+        Ok(())
+    };
+
+    loop_select_ticket(ac_fn, ROUTE_FLUSH_INTERVAL_MS, &stop_send).await;
+}
+
+async fn sync_subscribe_route(
+    cache_manager: &Arc<MQTTCacheManager>,
+    client_pool: &Arc<ClientPool>,
+    subscribe_manager: &Arc<SubscribeManager>,
+) -> ResultMqttBrokerError {
+    let state = &subscribe_manager.subscribe_route;
+    let now = now_second();
+    let full_sync = now.saturating_sub(state.last_full_sync.load(Ordering::Acquire))
+        >= ROUTE_FULL_SYNC_INTERVAL_SEC;
+    if !state.dirty.swap(false, Ordering::AcqRel) && !full_sync {
+        return Ok(());
+    }
+
+    let config = broker_config();
+    let routes = if cluster_forward_enabled(cache_manager) {
+        local_subscribe_routes(subscribe_manager, config.broker_id)
+    } else {
+        RouteSet::new()
+    };
+
+    let result = if full_sync {
+        let request = SyncSubscribeRouteRequest {
+            broker_id: config.broker_id,
+            routes: to_proto_routes(routes.iter()),
+        };
+        placement_sync_subscribe_route(client_pool, &config.get_meta_service_addr(), request)
+            .await
+            .map(|reply| {
+                if reply.changed {
+                    debug!("Subscribe routes repaired by full sync");
+                }
+                state.last_full_sync.store(now, Ordering::Release);
+            })
+    } else {
+        let registered = state.registered.lock().unwrap().clone();
+        let request = UpdateSubscribeRouteRequest {
+            broker_id: config.broker_id,
+            add: to_proto_routes(routes.difference(&registered)),
+            remove: to_proto_routes(registered.difference(&routes)),
+        };
+        if request.add.is_empty() && request.remove.is_empty() {
+            return Ok(());
+        }
+        placement_update_subscribe_route(client_pool, &config.get_meta_service_addr(), request)
+            .await
+            .map(|_| ())
+    };
+
+    match result {
+        Ok(()) => {
+            *state.registered.lock().unwrap() = routes;
+            Ok(())
+        }
+        Err(e) => {
+            // Retry on the next flush.
+            state.mark_dirty();
+            Err(e.into())
+        }
+    }
+}
+
+/// Aggregated routes of the subscriptions hosted by `broker_id`.
+pub fn local_subscribe_routes(subscribe_manager: &SubscribeManager, broker_id: u64) -> RouteSet {
+    let mut filters: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for tenant_map in subscribe_manager.subscribe_list.iter() {
+        for row in tenant_map.iter() {
+            let subscribe = row.value();
+            if subscribe.broker_id != broker_id {
+                continue;
+            }
+            filters
+                .entry(subscribe.tenant.clone())
+                .or_default()
+                .insert(route_filter(&subscribe.path));
+        }
+    }
+
+    let mut routes = RouteSet::new();
+    for (tenant, filters) in filters {
+        if filters.contains("#") || filters.len() > ROUTE_MAX_FILTERS_PER_TENANT {
+            routes.insert((tenant, "#".to_string()));
+            continue;
+        }
+        for filter in filters {
+            routes.insert((tenant.clone(), filter));
+        }
+    }
+    routes
+}
+
+/// Topic filter of a subscription path, without share or exclusive prefix.
+pub fn route_filter(sub_path: &str) -> String {
+    if is_mqtt_share_subscribe(sub_path) {
+        let (_, topic_path) = decode_share_info(sub_path);
+        return topic_path
+            .strip_prefix('/')
+            .unwrap_or(&topic_path)
+            .to_string();
+    }
+    decode_exclusive_sub_path_to_topic_name(sub_path).to_string()
+}
+
+fn to_proto_routes<'a>(routes: impl Iterator<Item = &'a (String, String)>) -> Vec<SubscribeRoute> {
+    routes
+        .map(|(tenant, filter)| SubscribeRoute {
+            tenant: tenant.clone(),
+            filter: filter.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metadata_struct::mqtt::subscribe::MqttSubscribe;
+
+    fn subscribe(tenant: &str, client_id: &str, path: &str, broker_id: u64) -> MqttSubscribe {
+        MqttSubscribe {
+            tenant: tenant.to_string(),
+            client_id: client_id.to_string(),
+            path: path.to_string(),
+            broker_id,
+            ..Default::default()
+        }
+    }
+
+    fn route(tenant: &str, filter: &str) -> (String, String) {
+        (tenant.to_string(), filter.to_string())
+    }
+
+    #[test]
+    fn test_route_filter() {
+        assert_eq!(route_filter("a/+"), "a/+");
+        assert_eq!(route_filter("$share/g1/a/#"), "a/#");
+        assert_eq!(route_filter("$exclusive/a/b"), "a/b");
+    }
+
+    #[test]
+    fn test_local_subscribe_routes() {
+        let manager = SubscribeManager::new();
+        manager.add_subscribe(&subscribe("t1", "c1", "a/+", 1));
+        manager.add_subscribe(&subscribe("t1", "c2", "a/+", 1));
+        manager.add_subscribe(&subscribe("t1", "c3", "$share/g1/b", 1));
+        manager.add_subscribe(&subscribe("t1", "c4", "c", 2));
+        manager.add_subscribe(&subscribe("t2", "c5", "x", 1));
+        manager.add_subscribe(&subscribe("t2", "c6", "#", 1));
+
+        let routes = local_subscribe_routes(&manager, 1);
+        assert_eq!(
+            routes,
+            RouteSet::from([route("t1", "a/+"), route("t1", "b"), route("t2", "#")])
+        );
+
+        for i in 0..=ROUTE_MAX_FILTERS_PER_TENANT {
+            manager.add_subscribe(&subscribe("t3", "c7", &format!("d/{i}"), 2));
+        }
+        assert_eq!(
+            local_subscribe_routes(&manager, 2),
+            RouteSet::from([route("t1", "c"), route("t3", "#")])
+        );
+    }
+}
@@ -15,7 +15,7 @@
 use crate::core::cache::MQTTCacheManager;
 use crate::core::error::MqttBrokerError;
 use crate::core::sub_option::message_is_same_client;
-use crate::subscribe::buckets::BucketsManager;
+use crate::subscribe::buckets::{BucketsManager, PushThreadStats};
 use crate::subscribe::common::{
     client_unavailable_error, message_is_exceeds_max_message_size, message_is_expire,
     record_sub_send_metrics, stale_subscriber_error, Subscriber,
@@ -48,6 +48,7 @@ pub struct SharePushManager {
     /// share_push inner-map key: "group_name/topic_name"
     share_key: String,
     seq: AtomicU64,
+    thread_stats: Arc<PushThreadStats>,
 }
 
 impl SharePushManager {
@@ -61,6 +62,7 @@ pub fn new(
         tenant: String,
         group_name: String,
         topic_name: String,
+        thread_stats: Arc<PushThreadStats>,
     ) -> Self {
         let share_key = share_push_key(&group_name, &topic_name);
         SharePushManager {
@@ -74,6 +76,7 @@ pub fn new(
             share_key,
             group_name,
             seq: AtomicU64::new(0),
+            thread_stats,
         }
     }
 
@@ -108,6 +111,7 @@ pub async fn start(&mut self, stop_sx: &Sender<bool>) {
     }
 
     pub async fn send_messages(&mut self, stop_sx: &Sender<bool>) -> Result<u64, MqttBrokerError> {
+        self.thread_stats.record_run();
         let Some(buckets) = self
             .subscribe_manager
             .share_push
@@ -245,9 +249,15 @@ async fn try_push_to_subscriber(
             subscriber,
             record,
             stop_sx,
+            // Not persisted: an undelivered shared message goes to another member.
+            None,
         )
         .await
         {
+            self.thread_stats.record_error();
+            self.subscribe_manager
+                .subscription_stats
+                .record_error(subscriber);
             if !client_unavailable_error(&e) {
                 self.subscribe_manager
                     .add_not_push_client(&subscriber.tenant, &subscriber.client_id);
@@ -255,6 +265,10 @@ async fn try_push_to_subscriber(
             return Ok(false);
         }
 
+        self.thread_stats.record_success();
+        self.subscribe_manager
+            .subscription_stats
+            .record_delivered(subscriber);
         record_sub_send_metrics(
             &subscriber.tenant,
             &subscriber.client_id,
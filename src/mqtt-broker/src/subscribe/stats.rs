@@ -0,0 +1,191 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Push statistics of each subscription delivered by this broker.
+//!
+//! The counters are always kept for the subscription describe API; they are
+//! also exported as subscription metrics when those are enabled.
+
+use crate::subscribe::common::Subscriber;
+use common_base::tools::now_second;
+use common_metrics::mqtt::subscription::{
+    record_subscription_delivered, record_subscription_dropped, record_subscription_error,
+    remove_subscription_series, SubscriptionLabel,
+};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+#[derive(Default)]
+struct SubscriptionStats {
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+    errors: AtomicU64,
+    last_push_time: AtomicU64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct SubscriptionStatsSnapshot {
+    pub delivered: u64,
+    pub dropped: u64,
+    pub errors: u64,
+    pub last_push_time: u64,
+}
+
+impl SubscriptionStats {
+    fn snapshot(&self) -> SubscriptionStatsSnapshot {
+        SubscriptionStatsSnapshot {
+            delivered: self.delivered.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            last_push_time: self.last_push_time.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SubscriptionStatsManager {
+    // (tenant/client_id/path/topic, SubscriptionStats)
+    stats: DashMap<SubscriptionLabel, Arc<SubscriptionStats>>,
+}
+
+impl SubscriptionStatsManager {
+    pub fn record_delivered(&self, subscriber: &Subscriber) {
+        let stats = self.entry(subscriber);
+        stats.delivered.fetch_add(1, Ordering::Relaxed);
+        stats.last_push_time.store(now_second(), Ordering::Relaxed);
+        record_subscription_delivered(subscription_label(subscriber));
+    }
+
+    pub fn record_dropped(&self, subscriber: &Subscriber) {
+        self.entry(subscriber)
+            .dropped
+            .fetch_add(1, Ordering::Relaxed);
+        record_subscription_dropped(subscription_label(subscriber));
+    }
+
+    pub fn record_error(&self, subscriber: &Subscriber) {
+        self.entry(subscriber)
+            .errors
+            .fetch_add(1, Ordering::Relaxed);
+        record_subscription_error(subscription_label(subscriber));
+    }
+
+    pub fn get(
+        &self,
+        tenant: &str,
+        client_id: &str,
+        path: &str,
+        topic: &str,
+    ) -> Option<SubscriptionStatsSnapshot> {
+        self.stats
+            .get(&SubscriptionLabel::new(tenant, client_id, path, topic))
+            .map(|stats| stats.snapshot())
+    }
+
+    pub fn list(&self) -> Vec<(SubscriptionLabel, SubscriptionStatsSnapshot)> {
+        self.stats
+            .iter()
+            .map(|row| (row.key().clone(), row.value().snapshot()))
+            .collect()
+    }
+
+    pub fn remove_by_client_id(&self, tenant: &str, client_id: &str) {
+        self.remove_where(|label| label.tenant == tenant && label.client_id == client_id);
+    }
+
+    pub fn remove_by_sub(&self, tenant: &str, client_id: &str, path: &str) {
+        self.remove_where(|label| {
+            label.tenant == tenant && label.client_id == client_id && label.path == path
+        });
+    }
+
+    pub fn remove_by_topic(&self, tenant: &str, topic: &str) {
+        self.remove_where(|label| label.tenant == tenant && label.topic == topic);
+    }
+
+    fn entry(&self, subscriber: &Subscriber) -> Arc<SubscriptionStats> {
+        self.stats
+            .entry(subscription_label(subscriber))
+            .or_default()
+            .clone()
+    }
+
+    fn remove_where(&self, matches: impl Fn(&SubscriptionLabel) -> bool) {
+        let labels: Vec<SubscriptionLabel> = self
+            .stats
+            .iter()
+            .filter(|row| matches(row.key()))
+            .map(|row| row.key().clone())
+            .collect();
+        for label in labels {
+            self.stats.remove(&label);
+            remove_subscription_series(&label);
+        }
+    }
+}
+
+pub fn subscription_label(subscriber: &Subscriber) -> SubscriptionLabel {
+    SubscriptionLabel::new(
+        &subscriber.tenant,
+        &subscriber.client_id,
+        &subscriber.sub_path,
+        &subscriber.topic_name,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subscriber(client_id: &str, path: &str, topic: &str) -> Subscriber {
+        Subscriber {
+            tenant: "stats_test".to_string(),
+            client_id: client_id.to_string(),
+            sub_path: path.to_string(),
+            topic_name: topic.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_subscription_stats() {
+        let manager = SubscriptionStatsManager::default();
+        let c1 = subscriber("c1", "a/#", "a/b");
+        let c2 = subscriber("c2", "a/b", "a/b");
+
+        manager.record_delivered(&c1);
+        manager.record_delivered(&c1);
+        manager.record_dropped(&c1);
+        manager.record_error(&c2);
+
+        let stats = manager.get("stats_test", "c1", "a/#", "a/b").unwrap();
+        assert_eq!(stats.delivered, 2);
+        assert_eq!(stats.dropped, 1);
+        assert_eq!(stats.errors, 0);
+        assert!(stats.last_push_time > 0);
+        assert_eq!(
+            manager
+                .get("stats_test", "c2", "a/b", "a/b")
+                .unwrap()
+                .errors,
+            1
+        );
+
+        manager.remove_by_sub("stats_test", "c1", "a/#");
+        assert!(manager.get("stats_test", "c1", "a/#", "a/b").is_none());
+        manager.remove_by_topic("stats_test", "a/b");
+        assert!(manager.list().is_empty());
+    }
+}
@@ -0,0 +1,194 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Topic trie over the subscription filters of this broker.
+//!
+//! Every subscription is indexed under the levels of its filter, with the
+//! `$share/{group}/` and `$exclusive/` prefixes stripped, so the
+//! subscriptions matching a topic are found by walking the topic levels
+//! instead of testing every filter. A lookup visits at most the literal,
+//! `+` and `#` children per level, which keeps it at O(topic depth) no matter
+//! how many subscriptions a tenant has.
+//!
+//! Lookups follow the MQTT matching rules (`#` also matches its parent
+//! level, wildcards at the first level skip topics starting with `$`), which
+//! is slightly wider than [`is_match_sub_and_topic`](super::common::is_match_sub_and_topic);
+//! callers treat the result as candidates and keep the exact check.
+
+use crate::subscribe::common::decode_sub_path;
+use dashmap::DashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+
+#[derive(Default, Debug)]
+struct TrieNode {
+    children: HashMap<String, TrieNode>,
+    // (client_id, sub_path) of the subscriptions whose filter ends here
+    subscribes: HashSet<(String, String)>,
+}
+
+impl TrieNode {
+    fn is_empty(&self) -> bool {
+        self.children.is_empty() && self.subscribes.is_empty()
+    }
+
+    /// Remove the subscription below `levels`, pruning emptied levels.
+    /// Returns whether this level is empty afterwards.
+    fn remove(&mut self, levels: &[&str], key: &(String, String)) -> bool {
+        match levels.split_first() {
+            None => {
+                self.subscribes.remove(key);
+            }
+            Some((level, rest)) => {
+                if let Some(child) = self.children.get_mut(*level) {
+                    if child.remove(rest, key) {
+                        self.children.remove(*level);
+                    }
+                }
+            }
+        }
+        self.is_empty()
+    }
+
+    fn collect(&self, levels: &[&str], first: bool, result: &mut HashSet<(String, String)>) {
+        // Wildcards at the first level do not match topics starting with '$'.
+        let match_wildcard = !(first && levels.first().is_some_and(|l| l.starts_with('$')));
+        if match_wildcard {
+            // '#' also matches the parent level, "a/#" matches "a".
+            if let Some(child) = self.children.get("#") {
+                result.extend(child.subscribes.iter().cloned());
+            }
+        }
+
+        let Some((level, rest)) = levels.split_first() else {
+            result.extend(self.subscribes.iter().cloned());
+            return;
+        };
+        if match_wildcard {
+            if let Some(child) = self.children.get("+") {
+                child.collect(rest, false, result);
+            }
+        }
+        if let Some(child) = self.children.get(*level) {
+            child.collect(rest, false, result);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SubscribeTopicTrie {
+    // (tenant, root)
+    tenants: DashMap<String, RwLock<TrieNode>>,
+}
+
+impl SubscribeTopicTrie {
+    pub fn insert(&self, tenant: &str, client_id: &str, sub_path: &str) {
+        let filter = decode_sub_path(sub_path);
+        let root = self.tenants.entry(tenant.to_owned()).or_default();
+        let mut guard = root.write().unwrap();
+        let mut node = &mut *guard;
+        for level in filter.split('/') {
+            node = node.children.entry(level.to_owned()).or_default();
+        }
+        node.subscribes
+            .insert((client_id.to_owned(), sub_path.to_owned()));
+    }
+
+    pub fn remove(&self, tenant: &str, client_id: &str, sub_path: &str) {
+        let filter = decode_sub_path(sub_path);
+        let levels: Vec<&str> = filter.split('/').collect();
+        let key = (client_id.to_owned(), sub_path.to_owned());
+        let empty = match self.tenants.get(tenant) {
+            Some(root) => root.write().unwrap().remove(&levels, &key),
+            None => return,
+        };
+        if empty {
+            self.tenants
+                .remove_if(tenant, |_, root| root.read().unwrap().is_empty());
+        }
+    }
+
+    /// `(client_id, sub_path)` of the subscriptions whose filter matches
+    /// `topic_name`.
+    pub fn matches(&self, tenant: &str, topic_name: &str) -> Vec<(String, String)> {
+        let Some(root) = self.tenants.get(tenant) else {
+            return Vec::new();
+        };
+        let levels: Vec<&str> = topic_name.split('/').collect();
+        let mut result = HashSet::new();
+        root.read().unwrap().collect(&levels, true, &mut result);
+        result.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut list: Vec<(String, String)>) -> Vec<String> {
+        list.sort();
+        list.into_iter().map(|(client_id, _)| client_id).collect()
+    }
+
+    #[test]
+    fn test_matches() {
+        let trie = SubscribeTopicTrie::default();
+        trie.insert("t1", "c1", "a/b");
+        trie.insert("t1", "c2", "a/+");
+        trie.insert("t1", "c3", "a/#");
+        trie.insert("t1", "c4", "#");
+        trie.insert("t1", "c5", "+/b/c");
+        trie.insert("t1", "c6", "$share/g1/a/b");
+        trie.insert("t1", "c7", "$exclusive/a/b");
+        trie.insert("t2", "c8", "a/b");
+
+        assert_eq!(
+            sorted(trie.matches("t1", "a/b")),
+            vec!["c1", "c2", "c3", "c4", "c6", "c7"]
+        );
+        assert_eq!(sorted(trie.matches("t1", "a")), vec!["c3", "c4"]);
+        assert_eq!(sorted(trie.matches("t1", "a/b/c")), vec!["c3", "c4", "c5"]);
+        assert_eq!(sorted(trie.matches("t2", "a/b")), vec!["c8"]);
+        assert!(trie.matches("t3", "a/b").is_empty());
+
+        // Wildcards at the first level skip '$' topics.
+        assert!(trie.matches("t1", "$SYS/b/c").is_empty());
+        trie.insert("t1", "c9", "$SYS/#");
+        assert_eq!(sorted(trie.matches("t1", "$SYS/b/c")), vec!["c9"]);
+
+        let shared = trie.matches("t2", "a/b");
+        assert_eq!(shared, vec![("c8".to_string(), "a/b".to_string())]);
+    }
+
+    #[test]
+    fn test_remove() {
+        let trie = SubscribeTopicTrie::default();
+        trie.insert("t1", "c1", "a/b");
+        trie.insert("t1", "c2", "a/b");
+        trie.insert("t1", "c1", "$share/g1/a/+");
+
+        trie.remove("t1", "c1", "a/b");
+        assert_eq!(sorted(trie.matches("t1", "a/b")), vec!["c1", "c2"]);
+        trie.remove("t1", "c1", "$share/g1/a/+");
+        assert_eq!(sorted(trie.matches("t1", "a/b")), vec!["c2"]);
+
+        // Removing something never inserted is a no-op.
+        trie.remove("t1", "c3", "x/y");
+        trie.remove("t9", "c3", "x/y");
+
+        trie.remove("t1", "c2", "a/b");
+        assert!(trie.matches("t1", "a/b").is_empty());
+        assert!(trie.tenants.get("t1").is_none());
+    }
+}
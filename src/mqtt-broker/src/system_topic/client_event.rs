@@ -0,0 +1,117 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::core::cache::MQTTCacheManager;
+use crate::system_topic::{replace_topic_name, write_topic_data};
+use bytes::Bytes;
+use grpc_clients::pool::ClientPool;
+use protocol::mqtt::common::DisconnectReasonCode;
+use protocol::mqtt::mqttv5::disconnect::code;
+use std::sync::Arc;
+use storage_adapter::driver::StorageDriverManager;
+use tracing::warn;
+
+// Per-client lifecycle events, same topic layout as EMQX
+pub(crate) const SYSTEM_TOPIC_BROKERS_CLIENT_CONNECTED: &str =
+    "$SYS/brokers/${node}/clients/${clientid}/connected";
+pub(crate) const SYSTEM_TOPIC_BROKERS_CLIENT_DISCONNECTED: &str =
+    "$SYS/brokers/${node}/clients/${clientid}/disconnected";
+pub(crate) const SYSTEM_TOPIC_BROKERS_CLIENT_SUBSCRIBED: &str =
+    "$SYS/brokers/${node}/clients/${clientid}/subscribed";
+pub(crate) const SYSTEM_TOPIC_BROKERS_CLIENT_UNSUBSCRIBED: &str =
+    "$SYS/brokers/${node}/clients/${clientid}/unsubscribed";
+
+pub(crate) fn client_event_topic_name(template: &str, client_id: &str) -> String {
+    // '/', '+' and '#' inside a client id would change the topic level layout
+    let client_id: String = client_id
+        .chars()
+        .map(|c| match c {
+            '/' | '+' | '#' => '_',
+            c => c,
+        })
+        .collect();
+    replace_topic_name(template.replace("${clientid}", &client_id))
+}
+
+/// Reason string (EMQX naming) and MQTT v5 reason code for a disconnect event.
+pub(crate) fn disconnect_reason(reason: Option<DisconnectReasonCode>) -> (String, u8) {
+    let Some(reason) = reason else {
+        return ("normal".to_string(), 0);
+    };
+    let name = match reason {
+        DisconnectReasonCode::NormalDisconnection
+        | DisconnectReasonCode::DisconnectWithWillMessage => "normal",
+        DisconnectReasonCode::KeepAliveTimeout => "keepalive_timeout",
+        DisconnectReasonCode::SessionTakenOver => "takenover",
+        DisconnectReasonCode::AdministrativeAction => "kicked",
+        DisconnectReasonCode::NotAuthorized => "not_authorized",
+        DisconnectReasonCode::ServerShuttingDown => "server_shutting_down",
+        DisconnectReasonCode::MalformedPacket | DisconnectReasonCode::ProtocolError => {
+            "protocol_error"
+        }
+        _ => "error",
+    };
+    (name.to_string(), code(reason))
+}
+
+pub(crate) async fn report_client_event(
+    client_pool: &Arc<ClientPool>,
+    metadata_cache: &Arc<MQTTCacheManager>,
+    storage_driver_manager: &Arc<StorageDriverManager>,
+    topic_name: String,
+    payload: String,
+) {
+    if let Err(e) = write_topic_data(
+        storage_driver_manager,
+        metadata_cache,
+        client_pool,
+        topic_name.clone(),
+        Bytes::from(payload),
+    )
+    .await
+    {
+        warn!(
+            "Failed to write client event to system topic {}: {:?}",
+            topic_name, e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common_base::tools::get_local_ip;
+
+    #[test]
+    fn test_client_event_topic_name() {
+        let topic = client_event_topic_name(SYSTEM_TOPIC_BROKERS_CLIENT_CONNECTED, "dev/1+#");
+        assert_eq!(
+            topic,
+            format!("$SYS/brokers/{}/clients/dev_1__/connected", get_local_ip())
+        );
+    }
+
+    #[test]
+    fn test_disconnect_reason() {
+        assert_eq!(disconnect_reason(None), ("normal".to_string(), 0));
+        assert_eq!(
+            disconnect_reason(Some(DisconnectReasonCode::KeepAliveTimeout)),
+            ("keepalive_timeout".to_string(), 0x8D)
+        );
+        assert_eq!(
+            disconnect_reason(Some(DisconnectReasonCode::SessionTakenOver)),
+            ("takenover".to_string(), 0x8E)
+        );
+    }
+}
@@ -0,0 +1,253 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::core::error::MqttBrokerError;
+use crate::system_topic::{build_system_topic_payload, SystemTopicEnvelope};
+use broker_core::inner_topic::METRICS_HISTORY_TOPIC;
+use common_base::error::ResultCommonError;
+use common_base::tools::loop_select_ticket;
+use common_metrics::mqtt::packets::{
+    record_mqtt_total_bytes_received_get, record_mqtt_total_bytes_sent_get,
+};
+use common_metrics::mqtt::publish::{
+    record_messages_dropped_no_subscribers_get, record_mqtt_messages_received_get,
+    record_mqtt_messages_sent_get,
+};
+use common_metrics::mqtt::statistics::{
+    record_mqtt_connections_get, record_mqtt_retained_get, record_mqtt_sessions_get,
+    record_mqtt_subscriptions_exclusive_get, record_mqtt_subscriptions_shared_get,
+    record_mqtt_topics_get,
+};
+use metadata_struct::adapter::adapter_offset::AdapterOffsetStrategy;
+use metadata_struct::adapter::adapter_read_config::AdapterReadConfig;
+use metadata_struct::adapter::adapter_record::AdapterWriteRecord;
+use metadata_struct::tenant::DEFAULT_TENANT;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use storage_adapter::driver::StorageDriverManager;
+use tokio::sync::broadcast;
+use tracing::warn;
+
+const METRICS_HISTORY_READ_BATCH: u64 = 500;
+
+/// One sample of the key `$SYS` metrics of a broker. Counters are cumulative
+/// since broker start, so rates are derived from consecutive snapshots.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetricsHistorySnapshot {
+    pub connections: i64,
+    pub sessions: i64,
+    pub topics: i64,
+    pub subscriptions: i64,
+    pub retained: i64,
+    pub messages_received: u64,
+    pub messages_sent: u64,
+    pub messages_dropped: u64,
+    pub bytes_received: u64,
+    pub bytes_sent: u64,
+}
+
+impl MetricsHistorySnapshot {
+    pub(crate) fn collect() -> Self {
+        MetricsHistorySnapshot {
+            connections: record_mqtt_connections_get(),
+            sessions: record_mqtt_sessions_get(),
+            topics: record_mqtt_topics_get(),
+            subscriptions: record_mqtt_subscriptions_exclusive_get()
+                + record_mqtt_subscriptions_shared_get(),
+            retained: record_mqtt_retained_get(),
+            messages_received: record_mqtt_messages_received_get(),
+            messages_sent: record_mqtt_messages_sent_get(),
+            messages_dropped: record_messages_dropped_no_subscribers_get(),
+            bytes_received: record_mqtt_total_bytes_received_get(),
+            bytes_sent: record_mqtt_total_bytes_sent_get(),
+        }
+    }
+}
+
+pub type MetricsHistoryPoint = SystemTopicEnvelope<MetricsHistorySnapshot>;
+
+/// Time range query over the `$metrics-history` shard. Times are unix
+/// seconds and both ends are inclusive.
+#[derive(Debug, Clone)]
+pub struct MetricsHistoryQuery {
+    pub start_time: u64,
+    pub end_time: u64,
+    pub node_id: Option<u64>,
+    pub limit: usize,
+}
+
+pub async fn save_metrics_snapshot(
+    storage_driver_manager: &Arc<StorageDriverManager>,
+    snapshot: MetricsHistorySnapshot,
+) -> Result<(), MqttBrokerError> {
+    let data = build_system_topic_payload(snapshot)?;
+    let record = AdapterWriteRecord::new(METRICS_HISTORY_TOPIC, data);
+    storage_driver_manager
+        .write(DEFAULT_TENANT, METRICS_HISTORY_TOPIC, &[record], 1)
+        .await?;
+    Ok(())
+}
+
+/// Read the snapshots stored between `start_time` and `end_time`, oldest first.
+pub async fn read_metrics_history(
+    storage_driver_manager: &Arc<StorageDriverManager>,
+    query: &MetricsHistoryQuery,
+) -> Result<Vec<MetricsHistoryPoint>, MqttBrokerError> {
+    if query.start_time > query.end_time {
+        return Err(MqttBrokerError::CommonError(format!(
+            "start_time {} is later than end_time {}",
+            query.start_time, query.end_time
+        )));
+    }
+
+    // Shards with nothing at or after start_time begin at their latest
+    // offset and read nothing.
+    let offsets = storage_driver_manager
+        .shard_offsets_by_timestamp(
+            DEFAULT_TENANT,
+            METRICS_HISTORY_TOPIC,
+            query.start_time,
+            AdapterOffsetStrategy::Latest,
+        )
+        .await?;
+
+    let read_config = AdapterReadConfig {
+        max_record_num: METRICS_HISTORY_READ_BATCH,
+        max_size: 1024 * 1024 * 10,
+    };
+    let start_ms = query.start_time as u128 * 1000;
+    let end_ms = (query.end_time as u128 + 1) * 1000;
+
+    // Each shard is in time order, so no shard contributes more than `limit`
+    // of the oldest points overall.
+    let mut results = Vec::new();
+    for (shard_name, mut offset) in offsets {
+        let mut shard_points = 0;
+        'shard: loop {
+            let records = storage_driver_manager
+                .read_by_shard_offset(
+                    DEFAULT_TENANT,
+                    METRICS_HISTORY_TOPIC,
+                    &shard_name,
+                    offset,
+                    &read_config,
+                )
+                .await?;
+            if records.is_empty() {
+                break;
+            }
+
+            for record in records {
+                offset = record.metadata.offset + 1;
+                if record.metadata.create_t > query.end_time {
+                    break 'shard;
+                }
+
+                let point: MetricsHistoryPoint = match serde_json::from_slice(&record.data) {
+                    Ok(point) => point,
+                    Err(e) => {
+                        warn!(
+                            "Skipping undecodable metrics history record at offset {} of shard {}: {}",
+                            record.metadata.offset, shard_name, e
+                        );
+                        continue;
+                    }
+                };
+                if point.ts < start_ms || point.ts >= end_ms {
+                    continue;
+                }
+                if query.node_id.is_some_and(|id| id != point.node_id) {
+                    continue;
+                }
+                results.push(point);
+                shard_points += 1;
+                if shard_points >= query.limit {
+                    break 'shard;
+                }
+            }
+        }
+    }
+
+    results.sort_by_key(|point| point.ts);
+    results.truncate(query.limit);
+    Ok(results)
+}
+
+pub async fn start_metrics_history_thread(
+    storage_driver_manager: Arc<StorageDriverManager>,
+    interval_ms: u64,
+    stop_send: broadcast::Sender<bool>,
+) {
+    let ac_fn = async || -> ResultCommonError {
+        if let Err(e) =
+            save_metrics_snapshot(&storage_driver_manager, MetricsHistorySnapshot::collect()).await
+        {
+            warn!("Failed to save metrics history snapshot: {}", e);
+        }
+        Ok(())
+    };
+    loop_select_ticket(ac_fn, interval_ms, &stop_send).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common_base::tools::now_second;
+    use common_config::broker::{default_broker_config, init_broker_conf_by_config};
+    use storage_adapter::storage::{test_add_topic, test_build_storage_driver_manager};
+
+    #[tokio::test]
+    async fn test_read_metrics_history_by_range() {
+        init_broker_conf_by_config(default_broker_config());
+        let storage_driver_manager = test_build_storage_driver_manager().await.unwrap();
+        test_add_topic(&storage_driver_manager, METRICS_HISTORY_TOPIC);
+
+        for connections in 1..=3 {
+            let snapshot = MetricsHistorySnapshot {
+                connections,
+                ..Default::default()
+            };
+            save_metrics_snapshot(&storage_driver_manager, snapshot)
+                .await
+                .unwrap();
+        }
+
+        let now = now_second();
+        let mut query = MetricsHistoryQuery {
+            start_time: now - 60,
+            end_time: now + 60,
+            node_id: None,
+            limit: 2,
+        };
+        let points = read_metrics_history(&storage_driver_manager, &query)
+            .await
+            .unwrap();
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].value.connections, 1);
+        assert_eq!(points[1].value.connections, 2);
+
+        query.node_id = Some(points[0].node_id + 1);
+        let points = read_metrics_history(&storage_driver_manager, &query)
+            .await
+            .unwrap();
+        assert!(points.is_empty());
+
+        query.start_time = now + 61;
+        query.end_time = now + 120;
+        query.node_id = None;
+        let points = read_metrics_history(&storage_driver_manager, &query)
+            .await
+            .unwrap();
+        assert!(points.is_empty());
+    }
+}
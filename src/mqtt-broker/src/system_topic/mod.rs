@@ -16,6 +16,7 @@
 use crate::core::error::MqttBrokerError;
 use crate::core::topic::try_init_topic;
 use crate::storage::message::MessageStorage;
+use crate::system_topic::slow_request::report_broker_slow_requests;
 use crate::system_topic::stats::route::report_broker_stat_routes;
 use bytes::Bytes;
 use common_base::error::ResultCommonError;
@@ -41,6 +42,8 @@
 pub(crate) const SYSTEM_TOPIC_BROKERS_METRICS_BYTES: &str = "$SYS/brokers/metrics/bytes";
 pub(crate) const SYSTEM_TOPIC_BROKERS_METRICS_MESSAGES: &str = "$SYS/brokers/metrics/messages";
 pub(crate) const SYSTEM_TOPIC_BROKERS_METRICS_PACKETS: &str = "$SYS/brokers/metrics/packets";
+pub(crate) const SYSTEM_TOPIC_BROKERS_METRICS_SUBSCRIPTIONS_LAG: &str =
+    "$SYS/brokers/metrics/subscriptions/lag";
 
 // Stats topics
 pub(crate) const SYSTEM_TOPIC_BROKERS_STATS_CONNECTIONS: &str = "$SYS/brokers/stats/connections";
@@ -48,9 +51,17 @@
 pub(crate) const SYSTEM_TOPIC_BROKERS_STATS_SUBSCRIPTIONS: &str =
     "$SYS/brokers/stats/subscriptions";
 pub(crate) const SYSTEM_TOPIC_BROKERS_STATS_TOPICS: &str = "$SYS/brokers/stats/topics";
+// Topics that received the most messages on this node since the last report
+pub(crate) const SYSTEM_TOPIC_BROKERS_STATS_TOPICS_HOT: &str = "$SYS/brokers/stats/topics/hot";
+
+// Inbound packets that exceeded the slow request threshold since the last report
+pub(crate) const SYSTEM_TOPIC_BROKERS_SLOW_REQUESTS: &str = "$SYS/brokers/slow_requests";
 
 pub mod broker;
+pub mod client_event;
+pub mod metrics_history;
 pub mod packet;
+pub mod slow_request;
 pub mod stats;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -111,6 +122,13 @@ pub async fn start_thread(&self, stop_send: broadcast::Sender<bool>) {
             )
             .await;
 
+            report_broker_slow_requests(
+                &self.client_pool,
+                &self.metadata_cache,
+                &self.storage_driver_manager,
+            )
+            .await;
+
             Ok(())
         };
 
@@ -178,6 +196,12 @@ pub(crate) async fn report_stats_info(
     //topics
     stats::topics::report_broker_stat_topics(client_pool, metadata_cache, storage_driver_manager)
         .await;
+    stats::topics::report_broker_stat_hot_topics(
+        client_pool,
+        metadata_cache,
+        storage_driver_manager,
+    )
+    .await;
 }
 
 pub(crate) fn build_system_topic_payload<T: Serialize>(
@@ -0,0 +1,40 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::core::cache::MQTTCacheManager;
+use crate::core::consumer_lag::ConsumerGroupLag;
+use crate::system_topic::report_system_data;
+use grpc_clients::pool::ClientPool;
+use std::sync::Arc;
+use storage_adapter::driver::StorageDriverManager;
+
+use crate::system_topic::SYSTEM_TOPIC_BROKERS_METRICS_SUBSCRIPTIONS_LAG;
+
+/// Publishes the lag of every subscription group served by this broker,
+/// with the per-shard breakdown, to `$SYS/brokers/metrics/subscriptions/lag`.
+pub(crate) async fn report_broker_metrics_subscriptions_lag(
+    client_pool: &Arc<ClientPool>,
+    metadata_cache: &Arc<MQTTCacheManager>,
+    storage_driver_manager: &Arc<StorageDriverManager>,
+    lags: Vec<ConsumerGroupLag>,
+) {
+    report_system_data(
+        client_pool,
+        metadata_cache,
+        storage_driver_manager,
+        SYSTEM_TOPIC_BROKERS_METRICS_SUBSCRIPTIONS_LAG,
+        || async move { lags },
+    )
+    .await;
+}
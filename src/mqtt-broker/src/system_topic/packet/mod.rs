@@ -13,5 +13,6 @@
 // limitations under the License.
 
 pub(crate) mod bytes;
+pub(crate) mod lag;
 pub(crate) mod messages;
 pub(crate) mod packets;
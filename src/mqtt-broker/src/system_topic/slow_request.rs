@@ -0,0 +1,41 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::core::cache::MQTTCacheManager;
+use crate::system_topic::{report_system_data, SYSTEM_TOPIC_BROKERS_SLOW_REQUESTS};
+use grpc_clients::pool::ClientPool;
+use std::sync::Arc;
+use storage_adapter::driver::StorageDriverManager;
+
+/// Publishes the slow requests recorded since the previous report to
+/// `$SYS/brokers/slow_requests`. Nothing is published when there are none.
+pub(crate) async fn report_broker_slow_requests(
+    client_pool: &Arc<ClientPool>,
+    metadata_cache: &Arc<MQTTCacheManager>,
+    storage_driver_manager: &Arc<StorageDriverManager>,
+) {
+    let slow_requests = metadata_cache.slow_request_log.take_unreported();
+    if slow_requests.is_empty() {
+        return;
+    }
+
+    report_system_data(
+        client_pool,
+        metadata_cache,
+        storage_driver_manager,
+        SYSTEM_TOPIC_BROKERS_SLOW_REQUESTS,
+        || async move { slow_requests },
+    )
+    .await;
+}
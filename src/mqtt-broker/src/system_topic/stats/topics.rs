@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use crate::core::cache::MQTTCacheManager;
+use crate::core::topic_stats::TOPIC_STATS_HOT_TOPICS;
 use crate::system_topic::report_system_data;
 use common_metrics::mqtt::statistics::record_mqtt_topics_get;
 use grpc_clients::pool::ClientPool;
@@ -20,7 +21,9 @@
 use std::sync::Arc;
 use storage_adapter::driver::StorageDriverManager;
 
-use crate::system_topic::SYSTEM_TOPIC_BROKERS_STATS_TOPICS;
+use crate::system_topic::{
+    SYSTEM_TOPIC_BROKERS_STATS_TOPICS, SYSTEM_TOPIC_BROKERS_STATS_TOPICS_HOT,
+};
 
 /// Topic statistics published as a single JSON payload to `$SYS/brokers/stats/topics`.
 #[derive(Debug, Serialize)]
@@ -54,3 +57,26 @@ pub(crate) async fn report_broker_stat_topics(
     )
     .await;
 }
+
+/// Publishes the topics that received the most messages since the previous
+/// report to `$SYS/brokers/stats/topics/hot`. Nothing is published when no
+/// message arrived.
+pub(crate) async fn report_broker_stat_hot_topics(
+    client_pool: &Arc<ClientPool>,
+    metadata_cache: &Arc<MQTTCacheManager>,
+    storage_driver_manager: &Arc<StorageDriverManager>,
+) {
+    let hot_topics = metadata_cache.topic_stats.take_hot(TOPIC_STATS_HOT_TOPICS);
+    if hot_topics.is_empty() {
+        return;
+    }
+
+    report_system_data(
+        client_pool,
+        metadata_cache,
+        storage_driver_manager,
+        SYSTEM_TOPIC_BROKERS_STATS_TOPICS_HOT,
+        || async move { hot_topics },
+    )
+    .await;
+}
@@ -0,0 +1,98 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use base64::Engine;
+use protocol::mqtt::common::{Publish, QoS};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PayloadEncoding {
+    /// UTF-8 text, sent as is.
+    Plain,
+    Base64,
+}
+
+/// One message as a browser receives it, the data of an SSE `message` event
+/// or the text of a WebSocket frame.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WebMessage {
+    pub topic: String,
+    pub payload: String,
+    pub encoding: PayloadEncoding,
+    pub qos: u8,
+    pub retain: bool,
+    /// Time the endpoint received the message, in milliseconds.
+    pub timestamp: u64,
+}
+
+impl WebMessage {
+    pub fn from_publish(publish: &Publish, timestamp: u64) -> Self {
+        let (payload, encoding) = match std::str::from_utf8(&publish.payload) {
+            Ok(text) => (text.to_string(), PayloadEncoding::Plain),
+            Err(_) => (
+                BASE64_STANDARD.encode(&publish.payload),
+                PayloadEncoding::Base64,
+            ),
+        };
+        WebMessage {
+            topic: String::from_utf8_lossy(&publish.topic).to_string(),
+            payload,
+            encoding,
+            qos: match publish.qos {
+                QoS::AtMostOnce => 0,
+                QoS::AtLeastOnce => 1,
+                QoS::ExactlyOnce => 2,
+            },
+            retain: publish.retain,
+            timestamp,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_publish() {
+        let publish = Publish {
+            topic: "sensors/t1".into(),
+            payload: "21.5".into(),
+            qos: QoS::AtLeastOnce,
+            retain: true,
+            ..Default::default()
+        };
+        let message = WebMessage::from_publish(&publish, 1000);
+        assert_eq!(
+            message.to_json(),
+            r#"{"topic":"sensors/t1","payload":"21.5","encoding":"plain","qos":1,"retain":true,"timestamp":1000}"#
+        );
+
+        let publish = Publish {
+            topic: "bin".into(),
+            payload: vec![0xff, 0x00].into(),
+            ..Default::default()
+        };
+        let message = WebMessage::from_publish(&publish, 1000);
+        assert_eq!(message.encoding, PayloadEncoding::Base64);
+        assert_eq!(message.payload, "/wA=");
+        assert_eq!(message.qos, 0);
+    }
+}
@@ -0,0 +1,26 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Web subscribe endpoint.
+//!
+//! Lets a browser consume topic filters directly, as Server-Sent Events or
+//! WebSocket JSON frames, without an MQTT client library. The browser presents
+//! a token naming an MQTT user; each browser connection is served as an MQTT
+//! connection of that user, so its subscriptions go through the usual ACL
+//! checks and its messages through the usual push path.
+
+pub mod message;
+pub mod server;
+pub mod session;
+pub mod token;
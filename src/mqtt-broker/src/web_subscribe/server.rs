@@ -0,0 +1,261 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::message::WebMessage;
+use super::session::{WebSubscribeContext, WebSubscriber};
+use super::token::{request_token, verify_token};
+use crate::core::cache::MQTTCacheManager;
+use crate::core::error::MqttBrokerError;
+use crate::core::tool::ResultMqttBrokerError;
+use axum::extract::ws::{Message, WebSocket};
+use axum::extract::{ConnectInfo, Query, State, WebSocketUpgrade};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use bytes::Bytes;
+use common_config::broker::broker_config;
+use futures::stream;
+use futures_util::{SinkExt, StreamExt};
+use network_server::command::ArcCommandAdapter;
+use network_server::common::connection_manager::ConnectionManager;
+use protocol::mqtt::common::QoS;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::select;
+use tokio::sync::{broadcast, mpsc};
+use tower_http::cors::CorsLayer;
+use tracing::{debug, info};
+
+pub const ROUTE_SSE: &str = "/subscribe/sse";
+pub const ROUTE_WS: &str = "/subscribe/ws";
+
+/// HTTP listener of the web subscribe endpoint.
+pub struct WebSubscribeServer {
+    context: WebSubscribeContext,
+}
+
+impl WebSubscribeServer {
+    pub fn new(
+        command: ArcCommandAdapter,
+        cache_manager: Arc<MQTTCacheManager>,
+        connection_manager: Arc<ConnectionManager>,
+        stop_sx: broadcast::Sender<bool>,
+    ) -> Self {
+        WebSubscribeServer {
+            context: WebSubscribeContext {
+                command,
+                cache_manager,
+                connection_manager,
+                stop_sx,
+            },
+        }
+    }
+
+    pub async fn start(&self) -> ResultMqttBrokerError {
+        let conf = broker_config();
+        if conf.mqtt_web_subscribe.jwt_secret.is_empty() {
+            return Err(MqttBrokerError::CommonError(
+                "mqtt_web_subscribe.jwt_secret must be set to enable the web subscribe endpoint"
+                    .to_string(),
+            ));
+        }
+
+        let addr = format!("0.0.0.0:{}", conf.mqtt_web_subscribe.port);
+        let listener = tokio::net::TcpListener::bind(&addr).await.map_err(|e| {
+            MqttBrokerError::CommonError(format!(
+                "Failed to bind web subscribe endpoint to {}: {}",
+                addr, e
+            ))
+        })?;
+        let app = Router::new()
+            .route(ROUTE_SSE, get(sse_handler))
+            .route(ROUTE_WS, get(ws_handler))
+            .with_state(self.context.clone())
+            .layer(CorsLayer::permissive());
+
+        let mut stop_rx = self.context.stop_sx.subscribe();
+        tokio::spawn(Box::pin(async move {
+            let shutdown = async move {
+                while let Ok(flag) = stop_rx.recv().await {
+                    if flag {
+                        break;
+                    }
+                }
+            };
+            if let Err(e) = axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .with_graceful_shutdown(shutdown)
+            .await
+            {
+                debug!("Web subscribe endpoint stopped with error: {}", e);
+            }
+        }));
+        info!(
+            "Web subscribe endpoint started successfully, listening port: {}",
+            conf.mqtt_web_subscribe.port
+        );
+        Ok(())
+    }
+}
+
+async fn sse_handler(
+    State(context): State<WebSubscribeContext>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Query(params): Query<Vec<(String, String)>>,
+) -> Response {
+    let frames = match open_subscription(context, addr, &headers, &params).await {
+        Ok(frames) => frames,
+        Err(resp) => return resp,
+    };
+
+    let events = stream::unfold(frames, |mut frames| async move {
+        let message = frames.recv().await?;
+        let event = Event::default().event("message").data(message.to_json());
+        Some((Ok::<_, Infallible>(event), frames))
+    });
+    let conf = broker_config();
+    Sse::new(events)
+        .keep_alive(
+            KeepAlive::new().interval(Duration::from_secs(conf.mqtt_web_subscribe.keep_alive_sec)),
+        )
+        .into_response()
+}
+
+async fn ws_handler(
+    ws: WebSocketUpgrade,
+    State(context): State<WebSubscribeContext>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Query(params): Query<Vec<(String, String)>>,
+) -> Response {
+    // Subscribe before upgrading, so auth and ACL failures are plain HTTP
+    // errors the browser can see.
+    let frames = match open_subscription(context, addr, &headers, &params).await {
+        Ok(frames) => frames,
+        Err(resp) => return resp,
+    };
+    ws.on_upgrade(move |socket| handle_socket(socket, frames))
+}
+
+/// Sends frames until either side goes away. Dropping `frames` on return
+/// closes the MQTT connection behind it.
+async fn handle_socket(socket: WebSocket, mut frames: mpsc::Receiver<WebMessage>) {
+    let conf = broker_config();
+    let (mut sender, mut receiver) = socket.split();
+    let mut ping = tokio::time::interval(Duration::from_secs(
+        conf.mqtt_web_subscribe.keep_alive_sec.max(1),
+    ));
+    loop {
+        select! {
+            val = frames.recv() => {
+                let Some(message) = val else {
+                    let _ = sender.send(Message::Close(None)).await;
+                    break;
+                };
+                if sender.send(Message::Text(message.to_json().into())).await.is_err() {
+                    break;
+                }
+            }
+            val = receiver.next() => {
+                match val {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    // The endpoint is receive only; anything else from the browser is ignored.
+                    Some(Ok(_)) => {}
+                }
+            }
+            _ = ping.tick() => {
+                if sender.send(Message::Ping(Bytes::new())).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Authenticates the request and subscribes on behalf of the browser. On
+/// success the returned receiver yields the messages to forward.
+async fn open_subscription(
+    context: WebSubscribeContext,
+    addr: SocketAddr,
+    headers: &HeaderMap,
+    params: &[(String, String)],
+) -> Result<mpsc::Receiver<WebMessage>, Response> {
+    let conf = broker_config();
+    let param = |name: &str| {
+        params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    };
+
+    let Some(token) = request_token(headers, param("token")) else {
+        return Err((StatusCode::UNAUTHORIZED, "Missing token".to_string()).into_response());
+    };
+    let claims = verify_token(&token, &conf.mqtt_web_subscribe.jwt_secret)
+        .map_err(|e| (StatusCode::UNAUTHORIZED, e.to_string()).into_response())?;
+
+    let topics: Vec<String> = params
+        .iter()
+        .filter(|(key, value)| key == "topic" && !value.is_empty())
+        .map(|(_, value)| value.clone())
+        .collect();
+    if topics.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "At least one topic parameter is required".to_string(),
+        )
+            .into_response());
+    }
+    if topics.len() > conf.mqtt_web_subscribe.max_topics_per_connection {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "At most {} topics can be subscribed per connection",
+                conf.mqtt_web_subscribe.max_topics_per_connection
+            ),
+        )
+            .into_response());
+    }
+    let qos = match param("qos").unwrap_or("0") {
+        "0" => QoS::AtMostOnce,
+        "1" => QoS::AtLeastOnce,
+        "2" => QoS::ExactlyOnce,
+        other => {
+            return Err((StatusCode::BAD_REQUEST, format!("Invalid qos {}", other)).into_response());
+        }
+    };
+
+    let subscriber =
+        WebSubscriber::open(context, addr, &claims.sub, claims.client_id, &topics, qos)
+            .await
+            .map_err(|e| (StatusCode::FORBIDDEN, e.to_string()).into_response())?;
+    debug!(
+        "Web subscriber {} of user {} at {} subscribed to {:?}",
+        subscriber.client_id(),
+        claims.sub,
+        addr,
+        topics
+    );
+
+    let (frames_sx, frames_rx) = mpsc::channel(conf.mqtt_web_subscribe.send_buffer_size.max(1));
+    tokio::spawn(Box::pin(subscriber.run(frames_sx)));
+    Ok(frames_rx)
+}
@@ -0,0 +1,256 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::message::WebMessage;
+use crate::core::cache::MQTTCacheManager;
+use crate::core::error::MqttBrokerError;
+use common_base::tools::now_millis;
+use common_config::broker::broker_config;
+use metadata_struct::connection::{NetworkConnection, NetworkConnectionType};
+use network_server::command::ArcCommandAdapter;
+use network_server::common::connection_manager::ConnectionManager;
+use protocol::mqtt::common::{
+    Connect, ConnectReturnCode, Disconnect, Filter, MqttPacket, PingReq, PubAck, PubComp, PubRec,
+    QoS, Subscribe, SubscribeReasonCode,
+};
+use protocol::robust::{RobustMQPacket, RobustMQPacketWrapper, RobustMQProtocol};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::select;
+use tokio::sync::{broadcast, mpsc};
+use tracing::debug;
+
+const SUBSCRIBE_PACKET_ID: u16 = 1;
+const OUTBOUND_CHANNEL_SIZE: usize = 1000;
+
+#[derive(Clone)]
+pub struct WebSubscribeContext {
+    pub command: ArcCommandAdapter,
+    pub cache_manager: Arc<MQTTCacheManager>,
+    pub connection_manager: Arc<ConnectionManager>,
+    pub stop_sx: broadcast::Sender<bool>,
+}
+
+/// MQTT side of one browser connection: a virtual connection logged in as the
+/// token user and subscribed to the requested filters. Messages pushed to it
+/// are acknowledged here and handed to the HTTP side as [`WebMessage`]s.
+pub struct WebSubscriber {
+    context: WebSubscribeContext,
+    network: NetworkConnection,
+    addr: SocketAddr,
+    client_id: String,
+    outbound: mpsc::Receiver<RobustMQPacketWrapper>,
+}
+
+impl WebSubscriber {
+    /// Connects and subscribes, failing when the broker refuses the
+    /// connection or the ACL denies any of the filters.
+    pub async fn open(
+        context: WebSubscribeContext,
+        addr: SocketAddr,
+        username: &str,
+        client_id: Option<String>,
+        filters: &[String],
+        qos: QoS,
+    ) -> Result<Self, MqttBrokerError> {
+        let conf = broker_config();
+        let mut network = NetworkConnection::new(NetworkConnectionType::WebSubscribe, addr, None);
+        network.set_protocol(RobustMQProtocol::MQTT4);
+        network.authenticated_user = Some(username.to_string());
+        let connect_id = context.connection_manager.add_connection(network.clone());
+
+        let (outbound_sx, outbound_rx) = mpsc::channel(OUTBOUND_CHANNEL_SIZE);
+        context
+            .connection_manager
+            .add_web_subscribe_write(connect_id, outbound_sx);
+
+        let client_id =
+            client_id.unwrap_or_else(|| format!("web-{}-{}", conf.broker_id, connect_id));
+        let subscriber = WebSubscriber {
+            context,
+            network,
+            addr,
+            client_id,
+            outbound: outbound_rx,
+        };
+        if let Err(e) = subscriber.subscribe(filters, qos).await {
+            subscriber.close().await;
+            return Err(e);
+        }
+        Ok(subscriber)
+    }
+
+    pub fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    async fn subscribe(&self, filters: &[String], qos: QoS) -> Result<(), MqttBrokerError> {
+        let conf = broker_config();
+        let keep_alive = conf.mqtt_web_subscribe.keep_alive_sec.saturating_mul(2);
+        let packet = MqttPacket::Connect(
+            RobustMQProtocol::MQTT4.to_u8(),
+            Connect {
+                keep_alive: keep_alive.min(u16::MAX as u64) as u16,
+                client_id: self.client_id.clone(),
+                clean_session: true,
+            },
+            None,
+            None,
+            None,
+            None,
+        );
+        match self.apply(packet).await {
+            Some(MqttPacket::ConnAck(ack, _)) if ack.code == ConnectReturnCode::Success => {}
+            resp => {
+                debug!(
+                    "Web subscriber {} was refused by the broker: {:?}",
+                    self.client_id, resp
+                );
+                return Err(MqttBrokerError::CommonError(
+                    "Connection refused by the broker".to_string(),
+                ));
+            }
+        }
+
+        let packet = MqttPacket::Subscribe(
+            Subscribe {
+                packet_identifier: SUBSCRIBE_PACKET_ID,
+                filters: filters
+                    .iter()
+                    .map(|path| Filter {
+                        path: path.clone(),
+                        qos,
+                        ..Default::default()
+                    })
+                    .collect(),
+            },
+            None,
+        );
+        let return_codes = match self.apply(packet).await {
+            Some(MqttPacket::SubAck(ack, _)) => ack.return_codes,
+            _ => Vec::new(),
+        };
+        for (i, path) in filters.iter().enumerate() {
+            let granted = matches!(
+                return_codes.get(i),
+                Some(SubscribeReasonCode::QoS0)
+                    | Some(SubscribeReasonCode::QoS1)
+                    | Some(SubscribeReasonCode::QoS2)
+                    | Some(SubscribeReasonCode::Success(_))
+            );
+            if !granted {
+                return Err(MqttBrokerError::CommonError(format!(
+                    "Not authorized to subscribe to {}",
+                    path
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Pumps messages into `frames` until the HTTP side drops the receiver,
+    /// the broker closes the connection or the broker stops. When `frames` is
+    /// full the message is dropped rather than stalling the push.
+    pub async fn run(mut self, frames: mpsc::Sender<WebMessage>) {
+        let conf = broker_config();
+        let mut ping = tokio::time::interval(Duration::from_secs(
+            conf.mqtt_web_subscribe.keep_alive_sec.max(1),
+        ));
+        let mut stop_rx = self.context.stop_sx.subscribe();
+        let mut dropped: u64 = 0;
+        loop {
+            select! {
+                val = stop_rx.recv() => {
+                    if let Ok(true) = val {
+                        break;
+                    }
+                }
+                _ = frames.closed() => break,
+                _ = ping.tick() => {
+                    self.apply(MqttPacket::PingReq(PingReq)).await;
+                }
+                val = self.outbound.recv() => {
+                    let Some(wrapper) = val else {
+                        break;
+                    };
+                    let Some(packet) = wrapper.packet.get_mqtt_packet() else {
+                        continue;
+                    };
+                    match packet {
+                        MqttPacket::Publish(publish, _) => {
+                            let message = WebMessage::from_publish(&publish, now_millis() as u64);
+                            if frames.try_send(message).is_err() {
+                                dropped += 1;
+                            }
+                            self.ack(publish.qos, publish.p_kid).await;
+                        }
+                        MqttPacket::PubRel(rel, _) => {
+                            self.apply(MqttPacket::PubComp(
+                                PubComp {
+                                    pkid: rel.pkid,
+                                    reason: None,
+                                },
+                                None,
+                            ))
+                            .await;
+                        }
+                        MqttPacket::Disconnect(_, _) => break,
+                        _ => {}
+                    }
+                }
+            }
+        }
+        if dropped > 0 {
+            debug!(
+                "Web subscriber {} dropped {} messages the browser did not keep up with",
+                self.client_id, dropped
+            );
+        }
+        self.close().await;
+    }
+
+    async fn ack(&self, qos: QoS, pkid: u16) {
+        let packet = match qos {
+            QoS::AtMostOnce => return,
+            QoS::AtLeastOnce => MqttPacket::PubAck(PubAck { pkid, reason: None }, None),
+            QoS::ExactlyOnce => MqttPacket::PubRec(PubRec { pkid, reason: None }, None),
+        };
+        self.apply(packet).await;
+    }
+
+    async fn close(self) {
+        self.apply(MqttPacket::Disconnect(
+            Disconnect { reason_code: None },
+            None,
+        ))
+        .await;
+        self.context
+            .connection_manager
+            .close_connect(self.network.connection_id)
+            .await;
+        self.context
+            .cache_manager
+            .remove_connection(self.network.connection_id);
+    }
+
+    async fn apply(&self, packet: MqttPacket) -> Option<MqttPacket> {
+        let resp = self
+            .context
+            .command
+            .apply(&self.network, &self.addr, &RobustMQPacket::MQTT(packet))
+            .await?;
+        resp.packet.get_mqtt_packet()
+    }
+}
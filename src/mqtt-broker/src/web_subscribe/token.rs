@@ -0,0 +1,100 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::core::error::MqttBrokerError;
+use axum::http::{header::AUTHORIZATION, HeaderMap};
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebSubscribeClaims {
+    /// MQTT username the browser subscribes as.
+    pub sub: String,
+    pub exp: u64,
+    /// Client id of the MQTT connection, generated when absent.
+    #[serde(default)]
+    pub client_id: Option<String>,
+}
+
+pub fn verify_token(token: &str, secret: &str) -> Result<WebSubscribeClaims, MqttBrokerError> {
+    let data = decode::<WebSubscribeClaims>(
+        token,
+        &DecodingKey::from_secret(secret.as_bytes()),
+        &Validation::new(Algorithm::HS256),
+    )
+    .map_err(|e| MqttBrokerError::CommonError(format!("Invalid token: {}", e)))?;
+    if data.claims.sub.is_empty() {
+        return Err(MqttBrokerError::CommonError(
+            "Invalid token: empty sub claim".to_string(),
+        ));
+    }
+    Ok(data.claims)
+}
+
+/// Token of a request: `Authorization: Bearer`, or the `token` query
+/// parameter for EventSource, which cannot set headers.
+pub fn request_token(headers: &HeaderMap, query_token: Option<&str>) -> Option<String> {
+    let bearer = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string());
+    bearer.or_else(|| query_token.map(|token| token.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common_base::tools::now_second;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+
+    fn token(sub: &str, exp: u64, secret: &str) -> String {
+        let claims = WebSubscribeClaims {
+            sub: sub.to_string(),
+            exp,
+            client_id: None,
+        };
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(secret.as_bytes()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_verify_token() {
+        let exp = now_second() + 3600;
+        let claims = verify_token(&token("dashboard", exp, "secret"), "secret").unwrap();
+        assert_eq!(claims.sub, "dashboard");
+        assert!(claims.client_id.is_none());
+
+        assert!(verify_token(&token("dashboard", exp, "secret"), "other").is_err());
+        assert!(
+            verify_token(&token("dashboard", now_second() - 3600, "secret"), "secret").is_err()
+        );
+        assert!(verify_token(&token("", exp, "secret"), "secret").is_err());
+        assert!(verify_token("not-a-token", "secret").is_err());
+    }
+
+    #[test]
+    fn test_request_token() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(request_token(&headers, None), None);
+        assert_eq!(request_token(&headers, Some("q")).as_deref(), Some("q"));
+
+        headers.insert(AUTHORIZATION, "Bearer h".parse().unwrap());
+        assert_eq!(request_token(&headers, Some("q")).as_deref(), Some("h"));
+    }
+}
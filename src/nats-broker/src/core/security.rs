@@ -21,7 +21,7 @@
 /// supplied credentials pass the password check.  Returns `false` if
 /// `auth_required` is `true` but no credentials were provided or the
 /// password check fails.
-pub fn login_check(
+pub async fn login_check(
     security_manager: &Arc<SecurityManager>,
     tenant: &str,
     auth_required: bool,
@@ -33,7 +33,7 @@ pub fn login_check(
     }
 
     match (user, pass) {
-        (Some(u), Some(p)) => password_check_by_login(security_manager, tenant, u, p),
+        (Some(u), Some(p)) => password_check_by_login(security_manager, tenant, u, p).await,
         _ => false,
     }
 }
@@ -95,7 +95,7 @@ pub async fn try_get_or_init_mq9_subject(
             conf.runtime.default_topic_replica_num,
         ));
 
-    create_topic_full(
+    let topic = create_topic_full(
         &cache_manager.node_cache,
         storage_driver_manager,
         client_pool,
@@ -124,7 +124,7 @@ pub async fn try_get_or_init_nats_core_subject(
             conf.runtime.default_topic_replica_num,
         ));
 
-    create_topic_full(
+    let topic = create_topic_full(
         &cache_manager.node_cache,
         storage_driver_manager,
         client_pool,
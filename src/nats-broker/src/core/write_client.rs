@@ -69,6 +69,14 @@ pub async fn write_nats_packet(
                 .await
                 .map_err(|e| NatsBrokerError::CommonError(e.to_string()))?;
         }
+        NetworkConnectionType::MqttSn
+        | NetworkConnectionType::CoAP
+        | NetworkConnectionType::WebSubscribe => {
+            return Err(NatsBrokerError::CommonError(format!(
+                "connection {} is not a NATS connection",
+                connect_id
+            )));
+        }
     }
 
     Ok(())
@@ -111,7 +111,7 @@ async fn apply(
             NatsPacket::Connect(req) => {
                 // verbose is not yet in cache when CONNECT arrives; read from req directly
                 let verbose = req.verbose;
-                match connect::process_connect(&ctx, req) {
+                match connect::process_connect(&ctx, req).await {
                     Ok(()) => verbose.then_some(NatsPacket::Ok),
                     Err(e) => Some(e),
                 }
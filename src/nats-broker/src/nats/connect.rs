@@ -21,7 +21,10 @@
 use crate::core::tenant::get_tenant;
 use crate::handler::command::NatsProcessContext;
 
-pub fn process_connect(ctx: &NatsProcessContext, req: &ClientConnect) -> Result<(), NatsPacket> {
+pub async fn process_connect(
+    ctx: &NatsProcessContext,
+    req: &ClientConnect,
+) -> Result<(), NatsPacket> {
     let auth_required = broker_config().nats_runtime.auth_required;
 
     let authed = login_check(
@@ -30,7 +33,8 @@ pub fn process_connect(ctx: &NatsProcessContext, req: &ClientConnect) -> Result<
         auth_required,
         req.user.as_deref(),
         req.pass.as_deref(),
-    );
+    )
+    .await;
 
     if !authed {
         return Err(NatsPacket::Err(
@@ -0,0 +1,27 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Raw bytes into the MQTT decoder, starting from every state a connection
+//! can be in: before CONNECT and after an MQTT 3.1, 3.1.1 or 5 CONNECT.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use protocol_fuzz::decode_mqtt_stream;
+
+fuzz_target!(|data: &[u8]| {
+    for protocol_version in [None, Some(3), Some(4), Some(5)] {
+        decode_mqtt_stream(protocol_version, data);
+    }
+});
@@ -0,0 +1,32 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Generated MQTT sessions into both the MQTT decoder and the codec the
+//! broker's MQTT listeners use, checking the latter does not depend on how
+//! the stream is segmented.
+
+#![no_main]
+
+use libfuzzer_sys::arbitrary::Unstructured;
+use libfuzzer_sys::fuzz_target;
+use protocol_fuzz::{check_broker_segmentation, decode_mqtt_stream, PacketGenerator};
+
+fuzz_target!(|data: &[u8]| {
+    let mut u = Unstructured::new(data);
+    let Ok(session) = PacketGenerator::new(&mut u).session() else {
+        return;
+    };
+    decode_mqtt_stream(None, &session);
+    check_broker_segmentation(&session);
+});
@@ -0,0 +1,25 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Raw bytes into the codec the broker's MQTT listeners use, replayed with
+//! several segmentations. Every decode error must map to a DISCONNECT reason.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use protocol_fuzz::check_broker_segmentation;
+
+fuzz_target!(|data: &[u8]| {
+    check_broker_segmentation(data);
+});
@@ -0,0 +1,452 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Shared pieces of the MQTT fuzz targets: a structured-random generator that
+//! turns fuzzer input into mostly well-formed MQTT 3.1.1 / 5 sessions, and the
+//! decode loops that check the codec invariants the broker relies on.
+
+use bytes::{BufMut, BytesMut};
+use common_base::error::common::CommonError;
+use common_base::error::mqtt_protocol_error::MQTTProtocolError;
+use libfuzzer_sys::arbitrary::{Result, Unstructured};
+use protocol::codec::{RobustMQCodec, RobustMQCodecWrapper};
+use protocol::mqtt::codec::{decode_error_reason_code, MqttCodec, MqttPacketWrapper};
+use protocol::mqtt::common::MqttPacket;
+
+/// Chunk sizes used to replay a stream the way TCP may segment it.
+pub const CHUNK_SIZES: [usize; 5] = [1, 2, 3, 7, 64];
+
+const PROTOCOL_VERSIONS: [u8; 3] = [3, 4, 5];
+
+const TOPICS: [&str; 8] = [
+    "",
+    "a/b",
+    "sensor/+/temperature",
+    "sensor/#",
+    "$share/group/t/#",
+    "$SYS/brokers",
+    "/",
+    "a//b",
+];
+
+#[derive(Clone, Copy)]
+enum PropertyValue {
+    Byte,
+    TwoByte,
+    FourByte,
+    VarInt,
+    Utf8,
+    Binary,
+    Utf8Pair,
+}
+
+/// MQTT 5 property identifiers and the wire type of their value.
+const PROPERTIES: [(u8, PropertyValue); 27] = [
+    (1, PropertyValue::Byte),
+    (2, PropertyValue::FourByte),
+    (3, PropertyValue::Utf8),
+    (8, PropertyValue::Utf8),
+    (9, PropertyValue::Binary),
+    (11, PropertyValue::VarInt),
+    (17, PropertyValue::FourByte),
+    (18, PropertyValue::Utf8),
+    (19, PropertyValue::TwoByte),
+    (21, PropertyValue::Utf8),
+    (22, PropertyValue::Binary),
+    (23, PropertyValue::Byte),
+    (24, PropertyValue::FourByte),
+    (25, PropertyValue::Byte),
+    (26, PropertyValue::Utf8),
+    (28, PropertyValue::Utf8),
+    (31, PropertyValue::Utf8),
+    (33, PropertyValue::TwoByte),
+    (34, PropertyValue::TwoByte),
+    (35, PropertyValue::TwoByte),
+    (36, PropertyValue::Byte),
+    (37, PropertyValue::Byte),
+    (38, PropertyValue::Utf8Pair),
+    (39, PropertyValue::FourByte),
+    (40, PropertyValue::Byte),
+    (41, PropertyValue::Byte),
+    (42, PropertyValue::Byte),
+];
+
+/// Builds MQTT sessions from fuzzer input. Frames are encoded by hand rather
+/// than through the codec so that field combinations the encoder would never
+/// produce still reach the decoder, and a fraction of them is mutated after
+/// framing to hit the error paths.
+pub struct PacketGenerator<'a, 'b> {
+    u: &'b mut Unstructured<'a>,
+    protocol_version: u8,
+}
+
+impl<'a, 'b> PacketGenerator<'a, 'b> {
+    pub fn new(u: &'b mut Unstructured<'a>) -> Self {
+        PacketGenerator {
+            u,
+            protocol_version: 4,
+        }
+    }
+
+    /// A CONNECT followed by up to 16 packets of the negotiated version.
+    pub fn session(&mut self) -> Result<Vec<u8>> {
+        let mut out = BytesMut::new();
+        let connect = self.connect()?;
+        self.push_frame(&mut out, connect)?;
+
+        let count = self.u.int_in_range(0..=16)?;
+        for _ in 0..count {
+            let packet = self.packet()?;
+            self.push_frame(&mut out, packet)?;
+        }
+        Ok(out.to_vec())
+    }
+
+    fn push_frame(&mut self, out: &mut BytesMut, (byte1, body): (u8, BytesMut)) -> Result<()> {
+        let mut frame = BytesMut::new();
+        frame.put_u8(byte1);
+        write_var_int(&mut frame, body.len());
+        frame.extend_from_slice(&body);
+
+        let mut frame = frame.to_vec();
+        if self.u.ratio(1, 8)? {
+            self.mutate(&mut frame)?;
+        }
+        out.extend_from_slice(&frame);
+        Ok(())
+    }
+
+    fn mutate(&mut self, frame: &mut Vec<u8>) -> Result<()> {
+        let index = self.u.choose_index(frame.len())?;
+        match self.u.int_in_range(0..=4)? {
+            0 => {
+                let bit: u8 = self.u.int_in_range(0..=7)?;
+                frame[index] ^= 1 << bit;
+            }
+            1 => frame.truncate(index),
+            2 => frame.insert(index, self.u.arbitrary()?),
+            3 => frame[1] = self.u.arbitrary()?,
+            _ => frame[0] = self.u.arbitrary()?,
+        }
+        Ok(())
+    }
+
+    fn connect(&mut self) -> Result<(u8, BytesMut)> {
+        let version = *self.u.choose(&PROTOCOL_VERSIONS)?;
+        self.protocol_version = version;
+
+        let mut body = BytesMut::new();
+        let name = if self.u.ratio(1, 16)? {
+            self.string()?
+        } else if version == 3 {
+            "MQIsdp".to_string()
+        } else {
+            "MQTT".to_string()
+        };
+        write_str(&mut body, &name);
+        body.put_u8(version);
+
+        let flags: u8 = self.u.arbitrary()?;
+        body.put_u8(flags);
+        body.put_u16(self.u.arbitrary()?);
+        if version == 5 {
+            self.properties(&mut body)?;
+        }
+        let client_id = self.string()?;
+        write_str(&mut body, &client_id);
+
+        if flags & 0b0000_0100 != 0 {
+            if version == 5 {
+                self.properties(&mut body)?;
+            }
+            let topic = self.topic()?;
+            write_str(&mut body, &topic);
+            let payload = self.binary()?;
+            write_bytes(&mut body, &payload);
+        }
+        if flags & 0b1000_0000 != 0 {
+            let username = self.string()?;
+            write_str(&mut body, &username);
+        }
+        if flags & 0b0100_0000 != 0 {
+            let password = self.binary()?;
+            write_bytes(&mut body, &password);
+        }
+        Ok((0x10, body))
+    }
+
+    fn packet(&mut self) -> Result<(u8, BytesMut)> {
+        let v5 = self.protocol_version == 5;
+        let mut body = BytesMut::new();
+        let kind: usize = self.u.int_in_range(0..=9)?;
+        let byte1 = match kind {
+            0 => {
+                let qos: u8 = self.u.int_in_range(0..=2)?;
+                let dup = u8::from(self.u.arbitrary::<bool>()?);
+                let retain = u8::from(self.u.arbitrary::<bool>()?);
+                let topic = self.topic()?;
+                write_str(&mut body, &topic);
+                if qos > 0 {
+                    body.put_u16(self.u.arbitrary()?);
+                }
+                if v5 {
+                    self.properties(&mut body)?;
+                }
+                let payload = self.binary()?;
+                body.extend_from_slice(&payload);
+                0x30 | dup << 3 | qos << 1 | retain
+            }
+            kind @ 1..=4 => {
+                body.put_u16(self.u.arbitrary()?);
+                if v5 && self.u.arbitrary()? {
+                    body.put_u8(self.u.arbitrary()?);
+                    self.properties(&mut body)?;
+                }
+                // PUBACK, PUBREC, PUBREL (with its fixed flags), PUBCOMP
+                [0x40, 0x50, 0x62, 0x70][kind - 1]
+            }
+            5 => {
+                body.put_u16(self.u.arbitrary()?);
+                if v5 {
+                    self.properties(&mut body)?;
+                }
+                for _ in 0..self.u.int_in_range(1..=4)? {
+                    let filter = self.topic()?;
+                    write_str(&mut body, &filter);
+                    let options: u8 = self.u.arbitrary()?;
+                    body.put_u8(if v5 {
+                        options & 0b0011_1111
+                    } else {
+                        options % 3
+                    });
+                }
+                0x82
+            }
+            6 => {
+                body.put_u16(self.u.arbitrary()?);
+                if v5 {
+                    self.properties(&mut body)?;
+                }
+                for _ in 0..self.u.int_in_range(1..=4)? {
+                    let filter = self.topic()?;
+                    write_str(&mut body, &filter);
+                }
+                0xA2
+            }
+            7 => 0xC0,
+            8 => {
+                if v5 && self.u.arbitrary()? {
+                    body.put_u8(self.u.arbitrary()?);
+                    self.properties(&mut body)?;
+                }
+                0xE0
+            }
+            _ => {
+                body.put_u8(self.u.arbitrary::<u8>()? & 0b1);
+                body.put_u8(self.u.arbitrary()?);
+                if v5 {
+                    self.properties(&mut body)?;
+                }
+                0x20
+            }
+        };
+        Ok((byte1, body))
+    }
+
+    fn properties(&mut self, body: &mut BytesMut) -> Result<()> {
+        let mut props = BytesMut::new();
+        for _ in 0..self.u.int_in_range(0..=4)? {
+            let (id, value) = if self.u.ratio(1, 32)? {
+                (self.u.arbitrary()?, PropertyValue::Byte)
+            } else {
+                *self.u.choose(&PROPERTIES)?
+            };
+            props.put_u8(id);
+            match value {
+                PropertyValue::Byte => props.put_u8(self.u.arbitrary()?),
+                PropertyValue::TwoByte => props.put_u16(self.u.arbitrary()?),
+                PropertyValue::FourByte => props.put_u32(self.u.arbitrary()?),
+                PropertyValue::VarInt => {
+                    let value = self.u.int_in_range(0..=268_435_455)?;
+                    write_var_int(&mut props, value);
+                }
+                PropertyValue::Utf8 => {
+                    let value = self.string()?;
+                    write_str(&mut props, &value);
+                }
+                PropertyValue::Binary => {
+                    let value = self.binary()?;
+                    write_bytes(&mut props, &value);
+                }
+                PropertyValue::Utf8Pair => {
+                    let key = self.string()?;
+                    write_str(&mut props, &key);
+                    let value = self.string()?;
+                    write_str(&mut props, &value);
+                }
+            }
+        }
+        write_var_int(body, props.len());
+        body.extend_from_slice(&props);
+        Ok(())
+    }
+
+    fn topic(&mut self) -> Result<String> {
+        if self.u.ratio(3, 4)? {
+            return Ok(self.u.choose(&TOPICS)?.to_string());
+        }
+        self.string()
+    }
+
+    fn string(&mut self) -> Result<String> {
+        Ok(String::from_utf8_lossy(&self.binary()?).into_owned())
+    }
+
+    fn binary(&mut self) -> Result<Vec<u8>> {
+        let len = self.u.int_in_range(0..=64usize)?.min(self.u.len());
+        Ok(self.u.bytes(len)?.to_vec())
+    }
+}
+
+/// Decode `data` with an [`MqttCodec`] and check that every decoded packet is
+/// consumed from the buffer, that an incomplete frame is left in place, and
+/// that every packet the codec accepts can be encoded and decoded again.
+pub fn decode_mqtt_stream(protocol_version: Option<u8>, data: &[u8]) -> Vec<MqttPacket> {
+    let mut codec = MqttCodec::new(protocol_version);
+    let mut stream = BytesMut::from(data);
+    let mut packets = Vec::new();
+    while !stream.is_empty() {
+        let before = stream.len();
+        match codec.decode_data(&mut stream) {
+            Ok(Some(packet)) => {
+                assert!(
+                    stream.len() < before,
+                    "decoded {packet:?} without consuming input"
+                );
+                check_round_trip(codec.protocol_version, &packet);
+                packets.push(packet);
+            }
+            Ok(None) => break,
+            Err(MQTTProtocolError::InsufficientBytes(_)) => {
+                assert_eq!(stream.len(), before, "an incomplete frame was consumed");
+                break;
+            }
+            Err(e) => {
+                assert!(
+                    decode_error_reason_code(&e).is_some(),
+                    "decode error {e:?} has no DISCONNECT reason"
+                );
+                break;
+            }
+        }
+    }
+    packets
+}
+
+fn check_round_trip(protocol_version: Option<u8>, packet: &MqttPacket) {
+    let Some(protocol_version) = protocol_version else {
+        return;
+    };
+    let mut codec = MqttCodec::new(Some(protocol_version));
+    let mut buffer = BytesMut::new();
+    let wrapper = MqttPacketWrapper {
+        protocol_version,
+        packet: packet.clone(),
+    };
+    if codec.encode_data(wrapper, &mut buffer).is_err() {
+        return;
+    }
+    let decoded = codec.decode_data(&mut buffer);
+    assert!(
+        decoded.is_ok(),
+        "re-encoded {packet:?} does not decode: {decoded:?}"
+    );
+}
+
+/// Outcome of running the broker codec over a byte stream.
+#[derive(Debug, PartialEq)]
+pub struct BrokerDecode {
+    pub packets: Vec<MqttPacket>,
+    /// The stream ended in a decode error, so the broker would have sent a
+    /// DISCONNECT (MQTT 5) and closed the connection.
+    pub disconnected: bool,
+}
+
+/// Feed `data` to the [`RobustMQCodec`] an MQTT listener uses, `chunk_size`
+/// bytes at a time, the same way the connection read loop does.
+pub fn decode_broker_stream(data: &[u8], chunk_size: usize) -> BrokerDecode {
+    let mut codec = RobustMQCodec::new();
+    let mut stream = BytesMut::new();
+    let mut packets = Vec::new();
+    for chunk in data.chunks(chunk_size.max(1)) {
+        stream.extend_from_slice(chunk);
+        loop {
+            match codec.decode_data(&mut stream) {
+                Ok(Some(RobustMQCodecWrapper::MQTT(wrapper))) => packets.push(wrapper.packet),
+                Ok(Some(other)) => panic!("MQTT listener decoded a non-MQTT frame: {other}"),
+                Ok(None) => break,
+                Err(CommonError::FromMQTTProtocolError(e)) => {
+                    assert!(
+                        decode_error_reason_code(&e).is_some(),
+                        "decode error {e:?} has no DISCONNECT reason"
+                    );
+                    return BrokerDecode {
+                        packets,
+                        disconnected: true,
+                    };
+                }
+                Err(e) => panic!("unexpected decode error: {e}"),
+            }
+        }
+    }
+    BrokerDecode {
+        packets,
+        disconnected: false,
+    }
+}
+
+/// Decoding must not depend on how the stream was segmented.
+pub fn check_broker_segmentation(data: &[u8]) {
+    let whole = decode_broker_stream(data, data.len());
+    for chunk_size in CHUNK_SIZES {
+        assert_eq!(
+            whole,
+            decode_broker_stream(data, chunk_size),
+            "decoding differs with {chunk_size}-byte chunks"
+        );
+    }
+}
+
+fn write_var_int(buffer: &mut BytesMut, mut value: usize) {
+    loop {
+        let mut byte = (value % 128) as u8;
+        value /= 128;
+        if value > 0 {
+            byte |= 0x80;
+        }
+        buffer.put_u8(byte);
+        if value == 0 {
+            break;
+        }
+    }
+}
+
+fn write_str(buffer: &mut BytesMut, value: &str) {
+    write_bytes(buffer, value.as_bytes());
+}
+
+fn write_bytes(buffer: &mut BytesMut, value: &[u8]) {
+    buffer.put_u16(value.len() as u16);
+    buffer.extend_from_slice(value);
+}
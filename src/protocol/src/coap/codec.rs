@@ -0,0 +1,266 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! CoAP message encoding, RFC 7252. Every datagram, or DTLS record, carries
+//! exactly one message.
+
+use super::packet::*;
+use bytes::{BufMut, Bytes, BytesMut};
+use std::fmt;
+
+const HEADER_LEN: usize = 4;
+const MAX_TOKEN_LEN: u8 = 8;
+const PAYLOAD_MARKER: u8 = 0xFF;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CoapCodecError {
+    Truncated,
+    UnsupportedVersion(u8),
+    InvalidTokenLength(u8),
+    InvalidOption,
+    EmptyPayload,
+}
+
+impl fmt::Display for CoapCodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoapCodecError::Truncated => write!(f, "Message is truncated"),
+            CoapCodecError::UnsupportedVersion(v) => write!(f, "Unsupported CoAP version: {}", v),
+            CoapCodecError::InvalidTokenLength(l) => write!(f, "Invalid token length: {}", l),
+            CoapCodecError::InvalidOption => write!(f, "Invalid option delta or length"),
+            CoapCodecError::EmptyPayload => write!(f, "Payload marker followed by no payload"),
+        }
+    }
+}
+
+impl std::error::Error for CoapCodecError {}
+
+pub fn decode(data: &[u8]) -> Result<CoapMessage, CoapCodecError> {
+    if data.len() < HEADER_LEN {
+        return Err(CoapCodecError::Truncated);
+    }
+    let version = data[0] >> 6;
+    if version != COAP_VERSION {
+        return Err(CoapCodecError::UnsupportedVersion(version));
+    }
+    let msg_type = match (data[0] >> 4) & 0x03 {
+        0 => CoapType::Confirmable,
+        1 => CoapType::NonConfirmable,
+        2 => CoapType::Acknowledgement,
+        _ => CoapType::Reset,
+    };
+    let token_len = data[0] & 0x0F;
+    if token_len > MAX_TOKEN_LEN {
+        return Err(CoapCodecError::InvalidTokenLength(token_len));
+    }
+    let code = CoapCode(data[1]);
+    let message_id = u16::from_be_bytes([data[2], data[3]]);
+
+    let mut pos = HEADER_LEN;
+    let token_end = pos + token_len as usize;
+    if data.len() < token_end {
+        return Err(CoapCodecError::Truncated);
+    }
+    let token = data[pos..token_end].to_vec();
+    pos = token_end;
+
+    let mut options = Vec::new();
+    let mut number: u32 = 0;
+    let mut payload = Bytes::new();
+    while pos < data.len() {
+        let byte = data[pos];
+        pos += 1;
+        if byte == PAYLOAD_MARKER {
+            if pos == data.len() {
+                return Err(CoapCodecError::EmptyPayload);
+            }
+            payload = Bytes::copy_from_slice(&data[pos..]);
+            break;
+        }
+        let delta = read_extended(data, &mut pos, byte >> 4)?;
+        let len = read_extended(data, &mut pos, byte & 0x0F)? as usize;
+        number += delta;
+        if number > u16::MAX as u32 || data.len() < pos + len {
+            return Err(CoapCodecError::InvalidOption);
+        }
+        options.push(CoapOption {
+            number: number as u16,
+            value: data[pos..pos + len].to_vec(),
+        });
+        pos += len;
+    }
+
+    Ok(CoapMessage {
+        msg_type,
+        code,
+        message_id,
+        token,
+        options,
+        payload,
+    })
+}
+
+fn read_extended(data: &[u8], pos: &mut usize, nibble: u8) -> Result<u32, CoapCodecError> {
+    match nibble {
+        0..=12 => Ok(nibble as u32),
+        13 => {
+            let value = *data.get(*pos).ok_or(CoapCodecError::Truncated)?;
+            *pos += 1;
+            Ok(value as u32 + 13)
+        }
+        14 => {
+            if data.len() < *pos + 2 {
+                return Err(CoapCodecError::Truncated);
+            }
+            let value = u16::from_be_bytes([data[*pos], data[*pos + 1]]);
+            *pos += 2;
+            Ok(value as u32 + 269)
+        }
+        _ => Err(CoapCodecError::InvalidOption),
+    }
+}
+
+pub fn encode(message: &CoapMessage) -> Vec<u8> {
+    let token_len = message.token.len().min(MAX_TOKEN_LEN as usize);
+    let msg_type = match message.msg_type {
+        CoapType::Confirmable => 0,
+        CoapType::NonConfirmable => 1,
+        CoapType::Acknowledgement => 2,
+        CoapType::Reset => 3,
+    };
+
+    let mut buf = BytesMut::with_capacity(HEADER_LEN + token_len + message.payload.len() + 32);
+    buf.put_u8((COAP_VERSION << 6) | (msg_type << 4) | token_len as u8);
+    buf.put_u8(message.code.0);
+    buf.put_u16(message.message_id);
+    buf.put_slice(&message.token[..token_len]);
+
+    // Options are delta encoded, so they go out in ascending order. The sort
+    // is stable and keeps repeated options, such as Uri-Path, in order.
+    let mut options: Vec<&CoapOption> = message.options.iter().collect();
+    options.sort_by_key(|opt| opt.number);
+    let mut last: u16 = 0;
+    for opt in options {
+        let (delta_nibble, delta_ext) = extended(opt.number as u32 - last as u32);
+        let (len_nibble, len_ext) = extended(opt.value.len() as u32);
+        buf.put_u8((delta_nibble << 4) | len_nibble);
+        buf.put_slice(&delta_ext);
+        buf.put_slice(&len_ext);
+        buf.put_slice(&opt.value);
+        last = opt.number;
+    }
+
+    if !message.payload.is_empty() {
+        buf.put_u8(PAYLOAD_MARKER);
+        buf.put_slice(&message.payload);
+    }
+    buf.to_vec()
+}
+
+fn extended(value: u32) -> (u8, Vec<u8>) {
+    if value < 13 {
+        (value as u8, Vec::new())
+    } else if value < 269 {
+        (13, vec![(value - 13) as u8])
+    } else {
+        (14, ((value - 269) as u16).to_be_bytes().to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode_request() {
+        let mut message =
+            CoapMessage::new(CoapType::Confirmable, CoapCode::PUT, 0x1234, vec![1, 2, 3]);
+        message.add_option(OPTION_URI_PATH, b"ps".to_vec());
+        message.add_option(OPTION_URI_PATH, b"sensors".to_vec());
+        message.add_option(OPTION_URI_PATH, b"temperature".to_vec());
+        message.add_option(OPTION_URI_QUERY, b"qos=1".to_vec());
+        message.add_uint_option(OPTION_CONTENT_FORMAT, 50);
+        message.payload = Bytes::from_static(b"21.5");
+
+        let decoded = decode(&encode(&message)).unwrap();
+        assert_eq!(decoded.msg_type, CoapType::Confirmable);
+        assert_eq!(decoded.code, CoapCode::PUT);
+        assert_eq!(decoded.message_id, 0x1234);
+        assert_eq!(decoded.token, vec![1, 2, 3]);
+        assert_eq!(decoded.uri_path(), vec!["ps", "sensors", "temperature"]);
+        assert_eq!(
+            decoded.uri_query(),
+            vec![("qos".to_string(), "1".to_string())]
+        );
+        assert_eq!(decoded.uint_option(OPTION_CONTENT_FORMAT), Some(50));
+        assert_eq!(decoded.payload, Bytes::from_static(b"21.5"));
+    }
+
+    #[test]
+    fn test_extended_option_delta_and_length() {
+        let mut message = CoapMessage::new(CoapType::NonConfirmable, CoapCode::POST, 1, vec![]);
+        let long_path = "a".repeat(300);
+        message.add_option(OPTION_URI_PATH, long_path.clone().into_bytes());
+        message.add_uint_option(OPTION_SIZE1, 4096);
+
+        let bytes = encode(&message);
+        let decoded = decode(&bytes).unwrap();
+        assert_eq!(decoded.uri_path(), vec![long_path]);
+        assert_eq!(decoded.uint_option(OPTION_SIZE1), Some(4096));
+        assert!(decoded.payload.is_empty());
+    }
+
+    #[test]
+    fn test_block_option() {
+        let block = BlockOption {
+            num: 5,
+            more: true,
+            szx: 6,
+        };
+        assert_eq!(block.size(), 1024);
+        assert_eq!(BlockOption::from_uint(block.to_uint()), Some(block));
+        assert_eq!(BlockOption::szx_for_size(1024), 6);
+        assert_eq!(BlockOption::szx_for_size(1000), 5);
+        assert_eq!(BlockOption::szx_for_size(8), 0);
+        assert!(BlockOption::from_uint(0x07).is_none());
+
+        let mut message = CoapMessage::new(CoapType::Confirmable, CoapCode::GET, 7, vec![9]);
+        message.add_uint_option(OPTION_BLOCK2, block.to_uint());
+        message.add_uint_option(OPTION_OBSERVE, OBSERVE_REGISTER);
+        let decoded = decode(&encode(&message)).unwrap();
+        assert_eq!(decoded.block2(), Some(block));
+        assert_eq!(decoded.observe(), Some(OBSERVE_REGISTER));
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(decode(&[0x40, 0x01]), Err(CoapCodecError::Truncated));
+        assert_eq!(
+            decode(&[0x80, 0x01, 0x00, 0x01]),
+            Err(CoapCodecError::UnsupportedVersion(2))
+        );
+        assert_eq!(
+            decode(&[0x49, 0x01, 0x00, 0x01]),
+            Err(CoapCodecError::InvalidTokenLength(9))
+        );
+        assert_eq!(
+            decode(&[0x40, 0x01, 0x00, 0x01, 0xFF]),
+            Err(CoapCodecError::EmptyPayload)
+        );
+        assert_eq!(
+            decode(&[0x40, 0x01, 0x00, 0x01, 0xB5, b'a']),
+            Err(CoapCodecError::InvalidOption)
+        );
+    }
+}
@@ -0,0 +1,273 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::Bytes;
+
+pub const COAP_VERSION: u8 = 1;
+
+pub const OPTION_IF_MATCH: u16 = 1;
+pub const OPTION_URI_HOST: u16 = 3;
+pub const OPTION_ETAG: u16 = 4;
+pub const OPTION_OBSERVE: u16 = 6;
+pub const OPTION_URI_PORT: u16 = 7;
+pub const OPTION_URI_PATH: u16 = 11;
+pub const OPTION_CONTENT_FORMAT: u16 = 12;
+pub const OPTION_MAX_AGE: u16 = 14;
+pub const OPTION_URI_QUERY: u16 = 15;
+pub const OPTION_BLOCK2: u16 = 23;
+pub const OPTION_BLOCK1: u16 = 27;
+pub const OPTION_SIZE2: u16 = 28;
+pub const OPTION_SIZE1: u16 = 60;
+
+/// Observe option value of a GET that registers an observer, RFC 7641.
+pub const OBSERVE_REGISTER: u32 = 0;
+/// Observe option value of a GET that cancels an observation.
+pub const OBSERVE_DEREGISTER: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoapType {
+    Confirmable,
+    NonConfirmable,
+    Acknowledgement,
+    Reset,
+}
+
+/// Request method or response code, `class.detail` packed into one byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoapCode(pub u8);
+
+impl CoapCode {
+    pub const EMPTY: CoapCode = CoapCode(0x00);
+    pub const GET: CoapCode = CoapCode(0x01);
+    pub const POST: CoapCode = CoapCode(0x02);
+    pub const PUT: CoapCode = CoapCode(0x03);
+    pub const DELETE: CoapCode = CoapCode(0x04);
+
+    pub const CREATED: CoapCode = CoapCode::new(2, 1);
+    pub const DELETED: CoapCode = CoapCode::new(2, 2);
+    pub const VALID: CoapCode = CoapCode::new(2, 3);
+    pub const CHANGED: CoapCode = CoapCode::new(2, 4);
+    pub const CONTENT: CoapCode = CoapCode::new(2, 5);
+    pub const CONTINUE: CoapCode = CoapCode::new(2, 31);
+    pub const BAD_REQUEST: CoapCode = CoapCode::new(4, 0);
+    pub const UNAUTHORIZED: CoapCode = CoapCode::new(4, 1);
+    pub const BAD_OPTION: CoapCode = CoapCode::new(4, 2);
+    pub const FORBIDDEN: CoapCode = CoapCode::new(4, 3);
+    pub const NOT_FOUND: CoapCode = CoapCode::new(4, 4);
+    pub const METHOD_NOT_ALLOWED: CoapCode = CoapCode::new(4, 5);
+    pub const REQUEST_ENTITY_INCOMPLETE: CoapCode = CoapCode::new(4, 8);
+    pub const REQUEST_ENTITY_TOO_LARGE: CoapCode = CoapCode::new(4, 13);
+    pub const INTERNAL_SERVER_ERROR: CoapCode = CoapCode::new(5, 0);
+    pub const SERVICE_UNAVAILABLE: CoapCode = CoapCode::new(5, 3);
+
+    pub const fn new(class: u8, detail: u8) -> Self {
+        CoapCode((class << 5) | (detail & 0x1F))
+    }
+
+    pub fn class(&self) -> u8 {
+        self.0 >> 5
+    }
+
+    pub fn detail(&self) -> u8 {
+        self.0 & 0x1F
+    }
+
+    pub fn is_request(&self) -> bool {
+        self.class() == 0 && self.0 != 0
+    }
+}
+
+impl std::fmt::Display for CoapCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{:02}", self.class(), self.detail())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoapOption {
+    pub number: u16,
+    pub value: Vec<u8>,
+}
+
+/// Block1 or Block2 option value, RFC 7959. The block size is
+/// `2^(szx + 4)` bytes, from 16 to 1024.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockOption {
+    pub num: u32,
+    pub more: bool,
+    pub szx: u8,
+}
+
+impl BlockOption {
+    pub const MAX_SZX: u8 = 6;
+
+    pub fn size(&self) -> usize {
+        1 << (self.szx as usize + 4)
+    }
+
+    /// Largest size exponent whose block fits in `size` bytes.
+    pub fn szx_for_size(size: usize) -> u8 {
+        let mut szx = Self::MAX_SZX;
+        while szx > 0 && (1usize << (szx + 4)) > size {
+            szx -= 1;
+        }
+        szx
+    }
+
+    pub fn from_uint(value: u32) -> Option<Self> {
+        let szx = (value & 0x07) as u8;
+        // szx 7 is reserved
+        if szx > Self::MAX_SZX {
+            return None;
+        }
+        Some(BlockOption {
+            num: value >> 4,
+            more: value & 0x08 != 0,
+            szx,
+        })
+    }
+
+    pub fn to_uint(&self) -> u32 {
+        let more = if self.more { 0x08 } else { 0 };
+        (self.num << 4) | more | self.szx as u32
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoapMessage {
+    pub msg_type: CoapType,
+    pub code: CoapCode,
+    pub message_id: u16,
+    pub token: Vec<u8>,
+    /// Sorted by option number when encoded.
+    pub options: Vec<CoapOption>,
+    pub payload: Bytes,
+}
+
+impl CoapMessage {
+    pub fn new(msg_type: CoapType, code: CoapCode, message_id: u16, token: Vec<u8>) -> Self {
+        CoapMessage {
+            msg_type,
+            code,
+            message_id,
+            token,
+            options: Vec::new(),
+            payload: Bytes::new(),
+        }
+    }
+
+    /// Piggybacked response to a confirmable request, or a non-confirmable
+    /// response to a non-confirmable one.
+    pub fn response_to(request: &CoapMessage, code: CoapCode, message_id: u16) -> Self {
+        let (msg_type, message_id) = match request.msg_type {
+            CoapType::Confirmable => (CoapType::Acknowledgement, request.message_id),
+            _ => (CoapType::NonConfirmable, message_id),
+        };
+        CoapMessage::new(msg_type, code, message_id, request.token.clone())
+    }
+
+    /// Empty ACK or RST for `message_id`.
+    pub fn empty(msg_type: CoapType, message_id: u16) -> Self {
+        CoapMessage::new(msg_type, CoapCode::EMPTY, message_id, Vec::new())
+    }
+
+    pub fn add_option(&mut self, number: u16, value: Vec<u8>) {
+        self.options.push(CoapOption { number, value });
+    }
+
+    pub fn add_uint_option(&mut self, number: u16, value: u32) {
+        self.add_option(number, encode_uint(value));
+    }
+
+    pub fn option(&self, number: u16) -> Option<&[u8]> {
+        self.options
+            .iter()
+            .find(|opt| opt.number == number)
+            .map(|opt| opt.value.as_slice())
+    }
+
+    pub fn uint_option(&self, number: u16) -> Option<u32> {
+        self.option(number).map(decode_uint)
+    }
+
+    pub fn string_options(&self, number: u16) -> Vec<String> {
+        self.options
+            .iter()
+            .filter(|opt| opt.number == number)
+            .map(|opt| String::from_utf8_lossy(&opt.value).to_string())
+            .collect()
+    }
+
+    pub fn uri_path(&self) -> Vec<String> {
+        self.string_options(OPTION_URI_PATH)
+    }
+
+    /// `key=value` pairs of the Uri-Query options. A query without `=` has
+    /// an empty value.
+    pub fn uri_query(&self) -> Vec<(String, String)> {
+        self.string_options(OPTION_URI_QUERY)
+            .into_iter()
+            .map(|query| match query.split_once('=') {
+                Some((key, value)) => (key.to_string(), value.to_string()),
+                None => (query, String::new()),
+            })
+            .collect()
+    }
+
+    pub fn observe(&self) -> Option<u32> {
+        self.uint_option(OPTION_OBSERVE)
+    }
+
+    pub fn block1(&self) -> Option<BlockOption> {
+        self.uint_option(OPTION_BLOCK1)
+            .and_then(BlockOption::from_uint)
+    }
+
+    pub fn block2(&self) -> Option<BlockOption> {
+        self.uint_option(OPTION_BLOCK2)
+            .and_then(BlockOption::from_uint)
+    }
+}
+
+/// Shortest big-endian encoding of an unsigned option value, zero is empty.
+pub fn encode_uint(value: u32) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let skip = bytes.iter().take_while(|b| **b == 0).count();
+    bytes[skip..].to_vec()
+}
+
+pub fn decode_uint(value: &[u8]) -> u32 {
+    value
+        .iter()
+        .take(4)
+        .fold(0u32, |acc, b| (acc << 8) | *b as u32)
+}
+
+pub fn coap_message_to_string(message: &CoapMessage) -> String {
+    let kind = match message.msg_type {
+        CoapType::Confirmable => "CON",
+        CoapType::NonConfirmable => "NON",
+        CoapType::Acknowledgement => "ACK",
+        CoapType::Reset => "RST",
+    };
+    let method = match message.code {
+        CoapCode::EMPTY => "EMPTY".to_string(),
+        CoapCode::GET => "GET".to_string(),
+        CoapCode::POST => "POST".to_string(),
+        CoapCode::PUT => "PUT".to_string(),
+        CoapCode::DELETE => "DELETE".to_string(),
+        code => code.to_string(),
+    };
+    format!("{} {} mid={}", kind, method, message.message_id)
+}
@@ -19,7 +19,7 @@
     kafka::{codec::KafkaCodec, packet::KafkaPacketWrapper},
     mqtt::{
         codec::{MqttCodec, MqttPacketWrapper},
-        common::mqtt_packet_to_string,
+        common::{mqtt_packet_to_string, MqttPacket},
     },
     nats::{codec::NatsCodec, packet::NatsPacket},
     robust::RobustMQProtocol,
@@ -27,6 +27,7 @@
 };
 use bytes::BytesMut;
 use common_base::error::common::CommonError;
+use common_base::error::mqtt_protocol_error::MQTTProtocolError;
 use std::fmt;
 use tokio_util::codec::{Decoder, Encoder};
 
@@ -111,7 +112,7 @@ pub fn decode_data(
     ) -> Result<Option<RobustMQCodecWrapper>, CommonError> {
         match self.protocol.clone() {
             Some(RobustMQProtocol::MQTT3 | RobustMQProtocol::MQTT4 | RobustMQProtocol::MQTT5) => {
-                if let Ok(Some(pkg)) = self.mqtt_codec.decode_data(stream) {
+                if let Some(pkg) = self.decode_mqtt(stream)? {
                     self.protocol = self
                         .mqtt_codec
                         .protocol_version
@@ -145,7 +146,7 @@ pub fn decode_data(
                 }
             }
             None => {
-                if let Ok(Some(pkg)) = self.mqtt_codec.decode_data(stream) {
+                if let Some(pkg) = self.decode_mqtt(stream)? {
                     self.protocol = self
                         .mqtt_codec
                         .protocol_version
@@ -161,6 +162,18 @@ pub fn decode_data(
         Ok(None)
     }
 
+    /// Decode an MQTT frame. Incomplete frames yield `None` so more bytes can
+    /// be read; oversized and malformed frames are returned as errors so the
+    /// caller can answer them with a DISCONNECT and close the connection.
+    #[allow(clippy::result_large_err)]
+    fn decode_mqtt(&mut self, stream: &mut BytesMut) -> Result<Option<MqttPacket>, CommonError> {
+        match self.mqtt_codec.decode_data(stream) {
+            Ok(pkg) => Ok(pkg),
+            Err(MQTTProtocolError::InsufficientBytes(_)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     #[allow(clippy::result_large_err)]
     pub fn encode_data(
         &mut self,
@@ -14,10 +14,12 @@
 
 pub mod amqp;
 pub mod broker;
+pub mod coap;
 pub mod codec;
 pub mod kafka;
 pub mod meta;
 pub mod mqtt;
+pub mod mqttsn;
 pub mod nats;
 pub mod robust;
 pub mod storage;
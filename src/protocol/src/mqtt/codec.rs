@@ -12,12 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use bytes::BytesMut;
+use bytes::{Bytes, BytesMut};
 use common_base::error::mqtt_protocol_error::MQTTProtocolError;
 use tokio_util::codec;
 
 use super::common::ConnectReadOutcome;
-use crate::mqtt::common::{check, connect_read, MqttPacket, PacketType};
+use crate::mqtt::common::{
+    check, connect_read, DisconnectReasonCode, FixedHeader, MqttPacket, PacketType,
+};
 
 #[derive(Debug, Clone)]
 pub struct MqttPacketWrapper {
@@ -25,14 +27,25 @@ pub struct MqttPacketWrapper {
     pub packet: MqttPacket,
 }
 
+/// Default upper bound on the remaining length of an inbound packet.
+pub const DEFAULT_MAX_PACKET_SIZE: usize = 1024 * 1024 * 10;
+
 #[derive(Clone, Debug)]
 pub struct MqttCodec {
     pub protocol_version: Option<u8>,
+    pub max_packet_size: usize,
 }
 
 impl MqttCodec {
     pub fn new(protocol_version: Option<u8>) -> MqttCodec {
-        MqttCodec { protocol_version }
+        MqttCodec {
+            protocol_version,
+            max_packet_size: DEFAULT_MAX_PACKET_SIZE,
+        }
+    }
+
+    pub fn set_max_packet_size(&mut self, max_packet_size: usize) {
+        self.max_packet_size = max_packet_size;
     }
 }
 
@@ -41,11 +54,25 @@ pub fn decode_data(
         &mut self,
         stream: &mut BytesMut,
     ) -> Result<Option<MqttPacket>, MQTTProtocolError> {
-        let fixed_header = check(stream.iter(), 1000000)?;
+        let fixed_header = check(stream.iter(), self.max_packet_size)?;
         // Test with a stream with exactly the size to check border panics
-        let packet = stream.split_to(fixed_header.frame_length());
+        let packet = stream.split_to(fixed_header.frame_length()).freeze();
+
+        // The whole frame is buffered at this point, so running out of bytes
+        // while parsing it means the packet is malformed, not incomplete.
+        self.decode_frame(fixed_header, packet)
+            .map_err(|e| match e {
+                MQTTProtocolError::InsufficientBytes(_) => MQTTProtocolError::MalformedPacket,
+                e => e,
+            })
+    }
+
+    fn decode_frame(
+        &mut self,
+        fixed_header: FixedHeader,
+        packet: Bytes,
+    ) -> Result<Option<MqttPacket>, MQTTProtocolError> {
         let packet_type = fixed_header.packet_type()?;
-        let packet = packet.freeze();
 
         if packet_type == PacketType::Connect {
             match connect_read(fixed_header, packet.clone()) {
@@ -343,6 +370,19 @@ fn decode(&mut self, stream: &mut BytesMut) -> Result<Option<Self::Item>, Self::
     }
 }
 
+/// DISCONNECT reason an MQTT 5 peer should receive when the codec rejects one
+/// of its frames. Incomplete frames and I/O failures are not protocol errors,
+/// so they have no reason code.
+pub fn decode_error_reason_code(err: &MQTTProtocolError) -> Option<DisconnectReasonCode> {
+    match err {
+        MQTTProtocolError::InsufficientBytes(_) | MQTTProtocolError::IoError(_) => None,
+        MQTTProtocolError::PayloadSizeLimitExceeded(_) => {
+            Some(DisconnectReasonCode::PacketTooLarge)
+        }
+        _ => Some(DisconnectReasonCode::MalformedPacket),
+    }
+}
+
 pub fn calc_mqtt_packet_size(packet_wrapper: MqttPacketWrapper) -> usize {
     calc_mqtt_packet_len(packet_wrapper).unwrap_or_default()
 }
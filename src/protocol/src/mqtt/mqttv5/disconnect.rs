@@ -212,7 +212,7 @@ pub fn read(bytes: &mut Bytes) -> Result<Option<DisconnectProperties>, MQTTProto
     }
 }
 
-fn code(reason: DisconnectReasonCode) -> u8 {
+pub fn code(reason: DisconnectReasonCode) -> u8 {
     match reason {
         DisconnectReasonCode::NormalDisconnection => 0x00,
         DisconnectReasonCode::DisconnectWithWillMessage => 0x04,
@@ -0,0 +1,569 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! MQTT-SN v1.2 encoding. Every UDP datagram carries exactly one message,
+//! so decoding works on a whole datagram rather than a byte stream.
+
+use super::packet::*;
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::fmt;
+
+const FLAG_DUP: u8 = 0x80;
+const FLAG_RETAIN: u8 = 0x10;
+const FLAG_WILL: u8 = 0x08;
+const FLAG_CLEAN_SESSION: u8 = 0x04;
+const FLAG_QOS_SHIFT: u8 = 5;
+
+const TOPIC_ID_TYPE_NORMAL: u8 = 0x00;
+const TOPIC_ID_TYPE_PREDEFINED: u8 = 0x01;
+const TOPIC_ID_TYPE_SHORT: u8 = 0x02;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MqttSnCodecError {
+    Truncated,
+    LengthMismatch(usize, usize),
+    UnknownMsgType(u8),
+    InvalidTopicIdType(u8),
+    UnsupportedProtocol(u8),
+    InvalidUtf8,
+}
+
+impl fmt::Display for MqttSnCodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MqttSnCodecError::Truncated => write!(f, "Message is truncated"),
+            MqttSnCodecError::LengthMismatch(declared, actual) => write!(
+                f,
+                "Declared length {} does not match datagram length {}",
+                declared, actual
+            ),
+            MqttSnCodecError::UnknownMsgType(t) => write!(f, "Unknown message type: {:#04x}", t),
+            MqttSnCodecError::InvalidTopicIdType(t) => write!(f, "Invalid topic id type: {}", t),
+            MqttSnCodecError::UnsupportedProtocol(p) => {
+                write!(f, "Unsupported protocol id: {:#04x}", p)
+            }
+            MqttSnCodecError::InvalidUtf8 => write!(f, "Invalid UTF-8"),
+        }
+    }
+}
+
+impl std::error::Error for MqttSnCodecError {}
+
+fn decode_flags(flags: u8) -> (MqttSnFlags, u8) {
+    let qos = match (flags >> FLAG_QOS_SHIFT) & 0x03 {
+        0 => MqttSnQoS::AtMostOnce,
+        1 => MqttSnQoS::AtLeastOnce,
+        2 => MqttSnQoS::ExactlyOnce,
+        _ => MqttSnQoS::NoSession,
+    };
+    (
+        MqttSnFlags {
+            dup: flags & FLAG_DUP != 0,
+            qos,
+            retain: flags & FLAG_RETAIN != 0,
+            will: flags & FLAG_WILL != 0,
+            clean_session: flags & FLAG_CLEAN_SESSION != 0,
+        },
+        flags & 0x03,
+    )
+}
+
+fn encode_flags(flags: &MqttSnFlags, topic_id_type: u8) -> u8 {
+    let qos: u8 = match flags.qos {
+        MqttSnQoS::AtMostOnce => 0,
+        MqttSnQoS::AtLeastOnce => 1,
+        MqttSnQoS::ExactlyOnce => 2,
+        MqttSnQoS::NoSession => 3,
+    };
+    let mut byte = (qos << FLAG_QOS_SHIFT) | (topic_id_type & 0x03);
+    if flags.dup {
+        byte |= FLAG_DUP;
+    }
+    if flags.retain {
+        byte |= FLAG_RETAIN;
+    }
+    if flags.will {
+        byte |= FLAG_WILL;
+    }
+    if flags.clean_session {
+        byte |= FLAG_CLEAN_SESSION;
+    }
+    byte
+}
+
+fn decode_return_code(code: u8) -> MqttSnReturnCode {
+    match code {
+        0x00 => MqttSnReturnCode::Accepted,
+        0x01 => MqttSnReturnCode::Congestion,
+        0x02 => MqttSnReturnCode::InvalidTopicId,
+        _ => MqttSnReturnCode::NotSupported,
+    }
+}
+
+fn encode_return_code(code: MqttSnReturnCode) -> u8 {
+    match code {
+        MqttSnReturnCode::Accepted => 0x00,
+        MqttSnReturnCode::Congestion => 0x01,
+        MqttSnReturnCode::InvalidTopicId => 0x02,
+        MqttSnReturnCode::NotSupported => 0x03,
+    }
+}
+
+fn take_u8(buf: &mut &[u8]) -> Result<u8, MqttSnCodecError> {
+    if buf.remaining() < 1 {
+        return Err(MqttSnCodecError::Truncated);
+    }
+    Ok(buf.get_u8())
+}
+
+fn take_u16(buf: &mut &[u8]) -> Result<u16, MqttSnCodecError> {
+    if buf.remaining() < 2 {
+        return Err(MqttSnCodecError::Truncated);
+    }
+    Ok(buf.get_u16())
+}
+
+fn take_string(buf: &mut &[u8]) -> Result<String, MqttSnCodecError> {
+    let s = std::str::from_utf8(buf)
+        .map_err(|_| MqttSnCodecError::InvalidUtf8)?
+        .to_string();
+    buf.advance(buf.len());
+    Ok(s)
+}
+
+/// Topic field of PUBLISH: always two bytes, read according to the topic id
+/// type. A normal topic id type with a name is only valid in (UN)SUBSCRIBE.
+fn take_topic(buf: &mut &[u8], topic_id_type: u8) -> Result<MqttSnTopic, MqttSnCodecError> {
+    match topic_id_type {
+        TOPIC_ID_TYPE_NORMAL => Ok(MqttSnTopic::Id(take_u16(buf)?)),
+        TOPIC_ID_TYPE_PREDEFINED => Ok(MqttSnTopic::Predefined(take_u16(buf)?)),
+        TOPIC_ID_TYPE_SHORT => {
+            if buf.remaining() < 2 {
+                return Err(MqttSnCodecError::Truncated);
+            }
+            let name = std::str::from_utf8(&buf[..2])
+                .map_err(|_| MqttSnCodecError::InvalidUtf8)?
+                .to_string();
+            buf.advance(2);
+            Ok(MqttSnTopic::Short(name))
+        }
+        other => Err(MqttSnCodecError::InvalidTopicIdType(other)),
+    }
+}
+
+/// Topic field of SUBSCRIBE and UNSUBSCRIBE, where a normal topic id type
+/// carries the full topic filter instead of an id.
+fn take_subscribe_topic(
+    buf: &mut &[u8],
+    topic_id_type: u8,
+) -> Result<MqttSnTopic, MqttSnCodecError> {
+    if topic_id_type == TOPIC_ID_TYPE_NORMAL {
+        return Ok(MqttSnTopic::Name(take_string(buf)?));
+    }
+    take_topic(buf, topic_id_type)
+}
+
+fn put_topic(out: &mut BytesMut, topic: &MqttSnTopic) {
+    match topic {
+        MqttSnTopic::Id(id) | MqttSnTopic::Predefined(id) => out.put_u16(*id),
+        MqttSnTopic::Short(name) => {
+            let bytes = name.as_bytes();
+            out.put_u8(bytes.first().copied().unwrap_or(0));
+            out.put_u8(bytes.get(1).copied().unwrap_or(0));
+        }
+        MqttSnTopic::Name(name) => out.put_slice(name.as_bytes()),
+    }
+}
+
+fn topic_id_type(topic: &MqttSnTopic) -> u8 {
+    match topic {
+        MqttSnTopic::Id(_) | MqttSnTopic::Name(_) => TOPIC_ID_TYPE_NORMAL,
+        MqttSnTopic::Predefined(_) => TOPIC_ID_TYPE_PREDEFINED,
+        MqttSnTopic::Short(_) => TOPIC_ID_TYPE_SHORT,
+    }
+}
+
+/// Decode one datagram.
+pub fn decode(datagram: &[u8]) -> Result<MqttSnPacket, MqttSnCodecError> {
+    let mut buf = datagram;
+    let first = take_u8(&mut buf)?;
+    let length = if first == 0x01 {
+        take_u16(&mut buf)? as usize
+    } else {
+        first as usize
+    };
+    if length != datagram.len() {
+        return Err(MqttSnCodecError::LengthMismatch(length, datagram.len()));
+    }
+
+    let msg_type = take_u8(&mut buf)?;
+    let buf = &mut buf;
+    let packet = match msg_type {
+        MSG_TYPE_ADVERTISE => MqttSnPacket::Advertise {
+            gw_id: take_u8(buf)?,
+            duration: take_u16(buf)?,
+        },
+        MSG_TYPE_SEARCHGW => MqttSnPacket::SearchGw {
+            radius: take_u8(buf)?,
+        },
+        MSG_TYPE_GWINFO => MqttSnPacket::GwInfo {
+            gw_id: take_u8(buf)?,
+        },
+        MSG_TYPE_CONNECT => {
+            let (flags, _) = decode_flags(take_u8(buf)?);
+            let protocol_id = take_u8(buf)?;
+            if protocol_id != MQTT_SN_PROTOCOL_ID {
+                return Err(MqttSnCodecError::UnsupportedProtocol(protocol_id));
+            }
+            MqttSnPacket::Connect(MqttSnConnect {
+                flags,
+                duration: take_u16(buf)?,
+                client_id: take_string(buf)?,
+            })
+        }
+        MSG_TYPE_CONNACK => MqttSnPacket::ConnAck {
+            return_code: decode_return_code(take_u8(buf)?),
+        },
+        MSG_TYPE_REGISTER => MqttSnPacket::Register(MqttSnRegister {
+            topic_id: take_u16(buf)?,
+            msg_id: take_u16(buf)?,
+            topic_name: take_string(buf)?,
+        }),
+        MSG_TYPE_REGACK => MqttSnPacket::RegAck(MqttSnRegAck {
+            topic_id: take_u16(buf)?,
+            msg_id: take_u16(buf)?,
+            return_code: decode_return_code(take_u8(buf)?),
+        }),
+        MSG_TYPE_PUBLISH => {
+            let (flags, topic_id_type) = decode_flags(take_u8(buf)?);
+            let topic = take_topic(buf, topic_id_type)?;
+            let msg_id = take_u16(buf)?;
+            MqttSnPacket::Publish(MqttSnPublish {
+                flags,
+                topic,
+                msg_id,
+                data: Bytes::copy_from_slice(&buf[..]),
+            })
+        }
+        MSG_TYPE_PUBACK => MqttSnPacket::PubAck(MqttSnPubAck {
+            topic_id: take_u16(buf)?,
+            msg_id: take_u16(buf)?,
+            return_code: decode_return_code(take_u8(buf)?),
+        }),
+        MSG_TYPE_PUBREC => MqttSnPacket::PubRec {
+            msg_id: take_u16(buf)?,
+        },
+        MSG_TYPE_PUBREL => MqttSnPacket::PubRel {
+            msg_id: take_u16(buf)?,
+        },
+        MSG_TYPE_PUBCOMP => MqttSnPacket::PubComp {
+            msg_id: take_u16(buf)?,
+        },
+        MSG_TYPE_SUBSCRIBE => {
+            let (flags, topic_id_type) = decode_flags(take_u8(buf)?);
+            let msg_id = take_u16(buf)?;
+            MqttSnPacket::Subscribe(MqttSnSubscribe {
+                flags,
+                msg_id,
+                topic: take_subscribe_topic(buf, topic_id_type)?,
+            })
+        }
+        MSG_TYPE_SUBACK => {
+            let (flags, _) = decode_flags(take_u8(buf)?);
+            MqttSnPacket::SubAck(MqttSnSubAck {
+                flags,
+                topic_id: take_u16(buf)?,
+                msg_id: take_u16(buf)?,
+                return_code: decode_return_code(take_u8(buf)?),
+            })
+        }
+        MSG_TYPE_UNSUBSCRIBE => {
+            let (_, topic_id_type) = decode_flags(take_u8(buf)?);
+            let msg_id = take_u16(buf)?;
+            MqttSnPacket::Unsubscribe(MqttSnUnsubscribe {
+                msg_id,
+                topic: take_subscribe_topic(buf, topic_id_type)?,
+            })
+        }
+        MSG_TYPE_UNSUBACK => MqttSnPacket::UnsubAck {
+            msg_id: take_u16(buf)?,
+        },
+        MSG_TYPE_PINGREQ => {
+            let client_id = take_string(buf)?;
+            MqttSnPacket::PingReq {
+                client_id: (!client_id.is_empty()).then_some(client_id),
+            }
+        }
+        MSG_TYPE_PINGRESP => MqttSnPacket::PingResp,
+        MSG_TYPE_DISCONNECT => MqttSnPacket::Disconnect {
+            duration: if buf.remaining() >= 2 {
+                Some(take_u16(buf)?)
+            } else {
+                None
+            },
+        },
+        other => return Err(MqttSnCodecError::UnknownMsgType(other)),
+    };
+    Ok(packet)
+}
+
+/// Encode one packet into a datagram, using the three byte length form when
+/// the message is longer than 255 bytes.
+pub fn encode(packet: &MqttSnPacket) -> Vec<u8> {
+    let mut body = BytesMut::new();
+    let msg_type = match packet {
+        MqttSnPacket::Advertise { gw_id, duration } => {
+            body.put_u8(*gw_id);
+            body.put_u16(*duration);
+            MSG_TYPE_ADVERTISE
+        }
+        MqttSnPacket::SearchGw { radius } => {
+            body.put_u8(*radius);
+            MSG_TYPE_SEARCHGW
+        }
+        MqttSnPacket::GwInfo { gw_id } => {
+            body.put_u8(*gw_id);
+            MSG_TYPE_GWINFO
+        }
+        MqttSnPacket::Connect(connect) => {
+            body.put_u8(encode_flags(&connect.flags, 0));
+            body.put_u8(MQTT_SN_PROTOCOL_ID);
+            body.put_u16(connect.duration);
+            body.put_slice(connect.client_id.as_bytes());
+            MSG_TYPE_CONNECT
+        }
+        MqttSnPacket::ConnAck { return_code } => {
+            body.put_u8(encode_return_code(*return_code));
+            MSG_TYPE_CONNACK
+        }
+        MqttSnPacket::Register(register) => {
+            body.put_u16(register.topic_id);
+            body.put_u16(register.msg_id);
+            body.put_slice(register.topic_name.as_bytes());
+            MSG_TYPE_REGISTER
+        }
+        MqttSnPacket::RegAck(ack) => {
+            body.put_u16(ack.topic_id);
+            body.put_u16(ack.msg_id);
+            body.put_u8(encode_return_code(ack.return_code));
+            MSG_TYPE_REGACK
+        }
+        MqttSnPacket::Publish(publish) => {
+            body.put_u8(encode_flags(&publish.flags, topic_id_type(&publish.topic)));
+            put_topic(&mut body, &publish.topic);
+            body.put_u16(publish.msg_id);
+            body.put_slice(&publish.data);
+            MSG_TYPE_PUBLISH
+        }
+        MqttSnPacket::PubAck(ack) => {
+            body.put_u16(ack.topic_id);
+            body.put_u16(ack.msg_id);
+            body.put_u8(encode_return_code(ack.return_code));
+            MSG_TYPE_PUBACK
+        }
+        MqttSnPacket::PubRec { msg_id } => {
+            body.put_u16(*msg_id);
+            MSG_TYPE_PUBREC
+        }
+        MqttSnPacket::PubRel { msg_id } => {
+            body.put_u16(*msg_id);
+            MSG_TYPE_PUBREL
+        }
+        MqttSnPacket::PubComp { msg_id } => {
+            body.put_u16(*msg_id);
+            MSG_TYPE_PUBCOMP
+        }
+        MqttSnPacket::Subscribe(subscribe) => {
+            body.put_u8(encode_flags(
+                &subscribe.flags,
+                topic_id_type(&subscribe.topic),
+            ));
+            body.put_u16(subscribe.msg_id);
+            put_topic(&mut body, &subscribe.topic);
+            MSG_TYPE_SUBSCRIBE
+        }
+        MqttSnPacket::SubAck(ack) => {
+            body.put_u8(encode_flags(&ack.flags, 0));
+            body.put_u16(ack.topic_id);
+            body.put_u16(ack.msg_id);
+            body.put_u8(encode_return_code(ack.return_code));
+            MSG_TYPE_SUBACK
+        }
+        MqttSnPacket::Unsubscribe(unsubscribe) => {
+            body.put_u8(encode_flags(
+                &MqttSnFlags::default(),
+                topic_id_type(&unsubscribe.topic),
+            ));
+            body.put_u16(unsubscribe.msg_id);
+            put_topic(&mut body, &unsubscribe.topic);
+            MSG_TYPE_UNSUBSCRIBE
+        }
+        MqttSnPacket::UnsubAck { msg_id } => {
+            body.put_u16(*msg_id);
+            MSG_TYPE_UNSUBACK
+        }
+        MqttSnPacket::PingReq { client_id } => {
+            if let Some(client_id) = client_id {
+                body.put_slice(client_id.as_bytes());
+            }
+            MSG_TYPE_PINGREQ
+        }
+        MqttSnPacket::PingResp => MSG_TYPE_PINGRESP,
+        MqttSnPacket::Disconnect { duration } => {
+            if let Some(duration) = duration {
+                body.put_u16(*duration);
+            }
+            MSG_TYPE_DISCONNECT
+        }
+    };
+
+    let short_len = body.len() + 2;
+    let mut out = Vec::with_capacity(short_len + 2);
+    if short_len <= u8::MAX as usize {
+        out.put_u8(short_len as u8);
+    } else {
+        out.put_u8(0x01);
+        out.put_u16((body.len() + 4) as u16);
+    }
+    out.put_u8(msg_type);
+    out.extend_from_slice(&body);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(packet: MqttSnPacket) {
+        let bytes = encode(&packet);
+        assert_eq!(decode(&bytes).unwrap(), packet);
+    }
+
+    #[test]
+    fn test_connect_and_register() {
+        let connect = MqttSnPacket::Connect(MqttSnConnect {
+            flags: MqttSnFlags {
+                clean_session: true,
+                ..Default::default()
+            },
+            duration: 60,
+            client_id: "sensor-1".to_string(),
+        });
+        let bytes = encode(&connect);
+        assert_eq!(
+            &bytes[..6],
+            &[14, MSG_TYPE_CONNECT, FLAG_CLEAN_SESSION, 0x01, 0x00, 60]
+        );
+        round_trip(connect);
+
+        round_trip(MqttSnPacket::Register(MqttSnRegister {
+            topic_id: 0,
+            msg_id: 7,
+            topic_name: "sensors/t1".to_string(),
+        }));
+        round_trip(MqttSnPacket::RegAck(MqttSnRegAck {
+            topic_id: 1,
+            msg_id: 7,
+            return_code: MqttSnReturnCode::Accepted,
+        }));
+    }
+
+    #[test]
+    fn test_publish_topic_types() {
+        for topic in [
+            MqttSnTopic::Id(3),
+            MqttSnTopic::Predefined(100),
+            MqttSnTopic::Short("ab".to_string()),
+        ] {
+            round_trip(MqttSnPacket::Publish(MqttSnPublish {
+                flags: MqttSnFlags {
+                    qos: MqttSnQoS::NoSession,
+                    retain: true,
+                    ..Default::default()
+                },
+                topic,
+                msg_id: 0,
+                data: Bytes::from_static(b"21.5"),
+            }));
+        }
+
+        // QoS -1 publish to predefined topic 1 as sent by a sensor.
+        let bytes = [0x09, 0x0C, 0x61, 0x00, 0x01, 0x00, 0x00, b'o', b'n'];
+        let MqttSnPacket::Publish(publish) = decode(&bytes).unwrap() else {
+            panic!("expected publish");
+        };
+        assert_eq!(publish.flags.qos, MqttSnQoS::NoSession);
+        assert_eq!(publish.topic, MqttSnTopic::Predefined(1));
+        assert_eq!(publish.data, Bytes::from_static(b"on"));
+    }
+
+    #[test]
+    fn test_subscribe_sleep_and_long_messages() {
+        round_trip(MqttSnPacket::Subscribe(MqttSnSubscribe {
+            flags: MqttSnFlags {
+                qos: MqttSnQoS::AtLeastOnce,
+                ..Default::default()
+            },
+            msg_id: 2,
+            topic: MqttSnTopic::Name("actuators/+/set".to_string()),
+        }));
+        round_trip(MqttSnPacket::Unsubscribe(MqttSnUnsubscribe {
+            msg_id: 3,
+            topic: MqttSnTopic::Predefined(5),
+        }));
+        round_trip(MqttSnPacket::Disconnect {
+            duration: Some(300),
+        });
+        round_trip(MqttSnPacket::Disconnect { duration: None });
+        round_trip(MqttSnPacket::PingReq {
+            client_id: Some("sensor-1".to_string()),
+        });
+        round_trip(MqttSnPacket::PingReq { client_id: None });
+
+        let long = MqttSnPacket::Publish(MqttSnPublish {
+            flags: MqttSnFlags::default(),
+            topic: MqttSnTopic::Id(1),
+            msg_id: 9,
+            data: Bytes::from(vec![7u8; 400]),
+        });
+        let bytes = encode(&long);
+        assert_eq!(bytes[0], 0x01);
+        assert_eq!(
+            u16::from_be_bytes([bytes[1], bytes[2]]) as usize,
+            bytes.len()
+        );
+        round_trip(long);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(decode(&[]), Err(MqttSnCodecError::Truncated));
+        assert_eq!(
+            decode(&[5, MSG_TYPE_PINGRESP]),
+            Err(MqttSnCodecError::LengthMismatch(5, 2))
+        );
+        assert_eq!(
+            decode(&[2, 0x30]),
+            Err(MqttSnCodecError::UnknownMsgType(0x30))
+        );
+        assert_eq!(
+            decode(&[4, MSG_TYPE_PUBACK, 0, 1]),
+            Err(MqttSnCodecError::Truncated)
+        );
+        assert_eq!(
+            decode(&[6, MSG_TYPE_CONNECT, 0, 0x02, 0, 10]),
+            Err(MqttSnCodecError::UnsupportedProtocol(0x02))
+        );
+    }
+}
@@ -0,0 +1,211 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::Bytes;
+
+pub const MQTT_SN_PROTOCOL_ID: u8 = 0x01;
+
+pub const MSG_TYPE_ADVERTISE: u8 = 0x00;
+pub const MSG_TYPE_SEARCHGW: u8 = 0x01;
+pub const MSG_TYPE_GWINFO: u8 = 0x02;
+pub const MSG_TYPE_CONNECT: u8 = 0x04;
+pub const MSG_TYPE_CONNACK: u8 = 0x05;
+pub const MSG_TYPE_REGISTER: u8 = 0x0A;
+pub const MSG_TYPE_REGACK: u8 = 0x0B;
+pub const MSG_TYPE_PUBLISH: u8 = 0x0C;
+pub const MSG_TYPE_PUBACK: u8 = 0x0D;
+pub const MSG_TYPE_PUBCOMP: u8 = 0x0E;
+pub const MSG_TYPE_PUBREC: u8 = 0x0F;
+pub const MSG_TYPE_PUBREL: u8 = 0x10;
+pub const MSG_TYPE_SUBSCRIBE: u8 = 0x12;
+pub const MSG_TYPE_SUBACK: u8 = 0x13;
+pub const MSG_TYPE_UNSUBSCRIBE: u8 = 0x14;
+pub const MSG_TYPE_UNSUBACK: u8 = 0x15;
+pub const MSG_TYPE_PINGREQ: u8 = 0x16;
+pub const MSG_TYPE_PINGRESP: u8 = 0x17;
+pub const MSG_TYPE_DISCONNECT: u8 = 0x18;
+
+/// QoS of an MQTT-SN message. `NoSession` is QoS -1: a PUBLISH sent without
+/// CONNECT, to a predefined topic id or a short topic name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MqttSnQoS {
+    #[default]
+    AtMostOnce,
+    AtLeastOnce,
+    ExactlyOnce,
+    NoSession,
+}
+
+/// Topic of a PUBLISH, SUBSCRIBE or UNSUBSCRIBE.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MqttSnTopic {
+    /// Topic id assigned with REGISTER or SUBACK.
+    Id(u16),
+    /// Topic id agreed out of band, configured on the gateway.
+    Predefined(u16),
+    /// Two character topic name sent in place of a topic id.
+    Short(String),
+    /// Full topic name or filter, only valid in SUBSCRIBE and UNSUBSCRIBE.
+    Name(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MqttSnReturnCode {
+    Accepted,
+    Congestion,
+    InvalidTopicId,
+    NotSupported,
+}
+
+/// Flags byte shared by most packets. Fields a packet type does not use are
+/// left at their default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MqttSnFlags {
+    pub dup: bool,
+    pub qos: MqttSnQoS,
+    pub retain: bool,
+    pub will: bool,
+    pub clean_session: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MqttSnConnect {
+    pub flags: MqttSnFlags,
+    pub duration: u16,
+    pub client_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MqttSnRegister {
+    pub topic_id: u16,
+    pub msg_id: u16,
+    pub topic_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MqttSnRegAck {
+    pub topic_id: u16,
+    pub msg_id: u16,
+    pub return_code: MqttSnReturnCode,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MqttSnPublish {
+    pub flags: MqttSnFlags,
+    pub topic: MqttSnTopic,
+    pub msg_id: u16,
+    pub data: Bytes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MqttSnPubAck {
+    pub topic_id: u16,
+    pub msg_id: u16,
+    pub return_code: MqttSnReturnCode,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MqttSnSubscribe {
+    pub flags: MqttSnFlags,
+    pub msg_id: u16,
+    pub topic: MqttSnTopic,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MqttSnSubAck {
+    pub flags: MqttSnFlags,
+    pub topic_id: u16,
+    pub msg_id: u16,
+    pub return_code: MqttSnReturnCode,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MqttSnUnsubscribe {
+    pub msg_id: u16,
+    pub topic: MqttSnTopic,
+}
+
+/// MQTT-SN v1.2 packets handled by the gateway. The will exchange
+/// (WILLTOPICREQ to WILLMSGUPD) and forwarder encapsulation are not supported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MqttSnPacket {
+    Advertise {
+        gw_id: u8,
+        duration: u16,
+    },
+    SearchGw {
+        radius: u8,
+    },
+    GwInfo {
+        gw_id: u8,
+    },
+    Connect(MqttSnConnect),
+    ConnAck {
+        return_code: MqttSnReturnCode,
+    },
+    Register(MqttSnRegister),
+    RegAck(MqttSnRegAck),
+    Publish(MqttSnPublish),
+    PubAck(MqttSnPubAck),
+    PubRec {
+        msg_id: u16,
+    },
+    PubRel {
+        msg_id: u16,
+    },
+    PubComp {
+        msg_id: u16,
+    },
+    Subscribe(MqttSnSubscribe),
+    SubAck(MqttSnSubAck),
+    Unsubscribe(MqttSnUnsubscribe),
+    UnsubAck {
+        msg_id: u16,
+    },
+    /// A client id marks a sleeping client that woke up to fetch its
+    /// buffered messages.
+    PingReq {
+        client_id: Option<String>,
+    },
+    PingResp,
+    /// A duration asks the gateway to keep the session while the client
+    /// sleeps for that many seconds.
+    Disconnect {
+        duration: Option<u16>,
+    },
+}
+
+pub fn mqtt_sn_packet_to_string(packet: &MqttSnPacket) -> &'static str {
+    match packet {
+        MqttSnPacket::Advertise { .. } => "Advertise",
+        MqttSnPacket::SearchGw { .. } => "SearchGw",
+        MqttSnPacket::GwInfo { .. } => "GwInfo",
+        MqttSnPacket::Connect(_) => "Connect",
+        MqttSnPacket::ConnAck { .. } => "ConnAck",
+        MqttSnPacket::Register(_) => "Register",
+        MqttSnPacket::RegAck(_) => "RegAck",
+        MqttSnPacket::Publish(_) => "Publish",
+        MqttSnPacket::PubAck(_) => "PubAck",
+        MqttSnPacket::PubRec { .. } => "PubRec",
+        MqttSnPacket::PubRel { .. } => "PubRel",
+        MqttSnPacket::PubComp { .. } => "PubComp",
+        MqttSnPacket::Subscribe(_) => "Subscribe",
+        MqttSnPacket::SubAck(_) => "SubAck",
+        MqttSnPacket::Unsubscribe(_) => "Unsubscribe",
+        MqttSnPacket::UnsubAck { .. } => "UnsubAck",
+        MqttSnPacket::PingReq { .. } => "PingReq",
+        MqttSnPacket::PingResp => "PingResp",
+        MqttSnPacket::Disconnect { .. } => "Disconnect",
+    }
+}
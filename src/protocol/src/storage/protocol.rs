@@ -132,6 +132,9 @@ pub struct WriteReqBody {
     pub acks: i8,
     pub current_leader_epoch: u32,
     pub timeout_ms: u64,
+    // Fencing token: with a segment, the write is rejected unless
+    // (current_segment_seq, current_leader_epoch) matches the active segment.
+    pub current_segment_seq: Option<u32>,
 }
 
 impl WriteReqBody {
@@ -142,9 +145,16 @@ pub fn new(shard_name: String, messages: Vec<Vec<u8>>) -> Self {
             acks: 1,
             current_leader_epoch: 0,
             timeout_ms: DEFAULT_WRITE_TIMEOUT_MS,
+            current_segment_seq: None,
         }
     }
 
+    pub fn with_fencing(mut self, segment_seq: u32, leader_epoch: u32) -> Self {
+        self.current_segment_seq = Some(segment_seq);
+        self.current_leader_epoch = leader_epoch;
+        self
+    }
+
     pub fn encode(&self) -> Vec<u8> {
         rkyv::to_bytes::<rkyv::rancor::Error>(self)
             .unwrap()
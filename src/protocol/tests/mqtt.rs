@@ -15,7 +15,9 @@
 #[cfg(test)]
 mod tests {
     use bytes::BytesMut;
+    use common_base::error::mqtt_protocol_error::MQTTProtocolError;
     use futures::{SinkExt, StreamExt};
+    use protocol::codec::RobustMQCodec;
     use protocol::mqtt::codec::{MqttCodec, MqttPacketWrapper};
     use protocol::mqtt::mqttv4::codec::Mqtt4Codec;
     use protocol::mqtt::mqttv5::codec::Mqtt5Codec;
@@ -62,6 +64,62 @@ async fn try_decode_data_from_mqtt_decoder() {
         assert!(packet.is_some());
     }
 
+    #[tokio::test]
+    async fn decode_rejects_packet_over_max_packet_size() {
+        let mut mqtt_codec = MqttCodec::new(None);
+        let mqtt_packet_wrapper = MqttPacketWrapper {
+            protocol_version: 4,
+            packet: build_mqtt4_connect_packet(),
+        };
+        let mut bytes_mut = BytesMut::with_capacity(0);
+        mqtt_codec
+            .encode(mqtt_packet_wrapper, &mut bytes_mut)
+            .unwrap();
+
+        mqtt_codec.set_max_packet_size(4);
+        let res = mqtt_codec.decode(&mut bytes_mut);
+        assert!(matches!(
+            res,
+            Err(MQTTProtocolError::PayloadSizeLimitExceeded(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn robustmq_codec_waits_for_incomplete_mqtt_frame() {
+        let mut mqtt_codec = MqttCodec::new(None);
+        let mqtt_packet_wrapper = MqttPacketWrapper {
+            protocol_version: 4,
+            packet: build_mqtt4_connect_packet(),
+        };
+        let mut frame = BytesMut::with_capacity(0);
+        mqtt_codec.encode(mqtt_packet_wrapper, &mut frame).unwrap();
+
+        let mut codec = RobustMQCodec::new();
+        let mut bytes_mut = frame.split_to(frame.len() - 1);
+        assert!(codec.decode_data(&mut bytes_mut).unwrap().is_none());
+
+        bytes_mut.extend_from_slice(&frame);
+        assert!(codec.decode_data(&mut bytes_mut).unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn robustmq_codec_rejects_malformed_mqtt_frame() {
+        // CONNECT with an empty protocol name.
+        let mut codec = RobustMQCodec::new();
+        let mut bytes_mut = BytesMut::from(&[0x10, 0x02, 0x00, 0x00][..]);
+        assert!(codec.decode_data(&mut bytes_mut).is_err());
+
+        // Reserved packet type 15 is not valid in MQTT 3.1.1.
+        let mut codec = RobustMQCodec::new();
+        let mut bytes_mut = BytesMut::from(&[0xF0, 0x00][..]);
+        assert!(codec.decode_data(&mut bytes_mut).is_err());
+
+        // Remaining length with a fifth continuation byte.
+        let mut codec = RobustMQCodec::new();
+        let mut bytes_mut = BytesMut::from(&[0x30, 0xFF, 0xFF, 0xFF, 0xFF, 0x01][..]);
+        assert!(codec.decode_data(&mut bytes_mut).is_err());
+    }
+
     #[tokio::test]
 
     async fn mqtt_frame_server() {
@@ -0,0 +1,196 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::ClientError;
+
+pub const LOGIN_PATH: &str = "/api/v1/login";
+pub const MESSAGE_REPLAY_CREATE_PATH: &str = "/api/mqtt/message-replay/create";
+pub const MESSAGE_REPLAY_CANCEL_PATH: &str = "/api/mqtt/message-replay/cancel";
+
+/// Body of a message replay creation, mirroring the admin server's request.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MessageReplayRequest {
+    pub tenant: String,
+    pub topic_name: String,
+    pub shard_name: Option<String>,
+    pub start_offset: Option<u64>,
+    /// Exclusive.
+    pub end_offset: Option<u64>,
+    /// Seconds, inclusive.
+    pub start_time: Option<u64>,
+    /// Seconds, inclusive.
+    pub end_time: Option<u64>,
+    pub target_topic: Option<String>,
+    pub target_client_id: Option<String>,
+    pub qos: u8,
+    /// Messages per second, 0 for no limit.
+    pub rate_limit: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessageReplayCreateResp {
+    replay_id: u64,
+}
+
+#[derive(Serialize)]
+struct MessageReplayCancelReq {
+    replay_id: u64,
+}
+
+#[derive(Serialize)]
+struct LoginRequest<'a> {
+    username: &'a str,
+    password: &'a str,
+}
+
+#[derive(Deserialize)]
+struct LoginResponse {
+    token: String,
+}
+
+/// Client for the broker's admin HTTP API. Requests from loopback addresses
+/// are accepted without a token; anything else needs [`AdminClient::login`]
+/// or [`AdminClient::with_token`] first.
+#[derive(Clone)]
+pub struct AdminClient {
+    client: Client,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl AdminClient {
+    /// `base_url` is the admin server root, e.g. `http://127.0.0.1:58080`.
+    pub fn new(base_url: impl Into<String>) -> Result<Self, ClientError> {
+        let client = Client::builder().timeout(Duration::from_secs(30)).build()?;
+        Ok(AdminClient {
+            client,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            token: None,
+        })
+    }
+
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Exchanges the admin credentials for a token used by later requests.
+    pub async fn login(&mut self, username: &str, password: &str) -> Result<(), ClientError> {
+        let resp: LoginResponse = self
+            .post(LOGIN_PATH, &LoginRequest { username, password })
+            .await?;
+        self.token = Some(resp.token);
+        Ok(())
+    }
+
+    /// Starts a message replay and returns its id.
+    pub async fn create_message_replay(
+        &self,
+        request: &MessageReplayRequest,
+    ) -> Result<u64, ClientError> {
+        let resp: MessageReplayCreateResp = self.post(MESSAGE_REPLAY_CREATE_PATH, request).await?;
+        Ok(resp.replay_id)
+    }
+
+    pub async fn cancel_message_replay(&self, replay_id: u64) -> Result<(), ClientError> {
+        let _: Value = self
+            .post(
+                MESSAGE_REPLAY_CANCEL_PATH,
+                &MessageReplayCancelReq { replay_id },
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// POSTs `body` as JSON to any admin path and returns the `data` field of
+    /// the response.
+    pub async fn post<T, R>(&self, path: &str, body: &T) -> Result<R, ClientError>
+    where
+        T: Serialize + ?Sized,
+        R: DeserializeOwned,
+    {
+        let mut request = self
+            .client
+            .post(format!("{}{}", self.base_url, path))
+            .json(body);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        let text = response.text().await?;
+        let envelope = match serde_json::from_str::<Value>(&text) {
+            Ok(envelope) => envelope,
+            Err(_) if !status.is_success() => {
+                return Err(ClientError::AdminError {
+                    code: status.as_u16() as i64,
+                    message: text,
+                })
+            }
+            Err(e) => return Err(e.into()),
+        };
+        parse_envelope(envelope)
+    }
+}
+
+/// Admin responses are `{code, data, ...}` with `code == 0` on success; the
+/// failure text is carried in `error` or `message` depending on the handler.
+fn parse_envelope<R: DeserializeOwned>(mut envelope: Value) -> Result<R, ClientError> {
+    let code = envelope.get("code").and_then(Value::as_i64).unwrap_or(-1);
+    if code != 0 {
+        let message = ["error", "message"]
+            .iter()
+            .find_map(|key| envelope.get(*key).and_then(Value::as_str))
+            .unwrap_or_default()
+            .to_string();
+        return Err(ClientError::AdminError { code, message });
+    }
+    let data = envelope
+        .get_mut("data")
+        .map(Value::take)
+        .unwrap_or(Value::Null);
+    Ok(serde_json::from_value(data)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_envelope() {
+        let id: MessageReplayCreateResp =
+            parse_envelope(json!({"code": 0, "data": {"replay_id": 9}, "error": null})).unwrap();
+        assert_eq!(id.replay_id, 9);
+
+        let err = parse_envelope::<Value>(json!({"code": 100, "data": "", "error": "no topic"}))
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ClientError::AdminError { code: 100, ref message } if message == "no topic"
+        ));
+
+        let err = parse_envelope::<Value>(
+            json!({"code": 401, "data": null, "message": "Invalid username or password"}),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ClientError::AdminError { code: 401, .. }));
+    }
+}
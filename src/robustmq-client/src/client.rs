@@ -0,0 +1,574 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::time::Duration;
+
+use bytes::Bytes;
+use common_base::tools::now_second;
+use futures::{SinkExt, StreamExt};
+use protocol::mqtt::common::{
+    ConnectReturnCode, Disconnect, DisconnectReasonCode, Filter, MqttPacket, PingReq, PubAck,
+    PubAckReason, PubComp, PubCompReason, PubRec, PubRecReason, PubRel, PubRelReason, Publish,
+    PublishProperties, QoS, Subscribe, SubscribeReasonCode, Unsubscribe,
+};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio::time::{interval_at, timeout, Instant};
+use tokio_util::codec::Framed;
+use tracing::{debug, warn};
+
+use crate::admin::AdminClient;
+use crate::codec::ClientCodec;
+use crate::error::ClientError;
+use crate::options::ConnectOptions;
+use crate::replay::ReplaySubscription;
+
+const DELAYED_TOPIC_PREFIX: &str = "$delayed";
+
+/// A PUBLISH received from the broker.
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub topic: String,
+    pub payload: Bytes,
+    pub qos: QoS,
+    pub retain: bool,
+    pub properties: Option<PublishProperties>,
+}
+
+struct Command {
+    packet: MqttPacket,
+    /// Registered before the packet is written, so the ack cannot race it.
+    ack: Option<(u16, oneshot::Sender<MqttPacket>)>,
+}
+
+/// MQTT 5 client. A background task owns the connection: it answers the
+/// broker's QoS handshakes, sends keep-alive pings and routes acks back to
+/// the call that is waiting for them.
+pub struct MqttClient {
+    client_id: String,
+    ack_timeout: Duration,
+    next_pkid: AtomicU16,
+    commands: mpsc::UnboundedSender<Command>,
+    incoming: mpsc::UnboundedReceiver<Message>,
+    event_loop: JoinHandle<()>,
+}
+
+impl MqttClient {
+    pub async fn connect(options: ConnectOptions) -> Result<Self, ClientError> {
+        let (framed, assigned_client_id) = timeout(options.connect_timeout, handshake(&options))
+            .await
+            .map_err(|_| ClientError::Timeout("CONNACK".to_string()))??;
+
+        let (commands, command_rx) = mpsc::unbounded_channel();
+        let (incoming_tx, incoming) = mpsc::unbounded_channel();
+        let event_loop = tokio::spawn(run(framed, command_rx, incoming_tx, options.keep_alive));
+
+        Ok(MqttClient {
+            client_id: assigned_client_id.unwrap_or(options.client_id),
+            ack_timeout: options.ack_timeout,
+            next_pkid: AtomicU16::new(1),
+            commands,
+            incoming,
+            event_loop,
+        })
+    }
+
+    /// The id the broker knows this client by, including an assigned one.
+    pub fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    /// Resolves once the broker has acknowledged the message at `qos`.
+    pub async fn publish(
+        &self,
+        topic: &str,
+        payload: impl Into<Bytes>,
+        qos: QoS,
+        retain: bool,
+    ) -> Result<(), ClientError> {
+        self.publish_with_properties(topic, payload, qos, retain, None)
+            .await
+    }
+
+    pub async fn publish_with_properties(
+        &self,
+        topic: &str,
+        payload: impl Into<Bytes>,
+        qos: QoS,
+        retain: bool,
+        properties: Option<PublishProperties>,
+    ) -> Result<(), ClientError> {
+        let p_kid = if qos == QoS::AtMostOnce {
+            0
+        } else {
+            self.next_pkid()
+        };
+        let packet = MqttPacket::Publish(
+            Publish {
+                dup: false,
+                qos,
+                p_kid,
+                retain,
+                topic: Bytes::copy_from_slice(topic.as_bytes()),
+                payload: payload.into(),
+            },
+            properties,
+        );
+
+        match qos {
+            QoS::AtMostOnce => self.send(packet),
+            QoS::AtLeastOnce => match self.request(packet, p_kid, "PUBACK").await? {
+                MqttPacket::PubAck(PubAck { reason, .. }, _) => check_puback(p_kid, reason),
+                other => Err(unexpected("PUBACK", &other)),
+            },
+            QoS::ExactlyOnce => {
+                match self.request(packet, p_kid, "PUBREC").await? {
+                    MqttPacket::PubRec(PubRec { reason, .. }, _) => check_pubrec(p_kid, reason)?,
+                    other => return Err(unexpected("PUBREC", &other)),
+                }
+                let pubrel = MqttPacket::PubRel(
+                    PubRel {
+                        pkid: p_kid,
+                        reason: Some(PubRelReason::Success),
+                    },
+                    None,
+                );
+                match self.request(pubrel, p_kid, "PUBCOMP").await? {
+                    MqttPacket::PubComp(PubComp { reason, .. }, _) => match reason {
+                        None | Some(PubCompReason::Success) => Ok(()),
+                        Some(reason) => {
+                            Err(ClientError::PublishRejected(p_kid, format!("{reason:?}")))
+                        }
+                    },
+                    other => Err(unexpected("PUBCOMP", &other)),
+                }
+            }
+        }
+    }
+
+    /// Publishes through the broker's delayed-publish topic so the message
+    /// reaches `topic` after `delay`. The broker delivers it to the target
+    /// topic with a leading `/`.
+    pub async fn publish_delayed(
+        &self,
+        topic: &str,
+        delay: Duration,
+        payload: impl Into<Bytes>,
+        qos: QoS,
+    ) -> Result<(), ClientError> {
+        let topic = delayed_topic(topic, delay)?;
+        self.publish(&topic, payload, qos, false).await
+    }
+
+    /// Returns the QoS granted by the broker.
+    pub async fn subscribe(&self, filter: &str, qos: QoS) -> Result<QoS, ClientError> {
+        let pkid = self.next_pkid();
+        let packet = MqttPacket::Subscribe(
+            Subscribe {
+                packet_identifier: pkid,
+                filters: vec![Filter {
+                    path: filter.to_string(),
+                    qos,
+                    ..Default::default()
+                }],
+            },
+            None,
+        );
+        match self.request(packet, pkid, "SUBACK").await? {
+            MqttPacket::SubAck(suback, _) => match suback.return_codes.first() {
+                Some(code) => granted_qos(code).ok_or_else(|| {
+                    ClientError::SubscribeRejected(filter.to_string(), format!("{code:?}"))
+                }),
+                None => Err(ClientError::SubscribeRejected(
+                    filter.to_string(),
+                    "empty SUBACK".to_string(),
+                )),
+            },
+            other => Err(unexpected("SUBACK", &other)),
+        }
+    }
+
+    pub async fn unsubscribe(&self, filter: &str) -> Result<(), ClientError> {
+        let pkid = self.next_pkid();
+        let packet = MqttPacket::Unsubscribe(
+            Unsubscribe {
+                pkid,
+                filters: vec![filter.to_string()],
+            },
+            None,
+        );
+        match self.request(packet, pkid, "UNSUBACK").await? {
+            MqttPacket::UnsubAck(..) => Ok(()),
+            other => Err(unexpected("UNSUBACK", &other)),
+        }
+    }
+
+    /// Subscribes to `subscription.topic` and has the broker replay the
+    /// topic's stored history to this client, from the requested offset or
+    /// time up to now. The live subscription is made first, so nothing is
+    /// missed at the switch-over, but messages published around it may
+    /// arrive twice. Returns the replay id, which can be cancelled through
+    /// [`AdminClient::cancel_message_replay`].
+    pub async fn replay_subscribe(
+        &self,
+        admin: &AdminClient,
+        subscription: &ReplaySubscription,
+    ) -> Result<u64, ClientError> {
+        self.subscribe(&subscription.topic, subscription.qos)
+            .await?;
+        let request = subscription.to_request(&self.client_id, now_second());
+        admin.create_message_replay(&request).await
+    }
+
+    /// Next message delivered by the broker, or `None` once the connection
+    /// is gone.
+    pub async fn recv(&mut self) -> Option<Message> {
+        self.incoming.recv().await
+    }
+
+    pub async fn disconnect(self) -> Result<(), ClientError> {
+        self.send(MqttPacket::Disconnect(
+            Disconnect {
+                reason_code: Some(DisconnectReasonCode::NormalDisconnection),
+            },
+            None,
+        ))?;
+        // The event loop stops after writing the DISCONNECT.
+        let _ = self.event_loop.await;
+        Ok(())
+    }
+
+    fn next_pkid(&self) -> u16 {
+        loop {
+            let pkid = self.next_pkid.fetch_add(1, Ordering::Relaxed);
+            if pkid != 0 {
+                return pkid;
+            }
+        }
+    }
+
+    fn send(&self, packet: MqttPacket) -> Result<(), ClientError> {
+        self.commands
+            .send(Command { packet, ack: None })
+            .map_err(|_| ClientError::ConnectionClosed)
+    }
+
+    async fn request(
+        &self,
+        packet: MqttPacket,
+        pkid: u16,
+        expected: &str,
+    ) -> Result<MqttPacket, ClientError> {
+        let (tx, rx) = oneshot::channel();
+        self.commands
+            .send(Command {
+                packet,
+                ack: Some((pkid, tx)),
+            })
+            .map_err(|_| ClientError::ConnectionClosed)?;
+        match timeout(self.ack_timeout, rx).await {
+            Ok(Ok(packet)) => Ok(packet),
+            Ok(Err(_)) => Err(ClientError::ConnectionClosed),
+            Err(_) => Err(ClientError::Timeout(format!("{expected} of packet {pkid}"))),
+        }
+    }
+}
+
+async fn handshake(
+    options: &ConnectOptions,
+) -> Result<(Framed<TcpStream, ClientCodec>, Option<String>), ClientError> {
+    let stream = TcpStream::connect(&options.addr).await?;
+    stream.set_nodelay(true)?;
+    let mut framed = Framed::new(stream, ClientCodec::new());
+    framed.send(options.connect_packet()).await?;
+
+    match framed.next().await {
+        Some(Ok(MqttPacket::ConnAck(connack, properties))) => {
+            if connack.code != ConnectReturnCode::Success {
+                return Err(ClientError::ConnectionRefused(format!(
+                    "{:?}",
+                    connack.code
+                )));
+            }
+            let assigned = properties.and_then(|p| p.assigned_client_identifier);
+            Ok((framed, assigned))
+        }
+        Some(Ok(other)) => Err(unexpected("CONNACK", &other)),
+        Some(Err(e)) => Err(e),
+        None => Err(ClientError::ConnectionClosed),
+    }
+}
+
+async fn run(
+    mut framed: Framed<TcpStream, ClientCodec>,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+    incoming: mpsc::UnboundedSender<Message>,
+    keep_alive: Duration,
+) {
+    let mut pending: HashMap<u16, oneshot::Sender<MqttPacket>> = HashMap::new();
+    let ping_enabled = !keep_alive.is_zero();
+    let ping_every = keep_alive.max(Duration::from_secs(1));
+    let mut ping = interval_at(Instant::now() + ping_every, ping_every);
+
+    loop {
+        tokio::select! {
+            command = commands.recv() => {
+                let Some(Command { packet, ack }) = command else {
+                    break;
+                };
+                let is_disconnect = matches!(packet, MqttPacket::Disconnect(..));
+                if let Some((pkid, tx)) = ack {
+                    pending.insert(pkid, tx);
+                }
+                if let Err(e) = framed.send(packet).await {
+                    warn!("Failed to write MQTT packet: {e}");
+                    break;
+                }
+                if is_disconnect {
+                    break;
+                }
+            }
+            frame = framed.next() => {
+                let packet = match frame {
+                    Some(Ok(packet)) => packet,
+                    Some(Err(e)) => {
+                        warn!("Failed to read MQTT packet: {e}");
+                        break;
+                    }
+                    None => break,
+                };
+                if let MqttPacket::Disconnect(disconnect, _) = &packet {
+                    debug!("Broker disconnected: {:?}", disconnect.reason_code);
+                    break;
+                }
+                if let Some(reply) = handle_packet(packet, &mut pending, &incoming) {
+                    if let Err(e) = framed.send(reply).await {
+                        warn!("Failed to write MQTT packet: {e}");
+                        break;
+                    }
+                }
+            }
+            _ = ping.tick(), if ping_enabled => {
+                if let Err(e) = framed.send(MqttPacket::PingReq(PingReq)).await {
+                    warn!("Failed to send PINGREQ: {e}");
+                    break;
+                }
+            }
+        }
+    }
+    // Dropping `pending` fails every in-flight call with ConnectionClosed.
+}
+
+/// Routes one packet from the broker and returns the reply it needs, if any.
+fn handle_packet(
+    packet: MqttPacket,
+    pending: &mut HashMap<u16, oneshot::Sender<MqttPacket>>,
+    incoming: &mpsc::UnboundedSender<Message>,
+) -> Option<MqttPacket> {
+    match packet {
+        MqttPacket::Publish(publish, properties) => {
+            let reply = match publish.qos {
+                QoS::AtMostOnce => None,
+                QoS::AtLeastOnce => Some(MqttPacket::PubAck(
+                    PubAck {
+                        pkid: publish.p_kid,
+                        reason: Some(PubAckReason::Success),
+                    },
+                    None,
+                )),
+                QoS::ExactlyOnce => Some(MqttPacket::PubRec(
+                    PubRec {
+                        pkid: publish.p_kid,
+                        reason: Some(PubRecReason::Success),
+                    },
+                    None,
+                )),
+            };
+            let _ = incoming.send(Message {
+                topic: String::from_utf8_lossy(&publish.topic).into_owned(),
+                payload: publish.payload,
+                qos: publish.qos,
+                retain: publish.retain,
+                properties,
+            });
+            reply
+        }
+        MqttPacket::PubRel(pubrel, _) => Some(MqttPacket::PubComp(
+            PubComp {
+                pkid: pubrel.pkid,
+                reason: Some(PubCompReason::Success),
+            },
+            None,
+        )),
+        packet => {
+            if let Some(pkid) = ack_pkid(&packet) {
+                match pending.remove(&pkid) {
+                    Some(tx) => {
+                        let _ = tx.send(packet);
+                    }
+                    None => debug!("Ack for unknown packet id {pkid}"),
+                }
+            }
+            None
+        }
+    }
+}
+
+fn ack_pkid(packet: &MqttPacket) -> Option<u16> {
+    match packet {
+        MqttPacket::PubAck(ack, _) => Some(ack.pkid),
+        MqttPacket::PubRec(ack, _) => Some(ack.pkid),
+        MqttPacket::PubComp(ack, _) => Some(ack.pkid),
+        MqttPacket::SubAck(ack, _) => Some(ack.pkid),
+        MqttPacket::UnsubAck(ack, _) => Some(ack.pkid),
+        _ => None,
+    }
+}
+
+/// `$delayed/{seconds}/{topic}`.
+pub fn delayed_topic(topic: &str, delay: Duration) -> Result<String, ClientError> {
+    let target = topic.strip_prefix('/').unwrap_or(topic);
+    if target.is_empty() {
+        return Err(ClientError::InvalidArgument(
+            "delayed publish needs a target topic".to_string(),
+        ));
+    }
+    Ok(format!(
+        "{DELAYED_TOPIC_PREFIX}/{}/{target}",
+        delay.as_secs()
+    ))
+}
+
+fn granted_qos(code: &SubscribeReasonCode) -> Option<QoS> {
+    match code {
+        SubscribeReasonCode::QoS0 => Some(QoS::AtMostOnce),
+        SubscribeReasonCode::QoS1 => Some(QoS::AtLeastOnce),
+        SubscribeReasonCode::QoS2 => Some(QoS::ExactlyOnce),
+        SubscribeReasonCode::Success(qos) => Some(*qos),
+        _ => None,
+    }
+}
+
+fn check_puback(pkid: u16, reason: Option<PubAckReason>) -> Result<(), ClientError> {
+    match reason {
+        None | Some(PubAckReason::Success) | Some(PubAckReason::NoMatchingSubscribers) => Ok(()),
+        Some(reason) => Err(ClientError::PublishRejected(pkid, format!("{reason:?}"))),
+    }
+}
+
+fn check_pubrec(pkid: u16, reason: Option<PubRecReason>) -> Result<(), ClientError> {
+    match reason {
+        None | Some(PubRecReason::Success) | Some(PubRecReason::NoMatchingSubscribers) => Ok(()),
+        Some(reason) => Err(ClientError::PublishRejected(pkid, format!("{reason:?}"))),
+    }
+}
+
+fn unexpected(expected: &str, received: &MqttPacket) -> ClientError {
+    ClientError::UnexpectedPacket {
+        expected: expected.to_string(),
+        received: format!("{received:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protocol::mqtt::common::SubAck;
+
+    #[test]
+    fn test_delayed_topic() {
+        let delay = Duration::from_secs(15);
+        assert_eq!(delayed_topic("x/y", delay).unwrap(), "$delayed/15/x/y");
+        assert_eq!(delayed_topic("/x/y", delay).unwrap(), "$delayed/15/x/y");
+        assert!(delayed_topic("/", delay).is_err());
+        assert!(delayed_topic("", delay).is_err());
+    }
+
+    #[test]
+    fn test_granted_qos() {
+        assert_eq!(
+            granted_qos(&SubscribeReasonCode::QoS1),
+            Some(QoS::AtLeastOnce)
+        );
+        assert_eq!(
+            granted_qos(&SubscribeReasonCode::Success(QoS::ExactlyOnce)),
+            Some(QoS::ExactlyOnce)
+        );
+        assert_eq!(granted_qos(&SubscribeReasonCode::NotAuthorized), None);
+    }
+
+    #[test]
+    fn test_handle_packet_acks_and_routes() {
+        let (incoming_tx, mut incoming_rx) = mpsc::unbounded_channel();
+        let mut pending = HashMap::new();
+
+        let reply = handle_packet(
+            MqttPacket::Publish(
+                Publish {
+                    qos: QoS::AtLeastOnce,
+                    p_kid: 3,
+                    topic: Bytes::from_static(b"a/b"),
+                    payload: Bytes::from_static(b"hi"),
+                    ..Default::default()
+                },
+                None,
+            ),
+            &mut pending,
+            &incoming_tx,
+        );
+        assert!(matches!(
+            reply,
+            Some(MqttPacket::PubAck(PubAck { pkid: 3, .. }, _))
+        ));
+        let message = incoming_rx.try_recv().unwrap();
+        assert_eq!(message.topic, "a/b");
+        assert_eq!(message.payload, Bytes::from_static(b"hi"));
+
+        let reply = handle_packet(
+            MqttPacket::PubRel(
+                PubRel {
+                    pkid: 4,
+                    reason: None,
+                },
+                None,
+            ),
+            &mut pending,
+            &incoming_tx,
+        );
+        assert!(matches!(
+            reply,
+            Some(MqttPacket::PubComp(PubComp { pkid: 4, .. }, _))
+        ));
+
+        let (tx, mut rx) = oneshot::channel();
+        pending.insert(5, tx);
+        let reply = handle_packet(
+            MqttPacket::SubAck(
+                SubAck {
+                    pkid: 5,
+                    return_codes: vec![SubscribeReasonCode::QoS1],
+                },
+                None,
+            ),
+            &mut pending,
+            &incoming_tx,
+        );
+        assert!(reply.is_none());
+        assert!(pending.is_empty());
+        assert!(matches!(rx.try_recv(), Ok(MqttPacket::SubAck(..))));
+    }
+}
@@ -0,0 +1,105 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bytes::BytesMut;
+use common_base::error::mqtt_protocol_error::MQTTProtocolError;
+use protocol::mqtt::codec::{MqttCodec, MqttPacketWrapper};
+use protocol::mqtt::common::MqttPacket;
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::error::ClientError;
+
+/// The client only speaks MQTT 5.
+pub const PROTOCOL_VERSION: u8 = 5;
+
+/// [`MqttCodec`] reports a partially received frame as `InsufficientBytes`;
+/// a framed stream needs `None` instead so it keeps reading.
+pub struct ClientCodec {
+    inner: MqttCodec,
+}
+
+impl ClientCodec {
+    pub fn new() -> Self {
+        ClientCodec {
+            inner: MqttCodec::new(Some(PROTOCOL_VERSION)),
+        }
+    }
+}
+
+impl Default for ClientCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for ClientCodec {
+    type Item = MqttPacket;
+    type Error = ClientError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.inner.decode_data(src) {
+            Ok(packet) => Ok(packet),
+            Err(MQTTProtocolError::InsufficientBytes(_)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl Encoder<MqttPacket> for ClientCodec {
+    type Error = ClientError;
+
+    fn encode(&mut self, packet: MqttPacket, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.inner.encode_data(
+            MqttPacketWrapper {
+                protocol_version: PROTOCOL_VERSION,
+                packet,
+            },
+            dst,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protocol::mqtt::common::{PubAck, PubAckReason};
+
+    #[test]
+    fn test_partial_frame_waits_for_more_bytes() {
+        let mut codec = ClientCodec::new();
+        let mut buf = BytesMut::new();
+        codec
+            .encode(
+                MqttPacket::PubAck(
+                    PubAck {
+                        pkid: 7,
+                        reason: Some(PubAckReason::Success),
+                    },
+                    None,
+                ),
+                &mut buf,
+            )
+            .unwrap();
+
+        let mut partial = buf.split_to(1);
+        assert!(codec.decode(&mut partial).unwrap().is_none());
+
+        partial.unsplit(buf);
+        match codec.decode(&mut partial).unwrap() {
+            Some(MqttPacket::PubAck(ack, _)) => assert_eq!(ack.pkid, 7),
+            other => panic!("unexpected packet: {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::error::mqtt_protocol_error::MQTTProtocolError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ClientError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Protocol error: {0}")]
+    Protocol(#[from] MQTTProtocolError),
+
+    #[error("Connection refused by broker: {0}")]
+    ConnectionRefused(String),
+
+    #[error("Connection closed")]
+    ConnectionClosed,
+
+    #[error("Timed out waiting for {0}")]
+    Timeout(String),
+
+    #[error("Unexpected packet while waiting for {expected}: {received}")]
+    UnexpectedPacket { expected: String, received: String },
+
+    #[error("Publish of packet {0} rejected: {1}")]
+    PublishRejected(u16, String),
+
+    #[error("Subscription to {0} rejected: {1}")]
+    SubscribeRejected(String, String),
+
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+
+    #[error("Admin request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("JSON serialization failed: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Admin server returned error: code={code}, message={message}")]
+    AdminError { code: i64, message: String },
+}
@@ -0,0 +1,53 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Async MQTT 5 client for RobustMQ.
+//!
+//! Besides plain publish/subscribe, the client wraps a few RobustMQ
+//! extensions:
+//!
+//! - [`MqttClient::publish_delayed`] publishes through the `$delayed/` topic
+//!   prefix so the broker delivers the message later.
+//! - [`MqttClient::replay_subscribe`] subscribes to a topic and asks the broker
+//!   to replay its history, from an offset or a point in time, to this client.
+//! - [`AdminClient`] calls the admin HTTP API.
+//!
+//! ```no_run
+//! use robustmq_client::{ConnectOptions, MqttClient, QoS};
+//!
+//! # async fn run() -> Result<(), robustmq_client::ClientError> {
+//! let mut client = MqttClient::connect(ConnectOptions::new("127.0.0.1:1883", "demo")).await?;
+//! client.subscribe("sensor/+", QoS::AtLeastOnce).await?;
+//! client.publish("sensor/1", "25.5", QoS::AtLeastOnce, false).await?;
+//! if let Some(message) = client.recv().await {
+//!     println!("{} {:?}", message.topic, message.payload);
+//! }
+//! client.disconnect().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod admin;
+pub mod client;
+pub mod codec;
+pub mod error;
+pub mod options;
+pub mod replay;
+
+pub use admin::AdminClient;
+pub use client::{Message, MqttClient};
+pub use error::ClientError;
+pub use options::ConnectOptions;
+pub use protocol::mqtt::common::{PublishProperties, QoS};
+pub use replay::{ReplayStart, ReplaySubscription};
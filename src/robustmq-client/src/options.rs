@@ -0,0 +1,142 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use protocol::mqtt::common::{Connect, ConnectProperties, Login, MqttPacket};
+
+use crate::codec::PROTOCOL_VERSION;
+
+/// Settings for [`crate::MqttClient::connect`].
+#[derive(Debug, Clone)]
+pub struct ConnectOptions {
+    /// `host:port` of the broker's MQTT TCP listener.
+    pub addr: String,
+    /// Left empty, the broker assigns one and the client adopts it.
+    pub client_id: String,
+    /// Zero disables keep-alive pings.
+    pub keep_alive: Duration,
+    pub clean_start: bool,
+    /// Seconds the broker keeps the session after the connection closes.
+    pub session_expiry_interval: Option<u32>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Bounds the TCP connect plus the CONNECT/CONNACK exchange.
+    pub connect_timeout: Duration,
+    /// How long publish, subscribe and unsubscribe wait for each ack.
+    pub ack_timeout: Duration,
+}
+
+impl ConnectOptions {
+    pub fn new(addr: impl Into<String>, client_id: impl Into<String>) -> Self {
+        ConnectOptions {
+            addr: addr.into(),
+            client_id: client_id.into(),
+            keep_alive: Duration::from_secs(60),
+            clean_start: true,
+            session_expiry_interval: None,
+            username: None,
+            password: None,
+            connect_timeout: Duration::from_secs(10),
+            ack_timeout: Duration::from_secs(30),
+        }
+    }
+
+    pub fn with_keep_alive(mut self, keep_alive: Duration) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    pub fn with_clean_start(mut self, clean_start: bool) -> Self {
+        self.clean_start = clean_start;
+        self
+    }
+
+    pub fn with_session_expiry_interval(mut self, seconds: u32) -> Self {
+        self.session_expiry_interval = Some(seconds);
+        self
+    }
+
+    pub fn with_credentials(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.username = Some(username.into());
+        self.password = Some(password.into());
+        self
+    }
+
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    pub fn with_ack_timeout(mut self, timeout: Duration) -> Self {
+        self.ack_timeout = timeout;
+        self
+    }
+
+    pub(crate) fn connect_packet(&self) -> MqttPacket {
+        let keep_alive = self.keep_alive.as_secs().min(u16::MAX as u64) as u16;
+        let login = self.username.as_ref().map(|username| Login {
+            username: username.clone(),
+            password: self.password.clone().unwrap_or_default(),
+        });
+        let properties = ConnectProperties {
+            session_expiry_interval: self.session_expiry_interval,
+            ..Default::default()
+        };
+        MqttPacket::Connect(
+            PROTOCOL_VERSION,
+            Connect {
+                keep_alive,
+                client_id: self.client_id.clone(),
+                clean_session: self.clean_start,
+            },
+            Some(properties),
+            None,
+            None,
+            login,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connect_packet() {
+        let options = ConnectOptions::new("127.0.0.1:1883", "c1")
+            .with_keep_alive(Duration::from_secs(100_000))
+            .with_clean_start(false)
+            .with_session_expiry_interval(30)
+            .with_credentials("admin", "secret");
+
+        let MqttPacket::Connect(version, connect, properties, _, _, login) =
+            options.connect_packet()
+        else {
+            panic!("not a CONNECT packet");
+        };
+        assert_eq!(version, 5);
+        assert_eq!(connect.client_id, "c1");
+        assert_eq!(connect.keep_alive, u16::MAX);
+        assert!(!connect.clean_session);
+        assert_eq!(properties.unwrap().session_expiry_interval, Some(30));
+        let login = login.unwrap();
+        assert_eq!(login.username, "admin");
+        assert_eq!(login.password, "secret");
+    }
+}
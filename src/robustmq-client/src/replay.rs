@@ -0,0 +1,115 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use protocol::mqtt::common::QoS;
+
+use crate::admin::MessageReplayRequest;
+
+/// Where a replay starts reading the topic's history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayStart {
+    /// Storage offset, applied to every shard of the topic unless a shard is
+    /// pinned with [`ReplaySubscription::with_shard`].
+    Offset(u64),
+    /// Unix timestamp in seconds.
+    Time(u64),
+}
+
+/// Parameters of [`crate::MqttClient::replay_subscribe`].
+#[derive(Debug, Clone)]
+pub struct ReplaySubscription {
+    pub tenant: String,
+    pub topic: String,
+    pub start: ReplayStart,
+    pub shard_name: Option<String>,
+    pub qos: QoS,
+    /// Messages per second, 0 for no limit.
+    pub rate_limit: u32,
+}
+
+impl ReplaySubscription {
+    pub fn new(tenant: impl Into<String>, topic: impl Into<String>, start: ReplayStart) -> Self {
+        ReplaySubscription {
+            tenant: tenant.into(),
+            topic: topic.into(),
+            start,
+            shard_name: None,
+            qos: QoS::AtLeastOnce,
+            rate_limit: 0,
+        }
+    }
+
+    pub fn with_shard(mut self, shard_name: impl Into<String>) -> Self {
+        self.shard_name = Some(shard_name.into());
+        self
+    }
+
+    pub fn with_qos(mut self, qos: QoS) -> Self {
+        self.qos = qos;
+        self
+    }
+
+    pub fn with_rate_limit(mut self, rate_limit: u32) -> Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    /// The replay stops at `now`; anything newer reaches the client through
+    /// its live subscription.
+    pub(crate) fn to_request(&self, client_id: &str, now: u64) -> MessageReplayRequest {
+        let (start_offset, start_time) = match self.start {
+            ReplayStart::Offset(offset) => (Some(offset), None),
+            ReplayStart::Time(time) => (None, Some(time)),
+        };
+        MessageReplayRequest {
+            tenant: self.tenant.clone(),
+            topic_name: self.topic.clone(),
+            shard_name: self.shard_name.clone(),
+            start_offset,
+            start_time,
+            end_time: Some(now),
+            target_client_id: Some(client_id.to_string()),
+            qos: self.qos as u8,
+            rate_limit: self.rate_limit,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_request() {
+        let sub = ReplaySubscription::new("t1", "a/b", ReplayStart::Offset(42))
+            .with_qos(QoS::ExactlyOnce)
+            .with_rate_limit(100);
+        let req = sub.to_request("c1", 1_700_000_000);
+        assert_eq!(req.start_offset, Some(42));
+        assert_eq!(req.start_time, None);
+        assert_eq!(req.end_offset, None);
+        assert_eq!(req.end_time, Some(1_700_000_000));
+        assert_eq!(req.target_client_id.as_deref(), Some("c1"));
+        assert_eq!(req.target_topic, None);
+        assert_eq!(req.qos, 2);
+        assert_eq!(req.rate_limit, 100);
+
+        let req = ReplaySubscription::new("t1", "a/b", ReplayStart::Time(1_600_000_000))
+            .to_request("c1", 1_700_000_000);
+        assert_eq!(req.start_offset, None);
+        assert_eq!(req.start_time, Some(1_600_000_000));
+        assert_eq!(req.qos, 1);
+    }
+}
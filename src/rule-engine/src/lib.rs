@@ -28,6 +28,7 @@
 pub mod encode;
 pub mod operator;
 pub mod rule_trait;
+pub mod sql;
 #[cfg(test)]
 pub mod test_data;
 
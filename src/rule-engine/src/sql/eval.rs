@@ -0,0 +1,154 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::parser::{number_value, BinaryOp, Expr};
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// Evaluate `expr` against a message context.
+///
+/// Evaluation never fails: missing fields, type mismatches and division by
+/// zero all yield `null`, and a `null` condition does not match.
+pub fn eval_expr(expr: &Expr, ctx: &Value) -> Value {
+    match expr {
+        Expr::Literal(value) => value.clone(),
+        Expr::Field(path) => lookup(ctx, path).cloned().unwrap_or(Value::Null),
+        Expr::Not(inner) => match eval_expr(inner, ctx) {
+            Value::Bool(b) => Value::Bool(!b),
+            _ => Value::Null,
+        },
+        Expr::Neg(inner) => match eval_expr(inner, ctx).as_f64() {
+            Some(n) => number_value(-n),
+            None => Value::Null,
+        },
+        Expr::Binary(BinaryOp::And, left, right) => {
+            Value::Bool(is_true(&eval_expr(left, ctx)) && is_true(&eval_expr(right, ctx)))
+        }
+        Expr::Binary(BinaryOp::Or, left, right) => {
+            Value::Bool(is_true(&eval_expr(left, ctx)) || is_true(&eval_expr(right, ctx)))
+        }
+        Expr::Binary(op, left, right) => {
+            let left = eval_expr(left, ctx);
+            let right = eval_expr(right, ctx);
+            match op {
+                BinaryOp::Eq => Value::Bool(values_equal(&left, &right)),
+                BinaryOp::NotEq => Value::Bool(!values_equal(&left, &right)),
+                BinaryOp::Lt => compare(&left, &right, |o| o == Ordering::Less),
+                BinaryOp::Le => compare(&left, &right, |o| o != Ordering::Greater),
+                BinaryOp::Gt => compare(&left, &right, |o| o == Ordering::Greater),
+                BinaryOp::Ge => compare(&left, &right, |o| o != Ordering::Less),
+                _ => arithmetic(op, &left, &right),
+            }
+        }
+    }
+}
+
+pub fn is_true(value: &Value) -> bool {
+    matches!(value, Value::Bool(true))
+}
+
+fn lookup<'a>(ctx: &'a Value, path: &[String]) -> Option<&'a Value> {
+    let mut current = ctx;
+    for segment in path {
+        current = match current {
+            Value::Object(map) => map.get(segment)?,
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(current)
+}
+
+fn values_equal(left: &Value, right: &Value) -> bool {
+    match (left.as_f64(), right.as_f64()) {
+        (Some(l), Some(r)) => l == r,
+        _ => left == right,
+    }
+}
+
+fn compare(left: &Value, right: &Value, check: impl Fn(Ordering) -> bool) -> Value {
+    let ordering = match (left, right) {
+        (Value::Number(_), Value::Number(_)) => left
+            .as_f64()
+            .zip(right.as_f64())
+            .and_then(|(l, r)| l.partial_cmp(&r)),
+        (Value::String(l), Value::String(r)) => Some(l.cmp(r)),
+        _ => None,
+    };
+    match ordering {
+        Some(ordering) => Value::Bool(check(ordering)),
+        None => Value::Null,
+    }
+}
+
+fn arithmetic(op: &BinaryOp, left: &Value, right: &Value) -> Value {
+    if let (BinaryOp::Add, Value::String(l), Value::String(r)) = (op, left, right) {
+        return Value::String(format!("{}{}", l, r));
+    }
+
+    let (Some(l), Some(r)) = (left.as_f64(), right.as_f64()) else {
+        return Value::Null;
+    };
+    let result = match op {
+        BinaryOp::Add => l + r,
+        BinaryOp::Sub => l - r,
+        BinaryOp::Mul => l * r,
+        BinaryOp::Div if r != 0.0 => l / r,
+        BinaryOp::Mod if r != 0.0 => l % r,
+        _ => return Value::Null,
+    };
+    number_value(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sql::parser::parse_sql;
+    use serde_json::json;
+
+    fn eval_where(sql_where: &str, ctx: &Value) -> Value {
+        let sql = format!("SELECT * FROM \"t\" WHERE {}", sql_where);
+        let stmt = parse_sql(&sql).unwrap();
+        eval_expr(stmt.condition.as_ref().unwrap(), ctx)
+    }
+
+    #[test]
+    fn test_eval_expr() {
+        let ctx = json!({
+            "payload": {"temp": 35.5, "unit": "C", "tags": ["a", "b"]},
+            "qos": 1
+        });
+        assert_eq!(eval_where("payload.temp > 30", &ctx), json!(true));
+        assert_eq!(eval_where("payload.temp <= 30", &ctx), json!(false));
+        assert_eq!(
+            eval_where("payload.unit = 'C' and qos = 1", &ctx),
+            json!(true)
+        );
+        assert_eq!(eval_where("payload.tags.1 = 'b'", &ctx), json!(true));
+        assert_eq!(eval_where("payload.temp * 2 - 1", &ctx), json!(70));
+        assert_eq!(eval_where("-qos + 3", &ctx), json!(2));
+        assert_eq!(eval_where("payload.unit + 'F'", &ctx), json!("CF"));
+        assert_eq!(eval_where("not (qos = 1) or qos <> 2", &ctx), json!(true));
+    }
+
+    #[test]
+    fn test_eval_null_semantics() {
+        let ctx = json!({"payload": "not json"});
+        assert_eq!(eval_where("payload.temp > 30", &ctx), Value::Null);
+        assert_eq!(eval_where("payload.temp = null", &ctx), json!(true));
+        assert_eq!(eval_where("1 / 0", &ctx), Value::Null);
+        assert_eq!(eval_where("payload > 1", &ctx), Value::Null);
+        assert!(!is_true(&eval_where("not payload.temp", &ctx)));
+    }
+}
@@ -0,0 +1,220 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! SQL-like message rules evaluated on the MQTT publish path.
+//!
+//! ```text
+//! SELECT payload.temp AS t, clientid FROM "sensors/#" WHERE payload.temp > 30
+//! ```
+//!
+//! `FROM` takes one or more quoted topic filters, `WHERE` is optional and the
+//! `SELECT` list builds the output object. Fields are resolved against the
+//! message context built by [`message_context`]; a JSON payload can be
+//! addressed field by field (`payload.a.b`, `payload.items.0`), and so can
+//! the publisher's client attributes (`client_attrs.model`).
+
+use common_base::error::common::CommonError;
+use parser::{parse_sql, SelectField, SelectStatement};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+pub mod eval;
+pub mod parser;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleSql {
+    statement: SelectStatement,
+}
+
+/// Publish attributes exposed to rule SQL.
+pub struct RuleMessage<'a> {
+    pub topic: &'a str,
+    pub client_id: &'a str,
+    pub username: &'a str,
+    pub qos: u8,
+    pub retain: bool,
+    pub payload: &'a [u8],
+    pub timestamp: u128,
+    pub client_attrs: &'a BTreeMap<String, String>,
+}
+
+impl RuleSql {
+    pub fn parse(sql: &str) -> Result<Self, CommonError> {
+        Ok(RuleSql {
+            statement: parse_sql(sql)?,
+        })
+    }
+
+    pub fn from_topics(&self) -> &[String] {
+        &self.statement.from
+    }
+
+    pub fn match_topic(&self, topic: &str) -> bool {
+        self.statement
+            .from
+            .iter()
+            .any(|filter| topic_filter_match(filter, topic))
+    }
+
+    /// Returns the selected output when the `WHERE` condition holds, or `None`
+    /// when the message does not match. The topic is not checked here.
+    pub fn evaluate(&self, ctx: &Value) -> Option<Map<String, Value>> {
+        if let Some(condition) = &self.statement.condition {
+            if !eval::is_true(&eval::eval_expr(condition, ctx)) {
+                return None;
+            }
+        }
+
+        let mut output = Map::new();
+        for field in self.statement.fields.iter() {
+            match field {
+                SelectField::All => {
+                    if let Value::Object(map) = ctx {
+                        output.extend(map.clone());
+                    }
+                }
+                SelectField::Expr { expr, alias } => {
+                    output.insert(alias.clone(), eval::eval_expr(expr, ctx));
+                }
+            }
+        }
+        Some(output)
+    }
+}
+
+/// Build the evaluation context for one message. A payload that is valid JSON
+/// is exposed as structured data, anything else as a (lossy) UTF-8 string.
+pub fn message_context(msg: &RuleMessage) -> Value {
+    let payload = serde_json::from_slice::<Value>(msg.payload)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(msg.payload).to_string()));
+
+    let mut ctx = Map::new();
+    ctx.insert("topic".to_string(), Value::String(msg.topic.to_string()));
+    ctx.insert(
+        "clientid".to_string(),
+        Value::String(msg.client_id.to_string()),
+    );
+    ctx.insert(
+        "username".to_string(),
+        Value::String(msg.username.to_string()),
+    );
+    ctx.insert("qos".to_string(), Value::from(msg.qos));
+    ctx.insert("retain".to_string(), Value::Bool(msg.retain));
+    ctx.insert("payload".to_string(), payload);
+    ctx.insert("timestamp".to_string(), Value::from(msg.timestamp as u64));
+    ctx.insert(
+        "client_attrs".to_string(),
+        Value::Object(
+            msg.client_attrs
+                .iter()
+                .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+                .collect(),
+        ),
+    );
+    Value::Object(ctx)
+}
+
+pub fn topic_filter_match(filter: &str, topic: &str) -> bool {
+    let mut topic_levels = topic.split('/');
+    for level in filter.split('/') {
+        match level {
+            "#" => return true,
+            "+" => {
+                if topic_levels.next().is_none() {
+                    return false;
+                }
+            }
+            _ => {
+                if topic_levels.next() != Some(level) {
+                    return false;
+                }
+            }
+        }
+    }
+    topic_levels.next().is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn message(topic: &str, payload: &[u8]) -> Value {
+        let mut client_attrs = BTreeMap::new();
+        client_attrs.insert("model".to_string(), "x1".to_string());
+        message_context(&RuleMessage {
+            topic,
+            client_id: "c1",
+            username: "u1",
+            qos: 1,
+            retain: false,
+            payload,
+            timestamp: 1000,
+            client_attrs: &client_attrs,
+        })
+    }
+
+    #[test]
+    fn test_rule_sql_evaluate() {
+        let rule = RuleSql::parse(
+            "SELECT payload.temp AS t, clientid FROM \"sensors/#\" WHERE payload.temp > 30",
+        )
+        .unwrap();
+        assert!(rule.match_topic("sensors/room1/temp"));
+        assert!(!rule.match_topic("devices/room1"));
+
+        let hot = message("sensors/room1", br#"{"temp": 35}"#);
+        let output = rule.evaluate(&hot).unwrap();
+        assert_eq!(Value::Object(output), json!({"t": 35, "clientid": "c1"}));
+
+        let cold = message("sensors/room1", br#"{"temp": 20}"#);
+        assert!(rule.evaluate(&cold).is_none());
+
+        let scalar = message("sensors/room1", b"35");
+        assert!(rule.evaluate(&scalar).is_none());
+    }
+
+    #[test]
+    fn test_rule_sql_client_attrs() {
+        let rule = RuleSql::parse(
+            "SELECT client_attrs.model AS model FROM \"sensors/#\" WHERE client_attrs.model = 'x1'",
+        )
+        .unwrap();
+        let output = rule.evaluate(&message("sensors/room1", b"{}")).unwrap();
+        assert_eq!(Value::Object(output), json!({"model": "x1"}));
+
+        let rule =
+            RuleSql::parse("SELECT * FROM \"sensors/#\" WHERE client_attrs.model = 'x2'").unwrap();
+        assert!(rule.evaluate(&message("sensors/room1", b"{}")).is_none());
+    }
+
+    #[test]
+    fn test_rule_sql_select_all() {
+        let rule = RuleSql::parse("SELECT * FROM \"a/+\"").unwrap();
+        let ctx = message("a/b", b"hello");
+        let output = rule.evaluate(&ctx).unwrap();
+        assert_eq!(output.get("payload"), Some(&json!("hello")));
+        assert_eq!(output.get("topic"), Some(&json!("a/b")));
+        assert_eq!(output.get("timestamp"), Some(&json!(1000)));
+    }
+
+    #[test]
+    fn test_topic_filter_match() {
+        assert!(topic_filter_match("a/b", "a/b"));
+        assert!(topic_filter_match("a/+/c", "a/b/c"));
+        assert!(topic_filter_match("#", "a/b/c"));
+        assert!(!topic_filter_match("a/+", "a/b/c"));
+        assert!(!topic_filter_match("a/b/c", "a/b"));
+    }
+}
@@ -0,0 +1,469 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use common_base::error::common::CommonError;
+use serde_json::Value;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BinaryOp {
+    Eq,
+    NotEq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    /// Dotted field path, e.g. `payload.temp` -> `["payload", "temp"]`.
+    Field(Vec<String>),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SelectField {
+    All,
+    Expr { expr: Expr, alias: String },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelectStatement {
+    pub fields: Vec<SelectField>,
+    pub from: Vec<String>,
+    pub condition: Option<Expr>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Str(String),
+    QuotedIdent(String),
+    Comma,
+    Dot,
+    LParen,
+    RParen,
+    Star,
+    Plus,
+    Minus,
+    Slash,
+    Percent,
+    Eq,
+    NotEq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+fn parse_err(msg: impl Into<String>) -> CommonError {
+    CommonError::CommonError(format!("rule sql: {}", msg.into()))
+}
+
+fn tokenize(sql: &str) -> Result<Vec<Token>, CommonError> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => {
+                i += 1;
+            }
+            '\'' | '"' => {
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err(parse_err("unterminated quoted string")),
+                        // a doubled quote is an escaped quote
+                        Some(q) if *q == c && chars.get(i + 1) == Some(&c) => {
+                            value.push(c);
+                            i += 2;
+                        }
+                        Some(q) if *q == c => {
+                            i += 1;
+                            break;
+                        }
+                        Some(ch) => {
+                            value.push(*ch);
+                            i += 1;
+                        }
+                    }
+                }
+                tokens.push(if c == '\'' {
+                    Token::Str(value)
+                } else {
+                    Token::QuotedIdent(value)
+                });
+            }
+            c if c.is_ascii_digit() => {
+                let start = i;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+                // only a '.' followed by a digit is a decimal point, so
+                // `payload.items.0.name` still tokenizes as a path
+                if chars.get(i) == Some(&'.')
+                    && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit())
+                {
+                    i += 1;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+                let text: String = chars[start..i].iter().collect();
+                let number = text
+                    .parse::<f64>()
+                    .map_err(|_| parse_err(format!("invalid number '{}'", text)))?;
+                tokens.push(Token::Number(number));
+            }
+            c if c.is_alphabetic() || c == '_' || c == '$' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '$')
+                {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            }
+            _ => {
+                let next = chars.get(i + 1).copied();
+                let (token, len) = match (c, next) {
+                    ('!', Some('=')) | ('<', Some('>')) => (Token::NotEq, 2),
+                    ('<', Some('=')) => (Token::Le, 2),
+                    ('>', Some('=')) => (Token::Ge, 2),
+                    ('=', Some('=')) => (Token::Eq, 2),
+                    ('=', _) => (Token::Eq, 1),
+                    ('<', _) => (Token::Lt, 1),
+                    ('>', _) => (Token::Gt, 1),
+                    (',', _) => (Token::Comma, 1),
+                    ('.', _) => (Token::Dot, 1),
+                    ('(', _) => (Token::LParen, 1),
+                    (')', _) => (Token::RParen, 1),
+                    ('*', _) => (Token::Star, 1),
+                    ('+', _) => (Token::Plus, 1),
+                    ('-', _) => (Token::Minus, 1),
+                    ('/', _) => (Token::Slash, 1),
+                    ('%', _) => (Token::Percent, 1),
+                    _ => return Err(parse_err(format!("unexpected character '{}'", c))),
+                };
+                tokens.push(token);
+                i += len;
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(s)) if s.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.peek_keyword(keyword) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), CommonError> {
+        if self.eat_keyword(keyword) {
+            return Ok(());
+        }
+        Err(parse_err(format!("expected {}", keyword)))
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn parse_select(&mut self) -> Result<SelectStatement, CommonError> {
+        self.expect_keyword("SELECT")?;
+        let mut fields = vec![self.parse_field()?];
+        while self.eat(&Token::Comma) {
+            fields.push(self.parse_field()?);
+        }
+
+        self.expect_keyword("FROM")?;
+        let mut from = vec![self.parse_topic()?];
+        while self.eat(&Token::Comma) {
+            from.push(self.parse_topic()?);
+        }
+
+        let condition = if self.eat_keyword("WHERE") {
+            Some(self.parse_expr()?)
+        } else {
+            None
+        };
+
+        if let Some(token) = self.peek() {
+            return Err(parse_err(format!("unexpected token {:?}", token)));
+        }
+        Ok(SelectStatement {
+            fields,
+            from,
+            condition,
+        })
+    }
+
+    fn parse_field(&mut self) -> Result<SelectField, CommonError> {
+        if self.eat(&Token::Star) {
+            return Ok(SelectField::All);
+        }
+
+        let expr = self.parse_expr()?;
+        let alias = if self.eat_keyword("AS") {
+            match self.next() {
+                Some(Token::Ident(alias)) | Some(Token::QuotedIdent(alias)) => alias,
+                _ => return Err(parse_err("expected alias after AS")),
+            }
+        } else if let Expr::Field(path) = &expr {
+            path.join(".")
+        } else {
+            return Err(parse_err("computed SELECT field requires an alias"));
+        };
+        Ok(SelectField::Expr { expr, alias })
+    }
+
+    fn parse_topic(&mut self) -> Result<String, CommonError> {
+        match self.next() {
+            Some(Token::QuotedIdent(topic)) | Some(Token::Str(topic)) if !topic.is_empty() => {
+                Ok(topic)
+            }
+            _ => Err(parse_err("FROM expects a quoted topic filter")),
+        }
+    }
+
+    fn parse_expr(&mut self) -> Result<Expr, CommonError> {
+        let mut left = self.parse_and()?;
+        while self.eat_keyword("OR") {
+            let right = self.parse_and()?;
+            left = Expr::Binary(BinaryOp::Or, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, CommonError> {
+        let mut left = self.parse_not()?;
+        while self.eat_keyword("AND") {
+            let right = self.parse_not()?;
+            left = Expr::Binary(BinaryOp::And, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, CommonError> {
+        if self.eat_keyword("NOT") {
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, CommonError> {
+        let left = self.parse_additive()?;
+        let op = match self.peek() {
+            Some(Token::Eq) => BinaryOp::Eq,
+            Some(Token::NotEq) => BinaryOp::NotEq,
+            Some(Token::Lt) => BinaryOp::Lt,
+            Some(Token::Le) => BinaryOp::Le,
+            Some(Token::Gt) => BinaryOp::Gt,
+            Some(Token::Ge) => BinaryOp::Ge,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+        let right = self.parse_additive()?;
+        Ok(Expr::Binary(op, Box::new(left), Box::new(right)))
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, CommonError> {
+        let mut left = self.parse_multiplicative()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Plus) => BinaryOp::Add,
+                Some(Token::Minus) => BinaryOp::Sub,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            let right = self.parse_multiplicative()?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, CommonError> {
+        let mut left = self.parse_unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Star) => BinaryOp::Mul,
+                Some(Token::Slash) => BinaryOp::Div,
+                Some(Token::Percent) => BinaryOp::Mod,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            let right = self.parse_unary()?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, CommonError> {
+        if self.eat(&Token::Minus) {
+            return Ok(Expr::Neg(Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, CommonError> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Literal(number_value(n))),
+            Some(Token::Str(s)) => Ok(Expr::Literal(Value::String(s))),
+            Some(Token::LParen) => {
+                let expr = self.parse_expr()?;
+                if !self.eat(&Token::RParen) {
+                    return Err(parse_err("expected ')'"));
+                }
+                Ok(expr)
+            }
+            Some(Token::Ident(ident)) => {
+                if ident.eq_ignore_ascii_case("true") {
+                    return Ok(Expr::Literal(Value::Bool(true)));
+                }
+                if ident.eq_ignore_ascii_case("false") {
+                    return Ok(Expr::Literal(Value::Bool(false)));
+                }
+                if ident.eq_ignore_ascii_case("null") {
+                    return Ok(Expr::Literal(Value::Null));
+                }
+
+                let mut path = vec![ident];
+                while self.eat(&Token::Dot) {
+                    match self.next() {
+                        Some(Token::Ident(segment)) | Some(Token::QuotedIdent(segment)) => {
+                            path.push(segment)
+                        }
+                        Some(Token::Number(n)) if n.fract() == 0.0 => path.push(n.to_string()),
+                        _ => return Err(parse_err("expected field name after '.'")),
+                    }
+                }
+                Ok(Expr::Field(path))
+            }
+            Some(token) => Err(parse_err(format!("unexpected token {:?}", token))),
+            None => Err(parse_err("unexpected end of input")),
+        }
+    }
+}
+
+pub(crate) fn number_value(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+        return Value::from(n as i64);
+    }
+    serde_json::Number::from_f64(n)
+        .map(Value::Number)
+        .unwrap_or(Value::Null)
+}
+
+pub fn parse_sql(sql: &str) -> Result<SelectStatement, CommonError> {
+    let mut parser = Parser {
+        tokens: tokenize(sql)?,
+        pos: 0,
+    };
+    parser.parse_select()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_select() {
+        let stmt = parse_sql(
+            "SELECT payload.temp AS t, clientid FROM \"sensors/#\" WHERE payload.temp > 30",
+        )
+        .unwrap();
+        assert_eq!(stmt.from, vec!["sensors/#".to_string()]);
+        assert_eq!(
+            stmt.fields,
+            vec![
+                SelectField::Expr {
+                    expr: Expr::Field(vec!["payload".to_string(), "temp".to_string()]),
+                    alias: "t".to_string(),
+                },
+                SelectField::Expr {
+                    expr: Expr::Field(vec!["clientid".to_string()]),
+                    alias: "clientid".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            stmt.condition,
+            Some(Expr::Binary(
+                BinaryOp::Gt,
+                Box::new(Expr::Field(vec!["payload".to_string(), "temp".to_string()])),
+                Box::new(Expr::Literal(Value::from(30))),
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        let stmt =
+            parse_sql("select * from 'a/+', \"b/#\" where a = 1 or b = 2 and not c").unwrap();
+        assert_eq!(stmt.fields, vec![SelectField::All]);
+        assert_eq!(stmt.from.len(), 2);
+        let Some(Expr::Binary(BinaryOp::Or, _, right)) = stmt.condition else {
+            panic!("OR must bind loosest");
+        };
+        assert!(matches!(*right, Expr::Binary(BinaryOp::And, _, _)));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_sql("SELECT * FROM sensors").is_err());
+        assert!(parse_sql("SELECT a + 1 FROM \"t\"").is_err());
+        assert!(parse_sql("SELECT * FROM \"t\" WHERE (a > 1").is_err());
+        assert!(parse_sql("SELECT * FROM \"t\" extra").is_err());
+        assert!(parse_sql("SELECT * FROM \"t").is_err());
+    }
+}
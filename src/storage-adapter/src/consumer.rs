@@ -12,13 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::consumer_group::GroupMembership;
 use crate::driver::StorageDriverManager;
-use common_base::error::common::CommonError;
+use common_base::{error::common::CommonError, tools::now_millis};
 use dashmap::DashMap;
 use metadata_struct::adapter::adapter_offset::AdapterOffsetStrategy;
 use metadata_struct::storage::{adapter_read_config::AdapterReadConfig, record::StorageRecord};
 use std::{collections::HashMap, sync::Arc};
 use tokio::sync::RwLock;
+use tracing::{info, warn};
 
 #[derive(Clone)]
 pub enum StartOffsetStrategy {
@@ -55,6 +57,8 @@ pub struct GroupConsumer {
     pending_offsets: DashMap<OffsetKey, u64>,
     auto_commit: bool,
     start_offset_strategy: RwLock<StartOffsetStrategy>,
+    /// Set when the group is shared with consumers on other broker nodes.
+    membership: Option<GroupMembership>,
 }
 
 impl GroupConsumer {
@@ -66,6 +70,7 @@ pub fn new(driver: Arc<StorageDriverManager>, group_name: impl Into<String>) ->
             pending_offsets: DashMap::new(),
             auto_commit: true,
             start_offset_strategy: RwLock::new(StartOffsetStrategy::Earliest),
+            membership: None,
         }
     }
 
@@ -76,6 +81,22 @@ pub fn new_manual(driver: Arc<StorageDriverManager>, group_name: impl Into<Strin
         }
     }
 
+    /// A manual-commit consumer that joins the group through meta-service and
+    /// only reads the shards assigned to it, so consumers of the same group
+    /// on several broker nodes split the shards between them. Call
+    /// [`GroupConsumer::leave_group`] on shutdown to hand the shards over
+    /// without waiting for the session to expire.
+    pub fn new_member(
+        driver: Arc<StorageDriverManager>,
+        group_name: impl Into<String>,
+        broker_id: u64,
+    ) -> Self {
+        GroupConsumer {
+            membership: Some(GroupMembership::new(broker_id)),
+            ..Self::new_manual(driver, group_name)
+        }
+    }
+
     pub async fn set_start_offset_strategy(&self, strategy: StartOffsetStrategy) {
         let mut write = self.start_offset_strategy.write().await;
         *write = strategy;
@@ -89,11 +110,24 @@ pub async fn next_messages(
     ) -> Result<Vec<StorageRecord>, CommonError> {
         self.ensure_offsets_loaded(tenant, topic_name).await?;
 
-        let shard_offsets = self.current_shard_offsets(tenant, topic_name);
-        let records = self
-            .driver
-            .read_by_offset(tenant, topic_name, &shard_offsets, read_config)
-            .await?;
+        let records = if let Some(membership) = &self.membership {
+            self.sync_membership(membership, tenant, topic_name).await?;
+            let mut records = Vec::new();
+            for shard in membership.owned_shards(tenant, topic_name) {
+                let offset = self.next_offset(tenant, topic_name, &shard).unwrap_or(0);
+                let resp = self
+                    .driver
+                    .read_by_shard_offset(tenant, topic_name, &shard, offset, read_config)
+                    .await?;
+                records.extend(resp);
+            }
+            records
+        } else {
+            let shard_offsets = self.current_shard_offsets(tenant, topic_name);
+            self.driver
+                .read_by_offset(tenant, topic_name, &shard_offsets, read_config)
+                .await?
+        };
         self.after_read(tenant, topic_name, records).await
     }
 
@@ -106,15 +140,40 @@ pub async fn next_messages_by_tags(
     ) -> Result<Vec<StorageRecord>, CommonError> {
         self.ensure_offsets_loaded(tenant, topic_name).await?;
 
+        if let Some(membership) = &self.membership {
+            self.sync_membership(membership, tenant, topic_name).await?;
+        }
+
         let shard_offsets = self.current_shard_offsets(tenant, topic_name);
-        let records = self
+        let mut records = self
             .driver
             .read_by_tag(tenant, topic_name, tag, &shard_offsets, read_config)
             .await?;
+        if let Some(membership) = &self.membership {
+            records.retain(|record| membership.owns(tenant, &record.metadata.shard));
+        }
 
         self.after_read(tenant, topic_name, records).await
     }
 
+    /// Commit, release every owned shard and leave the group. A no-op for
+    /// consumers that are not group members.
+    pub async fn leave_group(&self) -> Result<(), CommonError> {
+        let Some(membership) = &self.membership else {
+            return Ok(());
+        };
+        self.commit().await?;
+        for tenant in membership.tenants() {
+            let owned = membership.remove_tenant(&tenant);
+            self.release_shards(&tenant, &owned).await?;
+            self.driver
+                .offset_manager
+                .leave_group(&tenant, &self.group_name, membership.member_id())
+                .await?;
+        }
+        Ok(())
+    }
+
     /// Persist pending offsets to the offset store and advance current_offsets.
     /// Persist the pending offsets to the offset store and advance the internal consume position.
     ///
@@ -174,6 +233,14 @@ pub fn set_current_offsets(
         }
     }
 
+    /// Offset the next read of a shard starts at, `None` until it has been
+    /// loaded from the offset store or advanced by a commit.
+    pub fn next_offset(&self, tenant: &str, topic: &str, shard: &str) -> Option<u64> {
+        self.current_offsets
+            .get(&OffsetKey::new(tenant, topic, shard))
+            .map(|offset| *offset)
+    }
+
     /// Merge pending offsets into current_offsets without persisting to the offset store.
     ///
     /// Moves the in-memory consume position forward to the end of the last read batch,
@@ -190,6 +257,113 @@ pub fn advance(&self) {
         self.pending_offsets.clear();
     }
 
+    /// Heartbeat to meta-service when due and apply the assignment: revoked
+    /// shards are released, acquired shards resume from the offsets stored
+    /// in meta-service, as the previous owner committed them on another node.
+    async fn sync_membership(
+        &self,
+        membership: &GroupMembership,
+        tenant: &str,
+        topic_name: &str,
+    ) -> Result<(), CommonError> {
+        let shards = self.driver.topic_shard_names(tenant, topic_name)?;
+        let now = now_millis() as u64;
+        let Some(heartbeat) = membership.heartbeat_due(tenant, topic_name, shards, now) else {
+            return Ok(());
+        };
+
+        let reply = match self
+            .driver
+            .offset_manager
+            .group_member_heartbeat(
+                tenant,
+                &self.group_name,
+                membership.member_id(),
+                membership.broker_id(),
+                heartbeat.shards,
+                heartbeat.owned,
+            )
+            .await
+        {
+            Ok(reply) => reply,
+            Err(e) => {
+                warn!(
+                    "Consumer group '{}' member '{}' heartbeat failed: {}",
+                    self.group_name,
+                    membership.member_id(),
+                    e
+                );
+                let change = membership.heartbeat_failed(tenant, now);
+                self.drop_shards(tenant, &change.revoked);
+                return Ok(());
+            }
+        };
+
+        let change = membership.apply(tenant, &reply, now);
+        if !change.revoked.is_empty() {
+            self.release_shards(tenant, &change.revoked).await?;
+        }
+        if !change.acquired.is_empty() {
+            let stored = self
+                .driver
+                .offset_manager
+                .get_remote_offset(tenant, &self.group_name)
+                .await?;
+            for offset in stored {
+                if change.acquired.contains(&offset.shard_name) {
+                    self.current_offsets.insert(
+                        OffsetKey::new(tenant, topic_name, &offset.shard_name),
+                        offset.offset,
+                    );
+                }
+            }
+
+            // Shards the group never committed start by the start offset strategy.
+            let missing = change
+                .acquired
+                .iter()
+                .any(|shard| self.next_offset(tenant, topic_name, shard).is_none());
+            if missing {
+                let initial = self.resolve_initial_offsets(tenant, topic_name).await?;
+                for shard in &change.acquired {
+                    if let Some(offset) = initial.get(shard) {
+                        self.current_offsets
+                            .entry(OffsetKey::new(tenant, topic_name, shard))
+                            .or_insert(*offset);
+                    }
+                }
+            }
+        }
+        if !change.revoked.is_empty() || !change.acquired.is_empty() {
+            info!(
+                "Consumer group '{}' member '{}' rebalanced to generation {}, acquired {:?}, revoked {:?}",
+                self.group_name,
+                membership.member_id(),
+                reply.generation,
+                change.acquired,
+                change.revoked
+            );
+        }
+        Ok(())
+    }
+
+    /// Flush the committed offsets of released shards to meta-service and
+    /// forget them locally. Uncommitted reads are dropped and redelivered by
+    /// the new owner.
+    async fn release_shards(&self, tenant: &str, shards: &[String]) -> Result<(), CommonError> {
+        self.drop_shards(tenant, shards);
+        self.driver
+            .offset_manager
+            .release_shards(tenant, &self.group_name, shards)
+            .await
+    }
+
+    fn drop_shards(&self, tenant: &str, shards: &[String]) {
+        let released = |key: &OffsetKey| key.tenant == tenant && shards.contains(&key.shard);
+        self.pending_offsets.retain(|key, _| !released(key));
+        self.current_offsets.retain(|key, _| !released(key));
+    }
+
     /// Collect per-shard read-start offsets for the given tenant+topic.
     /// Returns offset + 1 so the next read begins after the last consumed record.
     fn current_shard_offsets(&self, tenant: &str, topic_name: &str) -> HashMap<String, u64> {
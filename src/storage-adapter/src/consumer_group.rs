@@ -0,0 +1,317 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Membership of a [`GroupConsumer`](crate::consumer::GroupConsumer) in a
+//! consumer group shared by several broker nodes.
+//!
+//! Members heartbeat to meta-service, which divides the shards of the
+//! consumed topics among them, and a member only reads the shards it owns.
+//! Hand-off is cooperative: a revoked shard stops being read and its
+//! committed offset is flushed before the next heartbeat reports it released,
+//! and only then is it assigned to its new owner, which resumes from the
+//! offset stored in meta-service.
+
+use common_base::uuid::unique_id;
+use protocol::meta::meta_service_common::ConsumerGroupHeartbeatReply;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+
+pub const GROUP_HEARTBEAT_INTERVAL_MS: u64 = 3_000;
+const DEFAULT_SESSION_TIMEOUT_MS: u64 = 30_000;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MembershipChange {
+    pub acquired: Vec<String>,
+    pub revoked: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct TenantMembership {
+    // (topic_name, shard names)
+    topics: HashMap<String, BTreeSet<String>>,
+    owned: BTreeSet<String>,
+    generation: u64,
+    session_timeout_ms: u64,
+    last_heartbeat_ms: u64,
+    last_success_ms: u64,
+}
+
+impl TenantMembership {
+    fn shards(&self) -> Vec<String> {
+        self.topics
+            .values()
+            .flatten()
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+}
+
+pub struct MemberHeartbeat {
+    pub shards: Vec<String>,
+    pub owned: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct GroupMembership {
+    member_id: String,
+    broker_id: u64,
+    // (tenant, TenantMembership)
+    tenants: Mutex<HashMap<String, TenantMembership>>,
+}
+
+impl GroupMembership {
+    pub fn new(broker_id: u64) -> Self {
+        GroupMembership {
+            member_id: format!("{}-{}", broker_id, unique_id()),
+            broker_id,
+            tenants: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn member_id(&self) -> &str {
+        &self.member_id
+    }
+
+    pub fn broker_id(&self) -> u64 {
+        self.broker_id
+    }
+
+    pub fn generation(&self, tenant: &str) -> u64 {
+        self.tenants
+            .lock()
+            .unwrap()
+            .get(tenant)
+            .map(|t| t.generation)
+            .unwrap_or(0)
+    }
+
+    pub fn tenants(&self) -> Vec<String> {
+        self.tenants.lock().unwrap().keys().cloned().collect()
+    }
+
+    pub fn owns(&self, tenant: &str, shard: &str) -> bool {
+        self.tenants
+            .lock()
+            .unwrap()
+            .get(tenant)
+            .is_some_and(|t| t.owned.contains(shard))
+    }
+
+    /// Owned shards of `topic_name`, in name order.
+    pub fn owned_shards(&self, tenant: &str, topic_name: &str) -> Vec<String> {
+        let tenants = self.tenants.lock().unwrap();
+        let Some(membership) = tenants.get(tenant) else {
+            return Vec::new();
+        };
+        membership
+            .topics
+            .get(topic_name)
+            .map(|shards| {
+                shards
+                    .iter()
+                    .filter(|shard| membership.owned.contains(*shard))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Register the shards of `topic_name` and return the heartbeat to send
+    /// when one is due: on the interval, or right away when the consumed
+    /// shards changed.
+    pub fn heartbeat_due(
+        &self,
+        tenant: &str,
+        topic_name: &str,
+        shards: Vec<String>,
+        now_ms: u64,
+    ) -> Option<MemberHeartbeat> {
+        let mut tenants = self.tenants.lock().unwrap();
+        let membership = tenants.entry(tenant.to_string()).or_default();
+
+        let shards: BTreeSet<String> = shards.into_iter().collect();
+        let shards_changed = membership.topics.get(topic_name) != Some(&shards);
+        if shards_changed {
+            membership.topics.insert(topic_name.to_string(), shards);
+        }
+        if !shards_changed
+            && now_ms.saturating_sub(membership.last_heartbeat_ms) < GROUP_HEARTBEAT_INTERVAL_MS
+        {
+            return None;
+        }
+
+        membership.last_heartbeat_ms = now_ms;
+        Some(MemberHeartbeat {
+            shards: membership.shards(),
+            owned: membership.owned.iter().cloned().collect(),
+        })
+    }
+
+    /// Apply the assignment returned by meta-service. Revoked shards are no
+    /// longer owned when this returns.
+    pub fn apply(
+        &self,
+        tenant: &str,
+        reply: &ConsumerGroupHeartbeatReply,
+        now_ms: u64,
+    ) -> MembershipChange {
+        let mut tenants = self.tenants.lock().unwrap();
+        let membership = tenants.entry(tenant.to_string()).or_default();
+        membership.generation = reply.generation;
+        membership.session_timeout_ms = reply.session_timeout_ms;
+        membership.last_success_ms = now_ms;
+
+        let revoked: Vec<String> = reply
+            .revoked
+            .iter()
+            .filter(|shard| membership.owned.remove(*shard))
+            .cloned()
+            .collect();
+        let acquired: Vec<String> = reply
+            .assigned
+            .iter()
+            .filter(|shard| membership.owned.insert((*shard).clone()))
+            .cloned()
+            .collect();
+        MembershipChange { acquired, revoked }
+    }
+
+    /// Called when a heartbeat fails. Once the session has expired on
+    /// meta-service the shards may already be read by another member, so
+    /// all of them are revoked locally.
+    pub fn heartbeat_failed(&self, tenant: &str, now_ms: u64) -> MembershipChange {
+        let mut tenants = self.tenants.lock().unwrap();
+        let Some(membership) = tenants.get_mut(tenant) else {
+            return MembershipChange::default();
+        };
+        let session_timeout_ms = if membership.session_timeout_ms > 0 {
+            membership.session_timeout_ms
+        } else {
+            DEFAULT_SESSION_TIMEOUT_MS
+        };
+        if now_ms.saturating_sub(membership.last_success_ms) <= session_timeout_ms {
+            return MembershipChange::default();
+        }
+        MembershipChange {
+            acquired: Vec::new(),
+            revoked: std::mem::take(&mut membership.owned).into_iter().collect(),
+        }
+    }
+
+    /// Forget the tenant, returning the shards that were owned.
+    pub fn remove_tenant(&self, tenant: &str) -> Vec<String> {
+        self.tenants
+            .lock()
+            .unwrap()
+            .remove(tenant)
+            .map(|membership| membership.owned.into_iter().collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shards(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_heartbeat_due() {
+        let membership = GroupMembership::new(1);
+        let request = membership
+            .heartbeat_due("t1", "topic", shards(&["s1", "s0"]), 0)
+            .unwrap();
+        assert_eq!(request.shards, shards(&["s0", "s1"]));
+        assert!(request.owned.is_empty());
+
+        assert!(membership
+            .heartbeat_due("t1", "topic", shards(&["s0", "s1"]), 10)
+            .is_none());
+        assert!(membership
+            .heartbeat_due("t1", "topic", shards(&["s0", "s1", "s2"]), 20)
+            .is_some());
+        assert!(membership
+            .heartbeat_due(
+                "t1",
+                "topic",
+                shards(&["s0", "s1", "s2"]),
+                20 + GROUP_HEARTBEAT_INTERVAL_MS
+            )
+            .is_some());
+    }
+
+    #[test]
+    fn test_apply_assignment() {
+        let membership = GroupMembership::new(1);
+        membership.heartbeat_due("t1", "topic", shards(&["s0", "s1", "s2"]), 0);
+
+        let change = membership.apply(
+            "t1",
+            &ConsumerGroupHeartbeatReply {
+                generation: 1,
+                assigned: shards(&["s0", "s1", "s2"]),
+                revoked: Vec::new(),
+                session_timeout_ms: 30_000,
+            },
+            0,
+        );
+        assert_eq!(change.acquired, shards(&["s0", "s1", "s2"]));
+        assert_eq!(membership.owned_shards("t1", "topic").len(), 3);
+
+        let change = membership.apply(
+            "t1",
+            &ConsumerGroupHeartbeatReply {
+                generation: 2,
+                assigned: shards(&["s0", "s2"]),
+                revoked: shards(&["s1"]),
+                session_timeout_ms: 30_000,
+            },
+            10,
+        );
+        assert!(change.acquired.is_empty());
+        assert_eq!(change.revoked, shards(&["s1"]));
+        assert_eq!(
+            membership.owned_shards("t1", "topic"),
+            shards(&["s0", "s2"])
+        );
+        assert!(!membership.owns("t1", "s1"));
+    }
+
+    #[test]
+    fn test_heartbeat_failed_after_session_timeout() {
+        let membership = GroupMembership::new(1);
+        membership.heartbeat_due("t1", "topic", shards(&["s0"]), 0);
+        membership.apply(
+            "t1",
+            &ConsumerGroupHeartbeatReply {
+                generation: 1,
+                assigned: shards(&["s0"]),
+                revoked: Vec::new(),
+                session_timeout_ms: 1_000,
+            },
+            0,
+        );
+
+        assert!(membership.heartbeat_failed("t1", 500).revoked.is_empty());
+        assert_eq!(
+            membership.heartbeat_failed("t1", 1_001).revoked,
+            shards(&["s0"])
+        );
+        assert!(membership.owned_shards("t1", "topic").is_empty());
+    }
+}
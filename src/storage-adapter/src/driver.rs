@@ -12,14 +12,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{engine::EngineStorageAdapter, storage::StorageAdapter};
+use crate::{engine::EngineStorageAdapter, memory::MemoryStorageAdapter, storage::StorageAdapter};
 use broker_core::cache::NodeCacheManager;
 use common_base::error::common::CommonError;
-use common_config::storage::StorageType;
+use common_base::fault::{fault_injector, FaultPoint};
+use common_config::storage::{memory::MemoryAdapterConfig, StorageType};
 use common_group::manager::OffsetManager;
 use dashmap::DashMap;
 use metadata_struct::{
-    adapter::adapter_shard::AdapterShardDetail,
+    adapter::adapter_shard::{AdapterShardDetail, AdapterShardWatermarks},
     mqtt::topic::Topic,
     storage::{
         adapter_offset::{AdapterConsumerGroupOffset, AdapterOffsetStrategy, AdapterShardInfo},
@@ -44,6 +45,9 @@ pub struct StorageDriverManager {
     pub broker_cache: Arc<NodeCacheManager>,
     pub offset_manager: Arc<OffsetManager>,
     pub message_seq: Arc<AtomicU64>,
+    memory_adapter_config: MemoryAdapterConfig,
+    // Serve every topic from the memory adapter, whatever its storage type.
+    edge_mode: bool,
 }
 
 impl StorageDriverManager {
@@ -57,9 +61,29 @@ pub async fn new(
             broker_cache: engine_storage_handler.cache_manager.broker_cache.clone(),
             offset_manager,
             message_seq: Arc::new(AtomicU64::new(0)),
+            memory_adapter_config: MemoryAdapterConfig::default(),
+            edge_mode: false,
         })
     }
 
+    pub fn with_memory_adapter(mut self, config: MemoryAdapterConfig, edge_mode: bool) -> Self {
+        self.memory_adapter_config = config;
+        self.edge_mode = edge_mode;
+        self
+    }
+
+    /// `topic` with the storage type picked by the cluster's storage routing
+    /// rules, or unchanged if no rule matches. Rules are read on every call,
+    /// so a change applies to the next topic created.
+    pub fn route_topic(&self, topic: &Topic) -> Topic {
+        let routing = self.broker_cache.get_cluster_config().storage_routing;
+        let mut topic = topic.clone();
+        if let Some(storage_type) = routing.route(&topic.tenant, &topic.topic_name) {
+            topic.storage_type = storage_type;
+        }
+        topic
+    }
+
     pub async fn create_storage_resource(
         &self,
         tenant: &str,
@@ -96,6 +120,21 @@ pub async fn list_storage_resource(
         Ok(results)
     }
 
+    /// Names of the storage shards backing the topic, from the broker cache.
+    pub fn topic_shard_names(
+        &self,
+        tenant: &str,
+        topic_name: &str,
+    ) -> Result<Vec<String>, CommonError> {
+        let Some(topic) = self.broker_cache.get_topic_by_name(tenant, topic_name) else {
+            return Err(CommonError::TopicNotFoundInBrokerCache(
+                tenant.to_string(),
+                topic_name.to_string(),
+            ));
+        };
+        Ok(topic.storage_name_list.into_values().collect())
+    }
+
     pub async fn delete_storage_resource(
         &self,
         tenant: &str,
@@ -115,6 +154,20 @@ pub async fn write(
         data: &[AdapterWriteRecord],
         acks: i8,
     ) -> Result<Vec<AdapterWriteRespRow>, CommonError> {
+        let (_, rows) = self
+            .write_with_shard(tenant, topic_name, data, acks)
+            .await?;
+        Ok(rows)
+    }
+
+    /// Like `write`, also returning the shard the records were written to.
+    pub async fn write_with_shard(
+        &self,
+        tenant: &str,
+        topic_name: &str,
+        data: &[AdapterWriteRecord],
+        acks: i8,
+    ) -> Result<(String, Vec<AdapterWriteRespRow>), CommonError> {
         let (topic, driver) = self.build_driver(tenant, topic_name).await?;
 
         // Pick a partition via round-robin and use the shard name from storage_name_list.
@@ -128,7 +181,8 @@ pub async fn write(
             .get(&(partition as u32))
             .cloned()
             .unwrap_or_else(|| Topic::build_storage_name(&topic.topic_id, partition as u32));
-        driver.write(&partition_name, data, acks).await
+        let rows = driver.write(&partition_name, data, acks).await?;
+        Ok((partition_name, rows))
     }
 
     pub async fn read_by_offset(
@@ -154,6 +208,20 @@ pub async fn read_by_offset(
         Ok(results)
     }
 
+    /// Read from one shard of the topic starting at `offset`, unlike
+    /// [`StorageDriverManager::read_by_offset`] which reads every shard.
+    pub async fn read_by_shard_offset(
+        &self,
+        tenant: &str,
+        topic_name: &str,
+        shard_name: &str,
+        offset: u64,
+        read_config: &AdapterReadConfig,
+    ) -> Result<Vec<StorageRecord>, CommonError> {
+        let (_, driver) = self.build_driver(tenant, topic_name).await?;
+        driver.read_by_offset(shard_name, offset, read_config).await
+    }
+
     pub async fn read_by_tag(
         &self,
         tenant: &str,
@@ -217,6 +285,19 @@ pub async fn delete_by_offsets(
         Ok(())
     }
 
+    /// Delete offsets from one shard of the topic, unlike
+    /// [`StorageDriverManager::delete_by_offsets`] which applies them to every shard.
+    pub async fn delete_by_shard_offsets(
+        &self,
+        tenant: &str,
+        topic_name: &str,
+        shard_name: &str,
+        offsets: &[u64],
+    ) -> Result<(), CommonError> {
+        let (_, driver) = self.build_driver(tenant, topic_name).await?;
+        driver.delete_by_offsets(shard_name, offsets).await
+    }
+
     pub async fn get_offset_by_timestamp(
         &self,
         tenant: &str,
@@ -236,6 +317,40 @@ pub async fn get_offset_by_timestamp(
         Ok(results.iter().min().copied().unwrap_or(0))
     }
 
+    /// Per-shard variant of `get_offset_by_timestamp`, keyed by shard name.
+    pub async fn shard_offsets_by_timestamp(
+        &self,
+        tenant: &str,
+        topic_name: &str,
+        timestamp: u64,
+        strategy: AdapterOffsetStrategy,
+    ) -> Result<HashMap<String, u64>, CommonError> {
+        let (topic, driver) = self.build_driver(tenant, topic_name).await?;
+        let mut results = HashMap::with_capacity(topic.storage_name_list.len());
+        for (_, shard_name) in topic.storage_name_list {
+            let offset = driver
+                .get_offset_by_timestamp(&shard_name, timestamp, strategy.clone())
+                .await?;
+            results.insert(shard_name, offset);
+        }
+        Ok(results)
+    }
+
+    /// Offset watermarks of every shard of the topic.
+    pub async fn shard_watermarks(
+        &self,
+        tenant: &str,
+        topic_name: &str,
+    ) -> Result<Vec<AdapterShardWatermarks>, CommonError> {
+        let (topic, driver) = self.build_driver(tenant, topic_name).await?;
+        let mut results = Vec::with_capacity(topic.storage_name_list.len());
+        for (_, shard_name) in topic.storage_name_list {
+            results.push(driver.shard_watermarks(&shard_name).await?);
+        }
+        results.sort_by(|a, b| a.shard_name.cmp(&b.shard_name));
+        Ok(results)
+    }
+
     pub async fn get_offset_by_group(
         &self,
         tenant: &str,
@@ -269,6 +384,16 @@ async fn build_driver(
             ));
         };
 
+        if fault_injector().is_enabled() {
+            let target = format!("{}/{}", tenant, topic_name);
+            if let Err(fault) = fault_injector().inject(FaultPoint::Storage, &target).await {
+                return Err(CommonError::CommonError(format!(
+                    "Storage operation on topic {} failed: {}",
+                    target, fault
+                )));
+            }
+        }
+
         let driver = self.get_storage_driver_by_topic(&topic).await?;
         Ok((topic, driver))
     }
@@ -277,15 +402,23 @@ async fn get_storage_driver_by_topic(
         &self,
         topic: &Topic,
     ) -> Result<ArcStorageAdapter, CommonError> {
-        let storage_type_str = format!("{:?}", topic.storage_type);
+        let storage_type = if self.edge_mode {
+            StorageType::Memory
+        } else {
+            topic.storage_type
+        };
+        let storage_type_str = format!("{:?}", storage_type);
         if let Some(driver) = self.driver_list.get(&storage_type_str) {
             return Ok(driver.clone());
         }
 
-        let driver = match topic.storage_type {
+        let driver: ArcStorageAdapter = match storage_type {
             StorageType::EngineMemory | StorageType::EngineRocksDB | StorageType::EngineSegment => {
                 Arc::new(EngineStorageAdapter::new(self.engine_storage_handler.clone()).await)
             }
+            StorageType::Memory => Arc::new(MemoryStorageAdapter::new(
+                self.memory_adapter_config.clone(),
+            )),
             _ => {
                 return Err(CommonError::CommonError(format!(
                     "Unsupported storage type '{:?}' for topic '{}'",
@@ -293,7 +426,12 @@ async fn get_storage_driver_by_topic(
                 )));
             }
         };
-        self.driver_list.insert(storage_type_str, driver.clone());
-        Ok(driver)
+        // The memory adapter holds the data, so concurrent callers must all
+        // end up with the instance that was registered first.
+        Ok(self
+            .driver_list
+            .entry(storage_type_str)
+            .or_insert(driver)
+            .clone())
     }
 }
@@ -18,7 +18,7 @@
 use metadata_struct::adapter::adapter_offset::{AdapterOffsetStrategy, AdapterShardInfo};
 use metadata_struct::adapter::adapter_read_config::{AdapterReadConfig, AdapterWriteRespRow};
 use metadata_struct::adapter::adapter_record::AdapterWriteRecord;
-use metadata_struct::adapter::adapter_shard::AdapterShardDetail;
+use metadata_struct::adapter::adapter_shard::{AdapterShardDetail, AdapterShardWatermarks};
 use metadata_struct::storage::record::StorageRecord;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -150,6 +150,41 @@ async fn get_offset_by_timestamp(
             .await
     }
 
+    async fn shard_watermarks(&self, shard: &str) -> Result<AdapterShardWatermarks, CommonError> {
+        let detail = self
+            .adapter
+            .list_shard(Some(shard.to_string()))
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| CommonError::CommonError(format!("Shard {} not found", shard)))?;
+
+        // end_offset is the last written offset, or 0 for an empty shard; the
+        // record stored there tells the two apart and carries the timestamp.
+        let read_config = AdapterReadConfig {
+            max_record_num: 1,
+            ..AdapterReadConfig::new()
+        };
+        let last = self
+            .adapter
+            .read_by_offset(shard, detail.offset.end_offset, &read_config)
+            .await?
+            .into_iter()
+            .next();
+        let (latest_offset, latest_timestamp) = match last {
+            Some(record) => (record.metadata.offset + 1, record.metadata.create_t),
+            None => (detail.offset.end_offset.max(detail.offset.start_offset), 0),
+        };
+
+        Ok(AdapterShardWatermarks {
+            shard_name: detail.shard_name,
+            earliest_offset: detail.offset.start_offset,
+            latest_offset,
+            high_watermark: detail.offset.high_watermark,
+            latest_timestamp,
+        })
+    }
+
     async fn close(&self) -> Result<(), CommonError> {
         Ok(())
     }
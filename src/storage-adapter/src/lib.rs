@@ -15,9 +15,11 @@
 #![allow(clippy::result_large_err)]
 pub mod driver;
 pub mod engine;
+pub mod memory;
 pub mod tests;
 // pub mod mysql;
 pub mod consumer;
+pub mod consumer_group;
 pub mod consumer_priority;
 pub mod priority;
 pub mod storage;
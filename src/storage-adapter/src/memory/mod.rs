@@ -0,0 +1,470 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage adapter keeping the records of each shard in broker memory.
+//!
+//! Every shard is a ring buffer with one slot per offset: writes append at
+//! the tail and, once the shard holds more records or bytes than its
+//! configured capacity, the oldest records are evicted from the head.
+//! Deleted records leave an empty slot so offsets stay stable. Nothing is
+//! persisted, so this serves edge nodes without storage and tests.
+
+use crate::storage::StorageAdapter;
+use async_trait::async_trait;
+use common_base::error::common::CommonError;
+use common_base::tools::now_second;
+use common_config::storage::memory::MemoryAdapterConfig;
+use dashmap::DashMap;
+use metadata_struct::adapter::adapter_offset::{AdapterOffsetStrategy, AdapterShardInfo};
+use metadata_struct::adapter::adapter_read_config::{AdapterReadConfig, AdapterWriteRespRow};
+use metadata_struct::adapter::adapter_record::AdapterWriteRecord;
+use metadata_struct::adapter::adapter_shard::{
+    AdapterShardDetail, AdapterShardDetailOffset, AdapterShardWatermarks,
+};
+use metadata_struct::storage::convert::convert_adapter_record_to_storage;
+use metadata_struct::storage::record::StorageRecord;
+use metadata_struct::storage::shard::EngineShard;
+use std::collections::{HashMap, VecDeque};
+
+struct MemoryShard {
+    info: AdapterShardInfo,
+    shard: EngineShard,
+    // slot i holds offset start_offset + i; None once deleted
+    records: VecDeque<Option<StorageRecord>>,
+    start_offset: u64,
+    record_num: u64,
+    bytes: u64,
+}
+
+impl MemoryShard {
+    fn new(info: AdapterShardInfo) -> Self {
+        let shard = EngineShard::new(
+            info.shard_name.clone(),
+            info.topic_name.clone(),
+            info.config.clone(),
+            info.desc.clone(),
+        );
+        MemoryShard {
+            info,
+            shard,
+            records: VecDeque::new(),
+            start_offset: 0,
+            record_num: 0,
+            bytes: 0,
+        }
+    }
+
+    fn next_offset(&self) -> u64 {
+        self.start_offset + self.records.len() as u64
+    }
+
+    fn append(&mut self, record: StorageRecord) {
+        self.bytes += record_size(&record);
+        self.record_num += 1;
+        self.records.push_back(Some(record));
+    }
+
+    fn evict(&mut self, config: &MemoryAdapterConfig) {
+        while self.records.len() > 1
+            && ((config.max_records_per_shard > 0
+                && self.record_num > config.max_records_per_shard)
+                || (config.max_bytes_per_shard > 0 && self.bytes > config.max_bytes_per_shard))
+        {
+            self.pop_front();
+        }
+    }
+
+    fn pop_front(&mut self) {
+        if let Some(Some(record)) = self.records.pop_front() {
+            self.bytes -= record_size(&record);
+            self.record_num -= 1;
+        }
+        self.start_offset += 1;
+    }
+
+    fn remove_where(&mut self, mut predicate: impl FnMut(&StorageRecord) -> bool) {
+        for slot in self.records.iter_mut() {
+            if slot.as_ref().is_some_and(&mut predicate) {
+                let record = slot.take().unwrap();
+                self.bytes -= record_size(&record);
+                self.record_num -= 1;
+            }
+        }
+        while self.records.len() > 1 && matches!(self.records.front(), Some(None)) {
+            self.pop_front();
+        }
+    }
+
+    /// Live records from `offset` on, skipping deleted and expired ones.
+    fn iter_from(&self, offset: u64) -> impl Iterator<Item = &StorageRecord> {
+        let now = now_second();
+        let skip = offset.saturating_sub(self.start_offset) as usize;
+        self.records
+            .iter()
+            .skip(skip)
+            .flatten()
+            .filter(move |record| record.metadata.expire_at == 0 || record.metadata.expire_at > now)
+    }
+
+    fn detail(&self) -> AdapterShardDetail {
+        AdapterShardDetail {
+            shard_name: self.info.shard_name.clone(),
+            topic_name: self.info.topic_name.clone(),
+            config: self.info.config.clone(),
+            shard: self.shard.clone(),
+            offset: AdapterShardDetailOffset {
+                start_offset: self.start_offset,
+                end_offset: self.next_offset().saturating_sub(1),
+                high_watermark: self.next_offset(),
+            },
+            desc: self.info.desc.clone(),
+        }
+    }
+}
+
+fn record_size(record: &StorageRecord) -> u64 {
+    let metadata = &record.metadata;
+    let mut size = record.data.len();
+    if let Some(key) = &metadata.key {
+        size += key.len();
+    }
+    if let Some(tags) = &metadata.tags {
+        size += tags.iter().map(|tag| tag.len()).sum::<usize>();
+    }
+    if let Some(headers) = &metadata.header {
+        size += headers
+            .iter()
+            .map(|h| h.name.len() + h.value.len())
+            .sum::<usize>();
+    }
+    size as u64
+}
+
+fn collect_records<'a>(
+    records: impl Iterator<Item = &'a StorageRecord>,
+    read_config: &AdapterReadConfig,
+) -> Vec<StorageRecord> {
+    let mut results = Vec::new();
+    let mut size = 0;
+    for record in records {
+        if results.len() as u64 >= read_config.max_record_num {
+            break;
+        }
+        let len = record.data.len() as u64;
+        if !results.is_empty() && size + len > read_config.max_size {
+            break;
+        }
+        size += len;
+        results.push(record.clone());
+    }
+    results
+}
+
+pub struct MemoryStorageAdapter {
+    config: MemoryAdapterConfig,
+    shards: DashMap<String, MemoryShard>,
+}
+
+impl MemoryStorageAdapter {
+    pub fn new(config: MemoryAdapterConfig) -> Self {
+        MemoryStorageAdapter {
+            config,
+            shards: DashMap::with_capacity(8),
+        }
+    }
+
+    fn shard_not_found(shard: &str) -> CommonError {
+        CommonError::CommonError(format!("Shard {} not found", shard))
+    }
+}
+
+#[async_trait]
+impl StorageAdapter for MemoryStorageAdapter {
+    async fn create_shard(&self, shard: &AdapterShardInfo) -> Result<(), CommonError> {
+        self.shards
+            .entry(shard.shard_name.clone())
+            .or_insert_with(|| MemoryShard::new(shard.clone()));
+        Ok(())
+    }
+
+    async fn list_shard(
+        &self,
+        shard: Option<String>,
+    ) -> Result<Vec<AdapterShardDetail>, CommonError> {
+        if let Some(shard_name) = shard {
+            return Ok(self
+                .shards
+                .get(&shard_name)
+                .map(|shard| vec![shard.detail()])
+                .unwrap_or_default());
+        }
+        Ok(self.shards.iter().map(|shard| shard.detail()).collect())
+    }
+
+    async fn delete_shard(&self, shard: &str) -> Result<(), CommonError> {
+        self.shards.remove(shard);
+        Ok(())
+    }
+
+    async fn write(
+        &self,
+        shard: &str,
+        data: &[AdapterWriteRecord],
+        _acks: i8,
+    ) -> Result<Vec<AdapterWriteRespRow>, CommonError> {
+        let mut memory_shard = self
+            .shards
+            .get_mut(shard)
+            .ok_or_else(|| Self::shard_not_found(shard))?;
+
+        let mut results = Vec::with_capacity(data.len());
+        for record in data {
+            let offset = memory_shard.next_offset();
+            memory_shard.append(convert_adapter_record_to_storage(
+                record.clone(),
+                shard,
+                offset,
+            ));
+            results.push(AdapterWriteRespRow {
+                offset,
+                pkid: record.record_id,
+                ..Default::default()
+            });
+        }
+        memory_shard.evict(&self.config);
+        Ok(results)
+    }
+
+    async fn read_by_offset(
+        &self,
+        shard: &str,
+        offset: u64,
+        read_config: &AdapterReadConfig,
+    ) -> Result<Vec<StorageRecord>, CommonError> {
+        let memory_shard = self
+            .shards
+            .get(shard)
+            .ok_or_else(|| Self::shard_not_found(shard))?;
+        Ok(collect_records(memory_shard.iter_from(offset), read_config))
+    }
+
+    async fn read_by_tag(
+        &self,
+        shard: &str,
+        tag: &str,
+        start_offset: Option<u64>,
+        read_config: &AdapterReadConfig,
+    ) -> Result<Vec<StorageRecord>, CommonError> {
+        let memory_shard = self
+            .shards
+            .get(shard)
+            .ok_or_else(|| Self::shard_not_found(shard))?;
+        let records = memory_shard
+            .iter_from(start_offset.unwrap_or(0))
+            .filter(|record| {
+                record
+                    .metadata
+                    .tags
+                    .as_ref()
+                    .is_some_and(|tags| tags.iter().any(|t| t == tag))
+            });
+        Ok(collect_records(records, read_config))
+    }
+
+    async fn read_by_keys(
+        &self,
+        shard: &str,
+        keys: &[&str],
+    ) -> Result<HashMap<String, Vec<StorageRecord>>, CommonError> {
+        let memory_shard = self
+            .shards
+            .get(shard)
+            .ok_or_else(|| Self::shard_not_found(shard))?;
+        let mut results: HashMap<String, Vec<StorageRecord>> = keys
+            .iter()
+            .map(|key| (key.to_string(), Vec::new()))
+            .collect();
+        for record in memory_shard.iter_from(0) {
+            if let Some(records) = record
+                .metadata
+                .key
+                .as_ref()
+                .and_then(|key| results.get_mut(key))
+            {
+                records.push(record.clone());
+            }
+        }
+        Ok(results)
+    }
+
+    async fn delete_by_keys(&self, shard: &str, keys: &[&str]) -> Result<(), CommonError> {
+        let mut memory_shard = self
+            .shards
+            .get_mut(shard)
+            .ok_or_else(|| Self::shard_not_found(shard))?;
+        memory_shard.remove_where(|record| {
+            record
+                .metadata
+                .key
+                .as_deref()
+                .is_some_and(|key| keys.contains(&key))
+        });
+        Ok(())
+    }
+
+    async fn delete_by_offsets(&self, shard: &str, offsets: &[u64]) -> Result<(), CommonError> {
+        let mut memory_shard = self
+            .shards
+            .get_mut(shard)
+            .ok_or_else(|| Self::shard_not_found(shard))?;
+        memory_shard.remove_where(|record| offsets.contains(&record.metadata.offset));
+        Ok(())
+    }
+
+    async fn get_offset_by_timestamp(
+        &self,
+        shard: &str,
+        timestamp: u64,
+        strategy: AdapterOffsetStrategy,
+    ) -> Result<u64, CommonError> {
+        let memory_shard = self
+            .shards
+            .get(shard)
+            .ok_or_else(|| Self::shard_not_found(shard))?;
+        if let Some(record) = memory_shard
+            .iter_from(0)
+            .find(|record| record.metadata.create_t >= timestamp)
+        {
+            return Ok(record.metadata.offset);
+        }
+        Ok(match strategy {
+            AdapterOffsetStrategy::Earliest => memory_shard.start_offset,
+            AdapterOffsetStrategy::Latest => memory_shard.next_offset(),
+        })
+    }
+
+    async fn shard_watermarks(&self, shard: &str) -> Result<AdapterShardWatermarks, CommonError> {
+        let memory_shard = self
+            .shards
+            .get(shard)
+            .ok_or_else(|| Self::shard_not_found(shard))?;
+        let latest_timestamp = memory_shard
+            .records
+            .iter()
+            .rev()
+            .flatten()
+            .next()
+            .map(|record| record.metadata.create_t)
+            .unwrap_or(0);
+        Ok(AdapterShardWatermarks {
+            shard_name: shard.to_string(),
+            earliest_offset: memory_shard.start_offset,
+            latest_offset: memory_shard.next_offset(),
+            high_watermark: memory_shard.next_offset(),
+            latest_timestamp,
+        })
+    }
+
+    async fn close(&self) -> Result<(), CommonError> {
+        self.shards.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::driver::ArcStorageAdapter;
+    use crate::tests::storage_adapter_conformance;
+    use common_base::uuid::unique_id;
+    use std::sync::Arc;
+
+    fn adapter(config: MemoryAdapterConfig) -> ArcStorageAdapter {
+        Arc::new(MemoryStorageAdapter::new(config))
+    }
+
+    #[tokio::test]
+    async fn memory_conformance_test() {
+        storage_adapter_conformance(adapter(MemoryAdapterConfig::default())).await;
+    }
+
+    #[tokio::test]
+    async fn evict_oldest_records_test() {
+        let adapter = adapter(MemoryAdapterConfig {
+            max_records_per_shard: 3,
+            max_bytes_per_shard: 0,
+        });
+        let shard_name = unique_id();
+        adapter
+            .create_shard(&AdapterShardInfo {
+                shard_name: shard_name.clone(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let records: Vec<AdapterWriteRecord> = (0..5)
+            .map(|i| AdapterWriteRecord::new(&shard_name, format!("msg{}", i).into_bytes()))
+            .collect();
+        adapter.write(&shard_name, &records, 1).await.unwrap();
+
+        let read = adapter
+            .read_by_offset(&shard_name, 0, &AdapterReadConfig::new())
+            .await
+            .unwrap();
+        let offsets: Vec<u64> = read.iter().map(|r| r.metadata.offset).collect();
+        assert_eq!(offsets, vec![2, 3, 4]);
+
+        let watermarks = adapter.shard_watermarks(&shard_name).await.unwrap();
+        assert_eq!(watermarks.earliest_offset, 2);
+        assert_eq!(watermarks.latest_offset, 5);
+    }
+
+    #[tokio::test]
+    async fn evict_by_bytes_and_delete_test() {
+        let adapter = adapter(MemoryAdapterConfig {
+            max_records_per_shard: 0,
+            max_bytes_per_shard: 8,
+        });
+        let shard_name = unique_id();
+        adapter
+            .create_shard(&AdapterShardInfo {
+                shard_name: shard_name.clone(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let records: Vec<AdapterWriteRecord> = (0..3)
+            .map(|i| AdapterWriteRecord::new(&shard_name, format!("msg{}", i).into_bytes()))
+            .collect();
+        adapter.write(&shard_name, &records, 1).await.unwrap();
+        assert_eq!(
+            adapter
+                .shard_watermarks(&shard_name)
+                .await
+                .unwrap()
+                .earliest_offset,
+            1
+        );
+
+        adapter.delete_by_offsets(&shard_name, &[1]).await.unwrap();
+        let read = adapter
+            .read_by_offset(&shard_name, 0, &AdapterReadConfig::new())
+            .await
+            .unwrap();
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].metadata.offset, 2);
+    }
+}
@@ -25,7 +25,7 @@
 use metadata_struct::adapter::adapter_offset::{AdapterOffsetStrategy, AdapterShardInfo};
 use metadata_struct::adapter::adapter_read_config::{AdapterReadConfig, AdapterWriteRespRow};
 use metadata_struct::adapter::adapter_record::AdapterWriteRecord;
-use metadata_struct::adapter::adapter_shard::AdapterShardDetail;
+use metadata_struct::adapter::adapter_shard::{AdapterShardDetail, AdapterShardWatermarks};
 use metadata_struct::storage::record::StorageRecord;
 use metadata_struct::storage::segment::EngineSegment;
 use metadata_struct::storage::segment_meta::EngineSegmentMetadata;
@@ -93,6 +93,8 @@ async fn get_offset_by_timestamp(
         strategy: AdapterOffsetStrategy,
     ) -> Result<u64, CommonError>;
 
+    async fn shard_watermarks(&self, shard: &str) -> Result<AdapterShardWatermarks, CommonError>;
+
     async fn close(&self) -> Result<(), CommonError>;
 }
 
@@ -12,12 +12,44 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+//! Conformance suite for [`StorageAdapter`](crate::storage::StorageAdapter)
+//! implementations. Every adapter runs [`storage_adapter_conformance`] from
+//! its own tests so they are all verified against the same behaviour.
+//! Consumer group offsets are kept by `OffsetManager`, not by the adapters,
+//! and are not covered here.
+
 use crate::driver::ArcStorageAdapter;
+use common_base::tools::now_second;
 use common_base::uuid::unique_id;
 use metadata_struct::adapter::adapter_offset::{AdapterOffsetStrategy, AdapterShardInfo};
 use metadata_struct::adapter::adapter_read_config::AdapterReadConfig;
 use metadata_struct::adapter::adapter_record::AdapterWriteRecord;
 use metadata_struct::storage::shard::EngineShardConfig;
+use std::collections::HashSet;
+
+/// Runs every conformance check against `adapter`, which must start
+/// without shards.
+pub async fn storage_adapter_conformance(adapter: ArcStorageAdapter) {
+    test_shard_lifecycle(adapter.clone()).await;
+    test_write_and_read(adapter.clone()).await;
+    test_offset_ordering(adapter.clone()).await;
+    test_delete_by_keys_and_offsets(adapter.clone()).await;
+    test_offset_by_timestamp(adapter.clone()).await;
+    test_shard_watermarks(adapter.clone()).await;
+    test_concurrent_writes(adapter).await;
+}
+
+async fn create_test_shard(adapter: &ArcStorageAdapter) -> String {
+    let shard_name = unique_id();
+    adapter
+        .create_shard(&AdapterShardInfo {
+            shard_name: shard_name.clone(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    shard_name
+}
 
 pub async fn test_shard_lifecycle(adapter: ArcStorageAdapter) {
     let shard1_name = unique_id();
@@ -52,6 +84,7 @@ pub async fn test_shard_lifecycle(adapter: ArcStorageAdapter) {
         adapter.list_shard(Some(shard1_name)).await.unwrap().len(),
         0
     );
+    adapter.delete_shard(&shard2_name).await.unwrap();
 }
 
 pub async fn test_write_and_read(adapter: ArcStorageAdapter) {
@@ -231,3 +264,173 @@ pub async fn test_timestamp_index_with_multiple_entries(adapter: ArcStorageAdapt
     assert!(!read_result.is_empty());
     assert_eq!(read_result[0].metadata.create_t, 6000);
 }
+
+pub async fn test_shard_watermarks(adapter: ArcStorageAdapter) {
+    let shard_name = unique_id();
+    adapter
+        .create_shard(&AdapterShardInfo {
+            shard_name: shard_name.clone(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    let watermarks = adapter.shard_watermarks(&shard_name).await.unwrap();
+    assert_eq!(watermarks.earliest_offset, 0);
+    assert_eq!(watermarks.latest_offset, 0);
+    assert_eq!(watermarks.latest_timestamp, 0);
+
+    let records: Vec<AdapterWriteRecord> = (0..3)
+        .map(|i| AdapterWriteRecord::new(&shard_name, format!("msg{}", i).into_bytes()))
+        .collect();
+    adapter.write(&shard_name, &records, 1).await.unwrap();
+
+    let watermarks = adapter.shard_watermarks(&shard_name).await.unwrap();
+    assert_eq!(watermarks.shard_name, shard_name);
+    assert_eq!(watermarks.earliest_offset, 0);
+    assert_eq!(watermarks.latest_offset, 3);
+    assert!(watermarks.latest_timestamp > 0);
+}
+
+pub async fn test_offset_ordering(adapter: ArcStorageAdapter) {
+    let shard_name = create_test_shard(&adapter).await;
+
+    for batch in 0..3u64 {
+        let records: Vec<AdapterWriteRecord> = (0..4u64)
+            .map(|i| {
+                AdapterWriteRecord::new(&shard_name, format!("msg{}", batch * 4 + i).into_bytes())
+            })
+            .collect();
+        let offsets: Vec<u64> = adapter
+            .write(&shard_name, &records, 1)
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.offset)
+            .collect();
+        assert_eq!(offsets, (batch * 4..batch * 4 + 4).collect::<Vec<u64>>());
+    }
+
+    let cfg = AdapterReadConfig {
+        max_record_num: 5,
+        max_size: 1024 * 1024,
+    };
+    let records = adapter.read_by_offset(&shard_name, 3, &cfg).await.unwrap();
+    assert_eq!(
+        records
+            .iter()
+            .map(|record| record.metadata.offset)
+            .collect::<Vec<u64>>(),
+        vec![3, 4, 5, 6, 7]
+    );
+    for record in records {
+        assert_eq!(
+            record.data.to_vec(),
+            format!("msg{}", record.metadata.offset).into_bytes()
+        );
+    }
+
+    assert!(adapter
+        .read_by_offset(&shard_name, 12, &cfg)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+pub async fn test_delete_by_keys_and_offsets(adapter: ArcStorageAdapter) {
+    let shard_name = create_test_shard(&adapter).await;
+    let cfg = AdapterReadConfig {
+        max_record_num: 10,
+        max_size: 1024 * 1024,
+    };
+
+    let records: Vec<AdapterWriteRecord> = (0..3)
+        .map(|i| {
+            AdapterWriteRecord::new(&shard_name, format!("msg{}", i).into_bytes())
+                .with_key(format!("k{}", i))
+        })
+        .collect();
+    adapter.write(&shard_name, &records, 1).await.unwrap();
+
+    adapter.delete_by_keys(&shard_name, &["k1"]).await.unwrap();
+    assert!(adapter
+        .read_by_keys(&shard_name, &["k1"])
+        .await
+        .unwrap()
+        .remove("k1")
+        .unwrap_or_default()
+        .is_empty());
+
+    adapter.delete_by_offsets(&shard_name, &[0]).await.unwrap();
+    let offsets: Vec<u64> = adapter
+        .read_by_offset(&shard_name, 0, &cfg)
+        .await
+        .unwrap()
+        .iter()
+        .map(|record| record.metadata.offset)
+        .collect();
+    assert_eq!(offsets, vec![2]);
+}
+
+pub async fn test_offset_by_timestamp(adapter: ArcStorageAdapter) {
+    let shard_name = create_test_shard(&adapter).await;
+    let start = now_second();
+
+    let records: Vec<AdapterWriteRecord> = (0..3)
+        .map(|i| AdapterWriteRecord::new(&shard_name, format!("msg{}", i).into_bytes()))
+        .collect();
+    adapter.write(&shard_name, &records, 1).await.unwrap();
+
+    let offset = adapter
+        .get_offset_by_timestamp(&shard_name, start, AdapterOffsetStrategy::Earliest)
+        .await
+        .unwrap();
+    assert_eq!(offset, 0);
+
+    let read = adapter
+        .read_by_offset(&shard_name, 0, &AdapterReadConfig::new())
+        .await
+        .unwrap();
+    assert!(read.iter().all(|record| record.metadata.create_t >= start));
+}
+
+pub async fn test_concurrent_writes(adapter: ArcStorageAdapter) {
+    let shard_name = create_test_shard(&adapter).await;
+    let writers = 8u64;
+    let per_writer = 50u64;
+
+    let mut handles = Vec::new();
+    for writer in 0..writers {
+        let adapter = adapter.clone();
+        let shard_name = shard_name.clone();
+        handles.push(tokio::spawn(async move {
+            let mut offsets = Vec::new();
+            for i in 0..per_writer {
+                let record =
+                    AdapterWriteRecord::new(&shard_name, format!("{}-{}", writer, i).into_bytes());
+                let rows = adapter.write(&shard_name, &[record], 1).await.unwrap();
+                offsets.push(rows[0].offset);
+            }
+            offsets
+        }));
+    }
+
+    let mut offsets = HashSet::new();
+    for handle in handles {
+        for offset in handle.await.unwrap() {
+            assert!(offsets.insert(offset), "offset {} written twice", offset);
+        }
+    }
+    let total = writers * per_writer;
+    assert_eq!(offsets, (0..total).collect::<HashSet<u64>>());
+
+    let cfg = AdapterReadConfig {
+        max_record_num: total,
+        max_size: 1024 * 1024,
+    };
+    let read = adapter.read_by_offset(&shard_name, 0, &cfg).await.unwrap();
+    assert_eq!(read.len() as u64, total);
+    assert!(read
+        .windows(2)
+        .all(|pair| pair[0].metadata.offset < pair[1].metadata.offset));
+}
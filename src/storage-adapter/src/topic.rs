@@ -17,14 +17,15 @@
     cache::NodeCacheManager,
     inner_topic::{
         AGENT_REPORT_INFO_TOPIC, DELAY_QUEUE_INDEX_TOPIC, DELAY_QUEUE_MESSAGE_TOPIC,
-        DELAY_TASK_INDEX_TOPIC, LAST_WILL_MESSAGE_TOPIC, QOS2_INNER_TOPIC, RETAIN_MESSAGE_TOPIC,
+        DELAY_TASK_INDEX_TOPIC, INFLIGHT_MESSAGE_TOPIC, LAST_WILL_MESSAGE_TOPIC,
+        METRICS_HISTORY_TOPIC, QOS2_INNER_TOPIC, RETAIN_MESSAGE_TOPIC,
     },
 };
 use common_base::error::common::CommonError;
 use common_config::{broker::broker_config, storage::StorageType};
 use grpc_clients::{meta::mqtt::call::placement_create_topic, pool::ClientPool};
 use metadata_struct::{
-    mqtt::topic::{Topic, TopicSource},
+    mqtt::topic::{Topic, TopicConfig, TopicSource},
     storage::shard::EngineShardConfig,
     tenant::DEFAULT_TENANT,
 };
@@ -42,12 +43,15 @@ pub fn topic_replication_num(replica_num: u32) -> u32 {
     }
 }
 
+/// Create the topic in meta-service and its storage shards. The storage type
+/// may be replaced by a storage routing rule; the topic as created is returned.
 pub async fn create_topic_full(
     broker_cache: &Arc<NodeCacheManager>,
     storage_driver_manager: &Arc<StorageDriverManager>,
     client_pool: &Arc<ClientPool>,
     topic: &Topic,
-) -> Result<(), CommonError> {
+) -> Result<Topic, CommonError> {
+    let topic = &storage_driver_manager.route_topic(topic);
     let conf = broker_config();
     let request = CreateTopicRequest {
         tenant: topic.tenant.clone(),
@@ -90,7 +94,7 @@ pub async fn create_topic_full(
     storage_driver_manager
         .create_storage_resource(&topic.tenant, &topic.topic_name, &shard_config)
         .await?;
-    Ok(())
+    Ok(topic.clone())
 }
 
 /// Initialize all internal (built-in) topics required by the broker.
@@ -110,12 +114,31 @@ pub async fn init_inner_topics(
         DELAY_QUEUE_INDEX_TOPIC,
         AGENT_REPORT_INFO_TOPIC,
         QOS2_INNER_TOPIC,
+        INFLIGHT_MESSAGE_TOPIC,
     ] {
         init_single_inner_topic(
             broker_cache,
             storage_driver_manager,
             client_pool,
             topic_name,
+            TopicConfig::default(),
+        )
+        .await?;
+    }
+
+    // Metrics history keeps one snapshot per broker per interval, so it is
+    // bounded by the configured retention instead of the default one.
+    let monitor = &broker_config().mqtt_system_monitor;
+    if monitor.metrics_history_enable {
+        init_single_inner_topic(
+            broker_cache,
+            storage_driver_manager,
+            client_pool,
+            METRICS_HISTORY_TOPIC,
+            TopicConfig {
+                retention_sec: monitor.metrics_history_retention_sec,
+                ..Default::default()
+            },
         )
         .await?;
     }
@@ -129,6 +152,7 @@ async fn init_single_inner_topic(
     storage_driver_manager: &Arc<StorageDriverManager>,
     client_pool: &Arc<ClientPool>,
     topic_name: &str,
+    config: TopicConfig,
 ) -> Result<(), CommonError> {
     if let Some(topic) = broker_cache.get_topic_by_name(DEFAULT_TENANT, topic_name) {
         debug!(
@@ -152,6 +176,7 @@ async fn init_single_inner_topic(
 
     let conf = broker_config();
     let topic = Topic::new(DEFAULT_TENANT, topic_name, StorageType::EngineRocksDB)
+        .with_config(config)
         .with_partition(conf.runtime.default_topic_partition_num)
         .with_replication(topic_replication_num(
             conf.runtime.default_topic_replica_num,
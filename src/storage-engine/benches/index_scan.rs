@@ -0,0 +1,106 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Reading a page of records through the tag and timestamp indexes of the
+// RocksDB commit log, on a shard large enough that scanning a whole index
+// shows up in the timings.
+// Run with: cargo bench -p storage-engine --bench index_scan
+
+use bytes::Bytes;
+use common_base::tools::now_second;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use metadata_struct::adapter::adapter_record::AdapterWriteRecord;
+use metadata_struct::storage::adapter_offset::AdapterOffsetStrategy;
+use metadata_struct::storage::adapter_read_config::AdapterReadConfig;
+use storage_engine::commitlog::rocksdb::engine::RocksDBStorageEngine;
+use storage_engine::core::test_tool::test_build_rocksdb_engine;
+use tokio::runtime::Runtime;
+
+const SHARD: &str = "bench-shard";
+const RECORDS: u64 = 100_000;
+const TAGS: u64 = 10;
+
+fn build_engine(rt: &Runtime) -> RocksDBStorageEngine {
+    let engine = test_build_rocksdb_engine();
+    engine
+        .commitlog_offset
+        .save_earliest_offset(SHARD, 0)
+        .unwrap();
+    engine
+        .commitlog_offset
+        .save_latest_offset(SHARD, 0)
+        .unwrap();
+
+    rt.block_on(async {
+        for start in (0..RECORDS).step_by(1000) {
+            let messages: Vec<AdapterWriteRecord> = (start..start + 1000)
+                .map(|i| {
+                    AdapterWriteRecord::new(SHARD, Bytes::from_static(b"payload"))
+                        .with_tags(vec![format!("tag{}", i % TAGS)])
+                })
+                .collect();
+            engine.batch_write(SHARD, &messages).await.unwrap();
+        }
+    });
+    engine
+}
+
+fn bench_read_by_tag(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let engine = build_engine(&rt);
+    let read_config = AdapterReadConfig {
+        max_record_num: 100,
+        max_size: 1024 * 1024,
+    };
+
+    let mut group = c.benchmark_group("read_by_tag");
+    for start_offset in [0, RECORDS / 2, RECORDS - 1000] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(start_offset),
+            &start_offset,
+            |b, start_offset| {
+                b.iter(|| {
+                    rt.block_on(engine.read_by_tag(
+                        SHARD,
+                        "tag3",
+                        Some(black_box(*start_offset)),
+                        &read_config,
+                    ))
+                    .unwrap()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn bench_offset_by_timestamp(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let engine = build_engine(&rt);
+    let now = now_second();
+
+    c.bench_function("get_offset_by_timestamp", |b| {
+        b.iter(|| {
+            rt.block_on(engine.get_offset_by_timestamp(
+                SHARD,
+                black_box(now),
+                AdapterOffsetStrategy::Latest,
+            ))
+            .unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, bench_read_by_tag, bench_offset_by_timestamp);
+criterion_main!(benches);
@@ -60,11 +60,18 @@ pub async fn send_write(
     }
 
     // send_write with a fully-formed body (e.g. custom acks, timeout_ms).
+    // A body without a fencing token is stamped with the active segment known
+    // to this node, so the leader can reject it if this node is behind.
     pub async fn send_write_body(
         &self,
         node_id: u64,
-        body: WriteReqBody,
+        mut body: WriteReqBody,
     ) -> Result<Vec<AdapterWriteRespRow>, StorageEngineError> {
+        if body.current_segment_seq.is_none() {
+            if let Some(segment) = self.cache_manager.get_active_segment(&body.shard_name) {
+                body = body.with_fencing(segment.segment_seq, segment.leader_epoch);
+            }
+        }
         let resp = self
             .write_send(node_id, StorageEnginePacket::WriteReq(WriteReq::new(body)))
             .await?;
@@ -86,7 +93,7 @@ pub async fn send_read(
             .write_send(node_id, StorageEnginePacket::ReadReq(req))
             .await?;
         match resp {
-            StorageEnginePacket::ReadResp(r) => Ok(read_resp_parse(&r)?),
+            StorageEnginePacket::ReadResp(r) => Ok(read_resp_parse(r)?),
             other => Err(StorageEngineError::ReceivedPacketError(
                 node_id,
                 format!("Expected ReadResp, got {other}"),
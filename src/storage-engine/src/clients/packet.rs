@@ -12,28 +12,27 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use common_base::utils::serialize::deserialize;
+use bytes::Bytes;
 use metadata_struct::storage::{adapter_read_config::AdapterWriteRespRow, record::StorageRecord};
 use protocol::storage::protocol::{
     ApiKey, FetchReq, FetchReqBody, ReadReq, ReadReqBody, ReadReqMessage, ReadResp, ReadRespBody,
     ReqHeader, RespHeader, StorageEngineNetworkError, WriteReq, WriteReqBody, WriteResp,
-    WriteRespBody, WriteRespMessage, DEFAULT_WRITE_TIMEOUT_MS,
+    WriteRespBody, WriteRespMessage,
 };
 
-use crate::core::error::StorageEngineError;
+use crate::core::error::{StorageEngineError, FENCED_LEADER_EPOCH_CODE};
 
-pub fn build_write_req(shard_name: String, messages: Vec<Vec<u8>>) -> WriteReq {
+pub fn build_write_req(
+    shard_name: String,
+    messages: Vec<Vec<u8>>,
+    segment_seq: u32,
+    leader_epoch: u32,
+) -> WriteReq {
     WriteReq {
         header: ReqHeader {
             api_key: ApiKey::Write,
         },
-        body: WriteReqBody {
-            shard_name,
-            messages,
-            acks: 1,
-            current_leader_epoch: 0,
-            timeout_ms: DEFAULT_WRITE_TIMEOUT_MS,
-        },
+        body: WriteReqBody::new(shard_name, messages).with_fencing(segment_seq, leader_epoch),
     }
 }
 
@@ -84,6 +83,11 @@ pub fn build_read_resp(
 // todo: In the future, there may be situations where some records are successfully written while others fail.
 pub fn write_resp_parse(resp: &WriteResp) -> Result<Vec<AdapterWriteRespRow>, StorageEngineError> {
     if let Some(err) = &resp.header.error {
+        if err.code == FENCED_LEADER_EPOCH_CODE {
+            return Err(StorageEngineError::RemoteFencedLeaderEpoch(
+                err.error.clone(),
+            ));
+        }
         return Err(StorageEngineError::CommonErrorStr(err.to_str()));
     }
 
@@ -108,13 +112,14 @@ pub fn write_resp_parse(resp: &WriteResp) -> Result<Vec<AdapterWriteRespRow>, St
     Ok(results)
 }
 
-pub fn read_resp_parse(resp: &ReadResp) -> Result<Vec<StorageRecord>, StorageEngineError> {
+/// Payloads of the returned records share the response buffers.
+pub fn read_resp_parse(resp: ReadResp) -> Result<Vec<StorageRecord>, StorageEngineError> {
     if let Some(err) = &resp.header.error {
         return Err(StorageEngineError::CommonErrorStr(err.to_str()));
     }
     let mut records = Vec::with_capacity(resp.body.messages.len());
-    for msg in &resp.body.messages {
-        records.push(deserialize::<StorageRecord>(msg)?);
+    for msg in resp.body.messages {
+        records.push(StorageRecord::decode_bytes(&Bytes::from(msg))?);
     }
     Ok(records)
 }
@@ -21,11 +21,19 @@
     adapter_offset::AdapterOffsetStrategy, adapter_read_config::AdapterReadConfig,
     record::StorageRecord,
 };
+use rocksdb::{DBAccess, DBRawIteratorWithThreadMode};
 use rocksdb_engine::keys::engine::{
     key_index_key, record_key, record_prefix, tag_index_key, tag_index_tag_prefix,
-    timestamp_index_prefix,
+    timestamp_index_key, timestamp_index_prefix,
 };
 
+/// Tag index entries resolved to records per multi_get.
+const TAG_SCAN_BATCH: usize = 100;
+
+/// Tag index entries visited by one read at most, so a tag whose records
+/// have mostly expired can't turn a read into a scan of the whole tag.
+const TAG_MAX_SCAN: usize = 10000;
+
 impl RocksDBStorageEngine {
     pub async fn read_by_offset(
         &self,
@@ -91,58 +99,64 @@ pub async fn read_by_tag(
             None => tag_prefix.clone(),
         };
 
-        let mut offsets = Vec::new();
         let mut iter = self.rocksdb_engine_handler.db.raw_iterator_cf(&cf);
         iter.seek(seek_key.as_bytes());
-        while iter.valid() {
-            let Some(key_bytes) = iter.key() else {
-                break;
-            };
-            if !key_bytes.starts_with(tag_prefix.as_bytes()) {
-                break;
-            }
-            let Some(value) = iter.value() else {
-                break;
-            };
-            offsets.push(deserialize::<IndexInfo>(value)?.offset);
-            iter.next();
-        }
 
-        if offsets.is_empty() {
-            return Ok(Vec::new());
-        }
-
-        // Limit after fetching so holes/expired entries don't cause under-reads.
-        let keys: Vec<String> = offsets
-            .iter()
-            .map(|off| record_key(shard, 0, *off))
-            .collect();
-        let batch_results = self
-            .rocksdb_engine_handler
-            .multi_get::<StorageRecord>(cf, &keys)?;
         let mut records = Vec::new();
         let mut total_size = 0;
-
-        for record_opt in batch_results {
-            let Some(record) = record_opt else {
-                continue;
-            };
-
-            if is_record_expired(&record.metadata) {
-                continue;
+        let mut scanned = 0;
+        let mut keys = Vec::with_capacity(TAG_SCAN_BATCH);
+
+        // Resolve the index in batches and stop as soon as the read is full.
+        // Limits apply to fetched records so holes/expired entries don't
+        // cause under-reads.
+        loop {
+            keys.clear();
+            while keys.len() < TAG_SCAN_BATCH && scanned < TAG_MAX_SCAN && iter.valid() {
+                let (Some(key_bytes), Some(value)) = (iter.key(), iter.value()) else {
+                    break;
+                };
+                if !key_bytes.starts_with(tag_prefix.as_bytes()) {
+                    break;
+                }
+                keys.push(record_key(
+                    shard,
+                    0,
+                    deserialize::<IndexInfo>(value)?.offset,
+                ));
+                scanned += 1;
+                iter.next();
             }
 
-            if records.len() >= read_config.max_record_num as usize {
+            if keys.is_empty() {
                 break;
             }
 
-            let record_bytes = record.data.len() as u64;
-            if !records.is_empty() && total_size + record_bytes > read_config.max_size {
-                break;
-            }
+            let batch_results = self
+                .rocksdb_engine_handler
+                .multi_get::<StorageRecord>(cf.clone(), &keys)?;
+
+            for record_opt in batch_results {
+                let Some(record) = record_opt else {
+                    continue;
+                };
+
+                if is_record_expired(&record.metadata) {
+                    continue;
+                }
+
+                if records.len() >= read_config.max_record_num as usize {
+                    return Ok(records);
+                }
+
+                let record_bytes = record.data.len() as u64;
+                if !records.is_empty() && total_size + record_bytes > read_config.max_size {
+                    return Ok(records);
+                }
 
-            total_size += record_bytes;
-            records.push(record);
+                total_size += record_bytes;
+                records.push(record);
+            }
         }
 
         Ok(records)
@@ -221,37 +235,19 @@ pub async fn search_index_by_timestamp(
         timestamp: u64,
     ) -> Result<Option<IndexInfo>, StorageEngineError> {
         let cf = self.get_cf()?;
-        let timestamp_index_prefix = timestamp_index_prefix(shard);
+        let prefix = timestamp_index_prefix(shard);
         let mut iter = self.rocksdb_engine_handler.db.raw_iterator_cf(&cf);
-        iter.seek(&timestamp_index_prefix);
-
-        let mut last_index = None;
-        while iter.valid() {
-            let Some(key_bytes) = iter.key() else {
-                break;
-            };
-            if !key_bytes.starts_with(timestamp_index_prefix.as_bytes()) {
-                break;
-            }
-            let Some(value_byte) = iter.value() else {
-                break;
-            };
-
-            let index = deserialize::<IndexInfo>(value_byte)?;
 
-            if last_index.is_none() {
-                last_index = Some(index.clone());
-            }
-
-            if index.create_time > timestamp {
-                return Ok(last_index);
-            }
-            last_index = Some(index);
-
-            iter.next();
+        // keys sort by timestamp, so the entry right before the first key
+        // past `timestamp` is the latest one at or before it
+        iter.seek_for_prev(timestamp_index_key(shard, timestamp, u64::MAX).as_bytes());
+        if let Some(index) = current_index(&iter, &prefix)? {
+            return Ok(Some(index));
         }
 
-        Ok(last_index)
+        // every entry is newer, start from the oldest one
+        iter.seek(prefix.as_bytes());
+        current_index(&iter, &prefix)
     }
 
     async fn read_data_by_time(
@@ -297,6 +293,22 @@ async fn read_data_by_time(
     }
 }
 
+fn current_index<D: DBAccess>(
+    iter: &DBRawIteratorWithThreadMode<'_, D>,
+    prefix: &str,
+) -> Result<Option<IndexInfo>, StorageEngineError> {
+    if !iter.valid() {
+        return Ok(None);
+    }
+    let (Some(key_bytes), Some(value)) = (iter.key(), iter.value()) else {
+        return Ok(None);
+    };
+    if !key_bytes.starts_with(prefix.as_bytes()) {
+        return Ok(None);
+    }
+    Ok(Some(deserialize::<IndexInfo>(value)?))
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -359,4 +371,82 @@ async fn test_batch_write_and_read_by_offset() {
         assert_eq!(key_records.len(), 1);
         assert_eq!(key_records[0].metadata.offset, 5);
     }
+
+    #[tokio::test]
+    async fn test_read_by_tag_stops_at_limit() {
+        let engine = test_build_rocksdb_engine();
+        let shard_name = unique_id();
+        engine
+            .commitlog_offset
+            .save_earliest_offset(&shard_name, 0)
+            .unwrap();
+        engine
+            .commitlog_offset
+            .save_latest_offset(&shard_name, 0)
+            .unwrap();
+
+        let messages: Vec<AdapterWriteRecord> = (0..300)
+            .map(|i| {
+                AdapterWriteRecord::new("", bytes::Bytes::default())
+                    .with_tags(vec![format!("tag{}", i % 2)])
+            })
+            .collect();
+        engine.batch_write(&shard_name, &messages).await.unwrap();
+
+        let read_config = AdapterReadConfig {
+            max_record_num: 3,
+            max_size: 1024 * 1024,
+        };
+        let records = engine
+            .read_by_tag(&shard_name, "tag1", Some(201), &read_config)
+            .await
+            .unwrap();
+        let offsets: Vec<u64> = records.iter().map(|r| r.metadata.offset).collect();
+        assert_eq!(offsets, vec![201, 203, 205]);
+
+        let records = engine
+            .read_by_tag(&shard_name, "tag1", Some(295), &read_config)
+            .await
+            .unwrap();
+        let offsets: Vec<u64> = records.iter().map(|r| r.metadata.offset).collect();
+        assert_eq!(offsets, vec![295, 297, 299]);
+    }
+
+    #[tokio::test]
+    async fn test_search_index_by_timestamp() {
+        let engine = test_build_rocksdb_engine();
+        let shard_name = unique_id();
+        assert!(engine
+            .search_index_by_timestamp(&shard_name, 100)
+            .await
+            .unwrap()
+            .is_none());
+
+        let cf = engine.get_cf().unwrap();
+        for (offset, create_time) in [(0, 100), (5000, 200), (10000, 300)] {
+            let index = IndexInfo {
+                shard_name: shard_name.clone(),
+                offset,
+                create_time,
+            };
+            engine
+                .rocksdb_engine_handler
+                .db
+                .put_cf(
+                    &cf,
+                    timestamp_index_key(&shard_name, create_time, offset),
+                    common_base::utils::serialize::serialize(&index).unwrap(),
+                )
+                .unwrap();
+        }
+
+        for (timestamp, offset) in [(250, 5000), (200, 5000), (50, 0), (400, 10000)] {
+            let index = engine
+                .search_index_by_timestamp(&shard_name, timestamp)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(index.offset, offset);
+        }
+    }
 }
@@ -124,8 +124,8 @@ async fn batch_write_internal(
                 }
             }
 
-            // timestamp index
-            let msg_timestamp = now_second();
+            // timestamp index, keyed by the same time the entry records
+            let msg_timestamp = offset_info.create_time;
             if msg_timestamp > 0 && offset % 5000 == 0 {
                 let timestamp_index_key = timestamp_index_key(shard_name, msg_timestamp, offset);
                 batch.put_cf(
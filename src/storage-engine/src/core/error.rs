@@ -92,6 +92,15 @@ pub enum StorageEngineError {
     #[error("Current node is not the Leader of Segment {0}")]
     NotLeader(String),
 
+    #[error("FencedLeaderEpoch: write to shard {0} carries segment {1} leader epoch {2}, older than the active segment {3} leader epoch {4}")]
+    FencedLeaderEpoch(String, u32, u32, u32, u32),
+
+    #[error("UnknownLeaderEpoch: write to shard {0} carries segment {1} leader epoch {2}, newer than the local segment {3} leader epoch {4}")]
+    UnknownLeaderEpoch(String, u32, u32, u32, u32),
+
+    #[error("The leader rejected the write with a stale fencing token: {0}")]
+    RemoteFencedLeaderEpoch(String),
+
     #[error("Segment {0} is not on this broker, please retry with the correct broker")]
     SegmentNotOnThisBroker(String),
 
@@ -167,6 +176,9 @@ pub enum StorageEngineError {
     OffsetOutOfRange(String, u64, u64, u64),
 }
 
+/// Code a leader answers with when a write carries a stale fencing token.
+pub const FENCED_LEADER_EPOCH_CODE: &str = "FencedLeaderEpoch";
+
 pub fn get_journal_server_code(e: &StorageEngineError) -> String {
     match e {
         StorageEngineError::FromBoxCommonError(_) => "CommonError".to_string(),
@@ -198,6 +210,9 @@ pub fn get_journal_server_code(e: &StorageEngineError) -> String {
         StorageEngineError::NotFoundConnectionInCache(_) => "NotFoundConnectionInCache".to_string(),
         StorageEngineError::SegmentStatusError(_, _) => "SegmentStatusError".to_string(),
         StorageEngineError::NotLeader(_) => "NotLeader".to_string(),
+        StorageEngineError::FencedLeaderEpoch(_, _, _, _, _)
+        | StorageEngineError::RemoteFencedLeaderEpoch(_) => FENCED_LEADER_EPOCH_CODE.to_string(),
+        StorageEngineError::UnknownLeaderEpoch(_, _, _, _, _) => "UnknownLeaderEpoch".to_string(),
         StorageEngineError::SegmentNotOnThisBroker(_) => "SegmentNotOnThisBroker".to_string(),
         StorageEngineError::SegmentFileNotExists(_) => "SegmentFileNotExists".to_string(),
         StorageEngineError::SegmentDataDirectoryNotFound(_, _) => {
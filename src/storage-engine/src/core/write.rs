@@ -27,8 +27,9 @@
 use common_config::{broker::broker_config, storage::StorageType};
 use metadata_struct::storage::{
     adapter_read_config::AdapterWriteRespRow, adapter_record::AdapterWriteRecord,
+    segment::EngineSegment,
 };
-use protocol::storage::protocol::DEFAULT_WRITE_TIMEOUT_MS;
+use protocol::storage::protocol::{WriteReqBody, DEFAULT_WRITE_TIMEOUT_MS};
 use std::sync::Arc;
 use tracing::warn;
 
@@ -56,14 +57,20 @@ pub async fn batch_write(
 
     let segment_iden = SegmentIdentity::new(shard_name, active_segment.segment_seq);
     segment_validator(cache_manager, &shard, &active_segment, &segment_iden)?;
+    if !active_segment.allow_write() {
+        return Err(StorageEngineError::SegmentStatusError(
+            segment_iden.name(),
+            active_segment.status.to_string(),
+        ));
+    }
 
     let conf = broker_config();
 
     if conf.broker_id != active_segment.leader {
         return write_data_to_remote(
+            cache_manager,
             client_connection_manager,
-            active_segment.leader,
-            shard_name,
+            &active_segment,
             records,
         )
         .await;
@@ -145,19 +152,66 @@ pub async fn batch_write(
     Ok(offsets)
 }
 
+/// Reject a write whose fencing token (segment, leader epoch) does not match
+/// the active segment. An older token comes from a sender that missed a seal or
+/// a leader change; a newer one means this node missed it, so the segment is
+/// queued for reconcile.
+pub fn check_write_fencing(
+    cache_manager: &Arc<StorageCacheManager>,
+    shard_name: &str,
+    segment_seq: u32,
+    leader_epoch: u32,
+) -> Result<(), StorageEngineError> {
+    let Some(active_segment) = cache_manager.get_active_segment(shard_name) else {
+        return Err(StorageEngineError::SegmentNotExist(shard_name.to_owned()));
+    };
+
+    let request = (segment_seq, leader_epoch);
+    let local = (active_segment.segment_seq, active_segment.leader_epoch);
+    if request < local {
+        return Err(StorageEngineError::FencedLeaderEpoch(
+            shard_name.to_owned(),
+            segment_seq,
+            leader_epoch,
+            local.0,
+            local.1,
+        ));
+    }
+    if request > local {
+        cache_manager.mark_reconcile_needed(shard_name, local.0, 1);
+        return Err(StorageEngineError::UnknownLeaderEpoch(
+            shard_name.to_owned(),
+            segment_seq,
+            leader_epoch,
+            local.0,
+            local.1,
+        ));
+    }
+    Ok(())
+}
+
 async fn write_data_to_remote(
+    cache_manager: &Arc<StorageCacheManager>,
     client_connection_manager: &Arc<ClientConnectionManager>,
-    target_broker_id: u64,
-    shard_name: &str,
+    active_segment: &EngineSegment,
     records: &[AdapterWriteRecord],
 ) -> Result<Vec<AdapterWriteRespRow>, StorageEngineError> {
+    let shard_name = active_segment.shard_name.as_str();
     let messages = records
         .iter()
         .map(serialize)
         .collect::<Result<Vec<_>, _>>()?;
-    client_connection_manager
-        .send_write(target_broker_id, shard_name, messages)
-        .await
+    let body = WriteReqBody::new(shard_name.to_string(), messages)
+        .with_fencing(active_segment.segment_seq, active_segment.leader_epoch);
+    let result = client_connection_manager
+        .send_write_body(active_segment.leader, body)
+        .await;
+
+    // The leader has seen a newer segment or leader epoch than this node.
+    if let Err(StorageEngineError::RemoteFencedLeaderEpoch(_)) = &result {
+        cache_manager.mark_reconcile_needed(shard_name, active_segment.segment_seq, 1);
+    }
+    result
 }
 
 async fn write_memory_to_local(
@@ -225,6 +279,7 @@ mod tests {
     use crate::isr::follower::update_follower_progress;
     use bytes::Bytes;
     use grpc_clients::pool::ClientPool;
+    use metadata_struct::storage::segment::SegmentStatus;
     use std::time::Duration;
     use tokio::sync::broadcast;
 
@@ -332,4 +387,39 @@ async fn acks_all_times_out_when_follower_lags() {
             .unwrap();
         assert!(err.is_err());
     }
+
+    #[tokio::test]
+    async fn write_fencing_rejects_stale_and_unknown_tokens() {
+        let e = env().await;
+        let mut seg = e.cache_manager.get_active_segment(&e.shard).unwrap();
+        seg.leader_epoch = 2;
+        e.cache_manager.set_segment(&seg);
+
+        assert!(check_write_fencing(&e.cache_manager, &e.shard, 0, 2).is_ok());
+        assert!(matches!(
+            check_write_fencing(&e.cache_manager, &e.shard, 0, 1),
+            Err(StorageEngineError::FencedLeaderEpoch(..))
+        ));
+        assert!(matches!(
+            check_write_fencing(&e.cache_manager, &e.shard, 1, 0),
+            Err(StorageEngineError::UnknownLeaderEpoch(..))
+        ));
+        assert!(e
+            .cache_manager
+            .take_reconcile_needed()
+            .contains(&(e.shard.clone(), 0)));
+    }
+
+    #[tokio::test]
+    async fn write_to_sealed_segment_is_rejected() {
+        let e = env().await;
+        let mut seg = e.cache_manager.get_active_segment(&e.shard).unwrap();
+        seg.status = SegmentStatus::SealUp;
+        e.cache_manager.set_segment(&seg);
+
+        assert!(matches!(
+            write(&e, 1).await,
+            Err(StorageEngineError::SegmentStatusError(..))
+        ));
+    }
 }
@@ -17,6 +17,7 @@
 use crate::commitlog::rocksdb::engine::RocksDBStorageEngine;
 use crate::core::cache::StorageCacheManager;
 use crate::core::error::get_journal_server_code;
+use crate::core::write::check_write_fencing;
 use crate::filesegment::write_manager::WriteManager;
 use crate::handler::data::{delete_data_req, read_data_req, shard_offset_req, write_data_req};
 use crate::isr::handle_epoch::handle_offsets_for_leader_epoch;
@@ -96,30 +97,32 @@ async fn apply(
                 let current_leader_epoch = request.body.current_leader_epoch;
                 let messages = request.body.messages;
 
-                if current_leader_epoch > 0 {
-                    if let Some(seg) = self
+                // Requests without a segment only carry a leader epoch, which is
+                // checked against the active segment.
+                let fencing_segment_seq = match request.body.current_segment_seq {
+                    Some(segment_seq) => Some(segment_seq),
+                    None if current_leader_epoch > 0 => self
                         .cache_manager
                         .get_active_segment(&request.body.shard_name)
-                    {
-                        if seg.leader_epoch != current_leader_epoch {
-                            let resp = protocol::storage::protocol::WriteResp {
-                                header: protocol::storage::protocol::RespHeader::with_error(
-                                    ApiKey::Write,
-                                    StorageEngineNetworkError {
-                                        code: "FencedLeaderEpoch".to_string(),
-                                        error: format!(
-                                            "FencedLeaderEpoch: request epoch {} != leader epoch {}",
-                                            current_leader_epoch, seg.leader_epoch
-                                        ),
-                                    },
-                                ),
-                                body: WriteRespBody::default(),
-                            };
-                            return Some(ResponsePackage::new(
-                                tcp_connection.connection_id,
-                                RobustMQPacket::StorageEngine(StorageEnginePacket::WriteResp(resp)),
-                            ));
-                        }
+                        .map(|seg| seg.segment_seq),
+                    None => None,
+                };
+
+                if let Some(segment_seq) = fencing_segment_seq {
+                    if let Err(e) = check_write_fencing(
+                        &self.cache_manager,
+                        &request.body.shard_name,
+                        segment_seq,
+                        current_leader_epoch,
+                    ) {
+                        let resp = WriteResp::with_error(StorageEngineNetworkError {
+                            code: get_journal_server_code(&e),
+                            error: e.to_string(),
+                        });
+                        return Some(ResponsePackage::new(
+                            tcp_connection.connection_id,
+                            RobustMQPacket::StorageEngine(StorageEnginePacket::WriteResp(resp)),
+                        ));
                     }
                 }
 
@@ -0,0 +1,274 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! In-process mini cluster for integration tests.
+//!
+//! [`MiniCluster::start`] boots a single node with the meta, broker and engine
+//! roles on free ports and a temp data dir, waits until its listeners are up,
+//! and tears it down again on drop. The broker configuration is process-global,
+//! so only one cluster can be started per process: tests that share a process
+//! use [`shared_cluster`], which is stopped when the process exits. Under
+//! `cargo nextest` every test runs in its own process and gets its own cluster.
+
+mod port;
+
+use broker_server::{request_shutdown, BrokerServer};
+use common_base::port::is_local_port_listening;
+use common_base::role::{ROLE_BROKER, ROLE_ENGINE, ROLE_META};
+use common_base::tools::now_nanos;
+use common_config::broker::init_broker_conf_by_config;
+use common_config::config::BrokerConfig;
+use port::free_port;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+const READY_TIMEOUT: Duration = Duration::from_secs(120);
+const STOP_TIMEOUT: Duration = Duration::from_secs(30);
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+pub struct MiniCluster {
+    config: &'static BrokerConfig,
+    data_dir: PathBuf,
+    server: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl MiniCluster {
+    /// Boot the cluster and wait until it accepts connections. Fails if a
+    /// cluster was already started in this process.
+    pub fn start() -> Result<MiniCluster, String> {
+        if STARTED.swap(true, Ordering::SeqCst) {
+            return Err(
+                "a mini cluster was already started in this process, use shared_cluster()"
+                    .to_string(),
+            );
+        }
+
+        let data_dir = std::env::temp_dir().join(format!(
+            "robustmq-testkit-{}-{}",
+            std::process::id(),
+            now_nanos()
+        ));
+        std::fs::create_dir_all(&data_dir)
+            .map_err(|e| format!("failed to create {}: {}", data_dir.display(), e))?;
+
+        let config = init_broker_conf_by_config(build_config(&data_dir)?);
+        let server = thread::Builder::new()
+            .name("testkit-broker".to_string())
+            .spawn(|| BrokerServer::new().run())
+            .map_err(|e| format!("failed to spawn the broker thread: {}", e))?;
+
+        let cluster = MiniCluster {
+            config,
+            data_dir,
+            server: Mutex::new(Some(server)),
+        };
+        cluster.wait_ready()?;
+        Ok(cluster)
+    }
+
+    pub fn config(&self) -> &'static BrokerConfig {
+        self.config
+    }
+
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// Admin HTTP API base url, e.g. `http://127.0.0.1:40123`.
+    pub fn admin_url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.config.http_port)
+    }
+
+    pub fn grpc_addr(&self) -> String {
+        format!("127.0.0.1:{}", self.config.grpc_port)
+    }
+
+    pub fn mqtt_addr(&self) -> String {
+        format!("tcp://127.0.0.1:{}", self.config.mqtt_server.tcp_port)
+    }
+
+    pub fn mqtt_ssl_addr(&self) -> String {
+        format!("mqtts://127.0.0.1:{}", self.config.mqtt_server.tls_port)
+    }
+
+    pub fn mqtt_ws_addr(&self) -> String {
+        format!("ws://127.0.0.1:{}", self.config.mqtt_server.websocket_port)
+    }
+
+    pub fn mqtt_wss_addr(&self) -> String {
+        format!(
+            "wss://127.0.0.1:{}",
+            self.config.mqtt_server.websockets_port
+        )
+    }
+
+    pub fn engine_addr(&self) -> String {
+        format!("127.0.0.1:{}", self.config.storage_runtime.tcp_port)
+    }
+
+    /// Stop the broker and remove its data dir. Safe to call more than once.
+    pub fn stop(&self) {
+        let Some(server) = self.server.lock().unwrap().take() else {
+            return;
+        };
+
+        request_shutdown();
+        let started = Instant::now();
+        while !server.is_finished() && started.elapsed() < STOP_TIMEOUT {
+            thread::sleep(Duration::from_millis(100));
+        }
+        if server.is_finished() {
+            let _ = server.join();
+        } else {
+            warn!(
+                "testkit broker did not stop within {:?}, leaving it running",
+                STOP_TIMEOUT
+            );
+        }
+
+        if let Err(e) = std::fs::remove_dir_all(&self.data_dir) {
+            warn!(
+                "failed to remove testkit data dir {}: {}",
+                self.data_dir.display(),
+                e
+            );
+        }
+    }
+
+    fn wait_ready(&self) -> Result<(), String> {
+        let ports = [
+            self.config.http_port,
+            self.config.grpc_port,
+            self.config.storage_runtime.tcp_port,
+            self.config.mqtt_server.tcp_port,
+            self.config.mqtt_server.tls_port,
+            self.config.mqtt_server.websocket_port,
+            self.config.mqtt_server.websockets_port,
+        ];
+        let started = Instant::now();
+        loop {
+            // The MQTT listeners are the last to start, after the default
+            // tenant and system user are created.
+            if ports.iter().all(|port| is_local_port_listening(*port)) {
+                return Ok(());
+            }
+            let exited = self
+                .server
+                .lock()
+                .unwrap()
+                .as_ref()
+                .is_none_or(|server| server.is_finished());
+            if exited {
+                return Err("the broker exited during startup".to_string());
+            }
+            if started.elapsed() > READY_TIMEOUT {
+                return Err(format!(
+                    "the broker was not ready within {:?}",
+                    READY_TIMEOUT
+                ));
+            }
+            thread::sleep(Duration::from_millis(200));
+        }
+    }
+}
+
+impl Drop for MiniCluster {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+static SHARED: OnceLock<MiniCluster> = OnceLock::new();
+
+extern "C" fn stop_shared_cluster() {
+    if let Some(cluster) = SHARED.get() {
+        cluster.stop();
+    }
+}
+
+/// The cluster shared by every test of the process, started on first use and
+/// stopped when the process exits.
+pub fn shared_cluster() -> &'static MiniCluster {
+    SHARED.get_or_init(|| {
+        let cluster =
+            MiniCluster::start().unwrap_or_else(|e| panic!("failed to start mini cluster: {e}"));
+        unsafe {
+            libc::atexit(stop_shared_cluster);
+        }
+        cluster
+    })
+}
+
+fn build_config(data_dir: &Path) -> Result<BrokerConfig, String> {
+    let mut config = BrokerConfig {
+        cluster_name: format!("testkit-{}", now_nanos()),
+        broker_id: 1,
+        broker_ip: Some("127.0.0.1".to_string()),
+        roles: vec![
+            ROLE_META.to_string(),
+            ROLE_BROKER.to_string(),
+            ROLE_ENGINE.to_string(),
+        ],
+        grpc_port: free_port()?,
+        http_port: free_port()?,
+        data_path: path_str(&data_dir.join("broker")),
+        ..Default::default()
+    };
+    config.meta_addrs.clear();
+    config.meta_addrs.insert(
+        config.broker_id.to_string(),
+        format!("127.0.0.1:{}", config.grpc_port).into(),
+    );
+    config.log.log_path = path_str(&data_dir.join("logs"));
+
+    config.storage_runtime.tcp_port = free_port()?;
+    config.storage_runtime.data_path = vec![path_str(&data_dir.join("engine"))];
+
+    config.mqtt_server.tcp_port = free_port()?;
+    config.mqtt_server.tls_port = free_port()?;
+    config.mqtt_server.websocket_port = free_port()?;
+    config.mqtt_server.websockets_port = free_port()?;
+    config.mqtt_server.quic_port = free_port()?;
+    config.kafka_runtime.tcp_port = free_port()?;
+    config.amqp_runtime.tcp_port = free_port()?;
+    config.nats_runtime.tcp_port = free_port()?;
+    config.nats_runtime.tls_port = free_port()?;
+    config.nats_runtime.ws_port = free_port()?;
+    config.nats_runtime.wss_port = free_port()?;
+
+    // The default certificate paths are relative to the repository root.
+    let certs = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../config/certs");
+    config.runtime.tls_cert = path_str(&certs.join("cert.pem"));
+    config.runtime.tls_key = path_str(&certs.join("key.pem"));
+
+    // Keep the thread count of a test process reasonable.
+    config.runtime.server_worker_threads = 2;
+    config.runtime.meta_worker_threads = 2;
+    config.runtime.broker_worker_threads = 2;
+    config.runtime.storage_worker_threads = 2;
+    config.runtime.push_worker_threads = 2;
+    config.runtime.background_worker_threads = 2;
+
+    config.fault_injection.enable = true;
+    Ok(config)
+}
+
+fn path_str(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
@@ -0,0 +1,45 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+use std::net::{TcpListener, UdpSocket};
+use std::sync::Mutex;
+
+/// A port that is free for both TCP and UDP (QUIC) and was not handed out
+/// before in this process. The OS picks it, so it stays free unless another
+/// process binds it in between.
+pub fn free_port() -> Result<u32, String> {
+    static USED: Mutex<Option<HashSet<u16>>> = Mutex::new(None);
+
+    for _ in 0..100 {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .map_err(|e| format!("failed to bind a free port: {}", e))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| format!("failed to read the bound port: {}", e))?
+            .port();
+        if UdpSocket::bind(("127.0.0.1", port)).is_err() {
+            continue;
+        }
+        if USED
+            .lock()
+            .unwrap()
+            .get_or_insert_with(HashSet::new)
+            .insert(port)
+        {
+            return Ok(port as u32);
+        }
+    }
+    Err("no free port found".to_string())
+}
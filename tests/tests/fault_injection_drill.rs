@@ -0,0 +1,237 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Fault injection drill.
+//
+// Requires a running 3-node cluster (config/cluster/server-{1,2,3}.toml) with
+// `[fault_injection] enable = true` on every node. Marked `#[ignore]`; run with:
+//   cargo test -p robustmq-test fault_injection_drill -- --ignored --nocapture
+//
+// Scenarios:
+//   - storage error on a topic        → message send fails, recovers once removed
+//   - grpc client error on a method   → tenant create through the node fails
+//   - raft partition of the leader    → the other nodes elect a new leader
+// Faults are cleared on every node before the drill and after each scenario.
+
+#[cfg(test)]
+mod tests {
+    use common_base::uuid::unique_id;
+    use reqwest::Client;
+    use serde_json::{json, Value};
+    use std::time::{Duration, Instant};
+    use tokio::time::sleep;
+
+    const NODES: [u64; 3] = [1, 2, 3];
+    const METADATA_GROUP: &str = "metadata_0";
+
+    fn admin_url(node_id: u64) -> String {
+        match node_id {
+            1 => "http://127.0.0.1:58080",
+            2 => "http://127.0.0.1:58082",
+            3 => "http://127.0.0.1:58083",
+            other => panic!("unknown node id {other}"),
+        }
+        .to_string()
+    }
+
+    fn grpc_addr(node_id: u64) -> String {
+        match node_id {
+            1 => "127.0.0.1:1228",
+            2 => "127.0.0.1:2228",
+            3 => "127.0.0.1:3228",
+            other => panic!("unknown node id {other}"),
+        }
+        .to_string()
+    }
+
+    // POST an admin API, returning the response envelope.
+    async fn post(client: &Client, node_id: u64, path: &str, body: Value) -> Value {
+        client
+            .post(format!("{}/api{}", admin_url(node_id), path))
+            .json(&body)
+            .timeout(Duration::from_secs(30))
+            .send()
+            .await
+            .unwrap_or_else(|e| panic!("POST {path} on node{node_id} failed: {e}"))
+            .json::<Value>()
+            .await
+            .unwrap()
+    }
+
+    fn is_ok(resp: &Value) -> bool {
+        resp.get("code").and_then(|c| c.as_i64()) == Some(0)
+    }
+
+    async fn set_fault(client: &Client, node_id: u64, fault: Value) {
+        let resp = post(client, node_id, "/cluster/fault/set", fault).await;
+        assert!(
+            is_ok(&resp),
+            "set fault on node{node_id} failed (is [fault_injection] enabled?): {resp}"
+        );
+    }
+
+    async fn clear_faults(client: &Client) {
+        for n in NODES {
+            post(client, n, "/cluster/fault/remove", json!({})).await;
+        }
+    }
+
+    async fn send_message(client: &Client, node_id: u64, topic: &str) -> Value {
+        post(
+            client,
+            node_id,
+            "/cluster/message/send",
+            json!({ "tenant": "default", "topic": topic, "payload": "fault-drill" }),
+        )
+        .await
+    }
+
+    async fn meta_leader(client: &Client, node_id: u64) -> Option<u64> {
+        let v: Value = client
+            .get(format!("{}/api/info", admin_url(node_id)))
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await
+            .ok()?
+            .json()
+            .await
+            .ok()?;
+        v.get("data")?
+            .get("meta")?
+            .get(METADATA_GROUP)?
+            .get("current_leader")?
+            .as_u64()
+            .filter(|leader| *leader != 0)
+    }
+
+    async fn storage_error(client: &Client) {
+        let topic = format!("fault-drill-{}", unique_id());
+        assert!(is_ok(&send_message(client, 1, &topic).await));
+
+        set_fault(
+            client,
+            1,
+            json!({
+                "id": "storage-error",
+                "point": "storage",
+                "target": format!("default/{topic}"),
+                "action": "error"
+            }),
+        )
+        .await;
+        let resp = send_message(client, 1, &topic).await;
+        assert!(
+            !is_ok(&resp),
+            "send succeeded under a storage fault: {resp}"
+        );
+
+        clear_faults(client).await;
+        assert!(is_ok(&send_message(client, 1, &topic).await));
+        println!("storage error OK");
+    }
+
+    async fn grpc_client_error(client: &Client) {
+        set_fault(
+            client,
+            1,
+            json!({
+                "id": "create-tenant-error",
+                "point": "grpc_client",
+                "target": "PlacementService/CreateTenant@",
+                "action": "error"
+            }),
+        )
+        .await;
+        let tenant = format!("fault-drill-{}", unique_id());
+        let body = json!({ "tenant_name": tenant, "desc": "fault-drill" });
+        let resp = post(client, 1, "/cluster/tenant/create", body.clone()).await;
+        assert!(
+            !is_ok(&resp),
+            "tenant create succeeded under a grpc fault: {resp}"
+        );
+
+        clear_faults(client).await;
+        assert!(is_ok(
+            &post(client, 1, "/cluster/tenant/create", body).await
+        ));
+        println!("grpc client error OK");
+    }
+
+    async fn raft_leader_partition(client: &Client) {
+        let leader = meta_leader(client, 1).await.expect("no metadata leader");
+        // Cut the leader off in both directions: it cannot reach the others,
+        // and the others cannot reach it.
+        set_fault(
+            client,
+            leader,
+            json!({ "id": "raft-partition", "point": "raft_network", "action": "partition" }),
+        )
+        .await;
+        for n in NODES.into_iter().filter(|n| *n != leader) {
+            set_fault(
+                client,
+                n,
+                json!({
+                    "id": "raft-partition",
+                    "point": "raft_network",
+                    "target": grpc_addr(leader),
+                    "action": "partition"
+                }),
+            )
+            .await;
+        }
+
+        let survivor = NODES.into_iter().find(|n| *n != leader).unwrap();
+        let t0 = Instant::now();
+        let new_leader = loop {
+            if let Some(l) = meta_leader(client, survivor).await.filter(|l| *l != leader) {
+                break l;
+            }
+            if t0.elapsed() > Duration::from_secs(60) {
+                clear_faults(client).await;
+                panic!("no re-election within 60s after partitioning leader n{leader}");
+            }
+            sleep(Duration::from_secs(1)).await;
+        };
+        println!("raft partition: n{new_leader} elected after partitioning n{leader}");
+
+        clear_faults(client).await;
+        let t0 = Instant::now();
+        loop {
+            let mut leaders = Vec::new();
+            for n in NODES {
+                leaders.push(meta_leader(client, n).await);
+            }
+            if leaders.iter().all(|l| l.is_some() && *l == leaders[0]) {
+                break;
+            }
+            if t0.elapsed() > Duration::from_secs(60) {
+                panic!("cluster did not converge within 60s after healing: {leaders:?}");
+            }
+            sleep(Duration::from_secs(1)).await;
+        }
+        println!("raft partition OK");
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a running 3-node cluster with [fault_injection] enabled"]
+    async fn fault_injection_drill() {
+        let client = Client::new();
+        clear_faults(&client).await;
+
+        storage_error(&client).await;
+        grpc_client_error(&client).await;
+        raft_leader_partition(&client).await;
+    }
+}
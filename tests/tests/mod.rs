@@ -14,6 +14,7 @@
 
 pub mod common;
 pub mod engine;
+pub mod fault_injection_drill;
 pub mod group_gc;
 pub mod mcp_test;
 pub mod mqtt;
@@ -162,6 +162,7 @@ fn create_test_acl(
             ip: None,
             action: action.to_string(),
             permission: permission.to_string(),
+            priority: None,
         }
     }
 
@@ -265,6 +266,8 @@ async fn create_user(admin_client: &AdminHttpClient, username: String, password:
             tenant: "default".to_string(),
             username,
             password,
+            salt: None,
+            hash_algorithm: None,
             is_superuser: false,
         };
         let res = admin_client.create_user(&user).await;
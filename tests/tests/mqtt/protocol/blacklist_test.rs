@@ -449,6 +449,8 @@ async fn create_user(admin_client: &AdminHttpClient, username: &str, password: &
             tenant: "default".to_string(),
             username: username.to_owned(),
             password: password.to_owned(),
+            salt: None,
+            hash_algorithm: None,
             is_superuser: false,
         };
         let res = admin_client.create_user(&user).await;
@@ -27,6 +27,7 @@
     DisconnectOptionsBuilder, Message, Properties, PropertyCode, ReasonCode, SslOptionsBuilder,
     SubscribeOptions,
 };
+use testkit::{shared_cluster, MiniCluster};
 use tokio::time::sleep;
 
 pub fn qos_list() -> Vec<i32> {
@@ -37,8 +38,23 @@ pub fn protocol_versions() -> Vec<u32> {
     vec![3, 4, 5]
 }
 
+/// Set `ROBUSTMQ_TESTKIT=1` to run against an in-process mini cluster instead
+/// of a broker started beforehand.
+fn testkit_cluster() -> Option<&'static MiniCluster> {
+    std::env::var("ROBUSTMQ_TESTKIT")
+        .is_ok_and(|v| v == "1" || v == "true")
+        .then(shared_cluster)
+}
+
+pub fn admin_base_url() -> String {
+    match testkit_cluster() {
+        Some(cluster) => cluster.admin_url(),
+        None => "http://127.0.0.1:58080".to_string(),
+    }
+}
+
 pub async fn create_test_env() -> AdminHttpClient {
-    AdminHttpClient::new("http://127.0.0.1:58080")
+    AdminHttpClient::new(admin_base_url())
 }
 
 pub async fn session_list_by_admin(client_id: &str) -> PageReplyData<Vec<SessionListRow>> {
@@ -345,23 +361,47 @@ pub fn build_client_id(_name: &str) -> String {
 }
 
 pub fn broker_addr() -> String {
-    "tcp://localhost:1883".to_string()
+    match testkit_cluster() {
+        Some(cluster) => cluster.mqtt_addr(),
+        None => "tcp://localhost:1883".to_string(),
+    }
+}
+
+/// `host:port` of the MQTT TCP listener, for tests that speak the protocol
+/// over a raw socket.
+pub fn broker_socket_addr() -> String {
+    match testkit_cluster() {
+        Some(cluster) => format!("127.0.0.1:{}", cluster.config().mqtt_server.tcp_port),
+        None => "127.0.0.1:1883".to_string(),
+    }
 }
 
 pub fn broker_ssl_addr() -> String {
-    "mqtts://localhost:1885".to_string()
+    match testkit_cluster() {
+        Some(cluster) => cluster.mqtt_ssl_addr(),
+        None => "mqtts://localhost:1885".to_string(),
+    }
 }
 
 pub fn broker_ws_addr() -> String {
-    "ws://localhost:8083".to_string()
+    match testkit_cluster() {
+        Some(cluster) => cluster.mqtt_ws_addr(),
+        None => "ws://localhost:8083".to_string(),
+    }
 }
 
 pub fn broker_wss_addr() -> String {
-    "wss://localhost:8085".to_string()
+    match testkit_cluster() {
+        Some(cluster) => cluster.mqtt_wss_addr(),
+        None => "wss://localhost:8085".to_string(),
+    }
 }
 
 pub fn broker_grpc_addr() -> String {
-    "localhost:1228".to_string()
+    match testkit_cluster() {
+        Some(cluster) => cluster.grpc_addr(),
+        None => "localhost:1228".to_string(),
+    }
 }
 
 pub fn username() -> String {
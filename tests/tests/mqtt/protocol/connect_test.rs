@@ -22,7 +22,6 @@ mod tests {
         },
         ClientTestProperties,
     };
-    use mqtt_broker::core::connection::REQUEST_RESPONSE_PREFIX_NAME;
     use paho_mqtt::{Client, PropertyCode, ReasonCode};
     #[tokio::test]
     async fn response_properties_check_test() {
@@ -198,7 +197,7 @@ async fn request_response_test() {
                     .unwrap()
                     .get_string()
                     .unwrap(),
-                REQUEST_RESPONSE_PREFIX_NAME.to_string()
+                format!("$rpc/{}", client_properties.client_id)
             );
 
             distinct_conn(cli);
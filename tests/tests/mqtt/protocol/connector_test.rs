@@ -81,6 +81,7 @@ async fn file_connector_test() {
                 ..Default::default()
             },
             tenant: DEFAULT_TENANT.to_string(),
+            max_records_per_sec: None,
         };
 
         let res = admin_client.create_connector(&connector).await;
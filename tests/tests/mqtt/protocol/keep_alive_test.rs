@@ -14,11 +14,11 @@
 
 #[cfg(test)]
 mod tests {
-    use crate::mqtt::protocol::common::{build_client_id, password};
+    use crate::mqtt::protocol::common::{broker_socket_addr, build_client_id, password};
     use bytes::Bytes;
     use common_base::tools::now_second;
     use futures::{SinkExt, StreamExt};
-    use protocol::mqtt::common::{Connect, LastWill, Login, MqttPacket};
+    use protocol::mqtt::common::{Connect, DisconnectReasonCode, LastWill, Login, MqttPacket};
     use protocol::mqtt::mqttv5::codec::Mqtt5Codec;
     use std::time::Duration;
     use tokio::net::TcpStream;
@@ -28,7 +28,7 @@ mod tests {
 
     #[tokio::test]
     async fn keep_alive_test() {
-        let socket = TcpStream::connect("127.0.0.1:1883")
+        let socket = TcpStream::connect(broker_socket_addr())
             .timeout(Duration::from_secs(3))
             .await
             .unwrap()
@@ -40,7 +40,7 @@ async fn keep_alive_test() {
         stream.send(packet).await.unwrap();
 
         let start = Instant::now();
-        // Expect broker to disconnect within ~keep_alive * default_timeout seconds.
+        // Expect broker to disconnect within ~keep_alive * timeout_multiplier seconds.
         let wait_res = timeout(Duration::from_secs(15), async {
             loop {
                 let Some(data) = stream.next().await else {
@@ -51,7 +51,11 @@ async fn keep_alive_test() {
                 match data {
                     Ok(pkt) => {
                         // Keep the log line lightweight; it helps diagnosing flaky CI.
-                        if matches!(pkt, MqttPacket::Disconnect(_, _)) {
+                        if let MqttPacket::Disconnect(disconnect, _) = pkt {
+                            assert_eq!(
+                                disconnect.reason_code,
+                                Some(DisconnectReasonCode::KeepAliveTimeout)
+                            );
                             return Ok(());
                         }
                     }
@@ -79,7 +83,7 @@ async fn keep_alive_test() {
         }
 
         let ts = start.elapsed().as_secs();
-        assert!((9..=11).contains(&ts));
+        assert!((7..=9).contains(&ts));
     }
 
     /// Build the CONNECT packet for MQTT 5.0 keep-alive test.
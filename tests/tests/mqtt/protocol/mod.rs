@@ -48,10 +48,12 @@ pub struct ClientTestProperties {
 pub mod payload_format_test;
 pub mod properties_test;
 pub mod protocol_version_test;
+pub mod qos2_receive_test;
 pub mod qos_test;
 pub mod req_resp_test;
 pub mod request_problem_info_test;
 pub mod retain_message_test;
+pub mod robustmq_client_test;
 pub mod schema_test;
 pub mod session_expire_test;
 pub mod share_sub_test;
@@ -14,7 +14,7 @@
 
 #[cfg(test)]
 mod tests {
-    use crate::mqtt::protocol::common::{build_client_id, password};
+    use crate::mqtt::protocol::common::{broker_socket_addr, build_client_id, password};
     use bytes::Bytes;
     use common_base::tools::now_second;
     use futures::{SinkExt, StreamExt};
@@ -29,7 +29,7 @@ mod tests {
 
     #[tokio::test]
     async fn mqtt34_properties_test() {
-        let socket = TcpStream::connect("127.0.0.1:1883")
+        let socket = TcpStream::connect(broker_socket_addr())
             .timeout(Duration::from_secs(3))
             .await
             .unwrap()
@@ -83,7 +83,7 @@ async fn mqtt34_properties_test() {
 
     #[tokio::test]
     async fn mqtt5_properties_test() {
-        let socket = TcpStream::connect("127.0.0.1:1883")
+        let socket = TcpStream::connect(broker_socket_addr())
             .timeout(Duration::from_secs(3))
             .await
             .unwrap()
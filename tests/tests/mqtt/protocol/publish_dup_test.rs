@@ -14,7 +14,7 @@
 
 #[cfg(test)]
 mod tests {
-    use crate::mqtt::protocol::common::{build_client_id, password};
+    use crate::mqtt::protocol::common::{broker_socket_addr, build_client_id, password};
     use bytes::Bytes;
     use common_base::uuid::unique_id;
     use futures::{SinkExt, StreamExt};
@@ -29,7 +29,7 @@ mod tests {
 
     #[tokio::test]
     async fn publish_qos1_dup_test() {
-        let socket = TcpStream::connect("127.0.0.1:1883")
+        let socket = TcpStream::connect(broker_socket_addr())
             .timeout(Duration::from_secs(3))
             .await
             .unwrap()
@@ -83,7 +83,7 @@ async fn publish_qos1_dup_test() {
 
     #[tokio::test]
     async fn publish_qos2_publish_dup_test() {
-        let socket = TcpStream::connect("127.0.0.1:1883")
+        let socket = TcpStream::connect(broker_socket_addr())
             .timeout(Duration::from_secs(3))
             .await
             .unwrap()
@@ -138,7 +138,7 @@ async fn publish_qos2_publish_dup_test() {
 
     #[tokio::test]
     async fn publish_qos2_pub_rel_dup_test() {
-        let socket = TcpStream::connect("127.0.0.1:1883")
+        let socket = TcpStream::connect(broker_socket_addr())
             .timeout(Duration::from_secs(3))
             .await
             .unwrap()
@@ -0,0 +1,273 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+mod tests {
+    use crate::mqtt::protocol::common::{broker_socket_addr, password, uniq_topic, username};
+    use bytes::Bytes;
+    use common_base::uuid::unique_id;
+    use futures::{SinkExt, StreamExt};
+    use protocol::mqtt::common::{
+        Connect, ConnectProperties, Disconnect, DisconnectReasonCode, Filter, Login, MqttPacket,
+        PubCompReason, PubRel, PubRelReason, Publish, QoS, Subscribe,
+    };
+    use protocol::mqtt::mqttv5::codec::Mqtt5Codec;
+    use std::time::Duration;
+    use tokio::net::TcpStream;
+    use tokio::time::timeout;
+    use tokio_util::codec::Framed;
+
+    type Stream = Framed<TcpStream, Mqtt5Codec>;
+
+    // Each test walks one order in which a QoS 2 publisher's packets can reach
+    // the broker and checks both the acks and how often subscribers get the
+    // message.
+
+    #[tokio::test]
+    async fn qos2_publish_and_pubrel_resent_test() {
+        let topic = uniq_topic();
+        let mut sub = subscriber(&topic).await;
+        let mut publisher = connect(&unique_id(), true).await;
+
+        publish(&mut publisher, &topic, 1, "m1").await;
+        expect_pub_rec(&mut publisher, 1).await;
+        // PUBREC lost, the client re-sends PUBLISH
+        publish(&mut publisher, &topic, 1, "m1").await;
+        expect_pub_rec(&mut publisher, 1).await;
+
+        pub_rel(&mut publisher, 1).await;
+        assert_eq!(
+            expect_pub_comp(&mut publisher, 1).await,
+            PubCompReason::Success
+        );
+        // PUBCOMP lost, the client re-sends PUBREL
+        pub_rel(&mut publisher, 1).await;
+        assert_eq!(
+            expect_pub_comp(&mut publisher, 1).await,
+            PubCompReason::Success
+        );
+
+        assert_eq!(count_publishes(&mut sub).await, 1);
+    }
+
+    #[tokio::test]
+    async fn qos2_pubrel_without_publish_test() {
+        let mut publisher = connect(&unique_id(), true).await;
+        pub_rel(&mut publisher, 7).await;
+        assert_eq!(
+            expect_pub_comp(&mut publisher, 7).await,
+            PubCompReason::PacketIdentifierNotFound
+        );
+    }
+
+    #[tokio::test]
+    async fn qos2_pkid_reused_after_release_test() {
+        let topic = uniq_topic();
+        let mut sub = subscriber(&topic).await;
+        let mut publisher = connect(&unique_id(), true).await;
+
+        for payload in ["m1", "m2"] {
+            publish(&mut publisher, &topic, 1, payload).await;
+            expect_pub_rec(&mut publisher, 1).await;
+            pub_rel(&mut publisher, 1).await;
+            assert_eq!(
+                expect_pub_comp(&mut publisher, 1).await,
+                PubCompReason::Success
+            );
+        }
+
+        assert_eq!(count_publishes(&mut sub).await, 2);
+    }
+
+    #[tokio::test]
+    async fn qos2_pubrel_after_reconnect_test() {
+        let topic = uniq_topic();
+        let client_id = unique_id();
+        let mut sub = subscriber(&topic).await;
+
+        let mut publisher = connect(&client_id, false).await;
+        publish(&mut publisher, &topic, 1, "m1").await;
+        expect_pub_rec(&mut publisher, 1).await;
+        disconnect(publisher).await;
+
+        // the session resumes with the packet id still unreleased
+        let mut publisher = connect(&client_id, false).await;
+        publish(&mut publisher, &topic, 1, "m1").await;
+        expect_pub_rec(&mut publisher, 1).await;
+        pub_rel(&mut publisher, 1).await;
+        assert_eq!(
+            expect_pub_comp(&mut publisher, 1).await,
+            PubCompReason::Success
+        );
+
+        assert_eq!(count_publishes(&mut sub).await, 1);
+    }
+
+    #[tokio::test]
+    async fn qos2_clean_start_drops_unreleased_test() {
+        let topic = uniq_topic();
+        let client_id = unique_id();
+        let mut sub = subscriber(&topic).await;
+
+        let mut publisher = connect(&client_id, false).await;
+        publish(&mut publisher, &topic, 1, "m1").await;
+        expect_pub_rec(&mut publisher, 1).await;
+        disconnect(publisher).await;
+
+        let mut publisher = connect(&client_id, true).await;
+        pub_rel(&mut publisher, 1).await;
+        assert_eq!(
+            expect_pub_comp(&mut publisher, 1).await,
+            PubCompReason::PacketIdentifierNotFound
+        );
+
+        assert_eq!(count_publishes(&mut sub).await, 0);
+    }
+
+    async fn connect(client_id: &str, clean_start: bool) -> Stream {
+        let socket = timeout(
+            Duration::from_secs(3),
+            TcpStream::connect(broker_socket_addr()),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        let mut stream = Framed::new(socket, Mqtt5Codec::new());
+
+        let connect = Connect {
+            keep_alive: 30,
+            client_id: client_id.to_string(),
+            clean_session: clean_start,
+        };
+        let properties = ConnectProperties {
+            session_expiry_interval: Some(60),
+            ..Default::default()
+        };
+        let login = Login {
+            username: username(),
+            password: password(),
+        };
+        stream
+            .send(MqttPacket::Connect(
+                5,
+                connect,
+                Some(properties),
+                None,
+                None,
+                Some(login),
+            ))
+            .await
+            .unwrap();
+        match next_packet(&mut stream).await {
+            MqttPacket::ConnAck(_, _) => stream,
+            packet => panic!("expected CONNACK, got {:?}", packet),
+        }
+    }
+
+    async fn disconnect(mut stream: Stream) {
+        stream
+            .send(MqttPacket::Disconnect(
+                Disconnect {
+                    reason_code: Some(DisconnectReasonCode::NormalDisconnection),
+                },
+                None,
+            ))
+            .await
+            .unwrap();
+        // let the broker finish the disconnect before the session reconnects
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+
+    async fn subscriber(topic: &str) -> Stream {
+        let mut stream = connect(&unique_id(), true).await;
+        let subscribe = Subscribe {
+            packet_identifier: 1,
+            filters: vec![Filter {
+                path: topic.to_string(),
+                qos: QoS::AtMostOnce,
+                ..Default::default()
+            }],
+        };
+        stream
+            .send(MqttPacket::Subscribe(subscribe, None))
+            .await
+            .unwrap();
+        match next_packet(&mut stream).await {
+            MqttPacket::SubAck(_, _) => stream,
+            packet => panic!("expected SUBACK, got {:?}", packet),
+        }
+    }
+
+    async fn publish(stream: &mut Stream, topic: &str, pkid: u16, payload: &str) {
+        let publish = Publish {
+            dup: false,
+            qos: QoS::ExactlyOnce,
+            p_kid: pkid,
+            retain: false,
+            topic: Bytes::from(topic.to_string()),
+            payload: Bytes::from(payload.to_string()),
+        };
+        stream
+            .send(MqttPacket::Publish(publish, None))
+            .await
+            .unwrap();
+    }
+
+    async fn pub_rel(stream: &mut Stream, pkid: u16) {
+        let pub_rel = PubRel {
+            pkid,
+            reason: Some(PubRelReason::Success),
+        };
+        stream
+            .send(MqttPacket::PubRel(pub_rel, None))
+            .await
+            .unwrap();
+    }
+
+    async fn expect_pub_rec(stream: &mut Stream, pkid: u16) {
+        match next_packet(stream).await {
+            MqttPacket::PubRec(rec, _) => assert_eq!(rec.pkid, pkid),
+            packet => panic!("expected PUBREC, got {:?}", packet),
+        }
+    }
+
+    async fn expect_pub_comp(stream: &mut Stream, pkid: u16) -> PubCompReason {
+        match next_packet(stream).await {
+            MqttPacket::PubComp(comp, _) => {
+                assert_eq!(comp.pkid, pkid);
+                comp.reason.unwrap_or(PubCompReason::Success)
+            }
+            packet => panic!("expected PUBCOMP, got {:?}", packet),
+        }
+    }
+
+    async fn next_packet(stream: &mut Stream) -> MqttPacket {
+        timeout(Duration::from_secs(10), stream.next())
+            .await
+            .expect("timed out waiting for a packet")
+            .expect("connection closed")
+            .unwrap()
+    }
+
+    /// Publishes delivered to a subscriber until it has been idle for a while.
+    async fn count_publishes(stream: &mut Stream) -> usize {
+        let mut count = 0;
+        while let Ok(Some(packet)) = timeout(Duration::from_secs(3), stream.next()).await {
+            if let Ok(MqttPacket::Publish(_, _)) = packet {
+                count += 1;
+            }
+        }
+        count
+    }
+}
@@ -23,7 +23,6 @@ mod tests {
         ClientTestProperties,
     };
     use common_base::uuid::unique_id;
-    use mqtt_broker::core::connection::REQUEST_RESPONSE_PREFIX_NAME;
     use paho_mqtt::{
         Client, ConnectOptionsBuilder, CreateOptionsBuilder, Message, MessageBuilder, Properties,
         PropertyCode,
@@ -34,9 +33,10 @@ async fn connect_request_response_information_test() {
         let addr = broker_addr_by_type("tcp");
 
         // RequestResponseInformation = 1
+        let client_id = unique_id();
         let create_opts = CreateOptionsBuilder::new()
             .server_uri(addr.clone())
-            .client_id(unique_id())
+            .client_id(client_id.clone())
             .finalize();
         let cli = Client::new(create_opts).unwrap();
         let mut conn_opts = ConnectOptionsBuilder::new_v5();
@@ -56,7 +56,7 @@ async fn connect_request_response_information_test() {
         let data = properties
             .get_string(PropertyCode::ResponseInformation)
             .unwrap();
-        assert_eq!(data, REQUEST_RESPONSE_PREFIX_NAME);
+        assert_eq!(data, format!("$rpc/{}", client_id));
 
         // RequestResponseInformation = 0
         let create_opts = CreateOptionsBuilder::new()
@@ -157,11 +157,12 @@ async fn client5_request_response_test() {
         let conn_opts = build_conn_pros(client_properties.clone(), client_properties.err_pwd);
         let result = cli.connect(conn_opts).unwrap();
 
-        // get response topic prefix
-        let response_topic_prefix = result
+        // the response topic namespace of this client
+        let response_information = result
             .properties()
             .get_string(PropertyCode::ResponseInformation)
             .unwrap();
+        assert_eq!(response_information, format!("$rpc/{}", client_id));
 
         let call_fn = |msg: Message| {
             let payload = String::from_utf8(msg.payload().to_vec()).unwrap();
@@ -195,7 +196,7 @@ async fn client5_request_response_test() {
             };
             let cli = connect_server(&client_properties);
             let msg = MessageBuilder::new()
-                .topic(format!("{}{}", response_topic_prefix, topic))
+                .topic(topic)
                 .payload(c_data_1)
                 .qos(qos)
                 .finalize();
@@ -234,13 +235,7 @@ async fn client5_request_response_test() {
             payload == correlation_data
         };
 
-        subscribe_data_by_qos(
-            &cli,
-            &format!("{}{}", response_topic_prefix, response_topic),
-            qos,
-            call_fn,
-        )
-        .unwrap();
+        subscribe_data_by_qos(&cli, &response_topic, qos, call_fn).unwrap();
         distinct_conn(cli);
     }
 }
@@ -14,7 +14,7 @@
 
 #[cfg(test)]
 mod tests {
-    use crate::mqtt::protocol::common::{build_client_id, err_password};
+    use crate::mqtt::protocol::common::{broker_socket_addr, build_client_id, err_password};
     use bytes::Bytes;
     use common_base::tools::now_second;
     use futures::{SinkExt, StreamExt};
@@ -28,7 +28,7 @@ mod tests {
 
     #[tokio::test]
     async fn mqtt5_problem_info_1_test() {
-        let socket = TcpStream::connect("127.0.0.1:1883")
+        let socket = TcpStream::connect(broker_socket_addr())
             .timeout(Duration::from_secs(3))
             .await
             .unwrap()
@@ -85,7 +85,7 @@ async fn mqtt5_problem_info_1_test() {
 
     #[tokio::test]
     async fn mqtt5_problem_info_0_test() {
-        let socket = TcpStream::connect("127.0.0.1:1883")
+        let socket = TcpStream::connect(broker_socket_addr())
             .timeout(Duration::from_secs(3))
             .await
             .unwrap()
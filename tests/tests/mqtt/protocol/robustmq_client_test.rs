@@ -0,0 +1,111 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::mqtt::protocol::common::{
+        admin_base_url, broker_socket_addr, build_client_id, uniq_topic,
+    };
+    use robustmq_client::{
+        AdminClient, ConnectOptions, Message, MqttClient, QoS, ReplayStart, ReplaySubscription,
+    };
+    use tokio::time::timeout;
+
+    async fn connect(name: &str) -> MqttClient {
+        let options = ConnectOptions::new(broker_socket_addr(), build_client_id(name));
+        MqttClient::connect(options).await.unwrap()
+    }
+
+    async fn recv(client: &mut MqttClient, wait: Duration) -> Message {
+        timeout(wait, client.recv())
+            .await
+            .expect("timed out waiting for message")
+            .expect("connection closed")
+    }
+
+    #[tokio::test]
+    async fn publish_subscribe_all_qos_test() {
+        let topic = uniq_topic();
+        let mut sub = connect("robustmq_client_sub").await;
+        let publisher = connect("robustmq_client_pub").await;
+
+        for qos in [QoS::AtMostOnce, QoS::AtLeastOnce, QoS::ExactlyOnce] {
+            assert_eq!(sub.subscribe(&topic, qos).await.unwrap(), qos);
+            let payload = format!("message {qos:?}");
+            publisher
+                .publish(&topic, payload.clone(), qos, false)
+                .await
+                .unwrap();
+
+            let message = recv(&mut sub, Duration::from_secs(10)).await;
+            assert_eq!(message.topic, topic);
+            assert_eq!(message.payload, payload.as_bytes());
+            sub.unsubscribe(&topic).await.unwrap();
+        }
+
+        publisher.disconnect().await.unwrap();
+        sub.disconnect().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn publish_delayed_test() {
+        let topic = uniq_topic();
+        let mut sub = connect("robustmq_client_delay_sub").await;
+        sub.subscribe(&topic, QoS::AtLeastOnce).await.unwrap();
+
+        let publisher = connect("robustmq_client_delay_pub").await;
+        publisher
+            .publish_delayed(&topic, Duration::from_secs(3), "later", QoS::AtLeastOnce)
+            .await
+            .unwrap();
+
+        assert!(timeout(Duration::from_secs(1), sub.recv()).await.is_err());
+        let message = recv(&mut sub, Duration::from_secs(15)).await;
+        assert_eq!(message.topic, topic);
+        assert_eq!(message.payload, "later".as_bytes());
+
+        publisher.disconnect().await.unwrap();
+        sub.disconnect().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn replay_subscribe_test() {
+        let topic = uniq_topic();
+        let publisher = connect("robustmq_client_replay_pub").await;
+        for i in 0..3 {
+            publisher
+                .publish(&topic, format!("history {i}"), QoS::AtLeastOnce, false)
+                .await
+                .unwrap();
+        }
+
+        let mut sub = connect("robustmq_client_replay_sub").await;
+        let admin = AdminClient::new(admin_base_url()).unwrap();
+        let subscription = ReplaySubscription::new("default", &topic, ReplayStart::Offset(0));
+        sub.replay_subscribe(&admin, &subscription).await.unwrap();
+
+        let mut received = Vec::new();
+        while received.len() < 3 {
+            let message = recv(&mut sub, Duration::from_secs(15)).await;
+            received.push(String::from_utf8(message.payload.to_vec()).unwrap());
+        }
+        received.sort();
+        assert_eq!(received, ["history 0", "history 1", "history 2"]);
+
+        publisher.disconnect().await.unwrap();
+        sub.disconnect().await.unwrap();
+    }
+}
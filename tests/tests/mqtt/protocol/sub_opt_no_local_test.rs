@@ -106,4 +106,36 @@ async fn no_local_is_false() {
         assert!(res.is_ok(), "subscribe_data_with_options failed: {:?}", res);
         distinct_conn(cli);
     }
+
+    #[tokio::test]
+    async fn no_local_on_share_subscribe() {
+        let network = "tcp";
+        let qos = 1;
+        let uid = unique_id();
+        let topic = format!("$share/g1/no_local_share/{uid}/{network}/{qos}");
+        let client_id = build_client_id(format!("no_local_share_{uid}").as_str());
+        let client_properties = ClientTestProperties {
+            mqtt_version: 5,
+            client_id: client_id.to_string(),
+            addr: broker_addr_by_type(network),
+            ..Default::default()
+        };
+        let cli = connect_server(&client_properties);
+
+        // MQTT 5 makes No Local on a shared subscription a Protocol Error,
+        // so the broker closes the connection instead of sending a SUBACK.
+        let sub_opts = &[SubscribeOptions::new(true, false, None)];
+        let res = cli.subscribe_many_with_options(&[topic.clone()], &[qos], sub_opts, None);
+        assert!(
+            res.is_err(),
+            "Expected subscribe failure but got: {:?}",
+            res
+        );
+
+        let cli = connect_server(&client_properties);
+        let sub_opts = &[SubscribeOptions::new(false, false, None)];
+        let res = cli.subscribe_many_with_options(&[topic], &[qos], sub_opts, None);
+        assert!(res.is_ok(), "subscribe failed: {:?}", res);
+        distinct_conn(cli);
+    }
 }
@@ -23,6 +23,7 @@ mod tests {
     use crate::mqtt::protocol::ClientTestProperties;
     use common_base::uuid::unique_id;
     use paho_mqtt::{Message, SubscribeOptions};
+    use std::time::{Duration, Instant};
 
     #[tokio::test]
     async fn retain_as_published() {
@@ -53,7 +54,9 @@ async fn retain_as_published() {
                     return false;
                 }
 
-                msg.retained() == retain_as_published
+                // Retained messages sent because of a new subscription always
+                // keep the RETAIN flag, whatever Retain As Published says.
+                msg.retained()
             };
 
             let subscribe_test_data = SubscribeTestData {
@@ -68,4 +71,56 @@ async fn retain_as_published() {
             distinct_conn(cli);
         }
     }
+
+    #[tokio::test]
+    async fn retain_as_published_live_message() {
+        for retain_as_published in [true, false] {
+            let subscribe_options = SubscribeOptions::new(false, retain_as_published, None);
+            let network = "tcp";
+            let qos = 1;
+            let uid = unique_id();
+            let topic = format!("/retain_as_published_live/{uid}/{network}/{qos}");
+
+            // subscribe
+            let sub_client_id =
+                build_client_id(format!("retain_as_published_live_sub{uid}").as_str());
+            let sub_cli = connect_server(&ClientTestProperties {
+                mqtt_version: 5,
+                client_id: sub_client_id,
+                addr: broker_addr_by_type(network),
+                ..Default::default()
+            });
+            let rx = sub_cli.start_consuming();
+            let res = sub_cli.subscribe_with_options(topic.clone(), qos, subscribe_options, None);
+            assert!(res.is_ok(), "subscribe failed: {:?}", res);
+
+            // publish a retained message after the subscription exists
+            let pub_client_id =
+                build_client_id(format!("retain_as_published_live_pub{uid}").as_str());
+            let pub_cli = connect_server(&ClientTestProperties {
+                mqtt_version: 5,
+                client_id: pub_client_id,
+                addr: broker_addr_by_type(network),
+                ..Default::default()
+            });
+            let message_content = "retain live message".to_string();
+            let msg = Message::new_retained(topic.clone(), message_content.clone(), qos);
+            publish_data(&pub_cli, msg, false);
+
+            let start_time = Instant::now();
+            let mut retained = None;
+            while start_time.elapsed() < Duration::from_secs(60) {
+                if let Ok(Some(msg)) = rx.recv_timeout(Duration::from_secs(1)) {
+                    if msg.payload() == message_content.as_bytes() {
+                        retained = Some(msg.retained());
+                        break;
+                    }
+                }
+            }
+            assert_eq!(retained, Some(retain_as_published));
+
+            distinct_conn(pub_cli);
+            distinct_conn(sub_cli);
+        }
+    }
 }
@@ -384,6 +384,8 @@ async fn create_user(
                 tenant: tenant.to_string(),
                 username: username.to_string(),
                 password: password.to_string(),
+                salt: None,
+                hash_algorithm: None,
                 is_superuser: false,
             })
             .await
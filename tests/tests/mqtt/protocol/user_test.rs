@@ -86,6 +86,8 @@ async fn create_user(admin_client: &AdminHttpClient, username: String, password:
             tenant: "default".to_string(),
             username: username.clone(),
             password,
+            salt: None,
+            hash_algorithm: None,
             is_superuser: false,
         };
         admin_client.create_user(&user).await.unwrap();
@@ -36,6 +36,7 @@ async fn user_test() {
             salt: None,
             is_superuser,
             create_time: now_second(),
+            hash_algorithm: None,
         };
         user_storage.save_user(user_info).await.unwrap();
 
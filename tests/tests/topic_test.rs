@@ -217,6 +217,7 @@ async fn topic_test() {
             let topic_list_req = ListTopicRequest {
                 tenant: tenant.clone(),
                 topic_name: topic_name.clone(),
+                ..Default::default()
             };
             let mut topic_stream =
                 placement_list_topic(&client_pool, &["127.0.0.1:1228"], topic_list_req)